@@ -6,24 +6,22 @@
 
 use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
 use rayon::prelude::*;
-use std::fs::{self, File};
-use std::io::Write;
+use std::fs;
 use std::path::PathBuf;
 use tempfile::TempDir;
 
 use vrift_cas::zero_copy_ingest::{ingest_phantom, ingest_solid_tier2};
+use vrift_genfs::{generate, SizeDist, Spec};
 
 fn create_test_files(dir: &std::path::Path, count: usize, size: usize) -> Vec<PathBuf> {
-    let content = vec![b'X'; size];
-    (0..count)
-        .map(|i| {
-            let path = dir.join(format!("file_{}.dat", i));
-            let mut f = File::create(&path).unwrap();
-            f.write_all(&content).unwrap();
-            f.write_all(format!("{}", i).as_bytes()).unwrap();
-            path
-        })
-        .collect()
+    let spec = Spec {
+        files: count as u64,
+        sizes: SizeDist::Fixed(size as u64),
+        files_per_dir: count as u64,
+        ..Spec::default()
+    };
+    generate(&spec, dir, 1).unwrap();
+    spec.files().map(|f| dir.join(f.path)).collect()
 }
 
 // Sequential benchmarks
@@ -0,0 +1,468 @@
+//! Two-phase garbage collection
+//!
+//! The bloom filter a sweep works from is a snapshot of what the registered
+//! manifests referenced when `vrift gc` built it; a blob ingested after that
+//! looks like an orphan. [`CasStore::sweep_with_grace`] therefore does not
+//! delete an unreferenced blob the first time it sees one. It records a
+//! tombstone in the store's GC journal (`<root>/gc-journal.<algorithm>.json`)
+//! and deletes the blob on a later sweep once the tombstone is older than the
+//! grace period. A blob that is referenced again in between loses its
+//! tombstone.
+//!
+//! A zero grace period marks and deletes in the same pass, which is what
+//! [`CasStore::sweep`] does.
+//!
+//! [`CasStore::sweep_refcounted`] goes through the same journal, deciding
+//! liveness from the store's [refcount database](crate::RefCounts) instead of
+//! a bloom filter.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Blake3Hash, BloomFilter, CasStore, Progress, Result};
+
+/// An unreferenced blob waiting out the grace period
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct Tombstone {
+    /// Seconds since the epoch of the sweep that first found it unreferenced
+    marked_at: u64,
+    size: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct GcJournal {
+    /// Seconds since the epoch of the last sweep that walked the whole store
+    last_sweep: Option<u64>,
+    /// Keyed by hex hash
+    tombstones: BTreeMap<String, Tombstone>,
+}
+
+/// Pending deletions of a store, as recorded by its last sweeps
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GcStatus {
+    /// Blobs marked unreferenced and not yet deleted
+    pub tombstones: u64,
+    /// Total size of those blobs
+    pub bytes: u64,
+    /// When the longest-waiting of them was marked
+    pub oldest_marked_at: Option<SystemTime>,
+    /// When a sweep last walked the whole store
+    pub last_sweep: Option<SystemTime>,
+}
+
+/// What a sweep would do, for a dry run
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SweepPlan {
+    /// Unreferenced blobs it would mark, with their sizes
+    pub mark: Vec<(Blake3Hash, u64)>,
+    /// Blobs past the grace period it would delete, with their sizes
+    pub delete: Vec<(Blake3Hash, u64)>,
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+fn to_system_time(secs: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+impl CasStore {
+    fn gc_journal_path(&self) -> PathBuf {
+        self.root
+            .join(format!("gc-journal.{}.json", self.algorithm.name()))
+    }
+
+    /// A journal that cannot be parsed is started over: that only postpones
+    /// deletions, never brings them forward.
+    fn load_gc_journal(&self) -> Result<GcJournal> {
+        let path = self.gc_journal_path();
+        match fs::read(&path) {
+            Ok(data) => Ok(serde_json::from_slice(&data).unwrap_or_else(|e| {
+                tracing::warn!("Discarding corrupt GC journal {}: {}", path.display(), e);
+                GcJournal::default()
+            })),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(GcJournal::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Write the journal atomically (temp file + rename)
+    fn save_gc_journal(&self, journal: &GcJournal) -> Result<()> {
+        let path = self.gc_journal_path();
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec(journal).map_err(io::Error::from)?)?;
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    /// Garbage-collect blobs missing from the bloom filter of active hashes,
+    /// deleting only those already marked unreferenced at least `grace` ago.
+    ///
+    /// Returns (deleted_count, reclaimed_bytes).
+    pub fn sweep_with_grace(&self, bloom_bits: &[u8], grace: Duration) -> Result<(u32, u64)> {
+        self.sweep_with_grace_progress(bloom_bits, grace, &Progress::default())
+    }
+
+    /// [`sweep_with_grace`](Self::sweep_with_grace) that publishes counters to
+    /// `progress` and stops early once [`Progress::cancel`] has been called.
+    /// Tombstones of the blobs a cancelled sweep did not reach are kept.
+    pub fn sweep_with_grace_progress(
+        &self,
+        bloom_bits: &[u8],
+        grace: Duration,
+        progress: &Progress,
+    ) -> Result<(u32, u64)> {
+        let bloom = BloomFilter {
+            bits: bloom_bits.to_vec(),
+        };
+        self.sweep_at(
+            |_, hex| bloom.contains(hex),
+            grace,
+            unix_now(),
+            progress,
+            None,
+        )
+    }
+
+    /// What [`sweep_with_grace`](Self::sweep_with_grace) would mark and
+    /// delete now, leaving the store and its journal as they are
+    pub fn plan_sweep_with_grace(&self, bloom_bits: &[u8], grace: Duration) -> Result<SweepPlan> {
+        let bloom = BloomFilter {
+            bits: bloom_bits.to_vec(),
+        };
+        let mut plan = SweepPlan::default();
+        self.sweep_at(
+            |_, hex| bloom.contains(hex),
+            grace,
+            unix_now(),
+            &Progress::default(),
+            Some(&mut plan),
+        )?;
+        Ok(plan)
+    }
+
+    /// Garbage-collect blobs no recorded manifest references, by the store's
+    /// refcount database, with the same grace period as
+    /// [`sweep_with_grace`](Self::sweep_with_grace). Fails if the store has
+    /// no refcount database.
+    pub fn sweep_refcounted(&self, grace: Duration) -> Result<(u32, u64)> {
+        self.sweep_refcounted_progress(grace, &Progress::default())
+    }
+
+    /// [`sweep_refcounted`](Self::sweep_refcounted) with progress and
+    /// cancellation, as for [`sweep_with_grace_progress`](Self::sweep_with_grace_progress)
+    pub fn sweep_refcounted_progress(
+        &self,
+        grace: Duration,
+        progress: &Progress,
+    ) -> Result<(u32, u64)> {
+        let refs = self.existing_refcounts()?;
+        // One snapshot for the whole walk
+        let rtxn = refs.read_txn()?;
+        self.sweep_at(
+            |hash, _| refs.is_referenced(&rtxn, hash),
+            grace,
+            unix_now(),
+            progress,
+            None,
+        )
+    }
+
+    /// What [`sweep_refcounted`](Self::sweep_refcounted) would mark and
+    /// delete now, as for [`plan_sweep_with_grace`](Self::plan_sweep_with_grace)
+    pub fn plan_sweep_refcounted(&self, grace: Duration) -> Result<SweepPlan> {
+        let refs = self.existing_refcounts()?;
+        let rtxn = refs.read_txn()?;
+        let mut plan = SweepPlan::default();
+        self.sweep_at(
+            |hash, _| refs.is_referenced(&rtxn, hash),
+            grace,
+            unix_now(),
+            &Progress::default(),
+            Some(&mut plan),
+        )?;
+        Ok(plan)
+    }
+
+    /// With `plan`, the blobs are recorded there instead of being marked or
+    /// deleted, and the journal is not written
+    fn sweep_at(
+        &self,
+        mut is_live: impl FnMut(&Blake3Hash, &str) -> bool,
+        grace: Duration,
+        now: u64,
+        progress: &Progress,
+        mut plan: Option<&mut SweepPlan>,
+    ) -> Result<(u32, u64)> {
+        let mut journal = self.load_gc_journal()?;
+        // Tombstoned blobs still in the store; the others were deleted by
+        // other means and their tombstones go
+        let mut pending = HashSet::new();
+        let mut complete = true;
+
+        let mut deleted_count = 0;
+        let mut reclaimed_bytes = 0;
+
+        for hash_res in self.iter()? {
+            if progress.is_cancelled() {
+                complete = false;
+                break;
+            }
+            let hash = hash_res?;
+            progress.processed.fetch_add(1, Ordering::Relaxed);
+
+            let hex = Self::hash_to_hex(&hash);
+            if is_live(&hash, &hex) {
+                journal.tombstones.remove(&hex);
+                continue;
+            }
+            let Some(size) = self
+                .find_blob_path(&hash)
+                .and_then(|path| fs::metadata(path).ok())
+                .map(|meta| meta.len())
+            else {
+                continue;
+            };
+            let newly_marked = !journal.tombstones.contains_key(&hex);
+            let tombstone = *journal.tombstones.entry(hex.clone()).or_insert(Tombstone {
+                marked_at: now,
+                size,
+            });
+            let expired = now.saturating_sub(tombstone.marked_at) >= grace.as_secs();
+
+            if let Some(plan) = plan.as_deref_mut() {
+                if expired {
+                    plan.delete.push((hash, size));
+                } else if newly_marked {
+                    plan.mark.push((hash, size));
+                }
+                continue;
+            }
+            // Delete the blob (handles immutable flags internally)
+            if expired && self.delete(&hash).is_ok() {
+                journal.tombstones.remove(&hex);
+                deleted_count += 1;
+                reclaimed_bytes += size;
+                progress.affected.fetch_add(1, Ordering::Relaxed);
+                progress.bytes.fetch_add(size, Ordering::Relaxed);
+            } else {
+                pending.insert(hex);
+            }
+        }
+
+        if plan.is_some() {
+            return Ok((0, 0));
+        }
+        if complete {
+            journal.tombstones.retain(|hex, _| pending.contains(hex));
+            journal.last_sweep = Some(now);
+        }
+        self.save_gc_journal(&journal)?;
+        Ok((deleted_count, reclaimed_bytes))
+    }
+
+    /// Blobs marked by earlier sweeps and waiting out the grace period
+    pub fn gc_status(&self) -> Result<GcStatus> {
+        let journal = self.load_gc_journal()?;
+        Ok(GcStatus {
+            tombstones: journal.tombstones.len() as u64,
+            bytes: journal.tombstones.values().map(|t| t.size).sum(),
+            oldest_marked_at: journal
+                .tombstones
+                .values()
+                .map(|t| t.marked_at)
+                .min()
+                .map(to_system_time),
+            last_sweep: journal.last_sweep.map(to_system_time),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BLOOM_SIZE;
+    use tempfile::TempDir;
+
+    const HOUR: u64 = 3600;
+
+    fn bloom_of(hashes: &[Blake3Hash]) -> impl Fn(&Blake3Hash, &str) -> bool {
+        let mut bloom = BloomFilter::new(BLOOM_SIZE);
+        for hash in hashes {
+            bloom.add(&CasStore::hash_to_hex(hash));
+        }
+        move |_, hex| bloom.contains(hex)
+    }
+
+    #[test]
+    fn test_orphans_outlive_the_grace_period() {
+        let temp = TempDir::new().unwrap();
+        let cas = CasStore::new(temp.path()).unwrap();
+        let keep = cas.store(b"keep me").unwrap();
+        let orphan = cas.store(b"orphan").unwrap();
+        let live = bloom_of(&[keep]);
+        let grace = Duration::from_secs(24 * HOUR);
+        let start = 1_700_000_000;
+        let progress = Progress::default();
+
+        // First sight only marks
+        assert_eq!(
+            cas.sweep_at(&live, grace, start, &progress, None).unwrap(),
+            (0, 0)
+        );
+        assert!(cas.exists(&orphan));
+        let status = cas.gc_status().unwrap();
+        assert_eq!((status.tombstones, status.bytes), (1, 6));
+        assert_eq!(status.oldest_marked_at, Some(to_system_time(start)));
+        assert_eq!(status.last_sweep, Some(to_system_time(start)));
+
+        // A later sweep inside the window keeps the original mark
+        let later = start + 23 * HOUR;
+        assert_eq!(
+            cas.sweep_at(&live, grace, later, &progress, None).unwrap(),
+            (0, 0)
+        );
+        assert_eq!(
+            cas.gc_status().unwrap().oldest_marked_at,
+            Some(to_system_time(start))
+        );
+
+        let expired = start + 24 * HOUR;
+        assert_eq!(
+            cas.sweep_at(&live, grace, expired, &progress, None)
+                .unwrap(),
+            (1, 6)
+        );
+        assert!(!cas.exists(&orphan));
+        assert!(cas.exists(&keep));
+        assert_eq!(cas.gc_status().unwrap().tombstones, 0);
+    }
+
+    #[test]
+    fn test_referenced_again_clears_the_tombstone() {
+        let temp = TempDir::new().unwrap();
+        let cas = CasStore::new(temp.path()).unwrap();
+        let blob = cas.store(b"comes back").unwrap();
+        let grace = Duration::from_secs(HOUR);
+        let progress = Progress::default();
+
+        cas.sweep_at(bloom_of(&[]), grace, 0, &progress, None)
+            .unwrap();
+        assert_eq!(cas.gc_status().unwrap().tombstones, 1);
+        cas.sweep_at(bloom_of(&[blob]), grace, HOUR, &progress, None)
+            .unwrap();
+        assert_eq!(cas.gc_status().unwrap().tombstones, 0);
+
+        // Unreferenced once more, the clock starts over
+        assert_eq!(
+            cas.sweep_at(bloom_of(&[]), grace, 2 * HOUR, &progress, None)
+                .unwrap(),
+            (0, 0)
+        );
+        assert!(cas.exists(&blob));
+    }
+
+    #[test]
+    fn test_journal_follows_the_store() {
+        let temp = TempDir::new().unwrap();
+        let cas = CasStore::new(temp.path()).unwrap();
+        let first = cas.store(b"first").unwrap();
+        cas.store(b"second").unwrap();
+        let grace = Duration::from_secs(HOUR);
+
+        let cancelled = Progress::default();
+        cancelled.cancel();
+        cas.sweep_at(bloom_of(&[]), grace, 0, &Progress::default(), None)
+            .unwrap();
+        assert_eq!(cas.gc_status().unwrap().tombstones, 2);
+
+        // A cancelled sweep reaches nothing and forgets nothing
+        cas.sweep_at(bloom_of(&[]), grace, 10, &cancelled, None)
+            .unwrap();
+        let status = cas.gc_status().unwrap();
+        assert_eq!(status.tombstones, 2);
+        assert_eq!(status.last_sweep, Some(to_system_time(0)));
+
+        // Blobs deleted behind the journal's back lose their tombstones
+        cas.delete(&first).unwrap();
+        cas.sweep_at(bloom_of(&[]), grace, 20, &Progress::default(), None)
+            .unwrap();
+        assert_eq!(cas.gc_status().unwrap().tombstones, 1);
+
+        // Corruption postpones deletions instead of failing the sweep
+        fs::write(cas.gc_journal_path(), b"{").unwrap();
+        assert_eq!(cas.gc_status().unwrap(), GcStatus::default());
+        assert_eq!(
+            cas.sweep_at(bloom_of(&[]), grace, 2 * HOUR, &Progress::default(), None)
+                .unwrap(),
+            (0, 0)
+        );
+        assert_eq!(cas.gc_status().unwrap().tombstones, 1);
+    }
+
+    #[test]
+    fn test_refcounted_sweep_keeps_exactly_the_referenced() {
+        let temp = TempDir::new().unwrap();
+        let cas = CasStore::new(temp.path()).unwrap();
+        let keep = cas.store(b"referenced").unwrap();
+        let orphan = cas.store(b"unreferenced").unwrap();
+
+        // Without a database every blob would look unreferenced
+        assert!(cas.sweep_refcounted(Duration::ZERO).is_err());
+        assert!(cas.exists(&orphan));
+
+        let refs = crate::RefCounts::open(temp.path()).unwrap();
+        refs.set_manifest("m", &[keep].into_iter().collect())
+            .unwrap();
+        assert_eq!(cas.sweep_refcounted(Duration::ZERO).unwrap(), (1, 12));
+        assert!(cas.exists(&keep));
+        assert!(!cas.exists(&orphan));
+
+        refs.remove_manifest("m").unwrap();
+        let grace = Duration::from_secs(HOUR);
+        assert_eq!(cas.sweep_refcounted(grace).unwrap(), (0, 0));
+        assert_eq!(cas.gc_status().unwrap().tombstones, 1);
+    }
+
+    #[test]
+    fn test_plan_leaves_store_and_journal_alone() {
+        let temp = TempDir::new().unwrap();
+        let cas = CasStore::new(temp.path()).unwrap();
+        let keep = cas.store(b"keep me").unwrap();
+        let orphan = cas.store(b"orphan").unwrap();
+        let grace = Duration::from_secs(HOUR);
+        let progress = Progress::default();
+
+        let mut plan = SweepPlan::default();
+        cas.sweep_at(bloom_of(&[keep]), grace, 0, &progress, Some(&mut plan))
+            .unwrap();
+        assert_eq!(
+            plan,
+            SweepPlan {
+                mark: vec![(orphan, 6)],
+                delete: vec![],
+            }
+        );
+        assert_eq!(cas.gc_status().unwrap(), GcStatus::default());
+
+        // Marked for real, the plan past the grace period deletes it
+        cas.sweep_at(bloom_of(&[keep]), grace, 0, &progress, None)
+            .unwrap();
+        let mut plan = SweepPlan::default();
+        cas.sweep_at(bloom_of(&[keep]), grace, HOUR, &progress, Some(&mut plan))
+            .unwrap();
+        assert_eq!(plan.delete, vec![(orphan, 6)]);
+        assert!(plan.mark.is_empty());
+        assert!(cas.exists(&orphan));
+        assert_eq!(cas.gc_status().unwrap().tombstones, 1);
+    }
+}
@@ -0,0 +1,173 @@
+//! Content hashing
+//!
+//! BLAKE3 is the default and what the rest of Velo Rift assumes: manifests,
+//! the inception layer and the zero-copy ingest paths compute BLAKE3
+//! themselves. A [`CasStore`](crate::CasStore) opened with another
+//! [`HashAlgorithm`] addresses content the way another tool does (Git's
+//! SHA-256 object format) and keeps its blobs in a tree of its own:
+//!
+//! ```text
+//! the_source/
+//! ├── blake3/ab/cd/<hex>_<size>
+//! └── sha256/ab/cd/sha256-<hex>_<size>
+//! ```
+//!
+//! The algorithm prefix in the file name lets a blob that ended up in the
+//! wrong tree be told apart from one with the same digest; BLAKE3 blobs
+//! predate the prefix and have none. Every algorithm yields a 32-byte
+//! digest, so hashes share the [`Blake3Hash`] type whatever produced them.
+
+use std::fmt;
+use std::io::{self, Read};
+
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+
+use crate::Blake3Hash;
+
+/// Incremental hashing of blob content
+pub trait ContentHasher: Send {
+    fn update(&mut self, data: &[u8]);
+
+    fn finalize(self: Box<Self>) -> Blake3Hash;
+}
+
+impl ContentHasher for blake3::Hasher {
+    fn update(&mut self, data: &[u8]) {
+        blake3::Hasher::update(self, data);
+    }
+
+    fn finalize(self: Box<Self>) -> Blake3Hash {
+        *blake3::Hasher::finalize(&self).as_bytes()
+    }
+}
+
+impl ContentHasher for sha2::Sha256 {
+    fn update(&mut self, data: &[u8]) {
+        Digest::update(self, data);
+    }
+
+    fn finalize(self: Box<Self>) -> Blake3Hash {
+        Digest::finalize(*self).into()
+    }
+}
+
+/// Hash algorithm of a CAS store
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    #[default]
+    Blake3,
+    Sha256,
+}
+
+impl HashAlgorithm {
+    pub const ALL: [HashAlgorithm; 2] = [HashAlgorithm::Blake3, HashAlgorithm::Sha256];
+
+    /// Name used in configuration and as the store's top-level directory
+    pub fn name(self) -> &'static str {
+        match self {
+            HashAlgorithm::Blake3 => "blake3",
+            HashAlgorithm::Sha256 => "sha256",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|a| a.name() == name)
+    }
+
+    /// Prefix of blob file names
+    pub fn file_prefix(self) -> &'static str {
+        match self {
+            HashAlgorithm::Blake3 => "",
+            HashAlgorithm::Sha256 => "sha256-",
+        }
+    }
+
+    pub fn hasher(self) -> Box<dyn ContentHasher> {
+        match self {
+            HashAlgorithm::Blake3 => Box::new(blake3::Hasher::new()),
+            HashAlgorithm::Sha256 => Box::new(sha2::Sha256::new()),
+        }
+    }
+
+    pub fn hash(self, data: &[u8]) -> Blake3Hash {
+        match self {
+            HashAlgorithm::Blake3 => *blake3::hash(data).as_bytes(),
+            HashAlgorithm::Sha256 => sha2::Sha256::digest(data).into(),
+        }
+    }
+
+    pub fn hash_reader<R: Read>(self, mut reader: R) -> io::Result<Blake3Hash> {
+        let mut hasher = self.hasher();
+        let mut buf = [0u8; 8192];
+        loop {
+            let n = reader.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buf[..n]);
+        }
+        Ok(hasher.finalize())
+    }
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Algorithm and hash of a blob file name (`[<prefix>]<hex>_<size>[.<ext>]`)
+pub fn parse_blob_name(name: &str) -> Option<(HashAlgorithm, Blake3Hash)> {
+    let stem = name.split('_').next().unwrap_or(name);
+    let (algorithm, hex) = match stem.split_once('-') {
+        Some((prefix, hex)) => (HashAlgorithm::from_name(prefix)?, hex),
+        None => (HashAlgorithm::Blake3, stem),
+    };
+    if algorithm.file_prefix().is_empty() && stem != hex {
+        return None;
+    }
+    Some((algorithm, crate::CasStore::hex_to_hash(hex)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_digests() {
+        let hex = |h: Blake3Hash| crate::CasStore::hash_to_hex(&h);
+        assert_eq!(
+            hex(HashAlgorithm::Sha256.hash(b"abc")),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            HashAlgorithm::Blake3.hash(b"abc"),
+            *blake3::hash(b"abc").as_bytes()
+        );
+        for algorithm in HashAlgorithm::ALL {
+            assert_eq!(
+                algorithm.hash_reader(&b"streamed"[..]).unwrap(),
+                algorithm.hash(b"streamed")
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_blob_name() {
+        let hash = [0xab; 32];
+        let hex = crate::CasStore::hash_to_hex(&hash);
+        assert_eq!(
+            parse_blob_name(&format!("{}_12", hex)),
+            Some((HashAlgorithm::Blake3, hash))
+        );
+        assert_eq!(
+            parse_blob_name(&format!("sha256-{}_12.bin", hex)),
+            Some((HashAlgorithm::Sha256, hash))
+        );
+        assert_eq!(parse_blob_name(&format!("blake3-{}_12", hex)), None);
+        assert_eq!(parse_blob_name(&format!("md5-{}_12", hex)), None);
+        assert_eq!(parse_blob_name("not-a-blob"), None);
+    }
+}
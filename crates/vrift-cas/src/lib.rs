@@ -15,6 +15,9 @@
 //!             └── abcd1234...efgh_12345.bin  # hash_size.ext
 //! ```
 //!
+//! Stores using another hash algorithm (see [`hasher`]) keep their blobs in a
+//! tree named after it.
+//!
 //! ## I/O Backend Abstraction
 //!
 //! The crate provides platform-specific I/O backends for optimal batch ingestion:
@@ -22,15 +25,24 @@
 //! - macOS: GCD-style dispatch
 //! - Fallback: Rayon thread pool
 
+mod gc;
+pub mod hasher;
 mod io_backend;
 pub mod link_strategy;
 pub mod parallel_ingest;
+pub mod path_key;
 pub mod protection;
+mod refcount;
 pub mod reflink;
+pub mod scan;
+mod scrub;
+pub mod skip;
 pub mod streaming_ingest;
 pub mod streaming_pipeline;
 pub mod zero_copy_ingest;
 
+pub use gc::{GcStatus, SweepPlan};
+pub use hasher::{ContentHasher, HashAlgorithm};
 pub use io_backend::{create_backend, rayon_backend, IngestBackend};
 #[cfg(target_os = "macos")]
 pub use link_strategy::is_binary_sensitive;
@@ -43,14 +55,18 @@ pub use parallel_ingest::{
 pub use protection::{
     enforce_cas_invariant, is_immutable, set_immutable, CAS_FORBIDDEN_PERM_MASK, CAS_READ_ONLY_PERM,
 };
+pub use refcount::RefCounts;
+pub use scan::{CommandScanner, ContentScanner, Finding};
+pub use skip::SkipPolicy;
 pub use streaming_ingest::{
-    streaming_ingest, streaming_ingest_cached, streaming_ingest_with_progress,
+    plan_ingest, streaming_ingest, streaming_ingest_cached, streaming_ingest_with_progress,
+    walk_files, IngestFilter, PlannedFile,
 };
 pub use streaming_pipeline::{IngestPipeline, IngestStats, PipelineConfig};
 pub use zero_copy_ingest::{
     ingest_phantom, ingest_solid_tier1, ingest_solid_tier1_dedup, ingest_solid_tier2,
     ingest_solid_tier2_cached, ingest_solid_tier2_dedup, mtime_nsec_from_metadata, CacheHint,
-    IngestResult,
+    IngestResult, IngestTotals,
 };
 
 use serde::{Deserialize, Serialize};
@@ -61,6 +77,7 @@ use std::path::{Path, PathBuf};
 use tracing::instrument;
 
 use thiserror::Error;
+use vrift_error::{Classify, ErrorKind};
 
 /// BLAKE3 hash type (32 bytes)
 pub type Blake3Hash = [u8; 32];
@@ -76,16 +93,42 @@ pub enum CasError {
 
     #[error("Hash mismatch: expected {expected}, got {actual}")]
     HashMismatch { expected: String, actual: String },
+
+    #[error("Refcount database error: {0}")]
+    Lmdb(#[from] heed::Error),
+
+    #[error("Quarantined {}: {reason}", path.display())]
+    Quarantined { path: PathBuf, reason: String },
+
+    #[error("Skipped {}: {reason}", path.display())]
+    Skipped { path: PathBuf, reason: String },
+}
+
+impl Classify for CasError {
+    fn classify(&self) -> ErrorKind {
+        match self {
+            CasError::Io(e) => e.classify(),
+            CasError::NotFound { .. } => ErrorKind::NotFound,
+            CasError::HashMismatch { .. } => ErrorKind::Corrupted,
+            CasError::Lmdb(heed::Error::Io(e)) => e.classify(),
+            CasError::Lmdb(heed::Error::Mdb(heed::MdbError::Corrupted))
+            | CasError::Lmdb(heed::Error::Decoding(_)) => ErrorKind::Corrupted,
+            CasError::Lmdb(_) => ErrorKind::Internal,
+            CasError::Quarantined { .. } | CasError::Skipped { .. } => ErrorKind::IngestFailed,
+        }
+    }
 }
 
 pub type Result<T> = std::result::Result<T, CasError>;
 
 /// Content-Addressable Storage store
 ///
-/// Stores blobs indexed by their BLAKE3 hash with a 2-char prefix fan-out.
+/// Stores blobs indexed by their BLAKE3 hash (or the store's
+/// [`HashAlgorithm`]) with a 2-char prefix fan-out.
 #[derive(Debug, Clone)]
 pub struct CasStore {
     root: PathBuf,
+    algorithm: HashAlgorithm,
 }
 
 impl CasStore {
@@ -93,9 +136,37 @@ impl CasStore {
     ///
     /// The directory will be created if it doesn't exist.
     pub fn new<P: AsRef<Path>>(root: P) -> Result<Self> {
+        Self::with_algorithm(root, HashAlgorithm::default())
+    }
+
+    /// Create a CAS store at `root` that addresses blobs by `algorithm`.
+    pub fn with_algorithm<P: AsRef<Path>>(root: P, algorithm: HashAlgorithm) -> Result<Self> {
         let root = root.as_ref().to_path_buf();
         fs::create_dir_all(&root)?;
-        Ok(Self { root })
+        Ok(Self { root, algorithm })
+    }
+
+    pub fn algorithm(&self) -> HashAlgorithm {
+        self.algorithm
+    }
+
+    /// Hash `data` with the store's algorithm.
+    pub fn hash(&self, data: &[u8]) -> Blake3Hash {
+        self.algorithm.hash(data)
+    }
+
+    /// Algorithms with a blob tree under the root. More than one means the
+    /// store is shared by pipelines that never deduplicate against each other.
+    pub fn algorithms(&self) -> Vec<HashAlgorithm> {
+        HashAlgorithm::ALL
+            .into_iter()
+            .filter(|a| self.root.join(a.name()).is_dir())
+            .collect()
+    }
+
+    /// Top-level directory of the store's blob tree
+    fn tree(&self) -> PathBuf {
+        self.root.join(self.algorithm.name())
     }
 
     /// Create a CAS store at the default location (`~/.vrift/the_source/`).
@@ -139,7 +210,7 @@ impl CasStore {
         let hex = Self::hash_to_hex(hash);
         let l1 = &hex[..2]; // First 2 chars
         let l2 = &hex[2..4]; // Next 2 chars
-        self.root.join("blake3").join(l1).join(l2)
+        self.tree().join(l1).join(l2)
     }
 
     /// Find the actual blob file path using RFC-0039 format.
@@ -152,13 +223,17 @@ impl CasStore {
             return None;
         }
 
-        let hex = Self::hash_to_hex(hash);
+        let stem = format!(
+            "{}{}_",
+            self.algorithm.file_prefix(),
+            Self::hash_to_hex(hash)
+        );
         if let Ok(entries) = fs::read_dir(&dir) {
             for entry in entries.flatten() {
                 let filename = entry.file_name();
                 let filename_str = filename.to_string_lossy();
-                // Match pattern: <hash>_* (RFC-0039 format only)
-                if filename_str.starts_with(&format!("{}_", hex)) {
+                // Match pattern: [<alg>-]<hash>_* (RFC-0039 format only)
+                if filename_str.starts_with(&stem) {
                     return Some(entry.path());
                 }
             }
@@ -174,14 +249,13 @@ impl CasStore {
     /// - Extension enables direct file type inspection
     pub fn blob_path_with_metadata(&self, hash: &Blake3Hash, size: u64, ext: &str) -> PathBuf {
         let hex = Self::hash_to_hex(hash);
-        let l1 = &hex[..2];
-        let l2 = &hex[2..4];
+        let prefix = self.algorithm.file_prefix();
         let filename = if ext.is_empty() {
-            format!("{}_{}", hex, size)
+            format!("{}{}_{}", prefix, hex, size)
         } else {
-            format!("{}_{}.{}", hex, size, ext)
+            format!("{}{}_{}.{}", prefix, hex, size, ext)
         };
-        self.root.join("blake3").join(l1).join(l2).join(filename)
+        self.blob_dir(hash).join(filename)
     }
 
     /// Store bytes in the CAS, returning the content hash.
@@ -191,7 +265,7 @@ impl CasStore {
     /// Uses RFC-0039 format: `blake3/ab/cd/hash_size`
     #[instrument(skip(self, data), level = "debug")]
     pub fn store(&self, data: &[u8]) -> Result<Blake3Hash> {
-        let hash = Self::compute_hash(data);
+        let hash = self.hash(data);
         let size = data.len() as u64;
 
         // Deduplication: skip if already exists (check via find_blob_path)
@@ -266,7 +340,7 @@ impl CasStore {
         let src = src_path.as_ref();
         let file = File::open(src)?;
         let size = file.metadata()?.len();
-        let hash = Self::compute_hash_reader(file)?;
+        let hash = self.algorithm.hash_reader(file)?;
 
         // Deduplication: if already exists, just remove the temp file
         if self.find_blob_path(&hash).is_some() {
@@ -332,7 +406,7 @@ impl CasStore {
         file.read_to_end(&mut data)?;
 
         // Verify hash on read (integrity check)
-        let actual_hash = Self::compute_hash(&data);
+        let actual_hash = self.hash(&data);
         if actual_hash != *hash {
             return Err(CasError::HashMismatch {
                 expected: Self::hash_to_hex(hash),
@@ -343,6 +417,39 @@ impl CasStore {
         Ok(data)
     }
 
+    /// Open a blob for reading without loading it into memory.
+    ///
+    /// The content is not verified; use [`verify_stream`](Self::verify_stream)
+    /// when it must match `hash`.
+    #[instrument(skip(self), level = "debug")]
+    pub fn get_stream(&self, hash: &Blake3Hash) -> Result<impl Read> {
+        self.open_blob(hash)
+    }
+
+    /// Open a blob for reading, hashing it as it is read.
+    ///
+    /// The check happens once the end of the blob is reached: the final
+    /// `read` fails with `InvalidData` (wrapping [`CasError::HashMismatch`])
+    /// if the content does not match `hash`. Read to the end before trusting
+    /// what came out.
+    #[instrument(skip(self), level = "debug")]
+    pub fn verify_stream(&self, hash: &Blake3Hash) -> Result<VerifyingReader<File>> {
+        Ok(VerifyingReader::with_algorithm(
+            self.open_blob(hash)?,
+            self.algorithm,
+            *hash,
+        ))
+    }
+
+    fn open_blob(&self, hash: &Blake3Hash) -> Result<File> {
+        let path = self
+            .find_blob_path(hash)
+            .ok_or_else(|| CasError::NotFound {
+                hash: Self::hash_to_hex(hash),
+            })?;
+        Ok(File::open(path)?)
+    }
+
     /// Check if a blob exists in the CAS.
     pub fn exists(&self, hash: &Blake3Hash) -> bool {
         self.find_blob_path(hash).is_some()
@@ -382,7 +489,7 @@ impl CasStore {
             std::collections::HashMap::new();
 
         // Level 0: blake3/ directory
-        let blake3_dir = self.root.join("blake3");
+        let blake3_dir = self.tree();
         if !blake3_dir.exists() {
             return Ok(CasStats::default());
         }
@@ -466,7 +573,7 @@ impl CasStore {
     ///
     /// Traverses the 3-level structure: blake3/ab/cd/hash
     pub fn iter(&self) -> Result<CasIterator> {
-        let blake3_dir = self.root.join("blake3");
+        let blake3_dir = self.tree();
         if !blake3_dir.exists() {
             // Return empty iterator if blake3 dir doesn't exist
             return Ok(CasIterator {
@@ -474,6 +581,7 @@ impl CasStore {
                 l2_iter: None,
                 l3_iter: None,
                 blake3_exists: false,
+                algorithm: self.algorithm,
             });
         }
         Ok(CasIterator {
@@ -481,42 +589,32 @@ impl CasStore {
             l2_iter: None,
             l3_iter: None,
             blake3_exists: true,
+            algorithm: self.algorithm,
         })
     }
 
     /// Perform a Garbage Collection sweep using a Bloom Filter of active hashes.
     ///
+    /// Deletes every unreferenced blob right away; see
+    /// [`sweep_with_grace`](Self::sweep_with_grace) for a sweep that races
+    /// safely with ingest.
+    ///
     /// Returns (deleted_count, reclaimed_bytes).
     pub fn sweep(&self, bloom_bits: &[u8]) -> Result<(u32, u64)> {
-        let bloom = BloomFilter {
-            bits: bloom_bits.to_vec(),
-        };
-
-        let mut deleted_count = 0;
-        let mut reclaimed_bytes = 0;
-
-        for hash_res in self.iter()? {
-            let hash = hash_res?;
-
-            // Convert Blake3Hash ([u8; 32]) to hex string for bloom lookup
-            let hex = Self::hash_to_hex(&hash);
-
-            if !bloom.contains(&hex) {
-                // Potential orphan (not in Bloom Filter)
-                if let Some(path) = self.find_blob_path(&hash) {
-                    if let Ok(meta) = fs::metadata(&path) {
-                        let size = meta.len();
-                        // Delete the blob (handles immutable flags internally)
-                        if self.delete(&hash).is_ok() {
-                            deleted_count += 1;
-                            reclaimed_bytes += size;
-                        }
-                    }
-                }
-            }
-        }
+        self.sweep_with_progress(bloom_bits, &Progress::default())
+    }
 
-        Ok((deleted_count, reclaimed_bytes))
+    /// [`sweep`](Self::sweep) that publishes counters to `progress` as it goes
+    /// and stops early once [`Progress::cancel`] has been called.
+    ///
+    /// A cancelled sweep returns the totals reached so far; blobs already
+    /// deleted stay deleted.
+    pub fn sweep_with_progress(
+        &self,
+        bloom_bits: &[u8],
+        progress: &Progress,
+    ) -> Result<(u32, u64)> {
+        self.sweep_with_grace_progress(bloom_bits, std::time::Duration::ZERO, progress)
     }
 
     pub fn blob_path_for_hash(&self, hash: &Blake3Hash) -> Option<PathBuf> {
@@ -535,7 +633,7 @@ impl CasStore {
     pub fn warm_directories(&self) -> Result<()> {
         use rayon::prelude::*;
 
-        let blake3_root = self.root.join("blake3");
+        let blake3_root = self.tree();
 
         // Phase5-#1: Probe the LAST shard directory. If it exists,
         // all 65K dirs were already created — skip entirely.
@@ -570,6 +668,51 @@ impl CasStore {
 
 pub const BLOOM_SIZE: usize = 128 * 1024;
 
+/// Bounds of [`bloom_bytes`]
+pub const BLOOM_MIN_BYTES: usize = 1024;
+pub const BLOOM_MAX_BYTES: usize = 64 * 1024 * 1024;
+
+/// Filter size in bytes that holds `entries` at a false positive rate of
+/// about `fpr` with the two probes of [`BloomFilter`], between
+/// `BLOOM_MIN_BYTES` and `BLOOM_MAX_BYTES`. The rate is clamped to
+/// `1e-6..=0.5`.
+pub fn bloom_bytes(entries: usize, fpr: f64) -> usize {
+    let fpr = if fpr.is_nan() {
+        0.01
+    } else {
+        fpr.clamp(1e-6, 0.5)
+    };
+    // k = 2 probes: fpr = (1 - e^(-2n/m))^2, so m = -2n / ln(1 - sqrt(fpr))
+    let bits = -2.0 * entries as f64 / (1.0 - fpr.sqrt()).ln();
+    ((bits / 8.0).ceil() as usize).clamp(BLOOM_MIN_BYTES, BLOOM_MAX_BYTES)
+}
+
+/// Live counters of a long-running store walk such as
+/// [`CasStore::sweep_with_progress`], readable from other threads while the
+/// walk runs. Also the cancellation flag the walk checks between blobs.
+#[derive(Debug, Default)]
+pub struct Progress {
+    /// Items examined so far (blobs scanned by a sweep or scrub)
+    pub processed: std::sync::atomic::AtomicU64,
+    /// Items acted upon so far (orphans deleted by a sweep)
+    pub affected: std::sync::atomic::AtomicU64,
+    /// Bytes acted upon so far (bytes reclaimed by a sweep)
+    pub bytes: std::sync::atomic::AtomicU64,
+    cancelled: std::sync::atomic::AtomicBool,
+}
+
+impl Progress {
+    /// Ask the walk to stop at the next item
+    pub fn cancel(&self) {
+        self.cancelled
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(std::sync::atomic::Ordering::Relaxed)
+    }
+}
+
 /// Simple Bloom Filter for fast existence checks
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BloomFilter {
@@ -583,6 +726,12 @@ impl BloomFilter {
         }
     }
 
+    /// Empty filter sized for `entries` at a false positive rate of `fpr`
+    /// (see [`bloom_bytes`])
+    pub fn with_capacity(entries: usize, fpr: f64) -> Self {
+        Self::new(bloom_bytes(entries, fpr))
+    }
+
     /// Add a string to the bloom filter
     pub fn add(&mut self, s: &str) {
         let (h1, h2) = bloom_hashes(s);
@@ -815,12 +964,60 @@ impl CasStats {
     }
 }
 
+/// Reader that checks the content it passes through against a BLAKE3 hash
+/// (see [`CasStore::verify_stream`])
+pub struct VerifyingReader<R> {
+    inner: R,
+    hasher: Option<Box<dyn ContentHasher>>,
+    expected: Blake3Hash,
+}
+
+impl<R: Read> VerifyingReader<R> {
+    pub fn new(inner: R, expected: Blake3Hash) -> Self {
+        Self::with_algorithm(inner, HashAlgorithm::Blake3, expected)
+    }
+
+    pub fn with_algorithm(inner: R, algorithm: HashAlgorithm, expected: Blake3Hash) -> Self {
+        Self {
+            inner,
+            hasher: Some(algorithm.hasher()),
+            expected,
+        }
+    }
+}
+
+impl<R: Read> Read for VerifyingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        if n > 0 {
+            if let Some(hasher) = self.hasher.as_mut() {
+                hasher.update(&buf[..n]);
+            }
+        } else if buf.is_empty() {
+            return Ok(0);
+        } else if let Some(hasher) = self.hasher.take() {
+            let actual = hasher.finalize();
+            if actual != self.expected {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    CasError::HashMismatch {
+                        expected: CasStore::hash_to_hex(&self.expected),
+                        actual: CasStore::hash_to_hex(&actual),
+                    },
+                ));
+            }
+        }
+        Ok(n)
+    }
+}
+
 /// Iterator over CAS hashes (3-level: blake3/ab/cd/hash)
 pub struct CasIterator {
     l1_iter: fs::ReadDir,         // Level 1: ab/ directories
     l2_iter: Option<fs::ReadDir>, // Level 2: cd/ directories
     l3_iter: Option<fs::ReadDir>, // Level 3: hash files
     blake3_exists: bool,
+    algorithm: HashAlgorithm,
 }
 
 impl Iterator for CasIterator {
@@ -846,9 +1043,17 @@ impl Iterator for CasIterator {
                             // Parse filename as hash (may include _size suffix)
                             if let Some(filename) = path.file_name().and_then(|n| n.to_str()) {
                                 // Handle both "hash" and "hash_size.ext" formats
-                                let hash_part = filename.split('_').next().unwrap_or(filename);
-                                if let Some(hash) = CasStore::hex_to_hash(hash_part) {
-                                    return Some(Ok(hash));
+                                match hasher::parse_blob_name(filename) {
+                                    Some((algorithm, hash)) if algorithm == self.algorithm => {
+                                        return Some(Ok(hash));
+                                    }
+                                    Some((algorithm, _)) => tracing::warn!(
+                                        "CAS: {} blob {:?} in the {} tree, skipping",
+                                        algorithm,
+                                        path,
+                                        self.algorithm
+                                    ),
+                                    None => {}
                                 }
                             }
                         }
@@ -935,6 +1140,94 @@ mod tests {
         assert!(matches!(result, Err(CasError::NotFound { .. })));
     }
 
+    #[test]
+    fn test_streams() {
+        let temp = TempDir::new().unwrap();
+        let cas = CasStore::new(temp.path()).unwrap();
+        let data = vec![42u8; 100_000];
+        let hash = cas.store(&data).unwrap();
+
+        let mut streamed = Vec::new();
+        cas.get_stream(&hash)
+            .unwrap()
+            .read_to_end(&mut streamed)
+            .unwrap();
+        assert_eq!(streamed, data);
+
+        let mut verified = Vec::new();
+        cas.verify_stream(&hash)
+            .unwrap()
+            .read_to_end(&mut verified)
+            .unwrap();
+        assert_eq!(verified, data);
+
+        assert!(matches!(
+            cas.verify_stream(&[0u8; 32]),
+            Err(CasError::NotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_verify_stream_detects_corruption() {
+        let temp = TempDir::new().unwrap();
+        let cas = CasStore::new(temp.path()).unwrap();
+        let hash = cas.store(b"original content").unwrap();
+
+        let path = cas.blob_path_for_hash(&hash).unwrap();
+        let _ = crate::protection::set_immutable(&path, false);
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        fs::write(&path, b"tampered content").unwrap();
+
+        let mut out = Vec::new();
+        let err = cas
+            .verify_stream(&hash)
+            .unwrap()
+            .read_to_end(&mut out)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let inner = err.into_inner().unwrap().downcast::<CasError>().unwrap();
+        assert!(matches!(*inner, CasError::HashMismatch { .. }));
+    }
+
+    #[test]
+    fn test_sha256_store_keeps_its_own_tree() {
+        let temp = TempDir::new().unwrap();
+        let blake3 = CasStore::new(temp.path()).unwrap();
+        let sha256 = CasStore::with_algorithm(temp.path(), HashAlgorithm::Sha256).unwrap();
+
+        let data = b"git object";
+        let hash = sha256.store(data).unwrap();
+        assert_eq!(hash, HashAlgorithm::Sha256.hash(data));
+        assert_eq!(sha256.get(&hash).unwrap(), data);
+
+        let path = sha256.blob_path_for_hash(&hash).unwrap();
+        let rel = path.strip_prefix(temp.path()).unwrap().to_string_lossy();
+        assert!(rel.starts_with("sha256/"));
+        assert!(path
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("sha256-"));
+
+        // The BLAKE3 store neither finds nor lists it
+        assert!(!blake3.exists(&hash));
+        blake3.store(data).unwrap();
+        assert_eq!(
+            blake3.algorithms(),
+            vec![HashAlgorithm::Blake3, HashAlgorithm::Sha256]
+        );
+        assert_eq!(blake3.iter().unwrap().count(), 1);
+        assert_eq!(sha256.iter().unwrap().count(), 1);
+
+        // A blob moved into the wrong tree is recognised by its name
+        let misplaced = blake3.blob_dir(&hash).join(path.file_name().unwrap());
+        fs::create_dir_all(misplaced.parent().unwrap()).unwrap();
+        fs::copy(&path, &misplaced).unwrap();
+        assert_eq!(blake3.iter().unwrap().count(), 1);
+        assert!(!blake3.exists(&hash));
+    }
+
     #[test]
     fn test_hash_to_hex_roundtrip() {
         let data = b"test data";
@@ -1183,4 +1476,62 @@ mod tests {
             "Iterator should find all stored hashes"
         );
     }
+
+    #[test]
+    fn test_bloom_sized_for_target_fpr() {
+        assert_eq!(bloom_bytes(0, 0.01), BLOOM_MIN_BYTES);
+        assert_eq!(bloom_bytes(usize::MAX / 64, 0.01), BLOOM_MAX_BYTES);
+        // A tighter rate or more entries take more room
+        assert!(bloom_bytes(100_000, 0.001) > bloom_bytes(100_000, 0.01));
+        assert!(bloom_bytes(200_000, 0.01) > bloom_bytes(100_000, 0.01));
+
+        let entries = 20_000;
+        let mut bloom = BloomFilter::with_capacity(entries, 0.01);
+        for i in 0..entries {
+            bloom.add(&format!("present-{}", i));
+        }
+        assert!((0..entries).all(|i| bloom.contains(&format!("present-{}", i))));
+        let false_positives = (0..entries)
+            .filter(|i| bloom.contains(&format!("absent-{}", i)))
+            .count();
+        assert!(
+            false_positives < entries * 2 / 100,
+            "{} false positives",
+            false_positives
+        );
+    }
+
+    #[test]
+    fn test_sweep_reports_progress_and_honours_cancel() {
+        let temp = TempDir::new().unwrap();
+        let cas = CasStore::new(temp.path()).unwrap();
+
+        let keep = cas.store(b"keep me").unwrap();
+        cas.store(b"orphan").unwrap();
+        let mut bloom = BloomFilter::new(BLOOM_SIZE);
+        bloom.add(&CasStore::hash_to_hex(&keep));
+
+        let cancelled = Progress::default();
+        cancelled.cancel();
+        assert_eq!(
+            cas.sweep_with_progress(&bloom.bits, &cancelled).unwrap(),
+            (0, 0)
+        );
+        assert_eq!(cas.stats().unwrap().blob_count, 2);
+
+        let progress = Progress::default();
+        let (deleted, reclaimed) = cas.sweep_with_progress(&bloom.bits, &progress).unwrap();
+        assert_eq!((deleted, reclaimed), (1, b"orphan".len() as u64));
+        assert_eq!(
+            progress
+                .processed
+                .load(std::sync::atomic::Ordering::Relaxed),
+            2
+        );
+        assert_eq!(
+            progress.affected.load(std::sync::atomic::Ordering::Relaxed),
+            1
+        );
+        assert!(cas.exists(&keep));
+    }
 }
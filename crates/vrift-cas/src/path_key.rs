@@ -0,0 +1,286 @@
+//! Manifest keys for paths that are not valid UTF-8.
+//!
+//! POSIX file names are arbitrary bytes, but manifest keys travel as `str`
+//! (LMDB, IPC frames, the VDir hash table). A key keeps every valid UTF-8
+//! sequence of its path as it is and stands in for each byte that is not
+//! part of one with a character of the Private Use Area:
+//!
+//! ```text
+//! byte 0x80..=0xFF  <->  U+EF80..=U+EFFF
+//! ```
+//!
+//! Bytes below 0x80 are always valid on their own, so 128 characters cover
+//! every invalid byte. Keys of UTF-8 paths are the paths themselves, which
+//! keeps existing manifests valid. A real name containing one of these
+//! characters reads back as the raw byte; such names are not supported.
+//!
+//! The same name can also arrive in two Unicode forms: HFS+ hands out
+//! decomposed (NFD) names, while editors and build tools usually pass
+//! composed (NFC) ones, and APFS opens either. Keys built from paths
+//! ([`from_path`], [`normalize`]) are brought to the process's
+//! [`Normalization`], so both forms find the same entry. Escaped bytes are
+//! Private Use characters, which no normalization touches.
+
+use std::borrow::Cow;
+use std::ffi::OsStr;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use unicode_normalization::{is_nfc, is_nfd, UnicodeNormalization};
+
+/// Unicode form manifest keys are stored and looked up in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Normalization {
+    /// Keys keep the form of the name on disk. Linux file systems tell the
+    /// forms apart, so two names differing only in form are two files.
+    None = 0,
+    /// Composed
+    Nfc = 1,
+    /// Decomposed
+    Nfd = 2,
+}
+
+impl Normalization {
+    /// NFC on macOS, whose file systems treat both forms as one name; none
+    /// elsewhere
+    pub const PLATFORM_DEFAULT: Self = if cfg!(target_os = "macos") {
+        Self::Nfc
+    } else {
+        Self::None
+    };
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "none" => Some(Self::None),
+            "nfc" => Some(Self::Nfc),
+            "nfd" => Some(Self::Nfd),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Nfc => "nfc",
+            Self::Nfd => "nfd",
+        }
+    }
+}
+
+static NORMALIZATION: AtomicU8 = AtomicU8::new(Normalization::PLATFORM_DEFAULT as u8);
+
+/// Normalization of the keys this process builds. Ingest and lookup must
+/// agree on it: the daemons and CLI take it from their config, the shim from
+/// `VRIFT_KEY_NORMALIZATION`.
+pub fn set_normalization(form: Normalization) {
+    NORMALIZATION.store(form as u8, Ordering::Relaxed);
+}
+
+pub fn normalization() -> Normalization {
+    match NORMALIZATION.load(Ordering::Relaxed) {
+        1 => Normalization::Nfc,
+        2 => Normalization::Nfd,
+        _ => Normalization::None,
+    }
+}
+
+/// First character of the escape range; byte `b` maps to `ESCAPE_BASE + b`
+const ESCAPE_BASE: u32 = 0xEF00;
+
+/// Lead byte of every escape character in UTF-8 (U+EF80..=U+EFFF is
+/// `EE BE 80`..=`EE BF BF`)
+const ESCAPE_LEAD: u8 = 0xEE;
+
+/// Key for the path bytes `bytes`; borrowed when they are valid UTF-8
+pub fn from_bytes(bytes: &[u8]) -> Cow<'_, str> {
+    if let Ok(s) = std::str::from_utf8(bytes) {
+        return Cow::Borrowed(s);
+    }
+    let mut key = String::with_capacity(bytes.len() + 8);
+    for chunk in bytes.utf8_chunks() {
+        key.push_str(chunk.valid());
+        for &b in chunk.invalid() {
+            key.push(escape(b));
+        }
+    }
+    Cow::Owned(key)
+}
+
+/// Key for `path`: [`from_bytes`], then [`normalize`]d
+pub fn from_path(path: &Path) -> Cow<'_, str> {
+    match from_bytes(path.as_os_str().as_bytes()) {
+        Cow::Borrowed(key) => normalize(key),
+        Cow::Owned(key) => Cow::Owned(normalize(&key).into_owned()),
+    }
+}
+
+/// `key` in the process's [`Normalization`]; borrowed when it already is
+pub fn normalize(key: &str) -> Cow<'_, str> {
+    normalize_to(key, normalization())
+}
+
+fn normalize_to(key: &str, form: Normalization) -> Cow<'_, str> {
+    match form {
+        Normalization::None => Cow::Borrowed(key),
+        Normalization::Nfc if is_nfc(key) => Cow::Borrowed(key),
+        Normalization::Nfd if is_nfd(key) => Cow::Borrowed(key),
+        Normalization::Nfc => Cow::Owned(key.nfc().collect()),
+        Normalization::Nfd => Cow::Owned(key.nfd().collect()),
+    }
+}
+
+/// Path bytes for the key `key`; borrowed when it holds no escaped byte
+pub fn to_bytes(key: &str) -> Cow<'_, [u8]> {
+    if !key.as_bytes().contains(&ESCAPE_LEAD) {
+        return Cow::Borrowed(key.as_bytes());
+    }
+    let mut bytes = Vec::with_capacity(key.len());
+    let mut buf = [0u8; 4];
+    for c in key.chars() {
+        match unescape(c) {
+            Some(b) => bytes.push(b),
+            None => bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes()),
+        }
+    }
+    Cow::Owned(bytes)
+}
+
+/// Path for the key `key`, see [`to_bytes`]
+pub fn to_path(key: &str) -> Cow<'_, Path> {
+    match to_bytes(key) {
+        Cow::Borrowed(b) => Cow::Borrowed(Path::new(OsStr::from_bytes(b))),
+        Cow::Owned(b) => Cow::Owned(OsStr::from_bytes(&b).into()),
+    }
+}
+
+/// Whether the relative path `path` matches `pattern`: `*` and `?` within a
+/// path component, `**` across components. A pattern without a `/` is
+/// matched against the file name alone. Leading `/`s, as in manifest keys,
+/// are ignored on both.
+pub fn glob_matches(pattern: &str, path: &str) -> bool {
+    let path = path.trim_start_matches('/');
+    if pattern.contains('/') {
+        glob_match(pattern.trim_start_matches('/').as_bytes(), path.as_bytes())
+    } else {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        glob_match(pattern.as_bytes(), name.as_bytes())
+    }
+}
+
+fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        [b'*', b'*', rest @ ..] => {
+            let rest = rest.strip_prefix(b"/").unwrap_or(rest);
+            (0..=text.len()).any(|i| glob_match(rest, &text[i..]))
+        }
+        [b'*', rest @ ..] => {
+            for i in 0..=text.len() {
+                if glob_match(rest, &text[i..]) {
+                    return true;
+                }
+                if text.get(i) == Some(&b'/') {
+                    break;
+                }
+            }
+            false
+        }
+        [b'?', rest @ ..] => {
+            matches!(text.first(), Some(c) if *c != b'/') && glob_match(rest, &text[1..])
+        }
+        [c, rest @ ..] => text.first() == Some(c) && glob_match(rest, &text[1..]),
+    }
+}
+
+fn escape(b: u8) -> char {
+    // b >= 0x80 for any byte utf8_chunks reports invalid
+    char::from_u32(ESCAPE_BASE + b as u32).unwrap_or(char::REPLACEMENT_CHARACTER)
+}
+
+fn unescape(c: char) -> Option<u8> {
+    match c as u32 {
+        n @ 0xEF80..=0xEFFF => Some((n - ESCAPE_BASE) as u8),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_utf8_paths_are_their_own_key() {
+        let key = from_bytes("/src/naïve.rs".as_bytes());
+        assert!(matches!(key, Cow::Borrowed("/src/naïve.rs")));
+        assert!(matches!(to_bytes(&key), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_invalid_bytes_round_trip() {
+        // "caf\xe9" is Latin-1; "\xc3" alone is a truncated sequence
+        for raw in [&b"/src/caf\xe9.txt"[..], b"/\xc3", b"/a\xff\xfe/b\x80"] {
+            let key = from_bytes(raw);
+            assert!(matches!(key, Cow::Owned(_)));
+            assert_eq!(&*to_bytes(&key), raw);
+        }
+        assert_eq!(from_bytes(b"/caf\xe9"), "/caf\u{efe9}");
+    }
+
+    #[test]
+    fn test_distinct_paths_get_distinct_keys() {
+        let latin1 = from_bytes(b"/caf\xe9");
+        let utf8 = from_bytes("/café".as_bytes());
+        assert_ne!(latin1, utf8);
+    }
+
+    #[test]
+    fn test_composed_and_decomposed_names_share_a_key() {
+        let composed = "/src/caf\u{e9}.rs";
+        let decomposed = "/src/cafe\u{301}.rs";
+        for form in [Normalization::Nfc, Normalization::Nfd] {
+            assert_eq!(normalize_to(composed, form), normalize_to(decomposed, form));
+        }
+        assert!(matches!(
+            normalize_to(composed, Normalization::Nfc),
+            Cow::Borrowed(_)
+        ));
+        assert_eq!(normalize_to(decomposed, Normalization::Nfc), composed);
+        assert_eq!(normalize_to(composed, Normalization::Nfd), decomposed);
+        assert_ne!(
+            normalize_to(composed, Normalization::None),
+            normalize_to(decomposed, Normalization::None)
+        );
+    }
+
+    #[test]
+    fn test_normalization_keeps_escaped_bytes() {
+        let key = from_bytes(b"/cafe\xcc\x81/\xe9");
+        let nfc = normalize_to(&key, Normalization::Nfc);
+        assert_eq!(&*to_bytes(&nfc), b"/caf\xc3\xa9/\xe9");
+    }
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob_match(b"*.so", b"libz.so"));
+        assert!(!glob_match(b"*.so", b"lib/libz.so"));
+        assert!(glob_match(b"lib/*.so", b"lib/libz.so"));
+        assert!(glob_match(b"**/*.so", b"a/b/libz.so"));
+        assert!(glob_match(b"**/*.so", b"libz.so"));
+        assert!(glob_match(b"data.sqlite?", b"data.sqlite3"));
+        assert!(!glob_match(b"a?b", b"a/b"));
+        assert!(glob_matches("*.so", "/lib/libz.so"));
+        assert!(glob_matches(
+            "target/release/**",
+            "/target/release/deps/a.rlib"
+        ));
+        assert!(!glob_matches("target/release/**", "target/debug/a.rlib"));
+    }
+
+    #[test]
+    fn test_path_round_trip() {
+        let path = Path::new(OsStr::from_bytes(b"/tmp/r\xe9sum\xe9"));
+        assert_eq!(&*to_path(&from_path(path)), path);
+    }
+}
@@ -0,0 +1,243 @@
+//! Per-blob reference counts
+//!
+//! An optional LMDB database at `<cas_root>/refs.lmdb` that records, for
+//! every blob, how many registered manifests reference it. Each manifest's
+//! hash set is stored alongside the counts so that re-registering a manifest
+//! applies only what changed. A sweep driven by these counts
+//! ([`CasStore::sweep_refcounted`]) keeps exactly the referenced blobs,
+//! where a bloom filter also keeps some orphans through false positives.
+//!
+//! The counts are only as current as the last [`RefCounts::set_manifest`]
+//! for each manifest; `vrift gc` brings every registered manifest up to date
+//! before it asks for a sweep.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use heed::byteorder::LE;
+use heed::types::{Bytes, Str, U64};
+use heed::{Database, Env, EnvOpenOptions, RoTxn};
+
+use crate::{Blake3Hash, CasError, CasStore, Result};
+
+const HASH_LEN: usize = std::mem::size_of::<Blake3Hash>();
+
+/// Reference counts of the blobs in one CAS root
+pub struct RefCounts {
+    env: Env,
+    /// hash -> number of manifests referencing it; zero counts are not kept
+    counts: Database<Bytes, U64<LE>>,
+    /// manifest id -> its hashes, sorted and concatenated
+    manifests: Database<Str, Bytes>,
+}
+
+impl std::fmt::Debug for RefCounts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RefCounts")
+            .field("path", &self.env.path())
+            .finish_non_exhaustive()
+    }
+}
+
+fn decode_hashes(bytes: &[u8]) -> HashSet<Blake3Hash> {
+    bytes
+        .chunks_exact(HASH_LEN)
+        .filter_map(|chunk| chunk.try_into().ok())
+        .collect()
+}
+
+fn encode_hashes(hashes: &HashSet<Blake3Hash>) -> Vec<u8> {
+    let mut sorted: Vec<&Blake3Hash> = hashes.iter().collect();
+    sorted.sort_unstable();
+    sorted.into_iter().flatten().copied().collect()
+}
+
+impl RefCounts {
+    /// LMDB map size: 1GB (sparse; only touched pages take space)
+    const MAP_SIZE: usize = 1024 * 1024 * 1024;
+
+    /// Where the database of the store at `cas_root` lives
+    pub fn path(cas_root: &Path) -> PathBuf {
+        cas_root.join("refs.lmdb")
+    }
+
+    /// Open the database of the store at `cas_root`, creating it if needed
+    pub fn open(cas_root: &Path) -> Result<Self> {
+        let path = Self::path(cas_root);
+        std::fs::create_dir_all(&path)?;
+        let env = unsafe {
+            EnvOpenOptions::new()
+                .map_size(Self::MAP_SIZE)
+                .max_dbs(2)
+                .open(&path)?
+        };
+        let mut wtxn = env.write_txn()?;
+        let counts = env.create_database(&mut wtxn, Some("counts"))?;
+        let manifests = env.create_database(&mut wtxn, Some("manifests"))?;
+        wtxn.commit()?;
+        Ok(Self {
+            env,
+            counts,
+            manifests,
+        })
+    }
+
+    /// Open the database only if refcounting was ever enabled for the store
+    pub fn open_existing(cas_root: &Path) -> Result<Option<Self>> {
+        if Self::path(cas_root).is_dir() {
+            Self::open(cas_root).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Record that manifest `id` references exactly `hashes`, replacing what
+    /// was recorded for it before
+    pub fn set_manifest(&self, id: &str, hashes: &HashSet<Blake3Hash>) -> Result<()> {
+        let mut wtxn = self.env.write_txn()?;
+        let old = self
+            .manifests
+            .get(&wtxn, id)?
+            .map(decode_hashes)
+            .unwrap_or_default();
+        for hash in hashes.difference(&old) {
+            let count = self.counts.get(&wtxn, hash)?.unwrap_or(0);
+            self.counts.put(&mut wtxn, hash, &(count + 1))?;
+        }
+        for hash in old.difference(hashes) {
+            self.decrement(&mut wtxn, hash)?;
+        }
+        self.manifests.put(&mut wtxn, id, &encode_hashes(hashes))?;
+        wtxn.commit()?;
+        Ok(())
+    }
+
+    /// Drop manifest `id` and the references it held
+    pub fn remove_manifest(&self, id: &str) -> Result<()> {
+        let mut wtxn = self.env.write_txn()?;
+        let Some(old) = self.manifests.get(&wtxn, id)?.map(decode_hashes) else {
+            return Ok(());
+        };
+        for hash in &old {
+            self.decrement(&mut wtxn, hash)?;
+        }
+        self.manifests.delete(&mut wtxn, id)?;
+        wtxn.commit()?;
+        Ok(())
+    }
+
+    fn decrement(&self, wtxn: &mut heed::RwTxn, hash: &Blake3Hash) -> Result<()> {
+        match self.counts.get(wtxn, hash)? {
+            Some(count) if count > 1 => self.counts.put(wtxn, hash, &(count - 1))?,
+            _ => {
+                self.counts.delete(wtxn, hash)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Ids of the manifests whose references are recorded
+    pub fn manifests(&self) -> Result<Vec<String>> {
+        let rtxn = self.env.read_txn()?;
+        let mut ids = Vec::new();
+        for item in self.manifests.iter(&rtxn)? {
+            let (id, _) = item?;
+            ids.push(id.to_string());
+        }
+        Ok(ids)
+    }
+
+    /// Number of recorded manifests referencing `hash`
+    pub fn refs(&self, hash: &Blake3Hash) -> Result<u64> {
+        let rtxn = self.env.read_txn()?;
+        Ok(self.counts.get(&rtxn, hash)?.unwrap_or(0))
+    }
+
+    pub(crate) fn read_txn(&self) -> Result<RoTxn<'_>> {
+        Ok(self.env.read_txn()?)
+    }
+
+    /// Whether `hash` is referenced, as of `rtxn`. A lookup that fails
+    /// counts as referenced: a sweep must never delete on an error.
+    pub(crate) fn is_referenced(&self, rtxn: &RoTxn, hash: &Blake3Hash) -> bool {
+        self.counts
+            .get(rtxn, hash)
+            .map_or(true, |count| count.unwrap_or(0) > 0)
+    }
+}
+
+impl CasStore {
+    /// How many recorded manifests reference `hash`, or `None` if the store
+    /// has no refcount database
+    pub fn refs(&self, hash: &Blake3Hash) -> Result<Option<u64>> {
+        match RefCounts::open_existing(&self.root)? {
+            Some(refs) => refs.refs(hash).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// The store's refcount database; fails if there is none, since a sweep
+    /// without one would find every blob unreferenced
+    pub(crate) fn existing_refcounts(&self) -> Result<RefCounts> {
+        RefCounts::open_existing(&self.root)?.ok_or_else(|| {
+            CasError::Io(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!(
+                    "no refcount database at {}",
+                    RefCounts::path(&self.root).display()
+                ),
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn set(hashes: &[Blake3Hash]) -> HashSet<Blake3Hash> {
+        hashes.iter().copied().collect()
+    }
+
+    #[test]
+    fn test_counts_follow_manifests() {
+        let temp = TempDir::new().unwrap();
+        let refs = RefCounts::open(temp.path()).unwrap();
+        let (a, b, c) = ([1u8; 32], [2u8; 32], [3u8; 32]);
+
+        refs.set_manifest("one", &set(&[a, b])).unwrap();
+        refs.set_manifest("two", &set(&[b, c])).unwrap();
+        assert_eq!(
+            [a, b, c].map(|h| refs.refs(&h).unwrap()),
+            [1, 2, 1],
+            "b is shared"
+        );
+
+        // Re-registering applies only the difference
+        refs.set_manifest("one", &set(&[a])).unwrap();
+        refs.set_manifest("one", &set(&[a])).unwrap();
+        assert_eq!([a, b, c].map(|h| refs.refs(&h).unwrap()), [1, 1, 1]);
+
+        refs.remove_manifest("two").unwrap();
+        refs.remove_manifest("missing").unwrap();
+        assert_eq!([a, b, c].map(|h| refs.refs(&h).unwrap()), [1, 0, 0]);
+        assert_eq!(refs.manifests().unwrap(), ["one"]);
+    }
+
+    #[test]
+    fn test_store_refs() {
+        let temp = TempDir::new().unwrap();
+        let cas = CasStore::new(temp.path()).unwrap();
+        let hash = cas.store(b"counted").unwrap();
+        assert_eq!(cas.refs(&hash).unwrap(), None);
+        assert!(cas.existing_refcounts().is_err());
+
+        RefCounts::open(temp.path())
+            .unwrap()
+            .set_manifest("m", &set(&[hash]))
+            .unwrap();
+        assert_eq!(cas.refs(&hash).unwrap(), Some(1));
+        assert_eq!(cas.refs(&[0u8; 32]).unwrap(), Some(0));
+    }
+}
@@ -12,6 +12,7 @@
 use std::fs::{self};
 use std::io;
 use std::path::Path;
+use vrift_error::{Classify, ErrorKind};
 
 /// Result of an ingestion operation
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -47,6 +48,16 @@ pub enum ReflinkError {
     Io(#[from] io::Error),
 }
 
+impl Classify for ReflinkError {
+    fn classify(&self) -> ErrorKind {
+        match self {
+            // Callers fall back to hardlink/copy; only surfaced when they don't
+            ReflinkError::NotSupported | ReflinkError::CrossDevice => ErrorKind::Io,
+            ReflinkError::Io(e) => e.classify(),
+        }
+    }
+}
+
 /// Try to create a reflink (CoW clone) from source to destination.
 ///
 /// On Linux, uses FICLONE ioctl (btrfs, xfs, ext4 with reflink)
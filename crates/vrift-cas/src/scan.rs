@@ -0,0 +1,312 @@
+//! Content scanning at ingest
+//!
+//! A [`ContentScanner`] sees every file the streaming ingest finds before it
+//! is hashed into the CAS, a batch at a time. The files it flags are
+//! quarantined: they come out of the ingest as [`CasError::Quarantined`]
+//! instead of an [`IngestResult`](crate::IngestResult), so nothing of them
+//! enters the CAS or the manifest. Phantom ingest leaves them where they are.
+//!
+//! [`CommandScanner`] runs an external program per batch, which is how
+//! ClamAV, trufflehog and the like are wired in:
+//!
+//! - the batch's paths go to its stdin, one absolute path per line;
+//! - it prints a line `<path>\t<reason>` (or just `<path>`) for each file
+//!   to quarantine, and nothing for clean ones;
+//! - it exits 0 once it has scanned the batch. Any other exit status, or no
+//!   exit before the timeout, is a failure and quarantines the whole batch:
+//!   a scanner that cannot vouch for a file keeps it out of the CAS.
+
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::io::{self, Read, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
+
+use crate::CasError;
+
+/// A file a scanner wants kept out of the CAS
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+/// Decides which files of an ingest are quarantined
+pub trait ContentScanner: Send + Sync {
+    /// Scan `paths`, returning the ones to quarantine. An error quarantines
+    /// the whole batch.
+    fn scan(&self, paths: &[&Path]) -> io::Result<Vec<Finding>>;
+}
+
+/// Runs an external scanner per batch (see the [module docs](self))
+#[derive(Debug, Clone)]
+pub struct CommandScanner {
+    argv: Vec<String>,
+    timeout: Duration,
+}
+
+impl CommandScanner {
+    /// Scanner running `argv`, killed if a batch takes longer than `timeout`
+    pub fn new(argv: Vec<String>, timeout: Duration) -> io::Result<Self> {
+        if argv.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "scanner command is empty",
+            ));
+        }
+        Ok(Self { argv, timeout })
+    }
+
+    pub fn program(&self) -> &str {
+        &self.argv[0]
+    }
+}
+
+impl ContentScanner for CommandScanner {
+    fn scan(&self, paths: &[&Path]) -> io::Result<Vec<Finding>> {
+        let mut input = Vec::new();
+        for path in paths {
+            input.extend_from_slice(path.as_os_str().as_bytes());
+            input.push(b'\n');
+        }
+
+        let mut child = Command::new(&self.argv[0])
+            .args(&self.argv[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        // Fed from its own thread: a scanner may report before it has read
+        // the whole batch, and would block on a full stdout pipe
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let feeder = std::thread::spawn(move || {
+            let _ = stdin.write_all(&input);
+        });
+        let mut stdout = child.stdout.take().expect("stdout is piped");
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let mut out = Vec::new();
+            let _ = tx.send(stdout.read_to_end(&mut out).map(|_| out));
+        });
+
+        let out = match rx.recv_timeout(self.timeout) {
+            Ok(out) => out,
+            Err(_) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!("{} took longer than {:?}", self.argv[0], self.timeout),
+                ));
+            }
+        };
+        let status = child.wait()?;
+        let _ = feeder.join();
+        let out = out?;
+        if !status.success() {
+            return Err(io::Error::other(format!(
+                "{} exited with {}",
+                self.argv[0], status
+            )));
+        }
+
+        Ok(parse_findings(&out, paths))
+    }
+}
+
+/// The lines of `out` naming one of `paths`; others are ignored
+fn parse_findings(out: &[u8], paths: &[&Path]) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for line in out.split(|&b| b == b'\n') {
+        let (path, reason) = match line.iter().position(|&b| b == b'\t') {
+            Some(tab) => (&line[..tab], &line[tab + 1..]),
+            None => (line, &b""[..]),
+        };
+        let path = Path::new(OsStr::from_bytes(path));
+        if path.as_os_str().is_empty() || !paths.contains(&path) {
+            continue;
+        }
+        let reason = String::from_utf8_lossy(reason).trim().to_string();
+        findings.push(Finding {
+            path: path.to_path_buf(),
+            reason: if reason.is_empty() {
+                "flagged by scanner".to_string()
+            } else {
+                reason
+            },
+        });
+    }
+    findings
+}
+
+/// Holds back the files of a streaming ingest until their batch is scanned
+pub(crate) struct ScanGate<T> {
+    scanner: Option<Arc<dyn ContentScanner>>,
+    batch_size: usize,
+    pending: Vec<T>,
+    path_of: fn(&T) -> &Path,
+    quarantined: Vec<CasError>,
+}
+
+impl<T> ScanGate<T> {
+    pub(crate) fn new(
+        scanner: Option<Arc<dyn ContentScanner>>,
+        batch_size: usize,
+        path_of: fn(&T) -> &Path,
+    ) -> Self {
+        Self {
+            scanner,
+            batch_size: batch_size.max(1),
+            pending: Vec::new(),
+            path_of,
+            quarantined: Vec::new(),
+        }
+    }
+
+    /// Pass `item` on to `send` once it is known clean; false once `send`
+    /// has refused one
+    pub(crate) fn push(&mut self, item: T, send: &mut impl FnMut(T) -> bool) -> bool {
+        if self.scanner.is_none() {
+            return send(item);
+        }
+        self.pending.push(item);
+        if self.pending.len() < self.batch_size {
+            return true;
+        }
+        self.flush(send)
+    }
+
+    /// Scan what is left, returning the quarantined files
+    pub(crate) fn finish(mut self, send: &mut impl FnMut(T) -> bool) -> Vec<CasError> {
+        self.flush(send);
+        self.quarantined
+    }
+
+    fn flush(&mut self, send: &mut impl FnMut(T) -> bool) -> bool {
+        let Some(ref scanner) = self.scanner else {
+            return true;
+        };
+        if self.pending.is_empty() {
+            return true;
+        }
+        let batch = std::mem::take(&mut self.pending);
+
+        let mut flagged: HashMap<PathBuf, String> = HashMap::new();
+        // A path with a newline cannot be told apart from two on the
+        // scanner's stdin
+        let (scannable, unscannable): (Vec<&Path>, Vec<&Path>) = batch
+            .iter()
+            .map(|item| (self.path_of)(item))
+            .partition(|path| !path.as_os_str().as_bytes().contains(&b'\n'));
+        for path in unscannable {
+            flagged.insert(
+                path.to_path_buf(),
+                "path cannot be passed to the scanner".to_string(),
+            );
+        }
+        if !scannable.is_empty() {
+            match scanner.scan(&scannable) {
+                Ok(findings) => {
+                    flagged.extend(findings.into_iter().map(|f| (f.path, f.reason)));
+                }
+                Err(e) => {
+                    let reason = format!("scanner failed: {}", e);
+                    for path in scannable {
+                        flagged.insert(path.to_path_buf(), reason.clone());
+                    }
+                }
+            }
+        }
+
+        for item in batch {
+            match flagged.remove((self.path_of)(&item)) {
+                Some(reason) => self.quarantined.push(CasError::Quarantined {
+                    path: (self.path_of)(&item).to_path_buf(),
+                    reason,
+                }),
+                None => {
+                    if !send(item) {
+                        return false;
+                    }
+                }
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sh(script: &str) -> CommandScanner {
+        CommandScanner::new(
+            vec!["sh".to_string(), "-c".to_string(), script.to_string()],
+            Duration::from_secs(10),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_command_scanner_reports_flagged_paths() {
+        let scanner =
+            sh(r#"while read -r p; do case "$p" in *bad*) printf '%s\tEICAR\n' "$p";; esac; done"#);
+        let findings = scanner
+            .scan(&[Path::new("/src/good.txt"), Path::new("/src/bad.txt")])
+            .unwrap();
+        assert_eq!(
+            findings,
+            vec![Finding {
+                path: PathBuf::from("/src/bad.txt"),
+                reason: "EICAR".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_command_scanner_failure_is_an_error() {
+        let scanner = sh("cat >/dev/null; exit 2");
+        assert!(scanner.scan(&[Path::new("/src/a")]).is_err());
+    }
+
+    #[test]
+    fn test_command_scanner_times_out() {
+        let scanner = CommandScanner::new(
+            vec!["sleep".to_string(), "10".to_string()],
+            Duration::from_millis(100),
+        )
+        .unwrap();
+        let err = scanner.scan(&[Path::new("/src/a")]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+    }
+
+    #[test]
+    fn test_parse_findings_ignores_unknown_paths() {
+        let paths = [Path::new("/src/a")];
+        let findings = parse_findings(b"/elsewhere\tx\n/src/a\n\n", &paths);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].reason, "flagged by scanner");
+    }
+
+    #[test]
+    fn test_scan_gate_quarantines_batch_on_failure() {
+        let mut gate: ScanGate<PathBuf> = ScanGate::new(
+            Some(Arc::new(sh("cat >/dev/null; exit 1"))),
+            2,
+            PathBuf::as_path,
+        );
+        let mut sent = Vec::new();
+        let mut send = |p: PathBuf| {
+            sent.push(p);
+            true
+        };
+        for name in ["/src/a", "/src/b", "/src/c"] {
+            assert!(gate.push(PathBuf::from(name), &mut send));
+        }
+        let quarantined = gate.finish(&mut send);
+        assert!(sent.is_empty());
+        assert_eq!(quarantined.len(), 3);
+    }
+}
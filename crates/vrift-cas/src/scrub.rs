@@ -0,0 +1,124 @@
+//! CAS integrity walk
+//!
+//! A blob is named by the hash of its content, so a blob whose bytes no
+//! longer hash to its name (a disk error, or a write that got past the
+//! read-only protection) is served as the wrong file until something reads
+//! it through [`CasStore::verify_stream`]. [`CasStore::scrub_progress`]
+//! re-hashes every blob in the store and reports those that fail.
+//!
+//! Removing a corrupt blob turns it into a miss: the next ingest of the file
+//! stores it again, and a configured upstream refetches it.
+
+use std::io;
+use std::sync::atomic::Ordering;
+
+use crate::{Blake3Hash, CasError, CasStore, Progress, Result};
+
+impl CasStore {
+    /// Re-hash every blob and return those whose content does not match
+    /// their hash, removing them from the store when `delete_corrupt` is set.
+    ///
+    /// Publishes blobs checked as `processed`, corrupt ones as `affected` and
+    /// bytes read as `bytes`, and stops early once [`Progress::cancel`] has
+    /// been called. Blobs deleted while the walk runs are skipped.
+    pub fn scrub_progress(
+        &self,
+        delete_corrupt: bool,
+        progress: &Progress,
+    ) -> Result<Vec<Blake3Hash>> {
+        let mut corrupt = Vec::new();
+        for hash_res in self.iter()? {
+            if progress.is_cancelled() {
+                break;
+            }
+            let hash = hash_res?;
+            let mut reader = match self.verify_stream(&hash) {
+                Ok(reader) => reader,
+                Err(CasError::NotFound { .. }) => continue,
+                Err(e) => return Err(e),
+            };
+            let result = io::copy(&mut reader, &mut io::sink());
+            progress.processed.fetch_add(1, Ordering::Relaxed);
+            match result {
+                Ok(bytes) => {
+                    progress.bytes.fetch_add(bytes, Ordering::Relaxed);
+                }
+                Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                    tracing::warn!("Corrupt blob {}: {}", Self::hash_to_hex(&hash), e);
+                    progress.affected.fetch_add(1, Ordering::Relaxed);
+                    if delete_corrupt {
+                        self.delete(&hash)?;
+                    }
+                    corrupt.push(hash);
+                }
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(corrupt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::TempDir;
+
+    fn tamper(cas: &CasStore, hash: &Blake3Hash) {
+        let path = cas.blob_path_for_hash(hash).unwrap();
+        let _ = crate::protection::set_immutable(&path, false);
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        fs::write(&path, b"tampered").unwrap();
+    }
+
+    #[test]
+    fn test_scrub_reports_only_corrupt_blobs() {
+        let temp = TempDir::new().unwrap();
+        let cas = CasStore::new(temp.path()).unwrap();
+        let good = cas.store(b"intact content").unwrap();
+        let bad = cas.store(b"content to damage").unwrap();
+        tamper(&cas, &bad);
+
+        let progress = Progress::default();
+        assert_eq!(cas.scrub_progress(false, &progress).unwrap(), vec![bad]);
+        assert_eq!(progress.processed.load(Ordering::Relaxed), 2);
+        assert_eq!(progress.affected.load(Ordering::Relaxed), 1);
+        assert!(cas.exists(&good));
+        assert!(cas.exists(&bad));
+    }
+
+    #[test]
+    fn test_scrub_deletes_corrupt_blobs_on_request() {
+        let temp = TempDir::new().unwrap();
+        let cas = CasStore::new(temp.path()).unwrap();
+        let good = cas.store(b"intact content").unwrap();
+        let bad = cas.store(b"content to damage").unwrap();
+        tamper(&cas, &bad);
+
+        assert_eq!(
+            cas.scrub_progress(true, &Progress::default()).unwrap(),
+            vec![bad]
+        );
+        assert!(cas.exists(&good));
+        assert!(!cas.exists(&bad));
+        assert!(cas
+            .scrub_progress(false, &Progress::default())
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_cancelled_scrub_stops() {
+        let temp = TempDir::new().unwrap();
+        let cas = CasStore::new(temp.path()).unwrap();
+        cas.store(b"one").unwrap();
+        cas.store(b"two").unwrap();
+
+        let progress = Progress::default();
+        progress.cancel();
+        assert!(cas.scrub_progress(false, &progress).unwrap().is_empty());
+        assert_eq!(progress.processed.load(Ordering::Relaxed), 0);
+    }
+}
@@ -0,0 +1,122 @@
+//! Files an ingest leaves out of the CAS
+//!
+//! Storing a 10 GB video in the CAS buys nothing: no build dedups it and
+//! Solid Tier-2 ingest keeps the original besides, doubling disk use. A
+//! [`SkipPolicy`] keeps such files out by size or, optionally, by looking
+//! binary. Skipped files come out of the ingest as [`CasError::Skipped`]
+//! instead of an [`IngestResult`](crate::IngestResult): they are not read
+//! into the CAS and stay where they are, so builds see the real file. They
+//! get no manifest entry, or with [`SkipPolicy::reference`] one that points
+//! the shim at the real file.
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use crate::CasError;
+
+/// Bytes at the start of a file the binary check looks at; git looks at as
+/// many
+const BINARY_SNIFF_LEN: usize = 8000;
+
+/// Which files an ingest leaves out by size or content. The default keeps
+/// every file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SkipPolicy {
+    /// Files larger than this many bytes are skipped
+    pub max_size: Option<u64>,
+    /// Files that look binary (a NUL byte near the start) are skipped
+    pub binary: bool,
+    /// Skipped files get a manifest entry referencing their real path
+    /// instead of none
+    pub reference: bool,
+}
+
+impl SkipPolicy {
+    pub fn is_empty(&self) -> bool {
+        self.max_size.is_none() && !self.binary
+    }
+
+    /// Why the `size`-byte file at `path` stays out of the CAS, if it does.
+    /// The binary check reads the start of the file; an unreadable file is
+    /// left to the ingest to fail on.
+    pub fn reason(&self, path: &Path, size: u64) -> Option<String> {
+        if let Some(max) = self.max_size.filter(|max| size > *max) {
+            return Some(format!("{} bytes, over the {} byte limit", size, max));
+        }
+        if self.binary && looks_binary(path).unwrap_or(false) {
+            return Some("binary content".to_string());
+        }
+        None
+    }
+
+    /// [`reason`](Self::reason) as the error an ingest reports the file with
+    pub fn check(&self, path: &Path, size: u64) -> Result<(), CasError> {
+        match self.reason(path, size) {
+            Some(reason) => Err(CasError::Skipped {
+                path: path.to_path_buf(),
+                reason,
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Whether the file at `path` looks binary: a NUL byte within its first
+/// 8000 bytes, as git decides
+pub fn looks_binary(path: &Path) -> io::Result<bool> {
+    let mut buf = [0u8; BINARY_SNIFF_LEN];
+    let mut file = File::open(path)?;
+    let mut len = 0;
+    while len < buf.len() {
+        match file.read(&mut buf[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(buf[..len].contains(&0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_skip_by_size_and_content() {
+        let temp = tempdir().unwrap();
+        let text = temp.path().join("notes.txt");
+        let binary = temp.path().join("video.mp4");
+        fs::write(&text, "plain text\n").unwrap();
+        fs::write(&binary, b"\x00\x00\x00\x18ftypmp42").unwrap();
+
+        assert!(SkipPolicy::default().is_empty());
+        assert_eq!(SkipPolicy::default().reason(&binary, u64::MAX), None);
+
+        let by_size = SkipPolicy {
+            max_size: Some(11),
+            ..SkipPolicy::default()
+        };
+        assert_eq!(by_size.reason(&text, 11), None);
+        assert!(by_size.reason(&text, 12).is_some());
+        assert!(matches!(
+            by_size.check(&text, 12),
+            Err(CasError::Skipped { path, .. }) if path == text
+        ));
+
+        let by_content = SkipPolicy {
+            binary: true,
+            ..SkipPolicy::default()
+        };
+        assert_eq!(by_content.reason(&text, 11), None);
+        assert_eq!(
+            by_content.reason(&binary, 12).as_deref(),
+            Some("binary content")
+        );
+        // Unreadable files are not skipped; the ingest reports them
+        assert_eq!(by_content.reason(&temp.path().join("gone"), 0), None);
+    }
+}
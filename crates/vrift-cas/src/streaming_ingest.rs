@@ -12,17 +12,26 @@ use std::sync::Arc;
 use crossbeam::channel::{self, Receiver, Sender};
 use jwalk::WalkDir;
 
+use crate::scan::{ContentScanner, ScanGate};
+use crate::skip::SkipPolicy;
 use crate::{CasError, IngestMode, IngestResult};
 
 /// Channel capacity (bounded ring buffer)
 const CHANNEL_CAP: usize = 1024;
 
 /// Streaming ingest with producer-consumer pipeline
+///
+/// Only the files `filter` lets through are taken in. With `scanning` (a content scanner and its batch size), the scanner
+/// thread holds files back in batches of that size until they are vetted.
+/// The ones it flags come back as [`CasError::Quarantined`] without having
+/// been read into the CAS.
 pub fn streaming_ingest(
     source: &Path,
     cas_root: &Path,
     mode: IngestMode,
     threads: Option<usize>,
+    filter: &IngestFilter,
+    scanning: Option<(Arc<dyn ContentScanner>, usize)>,
 ) -> Vec<Result<IngestResult, CasError>> {
     use crate::zero_copy_ingest::{ingest_phantom, ingest_solid_tier1, ingest_solid_tier2};
 
@@ -39,30 +48,24 @@ pub fn streaming_ingest(
 
     // Scanner thread - sends paths, then drops tx to signal completion
     let source_path = source.to_path_buf();
+    let skip = filter.skip;
+    let filter = filter.clone();
     tracing::info!("[INGEST] Starting scanner thread for: {:?}", source_path);
     let scanner = std::thread::spawn(move || {
         let mut file_count = 0;
-        for entry in WalkDir::new(&source_path)
-            .process_read_dir(|_depth, _path, _state, children| {
-                children.retain(|entry| {
-                    entry.as_ref().map_or(true, |e| {
-                        let name = e.file_name.to_str().unwrap_or("");
-                        name != ".vrift" && name != ".git"
-                    })
-                });
-            })
-            .into_iter()
-            .filter_map(|e| e.ok())
-            .filter(|e| e.file_type().is_file())
-        {
-            let path = entry.path();
+        let (content_scanner, scan_batch) = scanning.unzip();
+        let mut gate = ScanGate::new(content_scanner, scan_batch.unwrap_or(1), PathBuf::as_path);
+        let mut send = |path: PathBuf| tx.send(path).is_ok();
+        for path in walk_files(&source_path, &filter) {
             file_count += 1;
-            if tx.send(path).is_err() {
+            if !gate.push(path, &mut send) {
                 tracing::warn!("[INGEST] Scanner: receivers dropped, stopping");
                 break;
             }
         }
+        let quarantined = gate.finish(&mut send);
         tracing::info!("[INGEST] Scanner complete: {} files found", file_count);
+        quarantined
     });
 
     // Phase4-#3: Per-worker local Vec (no Mutex contention)
@@ -78,11 +81,11 @@ pub fn streaming_ingest(
                 let mut processed = 0;
                 for path in rx {
                     tracing::trace!("[INGEST] Worker {} processing: {:?}", i, path);
-                    let result = match mode {
+                    let result = check_skip(&skip, &path).and_then(|()| match mode {
                         IngestMode::Phantom => ingest_phantom(&path, &cas),
                         IngestMode::SolidTier1 => ingest_solid_tier1(&path, &cas),
                         IngestMode::SolidTier2 => ingest_solid_tier2(&path, &cas),
-                    };
+                    });
                     tracing::trace!("[INGEST] Worker {} done: {:?}", i, path);
                     local_results.push(result);
                     processed += 1;
@@ -101,11 +104,11 @@ pub fn streaming_ingest(
     drop(rx);
 
     // Wait for scanner to complete first
-    scanner.join().expect("Scanner thread panicked");
+    let quarantined = scanner.join().expect("Scanner thread panicked");
     tracing::info!("[INGEST] Scanner thread joined");
 
     // Collect per-worker results into a single Vec (no lock, just extend)
-    let mut all_results = Vec::new();
+    let mut all_results: Vec<_> = quarantined.into_iter().map(Err).collect();
     for (i, worker) in workers.into_iter().enumerate() {
         let worker_results = worker
             .join()
@@ -134,12 +137,18 @@ pub fn streaming_ingest(
 /// * `mode` - Ingest mode
 /// * `threads` - Worker thread count
 /// * `cache_lookup` - Closure: manifest_key → Option<CacheHint>
+/// * `filter` - Files to take in, as for `streaming_ingest`
+/// * `scanning` - Content scanner and its batch size, as for `streaming_ingest`.
+///   Cache hits are scanned too, so new signatures apply to files ingested
+///   before.
 pub fn streaming_ingest_cached<F>(
     source: &Path,
     cas_root: &Path,
     mode: IngestMode,
     threads: Option<usize>,
     cache_lookup: F,
+    filter: &IngestFilter,
+    scanning: Option<(Arc<dyn ContentScanner>, usize)>,
 ) -> Vec<Result<IngestResult, CasError>>
 where
     F: Fn(&str) -> Option<crate::zero_copy_ingest::CacheHint> + Send + Sync + 'static,
@@ -155,7 +164,7 @@ where
     );
 
     // Phase5-#2: Channel sends (PathBuf, size, mtime_nsec, mode) — metadata from scanner stat
-    type FileEntry = (PathBuf, u64, u64, u32);
+    type FileEntry = (PathBuf, u64, i64, u32);
     let (tx, rx): (Sender<FileEntry>, Receiver<FileEntry>) = channel::bounded(CHANNEL_CAP);
 
     let num_threads = threads.unwrap_or_else(|| std::cmp::min(4, num_cpus::get() / 2).max(1));
@@ -167,23 +176,17 @@ where
     // Scanner thread — stat's each file and sends metadata
     let source_path = source.to_path_buf();
     let scanner_source = source_path.clone();
+    let skip = filter.skip;
+    let filter = filter.clone();
     let scanner = std::thread::spawn(move || {
         use std::os::unix::fs::MetadataExt;
         let mut file_count = 0;
-        for entry in WalkDir::new(&scanner_source)
-            .process_read_dir(|_depth, _path, _state, children| {
-                children.retain(|entry| {
-                    entry.as_ref().map_or(true, |e| {
-                        let name = e.file_name.to_str().unwrap_or("");
-                        name != ".vrift" && name != ".git"
-                    })
-                });
-            })
-            .into_iter()
-            .filter_map(|e| e.ok())
-            .filter(|e| e.file_type().is_file())
-        {
-            let path = entry.path();
+        let (content_scanner, scan_batch) = scanning.unzip();
+        let mut gate = ScanGate::new(content_scanner, scan_batch.unwrap_or(1), |e: &FileEntry| {
+            e.0.as_path()
+        });
+        let mut send = |entry: FileEntry| tx.send(entry).is_ok();
+        for path in walk_files(&scanner_source, &filter) {
             // Phase5-#2: stat once in scanner, avoid re-stat in worker
             let (size, mtime, mode) = match std::fs::metadata(&path) {
                 Ok(m) => {
@@ -193,11 +196,13 @@ where
                 Err(_) => continue, // skip unreadable files
             };
             file_count += 1;
-            if tx.send((path, size, mtime, mode)).is_err() {
+            if !gate.push((path, size, mtime, mode), &mut send) {
                 break;
             }
         }
+        let quarantined = gate.finish(&mut send);
         tracing::info!("[INGEST] Scanner complete: {} files found", file_count);
+        quarantined
     });
 
     // Phase4-#3: Per-worker local Vec (no Mutex contention)
@@ -217,22 +222,15 @@ where
                 // Phase5-#3: Reusable String buffer for manifest_key
                 let mut key_buf = String::with_capacity(256);
                 for (path, size, mtime, file_mode) in rx {
+                    if let Err(skipped) = skip.check(&path, size) {
+                        local_results.push(Err(skipped));
+                        processed += 1;
+                        continue;
+                    }
                     let result = match mode {
                         IngestMode::SolidTier2 => {
                             // Phase5-#3: Reuse key_buf instead of format!() allocation
-                            key_buf.clear();
-                            key_buf.push('/');
-                            match path.strip_prefix(&source_root) {
-                                Ok(rel) => {
-                                    use std::fmt::Write;
-                                    let _ = write!(key_buf, "{}", rel.display());
-                                }
-                                Err(_) => {
-                                    key_buf.push_str(
-                                        &path.file_name().unwrap_or_default().to_string_lossy(),
-                                    );
-                                }
-                            }
+                            write_manifest_key(&mut key_buf, &path, &source_root);
                             // Phase5-#2: Use prestat variant — zero syscalls on cache hit
                             let res = ingest_solid_tier2_cached_prestat(
                                 &path, &cas, &key_buf, &*cache, size, mtime, file_mode,
@@ -262,10 +260,10 @@ where
         .collect();
 
     drop(rx);
-    scanner.join().expect("Scanner thread panicked");
+    let quarantined = scanner.join().expect("Scanner thread panicked");
 
     // Collect per-worker results (no lock, just extend)
-    let mut all_results = Vec::new();
+    let mut all_results: Vec<_> = quarantined.into_iter().map(Err).collect();
     for (i, worker) in workers.into_iter().enumerate() {
         let worker_results = worker
             .join()
@@ -276,6 +274,214 @@ where
     all_results
 }
 
+/// Which files under the source an ingest takes in. The default takes in
+/// every file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IngestFilter {
+    /// Files and directories, relative to the source, to walk instead of
+    /// the whole source
+    pub paths: Vec<PathBuf>,
+    /// Globs (see [`path_key::glob_matches`](crate::path_key::glob_matches))
+    /// a file must match one of, when any are given
+    pub include: Vec<String>,
+    /// Globs no file taken in may match
+    pub exclude: Vec<String>,
+    /// Files walked but left out of the CAS by size or content, reported
+    /// as [`CasError::Skipped`]
+    pub skip: SkipPolicy,
+}
+
+impl IngestFilter {
+    /// Filter of a partial ingest of `source`. `paths` may be relative to
+    /// `source` or absolute inside it; the first one that leaves it is the
+    /// error.
+    pub fn new(
+        source: &Path,
+        paths: &[String],
+        include: Vec<String>,
+        exclude: Vec<String>,
+    ) -> Result<Self, String> {
+        use std::path::Component;
+        let paths = paths
+            .iter()
+            .map(|path| {
+                let rel = Path::new(path)
+                    .strip_prefix(source)
+                    .unwrap_or(Path::new(path));
+                rel.components()
+                    .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+                    .then(|| rel.to_path_buf())
+                    .ok_or_else(|| path.clone())
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            paths,
+            include,
+            exclude,
+            skip: SkipPolicy::default(),
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+            && self.include.is_empty()
+            && self.exclude.is_empty()
+            && self.skip.is_empty()
+    }
+
+    /// Whether the globs let through the file at `rel`, a path relative to
+    /// the source or a manifest key
+    pub fn matches(&self, rel: &str) -> bool {
+        use crate::path_key::glob_matches;
+        (self.include.is_empty() || self.include.iter().any(|g| glob_matches(g, rel)))
+            && !self.exclude.iter().any(|g| glob_matches(g, rel))
+    }
+
+    /// Where the walk starts: the given paths, else the literal directory
+    /// of each include glob (`target/release/**` walks `target/release`
+    /// only), else the whole source. Roots inside another root are dropped.
+    fn roots(&self, source: &Path) -> Vec<PathBuf> {
+        let mut roots: Vec<PathBuf> = if !self.paths.is_empty() {
+            self.paths.iter().map(|p| source.join(p)).collect()
+        } else if !self.include.is_empty() {
+            self.include
+                .iter()
+                .map(|glob| {
+                    let mut root = source.to_path_buf();
+                    if glob.contains('/') {
+                        let mut parts = glob.trim_start_matches('/').split('/').peekable();
+                        while let Some(part) = parts.next() {
+                            if parts.peek().is_none() || part.contains(['*', '?']) {
+                                break;
+                            }
+                            root.push(part);
+                        }
+                    }
+                    root
+                })
+                .collect()
+        } else {
+            vec![source.to_path_buf()]
+        };
+        roots.sort();
+        roots.dedup();
+        let mut kept: Vec<PathBuf> = Vec::with_capacity(roots.len());
+        for root in roots {
+            if !kept.iter().any(|k| root.starts_with(k)) {
+                kept.push(root);
+            }
+        }
+        kept
+    }
+}
+
+/// Regular files under `source` that an ingest takes in, skipping `.vrift`
+/// and `.git` and whatever `filter` leaves out
+pub fn walk_files(source: &Path, filter: &IngestFilter) -> impl Iterator<Item = PathBuf> {
+    let source = source.to_path_buf();
+    let filter = filter.clone();
+    let mut key_buf = String::with_capacity(256);
+    filter
+        .roots(&source)
+        .into_iter()
+        .flat_map(|root| -> Box<dyn Iterator<Item = PathBuf> + Send> {
+            if std::fs::symlink_metadata(&root).is_ok_and(|m| m.is_file()) {
+                return Box::new(std::iter::once(root));
+            }
+            Box::new(
+                WalkDir::new(root)
+                    .process_read_dir(|_depth, _path, _state, children| {
+                        children.retain(|entry| {
+                            entry.as_ref().map_or(true, |e| {
+                                let name = e.file_name.to_str().unwrap_or("");
+                                name != ".vrift" && name != ".git"
+                            })
+                        });
+                    })
+                    .into_iter()
+                    .filter_map(|e| e.ok())
+                    .filter(|e| e.file_type().is_file())
+                    .map(|e| e.path()),
+            )
+        })
+        .filter(move |path| {
+            if filter.include.is_empty() && filter.exclude.is_empty() {
+                return true;
+            }
+            write_manifest_key(&mut key_buf, path, &source);
+            filter.matches(&key_buf)
+        })
+}
+
+/// `skip`'s verdict on `path`, stat'ing it only when there is a policy
+fn check_skip(skip: &SkipPolicy, path: &Path) -> Result<(), CasError> {
+    if skip.is_empty() {
+        return Ok(());
+    }
+    match std::fs::metadata(path) {
+        Ok(meta) => skip.check(path, meta.len()),
+        // The ingest itself reports the file
+        Err(_) => Ok(()),
+    }
+}
+
+/// Set `key_buf` to the manifest key (`/`-rooted relative path) of `path`
+fn write_manifest_key(key_buf: &mut String, path: &Path, source_root: &Path) {
+    key_buf.clear();
+    key_buf.push('/');
+    match path.strip_prefix(source_root) {
+        Ok(rel) => key_buf.push_str(&crate::path_key::from_path(rel)),
+        Err(_) => {
+            let name = Path::new(path.file_name().unwrap_or_default());
+            key_buf.push_str(&crate::path_key::from_path(name));
+        }
+    }
+}
+
+/// A file of an ingest plan (see [`plan_ingest`])
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedFile {
+    pub path: PathBuf,
+    pub size: u64,
+    /// Skipped by the ingest: its mtime and size match the cache
+    pub unchanged: bool,
+    /// Left out of the CAS by the filter's [`SkipPolicy`], and why
+    pub skipped: Option<String>,
+}
+
+/// The files [`streaming_ingest_cached`] would take in from `source`,
+/// without reading any of them. As there, the cache only spares Solid
+/// Tier-2 files and unreadable files are left out; a `cache_lookup` that
+/// always answers `None` plans a plain [`streaming_ingest`]. The content
+/// scanner is not run; the skip policy is, reading the start of files when
+/// it checks for binaries.
+pub fn plan_ingest(
+    source: &Path,
+    mode: IngestMode,
+    cache_lookup: &dyn Fn(&str) -> Option<crate::zero_copy_ingest::CacheHint>,
+    filter: &IngestFilter,
+) -> Vec<PlannedFile> {
+    let use_cache = mode == IngestMode::SolidTier2;
+    let mut key_buf = String::with_capacity(256);
+    walk_files(source, filter)
+        .filter_map(|path| {
+            let meta = std::fs::metadata(&path).ok()?;
+            let mtime = crate::zero_copy_ingest::mtime_nsec_from_metadata(&meta);
+            let skipped = filter.skip.reason(&path, meta.len());
+            let unchanged = use_cache && skipped.is_none() && {
+                write_manifest_key(&mut key_buf, &path, source);
+                cache_lookup(&key_buf).is_some_and(|hint| hint.matches(meta.len(), mtime))
+            };
+            Some(PlannedFile {
+                path,
+                size: meta.len(),
+                unchanged,
+                skipped,
+            })
+        })
+        .collect()
+}
+
 /// Streaming ingest with progress callback
 pub fn streaming_ingest_with_progress<F>(
     source: &Path,
@@ -376,9 +582,250 @@ mod tests {
             .unwrap();
         }
 
-        let results = streaming_ingest(&source, &cas, IngestMode::SolidTier2, Some(4));
+        let results = streaming_ingest(
+            &source,
+            &cas,
+            IngestMode::SolidTier2,
+            Some(4),
+            &IngestFilter::default(),
+            None,
+        );
 
         assert_eq!(results.len(), 100);
         assert!(results.iter().all(|r| r.is_ok()));
     }
+
+    #[test]
+    fn test_streaming_ingest_quarantines_flagged_files() {
+        let temp = tempdir().unwrap();
+        let source = temp.path().join("source");
+        let cas = temp.path().join("cas");
+        fs::create_dir_all(&source).unwrap();
+        fs::create_dir_all(&cas).unwrap();
+        for i in 0..10 {
+            fs::write(
+                source.join(format!("file_{}.txt", i)),
+                format!("content {}", i),
+            )
+            .unwrap();
+        }
+        fs::write(source.join("infected.bin"), "X5O!P%@AP").unwrap();
+
+        let scanner = crate::CommandScanner::new(
+            vec![
+                "sh".to_string(),
+                "-c".to_string(),
+                "grep infected || true".to_string(),
+            ],
+            std::time::Duration::from_secs(10),
+        )
+        .unwrap();
+        let results = streaming_ingest(
+            &source,
+            &cas,
+            IngestMode::SolidTier2,
+            Some(2),
+            &IngestFilter::default(),
+            Some((Arc::new(scanner), 4)),
+        );
+
+        assert_eq!(results.len(), 11);
+        let quarantined: Vec<_> = results
+            .iter()
+            .filter_map(|r| match r {
+                Err(CasError::Quarantined { path, .. }) => Some(path.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(quarantined, vec![source.join("infected.bin")]);
+        assert_eq!(results.iter().filter(|r| r.is_ok()).count(), 10);
+    }
+
+    #[test]
+    fn test_plan_ingest_touches_nothing() {
+        use std::os::unix::fs::MetadataExt;
+
+        let temp = tempdir().unwrap();
+        let source = temp.path().join("source");
+        fs::create_dir_all(source.join("src")).unwrap();
+        fs::create_dir_all(source.join(".git")).unwrap();
+        fs::write(source.join("src/kept.txt"), "kept").unwrap();
+        fs::write(source.join("src/edited.txt"), "edited").unwrap();
+        fs::write(source.join(".git/HEAD"), "ref").unwrap();
+        let kept = fs::metadata(source.join("src/kept.txt")).unwrap();
+        let kept_mtime = crate::zero_copy_ingest::mtime_nsec_from_metadata(&kept);
+        let lookup = move |key: &str| {
+            (key == "/src/kept.txt" || key == "/src/edited.txt").then_some(
+                crate::zero_copy_ingest::CacheHint {
+                    content_hash: [0; 32],
+                    size: 4,
+                    mtime: kept_mtime,
+                },
+            )
+        };
+
+        let mut plan = plan_ingest(
+            &source,
+            IngestMode::SolidTier2,
+            &lookup,
+            &IngestFilter::default(),
+        );
+        plan.sort_by(|a, b| a.path.cmp(&b.path));
+        assert_eq!(
+            plan,
+            vec![
+                PlannedFile {
+                    path: source.join("src/edited.txt"),
+                    size: 6,
+                    unchanged: false,
+                    skipped: None,
+                },
+                PlannedFile {
+                    path: source.join("src/kept.txt"),
+                    size: 4,
+                    unchanged: true,
+                    skipped: None,
+                },
+            ]
+        );
+        // Phantom ingest moves every file, whatever the cache says
+        let plan = plan_ingest(
+            &source,
+            IngestMode::Phantom,
+            &lookup,
+            &IngestFilter::default(),
+        );
+        assert!(plan.iter().all(|f| !f.unchanged));
+        assert_eq!(
+            fs::metadata(source.join("src/kept.txt")).unwrap().ino(),
+            kept.ino()
+        );
+    }
+
+    #[test]
+    fn test_filter_paths_stay_inside_source() {
+        let source = Path::new("/work/project");
+        let filter = IngestFilter::new(
+            source,
+            &["/work/project/src".into(), "lib/a.rs".into()],
+            vec![],
+            vec![],
+        )
+        .unwrap();
+        assert_eq!(
+            filter.paths,
+            vec![PathBuf::from("src"), PathBuf::from("lib/a.rs")]
+        );
+        for outside in ["/etc/passwd", "../other", "src/../../x"] {
+            assert_eq!(
+                IngestFilter::new(source, &[outside.into()], vec![], vec![]),
+                Err(outside.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn test_filter_walks_only_matching_files() {
+        let temp = tempdir().unwrap();
+        let source = temp.path().join("source");
+        for dir in ["src", "target/release/deps", "target/debug"] {
+            fs::create_dir_all(source.join(dir)).unwrap();
+        }
+        for file in [
+            "src/main.rs",
+            "target/release/app",
+            "target/release/deps/app.d",
+            "target/release/deps/libz.rlib",
+            "target/debug/app",
+        ] {
+            fs::write(source.join(file), file).unwrap();
+        }
+        let none = |_: &str| None;
+        let planned = |filter: IngestFilter| {
+            let mut keys: Vec<_> = plan_ingest(&source, IngestMode::SolidTier2, &none, &filter)
+                .into_iter()
+                .map(|f| f.path.strip_prefix(&source).unwrap().to_path_buf())
+                .collect();
+            keys.sort();
+            keys
+        };
+
+        let filter = IngestFilter {
+            include: vec!["target/release/**".into()],
+            exclude: vec!["*.d".into()],
+            ..Default::default()
+        };
+        assert_eq!(filter.roots(&source), vec![source.join("target/release")]);
+        assert_eq!(
+            planned(filter),
+            vec![
+                PathBuf::from("target/release/app"),
+                PathBuf::from("target/release/deps/libz.rlib"),
+            ]
+        );
+
+        let filter = IngestFilter {
+            paths: vec!["src/main.rs".into(), "target".into(), "target/debug".into()],
+            include: vec!["**/app".into(), "*.rs".into()],
+            ..Default::default()
+        };
+        assert_eq!(
+            filter.roots(&source),
+            vec![source.join("src/main.rs"), source.join("target")]
+        );
+        assert_eq!(
+            planned(filter),
+            vec![
+                PathBuf::from("src/main.rs"),
+                PathBuf::from("target/debug/app"),
+                PathBuf::from("target/release/app"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_skip_policy_keeps_files_out_of_the_cas() {
+        let temp = tempdir().unwrap();
+        let source = temp.path().join("source");
+        let cas = temp.path().join("cas");
+        fs::create_dir_all(&source).unwrap();
+        fs::create_dir_all(&cas).unwrap();
+        fs::write(source.join("small.txt"), "small").unwrap();
+        fs::write(source.join("large.txt"), "x".repeat(100)).unwrap();
+        fs::write(source.join("image.png"), b"\x89PNG\r\n\x1a\n\x00\x00").unwrap();
+        let filter = IngestFilter {
+            skip: SkipPolicy {
+                max_size: Some(64),
+                binary: true,
+                ..SkipPolicy::default()
+            },
+            ..Default::default()
+        };
+
+        let results = streaming_ingest(
+            &source,
+            &cas,
+            IngestMode::SolidTier2,
+            Some(2),
+            &filter,
+            None,
+        );
+        let mut skipped: Vec<_> = results
+            .iter()
+            .filter_map(|r| match r {
+                Err(CasError::Skipped { path, .. }) => Some(path.clone()),
+                _ => None,
+            })
+            .collect();
+        skipped.sort();
+        assert_eq!(
+            skipped,
+            vec![source.join("image.png"), source.join("large.txt")]
+        );
+        assert_eq!(results.iter().filter(|r| r.is_ok()).count(), 1);
+
+        let none = |_: &str| None;
+        let plan = plan_ingest(&source, IngestMode::SolidTier2, &none, &filter);
+        assert_eq!(plan.iter().filter(|f| f.skipped.is_some()).count(), 2);
+    }
 }
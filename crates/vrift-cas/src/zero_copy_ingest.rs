@@ -118,7 +118,7 @@ pub struct IngestResult {
     pub skipped_by_cache: bool,
     /// File modification time in **nanoseconds** since Unix epoch.
     /// Computed via `mtime_nsec_from_metadata()` for consistency.
-    pub mtime: u64,
+    pub mtime: i64,
     /// File mode bits (carried from ingest stat)
     pub mode: u32,
 }
@@ -126,16 +126,103 @@ pub struct IngestResult {
 /// Compute nanosecond-precision mtime from filesystem metadata.
 ///
 /// Combines `MetadataExt::mtime()` (seconds) and `MetadataExt::mtime_nsec()`
-/// (nanosecond component) into a single `u64` value.
+/// (nanosecond component) into signed nanoseconds, the unit of
+/// `VnodeEntry::mtime` (see `vrift_ipc::mtime`). Pre-1970 times stay
+/// negative instead of wrapping.
 ///
 /// **All code that stores mtime MUST use this function** to avoid
 /// accidentally storing seconds instead of nanoseconds.
 #[inline]
-pub fn mtime_nsec_from_metadata(metadata: &std::fs::Metadata) -> u64 {
+pub fn mtime_nsec_from_metadata(metadata: &std::fs::Metadata) -> i64 {
     use std::os::unix::fs::MetadataExt;
-    (metadata.mtime() as u64)
+    metadata
+        .mtime()
         .saturating_mul(1_000_000_000)
-        .saturating_add(metadata.mtime_nsec() as u64)
+        .saturating_add(metadata.mtime_nsec())
+}
+
+/// What one ingest run did, tallied from its results
+#[derive(Debug, Default, Clone)]
+pub struct IngestTotals {
+    /// Files recorded (quarantined and skipped ones excluded)
+    pub files: u64,
+    /// Blobs added to the CAS
+    pub blobs: u64,
+    /// Bytes of those blobs
+    pub new_bytes: u64,
+    /// Bytes of all files recorded
+    pub total_bytes: u64,
+    /// Files skipped unread: mtime and size unchanged since the last ingest
+    pub unchanged: u64,
+    /// Files hashed whose content the CAS already had
+    pub deduped: u64,
+    /// Files the content scanner kept out
+    pub quarantined: u64,
+    /// Files the skip policy kept out, by size or content
+    pub skipped: u64,
+    /// Largest blobs added, largest first
+    pub largest_new: Vec<(PathBuf, u64)>,
+}
+
+impl IngestTotals {
+    /// Tally `results`, keeping the `largest` biggest new blobs
+    pub fn of(results: &[std::result::Result<IngestResult, CasError>], largest: usize) -> Self {
+        let mut totals = Self::default();
+        for r in results {
+            let r = match r {
+                Ok(r) => r,
+                Err(CasError::Quarantined { .. }) => {
+                    totals.quarantined += 1;
+                    continue;
+                }
+                Err(CasError::Skipped { .. }) => {
+                    totals.skipped += 1;
+                    continue;
+                }
+                Err(_) => {
+                    totals.files += 1;
+                    continue;
+                }
+            };
+            totals.files += 1;
+            totals.total_bytes += r.size;
+            if r.was_new {
+                totals.blobs += 1;
+                totals.new_bytes += r.size;
+                totals.note_new(&r.source_path, r.size, largest);
+            } else if r.skipped_by_cache {
+                totals.unchanged += 1;
+            } else {
+                totals.deduped += 1;
+            }
+        }
+        totals
+    }
+
+    /// Share of the files hashed whose content was already stored, in percent
+    pub fn dedup_rate(&self) -> f64 {
+        let hashed = self.blobs + self.deduped;
+        if hashed == 0 {
+            return 0.0;
+        }
+        100.0 * self.deduped as f64 / hashed as f64
+    }
+
+    fn note_new(&mut self, path: &Path, size: u64, largest: usize) {
+        if largest == 0 {
+            return;
+        }
+        if self.largest_new.len() == largest {
+            match self.largest_new.last() {
+                Some(&(_, smallest)) if smallest < size => {
+                    self.largest_new.pop();
+                }
+                _ => return,
+            }
+        }
+        let at = self.largest_new.partition_point(|&(_, s)| s >= size);
+        self.largest_new.insert(at, (path.to_path_buf(), size));
+    }
 }
 
 /// Cache hint from manifest for mtime+size skip optimization (P0)
@@ -147,7 +234,15 @@ pub struct CacheHint {
     pub content_hash: Blake3Hash,
     pub size: u64,
     /// Modification time in **nanoseconds** since Unix epoch (matches VnodeEntry.mtime)
-    pub mtime: u64,
+    pub mtime: i64,
+}
+
+impl CacheHint {
+    /// True if a file of `size` bytes last modified at `mtime` (ns) can be
+    /// taken to still have the recorded content
+    pub fn matches(&self, size: u64, mtime: i64) -> bool {
+        self.size == size && self.mtime == mtime
+    }
 }
 
 // ============================================================================
@@ -342,7 +437,7 @@ where
 
     // Cache hit: mtime(nsec)+size match → skip read+hash+link entirely
     if let Some(hint) = cache_lookup(manifest_key) {
-        if hint.size == size && hint.mtime == mtime {
+        if hint.matches(size, mtime) {
             return Ok(IngestResult {
                 source_path: source.to_owned(),
                 hash: hint.content_hash,
@@ -370,7 +465,7 @@ pub fn ingest_solid_tier2_cached_prestat<F>(
     manifest_key: &str,
     cache_lookup: &F,
     prestat_size: u64,
-    prestat_mtime: u64,
+    prestat_mtime: i64,
     prestat_mode: u32,
 ) -> Result<IngestResult>
 where
@@ -378,7 +473,7 @@ where
 {
     // Cache hit: mtime(nsec)+size match → ZERO syscalls
     if let Some(hint) = cache_lookup(manifest_key) {
-        if hint.size == prestat_size && hint.mtime == prestat_mtime {
+        if hint.matches(prestat_size, prestat_mtime) {
             return Ok(IngestResult {
                 source_path: source.to_owned(),
                 hash: hint.content_hash,
@@ -722,6 +817,24 @@ mod tests {
         assert_eq!(fs::read(&cas_file).unwrap(), original_content);
     }
 
+    #[test]
+    fn test_mtime_nsec_exact_and_signed() {
+        let (_source_dir, _cas_dir, test_file) = setup();
+        let file = fs::File::options().write(true).open(&test_file).unwrap();
+        let epoch = std::time::SystemTime::UNIX_EPOCH;
+
+        file.set_modified(epoch + std::time::Duration::new(1_700_000_000, 123_456_789))
+            .unwrap();
+        let meta = fs::metadata(&test_file).unwrap();
+        assert_eq!(mtime_nsec_from_metadata(&meta), 1_700_000_000_123_456_789);
+
+        // Before 1970: negative, not wrapped around
+        file.set_modified(epoch - std::time::Duration::from_millis(1_500))
+            .unwrap();
+        let meta = fs::metadata(&test_file).unwrap();
+        assert_eq!(mtime_nsec_from_metadata(&meta), -1_500_000_000);
+    }
+
     #[test]
     fn test_cached_ingest_skip() {
         let (_source_dir, cas_dir, test_file) = setup();
@@ -809,4 +922,49 @@ mod tests {
         );
         assert_eq!(result.hash, first.hash);
     }
+
+    #[test]
+    fn test_ingest_totals_breakdown() {
+        let result = |name: &str, size: u64, was_new: bool, skipped_by_cache: bool| {
+            Ok(IngestResult {
+                source_path: PathBuf::from(name),
+                hash: [0; 32],
+                size,
+                was_new,
+                skipped_by_cache,
+                mtime: 0,
+                mode: 0o644,
+            })
+        };
+        let results = vec![
+            result("a", 10, true, false),
+            result("b", 40, true, false),
+            result("c", 10, false, false),
+            result("d", 5, false, true),
+            result("e", 30, true, false),
+            Err(CasError::Quarantined {
+                path: PathBuf::from("f"),
+                reason: "secret".into(),
+            }),
+            Err(CasError::Skipped {
+                path: PathBuf::from("g"),
+                reason: "binary content".into(),
+            }),
+        ];
+
+        let totals = IngestTotals::of(&results, 2);
+        assert_eq!(totals.files, 5);
+        assert_eq!(totals.blobs, 3);
+        assert_eq!(totals.new_bytes, 80);
+        assert_eq!(totals.total_bytes, 95);
+        assert_eq!(totals.unchanged, 1);
+        assert_eq!(totals.deduped, 1);
+        assert_eq!(totals.quarantined, 1);
+        assert_eq!(totals.skipped, 1);
+        assert_eq!(totals.dedup_rate(), 25.0);
+        assert_eq!(
+            totals.largest_new,
+            vec![(PathBuf::from("b"), 40), (PathBuf::from("e"), 30)]
+        );
+    }
 }
@@ -2,7 +2,7 @@ use anyhow::{Context, Result};
 use std::path::{Path, PathBuf};
 use tokio::net::UnixStream;
 use vrift_config::path::{normalize_nonexistent, normalize_or_original};
-use vrift_ipc::{VeloRequest, VeloResponse, PROTOCOL_VERSION};
+use vrift_ipc::{IngestSelection, VeloRequest, VeloResponse, PROTOCOL_VERSION};
 
 /// Phase 1.2: Connection state returned by connect_to_daemon.
 /// Contains the vriftd stream plus vDird connection info from RegisterAck.
@@ -17,7 +17,7 @@ fn get_socket_path() -> PathBuf {
     vrift_config::config().socket_path().to_path_buf()
 }
 
-pub async fn check_status(_project_root: &Path) -> Result<()> {
+pub async fn check_status(project_root: &Path) -> Result<()> {
     let mut stream = tokio::time::timeout(std::time::Duration::from_secs(10), connect_simple())
         .await
         .map_err(|_| anyhow::anyhow!("Timed out connecting to daemon (10s)"))??;
@@ -40,9 +40,205 @@ pub async fn check_status(_project_root: &Path) -> Result<()> {
         _ => anyhow::bail!("Unexpected status response: {:?}", resp),
     }
 
+    send_request(&mut stream, VeloRequest::WorkspaceList).await?;
+    let resp = tokio::time::timeout(
+        std::time::Duration::from_secs(5),
+        read_response(&mut stream),
+    )
+    .await
+    .map_err(|_| anyhow::anyhow!("Timed out waiting for workspace list (5s)"))??;
+
+    if let VeloResponse::WorkspaceListAck { workspaces } = resp {
+        if !workspaces.is_empty() {
+            println!("Workspaces:");
+        }
+        for ws in workspaces {
+            let state = match ws.state {
+                vrift_ipc::WorkspaceState::Ready => "ready",
+                vrift_ipc::WorkspaceState::Loading => "loading",
+                vrift_ipc::WorkspaceState::Failed => "failed",
+            };
+            print!("  {:<8} {:>7}ms  {}", state, ws.load_ms, ws.project_root);
+            if ws.vdird_pid != 0 {
+                print!("  (vDird pid {})", ws.vdird_pid);
+            }
+            if let Some(err) = ws.error {
+                print!("  {}", err);
+            }
+            println!();
+        }
+    }
+
+    if let Some((committed, coalesced, pending)) = reingest_stats(project_root).await {
+        println!(
+            "Reingests: {} committed, {} coalesced, {} pending",
+            committed, coalesced, pending
+        );
+    }
+
     Ok(())
 }
 
+/// Reingest counters of the project's vDird, if one is running
+async fn reingest_stats(project_root: &Path) -> Option<(u64, u64, u64)> {
+    let project_id = vrift_config::path::compute_project_id(normalize_or_original(project_root));
+    let socket = vrift_config::path::get_vdird_socket_path(&project_id)?;
+    let mut stream = UnixStream::connect(&socket).await.ok()?;
+    send_request(&mut stream, VeloRequest::ReingestStats)
+        .await
+        .ok()?;
+    let resp = tokio::time::timeout(
+        std::time::Duration::from_secs(5),
+        read_response(&mut stream),
+    )
+    .await
+    .ok()?
+    .ok()?;
+    match resp {
+        VeloResponse::ReingestStatsAck {
+            committed,
+            coalesced,
+            pending,
+        } => Some((committed, coalesced, pending)),
+        _ => None,
+    }
+}
+
+/// Request and lookup counters of a workspace's vDird, if one is running
+pub async fn workspace_metrics(project_root: &Path) -> Option<vrift_ipc::WorkspaceMetrics> {
+    let project_id = vrift_config::path::compute_project_id(normalize_or_original(project_root));
+    let socket = vrift_config::path::get_vdird_socket_path(&project_id)?;
+    let control = vrift_ipc::control_socket_path(&socket.to_string_lossy());
+    let mut stream = UnixStream::connect(&control).await.ok()?;
+    send_request(&mut stream, VeloRequest::Metrics).await.ok()?;
+    let resp = tokio::time::timeout(
+        std::time::Duration::from_secs(5),
+        read_response(&mut stream),
+    )
+    .await
+    .ok()?
+    .ok()?;
+    match resp {
+        VeloResponse::MetricsAck { metrics } => Some(metrics),
+        _ => None,
+    }
+}
+
+/// Answer to a health probe
+#[derive(Debug)]
+pub struct DaemonHealth {
+    pub version: String,
+    pub uptime_secs: u64,
+    pub pid: u32,
+}
+
+/// Health probe for readiness checks. Unlike `connect_simple`, this never
+/// spawns the daemon: a probe that starts what it checks always passes.
+/// (A socket-activated daemon is still started by the service manager.)
+pub async fn ping(timeout: std::time::Duration) -> Result<DaemonHealth> {
+    ping_at(&get_socket_path(), timeout).await
+}
+
+async fn ping_at(socket_path: &Path, timeout: std::time::Duration) -> Result<DaemonHealth> {
+    let probe = async {
+        let mut stream = UnixStream::connect(socket_path)
+            .await
+            .with_context(|| format!("Daemon not reachable at {}", socket_path.display()))?;
+        send_request(&mut stream, VeloRequest::Ping).await?;
+        match read_response(&mut stream).await? {
+            VeloResponse::PingAck {
+                version,
+                uptime_secs,
+                pid,
+            } => Ok(DaemonHealth {
+                version,
+                uptime_secs,
+                pid,
+            }),
+            VeloResponse::Error(e) => anyhow::bail!("Ping failed: {}", e),
+            resp => anyhow::bail!("Unexpected ping response: {:?}", resp),
+        }
+    };
+    tokio::time::timeout(timeout, probe)
+        .await
+        .map_err(|_| anyhow::anyhow!("Daemon did not answer within {:?}", timeout))?
+}
+
+/// Fetch live workspace sessions from the daemon
+pub async fn list_sessions() -> Result<Vec<vrift_ipc::SessionInfo>> {
+    let mut stream = tokio::time::timeout(std::time::Duration::from_secs(10), connect_simple())
+        .await
+        .map_err(|_| anyhow::anyhow!("Timed out connecting to daemon (10s)"))??;
+
+    send_request(&mut stream, VeloRequest::SessionList).await?;
+    let resp = tokio::time::timeout(
+        std::time::Duration::from_secs(5),
+        read_response(&mut stream),
+    )
+    .await
+    .map_err(|_| anyhow::anyhow!("Timed out waiting for session list (5s)"))??;
+
+    match resp {
+        VeloResponse::SessionListAck { sessions } => Ok(sessions),
+        VeloResponse::Error(e) => anyhow::bail!("Session list failed: {}", e),
+        _ => anyhow::bail!("Unexpected session list response: {:?}", resp),
+    }
+}
+
+/// Recent daemon jobs, newest first
+pub async fn list_jobs() -> Result<Vec<vrift_ipc::JobInfo>> {
+    let mut stream = connect_simple().await?;
+    send_request(&mut stream, VeloRequest::JobList).await?;
+    match read_response(&mut stream).await? {
+        VeloResponse::JobListAck { jobs } => Ok(jobs),
+        VeloResponse::Error(e) => anyhow::bail!("Job list failed: {}", e),
+        resp => anyhow::bail!("Unexpected job list response: {:?}", resp),
+    }
+}
+
+/// Workspaces the daemon serves
+pub async fn list_workspaces() -> Result<Vec<vrift_ipc::WorkspaceInfo>> {
+    let mut stream = connect_simple().await?;
+    send_request(&mut stream, VeloRequest::WorkspaceList).await?;
+    match read_response(&mut stream).await? {
+        VeloResponse::WorkspaceListAck { workspaces } => Ok(workspaces),
+        VeloResponse::Error(e) => anyhow::bail!("Workspace list failed: {}", e),
+        resp => anyhow::bail!("Unexpected workspace list response: {:?}", resp),
+    }
+}
+
+/// Connection on which the daemon pushes the `events` it serves
+pub async fn subscribe(events: Vec<vrift_ipc::EventKind>) -> Result<UnixStream> {
+    let mut stream = connect_simple().await?;
+    send_request(&mut stream, VeloRequest::Subscribe { events }).await?;
+    match read_response(&mut stream).await? {
+        VeloResponse::SubscribeAck { events } if !events.is_empty() => Ok(stream),
+        VeloResponse::SubscribeAck { .. } => anyhow::bail!("Daemon pushes none of these events"),
+        VeloResponse::Error(e) => anyhow::bail!("Subscribe failed: {}", e.message),
+        resp => anyhow::bail!("Unexpected subscribe response: {:?}", resp),
+    }
+}
+
+/// Send a single-job request (status, cancel, retry) on `stream`
+pub async fn job_request(stream: &mut UnixStream, req: VeloRequest) -> Result<vrift_ipc::JobInfo> {
+    send_request(stream, req).await?;
+    match read_response(stream).await? {
+        VeloResponse::JobAck { job } => Ok(job),
+        VeloResponse::Error(e) => anyhow::bail!("Job request failed: {}", e.message),
+        resp => anyhow::bail!("Unexpected job response: {:?}", resp),
+    }
+}
+
+/// Send a request with its `dry_run` set and return the plan it answers with
+pub async fn plan_request(stream: &mut UnixStream, req: VeloRequest) -> Result<vrift_ipc::Plan> {
+    send_request(stream, req).await?;
+    match read_response(stream).await? {
+        VeloResponse::PlanAck { plan } => Ok(plan),
+        VeloResponse::Error(e) => anyhow::bail!("Dry run failed: {}", e.message),
+        resp => anyhow::bail!("Unexpected dry-run response: {:?}", resp),
+    }
+}
+
 pub async fn spawn_command(command: &[String], cwd: PathBuf, project_root: &Path) -> Result<()> {
     let conn = connect_to_daemon(project_root).await?;
     let mut stream = conn.stream;
@@ -157,7 +353,7 @@ pub async fn connect_to_daemon(project_root: &Path) -> Result<DaemonConnection>
 
 /// Simple connection to daemon - only handshake, no workspace registration
 /// Used for standalone operations like IngestFullScan
-async fn connect_simple() -> Result<UnixStream> {
+pub async fn connect_simple() -> Result<UnixStream> {
     let socket_path = get_socket_path();
 
     // Try connecting + handshake directly first
@@ -236,6 +432,7 @@ fn spawn_daemon() -> Result<()> {
 
     std::process::Command::new(daemon_bin)
         .arg("start")
+        .env(vrift_ipc::NO_INCEPTION_ENV, "1")
         .stdin(std::process::Stdio::null())
         .stdout(std::process::Stdio::null())
         .stderr(std::process::Stdio::null())
@@ -271,6 +468,7 @@ pub async fn ingest_via_daemon(
     prefix: Option<String>,
     cas_root: Option<&Path>,
     force_hash: bool,
+    selection: IngestSelection,
 ) -> Result<IngestResult> {
     // Normalize paths before sending to daemon (daemon's cwd may differ)
     let abs_path = normalize_or_original(path);
@@ -292,6 +490,8 @@ pub async fn ingest_via_daemon(
         prefix,
         cas_root: cas_root.map(|p| p.to_string_lossy().to_string()),
         force_hash,
+        dry_run: false,
+        selection: selection.boxed(),
     };
 
     tracing::info!(
@@ -318,6 +518,13 @@ pub async fn ingest_via_daemon(
             total_bytes,
             duration_ms,
             manifest_path,
+            tier1_files,
+            tier2_files,
+            unchanged_files,
+            deduped_files,
+            quarantined_files,
+            largest_new,
+            skipped_files,
         } => Ok(IngestResult {
             files,
             blobs,
@@ -325,12 +532,48 @@ pub async fn ingest_via_daemon(
             total_bytes,
             duration_ms,
             manifest_path,
+            tier1_files,
+            tier2_files,
+            unchanged_files,
+            deduped_files,
+            quarantined_files,
+            largest_new,
+            skipped_files,
         }),
         VeloResponse::Error(e) => anyhow::bail!("Daemon ingest failed: {}", e),
         _ => anyhow::bail!("Unexpected response from daemon: {:?}", resp),
     }
 }
 
+/// What an ingest of `path` into `manifest_path` would do, planned by the
+/// daemon without touching either
+pub async fn plan_ingest_via_daemon(
+    path: &Path,
+    manifest_path: &Path,
+    phantom: bool,
+    tier1: bool,
+    force_hash: bool,
+    selection: IngestSelection,
+) -> Result<vrift_ipc::Plan> {
+    let abs_path = normalize_or_original(path);
+    let abs_manifest =
+        normalize_nonexistent(manifest_path).unwrap_or_else(|_| manifest_path.to_path_buf());
+    let mut stream = connect_simple().await?;
+    let req = VeloRequest::IngestFullScan {
+        path: abs_path.to_string_lossy().to_string(),
+        manifest_path: abs_manifest.to_string_lossy().to_string(),
+        threads: None,
+        phantom,
+        tier1,
+        prefix: None,
+        cas_root: None,
+        force_hash,
+        dry_run: true,
+        selection: selection.boxed(),
+    };
+    plan_request(&mut stream, req).await
+}
+
 /// Result from daemon ingest
 #[derive(Debug)]
 #[allow(dead_code)]
@@ -341,4 +584,65 @@ pub struct IngestResult {
     pub total_bytes: u64,
     pub duration_ms: u64,
     pub manifest_path: String,
+    pub tier1_files: u64,
+    pub tier2_files: u64,
+    /// Skipped: mtime and size unchanged since the last ingest
+    pub unchanged_files: u64,
+    /// Hashed, content already in the CAS
+    pub deduped_files: u64,
+    /// Kept out by the content scanner
+    pub quarantined_files: u64,
+    /// Kept out by `[ingest.skip]`, by size or content
+    pub skipped_files: u64,
+    /// Largest first
+    pub largest_new: Vec<vrift_ipc::IngestBlob>,
+}
+
+impl IngestResult {
+    /// Share of the files hashed whose content the CAS already had, in percent
+    pub fn dedup_rate(&self) -> f64 {
+        let hashed = self.blobs + self.deduped_files;
+        if hashed == 0 {
+            return 0.0;
+        }
+        100.0 * self.deduped_files as f64 / hashed as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use vrift_ipc::testing::MockDaemon;
+
+    #[tokio::test]
+    async fn test_ping_reports_daemon_health() {
+        let daemon = MockDaemon::start().unwrap();
+        daemon.respond(
+            "Ping",
+            &VeloResponse::PingAck {
+                version: "0.1.0".into(),
+                uptime_secs: 315,
+                pid: 4242,
+            },
+        );
+
+        let health = ping_at(daemon.socket_path(), Duration::from_secs(2))
+            .await
+            .unwrap();
+        assert_eq!(health.version, "0.1.0");
+        assert_eq!(health.uptime_secs, 315);
+        assert_eq!(health.pid, 4242);
+        // A probe is a single request, no handshake
+        assert_eq!(daemon.request_kinds(), vec!["Ping"]);
+    }
+
+    #[tokio::test]
+    async fn test_ping_surfaces_daemon_error() {
+        let daemon = MockDaemon::start().unwrap();
+        let err = ping_at(daemon.socket_path(), Duration::from_secs(2))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Ping failed"), "{:#}", err);
+    }
 }
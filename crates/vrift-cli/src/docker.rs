@@ -0,0 +1,283 @@
+//! # vrift docker
+//!
+//! `vrift docker run <docker run arguments>` starts a container that sees the
+//! VFS: the inception layer, the CAS and the daemon sockets are bind-mounted
+//! into it and `LD_PRELOAD` is set, so a containerized build gets the same
+//! acceleration as one on the host.
+//!
+//! Host paths are mounted at the same paths inside the container. Manifest
+//! entries, the vDird mmap and every path the shim sends the daemon then mean
+//! the same thing on both sides without translation. The container runs as
+//! the calling user, so files it writes into the project keep their owner and
+//! the daemon sockets accept it; `--user` and `-w` given to `vrift docker run`
+//! come later on the command line and win.
+//!
+//! `vrift docker args` prints just the flags, shell-quoted, for tools that
+//! assemble `docker run` themselves (CI wrappers, devcontainer `runArgs`):
+//! `eval "docker run $(vrift docker args) image make"`.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use clap::{Args, Subcommand};
+use nix::unistd::{getegid, geteuid};
+use vrift_config::path::normalize_for_ipc;
+
+/// Where the inception layer is mounted inside the container
+pub const CONTAINER_SHIM: &str = "/opt/vrift/lib/libvrift_inception_layer.so";
+
+#[derive(Subcommand, Debug)]
+pub enum DockerCommand {
+    /// Run `docker run` with the VFS injected into the container
+    ///
+    /// Usage: vrift docker run [--docker podman] [docker run args] <image> [cmd...]
+    Run {
+        #[command(flatten)]
+        options: InjectOptions,
+
+        /// Container CLI to invoke
+        #[arg(long, default_value = "docker")]
+        docker: String,
+
+        /// `docker run` options, image and command
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true, required = true)]
+        args: Vec<String>,
+    },
+    /// Print the `docker run` flags that inject the VFS
+    Args {
+        #[command(flatten)]
+        options: InjectOptions,
+    },
+}
+
+#[derive(Args, Debug)]
+pub struct InjectOptions {
+    /// Linux build of the inception layer to mount (required on macOS, where
+    /// the host build is a dylib)
+    #[arg(long)]
+    shim: Option<PathBuf>,
+
+    /// Project directory (default: current directory)
+    #[arg(short = 'C', long)]
+    directory: Option<PathBuf>,
+}
+
+/// What a container needs to run under the VFS
+#[derive(Debug, PartialEq, Eq)]
+pub struct Injection {
+    /// Host path of the inception layer
+    pub shim: PathBuf,
+    /// Host paths mounted at the same path inside the container
+    pub mounts: Vec<PathBuf>,
+    pub env: Vec<(String, String)>,
+    pub workdir: PathBuf,
+    /// uid and gid the container runs as
+    pub user: (u32, u32),
+}
+
+impl Injection {
+    /// `docker run` flags, to go before the image
+    pub fn docker_args(&self) -> Vec<String> {
+        let mut args = vec![
+            "-v".to_string(),
+            format!("{}:{}:ro", self.shim.display(), CONTAINER_SHIM),
+        ];
+        for path in &self.mounts {
+            args.push("-v".to_string());
+            args.push(format!("{0}:{0}", path.display()));
+        }
+        for (key, value) in &self.env {
+            args.push("-e".to_string());
+            args.push(format!("{}={}", key, value));
+        }
+        args.push("-e".to_string());
+        args.push(format!("LD_PRELOAD={}", CONTAINER_SHIM));
+        args.push("--user".to_string());
+        args.push(format!("{}:{}", self.user.0, self.user.1));
+        args.push("-w".to_string());
+        args.push(self.workdir.display().to_string());
+        args
+    }
+}
+
+/// The outermost of nested paths; mounting a parent already brings the rest
+fn outermost(mut paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    paths.sort();
+    paths.dedup();
+    let mut kept: Vec<PathBuf> = Vec::new();
+    for path in paths {
+        if !kept.iter().any(|k| path.starts_with(k)) {
+            kept.push(path);
+        }
+    }
+    kept
+}
+
+fn shell_quote(arg: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "-_./:=,@%+".contains(c);
+    if !arg.is_empty() && arg.chars().all(plain) {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+async fn injection(cas_root: &Path, options: &InjectOptions) -> Result<Injection> {
+    let dir = match &options.directory {
+        Some(dir) => dir.clone(),
+        None => std::env::current_dir().context("Failed to get current directory")?,
+    };
+    let project_root = normalize_for_ipc(&dir).context("resolve project path")?;
+
+    let shim = match &options.shim {
+        Some(shim) => normalize_for_ipc(shim)
+            .with_context(|| format!("Inception layer not found: {}", shim.display()))?,
+        None if cfg!(target_os = "macos") => anyhow::bail!(
+            "Containers need the Linux build of the inception layer; pass it with --shim \
+             (e.g. built with `cargo build -p vrift-inception-layer --target x86_64-unknown-linux-gnu`)"
+        ),
+        None => crate::inception::find_inception_library(&project_root)?,
+    };
+
+    let cfg = vrift_config::Config::load_for_project(&project_root).unwrap_or_else(|e| {
+        eprintln!("Warning: Config load failed: {}. Using defaults.", e);
+        vrift_config::Config::default()
+    });
+    // The daemon has to be up for the container's shim to reach, and it
+    // tells us where this project's vDird lives
+    let conn = crate::daemon::connect_to_daemon(&project_root)
+        .await
+        .context("Daemon not running or unreachable")?;
+
+    let mut env: Vec<(String, String)> = cfg
+        .shim_env()
+        .into_iter()
+        .filter(|(key, _)| key != "VR_THE_SOURCE")
+        .collect();
+    env.push(("VR_THE_SOURCE".to_string(), cas_root.display().to_string()));
+    let mut paths = vec![
+        project_root.clone(),
+        cas_root.to_path_buf(),
+        cfg.socket_path().to_path_buf(),
+    ];
+    if cfg.project.manifest.is_absolute() {
+        paths.push(cfg.project.manifest.clone());
+    }
+    for (key, value) in [
+        ("VRIFT_VDIRD_SOCKET", &conn.vdird_socket),
+        ("VRIFT_VDIR_MMAP", &conn.vdir_mmap_path),
+    ] {
+        if !value.is_empty() {
+            env.push((key.to_string(), value.clone()));
+            paths.push(PathBuf::from(value));
+        }
+    }
+    paths.retain(|p| p.exists());
+
+    Ok(Injection {
+        shim,
+        mounts: outermost(paths),
+        env,
+        workdir: project_root,
+        user: (geteuid().as_raw(), getegid().as_raw()),
+    })
+}
+
+pub async fn run(cas_root: &Path, command: DockerCommand) -> Result<()> {
+    match command {
+        DockerCommand::Run {
+            options,
+            docker,
+            args,
+        } => {
+            let injection = injection(cas_root, &options).await?;
+            let status = std::process::Command::new(&docker)
+                .arg("run")
+                .args(injection.docker_args())
+                .args(&args)
+                .status()
+                .with_context(|| format!("Failed to execute: {}", docker))?;
+            std::process::exit(status.code().unwrap_or(1));
+        }
+        DockerCommand::Args { options } => {
+            let injection = injection(cas_root, &options).await?;
+            let args: Vec<String> = injection
+                .docker_args()
+                .iter()
+                .map(|a| shell_quote(a))
+                .collect();
+            println!("{}", args.join(" "));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(subcommand)]
+        command: DockerCommand,
+    }
+
+    #[test]
+    fn test_docker_args() {
+        let injection = Injection {
+            shim: PathBuf::from("/opt/vrift/libvrift_inception_layer.so"),
+            mounts: outermost(vec![
+                PathBuf::from("/work/app"),
+                PathBuf::from("/work/app/.vrift/manifest.lmdb"),
+                PathBuf::from("/home/u/.vrift/the_source"),
+                PathBuf::from("/work/application"),
+            ]),
+            env: vec![("VRIFT_PROJECT_ROOT".to_string(), "/work/app".to_string())],
+            workdir: PathBuf::from("/work/app"),
+            user: (1000, 100),
+        };
+        assert_eq!(
+            injection.docker_args(),
+            [
+                "-v",
+                "/opt/vrift/libvrift_inception_layer.so:/opt/vrift/lib/libvrift_inception_layer.so:ro",
+                "-v",
+                "/home/u/.vrift/the_source:/home/u/.vrift/the_source",
+                "-v",
+                "/work/app:/work/app",
+                "-v",
+                "/work/application:/work/application",
+                "-e",
+                "VRIFT_PROJECT_ROOT=/work/app",
+                "-e",
+                "LD_PRELOAD=/opt/vrift/lib/libvrift_inception_layer.so",
+                "--user",
+                "1000:100",
+                "-w",
+                "/work/app",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("-v"), "-v");
+        assert_eq!(shell_quote("/a b:/a b"), "'/a b:/a b'");
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+        assert_eq!(shell_quote(""), "''");
+    }
+
+    #[test]
+    fn test_docker_options_pass_through() {
+        let cli = Cli::try_parse_from([
+            "docker", "run", "--shim", "/l.so", "--rm", "-it", "ubuntu", "make", "-j8",
+        ])
+        .unwrap();
+        let DockerCommand::Run { options, args, .. } = cli.command else {
+            panic!("expected run");
+        };
+        assert_eq!(options.shim, Some(PathBuf::from("/l.so")));
+        assert_eq!(args, ["--rm", "-it", "ubuntu", "make", "-j8"]);
+    }
+}
@@ -289,6 +289,18 @@ fn check_cas(d: &mut DiagResult) {
             format_bytes(dir_size)
         ));
 
+        // Blobs of different hash algorithms never deduplicate
+        if let Ok(cas) = vrift_cas::CasStore::new(&resolved) {
+            let algorithms = cas.algorithms();
+            if algorithms.len() > 1 {
+                let names: Vec<&str> = algorithms.iter().map(|a| a.name()).collect();
+                d.warn(&format!(
+                    "TheSource™ mixes hash algorithms ({}); their blobs never deduplicate",
+                    names.join(", ")
+                ));
+            }
+        }
+
         // Check permissions
         #[cfg(unix)]
         {
@@ -0,0 +1,331 @@
+//! # vrift du
+//!
+//! Dedup-aware disk usage. For each directory down to `--depth` levels it
+//! reports:
+//! - LOGICAL: the sum of file sizes, what plain `du` would show on a real tree
+//! - PHYSICAL: the CAS bytes of the distinct blobs under the directory
+//! - SHARING: LOGICAL / PHYSICAL
+//! - EXCLUSIVE: the CAS bytes only this directory references, neither other
+//!   paths of the project nor other registered workspaces; what deleting it
+//!   and running `vrift gc` would free
+//!
+//! Inline entries live in the manifest itself and take no CAS space.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use vrift_cas::{Blake3Hash, CasStore};
+use vrift_config::path::normalize_for_ipc;
+use vrift_manifest::digest::covers;
+use vrift_manifest::lmdb::LmdbManifest;
+use vrift_manifest::{Manifest, VnodeEntry};
+
+use crate::registry::ManifestRegistry;
+
+/// Usage of one directory
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct DuRow {
+    pub path: String,
+    pub files: u64,
+    pub logical: u64,
+    pub physical: u64,
+    pub exclusive: u64,
+}
+
+impl DuRow {
+    pub fn sharing(&self) -> f64 {
+        if self.physical == 0 {
+            return 1.0;
+        }
+        self.logical as f64 / self.physical as f64
+    }
+}
+
+#[derive(Default)]
+struct Bucket {
+    files: u64,
+    logical: u64,
+    /// References to each blob from inside the directory
+    blobs: HashMap<Blake3Hash, u64>,
+}
+
+/// `root` and the directories below it, down to `depth` levels, that contain
+/// `path`
+fn buckets(root: &str, path: &str, depth: usize) -> Vec<String> {
+    let rel = if root == "/" {
+        path
+    } else {
+        &path[root.len()..]
+    };
+    let components: Vec<&str> = rel.split('/').filter(|c| !c.is_empty()).collect();
+    let dirs = components.len().saturating_sub(1).min(depth);
+
+    let mut result = vec![root.to_string()];
+    let mut current = root.trim_end_matches('/').to_string();
+    for component in &components[..dirs] {
+        current.push('/');
+        current.push_str(component);
+        result.push(current.clone());
+    }
+    result
+}
+
+/// Usage of `root` and its subdirectories down to `depth` levels, in `du`
+/// order (children before their parent).
+///
+/// `entries` is the whole project so that blobs referenced outside `root`
+/// are not counted as exclusive; `elsewhere` holds the blobs other workspaces
+/// reference. `blob_size` gives the CAS size of a blob, given its logical size.
+pub fn summarize<'a, I>(
+    entries: I,
+    root: &str,
+    depth: usize,
+    elsewhere: &HashSet<Blake3Hash>,
+    blob_size: impl Fn(&Blake3Hash, u64) -> u64,
+) -> Vec<DuRow>
+where
+    I: IntoIterator<Item = (&'a str, &'a VnodeEntry)>,
+{
+    let mut total_refs: HashMap<Blake3Hash, u64> = HashMap::new();
+    let mut sizes: HashMap<Blake3Hash, u64> = HashMap::new();
+    let mut dirs: HashMap<String, Bucket> = HashMap::new();
+
+    for (path, vnode) in entries {
+        if vnode.is_dir() {
+            continue;
+        }
+        let blob = (!vnode.is_inline()).then_some(vnode.content_hash);
+        if let Some(hash) = blob {
+            *total_refs.entry(hash).or_insert(0) += 1;
+            sizes.entry(hash).or_insert(vnode.size);
+        }
+        if !covers(root, path) {
+            continue;
+        }
+        for dir in buckets(root, path, depth) {
+            let bucket = dirs.entry(dir).or_default();
+            bucket.files += 1;
+            bucket.logical += vnode.size;
+            if let Some(hash) = blob {
+                *bucket.blobs.entry(hash).or_insert(0) += 1;
+            }
+        }
+    }
+
+    let cas_sizes: HashMap<Blake3Hash, u64> = sizes
+        .iter()
+        .map(|(hash, size)| (*hash, blob_size(hash, *size)))
+        .collect();
+    let mut rows: Vec<DuRow> = dirs
+        .into_iter()
+        .map(|(path, bucket)| {
+            let mut row = DuRow {
+                path,
+                files: bucket.files,
+                logical: bucket.logical,
+                ..Default::default()
+            };
+            for (hash, refs) in &bucket.blobs {
+                let size = cas_sizes.get(hash).copied().unwrap_or(0);
+                row.physical += size;
+                if total_refs.get(hash) == Some(refs) && !elsewhere.contains(hash) {
+                    row.exclusive += size;
+                }
+            }
+            row
+        })
+        .collect();
+    // A parent sorts after everything below it
+    rows.sort_by_cached_key(|row| format!("{}\u{10FFFF}", row.path.trim_end_matches('/')));
+    rows
+}
+
+pub fn cmd_du(
+    cas_root: &Path,
+    project_dir: &Path,
+    path: Option<&Path>,
+    manifest: Option<&Path>,
+    depth: usize,
+    local: bool,
+) -> Result<()> {
+    let project_root = normalize_for_ipc(project_dir).context("resolve project path")?;
+    let root = match path {
+        Some(p) => crate::path_to_manifest_key(&project_root, p)?,
+        None => "/".to_string(),
+    };
+
+    let (entries, manifest_path): (Vec<(String, VnodeEntry)>, PathBuf) = match manifest {
+        Some(file) => {
+            let manifest = Manifest::load(file)
+                .with_context(|| format!("Failed to load manifest: {}", file.display()))?;
+            let entries = manifest
+                .iter()
+                .map(|(p, v)| (p.to_string(), v.clone()))
+                .collect();
+            (entries, file.to_path_buf())
+        }
+        None => {
+            let project_id = vrift_config::path::compute_project_id(&project_root);
+            let manifest_path = vrift_config::path::get_manifest_db_path(&project_id)
+                .ok_or_else(|| anyhow::anyhow!("Could not determine manifest path"))?;
+            if !manifest_path.exists() {
+                anyhow::bail!(
+                    "Manifest not found at {}. Run 'vrift init' first.",
+                    manifest_path.display()
+                );
+            }
+            let entries = LmdbManifest::open_readonly(&manifest_path)?
+                .iter()?
+                .into_iter()
+                .map(|(p, e)| (p, e.vnode))
+                .collect();
+            (entries, manifest_path)
+        }
+    };
+    if !entries.iter().any(|(p, _)| covers(&root, p)) {
+        anyhow::bail!("Not in manifest: {}", root);
+    }
+
+    let elsewhere = if local {
+        HashSet::new()
+    } else {
+        other_workspaces(cas_root, &project_root, &manifest_path).unwrap_or_else(|e| {
+            eprintln!(
+                "Warning: could not read other workspaces ({}); EXCLUSIVE only accounts for this one",
+                e
+            );
+            HashSet::new()
+        })
+    };
+
+    let cas = if cas_root.exists() {
+        Some(CasStore::new(cas_root)?)
+    } else {
+        None
+    };
+    let blob_size = |hash: &Blake3Hash, logical: u64| {
+        cas.as_ref()
+            .and_then(|cas| cas.blob_path_for_hash(hash))
+            .and_then(|p| std::fs::metadata(p).ok())
+            .map_or(logical, |m| m.len())
+    };
+
+    let rows = summarize(
+        entries.iter().map(|(p, v)| (p.as_str(), v)),
+        &root,
+        depth,
+        &elsewhere,
+        blob_size,
+    );
+
+    println!(
+        "{:>11}  {:>11}  {:>7}  {:>11}  PATH",
+        "LOGICAL", "PHYSICAL", "SHARING", "EXCLUSIVE"
+    );
+    for row in &rows {
+        println!(
+            "{:>11}  {:>11}  {:>6.2}x  {:>11}  {}",
+            crate::format_bytes(row.logical),
+            crate::format_bytes(row.physical),
+            row.sharing(),
+            crate::format_bytes(row.exclusive),
+            row.path
+        );
+    }
+    Ok(())
+}
+
+/// Blobs referenced by registered workspaces other than this one
+fn other_workspaces(
+    cas_root: &Path,
+    project_root: &Path,
+    manifest_path: &Path,
+) -> Result<HashSet<Blake3Hash>> {
+    let mut registry = ManifestRegistry::load_or_create()?;
+    registry.verify_all();
+    let own_manifest = vrift_config::path::normalize_or_original(manifest_path);
+    registry
+        .manifests
+        .retain(|_, e| e.project_root != project_root && e.source_path != own_manifest);
+    registry.get_all_blob_hashes(cas_root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(content: &[u8]) -> VnodeEntry {
+        VnodeEntry::new_file(
+            CasStore::compute_hash(content),
+            content.len() as u64,
+            1,
+            0o644,
+        )
+    }
+
+    #[test]
+    fn test_buckets() {
+        assert_eq!(buckets("/", "/a/b/c.rs", 1), vec!["/", "/a"]);
+        assert_eq!(buckets("/", "/a/b/c.rs", 5), vec!["/", "/a", "/a/b"]);
+        assert_eq!(buckets("/a", "/a/b/c.rs", 1), vec!["/a", "/a/b"]);
+        assert_eq!(buckets("/", "/top.rs", 1), vec!["/"]);
+        assert_eq!(buckets("/a", "/a/b/c.rs", 0), vec!["/a"]);
+    }
+
+    #[test]
+    fn test_summarize_dedup_and_exclusive() {
+        let big = vec![7u8; 1000];
+        let other = vec![8u8; 400];
+        let entries = [
+            ("/a/x.bin".to_string(), file(&big)),
+            ("/a/y.bin".to_string(), file(&big)),
+            ("/b/z.bin".to_string(), file(&big)),
+            ("/b/w.bin".to_string(), file(&other)),
+            (
+                "/b/tiny".to_string(),
+                VnodeEntry::new_inline(b"hi", 1, 0o644).unwrap(),
+            ),
+        ];
+        let rows = summarize(
+            entries.iter().map(|(p, v)| (p.as_str(), v)),
+            "/",
+            1,
+            &HashSet::new(),
+            |_, size| size,
+        );
+        let paths: Vec<&str> = rows.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(paths, vec!["/a", "/b", "/"]);
+
+        // `big` is shared with /b, so /a frees nothing on its own
+        assert_eq!(
+            rows[0],
+            DuRow {
+                path: "/a".to_string(),
+                files: 2,
+                logical: 2000,
+                physical: 1000,
+                exclusive: 0,
+            }
+        );
+        assert_eq!(rows[0].sharing(), 2.0);
+        assert_eq!((rows[1].logical, rows[1].physical), (1402, 1400));
+        assert_eq!(rows[1].exclusive, 400);
+        assert_eq!(
+            (rows[2].files, rows[2].physical, rows[2].exclusive),
+            (5, 1400, 1400)
+        );
+
+        // A blob another workspace references is never exclusive
+        let elsewhere = HashSet::from([CasStore::compute_hash(&other)]);
+        let rows = summarize(
+            entries.iter().map(|(p, v)| (p.as_str(), v)),
+            "/b",
+            0,
+            &elsewhere,
+            |_, size| size,
+        );
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].exclusive, 0);
+    }
+}
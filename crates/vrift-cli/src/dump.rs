@@ -0,0 +1,354 @@
+//! # vrift manifest dump
+//!
+//! Export a project manifest to a SQLite database for ad hoc analysis of the
+//! build tree, e.g. the largest directories or the most shared blobs:
+//!
+//! ```sql
+//! SELECT path, total_size FROM directories ORDER BY total_size DESC LIMIT 10;
+//! SELECT hash, size, refs FROM blobs ORDER BY size * (refs - 1) DESC;
+//! ```
+//!
+//! Tables:
+//! - `entries`: one row per manifest entry
+//! - `directories`: every directory of the tree with direct and recursive
+//!   counts, including directories only implied by the paths below them
+//! - `blobs`: one row per CAS blob referenced by the manifest
+//! - `access_counts`: how many of the given `vrift record` JSON reports read
+//!   each path
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection};
+use vrift_cas::CasStore;
+use vrift_manifest::lmdb::{AssetTier, ManifestEntry};
+use vrift_manifest::VnodeEntry;
+
+const SCHEMA: &str = "
+CREATE TABLE entries (
+    path TEXT PRIMARY KEY,
+    parent TEXT,
+    name TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    mode INTEGER NOT NULL,
+    mtime_ns INTEGER NOT NULL,
+    ingested_at_ns INTEGER,
+    hash TEXT,
+    storage TEXT NOT NULL,
+    tier TEXT NOT NULL,
+    stale INTEGER NOT NULL
+);
+CREATE TABLE directories (
+    path TEXT PRIMARY KEY,
+    parent TEXT,
+    files INTEGER NOT NULL,
+    subdirs INTEGER NOT NULL,
+    total_files INTEGER NOT NULL,
+    total_size INTEGER NOT NULL
+);
+CREATE TABLE blobs (
+    hash TEXT PRIMARY KEY,
+    size INTEGER NOT NULL,
+    refs INTEGER NOT NULL,
+    in_cas INTEGER
+);
+CREATE TABLE access_counts (
+    path TEXT PRIMARY KEY,
+    reads INTEGER NOT NULL
+);
+CREATE INDEX entries_parent ON entries(parent);
+CREATE INDEX entries_hash ON entries(hash);
+";
+
+/// Rows written to each table
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct DumpCounts {
+    pub entries: usize,
+    pub directories: usize,
+    pub blobs: usize,
+    pub accessed: usize,
+}
+
+#[derive(Default)]
+struct DirStats {
+    files: u64,
+    subdirs: u64,
+    total_files: u64,
+    total_size: u64,
+}
+
+struct BlobStats {
+    size: u64,
+    refs: u64,
+}
+
+/// Parent directory of a manifest key; `None` for the root
+fn parent(path: &str) -> Option<&str> {
+    let trimmed = path.trim_end_matches('/');
+    if trimmed.is_empty() {
+        return None;
+    }
+    match trimmed.rfind('/') {
+        Some(0) | None => Some("/"),
+        Some(i) => Some(&trimmed[..i]),
+    }
+}
+
+fn name(path: &str) -> &str {
+    let trimmed = path.trim_end_matches('/');
+    trimmed.rsplit('/').next().unwrap_or(trimmed)
+}
+
+fn kind(vnode: &VnodeEntry) -> &'static str {
+    if vnode.is_dir() {
+        "dir"
+    } else if vnode.is_symlink() {
+        "symlink"
+    } else {
+        "file"
+    }
+}
+
+fn storage(vnode: &VnodeEntry) -> &'static str {
+    if vnode.is_inline() {
+        "inline"
+    } else if vnode.is_external() {
+        "external"
+    } else if vnode.is_encrypted() {
+        "encrypted"
+    } else if vnode.is_compressed() {
+        "compressed"
+    } else {
+        "blob"
+    }
+}
+
+fn tier(tier: AssetTier) -> &'static str {
+    match tier {
+        AssetTier::Tier1Immutable => "tier1",
+        AssetTier::Tier2Mutable => "tier2",
+    }
+}
+
+/// Count how many of the `vrift record` reports read each manifest path
+pub fn access_counts(reports: &[PathBuf]) -> Result<BTreeMap<String, u64>> {
+    let mut counts = BTreeMap::new();
+    for report in reports {
+        for key in crate::record::read_input_keys(report)? {
+            *counts.entry(key).or_insert(0) += 1;
+        }
+    }
+    Ok(counts)
+}
+
+/// Write `entries` to a new SQLite database at `output`, replacing any file
+/// already there. `cas` fills `blobs.in_cas`; without it the column is NULL.
+pub fn dump_sqlite(
+    entries: &[(String, ManifestEntry)],
+    cas: Option<&CasStore>,
+    access: &BTreeMap<String, u64>,
+    output: &Path,
+) -> Result<DumpCounts> {
+    if output.exists() {
+        std::fs::remove_file(output)
+            .with_context(|| format!("Failed to replace {}", output.display()))?;
+    }
+    let mut db = Connection::open(output)
+        .with_context(|| format!("Failed to create {}", output.display()))?;
+    let tx = db.transaction()?;
+    tx.execute_batch(SCHEMA)?;
+
+    let mut dirs: BTreeMap<String, DirStats> = BTreeMap::new();
+    let mut blobs: BTreeMap<[u8; 32], BlobStats> = BTreeMap::new();
+    {
+        let mut insert = tx.prepare(
+            "INSERT INTO entries VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+        )?;
+        for (path, entry) in entries {
+            let vnode = &entry.vnode;
+            let has_blob = !vnode.is_dir() && !vnode.is_inline();
+            let hash = has_blob.then(|| CasStore::hash_to_hex(&vnode.content_hash));
+            insert.execute(params![
+                path,
+                parent(path),
+                name(path),
+                kind(vnode),
+                vnode.size as i64,
+                vnode.mode,
+                vnode.mtime,
+                (entry.ingested_at != 0).then_some(entry.ingested_at),
+                hash,
+                storage(vnode),
+                tier(entry.tier),
+                entry.stale,
+            ])?;
+
+            if vnode.is_dir() {
+                let key = path.trim_end_matches('/');
+                dirs.entry(if key.is_empty() { "/" } else { key }.to_string())
+                    .or_default();
+            } else {
+                if has_blob {
+                    blobs
+                        .entry(vnode.content_hash)
+                        .or_insert(BlobStats {
+                            size: vnode.size,
+                            refs: 0,
+                        })
+                        .refs += 1;
+                }
+                // Every ancestor gets the file in its totals; only the
+                // nearest counts it as a direct child
+                let mut ancestor = parent(path);
+                let mut direct = true;
+                while let Some(dir) = ancestor {
+                    let stats = dirs.entry(dir.to_string()).or_default();
+                    stats.total_files += 1;
+                    stats.total_size += vnode.size;
+                    if direct {
+                        stats.files += 1;
+                        direct = false;
+                    }
+                    ancestor = parent(dir);
+                }
+            }
+        }
+    }
+
+    // Implied directories may have ancestors that were never seen
+    let known: Vec<String> = dirs.keys().cloned().collect();
+    for dir in &known {
+        let mut ancestor = parent(dir);
+        while let Some(up) = ancestor {
+            dirs.entry(up.to_string()).or_default();
+            ancestor = parent(up);
+        }
+    }
+    let all: Vec<String> = dirs.keys().cloned().collect();
+    for dir in &all {
+        if let Some(up) = parent(dir) {
+            if let Some(stats) = dirs.get_mut(up) {
+                stats.subdirs += 1;
+            }
+        }
+    }
+
+    {
+        let mut insert = tx.prepare("INSERT INTO directories VALUES (?1, ?2, ?3, ?4, ?5, ?6)")?;
+        for (path, stats) in &dirs {
+            insert.execute(params![
+                path,
+                parent(path),
+                stats.files as i64,
+                stats.subdirs as i64,
+                stats.total_files as i64,
+                stats.total_size as i64,
+            ])?;
+        }
+        let mut insert = tx.prepare("INSERT INTO blobs VALUES (?1, ?2, ?3, ?4)")?;
+        for (hash, stats) in &blobs {
+            insert.execute(params![
+                CasStore::hash_to_hex(hash),
+                stats.size as i64,
+                stats.refs as i64,
+                cas.map(|cas| cas.exists(hash)),
+            ])?;
+        }
+        let mut insert = tx.prepare("INSERT INTO access_counts VALUES (?1, ?2)")?;
+        for (path, reads) in access {
+            insert.execute(params![path, *reads as i64])?;
+        }
+    }
+    tx.commit()?;
+
+    Ok(DumpCounts {
+        entries: entries.len(),
+        directories: dirs.len(),
+        blobs: blobs.len(),
+        accessed: access.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(content: &[u8]) -> ManifestEntry {
+        ManifestEntry {
+            vnode: VnodeEntry::new_file(
+                CasStore::compute_hash(content),
+                content.len() as u64,
+                1,
+                0o644,
+            ),
+            tier: AssetTier::Tier2Mutable,
+            stale: false,
+            ingested_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_parent_and_name() {
+        assert_eq!(parent("/src/lib.rs"), Some("/src"));
+        assert_eq!(parent("/Cargo.toml"), Some("/"));
+        assert_eq!(parent("/src/"), Some("/"));
+        assert_eq!(parent("/"), None);
+        assert_eq!(name("/src/lib.rs"), "lib.rs");
+        assert_eq!(name("/src/"), "src");
+    }
+
+    #[test]
+    fn test_dump_tables() {
+        let temp = tempfile::tempdir().unwrap();
+        let output = temp.path().join("out.db");
+        let entries = vec![
+            ("/Cargo.toml".to_string(), file(b"[package]")),
+            ("/src/lib.rs".to_string(), file(b"shared")),
+            ("/src/bin/main.rs".to_string(), file(b"shared")),
+        ];
+        let access = BTreeMap::from([("/src/lib.rs".to_string(), 2)]);
+
+        // An existing file is replaced
+        std::fs::write(&output, b"stale").unwrap();
+        let counts = dump_sqlite(&entries, None, &access, &output).unwrap();
+        assert_eq!(
+            counts,
+            DumpCounts {
+                entries: 3,
+                directories: 3,
+                blobs: 2,
+                accessed: 1,
+            }
+        );
+
+        let db = Connection::open(&output).unwrap();
+        let (files, subdirs, total_files, total_size): (i64, i64, i64, i64) = db
+            .query_row(
+                "SELECT files, subdirs, total_files, total_size FROM directories WHERE path = '/src'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .unwrap();
+        assert_eq!((files, subdirs, total_files, total_size), (1, 1, 2, 12));
+
+        let (refs, in_cas): (i64, Option<bool>) = db
+            .query_row(
+                "SELECT refs, in_cas FROM blobs WHERE hash = ?1",
+                [CasStore::hash_to_hex(&CasStore::compute_hash(b"shared"))],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((refs, in_cas), (2, None));
+
+        let reads: i64 = db
+            .query_row(
+                "SELECT a.reads FROM access_counts a JOIN entries e ON e.path = a.path",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(reads, 2);
+    }
+}
@@ -0,0 +1,80 @@
+//! # Exit Codes
+//!
+//! Maps the error that ended a command to a process exit code, so scripts can
+//! tell "not found" from "permission denied" from "daemon not running"
+//! without parsing messages. Codes are listed on [`ErrorKind::exit_code`].
+
+use std::error::Error as StdError;
+
+use vrift_error::{Classify, ErrorKind};
+
+/// Kind of the first error in the chain that has one
+///
+/// The chain is walked outermost-first. `anyhow` context carries no kind,
+/// and the crate error types delegate wrapper variants to the error they
+/// wrap, so the first typed error already reflects the original cause.
+/// Errors raised with a bare `anyhow!`/`bail!` stay `Internal` (exit 1).
+pub fn error_kind(err: &anyhow::Error) -> ErrorKind {
+    err.chain()
+        .find_map(classify)
+        .unwrap_or(ErrorKind::Internal)
+}
+
+macro_rules! downcast_classify {
+    ($err:expr, $($ty:ty),+ $(,)?) => {
+        $(
+            if let Some(e) = $err.downcast_ref::<$ty>() {
+                return Some(e.classify());
+            }
+        )+
+    };
+}
+
+fn classify(err: &(dyn StdError + 'static)) -> Option<ErrorKind> {
+    downcast_classify!(
+        err,
+        vrift_error::Error,
+        vrift_cas::CasError,
+        vrift_cas::reflink::ReflinkError,
+        vrift_manifest::ManifestError,
+        vrift_manifest::LmdbError,
+        vrift_lock::LockError,
+        vrift_config::ConfigError,
+        vrift_runtime::RuntimeError,
+        vrift_pack::PackError,
+        vrift_ipc::VeloError,
+        std::io::Error,
+    );
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_kind_survives_anyhow_context() {
+        let err = Err::<(), _>(vrift_cas::CasError::NotFound {
+            hash: "abc".to_string(),
+        })
+        .context("Failed to restore src/main.rs")
+        .unwrap_err();
+        assert_eq!(error_kind(&err), ErrorKind::NotFound);
+        assert_eq!(error_kind(&err).exit_code(), 2);
+    }
+
+    #[test]
+    fn test_wrapped_io_cause() {
+        let io = std::io::Error::from(std::io::ErrorKind::PermissionDenied);
+        let err = anyhow::Error::new(vrift_manifest::LmdbError::Io(io)).context("open manifest");
+        assert_eq!(error_kind(&err), ErrorKind::PermissionDenied);
+    }
+
+    #[test]
+    fn test_untyped_error_is_internal() {
+        let err = anyhow::anyhow!("something went wrong");
+        assert_eq!(error_kind(&err), ErrorKind::Internal);
+        assert_eq!(error_kind(&err).exit_code(), 1);
+    }
+}
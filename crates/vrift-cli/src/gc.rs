@@ -1,18 +1,25 @@
 //! # Garbage Collection (RFC-0041)
 //!
-//! Multi-manifest garbage collection with registry integration and Bloom-assisted daemon sweep.
+//! Multi-manifest garbage collection with registry integration and a Bloom-assisted
+//! (or, with `[gc] refcount`, refcounted) daemon sweep.
+//!
+//! `vrift scrub` is the matching integrity check: a daemon job that re-hashes
+//! every blob and reports (or, with `--delete`, removes) the corrupt ones.
 
 use anyhow::{Context, Result};
 use clap::Args;
 use std::collections::HashSet;
 use std::io::{self, Write};
 use std::path::{Path, PathBuf};
-use std::time::Instant;
-use vrift_cas::CasStore;
+use std::time::{Duration, Instant};
+use vrift_cas::{CasStore, RefCounts};
 use vrift_manifest::Manifest;
 
 use crate::registry::ManifestRegistry;
 
+/// How often `--delete` polls the daemon for sweep progress
+const SWEEP_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
 #[derive(Args, Debug)]
 pub struct GcArgs {
     /// Path to a single Manifest file (legacy mode, bypasses registry)
@@ -27,15 +34,40 @@ pub struct GcArgs {
     #[arg(long)]
     prune_stale: bool,
 
+    /// Have the daemon list the blobs a sweep would mark and delete now,
+    /// grace period included, without sweeping
+    #[arg(long, conflicts_with_all = ["delete", "prune_stale"])]
+    dry_run: bool,
+
+    /// Print the dry-run plan as JSON (the summary goes to stderr)
+    #[arg(long, requires = "dry_run")]
+    json: bool,
+
     /// Skip confirmation prompt (for scripts and CI)
     #[arg(long, short = 'y', default_value = "false")]
     yes: bool,
 }
 
+#[derive(Args, Debug)]
+pub struct ScrubArgs {
+    /// Remove corrupt blobs, so the next ingest or upstream fetch stores
+    /// them again (default only reports them)
+    #[arg(long)]
+    delete: bool,
+}
+
+/// `println!`, or `eprintln!` while stdout carries a JSON plan
+macro_rules! say {
+    ($args:expr) => { if $args.json { eprintln!() } else { println!() } };
+    ($args:expr, $($fmt:tt)+) => {
+        if $args.json { eprintln!($($fmt)+) } else { println!($($fmt)+) }
+    };
+}
+
 pub async fn run(cas_root: &Path, args: GcArgs) -> Result<()> {
-    println!();
-    println!("🗑️  VRift Garbage Collection");
-    println!("   CAS:     {}", cas_root.display());
+    say!(args);
+    say!(args, "🗑️  VRift Garbage Collection");
+    say!(args, "   CAS:     {}", cas_root.display());
 
     // Acquire exclusive lock
     let _lock = ManifestRegistry::acquire_lock().context("Failed to acquire registry lock")?;
@@ -48,17 +80,22 @@ pub async fn run(cas_root: &Path, args: GcArgs) -> Result<()> {
 
     // Collect all referenced blob hashes
     let keep_set: HashSet<_> = if let Some(ref manifest_path) = args.manifest {
-        println!();
-        println!("  [Legacy Mode] Using single manifest: {:?}", manifest_path);
+        say!(args);
+        say!(
+            args,
+            "  [Legacy Mode] Using single manifest: {:?}",
+            manifest_path
+        );
         let manifest = Manifest::load(manifest_path).context("Failed to parse manifest")?;
         manifest
             .iter()
             .map(|(_, entry)| entry.content_hash)
             .collect()
     } else {
-        println!();
-        println!("  Registry Status:");
-        println!(
+        say!(args);
+        say!(args, "  Registry Status:");
+        say!(
+            args,
             "    📁 Registered manifests: {} ({} active, {} stale)",
             registry.manifests.len(),
             active_count,
@@ -72,12 +109,29 @@ pub async fn run(cas_root: &Path, args: GcArgs) -> Result<()> {
         }
 
         registry
-            .get_all_blob_hashes()
+            .get_all_blob_hashes(cas_root)
             .context("Failed to collect blob hashes from manifests")?
     };
 
-    println!();
-    println!(
+    // With [gc] refcount the daemon sweeps by exact reference counts, which
+    // have to reflect the registry first. A legacy single-manifest run keeps
+    // the bloom filter: it is not what the counts describe.
+    let refcounted = args.manifest.is_none() && vrift_config::config().gc.refcount;
+    if refcounted {
+        let refs = RefCounts::open(cas_root).context("Failed to open blob refcounts")?;
+        let synced = registry
+            .sync_refcounts(&refs, cas_root)
+            .context("Failed to update blob refcounts")?;
+        say!(
+            args,
+            "    🔢 Reference counts synced for {} manifests",
+            synced
+        );
+    }
+
+    say!(args);
+    say!(
+        args,
         "  ✅ Referenced blobs: {}",
         format_number(keep_set.len() as u64)
     );
@@ -89,10 +143,33 @@ pub async fn run(cas_root: &Path, args: GcArgs) -> Result<()> {
         bloom.add(&CasStore::hash_to_hex(hash));
     }
 
-    if args.delete {
+    if args.dry_run {
+        use vrift_ipc::VeloRequest;
+        let project_root = std::env::current_dir().context("Failed to get current directory")?;
+        let conn = crate::daemon::connect_to_daemon(&project_root)
+            .await
+            .context("Daemon not running or unreachable")?;
+        let mut stream = conn.stream;
+        let request = if refcounted {
+            VeloRequest::CasSweepRefcounted { dry_run: true }
+        } else {
+            VeloRequest::CasSweep {
+                bloom_filter: bloom.bits.clone(),
+                dry_run: true,
+            }
+        };
+        let plan = crate::daemon::plan_request(&mut stream, request).await?;
+        say!(args);
+        say!(args, "  📋 What `--delete` would do now:");
+        crate::plan::print(&plan, args.json)?;
+    } else if args.delete {
         if !args.yes {
             println!();
-            print!("  ⚠️  Proceed with Bloom-assisted GC sweep on daemon? [y/N] ");
+            if refcounted {
+                print!("  ⚠️  Proceed with refcounted GC sweep on daemon? [y/N] ");
+            } else {
+                print!("  ⚠️  Proceed with Bloom-assisted GC sweep on daemon? [y/N] ");
+            }
             io::stdout().flush()?;
             let mut input = String::new();
             io::stdin().read_line(&mut input)?;
@@ -106,40 +183,74 @@ pub async fn run(cas_root: &Path, args: GcArgs) -> Result<()> {
         println!("  🧼 Triggering CAS sweep via daemon...");
 
         // Connect to daemon and send sweep request
-        use vrift_ipc::{VeloRequest, VeloResponse};
+        use vrift_ipc::{JobState, VeloRequest, VeloResponse};
         let project_root = std::env::current_dir().context("Failed to get current directory")?;
         let conn = crate::daemon::connect_to_daemon(&project_root)
             .await
             .context("Daemon not running or unreachable")?;
         let mut stream = conn.stream;
-        crate::daemon::send_request(
-            &mut stream,
+        let request = if refcounted {
+            VeloRequest::CasSweepRefcounted { dry_run: false }
+        } else {
             VeloRequest::CasSweep {
                 bloom_filter: bloom.bits.clone(),
-            },
-        )
-        .await?;
-
-        match crate::daemon::read_response(&mut stream).await? {
-            VeloResponse::CasSweepAck {
-                deleted_count,
-                reclaimed_bytes,
-            } => {
-                let gc_elapsed = gc_start.elapsed().as_secs_f64();
+                dry_run: false,
+            }
+        };
+        crate::daemon::send_request(&mut stream, request).await?;
+
+        let mut job = match crate::daemon::read_response(&mut stream).await? {
+            VeloResponse::JobAck { job } => job,
+            VeloResponse::Error(e) => return Err(anyhow::anyhow!("Sweep failed: {}", e)),
+            _ => return Err(anyhow::anyhow!("Unexpected response from daemon")),
+        };
+
+        // The sweep runs in the background on the daemon; poll it until it
+        // stops, cancelling on Ctrl-C
+        let mut cancel_sent = false;
+        let ctrl_c = tokio::signal::ctrl_c();
+        tokio::pin!(ctrl_c);
+        while !job.state.is_finished() {
+            print_sweep_progress(&job);
+            let request = tokio::select! {
+                _ = tokio::time::sleep(SWEEP_POLL_INTERVAL) => VeloRequest::JobStatus { job_id: job.job_id },
+                _ = &mut ctrl_c, if !cancel_sent => {
+                    cancel_sent = true;
+                    println!();
+                    println!("  ⏹️  Cancelling sweep (blobs already deleted stay deleted)...");
+                    VeloRequest::JobCancel { job_id: job.job_id }
+                }
+            };
+            job = crate::daemon::job_request(&mut stream, request).await?;
+        }
+        println!();
+
+        let gc_elapsed = gc_start.elapsed().as_secs_f64();
+        match job.state {
+            JobState::Failed => {
+                return Err(anyhow::anyhow!(
+                    "Sweep failed: {}",
+                    job.error.unwrap_or_else(|| "unknown error".to_string())
+                ));
+            }
+            JobState::Cancelled => {
+                println!();
+                println!("  ⏹️  Sweep cancelled after {:.2}s", gc_elapsed);
+            }
+            _ => {
                 println!();
                 println!("╔════════════════════════════════════════╗");
                 println!("║  ✅ GC Complete in {:.2}s              ║", gc_elapsed);
                 println!("╚════════════════════════════════════════╝");
-                println!();
-                println!(
-                    "   🗑️  {} orphaned blobs deleted",
-                    format_number(deleted_count as u64)
-                );
-                println!("   💾 {} reclaimed", format_bytes(reclaimed_bytes));
             }
-            VeloResponse::Error(e) => return Err(anyhow::anyhow!("Sweep failed: {}", e)),
-            _ => return Err(anyhow::anyhow!("Unexpected response from daemon")),
         }
+        println!();
+        println!(
+            "   🗑️  {} orphaned blobs deleted",
+            format_number(job.affected)
+        );
+        println!("   💾 {} reclaimed", format_bytes(job.bytes));
+        print_pending(&CasStore::new(cas_root)?)?;
     } else {
         println!("\n  📋 Dry Run: Scanning CAS for orphaned blobs...");
         let cas = CasStore::new(cas_root)?;
@@ -168,6 +279,7 @@ pub async fn run(cas_root: &Path, args: GcArgs) -> Result<()> {
         } else {
             println!("   ✅ No orphans found.");
         }
+        print_pending(&cas)?;
 
         println!();
         println!("     👉 Run with --delete to trigger daemon sweep.");
@@ -175,10 +287,140 @@ pub async fn run(cas_root: &Path, args: GcArgs) -> Result<()> {
 
     // Save registry
     registry.save()?;
+    say!(args);
+    Ok(())
+}
+
+/// Have the daemon re-hash every blob, following the job until it ends;
+/// Ctrl-C cancels it
+pub async fn scrub(cas_root: &Path, args: ScrubArgs) -> Result<()> {
+    use vrift_ipc::{JobState, VeloRequest};
+
+    println!();
+    println!("🩺 VRift CAS Scrub");
+    println!("   CAS:     {}", cas_root.display());
     println!();
+
+    let start = Instant::now();
+    let mut stream = crate::daemon::connect_simple().await?;
+    let mut job = crate::daemon::job_request(
+        &mut stream,
+        VeloRequest::CasScrub {
+            delete_corrupt: args.delete,
+        },
+    )
+    .await?;
+
+    let mut cancel_sent = false;
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+    while !job.state.is_finished() {
+        print_scrub_progress(&job);
+        let request = tokio::select! {
+            _ = tokio::time::sleep(SWEEP_POLL_INTERVAL) => VeloRequest::JobStatus { job_id: job.job_id },
+            _ = &mut ctrl_c, if !cancel_sent => {
+                cancel_sent = true;
+                println!();
+                println!("  ⏹️  Cancelling scrub...");
+                VeloRequest::JobCancel { job_id: job.job_id }
+            }
+        };
+        job = crate::daemon::job_request(&mut stream, request).await?;
+    }
+    println!();
+
+    let elapsed = start.elapsed().as_secs_f64();
+    match job.state {
+        JobState::Failed => anyhow::bail!(
+            "Scrub failed: {}",
+            job.error.unwrap_or_else(|| "unknown error".to_string())
+        ),
+        JobState::Cancelled => println!("  ⏹️  Scrub cancelled after {:.2}s", elapsed),
+        _ => println!("  ✅ Scrub complete in {:.2}s", elapsed),
+    }
+    println!(
+        "   🔍 {} blobs checked ({})",
+        format_number(job.processed),
+        format_bytes(job.bytes)
+    );
+    if job.affected == 0 {
+        println!("   ✨ No corrupt blobs");
+    } else if args.delete {
+        println!(
+            "   🗑️  {} corrupt blobs removed",
+            format_number(job.affected)
+        );
+    } else {
+        println!(
+            "   ⚠️  {} corrupt blobs (see the daemon log; remove them with `vrift scrub --delete`)",
+            format_number(job.affected)
+        );
+    }
+    Ok(())
+}
+
+/// Report blobs that earlier sweeps marked and have yet to delete
+fn print_pending(cas: &CasStore) -> Result<()> {
+    let status = cas.gc_status()?;
+    if status.tombstones == 0 {
+        return Ok(());
+    }
+    let grace = vrift_config::config().gc_grace().as_secs();
+    println!(
+        "   ⏳ {} blobs ({}) marked unreferenced, waiting out the {} grace period",
+        format_number(status.tombstones),
+        format_bytes(status.bytes),
+        format_duration(grace)
+    );
     Ok(())
 }
 
+/// Overwrite the current line with a sweep progress summary
+fn print_sweep_progress(job: &vrift_ipc::JobInfo) {
+    if job.state == vrift_ipc::JobState::Queued {
+        print!(
+            "\r   ⏳ Job {} queued behind another store-wide job   ",
+            job.job_id
+        );
+        let _ = io::stdout().flush();
+        return;
+    }
+    let percent = if job.total_estimate > 0 {
+        format!(
+            " ({}%)",
+            (job.processed * 100 / job.total_estimate).min(100)
+        )
+    } else {
+        String::new()
+    };
+    print!(
+        "\r   🔍 {} blobs scanned{}, {} deleted, {} reclaimed   ",
+        format_number(job.processed),
+        percent,
+        format_number(job.affected),
+        format_bytes(job.bytes)
+    );
+    let _ = io::stdout().flush();
+}
+
+/// Overwrite the current line with a scrub progress summary
+fn print_scrub_progress(job: &vrift_ipc::JobInfo) {
+    if job.state == vrift_ipc::JobState::Queued {
+        print!(
+            "\r   ⏳ Job {} queued behind another store-wide job   ",
+            job.job_id
+        );
+    } else {
+        print!(
+            "\r   🔍 {}/{} blobs checked, {} corrupt   ",
+            format_number(job.processed),
+            format_number(job.total_estimate),
+            format_number(job.affected)
+        );
+    }
+    let _ = io::stdout().flush();
+}
+
 /// Format bytes in human-readable form
 fn format_bytes(bytes: u64) -> String {
     const KB: u64 = 1024;
@@ -196,6 +438,17 @@ fn format_bytes(bytes: u64) -> String {
     }
 }
 
+/// Format a whole number of seconds in the largest unit that divides it
+fn format_duration(secs: u64) -> String {
+    match secs {
+        0 => "0s".to_string(),
+        s if s % 86400 == 0 => format!("{}d", s / 86400),
+        s if s % 3600 == 0 => format!("{}h", s / 3600),
+        s if s % 60 == 0 => format!("{}m", s / 60),
+        s => format!("{}s", s),
+    }
+}
+
 /// Format number with comma separators
 fn format_number(n: u64) -> String {
     let s = n.to_string();
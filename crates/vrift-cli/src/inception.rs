@@ -517,7 +517,7 @@ fn get_vfs_stats(vrift_dir: &Path) -> (String, String) {
     (file_count, cas_size)
 }
 
-fn find_inception_library(project_root: &Path) -> Result<std::path::PathBuf> {
+pub(crate) fn find_inception_library(project_root: &Path) -> Result<std::path::PathBuf> {
     let inception_name = if cfg!(target_os = "macos") {
         "libvrift_inception_layer.dylib"
     } else {
@@ -0,0 +1,157 @@
+//! # vrift jobs
+//!
+//! Inspect and control long-running daemon jobs (ingest, CAS sweep, scrub,
+//! repack, prefetch). Job records persist across daemon restarts, so failed
+//! jobs can be retried later with their original parameters. `vrift jobs
+//! watch` follows ingest and GC jobs as the daemon pushes their progress.
+
+use anyhow::Result;
+use clap::Subcommand;
+use vrift_ipc::{EventKind, JobInfo, JobState, VeloEvent, VeloRequest};
+
+#[derive(Subcommand, Debug)]
+pub enum JobsCommand {
+    /// List recent jobs, newest first
+    List {
+        /// Only show queued and running jobs
+        #[arg(short, long)]
+        active: bool,
+    },
+    /// Show one job in detail
+    Show { job_id: u64 },
+    /// Cancel a queued or running job
+    Cancel { job_id: u64 },
+    /// Re-run a failed or cancelled job with its original parameters
+    Retry { job_id: u64 },
+    /// Follow ingest and GC jobs as they run, until interrupted
+    Watch,
+}
+
+pub async fn run(command: JobsCommand) -> Result<()> {
+    match command {
+        JobsCommand::List { active } => {
+            let jobs: Vec<_> = crate::daemon::list_jobs()
+                .await?
+                .into_iter()
+                .filter(|job| !active || !job.state.is_finished())
+                .collect();
+            if jobs.is_empty() {
+                println!("No jobs.");
+                return Ok(());
+            }
+            print_header();
+            for job in &jobs {
+                print_row(job);
+            }
+        }
+        JobsCommand::Show { job_id } => {
+            let job = send(VeloRequest::JobStatus { job_id }).await?;
+            print_job(&job);
+        }
+        JobsCommand::Cancel { job_id } => {
+            let job = send(VeloRequest::JobCancel { job_id }).await?;
+            if job.state.is_finished() {
+                println!("Job {} already {:?}.", job.job_id, job.state);
+            } else {
+                println!("Cancellation requested for job {}.", job.job_id);
+            }
+        }
+        JobsCommand::Retry { job_id } => {
+            let job = send(VeloRequest::JobRetry { job_id }).await?;
+            println!(
+                "Job {} submitted as a retry of job {} ({:?}).",
+                job.job_id, job_id, job.state
+            );
+        }
+        JobsCommand::Watch => {
+            let mut stream =
+                crate::daemon::subscribe(vec![EventKind::IngestProgress, EventKind::Gc]).await?;
+            print_header();
+            loop {
+                let (_, event) = vrift_ipc::frame_async::read_event(&mut stream).await?;
+                match event {
+                    VeloEvent::IngestProgress { job } | VeloEvent::Gc { job } => print_row(&job),
+                    VeloEvent::Lagged { missed } => {
+                        eprintln!("⚠️  Missed {} job updates", missed)
+                    }
+                    VeloEvent::ManifestChanged { .. } => {}
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_header() {
+    println!(
+        "{:>6}  {:<8} {:<9} {:>9} {:>10}  DESCRIPTION",
+        "ID", "KIND", "STATE", "ELAPSED", "PROGRESS"
+    );
+}
+
+fn print_row(job: &JobInfo) {
+    println!(
+        "{:>6}  {:<8} {:<9} {:>9} {:>10}  {}",
+        job.job_id,
+        format!("{:?}", job.kind),
+        format!("{:?}", job.state),
+        format_elapsed(job.elapsed_ms),
+        format_progress(job),
+        job.description
+    );
+}
+
+async fn send(req: VeloRequest) -> Result<JobInfo> {
+    let mut stream = crate::daemon::connect_simple().await?;
+    crate::daemon::job_request(&mut stream, req).await
+}
+
+fn print_job(job: &JobInfo) {
+    println!("Job {}", job.job_id);
+    println!("  Kind:        {:?}", job.kind);
+    println!("  State:       {:?}", job.state);
+    println!("  Target:      {}", job.description);
+    println!("  Elapsed:     {}", format_elapsed(job.elapsed_ms));
+    println!("  Processed:   {}", format_progress(job));
+    println!("  Affected:    {} ({} bytes)", job.affected, job.bytes);
+    if let Some(retry_of) = job.retry_of {
+        println!("  Retry of:    {}", retry_of);
+    }
+    if let Some(ref error) = job.error {
+        println!("  Error:       {}", error);
+    }
+    if matches!(job.state, JobState::Failed | JobState::Cancelled) {
+        println!();
+        println!(
+            "  👉 Run `vrift jobs retry {}` to run it again.",
+            job.job_id
+        );
+    }
+}
+
+fn format_progress(job: &JobInfo) -> String {
+    if job.total_estimate > 0 && !job.state.is_finished() {
+        format!("{}%", (job.processed * 100 / job.total_estimate).min(100))
+    } else {
+        job.processed.to_string()
+    }
+}
+
+fn format_elapsed(ms: u64) -> String {
+    if ms >= 60_000 {
+        format!("{}m{}s", ms / 60_000, (ms % 60_000) / 1000)
+    } else {
+        format!("{:.1}s", ms as f64 / 1000.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_elapsed() {
+        assert_eq!(format_elapsed(1_500), "1.5s");
+        assert_eq!(format_elapsed(125_000), "2m5s");
+    }
+}
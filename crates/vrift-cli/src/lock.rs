@@ -0,0 +1,498 @@
+//! # vrift lock / vrift verify --lock
+//!
+//! Pins a subtree of the virtual tree (typically a dependency directory such
+//! as `node_modules`) to the exact content it has now. `vrift lock` writes
+//! one line per file or symlink under the subtree:
+//!
+//! ```text
+//! # vrift lock /node_modules
+//! <blake3 hex>  <size>  <tier1|tier2>  <file|link>  <path>
+//! ```
+//!
+//! sorted by path, so the file diffs cleanly in review. `vrift verify --lock`
+//! compares the manifest against it and fails on any file added,
+//! removed or changed since. Inline entries are locked by the hash of their
+//! content, as if they had a blob. Directories are not listed: a directory
+//! only matters through what it contains.
+//!
+//! The default file name is `vrift.sum`; `vrift.lock` belongs to
+//! `vrift resolve`.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use vrift_cas::{Blake3Hash, CasStore};
+use vrift_config::path::normalize_for_ipc;
+use vrift_manifest::digest::covers;
+use vrift_manifest::lmdb::{AssetTier, LmdbManifest, ManifestEntry};
+use vrift_manifest::Manifest;
+
+const HEADER: &str = "# vrift lock ";
+
+/// What a lockfile records of one path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Locked {
+    pub hash: Blake3Hash,
+    pub size: u64,
+    pub tier: AssetTier,
+    pub symlink: bool,
+}
+
+impl Locked {
+    pub(crate) fn of(entry: &ManifestEntry) -> Self {
+        let vnode = &entry.vnode;
+        let hash = match vnode.inline_content() {
+            Some(content) => CasStore::compute_hash(content),
+            None => vnode.content_hash,
+        };
+        Self {
+            hash,
+            size: vnode.size,
+            tier: entry.tier,
+            symlink: vnode.is_symlink(),
+        }
+    }
+}
+
+/// A lockfile: its subtree and what every path under it held
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Lockfile {
+    pub root: String,
+    pub entries: BTreeMap<String, Locked>,
+}
+
+/// How the virtual tree differs from a lockfile at one path
+#[derive(Debug, PartialEq, Eq)]
+pub enum Drift {
+    Added(String),
+    Removed(String),
+    Changed {
+        path: String,
+        locked: Locked,
+        current: Locked,
+    },
+}
+
+impl std::fmt::Display for Drift {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Drift::Added(path) => write!(f, "+ {} (not in the lockfile)", path),
+            Drift::Removed(path) => write!(f, "- {} (locked, now missing)", path),
+            Drift::Changed {
+                path,
+                locked,
+                current,
+            } => {
+                write!(f, "~ {}:", path)?;
+                if locked.hash != current.hash {
+                    write!(
+                        f,
+                        " hash {} -> {}",
+                        &CasStore::hash_to_hex(&locked.hash)[..16],
+                        &CasStore::hash_to_hex(&current.hash)[..16]
+                    )?;
+                }
+                if locked.size != current.size {
+                    write!(f, " size {} -> {}", locked.size, current.size)?;
+                }
+                if locked.tier != current.tier {
+                    write!(
+                        f,
+                        " tier {} -> {}",
+                        tier_name(locked.tier),
+                        tier_name(current.tier)
+                    )?;
+                }
+                if locked.symlink != current.symlink {
+                    write!(
+                        f,
+                        " kind {} -> {}",
+                        kind_name(locked.symlink),
+                        kind_name(current.symlink)
+                    )?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn tier_name(tier: AssetTier) -> &'static str {
+    match tier {
+        AssetTier::Tier1Immutable => "tier1",
+        AssetTier::Tier2Mutable => "tier2",
+    }
+}
+
+fn kind_name(symlink: bool) -> &'static str {
+    if symlink {
+        "link"
+    } else {
+        "file"
+    }
+}
+
+impl Lockfile {
+    /// Lock the entries of `entries` under `root`
+    pub fn build<'a, I>(entries: I, root: &str) -> Self
+    where
+        I: IntoIterator<Item = (&'a str, &'a ManifestEntry)>,
+    {
+        let entries = entries
+            .into_iter()
+            .filter(|(path, entry)| !entry.vnode.is_dir() && covers(root, path))
+            .map(|(path, entry)| (path.to_string(), Locked::of(entry)))
+            .collect();
+        Self {
+            root: root.to_string(),
+            entries,
+        }
+    }
+
+    pub fn render(&self) -> Result<String> {
+        let mut out = format!("{}{}\n", HEADER, self.root);
+        for (path, locked) in &self.entries {
+            if path.contains('\n') {
+                bail!("Cannot lock {:?}: the path contains a newline", path);
+            }
+            let _ = writeln!(
+                out,
+                "{}  {}  {}  {}  {}",
+                CasStore::hash_to_hex(&locked.hash),
+                locked.size,
+                tier_name(locked.tier),
+                kind_name(locked.symlink),
+                path
+            );
+        }
+        Ok(out)
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut lines = text.lines().enumerate();
+        let root = lines
+            .next()
+            .and_then(|(_, line)| line.strip_prefix(HEADER))
+            .map(str::trim)
+            .filter(|root| root.starts_with('/'))
+            .context("Not a vrift lockfile (missing '# vrift lock <path>' header)")?;
+
+        let mut entries = BTreeMap::new();
+        for (n, line) in lines {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let bad = || format!("Malformed lockfile line {}: {}", n + 1, line);
+            // The path comes last and may contain spaces
+            let mut fields = line.splitn(5, "  ");
+            let (Some(hash), Some(size), Some(tier), Some(kind), Some(path)) = (
+                fields.next(),
+                fields.next(),
+                fields.next(),
+                fields.next(),
+                fields.next(),
+            ) else {
+                bail!(bad());
+            };
+            let locked = Locked {
+                hash: CasStore::hex_to_hash(hash).with_context(bad)?,
+                size: size.parse().with_context(bad)?,
+                tier: match tier {
+                    "tier1" => AssetTier::Tier1Immutable,
+                    "tier2" => AssetTier::Tier2Mutable,
+                    _ => bail!(bad()),
+                },
+                symlink: match kind {
+                    "file" => false,
+                    "link" => true,
+                    _ => bail!(bad()),
+                },
+            };
+            if !covers(root, path) {
+                bail!("Lockfile line {}: {} is outside {}", n + 1, path, root);
+            }
+            entries.insert(path.to_string(), locked);
+        }
+        Ok(Self {
+            root: root.to_string(),
+            entries,
+        })
+    }
+
+    /// What changed between `self` (the lock) and `current`, by path
+    pub fn drift(&self, current: &Lockfile) -> Vec<Drift> {
+        let mut drift = Vec::new();
+        for (path, locked) in &self.entries {
+            match current.entries.get(path) {
+                None => drift.push(Drift::Removed(path.clone())),
+                Some(now) if now != locked => drift.push(Drift::Changed {
+                    path: path.clone(),
+                    locked: locked.clone(),
+                    current: now.clone(),
+                }),
+                Some(_) => {}
+            }
+        }
+        for path in current.entries.keys() {
+            if !self.entries.contains_key(path) {
+                drift.push(Drift::Added(path.clone()));
+            }
+        }
+        drift.sort_by(|a, b| drift_path(a).cmp(drift_path(b)));
+        drift
+    }
+}
+
+fn drift_path(drift: &Drift) -> &str {
+    match drift {
+        Drift::Added(path) | Drift::Removed(path) | Drift::Changed { path, .. } => path,
+    }
+}
+
+/// The entries of `manifest` (an LMDB manifest or a manifest file), or of
+/// the project's live manifest. Warns about entries under `root` pending
+/// re-ingest, whose recorded hash may be out of date.
+pub(crate) fn load_entries(
+    project_root: &Path,
+    manifest: Option<&Path>,
+    root: &str,
+) -> Result<Vec<(String, ManifestEntry)>> {
+    let lmdb_path = match manifest {
+        Some(file) if !file.is_dir() => {
+            // Manifest files carry no tiers; their entries count as the default
+            let manifest = Manifest::load(file)
+                .with_context(|| format!("Failed to load manifest: {}", file.display()))?;
+            return Ok(manifest
+                .iter()
+                .map(|(p, vnode)| {
+                    let entry = ManifestEntry {
+                        vnode: vnode.clone(),
+                        tier: AssetTier::default(),
+                        stale: false,
+                        ingested_at: 0,
+                    };
+                    (p.to_string(), entry)
+                })
+                .collect());
+        }
+        Some(dir) => dir.to_path_buf(),
+        None => {
+            let project_id = vrift_config::path::compute_project_id(project_root);
+            let manifest_path = vrift_config::path::get_manifest_db_path(&project_id)
+                .ok_or_else(|| anyhow::anyhow!("Could not determine manifest path"))?;
+            if !manifest_path.exists() {
+                bail!(
+                    "Manifest not found at {}. Run 'vrift init' first.",
+                    manifest_path.display()
+                );
+            }
+            manifest_path
+        }
+    };
+    let entries = LmdbManifest::open_readonly(&lmdb_path)?.iter()?;
+    let stale = entries
+        .iter()
+        .filter(|(p, e)| e.stale && covers(root, p))
+        .count();
+    if stale > 0 {
+        eprintln!(
+            "Warning: {} entries under {} are pending re-ingest; using their last recorded hash",
+            stale, root
+        );
+    }
+    Ok(entries)
+}
+
+/// Write the lockfile of `path` (the whole project by default) to `output`
+pub fn cmd_lock(
+    project_dir: &Path,
+    path: Option<&Path>,
+    manifest: Option<&Path>,
+    output: &Path,
+) -> Result<()> {
+    let project_root = normalize_for_ipc(project_dir).context("resolve project path")?;
+    let root = match path {
+        Some(p) => crate::path_to_manifest_key(&project_root, p)?,
+        None => "/".to_string(),
+    };
+    let entries = load_entries(&project_root, manifest, &root)?;
+    let lock = Lockfile::build(entries.iter().map(|(p, e)| (p.as_str(), e)), &root);
+    if lock.entries.is_empty() {
+        bail!("Not in manifest: {}", root);
+    }
+    std::fs::write(output, lock.render()?)
+        .with_context(|| format!("Failed to write {}", output.display()))?;
+    println!(
+        "Locked {} entries under {} in {}",
+        lock.entries.len(),
+        root,
+        output.display()
+    );
+    Ok(())
+}
+
+/// Fail if the project's virtual tree drifted from `lockfile`
+pub fn cmd_verify_lock(project_dir: &Path, manifest: Option<&Path>, lockfile: &Path) -> Result<()> {
+    let project_root = normalize_for_ipc(project_dir).context("resolve project path")?;
+    let text = std::fs::read_to_string(lockfile)
+        .with_context(|| format!("Failed to read {}", lockfile.display()))?;
+    let lock = Lockfile::parse(&text).with_context(|| lockfile.display().to_string())?;
+    let entries = load_entries(&project_root, manifest, &lock.root)?;
+    let current = Lockfile::build(entries.iter().map(|(p, e)| (p.as_str(), e)), &lock.root);
+
+    let drift = lock.drift(&current);
+    if drift.is_empty() {
+        println!(
+            "{}: {} entries under {} match",
+            lockfile.display(),
+            lock.entries.len(),
+            lock.root
+        );
+        return Ok(());
+    }
+    for d in &drift {
+        println!("{}", d);
+    }
+    bail!(
+        "{} entries under {} drifted from {}",
+        drift.len(),
+        lock.root,
+        lockfile.display()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vrift_manifest::VnodeEntry;
+
+    fn entry(content: &[u8], tier: AssetTier) -> ManifestEntry {
+        ManifestEntry {
+            vnode: VnodeEntry::new_file(
+                CasStore::compute_hash(content),
+                content.len() as u64,
+                1,
+                0o644,
+            ),
+            tier,
+            stale: false,
+            ingested_at: 0,
+        }
+    }
+
+    fn sample() -> Vec<(String, ManifestEntry)> {
+        vec![
+            (
+                "/node_modules/a/index.js".to_string(),
+                entry(b"module.exports = 1", AssetTier::Tier1Immutable),
+            ),
+            (
+                "/node_modules/a dir/with space.js".to_string(),
+                entry(b"x", AssetTier::Tier2Mutable),
+            ),
+            (
+                "/node_modules/tiny".to_string(),
+                ManifestEntry {
+                    vnode: VnodeEntry::new_inline(b"hi", 1, 0o644).unwrap(),
+                    tier: AssetTier::Tier2Mutable,
+                    stale: false,
+                    ingested_at: 0,
+                },
+            ),
+            (
+                "/node_modules/a".to_string(),
+                ManifestEntry {
+                    vnode: VnodeEntry::new_directory(1, 0o755),
+                    tier: AssetTier::Tier2Mutable,
+                    stale: false,
+                    ingested_at: 0,
+                },
+            ),
+            (
+                "/src/main.rs".to_string(),
+                entry(b"fn main() {}", AssetTier::Tier2Mutable),
+            ),
+        ]
+    }
+
+    fn build(entries: &[(String, ManifestEntry)]) -> Lockfile {
+        Lockfile::build(
+            entries.iter().map(|(p, e)| (p.as_str(), e)),
+            "/node_modules",
+        )
+    }
+
+    #[test]
+    fn test_lock_covers_files_under_root_only() {
+        let lock = build(&sample());
+        let paths: Vec<&str> = lock.entries.keys().map(String::as_str).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "/node_modules/a dir/with space.js",
+                "/node_modules/a/index.js",
+                "/node_modules/tiny",
+            ]
+        );
+        // Inline content is locked by its hash, not by the raw bytes
+        assert_eq!(
+            lock.entries["/node_modules/tiny"].hash,
+            CasStore::compute_hash(b"hi")
+        );
+    }
+
+    #[test]
+    fn test_render_parse_roundtrip() {
+        let lock = build(&sample());
+        let text = lock.render().unwrap();
+        assert!(text.starts_with("# vrift lock /node_modules\n"));
+        assert_eq!(Lockfile::parse(&text).unwrap(), lock);
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed() {
+        assert!(Lockfile::parse("").is_err());
+        assert!(Lockfile::parse("not a lockfile\n").is_err());
+        let hash = CasStore::hash_to_hex(&[0u8; 32]);
+        let line = |tier: &str, path: &str| {
+            format!(
+                "# vrift lock /deps\n{}  1  {}  file  {}\n",
+                hash, tier, path
+            )
+        };
+        assert!(Lockfile::parse(&line("tier1", "/deps/x")).is_ok());
+        assert!(Lockfile::parse(&line("tier3", "/deps/x")).is_err());
+        assert!(Lockfile::parse(&line("tier1", "/elsewhere/x")).is_err());
+    }
+
+    #[test]
+    fn test_drift() {
+        let lock = build(&sample());
+        assert!(lock.drift(&build(&sample())).is_empty());
+
+        let mut now = sample();
+        now.retain(|(p, _)| p != "/node_modules/tiny");
+        now[0].1 = entry(b"module.exports = 2", AssetTier::Tier1Immutable);
+        now.push((
+            "/node_modules/b.js".to_string(),
+            entry(b"new", AssetTier::Tier2Mutable),
+        ));
+        // Outside the locked subtree: not drift
+        now.push((
+            "/src/lib.rs".to_string(),
+            entry(b"", AssetTier::Tier2Mutable),
+        ));
+
+        let drift = lock.drift(&build(&now));
+        assert_eq!(drift.len(), 3);
+        assert!(
+            matches!(&drift[0], Drift::Changed { path, .. } if path == "/node_modules/a/index.js")
+        );
+        assert_eq!(drift[1], Drift::Added("/node_modules/b.js".to_string()));
+        assert_eq!(drift[2], Drift::Removed("/node_modules/tiny".to_string()));
+        assert!(drift[0].to_string().contains(" hash "));
+        assert!(!drift[0].to_string().contains(" size "));
+    }
+}
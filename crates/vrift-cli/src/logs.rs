@@ -0,0 +1,165 @@
+//! # vrift logs
+//!
+//! Collects the log files in `daemon.log_dir`: per-process shim logs (rotated
+//! generations first), shim crash reports, and the daemon's own logs and
+//! panic reports. `--follow` keeps polling and prints whatever is appended,
+//! including logs of processes that exit while it runs.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use vrift_config::log_files::{self, LogFile, LogKind};
+
+const FOLLOW_POLL: Duration = Duration::from_millis(500);
+
+pub fn cmd_logs(pid: Option<u32>, follow: bool) -> Result<()> {
+    let dir = vrift_config::config().log_dir().to_path_buf();
+    let mut tail = Tail::default();
+    let mut out = io::stdout().lock();
+
+    let files = select(&dir, pid)?;
+    if files.is_empty() && !follow {
+        match pid {
+            Some(pid) => eprintln!("No logs for pid {} in {}", pid, dir.display()),
+            None => eprintln!("No logs in {}", dir.display()),
+        }
+        return Ok(());
+    }
+    tail.poll(&files, &mut out)?;
+
+    if !follow {
+        return Ok(());
+    }
+    loop {
+        std::thread::sleep(FOLLOW_POLL);
+        // Rotated generations hold data already shown from the live file
+        let live: Vec<LogFile> = select(&dir, pid)?
+            .into_iter()
+            .filter(|f| f.generation == 0)
+            .collect();
+        tail.poll(&live, &mut out)?;
+    }
+}
+
+/// Logs in `dir`, oldest first; with `pid`, only that process's shim log,
+/// crash report and (if it was the daemon) panic report
+fn select(dir: &Path, pid: Option<u32>) -> Result<Vec<LogFile>> {
+    let files = log_files::list(dir).with_context(|| format!("Cannot read {}", dir.display()))?;
+    Ok(match pid {
+        Some(pid) => files.into_iter().filter(|f| f.pid == Some(pid)).collect(),
+        None => files,
+    })
+}
+
+/// Remembers how much of each file has been printed
+#[derive(Default)]
+struct Tail {
+    offsets: HashMap<PathBuf, u64>,
+    last_header: Option<PathBuf>,
+}
+
+impl Tail {
+    /// Print everything not printed yet, with a `==> path <==` header when
+    /// switching files. A file that shrank was rotated and is read from the
+    /// start again.
+    fn poll(&mut self, files: &[LogFile], out: &mut impl Write) -> io::Result<()> {
+        for file in files {
+            let offset = self.offsets.get(&file.path).copied().unwrap_or(0);
+            let offset = if file.len < offset { 0 } else { offset };
+            if file.len == offset && self.offsets.contains_key(&file.path) {
+                continue;
+            }
+
+            let mut f = match File::open(&file.path) {
+                Ok(f) => f,
+                // Rotated or pruned since it was listed
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            };
+            f.seek(SeekFrom::Start(offset))?;
+            let mut data = Vec::new();
+            f.read_to_end(&mut data)?;
+
+            if self.last_header.as_ref() != Some(&file.path) {
+                writeln!(out, "==> {} ({}) <==", file.path.display(), describe(file))?;
+                self.last_header = Some(file.path.clone());
+            }
+            out.write_all(&data)?;
+            if !data.ends_with(b"\n") && !data.is_empty() {
+                writeln!(out)?;
+            }
+            self.offsets
+                .insert(file.path.clone(), offset + data.len() as u64);
+        }
+        out.flush()
+    }
+}
+
+fn describe(file: &LogFile) -> String {
+    let pid = file.pid.map(|p| format!(" pid {}", p)).unwrap_or_default();
+    match file.kind {
+        LogKind::Shim if file.generation > 0 => {
+            format!("shim{}, rotation {}", pid, file.generation)
+        }
+        LogKind::Shim => format!("shim{}", pid),
+        LogKind::ShimCrash => format!("shim crash{}", pid),
+        LogKind::Daemon => "daemon".to_string(),
+        LogKind::DaemonCrash => format!("daemon crash{}", pid),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn render(tail: &mut Tail, dir: &Path, pid: Option<u32>) -> String {
+        let mut out = Vec::new();
+        tail.poll(&select(dir, pid).unwrap(), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_pid_filter_and_rotation_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let live = log_files::shim_log_path(dir.path(), 7);
+        fs::write(&live, "old\n").unwrap();
+        assert!(log_files::rotate_if_needed(&live, 1).unwrap());
+        fs::write(&live, "new\n").unwrap();
+        fs::write(log_files::shim_log_path(dir.path(), 8), "other\n").unwrap();
+
+        let text = render(&mut Tail::default(), dir.path(), Some(7));
+        let old = text.find("old").unwrap();
+        let new = text.find("new").unwrap();
+        assert!(old < new, "rotated generation must come first:\n{}", text);
+        assert!(text.contains("rotation 1"));
+        assert!(!text.contains("other"));
+    }
+
+    #[test]
+    fn test_follow_prints_only_appended_data() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = log_files::shim_log_path(dir.path(), 1);
+        fs::write(&path, "first\n").unwrap();
+
+        let mut tail = Tail::default();
+        assert!(render(&mut tail, dir.path(), None).contains("first"));
+        assert_eq!(render(&mut tail, dir.path(), None), "");
+
+        fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"second\n")
+            .unwrap();
+        assert_eq!(render(&mut tail, dir.path(), None), "second\n");
+
+        // Truncated/recreated by rotation: start over
+        fs::write(&path, "3\n").unwrap();
+        assert_eq!(render(&mut tail, dir.path(), None), "3\n");
+    }
+}
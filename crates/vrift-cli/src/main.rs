@@ -6,7 +6,13 @@
 //!
 //! - `vrift ingest <dir>` - Import files to CAS and generate manifest
 //! - `vrift run <cmd>` - Execute command with VeloVFS virtualization
+//! - `vrift record -- <cmd>` - Record the files a command reads
+//! - `vrift hash <paths>` - Print a VFS-aware cache key for paths
+//! - `vrift lock <path>` - Pin a subtree's content in a reviewable lockfile
+//! - `vrift verify --lock <file>` - Check the virtual tree against a lockfile
+//! - `vrift sbom <path>` - Emit a CycloneDX/SPDX SBOM of the packages in a subtree
 //! - `vrift status` - Display CAS statistics
+//! - `vrift selftest` - Check syscall interception end to end
 
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -17,19 +23,35 @@ use vrift_config::path::{normalize_for_ipc, normalize_or_original};
 
 mod active;
 mod daemon;
+mod docker;
 mod doctor;
+mod du;
+mod dump;
+mod exit;
 pub mod gc;
 mod inception;
 mod isolation;
+mod jobs;
+mod lock;
+mod logs;
 mod mount;
+mod pack;
+mod plan;
 mod preflight;
+mod preload;
+mod profile;
+mod record;
 pub mod registry;
+mod sbom;
 #[allow(dead_code)]
 mod security_filter;
+mod selftest;
+mod service;
+mod top;
 
 use vrift_cas::CasStore;
 use vrift_manifest::lmdb::LmdbManifest;
-use vrift_manifest::Manifest;
+use vrift_manifest::{DiffKind, Manifest};
 
 /// Velo Rift™ - Content-Addressable Virtual Filesystem (Powered by VeloVFS)
 #[derive(Parser)]
@@ -41,6 +63,10 @@ struct Cli {
     #[arg(long = "the-source-root")]
     the_source_root: Option<PathBuf>,
 
+    /// Config profile to apply (`[profile.<name>]` in config.toml)
+    #[arg(long, global = true, env = "VRIFT_PROFILE")]
+    profile: Option<String>,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -91,6 +117,30 @@ enum Commands {
         /// Useful for audit/verification when you suspect data corruption
         #[arg(long)]
         force_hash: bool,
+
+        /// List the files the ingest would hash, link or move, changing nothing
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Print the dry-run plan as JSON
+        #[arg(long, requires = "dry_run")]
+        json: bool,
+
+        /// Only ingest the files and directories listed in FILE, one per
+        /// line, relative to DIR (`-` reads stdin). Other manifest entries
+        /// are left as they are.
+        #[arg(long, value_name = "FILE")]
+        paths_from: Option<PathBuf>,
+
+        /// Only ingest files matching GLOB (repeatable). `*` and `?` stay
+        /// within a path component, `**` spans them; a GLOB without `/`
+        /// matches file names. E.g. --include 'target/release/**'
+        #[arg(long, value_name = "GLOB")]
+        include: Vec<String>,
+
+        /// Leave out files matching GLOB (repeatable)
+        #[arg(long, value_name = "GLOB")]
+        exclude: Vec<String>,
     },
 
     /// Execute a command with VeloVFS virtualization
@@ -116,6 +166,147 @@ enum Commands {
         daemon: bool,
     },
 
+    /// Run containers with the VFS injected (shim, CAS and daemon sockets)
+    Docker {
+        #[command(subcommand)]
+        command: docker::DockerCommand,
+    },
+
+    /// Run a command and record every file it reads
+    ///
+    /// Usage: vrift record [-o inputs.json] -- <cmd> [args...]
+    Record {
+        /// Output file (default: vrift.inputs.json or vrift.inputs.manifest)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Output format
+        #[arg(long, value_enum, default_value = "json")]
+        format: record::RecordFormat,
+
+        /// Project directory (default: current directory)
+        #[arg(short = 'C', long)]
+        directory: Option<PathBuf>,
+
+        /// Command to execute
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        command: Vec<String>,
+    },
+
+    /// Print a content digest of the manifest entries under the given paths
+    ///
+    /// Uses recorded hashes only, so it is cheap enough for Makefile cache keys:
+    /// KEY := $(shell vrift hash src Cargo.toml)
+    Hash {
+        /// Files or directories to cover (default: the whole project)
+        #[arg(value_name = "PATH")]
+        paths: Vec<PathBuf>,
+
+        /// Use a manifest file instead of the project's live manifest
+        #[arg(short, long)]
+        manifest: Option<PathBuf>,
+
+        /// Project directory (default: current directory)
+        #[arg(short = 'C', long)]
+        directory: Option<PathBuf>,
+
+        /// Also print the number of entries covered
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// Write a lockfile pinning the content of a subtree (e.g. node_modules)
+    ///
+    /// One line per file: hash, size, tier and path, sorted for review.
+    /// Check it later with `vrift verify --lock`.
+    Lock {
+        /// Subtree to lock (default: the whole project)
+        #[arg(value_name = "PATH")]
+        path: Option<PathBuf>,
+
+        /// Lockfile to write
+        #[arg(short, long, default_value = "vrift.sum")]
+        output: PathBuf,
+
+        /// Use a manifest (file or LMDB) instead of the project's live manifest
+        #[arg(short, long)]
+        manifest: Option<PathBuf>,
+
+        /// Project directory (default: current directory)
+        #[arg(short = 'C', long)]
+        directory: Option<PathBuf>,
+    },
+
+    /// Check the virtual tree against a lockfile from `vrift lock`
+    ///
+    /// Fails, listing every difference, when a locked file was changed or
+    /// removed or a file was added under the locked subtree.
+    Verify {
+        /// Lockfile to check against
+        #[arg(long, value_name = "FILE")]
+        lock: PathBuf,
+
+        /// Use a manifest (file or LMDB) instead of the project's live manifest
+        #[arg(short, long)]
+        manifest: Option<PathBuf>,
+
+        /// Project directory (default: current directory)
+        #[arg(short = 'C', long)]
+        directory: Option<PathBuf>,
+    },
+
+    /// Emit a software bill of materials for the packages in the manifest
+    ///
+    /// Finds npm packages by package.json and Python packages by
+    /// *.dist-info/METADATA and lists every file with its BLAKE3 hash.
+    Sbom {
+        /// Subtree to scan (default: the whole project)
+        #[arg(value_name = "PATH")]
+        path: Option<PathBuf>,
+
+        /// Document format
+        #[arg(long, value_enum, default_value = "cyclonedx")]
+        format: sbom::SbomFormat,
+
+        /// Output file (default: stdout)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Use a manifest (file or LMDB) instead of the project's live manifest
+        #[arg(short, long)]
+        manifest: Option<PathBuf>,
+
+        /// Project directory (default: current directory)
+        #[arg(short = 'C', long)]
+        directory: Option<PathBuf>,
+    },
+
+    /// Show logical, deduplicated and exclusive size per directory
+    ///
+    /// EXCLUSIVE is the CAS space only that directory references, i.e. what
+    /// removing it would free across all registered workspaces
+    Du {
+        /// Directory to report (default: the whole project)
+        #[arg(value_name = "PATH")]
+        path: Option<PathBuf>,
+
+        /// Levels of subdirectories to list
+        #[arg(short, long, default_value = "1")]
+        depth: usize,
+
+        /// Use a manifest file instead of the project's live manifest
+        #[arg(short, long)]
+        manifest: Option<PathBuf>,
+
+        /// Project directory (default: current directory)
+        #[arg(short = 'C', long)]
+        directory: Option<PathBuf>,
+
+        /// Ignore other registered workspaces when computing EXCLUSIVE
+        #[arg(long)]
+        local: bool,
+    },
+
     /// Display CAS statistics and session status
     Status {
         /// Also show manifest statistics if a manifest file is provided
@@ -141,6 +332,30 @@ enum Commands {
     /// Garbage Collect unreferenced blobs
     Gc(gc::GcArgs),
 
+    /// Re-hash every CAS blob and report corrupt ones
+    Scrub(gc::ScrubArgs),
+
+    /// Trace a command's reads and pack them into a hot packfile
+    Pack {
+        #[command(subcommand)]
+        command: pack::PackCommand,
+    },
+
+    /// Export, merge and import access profiles for pack ordering
+    Profile {
+        #[command(subcommand)]
+        command: profile::ProfileCommand,
+    },
+
+    /// List, cancel and retry long-running daemon jobs
+    Jobs {
+        #[command(subcommand)]
+        command: jobs::JobsCommand,
+    },
+
+    /// Live view of request rates, lookup hit ratios, hot paths and jobs
+    Top(top::TopArgs),
+
     /// Resolve dependencies from a velo.lock file
     Resolve {
         /// Lockfile path
@@ -148,6 +363,17 @@ enum Commands {
         lockfile: PathBuf,
     },
 
+    /// List processes in active workspace sessions
+    Ps {
+        /// Show sessions for all workspaces, not just this project
+        #[arg(short, long)]
+        all: bool,
+
+        /// Project directory (default: current directory)
+        #[arg(value_name = "DIR")]
+        directory: Option<PathBuf>,
+    },
+
     /// Daemon management
     Daemon {
         #[command(subcommand)]
@@ -240,6 +466,52 @@ enum Commands {
         directory: Option<PathBuf>,
     },
 
+    /// Repair the manifest after vDird was killed
+    ///
+    /// Commits the changes left in the manifest journal and marks entries
+    /// whose blob is missing from the CAS as pending re-ingest.
+    Fsck {
+        /// Project directory (default: current directory)
+        #[arg(value_name = "DIR")]
+        directory: Option<PathBuf>,
+    },
+
+    /// Named snapshots of the manifest, to roll a workspace back
+    Snapshot {
+        #[command(subcommand)]
+        command: SnapshotCommands,
+    },
+
+    /// Show the paths that changed from one manifest snapshot to another
+    Diff {
+        /// Snapshot to compare from
+        from: String,
+
+        /// Snapshot to compare to
+        to: String,
+
+        /// Project directory (default: current directory)
+        #[arg(short, long, value_name = "DIR")]
+        directory: Option<PathBuf>,
+
+        /// Print the changes as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Verify interposition end to end against a scratch VFS project
+    ///
+    /// Prints which syscalls the inception layer intercepts on this OS/arch.
+    Selftest {
+        /// Keep the scratch project for inspection
+        #[arg(long)]
+        keep: bool,
+
+        /// Internal: run the syscall sequence (spawned under the shim)
+        #[arg(long, value_name = "DIR", hide = true)]
+        helper: Option<PathBuf>,
+    },
+
     /// Run diagnostic checks on the Velo Rift environment
     Doctor {
         /// Project directory (default: current directory)
@@ -247,6 +519,17 @@ enum Commands {
         directory: Option<PathBuf>,
     },
 
+    /// Show shim and daemon logs from the log directory
+    Logs {
+        /// Only logs of this process
+        #[arg(long)]
+        pid: Option<u32>,
+
+        /// Keep printing as logs grow or new ones appear
+        #[arg(short, long)]
+        follow: bool,
+    },
+
     /// Debugging and observability tools (internal use)
     Debug {
         #[command(subcommand)]
@@ -256,12 +539,29 @@ enum Commands {
 
 #[derive(Subcommand)]
 enum DaemonCommands {
-    /// Check daemon status (ping)
+    /// Check daemon status
     Status {
         /// Project directory (default: current directory)
         #[arg(short, long, value_name = "DIR")]
         directory: Option<PathBuf>,
     },
+    /// Health check for readiness probes (exit status 0 when the daemon answers)
+    Ping {
+        /// Seconds to wait for an answer
+        #[arg(long, default_value_t = 2)]
+        timeout: u64,
+        /// Print nothing; report through the exit status only
+        #[arg(short, long)]
+        quiet: bool,
+    },
+    /// Install vriftd as a systemd user service / launchd agent
+    Install {
+        /// Start the daemon at login instead of on first connection
+        #[arg(long)]
+        no_socket_activation: bool,
+    },
+    /// Remove the vriftd service
+    Uninstall,
 }
 
 #[derive(Subcommand)]
@@ -330,6 +630,58 @@ enum ManifestCommands {
         #[arg(value_name = "DIR")]
         directory: Option<PathBuf>,
     },
+
+    /// Export the manifest to a SQLite database for ad hoc queries
+    Dump {
+        /// Project directory (default: current directory)
+        #[arg(value_name = "DIR")]
+        directory: Option<PathBuf>,
+
+        /// Database to write (replaced if it exists)
+        #[arg(long, value_name = "FILE")]
+        sqlite: PathBuf,
+
+        /// `vrift record` JSON reports to count reads from (repeatable)
+        #[arg(long = "inputs", value_name = "FILE")]
+        inputs: Vec<PathBuf>,
+    },
+
+    /// Rewrite the manifest without the space of removed entries
+    Compact {
+        /// Project directory (default: current directory)
+        #[arg(value_name = "DIR")]
+        directory: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum SnapshotCommands {
+    /// List the snapshots of the manifest
+    List {
+        /// Project directory (default: current directory)
+        #[arg(value_name = "DIR")]
+        directory: Option<PathBuf>,
+    },
+
+    /// Capture the manifest as a named snapshot
+    Create {
+        /// Snapshot name: letters, digits, '.', '_' or '-'
+        name: String,
+
+        /// Project directory (default: current directory)
+        #[arg(short, long, value_name = "DIR")]
+        directory: Option<PathBuf>,
+    },
+
+    /// Replace the manifest with a snapshot (vriftd must be stopped)
+    Restore {
+        /// Snapshot to restore
+        name: String,
+
+        /// Project directory (default: current directory)
+        #[arg(short, long, value_name = "DIR")]
+        directory: Option<PathBuf>,
+    },
 }
 
 #[derive(Subcommand)]
@@ -346,7 +698,14 @@ enum DebugCommands {
     },
 }
 
-fn main() -> Result<()> {
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("Error: {:?}", e);
+        std::process::exit(exit::error_kind(&e).exit_code());
+    }
+}
+
+fn run() -> Result<()> {
     // BUG-008: Reset SIGPIPE handler to default to avoid panics when piping output (e.g. `vrift status | head`)
     // Rust's default behavior ignores SIGPIPE and panics on print!, which is noisy for CLI tools.
     #[cfg(unix)]
@@ -364,6 +723,17 @@ fn main() -> Result<()> {
         .init();
 
     let cli = Cli::parse();
+    if let Some(ref profile) = cli.profile {
+        // Config is loaded lazily and by the daemon and shim this spawns;
+        // all of them pick the profile up from here
+        std::env::set_var("VRIFT_PROFILE", profile);
+        if let Err(e @ vrift_config::ConfigError::Profile(_)) = vrift_config::Config::load() {
+            return Err(e.into());
+        }
+    }
+    // Loading the config sets the Unicode form of the manifest keys we build
+    drop(vrift_config::config());
+
     // RFC-0043: Resolve CAS root with proper precedence:
     // Explicit CLI arg > VR_THE_SOURCE env > config.toml > default (~/.vrift/the_source)
     // cli_cas_root_override is Some only when user explicitly passes --the-source-root
@@ -423,7 +793,20 @@ async fn async_main(
             no_security_filter: _,
             show_excluded: _,
             force_hash,
+            dry_run,
+            json,
+            paths_from,
+            include,
+            exclude,
         } => {
+            let selection = vrift_ipc::IngestSelection {
+                paths: match paths_from {
+                    Some(file) => read_path_list(&file)?,
+                    None => Vec::new(),
+                },
+                include,
+                exclude,
+            };
             let (mode, tier) = {
                 let config = vrift_config::config();
                 (
@@ -441,7 +824,7 @@ async fn async_main(
             // Must match daemon workspace path at get_or_create_workspace()
             let output = if output.to_string_lossy() == "vrift.manifest" {
                 let vrift_dir = directory.join(".vrift");
-                if !vrift_dir.exists() {
+                if !vrift_dir.exists() && !dry_run {
                     let _ = std::fs::create_dir_all(&vrift_dir);
                     let _ = std::fs::create_dir_all(vrift_dir.join("locks"));
                 }
@@ -450,6 +833,14 @@ async fn async_main(
                 output
             };
 
+            if dry_run {
+                let plan = daemon::plan_ingest_via_daemon(
+                    &directory, &output, is_phantom, is_tier1, force_hash, selection,
+                )
+                .await?;
+                return plan::print(&plan, json);
+            }
+
             match daemon::ingest_via_daemon(
                 &directory,
                 &output,
@@ -459,31 +850,12 @@ async fn async_main(
                 Some(prefix_val),
                 cli_cas_root_override.as_deref(),
                 force_hash,
+                selection,
             )
             .await
             {
                 Ok(result) => {
-                    let elapsed_secs = result.duration_ms as f64 / 1000.0;
-                    let files_per_sec = if elapsed_secs > 0.0 {
-                        result.files as f64 / elapsed_secs
-                    } else {
-                        0.0
-                    };
-                    let dedup_ratio = if result.files > 0 {
-                        100.0 * (1.0 - (result.blobs as f64 / result.files as f64))
-                    } else {
-                        0.0
-                    };
-
-                    println!();
-                    println!("╔════════════════════════════════════════╗");
-                    println!("║  ✅ VRift Complete                     ║");
-                    println!("╚════════════════════════════════════════╝");
-                    println!();
-                    println!("   📁 {} files → {} blobs", result.files, result.blobs);
-                    println!("   📊 {:.1}% dedup", dedup_ratio);
-                    println!("   ⚡ {:.0} files/sec", files_per_sec);
-                    println!("   📄 Manifest: {}", result.manifest_path);
+                    print_ingest_summary(&result);
 
                     // RFC-0041: Explicitly register the manifest after ingest for GC tracking
                     // We attempt to acquire lock but don't block indefinitely on failures
@@ -496,12 +868,22 @@ async fn async_main(
                                 std::path::Path::new(&result.manifest_path),
                                 &directory,
                             ) {
-                                Ok(_) => {
+                                Ok(uuid) => {
                                     if let Err(e) = registry.save() {
                                         tracing::warn!("Failed to save manifest registry: {}", e);
                                     } else {
                                         tracing::info!("Registered manifest for GC tracking");
                                     }
+                                    // `vrift gc` resyncs every manifest anyway; this
+                                    // keeps `CasStore::refs` current in between
+                                    if vrift_config::config().gc.refcount {
+                                        if let Err(e) = registry.record_refs(&cas_root, &uuid) {
+                                            tracing::warn!(
+                                                "Failed to update blob refcounts: {}",
+                                                e
+                                            );
+                                        }
+                                    }
                                 }
                                 Err(e) => tracing::warn!("Failed to register manifest: {}", e),
                             }
@@ -528,6 +910,77 @@ async fn async_main(
             base.as_deref(),
             daemon,
         ),
+        Commands::Docker { command } => docker::run(&cas_root, command).await,
+        Commands::Record {
+            output,
+            format,
+            directory,
+            command,
+        } => {
+            let dir = directory.unwrap_or_else(|| std::env::current_dir().unwrap());
+            let output = output.unwrap_or_else(|| record::default_output(format));
+            record::cmd_record(&dir, &command, &output, format).await
+        }
+        Commands::Hash {
+            paths,
+            manifest,
+            directory,
+            verbose,
+        } => {
+            let dir = directory.unwrap_or_else(|| std::env::current_dir().unwrap());
+            cmd_hash(&dir, &paths, manifest.as_deref(), verbose)
+        }
+        Commands::Lock {
+            path,
+            output,
+            manifest,
+            directory,
+        } => {
+            let dir = directory.unwrap_or_else(|| std::env::current_dir().unwrap());
+            lock::cmd_lock(&dir, path.as_deref(), manifest.as_deref(), &output)
+        }
+        Commands::Verify {
+            lock,
+            manifest,
+            directory,
+        } => {
+            let dir = directory.unwrap_or_else(|| std::env::current_dir().unwrap());
+            lock::cmd_verify_lock(&dir, manifest.as_deref(), &lock)
+        }
+        Commands::Sbom {
+            path,
+            format,
+            output,
+            manifest,
+            directory,
+        } => {
+            let dir = directory.unwrap_or_else(|| std::env::current_dir().unwrap());
+            sbom::cmd_sbom(
+                &dir,
+                cli_cas_root_override.as_deref(),
+                path.as_deref(),
+                manifest.as_deref(),
+                format,
+                output.as_deref(),
+            )
+        }
+        Commands::Du {
+            path,
+            depth,
+            manifest,
+            directory,
+            local,
+        } => {
+            let dir = directory.unwrap_or_else(|| std::env::current_dir().unwrap());
+            du::cmd_du(
+                &cas_root,
+                &dir,
+                path.as_deref(),
+                manifest.as_deref(),
+                depth,
+                local,
+            )
+        }
         Commands::Status {
             manifest,
             session,
@@ -539,12 +992,35 @@ async fn async_main(
         }
         Commands::Mount(args) => mount::run(args, &cas_root),
         Commands::Gc(args) => gc::run(&cas_root, args).await,
+        Commands::Scrub(args) => gc::scrub(&cas_root, args).await,
+        Commands::Pack { command } => pack::run(command).await,
+        Commands::Profile { command } => profile::run(command),
+        Commands::Jobs { command } => jobs::run(command).await,
+        Commands::Top(args) => top::run(args).await,
         Commands::Resolve { lockfile } => cmd_resolve(&cas_root, &lockfile),
+        Commands::Ps { all, directory } => {
+            let dir = directory.unwrap_or_else(|| std::env::current_dir().unwrap());
+            cmd_ps(&dir, all).await
+        }
         Commands::Daemon { command } => match command {
             DaemonCommands::Status { directory } => {
                 let dir = directory.unwrap_or_else(|| std::env::current_dir().unwrap());
                 daemon::check_status(&dir).await
             }
+            DaemonCommands::Ping { timeout, quiet } => {
+                let health = daemon::ping(std::time::Duration::from_secs(timeout)).await?;
+                if !quiet {
+                    println!(
+                        "vriftd {} (pid {}) up {}s",
+                        health.version, health.pid, health.uptime_secs
+                    );
+                }
+                Ok(())
+            }
+            DaemonCommands::Install {
+                no_socket_activation,
+            } => service::install(!no_socket_activation).await,
+            DaemonCommands::Uninstall => service::uninstall(),
         },
         Commands::Watch { directory, output } => cmd_watch(&cas_root, &directory, &output).await,
         Commands::Active { phantom, directory } => {
@@ -572,20 +1048,39 @@ async fn async_main(
         Commands::Wake => inception::cmd_wake(),
         Commands::Hook { shell } => inception::cmd_hook(&shell),
         Commands::Service { command } => match command {
-            ServiceCommands::Install => cmd_service_install(),
-            ServiceCommands::Uninstall => cmd_service_uninstall(),
-            ServiceCommands::Restart => cmd_service_restart(),
+            ServiceCommands::Install => service::install(true).await,
+            ServiceCommands::Uninstall => service::uninstall(),
+            ServiceCommands::Restart => service::restart(),
         },
         Commands::Config { command } => cmd_config(command),
-        Commands::Manifest { command } => cmd_manifest(command),
+        Commands::Manifest { command } => cmd_manifest(&cas_root, command).await,
         Commands::Sync { directory } => {
             let dir = directory.unwrap_or_else(|| std::env::current_dir().unwrap());
             cmd_sync(&dir).await
         }
+        Commands::Fsck { directory } => {
+            let dir = directory.unwrap_or_else(|| std::env::current_dir().unwrap());
+            cmd_fsck(&cas_root, &dir).await
+        }
+        Commands::Snapshot { command } => cmd_snapshot(&cas_root, command).await,
+        Commands::Diff {
+            from,
+            to,
+            directory,
+            json,
+        } => {
+            let dir = directory.unwrap_or_else(|| std::env::current_dir().unwrap());
+            cmd_diff(&cas_root, &dir, &from, &to, json)
+        }
+        Commands::Selftest { keep, helper } => match helper {
+            Some(dir) => selftest::run_helper(&dir),
+            None => selftest::cmd_selftest(keep).await,
+        },
         Commands::Doctor { directory } => {
             let dir = directory.unwrap_or_else(|| std::env::current_dir().unwrap());
             doctor::cmd_doctor(&dir)
         }
+        Commands::Logs { pid, follow } => logs::cmd_logs(pid, follow),
         Commands::Debug { command } => match command {
             DebugCommands::Vdir { file, directory } => cmd_debug_vdir(file, directory),
         },
@@ -659,7 +1154,7 @@ async fn cmd_init(directory: &Path) -> Result<()> {
     }
 
     let _ = vrift_manifest::LmdbManifest::open(&manifest_path)
-        .map_err(|e| anyhow::anyhow!("Failed to initialize manifest LMDB: {}", e))?;
+        .context("Failed to initialize manifest LMDB")?;
 
     // Output success (to stderr so it doesn't interfere with eval)
     eprintln!();
@@ -810,6 +1305,13 @@ fn cmd_config(command: ConfigCommands) -> Result<()> {
                         }
                     }
 
+                    for name in config.profile.keys() {
+                        if let Err(e) = config.clone().apply_profile(name) {
+                            println!("✗ {}", e);
+                            anyhow::bail!("Config validation failed");
+                        }
+                    }
+
                     println!();
                     println!("Summary:");
                     println!("  - Config version: {}", config.config_version);
@@ -822,6 +1324,10 @@ fn cmd_config(command: ConfigCommands) -> Result<()> {
                         config.security.exclude_patterns.len()
                     );
                     println!("  - Default mode: {}", config.storage.default_mode);
+                    if !config.profile.is_empty() {
+                        let names: Vec<&str> = config.profile.keys().map(String::as_str).collect();
+                        println!("  - Profiles: {}", names.join(", "));
+                    }
                     Ok(())
                 }
                 Err(e) => {
@@ -836,7 +1342,7 @@ fn cmd_config(command: ConfigCommands) -> Result<()> {
 }
 
 /// Manifest management commands (RFC-0039 Live Ingest)
-fn cmd_manifest(command: ManifestCommands) -> Result<()> {
+async fn cmd_manifest(cas_root: &Path, command: ManifestCommands) -> Result<()> {
     match command {
         ManifestCommands::Query { path, directory } => {
             let dir = directory.unwrap_or_else(|| std::env::current_dir().unwrap());
@@ -851,7 +1357,7 @@ fn cmd_manifest(command: ManifestCommands) -> Result<()> {
                 );
             }
 
-            let manifest = LmdbManifest::open(&manifest_path)?;
+            let manifest = LmdbManifest::open_readonly(&manifest_path)?;
 
             // Normalize path to manifest key format
             let query_path = if path.starts_with('/') {
@@ -865,7 +1371,20 @@ fn cmd_manifest(command: ManifestCommands) -> Result<()> {
                     println!("Found: {}", query_path);
                     println!("  Size:  {} bytes", entry.vnode.size);
                     println!("  Mode:  {:o}", entry.vnode.mode);
-                    println!("  MTime: {}", format_timestamp(entry.vnode.mtime));
+                    let (sec, nsec) = vrift_ipc::mtime::to_parts(entry.vnode.mtime);
+                    println!(
+                        "  MTime: {} ({}.{:09})",
+                        format_timestamp(u64::try_from(sec).unwrap_or(0)),
+                        sec,
+                        nsec
+                    );
+                    if entry.ingested_at != 0 {
+                        let (sec, _) = vrift_ipc::mtime::to_parts(entry.ingested_at);
+                        println!(
+                            "  Ingested: {}",
+                            format_timestamp(u64::try_from(sec).unwrap_or(0))
+                        );
+                    }
                     let hash_preview: String = entry.vnode.content_hash[..8]
                         .iter()
                         .map(|b| format!("{:02x}", b))
@@ -892,7 +1411,7 @@ fn cmd_manifest(command: ManifestCommands) -> Result<()> {
                 );
             }
 
-            let manifest = LmdbManifest::open(&manifest_path)?;
+            let manifest = LmdbManifest::open_readonly(&manifest_path)?;
             let entries = manifest.iter()?;
             let limit = limit.unwrap_or(entries.len());
 
@@ -919,7 +1438,7 @@ fn cmd_manifest(command: ManifestCommands) -> Result<()> {
                 );
             }
 
-            let manifest = LmdbManifest::open(&manifest_path)?;
+            let manifest = LmdbManifest::open_readonly(&manifest_path)?;
             let entries = manifest.iter()?;
 
             let total_size: u64 = entries.iter().map(|(_, e)| e.vnode.size).sum();
@@ -934,9 +1453,307 @@ fn cmd_manifest(command: ManifestCommands) -> Result<()> {
             println!("  Total Size: {}", format_bytes(total_size));
             Ok(())
         }
+        ManifestCommands::Dump {
+            directory,
+            sqlite,
+            inputs,
+        } => {
+            let dir = directory.unwrap_or_else(|| std::env::current_dir().unwrap());
+            let project_id = vrift_config::path::compute_project_id(&dir);
+            let manifest_path = vrift_config::path::get_manifest_db_path(&project_id)
+                .ok_or_else(|| anyhow::anyhow!("Could not determine manifest path"))?;
+
+            if !manifest_path.exists() {
+                anyhow::bail!(
+                    "Manifest not found at {}. Run 'vrift init' first.",
+                    manifest_path.display()
+                );
+            }
+
+            let manifest = LmdbManifest::open_readonly(&manifest_path)?;
+            let entries = manifest.iter()?;
+            let access = dump::access_counts(&inputs)?;
+            let cas = if cas_root.exists() {
+                Some(CasStore::new(cas_root)?)
+            } else {
+                None
+            };
+            let counts = dump::dump_sqlite(&entries, cas.as_ref(), &access, &sqlite)?;
+
+            println!("Wrote {}:", sqlite.display());
+            println!("  entries:       {}", format_number(counts.entries as u64));
+            println!(
+                "  directories:   {}",
+                format_number(counts.directories as u64)
+            );
+            println!("  blobs:         {}", format_number(counts.blobs as u64));
+            println!("  access_counts: {}", format_number(counts.accessed as u64));
+            Ok(())
+        }
+        ManifestCommands::Compact { directory } => {
+            let dir = directory.unwrap_or_else(|| std::env::current_dir().unwrap());
+            let project_id = vrift_config::path::compute_project_id(&dir);
+            let manifest_path = vrift_config::path::get_manifest_db_path(&project_id)
+                .ok_or_else(|| anyhow::anyhow!("Could not determine manifest path"))?;
+
+            if !manifest_path.exists() {
+                anyhow::bail!(
+                    "Manifest not found at {}. Run 'vrift init' first.",
+                    manifest_path.display()
+                );
+            }
+            // vDird keeps the manifest open while it serves the workspace
+            if daemon::workspace_metrics(&dir).await.is_some() {
+                anyhow::bail!(
+                    "vDird is serving {}; stop vriftd before compacting its manifest",
+                    dir.display()
+                );
+            }
+
+            let stats = vrift_manifest::store::compact(&manifest_path)?;
+            println!(
+                "Compacted {} ({}): {} -> {}",
+                manifest_path.display(),
+                stats.backend.as_str(),
+                format_bytes(stats.bytes_before),
+                format_bytes(stats.bytes_after)
+            );
+            Ok(())
+        }
     }
 }
 
+/// Replay the manifest journal and check every entry's blob
+async fn cmd_fsck(cas_root: &Path, directory: &Path) -> Result<()> {
+    let project_id = vrift_config::path::compute_project_id(directory);
+    let manifest_path = vrift_config::path::get_manifest_db_path(&project_id)
+        .ok_or_else(|| anyhow::anyhow!("Could not determine manifest path"))?;
+
+    if !manifest_path.exists() {
+        anyhow::bail!(
+            "Manifest not found at {}. Run 'vrift init' first.",
+            manifest_path.display()
+        );
+    }
+    // vDird owns the journal and has changes of its own in flight
+    if daemon::workspace_metrics(directory).await.is_some() {
+        anyhow::bail!(
+            "vDird is serving {}; stop vriftd before checking its manifest",
+            directory.display()
+        );
+    }
+    if !cas_root.exists() {
+        anyhow::bail!("CAS not found at {}", cas_root.display());
+    }
+
+    let cas = CasStore::new(cas_root)?;
+    let manifest = LmdbManifest::open(&manifest_path)?;
+    let report = manifest.repair(&cas)?;
+
+    println!("Checked {}:", manifest_path.display());
+    println!(
+        "  Replayed from journal: {}",
+        format_number(report.replayed as u64)
+    );
+    println!(
+        "  Blobs checked:         {}",
+        format_number(report.checked as u64)
+    );
+    println!(
+        "  Missing blobs:         {}",
+        format_number(report.missing_blobs.len() as u64)
+    );
+    for path in &report.missing_blobs {
+        println!("    {}", path);
+    }
+    if !report.missing_blobs.is_empty() {
+        println!();
+        println!(
+            "Marked pending re-ingest. Run 'vrift ingest {}' to restore them.",
+            directory.display()
+        );
+    }
+    Ok(())
+}
+
+/// List, create or restore manifest snapshots
+async fn cmd_snapshot(cas_root: &Path, command: SnapshotCommands) -> Result<()> {
+    let directory = match &command {
+        SnapshotCommands::List { directory }
+        | SnapshotCommands::Create { directory, .. }
+        | SnapshotCommands::Restore { directory, .. } => directory.clone(),
+    };
+    let dir = directory.unwrap_or_else(|| std::env::current_dir().unwrap());
+    let project_id = vrift_config::path::compute_project_id(&dir);
+    let manifest_path = vrift_config::path::get_manifest_db_path(&project_id)
+        .ok_or_else(|| anyhow::anyhow!("Could not determine manifest path"))?;
+
+    if !manifest_path.exists() {
+        anyhow::bail!(
+            "Manifest not found at {}. Run 'vrift init' first.",
+            manifest_path.display()
+        );
+    }
+
+    match command {
+        SnapshotCommands::List { .. } => {
+            let snapshots = vrift_manifest::snapshot::list(&manifest_path)?;
+            if snapshots.is_empty() {
+                println!("No snapshots of {}", manifest_path.display());
+                return Ok(());
+            }
+            println!("{:<24} {:>10}  {:<16}  CREATED", "NAME", "ENTRIES", "TREE");
+            for snapshot in snapshots {
+                println!(
+                    "{:<24} {:>10}  {:<16}  {}",
+                    snapshot.name,
+                    format_number(snapshot.entries as u64),
+                    &CasStore::hash_to_hex(&snapshot.tree)[..16],
+                    format_timestamp(snapshot.created)
+                );
+            }
+            Ok(())
+        }
+        SnapshotCommands::Create { name, .. } => {
+            let cas = CasStore::new(cas_root)?;
+            // While vDird serves the workspace it owns the manifest; its
+            // changes since the last commit are not in the snapshot
+            let serving = daemon::workspace_metrics(&dir).await.is_some();
+            let manifest = if serving {
+                LmdbManifest::open_readonly(&manifest_path)?
+            } else {
+                LmdbManifest::open(&manifest_path)?
+            };
+            let snapshot = manifest.snapshot_create(&name, &cas)?;
+            println!(
+                "Created snapshot {} of {}: {} entries (tree {})",
+                snapshot.name,
+                manifest_path.display(),
+                format_number(snapshot.entries as u64),
+                &CasStore::hash_to_hex(&snapshot.tree)[..16]
+            );
+            Ok(())
+        }
+        SnapshotCommands::Restore { name, .. } => {
+            // vDird holds the manifest and the VDir of the workspace
+            if daemon::workspace_metrics(&dir).await.is_some() {
+                anyhow::bail!(
+                    "vDird is serving {}; stop vriftd before restoring a snapshot",
+                    dir.display()
+                );
+            }
+            if !cas_root.exists() {
+                anyhow::bail!("CAS not found at {}", cas_root.display());
+            }
+
+            let cas = CasStore::new(cas_root)?;
+            let manifest = LmdbManifest::open(&manifest_path)?;
+            let report = manifest.snapshot_restore(&name, &cas)?;
+
+            // The VDir still holds entries from after the snapshot; without
+            // it lookups fall back to the manifest until vDird starts again
+            if let Some(vdir_path) = vrift_config::path::get_vdir_mmap_path(&project_id) {
+                match std::fs::remove_file(&vdir_path) {
+                    Ok(()) => {}
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                    Err(e) => {
+                        return Err(e).with_context(|| {
+                            format!("Failed to remove VDir {}", vdir_path.display())
+                        })
+                    }
+                }
+            }
+
+            println!("Restored snapshot {} to {}:", name, manifest_path.display());
+            println!("  Entries:       {}", format_number(report.entries as u64));
+            println!("  Removed:       {}", format_number(report.removed as u64));
+            println!(
+                "  Missing blobs: {}",
+                format_number(report.missing_blobs.len() as u64)
+            );
+            for path in &report.missing_blobs {
+                println!("    {}", path);
+            }
+            if !report.missing_blobs.is_empty() {
+                println!();
+                println!(
+                    "Marked pending re-ingest. Run 'vrift ingest {}' to restore them.",
+                    dir.display()
+                );
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Print the changes between two manifest snapshots
+fn cmd_diff(cas_root: &Path, directory: &Path, from: &str, to: &str, json: bool) -> Result<()> {
+    let project_id = vrift_config::path::compute_project_id(directory);
+    let manifest_path = vrift_config::path::get_manifest_db_path(&project_id)
+        .ok_or_else(|| anyhow::anyhow!("Could not determine manifest path"))?;
+
+    if !manifest_path.exists() {
+        anyhow::bail!(
+            "Manifest not found at {}. Run 'vrift init' first.",
+            manifest_path.display()
+        );
+    }
+
+    let cas = CasStore::new(cas_root)?;
+    let old = vrift_manifest::snapshot::find(&manifest_path, from)?;
+    let new = vrift_manifest::snapshot::find(&manifest_path, to)?;
+    let changes = vrift_manifest::snapshot::diff(&cas, &old, &new)?;
+    let count = |kind| changes.iter().filter(|d| d.kind == kind).count();
+    let (added, removed, modified) = (
+        count(DiffKind::Added),
+        count(DiffKind::Removed),
+        count(DiffKind::Modified),
+    );
+
+    if json {
+        let hex = |hash: Option<vrift_cas::Blake3Hash>| hash.map(|h| CasStore::hash_to_hex(&h));
+        let report = serde_json::json!({
+            "from": from,
+            "to": to,
+            "added": added,
+            "removed": removed,
+            "modified": modified,
+            "changes": changes
+                .iter()
+                .map(|d| serde_json::json!({
+                    "path": d.path,
+                    "change": d.kind.as_str(),
+                    "old_hash": hex(d.old_hash),
+                    "new_hash": hex(d.new_hash),
+                }))
+                .collect::<Vec<_>>(),
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    for change in &changes {
+        let mark = match change.kind {
+            DiffKind::Added => 'A',
+            DiffKind::Removed => 'D',
+            DiffKind::Modified => 'M',
+        };
+        println!("{} {}", mark, change.path);
+    }
+    if !changes.is_empty() {
+        println!();
+    }
+    println!(
+        "{} -> {}: {} added, {} removed, {} modified",
+        from,
+        to,
+        format_number(added as u64),
+        format_number(removed as u64),
+        format_number(modified as u64)
+    );
+    Ok(())
+}
+
 /// Synchronize project files with manifest (compensation scan)
 async fn cmd_sync(directory: &Path) -> Result<()> {
     use walkdir::WalkDir;
@@ -972,7 +1789,7 @@ async fn cmd_sync(directory: &Path) -> Result<()> {
                 continue;
             }
 
-            let manifest_key = format!("/{}", rel.display());
+            let manifest_key = format!("/{}", vrift_cas::path_key::from_path(rel));
 
             if !existing_paths.contains(&manifest_key) {
                 if path.is_dir() {
@@ -1090,6 +1907,11 @@ fn cmd_run(
     }
 
     // Standard LD_PRELOAD execution
+    // The loader would drop the preload without a word; refuse up front
+    if let Some(blocked) = preload::check(&command[0]) {
+        anyhow::bail!("{}", blocked);
+    }
+
     // Find the shim library
     let shim_path = find_shim_library()?;
 
@@ -1293,7 +2115,7 @@ fn cmd_status(
         if manifest_path.exists() {
             let (file_count, dir_count, total_size) =
                 if manifest_path.to_string_lossy().ends_with(".lmdb") {
-                    let m = LmdbManifest::open(manifest_path)?;
+                    let m = LmdbManifest::open_readonly(manifest_path)?;
                     let s = m.stats()?;
                     (s.file_count, s.dir_count, s.total_size)
                 } else {
@@ -1333,6 +2155,138 @@ fn cmd_status(
     Ok(())
 }
 
+/// Map a user-supplied path to a manifest key under `project_root`.
+///
+/// Resolution is lexical (the path need not exist on disk, e.g. in phantom
+/// mode); relative paths are taken from the current directory.
+fn path_to_manifest_key(project_root: &Path, path: &Path) -> Result<String> {
+    use std::path::Component;
+
+    let cwd = std::env::current_dir().context("Failed to get current directory")?;
+    let joined = normalize_or_original(cwd).join(path);
+    let mut abs = PathBuf::new();
+    for component in joined.components() {
+        match component {
+            Component::ParentDir => {
+                abs.pop();
+            }
+            Component::CurDir => {}
+            other => abs.push(other),
+        }
+    }
+    let abs = normalize_or_original(&abs);
+
+    let rel = abs.strip_prefix(project_root).map_err(|_| {
+        anyhow::anyhow!(
+            "{} is outside the project ({})",
+            path.display(),
+            project_root.display()
+        )
+    })?;
+    Ok(format!("/{}", vrift_cas::path_key::from_path(rel)))
+}
+
+/// Print a stable digest of the manifest entries covering `paths`
+fn cmd_hash(
+    project_dir: &Path,
+    paths: &[PathBuf],
+    manifest: Option<&Path>,
+    verbose: bool,
+) -> Result<()> {
+    let project_root = normalize_for_ipc(project_dir).context("resolve project path")?;
+    let keys: Vec<String> = if paths.is_empty() {
+        vec!["/".to_string()]
+    } else {
+        paths
+            .iter()
+            .map(|p| path_to_manifest_key(&project_root, p))
+            .collect::<Result<_>>()?
+    };
+    let roots: Vec<&str> = keys.iter().map(String::as_str).collect();
+
+    let digest = match manifest {
+        Some(file) => Manifest::load(file)
+            .with_context(|| format!("Failed to load manifest: {}", file.display()))?
+            .digest(&roots),
+        None => {
+            let project_id = vrift_config::path::compute_project_id(&project_root);
+            let manifest_path = vrift_config::path::get_manifest_db_path(&project_id)
+                .ok_or_else(|| anyhow::anyhow!("Could not determine manifest path"))?;
+            if !manifest_path.exists() {
+                anyhow::bail!(
+                    "Manifest not found at {}. Run 'vrift init' first.",
+                    manifest_path.display()
+                );
+            }
+            let lmdb = LmdbManifest::open_readonly(&manifest_path)?;
+            let stale = lmdb
+                .iter()?
+                .iter()
+                .filter(|(p, e)| {
+                    e.stale && roots.iter().any(|r| vrift_manifest::digest::covers(r, p))
+                })
+                .count();
+            if stale > 0 {
+                eprintln!(
+                    "Warning: {} covered entries are pending re-ingest; digest uses their last recorded hash",
+                    stale
+                );
+            }
+            lmdb.digest(&roots)?
+        }
+    };
+
+    if !digest.missing.is_empty() {
+        anyhow::bail!("Not in manifest: {}", digest.missing.join(", "));
+    }
+
+    if verbose {
+        println!("{}  ({} entries)", digest.to_hex(), digest.entries);
+    } else {
+        println!("{}", digest.to_hex());
+    }
+    Ok(())
+}
+
+/// List processes tracked by the daemon, grouped by workspace session
+async fn cmd_ps(project_dir: &Path, all: bool) -> Result<()> {
+    let project_root = vrift_config::path::normalize_or_original(project_dir);
+    let sessions: Vec<_> = daemon::list_sessions()
+        .await?
+        .into_iter()
+        .filter(|s| all || Path::new(&s.project_root) == project_root)
+        .collect();
+
+    if sessions.is_empty() {
+        println!("No active sessions.");
+        return Ok(());
+    }
+
+    for session in &sessions {
+        println!(
+            "Session {} — {} (root pid {}, started {}, {} write-backs)",
+            session.session_id,
+            session.project_root,
+            session.root_pid,
+            format_timestamp(session.started_at),
+            format_number(session.writebacks)
+        );
+        println!(
+            "  {:>7} {:>7} {:>6} {:>10}  COMMAND",
+            "PID", "PPID", "LOCKS", "WRITEBACKS"
+        );
+        for proc in &session.processes {
+            println!(
+                "  {:>7} {:>7} {:>6} {:>10}  {}",
+                proc.pid, proc.ppid, proc.locks_held, proc.writebacks, proc.command
+            );
+        }
+        println!();
+    }
+
+    Ok(())
+}
+
 /// Format Unix timestamp as human-readable date
 fn format_timestamp(epoch: u64) -> String {
     use std::time::{Duration, UNIX_EPOCH};
@@ -1355,6 +2309,82 @@ fn format_timestamp(epoch: u64) -> String {
     }
 }
 
+/// Paths of `--paths-from`: one per line, skipping blank lines and `#`
+/// comments; `-` reads stdin
+fn read_path_list(file: &Path) -> Result<Vec<String>> {
+    let text = if file == Path::new("-") {
+        std::io::read_to_string(std::io::stdin()).context("Failed to read paths from stdin")?
+    } else {
+        fs::read_to_string(file)
+            .with_context(|| format!("Failed to read paths from {}", file.display()))?
+    };
+    let paths: Vec<String> = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect();
+    if paths.is_empty() {
+        anyhow::bail!("No paths listed in {}", file.display());
+    }
+    Ok(paths)
+}
+
+/// What an ingest did: files per tier, dedup hits, what was kept out and
+/// the largest blobs it stored
+fn print_ingest_summary(result: &daemon::IngestResult) {
+    let elapsed_secs = result.duration_ms as f64 / 1000.0;
+    let files_per_sec = if elapsed_secs > 0.0 {
+        result.files as f64 / elapsed_secs
+    } else {
+        0.0
+    };
+
+    println!();
+    println!("╔════════════════════════════════════════╗");
+    println!("║  ✅ VRift Complete                     ║");
+    println!("╚════════════════════════════════════════╝");
+    println!();
+    println!(
+        "   📁 {} files → {} blobs ({} new)",
+        format_number(result.files),
+        format_number(result.blobs),
+        format_bytes(result.new_bytes)
+    );
+    println!(
+        "   📊 {:.1}% dedup ({} of {} hashed already stored)",
+        result.dedup_rate(),
+        format_number(result.deduped_files),
+        format_number(result.blobs + result.deduped_files)
+    );
+    println!(
+        "   🗂️  tier1: {}, tier2: {}, unchanged: {}",
+        format_number(result.tier1_files),
+        format_number(result.tier2_files),
+        format_number(result.unchanged_files)
+    );
+    if result.quarantined_files > 0 {
+        println!(
+            "   🛡️  {} skipped by the security scanner",
+            format_number(result.quarantined_files)
+        );
+    }
+    if result.skipped_files > 0 {
+        println!(
+            "   ⏭️  {} left on disk by [ingest.skip] (size or binary)",
+            format_number(result.skipped_files)
+        );
+    }
+    if !result.largest_new.is_empty() {
+        println!("   🐘 Largest new blobs:");
+        for blob in &result.largest_new {
+            println!("      {:>10}  {}", format_bytes(blob.size), blob.path);
+        }
+    }
+    println!("   ⚡ {:.0} files/sec", files_per_sec);
+    println!("   📄 Manifest: {}", result.manifest_path);
+}
+
 /// Format bytes in human-readable form
 fn format_bytes(bytes: u64) -> String {
     const KB: u64 = 1024;
@@ -1395,7 +2425,18 @@ async fn cmd_watch(_cas_root: &Path, directory: &Path, output: &Path) -> Result<
 
     // Initial ingest via daemon
     println!("\n[Initial Scan]");
-    daemon::ingest_via_daemon(directory, output, None, false, false, None, None, false).await?;
+    daemon::ingest_via_daemon(
+        directory,
+        output,
+        None,
+        false,
+        false,
+        None,
+        None,
+        false,
+        Default::default(),
+    )
+    .await?;
 
     // Create a channel to receive the events.
     let (tx, rx) = channel();
@@ -1428,7 +2469,15 @@ async fn cmd_watch(_cas_root: &Path, directory: &Path, output: &Path) -> Result<
                         if last_ingest.elapsed() > debounce_duration {
                             println!("\n[Change Detected] Re-ingesting...");
                             if let Err(e) = daemon::ingest_via_daemon(
-                                directory, output, None, false, false, None, None, false,
+                                directory,
+                                output,
+                                None,
+                                false,
+                                false,
+                                None,
+                                None,
+                                false,
+                                Default::default(),
                             )
                             .await
                             {
@@ -1450,197 +2499,6 @@ async fn cmd_watch(_cas_root: &Path, directory: &Path, output: &Path) -> Result<
     Ok(())
 }
 
-/// Install vriftd as a background service
-fn cmd_service_install() -> Result<()> {
-    let current_exe = std::env::current_exe()?;
-    let bin_dir = current_exe.parent().context("Failed to get bin dir")?;
-    let vriftd_bin = bin_dir.join("vriftd");
-
-    if !vriftd_bin.exists() {
-        anyhow::bail!("vriftd binary not found in {}", bin_dir.display());
-    }
-
-    #[cfg(target_os = "macos")]
-    {
-        let home = dirs::home_dir().context("Could not find home directory")?;
-        let agents_dir = home.join("Library/LaunchAgents");
-        std::fs::create_dir_all(&agents_dir)?;
-
-        let plist_path = agents_dir.join("sh.velo.vriftd.plist");
-        let plist_content = format!(
-            r#"<?xml version="1.0" encoding="UTF-8"?>
-<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
-<plist version="1.0">
-<dict>
-    <key>Label</key>
-    <string>sh.velo.vriftd</string>
-    <key>ProgramArguments</key>
-    <array>
-        <string>{}</string>
-        <string>start</string>
-    </array>
-    <key>RunAtLoad</key>
-    <true/>
-    <key>KeepAlive</key>
-    <true/>
-    <key>StandardErrorPath</key>
-    <string>/tmp/vriftd.err.log</string>
-    <key>StandardOutPath</key>
-    <string>/tmp/vriftd.out.log</string>
-</dict>
-</plist>"#,
-            vriftd_bin.display()
-        );
-
-        std::fs::write(&plist_path, plist_content)?;
-        println!("Created launchd agent: {}", plist_path.display());
-
-        // Load the agent
-        let status = std::process::Command::new("launchctl")
-            .arg("load")
-            .arg(&plist_path)
-            .status()?;
-
-        if status.success() {
-            println!("✅ vriftd service installed and started.");
-        } else {
-            println!("⚠️  Failed to load launchd agent. It might already be loaded.");
-        }
-    }
-
-    #[cfg(target_os = "linux")]
-    {
-        let home = dirs::home_dir().context("Could not find home directory")?;
-        let systemd_dir = home.join(".config/systemd/user");
-        std::fs::create_dir_all(&systemd_dir)?;
-
-        let service_path = systemd_dir.join("vriftd.service");
-        let service_content = format!(
-            r#"[Unit]
-Description=Velo Rift Daemon
-After=network.target
-
-[Service]
-ExecStart={} start
-Restart=always
-RestartSec=5
-StandardOutput=append:/tmp/vriftd.log
-StandardError=append:/tmp/vriftd.log
-
-[Install]
-WantedBy=default.target"#,
-            vriftd_bin.display()
-        );
-
-        std::fs::write(&service_path, service_content)?;
-        println!("Created systemd service: {}", service_path.display());
-
-        // Reload and enable
-        std::process::Command::new("systemctl")
-            .arg("--user")
-            .arg("daemon-reload")
-            .status()?;
-        std::process::Command::new("systemctl")
-            .arg("--user")
-            .arg("enable")
-            .arg("vriftd")
-            .status()?;
-        let status = std::process::Command::new("systemctl")
-            .arg("--user")
-            .arg("start")
-            .arg("vriftd")
-            .status()?;
-
-        if status.success() {
-            println!("✅ vriftd service installed and started.");
-        } else {
-            println!("❌ Failed to start systemd service.");
-        }
-    }
-
-    Ok(())
-}
-
-/// Uninstall vriftd background service
-fn cmd_service_uninstall() -> Result<()> {
-    #[cfg(target_os = "macos")]
-    {
-        let home = dirs::home_dir().context("Could not find home directory")?;
-        let plist_path = home.join("Library/LaunchAgents/sh.velo.vriftd.plist");
-
-        if plist_path.exists() {
-            std::process::Command::new("launchctl")
-                .arg("unload")
-                .arg(&plist_path)
-                .status()?;
-            std::fs::remove_file(&plist_path)?;
-            println!("✅ vriftd service uninstalled.");
-        } else {
-            println!("ℹ️  vriftd service not found.");
-        }
-    }
-
-    #[cfg(target_os = "linux")]
-    {
-        let home = dirs::home_dir().context("Could not find home directory")?;
-        let service_path = home.join(".config/systemd/user/vriftd.service");
-
-        if service_path.exists() {
-            std::process::Command::new("systemctl")
-                .arg("--user")
-                .arg("stop")
-                .arg("vriftd")
-                .status()?;
-            std::process::Command::new("systemctl")
-                .arg("--user")
-                .arg("disable")
-                .arg("vriftd")
-                .status()?;
-            std::fs::remove_file(&service_path)?;
-            println!("✅ vriftd service uninstalled.");
-        } else {
-            println!("ℹ️  vriftd service not found.");
-        }
-    }
-
-    Ok(())
-}
-
-/// Restart vriftd background service
-fn cmd_service_restart() -> Result<()> {
-    #[cfg(target_os = "macos")]
-    {
-        let home = dirs::home_dir().context("Could not find home directory")?;
-        let plist_path = home.join("Library/LaunchAgents/sh.velo.vriftd.plist");
-
-        if plist_path.exists() {
-            std::process::Command::new("launchctl")
-                .arg("unload")
-                .arg(&plist_path)
-                .status()?;
-            std::process::Command::new("launchctl")
-                .arg("load")
-                .arg(&plist_path)
-                .status()?;
-            println!("✅ vriftd service restarted.");
-        } else {
-            println!("⚠️  vriftd service not found. Use 'install' first.");
-        }
-    }
-
-    #[cfg(target_os = "linux")]
-    {
-        std::process::Command::new("systemctl")
-            .arg("--user")
-            .arg("restart")
-            .arg("vriftd")
-            .status()?;
-        println!("✅ vriftd service restarted.");
-    }
-
-    Ok(())
-}
-
 /// Debug VDir health
 fn cmd_debug_vdir(file: Option<PathBuf>, directory: Option<PathBuf>) -> Result<()> {
     use console::style;
@@ -0,0 +1,329 @@
+//! # vrift pack
+//!
+//! Profile-guided hot packfiles. `trace` runs a command under the inception
+//! layer with `VRIFT_PACK_TRACE` set, so each of its processes reports the
+//! blobs it opened to vriftd, then asks vriftd to pack those blobs in
+//! first-access order. Later runs in the workspace read them from the one
+//! packfile instead of from many small CAS files.
+//!
+//! `build` repacks from the saved profile, or from a profile merged with
+//! `vrift profile`; `prefetch` reads the profiled blobs into the page cache;
+//! `status` shows what the current packfile holds.
+
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use clap::{Args, Subcommand};
+use vrift_config::path::normalize_for_ipc;
+use vrift_ipc::{JobInfo, JobState, VeloRequest, VeloResponse};
+use vrift_pack::{PackReader, ProfileAggregate};
+
+/// How often a build is polled for progress
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Processes report their trace as they exit; give vriftd a moment to take
+/// in the last reports before the build request
+const TRACE_SETTLE: Duration = Duration::from_millis(250);
+
+#[derive(Subcommand, Debug)]
+pub enum PackCommand {
+    /// Run a command, record the blobs it opens and pack them
+    ///
+    /// Usage: vrift pack trace [--no-build] -- <cmd> [args...]
+    Trace {
+        #[command(flatten)]
+        project: ProjectArg,
+
+        /// Only record the profile; pack later with `vrift pack build`
+        #[arg(long)]
+        no_build: bool,
+
+        /// Command to execute
+        #[arg(trailing_var_arg = true, required = true)]
+        command: Vec<String>,
+    },
+    /// Build the packfile from the saved (or a given) access profile
+    Build {
+        #[command(flatten)]
+        project: ProjectArg,
+
+        /// Access profile to pack by (binary or exported), replacing the saved one
+        #[arg(long)]
+        profile: Option<PathBuf>,
+
+        /// List the blobs the build would pack, changing nothing (a running
+        /// trace goes on)
+        #[arg(long, conflicts_with = "profile")]
+        dry_run: bool,
+
+        /// Print the dry-run plan as JSON
+        #[arg(long, requires = "dry_run")]
+        json: bool,
+    },
+    /// Read the profiled blobs into the page cache ahead of a run
+    Prefetch {
+        #[command(flatten)]
+        project: ProjectArg,
+    },
+    /// Show the workspace's packfile
+    Status {
+        #[command(flatten)]
+        project: ProjectArg,
+    },
+}
+
+#[derive(Args, Debug)]
+pub struct ProjectArg {
+    /// Project directory (default: current directory)
+    #[arg(short = 'C', long)]
+    directory: Option<PathBuf>,
+}
+
+impl ProjectArg {
+    fn root(&self) -> Result<PathBuf> {
+        let dir = match &self.directory {
+            Some(dir) => dir.clone(),
+            None => std::env::current_dir().context("Failed to get current directory")?,
+        };
+        normalize_for_ipc(&dir).context("resolve project path")
+    }
+}
+
+pub async fn run(command: PackCommand) -> Result<()> {
+    match command {
+        PackCommand::Trace {
+            project,
+            no_build,
+            command,
+        } => {
+            let root = project.root()?;
+            let exit_code = trace(&root, &command).await?;
+            if no_build {
+                println!("Trace recorded; pack it with `vrift pack build`");
+            } else {
+                tokio::time::sleep(TRACE_SETTLE).await;
+                build(&root).await?;
+            }
+            if exit_code != 0 {
+                std::process::exit(exit_code);
+            }
+            Ok(())
+        }
+        PackCommand::Build {
+            project,
+            profile,
+            dry_run,
+            json,
+        } => {
+            let root = project.root()?;
+            if dry_run {
+                return plan_build(&root, json).await;
+            }
+            if let Some(profile) = profile {
+                install_profile(&root, &profile)?;
+            }
+            build(&root).await
+        }
+        PackCommand::Prefetch { project } => prefetch(&project.root()?).await,
+        PackCommand::Status { project } => status(&project.root()?),
+    }
+}
+
+/// Run `command` with pack tracing on; returns its exit code
+async fn trace(project_root: &Path, command: &[String]) -> Result<i32> {
+    let inception_path = crate::inception::find_inception_library(project_root)?;
+    // The traces go to vriftd, so it has to be up before the command starts
+    let conn = crate::daemon::connect_to_daemon(project_root)
+        .await
+        .context("Daemon not running or unreachable")?;
+    let cfg = vrift_config::Config::load_for_project(project_root).unwrap_or_else(|e| {
+        eprintln!("Warning: Config load failed: {}. Using defaults.", e);
+        vrift_config::Config::default()
+    });
+
+    let mut cmd = std::process::Command::new(&command[0]);
+    cmd.args(&command[1..]);
+    for (key, value) in cfg.shim_env() {
+        cmd.env(key, value);
+    }
+    if !conn.vdird_socket.is_empty() {
+        cmd.env("VRIFT_VDIRD_SOCKET", &conn.vdird_socket);
+    }
+    if !conn.vdir_mmap_path.is_empty() {
+        cmd.env("VRIFT_VDIR_MMAP", &conn.vdir_mmap_path);
+    }
+    cmd.env("VRIFT_PACK_TRACE", "1");
+
+    #[cfg(target_os = "macos")]
+    {
+        cmd.env("DYLD_INSERT_LIBRARIES", &inception_path)
+            .env("DYLD_FORCE_FLAT_NAMESPACE", "1");
+    }
+    #[cfg(target_os = "linux")]
+    {
+        cmd.env("LD_PRELOAD", &inception_path);
+    }
+
+    let status = cmd
+        .status()
+        .with_context(|| format!("Failed to execute: {}", command[0]))?;
+    Ok(status.code().unwrap_or(1))
+}
+
+/// Replace the saved profile of `project_root` with `source`
+fn install_profile(project_root: &Path, source: &Path) -> Result<()> {
+    let aggregate = ProfileAggregate::load(source)
+        .with_context(|| format!("Cannot read access profile {}", source.display()))?;
+    let project_id = vrift_config::path::compute_project_id(project_root);
+    let dest = vrift_config::path::get_access_profile_path(&project_id)
+        .context("No home directory for access profiles")?;
+    if let Some(parent) = dest.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    aggregate
+        .to_profile()
+        .save(&dest)
+        .with_context(|| format!("Cannot write {}", dest.display()))?;
+    Ok(())
+}
+
+async fn build(project_root: &Path) -> Result<()> {
+    let conn = crate::daemon::connect_to_daemon(project_root)
+        .await
+        .context("Daemon not running or unreachable")?;
+    let mut stream = conn.stream;
+    crate::daemon::send_request(&mut stream, VeloRequest::PackBuild { dry_run: false }).await?;
+    let mut job = match crate::daemon::read_response(&mut stream).await? {
+        VeloResponse::JobAck { job } => job,
+        VeloResponse::Error(e) => anyhow::bail!("Pack build failed: {}", e.message),
+        resp => anyhow::bail!("Unexpected response from daemon: {:?}", resp),
+    };
+    while !job.state.is_finished() {
+        print_progress(&job);
+        tokio::time::sleep(POLL_INTERVAL).await;
+        job =
+            crate::daemon::job_request(&mut stream, VeloRequest::JobStatus { job_id: job.job_id })
+                .await?;
+    }
+    println!();
+    match job.state {
+        JobState::Failed => anyhow::bail!(
+            "Pack build failed: {}",
+            job.error.unwrap_or_else(|| "unknown error".to_string())
+        ),
+        JobState::Cancelled => println!("Pack build cancelled; the previous packfile is kept"),
+        _ => println!(
+            "Packed {} of {} profiled blobs ({} bytes)",
+            job.affected, job.processed, job.bytes
+        ),
+    }
+    Ok(())
+}
+
+async fn prefetch(project_root: &Path) -> Result<()> {
+    let conn = crate::daemon::connect_to_daemon(project_root)
+        .await
+        .context("Daemon not running or unreachable")?;
+    let mut stream = conn.stream;
+    let mut job = crate::daemon::job_request(&mut stream, VeloRequest::PackPrefetch).await?;
+    while !job.state.is_finished() {
+        print!(
+            "\rPrefetching: {}/{} blobs   ",
+            job.processed, job.total_estimate
+        );
+        let _ = io::stdout().flush();
+        tokio::time::sleep(POLL_INTERVAL).await;
+        job =
+            crate::daemon::job_request(&mut stream, VeloRequest::JobStatus { job_id: job.job_id })
+                .await?;
+    }
+    println!();
+    match job.state {
+        JobState::Failed => anyhow::bail!(
+            "Prefetch failed: {}",
+            job.error.unwrap_or_else(|| "unknown error".to_string())
+        ),
+        JobState::Cancelled => println!("Prefetch cancelled"),
+        _ => println!(
+            "Prefetched {} of {} profiled blobs ({} bytes)",
+            job.affected, job.processed, job.bytes
+        ),
+    }
+    Ok(())
+}
+
+async fn plan_build(project_root: &Path, json: bool) -> Result<()> {
+    let conn = crate::daemon::connect_to_daemon(project_root)
+        .await
+        .context("Daemon not running or unreachable")?;
+    let mut stream = conn.stream;
+    let plan =
+        crate::daemon::plan_request(&mut stream, VeloRequest::PackBuild { dry_run: true }).await?;
+    crate::plan::print(&plan, json)
+}
+
+fn print_progress(job: &JobInfo) {
+    if job.state == JobState::Queued {
+        print!(
+            "\rJob {} queued behind another store-wide job   ",
+            job.job_id
+        );
+    } else {
+        print!(
+            "\rPacking: {}/{} blobs   ",
+            job.processed, job.total_estimate
+        );
+    }
+    let _ = io::stdout().flush();
+}
+
+fn status(project_root: &Path) -> Result<()> {
+    let project_id = vrift_config::path::compute_project_id(project_root);
+    let path = vrift_config::path::get_pack_path(&project_id)
+        .context("No home directory for packfiles")?;
+    if !path.exists() {
+        println!("No packfile for {}", project_root.display());
+        println!("Create one with `vrift pack trace -- <cmd>`");
+        return Ok(());
+    }
+    let reader = PackReader::open(&path)
+        .with_context(|| format!("Cannot read packfile {}", path.display()))?;
+    let bytes: u64 = reader
+        .hashes()
+        .filter_map(|hash| reader.get(hash).ok())
+        .map(|data| data.len() as u64)
+        .sum();
+    println!("Packfile: {}", path.display());
+    println!("   {} blobs, {} bytes", reader.len(), bytes);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(subcommand)]
+        command: PackCommand,
+    }
+
+    #[test]
+    fn test_trace_takes_the_command_verbatim() {
+        let cli = Cli::try_parse_from(["pack", "trace", "-C", "/p", "--", "make", "-j8"]).unwrap();
+        let PackCommand::Trace {
+            project,
+            no_build,
+            command,
+        } = cli.command
+        else {
+            panic!("expected trace");
+        };
+        assert_eq!(project.directory, Some(PathBuf::from("/p")));
+        assert!(!no_build);
+        assert_eq!(command, ["make", "-j8"]);
+    }
+}
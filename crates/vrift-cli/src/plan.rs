@@ -0,0 +1,85 @@
+//! # Dry runs
+//!
+//! `--dry-run` on `vrift ingest`, `vrift gc` and `vrift pack build` has
+//! vriftd work out what the operation would do, with the same walk and the
+//! same decisions, and answer with a [`Plan`] instead of doing it. The plan
+//! is printed as a listing, or with `--json` as one document for CI policy
+//! checks.
+
+use anyhow::Result;
+use vrift_ipc::Plan;
+
+/// Print `plan` as a listing, or as JSON
+pub fn print(plan: &Plan, json: bool) -> Result<()> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(plan)?);
+    } else {
+        print!("{}", render(plan));
+    }
+    Ok(())
+}
+
+fn render(plan: &Plan) -> String {
+    let mut out = String::new();
+    for step in &plan.steps {
+        out += &format!(
+            "  {:<6} {:>10}  {}\n",
+            step.action.name(),
+            crate::format_bytes(step.bytes),
+            step.target
+        );
+    }
+    let omitted = plan.omitted();
+    if omitted > 0 {
+        out += &format!("  ... and {} more\n", omitted);
+    }
+    if !plan.steps.is_empty() {
+        out += "\n";
+    }
+    out += "Dry run, nothing was changed:\n";
+    if plan.totals.is_empty() {
+        out += "  nothing to do\n";
+    }
+    for total in &plan.totals {
+        out += &format!(
+            "  {:<6} {} ({})\n",
+            total.action.name(),
+            total.steps,
+            crate::format_bytes(total.bytes)
+        );
+    }
+    if plan.unchanged > 0 {
+        out += &format!(
+            "  {} files unchanged since the last ingest\n",
+            plan.unchanged
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vrift_ipc::PlanAction;
+
+    #[test]
+    fn test_render_lists_steps_then_totals() {
+        let mut plan = Plan::default();
+        plan.push(PlanAction::Hash, "/p/src/a.rs", 2048);
+        plan.push(PlanAction::Hash, "/p/src/b.rs", 10);
+        plan.unchanged = 3;
+        assert_eq!(
+            render(&plan),
+            "  hash      2.00 KB  /p/src/a.rs\n\
+             \x20 hash         10 B  /p/src/b.rs\n\
+             \n\
+             Dry run, nothing was changed:\n\
+             \x20 hash   2 (2.01 KB)\n\
+             \x20 3 files unchanged since the last ingest\n"
+        );
+        assert_eq!(
+            render(&Plan::default()),
+            "Dry run, nothing was changed:\n  nothing to do\n"
+        );
+    }
+}
@@ -9,11 +9,7 @@ use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 use console::style;
-
-/// VDIR_MAGIC must match vrift-vdird/src/vdir.rs
-const VDIR_MAGIC: u32 = 0x56524654; // "VRFT"
-/// VDIR_VERSION must match vrift-vdird/src/vdir.rs
-const VDIR_VERSION: u32 = 2;
+use vrift_ipc::vdir_types::{vdir_version_supported, VDIR_MAGIC, VDIR_MIN_VERSION, VDIR_VERSION};
 
 /// Result of preflight checks
 #[derive(Debug)]
@@ -140,10 +136,12 @@ fn validate_vdir_version(vdir_path: &Path) -> Result<(), String> {
         ));
     }
 
-    if version != VDIR_VERSION {
+    // Older versions are migrated in place by vDird; only unknown ones need a rebuild
+    if !vdir_version_supported(version) {
         return Err(format!(
-            "VDir version mismatch (have: {}, need: {}). Run: {}",
+            "VDir version mismatch (have: {}, supported: {}..={}). Run: {}",
             version,
+            VDIR_MIN_VERSION,
             VDIR_VERSION,
             style("vrift init --force").cyan()
         ));
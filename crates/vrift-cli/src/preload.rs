@@ -0,0 +1,566 @@
+//! # Preload Eligibility
+//!
+//! `vrift run` injects the shim through the dynamic loader
+//! (`DYLD_INSERT_LIBRARIES` / `LD_PRELOAD`). The loader drops those variables
+//! without a word for binaries it treats as restricted, and the command then
+//! runs against the real filesystem. [`check`] tells beforehand, from the
+//! binary on disk:
+//!
+//! - setuid / setgid binaries (both platforms)
+//! - macOS: binaries under SIP-protected directories while SIP is enabled
+//! - macOS: a `__RESTRICT` segment
+//! - macOS: a code signature with the restrict flag, or with the hardened
+//!   runtime but without the entitlements to load an injected library
+//!
+//! A script is judged by its interpreter as well: `#!/usr/bin/env python3`
+//! loses the variables in `/usr/bin/env` before python ever starts.
+
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::os::unix::fs::{FileExt, MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+use nix::unistd::{getegid, geteuid};
+
+/// Why the loader will not inject the shim into a binary
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Blocker {
+    Setuid,
+    #[cfg(target_os = "macos")]
+    Sip,
+    RestrictSegment,
+    RestrictFlag,
+    HardenedRuntime,
+}
+
+impl Blocker {
+    fn describe(self) -> &'static str {
+        match self {
+            Blocker::Setuid => "it is setuid/setgid",
+            #[cfg(target_os = "macos")]
+            Blocker::Sip => "it is protected by System Integrity Protection",
+            Blocker::RestrictSegment => "it has a __RESTRICT segment",
+            Blocker::RestrictFlag => "its code signature has the restrict flag",
+            Blocker::HardenedRuntime => {
+                "it uses the hardened runtime without the entitlements to load an injected library"
+            }
+        }
+    }
+}
+
+/// A command the shim cannot be injected into
+#[derive(Debug, PartialEq, Eq)]
+pub struct Blocked {
+    /// The script the command names, when the blocked binary is its interpreter
+    pub script: Option<PathBuf>,
+    pub binary: PathBuf,
+    pub blocker: Blocker,
+}
+
+impl fmt::Display for Blocked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let variable = if cfg!(target_os = "macos") {
+            "DYLD_INSERT_LIBRARIES"
+        } else {
+            "LD_PRELOAD"
+        };
+        if let Some(script) = &self.script {
+            write!(
+                f,
+                "{} runs under {}, which",
+                script.display(),
+                self.binary.display()
+            )?;
+        } else {
+            write!(f, "{}", self.binary.display())?;
+        }
+        writeln!(
+            f,
+            " will not load the VFS shim: {}.",
+            self.blocker.describe()
+        )?;
+        writeln!(
+            f,
+            "The loader drops {} for it, so the command would run against the real filesystem.",
+            variable
+        )?;
+        match self.blocker {
+            Blocker::Setuid => write!(
+                f,
+                "Run a copy without the setuid bit, or use `vrift run --isolate` on Linux."
+            ),
+            _ => write!(
+                f,
+                "Use a copy outside the system directories (e.g. from Homebrew) or one re-signed \
+                 without the hardened runtime (`codesign --force -s - <copy>`), or mount the \
+                 manifest with `vrift mount` and run the command on the mount point."
+            ),
+        }
+    }
+}
+
+/// Longest chain of `#!` interpreters followed
+const MAX_CHAIN: usize = 4;
+
+/// Whether the loader will refuse to inject the shim into `program`, the
+/// first word of a `vrift run` command
+pub fn check(program: &str) -> Option<Blocked> {
+    let resolved = resolve(program)?;
+    let mut chain = vec![resolved.clone()];
+    while chain.len() < MAX_CHAIN {
+        let Some(interpreters) = chain.last().and_then(|p| read_shebang(p)) else {
+            break;
+        };
+        chain.extend(interpreters.iter().filter_map(|p| resolve(p)));
+    }
+    chain.into_iter().find_map(|binary| {
+        let blocker = blocker(&binary)?;
+        Some(Blocked {
+            script: (binary != resolved).then(|| resolved.clone()),
+            binary,
+            blocker,
+        })
+    })
+}
+
+/// Path of `program` as `execvp` would find it
+fn resolve(program: &str) -> Option<PathBuf> {
+    if program.contains('/') {
+        let path = PathBuf::from(program);
+        return path.is_file().then_some(path);
+    }
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .map(|dir| dir.join(program))
+        .find(|path| {
+            path.metadata()
+                .is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        })
+}
+
+fn read_shebang(path: &Path) -> Option<Vec<String>> {
+    let mut head = [0u8; 256];
+    let n = File::open(path).ok()?.read(&mut head).ok()?;
+    let line = head[..n].split(|&b| b == b'\n').next()?;
+    shebang(std::str::from_utf8(line).ok()?)
+}
+
+/// Programs a `#!` line runs: the interpreter, and for `/usr/bin/env` the
+/// program it looks up
+fn shebang(line: &str) -> Option<Vec<String>> {
+    let mut words = line.strip_prefix("#!")?.split_whitespace();
+    let interpreter = words.next()?;
+    let mut programs = vec![interpreter.to_string()];
+    if Path::new(interpreter)
+        .file_name()
+        .is_some_and(|n| n == "env")
+    {
+        // Skip env's options (`-S`, `-i`) and variable assignments
+        if let Some(program) = words.find(|w| !w.starts_with('-') && !w.contains('=')) {
+            programs.push(program.to_string());
+        }
+    }
+    Some(programs)
+}
+
+fn blocker(path: &Path) -> Option<Blocker> {
+    let file = File::open(path).ok()?;
+    let meta = file.metadata().ok()?;
+    if changes_identity(
+        meta.mode(),
+        (meta.uid(), meta.gid()),
+        (geteuid().as_raw(), getegid().as_raw()),
+    ) {
+        return Some(Blocker::Setuid);
+    }
+    #[cfg(target_os = "macos")]
+    if sip_protected(path) && sip_enabled() {
+        return Some(Blocker::Sip);
+    }
+    macho_blocker(&file)
+}
+
+/// Whether executing a file with `mode` and `owner` (uid, gid) switches to
+/// an identity other than `current`: the secure-execution mode in which the
+/// loader ignores preloads
+fn changes_identity(mode: u32, owner: (u32, u32), current: (u32, u32)) -> bool {
+    (mode & 0o4000 != 0 && owner.0 != current.0) || (mode & 0o2000 != 0 && owner.1 != current.1)
+}
+
+/// Directories SIP keeps Apple's binaries in; `/usr/local` is not one
+#[cfg(target_os = "macos")]
+fn sip_protected(path: &Path) -> bool {
+    let Ok(path) = path.canonicalize() else {
+        return false;
+    };
+    ["/System", "/bin", "/sbin", "/usr"]
+        .iter()
+        .any(|dir| path.starts_with(dir))
+        && !path.starts_with("/usr/local")
+}
+
+/// SIP is on unless `csrutil` says otherwise
+#[cfg(target_os = "macos")]
+fn sip_enabled() -> bool {
+    std::process::Command::new("/usr/bin/csrutil")
+        .arg("status")
+        .output()
+        .map_or(true, |out| {
+            !String::from_utf8_lossy(&out.stdout).contains("disabled")
+        })
+}
+
+// ============================================================================
+// Mach-O inspection
+// ============================================================================
+
+const MH_MAGIC: u32 = 0xfeed_face;
+const MH_MAGIC_64: u32 = 0xfeed_facf;
+const FAT_MAGIC: u32 = 0xcafe_babe;
+const FAT_MAGIC_64: u32 = 0xcafe_babf;
+
+const LC_SEGMENT: u32 = 0x1;
+const LC_SEGMENT_64: u32 = 0x19;
+const LC_CODE_SIGNATURE: u32 = 0x1d;
+
+const CSMAGIC_EMBEDDED_SIGNATURE: u32 = 0xfade_0cc0;
+const CSMAGIC_CODEDIRECTORY: u32 = 0xfade_0c02;
+const CSMAGIC_EMBEDDED_ENTITLEMENTS: u32 = 0xfade_7171;
+const CS_RESTRICT: u32 = 0x800;
+const CS_RUNTIME: u32 = 0x1_0000;
+
+/// Both are needed under the hardened runtime: the first keeps dyld from
+/// dropping the variable, the second lets it load a library signed by
+/// someone else
+const INJECTION_ENTITLEMENTS: [&str; 2] = [
+    "com.apple.security.cs.allow-dyld-environment-variables",
+    "com.apple.security.cs.disable-library-validation",
+];
+
+const CPU_TYPE_X86_64: u32 = 0x0100_0007;
+const CPU_TYPE_ARM64: u32 = 0x0100_000c;
+const HOST_CPU: u32 = if cfg!(target_arch = "aarch64") {
+    CPU_TYPE_ARM64
+} else {
+    CPU_TYPE_X86_64
+};
+
+/// Bounds on what a corrupt header can make us read
+const MAX_LOAD_COMMANDS: usize = 1 << 20;
+const MAX_SIGNATURE_BLOBS: usize = 64;
+const MAX_ENTITLEMENTS: usize = 1 << 20;
+
+fn read_at(file: &File, offset: u64, len: usize) -> Option<Vec<u8>> {
+    let mut buf = vec![0; len];
+    file.read_exact_at(&mut buf, offset).ok()?;
+    Some(buf)
+}
+
+fn be32(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn le32(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn be64(bytes: &[u8], at: usize) -> Option<u64> {
+    Some(u64::from_be_bytes(bytes.get(at..at + 8)?.try_into().ok()?))
+}
+
+/// Blocker found in a Mach-O image (or the host slice of a universal
+/// binary); `None` for anything else
+fn macho_blocker(file: &File) -> Option<Blocker> {
+    let magic = be32(&read_at(file, 0, 4)?, 0)?;
+    if magic != FAT_MAGIC && magic != FAT_MAGIC_64 {
+        return slice_blocker(file, 0);
+    }
+
+    let count = be32(&read_at(file, 4, 4)?, 0)? as usize;
+    let entry = if magic == FAT_MAGIC_64 { 32 } else { 20 };
+    let archs = read_at(file, 8, count.min(MAX_SIGNATURE_BLOBS) * entry)?;
+    let slices: Vec<(u32, u64)> = archs
+        .chunks_exact(entry)
+        .filter_map(|arch| {
+            let offset = if magic == FAT_MAGIC_64 {
+                be64(arch, 8)?
+            } else {
+                be32(arch, 8)? as u64
+            };
+            Some((be32(arch, 0)?, offset))
+        })
+        .collect();
+    // Without a slice for this machine there is nothing it could run; judge
+    // them all
+    let host: Vec<u64> = slices
+        .iter()
+        .filter(|(cpu, _)| *cpu == HOST_CPU)
+        .map(|(_, offset)| *offset)
+        .collect();
+    let offsets = if host.is_empty() {
+        slices.iter().map(|(_, offset)| *offset).collect()
+    } else {
+        host
+    };
+    offsets
+        .into_iter()
+        .find_map(|offset| slice_blocker(file, offset))
+}
+
+fn slice_blocker(file: &File, base: u64) -> Option<Blocker> {
+    let header = read_at(file, base, 28)?;
+    let header_len = match le32(&header, 0)? {
+        MH_MAGIC_64 => 32,
+        MH_MAGIC => 28,
+        _ => return None,
+    };
+    let ncmds = le32(&header, 16)?;
+    let sizeofcmds = le32(&header, 20)? as usize;
+    if sizeofcmds > MAX_LOAD_COMMANDS {
+        return None;
+    }
+    let cmds = read_at(file, base + header_len, sizeofcmds)?;
+
+    let mut at = 0;
+    let mut signature = None;
+    for _ in 0..ncmds {
+        let cmd = le32(&cmds, at)?;
+        let size = le32(&cmds, at + 4)? as usize;
+        match cmd {
+            LC_SEGMENT | LC_SEGMENT_64 => {
+                let name = cmds.get(at + 8..at + 24)?;
+                if name.split(|&b| b == 0).next() == Some(b"__RESTRICT") {
+                    return Some(Blocker::RestrictSegment);
+                }
+            }
+            LC_CODE_SIGNATURE => signature = Some(le32(&cmds, at + 8)?),
+            _ => {}
+        }
+        if size < 8 {
+            return None;
+        }
+        at += size;
+    }
+    signature_blocker(file, base + signature? as u64)
+}
+
+/// Flags and entitlements of an embedded code signature (big-endian
+/// SuperBlob of CodeDirectory, entitlements, ... blobs)
+fn signature_blocker(file: &File, at: u64) -> Option<Blocker> {
+    let head = read_at(file, at, 12)?;
+    if be32(&head, 0)? != CSMAGIC_EMBEDDED_SIGNATURE {
+        return None;
+    }
+    let count = be32(&head, 8)? as usize;
+    if count > MAX_SIGNATURE_BLOBS {
+        return None;
+    }
+    let index = read_at(file, at + 12, count * 8)?;
+
+    let mut flags = 0;
+    let mut entitlements = Vec::new();
+    for slot in index.chunks_exact(8) {
+        let blob_at = at + be32(slot, 4)? as u64;
+        let blob = read_at(file, blob_at, 8)?;
+        match be32(&blob, 0)? {
+            // Alternate code directories carry the same flags
+            CSMAGIC_CODEDIRECTORY => flags |= be32(&read_at(file, blob_at, 16)?, 12)?,
+            CSMAGIC_EMBEDDED_ENTITLEMENTS => {
+                let len = (be32(&blob, 4)? as usize).saturating_sub(8);
+                if len <= MAX_ENTITLEMENTS {
+                    entitlements = read_at(file, blob_at + 8, len)?;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if flags & CS_RESTRICT != 0 {
+        return Some(Blocker::RestrictFlag);
+    }
+    let plist = String::from_utf8_lossy(&entitlements);
+    if flags & CS_RUNTIME != 0 && !INJECTION_ENTITLEMENTS.iter().all(|e| entitled(&plist, e)) {
+        return Some(Blocker::HardenedRuntime);
+    }
+    None
+}
+
+/// Whether an entitlements plist grants `key`
+fn entitled(plist: &str, key: &str) -> bool {
+    plist
+        .split_once(&format!("<key>{}</key>", key))
+        .is_some_and(|(_, rest)| rest.trim_start().starts_with("<true/>"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(name: &str) -> Vec<u8> {
+        let mut cmd = Vec::new();
+        cmd.extend(LC_SEGMENT_64.to_le_bytes());
+        cmd.extend(72u32.to_le_bytes());
+        let mut segname = [0u8; 16];
+        segname[..name.len()].copy_from_slice(name.as_bytes());
+        cmd.extend(segname);
+        cmd.resize(72, 0);
+        cmd
+    }
+
+    fn signature(flags: u32, entitlements: &[&str]) -> Vec<u8> {
+        let mut cd = Vec::new();
+        cd.extend(CSMAGIC_CODEDIRECTORY.to_be_bytes());
+        cd.extend(16u32.to_be_bytes());
+        cd.extend(0x20400u32.to_be_bytes());
+        cd.extend(flags.to_be_bytes());
+
+        let plist: String = entitlements
+            .iter()
+            .map(|e| format!("<key>{}</key>\n\t<true/>\n", e))
+            .collect();
+        let plist = format!("<plist><dict>\n{}</dict></plist>", plist);
+        let mut ents = Vec::new();
+        ents.extend(CSMAGIC_EMBEDDED_ENTITLEMENTS.to_be_bytes());
+        ents.extend((8 + plist.len() as u32).to_be_bytes());
+        ents.extend(plist.as_bytes());
+
+        let header_len = 12 + 2 * 8;
+        let mut sig = Vec::new();
+        sig.extend(CSMAGIC_EMBEDDED_SIGNATURE.to_be_bytes());
+        sig.extend(((header_len + cd.len() + ents.len()) as u32).to_be_bytes());
+        sig.extend(2u32.to_be_bytes());
+        sig.extend(0u32.to_be_bytes());
+        sig.extend((header_len as u32).to_be_bytes());
+        sig.extend(5u32.to_be_bytes());
+        sig.extend(((header_len + cd.len()) as u32).to_be_bytes());
+        sig.extend(cd);
+        sig.extend(ents);
+        sig
+    }
+
+    fn image(segments: &[&str], sig: Option<Vec<u8>>) -> Vec<u8> {
+        let mut cmds: Vec<u8> = segments.iter().flat_map(|s| segment(s)).collect();
+        let ncmds = segments.len() + sig.is_some() as usize;
+        if let Some(sig) = &sig {
+            let offset = 32 + cmds.len() + 16;
+            cmds.extend(LC_CODE_SIGNATURE.to_le_bytes());
+            cmds.extend(16u32.to_le_bytes());
+            cmds.extend((offset as u32).to_le_bytes());
+            cmds.extend((sig.len() as u32).to_le_bytes());
+        }
+        let mut out = Vec::new();
+        for word in [
+            MH_MAGIC_64,
+            CPU_TYPE_ARM64,
+            0,
+            2,
+            ncmds as u32,
+            cmds.len() as u32,
+            0,
+            0,
+        ] {
+            out.extend(word.to_le_bytes());
+        }
+        out.extend(cmds);
+        out.extend(sig.unwrap_or_default());
+        out
+    }
+
+    fn fat(slice: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for word in [FAT_MAGIC, 1, CPU_TYPE_ARM64, 0, 28, slice.len() as u32, 0] {
+            out.extend(word.to_be_bytes());
+        }
+        out.extend(slice);
+        out
+    }
+
+    fn inspect(bytes: &[u8]) -> Option<Blocker> {
+        let mut file = tempfile::tempfile().unwrap();
+        std::io::Write::write_all(&mut file, bytes).unwrap();
+        macho_blocker(&file)
+    }
+
+    #[test]
+    fn test_macho_blockers() {
+        assert_eq!(inspect(&image(&["__TEXT", "__DATA"], None)), None);
+        assert_eq!(
+            inspect(&image(&["__TEXT", "__RESTRICT"], None)),
+            Some(Blocker::RestrictSegment)
+        );
+        assert_eq!(inspect(&image(&["__TEXT"], Some(signature(0, &[])))), None);
+        assert_eq!(
+            inspect(&image(&["__TEXT"], Some(signature(CS_RESTRICT, &[])))),
+            Some(Blocker::RestrictFlag)
+        );
+        assert_eq!(
+            inspect(&image(&["__TEXT"], Some(signature(CS_RUNTIME, &[])))),
+            Some(Blocker::HardenedRuntime)
+        );
+        // One of the two entitlements is not enough
+        assert_eq!(
+            inspect(&image(
+                &["__TEXT"],
+                Some(signature(CS_RUNTIME, &INJECTION_ENTITLEMENTS[..1]))
+            )),
+            Some(Blocker::HardenedRuntime)
+        );
+        assert_eq!(
+            inspect(&image(
+                &["__TEXT"],
+                Some(signature(CS_RUNTIME, &INJECTION_ENTITLEMENTS))
+            )),
+            None
+        );
+        assert_eq!(
+            inspect(&fat(&image(&["__RESTRICT"], None))),
+            Some(Blocker::RestrictSegment)
+        );
+        assert_eq!(inspect(b"\x7fELF\x02\x01\x01"), None);
+        assert_eq!(inspect(b""), None);
+    }
+
+    #[test]
+    fn test_shebang() {
+        assert_eq!(shebang("#!/bin/sh -e"), Some(vec!["/bin/sh".to_string()]));
+        assert_eq!(
+            shebang("#!/usr/bin/env -S PYTHONUNBUFFERED=1 python3 -u"),
+            Some(vec!["/usr/bin/env".to_string(), "python3".to_string()])
+        );
+        assert_eq!(shebang("\x7fELF"), None);
+    }
+
+    #[test]
+    fn test_changes_identity() {
+        let me = (501, 20);
+        assert!(!changes_identity(0o755, (0, 0), me));
+        assert!(changes_identity(0o4755, (0, 0), me));
+        assert!(changes_identity(0o2755, (501, 0), me));
+        // setuid to ourselves changes nothing
+        assert!(!changes_identity(0o6755, me, me));
+    }
+
+    #[test]
+    fn test_script_is_judged_by_its_interpreter() {
+        let temp = tempfile::tempdir().unwrap();
+        let interpreter = temp.path().join("interp");
+        std::fs::write(&interpreter, image(&["__RESTRICT"], None)).unwrap();
+        let script = temp.path().join("build.sh");
+        std::fs::write(&script, format!("#!{} -e\n", interpreter.display())).unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let blocked = check(script.to_str().unwrap()).unwrap();
+        assert_eq!(
+            blocked,
+            Blocked {
+                script: Some(script.clone()),
+                binary: interpreter.clone(),
+                blocker: Blocker::RestrictSegment,
+            }
+        );
+        assert!(blocked.to_string().contains("runs under"));
+
+        std::fs::write(&interpreter, image(&["__TEXT"], None)).unwrap();
+        assert_eq!(check(script.to_str().unwrap()), None);
+    }
+}
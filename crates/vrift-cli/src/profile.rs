@@ -0,0 +1,105 @@
+//! # vrift profile
+//!
+//! Share access profiles between machines. Each developer or CI run exports
+//! its profile, `merge` aggregates any number of them weighted by how many
+//! runs each stands for, and `import` turns the result into the binary
+//! profile the pack planner reads.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use clap::Subcommand;
+use vrift_pack::{AccessProfile, ProfileAggregate};
+
+#[derive(Subcommand, Debug)]
+pub enum ProfileCommand {
+    /// Convert a binary access profile to the portable text format
+    Export {
+        /// Binary profile written by a profiling run
+        profile: PathBuf,
+
+        /// Output file
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Number of runs this profile stands for
+        #[arg(long, default_value_t = 1)]
+        weight: u64,
+    },
+    /// Aggregate exported (or binary) profiles into one export
+    Merge {
+        /// Profiles to merge
+        #[arg(required = true)]
+        inputs: Vec<PathBuf>,
+
+        /// Output file
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+    /// Write the packing order of an export as a binary profile
+    Import {
+        /// Exported (or merged) profile
+        input: PathBuf,
+
+        /// Binary profile for the pack planner
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+}
+
+pub fn run(command: ProfileCommand) -> Result<()> {
+    match command {
+        ProfileCommand::Export {
+            profile,
+            output,
+            weight,
+        } => {
+            anyhow::ensure!(weight > 0, "--weight must be at least 1");
+            let mut aggregate = ProfileAggregate::new();
+            aggregate.add_run(&load_binary(&profile)?, weight);
+            save_export(&aggregate, &output)
+        }
+        ProfileCommand::Merge { inputs, output } => {
+            let mut fleet = ProfileAggregate::new();
+            for input in &inputs {
+                fleet.merge(&load(input)?);
+            }
+            save_export(&fleet, &output)
+        }
+        ProfileCommand::Import { input, output } => {
+            let aggregate = load(&input)?;
+            aggregate
+                .to_profile()
+                .save(&output)
+                .with_context(|| format!("Cannot write {}", output.display()))?;
+            println!(
+                "Wrote packing order of {} blobs ({} runs) to {}",
+                aggregate.len(),
+                aggregate.weight(),
+                output.display()
+            );
+            Ok(())
+        }
+    }
+}
+
+fn load_binary(path: &Path) -> Result<AccessProfile> {
+    AccessProfile::load(path).with_context(|| format!("Cannot read profile {}", path.display()))
+}
+
+fn load(path: &Path) -> Result<ProfileAggregate> {
+    ProfileAggregate::load(path).with_context(|| format!("Cannot read profile {}", path.display()))
+}
+
+fn save_export(aggregate: &ProfileAggregate, output: &Path) -> Result<()> {
+    aggregate
+        .export_to(output)
+        .with_context(|| format!("Cannot write {}", output.display()))?;
+    println!(
+        "Wrote {} blobs from {} runs to {}",
+        aggregate.len(),
+        aggregate.weight(),
+        output.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,239 @@
+//! # vrift record
+//!
+//! Build input fingerprinting: run a command under the inception layer with
+//! read tracking enabled and write out every file it actually read.
+//!
+//! The result is either a JSON inputs list (with content hashes for files the
+//! project manifest knows about) or a manifest subset that can be fed back to
+//! `vrift run`, packing, or used as a cache key.
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use vrift_cas::CasStore;
+use vrift_config::path::normalize_for_ipc;
+use vrift_manifest::lmdb::LmdbManifest;
+use vrift_manifest::Manifest;
+
+/// Output format for `vrift record`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum RecordFormat {
+    /// JSON list of inputs with hashes
+    Json,
+    /// Binary manifest containing only the inputs found in the project manifest
+    Manifest,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RecordedInput {
+    path: String,
+    /// Manifest key, for inputs inside the project
+    #[serde(skip_serializing_if = "Option::is_none")]
+    key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RecordedInputs {
+    command: Vec<String>,
+    project_root: String,
+    generated_at: u64,
+    exit_code: i32,
+    inputs: Vec<RecordedInput>,
+}
+
+/// Parse a raw trace into a sorted, de-duplicated path set
+fn parse_trace(raw: &str) -> BTreeSet<String> {
+    raw.lines()
+        .map(str::trim_end)
+        .filter(|line| line.starts_with('/'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Manifest key for `path` if it lies inside `project_root`
+fn manifest_key(project_root: &Path, path: &str) -> Option<String> {
+    let rel = Path::new(path).strip_prefix(project_root).ok()?;
+    if rel.as_os_str().is_empty() || rel.starts_with(".vrift") {
+        return None;
+    }
+    Some(format!("/{}", rel.display()))
+}
+
+pub async fn cmd_record(
+    project_dir: &Path,
+    command: &[String],
+    output: &Path,
+    format: RecordFormat,
+) -> Result<()> {
+    if command.is_empty() {
+        anyhow::bail!("No command specified. Usage: vrift record -- <cmd> [args...]");
+    }
+
+    let project_root = normalize_for_ipc(project_dir).context("resolve project path")?;
+    let inception_path = crate::inception::find_inception_library(&project_root)?;
+    let daemon_conn = crate::daemon::connect_to_daemon(&project_root).await.ok();
+
+    let cfg = vrift_config::Config::load_for_project(&project_root).unwrap_or_else(|e| {
+        eprintln!("Warning: Config load failed: {}. Using defaults.", e);
+        vrift_config::Config::default()
+    });
+
+    let trace_dir = project_root.join(".vrift");
+    std::fs::create_dir_all(&trace_dir)
+        .with_context(|| format!("Failed to create {}", trace_dir.display()))?;
+    let trace = tempfile::Builder::new()
+        .prefix("record_")
+        .suffix(".trace")
+        .tempfile_in(&trace_dir)
+        .context("Failed to create trace file")?;
+
+    let mut cmd = std::process::Command::new(&command[0]);
+    cmd.args(&command[1..]);
+    for (key, value) in cfg.shim_env() {
+        cmd.env(key, value);
+    }
+    if let Some(ref conn) = daemon_conn {
+        if !conn.vdird_socket.is_empty() {
+            cmd.env("VRIFT_VDIRD_SOCKET", &conn.vdird_socket);
+        }
+        if !conn.vdir_mmap_path.is_empty() {
+            cmd.env("VRIFT_VDIR_MMAP", &conn.vdir_mmap_path);
+        }
+    }
+    cmd.env("VRIFT_RECORD", trace.path());
+
+    #[cfg(target_os = "macos")]
+    {
+        cmd.env("DYLD_INSERT_LIBRARIES", &inception_path)
+            .env("DYLD_FORCE_FLAT_NAMESPACE", "1");
+    }
+    #[cfg(target_os = "linux")]
+    {
+        cmd.env("LD_PRELOAD", &inception_path);
+    }
+
+    let status = cmd
+        .status()
+        .with_context(|| format!("Failed to execute: {}", command[0]))?;
+    let exit_code = status.code().unwrap_or(1);
+
+    let raw = std::fs::read_to_string(trace.path()).context("Failed to read trace")?;
+    let paths = parse_trace(&raw);
+
+    let project_id = vrift_config::path::compute_project_id(&project_root);
+    let lmdb = vrift_config::path::get_manifest_db_path(&project_id)
+        .filter(|p| p.exists())
+        .map(|p| LmdbManifest::open_readonly(&p))
+        .transpose()?;
+    if lmdb.is_none() {
+        eprintln!("Warning: no project manifest found; inputs will be recorded without hashes.");
+    }
+
+    let mut inputs = Vec::with_capacity(paths.len());
+    let mut subset = Manifest::new();
+    for path in paths {
+        let key = manifest_key(&project_root, &path);
+        let entry = match (&lmdb, &key) {
+            (Some(m), Some(k)) => m.get(k)?,
+            _ => None,
+        };
+        if let (Some(k), Some(e)) = (&key, &entry) {
+            subset.insert(k, e.vnode.clone());
+        }
+        inputs.push(RecordedInput {
+            path,
+            key,
+            hash: entry
+                .as_ref()
+                .map(|e| CasStore::hash_to_hex(&e.vnode.content_hash)),
+            size: entry.as_ref().map(|e| e.vnode.size),
+        });
+    }
+
+    match format {
+        RecordFormat::Json => {
+            let report = RecordedInputs {
+                command: command.to_vec(),
+                project_root: project_root.to_string_lossy().to_string(),
+                generated_at: std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0),
+                exit_code,
+                inputs,
+            };
+            let file = std::fs::File::create(output)
+                .with_context(|| format!("Failed to create {}", output.display()))?;
+            serde_json::to_writer_pretty(file, &report)?;
+            eprintln!(
+                "Recorded {} inputs ({} in manifest) -> {}",
+                report.inputs.len(),
+                subset.len(),
+                output.display()
+            );
+        }
+        RecordFormat::Manifest => {
+            subset
+                .save(output)
+                .with_context(|| format!("Failed to write {}", output.display()))?;
+            eprintln!(
+                "Recorded {} inputs, {} in manifest -> {}",
+                inputs.len(),
+                subset.len(),
+                output.display()
+            );
+        }
+    }
+
+    if !status.success() {
+        std::process::exit(exit_code);
+    }
+    Ok(())
+}
+
+/// Manifest keys of the inputs in a JSON report written by `vrift record`
+pub fn read_input_keys(report: &Path) -> Result<Vec<String>> {
+    let file = std::fs::File::open(report)
+        .with_context(|| format!("Failed to open {}", report.display()))?;
+    let inputs: RecordedInputs = serde_json::from_reader(std::io::BufReader::new(file))
+        .with_context(|| format!("{} is not a vrift record JSON report", report.display()))?;
+    Ok(inputs.inputs.into_iter().filter_map(|i| i.key).collect())
+}
+
+/// Default output file name for a format
+pub fn default_output(format: RecordFormat) -> PathBuf {
+    match format {
+        RecordFormat::Json => PathBuf::from("vrift.inputs.json"),
+        RecordFormat::Manifest => PathBuf::from("vrift.inputs.manifest"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_trace_dedupes_and_sorts() {
+        let raw = "/p/src/b.rs\n/p/src/a.rs\n/p/src/b.rs\npartial\n";
+        let paths: Vec<_> = parse_trace(raw).into_iter().collect();
+        assert_eq!(paths, vec!["/p/src/a.rs", "/p/src/b.rs"]);
+    }
+
+    #[test]
+    fn test_manifest_key_scoping() {
+        let root = Path::new("/p");
+        assert_eq!(
+            manifest_key(root, "/p/src/a.rs").as_deref(),
+            Some("/src/a.rs")
+        );
+        assert_eq!(manifest_key(root, "/p/.vrift/cache"), None);
+        assert_eq!(manifest_key(root, "/usr/lib/libc.so"), None);
+        assert_eq!(manifest_key(root, "/p"), None);
+    }
+}
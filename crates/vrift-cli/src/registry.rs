@@ -19,9 +19,9 @@ use std::io::{BufReader, BufWriter};
 use std::path::{Path, PathBuf};
 use std::time::Duration;
 use uuid::Uuid;
-use vrift_cas::Blake3Hash;
+use vrift_cas::{Blake3Hash, CasStore, RefCounts};
 use vrift_config::path::normalize_or_original;
-use vrift_manifest::{LmdbManifest, Manifest};
+use vrift_manifest::{snapshot, LmdbManifest, Manifest};
 
 /// Default lock timeout in seconds
 const DEFAULT_LOCK_TIMEOUT_SECS: u64 = 30;
@@ -276,8 +276,9 @@ impl ManifestRegistry {
         count
     }
 
-    /// Get all blob hashes referenced by all active manifests
-    pub fn get_all_blob_hashes(&self) -> Result<HashSet<Blake3Hash>> {
+    /// Get all blob hashes referenced by all active manifests and their
+    /// snapshots in the CAS at `cas_root`
+    pub fn get_all_blob_hashes(&self, cas_root: &Path) -> Result<HashSet<Blake3Hash>> {
         let mut hashes = HashSet::new();
 
         for entry in self.manifests.values() {
@@ -289,30 +290,86 @@ impl ManifestRegistry {
                 continue;
             }
 
-            if entry.source_path.is_dir() {
-                // RFC-0039: Load LMDB manifest
-                let lmdb = LmdbManifest::open(&entry.source_path).with_context(|| {
-                    format!("Failed to open LMDB manifest at {:?}", entry.source_path)
-                })?;
-                let entries = lmdb.iter().with_context(|| {
-                    format!("Failed to iterate LMDB manifest at {:?}", entry.source_path)
-                })?;
-                for (_, m_entry) in entries {
-                    hashes.insert(m_entry.vnode.content_hash);
-                }
-            } else {
-                // In-memory manifest (rkyv format)
-                let manifest = Manifest::load(&entry.source_path)
-                    .with_context(|| format!("Failed to load manifest: {:?}", entry.source_path))?;
-                for (_, vnode) in manifest.iter() {
-                    hashes.insert(vnode.content_hash);
-                }
-            }
+            hashes.extend(Self::blob_hashes(entry, cas_root)?);
         }
 
         Ok(hashes)
     }
 
+    /// Blob hashes referenced by a single manifest, including the tree
+    /// objects of its snapshots in the CAS at `cas_root` and their blobs
+    pub fn blob_hashes(entry: &ManifestEntry, cas_root: &Path) -> Result<HashSet<Blake3Hash>> {
+        let mut hashes = HashSet::new();
+        if entry.source_path.is_dir() {
+            // RFC-0039: Load LMDB manifest (a directory without one yet
+            // references nothing)
+            if vrift_manifest::ManifestBackend::detect(&entry.source_path).is_none() {
+                return Ok(hashes);
+            }
+            let lmdb = LmdbManifest::open_readonly(&entry.source_path).with_context(|| {
+                format!("Failed to open LMDB manifest at {:?}", entry.source_path)
+            })?;
+            let entries = lmdb.iter().with_context(|| {
+                format!("Failed to iterate LMDB manifest at {:?}", entry.source_path)
+            })?;
+            for (_, m_entry) in entries {
+                hashes.insert(m_entry.vnode.content_hash);
+            }
+            if entry.source_path.join(snapshot::SNAPSHOTS_FILE).exists() {
+                let cas = CasStore::new(cas_root)?;
+                let snapshots =
+                    snapshot::referenced_blobs(&entry.source_path, &cas).with_context(|| {
+                        format!("Failed to read snapshots of {:?}", entry.source_path)
+                    })?;
+                hashes.extend(snapshots);
+            }
+        } else {
+            // In-memory manifest (rkyv format)
+            let manifest = Manifest::load(&entry.source_path)
+                .with_context(|| format!("Failed to load manifest: {:?}", entry.source_path))?;
+            for (_, vnode) in manifest.iter() {
+                hashes.insert(vnode.content_hash);
+            }
+        }
+        Ok(hashes)
+    }
+
+    /// Record the current references of manifest `uuid` in the refcount
+    /// database of the CAS at `cas_root`, creating it if needed
+    pub fn record_refs(&self, cas_root: &Path, uuid: &str) -> Result<()> {
+        let entry = self
+            .manifests
+            .get(uuid)
+            .with_context(|| format!("Manifest {} is not registered", uuid))?;
+        let refs = RefCounts::open(cas_root)?;
+        refs.set_manifest(uuid, &Self::blob_hashes(entry, cas_root)?)?;
+        Ok(())
+    }
+
+    /// Bring the CAS reference counts in line with the registry: every
+    /// active manifest is recorded with its current hashes, and manifests
+    /// that are stale or no longer registered give up their references.
+    ///
+    /// Returns the number of manifests recorded.
+    pub fn sync_refcounts(&self, refs: &RefCounts, cas_root: &Path) -> Result<usize> {
+        let mut synced = 0;
+        for (uuid, entry) in &self.manifests {
+            if entry.status == ManifestStatus::Active && entry.source_path.exists() {
+                refs.set_manifest(uuid, &Self::blob_hashes(entry, cas_root)?)?;
+                synced += 1;
+            }
+        }
+        for uuid in refs.manifests()? {
+            let live = self.manifests.get(&uuid).is_some_and(|entry| {
+                entry.status == ManifestStatus::Active && entry.source_path.exists()
+            });
+            if !live {
+                refs.remove_manifest(&uuid)?;
+            }
+        }
+        Ok(synced)
+    }
+
     /// Get list of active manifests
     pub fn active_manifests(&self) -> Vec<(&String, &ManifestEntry)> {
         self.manifests
@@ -416,4 +473,71 @@ mod tests {
         assert_eq!(uuid1, uuid2);
         assert_eq!(registry.manifests.len(), 1);
     }
+
+    #[test]
+    fn test_registry_sync_refcounts() {
+        let temp = TempDir::new().unwrap();
+        let cas_root = temp.path().join("cas");
+        let refs = RefCounts::open(&cas_root).unwrap();
+        let (shared, own) = ([1u8; 32], [2u8; 32]);
+
+        let mut registry = ManifestRegistry::new();
+        let mut ids = Vec::new();
+        for (name, hashes) in [
+            ("a.manifest", vec![shared, own]),
+            ("b.manifest", vec![shared]),
+        ] {
+            let mut manifest = Manifest::new();
+            for (i, hash) in hashes.into_iter().enumerate() {
+                manifest.insert(
+                    &format!("/f{}", i),
+                    vrift_manifest::VnodeEntry::new_file(hash, 1, 0, 0o644),
+                );
+            }
+            let path = temp.path().join(name);
+            manifest.save(&path).unwrap();
+            ids.push(registry.register_manifest(&path, temp.path()).unwrap());
+        }
+
+        assert_eq!(registry.sync_refcounts(&refs, &cas_root).unwrap(), 2);
+        assert_eq!(
+            (refs.refs(&shared).unwrap(), refs.refs(&own).unwrap()),
+            (2, 1)
+        );
+
+        // A manifest gone from disk gives up its references
+        std::fs::remove_file(temp.path().join("a.manifest")).unwrap();
+        registry.verify_all();
+        assert_eq!(registry.sync_refcounts(&refs, &cas_root).unwrap(), 1);
+        assert_eq!(
+            (refs.refs(&shared).unwrap(), refs.refs(&own).unwrap()),
+            (1, 0)
+        );
+        assert_eq!(refs.manifests().unwrap(), [ids[1].clone()]);
+    }
+
+    #[test]
+    fn test_registry_keeps_snapshot_blobs() {
+        let temp = TempDir::new().unwrap();
+        let cas_root = temp.path().join("cas");
+        let cas = CasStore::new(&cas_root).unwrap();
+        let manifest_dir = temp.path().join("manifest.lmdb");
+        let manifest = LmdbManifest::open(&manifest_dir).unwrap();
+        let file = |hash| vrift_manifest::VnodeEntry::new_file(hash, 1, 0, 0o644);
+        let (old, new) = ([1u8; 32], [2u8; 32]);
+
+        manifest.insert("/a", file(old), vrift_manifest::AssetTier::Tier2Mutable);
+        manifest.commit().unwrap();
+        let snapshot = manifest.snapshot_create("before", &cas).unwrap();
+        manifest.insert("/a", file(new), vrift_manifest::AssetTier::Tier2Mutable);
+        manifest.commit().unwrap();
+        drop(manifest);
+
+        let mut registry = ManifestRegistry::new();
+        registry
+            .register_manifest(&manifest_dir, temp.path())
+            .unwrap();
+        let hashes = registry.get_all_blob_hashes(&cas_root).unwrap();
+        assert_eq!(hashes, HashSet::from([old, new, snapshot.tree]));
+    }
 }
@@ -0,0 +1,635 @@
+//! # vrift sbom
+//!
+//! Software bill of materials straight from the manifest. Packages are found
+//! by their metadata files:
+//!
+//! - npm: a `package.json` with a `name`; the package is everything below
+//!   its directory that no nested package claims.
+//! - Python: a `<name>.dist-info/METADATA`; the package is the files its
+//!   `RECORD` lists (relative to the `site-packages` holding the
+//!   `.dist-info`), or just the `.dist-info` when there is no `RECORD`.
+//!
+//! Each package is listed with a purl and each of its files with the BLAKE3
+//! hash the manifest records, as CycloneDX 1.5 or SPDX 2.3 JSON. Only the
+//! metadata files are read from the CAS; file hashes are never recomputed,
+//! so a package tree of any size costs one manifest scan.
+//!
+//! SPDX 2.3 expects a SHA1 for every file, which the manifest does not have:
+//! files carry their BLAKE3 checksum only and packages are marked
+//! `filesAnalyzed: false`.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use vrift_cas::{Blake3Hash, CasStore};
+use vrift_config::path::normalize_for_ipc;
+use vrift_manifest::digest::covers;
+use vrift_manifest::lmdb::ManifestEntry;
+use vrift_manifest::VnodeEntry;
+
+use crate::lock::{load_entries, Locked};
+
+/// Output format for `vrift sbom`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SbomFormat {
+    /// CycloneDX 1.5 JSON
+    Cyclonedx,
+    /// SPDX 2.3 JSON
+    Spdx,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Ecosystem {
+    Npm,
+    Pypi,
+}
+
+/// A package found in the manifest
+#[derive(Debug)]
+struct Package {
+    ecosystem: Ecosystem,
+    name: String,
+    version: Option<String>,
+    /// Manifest key of the file the package was found by
+    origin: String,
+    /// Manifest key -> content hash
+    files: BTreeMap<String, Blake3Hash>,
+}
+
+impl Package {
+    fn purl(&self) -> String {
+        let (kind, name) = match self.ecosystem {
+            // The `@` of a scope is percent-encoded in a purl
+            Ecosystem::Npm => ("npm", self.name.replacen('@', "%40", 1)),
+            // PyPI names are case-insensitive and treat `_`, `.` and `-` alike
+            Ecosystem::Pypi => ("pypi", self.name.to_lowercase().replace(['_', '.'], "-")),
+        };
+        match self.version {
+            Some(ref version) => format!("pkg:{}/{}@{}", kind, name, version),
+            None => format!("pkg:{}/{}", kind, name),
+        }
+    }
+}
+
+/// What was found under the scanned subtree
+#[derive(Debug, Default)]
+struct Inventory {
+    packages: Vec<Package>,
+    /// Files that belong to no package
+    unowned: usize,
+    /// Metadata files that could not be read, or `METADATA` without a `Name`
+    skipped: Vec<String>,
+}
+
+/// Parent directory of manifest key `path`
+fn parent(path: &str) -> &str {
+    match path.rsplit_once('/') {
+        Some(("", _)) | None => "/",
+        Some((dir, _)) => dir,
+    }
+}
+
+/// `rel` resolved against directory `dir`, `None` if it climbs above `/`
+fn join(dir: &str, rel: &str) -> Option<String> {
+    let mut parts: Vec<&str> = dir.split('/').filter(|p| !p.is_empty()).collect();
+    for part in rel.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop()?;
+            }
+            part => parts.push(part),
+        }
+    }
+    Some(format!("/{}", parts.join("/")))
+}
+
+/// `name` and `version` of a `package.json`; `None` if it names no package
+fn parse_package_json(content: &[u8]) -> Option<(String, Option<String>)> {
+    let json: Value = serde_json::from_slice(content).ok()?;
+    let name = json.get("name")?.as_str()?.to_string();
+    let version = json
+        .get("version")
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    Some((name, version))
+}
+
+/// `Name` and `Version` from the headers of a `METADATA` file
+fn parse_metadata(content: &[u8]) -> Option<(String, Option<String>)> {
+    let text = String::from_utf8_lossy(content);
+    let mut name = None;
+    let mut version = None;
+    for line in text.lines() {
+        if line.is_empty() {
+            // The description body follows the headers
+            break;
+        }
+        if let Some(v) = line.strip_prefix("Name:") {
+            name.get_or_insert_with(|| v.trim().to_string());
+        } else if let Some(v) = line.strip_prefix("Version:") {
+            version.get_or_insert_with(|| v.trim().to_string());
+        }
+    }
+    Some((name?, version))
+}
+
+/// Paths listed in a `RECORD`: the first CSV field of each line
+fn parse_record(content: &[u8]) -> Vec<String> {
+    let text = String::from_utf8_lossy(content);
+    let mut paths = Vec::new();
+    for line in text.lines() {
+        let path = match line.strip_prefix('"') {
+            // A quoted field, with `""` for a quote
+            Some(rest) => {
+                let mut path = String::new();
+                let mut chars = rest.chars().peekable();
+                while let Some(c) = chars.next() {
+                    if c == '"' {
+                        if chars.peek() != Some(&'"') {
+                            break;
+                        }
+                        chars.next();
+                    }
+                    path.push(c);
+                }
+                path
+            }
+            None => line.split(',').next().unwrap_or_default().to_string(),
+        };
+        if !path.is_empty() {
+            paths.push(path);
+        }
+    }
+    paths
+}
+
+/// Find the packages among the `entries` under `root`. `read` returns the
+/// content of an entry, for the metadata files.
+fn detect<F>(entries: &[(String, ManifestEntry)], root: &str, read: F) -> Inventory
+where
+    F: Fn(&VnodeEntry) -> Option<Vec<u8>>,
+{
+    let files: BTreeMap<&str, &ManifestEntry> = entries
+        .iter()
+        .filter(|(p, e)| covers(root, p) && !e.vnode.is_dir() && !e.vnode.is_symlink())
+        .map(|(p, e)| (p.as_str(), e))
+        .collect();
+
+    let mut inventory = Inventory::default();
+    // Directory of each npm package -> its index
+    let mut npm_roots: HashMap<&str, usize> = HashMap::new();
+    // Files claimed by a Python package's RECORD -> its index
+    let mut claimed: HashMap<String, usize> = HashMap::new();
+
+    for (&path, entry) in &files {
+        let name = path.rsplit('/').next().unwrap_or_default();
+        let dir = parent(path);
+        let ecosystem = if name == "package.json" {
+            Ecosystem::Npm
+        } else if name == "METADATA" && dir.ends_with(".dist-info") {
+            Ecosystem::Pypi
+        } else {
+            continue;
+        };
+        let Some(content) = read(&entry.vnode) else {
+            inventory.skipped.push(path.to_string());
+            continue;
+        };
+        let parsed = match ecosystem {
+            Ecosystem::Npm => parse_package_json(&content),
+            Ecosystem::Pypi => parse_metadata(&content),
+        };
+        let Some((name, version)) = parsed else {
+            // A package.json without a name (e.g. `{"type": "module"}` in a
+            // subdirectory) describes no package
+            if ecosystem == Ecosystem::Pypi {
+                inventory.skipped.push(path.to_string());
+            }
+            continue;
+        };
+        let index = inventory.packages.len();
+        match ecosystem {
+            Ecosystem::Npm => {
+                npm_roots.insert(dir, index);
+            }
+            Ecosystem::Pypi => {
+                let record = format!("{}/RECORD", dir);
+                let listed = files
+                    .get(record.as_str())
+                    .and_then(|e| read(&e.vnode))
+                    .map(|c| parse_record(&c));
+                match listed {
+                    Some(listed) => {
+                        let site = parent(dir);
+                        for rel in listed {
+                            if let Some(p) = join(site, &rel) {
+                                claimed.entry(p).or_insert(index);
+                            }
+                        }
+                    }
+                    None => {
+                        for &p in files.keys().filter(|p| covers(dir, p)) {
+                            claimed.entry(p.to_string()).or_insert(index);
+                        }
+                    }
+                }
+            }
+        }
+        inventory.packages.push(Package {
+            ecosystem,
+            name,
+            version,
+            origin: path.to_string(),
+            files: BTreeMap::new(),
+        });
+    }
+
+    for (&path, entry) in &files {
+        let owner = claimed.get(path).copied().or_else(|| {
+            // The innermost npm package above the file
+            let mut dir = parent(path);
+            loop {
+                if let Some(&index) = npm_roots.get(dir) {
+                    return Some(index);
+                }
+                if dir == "/" {
+                    return None;
+                }
+                dir = parent(dir);
+            }
+        });
+        match owner {
+            Some(index) => {
+                inventory.packages[index]
+                    .files
+                    .insert(path.to_string(), Locked::of(entry).hash);
+            }
+            None => inventory.unowned += 1,
+        }
+    }
+    inventory
+        .packages
+        .sort_by(|a, b| (&a.name, &a.version, &a.origin).cmp(&(&b.name, &b.version, &b.origin)));
+    inventory
+}
+
+/// Manifest key as a path relative to the project
+fn relative(path: &str) -> &str {
+    path.trim_start_matches('/')
+}
+
+fn cyclonedx(inventory: &Inventory, project: &str, serial: &str, timestamp: &str) -> Value {
+    let components: Vec<Value> = inventory
+        .packages
+        .iter()
+        .map(|pkg| {
+            let purl = pkg.purl();
+            let files: Vec<Value> = pkg
+                .files
+                .iter()
+                .map(|(path, hash)| {
+                    json!({
+                        "type": "file",
+                        "name": relative(path),
+                        "hashes": [{"alg": "BLAKE3", "content": CasStore::hash_to_hex(hash)}],
+                    })
+                })
+                .collect();
+            let mut component = json!({
+                "type": "library",
+                "bom-ref": format!("{}#{}", purl, relative(&pkg.origin)),
+                "name": pkg.name,
+                "purl": purl,
+                "components": files,
+            });
+            if let Some(ref version) = pkg.version {
+                component["version"] = json!(version);
+            }
+            component
+        })
+        .collect();
+    json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "serialNumber": format!("urn:uuid:{}", serial),
+        "version": 1,
+        "metadata": {
+            "timestamp": timestamp,
+            "tools": {
+                "components": [{
+                    "type": "application",
+                    "name": "vrift",
+                    "version": env!("CARGO_PKG_VERSION"),
+                }],
+            },
+            "component": {"type": "application", "name": project},
+        },
+        "components": components,
+    })
+}
+
+fn spdx(inventory: &Inventory, project: &str, serial: &str, timestamp: &str) -> Value {
+    let mut packages = Vec::new();
+    let mut files = Vec::new();
+    let mut relationships = Vec::new();
+    for (i, pkg) in inventory.packages.iter().enumerate() {
+        let pkg_id = format!("SPDXRef-Package-{}", i + 1);
+        let mut package = json!({
+            "name": pkg.name,
+            "SPDXID": pkg_id,
+            "downloadLocation": "NOASSERTION",
+            "filesAnalyzed": false,
+            "externalRefs": [{
+                "referenceCategory": "PACKAGE-MANAGER",
+                "referenceType": "purl",
+                "referenceLocator": pkg.purl(),
+            }],
+        });
+        if let Some(ref version) = pkg.version {
+            package["versionInfo"] = json!(version);
+        }
+        packages.push(package);
+        relationships.push(json!({
+            "spdxElementId": "SPDXRef-DOCUMENT",
+            "relationshipType": "DESCRIBES",
+            "relatedSpdxElement": pkg_id,
+        }));
+        for (path, hash) in &pkg.files {
+            let file_id = format!("SPDXRef-File-{}", files.len() + 1);
+            files.push(json!({
+                "fileName": format!("./{}", relative(path)),
+                "SPDXID": file_id,
+                "checksums": [{
+                    "algorithm": "BLAKE3",
+                    "checksumValue": CasStore::hash_to_hex(hash),
+                }],
+            }));
+            relationships.push(json!({
+                "spdxElementId": pkg_id,
+                "relationshipType": "CONTAINS",
+                "relatedSpdxElement": file_id,
+            }));
+        }
+    }
+    json!({
+        "spdxVersion": "SPDX-2.3",
+        "dataLicense": "CC0-1.0",
+        "SPDXID": "SPDXRef-DOCUMENT",
+        "name": project,
+        "documentNamespace": format!("https://github.com/velo-sh/velo-rift/spdx/{}-{}", project, serial),
+        "creationInfo": {
+            "created": timestamp,
+            "creators": [format!("Tool: vrift-{}", env!("CARGO_PKG_VERSION"))],
+        },
+        "packages": packages,
+        "files": files,
+        "relationships": relationships,
+    })
+}
+
+/// Write the SBOM of `path` (the whole project by default) to `output`, or
+/// to stdout
+pub fn cmd_sbom(
+    project_dir: &Path,
+    cas_root_override: Option<&Path>,
+    path: Option<&Path>,
+    manifest: Option<&Path>,
+    format: SbomFormat,
+    output: Option<&Path>,
+) -> Result<()> {
+    let project_root = normalize_for_ipc(project_dir).context("resolve project path")?;
+    let root = match path {
+        Some(p) => crate::path_to_manifest_key(&project_root, p)?,
+        None => "/".to_string(),
+    };
+    let entries = load_entries(&project_root, manifest, &root)?;
+    if !entries.iter().any(|(p, _)| covers(&root, p)) {
+        bail!("Not in manifest: {}", root);
+    }
+
+    // The daemon's CAS, where ingest put the blobs
+    let cas_root = match cas_root_override {
+        Some(root) => root.to_path_buf(),
+        None => {
+            let cfg = vrift_config::Config::load_for_project(&project_root).unwrap_or_default();
+            vrift_manifest::normalize_path(&cfg.cas_root().to_string_lossy())
+        }
+    };
+    let cas = if cas_root.exists() {
+        Some(CasStore::new(&cas_root)?)
+    } else {
+        None
+    };
+    let inventory = detect(&entries, &root, |vnode| match vnode.inline_content() {
+        Some(content) => Some(content.to_vec()),
+        None if vnode.is_external() => {
+            let target = cas.as_ref()?.get(&vnode.content_hash).ok()?;
+            std::fs::read(String::from_utf8(target).ok()?).ok()
+        }
+        None if vnode.is_compressed() || vnode.is_encrypted() => None,
+        None => cas.as_ref()?.get(&vnode.content_hash).ok(),
+    });
+    for path in &inventory.skipped {
+        eprintln!("Warning: could not read package metadata {}; skipped", path);
+    }
+
+    let project = project_root
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "project".to_string());
+    let serial = uuid::Uuid::new_v4().to_string();
+    let timestamp = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+    let document = match format {
+        SbomFormat::Cyclonedx => cyclonedx(&inventory, &project, &serial, &timestamp),
+        SbomFormat::Spdx => spdx(&inventory, &project, &serial, &timestamp),
+    };
+    let text = serde_json::to_string_pretty(&document)? + "\n";
+    match output {
+        Some(file) => std::fs::write(file, text)
+            .with_context(|| format!("Failed to write {}", file.display()))?,
+        None => print!("{}", text),
+    }
+
+    let files: usize = inventory.packages.iter().map(|p| p.files.len()).sum();
+    eprintln!(
+        "{} packages, {} files under {} ({} files outside any package)",
+        inventory.packages.len(),
+        files,
+        root,
+        inventory.unowned
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vrift_manifest::lmdb::AssetTier;
+
+    const SAMPLE: &[(&str, &[u8])] = &[
+        ("/package.json", br#"{"name": "app", "private": true}"#),
+        ("/src/app.js", b"app"),
+        (
+            "/node_modules/@scope/pkg/package.json",
+            br#"{"name": "@scope/pkg", "version": "1.2.3"}"#,
+        ),
+        ("/node_modules/@scope/pkg/index.js", b"a"),
+        ("/node_modules/@scope/pkg/esm/package.json", br#"{"type": "module"}"#),
+        ("/node_modules/@scope/pkg/esm/index.mjs", b"b"),
+        (
+            "/node_modules/@scope/pkg/node_modules/dep/package.json",
+            br#"{"name": "dep", "version": "0.1.0"}"#,
+        ),
+        ("/node_modules/@scope/pkg/node_modules/dep/lib.js", b"c"),
+        (
+            "/venv/lib/site-packages/Foo_Bar-2.0.dist-info/METADATA",
+            b"Metadata-Version: 2.1\nName: Foo_Bar\nVersion: 2.0\n\nName: not a header\n",
+        ),
+        (
+            "/venv/lib/site-packages/Foo_Bar-2.0.dist-info/RECORD",
+            b"foo_bar/__init__.py,sha256=x,1\n\"foo_bar/a,b.py\",,\n../../bin/foo,,\nFoo_Bar-2.0.dist-info/RECORD,,\n",
+        ),
+        ("/venv/lib/site-packages/foo_bar/__init__.py", b"d"),
+        ("/venv/lib/site-packages/foo_bar/a,b.py", b"e"),
+        ("/venv/bin/foo", b"f"),
+        ("/venv/lib/site-packages/stray.py", b"g"),
+    ];
+
+    fn sample() -> Vec<(String, ManifestEntry)> {
+        SAMPLE
+            .iter()
+            .map(|(p, c)| {
+                let entry = ManifestEntry {
+                    vnode: VnodeEntry::new_file(
+                        CasStore::compute_hash(c),
+                        c.len() as u64,
+                        1,
+                        0o644,
+                    ),
+                    tier: AssetTier::Tier1Immutable,
+                    stale: false,
+                    ingested_at: 0,
+                };
+                (p.to_string(), entry)
+            })
+            .collect()
+    }
+
+    /// Content of a `SAMPLE` entry, as the CAS would return it
+    fn read(vnode: &VnodeEntry) -> Option<Vec<u8>> {
+        SAMPLE
+            .iter()
+            .map(|(_, c)| *c)
+            .find(|c| CasStore::compute_hash(c) == vnode.content_hash)
+            .map(<[u8]>::to_vec)
+    }
+
+    fn find<'a>(inventory: &'a Inventory, name: &str) -> &'a Package {
+        inventory
+            .packages
+            .iter()
+            .find(|p| p.name == name)
+            .unwrap_or_else(|| panic!("no package {}", name))
+    }
+
+    fn paths(pkg: &Package) -> Vec<&str> {
+        pkg.files.keys().map(String::as_str).collect()
+    }
+
+    #[test]
+    fn test_detect_npm_packages_innermost_wins() {
+        let inventory = detect(&sample(), "/", read);
+        let names: Vec<&str> = inventory.packages.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["@scope/pkg", "Foo_Bar", "app", "dep"]);
+
+        let pkg = find(&inventory, "@scope/pkg");
+        assert_eq!(pkg.purl(), "pkg:npm/%40scope/pkg@1.2.3");
+        assert_eq!(
+            paths(pkg),
+            [
+                "/node_modules/@scope/pkg/esm/index.mjs",
+                "/node_modules/@scope/pkg/esm/package.json",
+                "/node_modules/@scope/pkg/index.js",
+                "/node_modules/@scope/pkg/package.json",
+            ]
+        );
+        assert_eq!(
+            paths(find(&inventory, "dep")),
+            [
+                "/node_modules/@scope/pkg/node_modules/dep/lib.js",
+                "/node_modules/@scope/pkg/node_modules/dep/package.json",
+            ]
+        );
+        assert_eq!(
+            find(&inventory, "dep").files["/node_modules/@scope/pkg/node_modules/dep/lib.js"],
+            CasStore::compute_hash(b"c")
+        );
+    }
+
+    #[test]
+    fn test_detect_python_packages_by_record() {
+        let inventory = detect(&sample(), "/venv", read);
+        assert_eq!(inventory.packages.len(), 1);
+        let pkg = &inventory.packages[0];
+        assert_eq!(pkg.purl(), "pkg:pypi/foo-bar@2.0");
+        assert_eq!(
+            paths(pkg),
+            [
+                "/venv/bin/foo",
+                "/venv/lib/site-packages/Foo_Bar-2.0.dist-info/RECORD",
+                "/venv/lib/site-packages/foo_bar/__init__.py",
+                "/venv/lib/site-packages/foo_bar/a,b.py",
+            ]
+        );
+        // stray.py and METADATA itself, which RECORD does not list here
+        assert_eq!(inventory.unowned, 2);
+    }
+
+    #[test]
+    fn test_unreadable_metadata_is_reported() {
+        let inventory = detect(&sample(), "/venv", |_| None);
+        assert!(inventory.packages.is_empty());
+        assert_eq!(
+            inventory.skipped,
+            ["/venv/lib/site-packages/Foo_Bar-2.0.dist-info/METADATA"]
+        );
+    }
+
+    #[test]
+    fn test_documents() {
+        let inventory = detect(&sample(), "/node_modules", read);
+        let bom = cyclonedx(
+            &inventory,
+            "demo",
+            "00000000-0000-0000-0000-000000000000",
+            "t",
+        );
+        assert_eq!(bom["specVersion"], "1.5");
+        assert_eq!(bom["components"][1]["purl"], "pkg:npm/dep@0.1.0");
+        let file = &bom["components"][1]["components"][0];
+        assert_eq!(
+            file["name"],
+            "node_modules/@scope/pkg/node_modules/dep/lib.js"
+        );
+        assert_eq!(file["hashes"][0]["alg"], "BLAKE3");
+        assert_eq!(
+            file["hashes"][0]["content"],
+            CasStore::hash_to_hex(&CasStore::compute_hash(b"c"))
+        );
+
+        let doc = spdx(
+            &inventory,
+            "demo",
+            "00000000-0000-0000-0000-000000000000",
+            "t",
+        );
+        assert_eq!(doc["packages"].as_array().unwrap().len(), 2);
+        assert_eq!(doc["files"].as_array().unwrap().len(), 6);
+        // One DESCRIBES per package, one CONTAINS per file
+        assert_eq!(doc["relationships"].as_array().unwrap().len(), 8);
+        assert_eq!(doc["files"][0]["checksums"][0]["algorithm"], "BLAKE3");
+    }
+}
@@ -0,0 +1,543 @@
+//! # vrift selftest
+//!
+//! End-to-end check of the inception layer on this machine. A throwaway
+//! project is ingested in phantom mode, so its files exist only in the VFS,
+//! and `vrift selftest --helper` is re-executed under the shim to walk a
+//! canonical sequence against it: stat, open, read, readdir, a CoW write,
+//! close and a re-stat that must see the new size.
+//!
+//! Because nothing is left on disk, a call that succeeds was intercepted.
+//! Besides the canonical steps the helper probes sibling entry points
+//! (`lstat`, `fstatat`, `statx`, ...) and the parent prints everything as a
+//! capability matrix for the current OS/arch.
+
+use std::ffi::{CStr, CString};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use console::{style, Emoji};
+use vrift_config::path::normalize_for_ipc;
+
+static CHECK: Emoji<'_, '_> = Emoji("✔ ", "[ok] ");
+static CROSS: Emoji<'_, '_> = Emoji("✘ ", "[!!] ");
+static DOT: Emoji<'_, '_> = Emoji("● ", "[-] ");
+
+const FILE_DIR: &str = "src";
+const FILE_NAME: &str = "hello.txt";
+const ORIGINAL: &[u8] = b"vrift selftest: original content\n";
+const UPDATED: &[u8] = b"vrift selftest: rewritten through the VFS\n";
+
+/// Steps that must pass for the self-test to succeed, in execution order
+const CANONICAL: &[&str] = &[
+    "stat", "open", "read", "readdir", "write", "close", "re-stat",
+];
+
+/// How long the re-stat waits for the write-back to become visible
+const RESTAT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Marker for result lines, so shim debug output on stdout is ignored
+const RESULT_TAG: &str = "VRIFT-SELFTEST";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Ok,
+    Fail,
+    Skip,
+}
+
+impl Outcome {
+    fn as_str(self) -> &'static str {
+        match self {
+            Outcome::Ok => "ok",
+            Outcome::Fail => "fail",
+            Outcome::Skip => "skip",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "ok" => Some(Outcome::Ok),
+            "fail" => Some(Outcome::Fail),
+            "skip" => Some(Outcome::Skip),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct StepResult {
+    name: String,
+    outcome: Outcome,
+    detail: String,
+}
+
+/// Extract step results from the helper's stdout
+fn parse_results(stdout: &str) -> Vec<StepResult> {
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(4, '\t');
+            if fields.next()? != RESULT_TAG {
+                return None;
+            }
+            let name = fields.next()?.to_string();
+            let outcome = Outcome::parse(fields.next()?)?;
+            let detail = fields.next().unwrap_or("").to_string();
+            Some(StepResult {
+                name,
+                outcome,
+                detail,
+            })
+        })
+        .collect()
+}
+
+/// Canonical steps that did not pass, including ones missing from the output
+fn failed_steps(results: &[StepResult]) -> Vec<&'static str> {
+    CANONICAL
+        .iter()
+        .copied()
+        .filter(|step| {
+            !results
+                .iter()
+                .any(|r| r.name == *step && r.outcome == Outcome::Ok)
+        })
+        .collect()
+}
+
+pub async fn cmd_selftest(keep: bool) -> Result<()> {
+    eprintln!();
+    eprintln!("{}", style("🧪 Velo Rift Self-Test").bold().cyan());
+    eprintln!("{}", style("─".repeat(40)).dim());
+
+    let scratch = tempfile::Builder::new()
+        .prefix("vrift-selftest-")
+        .tempdir()
+        .context("Failed to create scratch project")?;
+    let project_root = normalize_for_ipc(scratch.path()).context("resolve scratch path")?;
+    let file_dir = project_root.join(FILE_DIR);
+    let file_path = file_dir.join(FILE_NAME);
+    std::fs::create_dir_all(&file_dir)?;
+    std::fs::write(&file_path, ORIGINAL)?;
+
+    let vrift_dir = project_root.join(".vrift");
+    std::fs::create_dir_all(vrift_dir.join("locks"))?;
+    let project_id = vrift_config::path::compute_project_id(&project_root);
+    let manifest_path = vrift_config::path::get_manifest_db_path(&project_id)
+        .context("Could not determine manifest path")?;
+    let ingest = crate::daemon::ingest_via_daemon(
+        &project_root,
+        &manifest_path,
+        None,
+        true,
+        false,
+        Some(String::new()),
+        None,
+        false,
+        Default::default(),
+    )
+    .await
+    .context("Failed to ingest scratch project")?;
+    // Phantom mode moves content into the CAS; anything left behind would
+    // let un-intercepted calls pass.
+    if file_path.exists() {
+        std::fs::remove_file(&file_path)?;
+    }
+    eprintln!(
+        "  {} {}",
+        DOT,
+        style(format!(
+            "Scratch project {} ({} file ingested)",
+            project_root.display(),
+            ingest.files
+        ))
+        .dim()
+    );
+
+    let inception_path = crate::inception::find_inception_library(&project_root)?;
+    let daemon_conn = crate::daemon::connect_to_daemon(&project_root).await.ok();
+    let cfg = vrift_config::Config::load_for_project(&project_root).unwrap_or_else(|e| {
+        eprintln!("Warning: Config load failed: {}. Using defaults.", e);
+        vrift_config::Config::default()
+    });
+
+    let mut cmd = std::process::Command::new(std::env::current_exe()?);
+    cmd.arg("selftest").arg("--helper").arg(&project_root);
+    cmd.current_dir(&project_root);
+    for (key, value) in cfg.shim_env() {
+        cmd.env(key, value);
+    }
+    cmd.env("VRIFT_MANIFEST", vrift_dir.join("manifest.lmdb"))
+        .env("VRIFT_PROJECT_ROOT", &project_root)
+        .env("VRIFT_VFS_PREFIX", &project_root);
+    if let Some(ref conn) = daemon_conn {
+        if !conn.vdird_socket.is_empty() {
+            cmd.env("VRIFT_VDIRD_SOCKET", &conn.vdird_socket);
+        }
+        if !conn.vdir_mmap_path.is_empty() {
+            cmd.env("VRIFT_VDIR_MMAP", &conn.vdir_mmap_path);
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        cmd.env("DYLD_INSERT_LIBRARIES", &inception_path)
+            .env("DYLD_FORCE_FLAT_NAMESPACE", "1");
+    }
+    #[cfg(target_os = "linux")]
+    {
+        cmd.env("LD_PRELOAD", &inception_path);
+    }
+
+    let output = cmd.output().context("Failed to run self-test helper")?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let results = parse_results(&stdout);
+
+    eprintln!();
+    eprintln!(
+        "{}",
+        style(format!(
+            "Capability matrix ({}/{})",
+            std::env::consts::OS,
+            std::env::consts::ARCH
+        ))
+        .bold()
+    );
+    for r in &results {
+        let canonical = CANONICAL.contains(&r.name.as_str());
+        let line = format!("{:<10} {}", r.name, r.detail);
+        match (r.outcome, canonical) {
+            (Outcome::Ok, _) => eprintln!("  {} {}", CHECK, style(line).green()),
+            (Outcome::Fail, true) => eprintln!("  {} {}", CROSS, style(line).red()),
+            // Optional entry points are informational
+            (Outcome::Fail, false) => eprintln!("  {} {}", CROSS, style(line).yellow()),
+            (Outcome::Skip, _) => eprintln!("  {} {}", DOT, style(line).dim()),
+        }
+    }
+
+    let failed = failed_steps(&results);
+    let passed = failed.is_empty();
+    if !passed && !output.stderr.is_empty() {
+        eprintln!();
+        eprintln!("{}", style("Helper stderr").bold());
+        eprintln!("{}", String::from_utf8_lossy(&output.stderr).trim_end());
+    }
+
+    if keep {
+        let kept = scratch.keep();
+        eprintln!();
+        eprintln!("  {} Scratch project kept at {}", DOT, kept.display());
+    } else {
+        // Explicitly, as the failure path below exits without unwinding
+        drop(scratch);
+        let _ = std::fs::remove_dir_all(&manifest_path);
+    }
+
+    eprintln!();
+    eprintln!("{}", style("─".repeat(40)).dim());
+    if passed {
+        eprintln!(
+            "{} {}",
+            CHECK,
+            style("Interposition works end to end").green().bold()
+        );
+        Ok(())
+    } else {
+        if !output.status.success() && results.is_empty() {
+            eprintln!("  Helper exited with {}", output.status);
+        }
+        eprintln!(
+            "{} {}",
+            CROSS,
+            style(format!("Failed steps: {}", failed.join(", ")))
+                .red()
+                .bold()
+        );
+        std::process::exit(1);
+    }
+}
+
+// ============================================================================
+// Helper: runs under the inception layer
+// ============================================================================
+
+fn report(name: &str, outcome: Outcome, detail: impl AsRef<str>) {
+    println!(
+        "{}\t{}\t{}\t{}",
+        RESULT_TAG,
+        name,
+        outcome.as_str(),
+        detail.as_ref()
+    );
+}
+
+fn errno_detail(call: &str) -> String {
+    format!("{}: {}", call, std::io::Error::last_os_error())
+}
+
+/// Entry point of `vrift selftest --helper <root>`
+///
+/// Calls libc directly so each step exercises exactly the symbol it names
+/// (std would route metadata through `statx` on Linux, for instance).
+pub fn run_helper(project_root: &Path) -> Result<()> {
+    let dir = CString::new(project_root.join(FILE_DIR).to_string_lossy().as_bytes())?;
+    let file = CString::new(
+        project_root
+            .join(FILE_DIR)
+            .join(FILE_NAME)
+            .to_string_lossy()
+            .as_bytes(),
+    )?;
+
+    // stat
+    match stat_size(&file) {
+        Ok(size) if size == ORIGINAL.len() as libc::off_t => {
+            report("stat", Outcome::Ok, format!("size {}", size))
+        }
+        Ok(size) => report(
+            "stat",
+            Outcome::Fail,
+            format!("size {}, expected {}", size, ORIGINAL.len()),
+        ),
+        Err(e) => report("stat", Outcome::Fail, e),
+    }
+
+    // open + read
+    let fd = unsafe { libc::open(file.as_ptr(), libc::O_RDONLY) };
+    if fd >= 0 {
+        report("open", Outcome::Ok, format!("O_RDONLY fd {}", fd));
+        let mut buf = [0u8; 256];
+        let n = unsafe { libc::read(fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len()) };
+        if n < 0 {
+            report("read", Outcome::Fail, errno_detail("read"));
+        } else if &buf[..n as usize] == ORIGINAL {
+            report("read", Outcome::Ok, format!("{} bytes, content matches", n));
+        } else {
+            report(
+                "read",
+                Outcome::Fail,
+                format!("{} bytes, content differs", n),
+            );
+        }
+        probe_fstat(fd);
+        unsafe { libc::close(fd) };
+    } else {
+        report("open", Outcome::Fail, errno_detail("open"));
+        report("read", Outcome::Skip, "open failed");
+    }
+
+    // readdir
+    match list_dir(&dir) {
+        Ok(names) if names.iter().any(|n| n == FILE_NAME) => {
+            report("readdir", Outcome::Ok, format!("{} entries", names.len()))
+        }
+        Ok(names) => report(
+            "readdir",
+            Outcome::Fail,
+            format!("{} not among {} entries", FILE_NAME, names.len()),
+        ),
+        Err(e) => report("readdir", Outcome::Fail, e),
+    }
+
+    probe_sibling_calls(&file);
+
+    // write (CoW) + close
+    let fd = unsafe { libc::open(file.as_ptr(), libc::O_WRONLY | libc::O_TRUNC) };
+    if fd < 0 {
+        report(
+            "write",
+            Outcome::Fail,
+            errno_detail("open(O_WRONLY|O_TRUNC)"),
+        );
+        report("close", Outcome::Skip, "write failed");
+        report("re-stat", Outcome::Skip, "write failed");
+        return Ok(());
+    }
+    let n = unsafe { libc::write(fd, UPDATED.as_ptr() as *const libc::c_void, UPDATED.len()) };
+    if n == UPDATED.len() as isize {
+        report("write", Outcome::Ok, format!("{} bytes via CoW", n));
+    } else if n < 0 {
+        report("write", Outcome::Fail, errno_detail("write"));
+    } else {
+        report("write", Outcome::Fail, format!("short write: {} bytes", n));
+    }
+    if unsafe { libc::close(fd) } == 0 {
+        report("close", Outcome::Ok, "committed");
+    } else {
+        report("close", Outcome::Fail, errno_detail("close"));
+    }
+
+    // re-stat: the write-back may be applied asynchronously
+    let deadline = Instant::now() + RESTAT_TIMEOUT;
+    loop {
+        let size = stat_size(&file);
+        match size {
+            Ok(size) if size == UPDATED.len() as libc::off_t => {
+                report("re-stat", Outcome::Ok, format!("size {}", size));
+                break;
+            }
+            _ if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(50)),
+            Ok(size) => {
+                report(
+                    "re-stat",
+                    Outcome::Fail,
+                    format!("size {}, expected {}", size, UPDATED.len()),
+                );
+                break;
+            }
+            Err(e) => {
+                report("re-stat", Outcome::Fail, e);
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn stat_size(path: &CStr) -> std::result::Result<libc::off_t, String> {
+    let mut st: libc::stat = unsafe { std::mem::zeroed() };
+    if unsafe { libc::stat(path.as_ptr(), &mut st) } != 0 {
+        return Err(errno_detail("stat"));
+    }
+    if st.st_mode & libc::S_IFMT != libc::S_IFREG {
+        return Err(format!("not a regular file (mode {:o})", st.st_mode));
+    }
+    Ok(st.st_size)
+}
+
+fn list_dir(dir: &CStr) -> std::result::Result<Vec<String>, String> {
+    let handle = unsafe { libc::opendir(dir.as_ptr()) };
+    if handle.is_null() {
+        return Err(errno_detail("opendir"));
+    }
+    let mut names = Vec::new();
+    loop {
+        let entry = unsafe { libc::readdir(handle) };
+        if entry.is_null() {
+            break;
+        }
+        let name = unsafe { CStr::from_ptr((*entry).d_name.as_ptr()) };
+        let name = name.to_string_lossy();
+        if name != "." && name != ".." {
+            names.push(name.into_owned());
+        }
+    }
+    unsafe { libc::closedir(handle) };
+    Ok(names)
+}
+
+fn probe_fstat(fd: libc::c_int) {
+    let mut st: libc::stat = unsafe { std::mem::zeroed() };
+    if unsafe { libc::fstat(fd, &mut st) } != 0 {
+        report("fstat", Outcome::Fail, errno_detail("fstat"));
+    } else if st.st_size == ORIGINAL.len() as libc::off_t {
+        report("fstat", Outcome::Ok, format!("size {}", st.st_size));
+    } else {
+        report("fstat", Outcome::Fail, format!("size {}", st.st_size));
+    }
+}
+
+/// Entry points outside the canonical sequence; failures are reported but
+/// do not fail the self-test
+fn probe_sibling_calls(file: &CStr) {
+    let mut st: libc::stat = unsafe { std::mem::zeroed() };
+    if unsafe { libc::lstat(file.as_ptr(), &mut st) } == 0 {
+        report("lstat", Outcome::Ok, format!("size {}", st.st_size));
+    } else {
+        report("lstat", Outcome::Fail, errno_detail("lstat"));
+    }
+
+    if unsafe { libc::fstatat(libc::AT_FDCWD, file.as_ptr(), &mut st, 0) } == 0 {
+        report("fstatat", Outcome::Ok, format!("size {}", st.st_size));
+    } else {
+        report("fstatat", Outcome::Fail, errno_detail("fstatat"));
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        let mut stx: libc::statx = unsafe { std::mem::zeroed() };
+        if unsafe {
+            libc::statx(
+                libc::AT_FDCWD,
+                file.as_ptr(),
+                0,
+                libc::STATX_BASIC_STATS,
+                &mut stx,
+            )
+        } == 0
+        {
+            report("statx", Outcome::Ok, format!("size {}", stx.stx_size));
+        } else {
+            report("statx", Outcome::Fail, errno_detail("statx"));
+        }
+    }
+    #[cfg(not(target_os = "linux"))]
+    report("statx", Outcome::Skip, "not available on this OS");
+
+    if unsafe { libc::access(file.as_ptr(), libc::R_OK) } == 0 {
+        report("access", Outcome::Ok, "R_OK");
+    } else {
+        report("access", Outcome::Fail, errno_detail("access"));
+    }
+
+    let fd = unsafe { libc::openat(libc::AT_FDCWD, file.as_ptr(), libc::O_RDONLY) };
+    if fd >= 0 {
+        report("openat", Outcome::Ok, format!("O_RDONLY fd {}", fd));
+        unsafe { libc::close(fd) };
+    } else {
+        report("openat", Outcome::Fail, errno_detail("openat"));
+    }
+
+    let mut buf = [0 as libc::c_char; libc::PATH_MAX as usize];
+    if unsafe { libc::realpath(file.as_ptr(), buf.as_mut_ptr()) }.is_null() {
+        report("realpath", Outcome::Fail, errno_detail("realpath"));
+    } else {
+        report("realpath", Outcome::Ok, "resolved");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_results_ignores_foreign_lines() {
+        let stdout = "[vrift-shim] init\n\
+                      VRIFT-SELFTEST\tstat\tok\tsize 33\n\
+                      VRIFT-SELFTEST\tstatx\tfail\tstatx: No such file or directory (os error 2)\n\
+                      VRIFT-SELFTEST\tread\tskip\n\
+                      VRIFT-SELFTEST\tbogus\tmaybe\tx\n";
+        let results = parse_results(stdout);
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].name, "stat");
+        assert_eq!(results[0].outcome, Outcome::Ok);
+        assert_eq!(results[0].detail, "size 33");
+        assert_eq!(results[1].outcome, Outcome::Fail);
+        assert_eq!(results[2].outcome, Outcome::Skip);
+        assert_eq!(results[2].detail, "");
+    }
+
+    #[test]
+    fn test_failed_steps_counts_missing_and_skipped() {
+        let mut results: Vec<StepResult> = CANONICAL
+            .iter()
+            .map(|name| StepResult {
+                name: name.to_string(),
+                outcome: Outcome::Ok,
+                detail: String::new(),
+            })
+            .collect();
+        // Optional probes never fail the run
+        results.push(StepResult {
+            name: "statx".into(),
+            outcome: Outcome::Fail,
+            detail: String::new(),
+        });
+        assert!(failed_steps(&results).is_empty());
+
+        results[2].outcome = Outcome::Skip;
+        results.retain(|r| r.name != "re-stat");
+        assert_eq!(failed_steps(&results), vec!["read", "re-stat"]);
+    }
+}
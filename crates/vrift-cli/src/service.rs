@@ -0,0 +1,359 @@
+//! # vriftd as a managed service
+//!
+//! Generates and loads a systemd user unit (Linux) or a launchd agent
+//! (macOS) for vriftd. With socket activation the service manager owns the
+//! IPC socket and starts the daemon on the first connection, so clients
+//! never race a daemon that is still starting up.
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+#[cfg(target_os = "macos")]
+const LAUNCHD_LABEL: &str = "sh.velo.vriftd";
+
+fn vriftd_binary() -> Result<PathBuf> {
+    let current_exe = std::env::current_exe()?;
+    let bin_dir = current_exe.parent().context("Failed to get bin dir")?;
+    let vriftd_bin = bin_dir.join("vriftd");
+
+    if !vriftd_bin.exists() {
+        anyhow::bail!("vriftd binary not found in {}", bin_dir.display());
+    }
+    Ok(vriftd_bin)
+}
+
+/// Install vriftd as a background service and start it (or its socket)
+pub async fn install(socket_activation: bool) -> Result<()> {
+    let vriftd_bin = vriftd_binary()?;
+    let socket = vrift_config::config().socket_path().to_path_buf();
+    let home = dirs::home_dir().context("Could not find home directory")?;
+
+    #[cfg(target_os = "macos")]
+    install_launchd(&home, &vriftd_bin, &socket, socket_activation)?;
+
+    #[cfg(target_os = "linux")]
+    install_systemd(&home, &vriftd_bin, &socket, socket_activation)?;
+
+    // The first connection starts a socket-activated daemon, so this also
+    // checks the activation path end to end.
+    match crate::daemon::ping(std::time::Duration::from_secs(10)).await {
+        Ok(health) => println!(
+            "✅ vriftd {} is up (pid {}, socket {}).",
+            health.version,
+            health.pid,
+            socket.display()
+        ),
+        Err(e) => println!("⚠️  vriftd did not answer a health check: {:#}", e),
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn install_launchd(
+    home: &Path,
+    vriftd_bin: &Path,
+    socket: &Path,
+    socket_activation: bool,
+) -> Result<()> {
+    let agents_dir = home.join("Library/LaunchAgents");
+    std::fs::create_dir_all(&agents_dir)?;
+
+    // launchd binds SockPathName itself but does not create its directory
+    if let Some(parent) = socket.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let plist_path = agents_dir.join(format!("{}.plist", LAUNCHD_LABEL));
+    let plist_content = launchd_plist(vriftd_bin, socket, socket_activation);
+    std::fs::write(&plist_path, plist_content)?;
+    println!("Created launchd agent: {}", plist_path.display());
+
+    // Reload so an existing agent picks up the new definition
+    let _ = Command::new("launchctl")
+        .arg("unload")
+        .arg(&plist_path)
+        .output();
+    let status = Command::new("launchctl")
+        .arg("load")
+        .arg(&plist_path)
+        .status()?;
+
+    if !status.success() {
+        anyhow::bail!("Failed to load launchd agent {}", plist_path.display());
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn install_systemd(
+    home: &Path,
+    vriftd_bin: &Path,
+    socket: &Path,
+    socket_activation: bool,
+) -> Result<()> {
+    let systemd_dir = home.join(".config/systemd/user");
+    std::fs::create_dir_all(&systemd_dir)?;
+
+    let service_path = systemd_dir.join("vriftd.service");
+    std::fs::write(
+        &service_path,
+        systemd_service(vriftd_bin, socket_activation),
+    )?;
+    println!("Created systemd service: {}", service_path.display());
+
+    let socket_path = systemd_dir.join("vriftd.socket");
+    if socket_activation {
+        std::fs::write(&socket_path, systemd_socket(socket))?;
+        println!("Created systemd socket: {}", socket_path.display());
+    } else if socket_path.exists() {
+        systemctl(&["disable", "--now", "vriftd.socket"])?;
+        std::fs::remove_file(&socket_path)?;
+    }
+
+    systemctl(&["daemon-reload"])?;
+    // A daemon started by an earlier install holds the socket path
+    systemctl(&["stop", "vriftd.service"])?;
+    let unit = if socket_activation {
+        "vriftd.socket"
+    } else {
+        "vriftd.service"
+    };
+    if !systemctl(&["enable", "--now", unit])? {
+        anyhow::bail!("Failed to start {}", unit);
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn systemctl(args: &[&str]) -> Result<bool> {
+    Ok(Command::new("systemctl")
+        .arg("--user")
+        .args(args)
+        .status()?
+        .success())
+}
+
+/// Uninstall vriftd background service
+pub fn uninstall() -> Result<()> {
+    #[cfg(target_os = "macos")]
+    {
+        let home = dirs::home_dir().context("Could not find home directory")?;
+        let plist_path = home.join(format!("Library/LaunchAgents/{}.plist", LAUNCHD_LABEL));
+
+        if plist_path.exists() {
+            Command::new("launchctl")
+                .arg("unload")
+                .arg(&plist_path)
+                .status()?;
+            std::fs::remove_file(&plist_path)?;
+            println!("✅ vriftd service uninstalled.");
+        } else {
+            println!("ℹ️  vriftd service not found.");
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        let home = dirs::home_dir().context("Could not find home directory")?;
+        let systemd_dir = home.join(".config/systemd/user");
+        let service_path = systemd_dir.join("vriftd.service");
+        let socket_path = systemd_dir.join("vriftd.socket");
+
+        if service_path.exists() || socket_path.exists() {
+            if socket_path.exists() {
+                systemctl(&["disable", "--now", "vriftd.socket"])?;
+                std::fs::remove_file(&socket_path)?;
+            }
+            systemctl(&["disable", "--now", "vriftd.service"])?;
+            if service_path.exists() {
+                std::fs::remove_file(&service_path)?;
+            }
+            systemctl(&["daemon-reload"])?;
+            println!("✅ vriftd service uninstalled.");
+        } else {
+            println!("ℹ️  vriftd service not found.");
+        }
+    }
+
+    Ok(())
+}
+
+/// Restart vriftd background service
+pub fn restart() -> Result<()> {
+    #[cfg(target_os = "macos")]
+    {
+        let home = dirs::home_dir().context("Could not find home directory")?;
+        let plist_path = home.join(format!("Library/LaunchAgents/{}.plist", LAUNCHD_LABEL));
+
+        if plist_path.exists() {
+            Command::new("launchctl")
+                .arg("unload")
+                .arg(&plist_path)
+                .status()?;
+            Command::new("launchctl")
+                .arg("load")
+                .arg(&plist_path)
+                .status()?;
+            println!("✅ vriftd service restarted.");
+        } else {
+            println!("⚠️  vriftd service not found. Use 'install' first.");
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        systemctl(&["restart", "vriftd.service"])?;
+        println!("✅ vriftd service restarted.");
+    }
+
+    Ok(())
+}
+
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn launchd_plist(vriftd_bin: &Path, socket: &Path, socket_activation: bool) -> String {
+    // Socket-activated agents start on demand and are restarted only after
+    // a crash; otherwise the daemon runs from login.
+    let (launch, sockets) = if socket_activation {
+        (
+            r#"    <key>KeepAlive</key>
+    <dict>
+        <key>SuccessfulExit</key>
+        <false/>
+    </dict>"#
+                .to_string(),
+            format!(
+                r#"
+    <key>Sockets</key>
+    <dict>
+        <key>Listeners</key>
+        <dict>
+            <key>SockPathName</key>
+            <string>{}</string>
+            <key>SockPathMode</key>
+            <integer>384</integer>
+        </dict>
+    </dict>"#,
+                socket.display()
+            ),
+        )
+    } else {
+        (
+            r#"    <key>RunAtLoad</key>
+    <true/>
+    <key>KeepAlive</key>
+    <true/>"#
+                .to_string(),
+            String::new(),
+        )
+    };
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>sh.velo.vriftd</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{}</string>
+        <string>start</string>
+    </array>
+{}{}
+    <key>StandardErrorPath</key>
+    <string>/tmp/vriftd.err.log</string>
+    <key>StandardOutPath</key>
+    <string>/tmp/vriftd.out.log</string>
+</dict>
+</plist>
+"#,
+        vriftd_bin.display(),
+        launch,
+        sockets
+    )
+}
+
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn systemd_service(vriftd_bin: &Path, socket_activation: bool) -> String {
+    let (deps, install) = if socket_activation {
+        ("Requires=vriftd.socket\nAfter=vriftd.socket", "")
+    } else {
+        (
+            "After=network.target",
+            "\n[Install]\nWantedBy=default.target\n",
+        )
+    };
+    format!(
+        r#"[Unit]
+Description=Velo Rift Daemon
+{}
+
+[Service]
+ExecStart={} start
+Restart=on-failure
+RestartSec=5
+StandardOutput=append:/tmp/vriftd.log
+StandardError=append:/tmp/vriftd.log
+{}"#,
+        deps,
+        vriftd_bin.display(),
+        install
+    )
+}
+
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn systemd_socket(socket: &Path) -> String {
+    format!(
+        r#"[Unit]
+Description=Velo Rift Daemon socket
+
+[Socket]
+ListenStream={}
+SocketMode=0600
+DirectoryMode=0700
+
+[Install]
+WantedBy=sockets.target
+"#,
+        socket.display()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_systemd_units_for_socket_activation() {
+        let service = systemd_service(Path::new("/usr/bin/vriftd"), true);
+        assert!(service.contains("ExecStart=/usr/bin/vriftd start"));
+        assert!(service.contains("Requires=vriftd.socket"));
+        // Started through the socket, never on its own
+        assert!(!service.contains("[Install]"));
+
+        let socket = systemd_socket(Path::new("/run/user/1000/vrift.sock"));
+        assert!(socket.contains("ListenStream=/run/user/1000/vrift.sock"));
+        assert!(socket.contains("WantedBy=sockets.target"));
+
+        let plain = systemd_service(Path::new("/usr/bin/vriftd"), false);
+        assert!(plain.contains("WantedBy=default.target"));
+        assert!(!plain.contains("vriftd.socket"));
+    }
+
+    #[test]
+    fn test_launchd_plist_declares_listener_socket() {
+        let plist = launchd_plist(Path::new("/usr/bin/vriftd"), Path::new("/tmp/v.sock"), true);
+        // Name must match what vriftd passes to launch_activate_socket
+        assert!(plist.contains("<key>Listeners</key>"));
+        assert!(plist.contains("<string>/tmp/v.sock</string>"));
+        assert!(!plist.contains("RunAtLoad"));
+
+        let plain = launchd_plist(
+            Path::new("/usr/bin/vriftd"),
+            Path::new("/tmp/v.sock"),
+            false,
+        );
+        assert!(!plain.contains("Sockets"));
+        assert!(plain.contains("<key>RunAtLoad</key>"));
+    }
+}
@@ -0,0 +1,318 @@
+//! # vrift top
+//!
+//! Live view of VFS activity. Every interval it asks vriftd for its
+//! workspaces and jobs, and each workspace's vDird for its request and
+//! lookup counters (`VeloRequest::Metrics`, served on the control socket).
+//! Rates are the difference between two snapshots.
+//!
+//! Lookups are the manifest queries that reach vDird, i.e. the ones the
+//! shim could not answer from its mmaps. A hit is a path vDird knew, from
+//! the VDir overlay or from LMDB.
+
+use anyhow::Result;
+use clap::Args;
+use console::{style, Term};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use vrift_ipc::{JobInfo, WorkspaceInfo, WorkspaceMetrics, WorkspaceState};
+
+/// Hottest paths shown
+const HOT_PATHS: usize = 10;
+
+#[derive(Args, Debug)]
+pub struct TopArgs {
+    /// Seconds between refreshes
+    #[arg(short = 'd', long, default_value_t = 1.0)]
+    delay: f64,
+
+    /// Print one refresh and exit (the default when stdout is not a terminal)
+    #[arg(long)]
+    once: bool,
+}
+
+/// What the daemons reported at one point in time
+struct Sample {
+    at: Instant,
+    workspaces: Vec<(WorkspaceInfo, Option<WorkspaceMetrics>)>,
+    jobs: Vec<JobInfo>,
+}
+
+pub async fn run(args: TopArgs) -> Result<()> {
+    let delay = Duration::from_secs_f64(args.delay.max(0.1));
+    let term = Term::stdout();
+    let mut prev = sample().await?;
+
+    if args.once || !term.is_term() {
+        tokio::time::sleep(delay).await;
+        let cur = sample().await?;
+        for line in render(&cur, Some(&prev), usize::MAX) {
+            println!("{}", line);
+        }
+        return Ok(());
+    }
+
+    term.hide_cursor()?;
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+    let result = loop {
+        tokio::select! {
+            _ = &mut ctrl_c => break Ok(()),
+            _ = tokio::time::sleep(delay) => {}
+        }
+        let cur = match sample().await {
+            Ok(cur) => cur,
+            Err(e) => break Err(e),
+        };
+        let (_, width) = term.size();
+        term.clear_screen()?;
+        for line in render(&cur, Some(&prev), width as usize) {
+            term.write_line(&line)?;
+        }
+        prev = cur;
+    };
+    term.show_cursor()?;
+    result
+}
+
+async fn sample() -> Result<Sample> {
+    let workspaces = crate::daemon::list_workspaces().await?;
+    let jobs = crate::daemon::list_jobs().await?;
+    let mut sampled = Vec::with_capacity(workspaces.len());
+    for ws in workspaces {
+        let metrics = if ws.vdird_pid != 0 {
+            crate::daemon::workspace_metrics(std::path::Path::new(&ws.project_root)).await
+        } else {
+            None
+        };
+        sampled.push((ws, metrics));
+    }
+    Ok(Sample {
+        at: Instant::now(),
+        workspaces: sampled,
+        jobs: jobs
+            .into_iter()
+            .filter(|job| !job.state.is_finished())
+            .collect(),
+    })
+}
+
+/// Counter growth of one workspace between two snapshots
+#[derive(Debug, Default, PartialEq)]
+struct Activity {
+    requests: u64,
+    hits: u64,
+    vdir_hits: u64,
+    lookups: u64,
+    by_kind: HashMap<String, u64>,
+    by_path: HashMap<String, u64>,
+}
+
+/// Counter growth from `prev` to `cur`. A vDird that restarted in between
+/// (its uptime went back) counts from zero.
+fn activity(cur: &WorkspaceMetrics, prev: Option<&WorkspaceMetrics>) -> Activity {
+    let prev = prev.filter(|p| p.uptime_secs <= cur.uptime_secs);
+    let before = |list: fn(&WorkspaceMetrics) -> &Vec<vrift_ipc::NamedCount>, name: &str| {
+        prev.and_then(|p| list(p).iter().find(|c| c.name == name))
+            .map_or(0, |c| c.count)
+    };
+    let mut activity = Activity::default();
+    for count in &cur.requests {
+        let grown = count
+            .count
+            .saturating_sub(before(|m| &m.requests, &count.name));
+        activity.requests += grown;
+        if grown > 0 {
+            activity.by_kind.insert(count.name.clone(), grown);
+        }
+    }
+    for count in &cur.hot_paths {
+        let grown = count
+            .count
+            .saturating_sub(before(|m| &m.hot_paths, &count.name));
+        if grown > 0 {
+            activity.by_path.insert(count.name.clone(), grown);
+        }
+    }
+    let (vdir, lmdb, misses) = prev.map_or((0, 0, 0), |p| (p.vdir_hits, p.lmdb_hits, p.misses));
+    activity.vdir_hits = cur.vdir_hits.saturating_sub(vdir);
+    activity.hits = activity.vdir_hits + cur.lmdb_hits.saturating_sub(lmdb);
+    activity.lookups = activity.hits + cur.misses.saturating_sub(misses);
+    activity
+}
+
+fn render(cur: &Sample, prev: Option<&Sample>, width: usize) -> Vec<String> {
+    let secs = prev
+        .map(|p| cur.at.duration_since(p.at).as_secs_f64())
+        .unwrap_or(1.0)
+        .max(0.001);
+    let rate = |n: u64| n as f64 / secs;
+
+    let mut lines = vec![format!(
+        "vrift top - {} workspace(s), {} active job(s)",
+        cur.workspaces.len(),
+        cur.jobs.len()
+    )];
+    lines.push(String::new());
+    lines.push(
+        style(format!(
+            "{:<8} {:>9} {:>9} {:>6} {:>6}  WORKSPACE",
+            "STATE", "REQ/S", "LOOKUP/S", "HIT%", "VDIR%"
+        ))
+        .bold()
+        .to_string(),
+    );
+
+    let mut by_kind: HashMap<String, u64> = HashMap::new();
+    let mut by_path: Vec<(String, u64)> = Vec::new();
+    for (ws, metrics) in &cur.workspaces {
+        let state = match ws.state {
+            WorkspaceState::Ready => "ready",
+            WorkspaceState::Loading => "loading",
+            WorkspaceState::Failed => "failed",
+        };
+        let Some(metrics) = metrics else {
+            lines.push(format!(
+                "{:<8} {:>9} {:>9} {:>6} {:>6}  {}",
+                state, "-", "-", "-", "-", ws.project_root
+            ));
+            continue;
+        };
+        let before = prev.and_then(|p| {
+            p.workspaces
+                .iter()
+                .find(|(w, _)| w.project_root == ws.project_root)
+                .and_then(|(_, m)| m.as_ref())
+        });
+        let activity = activity(metrics, before);
+        lines.push(format!(
+            "{:<8} {:>9.1} {:>9.1} {:>6} {:>6}  {}",
+            state,
+            rate(activity.requests),
+            rate(activity.lookups),
+            percent(activity.hits, activity.lookups),
+            percent(activity.vdir_hits, activity.lookups),
+            ws.project_root
+        ));
+        for (kind, n) in activity.by_kind {
+            *by_kind.entry(kind).or_default() += n;
+        }
+        let root = ws.project_root.trim_end_matches('/');
+        for (path, n) in activity.by_path {
+            let shown = path
+                .strip_prefix(root)
+                .map(|rel| rel.trim_start_matches('/').to_string())
+                .unwrap_or(path);
+            by_path.push((shown, n));
+        }
+    }
+
+    let mut kinds: Vec<_> = by_kind.into_iter().collect();
+    kinds.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    lines.push(String::new());
+    lines.push(
+        style(format!("{:>9}  REQUEST", "PER SEC"))
+            .bold()
+            .to_string(),
+    );
+    for (kind, n) in kinds {
+        lines.push(format!("{:>9.1}  {}", rate(n), kind));
+    }
+
+    by_path.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    lines.push(String::new());
+    lines.push(
+        style(format!("{:>9}  HOTTEST PATH", "LOOKUP/S"))
+            .bold()
+            .to_string(),
+    );
+    for (path, n) in by_path.into_iter().take(HOT_PATHS) {
+        lines.push(format!("{:>9.1}  {}", rate(n), path));
+    }
+
+    lines.push(String::new());
+    lines.push(
+        style(format!(
+            "{:>6}  {:<8} {:>10}  JOB",
+            "ID", "KIND", "PROGRESS"
+        ))
+        .bold()
+        .to_string(),
+    );
+    for job in &cur.jobs {
+        let progress = if job.total_estimate > 0 {
+            format!("{}%", (job.processed * 100 / job.total_estimate).min(100))
+        } else {
+            job.processed.to_string()
+        };
+        lines.push(format!(
+            "{:>6}  {:<8} {:>10}  {}",
+            job.job_id,
+            format!("{:?}", job.kind),
+            progress,
+            job.description
+        ));
+    }
+
+    lines
+        .into_iter()
+        .map(|line| console::truncate_str(&line, width, "…").into_owned())
+        .collect()
+}
+
+fn percent(part: u64, whole: u64) -> String {
+    if whole == 0 {
+        "-".to_string()
+    } else {
+        format!("{}%", part * 100 / whole)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vrift_ipc::NamedCount;
+
+    fn counts(list: &[(&str, u64)]) -> Vec<NamedCount> {
+        list.iter()
+            .map(|(name, count)| NamedCount {
+                name: name.to_string(),
+                count: *count,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_activity_between_snapshots() {
+        let prev = WorkspaceMetrics {
+            uptime_secs: 10,
+            requests: counts(&[("ManifestGet", 5)]),
+            vdir_hits: 2,
+            lmdb_hits: 1,
+            misses: 2,
+            hot_paths: counts(&[("/p/a", 3)]),
+        };
+        let cur = WorkspaceMetrics {
+            uptime_secs: 11,
+            requests: counts(&[("ManifestGet", 9), ("Status", 1)]),
+            vdir_hits: 4,
+            lmdb_hits: 2,
+            misses: 3,
+            hot_paths: counts(&[("/p/a", 3), ("/p/b", 4)]),
+        };
+        let activity = activity(&cur, Some(&prev));
+        assert_eq!(activity.requests, 5);
+        assert_eq!(
+            (activity.hits, activity.vdir_hits, activity.lookups),
+            (3, 2, 4)
+        );
+        assert_eq!(activity.by_path.len(), 1);
+        assert_eq!(activity.by_path["/p/b"], 4);
+
+        // vDird restarted: everything counts as new
+        let restarted = WorkspaceMetrics {
+            uptime_secs: 1,
+            ..cur.clone()
+        };
+        assert_eq!(super::activity(&restarted, Some(&prev)).requests, 10);
+    }
+}
@@ -73,6 +73,21 @@ fn e2e_config_path_shows_locations() {
     );
 }
 
+// ========== E2E: Profiles ==========
+
+#[test]
+fn e2e_unknown_profile_is_refused() {
+    let output = vrift(&["--profile", "no-such-profile", "config", "show"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(output.status.code(), Some(22), "stderr: {}", stderr);
+    assert!(
+        stderr.contains("no profile 'no-such-profile'"),
+        "{}",
+        stderr
+    );
+}
+
 // ========== E2E: Tier Classification Verification ==========
 
 #[test]
@@ -0,0 +1,114 @@
+//! Client error type
+
+use std::path::PathBuf;
+
+use vrift_error::Classify;
+
+/// Category of an error reported by the daemon
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    NotFound,
+    PermissionDenied,
+    InvalidPath,
+    WorkspaceNotRegistered,
+    IngestFailed,
+    Io,
+    LockFailed,
+    Internal,
+    Conflict,
+}
+
+impl From<vrift_ipc::VeloErrorKind> for ErrorKind {
+    fn from(kind: vrift_ipc::VeloErrorKind) -> Self {
+        use vrift_ipc::VeloErrorKind as K;
+        match kind {
+            K::NotFound => ErrorKind::NotFound,
+            K::PermissionDenied => ErrorKind::PermissionDenied,
+            K::InvalidPath => ErrorKind::InvalidPath,
+            K::WorkspaceNotRegistered => ErrorKind::WorkspaceNotRegistered,
+            K::IngestFailed => ErrorKind::IngestFailed,
+            K::IoError => ErrorKind::Io,
+            K::LockFailed => ErrorKind::LockFailed,
+            K::Internal => ErrorKind::Internal,
+            K::Conflict => ErrorKind::Conflict,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// The daemon (or workspace vDird) socket could not be reached
+    #[error("cannot connect to {}: {source}", path.display())]
+    Connect {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    /// The daemon does not speak a protocol version this client understands
+    #[error("daemon {server_version} is incompatible with this client")]
+    Incompatible { server_version: String },
+
+    /// The daemon handled the request and reported an error
+    #[error("{kind:?}: {message}")]
+    Daemon {
+        kind: ErrorKind,
+        message: String,
+        /// Path the error refers to, if any
+        path: Option<String>,
+    },
+
+    /// The daemon answered with a response that does not fit the request
+    #[error("unexpected response to {request}")]
+    UnexpectedResponse { request: &'static str },
+
+    /// A subscription fell behind and missed changes
+    #[error("change subscription lagged; changes were missed")]
+    Lagged,
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl Error {
+    /// Daemon-side error category, if the daemon reported one
+    pub fn kind(&self) -> Option<ErrorKind> {
+        match self {
+            Error::Daemon { kind, .. } => Some(*kind),
+            _ => None,
+        }
+    }
+}
+
+impl Classify for Error {
+    fn classify(&self) -> vrift_error::ErrorKind {
+        use vrift_error::ErrorKind as K;
+        match self {
+            Error::Connect { .. } | Error::Incompatible { .. } => K::Unavailable,
+            Error::Daemon { kind, .. } => match kind {
+                ErrorKind::NotFound | ErrorKind::WorkspaceNotRegistered => K::NotFound,
+                ErrorKind::PermissionDenied => K::PermissionDenied,
+                ErrorKind::InvalidPath => K::InvalidInput,
+                ErrorKind::IngestFailed | ErrorKind::Conflict => K::IngestFailed,
+                ErrorKind::Io => K::Io,
+                ErrorKind::LockFailed => K::LockFailed,
+                ErrorKind::Internal => K::Internal,
+            },
+            Error::UnexpectedResponse { .. } | Error::Lagged => K::Internal,
+            Error::Io(e) => e.classify(),
+        }
+    }
+}
+
+impl From<vrift_ipc::VeloError> for Error {
+    fn from(e: vrift_ipc::VeloError) -> Self {
+        Error::Daemon {
+            kind: e.kind.into(),
+            message: e.message,
+            path: e.path,
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
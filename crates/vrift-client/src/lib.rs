@@ -0,0 +1,501 @@
+//! # vrift-client
+//!
+//! Typed client for the Velo Rift daemon, for tools outside this repository
+//! (IDE plugins, CI orchestrators, build wrappers).
+//!
+//! The IPC enums in `vrift-ipc` are an internal protocol and change whenever
+//! the daemon needs them to. This crate wraps them behind typed methods and
+//! its own data types, and follows semver on its own: public structs and
+//! enums are `#[non_exhaustive]`, so fields and variants can be added in
+//! minor releases, and a protocol change only needs a new release of this
+//! crate, not changes in client code.
+//!
+//! ```no_run
+//! # async fn demo() -> vrift_client::Result<()> {
+//! use vrift_client::Client;
+//!
+//! let mut client = Client::connect().await?;
+//! let mut workspace = client.open_workspace("/path/to/project").await?;
+//!
+//! if let Some(entry) = workspace.get_entry("/src/main.rs").await? {
+//!     println!("{} bytes", entry.size);
+//! }
+//! for child in workspace.list_dir("/src").await? {
+//!     println!("{}", child.name);
+//! }
+//!
+//! let mut changes = workspace.subscribe().await?;
+//! let change = changes.next().await?;
+//! println!("{:?} {}", change.kind, change.path);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Paths passed to [`Workspace`] methods are manifest keys: the path
+//! relative to the project root, with a leading `/`.
+
+mod error;
+mod types;
+
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use tokio::net::UnixStream;
+use vrift_ipc::{EventKind, VeloEvent, VeloRequest, VeloResponse};
+
+pub use error::{Error, ErrorKind, Result};
+pub use types::{
+    Change, ChangeKind, DirEntry, Entry, EntryKind, IngestMode, IngestOptions, IngestSummary,
+};
+
+/// Default interval between change feed polls
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// A handshaken connection to vriftd or a workspace vDird
+struct Connection {
+    stream: UnixStream,
+    server_version: String,
+}
+
+impl Connection {
+    async fn open(socket: &Path) -> Result<Self> {
+        let stream = UnixStream::connect(socket)
+            .await
+            .map_err(|source| Error::Connect {
+                path: socket.to_path_buf(),
+                source,
+            })?;
+        let mut conn = Self {
+            stream,
+            server_version: String::new(),
+        };
+        let handshake = VeloRequest::Handshake {
+            client_version: env!("CARGO_PKG_VERSION").to_string(),
+            protocol_version: vrift_ipc::PROTOCOL_VERSION,
+        };
+        match conn.call(handshake, "Handshake").await? {
+            VeloResponse::HandshakeAck {
+                server_version,
+                compatible: true,
+                ..
+            } => conn.server_version = server_version,
+            VeloResponse::HandshakeAck { server_version, .. } => {
+                return Err(Error::Incompatible { server_version })
+            }
+            _ => {
+                return Err(Error::UnexpectedResponse {
+                    request: "Handshake",
+                })
+            }
+        }
+        Ok(conn)
+    }
+
+    /// Send one request and wait for its response; daemon errors become `Err`
+    async fn call(&mut self, request: VeloRequest, name: &'static str) -> Result<VeloResponse> {
+        let seq_id = vrift_ipc::frame_async::send_request(&mut self.stream, &request).await?;
+        let (header, response) = vrift_ipc::frame_async::read_response(&mut self.stream).await?;
+        if header.seq_id != seq_id {
+            return Err(Error::UnexpectedResponse { request: name });
+        }
+        match response {
+            VeloResponse::Error(e) => Err(e.into()),
+            response => Ok(response),
+        }
+    }
+}
+
+/// Connection to the vriftd daemon
+pub struct Client {
+    conn: Connection,
+}
+
+impl Client {
+    /// Connect to the daemon socket from the Velo Rift configuration
+    pub async fn connect() -> Result<Self> {
+        let socket = vrift_config::config().socket_path().to_path_buf();
+        Self::connect_to(socket).await
+    }
+
+    /// Connect to a daemon listening on `socket`
+    pub async fn connect_to(socket: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self {
+            conn: Connection::open(socket.as_ref()).await?,
+        })
+    }
+
+    /// Version string the daemon reported during the handshake
+    pub fn server_version(&self) -> &str {
+        &self.conn.server_version
+    }
+
+    /// Human-readable daemon status line
+    pub async fn status(&mut self) -> Result<String> {
+        match self.conn.call(VeloRequest::Status, "Status").await? {
+            VeloResponse::StatusAck { status } => Ok(status),
+            _ => Err(Error::UnexpectedResponse { request: "Status" }),
+        }
+    }
+
+    /// Register `project_root` with the daemon (starting its vDird if
+    /// needed) and connect to the workspace
+    pub async fn open_workspace(&mut self, project_root: impl AsRef<Path>) -> Result<Workspace> {
+        let project_root = project_root.as_ref();
+        let request = VeloRequest::RegisterWorkspace {
+            project_root: project_root.to_string_lossy().to_string(),
+        };
+        match self.conn.call(request, "RegisterWorkspace").await? {
+            VeloResponse::RegisterAck {
+                workspace_id,
+                vdird_socket,
+                ..
+            } => {
+                let socket = PathBuf::from(vdird_socket);
+                Ok(Workspace {
+                    conn: Connection::open(&socket).await?,
+                    id: workspace_id,
+                    project_root: project_root.to_path_buf(),
+                    socket,
+                })
+            }
+            _ => Err(Error::UnexpectedResponse {
+                request: "RegisterWorkspace",
+            }),
+        }
+    }
+
+    /// Run a full-scan ingest and wait for it to finish
+    pub async fn ingest(&mut self, options: IngestOptions) -> Result<IngestSummary> {
+        match self.conn.call(options.into_request(), "Ingest").await? {
+            VeloResponse::IngestAck {
+                files,
+                blobs,
+                new_bytes,
+                total_bytes,
+                duration_ms,
+                manifest_path,
+                tier1_files,
+                tier2_files,
+                unchanged_files,
+                deduped_files,
+                quarantined_files,
+                largest_new,
+                skipped_files,
+            } => Ok(IngestSummary {
+                files,
+                blobs,
+                new_bytes,
+                total_bytes,
+                duration: Duration::from_millis(duration_ms),
+                manifest_path: PathBuf::from(manifest_path),
+                tier1_files,
+                tier2_files,
+                unchanged_files,
+                deduped_files,
+                quarantined_files,
+                skipped_files,
+                largest_new: largest_new
+                    .into_iter()
+                    .map(|blob| (PathBuf::from(blob.path), blob.size))
+                    .collect(),
+            }),
+            _ => Err(Error::UnexpectedResponse { request: "Ingest" }),
+        }
+    }
+}
+
+/// Connection to one workspace's vDird
+pub struct Workspace {
+    conn: Connection,
+    id: String,
+    project_root: PathBuf,
+    socket: PathBuf,
+}
+
+impl Workspace {
+    /// Workspace (project) id assigned by the daemon
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn project_root(&self) -> &Path {
+        &self.project_root
+    }
+
+    /// Look up a path; `None` if the VFS does not know it
+    pub async fn get_entry(&mut self, path: &str) -> Result<Option<Entry>> {
+        let request = VeloRequest::ManifestGet {
+            path: path.to_string(),
+        };
+        match self.conn.call(request, "ManifestGet").await? {
+            VeloResponse::ManifestAck { entry } => Ok(entry.map(Entry::from)),
+            _ => Err(Error::UnexpectedResponse {
+                request: "ManifestGet",
+            }),
+        }
+    }
+
+    /// Look up several paths in one round trip; entries come back in the
+    /// order of `paths`
+    pub async fn get_entries(&mut self, paths: &[&str]) -> Result<Vec<Option<Entry>>> {
+        let request = VeloRequest::ManifestGetMany {
+            paths: paths.iter().map(|p| p.to_string()).collect(),
+        };
+        match self.conn.call(request, "ManifestGetMany").await? {
+            VeloResponse::ManifestManyAck { entries } if entries.len() == paths.len() => {
+                Ok(entries.into_iter().map(|e| e.map(Entry::from)).collect())
+            }
+            _ => Err(Error::UnexpectedResponse {
+                request: "ManifestGetMany",
+            }),
+        }
+    }
+
+    /// Children of a directory
+    pub async fn list_dir(&mut self, path: &str) -> Result<Vec<DirEntry>> {
+        let request = VeloRequest::ManifestListDir {
+            path: path.to_string(),
+        };
+        match self.conn.call(request, "ManifestListDir").await? {
+            VeloResponse::ManifestListAck { entries } => {
+                Ok(entries.into_iter().map(DirEntry::from).collect())
+            }
+            _ => Err(Error::UnexpectedResponse {
+                request: "ManifestListDir",
+            }),
+        }
+    }
+
+    /// Follow manifest changes made from now on, on a separate connection.
+    /// The workspace pushes each change as it is made; one that cannot push
+    /// changes is polled instead.
+    pub async fn subscribe(&self) -> Result<Subscription> {
+        let mut conn = Connection::open(&self.socket).await?;
+        let request = VeloRequest::Subscribe {
+            events: vec![EventKind::ManifestChange],
+        };
+        let pushed = match conn.call(request, "Subscribe").await {
+            Ok(VeloResponse::SubscribeAck { events }) => {
+                events.contains(&EventKind::ManifestChange)
+            }
+            // Sent by a vDird that predates Subscribe
+            Err(Error::Daemon { .. }) => false,
+            Ok(_) => {
+                return Err(Error::UnexpectedResponse {
+                    request: "Subscribe",
+                })
+            }
+            Err(e) => return Err(e),
+        };
+        let cursor = if pushed {
+            None
+        } else {
+            Some(Subscription::poll(&mut conn, u64::MAX).await?.cursor)
+        };
+        Ok(Subscription {
+            conn,
+            cursor,
+            pending: VecDeque::new(),
+            interval: DEFAULT_POLL_INTERVAL,
+        })
+    }
+}
+
+struct ChangePage {
+    cursor: u64,
+    changes: Vec<Change>,
+    truncated: bool,
+}
+
+/// Stream of manifest changes for one workspace
+pub struct Subscription {
+    conn: Connection,
+    /// Next poll's cursor; `None` while changes are pushed
+    cursor: Option<u64>,
+    pending: VecDeque<Change>,
+    interval: Duration,
+}
+
+impl Subscription {
+    /// How long to wait between checks while no changes are pending, when
+    /// the workspace cannot push changes
+    pub fn set_poll_interval(&mut self, interval: Duration) {
+        self.interval = interval;
+    }
+
+    /// Next change, waiting until one arrives.
+    ///
+    /// Returns [`Error::Lagged`] if the workspace discarded changes before
+    /// they were read; the subscription then continues with the changes
+    /// still available, and the caller should rescan what it tracks.
+    pub async fn next(&mut self) -> Result<Change> {
+        loop {
+            if let Some(change) = self.pending.pop_front() {
+                return Ok(change);
+            }
+            let Some(cursor) = self.cursor else {
+                return self.next_pushed().await;
+            };
+            let page = Self::poll(&mut self.conn, cursor).await?;
+            self.cursor = Some(page.cursor);
+            self.pending.extend(page.changes);
+            if page.truncated {
+                return Err(Error::Lagged);
+            }
+            if self.pending.is_empty() {
+                tokio::time::sleep(self.interval).await;
+            }
+        }
+    }
+
+    async fn next_pushed(&mut self) -> Result<Change> {
+        loop {
+            let (_, event) = vrift_ipc::frame_async::read_event(&mut self.conn.stream).await?;
+            match event {
+                VeloEvent::ManifestChanged { change } => return Ok(change.into()),
+                VeloEvent::Lagged { .. } => return Err(Error::Lagged),
+                _ => {}
+            }
+        }
+    }
+
+    async fn poll(conn: &mut Connection, cursor: u64) -> Result<ChangePage> {
+        match conn
+            .call(
+                VeloRequest::ManifestChangesSince { cursor },
+                "ManifestChangesSince",
+            )
+            .await?
+        {
+            VeloResponse::ManifestChanges {
+                cursor,
+                changes,
+                truncated,
+            } => Ok(ChangePage {
+                cursor,
+                changes: changes.into_iter().map(Change::from).collect(),
+                truncated,
+            }),
+            _ => Err(Error::UnexpectedResponse {
+                request: "ManifestChangesSince",
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::UnixListener;
+    use vrift_ipc::{VeloError, VnodeEntry};
+
+    /// Answer requests the way vriftd and vDird would; one socket plays both
+    async fn fake_daemon(listener: UnixListener, socket: PathBuf) {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let socket = socket.clone();
+            tokio::spawn(async move {
+                while let Ok((header, request)) =
+                    vrift_ipc::frame_async::read_request(&mut stream).await
+                {
+                    let response = match request {
+                        VeloRequest::Handshake { .. } => VeloResponse::HandshakeAck {
+                            server_version: "test".to_string(),
+                            protocol_version: vrift_ipc::PROTOCOL_VERSION,
+                            compatible: true,
+                        },
+                        VeloRequest::RegisterWorkspace { .. } => VeloResponse::RegisterAck {
+                            workspace_id: "ws1".to_string(),
+                            vdird_socket: socket.to_string_lossy().to_string(),
+                            vdir_mmap_path: String::new(),
+                        },
+                        VeloRequest::ManifestGet { path } if path == "/a.txt" => {
+                            VeloResponse::ManifestAck {
+                                entry: Some(VnodeEntry::new_file([7; 32], 5, 1, 0o644)),
+                            }
+                        }
+                        VeloRequest::ManifestGet { .. } => {
+                            VeloResponse::ManifestAck { entry: None }
+                        }
+                        VeloRequest::ManifestGetMany { paths } => VeloResponse::ManifestManyAck {
+                            entries: paths
+                                .iter()
+                                .map(|p| {
+                                    (p == "/a.txt")
+                                        .then(|| VnodeEntry::new_file([7; 32], 5, 1, 0o644))
+                                })
+                                .collect(),
+                        },
+                        VeloRequest::ManifestListDir { .. } => VeloResponse::ManifestListAck {
+                            entries: vec![vrift_ipc::DirEntry {
+                                name: "a.txt".to_string(),
+                                is_dir: false,
+                            }],
+                        },
+                        VeloRequest::Subscribe { events } => VeloResponse::SubscribeAck { events },
+                        _ => VeloResponse::Error(VeloError::not_found("nope")),
+                    };
+                    let _ = vrift_ipc::frame_async::send_response(
+                        &mut stream,
+                        &response,
+                        header.seq_id,
+                    )
+                    .await;
+                    if matches!(response, VeloResponse::SubscribeAck { .. }) {
+                        let change = vrift_ipc::ManifestChange {
+                            seq: 3,
+                            kind: vrift_ipc::ManifestChangeKind::Removed,
+                            path: "/a.txt".to_string(),
+                            is_dir: false,
+                            cookie: 0,
+                        };
+                        let _ = vrift_ipc::frame_async::send_event(
+                            &mut stream,
+                            &VeloEvent::ManifestChanged { change },
+                            header.seq_id,
+                        )
+                        .await;
+                    }
+                }
+            });
+        }
+    }
+
+    #[tokio::test]
+    async fn test_workspace_queries_are_typed() {
+        let temp = tempfile::tempdir().unwrap();
+        let socket = temp.path().join("vriftd.sock");
+        let listener = UnixListener::bind(&socket).unwrap();
+        tokio::spawn(fake_daemon(listener, socket.clone()));
+
+        let mut client = Client::connect_to(&socket).await.unwrap();
+        assert_eq!(client.server_version(), "test");
+
+        let mut workspace = client.open_workspace("/project").await.unwrap();
+        assert_eq!(workspace.id(), "ws1");
+
+        let entry = workspace.get_entry("/a.txt").await.unwrap().unwrap();
+        assert_eq!(entry.kind, EntryKind::File);
+        assert_eq!((entry.size, entry.mode), (5, 0o644));
+        assert!(workspace.get_entry("/missing").await.unwrap().is_none());
+
+        let batch = workspace
+            .get_entries(&["/missing", "/a.txt"])
+            .await
+            .unwrap();
+        assert!(batch[0].is_none());
+        assert_eq!(batch[1].as_ref().map(|e| e.size), Some(5));
+
+        let children = workspace.list_dir("/").await.unwrap();
+        assert_eq!(children.len(), 1);
+        assert_eq!(children[0].name, "a.txt");
+
+        let mut changes = workspace.subscribe().await.unwrap();
+        let change = changes.next().await.unwrap();
+        assert_eq!((change.seq, change.kind), (3, ChangeKind::Removed));
+        assert_eq!(change.path, "/a.txt");
+
+        let err = client.status().await.unwrap_err();
+        assert_eq!(err.kind(), Some(ErrorKind::NotFound));
+    }
+}
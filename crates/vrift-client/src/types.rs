@@ -0,0 +1,250 @@
+//! Public data types
+//!
+//! These mirror the IPC payloads but are owned by this crate, so the wire
+//! format can change without breaking client code. Structs and enums are
+//! `#[non_exhaustive]`: new fields and variants are not breaking changes.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Type of a manifest entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum EntryKind {
+    File,
+    Directory,
+    Symlink,
+}
+
+/// A path as the VFS sees it
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Entry {
+    pub kind: EntryKind,
+    /// BLAKE3 hash of the content in the CAS
+    pub content_hash: [u8; 32],
+    pub size: u64,
+    /// Modification time as recorded by the workspace, in signed
+    /// nanoseconds since the Unix epoch (see `vrift_ipc::mtime`)
+    pub mtime: i64,
+    /// Permission bits
+    pub mode: u32,
+}
+
+impl From<vrift_ipc::VnodeEntry> for Entry {
+    fn from(vnode: vrift_ipc::VnodeEntry) -> Self {
+        let kind = if vnode.is_dir() {
+            EntryKind::Directory
+        } else if vnode.is_symlink() {
+            EntryKind::Symlink
+        } else {
+            EntryKind::File
+        };
+        Self {
+            kind,
+            content_hash: vnode.content_hash,
+            size: vnode.size,
+            mtime: vnode.mtime,
+            mode: vnode.mode,
+        }
+    }
+}
+
+/// One child of a listed directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DirEntry {
+    pub name: String,
+    pub is_dir: bool,
+}
+
+impl From<vrift_ipc::DirEntry> for DirEntry {
+    fn from(entry: vrift_ipc::DirEntry) -> Self {
+        Self {
+            name: entry.name,
+            is_dir: entry.is_dir,
+        }
+    }
+}
+
+/// What happened to a path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ChangeKind {
+    Created,
+    Modified,
+    Removed,
+    /// Source side of a rename, paired with `MovedTo` by `cookie`
+    MovedFrom,
+    /// Destination side of a rename, paired with `MovedFrom` by `cookie`
+    MovedTo,
+}
+
+/// One manifest mutation delivered by a [`Subscription`](crate::Subscription)
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Change {
+    /// Monotonic sequence number within the workspace
+    pub seq: u64,
+    pub kind: ChangeKind,
+    /// Manifest key of the affected path
+    pub path: String,
+    pub is_dir: bool,
+    /// Non-zero for rename pairs
+    pub cookie: u32,
+}
+
+impl From<vrift_ipc::ManifestChange> for Change {
+    fn from(change: vrift_ipc::ManifestChange) -> Self {
+        use vrift_ipc::ManifestChangeKind as K;
+        Self {
+            seq: change.seq,
+            kind: match change.kind {
+                K::Created => ChangeKind::Created,
+                K::Modified => ChangeKind::Modified,
+                K::Removed => ChangeKind::Removed,
+                K::MovedFrom => ChangeKind::MovedFrom,
+                K::MovedTo => ChangeKind::MovedTo,
+            },
+            path: change.path,
+            is_dir: change.is_dir,
+            cookie: change.cookie,
+        }
+    }
+}
+
+/// How blobs are placed in the CAS during ingest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum IngestMode {
+    /// Hard-link into the CAS, files stay mutable (tier 2)
+    #[default]
+    Solid,
+    /// Hard-link and mark immutable (tier 1)
+    Immutable,
+    /// Move files into the CAS; the VFS becomes the only view
+    Phantom,
+}
+
+/// Parameters of a full-scan ingest
+#[derive(Debug, Clone)]
+pub struct IngestOptions {
+    pub(crate) path: PathBuf,
+    pub(crate) manifest_path: PathBuf,
+    pub(crate) mode: IngestMode,
+    pub(crate) threads: Option<usize>,
+    pub(crate) prefix: Option<String>,
+    pub(crate) cas_root: Option<PathBuf>,
+    pub(crate) force_hash: bool,
+    pub(crate) selection: vrift_ipc::IngestSelection,
+}
+
+impl IngestOptions {
+    /// Ingest the directory `path` into the manifest at `manifest_path`
+    pub fn new(path: impl Into<PathBuf>, manifest_path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            manifest_path: manifest_path.into(),
+            mode: IngestMode::default(),
+            threads: None,
+            prefix: None,
+            cas_root: None,
+            force_hash: false,
+            selection: Default::default(),
+        }
+    }
+
+    pub fn mode(mut self, mode: IngestMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Worker threads (default: chosen by the daemon)
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = Some(threads);
+        self
+    }
+
+    /// Prefix for manifest keys, e.g. `/vendor`
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = Some(prefix.into());
+        self
+    }
+
+    /// CAS root to ingest into instead of the daemon's
+    pub fn cas_root(mut self, cas_root: impl Into<PathBuf>) -> Self {
+        self.cas_root = Some(cas_root.into());
+        self
+    }
+
+    /// Re-hash every file instead of trusting unchanged mtime and size
+    pub fn force_hash(mut self, force_hash: bool) -> Self {
+        self.force_hash = force_hash;
+        self
+    }
+
+    /// Only ingest this file or directory, relative to the ingested one.
+    /// Can be given several times; manifest entries outside them are kept.
+    pub fn path(mut self, path: impl Into<String>) -> Self {
+        self.selection.paths.push(path.into());
+        self
+    }
+
+    /// Only ingest files matching `glob` (see `vrift ingest --include`)
+    pub fn include(mut self, glob: impl Into<String>) -> Self {
+        self.selection.include.push(glob.into());
+        self
+    }
+
+    /// Leave out files matching `glob`
+    pub fn exclude(mut self, glob: impl Into<String>) -> Self {
+        self.selection.exclude.push(glob.into());
+        self
+    }
+
+    pub(crate) fn into_request(self) -> vrift_ipc::VeloRequest {
+        vrift_ipc::VeloRequest::IngestFullScan {
+            path: self.path.to_string_lossy().to_string(),
+            manifest_path: self.manifest_path.to_string_lossy().to_string(),
+            threads: self.threads,
+            phantom: self.mode == IngestMode::Phantom,
+            tier1: self.mode == IngestMode::Immutable,
+            prefix: self.prefix,
+            cas_root: self.cas_root.map(|p| p.to_string_lossy().to_string()),
+            force_hash: self.force_hash,
+            dry_run: false,
+            selection: self.selection.boxed(),
+        }
+    }
+}
+
+/// Result of a completed ingest
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct IngestSummary {
+    /// Files processed
+    pub files: u64,
+    /// Blobs newly stored
+    pub blobs: u64,
+    /// Bytes newly stored
+    pub new_bytes: u64,
+    /// Bytes processed
+    pub total_bytes: u64,
+    pub duration: Duration,
+    pub manifest_path: PathBuf,
+    /// Files written as Tier-1 (immutable)
+    pub tier1_files: u64,
+    /// Files written as Tier-2 (mutable)
+    pub tier2_files: u64,
+    /// Files skipped, unchanged since the last ingest
+    pub unchanged_files: u64,
+    /// Files whose content was already stored
+    pub deduped_files: u64,
+    /// Files kept out by the content scanner
+    pub quarantined_files: u64,
+    /// Files kept out by `[ingest.skip]`, by size or content
+    pub skipped_files: u64,
+    /// Largest blobs newly stored, relative to the ingested directory,
+    /// largest first
+    pub largest_new: Vec<(PathBuf, u64)>,
+}
@@ -5,17 +5,26 @@
 //! Loads configuration from:
 //! 1. `~/.vrift/config.toml` (global)
 //! 2. `.vrift/config.toml` (project-local, overrides global)
-//! 3. Environment variables (highest priority)
+//! 3. The profile named by `VRIFT_PROFILE` (`vrift --profile`), a
+//!    `[profile.<name>]` table of either file
+//! 4. Environment variables (highest priority)
 
+pub mod log_files;
 pub mod logging;
 pub mod path;
+pub mod serve;
 pub mod testing;
 
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
+use serve::{ServeMode, ServePolicy};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use std::sync::RwLock;
 use tracing::debug;
+use vrift_error::{Classify, ErrorKind};
+use vrift_ipc::path_key::Normalization;
+use vrift_manifest::ManifestBackend;
 
 /// Global config instance
 static CONFIG: Lazy<RwLock<Config>> = Lazy::new(|| {
@@ -51,11 +60,26 @@ pub enum ConfigError {
     Io(#[from] std::io::Error),
     #[error("TOML parse error: {0}")]
     Toml(#[from] toml::de::Error),
+    #[error("Profile error: {0}")]
+    Profile(String),
+}
+
+impl Classify for ConfigError {
+    fn classify(&self) -> ErrorKind {
+        match self {
+            ConfigError::Io(e) => e.classify(),
+            ConfigError::Toml(_) | ConfigError::Profile(_) => ErrorKind::InvalidInput,
+        }
+    }
 }
 
 /// Current config schema version
 pub const CONFIG_VERSION: u32 = 1;
 
+/// Sections a `[profile.<name>]` may override; of `[ingest]` only
+/// `ignore_patterns`
+const PROFILE_SECTIONS: &[&str] = &["tiers", "ingest", "gc", "serve"];
+
 /// Main configuration structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
@@ -67,7 +91,20 @@ pub struct Config {
     pub ingest: IngestConfig,
     pub tiers: TierConfig,
     pub security: SecurityConfig,
+    pub sandbox: SandboxConfig,
+    pub stat: StatConfig,
+    pub serve: ServeConfig,
+    pub gc: GcConfig,
     pub daemon: DaemonConfig,
+    pub grpc: GrpcConfig,
+    pub http: HttpConfig,
+    pub tcp: TcpConfig,
+    pub upstream: UpstreamConfig,
+    pub peers: PeersConfig,
+    /// Named overrides selected with `vrift --profile` / `VRIFT_PROFILE`,
+    /// kept as raw TOML so that only the keys they set are applied
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub profile: BTreeMap<String, toml::Value>,
 }
 
 impl Default for Config {
@@ -79,7 +116,17 @@ impl Default for Config {
             ingest: IngestConfig::default(),
             tiers: TierConfig::default(),
             security: SecurityConfig::default(),
+            sandbox: SandboxConfig::default(),
+            stat: StatConfig::default(),
+            serve: ServeConfig::default(),
+            gc: GcConfig::default(),
             daemon: DaemonConfig::default(),
+            grpc: GrpcConfig::default(),
+            http: HttpConfig::default(),
+            tcp: TcpConfig::default(),
+            upstream: UpstreamConfig::default(),
+            peers: PeersConfig::default(),
+            profile: BTreeMap::new(),
         }
     }
 }
@@ -91,7 +138,7 @@ impl Config {
     }
 
     /// Load config for a specific project root directory.
-    /// Resolution order: global → project → env vars.
+    /// Resolution order: global → project → profile → env vars.
     pub fn load_for_project(project_root: &Path) -> Result<Self, ConfigError> {
         let mut config = Config::default();
 
@@ -118,10 +165,17 @@ impl Config {
             config.merge_with_presence(project_config, &raw);
         }
 
-        // 3. Apply environment variable overrides
+        // 3. Apply the selected profile over both files
+        if let Ok(name) = std::env::var("VRIFT_PROFILE") {
+            if !name.is_empty() {
+                config.apply_profile(&name)?;
+            }
+        }
+
+        // 4. Apply environment variable overrides
         config.apply_env_overrides();
 
-        // 4. Resolve project root to absolute path if relative
+        // 5. Resolve project root to absolute path if relative
         if config.project.root.as_os_str() == "." {
             if let Ok(abs) = std::fs::canonicalize(project_root) {
                 config.project.root = abs;
@@ -130,7 +184,7 @@ impl Config {
             }
         }
 
-        // 5. Validate socket path: if parent dir doesn't exist and can't
+        // 6. Validate socket path: if parent dir doesn't exist and can't
         //    be created, fall back to default /tmp/vrift.sock so all
         //    components (CLI, daemon, tests) resolve to the same socket.
         if let Some(parent) = config.daemon.socket.parent() {
@@ -146,9 +200,63 @@ impl Config {
             }
         }
 
+        // 7. Manifest keys this process builds take the configured form
+        vrift_ipc::path_key::set_normalization(config.storage.normalization());
+
+        // 8. Manifests this process creates use the configured store
+        vrift_manifest::store::set_default_backend(config.storage.manifest_backend());
+
         Ok(config)
     }
 
+    /// Apply the overrides of `[profile.<name>]`
+    pub fn apply_profile(&mut self, name: &str) -> Result<(), ConfigError> {
+        let raw = self.profile.get(name).cloned().ok_or_else(|| {
+            let known: Vec<&str> = self.profile.keys().map(String::as_str).collect();
+            ConfigError::Profile(if known.is_empty() {
+                format!("no profile '{}' (none defined)", name)
+            } else {
+                format!("no profile '{}' (defined: {})", name, known.join(", "))
+            })
+        })?;
+        Self::check_profile(name, &raw)?;
+        let overlay: Config = raw.clone().try_into()?;
+        self.merge_with_presence(overlay, &raw);
+        Ok(())
+    }
+
+    /// Refuse a profile setting anything but the sections it may override
+    fn check_profile(name: &str, raw: &toml::Value) -> Result<(), ConfigError> {
+        let Some(table) = raw.as_table() else {
+            return Err(ConfigError::Profile(format!(
+                "[profile.{}] is not a table",
+                name
+            )));
+        };
+        for (section, value) in table {
+            if !PROFILE_SECTIONS.contains(&section.as_str()) {
+                return Err(ConfigError::Profile(format!(
+                    "[profile.{}] cannot set [{}]; profiles override {}",
+                    name,
+                    section,
+                    PROFILE_SECTIONS.join(", ")
+                )));
+            }
+            if section == "ingest" {
+                if let Some(key) = value
+                    .as_table()
+                    .and_then(|t| t.keys().find(|k| *k != "ignore_patterns"))
+                {
+                    return Err(ConfigError::Profile(format!(
+                        "[profile.{}.ingest] can only set ignore_patterns, not {}",
+                        name, key
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Global config path: ~/.vrift/config.toml
     pub fn global_config_path() -> Option<PathBuf> {
         dirs::home_dir().map(|h| h.join(".vrift/config.toml"))
@@ -183,6 +291,27 @@ impl Config {
         if has_key("storage", "default_mode") {
             self.storage.default_mode = other.storage.default_mode;
         }
+        if has_key("storage", "manifest_backend") {
+            self.storage.manifest_backend = other.storage.manifest_backend;
+        }
+        // `storage.key_normalization` is global-only like the CAS itself:
+        // vriftd builds the keys of every project with one form
+
+        // Ingest
+        if has_key("ingest", "hot_write_breaks") {
+            self.ingest.hot_write_breaks = other.ingest.hot_write_breaks;
+        }
+        if has_key("ingest", "hot_write_window_secs") {
+            self.ingest.hot_write_window_secs = other.ingest.hot_write_window_secs;
+        }
+        if has_key("ingest", "keep_conflicts") {
+            self.ingest.keep_conflicts = other.ingest.keep_conflicts;
+        }
+        if has_key("ingest", "ignore_patterns") {
+            self.ingest.ignore_patterns = other.ingest.ignore_patterns;
+        }
+        // `ingest.scanner` is deliberately global-only: a checked-out project
+        // must not be able to turn the organization's scanner off
 
         // Daemon
         if has_key("daemon", "socket") {
@@ -200,12 +329,56 @@ impl Config {
             if has_key("tiers", "tier2_patterns") {
                 self.tiers.tier2_patterns = other.tiers.tier2_patterns;
             }
+            if has_key("tiers", "reevaluate_interval_secs") {
+                self.tiers.reevaluate_interval_secs = other.tiers.reevaluate_interval_secs;
+            }
+            if has_key("tiers", "promote_after_sessions") {
+                self.tiers.promote_after_sessions = other.tiers.promote_after_sessions;
+            }
         }
 
         // Security (replace entire list if section is present)
         if has_section("security") && has_key("security", "exclude_patterns") {
             self.security.exclude_patterns = other.security.exclude_patterns;
         }
+
+        // Sandbox
+        if has_key("sandbox", "mode") {
+            self.sandbox.mode = other.sandbox.mode;
+        }
+        if has_key("sandbox", "allow") {
+            self.sandbox.allow = other.sandbox.allow;
+        }
+        if has_key("sandbox", "report") {
+            self.sandbox.report = other.sandbox.report;
+        }
+
+        // Stat synthesis
+        if has_key("stat", "ctime") {
+            self.stat.ctime = other.stat.ctime;
+        }
+        if has_key("stat", "birthtime") {
+            self.stat.birthtime = other.stat.birthtime;
+        }
+        if has_key("stat", "watchdog_us") {
+            self.stat.watchdog_us = other.stat.watchdog_us;
+        }
+
+        // Serve policy (project patterns add to or override global ones)
+        if has_key("serve", "policy") {
+            self.serve.policy.extend(other.serve.policy);
+        }
+
+        // Garbage collection
+        if has_key("gc", "grace_secs") {
+            self.gc.grace_secs = other.gc.grace_secs;
+        }
+        if has_key("gc", "refcount") {
+            self.gc.refcount = other.gc.refcount;
+        }
+
+        // Profiles (a project's replace global ones of the same name)
+        self.profile.extend(other.profile);
     }
 
     /// Apply environment variable overrides (highest priority)
@@ -225,6 +398,12 @@ impl Config {
         if let Ok(path) = std::env::var("VR_THE_SOURCE") {
             self.storage.the_source = PathBuf::from(path);
         }
+        if let Ok(form) = std::env::var("VRIFT_KEY_NORMALIZATION") {
+            self.storage.key_normalization = form;
+        }
+        if let Ok(backend) = std::env::var("VRIFT_MANIFEST_BACKEND") {
+            self.storage.manifest_backend = backend;
+        }
 
         // Ingest
         if let Ok(threads) = std::env::var("VRIFT_THREADS") {
@@ -232,6 +411,51 @@ impl Config {
                 self.ingest.threads = Some(n);
             }
         }
+        if let Ok(breaks) = std::env::var("VRIFT_HOT_WRITE_BREAKS") {
+            if let Ok(n) = breaks.parse() {
+                self.ingest.hot_write_breaks = n;
+            }
+        }
+        if let Ok(command) = std::env::var("VRIFT_INGEST_SCANNER") {
+            self.ingest.scanner.command = command.split_whitespace().map(String::from).collect();
+        }
+
+        // Sandbox
+        if let Ok(mode) = std::env::var("VRIFT_SANDBOX") {
+            self.sandbox.mode = mode;
+        }
+        if let Ok(allow) = std::env::var("VRIFT_SANDBOX_ALLOW") {
+            self.sandbox.allow = allow
+                .split(':')
+                .filter(|p| !p.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+        if let Ok(report) = std::env::var("VRIFT_SANDBOX_REPORT") {
+            self.sandbox.report = Some(PathBuf::from(report));
+        }
+
+        // Stat synthesis
+        if let Ok(source) = std::env::var("VRIFT_STAT_CTIME") {
+            self.stat.ctime = source;
+        }
+        if let Ok(source) = std::env::var("VRIFT_STAT_BIRTHTIME") {
+            self.stat.birthtime = source;
+        }
+        if let Ok(us) = std::env::var("VRIFT_STAT_WATCHDOG_US") {
+            if let Ok(us) = us.parse() {
+                self.stat.watchdog_us = us;
+            }
+        }
+
+        // Serve policy
+        if let Ok(policy) = std::env::var("VRIFT_SERVE_POLICY") {
+            self.serve.policy = ServePolicy::from_env_value(&policy)
+                .rules()
+                .iter()
+                .cloned()
+                .collect();
+        }
 
         // Daemon
         if let Ok(socket) = std::env::var("VRIFT_SOCKET_PATH") {
@@ -257,6 +481,50 @@ impl Config {
         if let Ok(log) = std::env::var("VRIFT_LOG_DIR") {
             self.daemon.log_dir = PathBuf::from(log);
         }
+        if let Ok(max) = std::env::var("VRIFT_LOG_MAX_SIZE") {
+            if let Ok(bytes) = max.parse() {
+                self.daemon.log_max_bytes = bytes;
+            }
+        }
+        if let Ok(max) = std::env::var("VRIFT_MAX_ACTIVE_WORKSPACES") {
+            if let Ok(n) = max.parse() {
+                self.daemon.max_active_workspaces = n;
+            }
+        }
+
+        // gRPC
+        if let Ok(listen) = std::env::var("VRIFT_GRPC_LISTEN") {
+            self.grpc.listen = Some(listen);
+        }
+        if let Ok(token_file) = std::env::var("VRIFT_GRPC_TOKEN_FILE") {
+            self.grpc.token_file = Some(PathBuf::from(token_file));
+        }
+
+        // HTTP export
+        if let Ok(listen) = std::env::var("VRIFT_HTTP_LISTEN") {
+            self.http.listen = Some(listen);
+        }
+        if let Ok(token_file) = std::env::var("VRIFT_HTTP_TOKEN_FILE") {
+            self.http.token_file = Some(PathBuf::from(token_file));
+        }
+
+        // IPC over TCP
+        if let Ok(listen) = std::env::var("VRIFT_TCP_LISTEN") {
+            self.tcp.listen = Some(listen);
+        }
+        if let Ok(token_file) = std::env::var("VRIFT_TCP_TOKEN_FILE") {
+            self.tcp.token_file = Some(PathBuf::from(token_file));
+        }
+
+        // Upstream CAS
+        if let Ok(url) = std::env::var("VRIFT_UPSTREAM_URL") {
+            self.upstream.url = Some(url);
+        }
+
+        // LAN peers
+        if let Ok(enabled) = std::env::var("VRIFT_PEERS") {
+            self.peers.enabled = matches!(enabled.as_str(), "1" | "true");
+        }
     }
 
     /// Derive environment variables for shim-wrapped processes.
@@ -283,10 +551,63 @@ impl Config {
                 "VRIFT_MANIFEST".to_string(),
                 self.project.manifest.display().to_string(),
             ),
+            (
+                "VRIFT_LOG_DIR".to_string(),
+                self.daemon.log_dir.display().to_string(),
+            ),
+            (
+                "VRIFT_LOG_MAX_SIZE".to_string(),
+                self.daemon.log_max_bytes.to_string(),
+            ),
         ];
         if self.daemon.debug {
             env.push(("VRIFT_DEBUG".to_string(), "1".to_string()));
         }
+        if self.sandbox.is_enabled() {
+            env.push(("VRIFT_SANDBOX".to_string(), self.sandbox.mode.clone()));
+            if !self.sandbox.allow.is_empty() {
+                env.push((
+                    "VRIFT_SANDBOX_ALLOW".to_string(),
+                    self.sandbox.allow.join(":"),
+                ));
+            }
+            if let Some(ref report) = self.sandbox.report {
+                env.push((
+                    "VRIFT_SANDBOX_REPORT".to_string(),
+                    report.display().to_string(),
+                ));
+            }
+        }
+        if self.stat.ctime != StatConfig::MTIME {
+            env.push(("VRIFT_STAT_CTIME".to_string(), self.stat.ctime.clone()));
+        }
+        if self.stat.birthtime != StatConfig::MTIME {
+            env.push((
+                "VRIFT_STAT_BIRTHTIME".to_string(),
+                self.stat.birthtime.clone(),
+            ));
+        }
+        if self.stat.watchdog_us != StatConfig::WATCHDOG_US {
+            env.push((
+                "VRIFT_STAT_WATCHDOG_US".to_string(),
+                self.stat.watchdog_us.to_string(),
+            ));
+        }
+        if self.storage.normalization() != Normalization::PLATFORM_DEFAULT {
+            env.push((
+                "VRIFT_KEY_NORMALIZATION".to_string(),
+                self.storage.normalization().as_str().to_string(),
+            ));
+        }
+        let serve = self.serve.policy();
+        if !serve.is_empty() {
+            env.push(("VRIFT_SERVE_POLICY".to_string(), serve.to_env_value()));
+        }
+        // Pinned, so a wrapped process that changes XDG_RUNTIME_DIR still
+        // finds the workspace's vDird socket and VDir
+        if let Some(base) = path::runtime_base_dir() {
+            env.push(("VRIFT_RUNTIME_DIR".to_string(), base.display().to_string()));
+        }
         env
     }
 
@@ -309,14 +630,81 @@ the_source = "{the_source}"
 [daemon]
 # socket = "{socket}"
 # debug = false
+# warm_start = false            # start all registered workspaces with the daemon
+# max_active_workspaces = 8     # LRU cap on loaded workspaces (0 = unlimited)
+# workspace_idle_secs = 1800    # unload idle workspaces (0 = never)
 
 # [ingest]
 # threads = auto
 # default_tier = "tier2"
+# hot_write_breaks = 3          # CoW breaks within the window before a path is copied up; 0 = never
+# hot_write_window_secs = 86400
+# keep_conflicts = true         # keep the losing write of a conflict as <path>.conflict-<hash>
+
+# [ingest.scanner]  # ~/.vrift/config.toml only: quarantine files a malware/secret scanner flags
+# command = ["/usr/local/bin/vrift-clamscan"]   # paths on stdin, "<path>\t<reason>" per match on stdout
+# batch_size = 256
+# timeout_secs = 300
+
+# [ingest.skip]     # ~/.vrift/config.toml, read by vriftd: files left out of the CAS, served from disk
+# max_file_bytes = 1073741824   # skip files over 1 GiB; 0 = no limit
+# binary = false                # skip files with a NUL byte in their first 8000 bytes
+# reference = false             # keep skipped files in the manifest, served from their real path
 
 # [tiers]
 # tier1_patterns = ["node_modules/", ".cargo/registry/"]
 # tier2_patterns = ["target/", "build/"]
+# reevaluate_interval_secs = 3600   # re-tier workspaces whose sessions ended; 0 = never
+# promote_after_sessions = 10       # sessions without a write before Tier-2 becomes Tier-1; 0 = never
+
+# [sandbox]
+# mode = "off"    # off | log | deny: report or refuse reads of undeclared inputs
+# allow = ["/opt/homebrew"]
+# report = "/tmp/vrift-undeclared.txt"
+
+# [stat]          # ctime / birthtime of VFS files: mtime | ingest | zero
+# ctime = "mtime"
+# birthtime = "mtime"
+# watchdog_us = 200  # re-advise the manifest mmap when a sampled hit is slower; 0 = off
+
+# [serve.policy]  # how reads are served: cas (shared blob) | materialize (real file)
+# "*.so" = "materialize"
+# "*.sqlite" = "materialize"
+
+# [gc]
+# grace_secs = 86400  # an unreferenced blob is deleted by the first sweep this long after it was marked; 0 = at once
+# refcount = false    # count references per blob (<the_source>/refs.lmdb) so GC deletes exactly the unreferenced ones
+
+# [grpc]          # remote orchestration (vriftd built with --features grpc)
+# listen = "0.0.0.0:7420"
+# token_file = "~/.vrift/grpc.token"
+# tls_cert = "/etc/vrift/tls.crt"
+# tls_key = "/etc/vrift/tls.key"
+
+# [http]          # read-only blob/packfile export (vriftd built with --features http)
+# listen = "0.0.0.0:7421"
+# token_file = "~/.vrift/http.token"
+
+# [tcp]           # the IPC protocol over TCP, for clients in containers or on other hosts (--features tcp)
+# listen = "0.0.0.0:7422"
+# token_file = "~/.vrift/tcp.token"
+
+# [upstream]      # fetch blobs missing locally on open (vriftd built with --features upstream)
+# url = "http://cache.internal:7421/blobs/{{hash}}"
+# token_file = "~/.vrift/upstream.token"
+# max_concurrent = 8
+# timeout_secs = 60
+# negative_ttl_secs = 300
+
+# [peers]         # share blobs with daemons on the LAN over mDNS (--features peers; needs [http] listen)
+# enabled = true
+# refresh_secs = 30
+# bloom_fpr = 0.01
+
+# [profile.ci]    # applied by `vrift --profile ci` or VRIFT_PROFILE=ci; may set [tiers], [gc], [serve] and [ingest] ignore_patterns
+# gc = {{ grace_secs = 0 }}
+# ingest = {{ ignore_patterns = [".vrift", "node_modules/.cache"] }}
+# serve.policy = {{ "*.so" = "materialize" }}
 "#,
             vfs_prefix = default.project.vfs_prefix,
             the_source = default.storage.the_source.display(),
@@ -365,6 +753,17 @@ the_source = "{the_source}"
     pub fn log_dir(&self) -> &Path {
         &self.daemon.log_dir
     }
+
+    /// How long a blob stays marked unreferenced before GC deletes it
+    pub fn gc_grace(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.gc.grace_secs)
+    }
+
+    /// Idle time after which an unused workspace is deactivated
+    pub fn workspace_idle_timeout(&self) -> Option<std::time::Duration> {
+        (self.daemon.workspace_idle_secs > 0)
+            .then(|| std::time::Duration::from_secs(self.daemon.workspace_idle_secs))
+    }
 }
 
 /// Project-level configuration
@@ -399,6 +798,28 @@ pub struct StorageConfig {
     pub the_source: PathBuf,
     /// Default projection mode: solid or phantom
     pub default_mode: String,
+    /// Unicode form of manifest keys: `nfc`, `nfd` or `none` (default `nfc`
+    /// on macOS, `none` elsewhere). Global-only: vriftd builds the keys of
+    /// every project it serves. Changing it needs a re-ingest.
+    /// Env override: VRIFT_KEY_NORMALIZATION
+    pub key_normalization: String,
+    /// Store for new manifests: `lmdb` (default) or `redb`, a single
+    /// pure-Rust file for hosts where LMDB's map size or lock file get in
+    /// the way. Existing manifests keep the store they were created with.
+    /// Env override: VRIFT_MANIFEST_BACKEND
+    pub manifest_backend: String,
+}
+
+impl StorageConfig {
+    /// `key_normalization`; the platform default if it is not a known form
+    pub fn normalization(&self) -> Normalization {
+        Normalization::parse(&self.key_normalization).unwrap_or(Normalization::PLATFORM_DEFAULT)
+    }
+
+    /// `manifest_backend`; LMDB if it is not a known backend
+    pub fn manifest_backend(&self) -> ManifestBackend {
+        ManifestBackend::parse(&self.manifest_backend).unwrap_or_default()
+    }
 }
 
 impl Default for StorageConfig {
@@ -406,6 +827,8 @@ impl Default for StorageConfig {
         Self {
             the_source: PathBuf::from(DEFAULT_CAS_ROOT),
             default_mode: "solid".to_string(),
+            key_normalization: Normalization::PLATFORM_DEFAULT.as_str().to_string(),
+            manifest_backend: ManifestBackend::default().as_str().to_string(),
         }
     }
 }
@@ -426,6 +849,18 @@ pub struct IngestConfig {
     pub batch_timeout_ms: u64,
     /// Patterns to ignore during ingest and live watch
     pub ignore_patterns: Vec<String>,
+    /// CoW breaks of one path within `hot_write_window_secs` after which
+    /// vDird copies it up to a plain file the shim passes through (0 = never)
+    pub hot_write_breaks: u32,
+    /// Window over which CoW breaks are counted
+    pub hot_write_window_secs: u64,
+    /// Keep the losing write of a concurrent-write conflict as
+    /// `<path>.conflict-<hash>` instead of discarding it (default: true)
+    pub keep_conflicts: bool,
+    /// Content scanner vetting files before they enter the CAS
+    pub scanner: ScannerConfig,
+    /// Files kept out of the CAS by size or content
+    pub skip: SkipConfig,
 }
 
 impl Default for IngestConfig {
@@ -441,10 +876,55 @@ impl Default for IngestConfig {
                 ".vrift".to_string(),    // Vrift system directory (always needed)
                 ".DS_Store".to_string(), // macOS junk
             ],
+            hot_write_breaks: 3,
+            hot_write_window_secs: 24 * 3600,
+            keep_conflicts: true,
+            scanner: ScannerConfig::default(),
+            skip: SkipConfig::default(),
         }
     }
 }
 
+/// External malware/secret scanner run by `vrift ingest` (`[ingest.scanner]`).
+/// It gets each batch of paths on stdin, one per line, and prints
+/// `<path>\t<reason>` for each file to quarantine; it must exit 0 once the
+/// batch is scanned. Quarantined files stay out of the CAS and the manifest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScannerConfig {
+    /// Program and arguments (empty = no scanning)
+    pub command: Vec<String>,
+    /// Files per scanner run
+    pub batch_size: usize,
+    /// Kill a run taking longer than this, quarantining its batch
+    pub timeout_secs: u64,
+}
+
+impl Default for ScannerConfig {
+    fn default() -> Self {
+        Self {
+            command: Vec::new(),
+            batch_size: 256,
+            timeout_secs: 300,
+        }
+    }
+}
+
+/// Files `vrift ingest` leaves out of the CAS (`[ingest.skip]`). They stay
+/// where they are, so builds read the real file, and get no manifest entry
+/// unless `reference` is set; the ingest summary counts them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SkipConfig {
+    /// Skip files larger than this many bytes (0 = no limit)
+    pub max_file_bytes: u64,
+    /// Skip files that look binary: a NUL byte in their first 8000 bytes
+    pub binary: bool,
+    /// Give skipped files a manifest entry referencing their real path, so
+    /// they are listed and served like ingested ones
+    pub reference: bool,
+}
+
 /// Tier classification patterns
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
@@ -453,6 +933,12 @@ pub struct TierConfig {
     pub tier1_patterns: Vec<String>,
     /// Tier-2 (Mutable) path patterns
     pub tier2_patterns: Vec<String>,
+    /// How often vriftd re-evaluates the tiers of workspaces whose sessions
+    /// ended (0 = never)
+    pub reevaluate_interval_secs: u64,
+    /// Sessions without a write before a Tier-2 file is promoted to Tier-1
+    /// (0 = never promote)
+    pub promote_after_sessions: u64,
 }
 
 impl Default for TierConfig {
@@ -480,6 +966,8 @@ impl Default for TierConfig {
                 ".cache/".to_string(),
                 "out/".to_string(),
             ],
+            reevaluate_interval_secs: 3600,
+            promote_after_sessions: 10,
         }
     }
 }
@@ -518,6 +1006,101 @@ impl Default for SecurityConfig {
     }
 }
 
+/// Sandbox mode: verify that a build only reads declared inputs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SandboxConfig {
+    /// off, log (report undeclared reads), or deny (fail them with EACCES)
+    pub mode: String,
+    /// Extra path prefixes that may be read besides the manifest and system dirs
+    pub allow: Vec<String>,
+    /// File that undeclared inputs are appended to
+    pub report: Option<PathBuf>,
+}
+
+impl Default for SandboxConfig {
+    fn default() -> Self {
+        Self {
+            mode: "off".to_string(),
+            allow: Vec::new(),
+            report: None,
+        }
+    }
+}
+
+impl SandboxConfig {
+    /// True for `log` and `deny`
+    pub fn is_enabled(&self) -> bool {
+        matches!(self.mode.as_str(), "log" | "deny")
+    }
+}
+
+/// Where synthesized `st_ctime` and `st_birthtime` come from
+///
+/// - `mtime`: the file's mtime, as if it had not changed since it was written
+/// - `ingest`: when the entry was ingested into the VFS (falls back to mtime
+///   for entries without a recorded ingest time)
+/// - `zero`: the Unix epoch
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct StatConfig {
+    pub ctime: String,
+    pub birthtime: String,
+    /// Sampled mmap stat latency (microseconds) above which the shim issues
+    /// `MADV_WILLNEED` on the manifest mapping; 0 disables the watchdog
+    pub watchdog_us: u64,
+}
+
+impl StatConfig {
+    pub const MTIME: &'static str = "mtime";
+    pub const WATCHDOG_US: u64 = 200;
+}
+
+impl Default for StatConfig {
+    fn default() -> Self {
+        Self {
+            ctime: Self::MTIME.to_string(),
+            birthtime: Self::MTIME.to_string(),
+            watchdog_us: Self::WATCHDOG_US,
+        }
+    }
+}
+
+/// Per-pattern serving of VFS reads; see [`serve`]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default)]
+pub struct ServeConfig {
+    /// Glob pattern to serve mode
+    pub policy: BTreeMap<String, ServeMode>,
+}
+
+impl ServeConfig {
+    pub fn policy(&self) -> ServePolicy {
+        ServePolicy::from_map(&self.policy)
+    }
+}
+
+/// CAS garbage collection
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct GcConfig {
+    /// Seconds a blob stays marked unreferenced before a sweep may delete
+    /// it (0 = delete on the sweep that finds it)
+    pub grace_secs: u64,
+    /// Keep per-blob reference counts under the CAS root and sweep by them
+    /// instead of by a bloom filter
+    pub refcount: bool,
+}
+
+impl Default for GcConfig {
+    fn default() -> Self {
+        Self {
+            grace_secs: 86400,
+            refcount: false,
+        }
+    }
+}
+
 /// Daemon configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
@@ -538,6 +1121,20 @@ pub struct DaemonConfig {
     pub cow_temp_dir: PathBuf,
     /// Log directory for daemon and inception-layer
     pub log_dir: PathBuf,
+    /// Size at which a per-process shim log is rotated (0 = never)
+    pub log_max_bytes: u64,
+    /// Total size of shim logs kept in `log_dir`; the daemon deletes the
+    /// oldest beyond this (0 = keep everything)
+    pub log_retain_bytes: u64,
+    /// Start vDirds for every registered workspace at daemon start-up instead
+    /// of on first registration
+    pub warm_start: bool,
+    /// Maximum workspaces kept active at once; the least recently used idle
+    /// workspace is deactivated beyond this (0 = unlimited)
+    pub max_active_workspaces: usize,
+    /// Deactivate workspaces with no live sessions after this many idle
+    /// seconds (0 = never)
+    pub workspace_idle_secs: u64,
 }
 
 impl Default for DaemonConfig {
@@ -553,6 +1150,105 @@ impl Default for DaemonConfig {
             mmap_path: PathBuf::from("/tmp/vrift-manifest.mmap"),
             cow_temp_dir: PathBuf::from("/tmp"),
             log_dir: PathBuf::from("/tmp"),
+            log_max_bytes: log_files::DEFAULT_MAX_BYTES,
+            log_retain_bytes: log_files::DEFAULT_RETAIN_BYTES,
+            warm_start: false,
+            max_active_workspaces: 8,
+            workspace_idle_secs: 1800,
+        }
+    }
+}
+
+/// gRPC facade for remote orchestration (daemon built with `--features grpc`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GrpcConfig {
+    /// Address to serve on, e.g. `0.0.0.0:7420` (None = disabled)
+    pub listen: Option<String>,
+    /// File holding the bearer token clients must present. Required,
+    /// whatever the address.
+    pub token_file: Option<PathBuf>,
+    /// PEM certificate chain; enables TLS together with `tls_key`
+    pub tls_cert: Option<PathBuf>,
+    /// PEM private key for `tls_cert`
+    pub tls_key: Option<PathBuf>,
+}
+
+/// Read-only HTTP export of the CAS (daemon built with `--features http`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpConfig {
+    /// Address to serve on, e.g. `0.0.0.0:7421` (None = disabled)
+    pub listen: Option<String>,
+    /// File holding the bearer token clients must present. Required,
+    /// whatever the address.
+    pub token_file: Option<PathBuf>,
+}
+
+/// The daemon's IPC protocol over TCP (daemon built with `--features tcp`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TcpConfig {
+    /// Address to serve on, e.g. `0.0.0.0:7422` (None = disabled)
+    pub listen: Option<String>,
+    /// File holding the token clients must authenticate with. Required,
+    /// whatever the address.
+    pub token_file: Option<PathBuf>,
+}
+
+/// Upstream CAS that blobs missing locally are fetched from when opened
+/// (daemon built with `--features upstream`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UpstreamConfig {
+    /// URL of a blob, `{hash}` standing for its hex hash: another vriftd's
+    /// HTTP export (`http://host:7421/blobs/{hash}`) or any HTTP(S) store
+    /// with that layout, such as an S3 bucket (None = disabled)
+    pub url: Option<String>,
+    /// File holding a bearer token to send, if the upstream wants one
+    pub token_file: Option<PathBuf>,
+    /// Fetches in flight at once
+    pub max_concurrent: usize,
+    /// Give up on a fetch after this long
+    pub timeout_secs: u64,
+    /// How long a blob the upstream lacked (or failed to send) is not asked
+    /// for again
+    pub negative_ttl_secs: u64,
+}
+
+impl Default for UpstreamConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            token_file: None,
+            max_concurrent: 8,
+            timeout_secs: 60,
+            negative_ttl_secs: 300,
+        }
+    }
+}
+
+/// Blob sharing between daemons on one network (daemon built with
+/// `--features peers`). Each daemon advertises its HTTP export over mDNS
+/// and a bloom filter of its CAS behind it; a blob missing on open is asked
+/// of a peer whose filter has it before the upstream.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PeersConfig {
+    pub enabled: bool,
+    /// How often peers' bloom filters are refreshed
+    pub refresh_secs: u64,
+    /// False positive rate the filter we advertise is sized for; each
+    /// false positive costs a peer one `404`
+    pub bloom_fpr: f64,
+}
+
+impl Default for PeersConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            refresh_secs: 30,
+            bloom_fpr: 0.01,
         }
     }
 }
@@ -587,6 +1283,16 @@ mod tests {
         assert_eq!(config.daemon.socket, PathBuf::from(DEFAULT_SOCKET_PATH));
         assert_eq!(config.daemon.lock_timeout_secs, 30);
         assert!(!config.daemon.debug);
+        assert!(!config.daemon.warm_start);
+        assert_eq!(config.daemon.max_active_workspaces, 8);
+
+        // Upstream fetch is off until a URL is set
+        assert!(config.upstream.url.is_none());
+        assert_eq!(config.upstream.max_concurrent, 8);
+        assert_eq!(config.upstream.negative_ttl_secs, 300);
+        assert!(!config.peers.enabled);
+        assert_eq!(config.peers.refresh_secs, 30);
+        assert_eq!(config.peers.bloom_fpr, 0.01);
     }
 
     #[test]
@@ -746,6 +1452,286 @@ default_tier = "tier1"
         assert_eq!(base.project.vfs_prefix, "/vrift");
     }
 
+    #[test]
+    fn test_sandbox_section_reaches_shim_env() {
+        let mut base = Config::default();
+        assert!(!base.shim_env().iter().any(|(k, _)| k == "VRIFT_SANDBOX"));
+
+        let overlay_toml = r#"
+            [sandbox]
+            mode = "deny"
+            allow = ["/opt/homebrew", "/nix/store"]
+        "#;
+        let raw: toml::Value = toml::from_str(overlay_toml).unwrap();
+        let overlay: Config = toml::from_str(overlay_toml).unwrap();
+        base.merge_with_presence(overlay, &raw);
+
+        let env = base.shim_env();
+        let get = |key: &str| env.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str());
+        assert_eq!(get("VRIFT_SANDBOX"), Some("deny"));
+        assert_eq!(get("VRIFT_SANDBOX_ALLOW"), Some("/opt/homebrew:/nix/store"));
+        assert_eq!(get("VRIFT_SANDBOX_REPORT"), None);
+    }
+
+    #[test]
+    fn test_stat_section_reaches_shim_env() {
+        let mut base = Config::default();
+        assert!(!base
+            .shim_env()
+            .iter()
+            .any(|(k, _)| k.starts_with("VRIFT_STAT_")));
+
+        let overlay_toml = r#"
+            [stat]
+            birthtime = "ingest"
+            watchdog_us = 0
+        "#;
+        let raw: toml::Value = toml::from_str(overlay_toml).unwrap();
+        let overlay: Config = toml::from_str(overlay_toml).unwrap();
+        base.merge_with_presence(overlay, &raw);
+
+        let env = base.shim_env();
+        let get = |key: &str| env.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str());
+        assert_eq!(get("VRIFT_STAT_BIRTHTIME"), Some("ingest"));
+        assert_eq!(get("VRIFT_STAT_CTIME"), None);
+        assert_eq!(get("VRIFT_STAT_WATCHDOG_US"), Some("0"));
+    }
+
+    #[test]
+    fn test_key_normalization_is_global_and_reaches_shim_env() {
+        let mut base = Config::default();
+        assert_eq!(
+            base.storage.normalization(),
+            Normalization::PLATFORM_DEFAULT
+        );
+        assert!(!base
+            .shim_env()
+            .iter()
+            .any(|(k, _)| k == "VRIFT_KEY_NORMALIZATION"));
+
+        // A project can't change the form vriftd builds keys in
+        let overlay_toml = r#"
+            [storage]
+            key_normalization = "nfd"
+        "#;
+        let raw: toml::Value = toml::from_str(overlay_toml).unwrap();
+        let overlay: Config = toml::from_str(overlay_toml).unwrap();
+        base.merge_with_presence(overlay.clone(), &raw);
+        assert_eq!(
+            base.storage.normalization(),
+            Normalization::PLATFORM_DEFAULT
+        );
+
+        let mut base = overlay;
+        assert_eq!(base.storage.normalization(), Normalization::Nfd);
+        let env = base.shim_env();
+        let value = env
+            .iter()
+            .find(|(k, _)| k == "VRIFT_KEY_NORMALIZATION")
+            .map(|(_, v)| v.as_str());
+        assert_eq!(value, Some("nfd"));
+
+        base.storage.key_normalization = "nfkc".to_string();
+        assert_eq!(
+            base.storage.normalization(),
+            Normalization::PLATFORM_DEFAULT
+        );
+    }
+
+    #[test]
+    fn test_manifest_backend_merges_from_project() {
+        let mut base = Config::default();
+        assert_eq!(base.storage.manifest_backend(), ManifestBackend::Lmdb);
+
+        let overlay_toml = r#"
+            [storage]
+            manifest_backend = "redb"
+        "#;
+        let raw: toml::Value = toml::from_str(overlay_toml).unwrap();
+        let overlay: Config = toml::from_str(overlay_toml).unwrap();
+        base.merge_with_presence(overlay, &raw);
+        assert_eq!(base.storage.manifest_backend(), ManifestBackend::Redb);
+
+        base.storage.manifest_backend = "sled".to_string();
+        assert_eq!(base.storage.manifest_backend(), ManifestBackend::Lmdb);
+    }
+
+    #[test]
+    fn test_serve_policy_merges_and_reaches_shim_env() {
+        let mut base: Config = toml::from_str(
+            r#"
+            [serve.policy]
+            "*.so" = "materialize"
+            "*.db" = "materialize"
+        "#,
+        )
+        .unwrap();
+
+        let overlay_toml = r#"
+            [serve.policy]
+            "*.db" = "cas"
+        "#;
+        let raw: toml::Value = toml::from_str(overlay_toml).unwrap();
+        let overlay: Config = toml::from_str(overlay_toml).unwrap();
+        base.merge_with_presence(overlay, &raw);
+
+        let policy = base.serve.policy();
+        assert_eq!(policy.mode_for("/lib/libz.so"), ServeMode::Materialize);
+        assert_eq!(policy.mode_for("/app.db"), ServeMode::Cas);
+
+        let env = base.shim_env();
+        let value = env
+            .iter()
+            .find(|(k, _)| k == "VRIFT_SERVE_POLICY")
+            .map(|(_, v)| v.as_str());
+        assert_eq!(value, Some("*.db=cas:*.so=materialize"));
+        assert!(!Config::default()
+            .shim_env()
+            .iter()
+            .any(|(k, _)| k == "VRIFT_SERVE_POLICY"));
+
+        assert!(toml::from_str::<Config>("[serve.policy]\n\"*.so\" = \"mmap\"\n").is_err());
+    }
+
+    #[test]
+    fn test_merge_hot_write_settings() {
+        let mut base = Config::default();
+        base.ingest.hot_write_window_secs = 600;
+
+        let overlay_toml = r#"
+            [ingest]
+            hot_write_breaks = 0
+        "#;
+        let raw: toml::Value = toml::from_str(overlay_toml).unwrap();
+        let overlay: Config = toml::from_str(overlay_toml).unwrap();
+        base.merge_with_presence(overlay, &raw);
+
+        assert_eq!(base.ingest.hot_write_breaks, 0);
+        assert_eq!(base.ingest.hot_write_window_secs, 600);
+    }
+
+    #[test]
+    fn test_merge_keeps_global_scanner() {
+        let mut base = Config::default();
+        base.ingest.scanner.command = vec!["clamscan-batch".to_string()];
+
+        let overlay_toml = r#"
+            [ingest.scanner]
+            command = []
+        "#;
+        let raw: toml::Value = toml::from_str(overlay_toml).unwrap();
+        let overlay: Config = toml::from_str(overlay_toml).unwrap();
+        base.merge_with_presence(overlay, &raw);
+
+        assert_eq!(base.ingest.scanner.command, vec!["clamscan-batch"]);
+    }
+
+    #[test]
+    fn test_profile_overrides_only_its_keys() {
+        let mut config: Config = toml::from_str(
+            r#"
+            [gc]
+            grace_secs = 600
+            refcount = true
+
+            [profile.ci.gc]
+            grace_secs = 0
+
+            [profile.ci.tiers]
+            tier1_patterns = ["vendor/"]
+
+            [profile.ci.ingest]
+            ignore_patterns = ["target"]
+
+            [profile.ci.serve.policy]
+            "*.so" = "materialize"
+        "#,
+        )
+        .unwrap();
+        let tier2 = config.tiers.tier2_patterns.clone();
+
+        config.apply_profile("ci").unwrap();
+        assert_eq!(config.gc.grace_secs, 0);
+        assert!(config.gc.refcount);
+        assert_eq!(config.tiers.tier1_patterns, vec!["vendor/"]);
+        assert_eq!(config.tiers.tier2_patterns, tier2);
+        assert_eq!(config.ingest.ignore_patterns, vec!["target"]);
+        assert_eq!(
+            config.serve.policy.get("*.so"),
+            Some(&ServeMode::Materialize)
+        );
+    }
+
+    #[test]
+    fn test_profile_errors() {
+        let mut config: Config = toml::from_str(
+            r#"
+            [profile.dev.tiers]
+            tier1_patterns = []
+
+            [profile.remote.daemon]
+            socket = "/tmp/other.sock"
+
+            [profile.threads.ingest]
+            threads = 64
+        "#,
+        )
+        .unwrap();
+
+        let err = config.apply_profile("ci").unwrap_err().to_string();
+        assert!(err.contains("dev, remote, threads"), "{}", err);
+        assert!(config.apply_profile("remote").is_err());
+        assert!(config.apply_profile("threads").is_err());
+        assert_eq!(config.daemon.socket, DaemonConfig::default().socket);
+        assert_eq!(config.ingest.threads, None);
+    }
+
+    #[test]
+    fn test_merge_project_profile_replaces_global_one() {
+        let mut base: Config = toml::from_str(
+            r#"
+            [profile.ci.gc]
+            grace_secs = 0
+            [profile.dev.gc]
+            refcount = true
+        "#,
+        )
+        .unwrap();
+
+        let overlay_toml = r#"
+            [profile.ci.gc]
+            refcount = true
+        "#;
+        let raw: toml::Value = toml::from_str(overlay_toml).unwrap();
+        let overlay: Config = toml::from_str(overlay_toml).unwrap();
+        base.merge_with_presence(overlay, &raw);
+        assert!(base.profile.contains_key("dev"));
+
+        base.apply_profile("ci").unwrap();
+        assert!(base.gc.refcount);
+        assert_eq!(base.gc.grace_secs, GcConfig::default().grace_secs);
+    }
+
+    #[test]
+    fn test_merge_gc_grace() {
+        let mut base = Config::default();
+        assert_eq!(base.gc_grace(), std::time::Duration::from_secs(86400));
+
+        let overlay_toml = "[gc]\ngrace_secs = 0\n";
+        let raw: toml::Value = toml::from_str(overlay_toml).unwrap();
+        let overlay: Config = toml::from_str(overlay_toml).unwrap();
+        base.merge_with_presence(overlay, &raw);
+        assert_eq!(base.gc_grace(), std::time::Duration::ZERO);
+        assert!(!base.gc.refcount);
+
+        let overlay_toml = "[gc]\nrefcount = true\n";
+        let raw: toml::Value = toml::from_str(overlay_toml).unwrap();
+        let overlay: Config = toml::from_str(overlay_toml).unwrap();
+        base.merge_with_presence(overlay, &raw);
+        assert!(base.gc.refcount);
+        assert_eq!(base.gc_grace(), std::time::Duration::ZERO);
+    }
+
     // ========== Environment Override Tests ==========
 
     #[test]
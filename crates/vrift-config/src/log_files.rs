@@ -0,0 +1,250 @@
+//! Log files under `daemon.log_dir`: naming, size-based rotation, retention
+//! and discovery for `vrift logs`.
+//!
+//! | File | Written by |
+//! |------|------------|
+//! | `vrift-shim-<pid>.log` (+ `.1` .. `.N` rotations) | shim, at process exit |
+//! | `vrift-shim-crash-<pid>.log` | shim crash handler |
+//! | `vriftd*.log` | daemon stdout/stderr (service units) |
+//! | `vriftd-crash-<ms>-<pid>.json` | daemon panic hook |
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+pub const SHIM_LOG_PREFIX: &str = "vrift-shim-";
+pub const SHIM_CRASH_PREFIX: &str = "vrift-shim-crash-";
+pub const DAEMON_LOG_PREFIX: &str = "vriftd";
+pub const DAEMON_CRASH_PREFIX: &str = "vriftd-crash-";
+
+/// Rotated generations kept per shim log (`.log.1` is the newest)
+pub const ROTATIONS: u32 = 3;
+
+/// Default size at which a shim log is rotated
+pub const DEFAULT_MAX_BYTES: u64 = 1024 * 1024;
+
+/// Default budget for all shim logs in `log_dir`; the daemon deletes the
+/// oldest beyond it
+pub const DEFAULT_RETAIN_BYTES: u64 = 64 * 1024 * 1024;
+
+pub fn shim_log_path(dir: &Path, pid: u32) -> PathBuf {
+    dir.join(format!("{}{}.log", SHIM_LOG_PREFIX, pid))
+}
+
+fn rotated_path(path: &Path, generation: u32) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", generation));
+    PathBuf::from(name)
+}
+
+/// Rotate `path` if it has reached `max_bytes`: `.log` becomes `.log.1`,
+/// `.log.1` becomes `.log.2`, and so on; the oldest generation is dropped.
+/// Returns whether a rotation happened. `max_bytes == 0` never rotates.
+pub fn rotate_if_needed(path: &Path, max_bytes: u64) -> io::Result<bool> {
+    if max_bytes == 0 {
+        return Ok(false);
+    }
+    match fs::metadata(path) {
+        Ok(meta) if meta.len() >= max_bytes => {}
+        Ok(_) => return Ok(false),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e),
+    }
+
+    for generation in (1..ROTATIONS).rev() {
+        let from = rotated_path(path, generation);
+        if from.exists() {
+            fs::rename(&from, rotated_path(path, generation + 1))?;
+        }
+    }
+    fs::rename(path, rotated_path(path, 1))?;
+    Ok(true)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogKind {
+    Shim,
+    ShimCrash,
+    Daemon,
+    DaemonCrash,
+}
+
+/// One log file found in `log_dir`
+#[derive(Debug, Clone)]
+pub struct LogFile {
+    pub path: PathBuf,
+    pub kind: LogKind,
+    /// Process the file belongs to (not known for daemon stdout/stderr logs)
+    pub pid: Option<u32>,
+    /// 0 for the live file, 1.. for rotated generations
+    pub generation: u32,
+    pub len: u64,
+    pub modified: SystemTime,
+}
+
+/// Classify a file name: `(kind, pid, generation)`
+fn parse_name(name: &str) -> Option<(LogKind, Option<u32>, u32)> {
+    if let Some(rest) = name.strip_prefix(SHIM_CRASH_PREFIX) {
+        let pid = rest.strip_suffix(".log")?.parse().ok()?;
+        return Some((LogKind::ShimCrash, Some(pid), 0));
+    }
+    if let Some(rest) = name.strip_prefix(SHIM_LOG_PREFIX) {
+        let (stem, generation) = match rest.rsplit_once(".log.") {
+            Some((stem, generation)) => (stem, generation.parse().ok()?),
+            None => (rest.strip_suffix(".log")?, 0),
+        };
+        return Some((LogKind::Shim, Some(stem.parse().ok()?), generation));
+    }
+    if let Some(rest) = name.strip_prefix(DAEMON_CRASH_PREFIX) {
+        // <unix ms>-<pid>.json
+        let pid = rest.strip_suffix(".json")?.rsplit_once('-')?.1.parse().ok();
+        return Some((LogKind::DaemonCrash, pid, 0));
+    }
+    if name.starts_with(DAEMON_LOG_PREFIX) && name.ends_with(".log") {
+        return Some((LogKind::Daemon, None, 0));
+    }
+    None
+}
+
+/// All vrift log files in `dir`, oldest first. For one pid, rotated
+/// generations come before the live file, so reading them in order gives a
+/// chronological log.
+pub fn list(dir: &Path) -> io::Result<Vec<LogFile>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let Some((kind, pid, generation)) = name.to_str().and_then(parse_name) else {
+            continue;
+        };
+        let meta = match entry.metadata() {
+            Ok(m) if m.is_file() => m,
+            _ => continue,
+        };
+        files.push(LogFile {
+            path: entry.path(),
+            kind,
+            pid,
+            generation,
+            len: meta.len(),
+            modified: meta.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+        });
+    }
+    files.sort_by(|a, b| {
+        a.modified
+            .cmp(&b.modified)
+            .then(b.generation.cmp(&a.generation))
+            .then(a.path.cmp(&b.path))
+    });
+    Ok(files)
+}
+
+/// Delete the oldest shim logs until those left total at most
+/// `retain_bytes`. Crash reports and daemon logs are never touched.
+/// Returns the number of bytes freed; `retain_bytes == 0` keeps everything.
+pub fn prune(dir: &Path, retain_bytes: u64) -> io::Result<u64> {
+    if retain_bytes == 0 {
+        return Ok(0);
+    }
+    let shim_logs: Vec<LogFile> = list(dir)?
+        .into_iter()
+        .filter(|f| f.kind == LogKind::Shim)
+        .collect();
+    let mut total: u64 = shim_logs.iter().map(|f| f.len).sum();
+    let mut freed = 0;
+    for file in shim_logs {
+        if total <= retain_bytes {
+            break;
+        }
+        match fs::remove_file(&file.path) {
+            Ok(()) => {}
+            // Rotated away or pruned concurrently
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        total -= file.len;
+        freed += file.len;
+    }
+    Ok(freed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_name() {
+        assert_eq!(
+            parse_name("vrift-shim-42.log"),
+            Some((LogKind::Shim, Some(42), 0))
+        );
+        assert_eq!(
+            parse_name("vrift-shim-42.log.2"),
+            Some((LogKind::Shim, Some(42), 2))
+        );
+        assert_eq!(
+            parse_name("vrift-shim-crash-7.log"),
+            Some((LogKind::ShimCrash, Some(7), 0))
+        );
+        assert_eq!(
+            parse_name("vriftd-crash-1700000000000-99.json"),
+            Some((LogKind::DaemonCrash, Some(99), 0))
+        );
+        assert_eq!(
+            parse_name("vriftd.err.log"),
+            Some((LogKind::Daemon, None, 0))
+        );
+        assert_eq!(parse_name("vrift-shim-abc.log"), None);
+        assert_eq!(parse_name("vrift-manifest.mmap"), None);
+    }
+
+    #[test]
+    fn test_rotate_keeps_bounded_generations() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = shim_log_path(dir.path(), 1);
+
+        assert!(!rotate_if_needed(&path, 4).unwrap());
+        for round in 0..ROTATIONS + 2 {
+            fs::write(&path, format!("round{}", round)).unwrap();
+            assert!(rotate_if_needed(&path, 4).unwrap());
+        }
+
+        assert!(!path.exists());
+        assert_eq!(
+            fs::read_to_string(rotated_path(&path, 1)).unwrap(),
+            format!("round{}", ROTATIONS + 1)
+        );
+        assert!(rotated_path(&path, ROTATIONS).exists());
+        assert!(!rotated_path(&path, ROTATIONS + 1).exists());
+
+        // Below the limit: left alone
+        fs::write(&path, "ab").unwrap();
+        assert!(!rotate_if_needed(&path, 4).unwrap());
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn test_prune_drops_oldest_shim_logs() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = shim_log_path(dir.path(), 1);
+        let new = shim_log_path(dir.path(), 2);
+        let crash = dir.path().join("vrift-shim-crash-1.log");
+        fs::write(&old, vec![b'x'; 100]).unwrap();
+        fs::write(&crash, vec![b'x'; 100]).unwrap();
+        let earlier = SystemTime::now() - std::time::Duration::from_secs(60);
+        fs::File::options()
+            .write(true)
+            .open(&old)
+            .unwrap()
+            .set_modified(earlier)
+            .unwrap();
+        fs::write(&new, vec![b'x'; 100]).unwrap();
+
+        assert_eq!(prune(dir.path(), 150).unwrap(), 100);
+        assert!(!old.exists());
+        assert!(new.exists());
+        assert!(crash.exists());
+        assert_eq!(prune(dir.path(), 0).unwrap(), 0);
+    }
+}
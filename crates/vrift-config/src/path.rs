@@ -207,21 +207,85 @@ pub fn get_manifest_db_path(project_id: &str) -> Option<PathBuf> {
     })
 }
 
-/// Get the standardized VDir mmap path for a given project ID.
+/// Base of the per-workspace runtime directories: `$VRIFT_RUNTIME_DIR`, else
+/// `$XDG_RUNTIME_DIR/vrift`. None when neither is set.
 ///
-/// Standard path: ~/.vrift/vdir/<project_id>.mmap (using first 16 chars of ID)
+/// `Config::shim_env` exports `VRIFT_RUNTIME_DIR`, so shims find the same
+/// directories as the daemon that created them.
+pub fn runtime_base_dir() -> Option<PathBuf> {
+    runtime_base_from(
+        std::env::var_os("VRIFT_RUNTIME_DIR"),
+        std::env::var_os("XDG_RUNTIME_DIR"),
+    )
+}
+
+fn runtime_base_from(
+    vrift: Option<std::ffi::OsString>,
+    xdg: Option<std::ffi::OsString>,
+) -> Option<PathBuf> {
+    let non_empty = |v: std::ffi::OsString| (!v.is_empty()).then(|| PathBuf::from(v));
+    vrift
+        .and_then(non_empty)
+        .or_else(|| xdg.and_then(non_empty).map(|xdg| xdg.join("vrift")))
+}
+
+/// Get the runtime directory of a workspace: the vDird socket and VDir mmap
+/// of one project live there, apart from every other project's.
+///
+/// Standard path: <runtime base>/<project_id> (using first 16 chars of ID);
+/// None without a runtime base, in which case the per-file paths below fall
+/// back to their shared locations
+pub fn get_runtime_dir(project_id: &str) -> Option<PathBuf> {
+    runtime_base_dir().map(|base| base.join(&project_id[..16]))
+}
+
+/// Create a workspace runtime directory (and the base above it) readable by
+/// the owner only. vriftd calls it before spawning the project's vDird.
+pub fn create_runtime_dir(dir: &Path) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        std::fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(dir)
+    }
+    #[cfg(not(unix))]
+    std::fs::create_dir_all(dir)
+}
+
+/// Get the standardized VDir mmap path for a given project ID: the file
+/// vDird writes and every shim of the project maps.
+///
+/// Standard path: <runtime dir>/vdir.mmap when there is a runtime base;
+/// otherwise /dev/shm/vrift_vdir_<project_id> on Linux, so the table lives
+/// in memory, and ~/.vrift/vdir/<project_id>.vdir elsewhere (using first 16
+/// chars of ID)
 pub fn get_vdir_mmap_path(project_id: &str) -> Option<PathBuf> {
+    if let Some(dir) = get_runtime_dir(project_id) {
+        return Some(dir.join("vdir.mmap"));
+    }
+    if cfg!(target_os = "linux") {
+        return Some(PathBuf::from(format!(
+            "/dev/shm/vrift_vdir_{}",
+            &project_id[..16]
+        )));
+    }
     dirs::home_dir().map(|h| {
         h.join(".vrift")
             .join("vdir")
-            .join(format!("{}.mmap", &project_id[..16]))
+            .join(format!("{}.vdir", &project_id[..16]))
     })
 }
 
 /// Get the standardized vDird socket path for a given project ID.
 ///
-/// Standard path: ~/.vrift/sockets/<project_id>.sock (using first 16 chars of ID)
+/// Standard path: <runtime dir>/vdird.sock when there is a runtime base,
+/// else ~/.vrift/sockets/<project_id>.sock (using first 16 chars of ID)
 pub fn get_vdird_socket_path(project_id: &str) -> Option<PathBuf> {
+    if let Some(dir) = get_runtime_dir(project_id) {
+        return Some(dir.join("vdird.sock"));
+    }
     dirs::home_dir().map(|h| {
         h.join(".vrift")
             .join("sockets")
@@ -229,6 +293,28 @@ pub fn get_vdird_socket_path(project_id: &str) -> Option<PathBuf> {
     })
 }
 
+/// Get the standardized hot packfile path for a given project ID.
+///
+/// Standard path: ~/.vrift/packs/<project_id>.pack (using first 16 chars of ID)
+pub fn get_pack_path(project_id: &str) -> Option<PathBuf> {
+    dirs::home_dir().map(|h| {
+        h.join(".vrift")
+            .join("packs")
+            .join(format!("{}.pack", &project_id[..16]))
+    })
+}
+
+/// Get the standardized access profile path a project's packfile is built from.
+///
+/// Standard path: ~/.vrift/packs/<project_id>.profile (using first 16 chars of ID)
+pub fn get_access_profile_path(project_id: &str) -> Option<PathBuf> {
+    dirs::home_dir().map(|h| {
+        h.join(".vrift")
+            .join("packs")
+            .join(format!("{}.profile", &project_id[..16]))
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -363,4 +449,38 @@ mod tests {
         let result = normalize_relative_to("src/main.rs", temp.path()).unwrap();
         assert_eq!(result, PathBuf::from("src/main.rs"));
     }
+
+    #[test]
+    fn test_runtime_base_prefers_vrift_dir() {
+        let base = |vrift: Option<&str>, xdg: Option<&str>| {
+            runtime_base_from(vrift.map(Into::into), xdg.map(Into::into))
+        };
+        assert_eq!(
+            base(Some("/run/vr"), Some("/run/user/1000")),
+            Some(PathBuf::from("/run/vr"))
+        );
+        assert_eq!(
+            base(None, Some("/run/user/1000")),
+            Some(PathBuf::from("/run/user/1000/vrift"))
+        );
+        assert_eq!(
+            base(Some(""), Some("/run/user/1000")),
+            Some(PathBuf::from("/run/user/1000/vrift"))
+        );
+        assert_eq!(base(None, Some("")), None);
+        assert_eq!(base(None, None), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_create_runtime_dir_owner_only() {
+        use std::os::unix::fs::PermissionsExt;
+        let temp = tempdir().unwrap();
+        let dir = temp.path().join("vrift").join("0123456789abcdef");
+        create_runtime_dir(&dir).unwrap();
+        // Again: an existing directory is fine
+        create_runtime_dir(&dir).unwrap();
+        let mode = fs::metadata(&dir).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+    }
 }
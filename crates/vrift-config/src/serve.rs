@@ -0,0 +1,144 @@
+//! How the shim serves reads of VFS files, per path pattern (`[serve.policy]`).
+//!
+//! By default a read-only open is redirected to the shared CAS blob, which is
+//! cheap and lets every process map the same pages. A few tools need the file
+//! they open to be a real, private file at its project path instead: `dlopen`
+//! of a library that finds its neighbours through `$ORIGIN`, or sqlite, whose
+//! locks must not be shared with every other database of the same content.
+//!
+//! ```toml
+//! [serve.policy]
+//! "*.so" = "materialize"
+//! "*.sqlite" = "materialize"
+//! "vendor/fast/*.so" = "cas"
+//! ```
+//!
+//! Patterns without a `/` match the file name, others match the path relative
+//! to the project root. `*` and `?` do not cross `/`, `**` does. When several
+//! patterns match, the longest one wins. The policy reaches the shim as
+//! `VRIFT_SERVE_POLICY` (`pattern=mode` pairs separated by `:`).
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use vrift_ipc::path_key::glob_matches;
+
+/// How reads of a VFS file are served
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ServeMode {
+    /// Open the shared CAS blob (default)
+    #[default]
+    Cas,
+    /// Open the real file at its project path when one exists on disk,
+    /// otherwise a private copy of the blob
+    Materialize,
+}
+
+impl ServeMode {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "cas" => Some(Self::Cas),
+            "materialize" => Some(Self::Materialize),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Cas => "cas",
+            Self::Materialize => "materialize",
+        }
+    }
+}
+
+/// Parsed `[serve.policy]`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ServePolicy {
+    rules: Vec<(String, ServeMode)>,
+}
+
+impl ServePolicy {
+    pub fn from_map(map: &BTreeMap<String, ServeMode>) -> Self {
+        let rules = map
+            .iter()
+            .map(|(pattern, mode)| (pattern.clone(), *mode))
+            .collect();
+        Self { rules }
+    }
+
+    /// Parse `VRIFT_SERVE_POLICY`, skipping malformed entries
+    pub fn from_env_value(value: &str) -> Self {
+        let rules = value
+            .split(':')
+            .filter_map(|rule| {
+                let (pattern, mode) = rule.rsplit_once('=')?;
+                if pattern.is_empty() {
+                    return None;
+                }
+                Some((pattern.to_string(), ServeMode::parse(mode)?))
+            })
+            .collect();
+        Self { rules }
+    }
+
+    pub fn to_env_value(&self) -> String {
+        self.rules
+            .iter()
+            .map(|(pattern, mode)| format!("{}={}", pattern, mode.as_str()))
+            .collect::<Vec<_>>()
+            .join(":")
+    }
+
+    pub fn rules(&self) -> &[(String, ServeMode)] {
+        &self.rules
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Mode for `path` (relative to the project root; a leading `/`, as in
+    /// manifest keys, is ignored)
+    pub fn mode_for(&self, path: &str) -> ServeMode {
+        self.rules
+            .iter()
+            .filter(|(pattern, _)| glob_matches(pattern, path))
+            .max_by_key(|(pattern, _)| pattern.len())
+            .map(|(_, mode)| *mode)
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(rules: &[(&str, ServeMode)]) -> ServePolicy {
+        let map = rules.iter().map(|(p, m)| (p.to_string(), *m)).collect();
+        ServePolicy::from_map(&map)
+    }
+
+    #[test]
+    fn test_mode_for_prefers_longest_pattern() {
+        let policy = policy(&[
+            ("*.so", ServeMode::Materialize),
+            ("vendor/fast/*.so", ServeMode::Cas),
+        ]);
+        assert_eq!(policy.mode_for("/lib/libz.so"), ServeMode::Materialize);
+        assert_eq!(policy.mode_for("/vendor/fast/libx.so"), ServeMode::Cas);
+        assert_eq!(policy.mode_for("/src/main.rs"), ServeMode::Cas);
+    }
+
+    #[test]
+    fn test_env_value_roundtrip() {
+        let policy = policy(&[
+            ("*.so", ServeMode::Materialize),
+            ("lib/*.a", ServeMode::Cas),
+        ]);
+        let value = policy.to_env_value();
+        assert_eq!(value, "*.so=materialize:lib/*.a=cas");
+        assert_eq!(ServePolicy::from_env_value(&value), policy);
+        assert!(ServePolicy::from_env_value("junk:=cas:*.x=nope").is_empty());
+    }
+}
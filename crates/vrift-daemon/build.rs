@@ -0,0 +1,62 @@
+//! Build script for vriftd
+//!
+//! With the `grpc` feature, generates the tonic service for `vrift.v1.Daemon`.
+//! The messages are hand-written prost structs in `src/grpc.rs` (mirroring
+//! `proto/vrift/v1/daemon.proto`), so no `protoc` is needed at build time.
+
+fn main() {
+    #[cfg(feature = "grpc")]
+    grpc::compile();
+}
+
+#[cfg(feature = "grpc")]
+mod grpc {
+    use tonic_build::manual::{Builder, Method, Service};
+
+    /// (rust name, route name, input, output)
+    const METHODS: &[(&str, &str, &str, &str)] = &[
+        ("status", "Status", "StatusRequest", "StatusReply"),
+        (
+            "warm_workspace",
+            "WarmWorkspace",
+            "WarmWorkspaceRequest",
+            "WarmWorkspaceReply",
+        ),
+        ("ingest", "Ingest", "IngestRequest", "IngestReply"),
+        (
+            "manifest_get",
+            "ManifestGet",
+            "ManifestGetRequest",
+            "ManifestGetReply",
+        ),
+        (
+            "manifest_list_dir",
+            "ManifestListDir",
+            "ManifestListDirRequest",
+            "ManifestListDirReply",
+        ),
+        ("gc", "Gc", "GcRequest", "Job"),
+        ("list_jobs", "ListJobs", "ListJobsRequest", "ListJobsReply"),
+        ("get_job", "GetJob", "JobRequest", "Job"),
+        ("cancel_job", "CancelJob", "JobRequest", "Job"),
+    ];
+
+    pub fn compile() {
+        println!("cargo:rerun-if-changed=build.rs");
+        let mut service = Service::builder().name("Daemon").package("vrift.v1");
+        for (name, route, input, output) in METHODS {
+            service = service.method(
+                Method::builder()
+                    .name(*name)
+                    .route_name(*route)
+                    .input_type(format!("crate::grpc::proto::{}", input))
+                    .output_type(format!("crate::grpc::proto::{}", output))
+                    .codec_path("tonic::codec::ProstCodec")
+                    .build(),
+            );
+        }
+        Builder::new()
+            .build_client(false)
+            .compile(&[service.build()]);
+    }
+}
@@ -1,4 +1,3 @@
-use std::time::{SystemTime, UNIX_EPOCH};
 use vrift_ipc::client::DaemonClient;
 use vrift_ipc::VeloRequest;
 use vrift_manifest::VnodeEntry;
@@ -8,10 +7,7 @@ async fn main() -> anyhow::Result<()> {
     let mut client = DaemonClient::connect().await?;
     client.handshake().await?;
 
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_nanos() as u64;
+    let now = vrift_ipc::mtime::now();
 
     let entry = VnodeEntry::new_file(
         [0u8; 32], // dummy hash
@@ -0,0 +1,103 @@
+//! Socket activation
+//!
+//! When `vrift daemon install` sets vriftd up as a service, the service
+//! manager binds the IPC socket and starts the daemon on the first
+//! connection. systemd passes the listening socket as fd 3 (`LISTEN_FDS`);
+//! launchd hands it out through `launch_activate_socket`.
+
+use std::os::unix::net::UnixListener;
+
+/// Listening socket inherited from the service manager, if any
+pub fn inherited_listener() -> std::io::Result<Option<UnixListener>> {
+    imp::inherited_listener()
+}
+
+#[cfg(target_os = "linux")]
+mod imp {
+    use std::os::fd::FromRawFd;
+    use std::os::unix::net::UnixListener;
+
+    /// First descriptor passed by systemd (`SD_LISTEN_FDS_START`)
+    const LISTEN_FDS_START: libc::c_int = 3;
+
+    pub fn inherited_listener() -> std::io::Result<Option<UnixListener>> {
+        let for_us = std::env::var("LISTEN_PID")
+            .ok()
+            .and_then(|pid| pid.parse::<u32>().ok())
+            == Some(std::process::id());
+        let fds = std::env::var("LISTEN_FDS")
+            .ok()
+            .and_then(|n| n.parse::<u32>().ok())
+            .unwrap_or(0);
+        // Don't let vDird children think the sockets are theirs
+        std::env::remove_var("LISTEN_PID");
+        std::env::remove_var("LISTEN_FDS");
+        std::env::remove_var("LISTEN_FDNAMES");
+        if !for_us || fds == 0 {
+            return Ok(None);
+        }
+        if fds > 1 {
+            tracing::warn!("vriftd: {} sockets passed, using the first", fds);
+        }
+
+        let fd = LISTEN_FDS_START;
+        if unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) } < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        // SAFETY: systemd passes ownership of the descriptor to this process.
+        Ok(Some(unsafe { UnixListener::from_raw_fd(fd) }))
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod imp {
+    use std::ffi::CString;
+    use std::os::fd::FromRawFd;
+    use std::os::unix::net::UnixListener;
+
+    /// `Sockets` key of the launchd plist written by `vrift daemon install`
+    const SOCKET_NAME: &str = "Listeners";
+
+    extern "C" {
+        fn launch_activate_socket(
+            name: *const libc::c_char,
+            fds: *mut *mut libc::c_int,
+            cnt: *mut libc::size_t,
+        ) -> libc::c_int;
+    }
+
+    pub fn inherited_listener() -> std::io::Result<Option<UnixListener>> {
+        let name = CString::new(SOCKET_NAME).expect("static name has no NUL");
+        let mut fds: *mut libc::c_int = std::ptr::null_mut();
+        let mut cnt: libc::size_t = 0;
+        let ret = unsafe { launch_activate_socket(name.as_ptr(), &mut fds, &mut cnt) };
+        match ret {
+            0 => {}
+            // Not started by launchd, or no socket of that name
+            libc::ESRCH | libc::ENOENT => return Ok(None),
+            errno => return Err(std::io::Error::from_raw_os_error(errno)),
+        }
+        if fds.is_null() || cnt == 0 {
+            return Ok(None);
+        }
+        // SAFETY: launchd returns `cnt` descriptors in a malloc'd array that
+        // the caller owns.
+        let listener = unsafe {
+            let fd = *fds;
+            for i in 1..cnt {
+                libc::close(*fds.add(i));
+            }
+            libc::free(fds as *mut libc::c_void);
+            libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC);
+            UnixListener::from_raw_fd(fd)
+        };
+        Ok(Some(listener))
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+mod imp {
+    pub fn inherited_listener() -> std::io::Result<Option<std::os::unix::net::UnixListener>> {
+        Ok(None)
+    }
+}
@@ -0,0 +1,47 @@
+//! Bearer tokens for the network facades (gRPC, HTTP export, IPC over TCP)
+//! and the upstream CAS
+
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+
+/// The token clients of `section` must present, on any address: the
+/// facade runs requests as the daemon's user, so without a token every
+/// local user could, loopback included
+#[cfg(any(feature = "grpc", feature = "http", feature = "tcp"))]
+pub fn require_token(section: &str, token_file: Option<&Path>) -> Result<String> {
+    match token_file {
+        Some(path) => read_token(section, path),
+        None => bail!("Refusing to serve [{}] without token_file", section),
+    }
+}
+
+/// The token in `[section] token_file`
+pub fn read_token(section: &str, token_file: &Path) -> Result<String> {
+    let path = vrift_manifest::normalize_path(&token_file.to_string_lossy());
+    let token = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read [{}] token file {:?}", section, path))?;
+    let token = token.trim().to_string();
+    if token.is_empty() {
+        bail!("[{}] token file {:?} is empty", section, path);
+    }
+    Ok(token)
+}
+
+/// Whether an `authorization` header value carries `token`
+#[cfg(any(feature = "grpc", feature = "http"))]
+pub fn bearer_matches(header: Option<&str>, token: &str) -> bool {
+    let presented = header.and_then(|v| v.strip_prefix("Bearer ")).unwrap_or("");
+    token_matches(presented, token)
+}
+
+/// Whether `presented` is `token`, compared in constant time
+#[cfg(any(feature = "grpc", feature = "http", feature = "tcp"))]
+pub fn token_matches(presented: &str, token: &str) -> bool {
+    constant_time_eq(presented.as_bytes(), token.as_bytes())
+}
+
+#[cfg(any(feature = "grpc", feature = "http", feature = "tcp"))]
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
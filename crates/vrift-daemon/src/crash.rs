@@ -0,0 +1,140 @@
+//! Panic reports
+//!
+//! A panic in a connection or job task only kills that task, so without a
+//! report it shows up as a client seeing EOF. The hook writes
+//! `<log_dir>/vriftd-crash-<unix ms>-<pid>.json` with the panic message,
+//! backtrace and, when the panic happened while serving a request, the
+//! request and the connection's workspace.
+
+use std::cell::RefCell;
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+/// Longest request rendering kept in a report
+const MAX_REQUEST_LEN: usize = 512;
+
+/// What a connection task is doing, for crash reports
+#[derive(Default)]
+pub struct RequestContext {
+    request: Option<String>,
+    workspace: Option<String>,
+}
+
+tokio::task_local! {
+    pub static REQUEST_CONTEXT: RefCell<RequestContext>;
+}
+
+/// Record the request about to be handled by the current connection task
+pub fn enter_request(req: &vrift_ipc::VeloRequest) {
+    let _ = REQUEST_CONTEXT.try_with(|ctx| {
+        let mut rendered = BoundedString::default();
+        let _ = write!(rendered, "{:?}", req);
+        ctx.borrow_mut().request = Some(rendered.0);
+    });
+}
+
+/// Mark the current connection task as idle between requests
+pub fn leave_request(workspace: Option<&std::path::Path>) {
+    let _ = REQUEST_CONTEXT.try_with(|ctx| {
+        let mut ctx = ctx.borrow_mut();
+        ctx.request = None;
+        ctx.workspace = workspace.map(|p| p.display().to_string());
+    });
+}
+
+#[derive(Serialize)]
+struct CrashReport {
+    time: u64,
+    pid: u32,
+    version: &'static str,
+    thread: Option<String>,
+    message: String,
+    location: Option<String>,
+    request: Option<String>,
+    workspace: Option<String>,
+    backtrace: String,
+}
+
+/// Install the panic hook; the default hook still prints to stderr
+pub fn install_panic_hook(log_dir: PathBuf) {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let message = if let Some(s) = info.payload().downcast_ref::<&str>() {
+            s.to_string()
+        } else if let Some(s) = info.payload().downcast_ref::<String>() {
+            s.clone()
+        } else {
+            "<non-string panic payload>".to_string()
+        };
+        // try_borrow: the panic may have happened while the context was borrowed
+        let (request, workspace) = REQUEST_CONTEXT
+            .try_with(|ctx| {
+                ctx.try_borrow()
+                    .map(|c| (c.request.clone(), c.workspace.clone()))
+                    .unwrap_or_default()
+            })
+            .unwrap_or_default();
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default();
+        let report = CrashReport {
+            time: now.as_secs(),
+            pid: std::process::id(),
+            version: env!("CARGO_PKG_VERSION"),
+            thread: std::thread::current().name().map(str::to_string),
+            message,
+            location: info.location().map(|l| l.to_string()),
+            request,
+            workspace,
+            backtrace: std::backtrace::Backtrace::force_capture().to_string(),
+        };
+
+        let path = log_dir.join(format!(
+            "vriftd-crash-{}-{}.json",
+            now.as_millis(),
+            report.pid
+        ));
+        // Requests can carry environment variables: keep the report private
+        let written = serde_json::to_vec_pretty(&report)
+            .map_err(std::io::Error::other)
+            .and_then(|json| {
+                use std::io::Write as _;
+                use std::os::unix::fs::OpenOptionsExt;
+                std::fs::OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .mode(0o600)
+                    .open(&path)?
+                    .write_all(&json)
+            });
+        match written {
+            Ok(()) => tracing::error!("vriftd: panic, crash report written to {}", path.display()),
+            Err(e) => tracing::error!("vriftd: panic, failed to write crash report: {}", e),
+        }
+        default_hook(info);
+    }));
+}
+
+/// Stops accepting input past `MAX_REQUEST_LEN`, so rendering a request
+/// with a large payload (bloom filter, environment) stays cheap
+#[derive(Default)]
+struct BoundedString(String);
+
+impl Write for BoundedString {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        let room = MAX_REQUEST_LEN.saturating_sub(self.0.len());
+        if s.len() <= room {
+            self.0.push_str(s);
+            return Ok(());
+        }
+        let mut end = room;
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        self.0.push_str(&s[..end]);
+        self.0.push('…');
+        Err(std::fmt::Error)
+    }
+}
@@ -0,0 +1,924 @@
+//! # gRPC facade (`--features grpc`)
+//!
+//! Serves `vrift.v1.Daemon` (see `proto/vrift/v1/daemon.proto`) so a fleet
+//! manager can warm workspaces, ingest, query manifests and run GC on many
+//! build agents. Each call is translated into the equivalent `VeloRequest`
+//! and answered by `handle_request`, so remote and local clients share one
+//! code path. Manifest queries are forwarded to the workspace's vDird, as
+//! local clients do after `RegisterWorkspace`.
+//!
+//! Calls must carry `authorization: Bearer <token>` (token from
+//! `[grpc] token_file`); the daemon refuses to serve without one, loopback
+//! address or not. TLS is enabled by `tls_cert` + `tls_key`.
+
+// tonic's interceptor and handler signatures fix the error type to `Status`.
+#![allow(clippy::result_large_err)]
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use tonic::transport::{Identity, Server, ServerTlsConfig};
+use tonic::{Request, Response, Status};
+use vrift_ipc::{VeloError, VeloErrorKind, VeloRequest, VeloResponse};
+
+use crate::DaemonState;
+use proto::daemon_server::{Daemon, DaemonServer};
+
+/// Messages of `vrift.v1`, written by hand; the tests check them against
+/// `daemon.proto`
+pub mod proto {
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct StatusRequest {}
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct StatusReply {
+        #[prost(string, tag = "1")]
+        pub status: String,
+        #[prost(string, tag = "2")]
+        pub server_version: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct WarmWorkspaceRequest {
+        #[prost(string, tag = "1")]
+        pub project_root: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct WarmWorkspaceReply {
+        #[prost(string, tag = "1")]
+        pub workspace_id: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct IngestRequest {
+        #[prost(string, tag = "1")]
+        pub path: String,
+        #[prost(string, tag = "2")]
+        pub manifest_path: String,
+        #[prost(uint32, optional, tag = "3")]
+        pub threads: Option<u32>,
+        #[prost(bool, tag = "4")]
+        pub phantom: bool,
+        #[prost(bool, tag = "5")]
+        pub tier1: bool,
+        #[prost(string, optional, tag = "6")]
+        pub prefix: Option<String>,
+        #[prost(string, optional, tag = "7")]
+        pub cas_root: Option<String>,
+        #[prost(bool, tag = "8")]
+        pub force_hash: bool,
+        #[prost(string, repeated, tag = "9")]
+        pub paths: Vec<String>,
+        #[prost(string, repeated, tag = "10")]
+        pub include: Vec<String>,
+        #[prost(string, repeated, tag = "11")]
+        pub exclude: Vec<String>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct IngestReply {
+        #[prost(uint64, tag = "1")]
+        pub files: u64,
+        #[prost(uint64, tag = "2")]
+        pub blobs: u64,
+        #[prost(uint64, tag = "3")]
+        pub new_bytes: u64,
+        #[prost(uint64, tag = "4")]
+        pub total_bytes: u64,
+        #[prost(uint64, tag = "5")]
+        pub duration_ms: u64,
+        #[prost(string, tag = "6")]
+        pub manifest_path: String,
+        #[prost(uint64, tag = "7")]
+        pub tier1_files: u64,
+        #[prost(uint64, tag = "8")]
+        pub tier2_files: u64,
+        #[prost(uint64, tag = "9")]
+        pub unchanged_files: u64,
+        #[prost(uint64, tag = "10")]
+        pub deduped_files: u64,
+        #[prost(uint64, tag = "11")]
+        pub quarantined_files: u64,
+        #[prost(message, repeated, tag = "12")]
+        pub largest_new: Vec<IngestBlob>,
+        #[prost(uint64, tag = "13")]
+        pub skipped_files: u64,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct IngestBlob {
+        #[prost(string, tag = "1")]
+        pub path: String,
+        #[prost(uint64, tag = "2")]
+        pub size: u64,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ManifestGetRequest {
+        #[prost(string, tag = "1")]
+        pub project_root: String,
+        #[prost(string, tag = "2")]
+        pub path: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Entry {
+        #[prost(bytes = "vec", tag = "1")]
+        pub content_hash: Vec<u8>,
+        #[prost(uint64, tag = "2")]
+        pub size: u64,
+        #[prost(int64, tag = "3")]
+        pub mtime: i64,
+        #[prost(uint32, tag = "4")]
+        pub mode: u32,
+        #[prost(bool, tag = "5")]
+        pub is_dir: bool,
+        #[prost(bool, tag = "6")]
+        pub is_symlink: bool,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ManifestGetReply {
+        #[prost(message, optional, tag = "1")]
+        pub entry: Option<Entry>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ManifestListDirRequest {
+        #[prost(string, tag = "1")]
+        pub project_root: String,
+        #[prost(string, tag = "2")]
+        pub path: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct DirEntry {
+        #[prost(string, tag = "1")]
+        pub name: String,
+        #[prost(bool, tag = "2")]
+        pub is_dir: bool,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ManifestListDirReply {
+        #[prost(message, repeated, tag = "1")]
+        pub entries: Vec<DirEntry>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct GcRequest {
+        #[prost(bytes = "vec", tag = "1")]
+        pub bloom_filter: Vec<u8>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ListJobsRequest {}
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct JobRequest {
+        #[prost(uint64, tag = "1")]
+        pub job_id: u64,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+    #[repr(i32)]
+    pub enum JobKind {
+        Ingest = 0,
+        Sweep = 1,
+        Scrub = 2,
+        Repack = 3,
+        Prefetch = 4,
+        Retier = 5,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+    #[repr(i32)]
+    pub enum JobState {
+        Queued = 0,
+        Running = 1,
+        Completed = 2,
+        Cancelled = 3,
+        Failed = 4,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Job {
+        #[prost(uint64, tag = "1")]
+        pub job_id: u64,
+        #[prost(enumeration = "JobKind", tag = "2")]
+        pub kind: i32,
+        #[prost(enumeration = "JobState", tag = "3")]
+        pub state: i32,
+        #[prost(string, tag = "4")]
+        pub description: String,
+        #[prost(uint64, tag = "5")]
+        pub processed: u64,
+        #[prost(uint64, tag = "6")]
+        pub total_estimate: u64,
+        #[prost(uint64, tag = "7")]
+        pub affected: u64,
+        #[prost(uint64, tag = "8")]
+        pub bytes: u64,
+        #[prost(uint64, tag = "9")]
+        pub created_at: u64,
+        #[prost(uint64, tag = "10")]
+        pub elapsed_ms: u64,
+        #[prost(uint64, optional, tag = "11")]
+        pub retry_of: Option<u64>,
+        #[prost(string, optional, tag = "12")]
+        pub error: Option<String>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ListJobsReply {
+        #[prost(message, repeated, tag = "1")]
+        pub jobs: Vec<Job>,
+    }
+
+    include!(concat!(env!("OUT_DIR"), "/vrift.v1.Daemon.rs"));
+}
+
+/// Start serving `[grpc] listen`; returns when the server stops
+pub async fn serve(state: Arc<DaemonState>, cfg: &vrift_config::GrpcConfig) -> Result<()> {
+    let Some(ref listen) = cfg.listen else {
+        return Ok(());
+    };
+    let addr: SocketAddr = listen
+        .parse()
+        .with_context(|| format!("Invalid [grpc] listen address '{}'", listen))?;
+
+    let token = crate::auth::require_token("grpc", cfg.token_file.as_deref())?;
+
+    let mut server = Server::builder();
+    match (&cfg.tls_cert, &cfg.tls_key) {
+        (Some(cert), Some(key)) => {
+            let identity = Identity::from_pem(
+                std::fs::read(cert).with_context(|| format!("Failed to read {:?}", cert))?,
+                std::fs::read(key).with_context(|| format!("Failed to read {:?}", key))?,
+            );
+            server = server.tls_config(ServerTlsConfig::new().identity(identity))?;
+        }
+        (None, None) if !addr.ip().is_loopback() => tracing::warn!(
+            "vriftd: gRPC on {} without TLS; bearer tokens travel in clear text",
+            addr
+        ),
+        (None, None) => {}
+        _ => bail!("[grpc] tls_cert and tls_key must be set together"),
+    }
+
+    let service =
+        DaemonServer::with_interceptor(DaemonService { state }, move |req| authorize(req, &token));
+    tracing::info!("vriftd: gRPC listening on {}", addr);
+    server.add_service(service).serve(addr).await?;
+    Ok(())
+}
+
+fn authorize(req: Request<()>, token: &str) -> Result<Request<()>, Status> {
+    let header = req
+        .metadata()
+        .get("authorization")
+        .and_then(|v| v.to_str().ok());
+    if crate::auth::bearer_matches(header, token) {
+        Ok(req)
+    } else {
+        Err(Status::unauthenticated("Invalid or missing bearer token"))
+    }
+}
+
+struct DaemonService {
+    state: Arc<DaemonState>,
+}
+
+impl DaemonService {
+    /// Answer `req` exactly as a local IPC client would get it answered
+    async fn call(&self, req: VeloRequest) -> Result<VeloResponse, Status> {
+        let daemon_uid = unsafe { libc::getuid() };
+        // No peer credentials: requests that need them (sessions, flock,
+        // spawn) are not exposed here.
+        match crate::handle_request(req, &self.state, None, daemon_uid, &mut None).await {
+            VeloResponse::Error(e) => Err(to_status(e)),
+            resp => Ok(resp),
+        }
+    }
+
+    /// Forward a manifest query to the vDird of `project_root`
+    async fn call_vdird(
+        &self,
+        project_root: String,
+        req: VeloRequest,
+    ) -> Result<VeloResponse, Status> {
+        let socket = match self
+            .call(VeloRequest::RegisterWorkspace { project_root })
+            .await?
+        {
+            VeloResponse::RegisterAck { vdird_socket, .. } => vdird_socket,
+            _ => return Err(unexpected("RegisterWorkspace")),
+        };
+        let mut stream = tokio::net::UnixStream::connect(&socket)
+            .await
+            .map_err(|e| Status::unavailable(format!("vDird unreachable: {}", e)))?;
+        vrift_ipc::frame_async::send_request(&mut stream, &req)
+            .await
+            .map_err(|e| Status::unavailable(format!("vDird request failed: {}", e)))?;
+        let (_, resp) = vrift_ipc::frame_async::read_response(&mut stream)
+            .await
+            .map_err(|e| Status::unavailable(format!("vDird response failed: {}", e)))?;
+        match resp {
+            VeloResponse::Error(e) => Err(to_status(e)),
+            resp => Ok(resp),
+        }
+    }
+
+    async fn job(&self, req: VeloRequest) -> Result<Response<proto::Job>, Status> {
+        match self.call(req).await? {
+            VeloResponse::JobAck { job } => Ok(Response::new(to_proto_job(job))),
+            _ => Err(unexpected("job request")),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl Daemon for DaemonService {
+    async fn status(
+        &self,
+        _request: Request<proto::StatusRequest>,
+    ) -> Result<Response<proto::StatusReply>, Status> {
+        match self.call(VeloRequest::Status).await? {
+            VeloResponse::StatusAck { status } => Ok(Response::new(proto::StatusReply {
+                status,
+                server_version: env!("CARGO_PKG_VERSION").to_string(),
+            })),
+            _ => Err(unexpected("Status")),
+        }
+    }
+
+    async fn warm_workspace(
+        &self,
+        request: Request<proto::WarmWorkspaceRequest>,
+    ) -> Result<Response<proto::WarmWorkspaceReply>, Status> {
+        let project_root = request.into_inner().project_root;
+        match self
+            .call(VeloRequest::RegisterWorkspace { project_root })
+            .await?
+        {
+            VeloResponse::RegisterAck { workspace_id, .. } => {
+                Ok(Response::new(proto::WarmWorkspaceReply { workspace_id }))
+            }
+            _ => Err(unexpected("RegisterWorkspace")),
+        }
+    }
+
+    async fn ingest(
+        &self,
+        request: Request<proto::IngestRequest>,
+    ) -> Result<Response<proto::IngestReply>, Status> {
+        let r = request.into_inner();
+        let req = VeloRequest::IngestFullScan {
+            path: r.path,
+            manifest_path: r.manifest_path,
+            threads: r.threads.map(|t| t as usize),
+            phantom: r.phantom,
+            tier1: r.tier1,
+            prefix: r.prefix,
+            cas_root: r.cas_root,
+            force_hash: r.force_hash,
+            dry_run: false,
+            selection: vrift_ipc::IngestSelection {
+                paths: r.paths,
+                include: r.include,
+                exclude: r.exclude,
+            }
+            .boxed(),
+        };
+        match self.call(req).await? {
+            VeloResponse::IngestAck {
+                files,
+                blobs,
+                new_bytes,
+                total_bytes,
+                duration_ms,
+                manifest_path,
+                tier1_files,
+                tier2_files,
+                unchanged_files,
+                deduped_files,
+                quarantined_files,
+                largest_new,
+                skipped_files,
+            } => Ok(Response::new(proto::IngestReply {
+                files,
+                blobs,
+                new_bytes,
+                total_bytes,
+                duration_ms,
+                manifest_path,
+                tier1_files,
+                tier2_files,
+                unchanged_files,
+                deduped_files,
+                quarantined_files,
+                skipped_files,
+                largest_new: largest_new
+                    .into_iter()
+                    .map(|blob| proto::IngestBlob {
+                        path: blob.path,
+                        size: blob.size,
+                    })
+                    .collect(),
+            })),
+            // Cancelled while queued
+            VeloResponse::JobAck { job } => Err(Status::cancelled(format!(
+                "Ingest job {} ended {:?}",
+                job.job_id, job.state
+            ))),
+            _ => Err(unexpected("Ingest")),
+        }
+    }
+
+    async fn manifest_get(
+        &self,
+        request: Request<proto::ManifestGetRequest>,
+    ) -> Result<Response<proto::ManifestGetReply>, Status> {
+        let r = request.into_inner();
+        let req = VeloRequest::ManifestGet { path: r.path };
+        match self.call_vdird(r.project_root, req).await? {
+            VeloResponse::ManifestAck { entry } => Ok(Response::new(proto::ManifestGetReply {
+                entry: entry.map(|e| proto::Entry {
+                    content_hash: e.content_hash.to_vec(),
+                    size: e.size,
+                    mtime: e.mtime,
+                    mode: e.mode,
+                    is_dir: e.is_dir(),
+                    is_symlink: e.is_symlink(),
+                }),
+            })),
+            _ => Err(unexpected("ManifestGet")),
+        }
+    }
+
+    async fn manifest_list_dir(
+        &self,
+        request: Request<proto::ManifestListDirRequest>,
+    ) -> Result<Response<proto::ManifestListDirReply>, Status> {
+        let r = request.into_inner();
+        let req = VeloRequest::ManifestListDir { path: r.path };
+        match self.call_vdird(r.project_root, req).await? {
+            VeloResponse::ManifestListAck { entries } => {
+                Ok(Response::new(proto::ManifestListDirReply {
+                    entries: entries
+                        .into_iter()
+                        .map(|e| proto::DirEntry {
+                            name: e.name,
+                            is_dir: e.is_dir,
+                        })
+                        .collect(),
+                }))
+            }
+            _ => Err(unexpected("ManifestListDir")),
+        }
+    }
+
+    async fn gc(&self, request: Request<proto::GcRequest>) -> Result<Response<proto::Job>, Status> {
+        let mut bloom_filter = request.into_inner().bloom_filter;
+        if bloom_filter.is_empty() {
+            bloom_filter = tokio::task::spawn_blocking(registry_bloom)
+                .await
+                .map_err(|e| Status::internal(e.to_string()))??;
+        }
+        self.job(VeloRequest::CasSweep {
+            bloom_filter,
+            dry_run: false,
+        })
+        .await
+    }
+
+    async fn list_jobs(
+        &self,
+        _request: Request<proto::ListJobsRequest>,
+    ) -> Result<Response<proto::ListJobsReply>, Status> {
+        match self.call(VeloRequest::JobList).await? {
+            VeloResponse::JobListAck { jobs } => Ok(Response::new(proto::ListJobsReply {
+                jobs: jobs.into_iter().map(to_proto_job).collect(),
+            })),
+            _ => Err(unexpected("JobList")),
+        }
+    }
+
+    async fn get_job(
+        &self,
+        request: Request<proto::JobRequest>,
+    ) -> Result<Response<proto::Job>, Status> {
+        let job_id = request.into_inner().job_id;
+        self.job(VeloRequest::JobStatus { job_id }).await
+    }
+
+    async fn cancel_job(
+        &self,
+        request: Request<proto::JobRequest>,
+    ) -> Result<Response<proto::Job>, Status> {
+        let job_id = request.into_inner().job_id;
+        self.job(VeloRequest::JobCancel { job_id }).await
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct RegistryEntry {
+    source_path: PathBuf,
+    status: String,
+}
+
+#[derive(serde::Deserialize)]
+struct Registry {
+    manifests: std::collections::HashMap<String, RegistryEntry>,
+}
+
+/// Bloom filter of every blob referenced by an active registered manifest,
+/// as `vrift gc` builds it. Any unreadable manifest aborts: sweeping with an
+/// incomplete filter would delete live blobs.
+fn registry_bloom() -> Result<Vec<u8>, Status> {
+    use vrift_ipc::{BloomFilter, BLOOM_SIZE};
+
+    let registry_path = vrift_config::config().registry_dir().join("manifests.json");
+    let file = std::fs::File::open(&registry_path).map_err(|e| {
+        Status::failed_precondition(format!(
+            "No manifest registry at {:?}: {}",
+            registry_path, e
+        ))
+    })?;
+    let registry: Registry = serde_json::from_reader(file)
+        .map_err(|e| Status::internal(format!("Corrupt manifest registry: {}", e)))?;
+
+    let mut bloom = BloomFilter::new(BLOOM_SIZE);
+    let mut active = 0;
+    for entry in registry.manifests.values() {
+        if entry.status != "active" || !entry.source_path.exists() {
+            continue;
+        }
+        let load_err =
+            |e: String| Status::internal(format!("Failed to load {:?}: {}", entry.source_path, e));
+        if entry.source_path.is_dir() {
+            let lmdb = vrift_manifest::lmdb::LmdbManifest::open(&entry.source_path)
+                .map_err(|e| load_err(e.to_string()))?;
+            for (_, m) in lmdb.iter().map_err(|e| load_err(e.to_string()))? {
+                bloom.add(&vrift_cas::CasStore::hash_to_hex(&m.vnode.content_hash));
+            }
+        } else {
+            let manifest = vrift_manifest::Manifest::load(&entry.source_path)
+                .map_err(|e| load_err(e.to_string()))?;
+            for (_, vnode) in manifest.iter() {
+                bloom.add(&vrift_cas::CasStore::hash_to_hex(&vnode.content_hash));
+            }
+        }
+        active += 1;
+    }
+    if active == 0 {
+        return Err(Status::failed_precondition(
+            "No active manifests registered; refusing to sweep the whole CAS",
+        ));
+    }
+    Ok(bloom.bits)
+}
+
+fn to_proto_job(job: vrift_ipc::JobInfo) -> proto::Job {
+    use vrift_ipc::{JobKind as K, JobState as S};
+    let kind = match job.kind {
+        K::Ingest => proto::JobKind::Ingest,
+        K::Sweep => proto::JobKind::Sweep,
+        K::Scrub => proto::JobKind::Scrub,
+        K::Repack => proto::JobKind::Repack,
+        K::Prefetch => proto::JobKind::Prefetch,
+        K::Retier => proto::JobKind::Retier,
+    };
+    let state = match job.state {
+        S::Queued => proto::JobState::Queued,
+        S::Running => proto::JobState::Running,
+        S::Completed => proto::JobState::Completed,
+        S::Cancelled => proto::JobState::Cancelled,
+        S::Failed => proto::JobState::Failed,
+    };
+    proto::Job {
+        job_id: job.job_id,
+        kind: kind as i32,
+        state: state as i32,
+        description: job.description,
+        processed: job.processed,
+        total_estimate: job.total_estimate,
+        affected: job.affected,
+        bytes: job.bytes,
+        created_at: job.created_at,
+        elapsed_ms: job.elapsed_ms,
+        retry_of: job.retry_of,
+        error: job.error,
+    }
+}
+
+fn to_status(e: VeloError) -> Status {
+    let message = match e.path {
+        Some(ref path) => format!("{} ({})", e.message, path),
+        None => e.message.clone(),
+    };
+    match e.kind {
+        VeloErrorKind::NotFound => Status::not_found(message),
+        VeloErrorKind::PermissionDenied => Status::permission_denied(message),
+        VeloErrorKind::InvalidPath => Status::invalid_argument(message),
+        VeloErrorKind::WorkspaceNotRegistered => Status::failed_precondition(message),
+        VeloErrorKind::LockFailed | VeloErrorKind::Conflict => Status::aborted(message),
+        VeloErrorKind::IngestFailed | VeloErrorKind::IoError | VeloErrorKind::Internal => {
+            Status::internal(message)
+        }
+    }
+}
+
+fn unexpected(request: &str) -> Status {
+    Status::internal(format!("Unexpected daemon response to {}", request))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use prost::Message;
+
+    use super::*;
+
+    /// The contract the hand-written `proto` structs must match
+    const PROTO: &str = include_str!("../proto/vrift/v1/daemon.proto");
+
+    /// `[optional|repeated] type name = number;` of a message
+    struct Field {
+        label: String,
+        ty: String,
+        name: String,
+        number: u32,
+    }
+
+    #[derive(Default)]
+    struct Schema {
+        messages: HashMap<String, Vec<Field>>,
+        /// (name, number) of each value
+        enums: HashMap<String, Vec<(String, i32)>>,
+    }
+
+    /// The messages and enums of `daemon.proto`; enough of the proto3
+    /// grammar for this file, no more
+    fn schema() -> Schema {
+        let mut schema = Schema::default();
+        let mut block: Option<(String, String)> = None;
+        for line in PROTO.lines() {
+            let line = line.split("//").next().unwrap().trim();
+            let words: Vec<&str> = line
+                .split(|c: char| c.is_whitespace() || c == ';' || c == '=')
+                .filter(|w| !w.is_empty())
+                .collect();
+            match words.as_slice() {
+                [kind, name, "{}"] => {
+                    assert_eq!(*kind, "message", "{}", line);
+                    schema.messages.insert(name.to_string(), Vec::new());
+                }
+                [kind, name, "{"] => {
+                    if *kind == "message" {
+                        schema.messages.insert(name.to_string(), Vec::new());
+                    } else if *kind == "enum" {
+                        schema.enums.insert(name.to_string(), Vec::new());
+                    }
+                    block = Some((kind.to_string(), name.to_string()));
+                }
+                ["}"] => block = None,
+                _ => match &block {
+                    Some((kind, name)) if kind == "message" && !words.is_empty() => {
+                        let (label, rest) = match words[0] {
+                            "optional" | "repeated" => (words[0], &words[1..]),
+                            _ => ("", &words[..]),
+                        };
+                        let [ty, field, number] = rest else {
+                            panic!("Unparsed field: {}", line);
+                        };
+                        schema.messages.get_mut(name).unwrap().push(Field {
+                            label: label.to_string(),
+                            ty: ty.to_string(),
+                            name: field.to_string(),
+                            number: number.parse().unwrap(),
+                        });
+                    }
+                    Some((kind, name))
+                        if kind == "enum" && !words.is_empty() && words[0] != "reserved" =>
+                    {
+                        let [value, number] = words[..] else {
+                            panic!("Unparsed enum value: {}", line);
+                        };
+                        schema
+                            .enums
+                            .get_mut(name)
+                            .unwrap()
+                            .push((value.to_string(), number.parse().unwrap()));
+                    }
+                    _ => {}
+                },
+            }
+        }
+        schema
+    }
+
+    fn varint(out: &mut Vec<u8>, mut value: u64) {
+        while value >= 0x80 {
+            out.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        out.push(value as u8);
+    }
+
+    fn key(out: &mut Vec<u8>, number: u32, wire_type: u64) {
+        varint(out, (number as u64) << 3 | wire_type);
+    }
+
+    fn delimited(out: &mut Vec<u8>, number: u32, bytes: &[u8]) {
+        key(out, number, 2);
+        varint(out, bytes.len() as u64);
+        out.extend_from_slice(bytes);
+    }
+
+    /// `message` encoded from the schema alone, every field set: optional
+    /// scalars to their zero value (only a field with presence keeps it),
+    /// repeated fields twice, the rest to a value distinct from any default
+    fn encode(schema: &Schema, message: &str) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut fields: Vec<&Field> = schema.messages[message].iter().collect();
+        fields.sort_by_key(|f| f.number);
+        for field in fields {
+            let optional = field.label == "optional";
+            let times = if field.label == "repeated" { 2 } else { 1 };
+            for _ in 0..times {
+                match field.ty.as_str() {
+                    "string" if optional => delimited(&mut out, field.number, b""),
+                    "string" => delimited(&mut out, field.number, field.name.as_bytes()),
+                    "bytes" => delimited(&mut out, field.number, &[field.number as u8; 3]),
+                    "bool" => {
+                        key(&mut out, field.number, 0);
+                        varint(&mut out, 1);
+                    }
+                    "uint32" | "uint64" | "int64" => {
+                        key(&mut out, field.number, 0);
+                        varint(
+                            &mut out,
+                            if optional {
+                                0
+                            } else {
+                                100 + field.number as u64
+                            },
+                        );
+                    }
+                    ty if schema.enums.contains_key(ty) => {
+                        let last = schema.enums[ty].iter().map(|v| v.1).max().unwrap();
+                        key(&mut out, field.number, 0);
+                        varint(&mut out, last as u64);
+                    }
+                    ty if schema.messages.contains_key(ty) => {
+                        delimited(&mut out, field.number, &encode(schema, ty))
+                    }
+                    ty => panic!("{}.{}: unsupported type {}", message, field.name, ty),
+                }
+            }
+        }
+        out
+    }
+
+    /// `bytes` decoded as the struct for `message` and encoded again, and
+    /// the struct's `Debug` form
+    fn round_trip(message: &str, bytes: &[u8]) -> (Vec<u8>, String) {
+        macro_rules! messages {
+            ($($name:ident),* $(,)?) => {
+                match message {
+                    $(stringify!($name) => {
+                        let decoded = proto::$name::decode(bytes)
+                            .unwrap_or_else(|e| panic!("{}: {}", message, e));
+                        (decoded.encode_to_vec(), format!("{:?}", decoded))
+                    })*
+                    _ => panic!("No struct in grpc::proto for message {}", message),
+                }
+            };
+        }
+        messages!(
+            StatusRequest,
+            StatusReply,
+            WarmWorkspaceRequest,
+            WarmWorkspaceReply,
+            IngestRequest,
+            IngestReply,
+            IngestBlob,
+            ManifestGetRequest,
+            Entry,
+            ManifestGetReply,
+            ManifestListDirRequest,
+            DirEntry,
+            ManifestListDirReply,
+            GcRequest,
+            ListJobsRequest,
+            JobRequest,
+            Job,
+            ListJobsReply,
+        )
+    }
+
+    /// The variant name of value `number` of enum `name`, if it has one
+    fn variant(name: &str, number: i32) -> Option<String> {
+        match name {
+            "JobKind" => proto::JobKind::try_from(number)
+                .ok()
+                .map(|v| format!("{:?}", v)),
+            "JobState" => proto::JobState::try_from(number)
+                .ok()
+                .map(|v| format!("{:?}", v)),
+            _ => panic!("No enum in grpc::proto for {}", name),
+        }
+    }
+
+    /// `JOB_STATE_QUEUED` of enum `JobState` -> `Queued`
+    fn variant_name(enum_name: &str, value: &str) -> String {
+        let prefix: String = enum_name
+            .chars()
+            .flat_map(|c| {
+                let sep = c.is_uppercase().then_some('_');
+                sep.into_iter().chain(c.to_uppercase())
+            })
+            .collect();
+        let prefix = format!("{}_", prefix.trim_start_matches('_'));
+        let rest = value.strip_prefix(&prefix).unwrap();
+        rest.split('_')
+            .map(|word| word[..1].to_string() + &word[1..].to_lowercase())
+            .collect()
+    }
+
+    #[test]
+    fn test_proto_messages_match_daemon_proto() {
+        let schema = schema();
+        assert!(schema.messages.len() > 10, "daemon.proto not parsed");
+        for (message, fields) in &schema.messages {
+            let bytes = encode(&schema, message);
+            let (again, debug) = round_trip(message, &bytes);
+            assert_eq!(again, bytes, "{} does not match daemon.proto", message);
+            for field in fields {
+                assert!(
+                    debug.contains(&format!("{}: ", field.name)),
+                    "{} has no field {}: {}",
+                    message,
+                    field.name,
+                    debug
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_proto_enums_match_daemon_proto() {
+        let schema = schema();
+        assert_eq!(schema.enums.len(), 2);
+        for (name, values) in &schema.enums {
+            let expected: HashMap<i32, String> = values
+                .iter()
+                .map(|(value, number)| (*number, variant_name(name, value)))
+                .collect();
+            for number in -1..16 {
+                assert_eq!(
+                    variant(name, number),
+                    expected.get(&number).cloned(),
+                    "{} = {}",
+                    name,
+                    number
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_authorize_requires_bearer_token() {
+        let request = |header: Option<&str>| {
+            let mut req = Request::new(());
+            if let Some(header) = header {
+                req.metadata_mut()
+                    .insert("authorization", header.parse().unwrap());
+            }
+            req
+        };
+        assert!(authorize(request(Some("Bearer s3cret")), "s3cret").is_ok());
+        for header in [None, Some("Bearer guess"), Some("s3cret")] {
+            let status = authorize(request(header), "s3cret").unwrap_err();
+            assert_eq!(status.code(), tonic::Code::Unauthenticated, "{:?}", header);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_serve_requires_token_file() {
+        let temp = tempfile::tempdir().unwrap();
+        let cfg = vrift_config::GrpcConfig {
+            listen: Some("127.0.0.1:0".to_string()),
+            ..Default::default()
+        };
+        let err = serve(DaemonState::for_test(temp.path()), &cfg)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("without token_file"), "{:#}", err);
+    }
+}
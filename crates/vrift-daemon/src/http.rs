@@ -0,0 +1,700 @@
+//! # HTTP export (`--features http`)
+//!
+//! Read-only access to TheSource for remote runners, which can then fetch a
+//! blob when they miss it instead of syncing the whole store up front:
+//!
+//! - `GET /blobs/<hash>`: a blob by its BLAKE3 hash (hex)
+//! - `GET /packs/<project_id>`: a workspace's hot packfile (the id, or its
+//!   first 16 characters, as in `~/.vrift/packs/`)
+//! - `GET /bloom`: a bloom filter of the blobs in the CAS, for LAN peers
+//!   (`--features peers`)
+//!
+//! `HEAD` is answered as well. The `ETag` is the content's BLAKE3 hash, so a
+//! runner can verify what it got, and `If-None-Match` / `If-Range` work as
+//! usual. A single byte range (`Range: bytes=a-b`, `a-` or `-n`) is answered
+//! with `206 Partial Content`; a request for several ranges gets the whole
+//! body. Blobs never change, so they are cacheable forever; packfiles are
+//! rebuilt in place and must be revalidated.
+//!
+//! Clients authenticate as for the gRPC facade: `authorization: Bearer
+//! <token>` with the token from `[http] token_file`, which is required on any
+//! address, loopback included. There is no TLS; put a TLS proxy in front to serve
+//! beyond a trusted network.
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::io::{Read, Seek, SeekFrom};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context as TaskContext, Poll};
+use std::time::SystemTime;
+
+use anyhow::{Context, Result};
+use hyper::body::{Body, Bytes, Frame, Incoming};
+use hyper::header::{self, HeaderMap, HeaderValue};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use tokio::io::{AsyncRead, ReadBuf};
+use tokio::net::TcpListener;
+use vrift_cas::CasStore;
+
+use crate::DaemonState;
+
+/// Size of the chunks a file body is sent in
+const CHUNK_SIZE: usize = 256 * 1024;
+
+const BLOB_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+const PACK_CACHE_CONTROL: &str = "no-cache";
+
+/// A filter younger than this is served even if blobs came and went since
+#[cfg(feature = "peers")]
+const BLOOM_MIN_AGE: std::time::Duration = std::time::Duration::from_secs(5);
+/// Rebuilt after this long regardless, in case as many went as came
+#[cfg(feature = "peers")]
+const BLOOM_MAX_AGE: std::time::Duration = std::time::Duration::from_secs(300);
+
+/// Start serving `[http] listen`; returns when the listener fails
+pub async fn serve(state: Arc<DaemonState>, cfg: &vrift_config::HttpConfig) -> Result<()> {
+    let Some(ref listen) = cfg.listen else {
+        return Ok(());
+    };
+    let addr: SocketAddr = listen
+        .parse()
+        .with_context(|| format!("Invalid [http] listen address '{}'", listen))?;
+    let token = crate::auth::require_token("http", cfg.token_file.as_deref())?;
+    if !addr.ip().is_loopback() {
+        tracing::warn!(
+            "vriftd: HTTP export on {} without TLS; bearer tokens travel in clear text",
+            addr
+        );
+    }
+
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("Failed to bind {}", addr))?;
+    tracing::info!("vriftd: HTTP export listening on {}", addr);
+
+    let export = Arc::new(Export {
+        state,
+        token,
+        pack_etags: Mutex::new(HashMap::new()),
+        #[cfg(feature = "peers")]
+        bloom: Mutex::new(None),
+    });
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                tracing::warn!("vriftd: HTTP accept failed: {}", e);
+                continue;
+            }
+        };
+        let export = export.clone();
+        tokio::spawn(async move {
+            let service = service_fn(move |req| {
+                let export = export.clone();
+                async move { Ok::<_, Infallible>(export.handle(req).await) }
+            });
+            if let Err(e) = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                tracing::debug!("vriftd: HTTP connection from {} ended: {}", peer, e);
+            }
+        });
+    }
+}
+
+struct Export {
+    state: Arc<DaemonState>,
+    token: String,
+    /// Packfile -> (length, mtime, content hash) it was last hashed at
+    pack_etags: Mutex<HashMap<PathBuf, (u64, SystemTime, String)>>,
+    /// The filter last served at `/bloom`
+    #[cfg(feature = "peers")]
+    bloom: Mutex<Option<Arc<Bloom>>>,
+}
+
+/// A bloom filter of the CAS as of `built`
+#[cfg(feature = "peers")]
+struct Bloom {
+    blobs: usize,
+    built: std::time::Instant,
+    bits: Bytes,
+    etag: String,
+}
+
+impl Export {
+    async fn handle(&self, req: Request<Incoming>) -> Response<Payload> {
+        let header = req
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok());
+        if !crate::auth::bearer_matches(header, &self.token) {
+            let mut resp = status(StatusCode::UNAUTHORIZED);
+            resp.headers_mut()
+                .insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+            return resp;
+        }
+        let head = match *req.method() {
+            Method::GET => false,
+            Method::HEAD => true,
+            _ => {
+                let mut resp = status(StatusCode::METHOD_NOT_ALLOWED);
+                resp.headers_mut()
+                    .insert(header::ALLOW, HeaderValue::from_static("GET, HEAD"));
+                return resp;
+            }
+        };
+
+        let path = req.uri().path();
+        #[cfg(feature = "peers")]
+        if path == "/bloom" {
+            return match self.bloom().await {
+                Ok(bloom) => respond_bloom(&bloom, req.headers(), head),
+                Err(e) => {
+                    tracing::warn!("vriftd: HTTP {} failed: {:#}", path, e);
+                    status(StatusCode::INTERNAL_SERVER_ERROR)
+                }
+            };
+        }
+        let opened = if let Some(hex) = path.strip_prefix("/blobs/") {
+            match CasStore::hex_to_hash(hex) {
+                Some(hash) => self.open_blob(&hash).await,
+                None => Ok(None),
+            }
+        } else if let Some(id) = path.strip_prefix("/packs/") {
+            self.open_pack(id).await
+        } else {
+            Ok(None)
+        };
+        match opened {
+            Ok(Some(file)) => respond(file, req.headers(), head),
+            Ok(None) => status(StatusCode::NOT_FOUND),
+            Err(e) => {
+                tracing::warn!("vriftd: HTTP {} failed: {:#}", path, e);
+                status(StatusCode::INTERNAL_SERVER_ERROR)
+            }
+        }
+    }
+
+    async fn open_blob(&self, hash: &vrift_cas::Blake3Hash) -> Result<Option<Served>> {
+        let Some(path) = self.state.cas.blob_path_for_hash(hash) else {
+            return Ok(None);
+        };
+        let etag = CasStore::hash_to_hex(hash);
+        let opened = tokio::task::spawn_blocking(move || open_file(&path)).await??;
+        Ok(opened.map(|(file, len)| Served {
+            file,
+            len,
+            etag,
+            cache_control: BLOB_CACHE_CONTROL,
+        }))
+    }
+
+    async fn open_pack(&self, id: &str) -> Result<Option<Served>> {
+        if id.len() < 16 || !id.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Ok(None);
+        }
+        let Some(path) = vrift_config::path::get_pack_path(id) else {
+            return Ok(None);
+        };
+        let cached = self.pack_etags.lock().unwrap().get(&path).cloned();
+        let hashed = tokio::task::spawn_blocking(move || -> Result<_> {
+            let Some((file, len)) = open_file(&path)? else {
+                return Ok(None);
+            };
+            // Hash the open file, not the path: a rebuild renames a new pack
+            // into place and the tag has to match the bytes sent
+            let mtime = file.metadata()?.modified()?;
+            let etag = match cached {
+                Some((l, m, etag)) if l == len && m == mtime => etag,
+                _ => {
+                    let mut hasher = blake3::Hasher::new();
+                    std::io::copy(&mut (&file).take(len), &mut hasher)?;
+                    hasher.finalize().to_hex().to_string()
+                }
+            };
+            Ok(Some((path, file, len, mtime, etag)))
+        })
+        .await??;
+
+        let Some((path, file, len, mtime, etag)) = hashed else {
+            return Ok(None);
+        };
+        self.pack_etags
+            .lock()
+            .unwrap()
+            .insert(path, (len, mtime, etag.clone()));
+        Ok(Some(Served {
+            file,
+            len,
+            etag,
+            cache_control: PACK_CACHE_CONTROL,
+        }))
+    }
+}
+
+#[cfg(feature = "peers")]
+impl Export {
+    /// The filter of the CAS, rebuilt when blobs were added or removed
+    async fn bloom(&self) -> Result<Arc<Bloom>> {
+        let blobs = self.state.cas_index.lock().unwrap().len();
+        if let Some(ref bloom) = *self.bloom.lock().unwrap() {
+            let age = bloom.built.elapsed();
+            if age < BLOOM_MIN_AGE || (bloom.blobs == blobs && age < BLOOM_MAX_AGE) {
+                return Ok(bloom.clone());
+            }
+        }
+        let index = self.state.cas_index.clone();
+        let fpr = vrift_config::config().peers.bloom_fpr;
+        let bloom = tokio::task::spawn_blocking(move || {
+            let index = index.lock().unwrap();
+            let bits = crate::peers::build_bloom(index.keys(), fpr);
+            Bloom {
+                blobs: index.len(),
+                built: std::time::Instant::now(),
+                etag: blake3::hash(&bits).to_hex().to_string(),
+                bits: Bytes::from(bits),
+            }
+        })
+        .await?;
+        let bloom = Arc::new(bloom);
+        *self.bloom.lock().unwrap() = Some(bloom.clone());
+        Ok(bloom)
+    }
+}
+
+#[cfg(feature = "peers")]
+fn respond_bloom(bloom: &Bloom, headers: &HeaderMap, head: bool) -> Response<Payload> {
+    let etag = format!("\"{}\"", bloom.etag);
+    let unchanged = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| etag_matches(v, &etag));
+    let mut resp = Response::new(Payload::Empty);
+    let out = resp.headers_mut();
+    out.insert(header::ETAG, header_value(&etag));
+    out.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static(PACK_CACHE_CONTROL),
+    );
+    if unchanged {
+        *resp.status_mut() = StatusCode::NOT_MODIFIED;
+        return resp;
+    }
+    out.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/octet-stream"),
+    );
+    out.insert(
+        header::CONTENT_LENGTH,
+        header_value(&bloom.bits.len().to_string()),
+    );
+    if !head {
+        *resp.body_mut() = Payload::Buffer(Some(bloom.bits.clone()));
+    }
+    resp
+}
+
+/// Open `path` for reading; `None` if it does not exist
+fn open_file(path: &Path) -> std::io::Result<Option<(std::fs::File, u64)>> {
+    match std::fs::File::open(path) {
+        Ok(file) => {
+            let len = file.metadata()?.len();
+            Ok(Some((file, len)))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// A file about to be sent
+struct Served {
+    file: std::fs::File,
+    len: u64,
+    /// Content hash (hex), sent quoted as the strong `ETag`
+    etag: String,
+    cache_control: &'static str,
+}
+
+fn respond(mut served: Served, headers: &HeaderMap, head: bool) -> Response<Payload> {
+    let etag = format!("\"{}\"", served.etag);
+    let header_str = |name| {
+        headers
+            .get(name)
+            .and_then(|v: &HeaderValue| v.to_str().ok())
+    };
+
+    let (code, range) = if header_str(header::IF_NONE_MATCH).is_some_and(|v| etag_matches(v, &etag))
+    {
+        (StatusCode::NOT_MODIFIED, None)
+    } else {
+        // A stale If-Range means the client's partial copy is of other
+        // content: send everything
+        let range = header_str(header::RANGE)
+            .filter(|_| header_str(header::IF_RANGE).is_none_or(|v| v == etag));
+        match range.map(|r| parse_range(r, served.len)) {
+            Some(Ok(Some(range))) => (StatusCode::PARTIAL_CONTENT, Some(range)),
+            Some(Err(())) => {
+                let mut resp = status(StatusCode::RANGE_NOT_SATISFIABLE);
+                resp.headers_mut().insert(
+                    header::CONTENT_RANGE,
+                    header_value(&format!("bytes */{}", served.len)),
+                );
+                return resp;
+            }
+            Some(Ok(None)) | None => (StatusCode::OK, None),
+        }
+    };
+
+    let mut resp = Response::new(Payload::Empty);
+    *resp.status_mut() = code;
+    let out = resp.headers_mut();
+    out.insert(header::ETAG, header_value(&etag));
+    out.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static(served.cache_control),
+    );
+    if code == StatusCode::NOT_MODIFIED {
+        return resp;
+    }
+    out.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    out.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/octet-stream"),
+    );
+
+    let (start, len) = match range {
+        Some((start, end)) => {
+            out.insert(
+                header::CONTENT_RANGE,
+                header_value(&format!("bytes {}-{}/{}", start, end, served.len)),
+            );
+            (start, end - start + 1)
+        }
+        None => (0, served.len),
+    };
+    out.insert(header::CONTENT_LENGTH, header_value(&len.to_string()));
+    if head {
+        return resp;
+    }
+    // lseek does not block; only the reads go through tokio's pool
+    if let Err(e) = served.file.seek(SeekFrom::Start(start)) {
+        tracing::warn!("vriftd: HTTP seek failed: {}", e);
+        return status(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+    *resp.body_mut() = Payload::File {
+        file: tokio::fs::File::from_std(served.file),
+        remaining: len,
+        buf: vec![0; CHUNK_SIZE.min(len as usize)].into_boxed_slice(),
+    };
+    resp
+}
+
+/// Whether an `If-None-Match` value names `etag` (weak comparison)
+fn etag_matches(value: &str, etag: &str) -> bool {
+    value
+        .split(',')
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+/// The inclusive byte range a `Range` header asks of a `len`-byte body.
+/// `Ok(None)` means serving the whole body (not a single byte range),
+/// `Err` that no byte of the range exists.
+fn parse_range(value: &str, len: u64) -> Result<Option<(u64, u64)>, ()> {
+    let Some(spec) = value.trim().strip_prefix("bytes=") else {
+        return Ok(None);
+    };
+    if spec.contains(',') {
+        return Ok(None);
+    }
+    let Some((first, last)) = spec.trim().split_once('-') else {
+        return Ok(None);
+    };
+    let (first, last) = (first.trim(), last.trim());
+    let range = if first.is_empty() {
+        // Suffix: the last `n` bytes
+        let Ok(n) = last.parse::<u64>() else {
+            return Ok(None);
+        };
+        if n == 0 || len == 0 {
+            return Err(());
+        }
+        (len.saturating_sub(n), len - 1)
+    } else {
+        let Ok(start) = first.parse::<u64>() else {
+            return Ok(None);
+        };
+        let end = match last {
+            "" => u64::MAX,
+            last => match last.parse::<u64>() {
+                Ok(end) if end >= start => end,
+                _ => return Ok(None),
+            },
+        };
+        if start >= len {
+            return Err(());
+        }
+        (start, end.min(len - 1))
+    };
+    Ok(Some(range))
+}
+
+fn header_value(value: &str) -> HeaderValue {
+    HeaderValue::from_str(value).unwrap_or_else(|_| HeaderValue::from_static(""))
+}
+
+fn status(code: StatusCode) -> Response<Payload> {
+    let reason = code.canonical_reason().unwrap_or("");
+    let mut resp = Response::new(Payload::Buffer(Some(Bytes::from(format!("{}\n", reason)))));
+    *resp.status_mut() = code;
+    resp.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("text/plain; charset=utf-8"),
+    );
+    resp
+}
+
+/// Response body: nothing, bytes in memory, or the rest of a file read in
+/// chunks
+enum Payload {
+    Empty,
+    Buffer(Option<Bytes>),
+    File {
+        file: tokio::fs::File,
+        remaining: u64,
+        buf: Box<[u8]>,
+    },
+}
+
+impl Body for Payload {
+    type Data = Bytes;
+    type Error = std::io::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+    ) -> Poll<Option<std::io::Result<Frame<Bytes>>>> {
+        match self.get_mut() {
+            Payload::Empty => Poll::Ready(None),
+            Payload::Buffer(text) => Poll::Ready(text.take().map(|t| Ok(Frame::data(t)))),
+            Payload::File {
+                file,
+                remaining,
+                buf,
+            } => {
+                if *remaining == 0 {
+                    return Poll::Ready(None);
+                }
+                let want = (*remaining).min(buf.len() as u64) as usize;
+                let mut read_buf = ReadBuf::new(&mut buf[..want]);
+                match Pin::new(&mut *file).poll_read(cx, &mut read_buf) {
+                    Poll::Pending => Poll::Pending,
+                    Poll::Ready(Err(e)) => Poll::Ready(Some(Err(e))),
+                    Poll::Ready(Ok(())) if read_buf.filled().is_empty() => {
+                        // Truncated underneath us; the length is already sent
+                        Poll::Ready(Some(Err(std::io::ErrorKind::UnexpectedEof.into())))
+                    }
+                    Poll::Ready(Ok(())) => {
+                        let chunk = Bytes::copy_from_slice(read_buf.filled());
+                        *remaining -= chunk.len() as u64;
+                        Poll::Ready(Some(Ok(Frame::data(chunk))))
+                    }
+                }
+            }
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        match self {
+            Payload::Empty => true,
+            Payload::Buffer(text) => text.is_none(),
+            Payload::File { remaining, .. } => *remaining == 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_parse_range() {
+        // Closed, open-ended and suffix ranges
+        assert_eq!(parse_range("bytes=0-3", 10), Ok(Some((0, 3))));
+        assert_eq!(parse_range(" bytes= 2 - 4 ", 10), Ok(Some((2, 4))));
+        assert_eq!(parse_range("bytes=7-", 10), Ok(Some((7, 9))));
+        assert_eq!(parse_range("bytes=-3", 10), Ok(Some((7, 9))));
+        // Clamped to the body
+        assert_eq!(parse_range("bytes=5-100", 10), Ok(Some((5, 9))));
+        assert_eq!(parse_range("bytes=-100", 10), Ok(Some((0, 9))));
+        assert_eq!(parse_range("bytes=9-9", 10), Ok(Some((9, 9))));
+    }
+
+    #[test]
+    fn test_parse_range_unsatisfiable() {
+        assert_eq!(parse_range("bytes=10-", 10), Err(()));
+        assert_eq!(parse_range("bytes=10-20", 10), Err(()));
+        assert_eq!(parse_range("bytes=-0", 10), Err(()));
+        assert_eq!(parse_range("bytes=-5", 0), Err(()));
+        assert_eq!(parse_range("bytes=0-", 0), Err(()));
+    }
+
+    #[test]
+    fn test_parse_range_ignored() {
+        // Answered with the whole body
+        for value in [
+            "bytes=0-1,4-5",
+            "bytes=-1, -2",
+            "items=0-3",
+            "bytes=5-2",
+            "bytes=a-3",
+            "bytes=3-b",
+            "bytes=-x",
+            "bytes=3",
+            "",
+        ] {
+            assert_eq!(parse_range(value, 10), Ok(None), "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn test_etag_matches() {
+        let etag = "\"abc\"";
+        assert!(etag_matches("\"abc\"", etag));
+        assert!(etag_matches("W/\"abc\"", etag));
+        assert!(etag_matches("*", etag));
+        assert!(etag_matches("\"x\", \"abc\"", etag));
+        assert!(etag_matches("\"x\",W/\"abc\" ", etag));
+        assert!(!etag_matches("\"abd\"", etag));
+        assert!(!etag_matches("abc", etag));
+        assert!(!etag_matches("\"x\", \"y\"", etag));
+        assert!(!etag_matches("", etag));
+    }
+
+    /// A 10-byte file served with ETag `"abc"`
+    fn served(dir: &Path) -> Served {
+        let path = dir.join("blob");
+        std::fs::File::create(&path)
+            .unwrap()
+            .write_all(b"0123456789")
+            .unwrap();
+        let (file, len) = open_file(&path).unwrap().unwrap();
+        Served {
+            file,
+            len,
+            etag: "abc".to_string(),
+            cache_control: BLOB_CACHE_CONTROL,
+        }
+    }
+
+    fn headers(pairs: &[(header::HeaderName, &'static str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in pairs {
+            map.insert(name.clone(), HeaderValue::from_static(value));
+        }
+        map
+    }
+
+    async fn body(resp: Response<Payload>) -> Vec<u8> {
+        let mut payload = resp.into_body();
+        let mut out = Vec::new();
+        while let Some(frame) =
+            std::future::poll_fn(|cx| Pin::new(&mut payload).poll_frame(cx)).await
+        {
+            out.extend_from_slice(&frame.unwrap().into_data().unwrap());
+        }
+        out
+    }
+
+    fn header_of(resp: &Response<Payload>, name: header::HeaderName) -> Option<&str> {
+        resp.headers().get(name).and_then(|v| v.to_str().ok())
+    }
+
+    #[tokio::test]
+    async fn test_respond_statuses() {
+        let temp = tempfile::tempdir().unwrap();
+
+        let resp = respond(served(temp.path()), &HeaderMap::new(), false);
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(header_of(&resp, header::ETAG), Some("\"abc\""));
+        assert_eq!(header_of(&resp, header::CONTENT_LENGTH), Some("10"));
+        assert_eq!(body(resp).await, b"0123456789");
+
+        let resp = respond(
+            served(temp.path()),
+            &headers(&[(header::RANGE, "bytes=-4")]),
+            false,
+        );
+        assert_eq!(resp.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(
+            header_of(&resp, header::CONTENT_RANGE),
+            Some("bytes 6-9/10")
+        );
+        assert_eq!(header_of(&resp, header::CONTENT_LENGTH), Some("4"));
+        assert_eq!(body(resp).await, b"6789");
+
+        let resp = respond(
+            served(temp.path()),
+            &headers(&[(header::RANGE, "bytes=10-")]),
+            false,
+        );
+        assert_eq!(resp.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(header_of(&resp, header::CONTENT_RANGE), Some("bytes */10"));
+
+        let resp = respond(
+            served(temp.path()),
+            &headers(&[(header::IF_NONE_MATCH, "\"old\", W/\"abc\"")]),
+            false,
+        );
+        assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(header_of(&resp, header::CONTENT_LENGTH), None);
+        assert!(body(resp).await.is_empty());
+
+        // A stale If-Range gets the whole body, a current one the range
+        let resp = respond(
+            served(temp.path()),
+            &headers(&[(header::RANGE, "bytes=2-3"), (header::IF_RANGE, "\"old\"")]),
+            false,
+        );
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(body(resp).await, b"0123456789");
+        let resp = respond(
+            served(temp.path()),
+            &headers(&[(header::RANGE, "bytes=2-3"), (header::IF_RANGE, "\"abc\"")]),
+            false,
+        );
+        assert_eq!(resp.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(body(resp).await, b"23");
+
+        // HEAD: the headers of a GET, no body
+        let resp = respond(
+            served(temp.path()),
+            &headers(&[(header::RANGE, "bytes=0-1")]),
+            true,
+        );
+        assert_eq!(resp.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(header_of(&resp, header::CONTENT_LENGTH), Some("2"));
+        assert!(body(resp).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_serve_requires_token_file() {
+        let temp = tempfile::tempdir().unwrap();
+        let cfg = vrift_config::HttpConfig {
+            listen: Some("127.0.0.1:0".to_string()),
+            token_file: None,
+        };
+        let err = serve(DaemonState::for_test(temp.path()), &cfg)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("without token_file"), "{:#}", err);
+    }
+}
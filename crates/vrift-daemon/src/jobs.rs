@@ -0,0 +1,827 @@
+//! Daemon job subsystem
+//!
+//! Long-running operations (ingest, CAS sweep, scrub, repack, prefetch and
+//! tier re-evaluation) run as jobs. A job has an id, live progress counters
+//! and a persisted record under `~/.vrift/jobs/` that holds its parameters,
+//! so `vrift jobs` can list, cancel and retry jobs, including jobs from
+//! before a daemon restart. Jobs that were still queued or running when the
+//! daemon went away are recorded as failed on the next start.
+//!
+//! Store-wide jobs (sweep, scrub, repack) walk or rewrite the whole CAS and
+//! run one at a time; further ones queue behind the running one. Ingest,
+//! tier re-evaluation and prefetch start immediately.
+//!
+//! Every finished job emits one completion event: a structured `tracing`
+//! event on the `vrift::jobs` target and a JSON line in `events.jsonl`.
+//! Connections subscribed to job events are also told when a job is
+//! submitted, starts and finishes (see [`JobManager::subscribe`]).
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, Notify, OwnedSemaphorePermit, Semaphore};
+use vrift_cas::Progress;
+use vrift_ipc::{JobInfo, JobKind, JobState, VeloError, VeloErrorKind};
+
+/// Job records kept on disk and in memory; older finished jobs are dropped
+const RETAINED_JOBS: usize = 64;
+
+/// `events.jsonl` is rotated to `events.jsonl.1` past this size
+const EVENT_LOG_MAX_BYTES: u64 = 1024 * 1024;
+
+/// State changes a subscriber may fall behind by before it is told it lagged
+const SUBSCRIBER_BACKLOG: usize = 256;
+
+/// Parameters of a full-scan ingest (see `VeloRequest::IngestFullScan`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IngestSpec {
+    pub path: String,
+    pub manifest_path: String,
+    pub threads: Option<usize>,
+    pub phantom: bool,
+    pub tier1: bool,
+    pub prefix: Option<String>,
+    pub cas_root: Option<String>,
+    pub force_hash: bool,
+    /// Part of `path` to take in; all of it when `None`
+    #[serde(default)]
+    pub selection: Option<vrift_ipc::IngestSelection>,
+}
+
+impl IngestSpec {
+    /// Files the ingest takes in
+    pub fn filter(&self) -> Result<vrift_cas::IngestFilter, VeloError> {
+        let Some(ref selection) = self.selection else {
+            return Ok(vrift_cas::IngestFilter::default());
+        };
+        vrift_cas::IngestFilter::new(
+            Path::new(&self.path),
+            &selection.paths,
+            selection.include.clone(),
+            selection.exclude.clone(),
+        )
+        .map_err(|path| {
+            VeloError::with_path(
+                VeloErrorKind::InvalidPath,
+                "Path is outside the ingested directory",
+                path,
+            )
+        })
+    }
+}
+
+/// Everything needed to run a job again
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum JobSpec {
+    Ingest(IngestSpec),
+    Sweep {
+        /// Bloom filter of live hashes, hex-encoded; unused when `refcounted`
+        bloom_hex: String,
+        /// Decide liveness from the CAS refcount database instead
+        #[serde(default)]
+        refcounted: bool,
+    },
+    Retier {
+        project_root: String,
+        /// Sessions that ended in the workspace since its last re-evaluation
+        sessions: u64,
+    },
+    /// Hot packfile build from the workspace's saved access profile
+    Pack {
+        project_root: String,
+    },
+    /// Integrity walk of the whole CAS
+    Scrub {
+        delete_corrupt: bool,
+    },
+    /// Page-cache warm-up of the blobs in the workspace's saved access profile
+    Prefetch {
+        project_root: String,
+    },
+}
+
+impl JobSpec {
+    pub fn kind(&self) -> JobKind {
+        match self {
+            JobSpec::Ingest(_) => JobKind::Ingest,
+            JobSpec::Sweep { .. } => JobKind::Sweep,
+            JobSpec::Retier { .. } => JobKind::Retier,
+            JobSpec::Pack { .. } => JobKind::Repack,
+            JobSpec::Scrub { .. } => JobKind::Scrub,
+            JobSpec::Prefetch { .. } => JobKind::Prefetch,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            JobSpec::Ingest(spec) => format!("{} -> {}", spec.path, spec.manifest_path),
+            JobSpec::Sweep { .. } => "CAS garbage collection".to_string(),
+            JobSpec::Retier { project_root, .. } => {
+                format!("Tier re-evaluation of {}", project_root)
+            }
+            JobSpec::Pack { project_root } => format!("Packfile build for {}", project_root),
+            JobSpec::Scrub { .. } => "CAS integrity check".to_string(),
+            JobSpec::Prefetch { project_root } => format!("Prefetch for {}", project_root),
+        }
+    }
+
+    /// Whether the job checks `Progress::is_cancelled` while running. Any job
+    /// can be cancelled while it is still queued.
+    fn cancellable_while_running(&self) -> bool {
+        matches!(
+            self,
+            JobSpec::Sweep { .. }
+                | JobSpec::Pack { .. }
+                | JobSpec::Scrub { .. }
+                | JobSpec::Prefetch { .. }
+        )
+    }
+}
+
+fn is_store_wide(kind: JobKind) -> bool {
+    matches!(kind, JobKind::Sweep | JobKind::Scrub | JobKind::Repack)
+}
+
+/// On-disk form of a job
+#[derive(Debug, Serialize, Deserialize)]
+struct JobRecord {
+    id: u64,
+    spec: JobSpec,
+    state: JobState,
+    created_at: u64,
+    elapsed_ms: u64,
+    processed: u64,
+    total_estimate: u64,
+    affected: u64,
+    bytes: u64,
+    retry_of: Option<u64>,
+    error: Option<String>,
+}
+
+/// Line written to `events.jsonl` when a job finishes
+#[derive(Serialize)]
+struct CompletionEvent<'a> {
+    finished_at: u64,
+    #[serde(flatten)]
+    job: &'a JobInfo,
+}
+
+struct Status {
+    state: JobState,
+    started: Option<Instant>,
+    /// Total run time, set once the job has finished
+    elapsed: Option<Duration>,
+    error: Option<String>,
+}
+
+pub struct Job {
+    pub id: u64,
+    pub spec: JobSpec,
+    /// Counters published by the running operation
+    pub progress: Progress,
+    pub total_estimate: AtomicU64,
+    retry_of: Option<u64>,
+    created_at: u64,
+    status: Mutex<Status>,
+    /// Wakes a queued job so it can observe cancellation
+    cancelled: Notify,
+}
+
+impl Job {
+    pub fn state(&self) -> JobState {
+        self.status.lock().unwrap().state
+    }
+
+    pub fn info(&self) -> JobInfo {
+        let status = self.status.lock().unwrap();
+        let elapsed = status
+            .elapsed
+            .or_else(|| status.started.map(|s| s.elapsed()))
+            .unwrap_or_default();
+        JobInfo {
+            job_id: self.id,
+            kind: self.spec.kind(),
+            state: status.state,
+            description: self.spec.describe(),
+            processed: self.progress.processed.load(Ordering::Relaxed),
+            total_estimate: self.total_estimate.load(Ordering::Relaxed),
+            affected: self.progress.affected.load(Ordering::Relaxed),
+            bytes: self.progress.bytes.load(Ordering::Relaxed),
+            created_at: self.created_at,
+            elapsed_ms: elapsed.as_millis() as u64,
+            retry_of: self.retry_of,
+            error: status.error.clone(),
+        }
+    }
+
+    fn record(&self) -> JobRecord {
+        let info = self.info();
+        JobRecord {
+            id: self.id,
+            spec: self.spec.clone(),
+            state: info.state,
+            created_at: info.created_at,
+            elapsed_ms: info.elapsed_ms,
+            processed: info.processed,
+            total_estimate: info.total_estimate,
+            affected: info.affected,
+            bytes: info.bytes,
+            retry_of: info.retry_of,
+            error: info.error,
+        }
+    }
+
+    fn from_record(record: JobRecord) -> Self {
+        let progress = Progress::default();
+        progress
+            .processed
+            .store(record.processed, Ordering::Relaxed);
+        progress.affected.store(record.affected, Ordering::Relaxed);
+        progress.bytes.store(record.bytes, Ordering::Relaxed);
+
+        // A record that never reached a final state belongs to a previous
+        // daemon that exited mid-job
+        let (state, error) = if record.state.is_finished() {
+            (record.state, record.error)
+        } else {
+            (
+                JobState::Failed,
+                Some("Interrupted by daemon restart".to_string()),
+            )
+        };
+        Self {
+            id: record.id,
+            spec: record.spec,
+            progress,
+            total_estimate: AtomicU64::new(record.total_estimate),
+            retry_of: record.retry_of,
+            created_at: record.created_at,
+            status: Mutex::new(Status {
+                state,
+                started: None,
+                elapsed: Some(Duration::from_millis(record.elapsed_ms)),
+                error,
+            }),
+            cancelled: Notify::new(),
+        }
+    }
+}
+
+/// Held while a job runs; releases the store-wide slot on drop
+pub struct JobSlot {
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+struct Inner {
+    jobs: BTreeMap<u64, Arc<Job>>,
+    next_id: u64,
+}
+
+pub struct JobManager {
+    /// Where records and events are persisted; `None` keeps jobs in memory
+    dir: Option<PathBuf>,
+    inner: Mutex<Inner>,
+    store_wide: Arc<Semaphore>,
+    /// Jobs that were submitted, started or finished, for subscribers
+    transitions: broadcast::Sender<JobInfo>,
+}
+
+impl JobManager {
+    /// Load job history from `dir`, creating it if needed
+    pub fn open(dir: Option<PathBuf>) -> Self {
+        let mut jobs = BTreeMap::new();
+        if let Some(ref dir) = dir {
+            if let Err(e) = std::fs::create_dir_all(dir) {
+                tracing::warn!("vriftd: Cannot create job directory {:?}: {}", dir, e);
+            }
+            for record in load_records(dir) {
+                let interrupted = !record.state.is_finished();
+                let job = Arc::new(Job::from_record(record));
+                if interrupted {
+                    tracing::warn!("vriftd: Job {} was interrupted by a restart", job.id);
+                    write_record(dir, &job);
+                }
+                jobs.insert(job.id, job);
+            }
+        }
+        let next_id = jobs.keys().next_back().map_or(1, |id| id + 1);
+        Self {
+            dir,
+            inner: Mutex::new(Inner { jobs, next_id }),
+            store_wide: Arc::new(Semaphore::new(1)),
+            transitions: broadcast::channel(SUBSCRIBER_BACKLOG).0,
+        }
+    }
+
+    /// Receive a snapshot of every job that is submitted, starts or finishes
+    /// from now on. Progress in between is read from [`JobManager::running`].
+    pub fn subscribe(&self) -> broadcast::Receiver<JobInfo> {
+        self.transitions.subscribe()
+    }
+
+    /// Jobs currently running
+    pub fn running(&self) -> Vec<JobInfo> {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .jobs
+            .values()
+            .filter(|job| job.state() == JobState::Running)
+            .map(|job| job.info())
+            .collect()
+    }
+
+    /// Register a new queued job
+    pub fn submit(&self, spec: JobSpec, total_estimate: u64, retry_of: Option<u64>) -> Arc<Job> {
+        let mut inner = self.inner.lock().unwrap();
+        let job = Arc::new(Job {
+            id: inner.next_id,
+            spec,
+            progress: Progress::default(),
+            total_estimate: AtomicU64::new(total_estimate),
+            retry_of,
+            created_at: now_secs(),
+            status: Mutex::new(Status {
+                state: JobState::Queued,
+                started: None,
+                elapsed: None,
+                error: None,
+            }),
+            cancelled: Notify::new(),
+        });
+        inner.next_id += 1;
+        inner.jobs.insert(job.id, job.clone());
+        self.prune(&mut inner);
+        drop(inner);
+
+        tracing::info!(
+            target: "vrift::jobs",
+            job_id = job.id,
+            kind = ?job.spec.kind(),
+            "Job submitted: {}",
+            job.spec.describe()
+        );
+        self.persist(&job);
+        self.publish(&job);
+        job
+    }
+
+    /// Wait until `job` may run and mark it running.
+    ///
+    /// Returns `None` if the job was cancelled while queued; it is then
+    /// already finished as cancelled.
+    pub async fn start(&self, job: &Job) -> Option<JobSlot> {
+        let permit = if is_store_wide(job.spec.kind()) {
+            tokio::select! {
+                permit = self.store_wide.clone().acquire_owned() => permit.ok(),
+                _ = job.cancelled.notified() => None,
+            }
+        } else {
+            None
+        };
+        if job.progress.is_cancelled() {
+            self.finish(job, Ok(()));
+            return None;
+        }
+
+        {
+            let mut status = job.status.lock().unwrap();
+            status.state = JobState::Running;
+            status.started = Some(Instant::now());
+        }
+        self.persist(job);
+        self.publish(job);
+        Some(JobSlot { _permit: permit })
+    }
+
+    /// Record the end of a job and emit its completion event. A job that
+    /// stopped because it was cancelled counts as cancelled even if it
+    /// returned `Ok`.
+    pub fn finish(&self, job: &Job, result: Result<(), String>) {
+        {
+            let mut status = job.status.lock().unwrap();
+            status.state = match (&result, job.progress.is_cancelled()) {
+                (Err(_), _) => JobState::Failed,
+                (Ok(()), true) => JobState::Cancelled,
+                (Ok(()), false) => JobState::Completed,
+            };
+            status.elapsed = Some(status.started.map(|s| s.elapsed()).unwrap_or_default());
+            status.error = result.err();
+        }
+        self.persist(job);
+
+        let info = job.info();
+        tracing::info!(
+            target: "vrift::jobs",
+            job_id = info.job_id,
+            kind = ?info.kind,
+            state = ?info.state,
+            elapsed_ms = info.elapsed_ms,
+            processed = info.processed,
+            affected = info.affected,
+            bytes = info.bytes,
+            error = info.error.as_deref().unwrap_or(""),
+            "Job finished"
+        );
+        if let Some(ref dir) = self.dir {
+            if let Err(e) = append_event(dir, &info) {
+                tracing::warn!("vriftd: Failed to write job event: {}", e);
+            }
+        }
+        if self.transitions.receiver_count() > 0 {
+            let _ = self.transitions.send(info);
+        }
+    }
+
+    pub fn get(&self, job_id: u64) -> Option<Arc<Job>> {
+        self.inner.lock().unwrap().jobs.get(&job_id).cloned()
+    }
+
+    /// All retained jobs, newest first
+    pub fn list(&self) -> Vec<JobInfo> {
+        let inner = self.inner.lock().unwrap();
+        inner.jobs.values().rev().map(|job| job.info()).collect()
+    }
+
+    /// Request cancellation; finished jobs are returned unchanged
+    pub fn cancel(&self, job_id: u64) -> Result<JobInfo, VeloError> {
+        let job = self
+            .get(job_id)
+            .ok_or_else(|| VeloError::not_found(format!("No job {}", job_id)))?;
+        match job.state() {
+            JobState::Queued => {}
+            JobState::Running if job.spec.cancellable_while_running() => {}
+            JobState::Running => {
+                return Err(VeloError::new(
+                    VeloErrorKind::LockFailed,
+                    format!(
+                        "{:?} job {} cannot be cancelled once running",
+                        job.spec.kind(),
+                        job_id
+                    ),
+                ))
+            }
+            _ => return Ok(job.info()),
+        }
+        tracing::info!(target: "vrift::jobs", job_id, "Cancelling job");
+        job.progress.cancel();
+        job.cancelled.notify_one();
+        Ok(job.info())
+    }
+
+    /// Submit a new job with the parameters of a failed or cancelled one
+    pub fn retry(&self, job_id: u64) -> Result<Arc<Job>, VeloError> {
+        let job = self
+            .get(job_id)
+            .ok_or_else(|| VeloError::not_found(format!("No job {}", job_id)))?;
+        match job.state() {
+            JobState::Failed | JobState::Cancelled => Ok(self.submit(
+                job.spec.clone(),
+                job.total_estimate.load(Ordering::Relaxed),
+                Some(job_id),
+            )),
+            state => Err(VeloError::new(
+                VeloErrorKind::Internal,
+                format!(
+                    "Job {} is {:?}; only failed or cancelled jobs can be retried",
+                    job_id, state
+                ),
+            )),
+        }
+    }
+
+    /// Drop the oldest finished jobs beyond the retention limit
+    fn prune(&self, inner: &mut Inner) {
+        let excess = inner.jobs.len().saturating_sub(RETAINED_JOBS);
+        let expired: Vec<u64> = inner
+            .jobs
+            .values()
+            .filter(|job| job.state().is_finished())
+            .take(excess)
+            .map(|job| job.id)
+            .collect();
+        for id in expired {
+            inner.jobs.remove(&id);
+            if let Some(ref dir) = self.dir {
+                let _ = std::fs::remove_file(record_path(dir, id));
+            }
+        }
+    }
+
+    fn publish(&self, job: &Job) {
+        if self.transitions.receiver_count() > 0 {
+            let _ = self.transitions.send(job.info());
+        }
+    }
+
+    fn persist(&self, job: &Job) {
+        if let Some(ref dir) = self.dir {
+            write_record(dir, job);
+        }
+    }
+}
+
+fn record_path(dir: &Path, id: u64) -> PathBuf {
+    dir.join(format!("{}.json", id))
+}
+
+fn load_records(dir: &Path) -> Vec<JobRecord> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| {
+            let data = std::fs::read(&path).ok()?;
+            match serde_json::from_slice(&data) {
+                Ok(record) => Some(record),
+                Err(e) => {
+                    tracing::warn!("vriftd: Ignoring unreadable job record {:?}: {}", path, e);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Write a job record atomically (temp file + rename)
+fn write_record(dir: &Path, job: &Job) {
+    let path = record_path(dir, job.id);
+    let tmp = path.with_extension("json.tmp");
+    let result = serde_json::to_vec_pretty(&job.record())
+        .map_err(std::io::Error::other)
+        .and_then(|data| std::fs::write(&tmp, data))
+        .and_then(|_| std::fs::rename(&tmp, &path));
+    if let Err(e) = result {
+        tracing::warn!("vriftd: Failed to persist job {}: {}", job.id, e);
+    }
+}
+
+fn append_event(dir: &Path, info: &JobInfo) -> std::io::Result<()> {
+    let path = dir.join("events.jsonl");
+    if std::fs::metadata(&path).is_ok_and(|m| m.len() > EVENT_LOG_MAX_BYTES) {
+        std::fs::rename(&path, dir.join("events.jsonl.1"))?;
+    }
+    let mut line = serde_json::to_vec(&CompletionEvent {
+        finished_at: now_secs(),
+        job: info,
+    })
+    .map_err(std::io::Error::other)?;
+    line.push(b'\n');
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)?
+        .write_all(&line)
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn retier() -> JobSpec {
+        JobSpec::Retier {
+            project_root: "/ws".to_string(),
+            sessions: 1,
+        }
+    }
+
+    fn scrub() -> JobSpec {
+        JobSpec::Scrub {
+            delete_corrupt: false,
+        }
+    }
+
+    fn read_record(dir: &Path, id: u64) -> JobRecord {
+        serde_json::from_slice(&std::fs::read(record_path(dir, id)).unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_records_survive_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let jobs = JobManager::open(Some(dir.path().to_path_buf()));
+        let job = jobs.submit(retier(), 7, None);
+        assert_eq!(read_record(dir.path(), job.id).state, JobState::Queued);
+
+        let _slot = jobs.start(&job).await.unwrap();
+        job.progress.processed.store(5, Ordering::Relaxed);
+        jobs.finish(&job, Ok(()));
+        let record = read_record(dir.path(), job.id);
+        assert_eq!(record.state, JobState::Completed);
+        assert_eq!(record.processed, 5);
+
+        let reopened = JobManager::open(Some(dir.path().to_path_buf()));
+        let info = reopened.get(job.id).unwrap().info();
+        assert_eq!(info.state, JobState::Completed);
+        assert_eq!(info.kind, JobKind::Retier);
+        assert_eq!(info.processed, 5);
+        assert_eq!(info.total_estimate, 7);
+        // Ids carry on after the loaded ones
+        assert_eq!(reopened.submit(retier(), 0, None).id, job.id + 1);
+    }
+
+    #[tokio::test]
+    async fn test_unfinished_jobs_fail_on_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let jobs = JobManager::open(Some(dir.path().to_path_buf()));
+        let queued = jobs.submit(scrub(), 0, None);
+        let running = jobs.submit(retier(), 0, None);
+        let _slot = jobs.start(&running).await.unwrap();
+        drop(jobs);
+
+        let reopened = JobManager::open(Some(dir.path().to_path_buf()));
+        for id in [queued.id, running.id] {
+            let info = reopened.get(id).unwrap().info();
+            assert_eq!(info.state, JobState::Failed);
+            assert_eq!(info.error.as_deref(), Some("Interrupted by daemon restart"));
+            assert_eq!(read_record(dir.path(), id).state, JobState::Failed);
+        }
+        assert!(reopened.running().is_empty());
+    }
+
+    #[test]
+    fn test_unreadable_records_are_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("3.json"), b"{oops").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), b"ignored").unwrap();
+        let jobs = JobManager::open(Some(dir.path().to_path_buf()));
+        assert!(jobs.list().is_empty());
+        assert_eq!(jobs.submit(retier(), 0, None).id, 1);
+    }
+
+    #[tokio::test]
+    async fn test_store_wide_jobs_run_one_at_a_time() {
+        let jobs = JobManager::open(None);
+        let first = jobs.submit(scrub(), 0, None);
+        let second = jobs.submit(scrub(), 0, None);
+        let other = jobs.submit(retier(), 0, None);
+
+        let slot = jobs.start(&first).await.unwrap();
+        let waiting = tokio::time::timeout(Duration::from_millis(50), jobs.start(&second)).await;
+        assert!(waiting.is_err());
+        assert_eq!(second.state(), JobState::Queued);
+
+        // Jobs that are not store-wide do not wait for the slot
+        let _other_slot = jobs.start(&other).await.unwrap();
+        assert_eq!(other.state(), JobState::Running);
+
+        jobs.finish(&first, Ok(()));
+        drop(slot);
+        let _slot = jobs.start(&second).await.unwrap();
+        assert_eq!(second.state(), JobState::Running);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_while_queued() {
+        let jobs = Arc::new(JobManager::open(None));
+        let first = jobs.submit(scrub(), 0, None);
+        let queued = jobs.submit(scrub(), 0, None);
+        let _slot = jobs.start(&first).await.unwrap();
+
+        let waiter = {
+            let jobs = jobs.clone();
+            let queued = queued.clone();
+            tokio::spawn(async move { jobs.start(&queued).await.is_some() })
+        };
+        tokio::task::yield_now().await;
+        jobs.cancel(queued.id).unwrap();
+        assert!(!waiter.await.unwrap());
+        assert_eq!(queued.state(), JobState::Cancelled);
+        assert_eq!(first.state(), JobState::Running);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_running_only_where_supported() {
+        let jobs = JobManager::open(None);
+        let retier = jobs.submit(retier(), 0, None);
+        let scrub = jobs.submit(scrub(), 0, None);
+        let _a = jobs.start(&retier).await.unwrap();
+        let _b = jobs.start(&scrub).await.unwrap();
+
+        assert!(jobs.cancel(retier.id).is_err());
+        assert!(!retier.progress.is_cancelled());
+        jobs.cancel(scrub.id).unwrap();
+        assert!(scrub.progress.is_cancelled());
+
+        // A job that stops because it was cancelled ends cancelled
+        jobs.finish(&scrub, Ok(()));
+        assert_eq!(scrub.state(), JobState::Cancelled);
+        // Cancelling a finished job leaves it as it is
+        assert_eq!(jobs.cancel(scrub.id).unwrap().state, JobState::Cancelled);
+        assert!(jobs.cancel(999).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_retry_failed_jobs_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let jobs = JobManager::open(Some(dir.path().to_path_buf()));
+        let failed = jobs.submit(retier(), 3, None);
+        let _slot = jobs.start(&failed).await.unwrap();
+        jobs.finish(&failed, Err("boom".to_string()));
+        assert_eq!(failed.info().error.as_deref(), Some("boom"));
+
+        let retry = jobs.retry(failed.id).unwrap();
+        let info = retry.info();
+        assert_eq!(info.retry_of, Some(failed.id));
+        assert_eq!(info.state, JobState::Queued);
+        assert_eq!(info.kind, JobKind::Retier);
+        assert_eq!(info.total_estimate, 3);
+        assert_eq!(read_record(dir.path(), retry.id).retry_of, Some(failed.id));
+
+        // Queued and completed jobs cannot be retried
+        assert!(jobs.retry(retry.id).is_err());
+        let _slot = jobs.start(&retry).await.unwrap();
+        jobs.finish(&retry, Ok(()));
+        assert!(jobs.retry(retry.id).is_err());
+        assert!(jobs.retry(999).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_finish_appends_completion_event() {
+        let dir = tempfile::tempdir().unwrap();
+        let jobs = JobManager::open(Some(dir.path().to_path_buf()));
+        for result in [Ok(()), Err("boom".to_string())] {
+            let job = jobs.submit(retier(), 0, None);
+            let _slot = jobs.start(&job).await.unwrap();
+            jobs.finish(&job, result);
+        }
+
+        let events = std::fs::read_to_string(dir.path().join("events.jsonl")).unwrap();
+        let events: Vec<serde_json::Value> = events
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0]["job_id"], 1);
+        assert_eq!(events[1]["job_id"], 2);
+        assert_eq!(events[1]["error"], "boom");
+        assert!(events[0]["finished_at"].as_u64().unwrap() > 0);
+    }
+
+    #[tokio::test]
+    async fn test_subscribers_see_transitions() {
+        let jobs = JobManager::open(None);
+        let mut rx = jobs.subscribe();
+        let job = jobs.submit(retier(), 0, None);
+        let _slot = jobs.start(&job).await.unwrap();
+        jobs.finish(&job, Ok(()));
+
+        let states: Vec<JobState> = (0..3).map(|_| rx.try_recv().unwrap().state).collect();
+        assert_eq!(
+            states,
+            vec![JobState::Queued, JobState::Running, JobState::Completed]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_prune_keeps_unfinished_jobs() {
+        let dir = tempfile::tempdir().unwrap();
+        let jobs = JobManager::open(Some(dir.path().to_path_buf()));
+        let pending = jobs.submit(retier(), 0, None);
+        for _ in 0..RETAINED_JOBS + 5 {
+            let job = jobs.submit(retier(), 0, None);
+            let _slot = jobs.start(&job).await.unwrap();
+            jobs.finish(&job, Ok(()));
+        }
+
+        let listed = jobs.list();
+        assert_eq!(listed.len(), RETAINED_JOBS);
+        assert!(jobs.get(pending.id).is_some());
+        // The oldest finished jobs went, records included
+        assert!(jobs.get(2).is_none());
+        assert!(!record_path(dir.path(), 2).exists());
+        assert!(record_path(dir.path(), pending.id).exists());
+    }
+
+    #[test]
+    fn test_spec_kinds() {
+        assert!(is_store_wide(scrub().kind()));
+        assert!(!is_store_wide(retier().kind()));
+        let prefetch = JobSpec::Prefetch {
+            project_root: "/ws".to_string(),
+        };
+        assert_eq!(prefetch.kind(), JobKind::Prefetch);
+        assert!(!is_store_wide(prefetch.kind()));
+        assert!(prefetch.cancellable_while_running());
+
+        // Specs are persisted as tagged JSON
+        let json = serde_json::to_value(scrub()).unwrap();
+        assert_eq!(json["op"], "scrub");
+        let back: JobSpec = serde_json::from_value(json).unwrap();
+        assert_eq!(back.kind(), JobKind::Scrub);
+    }
+}
@@ -3,6 +3,32 @@ use clap::{Parser, Subcommand};
 
 use tokio::signal;
 
+mod activation;
+#[cfg(any(
+    feature = "grpc",
+    feature = "http",
+    feature = "tcp",
+    feature = "upstream"
+))]
+mod auth;
+mod crash;
+#[cfg(feature = "grpc")]
+mod grpc;
+#[cfg(feature = "http")]
+mod http;
+mod jobs;
+mod pack;
+#[cfg(feature = "peers")]
+mod peers;
+mod session;
+mod snapshot;
+#[cfg(feature = "tcp")]
+mod tcp;
+mod tiering;
+#[cfg(feature = "upstream")]
+mod upstream;
+mod workspace;
+
 #[derive(Parser)]
 #[command(name = "vriftd")]
 #[command(version, about = "Velo Rift Daemon", long_about = None)]
@@ -38,28 +64,32 @@ async fn main() -> Result<()> {
 
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
 use std::sync::{Arc, Mutex};
 
 use tokio::net::{UnixListener, UnixStream};
 use vrift_config::path::is_within_directory;
-use vrift_ipc::{VeloError, VeloErrorKind, VeloRequest, VeloResponse};
+use vrift_error::Classify;
+use vrift_ipc::{EventKind, VeloError, VeloErrorKind, VeloEvent, VeloRequest, VeloResponse};
 use vrift_manifest::lmdb::{AssetTier, LmdbManifest};
 
 // RFC-0043: Minimal registry for workspace discovery
-// TEMPORARILY DISABLED: Investigating UE blocking issues
-#[allow(dead_code)]
 #[derive(serde::Deserialize)]
 struct MinimalManifestEntry {
     project_root: PathBuf,
 }
 
-#[allow(dead_code)]
 #[derive(serde::Deserialize)]
 struct MinimalRegistry {
     manifests: std::collections::HashMap<String, MinimalManifestEntry>,
 }
 
-#[allow(dead_code)]
+/// Persisted job records and completion events
+fn jobs_dir() -> Option<PathBuf> {
+    let home = std::env::var("HOME").ok()?;
+    Some(PathBuf::from(home).join(".vrift/jobs"))
+}
+
 fn load_registered_workspaces() -> Vec<PathBuf> {
     let home = std::env::var("HOME").unwrap_or_else(|_| "/root".to_string());
     let path = PathBuf::from(home).join(".vrift/registry/manifests.json");
@@ -69,11 +99,13 @@ fn load_registered_workspaces() -> Vec<PathBuf> {
 
     if let Ok(file) = std::fs::File::open(path) {
         if let Ok(registry) = serde_json::from_reader::<_, MinimalRegistry>(file) {
-            return registry
+            // Several manifests may share a project root; warm each root once
+            let roots: HashSet<PathBuf> = registry
                 .manifests
                 .values()
-                .map(|e| e.project_root.clone())
+                .filter_map(|e| e.project_root.canonicalize().ok())
                 .collect();
+            return roots.into_iter().collect();
         }
     }
     Vec::new()
@@ -249,6 +281,34 @@ impl LockManager {
         }
     }
 
+    /// Release every lock held by `pid` (owner exited without unlocking)
+    fn release_all(&self, pid: u32) -> usize {
+        let mut locks = self.locks.lock().unwrap();
+        let mut released = 0;
+        for state in locks.values_mut() {
+            let mut held = false;
+            if state.exclusive == Some(pid) {
+                state.exclusive = None;
+                held = true;
+            }
+            held |= state.shared.remove(&pid);
+            if held {
+                released += 1;
+                state.notify.notify_waiters();
+            }
+        }
+        released
+    }
+
+    /// Number of locks currently held by `pid`
+    fn held_by(&self, pid: u32) -> u32 {
+        let locks = self.locks.lock().unwrap();
+        locks
+            .values()
+            .filter(|s| s.exclusive == Some(pid) || s.shared.contains(&pid))
+            .count() as u32
+    }
+
     fn get_notify(&self, path: &str) -> Arc<tokio::sync::Notify> {
         let mut locks = self.locks.lock().unwrap();
         let state = locks.entry(path.to_string()).or_insert_with(|| LockState {
@@ -272,17 +332,67 @@ struct VDirdProcess {
 
 struct DaemonState {
     // In-memory index of CAS blobs (Hash -> Size) - Shared across all workspaces for global dedup
-    cas_index: Mutex<HashMap<[u8; 32], u64>>,
+    cas_index: Arc<Mutex<HashMap<[u8; 32], u64>>>,
     // Per-project vDird subprocess tracking
     vdird_processes: Mutex<HashMap<PathBuf, Arc<VDirdProcess>>>,
     // Content-Addressable Storage store
     cas: vrift_cas::CasStore,
     // Lock Manager for flock virtualization
     lock_manager: LockManager,
+    // Process-tree sessions reported by the inception layer
+    sessions: session::SessionTracker,
+    // Long-running operations (ingest, sweep)
+    jobs: jobs::JobManager,
+    // Per-workspace vDird readiness
+    workspaces: workspace::WorkspaceTracker,
+    // Sessions ended per project root since its last tier re-evaluation
+    ended_sessions: Mutex<HashMap<PathBuf, u64>>,
+    // LRU limits for active workspaces (daemon.max_active_workspaces / workspace_idle_secs)
+    max_active_workspaces: usize,
+    workspace_idle_timeout: Option<std::time::Duration>,
+    // How long a sweep leaves an unreferenced blob marked before deleting it (gc.grace_secs)
+    gc_grace: std::time::Duration,
+    // Access profiles being traced for hot packfiles, per workspace
+    pack_traces: pack::PackTraces,
+    // Scanner vetting ingested files and its batch size ([ingest.scanner])
+    ingest_scanner: Option<(Arc<dyn vrift_cas::ContentScanner>, usize)>,
+    // Files ingests leave out of the CAS by size or content ([ingest.skip])
+    ingest_skip: vrift_cas::SkipPolicy,
+    // Where blobs missing from the CAS are fetched from on open ([upstream], [peers])
+    #[cfg(feature = "upstream")]
+    upstream: Option<upstream::Upstream>,
     // Daemon start time (for uptime reporting)
     start_time: std::time::Instant,
 }
 
+#[cfg(all(test, any(feature = "grpc", feature = "http", feature = "tcp")))]
+impl DaemonState {
+    /// A daemon over the CAS at `cas_root` with default settings, keeping
+    /// no job history
+    fn for_test(cas_root: &Path) -> Arc<Self> {
+        let cfg = vrift_config::Config::default();
+        Arc::new(DaemonState {
+            cas_index: Arc::new(Mutex::new(HashMap::new())),
+            vdird_processes: Mutex::new(HashMap::new()),
+            cas: vrift_cas::CasStore::new(cas_root).unwrap(),
+            lock_manager: LockManager::new(),
+            sessions: session::SessionTracker::new(),
+            jobs: jobs::JobManager::open(None),
+            workspaces: workspace::WorkspaceTracker::new(),
+            ended_sessions: Mutex::new(HashMap::new()),
+            max_active_workspaces: cfg.daemon.max_active_workspaces,
+            workspace_idle_timeout: cfg.workspace_idle_timeout(),
+            gc_grace: cfg.gc_grace(),
+            pack_traces: pack::PackTraces::new(),
+            ingest_scanner: None,
+            ingest_skip: vrift_cas::SkipPolicy::default(),
+            #[cfg(feature = "upstream")]
+            upstream: None,
+            start_time: std::time::Instant::now(),
+        })
+    }
+}
+
 async fn start_daemon() -> Result<()> {
     tracing::info!("vriftd: Starting multi-tenant daemon...");
 
@@ -290,22 +400,39 @@ async fn start_daemon() -> Result<()> {
         tracing::warn!("Config load failed: {}. Using defaults.", e);
         vrift_config::Config::default()
     });
+    let log_dir = vrift_manifest::normalize_path(&cfg.log_dir().to_string_lossy());
+    crash::install_panic_hook(log_dir.clone());
+
     let socket_str = cfg.socket_path().to_string_lossy().to_string();
     let path = Path::new(&socket_str);
 
-    // Ensure socket parent directory exists
-    if let Some(parent) = path.parent() {
-        if !parent.exists() {
-            std::fs::create_dir_all(parent)?;
+    // Under systemd/launchd socket activation the service manager owns the
+    // socket file; we only accept on the descriptor it hands us.
+    let activated = activation::inherited_listener()?;
+    let owns_socket = activated.is_none();
+    let listener = match activated {
+        Some(std_listener) => {
+            std_listener.set_nonblocking(true)?;
+            tracing::info!("vriftd: Using socket-activated listener");
+            UnixListener::from_std(std_listener)?
         }
-    }
+        None => {
+            // Ensure socket parent directory exists
+            if let Some(parent) = path.parent() {
+                if !parent.exists() {
+                    std::fs::create_dir_all(parent)?;
+                }
+            }
 
-    if path.exists() {
-        tokio::fs::remove_file(path).await?;
-    }
+            if path.exists() {
+                tokio::fs::remove_file(path).await?;
+            }
 
-    let listener = UnixListener::bind(path)?;
-    tracing::info!("vriftd: Listening on {}", socket_str);
+            let listener = UnixListener::bind(path)?;
+            tracing::info!("vriftd: Listening on {}", socket_str);
+            listener
+        }
+    };
 
     // Initialize shared state
     // RFC-0050: VR_THE_SOURCE via unified Config SSOT
@@ -313,11 +440,91 @@ async fn start_daemon() -> Result<()> {
     let cas_root = vrift_manifest::normalize_path(&cas_root_str);
     let cas = vrift_cas::CasStore::new(&cas_root)?;
 
+    // Warm start: serve from the previous run's index until the scan below
+    // has rebuilt it
+    let snapshot_path = snapshot::path();
+    let (cas_index, previous_recent) = match snapshot_path
+        .as_deref()
+        .and_then(|p| snapshot::Snapshot::load(p, &cas_root))
+    {
+        Some(previous) => {
+            tracing::info!(
+                "vriftd: Loaded warm-start snapshot ({} blobs, {} recent workspaces)",
+                previous.cas_index.len(),
+                previous.recent_workspaces.len()
+            );
+            (
+                previous.cas_index.into_iter().collect(),
+                previous.recent_workspaces,
+            )
+        }
+        None => (HashMap::new(), Vec::new()),
+    };
+
+    #[cfg(feature = "upstream")]
+    let upstream = match upstream::Upstream::new(&cfg) {
+        Ok(Some(upstream)) => {
+            if let Some(url) = upstream.url() {
+                tracing::info!("vriftd: Fetching missing blobs from {}", url);
+            }
+            Some(upstream)
+        }
+        Ok(None) => None,
+        Err(e) => {
+            tracing::error!("vriftd: Upstream disabled: {:#}", e);
+            None
+        }
+    };
+    #[cfg(not(feature = "upstream"))]
+    if cfg.upstream.url.is_some() {
+        tracing::warn!(
+            "vriftd: [upstream] url is set but vriftd was built without the upstream feature"
+        );
+    }
+    #[cfg(not(feature = "peers"))]
+    if cfg.peers.enabled {
+        tracing::warn!("vriftd: [peers] is enabled but vriftd was built without the peers feature");
+    }
+
+    let scanner_cfg = &cfg.ingest.scanner;
+    let ingest_scanner = if scanner_cfg.command.is_empty() {
+        None
+    } else {
+        let scanner = vrift_cas::CommandScanner::new(
+            scanner_cfg.command.clone(),
+            std::time::Duration::from_secs(scanner_cfg.timeout_secs),
+        )?;
+        tracing::info!("vriftd: Scanning ingested files with {}", scanner.program());
+        Some((
+            Arc::new(scanner) as Arc<dyn vrift_cas::ContentScanner>,
+            scanner_cfg.batch_size,
+        ))
+    };
+
+    let skip_cfg = &cfg.ingest.skip;
+    let ingest_skip = vrift_cas::SkipPolicy {
+        max_size: (skip_cfg.max_file_bytes > 0).then_some(skip_cfg.max_file_bytes),
+        binary: skip_cfg.binary,
+        reference: skip_cfg.reference,
+    };
+
     let state = Arc::new(DaemonState {
-        cas_index: Mutex::new(HashMap::new()),
+        cas_index: Arc::new(Mutex::new(cas_index)),
         vdird_processes: Mutex::new(HashMap::new()),
         cas: cas.clone(),
         lock_manager: LockManager::new(),
+        sessions: session::SessionTracker::new(),
+        jobs: jobs::JobManager::open(jobs_dir()),
+        workspaces: workspace::WorkspaceTracker::new(),
+        ended_sessions: Mutex::new(HashMap::new()),
+        max_active_workspaces: cfg.daemon.max_active_workspaces,
+        workspace_idle_timeout: cfg.workspace_idle_timeout(),
+        gc_grace: cfg.gc_grace(),
+        pack_traces: pack::PackTraces::new(),
+        ingest_scanner,
+        ingest_skip,
+        #[cfg(feature = "upstream")]
+        upstream,
         start_time: std::time::Instant::now(),
     });
 
@@ -334,18 +541,47 @@ async fn start_daemon() -> Result<()> {
         }
     });
 
-    // RFC-0043: Warm up all registered workspaces on start so mmaps are ready for shims
-    // TEMPORARILY DISABLED: Investigating UE blocking issues
-    // {
-    //     for project_root in load_registered_workspaces() {
-    //         let state_clone = state.clone();
-    //         tokio::spawn(async move {
-    //             if let Err(e) = get_or_create_workspace(&state_clone, project_root).await {
-    //                 tracing::warn!("Failed to warm up workspace: {}", e);
-    //             }
-    //         });
-    //     }
-    // }
+    // RFC-0043: Optionally warm up registered workspaces on start so mmaps are ready
+    // for shims. Otherwise workspaces activate lazily on first registration.
+    // Workspaces load in parallel; the accept loop below runs concurrently, so a
+    // client only ever waits for the workspace it registers.
+    if cfg.daemon.warm_start {
+        let mut roots = load_registered_workspaces();
+        snapshot::order_by_recency(&previous_recent, &mut roots);
+        if state.max_active_workspaces > 0 {
+            roots.truncate(state.max_active_workspaces);
+        }
+        if !roots.is_empty() {
+            tracing::info!("vriftd: Warming up {} registered workspaces", roots.len());
+            let mut warmups = tokio::task::JoinSet::new();
+            for project_root in roots {
+                let state_clone = state.clone();
+                warmups.spawn(async move {
+                    let started = std::time::Instant::now();
+                    match spawn_or_get_vdird(&state_clone, project_root.clone()).await {
+                        Ok(_) => tracing::info!(
+                            "vriftd: Workspace {:?} ready in {:?}",
+                            project_root,
+                            started.elapsed()
+                        ),
+                        Err(e) => {
+                            tracing::warn!("Failed to warm up workspace {:?}: {}", project_root, e)
+                        }
+                    }
+                });
+            }
+            let warm_state = state.clone();
+            tokio::spawn(async move {
+                while warmups.join_next().await.is_some() {}
+                let (ready, _, failed) = warm_state.workspaces.counts();
+                tracing::info!(
+                    "vriftd: Workspace warm-up complete ({} ready, {} failed)",
+                    ready,
+                    failed
+                );
+            });
+        }
+    }
 
     // vDird health monitor: periodically check child processes via waitpid(WNOHANG)
     // If a vDird crashes, remove stale entry so next request triggers respawn
@@ -381,22 +617,136 @@ async fn start_daemon() -> Result<()> {
                     let mut processes = health_state.vdird_processes.lock().unwrap();
                     for key in &stale_keys {
                         if let Some(vdird) = processes.remove(key) {
+                            health_state.workspaces.forget(key);
                             let _ = std::fs::remove_file(&vdird.socket_path);
                             tracing::info!("vriftd: Cleaned up stale vDird for {:?}", key);
                         }
                     }
                 }
+
+                // Unload workspaces that have been idle too long
+                enforce_workspace_limits(&health_state).await;
+            }
+        });
+    }
+
+    // Shim log retention: every shim process leaves a log in log_dir, so keep
+    // their total under daemon.log_retain_bytes (first pass at start-up)
+    if cfg.daemon.log_retain_bytes > 0 {
+        let retain = cfg.daemon.log_retain_bytes;
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
+            loop {
+                interval.tick().await;
+                let dir = log_dir.clone();
+                let pruned = tokio::task::spawn_blocking(move || {
+                    vrift_config::log_files::prune(&dir, retain)
+                })
+                .await;
+                match pruned {
+                    Ok(Ok(0)) | Err(_) => {}
+                    Ok(Ok(freed)) => {
+                        tracing::info!("vriftd: Pruned {} bytes of old shim logs", freed)
+                    }
+                    Ok(Err(e)) => tracing::warn!("vriftd: Shim log pruning failed: {}", e),
+                }
+            }
+        });
+    }
+
+    // Remote orchestration facade (fleet warmups / GC), off unless configured
+    #[cfg(feature = "grpc")]
+    if cfg.grpc.listen.is_some() {
+        let grpc_state = state.clone();
+        let grpc_cfg = cfg.grpc.clone();
+        tokio::spawn(async move {
+            if let Err(e) = grpc::serve(grpc_state, &grpc_cfg).await {
+                tracing::error!("vriftd: gRPC server failed: {:#}", e);
+            }
+        });
+    }
+    #[cfg(not(feature = "grpc"))]
+    if cfg.grpc.listen.is_some() {
+        tracing::warn!(
+            "vriftd: [grpc] listen is set but vriftd was built without the grpc feature"
+        );
+    }
+
+    // Read-only blob export for remote runners, off unless configured
+    #[cfg(feature = "http")]
+    if cfg.http.listen.is_some() {
+        let http_state = state.clone();
+        let http_cfg = cfg.http.clone();
+        tokio::spawn(async move {
+            if let Err(e) = http::serve(http_state, &http_cfg).await {
+                tracing::error!("vriftd: HTTP export failed: {:#}", e);
+            }
+        });
+    }
+    #[cfg(not(feature = "http"))]
+    if cfg.http.listen.is_some() {
+        tracing::warn!(
+            "vriftd: [http] listen is set but vriftd was built without the http feature"
+        );
+    }
+
+    // The IPC protocol over TCP, off unless configured
+    #[cfg(feature = "tcp")]
+    if cfg.tcp.listen.is_some() {
+        let tcp_state = state.clone();
+        let tcp_cfg = cfg.tcp.clone();
+        tokio::spawn(async move {
+            if let Err(e) = tcp::serve(tcp_state, &tcp_cfg).await {
+                tracing::error!("vriftd: TCP listener failed: {:#}", e);
+            }
+        });
+    }
+    #[cfg(not(feature = "tcp"))]
+    if cfg.tcp.listen.is_some() {
+        tracing::warn!("vriftd: [tcp] listen is set but vriftd was built without the tcp feature");
+    }
+
+    // Session reaper: drop exited processes, release their locks and clean up
+    // staging files once a whole process tree is gone
+    {
+        let reap_state = state.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(5));
+            loop {
+                interval.tick().await;
+                reap_sessions(&reap_state);
+            }
+        });
+    }
+
+    // Tier re-evaluation: once no session is live in a workspace, re-tier it
+    // from the sessions that ended there
+    if cfg.tiers.reevaluate_interval_secs > 0 {
+        let retier_state = state.clone();
+        let period = std::time::Duration::from_secs(cfg.tiers.reevaluate_interval_secs);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            interval.tick().await; // skip first immediate tick
+            loop {
+                interval.tick().await;
+                schedule_retier(&retier_state);
             }
         });
     }
 
+    // `systemctl stop` / `launchctl unload` send SIGTERM
+    let mut sigterm = signal::unix::signal(signal::unix::SignalKind::terminate())?;
     loop {
         tokio::select! {
             accept_result = listener.accept() => {
                 match accept_result {
                     Ok((stream, _addr)) => {
                         let state = state.clone();
-                        tokio::spawn(handle_connection(stream, state));
+                        let peer_creds = PeerCredentials::from_stream(&stream);
+                        tokio::spawn(crash::REQUEST_CONTEXT.scope(
+                            Default::default(),
+                            handle_connection(stream, peer_creds, state),
+                        ));
                     }
                     Err(err) => {
                         tracing::error!("vriftd: Accept error: {}", err);
@@ -407,19 +757,94 @@ async fn start_daemon() -> Result<()> {
                 println!("vriftd: Shutdown signal received");
                 break;
             }
+            _ = sigterm.recv() => {
+                println!("vriftd: SIGTERM received");
+                break;
+            }
         }
     }
 
     println!("vriftd: Shutting down");
+    if let Some(ref path) = snapshot_path {
+        let snapshot = snapshot::Snapshot::new(
+            &cas_root,
+            &state.cas_index.lock().unwrap(),
+            state.workspaces.recent(),
+            &previous_recent,
+        );
+        match snapshot.save(path) {
+            Ok(()) => tracing::info!("vriftd: Saved warm-start snapshot to {:?}", path),
+            Err(e) => tracing::warn!("vriftd: Failed to save snapshot {:?}: {}", path, e),
+        }
+    }
     cleanup_vdird_processes(&state).await;
 
-    if path.exists() {
+    if owns_socket && path.exists() {
         tokio::fs::remove_file(path).await?;
     }
 
     Ok(())
 }
 
+fn reap_sessions(state: &DaemonState) {
+    let (reaped, ended) = state.sessions.reap(session::pid_alive);
+    for proc in reaped {
+        let released = state.lock_manager.release_all(proc.pid);
+        if released > 0 {
+            tracing::info!(
+                "vriftd: Released {} lock(s) held by exited pid={} (session {})",
+                released,
+                proc.pid,
+                proc.session_id
+            );
+        }
+    }
+    for ended in ended {
+        let removed = session::cleanup_staging(&ended.project_root, &ended.members);
+        tracing::info!(
+            "vriftd: Session {} ended ({} processes), removed {} staging file(s)",
+            ended.session_id,
+            ended.members.len(),
+            removed
+        );
+        *state
+            .ended_sessions
+            .lock()
+            .unwrap()
+            .entry(ended.project_root)
+            .or_insert(0) += 1;
+    }
+}
+
+/// Submit a tier re-evaluation for every workspace with ended sessions and
+/// no live one
+fn schedule_retier(state: &Arc<DaemonState>) {
+    let busy = state.sessions.active_roots();
+    let due: Vec<(PathBuf, u64)> = {
+        let mut ended = state.ended_sessions.lock().unwrap();
+        let roots: Vec<PathBuf> = ended
+            .keys()
+            .filter(|root| !busy.contains(root))
+            .cloned()
+            .collect();
+        roots
+            .into_iter()
+            .filter_map(|root| ended.remove_entry(&root))
+            .collect()
+    };
+    for (project_root, sessions) in due {
+        let job = state.jobs.submit(
+            jobs::JobSpec::Retier {
+                project_root: project_root.to_string_lossy().to_string(),
+                sessions,
+            },
+            0,
+            None,
+        );
+        spawn_job(state.clone(), job);
+    }
+}
+
 async fn cleanup_vdird_processes(state: &DaemonState) {
     let processes = {
         let mut processes = state.vdird_processes.lock().unwrap();
@@ -455,9 +880,16 @@ async fn cleanup_vdird_processes(state: &DaemonState) {
     }
 }
 
-async fn handle_connection(mut stream: UnixStream, state: Arc<DaemonState>) {
+/// Serve requests from a Unix socket connection, or a TCP one (without
+/// `peer_creds`) once it has authenticated
+async fn handle_connection<S>(
+    mut stream: S,
+    peer_creds: Option<PeerCredentials>,
+    state: Arc<DaemonState>,
+) where
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin + Send + 'static,
+{
     tracing::info!("[DAEMON] New connection accepted");
-    let peer_creds = PeerCredentials::from_stream(&stream);
     let daemon_uid = unsafe { libc::getuid() };
     let mut current_vdird: Option<Arc<VDirdProcess>> = None;
 
@@ -484,13 +916,36 @@ async fn handle_connection(mut stream: UnixStream, state: Arc<DaemonState>) {
             header.length
         );
 
+        if let VeloRequest::Subscribe { events } = req {
+            // Manifest changes are pushed by each workspace's vDird
+            let events: Vec<EventKind> = events
+                .into_iter()
+                .filter(|kind| matches!(kind, EventKind::IngestProgress | EventKind::Gc))
+                .collect();
+            let subscribed = !events.is_empty();
+            let ack = VeloResponse::SubscribeAck {
+                events: events.clone(),
+            };
+            if let Err(e) = vrift_ipc::frame_async::send_response(&mut stream, &ack, seq_id).await {
+                tracing::warn!("[DAEMON] Failed to send response: {}", e);
+                return;
+            }
+            if subscribed {
+                push_job_events(stream, &state, &events, seq_id).await;
+                return;
+            }
+            continue;
+        }
+
         let response = {
             tracing::info!(
                 "[DAEMON] Processing request: {:?}",
                 std::mem::discriminant(&req)
             );
+            crash::enter_request(&req);
             let resp =
                 handle_request(req, &state, peer_creds, daemon_uid, &mut current_vdird).await;
+            crash::leave_request(current_vdird.as_deref().map(|v| v.project_root.as_path()));
             tracing::info!(
                 "[DAEMON] Request processed, response: {:?}",
                 std::mem::discriminant(&resp)
@@ -509,9 +964,58 @@ async fn handle_connection(mut stream: UnixStream, state: Arc<DaemonState>) {
     }
 }
 
+/// How often a subscribed connection is sent the progress of running jobs
+const JOB_PROGRESS_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Push job events of the subscribed `kinds` until the client goes away or
+/// sends another request: every state change, and the progress of running
+/// jobs every [`JOB_PROGRESS_INTERVAL`]
+async fn push_job_events<S>(stream: S, state: &DaemonState, kinds: &[EventKind], seq_id: u32)
+where
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Send + 'static,
+{
+    use tokio::sync::broadcast::error::RecvError;
+
+    let mut transitions = state.jobs.subscribe();
+    let (mut reader, mut writer) = tokio::io::split(stream);
+    // Any frame but a heartbeat, or EOF, ends the subscription
+    let mut client_done =
+        tokio::spawn(async move { vrift_ipc::frame_async::read_request(&mut reader).await });
+    let mut ticks = tokio::time::interval(JOB_PROGRESS_INTERVAL);
+    ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+    loop {
+        let events = tokio::select! {
+            job = transitions.recv() => match job {
+                Ok(job) => VeloEvent::for_job(job).into_iter().collect(),
+                Err(RecvError::Lagged(missed)) => vec![VeloEvent::Lagged { missed }],
+                Err(RecvError::Closed) => break,
+            },
+            _ = ticks.tick() => state
+                .jobs
+                .running()
+                .into_iter()
+                .filter_map(VeloEvent::for_job)
+                .collect(),
+            _ = &mut client_done => break,
+        };
+        for event in events {
+            if event.kind().is_some_and(|kind| !kinds.contains(&kind)) {
+                continue;
+            }
+            if let Err(e) = vrift_ipc::frame_async::send_event(&mut writer, &event, seq_id).await {
+                tracing::debug!("[DAEMON] Subscriber went away: {}", e);
+                client_done.abort();
+                return;
+            }
+        }
+    }
+    client_done.abort();
+}
+
 async fn handle_request(
     req: VeloRequest,
-    state: &DaemonState,
+    state: &Arc<DaemonState>,
     peer_creds: Option<PeerCredentials>,
     daemon_uid: u32,
     current_vdird: &mut Option<Arc<VDirdProcess>>,
@@ -526,6 +1030,11 @@ async fn handle_request(
             protocol_version: vrift_ipc::PROTOCOL_VERSION,
             compatible: vrift_ipc::is_version_compatible(protocol_version),
         },
+        VeloRequest::Ping => VeloResponse::PingAck {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            uptime_secs: state.start_time.elapsed().as_secs(),
+            pid: std::process::id(),
+        },
         VeloRequest::Status => {
             let blob_count = state.cas_index.lock().unwrap().len();
             let vdird_count = state.vdird_processes.lock().unwrap().len();
@@ -541,10 +1050,11 @@ async fn handle_request(
             } else {
                 format!("{}s", uptime.as_secs())
             };
+            let (ready, loading, failed) = state.workspaces.counts();
             VeloResponse::StatusAck {
                 status: format!(
-                    "Multi-tenant Operational (Global Blobs: {}, vDird Processes: {}, Uptime: {})",
-                    blob_count, vdird_count, uptime_str
+                    "Multi-tenant Operational (Global Blobs: {}, vDird Processes: {}, Workspaces: {} ready/{} loading/{} failed, Uptime: {})",
+                    blob_count, vdird_count, ready, loading, failed, uptime_str
                 ),
             }
         }
@@ -574,6 +1084,7 @@ async fn handle_request(
                         vdird.socket_path,
                         vdird.project_root
                     );
+                    state.workspaces.touch(&vdird.project_root);
                     *current_vdird = Some(vdird.clone());
                     VeloResponse::RegisterAck {
                         workspace_id: vdird.project_id.clone(),
@@ -600,6 +1111,54 @@ async fn handle_request(
             }
             handle_spawn(command, env, cwd).await
         }
+        VeloRequest::SessionRegister { pid, ppid, command } => {
+            let Some(ref vdird) = current_vdird else {
+                return VeloResponse::Error(VeloError::workspace_not_registered());
+            };
+            // A process may report itself, or a child it just spawned
+            let peer_pid = peer_creds.and_then(|c| c.pid).map(|p| p as u32);
+            if peer_pid.is_some() && peer_pid != Some(pid) && peer_pid != Some(ppid) {
+                return VeloResponse::Error(VeloError::permission_denied(
+                    "Session report for unrelated pid",
+                ));
+            }
+            state.workspaces.touch(&vdird.project_root);
+            let session_id = state
+                .sessions
+                .register(pid, ppid, command, &vdird.project_root);
+            tracing::debug!(
+                "vriftd: pid={} (ppid={}) joined session {}",
+                pid,
+                ppid,
+                session_id
+            );
+            VeloResponse::StatusAck {
+                status: session_id.to_string(),
+            }
+        }
+        VeloRequest::SessionWriteBack { pid, path } => {
+            let peer_pid = peer_creds.and_then(|c| c.pid).map(|p| p as u32);
+            if peer_pid.is_some() && peer_pid != Some(pid) {
+                return VeloResponse::Error(VeloError::permission_denied(
+                    "Write-back reported for another pid",
+                ));
+            }
+            if !state.sessions.record_writeback(pid) {
+                tracing::debug!("vriftd: Write-back of '{}' by untracked pid={}", path, pid);
+            }
+            VeloResponse::StatusAck {
+                status: "ok".to_string(),
+            }
+        }
+        VeloRequest::SessionList => {
+            reap_sessions(state);
+            VeloResponse::SessionListAck {
+                sessions: state.sessions.list(|pid| state.lock_manager.held_by(pid)),
+            }
+        }
+        VeloRequest::WorkspaceList => VeloResponse::WorkspaceListAck {
+            workspaces: state.workspaces.list(),
+        },
         VeloRequest::CasInsert { hash, size } => {
             let mut index = state.cas_index.lock().unwrap();
             index.insert(hash, size);
@@ -613,6 +1172,27 @@ async fn handle_request(
                 VeloResponse::CasNotFound
             }
         }
+        VeloRequest::CasFetch { hash, size } => {
+            if state.cas.exists(&hash) {
+                return VeloResponse::CasFound { size };
+            }
+            #[cfg(feature = "upstream")]
+            if let Some(ref upstream) = state.upstream {
+                match upstream.fetch(&state.cas, &hash, size).await {
+                    Ok(true) => {
+                        state.cas_index.lock().unwrap().insert(hash, size);
+                        return VeloResponse::CasFound { size };
+                    }
+                    Ok(false) => {}
+                    Err(e) => tracing::warn!(
+                        "vriftd: Fetching {} failed: {:#}",
+                        vrift_cas::CasStore::hash_to_hex(&hash),
+                        e
+                    ),
+                }
+            }
+            VeloResponse::CasNotFound
+        }
         VeloRequest::Protect {
             path,
             immutable,
@@ -740,29 +1320,101 @@ async fn handle_request(
             state.lock_manager.release(&path, pid);
             VeloResponse::FlockAck
         }
-        VeloRequest::CasSweep { bloom_filter } => {
-            match state.cas.sweep(&bloom_filter) {
-                Ok((deleted_count, reclaimed_bytes)) => {
-                    // Update global index
-                    let mut index = state.cas_index.lock().unwrap();
-                    index.clear();
-                    if let Ok(iter) = state.cas.iter() {
-                        for hash in iter.flatten() {
-                            if let Some(path) = state.cas.blob_path_for_hash(&hash) {
-                                if let Ok(meta) = std::fs::metadata(path) {
-                                    index.insert(hash, meta.len());
-                                }
-                            }
-                        }
-                    }
-                    VeloResponse::CasSweepAck {
-                        deleted_count,
-                        reclaimed_bytes,
-                    }
-                }
-                Err(e) => VeloResponse::Error(VeloError::internal(format!("Sweep failed: {}", e))),
+        VeloRequest::CasSweep {
+            bloom_filter,
+            dry_run: true,
+        } => plan_sweep(state, Some(bloom_filter)).await,
+        VeloRequest::CasSweep { bloom_filter, .. } => submit_sweep(
+            state,
+            jobs::JobSpec::Sweep {
+                bloom_hex: hex::encode(&bloom_filter),
+                refcounted: false,
+            },
+        ),
+        VeloRequest::CasSweepRefcounted { dry_run: true } => plan_sweep(state, None).await,
+        VeloRequest::CasSweepRefcounted { .. } => submit_sweep(
+            state,
+            jobs::JobSpec::Sweep {
+                bloom_hex: String::new(),
+                refcounted: true,
+            },
+        ),
+        VeloRequest::PackTraceRecord { hashes } => {
+            let Some(ref vdird) = current_vdird else {
+                return VeloResponse::Error(VeloError::workspace_not_registered());
+            };
+            state.pack_traces.record(&vdird.project_root, &hashes);
+            VeloResponse::StatusAck {
+                status: "ok".to_string(),
             }
         }
+        VeloRequest::PackBuild { dry_run } => {
+            let Some(ref vdird) = current_vdird else {
+                return VeloResponse::Error(VeloError::workspace_not_registered());
+            };
+            if dry_run {
+                return plan_pack(state, &vdird.project_root).await;
+            }
+            let blobs = match state.pack_traces.save(&vdird.project_root) {
+                Ok(blobs) => blobs,
+                Err(e) => return VeloResponse::Error(VeloError::not_found(format!("{:#}", e))),
+            };
+            let job = state.jobs.submit(
+                jobs::JobSpec::Pack {
+                    project_root: vdird.project_root.to_string_lossy().to_string(),
+                },
+                blobs as u64,
+                None,
+            );
+            spawn_job(state.clone(), job.clone());
+            VeloResponse::JobAck { job: job.info() }
+        }
+        VeloRequest::PackPrefetch => {
+            let Some(ref vdird) = current_vdird else {
+                return VeloResponse::Error(VeloError::workspace_not_registered());
+            };
+            let blobs = match pack::saved_profile(&vdird.project_root) {
+                Ok(profile) => profile.access_order.len(),
+                Err(e) => return VeloResponse::Error(VeloError::not_found(format!("{:#}", e))),
+            };
+            let job = state.jobs.submit(
+                jobs::JobSpec::Prefetch {
+                    project_root: vdird.project_root.to_string_lossy().to_string(),
+                },
+                blobs as u64,
+                None,
+            );
+            spawn_job(state.clone(), job.clone());
+            VeloResponse::JobAck { job: job.info() }
+        }
+        VeloRequest::CasScrub { delete_corrupt } => {
+            let total_estimate = state.cas_index.lock().unwrap().len() as u64;
+            let job = state.jobs.submit(
+                jobs::JobSpec::Scrub { delete_corrupt },
+                total_estimate,
+                None,
+            );
+            spawn_job(state.clone(), job.clone());
+            VeloResponse::JobAck { job: job.info() }
+        }
+        VeloRequest::JobList => VeloResponse::JobListAck {
+            jobs: state.jobs.list(),
+        },
+        VeloRequest::JobStatus { job_id } => match state.jobs.get(job_id) {
+            Some(job) => VeloResponse::JobAck { job: job.info() },
+            None => VeloResponse::Error(VeloError::not_found(format!("No job {}", job_id))),
+        },
+        VeloRequest::JobCancel { job_id } => match state.jobs.cancel(job_id) {
+            Ok(job) => VeloResponse::JobAck { job },
+            Err(e) => VeloResponse::Error(e),
+        },
+        VeloRequest::JobRetry { job_id } => match state.jobs.retry(job_id) {
+            Ok(job) => {
+                spawn_job(state.clone(), job.clone());
+                VeloResponse::JobAck { job: job.info() }
+            }
+            Err(e) => VeloResponse::Error(e),
+        },
         VeloRequest::ManifestListDir { path } => {
             tracing::warn!(
                 "vriftd: ManifestListDir '{}' received — route to vDird instead",
@@ -773,8 +1425,27 @@ async fn handle_request(
                 "Manifest operations must be routed to vDird. Use the vdird_socket from RegisterAck.",
             ))
         }
+        VeloRequest::ManifestChangesSince { .. }
+        | VeloRequest::ManifestGetMany { .. }
+        | VeloRequest::VDirNegotiate { .. }
+        | VeloRequest::ReingestStats
+        | VeloRequest::Metrics
+        | VeloRequest::ManifestRenameOver { .. }
+        | VeloRequest::PackReload
+        | VeloRequest::ManifestReingestChecked { .. } => VeloResponse::Error(VeloError::new(
+            VeloErrorKind::WorkspaceNotRegistered,
+            "Manifest operations must be routed to vDird. Use the vdird_socket from RegisterAck.",
+        )),
+        // Turns the connection into an event stream; see handle_connection
+        VeloRequest::Subscribe { .. } => VeloResponse::Error(VeloError::internal(
+            "Subscribe is only served on a daemon socket connection",
+        )),
+        // Connections get here already trusted: Unix ones by the socket's
+        // permissions, TCP ones by tcp::authenticate
+        VeloRequest::Authenticate { .. } => VeloResponse::AuthAck,
         // IngestFullScan: Unified ingest architecture
-        // CLI becomes thin client, daemon handles all ingest logic
+        // CLI becomes thin client, daemon handles all ingest logic.
+        // Runs as a job so it shows up in `vrift jobs` and can be retried.
         VeloRequest::IngestFullScan {
             path,
             manifest_path,
@@ -784,256 +1455,649 @@ async fn handle_request(
             prefix,
             cas_root,
             force_hash,
+            dry_run,
+            selection,
         } => {
-            use std::time::Instant;
-            use vrift_cas::{streaming_ingest, streaming_ingest_cached, CacheHint, IngestMode};
-
-            let source_path = PathBuf::from(&path);
-            let manifest_out = PathBuf::from(&manifest_path);
+            let spec = jobs::IngestSpec {
+                path,
+                manifest_path,
+                threads,
+                phantom,
+                tier1,
+                prefix,
+                cas_root,
+                force_hash,
+                selection: selection.map(|s| *s),
+            };
+            if dry_run {
+                return plan_ingest(state, spec).await;
+            }
+            let job = state.jobs.submit(jobs::JobSpec::Ingest(spec), 0, None);
+            run_job(state, &job).await
+        }
+    }
+}
 
-            tracing::info!(
-                path = %path,
-                manifest = %manifest_path,
-                threads = ?threads,
-                phantom = phantom,
-                tier1 = tier1,
-                prefix = ?prefix,
-                "Starting streaming ingest"
-            );
+/// Run a submitted job to completion: wait for its slot, execute it and
+/// record the outcome. Returns the response for a client waiting on the job.
+async fn run_job(state: &DaemonState, job: &Arc<jobs::Job>) -> VeloResponse {
+    let Some(_slot) = state.jobs.start(job).await else {
+        return VeloResponse::JobAck { job: job.info() };
+    };
+    let result = match &job.spec {
+        jobs::JobSpec::Ingest(spec) => run_ingest(state, job, spec.clone()).await,
+        jobs::JobSpec::Sweep {
+            bloom_hex,
+            refcounted,
+        } => run_sweep(state, job, bloom_hex, *refcounted).await,
+        jobs::JobSpec::Retier {
+            project_root,
+            sessions,
+        } => run_retier(state, job, project_root, *sessions).await,
+        jobs::JobSpec::Pack { project_root } => run_pack(state, job, project_root).await,
+        jobs::JobSpec::Scrub { delete_corrupt } => run_scrub(state, job, *delete_corrupt).await,
+        jobs::JobSpec::Prefetch { project_root } => run_prefetch(state, job, project_root).await,
+    };
+    state.jobs.finish(
+        job,
+        result.as_ref().map(|_| ()).map_err(|e| e.message.clone()),
+    );
+    match result {
+        Ok(resp) => resp,
+        Err(e) => VeloResponse::Error(e),
+    }
+}
 
-            let start = Instant::now();
+/// Run a job in the background
+fn spawn_job(state: Arc<DaemonState>, job: Arc<jobs::Job>) {
+    tokio::spawn(async move {
+        run_job(&state, &job).await;
+    });
+}
 
-            // Determine mode
-            let mode = if phantom {
-                IngestMode::Phantom
-            } else if tier1 {
-                IngestMode::SolidTier1
-            } else {
-                IngestMode::SolidTier2
-            };
+/// Queue a sweep, estimating its size from the global index
+fn submit_sweep(state: &Arc<DaemonState>, spec: jobs::JobSpec) -> VeloResponse {
+    let total_estimate = state.cas_index.lock().unwrap().len() as u64;
+    let job = state.jobs.submit(spec, total_estimate, None);
+    spawn_job(state.clone(), job.clone());
+    VeloResponse::JobAck { job: job.info() }
+}
 
-            // CAS path precedence: CLI arg > daemon global
-            let cas_root_path = match cas_root {
-                Some(ref cli_cas) => {
-                    let p = vrift_manifest::normalize_path(cli_cas);
-                    tracing::info!(cas_root = %p.display(), "Using CLI-provided CAS root");
-                    p
-                }
-                None => state.cas.root().to_path_buf(),
-            };
+/// CAS sweep on the blocking pool. Cancellation stops the walk at the next
+/// blob; the global index is rebuilt either way.
+async fn run_sweep(
+    state: &DaemonState,
+    job: &Arc<jobs::Job>,
+    bloom_hex: &str,
+    refcounted: bool,
+) -> Result<VeloResponse, VeloError> {
+    let bloom_filter = hex::decode(bloom_hex)
+        .map_err(|e| VeloError::internal(format!("Corrupt sweep parameters: {}", e)))?;
+    let cas = state.cas.clone();
+    let cas_index = state.cas_index.clone();
+    let grace = state.gc_grace;
+    let sweep_job = job.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        let result = if refcounted {
+            cas.sweep_refcounted_progress(grace, &sweep_job.progress)
+        } else {
+            cas.sweep_with_grace_progress(&bloom_filter, grace, &sweep_job.progress)
+        };
 
-            // P0: Load existing manifest for mtime+size cache skip (SolidTier2 only)
-            // --force-hash bypasses cache skip but loads manifest for audit comparison
-            let existing_manifest = if mode == IngestMode::SolidTier2 && !force_hash {
-                match LmdbManifest::open(&manifest_out) {
-                    Ok(m) => {
-                        tracing::info!("P0: loaded existing manifest for cache skip");
-                        Some(std::sync::Arc::new(m))
-                    }
-                    Err(e) => {
-                        tracing::info!("P0: no existing manifest (first ingest): {}", e);
-                        None
+        // Rebuild the global index off-lock, then swap it in
+        let mut rebuilt = HashMap::new();
+        if let Ok(iter) = cas.iter() {
+            for hash in iter.flatten() {
+                if let Some(path) = cas.blob_path_for_hash(&hash) {
+                    if let Ok(meta) = std::fs::metadata(path) {
+                        rebuilt.insert(hash, meta.len());
                     }
                 }
-            } else {
-                None
-            };
+            }
+        }
+        *cas_index.lock().unwrap() = rebuilt;
+        result
+    })
+    .await
+    .map_err(|e| VeloError::internal(format!("Sweep task failed: {}", e)))?;
+
+    match result {
+        Ok(_) => Ok(VeloResponse::JobAck { job: job.info() }),
+        Err(e) => Err(VeloError::new(
+            e.classify().into(),
+            format!("Sweep failed: {}", e),
+        )),
+    }
+}
 
-            // --force-hash audit: load old manifest to compare after full re-hash
-            let audit_manifest = if force_hash {
-                match LmdbManifest::open(&manifest_out) {
-                    Ok(m) => {
-                        tracing::info!("--force-hash: loaded manifest for audit comparison");
-                        Some(std::sync::Arc::new(m))
-                    }
-                    Err(_) => None,
-                }
-            } else {
-                None
-            };
+/// Packfile build on the blocking pool
+async fn run_pack(
+    state: &DaemonState,
+    job: &Arc<jobs::Job>,
+    project_root: &str,
+) -> Result<VeloResponse, VeloError> {
+    let cas = state.cas.clone();
+    let pack_job = job.clone();
+    let root = PathBuf::from(project_root);
+    tokio::task::spawn_blocking(move || pack::build(&root, &cas, &pack_job.progress))
+        .await
+        .map_err(|e| VeloError::internal(format!("Pack task failed: {}", e)))?
+        .map_err(|e| VeloError::internal(format!("Pack failed: {:#}", e)))?;
+    reload_vdird_pack(state, Path::new(project_root)).await;
+    Ok(VeloResponse::JobAck { job: job.info() })
+}
 
-            // Phase4-#1: Pre-create CAS directory tree so per-file mkdir_all is a fast stat-only path
-            if let Ok(cas_store) = vrift_cas::CasStore::new(&cas_root_path) {
-                if let Err(e) = cas_store.warm_directories() {
-                    tracing::warn!("warm_directories failed (non-fatal): {}", e);
-                } else {
-                    tracing::info!("CAS directory tree warmed");
-                }
-            }
+/// Have the vDird of `project_root` point its VDir entries into the rebuilt
+/// packfile. Best effort: a vDird started later reads the pack on its own.
+async fn reload_vdird_pack(state: &DaemonState, project_root: &Path) {
+    let socket_path = match state.vdird_processes.lock().unwrap().get(project_root) {
+        Some(vdird) => vdird.socket_path.clone(),
+        None => return,
+    };
+    let result = async {
+        let mut stream = tokio::net::UnixStream::connect(&socket_path).await?;
+        vrift_ipc::frame_async::send_request(&mut stream, &VeloRequest::PackReload).await?;
+        vrift_ipc::frame_async::read_response(&mut stream).await?;
+        anyhow::Ok(())
+    }
+    .await;
+    if let Err(e) = result {
+        tracing::warn!(socket = %socket_path.display(), error = %e, "vDird pack reload failed");
+    }
+}
 
-            // Run streaming ingest in blocking task
-            let source_clone = source_path.clone();
-            let cas_clone = cas_root_path.clone();
-            let results = match tokio::task::spawn_blocking(move || {
-                if let Some(manifest_arc) = existing_manifest {
-                    // P0: Pre-load manifest into HashMap for O(1) cache lookups
-                    // (avoids per-file LMDB get() with transaction overhead)
-                    tracing::info!("spawn_blocking: pre-loading manifest into HashMap");
-                    let cache_map: std::collections::HashMap<String, CacheHint> = {
-                        match manifest_arc.iter() {
-                            Ok(entries) => entries
-                                .into_iter()
-                                .map(|(key, entry)| {
-                                    (
-                                        key,
-                                        CacheHint {
-                                            content_hash: entry.vnode.content_hash,
-                                            size: entry.vnode.size,
-                                            mtime: entry.vnode.mtime,
-                                        },
-                                    )
-                                })
-                                .collect(),
-                            Err(e) => {
-                                tracing::warn!(
-                                    "Failed to pre-load manifest: {}, falling back to empty cache",
-                                    e
-                                );
-                                std::collections::HashMap::new()
-                            }
-                        }
-                    };
-                    tracing::info!(
-                        "spawn_blocking: loaded {} entries into cache HashMap",
-                        cache_map.len()
-                    );
-                    let cache_map = std::sync::Arc::new(cache_map);
-                    let cache_lookup =
-                        move |key: &str| -> Option<CacheHint> { cache_map.get(key).cloned() };
-                    let r = streaming_ingest_cached(
-                        &source_clone,
-                        &cas_clone,
-                        mode,
-                        threads,
-                        cache_lookup,
-                    );
-                    tracing::info!(
-                        "spawn_blocking: streaming_ingest_cached done, {} results",
-                        r.len()
-                    );
-                    r
-                } else {
-                    // Standard path (first ingest or non-SolidTier2)
-                    tracing::info!("spawn_blocking: starting streaming_ingest");
-                    let r = streaming_ingest(&source_clone, &cas_clone, mode, threads);
-                    tracing::info!("spawn_blocking: streaming_ingest done, {} results", r.len());
-                    r
-                }
-            })
-            .await
-            {
-                Ok(r) => r,
-                Err(e) => {
-                    return VeloResponse::Error(VeloError::new(
-                        VeloErrorKind::IngestFailed,
-                        format!("Ingest task failed: {}", e),
-                    ))
-                }
-            };
+/// CAS integrity walk on the blocking pool. Corrupt blobs that were
+/// removed also leave the global index.
+async fn run_scrub(
+    state: &DaemonState,
+    job: &Arc<jobs::Job>,
+    delete_corrupt: bool,
+) -> Result<VeloResponse, VeloError> {
+    let cas = state.cas.clone();
+    let scrub_job = job.clone();
+    let corrupt = tokio::task::spawn_blocking(move || {
+        cas.scrub_progress(delete_corrupt, &scrub_job.progress)
+    })
+    .await
+    .map_err(|e| VeloError::internal(format!("Scrub task failed: {}", e)))?
+    .map_err(|e| VeloError::new(e.classify().into(), format!("Scrub failed: {}", e)))?;
+    if delete_corrupt {
+        let mut cas_index = state.cas_index.lock().unwrap();
+        for hash in &corrupt {
+            cas_index.remove(hash);
+        }
+    }
+    Ok(VeloResponse::JobAck { job: job.info() })
+}
 
-            let total_files = results.len() as u64;
+/// Page-cache warm-up on the blocking pool
+async fn run_prefetch(
+    state: &DaemonState,
+    job: &Arc<jobs::Job>,
+    project_root: &str,
+) -> Result<VeloResponse, VeloError> {
+    let cas = state.cas.clone();
+    let prefetch_job = job.clone();
+    let root = PathBuf::from(project_root);
+    tokio::task::spawn_blocking(move || pack::prefetch(&root, &cas, &prefetch_job.progress))
+        .await
+        .map_err(|e| VeloError::internal(format!("Prefetch task failed: {}", e)))?
+        .map_err(|e| VeloError::internal(format!("Prefetch failed: {:#}", e)))?;
+    Ok(VeloResponse::JobAck { job: job.info() })
+}
 
-            // 5. Collect stats (including P0 cache skip count)
-            let mut total_bytes = 0u64;
-            let mut new_bytes = 0u64;
-            let mut unique_blobs = 0u64;
-            let mut cache_skipped = 0u64;
+/// Tier re-evaluation on the blocking pool
+async fn run_retier(
+    state: &DaemonState,
+    job: &Arc<jobs::Job>,
+    project_root: &str,
+    sessions: u64,
+) -> Result<VeloResponse, VeloError> {
+    let cas = state.cas.clone();
+    let retier_job = job.clone();
+    let root = PathBuf::from(project_root);
+    tokio::task::spawn_blocking(move || {
+        tiering::retier(&root, sessions, &cas, &retier_job.progress)
+    })
+    .await
+    .map_err(|e| VeloError::internal(format!("Retier task failed: {}", e)))?
+    .map_err(|e| VeloError::internal(format!("Tier re-evaluation failed: {:#}", e)))?;
+    Ok(VeloResponse::JobAck { job: job.info() })
+}
 
-            for r in results.iter().flatten() {
-                total_bytes += r.size;
-                if r.was_new {
-                    unique_blobs += 1;
-                    new_bytes += r.size;
-                }
-                if r.skipped_by_cache {
-                    cache_skipped += 1;
-                }
-            }
+/// Streaming full-scan ingest; publishes its totals to the job when done
+async fn run_ingest(
+    state: &DaemonState,
+    job: &jobs::Job,
+    spec: jobs::IngestSpec,
+) -> Result<VeloResponse, VeloError> {
+    let mut filter = spec.filter()?;
+    filter.skip = state.ingest_skip;
+    let jobs::IngestSpec {
+        path,
+        manifest_path,
+        threads,
+        phantom,
+        tier1,
+        prefix,
+        cas_root,
+        force_hash,
+        ..
+    } = spec;
+    use std::time::Instant;
+    use vrift_cas::{streaming_ingest, streaming_ingest_cached, CacheHint, IngestMode};
+
+    let source_path = PathBuf::from(&path);
+    let manifest_out = PathBuf::from(&manifest_path);
 
-            // --force-hash audit: compare re-hashed results against old manifest
-            if let Some(ref audit) = audit_manifest {
-                let canon_root = source_path
-                    .canonicalize()
-                    .unwrap_or_else(|_| source_path.clone());
-                let prefix_str = prefix.as_deref().unwrap_or("");
-                let mut verified = 0u64;
-                let mut mismatched = 0u64;
-                for r in results.iter().flatten() {
-                    let canon_src = r
-                        .source_path
-                        .canonicalize()
-                        .unwrap_or_else(|_| r.source_path.clone());
-                    let rel = canon_src.strip_prefix(&canon_root).unwrap_or(&canon_src);
-                    let key = if prefix_str.is_empty() || prefix_str == "/" {
-                        format!("/{}", rel.display())
-                    } else {
-                        format!("{}/{}", prefix_str.trim_end_matches('/'), rel.display())
-                    };
-                    if let Ok(Some(old_entry)) = audit.get(&key) {
-                        if old_entry.vnode.content_hash != r.hash {
-                            tracing::warn!(
-                                "--force-hash MISMATCH: {} old={} new={}",
-                                key,
-                                hex::encode(old_entry.vnode.content_hash),
-                                hex::encode(r.hash),
-                            );
-                            mismatched += 1;
-                        }
-                        verified += 1;
-                    }
-                }
-                tracing::info!(
-                    "--force-hash audit complete: verified={}, mismatched={}",
-                    verified,
-                    mismatched,
-                );
-            }
+    tracing::info!(
+        path = %path,
+        manifest = %manifest_path,
+        threads = ?threads,
+        phantom = phantom,
+        tier1 = tier1,
+        prefix = ?prefix,
+        filter = ?(!filter.is_empty()).then_some(&filter),
+        "Starting streaming ingest"
+    );
 
-            let duration = start.elapsed();
+    let start = Instant::now();
 
-            // 6. Write LMDB manifest (RFC-0039 compatible with shim)
-            if let Err(e) = write_ingest_manifest(
-                &manifest_out,
-                &source_path,
-                &results,
-                tier1,
-                prefix.as_deref(),
-            ) {
-                return VeloResponse::Error(VeloError::io_error(format!(
-                    "Failed to write manifest: {}",
-                    e
-                )));
+    let mode = ingest_mode(phantom, tier1);
+
+    // CAS path precedence: CLI arg > daemon global
+    let cas_root_path = match cas_root {
+        Some(ref cli_cas) => {
+            let p = vrift_manifest::normalize_path(cli_cas);
+            tracing::info!(cas_root = %p.display(), "Using CLI-provided CAS root");
+            p
+        }
+        None => state.cas.root().to_path_buf(),
+    };
+
+    // P0: Load existing manifest for mtime+size cache skip (SolidTier2 only)
+    // --force-hash bypasses cache skip but loads manifest for audit comparison
+    let existing_manifest = if mode == IngestMode::SolidTier2 && !force_hash {
+        match LmdbManifest::open(&manifest_out) {
+            Ok(m) => {
+                tracing::info!("P0: loaded existing manifest for cache skip");
+                Some(std::sync::Arc::new(m))
+            }
+            Err(e) => {
+                tracing::info!("P0: no existing manifest (first ingest): {}", e);
+                None
             }
+        }
+    } else {
+        None
+    };
 
+    // --force-hash audit: load old manifest to compare after full re-hash
+    let audit_manifest = if force_hash {
+        match LmdbManifest::open(&manifest_out) {
+            Ok(m) => {
+                tracing::info!("--force-hash: loaded manifest for audit comparison");
+                Some(std::sync::Arc::new(m))
+            }
+            Err(_) => None,
+        }
+    } else {
+        None
+    };
+
+    // Phase4-#1: Pre-create CAS directory tree so per-file mkdir_all is a fast stat-only path
+    if let Ok(cas_store) = vrift_cas::CasStore::new(&cas_root_path) {
+        if let Err(e) = cas_store.warm_directories() {
+            tracing::warn!("warm_directories failed (non-fatal): {}", e);
+        } else {
+            tracing::info!("CAS directory tree warmed");
+        }
+    }
+
+    // Run streaming ingest in blocking task
+    let source_clone = source_path.clone();
+    let cas_clone = cas_root_path.clone();
+    let scanning = state.ingest_scanner.clone();
+    let results = match tokio::task::spawn_blocking(move || {
+        if let Some(manifest_arc) = existing_manifest {
+            // P0: Pre-load manifest into HashMap for O(1) cache lookups
+            // (avoids per-file LMDB get() with transaction overhead)
+            tracing::info!("spawn_blocking: pre-loading manifest into HashMap");
+            let cache_map = cache_hints(&manifest_arc);
             tracing::info!(
-                files = total_files,
-                blobs = unique_blobs,
-                new_bytes = new_bytes,
-                cache_skipped = cache_skipped,
-                duration_ms = duration.as_millis() as u64,
-                "Full scan ingest complete"
+                "spawn_blocking: loaded {} entries into cache HashMap",
+                cache_map.len()
+            );
+            let cache_map = std::sync::Arc::new(cache_map);
+            let cache_lookup =
+                move |key: &str| -> Option<CacheHint> { cache_map.get(key).cloned() };
+            let r = streaming_ingest_cached(
+                &source_clone,
+                &cas_clone,
+                mode,
+                threads,
+                cache_lookup,
+                &filter,
+                scanning,
             );
+            tracing::info!(
+                "spawn_blocking: streaming_ingest_cached done, {} results",
+                r.len()
+            );
+            r
+        } else {
+            // Standard path (first ingest or non-SolidTier2)
+            tracing::info!("spawn_blocking: starting streaming_ingest");
+            let r = streaming_ingest(&source_clone, &cas_clone, mode, threads, &filter, scanning);
+            tracing::info!("spawn_blocking: streaming_ingest done, {} results", r.len());
+            r
+        }
+    })
+    .await
+    {
+        Ok(r) => r,
+        Err(e) => {
+            return Err(VeloError::new(
+                VeloErrorKind::IngestFailed,
+                format!("Ingest task failed: {}", e),
+            ))
+        }
+    };
 
-            VeloResponse::IngestAck {
-                files: total_files,
-                blobs: unique_blobs,
-                new_bytes,
-                total_bytes,
-                duration_ms: duration.as_millis() as u64,
-                manifest_path,
+    // 5. Collect stats (including P0 cache skip count)
+    for r in &results {
+        match r {
+            Err(vrift_cas::CasError::Quarantined { path, reason }) => {
+                tracing::warn!(path = %path.display(), reason = %reason, "Quarantined at ingest");
+            }
+            Err(vrift_cas::CasError::Skipped { path, reason }) => {
+                tracing::info!(path = %path.display(), reason = %reason, "Skipped at ingest");
             }
+            _ => {}
+        }
+    }
+    let totals = vrift_cas::IngestTotals::of(&results, vrift_ipc::IngestBlob::MAX_LISTED);
+
+    // --force-hash audit: compare re-hashed results against old manifest
+    if let Some(ref audit) = audit_manifest {
+        let canon_root = source_path
+            .canonicalize()
+            .unwrap_or_else(|_| source_path.clone());
+        let prefix_str = prefix.as_deref().unwrap_or("");
+        let mut verified = 0u64;
+        let mut mismatched = 0u64;
+        for r in results.iter().flatten() {
+            let canon_src = r
+                .source_path
+                .canonicalize()
+                .unwrap_or_else(|_| r.source_path.clone());
+            let rel = canon_src.strip_prefix(&canon_root).unwrap_or(&canon_src);
+            let rel = vrift_ipc::path_key::from_path(rel);
+            let key = if prefix_str.is_empty() || prefix_str == "/" {
+                format!("/{}", rel)
+            } else {
+                format!("{}/{}", prefix_str.trim_end_matches('/'), rel)
+            };
+            if let Ok(Some(old_entry)) = audit.get(&key) {
+                if old_entry.vnode.content_hash != r.hash {
+                    tracing::warn!(
+                        "--force-hash MISMATCH: {} old={} new={}",
+                        key,
+                        hex::encode(old_entry.vnode.content_hash),
+                        hex::encode(r.hash),
+                    );
+                    mismatched += 1;
+                }
+                verified += 1;
+            }
+        }
+        tracing::info!(
+            "--force-hash audit complete: verified={}, mismatched={}",
+            verified,
+            mismatched,
+        );
+    }
+
+    let duration = start.elapsed();
+
+    // 6. Write LMDB manifest (RFC-0039 compatible with shim)
+    // Skipped files referenced by path need the path stored as a blob
+    let reference_cas = if state.ingest_skip.reference {
+        vrift_cas::CasStore::new(&cas_root_path).ok()
+    } else {
+        None
+    };
+    if let Err(e) = write_ingest_manifest(
+        &manifest_out,
+        &source_path,
+        &results,
+        tier1,
+        prefix.as_deref(),
+        reference_cas.as_ref(),
+    ) {
+        return Err(VeloError::io_error(format!(
+            "Failed to write manifest: {}",
+            e
+        )));
+    }
+
+    tracing::info!(
+        files = totals.files,
+        blobs = totals.blobs,
+        new_bytes = totals.new_bytes,
+        cache_skipped = totals.unchanged,
+        deduped = totals.deduped,
+        quarantined = totals.quarantined,
+        skipped = totals.skipped,
+        duration_ms = duration.as_millis() as u64,
+        "Full scan ingest complete"
+    );
+
+    job.progress
+        .processed
+        .store(totals.files, Ordering::Relaxed);
+    job.progress.affected.store(totals.blobs, Ordering::Relaxed);
+    job.progress
+        .bytes
+        .store(totals.new_bytes, Ordering::Relaxed);
+
+    // Cache-skipped files keep the tier they had; the rest get the one asked for
+    let written = totals.blobs + totals.deduped;
+    Ok(VeloResponse::IngestAck {
+        files: totals.files,
+        blobs: totals.blobs,
+        new_bytes: totals.new_bytes,
+        total_bytes: totals.total_bytes,
+        duration_ms: duration.as_millis() as u64,
+        manifest_path,
+        tier1_files: if tier1 { written } else { 0 },
+        tier2_files: if tier1 { 0 } else { written },
+        unchanged_files: totals.unchanged,
+        deduped_files: totals.deduped,
+        quarantined_files: totals.quarantined,
+        skipped_files: totals.skipped,
+        largest_new: totals
+            .largest_new
+            .iter()
+            .map(|(path, size)| vrift_ipc::IngestBlob::new(&source_path, path, *size))
+            .collect(),
+    })
+}
+
+fn ingest_mode(phantom: bool, tier1: bool) -> vrift_cas::IngestMode {
+    if phantom {
+        vrift_cas::IngestMode::Phantom
+    } else if tier1 {
+        vrift_cas::IngestMode::SolidTier1
+    } else {
+        vrift_cas::IngestMode::SolidTier2
+    }
+}
+
+/// mtime+size cache of an ingest, from the manifest it rewrites
+fn cache_hints(manifest: &LmdbManifest) -> HashMap<String, vrift_cas::CacheHint> {
+    match manifest.iter() {
+        Ok(entries) => entries
+            .into_iter()
+            .map(|(key, entry)| {
+                (
+                    key,
+                    vrift_cas::CacheHint {
+                        content_hash: entry.vnode.content_hash,
+                        size: entry.vnode.size,
+                        mtime: entry.vnode.mtime,
+                    },
+                )
+            })
+            .collect(),
+        Err(e) => {
+            tracing::warn!(
+                "Failed to pre-load manifest: {}, falling back to empty cache",
+                e
+            );
+            HashMap::new()
+        }
+    }
+}
+
+/// Dry run of an ingest: the files it would hash, link or move, found by
+/// the same walk and mtime+size cache
+async fn plan_ingest(state: &DaemonState, spec: jobs::IngestSpec) -> VeloResponse {
+    use vrift_cas::IngestMode;
+    use vrift_ipc::{Plan, PlanAction};
+
+    let source = PathBuf::from(&spec.path);
+    if !source.is_dir() {
+        return VeloResponse::Error(VeloError::not_found(format!(
+            "Not a directory: {}",
+            spec.path
+        )));
+    }
+    let mut filter = match spec.filter() {
+        Ok(filter) => filter,
+        Err(e) => return VeloResponse::Error(e),
+    };
+    filter.skip = state.ingest_skip;
+    let mode = ingest_mode(spec.phantom, spec.tier1);
+    let manifest_out = PathBuf::from(&spec.manifest_path);
+    let use_cache = mode == IngestMode::SolidTier2 && !spec.force_hash;
+    let files = match tokio::task::spawn_blocking(move || {
+        // Opening a manifest creates it, so only an existing one is read
+        let hints = (use_cache && manifest_out.is_dir())
+            .then(|| LmdbManifest::open(&manifest_out).ok())
+            .flatten()
+            .map(|manifest| cache_hints(&manifest));
+        vrift_cas::plan_ingest(
+            &source,
+            mode,
+            &|key: &str| hints.as_ref().and_then(|hints| hints.get(key).cloned()),
+            &filter,
+        )
+    })
+    .await
+    {
+        Ok(files) => files,
+        Err(e) => {
+            return VeloResponse::Error(VeloError::internal(format!("Plan task failed: {}", e)))
+        }
+    };
+
+    let action = match mode {
+        IngestMode::Phantom => PlanAction::Move,
+        IngestMode::SolidTier1 => PlanAction::Link,
+        IngestMode::SolidTier2 => PlanAction::Hash,
+    };
+    let mut plan = Plan::default();
+    for file in files {
+        if file.skipped.is_some() {
+            plan.push(PlanAction::Skip, file.path.to_string_lossy(), file.size);
+        } else if file.unchanged {
+            plan.unchanged += 1;
+        } else {
+            plan.push(action, file.path.to_string_lossy(), file.size);
+        }
+    }
+    VeloResponse::PlanAck { plan }
+}
+
+/// Dry run of a sweep by `bloom_filter`, or by refcount without one
+async fn plan_sweep(state: &DaemonState, bloom_filter: Option<Vec<u8>>) -> VeloResponse {
+    use vrift_ipc::{Plan, PlanAction};
+
+    let cas = state.cas.clone();
+    let grace = state.gc_grace;
+    let result = tokio::task::spawn_blocking(move || match bloom_filter {
+        Some(bloom_filter) => cas.plan_sweep_with_grace(&bloom_filter, grace),
+        None => cas.plan_sweep_refcounted(grace),
+    })
+    .await;
+    let sweep = match result {
+        Ok(Ok(sweep)) => sweep,
+        Ok(Err(e)) => {
+            return VeloResponse::Error(VeloError::new(
+                e.classify().into(),
+                format!("Sweep plan failed: {}", e),
+            ))
+        }
+        Err(e) => {
+            return VeloResponse::Error(VeloError::internal(format!("Plan task failed: {}", e)))
         }
+    };
+
+    let mut plan = Plan::default();
+    for (hash, size) in sweep.delete {
+        plan.push(
+            PlanAction::Delete,
+            vrift_cas::CasStore::hash_to_hex(&hash),
+            size,
+        );
+    }
+    for (hash, size) in sweep.mark {
+        plan.push(
+            PlanAction::Mark,
+            vrift_cas::CasStore::hash_to_hex(&hash),
+            size,
+        );
+    }
+    VeloResponse::PlanAck { plan }
+}
+
+/// Dry run of a pack build of `project_root`; the trace goes on
+async fn plan_pack(state: &DaemonState, project_root: &Path) -> VeloResponse {
+    use vrift_ipc::{Plan, PlanAction};
+
+    let profiled = match state.pack_traces.profiled(project_root) {
+        Ok(profiled) => profiled,
+        Err(e) => return VeloResponse::Error(VeloError::not_found(format!("{:#}", e))),
+    };
+    let cas = state.cas.clone();
+    let blobs = match tokio::task::spawn_blocking(move || pack::plan(&profiled, &cas)).await {
+        Ok(blobs) => blobs,
+        Err(e) => {
+            return VeloResponse::Error(VeloError::internal(format!("Plan task failed: {}", e)))
+        }
+    };
+    let mut plan = Plan::default();
+    for (hash, size) in blobs {
+        plan.push(
+            PlanAction::Pack,
+            vrift_cas::CasStore::hash_to_hex(&hash),
+            size,
+        );
     }
+    VeloResponse::PlanAck { plan }
 }
 
 /// Write manifest file from ingest results using LMDB format
-/// (RFC-0039: Compatible with cmd_ingest and shim)
+/// (RFC-0039: Compatible with cmd_ingest and shim). With `reference_cas`,
+/// skipped files get an external entry whose path blob is stored there.
 fn write_ingest_manifest(
     manifest_path: &Path,
     source_root: &Path,
     results: &[Result<vrift_cas::IngestResult, vrift_cas::CasError>],
     tier1: bool,
     prefix: Option<&str>,
+    reference_cas: Option<&vrift_cas::CasStore>,
 ) -> Result<()> {
     use vrift_manifest::VnodeEntry;
 
@@ -1060,23 +2124,35 @@ fn write_ingest_manifest(
         prefix_str.trim_end_matches('/')
     };
 
-    for result in results.iter().flatten() {
-        // P1: Skip manifest write for cache-hit entries — their hash/mtime/size
-        // are already correct in the existing manifest, no need to re-write.
-        if result.skipped_by_cache {
-            continue;
-        }
-
-        // P2: Use mtime/mode carried from ingest stat (avoids redundant fs::metadata())
-        let mtime = result.mtime;
-        let mode = result.mode;
+    for result in results {
+        let (source_path, vnode) = match result {
+            // P1: Skip manifest write for cache-hit entries — their hash/mtime/size
+            // are already correct in the existing manifest, no need to re-write.
+            Ok(result) if result.skipped_by_cache => continue,
+            // P2: Use mtime/mode carried from ingest stat (avoids redundant fs::metadata())
+            Ok(result) => (
+                &result.source_path,
+                Some(VnodeEntry::new_file(
+                    result.hash,
+                    result.size,
+                    result.mtime,
+                    result.mode,
+                )),
+            ),
+            Err(vrift_cas::CasError::Skipped { path, .. }) if reference_cas.is_some() => (
+                path,
+                reference_cas.and_then(|cas| external_entry(cas, path)),
+            ),
+            // An earlier ingest may have let it in before the scanner knew
+            // better, or before it grew past the skip policy's limit
+            Err(vrift_cas::CasError::Quarantined { path, .. })
+            | Err(vrift_cas::CasError::Skipped { path, .. }) => (path, None),
+            Err(_) => continue,
+        };
 
         // #1: Use strip_prefix directly — jwalk yields absolute paths,
         // no need for per-file canonicalize() syscall
-        let relative_path = result
-            .source_path
-            .strip_prefix(&canon_root)
-            .unwrap_or(&result.source_path);
+        let relative_path = source_path.strip_prefix(&canon_root).unwrap_or(source_path);
 
         // #2: Reuse manifest_key buffer (clear + push instead of format! alloc)
         manifest_key.clear();
@@ -1084,13 +2160,13 @@ fn write_ingest_manifest(
             manifest_key.push_str(prefix_trimmed);
         }
         manifest_key.push('/');
-        manifest_key.push_str(&relative_path.to_string_lossy());
-
-        // Create VnodeEntry
-        let vnode = VnodeEntry::new_file(result.hash, result.size, mtime, mode);
+        manifest_key.push_str(&vrift_ipc::path_key::from_path(relative_path));
 
         // Insert into LMDB manifest
-        manifest.insert(&manifest_key, vnode, asset_tier);
+        match vnode {
+            Some(vnode) => manifest.insert(&manifest_key, vnode, asset_tier),
+            None => manifest.remove(&manifest_key),
+        }
     }
 
     // Commit delta layer to LMDB base layer (required for persistence!)
@@ -1101,6 +2177,27 @@ fn write_ingest_manifest(
     Ok(())
 }
 
+/// Manifest entry serving `path` from where it is, its path stored in `cas`
+fn external_entry(cas: &vrift_cas::CasStore, path: &Path) -> Option<vrift_manifest::VnodeEntry> {
+    use std::os::unix::fs::MetadataExt;
+    let meta = std::fs::metadata(path).ok()?;
+    let target = path.canonicalize().ok()?;
+    let target = target.to_str()?;
+    let hash = match cas.store(target.as_bytes()) {
+        Ok(hash) => hash,
+        Err(e) => {
+            tracing::warn!(path = %path.display(), "Failed to store reference: {}", e);
+            return None;
+        }
+    };
+    Some(vrift_manifest::VnodeEntry::new_external(
+        hash,
+        target.len() as u64,
+        vrift_cas::mtime_nsec_from_metadata(&meta),
+        meta.mode(),
+    ))
+}
+
 async fn handle_protect(path_str: String, immutable: bool, owner: Option<String>) -> VeloResponse {
     // Security: Path sandboxing - reject suspicious paths
     if path_str.contains("..") || path_str.contains('\0') {
@@ -1174,6 +2271,8 @@ async fn handle_spawn(
 
     let mut cmd = tokio::process::Command::new(&command[0]);
     cmd.args(&command[1..]);
+    // Only vriftd itself is exempt from the inception layer
+    cmd.env_remove(vrift_ipc::NO_INCEPTION_ENV);
     cmd.envs(env);
     cmd.current_dir(cwd);
 
@@ -1198,7 +2297,10 @@ async fn handle_spawn(
 
             VeloResponse::SpawnAck { pid }
         }
-        Err(e) => VeloResponse::Error(VeloError::internal(format!("Failed to spawn: {}", e))),
+        Err(e) => VeloResponse::Error(VeloError::new(
+            e.classify().into(),
+            format!("Failed to spawn: {}", e),
+        )),
     }
 }
 
@@ -1221,7 +2323,9 @@ async fn scan_cas_root(state: &DaemonState, cas_root_path: &str) -> Result<()> {
     // Iterating millions of files might take time, so blocking the runtime is bad if not careful.
     // But this is a separate task.
 
-    let mut index = state.cas_index.lock().unwrap();
+    // Built off-lock and swapped in, so a warm-start index keeps answering
+    // until the scan is done
+    let mut index = HashMap::new();
 
     // Using blocking iterator
     for hash in (cas.iter()?).flatten() {
@@ -1236,6 +2340,14 @@ async fn scan_cas_root(state: &DaemonState, cas_root_path: &str) -> Result<()> {
         }
     }
 
+    let mut current = state.cas_index.lock().unwrap();
+    // Blobs inserted while the walk ran may have been missed by it
+    for (hash, size) in current.iter() {
+        if !index.contains_key(hash) && cas.exists(hash) {
+            index.insert(*hash, *size);
+        }
+    }
+    *current = index;
     Ok(())
 }
 
@@ -1245,21 +2357,112 @@ async fn spawn_or_get_vdird(
     state: &DaemonState,
     project_root: PathBuf,
 ) -> Result<Arc<VDirdProcess>> {
-    // Check if already running
-    {
-        let processes = state.vdird_processes.lock().unwrap();
-        if let Some(vdird) = processes.get(&project_root) {
-            // Verify socket still exists (basic health check)
-            if vdird.socket_path.exists() {
-                return Ok(vdird.clone());
+    if let Some(vdird) = running_vdird(state, &project_root) {
+        return Ok(vdird);
+    }
+
+    let result = {
+        let guard = state.workspaces.spawn_guard(&project_root);
+        let _spawning = guard.lock().await;
+        // Another task may have brought the workspace up while we waited
+        if let Some(vdird) = running_vdird(state, &project_root) {
+            return Ok(vdird);
+        }
+
+        state.workspaces.mark_loading(&project_root);
+        match spawn_vdird(state, project_root.clone()).await {
+            Ok(vdird) => {
+                state.workspaces.mark_ready(&project_root, vdird.child_pid);
+                Ok(vdird)
+            }
+            Err(e) => {
+                state.workspaces.mark_failed(&project_root, e.to_string());
+                Err(e)
             }
-            tracing::warn!(
-                "vriftd: vDird socket missing for {:?}, respawning...",
-                project_root
-            );
         }
+    };
+
+    // Make room for the new workspace (outside its spawn guard: deactivation
+    // takes the victim's guard, and two spawns must not wait on each other)
+    if result.is_ok() {
+        enforce_workspace_limits(state).await;
     }
+    result
+}
 
+/// Deactivate workspaces beyond the LRU cap or past the idle timeout.
+/// Workspaces with live session processes are left alone.
+async fn enforce_workspace_limits(state: &DaemonState) {
+    let busy = state.sessions.active_roots();
+    let victims = state.workspaces.eviction_candidates(
+        state.max_active_workspaces,
+        state.workspace_idle_timeout,
+        &busy,
+    );
+    for project_root in victims {
+        deactivate_workspace(state, &project_root).await;
+    }
+}
+
+/// Shut down a workspace's vDird gracefully so it commits its manifest.
+/// The next registration for the root activates it again.
+async fn deactivate_workspace(state: &DaemonState, project_root: &Path) {
+    let guard = state.workspaces.spawn_guard(project_root);
+    let _spawning = guard.lock().await;
+
+    let Some(vdird) = state.vdird_processes.lock().unwrap().remove(project_root) else {
+        state.workspaces.forget(project_root);
+        return;
+    };
+    state.workspaces.forget(project_root);
+
+    let pid = vdird.child_pid as libc::pid_t;
+    tracing::info!(
+        "vriftd: Deactivating workspace {:?} (vDird pid={})",
+        project_root,
+        pid
+    );
+    unsafe {
+        libc::kill(pid, libc::SIGINT);
+    }
+
+    // vDird commits its manifest on SIGINT; give it a few seconds before forcing
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+    loop {
+        let mut status: libc::c_int = 0;
+        let ret = unsafe { libc::waitpid(pid, &mut status, libc::WNOHANG) };
+        if ret != 0 {
+            break;
+        }
+        if std::time::Instant::now() >= deadline {
+            tracing::warn!("vriftd: vDird pid={} did not exit, killing", pid);
+            unsafe {
+                libc::kill(pid, libc::SIGKILL);
+                libc::waitpid(pid, &mut status, 0);
+            }
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+    let _ = std::fs::remove_file(&vdird.socket_path);
+}
+
+/// Running vDird for `project_root`, if its socket is still present
+fn running_vdird(state: &DaemonState, project_root: &Path) -> Option<Arc<VDirdProcess>> {
+    let processes = state.vdird_processes.lock().unwrap();
+    let vdird = processes.get(project_root)?;
+    // Verify socket still exists (basic health check)
+    if vdird.socket_path.exists() {
+        return Some(vdird.clone());
+    }
+    tracing::warn!(
+        "vriftd: vDird socket missing for {:?}, respawning...",
+        project_root
+    );
+    None
+}
+
+async fn spawn_vdird(state: &DaemonState, project_root: PathBuf) -> Result<Arc<VDirdProcess>> {
     tracing::info!("vriftd: Spawning vDird for: {:?}", project_root);
 
     // Compute project ID and paths
@@ -1274,6 +2477,12 @@ async fn spawn_or_get_vdird(
     let vdir_mmap_path = vrift_config::path::get_vdir_mmap_path(&project_id)
         .unwrap_or_else(|| project_root.join(".vrift").join("vdir.mmap"));
 
+    // The workspace's own runtime directory, owner-only, when there is a
+    // runtime base; the socket parent below is then that directory
+    if let Some(dir) = vrift_config::path::get_runtime_dir(&project_id) {
+        vrift_config::path::create_runtime_dir(&dir)?;
+    }
+
     // Ensure socket parent directory exists
     if let Some(parent) = socket_path.parent() {
         if !parent.exists() {
@@ -1290,6 +2499,7 @@ async fn spawn_or_get_vdird(
     let child = std::process::Command::new(&vdird_bin)
         .arg(project_root.to_string_lossy().as_ref())
         .env_remove("VRIFT_SOCKET_PATH")
+        .env(vrift_ipc::NO_INCEPTION_ENV, "1")
         .stdin(std::process::Stdio::null())
         .stdout(std::process::Stdio::null())
         .stderr(std::process::Stdio::null())
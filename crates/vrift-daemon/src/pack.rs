@@ -0,0 +1,225 @@
+//! Hot packfiles
+//!
+//! `vrift pack trace` runs a command with `VRIFT_PACK_TRACE` set. Every
+//! process under the inception layer then reports, as it exits, the blobs it
+//! opened in first-access order (`PackTraceRecord`), and vriftd appends the
+//! reports to its workspace's access profile. `PackBuild` saves that profile
+//! next to the workspace's packfile (`~/.vrift/packs/<project_id>.profile`)
+//! and queues a `Repack` job that copies the profiled blobs, in profile order,
+//! into `~/.vrift/packs/<project_id>.pack` with velo-pack. The inception
+//! layer serves read-only opens of those blobs from the packfile's mapping,
+//! so a cold start reads one file front to back instead of many small ones.
+//!
+//! Packed content is keyed by hash, so an outdated packfile only misses:
+//! files changed since the trace are served from the CAS as before.
+//!
+//! `PackPrefetch` queues a `Prefetch` job that reads the same profiled blobs
+//! (the packfile, and from the CAS those it does not hold) into the page
+//! cache ahead of a build, without writing anything.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use vrift_cas::{Blake3Hash, CasError, CasStore, Progress};
+use vrift_pack::{AccessProfile, PackReader, PackWriter};
+
+/// Blobs larger than this stay in the CAS; packing pays off for small files,
+/// and the writer holds the whole pack in memory
+const MAX_PACKED_BLOB: u64 = 1024 * 1024;
+
+/// Where the packfile of `project_root` lives
+pub fn pack_path(project_root: &Path) -> Result<PathBuf> {
+    let project_id = vrift_config::path::compute_project_id(project_root);
+    vrift_config::path::get_pack_path(&project_id).context("No home directory for packfiles")
+}
+
+/// Where the access profile of `project_root` is saved
+pub fn profile_path(project_root: &Path) -> Result<PathBuf> {
+    let project_id = vrift_config::path::compute_project_id(project_root);
+    vrift_config::path::get_access_profile_path(&project_id)
+        .context("No home directory for access profiles")
+}
+
+#[derive(Default)]
+struct Trace {
+    profile: AccessProfile,
+    seen: HashSet<Blake3Hash>,
+}
+
+/// Access profiles being traced, per workspace
+#[derive(Default)]
+pub struct PackTraces {
+    traces: Mutex<HashMap<PathBuf, Trace>>,
+}
+
+impl PackTraces {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append one process's report to the trace of `project_root`
+    pub fn record(&self, project_root: &Path, hashes: &[Blake3Hash]) {
+        let mut traces = self.traces.lock().unwrap();
+        let trace = traces.entry(project_root.to_path_buf()).or_default();
+        for hash in hashes {
+            if trace.seen.insert(*hash) {
+                trace.profile.access_order.push(*hash);
+            }
+        }
+    }
+
+    /// End the trace of `project_root`, saving it as the profile the next
+    /// build reads. Without a trace, the profile saved before (or put in
+    /// place by `vrift pack build --profile`) is used.
+    ///
+    /// Returns the number of blobs in the profile.
+    pub fn save(&self, project_root: &Path) -> Result<usize> {
+        let path = profile_path(project_root)?;
+        let traced = self.traces.lock().unwrap().remove(project_root);
+        match traced {
+            Some(trace) => {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                trace
+                    .profile
+                    .save(&path)
+                    .with_context(|| format!("Failed to save {}", path.display()))?;
+                Ok(trace.profile.access_order.len())
+            }
+            None if path.exists() => Ok(AccessProfile::load(&path)
+                .with_context(|| format!("Failed to load {}", path.display()))?
+                .access_order
+                .len()),
+            None => anyhow::bail!(
+                "No access profile for {}; run `vrift pack trace -- <cmd>` first",
+                project_root.display()
+            ),
+        }
+    }
+
+    /// The blobs a build of `project_root` would go by if the trace ended
+    /// now, as [`save`](Self::save) picks them, without ending it
+    pub fn profiled(&self, project_root: &Path) -> Result<Vec<Blake3Hash>> {
+        if let Some(trace) = self.traces.lock().unwrap().get(project_root) {
+            return Ok(trace.profile.access_order.clone());
+        }
+        Ok(saved_profile(project_root)?.access_order)
+    }
+}
+
+/// Size of the blob `hash` if a build would pack it
+fn packable_size(cas: &CasStore, hash: &Blake3Hash) -> Option<u64> {
+    cas.blob_path_for_hash(hash)
+        .and_then(|path| fs::metadata(path).ok())
+        .map(|meta| meta.len())
+        .filter(|&size| size <= MAX_PACKED_BLOB)
+}
+
+/// The blobs of `profiled` a build would copy into the packfile, in pack
+/// order, with their sizes
+pub fn plan(profiled: &[Blake3Hash], cas: &CasStore) -> Vec<(Blake3Hash, u64)> {
+    profiled
+        .iter()
+        .filter_map(|hash| Some((*hash, packable_size(cas, hash)?)))
+        .collect()
+}
+
+/// The access profile saved for `project_root` by its last build
+pub fn saved_profile(project_root: &Path) -> Result<AccessProfile> {
+    let path = profile_path(project_root)?;
+    if !path.exists() {
+        anyhow::bail!(
+            "No access profile for {}; run `vrift pack trace -- <cmd>` first",
+            project_root.display()
+        );
+    }
+    AccessProfile::load(&path).with_context(|| format!("Failed to load {}", path.display()))
+}
+
+/// Build the packfile of `project_root` from its saved profile. Publishes
+/// profiled blobs as `processed`, packed ones as `affected` and their size
+/// as `bytes`. A cancelled build leaves the previous packfile in place.
+pub fn build(project_root: &Path, cas: &CasStore, progress: &Progress) -> Result<()> {
+    let profile = saved_profile(project_root)?;
+    let pack_path = pack_path(project_root)?;
+    let tmp = pack_path.with_extension("pack.tmp");
+
+    let mut writer = PackWriter::new(&tmp);
+    for hash in &profile.access_order {
+        if progress.is_cancelled() {
+            return Ok(());
+        }
+        progress.processed.fetch_add(1, Ordering::Relaxed);
+        if packable_size(cas, hash).is_none() {
+            continue;
+        }
+        let data = match cas.get(hash) {
+            Ok(data) => data,
+            // Swept since the trace
+            Err(CasError::NotFound { .. }) => continue,
+            Err(e) => return Err(e.into()),
+        };
+        writer.add(*hash, &data);
+        progress.affected.fetch_add(1, Ordering::Relaxed);
+        progress
+            .bytes
+            .fetch_add(data.len() as u64, Ordering::Relaxed);
+    }
+
+    if let Some(parent) = pack_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    writer
+        .finish()
+        .with_context(|| format!("Failed to write {}", tmp.display()))?;
+    fs::rename(&tmp, &pack_path)?;
+    tracing::info!(
+        "vriftd: Packed {} blobs for {} into {}",
+        progress.affected.load(Ordering::Relaxed),
+        project_root.display(),
+        pack_path.display()
+    );
+    Ok(())
+}
+
+/// Read the blobs of `project_root`'s saved profile into the page cache: the
+/// packfile as a whole, then the profiled blobs it does not hold from the
+/// CAS. Publishes profiled blobs as `processed`, those read as `affected`
+/// and the bytes read as `bytes`.
+pub fn prefetch(project_root: &Path, cas: &CasStore, progress: &Progress) -> Result<()> {
+    let profile = saved_profile(project_root)?;
+    let pack_path = pack_path(project_root)?;
+    let pack = match PackReader::open(&pack_path) {
+        Ok(pack) => {
+            let bytes = io::copy(&mut fs::File::open(&pack_path)?, &mut io::sink())?;
+            progress.bytes.fetch_add(bytes, Ordering::Relaxed);
+            Some(pack)
+        }
+        Err(_) => None,
+    };
+
+    for hash in &profile.access_order {
+        if progress.is_cancelled() {
+            return Ok(());
+        }
+        progress.processed.fetch_add(1, Ordering::Relaxed);
+        if !pack.as_ref().is_some_and(|pack| pack.contains(hash)) {
+            let mut blob = match cas.get_stream(hash) {
+                Ok(blob) => blob,
+                // Swept since the trace
+                Err(CasError::NotFound { .. }) => continue,
+                Err(e) => return Err(e.into()),
+            };
+            let bytes = io::copy(&mut blob, &mut io::sink())?;
+            progress.bytes.fetch_add(bytes, Ordering::Relaxed);
+        }
+        progress.affected.fetch_add(1, Ordering::Relaxed);
+    }
+    Ok(())
+}
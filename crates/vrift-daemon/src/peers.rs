@@ -0,0 +1,315 @@
+//! # LAN peers (`--features peers`)
+//!
+//! Daemons on one network share their CAS so that a toolchain downloaded by
+//! one machine in the office is not downloaded again by the next. Each
+//! daemon with `[peers] enabled` advertises its HTTP export over mDNS
+//! (`_vrift-cas._tcp`) and serves a bloom filter of its blobs there
+//! (`GET /bloom`). The others keep an up-to-date copy of every peer's filter
+//! and, when a blob is missing on open, ask the peers whose filter has it
+//! (`GET /blobs/<hash>`) before falling back to the upstream.
+//!
+//! Peers authenticate with the bearer token of `[http] token_file`, so a
+//! group of peers shares one token. A false positive of a filter costs one
+//! `404`; whatever a peer sends is checked against the hash before it
+//! enters the CAS, as for the upstream.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use vrift_cas::{Blake3Hash, BloomFilter, CasStore};
+
+const SERVICE_TYPE: &str = "_vrift-cas._tcp.local.";
+
+/// Give up on a peer request after this long; peers are on the LAN
+const PEER_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Bloom filter of `hashes` as advertised at `/bloom`, keyed by hex hash and
+/// sized for a false positive rate of `fpr`. Peers take its size from the
+/// length of the response.
+pub fn build_bloom<'a>(hashes: impl ExactSizeIterator<Item = &'a Blake3Hash>, fpr: f64) -> Vec<u8> {
+    let mut bloom = BloomFilter::with_capacity(hashes.len(), fpr);
+    for hash in hashes {
+        bloom.add(&CasStore::hash_to_hex(hash));
+    }
+    bloom.bits
+}
+
+struct Peer {
+    /// `http://<addr>` of its HTTP export
+    base: String,
+    bloom: Option<Arc<BloomFilter>>,
+    /// `ETag` of `bloom`, to revalidate it
+    etag: Option<String>,
+}
+
+pub struct Peers {
+    /// Kept for as long as we advertise and browse
+    _mdns: ServiceDaemon,
+    /// Our own instance, which browsing finds too
+    fullname: String,
+    client: reqwest::Client,
+    token: Option<String>,
+    /// Peers by mDNS instance
+    peers: Mutex<HashMap<String, Peer>>,
+}
+
+impl Peers {
+    /// Advertise our HTTP export and start looking for peers, if `cfg`
+    /// enables it
+    pub fn start(
+        cfg: &vrift_config::PeersConfig,
+        http: &vrift_config::HttpConfig,
+    ) -> Result<Option<Arc<Self>>> {
+        if !cfg.enabled {
+            return Ok(None);
+        }
+        let Some(ref listen) = http.listen else {
+            bail!("[peers] needs [http] listen: peers fetch blobs from each other's HTTP export");
+        };
+        let addr: SocketAddr = listen
+            .parse()
+            .with_context(|| format!("Invalid [http] listen address '{}'", listen))?;
+        if addr.ip().is_loopback() {
+            bail!(
+                "[peers] needs [http] listen on an address peers can reach, not {}",
+                addr
+            );
+        }
+        let token = match http.token_file {
+            Some(ref path) => Some(crate::auth::read_token("http", path)?),
+            None => None,
+        };
+
+        let mdns = ServiceDaemon::new().context("Failed to start mDNS")?;
+        let host = hostname();
+        let instance = format!("{}-{}", host, std::process::id());
+        let host_name = format!("{}.local.", host);
+        let properties = [("v", "1")];
+        let info = if addr.ip().is_unspecified() {
+            ServiceInfo::new(
+                SERVICE_TYPE,
+                &instance,
+                &host_name,
+                (),
+                addr.port(),
+                &properties[..],
+            )?
+            .enable_addr_auto()
+        } else {
+            ServiceInfo::new(
+                SERVICE_TYPE,
+                &instance,
+                &host_name,
+                addr.ip(),
+                addr.port(),
+                &properties[..],
+            )?
+        };
+        let fullname = info.get_fullname().to_string();
+        mdns.register(info)
+            .context("Failed to advertise over mDNS")?;
+        let events = mdns.browse(SERVICE_TYPE)?;
+
+        let client = reqwest::Client::builder()
+            .timeout(PEER_TIMEOUT)
+            .build()
+            .context("Failed to set up the peer HTTP client")?;
+        let peers = Arc::new(Self {
+            _mdns: mdns,
+            fullname,
+            client,
+            token,
+            peers: Mutex::new(HashMap::new()),
+        });
+        tracing::info!("vriftd: Sharing blobs with LAN peers as {}", peers.fullname);
+
+        let browsing = peers.clone();
+        tokio::spawn(async move {
+            while let Ok(event) = events.recv_async().await {
+                browsing.on_event(event);
+            }
+        });
+        let refreshing = peers.clone();
+        let period = Duration::from_secs(cfg.refresh_secs.max(1));
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                refreshing.refresh().await;
+            }
+        });
+        Ok(Some(peers))
+    }
+
+    fn on_event(self: &Arc<Self>, event: ServiceEvent) {
+        match event {
+            ServiceEvent::ServiceResolved(info) if info.get_fullname() != self.fullname => {
+                // Link-local IPv6 needs a scope id to be dialed, which mDNS
+                // does not give; such a peer waits for an address that works
+                let addresses = info.get_addresses();
+                let Some(ip) = addresses
+                    .iter()
+                    .find(|ip| ip.is_ipv4())
+                    .or_else(|| addresses.iter().find(|ip| !is_link_local(ip)))
+                else {
+                    return;
+                };
+                let base = format!("http://{}", SocketAddr::new(*ip, info.get_port()));
+                let mut peers = self.peers.lock().unwrap();
+                let peer = peers
+                    .entry(info.get_fullname().to_string())
+                    .or_insert_with(|| {
+                        tracing::info!("vriftd: Found peer {} at {}", info.get_fullname(), base);
+                        Peer {
+                            base: base.clone(),
+                            bloom: None,
+                            etag: None,
+                        }
+                    });
+                if peer.base != base {
+                    *peer = Peer {
+                        base,
+                        bloom: None,
+                        etag: None,
+                    };
+                }
+                if peer.bloom.is_none() {
+                    // Don't wait for the next round to make use of it
+                    let peers = self.clone();
+                    tokio::spawn(async move { peers.refresh().await });
+                }
+            }
+            ServiceEvent::ServiceRemoved(_, fullname) => {
+                if self.peers.lock().unwrap().remove(&fullname).is_some() {
+                    tracing::info!("vriftd: Peer {} left", fullname);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Bring every peer's filter up to date
+    async fn refresh(&self) {
+        let known: Vec<(String, String, Option<String>)> = self
+            .peers
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, peer)| (name.clone(), peer.base.clone(), peer.etag.clone()))
+            .collect();
+        for (name, base, etag) in known {
+            let fetched = self.fetch_bloom(&base, etag.as_deref()).await;
+            let mut peers = self.peers.lock().unwrap();
+            let Some(peer) = peers.get_mut(&name).filter(|peer| peer.base == base) else {
+                continue;
+            };
+            match fetched {
+                Ok(Some((bloom, etag))) => {
+                    peer.bloom = Some(Arc::new(bloom));
+                    peer.etag = etag;
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    // Not asked for blobs until it answers again
+                    tracing::debug!("vriftd: Peer {} unreachable: {:#}", name, e);
+                    peer.bloom = None;
+                    peer.etag = None;
+                }
+            }
+        }
+    }
+
+    /// The filter at `base`, `None` if it still has `etag`
+    async fn fetch_bloom(
+        &self,
+        base: &str,
+        etag: Option<&str>,
+    ) -> Result<Option<(BloomFilter, Option<String>)>> {
+        let mut request = self.client.get(format!("{}/bloom", base));
+        if let Some(ref token) = self.token {
+            request = request.bearer_auth(token);
+        }
+        if let Some(etag) = etag {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+        let response = request.send().await?;
+        if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+            return Ok(None);
+        }
+        let response = response.error_for_status()?;
+        let etag = response
+            .headers()
+            .get(reqwest::header::ETAG)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let bits = response.bytes().await?;
+        if bits.is_empty() {
+            bail!("empty bloom filter");
+        }
+        Ok(Some((
+            BloomFilter {
+                bits: bits.to_vec(),
+            },
+            etag,
+        )))
+    }
+
+    /// Download blob `hash` from a peer whose filter has it; whether one
+    /// did. Peer failures are logged, never returned: the upstream is next.
+    pub async fn fetch(&self, cas: &CasStore, hash: &Blake3Hash, size: u64) -> bool {
+        let hex = CasStore::hash_to_hex(hash);
+        let candidates: Vec<String> = self
+            .peers
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|peer| peer.bloom.as_ref().is_some_and(|b| b.contains(&hex)))
+            .map(|peer| peer.base.clone())
+            .collect();
+        for base in candidates {
+            let url = format!("{}/blobs/{}", base, hex);
+            match crate::upstream::download(
+                &self.client,
+                &url,
+                self.token.as_deref(),
+                cas,
+                hash,
+                size,
+            )
+            .await
+            {
+                Ok(true) => return true,
+                Ok(false) => {}
+                Err(e) => tracing::debug!("vriftd: Peer fetch failed: {:#}", e),
+            }
+        }
+        false
+    }
+}
+
+fn is_link_local(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => v4.is_link_local(),
+        IpAddr::V6(v6) => v6.segments()[0] & 0xffc0 == 0xfe80,
+    }
+}
+
+/// This machine's name, for the mDNS instance and host records
+fn hostname() -> String {
+    let mut buf = [0u8; 256];
+    // SAFETY: the buffer outlives the call and its length is passed along
+    let ok = unsafe { libc::gethostname(buf.as_mut_ptr() as *mut libc::c_char, buf.len()) } == 0;
+    let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    let name = String::from_utf8_lossy(&buf[..len]);
+    // A `.local` hostname gets its suffix back from the host record
+    let name = name.split('.').next().unwrap_or_default();
+    if ok && !name.is_empty() {
+        name.to_string()
+    } else {
+        "vriftd".to_string()
+    }
+}
@@ -0,0 +1,426 @@
+//! Process-tree session tracking
+//!
+//! Every process that loads the inception layer reports `(pid, ppid)` on
+//! start-up, and spawn interposers report children on their behalf. A process
+//! whose parent is already tracked joins the parent's session; anything else
+//! starts a new session for the workspace it registered against.
+//!
+//! Sessions scope the daemon's per-process state: locks left behind by an
+//! exited process are released, CoW staging files are removed once the whole
+//! tree has exited, and write-backs are attributed to the process that
+//! committed them.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use vrift_ipc::{SessionInfo, SessionProcess};
+
+struct TrackedProcess {
+    ppid: u32,
+    session_id: u64,
+    command: String,
+    started_at: u64,
+    writebacks: u64,
+}
+
+struct Session {
+    project_root: PathBuf,
+    root_pid: u32,
+    started_at: u64,
+    writebacks: u64,
+    /// Every pid that ever belonged to the session (staging files are named by pid)
+    members: Vec<u32>,
+    live: usize,
+}
+
+/// A process found dead by [`SessionTracker::reap`]
+pub struct ReapedProcess {
+    pub pid: u32,
+    pub session_id: u64,
+}
+
+/// A session whose last live process exited
+pub struct EndedSession {
+    pub session_id: u64,
+    pub project_root: PathBuf,
+    pub members: Vec<u32>,
+}
+
+#[derive(Default)]
+struct Inner {
+    processes: HashMap<u32, TrackedProcess>,
+    sessions: HashMap<u64, Session>,
+    next_session_id: u64,
+}
+
+#[derive(Default)]
+pub struct SessionTracker {
+    inner: Mutex<Inner>,
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+impl SessionTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Track `pid` and return its session id.
+    ///
+    /// A pid that is already tracked (e.g. re-reporting after exec) keeps its
+    /// session and only has its command refreshed.
+    pub fn register(&self, pid: u32, ppid: u32, command: String, project_root: &Path) -> u64 {
+        let command = if command.is_empty() {
+            process_command(pid)
+        } else {
+            command
+        };
+        let mut inner = self.inner.lock().unwrap();
+
+        if let Some(proc) = inner.processes.get_mut(&pid) {
+            if !command.is_empty() {
+                proc.command = command;
+            }
+            return proc.session_id;
+        }
+
+        let parent_session = inner.processes.get(&ppid).map(|p| p.session_id);
+        let started_at = now_secs();
+        let session_id = match parent_session {
+            Some(id) => id,
+            None => {
+                inner.next_session_id += 1;
+                let id = inner.next_session_id;
+                inner.sessions.insert(
+                    id,
+                    Session {
+                        project_root: project_root.to_path_buf(),
+                        root_pid: pid,
+                        started_at,
+                        writebacks: 0,
+                        members: Vec::new(),
+                        live: 0,
+                    },
+                );
+                id
+            }
+        };
+
+        if let Some(session) = inner.sessions.get_mut(&session_id) {
+            session.members.push(pid);
+            session.live += 1;
+        }
+        inner.processes.insert(
+            pid,
+            TrackedProcess {
+                ppid,
+                session_id,
+                command,
+                started_at,
+                writebacks: 0,
+            },
+        );
+        session_id
+    }
+
+    /// Attribute a write-back to `pid`. Returns false if the pid is untracked.
+    pub fn record_writeback(&self, pid: u32) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        let session_id = match inner.processes.get_mut(&pid) {
+            Some(proc) => {
+                proc.writebacks += 1;
+                proc.session_id
+            }
+            None => return false,
+        };
+        if let Some(session) = inner.sessions.get_mut(&session_id) {
+            session.writebacks += 1;
+        }
+        true
+    }
+
+    /// Drop processes that no longer exist, according to `is_alive`.
+    ///
+    /// Returns the reaped processes and any sessions that ended as a result.
+    pub fn reap(&self, is_alive: impl Fn(u32) -> bool) -> (Vec<ReapedProcess>, Vec<EndedSession>) {
+        let mut inner = self.inner.lock().unwrap();
+
+        let dead: Vec<u32> = inner
+            .processes
+            .keys()
+            .copied()
+            .filter(|&pid| !is_alive(pid))
+            .collect();
+
+        let mut reaped = Vec::with_capacity(dead.len());
+        let mut ended = Vec::new();
+        for pid in dead {
+            let Some(proc) = inner.processes.remove(&pid) else {
+                continue;
+            };
+            reaped.push(ReapedProcess {
+                pid,
+                session_id: proc.session_id,
+            });
+
+            let finished = match inner.sessions.get_mut(&proc.session_id) {
+                Some(session) => {
+                    session.live = session.live.saturating_sub(1);
+                    session.live == 0
+                }
+                None => false,
+            };
+            if finished {
+                if let Some(session) = inner.sessions.remove(&proc.session_id) {
+                    ended.push(EndedSession {
+                        session_id: proc.session_id,
+                        project_root: session.project_root,
+                        members: session.members,
+                    });
+                }
+            }
+        }
+
+        (reaped, ended)
+    }
+
+    /// Project roots that currently have at least one live process
+    pub fn active_roots(&self) -> Vec<PathBuf> {
+        let inner = self.inner.lock().unwrap();
+        let mut roots: Vec<PathBuf> = inner
+            .sessions
+            .values()
+            .filter(|s| s.live > 0)
+            .map(|s| s.project_root.clone())
+            .collect();
+        roots.sort();
+        roots.dedup();
+        roots
+    }
+
+    /// Snapshot of live sessions, ordered by session id.
+    /// `locks_held` reports the number of virtual locks owned by a pid.
+    pub fn list(&self, locks_held: impl Fn(u32) -> u32) -> Vec<SessionInfo> {
+        let inner = self.inner.lock().unwrap();
+
+        let mut sessions: Vec<SessionInfo> = inner
+            .sessions
+            .iter()
+            .map(|(&id, s)| SessionInfo {
+                session_id: id,
+                project_root: s.project_root.to_string_lossy().to_string(),
+                root_pid: s.root_pid,
+                started_at: s.started_at,
+                writebacks: s.writebacks,
+                processes: Vec::new(),
+            })
+            .collect();
+        sessions.sort_by_key(|s| s.session_id);
+
+        for (&pid, proc) in &inner.processes {
+            if let Ok(idx) = sessions.binary_search_by_key(&proc.session_id, |s| s.session_id) {
+                sessions[idx].processes.push(SessionProcess {
+                    pid,
+                    ppid: proc.ppid,
+                    command: proc.command.clone(),
+                    started_at: proc.started_at,
+                    writebacks: proc.writebacks,
+                    locks_held: locks_held(pid),
+                });
+            }
+        }
+        for session in &mut sessions {
+            session.processes.sort_by_key(|p| p.pid);
+        }
+
+        sessions
+    }
+}
+
+/// Best-effort command line of a running process
+#[cfg(target_os = "linux")]
+fn process_command(pid: u32) -> String {
+    std::fs::read(format!("/proc/{}/cmdline", pid))
+        .map(|raw| {
+            raw.split(|&b| b == 0)
+                .filter(|arg| !arg.is_empty())
+                .map(|arg| String::from_utf8_lossy(arg).into_owned())
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .unwrap_or_default()
+}
+
+/// Best-effort executable path of a running process
+#[cfg(target_os = "macos")]
+fn process_command(pid: u32) -> String {
+    let mut buf = vec![0u8; libc::PROC_PIDPATHINFO_MAXSIZE as usize];
+    let len = unsafe {
+        libc::proc_pidpath(
+            pid as libc::c_int,
+            buf.as_mut_ptr() as *mut libc::c_void,
+            buf.len() as u32,
+        )
+    };
+    if len <= 0 {
+        return String::new();
+    }
+    buf.truncate(len as usize);
+    String::from_utf8_lossy(&buf).into_owned()
+}
+
+/// Check whether a pid still exists (EPERM means it exists but isn't ours)
+pub fn pid_alive(pid: u32) -> bool {
+    if pid == 0 {
+        return false;
+    }
+    let ret = unsafe { libc::kill(pid as libc::pid_t, 0) };
+    ret == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// Remove CoW staging files left behind by the given pids.
+///
+/// The inception layer names staging files `vrift_cow_<pid>_...`, so cleanup
+/// can be limited to the processes of one session without touching files
+/// belonging to other sessions of the same workspace.
+pub fn cleanup_staging(project_root: &Path, pids: &[u32]) -> usize {
+    let staging = project_root.join(".vrift").join("staging");
+    let Ok(entries) = std::fs::read_dir(&staging) else {
+        return 0;
+    };
+
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let Some(owner) = name
+            .to_str()
+            .and_then(|n| n.strip_prefix("vrift_cow_"))
+            .and_then(|rest| rest.split('_').next())
+            .and_then(|pid| pid.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        if pids.contains(&owner) && std::fs::remove_file(entry.path()).is_ok() {
+            removed += 1;
+        }
+    }
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_children_join_parent_session() {
+        let tracker = SessionTracker::new();
+        let root = Path::new("/ws");
+        let a = tracker.register(100, 1, "make".into(), root);
+        let b = tracker.register(101, 100, "cc".into(), root);
+        let c = tracker.register(200, 1, "cargo".into(), root);
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        // Re-registering keeps the session and refreshes the command
+        assert_eq!(tracker.register(101, 100, "ld".into(), root), a);
+
+        let sessions = tracker.list(|_| 0);
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].processes.len(), 2);
+        assert_eq!(sessions[0].processes[1].command, "ld");
+    }
+
+    #[test]
+    fn test_reap_ends_session_after_last_process() {
+        let tracker = SessionTracker::new();
+        let root = Path::new("/ws");
+        let id = tracker.register(100, 1, "make".into(), root);
+        tracker.register(101, 100, "cc".into(), root);
+
+        let (reaped, ended) = tracker.reap(|pid| pid != 101);
+        assert_eq!(reaped.len(), 1);
+        assert_eq!(reaped[0].pid, 101);
+        assert_eq!(reaped[0].session_id, id);
+        assert!(ended.is_empty());
+        assert_eq!(tracker.active_roots(), vec![PathBuf::from("/ws")]);
+
+        let (reaped, ended) = tracker.reap(|_| false);
+        assert_eq!(reaped.len(), 1);
+        assert_eq!(ended.len(), 1);
+        assert_eq!(ended[0].session_id, id);
+        assert_eq!(ended[0].project_root, PathBuf::from("/ws"));
+        let mut members = ended[0].members.clone();
+        members.sort();
+        assert_eq!(members, vec![100, 101]);
+        assert!(tracker.active_roots().is_empty());
+        assert!(tracker.list(|_| 0).is_empty());
+    }
+
+    #[test]
+    fn test_reap_keeps_live_processes() {
+        let tracker = SessionTracker::new();
+        tracker.register(100, 1, "make".into(), Path::new("/ws"));
+        let (reaped, ended) = tracker.reap(|_| true);
+        assert!(reaped.is_empty());
+        assert!(ended.is_empty());
+        assert_eq!(tracker.list(|_| 0).len(), 1);
+    }
+
+    #[test]
+    fn test_record_writeback() {
+        let tracker = SessionTracker::new();
+        tracker.register(100, 1, "make".into(), Path::new("/ws"));
+        tracker.register(101, 100, "cc".into(), Path::new("/ws"));
+        assert!(tracker.record_writeback(101));
+        assert!(tracker.record_writeback(101));
+        assert!(!tracker.record_writeback(999));
+
+        let sessions = tracker.list(|pid| pid);
+        assert_eq!(sessions[0].writebacks, 2);
+        assert_eq!(sessions[0].processes[1].writebacks, 2);
+        assert_eq!(sessions[0].processes[1].locks_held, 101);
+    }
+
+    #[test]
+    fn test_pid_alive() {
+        assert!(pid_alive(std::process::id()));
+        assert!(!pid_alive(0));
+    }
+
+    #[test]
+    fn test_cleanup_staging_only_removes_session_pids() {
+        let dir = tempfile::tempdir().unwrap();
+        let staging = dir.path().join(".vrift").join("staging");
+        std::fs::create_dir_all(&staging).unwrap();
+        for name in [
+            "vrift_cow_100_a",
+            "vrift_cow_100_b",
+            "vrift_cow_101_c",
+            "vrift_cow_200_d",
+            "vrift_cow_x_e",
+            "other_100",
+        ] {
+            std::fs::write(staging.join(name), b"x").unwrap();
+        }
+
+        assert_eq!(cleanup_staging(dir.path(), &[100, 101]), 3);
+
+        let mut left: Vec<String> = std::fs::read_dir(&staging)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        left.sort();
+        assert_eq!(left, vec!["other_100", "vrift_cow_200_d", "vrift_cow_x_e"]);
+    }
+
+    #[test]
+    fn test_cleanup_staging_without_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(cleanup_staging(dir.path(), &[100]), 0);
+    }
+}
@@ -0,0 +1,130 @@
+//! Warm-start snapshot
+//!
+//! Some daemon state is slow to rebuild: the CAS index needs a walk and a
+//! stat per blob, and which workspaces matter is only learnt from traffic.
+//! Both are written to `~/.vrift/daemon-state.bin` on shutdown and loaded at
+//! start-up, so the first build after a restart gets an index right away and
+//! its workspace warmed first.
+//!
+//! The snapshot is a hint. The start-up CAS scan still runs and replaces the
+//! index when it finishes, picking up blobs added or removed while the
+//! daemon was down. A snapshot for a different CAS root, or one that fails to
+//! decode, is ignored.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use rkyv::{Archive, Deserialize, Serialize};
+
+/// File prefix; bump the digits when the layout changes
+const MAGIC: &[u8; 8] = b"VRSNAP01";
+
+/// Workspaces remembered across restarts
+const MAX_RECENT_WORKSPACES: usize = 64;
+
+#[derive(Archive, Serialize, Deserialize, Default)]
+pub struct Snapshot {
+    /// CAS root the index describes
+    pub cas_root: String,
+    /// Blob hash and size
+    pub cas_index: Vec<([u8; 32], u64)>,
+    /// Project roots by last use, most recent first
+    pub recent_workspaces: Vec<String>,
+}
+
+pub fn path() -> Option<PathBuf> {
+    let home = std::env::var("HOME").ok()?;
+    Some(PathBuf::from(home).join(".vrift/daemon-state.bin"))
+}
+
+impl Snapshot {
+    pub fn new(
+        cas_root: &Path,
+        cas_index: &HashMap<[u8; 32], u64>,
+        recent: Vec<PathBuf>,
+        previous_recent: &[String],
+    ) -> Self {
+        // This run's workspaces first, then older ones not seen this time
+        let mut recent_workspaces: Vec<String> = recent
+            .iter()
+            .map(|p| p.to_string_lossy().into_owned())
+            .collect();
+        for old in previous_recent {
+            if !recent_workspaces.contains(old) {
+                recent_workspaces.push(old.clone());
+            }
+        }
+        recent_workspaces.truncate(MAX_RECENT_WORKSPACES);
+
+        Self {
+            cas_root: cas_root.to_string_lossy().into_owned(),
+            cas_index: cas_index.iter().map(|(h, s)| (*h, *s)).collect(),
+            recent_workspaces,
+        }
+    }
+
+    /// Load the snapshot at `path` if it exists, decodes, and is for `cas_root`
+    pub fn load(path: &Path, cas_root: &Path) -> Option<Self> {
+        let data = match std::fs::read(path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+            Err(e) => {
+                tracing::warn!("vriftd: Cannot read snapshot {:?}: {}", path, e);
+                return None;
+            }
+        };
+        let Some(body) = data.strip_prefix(MAGIC.as_slice()) else {
+            tracing::warn!("vriftd: Ignoring snapshot {:?} from another version", path);
+            return None;
+        };
+        // rkyv needs an aligned buffer
+        let mut aligned = rkyv::util::AlignedVec::<16>::with_capacity(body.len());
+        aligned.extend_from_slice(body);
+        let snapshot = match rkyv::from_bytes::<Self, rkyv::rancor::Error>(&aligned) {
+            Ok(snapshot) => snapshot,
+            Err(e) => {
+                tracing::warn!("vriftd: Ignoring corrupt snapshot {:?}: {}", path, e);
+                return None;
+            }
+        };
+        if Path::new(&snapshot.cas_root) != cas_root {
+            tracing::info!(
+                "vriftd: Snapshot is for CAS root {:?}, not {:?}; ignoring",
+                snapshot.cas_root,
+                cas_root
+            );
+            return None;
+        }
+        Some(snapshot)
+    }
+
+    /// Write atomically (temp file + rename)
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let body = rkyv::to_bytes::<rkyv::rancor::Error>(self)?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let tmp = path.with_extension("bin.tmp");
+        let mut data = Vec::with_capacity(MAGIC.len() + body.len());
+        data.extend_from_slice(MAGIC);
+        data.extend_from_slice(&body);
+        std::fs::write(&tmp, data)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+}
+
+/// Sort `roots` so that workspaces in `recent` (most recent first) come first,
+/// keeping the original order among the rest
+pub fn order_by_recency(recent: &[String], roots: &mut [PathBuf]) {
+    let rank: HashMap<&str, usize> = recent
+        .iter()
+        .enumerate()
+        .map(|(i, root)| (root.as_str(), i))
+        .collect();
+    roots.sort_by_key(|root| {
+        root.to_str()
+            .and_then(|r| rank.get(r).copied())
+            .unwrap_or(usize::MAX)
+    });
+}
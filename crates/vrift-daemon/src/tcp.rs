@@ -0,0 +1,180 @@
+//! # IPC over TCP (`--features tcp`)
+//!
+//! The Unix socket rules out clients in another container or on another
+//! machine. `[tcp] listen` serves the same frames over TCP, so
+//! `DaemonClient::connect_to("tcp://host:port")` works like a local client.
+//!
+//! `[tcp] token_file` is required, loopback address or not: the daemon
+//! serves requests as its own user, which no other local user may borrow.
+//! The first frame on a connection must be `Authenticate` with that token;
+//! anything else, or a wrong token, gets `PermissionDenied` and the
+//! connection is closed. TCP peers have no credentials, so requests that need them
+//! (`Spawn`, flock) are refused as for the gRPC facade. There is no TLS;
+//! tunnel the port to serve beyond a trusted network.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use tokio::net::{TcpListener, TcpStream};
+use vrift_ipc::{VeloError, VeloRequest, VeloResponse};
+
+use crate::DaemonState;
+
+/// How long a new connection has to authenticate
+const AUTH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Start serving `[tcp] listen`; returns when the listener fails
+pub async fn serve(state: Arc<DaemonState>, cfg: &vrift_config::TcpConfig) -> Result<()> {
+    let Some(ref listen) = cfg.listen else {
+        return Ok(());
+    };
+    let addr: SocketAddr = listen
+        .parse()
+        .with_context(|| format!("Invalid [tcp] listen address '{}'", listen))?;
+    let token: Arc<str> = crate::auth::require_token("tcp", cfg.token_file.as_deref())?.into();
+    if !addr.ip().is_loopback() {
+        tracing::warn!(
+            "vriftd: IPC over TCP on {} without TLS; the token travels in clear text",
+            addr
+        );
+    }
+
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("Failed to bind {}", addr))?;
+    tracing::info!("vriftd: IPC listening on tcp://{}", addr);
+    accept(listener, state, token).await
+}
+
+/// Serve the connections `listener` accepts, each once it authenticated
+/// with `token`
+async fn accept(listener: TcpListener, state: Arc<DaemonState>, token: Arc<str>) -> Result<()> {
+    loop {
+        let (mut stream, peer) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                tracing::warn!("vriftd: TCP accept failed: {}", e);
+                continue;
+            }
+        };
+        let _ = stream.set_nodelay(true);
+        let state = state.clone();
+        let token = token.clone();
+        tokio::spawn(async move {
+            if !authenticate(&mut stream, &token).await {
+                tracing::warn!("vriftd: TCP client {} failed to authenticate", peer);
+                return;
+            }
+            crate::crash::REQUEST_CONTEXT
+                .scope(
+                    Default::default(),
+                    crate::handle_connection(stream, None, state),
+                )
+                .await;
+        });
+    }
+}
+
+/// Read the connection's first frame and answer it; whether it was an
+/// `Authenticate` with `token`
+async fn authenticate(stream: &mut TcpStream, token: &str) -> bool {
+    let (header, req) =
+        match vrift_ipc::frame_async::read_request_timeout(stream, AUTH_TIMEOUT).await {
+            Ok(frame) => frame,
+            Err(_) => return false,
+        };
+    let ok = matches!(
+        req,
+        VeloRequest::Authenticate { token: ref presented }
+            if crate::auth::token_matches(presented, token)
+    );
+    let response = if ok {
+        VeloResponse::AuthAck
+    } else {
+        VeloResponse::Error(VeloError::permission_denied("Invalid or missing token"))
+    };
+    let sent = vrift_ipc::frame_async::send_response(stream, &response, header.seq_id).await;
+    ok && sent.is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vrift_ipc::frame_async::{read_response, send_request};
+    use vrift_ipc::VeloErrorKind;
+
+    /// A daemon on a loopback port taking `token`; its address
+    async fn listen(token: &str) -> (SocketAddr, tempfile::TempDir) {
+        let temp = tempfile::tempdir().unwrap();
+        let state = DaemonState::for_test(temp.path());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(accept(listener, state, token.into()));
+        (addr, temp)
+    }
+
+    /// Send `request`; the answer, or `None` if the connection was closed
+    async fn ask(stream: &mut TcpStream, request: &VeloRequest) -> Option<VeloResponse> {
+        send_request(stream, request).await.ok()?;
+        read_response(stream)
+            .await
+            .ok()
+            .map(|(_, response)| response)
+    }
+
+    fn is_denied(response: Option<VeloResponse>) -> bool {
+        matches!(
+            response,
+            Some(VeloResponse::Error(VeloError {
+                kind: VeloErrorKind::PermissionDenied,
+                ..
+            }))
+        )
+    }
+
+    #[tokio::test]
+    async fn test_unauthenticated_connection_is_rejected() {
+        let (addr, _temp) = listen("s3cret").await;
+
+        // A request without authenticating first is refused, and the
+        // connection closed before anything else is served
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        assert!(is_denied(ask(&mut stream, &VeloRequest::Ping).await));
+        assert!(ask(&mut stream, &VeloRequest::Ping).await.is_none());
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let wrong = VeloRequest::Authenticate {
+            token: "guess".to_string(),
+        };
+        assert!(is_denied(ask(&mut stream, &wrong).await));
+        assert!(ask(&mut stream, &VeloRequest::Ping).await.is_none());
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let right = VeloRequest::Authenticate {
+            token: "s3cret".to_string(),
+        };
+        assert!(matches!(
+            ask(&mut stream, &right).await,
+            Some(VeloResponse::AuthAck)
+        ));
+        assert!(matches!(
+            ask(&mut stream, &VeloRequest::Ping).await,
+            Some(VeloResponse::PingAck { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_serve_requires_token_file() {
+        let temp = tempfile::tempdir().unwrap();
+        let cfg = vrift_config::TcpConfig {
+            listen: Some("127.0.0.1:0".to_string()),
+            token_file: None,
+        };
+        let err = serve(DaemonState::for_test(temp.path()), &cfg)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("without token_file"), "{:#}", err);
+    }
+}
@@ -0,0 +1,355 @@
+//! Tier re-evaluation
+//!
+//! Ingest picks a tier from path patterns, which guess wrong both ways: a
+//! vendored tree under `src/` is never written, while a generated file under
+//! `node_modules/` is rewritten by every build. vriftd counts the sessions
+//! that end in each workspace and, once no session is live there, runs a
+//! `Retier` job that feeds the manifest to the workspace's
+//! [`TierLedger`] (`<project>/.vrift/tier-ledger.json`) and applies the
+//! changes it decides (`tiers.promote_after_sessions`).
+//!
+//! A solid workspace has the tier on disk too: a Tier-1 file is a symlink to
+//! its CAS blob, a Tier-2 file a hard link (or copy) of it. Each change
+//! rewrites that projection with a temp file renamed over the original, so
+//! readers always see a complete file. A file that no longer holds the
+//! content of its manifest entry was changed behind vrift's back and keeps
+//! its tier; phantom workspaces have nothing on disk and only change the
+//! manifest.
+
+use std::fs;
+use std::io;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::time::{Duration, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use vrift_cas::{CasStore, Progress};
+use vrift_manifest::lmdb::{AssetTier, LmdbManifest, ManifestEntry};
+use vrift_manifest::TierLedger;
+
+fn ledger_path(project_root: &Path) -> PathBuf {
+    project_root.join(".vrift").join("tier-ledger.json")
+}
+
+/// Manifest vDird serves for `project_root`
+fn manifest_path(project_root: &Path) -> PathBuf {
+    std::env::var("VRIFT_MANIFEST")
+        .ok()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            let project_id = vrift_config::path::compute_project_id(project_root);
+            vrift_config::path::get_manifest_db_path(&project_id)
+                .unwrap_or_else(|| project_root.join(".vrift").join("manifest.lmdb"))
+        })
+}
+
+fn load_ledger(path: &Path) -> Result<TierLedger> {
+    match fs::read(path) {
+        Ok(data) => serde_json::from_slice(&data)
+            .with_context(|| format!("Corrupt tier ledger {}", path.display())),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(TierLedger::default()),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    }
+}
+
+/// Write the ledger atomically (temp file + rename)
+fn save_ledger(path: &Path, ledger: &TierLedger) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, serde_json::to_vec(ledger)?)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// How a tier change was applied on disk
+#[derive(Debug, PartialEq, Eq)]
+enum Projection {
+    /// The file was rewritten for the new tier
+    Rewritten,
+    /// Nothing on disk (phantom workspace); only the manifest changes
+    Virtual,
+    /// The file does not match its entry; the tier is left alone
+    Mismatch,
+}
+
+/// Record `sessions` more ended sessions of `project_root` and apply the tier
+/// changes they lead to. Publishes entries evaluated as `processed` and tier
+/// changes as `affected`.
+pub fn retier(
+    project_root: &Path,
+    sessions: u64,
+    cas: &CasStore,
+    progress: &Progress,
+) -> Result<()> {
+    let config = vrift_config::Config::load_for_project(project_root)
+        .unwrap_or_else(|_| vrift_config::Config::default());
+    let ledger_path = ledger_path(project_root);
+    let mut ledger = load_ledger(&ledger_path)?;
+    ledger.sessions += sessions;
+
+    let manifest_path = manifest_path(project_root);
+    let manifest = LmdbManifest::open(&manifest_path)
+        .with_context(|| format!("Failed to open manifest {}", manifest_path.display()))?;
+    let entries = manifest.iter()?;
+    progress
+        .processed
+        .store(entries.len() as u64, Ordering::Relaxed);
+
+    let changes = ledger.reevaluate(
+        entries.iter().map(|(path, entry)| (path.as_str(), entry)),
+        config.tiers.promote_after_sessions,
+    );
+    let mut applied = 0;
+    for change in &changes {
+        let Some(entry) = manifest.get(&change.path)? else {
+            continue;
+        };
+        let file = project_root.join(vrift_cas::path_key::to_path(
+            change.path.trim_start_matches('/'),
+        ));
+        let projection = match reproject(&file, &entry, change.to, cas) {
+            Ok(projection) => projection,
+            Err(e) => {
+                tracing::warn!("vriftd: Failed to re-tier {}: {}", file.display(), e);
+                continue;
+            }
+        };
+        if projection == Projection::Mismatch {
+            tracing::debug!(
+                "vriftd: {} no longer matches its manifest entry, keeping its tier",
+                file.display()
+            );
+            continue;
+        }
+        manifest.set_tier(&change.path, change.to)?;
+        applied += 1;
+        progress.affected.fetch_add(1, Ordering::Relaxed);
+    }
+    manifest.commit()?;
+    save_ledger(&ledger_path, &ledger)?;
+
+    if applied > 0 {
+        tracing::info!(
+            "vriftd: Re-tiered {} of {} entries in {:?}",
+            applied,
+            entries.len(),
+            project_root
+        );
+    }
+    Ok(())
+}
+
+/// Rewrite `file` as the projection of `entry` for tier `to`
+fn reproject(
+    file: &Path,
+    entry: &ManifestEntry,
+    to: AssetTier,
+    cas: &CasStore,
+) -> Result<Projection> {
+    let meta = match fs::symlink_metadata(file) {
+        Ok(meta) => meta,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Projection::Virtual),
+        Err(e) => return Err(e.into()),
+    };
+    let Some(blob) = cas.blob_path_for_hash(&entry.vnode.content_hash) else {
+        return Ok(Projection::Mismatch);
+    };
+    let Ok(blob_meta) = fs::metadata(&blob) else {
+        return Ok(Projection::Mismatch);
+    };
+    let tmp = file.with_file_name(format!(
+        ".{}.vrift-retier",
+        file.file_name().unwrap_or_default().to_string_lossy()
+    ));
+    let _ = fs::remove_file(&tmp);
+
+    match to {
+        AssetTier::Tier1Immutable => {
+            if !meta.is_file() || !holds_blob(file, &meta, &blob_meta, entry)? {
+                return Ok(Projection::Mismatch);
+            }
+            std::os::unix::fs::symlink(&blob, &tmp)?;
+        }
+        AssetTier::Tier2Mutable => {
+            // The link may spell the CAS root differently; compare targets
+            let target = fs::metadata(file).ok();
+            let links_blob = target.is_some_and(|t| same_file(&t, &blob_meta));
+            if !meta.is_symlink() || !links_blob {
+                return Ok(Projection::Mismatch);
+            }
+            // Same fallback as a Tier-2 ingest: hard link, else a copy
+            if fs::hard_link(&blob, &tmp).is_err() {
+                fs::copy(&blob, &tmp)?;
+                let mtime = UNIX_EPOCH + Duration::from_nanos(entry.vnode.mtime.max(0) as u64);
+                fs::File::options()
+                    .write(true)
+                    .open(&tmp)?
+                    .set_modified(mtime)?;
+                fs::set_permissions(&tmp, fs::Permissions::from_mode(entry.vnode.mode & 0o7777))?;
+            }
+        }
+    }
+    if let Err(e) = fs::rename(&tmp, file) {
+        let _ = fs::remove_file(&tmp);
+        return Err(e.into());
+    }
+    Ok(Projection::Rewritten)
+}
+
+/// Whether the regular file `file` has the content of its manifest entry
+fn holds_blob(
+    file: &Path,
+    meta: &fs::Metadata,
+    blob_meta: &fs::Metadata,
+    entry: &ManifestEntry,
+) -> Result<bool> {
+    if same_file(meta, blob_meta) {
+        return Ok(true);
+    }
+    if meta.len() != entry.vnode.size {
+        return Ok(false);
+    }
+    let hash = CasStore::compute_hash_reader(fs::File::open(file)?)?;
+    Ok(hash == entry.vnode.content_hash)
+}
+
+fn same_file(a: &fs::Metadata, b: &fs::Metadata) -> bool {
+    a.dev() == b.dev() && a.ino() == b.ino()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vrift_manifest::VnodeEntry;
+
+    const CONTENT: &[u8] = b"fn main() {}\n";
+
+    /// A CAS holding `CONTENT` and a manifest entry for it
+    fn setup() -> (tempfile::TempDir, CasStore, ManifestEntry) {
+        let dir = tempfile::tempdir().unwrap();
+        let cas = CasStore::new(dir.path().join("cas")).unwrap();
+        let hash = cas.store(CONTENT).unwrap();
+        let entry = ManifestEntry {
+            vnode: VnodeEntry::new_file(hash, CONTENT.len() as u64, 1_000_000_000, 0o644),
+            tier: AssetTier::Tier2Mutable,
+            stale: false,
+            ingested_at: 0,
+        };
+        (dir, cas, entry)
+    }
+
+    #[test]
+    fn test_missing_file_is_virtual() {
+        let (dir, cas, entry) = setup();
+        let file = dir.path().join("absent.rs");
+        for to in [AssetTier::Tier1Immutable, AssetTier::Tier2Mutable] {
+            assert_eq!(
+                reproject(&file, &entry, to, &cas).unwrap(),
+                Projection::Virtual
+            );
+        }
+        assert!(!file.exists());
+    }
+
+    #[test]
+    fn test_promote_matching_file_to_symlink() {
+        let (dir, cas, entry) = setup();
+        let file = dir.path().join("main.rs");
+        fs::write(&file, CONTENT).unwrap();
+
+        assert_eq!(
+            reproject(&file, &entry, AssetTier::Tier1Immutable, &cas).unwrap(),
+            Projection::Rewritten
+        );
+        let blob = cas.blob_path_for_hash(&entry.vnode.content_hash).unwrap();
+        assert!(fs::symlink_metadata(&file).unwrap().is_symlink());
+        assert_eq!(fs::read_link(&file).unwrap(), blob);
+        assert_eq!(fs::read(&file).unwrap(), CONTENT);
+    }
+
+    #[test]
+    fn test_promote_changed_file_keeps_tier() {
+        let (dir, cas, entry) = setup();
+        let file = dir.path().join("main.rs");
+        let changed = b"fn main() { 1 }";
+        fs::write(&file, changed).unwrap();
+
+        assert_eq!(
+            reproject(&file, &entry, AssetTier::Tier1Immutable, &cas).unwrap(),
+            Projection::Mismatch
+        );
+        assert!(fs::symlink_metadata(&file).unwrap().is_file());
+        assert_eq!(fs::read(&file).unwrap(), changed);
+    }
+
+    #[test]
+    fn test_demote_symlink_to_regular_file() {
+        let (dir, cas, entry) = setup();
+        let blob = cas.blob_path_for_hash(&entry.vnode.content_hash).unwrap();
+        let file = dir.path().join("main.rs");
+        std::os::unix::fs::symlink(&blob, &file).unwrap();
+
+        assert_eq!(
+            reproject(&file, &entry, AssetTier::Tier2Mutable, &cas).unwrap(),
+            Projection::Rewritten
+        );
+        let meta = fs::symlink_metadata(&file).unwrap();
+        assert!(meta.is_file());
+        assert_eq!(fs::read(&file).unwrap(), CONTENT);
+        assert!(!dir.path().join(".main.rs.vrift-retier").exists());
+    }
+
+    #[test]
+    fn test_demote_requires_link_to_blob() {
+        let (dir, cas, entry) = setup();
+        let file = dir.path().join("main.rs");
+        fs::write(&file, CONTENT).unwrap();
+        assert_eq!(
+            reproject(&file, &entry, AssetTier::Tier2Mutable, &cas).unwrap(),
+            Projection::Mismatch
+        );
+
+        let other = dir.path().join("other.rs");
+        fs::write(&other, CONTENT).unwrap();
+        let link = dir.path().join("link.rs");
+        std::os::unix::fs::symlink(&other, &link).unwrap();
+        assert_eq!(
+            reproject(&link, &entry, AssetTier::Tier2Mutable, &cas).unwrap(),
+            Projection::Mismatch
+        );
+        assert!(fs::symlink_metadata(&link).unwrap().is_symlink());
+    }
+
+    #[test]
+    fn test_missing_blob_keeps_tier() {
+        let (dir, cas, mut entry) = setup();
+        entry.vnode.content_hash = CasStore::compute_hash(b"not in the CAS");
+        let file = dir.path().join("main.rs");
+        fs::write(&file, CONTENT).unwrap();
+        assert_eq!(
+            reproject(&file, &entry, AssetTier::Tier1Immutable, &cas).unwrap(),
+            Projection::Mismatch
+        );
+        assert!(fs::symlink_metadata(&file).unwrap().is_file());
+    }
+
+    #[test]
+    fn test_ledger_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = ledger_path(dir.path());
+        assert_eq!(load_ledger(&path).unwrap(), TierLedger::default());
+
+        let ledger = TierLedger {
+            sessions: 3,
+            ..Default::default()
+        };
+        save_ledger(&path, &ledger).unwrap();
+        assert_eq!(load_ledger(&path).unwrap(), ledger);
+
+        fs::write(&path, b"{not json").unwrap();
+        assert!(load_ledger(&path).is_err());
+    }
+}
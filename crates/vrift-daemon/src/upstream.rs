@@ -0,0 +1,307 @@
+//! # Fetch-on-miss (`--features upstream`)
+//!
+//! With `[upstream] url` set, a workspace can be served from a manifest
+//! whose blobs are not all in the local CAS: when the inception layer finds
+//! a blob missing on open it sends `CasFetch`, vriftd downloads the blob
+//! from the upstream into the CAS and the open goes ahead, as if the
+//! checkout had been there all along.
+//!
+//! The upstream is any HTTP(S) store addressing blobs by hex hash: another
+//! vriftd's HTTP export (a peer or a shared cache) or a bucket laid out the
+//! same way. A download is checked against the hash and size the manifest
+//! expects before it enters the CAS. At most `max_concurrent` downloads run
+//! at once, and concurrent opens of one blob share a download. A blob the
+//! upstream lacked is not asked for again for `negative_ttl_secs`; after a
+//! failed download (upstream unreachable, bad content) the next attempt
+//! waits a few seconds at most.
+//!
+//! With `[peers]` enabled (`--features peers`) the daemons on the LAN are
+//! asked first; the upstream is then optional.
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context, Result};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Semaphore;
+use vrift_cas::{Blake3Hash, CasStore, ContentHasher};
+
+/// Misses remembered before expired ones are dropped
+const MISS_PRUNE_THRESHOLD: usize = 4096;
+
+/// How long a failed download keeps the blob from being retried
+const ERROR_BACKOFF: Duration = Duration::from_secs(5);
+
+pub struct Upstream {
+    client: reqwest::Client,
+    /// Blob URL with a `{hash}` placeholder
+    url: Option<String>,
+    token: Option<String>,
+    #[cfg(feature = "peers")]
+    peers: Option<std::sync::Arc<crate::peers::Peers>>,
+    permits: Semaphore,
+    /// Downloads in flight; an open of the same blob waits for the first
+    inflight: Mutex<HashMap<Blake3Hash, Arc<tokio::sync::Mutex<()>>>>,
+    /// Until when each recently missed blob is not asked for
+    misses: Mutex<HashMap<Blake3Hash, Instant>>,
+    negative_ttl: Duration,
+}
+
+impl Upstream {
+    /// Where `cfg` has missing blobs fetched from, or `None` if nowhere
+    pub fn new(cfg: &vrift_config::Config) -> Result<Option<Self>> {
+        #[cfg(feature = "peers")]
+        let peers = crate::peers::Peers::start(&cfg.peers, &cfg.http)?;
+        #[cfg(feature = "peers")]
+        let has_peers = peers.is_some();
+        #[cfg(not(feature = "peers"))]
+        let has_peers = false;
+
+        let cfg = &cfg.upstream;
+        if cfg.url.is_none() && !has_peers {
+            return Ok(None);
+        }
+        if let Some(ref url) = cfg.url {
+            if !url.contains("{hash}") {
+                bail!("[upstream] url '{}' has no {{hash}} placeholder", url);
+            }
+        }
+        let token = match cfg.token_file {
+            Some(ref path) => Some(crate::auth::read_token("upstream", path)?),
+            None => None,
+        };
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(cfg.timeout_secs))
+            .build()
+            .context("Failed to set up the upstream HTTP client")?;
+        Ok(Some(Self {
+            client,
+            url: cfg.url.clone(),
+            token,
+            #[cfg(feature = "peers")]
+            peers,
+            permits: Semaphore::new(cfg.max_concurrent.max(1)),
+            inflight: Mutex::new(HashMap::new()),
+            misses: Mutex::new(HashMap::new()),
+            negative_ttl: Duration::from_secs(cfg.negative_ttl_secs),
+        }))
+    }
+
+    pub fn url(&self) -> Option<&str> {
+        self.url.as_deref()
+    }
+
+    /// Make sure blob `hash` of `size` bytes is in `cas`, downloading it if
+    /// needed. Returns whether it is there now.
+    pub async fn fetch(&self, cas: &CasStore, hash: &Blake3Hash, size: u64) -> Result<bool> {
+        let gate = self
+            .inflight
+            .lock()
+            .unwrap()
+            .entry(*hash)
+            .or_default()
+            .clone();
+        let result = {
+            let _downloading = gate.lock().await;
+            if cas.exists(hash) {
+                // Fetched by the open we waited for
+                Ok(true)
+            } else {
+                self.fetch_missing(cas, hash, size).await
+            }
+        };
+        let mut inflight = self.inflight.lock().unwrap();
+        // Ours and the map's: nobody else is waiting on this gate
+        if Arc::strong_count(&gate) == 2 {
+            inflight.remove(hash);
+        }
+        result
+    }
+
+    async fn fetch_missing(&self, cas: &CasStore, hash: &Blake3Hash, size: u64) -> Result<bool> {
+        let _permit = self.permits.acquire().await?;
+        #[cfg(feature = "peers")]
+        if let Some(ref peers) = self.peers {
+            if peers.fetch(cas, hash, size).await {
+                return Ok(true);
+            }
+        }
+        let Some(ref url) = self.url else {
+            return Ok(false);
+        };
+        if self.recently_missed(hash) {
+            return Ok(false);
+        }
+        let url = url.replace("{hash}", &CasStore::hash_to_hex(hash));
+        let result = download(&self.client, &url, self.token.as_deref(), cas, hash, size).await;
+        match result {
+            Ok(true) => {}
+            Ok(false) => self.remember_miss(hash, self.negative_ttl),
+            Err(_) => self.remember_miss(hash, self.negative_ttl.min(ERROR_BACKOFF)),
+        }
+        result
+    }
+
+    fn recently_missed(&self, hash: &Blake3Hash) -> bool {
+        self.misses
+            .lock()
+            .unwrap()
+            .get(hash)
+            .is_some_and(|until| Instant::now() < *until)
+    }
+
+    fn remember_miss(&self, hash: &Blake3Hash, ttl: Duration) {
+        let now = Instant::now();
+        let mut misses = self.misses.lock().unwrap();
+        if misses.len() >= MISS_PRUNE_THRESHOLD {
+            misses.retain(|_, until| now < *until);
+        }
+        misses.insert(*hash, now + ttl);
+    }
+}
+
+/// Download blob `hash` of `size` bytes from `url` into `cas`; `Ok(false)`
+/// if the server has none
+pub(crate) async fn download(
+    client: &reqwest::Client,
+    url: &str,
+    token: Option<&str>,
+    cas: &CasStore,
+    hash: &Blake3Hash,
+    size: u64,
+) -> Result<bool> {
+    let hex = CasStore::hash_to_hex(hash);
+    let mut request = client.get(url);
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+    let response = request.send().await?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        tracing::debug!("vriftd: {} has no blob {}", url, hex);
+        return Ok(false);
+    }
+    let response = response.error_for_status()?;
+
+    // Next to where the blob goes, so that storing it is a rename; the
+    // `.tmp` suffix lets GC clean up after a crash
+    let dest = cas.blob_path_with_metadata(hash, size, "");
+    let tmp = dest.with_file_name(format!("{}.fetch.{}.tmp", hex, std::process::id()));
+    if let Some(parent) = tmp.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    // Hashed as it arrives: content that is not `hash` never enters the CAS
+    let received = receive(response, &tmp, size, cas.algorithm().hasher()).await;
+    let stored = match received {
+        Ok(got) if got == *hash => {
+            let cas = cas.clone();
+            let tmp = tmp.clone();
+            tokio::task::spawn_blocking(move || cas.store_by_move(&tmp)).await?
+        }
+        Ok(got) => {
+            let _ = tokio::fs::remove_file(&tmp).await;
+            bail!(
+                "{} sent other content for {} (hash {})",
+                url,
+                hex,
+                CasStore::hash_to_hex(&got)
+            );
+        }
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&tmp).await;
+            return Err(e.context(format!("Failed to download {}", url)));
+        }
+    };
+    match stored {
+        Ok(_) => {
+            tracing::info!("vriftd: Fetched blob {} ({} bytes) from {}", hex, size, url);
+            Ok(true)
+        }
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&tmp).await;
+            Err(e.into())
+        }
+    }
+}
+
+/// Write the body of `response` to `path`, insisting on exactly `size`
+/// bytes; the hash of what was written
+async fn receive(
+    mut response: reqwest::Response,
+    path: &Path,
+    size: u64,
+    mut hasher: Box<dyn ContentHasher>,
+) -> Result<Blake3Hash> {
+    let mut file = tokio::fs::File::create(path).await?;
+    let mut written = 0u64;
+    while let Some(chunk) = response.chunk().await? {
+        written += chunk.len() as u64;
+        if written > size {
+            bail!("more than the expected {} bytes", size);
+        }
+        hasher.update(&chunk);
+        file.write_all(&chunk).await?;
+    }
+    if written != size {
+        bail!("{} of the expected {} bytes", written, size);
+    }
+    file.sync_all().await?;
+    Ok(hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
+
+    /// Answer one request with `body`; the URL to ask
+    async fn serve_once(body: &'static [u8]) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 4096];
+            let _ = stream.read(&mut request).await;
+            let head = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            );
+            stream.write_all(head.as_bytes()).await.unwrap();
+            stream.write_all(body).await.unwrap();
+        });
+        format!("http://{}/blob", addr)
+    }
+
+    /// Files left under `dir`, the CAS root
+    fn files(dir: &Path) -> Vec<std::path::PathBuf> {
+        walkdir::WalkDir::new(dir)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+            .map(|e| e.into_path())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_download_rejects_wrong_content() {
+        let temp = tempfile::tempdir().unwrap();
+        let cas = CasStore::new(temp.path()).unwrap();
+        let client = reqwest::Client::new();
+        let hash = cas.hash(b"expected");
+
+        // Same size, other bytes
+        let url = serve_once(b"tampered").await;
+        let err = download(&client, &url, None, &cas, &hash, 8)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("other content"), "{:#}", err);
+        assert!(!cas.exists(&hash));
+        assert!(!cas.exists(&cas.hash(b"tampered")));
+        assert!(files(temp.path()).is_empty(), "{:?}", files(temp.path()));
+
+        let url = serve_once(b"expected").await;
+        assert!(download(&client, &url, None, &cas, &hash, 8).await.unwrap());
+        assert_eq!(cas.get(&hash).unwrap(), b"expected");
+    }
+}
@@ -0,0 +1,283 @@
+//! Per-workspace readiness
+//!
+//! Each workspace is served by its own vDird, which opens the LMDB manifest
+//! and maps the VDir before it starts listening. Workspaces come up
+//! independently: the daemon accepts connections immediately, start-up
+//! warm-up (`daemon.warm_start`) loads registered workspaces in parallel, and
+//! a client registering a workspace only waits for that workspace.
+//!
+//! Spawns are serialized per project root so that warm-up and a concurrent
+//! registration for the same workspace never start two vDirds.
+//!
+//! Activation is lazy by default. Active workspaces form an LRU: once more
+//! than the configured maximum are loaded, or one has sat idle past the idle
+//! timeout, it is deactivated (its vDird shut down gracefully) unless a
+//! session still has live processes in it.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use vrift_ipc::{WorkspaceInfo, WorkspaceState};
+
+struct Readiness {
+    state: WorkspaceState,
+    since: Instant,
+    last_used: Instant,
+    took: Option<Duration>,
+    vdird_pid: u32,
+    error: Option<String>,
+}
+
+#[derive(Default)]
+pub struct WorkspaceTracker {
+    readiness: Mutex<HashMap<PathBuf, Readiness>>,
+    spawn_guards: Mutex<HashMap<PathBuf, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+impl WorkspaceTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Per-root async lock held for the duration of a vDird spawn
+    pub fn spawn_guard(&self, project_root: &Path) -> Arc<tokio::sync::Mutex<()>> {
+        self.spawn_guards
+            .lock()
+            .unwrap()
+            .entry(project_root.to_path_buf())
+            .or_default()
+            .clone()
+    }
+
+    pub fn mark_loading(&self, project_root: &Path) {
+        self.readiness.lock().unwrap().insert(
+            project_root.to_path_buf(),
+            Readiness {
+                state: WorkspaceState::Loading,
+                since: Instant::now(),
+                last_used: Instant::now(),
+                took: None,
+                vdird_pid: 0,
+                error: None,
+            },
+        );
+    }
+
+    pub fn mark_ready(&self, project_root: &Path, vdird_pid: u32) {
+        self.finish(project_root, WorkspaceState::Ready, vdird_pid, None);
+    }
+
+    pub fn mark_failed(&self, project_root: &Path, error: String) {
+        self.finish(project_root, WorkspaceState::Failed, 0, Some(error));
+    }
+
+    /// Mark a workspace as used now
+    pub fn touch(&self, project_root: &Path) {
+        if let Some(w) = self.readiness.lock().unwrap().get_mut(project_root) {
+            w.last_used = Instant::now();
+        }
+    }
+
+    /// Ready workspaces to deactivate, least recently used first.
+    ///
+    /// Picks every workspace idle for longer than `idle_timeout`, then as many
+    /// more as needed to get down to `max_active` (0 = unlimited). Workspaces
+    /// in `busy` are never picked.
+    pub fn eviction_candidates(
+        &self,
+        max_active: usize,
+        idle_timeout: Option<Duration>,
+        busy: &[PathBuf],
+    ) -> Vec<PathBuf> {
+        let readiness = self.readiness.lock().unwrap();
+        let active = readiness
+            .values()
+            .filter(|w| w.state != WorkspaceState::Failed)
+            .count();
+        let mut idle: Vec<(&PathBuf, Instant)> = readiness
+            .iter()
+            .filter(|(root, w)| w.state == WorkspaceState::Ready && !busy.contains(root))
+            .map(|(root, w)| (root, w.last_used))
+            .collect();
+        idle.sort_by_key(|&(_, last_used)| last_used);
+
+        let over_cap = if max_active == 0 {
+            0
+        } else {
+            active.saturating_sub(max_active)
+        };
+        idle.into_iter()
+            .enumerate()
+            .filter(|&(i, (_, last_used))| {
+                i < over_cap || idle_timeout.is_some_and(|t| last_used.elapsed() > t)
+            })
+            .map(|(_, (root, _))| root.clone())
+            .collect()
+    }
+
+    /// Loaded workspaces, most recently used first
+    pub fn recent(&self) -> Vec<PathBuf> {
+        let readiness = self.readiness.lock().unwrap();
+        let mut recent: Vec<(&PathBuf, Instant)> = readiness
+            .iter()
+            .filter(|(_, w)| w.state != WorkspaceState::Failed)
+            .map(|(root, w)| (root, w.last_used))
+            .collect();
+        recent.sort_by_key(|&(_, last_used)| std::cmp::Reverse(last_used));
+        recent.into_iter().map(|(root, _)| root.clone()).collect()
+    }
+
+    /// Forget a workspace whose vDird exited; it reloads on next registration
+    pub fn forget(&self, project_root: &Path) {
+        self.readiness.lock().unwrap().remove(project_root);
+    }
+
+    fn finish(
+        &self,
+        project_root: &Path,
+        state: WorkspaceState,
+        vdird_pid: u32,
+        error: Option<String>,
+    ) {
+        let mut readiness = self.readiness.lock().unwrap();
+        let entry = readiness
+            .entry(project_root.to_path_buf())
+            .or_insert_with(|| Readiness {
+                state,
+                since: Instant::now(),
+                last_used: Instant::now(),
+                took: None,
+                vdird_pid: 0,
+                error: None,
+            });
+        entry.state = state;
+        entry.took = Some(entry.since.elapsed());
+        entry.vdird_pid = vdird_pid;
+        entry.error = error;
+    }
+
+    /// Counts of (ready, loading, failed) workspaces
+    pub fn counts(&self) -> (usize, usize, usize) {
+        let readiness = self.readiness.lock().unwrap();
+        readiness
+            .values()
+            .fold((0, 0, 0), |(r, l, f), w| match w.state {
+                WorkspaceState::Ready => (r + 1, l, f),
+                WorkspaceState::Loading => (r, l + 1, f),
+                WorkspaceState::Failed => (r, l, f + 1),
+            })
+    }
+
+    /// Snapshot ordered by project root
+    pub fn list(&self) -> Vec<WorkspaceInfo> {
+        let readiness = self.readiness.lock().unwrap();
+        let mut workspaces: Vec<WorkspaceInfo> = readiness
+            .iter()
+            .map(|(root, w)| WorkspaceInfo {
+                project_root: root.to_string_lossy().to_string(),
+                state: w.state,
+                load_ms: w.took.unwrap_or_else(|| w.since.elapsed()).as_millis() as u64,
+                vdird_pid: w.vdird_pid,
+                error: w.error.clone(),
+            })
+            .collect();
+        workspaces.sort_by(|a, b| a.project_root.cmp(&b.project_root));
+        workspaces
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ready workspaces `roots`, used in that order (first = least recent)
+    fn tracker_with(roots: &[&str]) -> WorkspaceTracker {
+        let tracker = WorkspaceTracker::new();
+        for root in roots {
+            tracker.mark_loading(Path::new(root));
+            tracker.mark_ready(Path::new(root), 1);
+        }
+        for root in roots {
+            std::thread::sleep(Duration::from_millis(2));
+            tracker.touch(Path::new(root));
+        }
+        tracker
+    }
+
+    fn paths(roots: &[&str]) -> Vec<PathBuf> {
+        roots.iter().map(PathBuf::from).collect()
+    }
+
+    #[test]
+    fn test_eviction_under_capacity() {
+        let tracker = tracker_with(&["/a", "/b", "/c"]);
+        assert!(tracker.eviction_candidates(3, None, &[]).is_empty());
+        assert!(tracker.eviction_candidates(0, None, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_eviction_picks_least_recently_used() {
+        let tracker = tracker_with(&["/a", "/b", "/c", "/d"]);
+        assert_eq!(
+            tracker.eviction_candidates(2, None, &[]),
+            paths(&["/a", "/b"])
+        );
+
+        std::thread::sleep(Duration::from_millis(2));
+        tracker.touch(Path::new("/a"));
+        assert_eq!(
+            tracker.eviction_candidates(2, None, &[]),
+            paths(&["/b", "/c"])
+        );
+        assert_eq!(tracker.recent(), paths(&["/a", "/d", "/c", "/b"]));
+    }
+
+    #[test]
+    fn test_eviction_skips_busy_workspaces() {
+        let tracker = tracker_with(&["/a", "/b", "/c"]);
+        assert_eq!(
+            tracker.eviction_candidates(1, None, &paths(&["/a"])),
+            paths(&["/b", "/c"])
+        );
+        // Busy workspaces still count towards capacity
+        assert_eq!(
+            tracker.eviction_candidates(2, None, &paths(&["/a"])),
+            paths(&["/b"])
+        );
+    }
+
+    #[test]
+    fn test_eviction_counts_loading_but_never_picks_it() {
+        let tracker = tracker_with(&["/a", "/b"]);
+        tracker.mark_loading(Path::new("/c"));
+        tracker.mark_failed(Path::new("/d"), "boom".into());
+        assert_eq!(tracker.counts(), (2, 1, 1));
+        assert_eq!(
+            tracker.eviction_candidates(1, None, &[]),
+            paths(&["/a", "/b"])
+        );
+        assert_eq!(tracker.eviction_candidates(2, None, &[]), paths(&["/a"]));
+    }
+
+    #[test]
+    fn test_eviction_by_idle_timeout() {
+        let tracker = tracker_with(&["/a", "/b"]);
+        assert!(tracker
+            .eviction_candidates(0, Some(Duration::from_secs(3600)), &[])
+            .is_empty());
+        assert_eq!(
+            tracker.eviction_candidates(0, Some(Duration::ZERO), &[]),
+            paths(&["/a", "/b"])
+        );
+    }
+
+    #[test]
+    fn test_forget_frees_capacity() {
+        let tracker = tracker_with(&["/a", "/b", "/c"]);
+        tracker.forget(Path::new("/a"));
+        assert!(tracker.eviction_candidates(2, None, &[]).is_empty());
+        assert_eq!(tracker.list().len(), 2);
+    }
+}
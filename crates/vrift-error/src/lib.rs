@@ -0,0 +1,259 @@
+//! # vrift-error
+//!
+//! Shared error classification for Velo Rift.
+//!
+//! Every crate keeps its own `thiserror` enum (`CasError`, `ManifestError`,
+//! `PackError`, ...) and implements [`Classify`] for it, mapping each variant
+//! to an [`ErrorKind`]. Wrapper variants delegate to the error they wrap, so
+//! the kind always reflects the original cause — a missing blob three layers
+//! down still exits the CLI with "not found" instead of a generic failure.
+//!
+//! [`ResultExt::context`] attaches a message while keeping both the kind and
+//! the source chain, for call sites that would otherwise reach for
+//! `map_err(|e| format!(...))`.
+
+use std::error::Error as StdError;
+use std::fmt;
+use std::io;
+
+/// Broad category of a failure, independent of which crate produced it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// Path, blob, manifest entry or workspace does not exist
+    NotFound,
+    /// Denied by the OS or by an access check
+    PermissionDenied,
+    /// Malformed path, argument or configuration
+    InvalidInput,
+    /// Stored data failed validation (hash mismatch, bad magic, undecodable)
+    Corrupted,
+    /// A lock is held by someone else
+    LockFailed,
+    /// Ingest could not complete
+    IngestFailed,
+    /// The daemon or another required service cannot be reached
+    Unavailable,
+    /// Any other I/O failure
+    Io,
+    /// Unexpected state; usually a bug
+    Internal,
+}
+
+impl ErrorKind {
+    /// Process exit code for this kind
+    ///
+    /// - 1: Io, Internal
+    /// - 2: NotFound
+    /// - 22: InvalidInput (EINVAL)
+    /// - 65: Corrupted (EX_DATAERR)
+    /// - 69: Unavailable (EX_UNAVAILABLE)
+    /// - 77: PermissionDenied (EX_NOPERM)
+    /// - 78: LockFailed
+    /// - 79: IngestFailed
+    pub fn exit_code(self) -> i32 {
+        match self {
+            ErrorKind::NotFound => 2,
+            ErrorKind::InvalidInput => 22,
+            ErrorKind::Corrupted => 65,
+            ErrorKind::Unavailable => 69,
+            ErrorKind::PermissionDenied => 77,
+            ErrorKind::LockFailed => 78,
+            ErrorKind::IngestFailed => 79,
+            ErrorKind::Io | ErrorKind::Internal => 1,
+        }
+    }
+}
+
+impl From<io::ErrorKind> for ErrorKind {
+    fn from(kind: io::ErrorKind) -> Self {
+        use io::ErrorKind as K;
+        match kind {
+            K::NotFound => ErrorKind::NotFound,
+            K::PermissionDenied => ErrorKind::PermissionDenied,
+            K::InvalidInput => ErrorKind::InvalidInput,
+            K::InvalidData | K::UnexpectedEof => ErrorKind::Corrupted,
+            K::WouldBlock => ErrorKind::LockFailed,
+            K::ConnectionRefused
+            | K::ConnectionReset
+            | K::ConnectionAborted
+            | K::NotConnected
+            | K::BrokenPipe
+            | K::TimedOut => ErrorKind::Unavailable,
+            _ => ErrorKind::Io,
+        }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ErrorKind::NotFound => "not found",
+            ErrorKind::PermissionDenied => "permission denied",
+            ErrorKind::InvalidInput => "invalid input",
+            ErrorKind::Corrupted => "corrupted data",
+            ErrorKind::LockFailed => "lock failed",
+            ErrorKind::IngestFailed => "ingest failed",
+            ErrorKind::Unavailable => "service unavailable",
+            ErrorKind::Io => "I/O error",
+            ErrorKind::Internal => "internal error",
+        })
+    }
+}
+
+/// Errors that know their [`ErrorKind`]
+pub trait Classify {
+    fn classify(&self) -> ErrorKind;
+
+    /// Shorthand for `self.classify().exit_code()`
+    fn exit_code(&self) -> i32 {
+        self.classify().exit_code()
+    }
+}
+
+impl Classify for io::Error {
+    fn classify(&self) -> ErrorKind {
+        self.kind().into()
+    }
+}
+
+impl<E: Classify + ?Sized> Classify for Box<E> {
+    fn classify(&self) -> ErrorKind {
+        (**self).classify()
+    }
+}
+
+/// An error with a message describing what was being attempted
+///
+/// Created by [`ResultExt::context`] (kind taken from the wrapped error) or
+/// [`Error::new`] (no source).
+#[derive(Debug)]
+pub struct Error {
+    kind: ErrorKind,
+    message: String,
+    source: Option<Box<dyn StdError + Send + Sync>>,
+}
+
+impl Error {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            source: None,
+        }
+    }
+
+    /// Wrap `source`, inheriting its kind
+    pub fn wrap<E>(source: E, message: impl Into<String>) -> Self
+    where
+        E: Classify + StdError + Send + Sync + 'static,
+    {
+        Self {
+            kind: source.classify(),
+            message: message.into(),
+            source: Some(Box::new(source)),
+        }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// The context message, without the source
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.source {
+            Some(source) => write!(f, "{}: {}", self.message, source),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        self.source
+            .as_deref()
+            .map(|e| e as &(dyn StdError + 'static))
+    }
+}
+
+impl Classify for Error {
+    fn classify(&self) -> ErrorKind {
+        self.kind
+    }
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Context chaining for results whose error is [`Classify`]
+pub trait ResultExt<T> {
+    fn context<C: fmt::Display>(self, context: C) -> Result<T>;
+
+    /// Like [`context`](ResultExt::context), building the message only on error
+    fn with_context<C: fmt::Display, F: FnOnce() -> C>(self, f: F) -> Result<T>;
+}
+
+impl<T, E> ResultExt<T> for std::result::Result<T, E>
+where
+    E: Classify + StdError + Send + Sync + 'static,
+{
+    fn context<C: fmt::Display>(self, context: C) -> Result<T> {
+        self.map_err(|e| Error::wrap(e, context.to_string()))
+    }
+
+    fn with_context<C: fmt::Display, F: FnOnce() -> C>(self, f: F) -> Result<T> {
+        self.map_err(|e| Error::wrap(e, f().to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_io_kinds() {
+        let e = io::Error::new(io::ErrorKind::NotFound, "gone");
+        assert_eq!(e.classify(), ErrorKind::NotFound);
+        assert_eq!(Classify::exit_code(&e), 2);
+
+        let e = io::Error::from(io::ErrorKind::ConnectionRefused);
+        assert_eq!(e.classify(), ErrorKind::Unavailable);
+
+        let e = io::Error::other("boom");
+        assert_eq!(e.classify(), ErrorKind::Io);
+        assert_eq!(Classify::exit_code(&e), 1);
+    }
+
+    #[test]
+    fn test_context_keeps_kind_and_source() {
+        let res: std::result::Result<(), io::Error> =
+            Err(io::Error::from(io::ErrorKind::PermissionDenied));
+        let err = res
+            .context("open blob")
+            .with_context(|| format!("ingest {}", "src/main.rs"))
+            .unwrap_err();
+
+        assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+        assert_eq!(err.exit_code(), 77);
+        assert_eq!(err.message(), "ingest src/main.rs");
+        assert!(err
+            .to_string()
+            .starts_with("ingest src/main.rs: open blob: "));
+
+        let inner = err.source().unwrap().downcast_ref::<Error>().unwrap();
+        assert_eq!(inner.message(), "open blob");
+        assert!(inner.source().unwrap().is::<io::Error>());
+    }
+
+    #[test]
+    fn test_new_has_no_source() {
+        let err = Error::new(ErrorKind::Corrupted, "bad magic");
+        assert_eq!(err.to_string(), "bad magic");
+        assert!(err.source().is_none());
+        assert_eq!(err.exit_code(), 65);
+    }
+}
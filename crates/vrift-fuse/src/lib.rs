@@ -149,14 +149,15 @@ mod imp {
         }
 
         fn vnode_to_attr(inode: u64, vnode: &VnodeEntry) -> FileAttr {
+            let mtime = vrift_ipc::mtime::to_system_time(vnode.mtime);
             FileAttr {
                 ino: inode,
                 size: vnode.size,
                 blocks: vnode.size.div_ceil(BLOCK_SIZE),
-                atime: UNIX_EPOCH + Duration::from_secs(vnode.mtime),
-                mtime: UNIX_EPOCH + Duration::from_secs(vnode.mtime),
-                ctime: UNIX_EPOCH + Duration::from_secs(vnode.mtime),
-                crtime: UNIX_EPOCH + Duration::from_secs(vnode.mtime),
+                atime: mtime,
+                mtime,
+                ctime: mtime,
+                crtime: mtime,
                 kind: if vnode.is_dir() {
                     FileType::Directory
                 } else {
@@ -0,0 +1,512 @@
+//! # vrift-genfs
+//!
+//! Reproducible synthetic file trees for the benchmarks and scalability
+//! tests. A [`Spec`] fully determines its tree, every path, size, byte and
+//! mtime, so numbers measured by different contributors on different
+//! machines come from the same corpus; [`Spec::corpus_id`] names it.
+//!
+//! Randomness comes from a built-in SplitMix64 rather than a `rand` RNG,
+//! whose output for a given seed is free to change between releases, and no
+//! floating point is involved past parsing. A change that alters the output
+//! bumps [`GENERATOR_VERSION`], which is part of the corpus id.
+
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+
+use serde::Serialize;
+
+/// Version of the generator's output for a given [`Spec`]
+pub const GENERATOR_VERSION: u32 = 1;
+
+/// mtime of every generated file (2020-01-01T00:00:00Z), so manifests of
+/// the tree are reproducible too
+const MTIME_SECS: u64 = 1_577_836_800;
+
+const SIZE_SALT: u64 = 0x5349_5a45;
+const DATA_SALT: u64 = 0x4441_5441;
+const DUP_SALT: u64 = 0x4455_5053;
+
+/// Resolution of [`Spec::dup_ratio`]
+const DUP_SCALE: u64 = 1_000_000;
+
+/// The SplitMix64 finalizer
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// SplitMix64, one independent stream per (seed, purpose, index), so any
+/// file can be worked out without the ones before it
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64, salt: u64, index: u64) -> Self {
+        Rng(mix(seed ^ mix(salt ^ mix(index))))
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        mix(self.0)
+    }
+
+    /// Uniform in `0..n`; `n` must not be zero
+    fn below(&mut self, n: u64) -> u64 {
+        ((self.next_u64() as u128 * n as u128) >> 64) as u64
+    }
+
+    /// Uniform in `min..=max`
+    fn between(&mut self, min: u64, max: u64) -> u64 {
+        match (max - min).checked_add(1) {
+            Some(span) => min + self.below(span),
+            None => self.next_u64(),
+        }
+    }
+}
+
+/// How file sizes are drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizeDist {
+    /// Every file has this size (`4k`)
+    Fixed(u64),
+    /// Uniform between the bounds, inclusive (`1k..64k`)
+    Uniform { min: u64, max: u64 },
+    /// Each power of two between the bounds is equally likely, then uniform
+    /// within it: mostly small files and a few large ones, like a source
+    /// tree (`log:512..1m`)
+    LogUniform { min: u64, max: u64 },
+}
+
+impl SizeDist {
+    fn sample(&self, rng: &mut Rng) -> u64 {
+        match *self {
+            SizeDist::Fixed(size) => size,
+            SizeDist::Uniform { min, max } => rng.between(min, max),
+            SizeDist::LogUniform { min, max } => {
+                let log2 = |n: u64| 63 - n.max(1).leading_zeros() as u64;
+                let bits = rng.between(log2(min), log2(max));
+                let low = (1u64 << bits).max(min);
+                let high = (1u64 << bits)
+                    .checked_mul(2)
+                    .map_or(u64::MAX, |n| n - 1)
+                    .min(max);
+                rng.between(low, high)
+            }
+        }
+    }
+}
+
+impl FromStr for SizeDist {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (log, range) = match s.strip_prefix("log:") {
+            Some(range) => (true, range),
+            None => (false, s),
+        };
+        let dist = match range.split_once("..") {
+            Some((min, max)) => {
+                let (min, max) = (parse_size(min)?, parse_size(max)?);
+                if min > max {
+                    return Err(format!("empty size range '{}'", s));
+                }
+                if log {
+                    SizeDist::LogUniform { min, max }
+                } else {
+                    SizeDist::Uniform { min, max }
+                }
+            }
+            None if log => return Err(format!("'{}' needs a range, as in log:512..1m", s)),
+            None => SizeDist::Fixed(parse_size(range)?),
+        };
+        Ok(dist)
+    }
+}
+
+impl fmt::Display for SizeDist {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            SizeDist::Fixed(size) => write!(f, "{}", SizeLiteral(size)),
+            SizeDist::Uniform { min, max } => {
+                write!(f, "{}..{}", SizeLiteral(min), SizeLiteral(max))
+            }
+            SizeDist::LogUniform { min, max } => {
+                write!(f, "log:{}..{}", SizeLiteral(min), SizeLiteral(max))
+            }
+        }
+    }
+}
+
+/// A byte count with an optional binary suffix: `512`, `4k`, `16M`, `1g`
+pub fn parse_size(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let (digits, shift) = match s.char_indices().last() {
+        Some((i, 'k' | 'K')) => (&s[..i], 10),
+        Some((i, 'm' | 'M')) => (&s[..i], 20),
+        Some((i, 'g' | 'G')) => (&s[..i], 30),
+        _ => (s, 0),
+    };
+    digits
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(1 << shift))
+        .ok_or_else(|| format!("invalid size '{}'", s))
+}
+
+/// A size in the form [`parse_size`] reads, with the largest exact suffix
+struct SizeLiteral(u64);
+
+impl fmt::Display for SizeLiteral {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let n = self.0;
+        for (shift, suffix) in [(30, "g"), (20, "m"), (10, "k")] {
+            if n != 0 && n.is_multiple_of(1 << shift) {
+                return write!(f, "{}{}", n >> shift, suffix);
+            }
+        }
+        write!(f, "{}", n)
+    }
+}
+
+/// Everything that determines a tree
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spec {
+    pub seed: u64,
+    pub files: u64,
+    pub sizes: SizeDist,
+    /// Fraction of files (0 to 1, in steps of one in a million) whose
+    /// content repeats that of an earlier file
+    pub dup_ratio: f64,
+    /// Files in each directory
+    pub files_per_dir: u64,
+    /// Subdirectories of each directory
+    pub fanout: u64,
+}
+
+impl Default for Spec {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            files: 10_000,
+            sizes: SizeDist::LogUniform {
+                min: 512,
+                max: 64 << 10,
+            },
+            dup_ratio: 0.0,
+            files_per_dir: 64,
+            fanout: 16,
+        }
+    }
+}
+
+impl fmt::Display for Spec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "seed={} files={} size={} dup={}.{:06} per-dir={} fanout={}",
+            self.seed,
+            self.files,
+            self.sizes,
+            self.dup_threshold() / DUP_SCALE,
+            self.dup_threshold() % DUP_SCALE,
+            self.files_per_dir,
+            self.fanout
+        )
+    }
+}
+
+/// One file of a tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenFile {
+    /// Relative to the root of the tree
+    pub path: PathBuf,
+    pub size: u64,
+    /// Index of the file whose bytes this one has: its own, unless it is a
+    /// duplicate
+    pub content: u64,
+}
+
+/// What a tree holds
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Summary {
+    pub corpus: String,
+    pub files: u64,
+    pub dirs: u64,
+    pub bytes: u64,
+    /// Files that are not duplicates, and their bytes
+    pub distinct_files: u64,
+    pub distinct_bytes: u64,
+}
+
+impl Summary {
+    fn add(&mut self, file: &GenFile, index: u64) {
+        self.files += 1;
+        self.bytes += file.size;
+        if file.content == index {
+            self.distinct_files += 1;
+            self.distinct_bytes += file.size;
+        }
+    }
+}
+
+impl Spec {
+    /// Short name of the tree: equal ids, byte-identical trees
+    pub fn corpus_id(&self) -> String {
+        let canonical = format!("vrift-genfs/{} {}", GENERATOR_VERSION, self);
+        blake3::hash(canonical.as_bytes()).to_hex()[..16].to_string()
+    }
+
+    pub fn validate(&self) -> io::Result<()> {
+        let problem = if !(0.0..=1.0).contains(&self.dup_ratio) {
+            "dup ratio must be between 0 and 1"
+        } else if self.files_per_dir == 0 {
+            "files per directory must be at least 1"
+        } else if self.fanout == 0 {
+            "fanout must be at least 1"
+        } else {
+            return Ok(());
+        };
+        Err(io::Error::new(io::ErrorKind::InvalidInput, problem))
+    }
+
+    fn dup_threshold(&self) -> u64 {
+        (self.dup_ratio * DUP_SCALE as f64).round() as u64
+    }
+
+    /// File `index` of the tree
+    pub fn file(&self, index: u64) -> GenFile {
+        let content = self.content_of(index);
+        GenFile {
+            path: self.dir_of(index).join(format!("f{}.dat", index)),
+            size: self
+                .sizes
+                .sample(&mut Rng::new(self.seed, SIZE_SALT, content)),
+            content,
+        }
+    }
+
+    /// Every file of the tree, without writing anything
+    pub fn files(&self) -> impl Iterator<Item = GenFile> + '_ {
+        (0..self.files).map(|index| self.file(index))
+    }
+
+    /// A duplicate repeats a random earlier file, which may be one itself
+    fn content_of(&self, mut index: u64) -> u64 {
+        let threshold = self.dup_threshold();
+        while index > 0 {
+            let mut rng = Rng::new(self.seed, DUP_SALT, index);
+            if rng.below(DUP_SCALE) >= threshold {
+                break;
+            }
+            index = rng.below(index);
+        }
+        index
+    }
+
+    /// Directories form a complete tree of `fanout` children each, filled
+    /// breadth first, `files_per_dir` files at a time
+    fn dir_of(&self, index: u64) -> PathBuf {
+        let mut node = index / self.files_per_dir;
+        let mut names = Vec::new();
+        while node > 0 {
+            names.push(format!("d{}", (node - 1) % self.fanout));
+            node = (node - 1) / self.fanout;
+        }
+        names.iter().rev().collect()
+    }
+
+    /// Write the `size` bytes of content `content` to `out`
+    pub fn write_content(&self, content: u64, size: u64, out: &mut impl Write) -> io::Result<()> {
+        let mut rng = Rng::new(self.seed, DATA_SALT, content);
+        let mut buf = vec![0u8; 64 << 10];
+        let mut left = size;
+        while left > 0 {
+            let n = left.min(buf.len() as u64) as usize;
+            for chunk in buf[..n].chunks_mut(8) {
+                chunk.copy_from_slice(&rng.next_u64().to_le_bytes()[..chunk.len()]);
+            }
+            out.write_all(&buf[..n])?;
+            left -= n as u64;
+        }
+        Ok(())
+    }
+}
+
+/// Write the tree of `spec` into `root`, which must be missing or empty.
+/// `threads` writers share the files; the tree does not depend on how many.
+pub fn generate(spec: &Spec, root: &Path, threads: usize) -> io::Result<Summary> {
+    spec.validate()?;
+    fs::create_dir_all(root)?;
+    if fs::read_dir(root)?.next().is_some() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} is not empty", root.display()),
+        ));
+    }
+
+    let mut summary = Summary {
+        corpus: spec.corpus_id(),
+        ..Summary::default()
+    };
+    if spec.files == 0 {
+        return Ok(summary);
+    }
+    let last_dir = (spec.files - 1) / spec.files_per_dir;
+    for node in 1..=last_dir {
+        fs::create_dir(root.join(spec.dir_of(node * spec.files_per_dir)))?;
+    }
+    summary.dirs = last_dir;
+
+    let threads = threads.clamp(1, 256) as u64;
+    let mtime = SystemTime::UNIX_EPOCH + Duration::from_secs(MTIME_SECS);
+    let parts = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|worker| {
+                scope.spawn(move || -> io::Result<Summary> {
+                    let mut part = Summary::default();
+                    let mut index = worker;
+                    while index < spec.files {
+                        let file = spec.file(index);
+                        let mut out = File::create(root.join(&file.path))?;
+                        spec.write_content(file.content, file.size, &mut out)?;
+                        out.set_modified(mtime)?;
+                        part.add(&file, index);
+                        index += threads;
+                    }
+                    Ok(part)
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .map(|worker| worker.join().expect("genfs writer panicked"))
+            .collect::<io::Result<Vec<_>>>()
+    })?;
+    for part in parts {
+        summary.files += part.files;
+        summary.bytes += part.bytes;
+        summary.distinct_files += part.distinct_files;
+        summary.distinct_bytes += part.distinct_bytes;
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_size_dist_round_trips() {
+        for s in ["4k", "1k..64k", "log:512..1m", "3..5", "2g"] {
+            assert_eq!(s.parse::<SizeDist>().unwrap().to_string(), s);
+        }
+        assert_eq!("16M".parse(), Ok(SizeDist::Fixed(16 << 20)));
+        assert!("log:4k".parse::<SizeDist>().is_err());
+        assert!("8k..1k".parse::<SizeDist>().is_err());
+        assert!("lots".parse::<SizeDist>().is_err());
+    }
+
+    #[test]
+    fn test_spec_determines_files() {
+        let spec = Spec {
+            seed: 7,
+            files: 2000,
+            dup_ratio: 0.25,
+            ..Spec::default()
+        };
+        let files: Vec<_> = spec.files().collect();
+        assert_eq!(files, spec.files().collect::<Vec<_>>());
+
+        // A known file pins the output of this generator version
+        assert_eq!(
+            spec.file(1234),
+            GenFile {
+                path: PathBuf::from("d0/d2/f1234.dat"),
+                size: 3724,
+                content: 671,
+            }
+        );
+        let mut data = Vec::new();
+        spec.write_content(671, 3724, &mut data).unwrap();
+        assert_eq!(
+            blake3::hash(&data).to_hex().as_str(),
+            "ba81c56535bc4869ecbc9ba80dc9e28692ad65c3797fd7e575150c18a6d0134d"
+        );
+        let other = Spec {
+            seed: 8,
+            ..spec.clone()
+        };
+        assert_ne!(files, other.files().collect::<Vec<_>>());
+        assert_ne!(spec.corpus_id(), other.corpus_id());
+
+        let paths: HashSet<_> = files.iter().map(|f| &f.path).collect();
+        assert_eq!(paths.len(), files.len());
+        for f in &files {
+            assert!((512..=64 << 10).contains(&f.size), "{:?}", f);
+            assert_eq!(f.size, files[f.content as usize].size);
+        }
+        let dups = files
+            .iter()
+            .enumerate()
+            .filter(|(i, f)| f.content != *i as u64)
+            .count();
+        assert!((400..600).contains(&dups), "{} duplicates", dups);
+    }
+
+    #[test]
+    fn test_log_uniform_favours_small_files() {
+        let spec = Spec {
+            files: 4000,
+            sizes: "log:1..1m".parse().unwrap(),
+            ..Spec::default()
+        };
+        let small = spec.files().filter(|f| f.size < 1 << 10).count();
+        // Half of the 21 powers of two lie below 1 KiB
+        assert!((1600..2200).contains(&small), "{} small files", small);
+    }
+
+    #[test]
+    fn test_generate_writes_the_plan() {
+        let spec = Spec {
+            seed: 3,
+            files: 300,
+            sizes: "0..100k".parse().unwrap(),
+            dup_ratio: 0.5,
+            files_per_dir: 10,
+            fanout: 4,
+        };
+        let one = tempfile::tempdir().unwrap();
+        let four = tempfile::tempdir().unwrap();
+        let summary = generate(&spec, one.path(), 1).unwrap();
+        assert_eq!(generate(&spec, four.path(), 4).unwrap(), summary);
+        assert_eq!(summary.files, 300);
+        assert_eq!(summary.dirs, 29);
+
+        let mut bytes = 0;
+        for file in spec.files() {
+            let data = fs::read(one.path().join(&file.path)).unwrap();
+            assert_eq!(data, fs::read(four.path().join(&file.path)).unwrap());
+            assert_eq!(data.len() as u64, file.size);
+            let original = spec.file(file.content);
+            assert_eq!(data, fs::read(one.path().join(&original.path)).unwrap());
+            bytes += file.size;
+        }
+        assert_eq!(summary.bytes, bytes);
+        let mtime = fs::metadata(one.path().join("f0.dat"))
+            .unwrap()
+            .modified()
+            .unwrap();
+        assert_eq!(
+            mtime,
+            SystemTime::UNIX_EPOCH + Duration::from_secs(MTIME_SECS)
+        );
+
+        let err = generate(&spec, one.path(), 1).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+    }
+}
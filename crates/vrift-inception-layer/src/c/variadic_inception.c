@@ -12,9 +12,15 @@
 #include <stdbool.h>
 #include <stddef.h>
 #include <stdint.h>
+#include <stdlib.h>
+#include <string.h>
 #include <sys/types.h>
 #include <unistd.h>
 
+#if defined(__APPLE__)
+#include <mach-o/dyld.h>
+#endif
+
 /* RFC-0051: C-based errno bridge for cross-language consistency */
 void set_inception_errno(int e) { errno = e; }
 int get_inception_errno() { return errno; }
@@ -67,7 +73,13 @@ extern int velo_fstatat_impl(int dirfd, const char *path, void *buf, int flags);
  */
 volatile char INITIALIZING = 2;
 
+static int is_vrift_process(void);
+
 __attribute__((constructor(101))) void inception_init_constructor() {
+  // Velo Rift's own processes stay in passthrough for good
+  if (is_vrift_process()) {
+    return;
+  }
   // RFC-0051: Ignore SIGPIPE to prevent IPC failures from killing processes
   signal(SIGPIPE, SIG_IGN);
   INITIALIZING = 1; // Transition to RustInit
@@ -137,6 +149,71 @@ static inline long raw_syscall(long number, long arg1, long arg2, long arg3,
 }
 #endif
 
+/* --- Self-interception interlock ---
+ *
+ * The CLI, vriftd and vDird read and write the real CAS, manifests and
+ * staging files. Started with the inception layer preloaded (an exported
+ * LD_PRELOAD, a `vrift shell` that launches the daemon) they would virtualize
+ * their own I/O. Such a process is recognized by its executable name, or by
+ * VRIFT_NO_INCEPTION, which vrift sets when it spawns its own daemons, and
+ * never leaves EarlyInit: every call passes straight to the kernel.
+ */
+
+static const char *const VRIFT_EXECUTABLES[] = {"vrift", "vriftd", "vdir_d"};
+
+static void warn_disabled(const char *why) {
+  static const char prefix[] = "[vrift] inception layer disabled in ";
+  static const char suffix[] = ": Velo Rift does not virtualize itself\n";
+  size_t len = 0;
+  while (why[len]) {
+    len++;
+  }
+  (void)!write(2, prefix, sizeof(prefix) - 1);
+  (void)!write(2, why, len);
+  (void)!write(2, suffix, sizeof(suffix) - 1);
+}
+
+static int is_vrift_process(void) {
+  const char *marker = getenv("VRIFT_NO_INCEPTION");
+  if (marker && marker[0] && !(marker[0] == '0' && marker[1] == 0)) {
+    return 1; // Set by vrift itself; no warning
+  }
+
+  char exe[4096];
+  long len = -1;
+#if defined(__APPLE__)
+  uint32_t size = sizeof(exe);
+  if (_NSGetExecutablePath(exe, &size) == 0) {
+    len = (long)strnlen(exe, sizeof(exe));
+  }
+#elif defined(__linux__) && defined(__x86_64__)
+  len = raw_syscall(SYS_READLINK, (long)"/proc/self/exe", (long)exe,
+                    sizeof(exe) - 1, 0);
+#elif defined(__linux__) && defined(__aarch64__)
+  len = raw_syscall(SYS_READLINKAT, AT_FDCWD, (long)"/proc/self/exe",
+                    (long)exe, sizeof(exe) - 1);
+#endif
+  if (len <= 0) {
+    return 0;
+  }
+  exe[len] = 0;
+
+  const char *name = exe;
+  for (const char *p = exe; *p; p++) {
+    if (*p == '/') {
+      name = p + 1;
+    }
+  }
+  for (size_t i = 0;
+       i < sizeof(VRIFT_EXECUTABLES) / sizeof(VRIFT_EXECUTABLES[0]); i++) {
+    if (strcmp(name, VRIFT_EXECUTABLES[i]) == 0) {
+      warn_disabled(exe);
+      return 1;
+    }
+  }
+  return 0;
+}
+
 /* --- Implementation Functions (called by Rust proxies or direct inception
  * layers) --- */
 
@@ -0,0 +1,179 @@
+//! # Fault Injection
+//!
+//! Built with `--features fault-injection`, the inception layer misbehaves on
+//! request, so build tooling can be checked against a VFS that fails.
+//! `VRIFT_FAULT` is a comma-separated list of faults:
+//!
+//! - `daemon-down`: connecting to vriftd or vDird fails, as when neither runs.
+//!   The shim retries, then falls back to the VDir mmap (degraded mode).
+//! - `delay=<ms>`: every daemon request waits `<ms>` milliseconds before it is
+//!   sent.
+//! - `eio[=<ops>][@<percent>]`: opens and stats of managed paths fail with
+//!   `EIO`. `<ops>` is `open`, `stat` or `open+stat` (the default); with
+//!   `@<percent>` only that share of calls fails.
+//!
+//! With `VRIFT_FAULT_TRIGGER` naming a file, the faults only apply while that
+//! file exists, so a test can switch them on and off mid-run.
+//!
+//! Without the feature the hooks compile to nothing and `VRIFT_FAULT` is
+//! ignored.
+
+/// Shim entry points an `eio` fault can fail
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Op {
+    Open = 1,
+    Stat = 2,
+}
+
+/// Whether connecting to a daemon should fail
+#[inline(always)]
+pub(crate) fn daemon_down() -> bool {
+    #[cfg(feature = "fault-injection")]
+    {
+        imp::daemon_down()
+    }
+    #[cfg(not(feature = "fault-injection"))]
+    {
+        false
+    }
+}
+
+/// Wait out the configured delay before a daemon request goes out
+#[inline(always)]
+pub(crate) fn delay_request() {
+    #[cfg(feature = "fault-injection")]
+    imp::delay_request();
+}
+
+/// The errno `op` on a managed path should fail with, if any
+#[inline(always)]
+pub(crate) fn injected_errno(op: Op) -> Option<libc::c_int> {
+    #[cfg(feature = "fault-injection")]
+    {
+        imp::injected_errno(op)
+    }
+    #[cfg(not(feature = "fault-injection"))]
+    {
+        let _ = op;
+        None
+    }
+}
+
+#[cfg(feature = "fault-injection")]
+mod imp {
+    use super::Op;
+    use std::ffi::{CStr, CString};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::OnceLock;
+
+    #[cfg(target_os = "linux")]
+    use crate::syscalls::linux_raw::raw_access;
+    #[cfg(target_os = "macos")]
+    use crate::syscalls::macos_raw::raw_access;
+
+    #[derive(Debug, Default)]
+    struct Faults {
+        daemon_down: bool,
+        delay_ms: u64,
+        /// Bit set of [`Op`]s that fail with EIO
+        eio_ops: u8,
+        /// Share of `eio_ops` calls that fail, 1..=100
+        eio_percent: u64,
+        trigger: Option<CString>,
+    }
+
+    static FAULTS: OnceLock<Faults> = OnceLock::new();
+
+    /// xorshift state for `eio@<percent>`
+    static RNG: AtomicU64 = AtomicU64::new(0);
+
+    fn parse(spec: &str) -> Faults {
+        let mut faults = Faults::default();
+        for item in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let (name, arg) = item.split_once('=').unwrap_or((item, ""));
+            match name {
+                "daemon-down" => faults.daemon_down = true,
+                "delay" => faults.delay_ms = arg.parse().unwrap_or(0),
+                "eio" => {
+                    let (ops, percent) = arg.split_once('@').unwrap_or((arg, "100"));
+                    faults.eio_percent = percent.parse::<u64>().unwrap_or(100).clamp(1, 100);
+                    for op in ops.split('+') {
+                        faults.eio_ops |= match op {
+                            "open" => Op::Open as u8,
+                            "stat" => Op::Stat as u8,
+                            "" => Op::Open as u8 | Op::Stat as u8,
+                            _ => {
+                                inception_warn!("VRIFT_FAULT: unknown eio op '{}'", op);
+                                0
+                            }
+                        };
+                    }
+                }
+                _ => inception_warn!("VRIFT_FAULT: unknown fault '{}'", name),
+            }
+        }
+        faults
+    }
+
+    fn faults() -> &'static Faults {
+        FAULTS.get_or_init(|| unsafe {
+            let ptr = libc::getenv(c"VRIFT_FAULT".as_ptr());
+            if ptr.is_null() {
+                return Faults::default();
+            }
+            let mut faults = parse(&CStr::from_ptr(ptr).to_string_lossy());
+            let trigger = libc::getenv(c"VRIFT_FAULT_TRIGGER".as_ptr());
+            if !trigger.is_null() && *trigger != 0 {
+                faults.trigger = Some(CStr::from_ptr(trigger).to_owned());
+            }
+            inception_warn!(
+                "fault injection active: {}",
+                CStr::from_ptr(ptr).to_string_lossy()
+            );
+            faults
+        })
+    }
+
+    impl Faults {
+        fn armed(&self) -> bool {
+            match self.trigger {
+                Some(ref path) => unsafe { raw_access(path.as_ptr(), libc::F_OK) == 0 },
+                None => true,
+            }
+        }
+    }
+
+    /// Roll for a `percent` chance
+    fn roll(percent: u64) -> bool {
+        if percent >= 100 {
+            return true;
+        }
+        let mut x = RNG.load(Ordering::Relaxed);
+        if x == 0 {
+            x = (unsafe { libc::getpid() } as u64) << 32 | 0x9E37_79B9;
+        }
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        RNG.store(x, Ordering::Relaxed);
+        x % 100 < percent
+    }
+
+    pub(super) fn daemon_down() -> bool {
+        let faults = faults();
+        faults.daemon_down && faults.armed()
+    }
+
+    pub(super) fn delay_request() {
+        let faults = faults();
+        if faults.delay_ms > 0 && faults.armed() {
+            std::thread::sleep(std::time::Duration::from_millis(faults.delay_ms));
+        }
+    }
+
+    pub(super) fn injected_errno(op: Op) -> Option<libc::c_int> {
+        let faults = faults();
+        (faults.eio_ops & op as u8 != 0 && faults.armed() && roll(faults.eio_percent))
+            .then_some(libc::EIO)
+    }
+}
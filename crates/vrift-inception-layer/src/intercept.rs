@@ -0,0 +1,198 @@
+//! Runtime interposer selection (`VRIFT_INTERCEPT`)
+//!
+//! Every interposer belongs to one group. A group that is switched off
+//! hands its calls straight to the raw syscall, exactly as during early
+//! init, so a misbehaving tool can be bisected without rebuilding the shim:
+//!
+//! ```text
+//! VRIFT_INTERCEPT=stat,open      only these two groups
+//! VRIFT_INTERCEPT=-dir,-write    everything except these
+//! VRIFT_INTERCEPT=none           load the shim but intercept nothing
+//! ```
+//!
+//! Unset or empty means `all`. The variable is read once, on the first
+//! check after init, with the same zero-allocation rules as the rest of the
+//! hot path.
+
+use libc::c_char;
+use std::ffi::CStr;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// stat, lstat, fstat, fstatat, statx, access
+pub const STAT: u32 = 1 << 0;
+/// open, openat, openat2, creat (includes sandbox checks and `VRIFT_RECORD`)
+pub const OPEN: u32 = 1 << 1;
+/// opendir (synthetic listings), getcwd, chdir
+pub const DIR: u32 = 1 << 2;
+/// close (CoW reingest), dup, dup2, flock
+pub const IO: u32 = 1 << 3;
+/// Mutation perimeter: unlink, rename, mkdir, rmdir, link, symlink, chmod,
+/// chown, utimes, truncate, xattrs, flags, sendfile/copy_file_range targets
+pub const WRITE: u32 = 1 << 4;
+/// realpath
+pub const PATH: u32 = 1 << 5;
+/// posix_spawn child reporting, exec and dlopen of VFS files
+pub const EXEC: u32 = 1 << 6;
+/// inotify emulation
+pub const WATCH: u32 = 1 << 7;
+
+pub const ALL: u32 = STAT | OPEN | DIR | IO | WRITE | PATH | EXEC | WATCH;
+
+/// Names accepted in `VRIFT_INTERCEPT`, in display order
+pub const GROUPS: &[(&str, u32)] = &[
+    ("stat", STAT),
+    ("open", OPEN),
+    ("dir", DIR),
+    ("io", IO),
+    ("write", WRITE),
+    ("path", PATH),
+    ("exec", EXEC),
+    ("watch", WATCH),
+];
+
+// High bit marks "env not read yet"; it is never a valid group.
+const UNPARSED: u32 = 1 << 31;
+
+static INTERCEPT_MASK: AtomicU32 = AtomicU32::new(UNPARSED);
+
+/// Whether interposers in `group` should run their VFS logic
+#[inline(always)]
+pub fn enabled(group: u32) -> bool {
+    let mut mask = INTERCEPT_MASK.load(Ordering::Relaxed);
+    if mask & UNPARSED != 0 {
+        mask = load_mask();
+    }
+    mask & group != 0
+}
+
+/// Currently active groups (reads the environment if not done yet)
+pub fn mask() -> u32 {
+    let mask = INTERCEPT_MASK.load(Ordering::Relaxed);
+    if mask & UNPARSED != 0 {
+        load_mask()
+    } else {
+        mask
+    }
+}
+
+#[cold]
+#[inline(never)]
+fn load_mask() -> u32 {
+    let val = unsafe { libc::getenv(c"VRIFT_INTERCEPT".as_ptr()) };
+    let mask = if val.is_null() {
+        ALL
+    } else {
+        let spec = unsafe { CStr::from_ptr(val as *const c_char) }.to_bytes();
+        parse(spec, |name| unsafe {
+            let msg = b"[vrift-inception] VRIFT_INTERCEPT: ignoring unknown group '";
+            libc::write(2, msg.as_ptr() as *const _, msg.len());
+            libc::write(2, name.as_ptr() as *const _, name.len());
+            libc::write(2, b"'\n".as_ptr() as *const _, 2);
+        })
+    };
+    // Racing threads compute the same value
+    INTERCEPT_MASK.store(mask, Ordering::Relaxed);
+    mask
+}
+
+/// Parse a comma-separated group list
+///
+/// A list that starts with a `-name` exclusion is taken relative to `all`;
+/// otherwise it starts from nothing. `all` and `none` reset the set.
+/// Unknown names are reported through `unknown` and skipped.
+pub fn parse(spec: &[u8], mut unknown: impl FnMut(&[u8])) -> u32 {
+    let mut tokens = spec
+        .split(|&b| b == b',')
+        .map(|t| t.trim_ascii())
+        .filter(|t| !t.is_empty())
+        .peekable();
+
+    let mut mask = match tokens.peek() {
+        None => return ALL,
+        Some(first) if first.starts_with(b"-") => ALL,
+        Some(_) => 0,
+    };
+
+    for token in tokens {
+        let (remove, name) = match token.strip_prefix(b"-") {
+            Some(rest) => (true, rest),
+            None => (false, token),
+        };
+        let bits = if name.eq_ignore_ascii_case(b"all") {
+            ALL
+        } else if name.eq_ignore_ascii_case(b"none") {
+            mask = 0;
+            continue;
+        } else if let Some(&(_, bits)) = GROUPS
+            .iter()
+            .find(|(group, _)| name.eq_ignore_ascii_case(group.as_bytes()))
+        {
+            bits
+        } else {
+            unknown(name);
+            continue;
+        };
+        if remove {
+            mask &= !bits;
+        } else {
+            mask |= bits;
+        }
+    }
+    mask
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_all(spec: &str) -> (u32, Vec<String>) {
+        let mut unknown = Vec::new();
+        let mask = parse(spec.as_bytes(), |name| {
+            unknown.push(String::from_utf8_lossy(name).into_owned())
+        });
+        (mask, unknown)
+    }
+
+    #[test]
+    fn test_parse_group_lists() {
+        for (spec, expected) in [
+            ("stat", STAT),
+            ("stat,open", STAT | OPEN),
+            (" Stat , OPEN ", STAT | OPEN),
+            ("-dir,-write", ALL & !(DIR | WRITE)),
+            ("-watch", ALL & !WATCH),
+            ("all", ALL),
+            ("all,-exec", ALL & !EXEC),
+            ("none", 0),
+            ("stat,none,io", IO),
+            ("exec,-exec", 0),
+            ("stat,open,dir,io,write,path,exec,watch", ALL),
+        ] {
+            assert_eq!(parse_all(spec), (expected, Vec::new()), "{:?}", spec);
+        }
+    }
+
+    #[test]
+    fn test_parse_empty_means_all() {
+        for spec in ["", " ", ",", " , ,"] {
+            assert_eq!(parse_all(spec), (ALL, Vec::new()), "{:?}", spec);
+        }
+    }
+
+    #[test]
+    fn test_parse_reports_unknown_groups() {
+        for (spec, expected, unknown) in [
+            ("stat,bogus", STAT, vec!["bogus"]),
+            ("-bogus", ALL, vec!["bogus"]),
+            ("bogus", 0, vec!["bogus"]),
+            ("stats,-opn,io", IO, vec!["stats", "opn"]),
+        ] {
+            assert_eq!(
+                parse_all(spec),
+                (expected, unknown.iter().map(|s| s.to_string()).collect()),
+                "{:?}",
+                spec
+            );
+        }
+    }
+}
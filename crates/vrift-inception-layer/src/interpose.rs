@@ -2,11 +2,11 @@
 //! Safety: All extern "C" functions here are dangerous FFI and must be used correctly.
 // Clippy lint checks enabled
 
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 use crate::syscalls::dir::{
     chdir_inception, closedir_inception, getcwd_inception, opendir_inception, readdir_inception,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 use crate::syscalls::io::{
     close_inception, dup2_inception, dup_inception, fchdir_inception, ftruncate_inception,
     lseek_inception, read_inception, sendfile_inception, write_inception,
@@ -29,7 +29,7 @@ extern "C" {
         options: libc::c_ulong,
     ) -> c_int;
 }
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 use crate::syscalls::misc::{
     chflags_inception, chmod_inception, chown_inception, exchangedata_inception, execve_inception,
     faccessat_inception, fchflags_inception, fchmod_inception, fchmodat_inception,
@@ -41,12 +41,16 @@ use crate::syscalls::misc::{
     utimensat_inception, utimes_inception,
 };
 
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
+use crate::syscalls::loader::dlopen_inception;
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 use crate::syscalls::mmap::{mmap_inception, munmap_inception};
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 use crate::syscalls::path::realpath_inception;
 
-use libc::{c_char, c_int, c_void, mode_t};
+#[cfg(any(target_os = "macos", not(feature = "minimal")))]
+use libc::mode_t;
+use libc::{c_char, c_int, c_void};
 
 #[cfg(target_os = "macos")]
 use libc::{c_long, dirent, pid_t, timeval, DIR};
@@ -302,14 +306,14 @@ extern "C" {
 }
 
 // Active Interpositions (Group 1 + Core)
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_OPEN: Interpose = Interpose {
     new_func: c_open_bridge as _,
     old_func: real_open as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_OPENAT: Interpose = Interpose {
@@ -330,7 +334,7 @@ pub static IT_LSTAT: Interpose = Interpose {
     new_func: c_lstat_bridge as _,
     old_func: real_lstat as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_FSTAT: Interpose = Interpose {
@@ -344,49 +348,49 @@ pub static IT_FSTATAT: Interpose = Interpose {
     new_func: c_fstatat_bridge as _,
     old_func: real_fstatat as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_ACCESS: Interpose = Interpose {
     new_func: c_access_bridge as _,
     old_func: real_access as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_READLINK: Interpose = Interpose {
     new_func: c_readlink_bridge as _,
     old_func: real_readlink as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_CREAT: Interpose = Interpose {
     new_func: c_creat_bridge as _,
     old_func: real_creat as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_GETATTRLIST: Interpose = Interpose {
     new_func: c_getattrlist_bridge as _,
     old_func: real_getattrlist as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_SETATTRLIST: Interpose = Interpose {
     new_func: c_setattrlist_bridge as _,
     old_func: real_setattrlist as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_RENAME: Interpose = Interpose {
     new_func: c_rename_bridge as _,
     old_func: real_rename as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_RENAMEAT: Interpose = Interpose {
@@ -394,7 +398,7 @@ pub static IT_RENAMEAT: Interpose = Interpose {
     old_func: real_renameat as _,
 };
 
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__nointerpose"]
 #[used]
 pub static IT_FCNTL: Interpose = Interpose {
@@ -402,14 +406,14 @@ pub static IT_FCNTL: Interpose = Interpose {
     old_func: real_fcntl as _,
 };
 
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__nointerpose"]
 #[used]
 pub static IT_MMAP: Interpose = Interpose {
     new_func: mmap_inception as _,
     old_func: real_mmap as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__nointerpose"]
 #[used]
 pub static IT_MUNMAP: Interpose = Interpose {
@@ -418,84 +422,84 @@ pub static IT_MUNMAP: Interpose = Interpose {
 };
 
 // Passthrough / Inactive Interpositions (Sectioned to __nointerpose to avoid dyld resolution overhead)
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__nointerpose"]
 #[used]
 pub static IT_WRITE: Interpose = Interpose {
     new_func: write_inception as _,
     old_func: real_write as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__nointerpose"]
 #[used]
 pub static IT_READ: Interpose = Interpose {
     new_func: read_inception as _,
     old_func: real_read as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__nointerpose"]
 #[used]
 pub static IT_CLOSE: Interpose = Interpose {
     new_func: close_inception as _,
     old_func: real_close as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__nointerpose"]
 #[used]
 pub static IT_OPENDIR: Interpose = Interpose {
     new_func: opendir_inception as _,
     old_func: real_opendir as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__nointerpose"]
 #[used]
 pub static IT_READDIR: Interpose = Interpose {
     new_func: readdir_inception as _,
     old_func: real_readdir as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__nointerpose"]
 #[used]
 pub static IT_CLOSEDIR: Interpose = Interpose {
     new_func: closedir_inception as _,
     old_func: real_closedir as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_REALPATH: Interpose = Interpose {
     new_func: realpath_inception as _,
     old_func: real_realpath as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_REALPATH_DARWIN: Interpose = Interpose {
     new_func: realpath_inception as _,
     old_func: real_realpath_darwin as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_GETCWD: Interpose = Interpose {
     new_func: getcwd_inception as _,
     old_func: real_getcwd as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_CHDIR: Interpose = Interpose {
     new_func: chdir_inception as _,
     old_func: real_chdir as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_UNLINK: Interpose = Interpose {
     new_func: unlink_inception as _,
     old_func: real_unlink as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_RMDIR: Interpose = Interpose {
@@ -504,168 +508,168 @@ pub static IT_RMDIR: Interpose = Interpose {
 };
 // NOTE: utimensat is a libc wrapper on macOS (no kernel syscall).
 // Using __nointerpose to avoid dlsym-triggered infinite recursion.
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__nointerpose"]
 #[used]
 pub static IT_UTIMENSAT: Interpose = Interpose {
     new_func: utimensat_inception as _,
     old_func: real_utimensat as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_MKDIR: Interpose = Interpose {
     new_func: mkdir_inception as _,
     old_func: real_mkdir as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_SYMLINK: Interpose = Interpose {
     new_func: symlink_inception as _,
     old_func: real_symlink as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__nointerpose"]
 #[used]
 pub static IT_FLOCK: Interpose = Interpose {
     new_func: flock_inception as _,
     old_func: real_flock as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_LINK: Interpose = Interpose {
     new_func: link_inception as _,
     old_func: real_link as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_LINKAT: Interpose = Interpose {
     new_func: linkat_inception as _,
     old_func: real_linkat as _,
 };
-#[cfg(target_os = "macos")]
-#[link_section = "__DATA,__nointerpose"]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
+#[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_EXECVE: Interpose = Interpose {
     new_func: execve_inception as _,
     old_func: real_execve as _,
 };
-#[cfg(target_os = "macos")]
-#[link_section = "__DATA,__nointerpose"]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
+#[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_POSIX_SPAWN: Interpose = Interpose {
     new_func: posix_spawn_inception as _,
     old_func: real_posix_spawn as _,
 };
-#[cfg(target_os = "macos")]
-#[link_section = "__DATA,__nointerpose"]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
+#[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_POSIX_SPAWNP: Interpose = Interpose {
     new_func: posix_spawnp_inception as _,
     old_func: real_posix_spawnp as _,
 };
-#[cfg(target_os = "macos")]
-#[link_section = "__DATA,__nointerpose"]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
+#[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_DLOPEN: Interpose = Interpose {
-    new_func: libc::dlopen as _,
+    new_func: dlopen_inception as _,
     old_func: real_dlopen as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__nointerpose"]
 #[used]
 pub static IT_DLSYM: Interpose = Interpose {
     new_func: libc::dlsym as _,
     old_func: real_dlsym as _,
 };
-#[cfg(target_os = "macos")]
-#[link_section = "__DATA,__nointerpose"]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
+#[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_FACCESSAT: Interpose = Interpose {
     new_func: faccessat_inception as _,
     old_func: real_faccessat as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_CHMOD: Interpose = Interpose {
     new_func: chmod_inception as _,
     old_func: real_chmod as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_FCHMODAT: Interpose = Interpose {
     new_func: fchmodat_inception as _,
     old_func: real_fchmodat as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_TRUNCATE: Interpose = Interpose {
     new_func: truncate_inception as _,
     old_func: real_truncate as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_FTRUNCATE: Interpose = Interpose {
     new_func: ftruncate_inception as _,
     old_func: real_ftruncate as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_CHFLAGS: Interpose = Interpose {
     new_func: chflags_inception as _,
     old_func: real_chflags as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_SETXATTR: Interpose = Interpose {
     new_func: setxattr_inception as _,
     old_func: real_setxattr as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_REMOVEXATTR: Interpose = Interpose {
     new_func: removexattr_inception as _,
     old_func: real_removexattr as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_UTIMES: Interpose = Interpose {
     new_func: utimes_inception as _,
     old_func: real_utimes as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__nointerpose"]
 #[used]
 pub static IT_DUP: Interpose = Interpose {
     new_func: dup_inception as _,
     old_func: real_dup as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__nointerpose"]
 #[used]
 pub static IT_DUP2: Interpose = Interpose {
     new_func: dup2_inception as _,
     old_func: real_dup2 as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__nointerpose"]
 #[used]
 pub static IT_FCHDIR: Interpose = Interpose {
     new_func: fchdir_inception as _,
     old_func: real_fchdir as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__nointerpose"]
 #[used]
 pub static IT_LSEEK: Interpose = Interpose {
@@ -680,7 +684,7 @@ pub static IT_UNLINKAT: Interpose = Interpose {
     old_func: real_unlinkat as _,
 };
 
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_MKDIRAT: Interpose = Interpose {
@@ -690,7 +694,7 @@ pub static IT_MKDIRAT: Interpose = Interpose {
 
 // NOTE: futimens is a libc wrapper on macOS (no kernel syscall).
 // Using __nointerpose to avoid dlsym-triggered infinite recursion.
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__nointerpose"]
 #[used]
 pub static IT_FUTIMENS: Interpose = Interpose {
@@ -706,35 +710,35 @@ pub static IT_SYMLINKAT: Interpose = Interpose {
     old_func: real_symlinkat as _,
 };
 
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_FUTIMES: Interpose = Interpose {
     new_func: futimes_inception as _,
     old_func: real_futimes as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_FCHFLAGS: Interpose = Interpose {
     new_func: fchflags_inception as _,
     old_func: real_fchflags as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_SENDFILE: Interpose = Interpose {
     new_func: sendfile_inception as _,
     old_func: real_sendfile as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_FCHMOD: Interpose = Interpose {
     new_func: fchmod_inception as _,
     old_func: real_fchmod as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__nointerpose"]
 #[used]
 pub static IT_SETRLIMIT: Interpose = Interpose {
@@ -743,21 +747,21 @@ pub static IT_SETRLIMIT: Interpose = Interpose {
 };
 
 // P0-P1 Gap Fix: fchown/fchownat/exchangedata interposition
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_FCHOWN: Interpose = Interpose {
     new_func: fchown_inception as _,
     old_func: real_fchown as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_FCHOWNAT: Interpose = Interpose {
     new_func: fchownat_inception as _,
     old_func: real_fchownat as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_EXCHANGEDATA: Interpose = Interpose {
@@ -766,21 +770,21 @@ pub static IT_EXCHANGEDATA: Interpose = Interpose {
 };
 
 // Gap Fix: chown/lchown/readlinkat interposition
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_CHOWN: Interpose = Interpose {
     new_func: chown_inception as _,
     old_func: real_chown as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_LCHOWN: Interpose = Interpose {
     new_func: lchown_inception as _,
     old_func: real_lchown as _,
 };
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[link_section = "__DATA,__interpose"]
 #[used]
 pub static IT_READLINKAT: Interpose = Interpose {
@@ -793,20 +797,88 @@ pub static IT_READLINKAT: Interpose = Interpose {
 // =============================================================================
 // On Linux, LD_PRELOAD works by symbol interposition. We export functions
 // with the same names as libc functions to intercept them.
+//
+// The `minimal` feature keeps only the path-based stat family (see the end
+// of this section); everything that opens, mutates or watches VFS paths is
+// left out.
 
-#[cfg(target_os = "linux")]
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
 #[no_mangle]
 pub unsafe extern "C" fn open(path: *const c_char, flags: c_int, mode: mode_t) -> c_int {
     crate::syscalls::open::open_inception_c_impl(path, flags, mode)
 }
 
-#[cfg(target_os = "linux")]
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
+#[no_mangle]
+pub unsafe extern "C" fn dlopen(path: *const c_char, flags: c_int) -> *mut c_void {
+    crate::syscalls::loader::dlopen_impl(path, flags)
+}
+
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
+#[no_mangle]
+pub unsafe extern "C" fn execve(
+    path: *const c_char,
+    argv: *const *const c_char,
+    envp: *const *const c_char,
+) -> c_int {
+    crate::syscalls::process::execve_impl(path, argv, envp)
+}
+
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
+#[no_mangle]
+pub unsafe extern "C" fn execv(path: *const c_char, argv: *const *const c_char) -> c_int {
+    crate::syscalls::process::execv_impl(path, argv)
+}
+
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
+#[no_mangle]
+pub unsafe extern "C" fn execvp(file: *const c_char, argv: *const *const c_char) -> c_int {
+    crate::syscalls::process::execvpe_impl(file, argv, None)
+}
+
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
+#[no_mangle]
+pub unsafe extern "C" fn execvpe(
+    file: *const c_char,
+    argv: *const *const c_char,
+    envp: *const *const c_char,
+) -> c_int {
+    crate::syscalls::process::execvpe_impl(file, argv, Some(envp))
+}
+
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
+#[no_mangle]
+pub unsafe extern "C" fn posix_spawn(
+    pid: *mut libc::pid_t,
+    path: *const c_char,
+    fa: *const libc::posix_spawn_file_actions_t,
+    attr: *const libc::posix_spawnattr_t,
+    argv: *const *const c_char,
+    envp: *const *const c_char,
+) -> c_int {
+    crate::syscalls::process::posix_spawn_impl(pid, path, fa, attr, argv, envp, false)
+}
+
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
+#[no_mangle]
+pub unsafe extern "C" fn posix_spawnp(
+    pid: *mut libc::pid_t,
+    file: *const c_char,
+    fa: *const libc::posix_spawn_file_actions_t,
+    attr: *const libc::posix_spawnattr_t,
+    argv: *const *const c_char,
+    envp: *const *const c_char,
+) -> c_int {
+    crate::syscalls::process::posix_spawn_impl(pid, file, fa, attr, argv, envp, true)
+}
+
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
 #[no_mangle]
 pub unsafe extern "C" fn open64(path: *const c_char, flags: c_int, mode: mode_t) -> c_int {
     crate::syscalls::open::open_inception_c_impl(path, flags, mode)
 }
 
-#[cfg(target_os = "linux")]
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
 #[no_mangle]
 pub unsafe extern "C" fn openat(
     dirfd: c_int,
@@ -817,7 +889,7 @@ pub unsafe extern "C" fn openat(
     crate::syscalls::open::velo_openat_impl(dirfd, path, flags, mode)
 }
 
-#[cfg(target_os = "linux")]
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
 #[no_mangle]
 pub unsafe extern "C" fn openat64(
     dirfd: c_int,
@@ -829,13 +901,13 @@ pub unsafe extern "C" fn openat64(
 }
 
 // Linux chmod interception - blocks VFS mutations
-#[cfg(target_os = "linux")]
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
 #[no_mangle]
 pub unsafe extern "C" fn chmod(path: *const c_char, mode: mode_t) -> c_int {
     crate::syscalls::misc::chmod_inception(path, mode)
 }
 
-#[cfg(target_os = "linux")]
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
 #[no_mangle]
 pub unsafe extern "C" fn fchmodat(
     dirfd: c_int,
@@ -847,25 +919,25 @@ pub unsafe extern "C" fn fchmodat(
 }
 
 // Linux unlink/rm interception - blocks VFS mutations
-#[cfg(target_os = "linux")]
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
 #[no_mangle]
 pub unsafe extern "C" fn unlink(path: *const c_char) -> c_int {
     crate::syscalls::misc::unlink_inception(path)
 }
 
-#[cfg(target_os = "linux")]
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
 #[no_mangle]
 pub unsafe extern "C" fn unlinkat(dirfd: c_int, path: *const c_char, flags: c_int) -> c_int {
     crate::syscalls::misc::unlinkat_inception(dirfd, path, flags)
 }
 
-#[cfg(target_os = "linux")]
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
 #[no_mangle]
 pub unsafe extern "C" fn symlink(oldpath: *const c_char, newpath: *const c_char) -> c_int {
     crate::syscalls::misc::symlink_inception(oldpath, newpath)
 }
 
-#[cfg(target_os = "linux")]
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
 #[no_mangle]
 pub unsafe extern "C" fn symlinkat(
     oldpath: *const c_char,
@@ -875,32 +947,43 @@ pub unsafe extern "C" fn symlinkat(
     crate::syscalls::misc::symlinkat_inception(oldpath, newdirfd, newpath)
 }
 
-#[cfg(target_os = "linux")]
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
 #[no_mangle]
 pub unsafe extern "C" fn mkdir(path: *const c_char, mode: mode_t) -> c_int {
     crate::syscalls::misc::mkdir_inception(path, mode)
 }
 
-#[cfg(target_os = "linux")]
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
 #[no_mangle]
 pub unsafe extern "C" fn mkdirat(dirfd: c_int, path: *const c_char, mode: mode_t) -> c_int {
     crate::syscalls::misc::mkdirat_inception(dirfd, path, mode)
 }
 
-#[cfg(target_os = "linux")]
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
 #[no_mangle]
 pub unsafe extern "C" fn rmdir(path: *const c_char) -> c_int {
     crate::syscalls::misc::rmdir_inception(path)
 }
 
-#[cfg(target_os = "linux")]
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
 #[no_mangle]
 pub unsafe extern "C" fn access(path: *const c_char, mode: c_int) -> c_int {
     crate::syscalls::stat::access_inception(path, mode)
 }
 
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
+#[no_mangle]
+pub unsafe extern "C" fn faccessat(
+    dirfd: c_int,
+    path: *const c_char,
+    mode: c_int,
+    flags: c_int,
+) -> c_int {
+    crate::syscalls::misc::faccessat_inception(dirfd, path, mode, flags)
+}
+
 // Linux utimensat/touch interception
-#[cfg(target_os = "linux")]
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
 #[no_mangle]
 pub unsafe extern "C" fn utimensat(
     dirfd: c_int,
@@ -912,33 +995,33 @@ pub unsafe extern "C" fn utimensat(
 }
 
 // Linux utimes interception (for touch command)
-#[cfg(target_os = "linux")]
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
 #[no_mangle]
 pub unsafe extern "C" fn utimes(path: *const c_char, times: *const libc::timeval) -> c_int {
     crate::syscalls::misc::utimes_inception(path, times)
 }
 
-#[cfg(target_os = "linux")]
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
 #[no_mangle]
 pub unsafe extern "C" fn utime(path: *const c_char, times: *const libc::c_void) -> c_int {
     crate::syscalls::misc::utime_inception(path, times)
 }
 
 // Linux futimes interception (FD-based timestamp mutation)
-#[cfg(target_os = "linux")]
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
 #[no_mangle]
 pub unsafe extern "C" fn futimes(fd: c_int, times: *const libc::timeval) -> c_int {
     crate::syscalls::misc::futimes_inception(fd, times)
 }
 
 // P0-P1 Gap Fix: Linux fchown/fchownat exports
-#[cfg(target_os = "linux")]
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
 #[no_mangle]
 pub unsafe extern "C" fn fchown(fd: c_int, owner: libc::uid_t, group: libc::gid_t) -> c_int {
     crate::syscalls::misc::fchown_inception(fd, owner, group)
 }
 
-#[cfg(target_os = "linux")]
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
 #[no_mangle]
 pub unsafe extern "C" fn chown(
     path: *const c_char,
@@ -948,7 +1031,7 @@ pub unsafe extern "C" fn chown(
     crate::syscalls::misc::chown_inception(path, owner, group)
 }
 
-#[cfg(target_os = "linux")]
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
 #[no_mangle]
 pub unsafe extern "C" fn lchown(
     path: *const c_char,
@@ -958,7 +1041,7 @@ pub unsafe extern "C" fn lchown(
     crate::syscalls::misc::lchown_inception(path, owner, group)
 }
 
-#[cfg(target_os = "linux")]
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
 #[no_mangle]
 pub unsafe extern "C" fn readlinkat(
     dirfd: c_int,
@@ -969,7 +1052,7 @@ pub unsafe extern "C" fn readlinkat(
     crate::syscalls::misc::readlinkat_inception(dirfd, path, buf, bufsiz)
 }
 
-#[cfg(target_os = "linux")]
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
 #[no_mangle]
 pub unsafe extern "C" fn fchownat(
     dirfd: c_int,
@@ -981,25 +1064,57 @@ pub unsafe extern "C" fn fchownat(
     crate::syscalls::misc::fchownat_inception(dirfd, path, owner, group, flags)
 }
 
-#[cfg(target_os = "linux")]
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
 #[no_mangle]
 pub unsafe extern "C" fn truncate(path: *const c_char, length: libc::off_t) -> c_int {
     crate::syscalls::misc::truncate_inception(path, length)
 }
 
-#[cfg(target_os = "linux")]
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
+#[no_mangle]
+pub unsafe extern "C" fn truncate64(path: *const c_char, length: libc::off_t) -> c_int {
+    crate::syscalls::misc::truncate_inception(path, length)
+}
+
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
 #[no_mangle]
 pub unsafe extern "C" fn ftruncate(fd: c_int, length: libc::off_t) -> c_int {
     crate::syscalls::io::ftruncate_inception(fd, length)
 }
 
-#[cfg(target_os = "linux")]
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
+#[no_mangle]
+pub unsafe extern "C" fn ftruncate64(fd: c_int, length: libc::off_t) -> c_int {
+    crate::syscalls::io::ftruncate_inception(fd, length)
+}
+
+// CoW write-back: closing a staged copy queues its reingest
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
+#[no_mangle]
+pub unsafe extern "C" fn close(fd: c_int) -> c_int {
+    crate::syscalls::io::close_inception(fd)
+}
+
+// A duplicate shares the staged copy, which is reingested on its last close
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
+#[no_mangle]
+pub unsafe extern "C" fn dup(oldfd: c_int) -> c_int {
+    crate::syscalls::io::dup_inception(oldfd)
+}
+
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
+#[no_mangle]
+pub unsafe extern "C" fn dup2(oldfd: c_int, newfd: c_int) -> c_int {
+    crate::syscalls::io::dup2_inception(oldfd, newfd)
+}
+
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
 #[no_mangle]
 pub unsafe extern "C" fn rename(old: *const c_char, new: *const c_char) -> c_int {
     crate::syscalls::misc::rename_inception_linux(old, new)
 }
 
-#[cfg(target_os = "linux")]
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
 #[no_mangle]
 pub unsafe extern "C" fn renameat(
     oldfd: c_int,
@@ -1010,13 +1125,13 @@ pub unsafe extern "C" fn renameat(
     crate::syscalls::misc::renameat_inception_linux(oldfd, old, newfd, new)
 }
 
-#[cfg(target_os = "linux")]
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
 #[no_mangle]
 pub unsafe extern "C" fn link(oldpath: *const c_char, newpath: *const c_char) -> c_int {
     crate::syscalls::misc::link_inception(oldpath, newpath)
 }
 
-#[cfg(target_os = "linux")]
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
 #[no_mangle]
 pub unsafe extern "C" fn linkat(
     olddirfd: c_int,
@@ -1028,13 +1143,13 @@ pub unsafe extern "C" fn linkat(
     crate::syscalls::misc::linkat_inception(olddirfd, oldpath, newdirfd, newpath, flags)
 }
 
-#[cfg(target_os = "linux")]
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
 #[no_mangle]
 pub unsafe extern "C" fn futimens(fd: c_int, times: *const libc::timespec) -> c_int {
     crate::syscalls::misc::futimens_inception(fd, times)
 }
 
-#[cfg(target_os = "linux")]
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
 #[no_mangle]
 pub unsafe extern "C" fn sendfile(
     out_fd: c_int,
@@ -1045,7 +1160,7 @@ pub unsafe extern "C" fn sendfile(
     crate::syscalls::io::sendfile_inception(out_fd, in_fd, offset, count)
 }
 
-#[cfg(target_os = "linux")]
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
 #[no_mangle]
 pub unsafe extern "C" fn copy_file_range(
     fd_in: c_int,
@@ -1058,7 +1173,7 @@ pub unsafe extern "C" fn copy_file_range(
     crate::syscalls::io::copy_file_range_inception(fd_in, off_in, fd_out, off_out, len, flags)
 }
 
-#[cfg(target_os = "linux")]
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
 #[no_mangle]
 pub unsafe extern "C" fn openat2(
     dirfd: c_int,
@@ -1068,19 +1183,300 @@ pub unsafe extern "C" fn openat2(
 ) -> c_int {
     crate::syscalls::open::openat2_inception(dirfd, p, how as _, size)
 }
-#[cfg(target_os = "linux")]
+// inotify emulation: synthesize change events for VFS paths
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
+#[no_mangle]
+pub unsafe extern "C" fn inotify_init() -> c_int {
+    crate::syscalls::inotify::inotify_init_inception()
+}
+
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
+#[no_mangle]
+pub unsafe extern "C" fn inotify_init1(flags: c_int) -> c_int {
+    crate::syscalls::inotify::inotify_init1_inception(flags)
+}
+
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
+#[no_mangle]
+pub unsafe extern "C" fn inotify_add_watch(fd: c_int, path: *const c_char, mask: u32) -> c_int {
+    crate::syscalls::inotify::inotify_add_watch_inception(fd, path, mask)
+}
+
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
+#[no_mangle]
+pub unsafe extern "C" fn inotify_rm_watch(fd: c_int, wd: c_int) -> c_int {
+    crate::syscalls::inotify::inotify_rm_watch_inception(fd, wd)
+}
+
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
 #[no_mangle]
 pub unsafe extern "C" fn creat(path: *const c_char, mode: mode_t) -> c_int {
     crate::syscalls::open::creat_inception(path, mode)
 }
 
-#[cfg(target_os = "macos")]
+// _FORTIFY_SOURCE builds call these checked variants instead of open/openat
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
+#[no_mangle]
+pub unsafe extern "C" fn __open_2(path: *const c_char, flags: c_int) -> c_int {
+    crate::syscalls::open::open_inception_c_impl(path, flags, 0)
+}
+
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
+#[no_mangle]
+pub unsafe extern "C" fn __open64_2(path: *const c_char, flags: c_int) -> c_int {
+    crate::syscalls::open::open_inception_c_impl(path, flags, 0)
+}
+
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
+#[no_mangle]
+pub unsafe extern "C" fn __openat_2(dirfd: c_int, path: *const c_char, flags: c_int) -> c_int {
+    crate::syscalls::open::velo_openat_impl(dirfd, path, flags, 0)
+}
+
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
+#[no_mangle]
+pub unsafe extern "C" fn __openat64_2(dirfd: c_int, path: *const c_char, flags: c_int) -> c_int {
+    crate::syscalls::open::velo_openat_impl(dirfd, path, flags, 0)
+}
+
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
+#[no_mangle]
+pub unsafe extern "C" fn creat64(path: *const c_char, mode: mode_t) -> c_int {
+    crate::syscalls::open::creat_inception(path, mode)
+}
+
+// stdio opens and closes its fds inside glibc, where the exports above never
+// see them
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
+#[no_mangle]
+pub unsafe extern "C" fn fopen(path: *const c_char, mode: *const c_char) -> *mut libc::FILE {
+    crate::syscalls::open::fopen_inception(path, mode)
+}
+
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
+#[no_mangle]
+pub unsafe extern "C" fn fopen64(path: *const c_char, mode: *const c_char) -> *mut libc::FILE {
+    crate::syscalls::open::fopen_inception(path, mode)
+}
+
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
+#[no_mangle]
+pub unsafe extern "C" fn freopen(
+    path: *const c_char,
+    mode: *const c_char,
+    stream: *mut libc::FILE,
+) -> *mut libc::FILE {
+    crate::syscalls::open::freopen_inception(path, mode, stream)
+}
+
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
+#[no_mangle]
+pub unsafe extern "C" fn freopen64(
+    path: *const c_char,
+    mode: *const c_char,
+    stream: *mut libc::FILE,
+) -> *mut libc::FILE {
+    crate::syscalls::open::freopen_inception(path, mode, stream)
+}
+
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
+#[no_mangle]
+pub unsafe extern "C" fn fclose(file: *mut libc::FILE) -> c_int {
+    crate::syscalls::io::fclose_inception(file)
+}
+
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
+#[no_mangle]
+pub unsafe extern "C" fn readlink(
+    path: *const c_char,
+    buf: *mut c_char,
+    bufsiz: libc::size_t,
+) -> libc::ssize_t {
+    crate::syscalls::path::readlink_inception(path, buf, bufsiz)
+}
+
+// Directory listings merge the manifest into the kernel's stream. `struct
+// dirent` and `struct dirent64` share a layout on LP64 targets.
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
+#[no_mangle]
+pub unsafe extern "C" fn opendir(path: *const c_char) -> *mut libc::DIR {
+    crate::syscalls::dir::opendir_inception(path)
+}
+
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
+#[no_mangle]
+pub unsafe extern "C" fn readdir(dir: *mut libc::DIR) -> *mut libc::dirent {
+    crate::syscalls::dir::readdir64_inception(dir) as *mut libc::dirent
+}
+
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
+#[no_mangle]
+pub unsafe extern "C" fn readdir64(dir: *mut libc::DIR) -> *mut libc::dirent64 {
+    crate::syscalls::dir::readdir64_inception(dir)
+}
+
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
+#[no_mangle]
+pub unsafe extern "C" fn rewinddir(dir: *mut libc::DIR) {
+    crate::syscalls::dir::rewinddir_inception(dir)
+}
+
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
+#[no_mangle]
+pub unsafe extern "C" fn closedir(dir: *mut libc::DIR) -> c_int {
+    crate::syscalls::dir::closedir_inception(dir)
+}
+
+// An fd is only in VFS territory if it was opened through the exports above
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
+#[no_mangle]
+pub unsafe extern "C" fn fstat(fd: c_int, buf: *mut libc::stat) -> c_int {
+    crate::syscalls::stat::fstat_inception(fd, buf)
+}
+
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
+#[no_mangle]
+pub unsafe extern "C" fn fstat64(fd: c_int, buf: *mut libc::stat64) -> c_int {
+    crate::syscalls::stat::fstat_inception(fd, buf as *mut libc::stat)
+}
+
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
+#[no_mangle]
+pub unsafe extern "C" fn __fxstat(_ver: c_int, fd: c_int, buf: *mut libc::stat) -> c_int {
+    crate::syscalls::stat::fstat_inception(fd, buf)
+}
+
+#[cfg(all(target_os = "linux", not(feature = "minimal")))]
+#[no_mangle]
+pub unsafe extern "C" fn __fxstat64(_ver: c_int, fd: c_int, buf: *mut libc::stat64) -> c_int {
+    crate::syscalls::stat::fstat_inception(fd, buf as *mut libc::stat)
+}
+
+// Path-based stat family, in every build: answered from the VDir mmap
+// (misses fall through to the kernel). glibc >= 2.33 exports these names
+// directly; older ones go through the versioned `__xstat` entry points. The
+// 64-bit variants share the layout of `struct stat` on LP64 targets.
+#[cfg(target_os = "linux")]
+#[no_mangle]
+pub unsafe extern "C" fn stat(path: *const c_char, buf: *mut libc::stat) -> c_int {
+    crate::syscalls::stat::stat_inception(path, buf)
+}
+
+#[cfg(target_os = "linux")]
+#[no_mangle]
+pub unsafe extern "C" fn stat64(path: *const c_char, buf: *mut libc::stat64) -> c_int {
+    crate::syscalls::stat::stat_inception(path, buf as *mut libc::stat)
+}
+
+#[cfg(target_os = "linux")]
+#[no_mangle]
+pub unsafe extern "C" fn lstat(path: *const c_char, buf: *mut libc::stat) -> c_int {
+    crate::syscalls::stat::lstat_inception(path, buf)
+}
+
+#[cfg(target_os = "linux")]
+#[no_mangle]
+pub unsafe extern "C" fn lstat64(path: *const c_char, buf: *mut libc::stat64) -> c_int {
+    crate::syscalls::stat::lstat_inception(path, buf as *mut libc::stat)
+}
+
+#[cfg(target_os = "linux")]
+#[no_mangle]
+pub unsafe extern "C" fn fstatat(
+    dirfd: c_int,
+    path: *const c_char,
+    buf: *mut libc::stat,
+    flags: c_int,
+) -> c_int {
+    crate::syscalls::stat::fstatat_inception(dirfd, path, buf, flags)
+}
+
+#[cfg(target_os = "linux")]
+#[no_mangle]
+pub unsafe extern "C" fn fstatat64(
+    dirfd: c_int,
+    path: *const c_char,
+    buf: *mut libc::stat64,
+    flags: c_int,
+) -> c_int {
+    crate::syscalls::stat::fstatat_inception(dirfd, path, buf as *mut libc::stat, flags)
+}
+
+#[cfg(target_os = "linux")]
+#[no_mangle]
+pub unsafe extern "C" fn statx(
+    dirfd: c_int,
+    path: *const c_char,
+    flags: c_int,
+    mask: libc::c_uint,
+    buf: *mut c_void,
+) -> c_int {
+    crate::syscalls::stat::statx_inception(dirfd, path, flags, mask, buf as _)
+}
+
+#[cfg(target_os = "linux")]
+#[no_mangle]
+pub unsafe extern "C" fn __xstat(_ver: c_int, path: *const c_char, buf: *mut libc::stat) -> c_int {
+    crate::syscalls::stat::stat_inception(path, buf)
+}
+
+#[cfg(target_os = "linux")]
+#[no_mangle]
+pub unsafe extern "C" fn __xstat64(
+    _ver: c_int,
+    path: *const c_char,
+    buf: *mut libc::stat64,
+) -> c_int {
+    crate::syscalls::stat::stat_inception(path, buf as *mut libc::stat)
+}
+
+#[cfg(target_os = "linux")]
+#[no_mangle]
+pub unsafe extern "C" fn __lxstat(_ver: c_int, path: *const c_char, buf: *mut libc::stat) -> c_int {
+    crate::syscalls::stat::lstat_inception(path, buf)
+}
+
+#[cfg(target_os = "linux")]
+#[no_mangle]
+pub unsafe extern "C" fn __lxstat64(
+    _ver: c_int,
+    path: *const c_char,
+    buf: *mut libc::stat64,
+) -> c_int {
+    crate::syscalls::stat::lstat_inception(path, buf as *mut libc::stat)
+}
+
+#[cfg(target_os = "linux")]
+#[no_mangle]
+pub unsafe extern "C" fn __fxstatat(
+    _ver: c_int,
+    dirfd: c_int,
+    path: *const c_char,
+    buf: *mut libc::stat,
+    flags: c_int,
+) -> c_int {
+    crate::syscalls::stat::fstatat_inception(dirfd, path, buf, flags)
+}
+
+#[cfg(target_os = "linux")]
+#[no_mangle]
+pub unsafe extern "C" fn __fxstatat64(
+    _ver: c_int,
+    dirfd: c_int,
+    path: *const c_char,
+    buf: *mut libc::stat64,
+    flags: c_int,
+) -> c_int {
+    crate::syscalls::stat::fstatat_inception(dirfd, path, buf as *mut libc::stat, flags)
+}
+
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[no_mangle]
 pub unsafe extern "C" fn creat(path: *const c_char, mode: mode_t) -> c_int {
     c_creat_bridge(path, mode)
 }
 
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[no_mangle]
 pub unsafe extern "C" fn getattrlist(
     path: *const c_char,
@@ -1092,7 +1488,7 @@ pub unsafe extern "C" fn getattrlist(
     c_getattrlist_bridge(path, attrlist, attrbuf, attrbufsize, options)
 }
 
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "minimal")))]
 #[no_mangle]
 pub unsafe extern "C" fn setattrlist(
     path: *const c_char,
@@ -12,7 +12,7 @@
 use crate::raw_context::RawContext;
 use libc::c_int;
 use std::ptr;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 
 /// The singleton RawContext for IPC operations.
 /// All raw syscall access must go through this instance.
@@ -28,6 +28,10 @@ unsafe fn ipc_raw_close(fd: c_int) -> c_int {
 /// Raw Unix socket connect using raw syscalls (avoids recursion through inception layer)
 /// RFC-0053: Adds 5 second timeout to prevent UE process states from blocking IPC
 pub(crate) unsafe fn raw_unix_connect(path: &str) -> c_int {
+    if crate::fault::daemon_down() {
+        return -1;
+    }
+
     // Fast-fail: Check if socket file exists before attempting connect
     let path_cstr = match std::ffi::CString::new(path) {
         Ok(p) => p,
@@ -79,6 +83,19 @@ pub(crate) unsafe fn raw_unix_connect(path: &str) -> c_int {
         &timeout as *const _ as *const libc::c_void,
         std::mem::size_of::<libc::timeval>() as libc::socklen_t,
     );
+    // A daemon that went away fails writes with EPIPE instead of killing the
+    // host with SIGPIPE (Linux gets the same from MSG_NOSIGNAL, see send_all)
+    #[cfg(target_os = "macos")]
+    {
+        let on: c_int = 1;
+        libc::setsockopt(
+            fd,
+            libc::SOL_SOCKET,
+            libc::SO_NOSIGPIPE,
+            &on as *const _ as *const libc::c_void,
+            std::mem::size_of::<c_int>() as libc::socklen_t,
+        );
+    }
 
     let mut addr: libc::sockaddr_un = std::mem::zeroed();
     addr.sun_family = libc::AF_UNIX as libc::sa_family_t;
@@ -108,6 +125,33 @@ pub(crate) unsafe fn raw_write_all(fd: c_int, data: &[u8]) -> bool {
     CTX.write_all(fd, data)
 }
 
+/// `raw_write_all` for IPC sockets: a closed peer fails the write with EPIPE
+/// rather than raising SIGPIPE in the host process. send is not interposed.
+unsafe fn send_all(fd: c_int, data: &[u8]) -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        let mut sent = 0;
+        while sent < data.len() {
+            let n = libc::send(
+                fd,
+                data[sent..].as_ptr() as *const libc::c_void,
+                data.len() - sent,
+                libc::MSG_NOSIGNAL,
+            );
+            if n <= 0 {
+                return false;
+            }
+            sent += n as usize;
+        }
+        true
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        // SO_NOSIGPIPE is set on the socket by raw_unix_connect
+        raw_write_all(fd, data)
+    }
+}
+
 /// Raw read using RawContext (avoids recursion through inception layer)
 pub(crate) unsafe fn raw_read_exact(fd: c_int, buf: &mut [u8]) -> bool {
     CTX.read_exact(fd, buf)
@@ -119,10 +163,19 @@ pub(crate) unsafe fn raw_read_exact(fd: c_int, buf: &mut [u8]) -> bool {
 unsafe fn sync_rpc(
     socket_path: &str,
     request: &vrift_ipc::VeloRequest,
+) -> Option<vrift_ipc::VeloResponse> {
+    sync_rpc_with_timeout(socket_path, request, None)
+}
+
+/// `sync_rpc` for requests the daemon may take longer than the default
+/// socket timeout to answer: waits up to `recv_timeout_secs` for the reply
+unsafe fn sync_rpc_with_timeout(
+    socket_path: &str,
+    request: &vrift_ipc::VeloRequest,
+    recv_timeout_secs: Option<libc::time_t>,
 ) -> Option<vrift_ipc::VeloResponse> {
     use crate::state::{
-        EventType, CIRCUIT_BREAKER_FAILED_COUNT, CIRCUIT_BREAKER_THRESHOLD, CIRCUIT_RECOVERY_DELAY,
-        CIRCUIT_TRIPPED, CIRCUIT_TRIP_TIME,
+        CIRCUIT_BREAKER_FAILED_COUNT, CIRCUIT_RECOVERY_DELAY, CIRCUIT_TRIPPED, CIRCUIT_TRIP_TIME,
     };
     use std::sync::atomic::Ordering;
 
@@ -148,77 +201,392 @@ unsafe fn sync_rpc(
         }
     }
 
-    let fd = raw_unix_connect(socket_path);
+    // Connections waiting longer than the default for a reply are not pooled
+    let pool = recv_timeout_secs.is_none().then_some(&VRIFTD_POOL);
+    let open = || {
+        let fd = managed_connect("vriftd", &|| raw_unix_connect(socket_path));
+        if fd < 0 {
+            return fd;
+        }
+        if let Some(secs) = recv_timeout_secs {
+            let timeout = libc::timeval {
+                tv_sec: secs,
+                tv_usec: 0,
+            };
+            libc::setsockopt(
+                fd,
+                libc::SOL_SOCKET,
+                libc::SO_RCVTIMEO,
+                &timeout as *const _ as *const libc::c_void,
+                std::mem::size_of::<libc::timeval>() as libc::socklen_t,
+            );
+        }
+        register_workspace(fd);
+        fd
+    };
+
+    // Phase 1.2: Manifest operations must be routed to vDird, not daemon. They
+    // only come here while its socket is unknown, and the RegisterAck of a
+    // new connection names it.
+    if is_manifest_request(request) && pool.is_none_or(ConnPool::is_empty) {
+        let fd = open();
+        if fd < 0 {
+            return None;
+        }
+        release(pool, fd);
+        if let Some(state) = crate::state::InceptionLayerState::get_no_spawn() {
+            if !state.vdird_socket_path.is_empty() {
+                return sync_rpc_vdird(&state.vdird_socket_path, request);
+            }
+        }
+    }
+
+    // Send original request (non-manifest ops go to daemon)
+    pooled_rpc(pool, request, &open)
+}
+
+/// RFC-0043: Registration ensures the daemon knows which project manifest to
+/// query. It lasts as long as the connection `fd`.
+unsafe fn register_workspace(fd: c_int) {
+    let project_root = get_project_root();
+    if project_root.is_empty() {
+        return;
+    }
+    let register_req = vrift_ipc::VeloRequest::RegisterWorkspace { project_root };
+    if send_request_on_fd(fd, &register_req) {
+        // Phase 1.2: Parse RegisterAck to extract vDird socket path
+        if let Some(vrift_ipc::VeloResponse::RegisterAck { vdird_socket, .. }) =
+            recv_response_on_fd(fd)
+        {
+            cache_vdird_socket(&vdird_socket);
+        }
+    }
+}
+
+// =============================================================================
+// Connection manager
+// =============================================================================
+// A daemon restarting mid-build leaves a window in which its socket is gone
+// or refuses connections. A process that has reached the daemon before
+// retries through that window with exponential backoff. When the retries run
+// out the shim goes degraded: one warning is logged, manifest lookups answer
+// from the VDir mmap alone, and one caller per IPC_PROBE_INTERVAL_MS tries to
+// connect again. Failed connects count towards the circuit breaker; calls
+// turned away while degraded do not, so the breaker trips only once the
+// daemon has missed CIRCUIT_BREAKER_THRESHOLD probes in a row.
+
+/// Retries after a failed connect; the first waits IPC_RETRY_BASE_MS and each
+/// further one twice as long (620ms in all)
+const IPC_CONNECT_RETRIES: u32 = 5;
+const IPC_RETRY_BASE_MS: u64 = 20;
+const IPC_PROBE_INTERVAL_MS: u64 = 1000;
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Open a connection with `connect` under the policy above; -1 if the peer
+/// (`peer`, for the log) stays unreachable
+unsafe fn managed_connect(peer: &str, connect: &dyn Fn() -> c_int) -> c_int {
+    use crate::state::{EventType, LogLevel, IPC_DEGRADED, IPC_EVER_CONNECTED, IPC_NEXT_PROBE_MS};
+    use std::sync::atomic::Ordering;
+
+    if IPC_DEGRADED.load(Ordering::Relaxed) {
+        let next = IPC_NEXT_PROBE_MS.load(Ordering::Relaxed);
+        let now = now_ms();
+        if now < next
+            || IPC_NEXT_PROBE_MS
+                .compare_exchange(
+                    next,
+                    now + IPC_PROBE_INTERVAL_MS,
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                )
+                .is_err()
+        {
+            return -1;
+        }
+        let fd = connect();
+        if fd < 0 {
+            connect_failed(peer);
+            return -1;
+        }
+        if IPC_DEGRADED.swap(false, Ordering::SeqCst) {
+            log_transition(
+                LogLevel::Warn,
+                format_args!("{} reachable again, leaving degraded mode", peer),
+            );
+            inception_record!(EventType::IpcRecovered, 0, fd);
+        }
+        return connected(fd);
+    }
+
+    let mut fd = connect();
+    if fd < 0 && IPC_EVER_CONNECTED.load(Ordering::Relaxed) {
+        let mut delay_ms = IPC_RETRY_BASE_MS;
+        for _ in 0..IPC_CONNECT_RETRIES {
+            std::thread::sleep(std::time::Duration::from_millis(delay_ms));
+            fd = connect();
+            if fd >= 0 {
+                break;
+            }
+            delay_ms *= 2;
+        }
+    }
     if fd < 0 {
-        let count = CIRCUIT_BREAKER_FAILED_COUNT.fetch_add(1, Ordering::SeqCst) + 1;
-        let threshold = CIRCUIT_BREAKER_THRESHOLD.load(Ordering::Relaxed);
-        inception_record!(EventType::IpcFail, 0, count as i32);
-        if count >= threshold && !CIRCUIT_TRIPPED.swap(true, Ordering::SeqCst) {
-            // Record trip time for auto-recovery
-            let now = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .map(|d| d.as_secs())
-                .unwrap_or(0);
-            CIRCUIT_TRIP_TIME.store(now, Ordering::Relaxed);
-            inception_error!(
-                "DAEMON CONNECTION FAILED {} TIMES. CIRCUIT BREAKER TRIPPED. WILL RETRY AFTER {}s.",
-                count,
-                CIRCUIT_RECOVERY_DELAY.load(Ordering::Relaxed)
+        IPC_NEXT_PROBE_MS.store(now_ms() + IPC_PROBE_INTERVAL_MS, Ordering::Relaxed);
+        if !IPC_DEGRADED.swap(true, Ordering::SeqCst) {
+            log_transition(
+                LogLevel::Warn,
+                format_args!(
+                    "{} unreachable, degraded mode: lookups use the VDir mmap only",
+                    peer
+                ),
             );
-            inception_record!(EventType::CircuitTripped, 0, count as i32);
+            inception_record!(EventType::IpcDegraded, 0, 0);
         }
-        return None;
+        connect_failed(peer);
+        return -1;
     }
+    connected(fd)
+}
 
-    inception_record!(EventType::IpcSuccess, 0, fd);
+fn connected(fd: c_int) -> c_int {
+    use crate::state::{EventType, CIRCUIT_BREAKER_FAILED_COUNT, IPC_EVER_CONNECTED};
+    use std::sync::atomic::Ordering;
 
-    // Success - reset failure count
+    inception_record!(EventType::IpcSuccess, 0, fd);
     CIRCUIT_BREAKER_FAILED_COUNT.store(0, Ordering::Relaxed);
+    IPC_EVER_CONNECTED.store(true, Ordering::Relaxed);
+    fd
+}
+
+fn connect_failed(peer: &str) {
+    use crate::state::{
+        EventType, LogLevel, CIRCUIT_BREAKER_FAILED_COUNT, CIRCUIT_BREAKER_THRESHOLD,
+        CIRCUIT_RECOVERY_DELAY, CIRCUIT_TRIPPED, CIRCUIT_TRIP_TIME,
+    };
+    use std::sync::atomic::Ordering;
 
-    // RFC-0043: Registration ensures the daemon knows which project manifest to query.
+    let count = CIRCUIT_BREAKER_FAILED_COUNT.fetch_add(1, Ordering::SeqCst) + 1;
+    let threshold = CIRCUIT_BREAKER_THRESHOLD.load(Ordering::Relaxed);
+    inception_record!(EventType::IpcFail, 0, count as i32);
+    if count >= threshold && !CIRCUIT_TRIPPED.swap(true, Ordering::SeqCst) {
+        // Record trip time for auto-recovery
+        CIRCUIT_TRIP_TIME.store(now_ms() / 1000, Ordering::Relaxed);
+        log_transition(
+            LogLevel::Error,
+            format_args!(
+                "{} CONNECTION FAILED {} TIMES. CIRCUIT BREAKER TRIPPED. WILL RETRY AFTER {}s.",
+                peer,
+                count,
+                CIRCUIT_RECOVERY_DELAY.load(Ordering::Relaxed)
+            ),
+        );
+        inception_record!(EventType::CircuitTripped, 0, count as i32);
+    }
+}
+
+/// Log a change of connection state. These mostly happen inside a hook, where
+/// the inception_* macros stay silent to keep the hook from re-entering
+/// itself; log_record only does raw writes, and each transition logs once,
+/// so neither the guard nor the rate limit is needed.
+fn log_transition(level: crate::state::LogLevel, args: std::fmt::Arguments) {
+    use crate::state::{log_record, LOG_LEVEL};
+    use std::sync::atomic::Ordering;
+
+    if LOG_LEVEL.load(Ordering::Relaxed) <= level as u8 {
+        log_record(level, 0, args);
+    }
+}
+
+/// Ask vriftd for this workspace's vDird again, which respawns one that
+/// went away; true once vriftd acknowledged
+unsafe fn reregister_workspace() -> bool {
+    let Some(state) = crate::state::InceptionLayerState::get_no_spawn() else {
+        return false;
+    };
     let project_root = get_project_root();
+    if project_root.is_empty() {
+        return false;
+    }
+    let fd = raw_unix_connect(&state.socket_path);
+    if fd < 0 {
+        return false;
+    }
+    let request = vrift_ipc::VeloRequest::RegisterWorkspace { project_root };
+    let acked = send_request_on_fd(fd, &request)
+        && matches!(
+            recv_response_on_fd(fd),
+            Some(vrift_ipc::VeloResponse::RegisterAck { .. })
+        );
+    ipc_raw_close(fd);
+    acked
+}
 
-    if !project_root.is_empty() {
-        let register_req = vrift_ipc::VeloRequest::RegisterWorkspace { project_root };
-        if send_request_on_fd(fd, &register_req) {
-            // Phase 1.2: Parse RegisterAck to extract vDird socket path
-            if let Some(vrift_ipc::VeloResponse::RegisterAck { vdird_socket, .. }) =
-                recv_response_on_fd(fd)
-            {
-                cache_vdird_socket(&vdird_socket);
+// =============================================================================
+// Connection pool
+// =============================================================================
+// A lookup used to cost a connect (and, to vriftd, a RegisterWorkspace round
+// trip) before its request went out. Connections now outlive their call: a
+// call that got its reply parks the connection in its peer's pool, and the
+// next call to that peer takes it from there. Each pool is a few lock-free
+// slots, so concurrent threads each get a connection without waiting on one
+// another; a connection that finds every slot taken is closed.
+//
+// A parked connection can go bad while it waits:
+// - the daemon restarted: the request fails to send (EPIPE, no SIGPIPE),
+//   which is safe to retry, so the call reconnects and sends it again;
+// - the reply never comes: only queries are sent again, a mutation may
+//   already have taken effect;
+// - the host closed or reused its fd: the socket inode recorded when it was
+//   parked no longer matches, and the slot is dropped without touching fd;
+// - fork: the child would share the socket with its parent, so the child
+//   closes its copies (pool_after_fork).
+
+const POOL_SLOTS: usize = 4;
+const POOL_EMPTY: u64 = u64::MAX;
+
+static VRIFTD_POOL: ConnPool = ConnPool::new();
+static VDIRD_POOL: ConnPool = ConnPool::new();
+static VDIRD_CONTROL_POOL: ConnPool = ConnPool::new();
+static POOL_ATFORK_REGISTERED: AtomicBool = AtomicBool::new(false);
+
+/// Idle connections to one peer. A slot holds an fd in its low half and the
+/// low bits of its socket's inode in its high half.
+struct ConnPool {
+    slots: [AtomicU64; POOL_SLOTS],
+}
 
-                // Phase 1.2: Manifest operations must be routed to vDird, not daemon.
-                // After caching the vDird socket, re-route manifest requests immediately.
-                if is_manifest_request(request) && !vdird_socket.is_empty() {
-                    ipc_raw_close(fd);
-                    // Route directly to the newly-cached vDird socket
-                    let vdird_fd = raw_unix_connect(&vdird_socket);
-                    if vdird_fd < 0 {
-                        return None;
-                    }
-                    if !send_request_on_fd(vdird_fd, request) {
-                        ipc_raw_close(vdird_fd);
-                        return None;
-                    }
-                    let response = recv_response_on_fd(vdird_fd);
-                    ipc_raw_close(vdird_fd);
-                    return response;
-                }
+impl ConnPool {
+    const fn new() -> Self {
+        Self {
+            slots: [const { AtomicU64::new(POOL_EMPTY) }; POOL_SLOTS],
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.slots
+            .iter()
+            .all(|slot| slot.load(Ordering::Relaxed) == POOL_EMPTY)
+    }
+
+    /// A parked connection that is still the socket it was parked as
+    unsafe fn take(&self) -> Option<c_int> {
+        for slot in &self.slots {
+            if slot.load(Ordering::Relaxed) == POOL_EMPTY {
+                continue;
+            }
+            let parked = slot.swap(POOL_EMPTY, Ordering::Acquire);
+            if parked == POOL_EMPTY {
+                continue;
+            }
+            let fd = parked as u32 as c_int;
+            if socket_tag(fd) == Some(parked >> 32) {
+                return Some(fd);
             }
         }
+        None
     }
 
-    // Send original request (non-manifest ops go to daemon)
-    if !send_request_on_fd(fd, request) {
+    /// Park `fd` for the next call; closed if every slot is taken
+    unsafe fn put(&self, fd: c_int) {
+        if let Some(tag) = socket_tag(fd) {
+            if !POOL_ATFORK_REGISTERED.swap(true, Ordering::SeqCst) {
+                libc::pthread_atfork(None, None, Some(pool_after_fork));
+            }
+            let parked = (tag << 32) | fd as u32 as u64;
+            for slot in &self.slots {
+                if slot
+                    .compare_exchange(POOL_EMPTY, parked, Ordering::Release, Ordering::Relaxed)
+                    .is_ok()
+                {
+                    return;
+                }
+            }
+        }
         ipc_raw_close(fd);
+    }
+
+    /// Close every parked connection
+    unsafe fn clear(&self) {
+        for slot in &self.slots {
+            let parked = slot.swap(POOL_EMPTY, Ordering::Acquire);
+            if parked != POOL_EMPTY {
+                ipc_raw_close(parked as u32 as c_int);
+            }
+        }
+    }
+}
+
+/// Low 32 bits of the inode of the socket `fd`; None if `fd` is not a socket
+unsafe fn socket_tag(fd: c_int) -> Option<u64> {
+    let mut st: libc::stat = std::mem::zeroed();
+    if CTX.fstat(fd, &mut st) != 0 || (st.st_mode & libc::S_IFMT) != libc::S_IFSOCK {
         return None;
     }
+    Some(st.st_ino as u32 as u64)
+}
 
-    let response = recv_response_on_fd(fd);
-    ipc_raw_close(fd);
+/// Park `fd` in `pool`, or close it when it is not to be pooled
+unsafe fn release(pool: Option<&ConnPool>, fd: c_int) {
+    match pool {
+        Some(pool) => pool.put(fd),
+        None => {
+            ipc_raw_close(fd);
+        }
+    }
+}
+
+/// Send `request` over a connection from `pool`, or over a new one from
+/// `open` (-1 if the peer is unreachable), and read the reply
+unsafe fn pooled_rpc(
+    pool: Option<&ConnPool>,
+    request: &vrift_ipc::VeloRequest,
+    open: &dyn Fn() -> c_int,
+) -> Option<vrift_ipc::VeloResponse> {
+    if let Some(fd) = pool.and_then(|pool| pool.take()) {
+        let sent = send_request_on_fd(fd, request);
+        if let Some(response) = sent.then(|| recv_response_on_fd(fd)).flatten() {
+            release(pool, fd);
+            return Some(response);
+        }
+        ipc_raw_close(fd);
+        if sent && !request.is_control() {
+            return None;
+        }
+        inception_record!(crate::state::EventType::IpcReconnect, 0, fd);
+    }
+
+    let fd = open();
+    if fd < 0 {
+        return None;
+    }
+    let response = send_request_on_fd(fd, request)
+        .then(|| recv_response_on_fd(fd))
+        .flatten();
+    match response {
+        Some(_) => release(pool, fd),
+        None => {
+            ipc_raw_close(fd);
+        }
+    }
     response
 }
 
+/// A forked child must not talk over its parent's connections: replies
+/// would go to whichever of the two reads first
+extern "C" fn pool_after_fork() {
+    unsafe {
+        VRIFTD_POOL.clear();
+        VDIRD_POOL.clear();
+        VDIRD_CONTROL_POOL.clear();
+    }
+}
+
 /// Phase 1.2: Check if a request is a manifest operation that must be routed to vDird.
 fn is_manifest_request(request: &vrift_ipc::VeloRequest) -> bool {
     matches!(
@@ -229,7 +597,10 @@ fn is_manifest_request(request: &vrift_ipc::VeloRequest) -> bool {
             | vrift_ipc::VeloRequest::ManifestRename { .. }
             | vrift_ipc::VeloRequest::ManifestUpdateMtime { .. }
             | vrift_ipc::VeloRequest::ManifestReingest { .. }
+            | vrift_ipc::VeloRequest::ManifestReingestChecked { .. }
             | vrift_ipc::VeloRequest::ManifestListDir { .. }
+            | vrift_ipc::VeloRequest::ManifestChangesSince { .. }
+            | vrift_ipc::VeloRequest::ManifestGetMany { .. }
     )
 }
 
@@ -248,6 +619,34 @@ unsafe fn cache_vdird_socket(vdird_socket: &str) {
         if state.vdird_socket_path.is_empty() {
             state.vdird_socket_path.set(vdird_socket);
             inception_info!("Cached vDird socket: {}", vdird_socket);
+            negotiate_vdir_version(state, vdird_socket);
+        }
+    }
+}
+
+/// Mixed-version fleets: if the mapped VDir was written in a format newer
+/// than this shim reads, ask vDird to fall back to our version. Until it
+/// does, vdir_lookup rejects the mapping and stats go through IPC.
+unsafe fn negotiate_vdir_version(state: &crate::state::InceptionLayerState, vdird_socket: &str) {
+    use vrift_ipc::vdir_types::{vdir_header_version, vdir_version_supported, VDIR_VERSION};
+
+    let (mmap_ptr, mmap_size) = state.vdir.get();
+    let Some(mapped) = vdir_header_version(mmap_ptr, mmap_size) else {
+        return;
+    };
+    if vdir_version_supported(mapped) {
+        return;
+    }
+
+    let request = vrift_ipc::VeloRequest::VDirNegotiate {
+        max_version: VDIR_VERSION,
+    };
+    match sync_rpc_vdird(vdird_socket, &request) {
+        Some(vrift_ipc::VeloResponse::VDirNegotiateAck { version }) => {
+            inception_info!("VDir v{} negotiated down to v{}", mapped, version);
+        }
+        _ => {
+            inception_warn!("VDir v{} unreadable, stats fall back to IPC", mapped);
         }
     }
 }
@@ -259,11 +658,14 @@ unsafe fn sync_rpc_vdird(
     request: &vrift_ipc::VeloRequest,
 ) -> Option<vrift_ipc::VeloResponse> {
     use crate::state::{
-        EventType, CIRCUIT_BREAKER_FAILED_COUNT, CIRCUIT_BREAKER_THRESHOLD, CIRCUIT_RECOVERY_DELAY,
-        CIRCUIT_TRIPPED, CIRCUIT_TRIP_TIME,
+        CIRCUIT_BREAKER_FAILED_COUNT, CIRCUIT_RECOVERY_DELAY, CIRCUIT_TRIPPED, CIRCUIT_TRIP_TIME,
     };
     use std::sync::atomic::Ordering;
 
+    if !request.is_control() {
+        forget_dir_batch();
+    }
+
     // If no vDird socket cached yet, fall back to daemon socket via sync_rpc
     if vdird_socket_path.is_empty() {
         // Fallback: use the daemon socket (which will trigger RegisterAck caching)
@@ -294,39 +696,33 @@ unsafe fn sync_rpc_vdird(
         }
     }
 
-    let fd = raw_unix_connect(vdird_socket_path);
-    if fd < 0 {
-        let count = CIRCUIT_BREAKER_FAILED_COUNT.fetch_add(1, Ordering::SeqCst) + 1;
-        let threshold = CIRCUIT_BREAKER_THRESHOLD.load(Ordering::Relaxed);
-        inception_record!(EventType::IpcFail, 0, count as i32);
-        if count >= threshold && !CIRCUIT_TRIPPED.swap(true, Ordering::SeqCst) {
-            let now = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .map(|d| d.as_secs())
-                .unwrap_or(0);
-            CIRCUIT_TRIP_TIME.store(now, Ordering::Relaxed);
-            inception_error!(
-                "VDIRD CONNECTION FAILED {} TIMES. CIRCUIT BREAKER TRIPPED.",
-                count
-            );
-            inception_record!(EventType::CircuitTripped, 0, count as i32);
+    // Queries go to vDird's control socket so they don't queue behind bulk
+    // work; a vDird without one still serves them on the data socket
+    let connect_vdird = || {
+        let mut fd = -1;
+        if request.is_control() {
+            fd = raw_unix_connect(&vrift_ipc::control_socket_path(vdird_socket_path));
         }
-        return None;
-    }
-
-    inception_record!(EventType::IpcSuccess, 0, fd);
-    CIRCUIT_BREAKER_FAILED_COUNT.store(0, Ordering::Relaxed);
+        if fd < 0 {
+            fd = raw_unix_connect(vdird_socket_path);
+        }
+        fd
+    };
+    // A vDird that went away is only respawned when vriftd is asked for it
+    let open = || {
+        managed_connect("vDird", &|| match connect_vdird() {
+            -1 if reregister_workspace() => connect_vdird(),
+            fd => fd,
+        })
+    };
+    let pool = if request.is_control() {
+        &VDIRD_CONTROL_POOL
+    } else {
+        &VDIRD_POOL
+    };
 
     // No RegisterWorkspace needed — vDird is already project-scoped
-    // Send request directly
-    if !send_request_on_fd(fd, request) {
-        ipc_raw_close(fd);
-        return None;
-    }
-
-    let response = recv_response_on_fd(fd);
-    ipc_raw_close(fd);
-    response
+    pooled_rpc(Some(pool), request, &open)
 }
 
 pub(crate) unsafe fn sync_ipc_manifest_remove(vdird_socket: &str, path: &str) -> bool {
@@ -350,10 +746,27 @@ pub(crate) unsafe fn sync_ipc_manifest_rename(vdird_socket: &str, old: &str, new
     )
 }
 
+/// An untracked file was renamed over `new` on disk; vDird hashes it and
+/// swaps the manifest entry
+pub(crate) unsafe fn sync_ipc_manifest_rename_over(
+    vdird_socket: &str,
+    old: &str,
+    new: &str,
+) -> bool {
+    let request = vrift_ipc::VeloRequest::ManifestRenameOver {
+        old_path: old.to_string(),
+        new_path: new.to_string(),
+    };
+    matches!(
+        sync_rpc_vdird(vdird_socket, &request),
+        Some(vrift_ipc::VeloResponse::ManifestAck { .. })
+    )
+}
+
 pub(crate) unsafe fn sync_ipc_manifest_update_mtime(
     vdird_socket: &str,
     path: &str,
-    mtime: u64,
+    mtime: i64,
 ) -> bool {
     let request = vrift_ipc::VeloRequest::ManifestUpdateMtime {
         path: path.to_string(),
@@ -372,10 +785,7 @@ pub(crate) unsafe fn sync_ipc_manifest_mkdir(vdird_socket: &str, path: &str, _mo
         entry: vrift_ipc::VnodeEntry {
             content_hash: [0u8; 32],
             size: 0,
-            mtime: SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .map(|d| d.as_secs())
-                .unwrap_or(0),
+            mtime: vrift_ipc::mtime::now(),
             mode: 0o755,
             flags: 1, // is_dir flag
             _pad: 0,
@@ -398,10 +808,7 @@ pub(crate) unsafe fn sync_ipc_manifest_symlink(
         entry: vrift_ipc::VnodeEntry {
             content_hash: [0u8; 32],
             size: 0,
-            mtime: SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .map(|d| d.as_secs())
-                .unwrap_or(0),
+            mtime: vrift_ipc::mtime::now(),
             mode: 0o777,
             flags: 2, // is_symlink pseudo-flag
             _pad: 0,
@@ -413,19 +820,60 @@ pub(crate) unsafe fn sync_ipc_manifest_symlink(
     )
 }
 
+/// Commit a staged CoW copy taken from content `base_hash`. vDird refuses it
+/// if another process committed a write of `vpath` meanwhile; a zero
+/// `base_hash` commits unconditionally.
 pub(crate) unsafe fn sync_ipc_manifest_reingest(
     vdird_socket: &str,
     vpath: &str,
     temp: &str,
+    base_hash: &[u8; 32],
 ) -> bool {
-    let request = vrift_ipc::VeloRequest::ManifestReingest {
-        vpath: vpath.to_string(),
-        temp_path: temp.to_string(),
+    let request = if *base_hash == [0; 32] {
+        vrift_ipc::VeloRequest::ManifestReingest {
+            vpath: vpath.to_string(),
+            temp_path: temp.to_string(),
+        }
+    } else {
+        vrift_ipc::VeloRequest::ManifestReingestChecked {
+            vpath: vpath.to_string(),
+            temp_path: temp.to_string(),
+            base_hash: *base_hash,
+        }
     };
-    matches!(
-        sync_rpc_vdird(vdird_socket, &request),
-        Some(vrift_ipc::VeloResponse::ManifestAck { .. })
-    )
+    match sync_rpc_vdird(vdird_socket, &request) {
+        Some(vrift_ipc::VeloResponse::ManifestAck { .. }) => true,
+        Some(vrift_ipc::VeloResponse::Error(e)) if e.kind == vrift_ipc::VeloErrorKind::Conflict => {
+            inception_warn!("write conflict on '{}': {}", vpath, e.message);
+            false
+        }
+        _ => false,
+    }
+}
+
+/// Report a process to vriftd's session tracker (fire-and-forget).
+/// `command` may be empty; the daemon then resolves it from the pid.
+pub(crate) unsafe fn report_session_process(
+    socket_path: &str,
+    pid: u32,
+    ppid: u32,
+    command: &str,
+) -> bool {
+    let request = vrift_ipc::VeloRequest::SessionRegister {
+        pid,
+        ppid,
+        command: command.to_string(),
+    };
+    fire_and_forget_ipc(socket_path, &request)
+}
+
+/// Attribute a committed CoW write-back to this process (fire-and-forget)
+pub(crate) unsafe fn report_session_writeback(socket_path: &str, vpath: &str) -> bool {
+    let request = vrift_ipc::VeloRequest::SessionWriteBack {
+        pid: libc::getpid() as u32,
+        path: vpath.to_string(),
+    };
+    fire_and_forget_ipc(socket_path, &request)
 }
 
 /// Phase 3: Fire-and-forget IPC — push a VeloRequest to the ring buffer
@@ -441,6 +889,10 @@ pub(crate) unsafe fn fire_and_forget_ipc(
     socket_path: &str,
     request: &vrift_ipc::VeloRequest,
 ) -> bool {
+    if !request.is_control() {
+        forget_dir_batch();
+    }
+
     // Serialize upfront so the worker only needs to connect + write
     let payload = match rkyv::to_bytes::<rkyv::rancor::Error>(request) {
         Ok(bytes) => bytes.to_vec(),
@@ -494,9 +946,7 @@ pub(crate) unsafe fn send_fire_and_forget_sync(socket_path: &str, payload: &[u8]
     }
 
     // Send the pre-serialized request
-    let seq_id = vrift_ipc::next_seq_id();
-    let header = vrift_ipc::IpcHeader::new_request(payload.len() as u32, seq_id);
-    let success = raw_write_all(fd, &header.to_bytes()) && raw_write_all(fd, payload);
+    let success = send_payload_on_fd(fd, payload);
     ipc_raw_close(fd);
     success
 }
@@ -576,6 +1026,18 @@ pub(crate) unsafe fn sync_ipc_flock(socket_path: &str, path: &str, op: i32) -> b
     )
 }
 
+/// Ask vriftd to fetch the missing blob `hash` (`size` bytes) from its
+/// upstream CAS; true once the blob is in the local CAS
+pub(crate) unsafe fn sync_ipc_cas_fetch(socket_path: &str, hash: &[u8; 32], size: u64) -> bool {
+    // Downloads take a while; vriftd bounds them with [upstream] timeout_secs
+    const FETCH_TIMEOUT_SECS: libc::time_t = 120;
+    let request = vrift_ipc::VeloRequest::CasFetch { hash: *hash, size };
+    matches!(
+        sync_rpc_with_timeout(socket_path, &request, Some(FETCH_TIMEOUT_SECS)),
+        Some(vrift_ipc::VeloResponse::CasFound { .. })
+    )
+}
+
 pub(crate) unsafe fn sync_ipc_fcntl_lock(
     _socket_path: &str,
     _path: &str,
@@ -601,50 +1063,191 @@ pub(crate) unsafe fn sync_ipc_manifest_get(
     }
 }
 
-// Helper: send request on existing FD (v3 frame protocol)
-unsafe fn send_request_on_fd(fd: libc::c_int, request: &vrift_ipc::VeloRequest) -> bool {
-    use vrift_ipc::{next_seq_id, IpcHeader};
+/// Resolve a batch of paths via vDird in one round trip, answers in request
+/// order
+pub(crate) unsafe fn sync_ipc_manifest_get_many(
+    vdird_socket: &str,
+    paths: Vec<String>,
+) -> Option<Vec<Option<vrift_ipc::VnodeEntry>>> {
+    let count = paths.len();
+    let request = vrift_ipc::VeloRequest::ManifestGetMany { paths };
+    match sync_rpc_vdird(vdird_socket, &request) {
+        Some(vrift_ipc::VeloResponse::ManifestManyAck { entries }) if entries.len() == count => {
+            Some(entries)
+        }
+        _ => None,
+    }
+}
 
-    let payload = match rkyv::to_bytes::<rkyv::rancor::Error>(request) {
-        Ok(b) => b,
-        Err(_) => return false,
+// Lookups in a listed directory are coalesced: build tools list a directory
+// and then stat every name in it. The first lookup of a listed name fetches
+// it and the names after it (up to DIR_BATCH_MAX) with one ManifestGetMany;
+// the lookups that follow are answered from that batch. Answers live for
+// DIR_BATCH_TTL_NS, and any manifest mutation from this process drops them.
+
+const DIR_BATCH_MAX: usize = 1024;
+const DIR_BATCH_TTL_NS: u64 = 500_000_000;
+
+/// Names of the directory listed last, and the entries fetched for them
+struct DirBatch {
+    /// Manifest key of the directory
+    dir: String,
+    /// Sorted
+    names: Vec<String>,
+    /// Per name; None until fetched
+    entries: Vec<Option<Option<vrift_ipc::VnodeEntry>>>,
+    /// When the oldest fetched entry was fetched; 0 with none fetched
+    fetched_ns: u64,
+    /// Bumped whenever entries are dropped, so that a fetch that raced it
+    /// does not store what it got
+    generation: u64,
+}
+
+static DIR_BATCH: crate::sync::RecursiveMutex<DirBatch> =
+    crate::sync::RecursiveMutex::new(DirBatch {
+        dir: String::new(),
+        names: Vec::new(),
+        entries: Vec::new(),
+        fetched_ns: 0,
+        generation: 0,
+    });
+
+/// Remember the listing of `dir` (a manifest key) for batched lookups
+pub(crate) fn note_dir_listing(dir: &str, listing: &[vrift_ipc::DirEntry]) {
+    let mut names: Vec<String> = listing.iter().map(|e| e.name.clone()).collect();
+    names.sort_unstable();
+    let mut batch = DIR_BATCH.lock();
+    batch.dir = dir.to_string();
+    batch.entries = vec![None; names.len()];
+    batch.names = names;
+    batch.fetched_ns = 0;
+    batch.generation += 1;
+}
+
+/// Drop the batched entries: the manifest is about to change
+pub(crate) fn forget_dir_batch() {
+    let mut batch = DIR_BATCH.lock();
+    if batch.fetched_ns != 0 {
+        batch.entries.iter_mut().for_each(|e| *e = None);
+        batch.fetched_ns = 0;
+    }
+    batch.generation += 1;
+}
+
+/// Query manifest for a single path via vDird, batched with the rest of its
+/// directory if that was just listed
+pub(crate) unsafe fn sync_ipc_manifest_get_batched(
+    vdird_socket: &str,
+    path: &str,
+) -> Option<vrift_ipc::VnodeEntry> {
+    let Some((parent, name)) = path.rsplit_once('/') else {
+        return sync_ipc_manifest_get(vdird_socket, path);
+    };
+    let parent = if parent.is_empty() { "/" } else { parent };
+    let now = crate::state::clock_ns(libc::CLOCK_MONOTONIC);
+
+    let (generation, first, keys) = {
+        let mut batch = DIR_BATCH.lock();
+        let listed = match batch.dir == parent {
+            true => batch.names.binary_search_by(|n| n.as_str().cmp(name)).ok(),
+            false => None,
+        };
+        let Some(first) = listed else {
+            drop(batch);
+            return sync_ipc_manifest_get(vdird_socket, path);
+        };
+        if batch.fetched_ns != 0 && now.saturating_sub(batch.fetched_ns) > DIR_BATCH_TTL_NS {
+            batch.entries.iter_mut().for_each(|e| *e = None);
+            batch.fetched_ns = 0;
+        }
+        if let Some(entry) = &batch.entries[first] {
+            return entry.clone();
+        }
+        let end = (first + DIR_BATCH_MAX).min(batch.names.len());
+        let dir = batch.dir.trim_end_matches('/');
+        let keys: Vec<String> = batch.names[first..end]
+            .iter()
+            .map(|n| format!("{}/{}", dir, n))
+            .collect();
+        (batch.generation, first, keys)
     };
 
-    if payload.len() > vrift_ipc::IpcHeader::MAX_LENGTH {
-        return false;
+    let entries = sync_ipc_manifest_get_many(vdird_socket, keys)?;
+    let answer = entries[0].clone();
+    let mut batch = DIR_BATCH.lock();
+    if batch.generation == generation {
+        for (i, entry) in entries.into_iter().enumerate() {
+            batch.entries[first + i] = Some(entry);
+        }
+        if batch.fetched_ns == 0 {
+            batch.fetched_ns = now;
+        }
     }
+    answer
+}
 
-    let seq_id = next_seq_id();
-    let header = IpcHeader::new_request(payload.len() as u32, seq_id);
+// Helper: send request on existing FD (v3 frame protocol)
+pub(crate) unsafe fn send_request_on_fd(fd: libc::c_int, request: &vrift_ipc::VeloRequest) -> bool {
+    crate::fault::delay_request();
+    match rkyv::to_bytes::<rkyv::rancor::Error>(request) {
+        Ok(payload) => send_payload_on_fd(fd, &payload),
+        Err(_) => false,
+    }
+}
+
+// Helper: send a serialized request, in continuation frames if it is large
+unsafe fn send_payload_on_fd(fd: libc::c_int, payload: &[u8]) -> bool {
+    use vrift_ipc::{next_seq_id, FrameType};
 
-    raw_write_all(fd, &header.to_bytes()) && raw_write_all(fd, &payload)
+    let seq_id = next_seq_id();
+    match vrift_ipc::message_frames(FrameType::Request, seq_id, payload) {
+        Ok(mut frames) => {
+            frames.all(|(header, part)| send_all(fd, &header.to_bytes()) && send_all(fd, part))
+        }
+        Err(_) => false,
+    }
 }
 
-// Helper: receive response on existing FD (v3 frame protocol)
-unsafe fn recv_response_on_fd(fd: libc::c_int) -> Option<vrift_ipc::VeloResponse> {
+// Helper: read one frame header
+unsafe fn recv_header_on_fd(fd: libc::c_int) -> Option<vrift_ipc::IpcHeader> {
     use vrift_ipc::IpcHeader;
 
-    // Read header
     let mut header_buf = [0u8; IpcHeader::SIZE];
     if !raw_read_exact(fd, &mut header_buf) {
         return None;
     }
 
     let header = IpcHeader::from_bytes(&header_buf);
-    if !header.is_valid() {
-        return None;
-    }
+    header.is_valid().then_some(header)
+}
+
+// Helper: receive response on existing FD (v3 frame protocol)
+pub(crate) unsafe fn recv_response_on_fd(fd: libc::c_int) -> Option<vrift_ipc::VeloResponse> {
+    use vrift_ipc::IpcHeader;
+
+    let header = recv_header_on_fd(fd)?;
 
     // Sanity check
-    if header.length as usize > 1024 * 1024 {
+    if header.length as usize > IpcHeader::MAX_LENGTH {
         return None;
     }
 
-    // Read payload
+    // Read payload, then whatever continues it (large directory listings)
     let mut payload = vec![0u8; header.length as usize];
     if !raw_read_exact(fd, &mut payload) {
         return None;
     }
+    let mut more = header.has_more();
+    while more {
+        let next = recv_header_on_fd(fd)?;
+        header.check_continuation(&next, payload.len()).ok()?;
+        let start = payload.len();
+        payload.resize(start + next.length as usize, 0);
+        if !raw_read_exact(fd, &mut payload[start..]) {
+            return None;
+        }
+        more = next.has_more();
+    }
 
     rkyv::from_bytes::<vrift_ipc::VeloResponse, rkyv::rancor::Error>(&payload).ok()
 }
@@ -663,3 +1266,20 @@ pub(crate) unsafe fn sync_ipc_manifest_list_dir(
         _ => None,
     }
 }
+
+/// Poll the vDird manifest change feed (inotify emulation)
+/// Returns (next_cursor, changes, truncated)
+pub(crate) unsafe fn sync_ipc_manifest_changes_since(
+    vdird_socket: &str,
+    cursor: u64,
+) -> Option<(u64, Vec<vrift_ipc::ManifestChange>, bool)> {
+    let request = vrift_ipc::VeloRequest::ManifestChangesSince { cursor };
+    match sync_rpc_vdird(vdird_socket, &request) {
+        Some(vrift_ipc::VeloResponse::ManifestChanges {
+            cursor,
+            changes,
+            truncated,
+        }) => Some((cursor, changes, truncated)),
+        _ => None,
+    }
+}
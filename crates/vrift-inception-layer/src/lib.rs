@@ -31,6 +31,18 @@
 //! ```
 //!
 //! See `docs/INCEPTION_LAYER_SAFETY_GUIDE.md` for full documentation.
+//!
+//! # Minimal build
+//!
+//! With `--features minimal` only the stat family is interposed and lookups
+//! are served from the VDir mmap (`VRIFT_VDIR_MMAP`) alone: no daemon IPC,
+//! no CoW, no CAS. Paths missing from the mmap fall through to the kernel.
+//!
+//! # Fault injection build
+//!
+//! With `--features fault-injection` the layer fails daemon connects, delays
+//! daemon requests, or fails opens and stats with `EIO` as `VRIFT_FAULT`
+//! asks; see [`fault`].
 
 // Allow dead code during incremental restoration
 #![allow(dead_code)]
@@ -42,14 +54,20 @@
 #[macro_use]
 pub mod macros;
 
+pub mod fault;
+pub mod intercept;
 pub mod interpose;
 pub mod ipc;
+pub mod pack;
 pub mod path;
 pub mod raw_context;
 pub mod reals;
+pub mod record;
+pub mod sandbox;
 pub mod state;
 pub mod sync;
 pub mod syscalls;
+pub mod watchdog;
 
 extern "C" {
     fn set_inception_errno(e: libc::c_int);
@@ -136,6 +154,25 @@ pub unsafe extern "C" fn vrift_get_telemetry(
 
     let _ = writeln!(writer, "{{");
     let _ = writeln!(writer, "  \"pid\": {},", pid);
+    let _ = writeln!(
+        writer,
+        "  \"variant\": \"{}\",",
+        if cfg!(feature = "minimal") {
+            "minimal"
+        } else {
+            "full"
+        }
+    );
+    let _ = write!(writer, "  \"intercept\": \"");
+    let active = crate::intercept::mask();
+    let mut first = true;
+    for (name, bits) in crate::intercept::GROUPS {
+        if active & bits != 0 {
+            let _ = write!(writer, "{}{}", if first { "" } else { "," }, name);
+            first = false;
+        }
+    }
+    let _ = writeln!(writer, "\",");
     let _ = writeln!(
         writer,
         "  \"inception_state\": \"{}\",",
@@ -156,6 +193,11 @@ pub unsafe extern "C" fn vrift_get_telemetry(
             "  \"open_fds\": {},",
             crate::syscalls::io::OPEN_FD_COUNT.load(std::sync::atomic::Ordering::Relaxed)
         );
+        let _ = writeln!(
+            writer,
+            "  \"sandbox_violations\": {},",
+            crate::sandbox::SANDBOX_VIOLATIONS.load(std::sync::atomic::Ordering::Relaxed)
+        );
     }
 
     let _ = writeln!(writer, "  \"events_last_1k\": {{");
@@ -23,6 +23,22 @@ macro_rules! passthrough_if_init {
     };
 }
 
+/// `VRIFT_INTERCEPT`: hand the call straight to `$real` when the interposer's
+/// group has been switched off for this process.
+///
+/// # Usage:
+/// ```ignore
+/// passthrough_if_disabled!(intercept::OPEN, raw_open, path, flags, mode);
+/// ```
+#[macro_export]
+macro_rules! passthrough_if_disabled {
+    ($group:expr, $real:expr $(, $arg:expr)*) => {
+        if !$crate::intercept::enabled($group) {
+            return $real($($arg),*);
+        }
+    };
+}
+
 /// BUG-007 Pattern: Safe early passthrough using interpose old_func.
 /// This MUST be called BEFORE any dlsym call to avoid malloc recursion deadlock.
 ///
@@ -56,28 +72,17 @@ macro_rules! inception_log_raw {
     };
 }
 
+/// Leveled, per-call-site rate-limited logging; see [`crate::state::log_record`]
+/// for the line format and [`crate::state::RateLimit`] for the limiter.
 #[macro_export]
 macro_rules! inception_log_at_level {
-    ($level:expr, $tag:expr, $($arg:tt)*) => {
+    ($level:expr, $($arg:tt)*) => {
         {
             if $crate::state::LOG_LEVEL.load(std::sync::atomic::Ordering::Relaxed) <= ($level as u8) {
-                // Stack-based formatting to avoid heap allocation
-                // Use a local buffer and recursion guard
+                static LIMIT: $crate::state::RateLimit = $crate::state::RateLimit::new();
                 if let Some(_guard) = $crate::state::InceptionLayerGuard::enter() {
-                    use std::fmt::Write;
-                    let mut buf = [0u8; 512];
-                    let mut wrapper = $crate::macros::StackWriter::new(&mut buf);
-                    let pid = unsafe { libc::getpid() };
-                    let _ = write!(wrapper, "[VR-INCEPTION][{}][{}] ", pid, $tag);
-                    let _ = write!(wrapper, $($arg)*);
-                    let _ = writeln!(wrapper);
-
-                    let msg = wrapper.as_str();
-                    unsafe {
-                        $crate::state::LOGGER.log(msg);
-                        if $crate::state::DEBUG_ENABLED.load(std::sync::atomic::Ordering::Relaxed) {
-                            libc::write(2, msg.as_ptr() as *const libc::c_void, msg.len());
-                        }
+                    if let Some(suppressed) = LIMIT.admit() {
+                        $crate::state::log_record($level, suppressed, format_args!($($arg)*));
                     }
                 }
             }
@@ -94,15 +99,15 @@ macro_rules! inception_record {
 }
 
 #[macro_export]
-macro_rules! inception_trace { ($($arg:tt)*) => { $crate::inception_log_at_level!($crate::state::LogLevel::Trace, "TRACE", $($arg)*) }; }
+macro_rules! inception_trace { ($($arg:tt)*) => { $crate::inception_log_at_level!($crate::state::LogLevel::Trace, $($arg)*) }; }
 #[macro_export]
-macro_rules! inception_debug { ($($arg:tt)*) => { $crate::inception_log_at_level!($crate::state::LogLevel::Debug, "DEBUG", $($arg)*) }; }
+macro_rules! inception_debug { ($($arg:tt)*) => { $crate::inception_log_at_level!($crate::state::LogLevel::Debug, $($arg)*) }; }
 #[macro_export]
-macro_rules! inception_info { ($($arg:tt)*) => { $crate::inception_log_at_level!($crate::state::LogLevel::Info, "INFO", $($arg)*) }; }
+macro_rules! inception_info { ($($arg:tt)*) => { $crate::inception_log_at_level!($crate::state::LogLevel::Info, $($arg)*) }; }
 #[macro_export]
-macro_rules! inception_warn { ($($arg:tt)*) => { $crate::inception_log_at_level!($crate::state::LogLevel::Warn, "WARN", $($arg)*) }; }
+macro_rules! inception_warn { ($($arg:tt)*) => { $crate::inception_log_at_level!($crate::state::LogLevel::Warn, $($arg)*) }; }
 #[macro_export]
-macro_rules! inception_error { ($($arg:tt)*) => { $crate::inception_log_at_level!($crate::state::LogLevel::Error, "ERROR", $($arg)*) }; }
+macro_rules! inception_error { ($($arg:tt)*) => { $crate::inception_log_at_level!($crate::state::LogLevel::Error, $($arg)*) }; }
 
 // Compatibility inception layer for existing code
 #[macro_export]
@@ -157,15 +162,91 @@ impl<'a> StackWriter<'a> {
     pub fn as_str(&self) -> &str {
         std::str::from_utf8(&self.buf[..self.pos]).unwrap_or("")
     }
+
+    /// Append `suffix`, truncating earlier output if needed so that it fits
+    pub fn finish_with(&mut self, suffix: &str) {
+        let suffix = suffix.as_bytes();
+        if suffix.len() > self.buf.len() {
+            return;
+        }
+        let mut end = self.pos.min(self.buf.len() - suffix.len());
+        // Don't leave half a UTF-8 sequence in front of the suffix
+        while end > 0 && end < self.pos && (self.buf[end] & 0xC0) == 0x80 {
+            end -= 1;
+        }
+        self.buf[end..end + suffix.len()].copy_from_slice(suffix);
+        self.pos = end + suffix.len();
+    }
 }
 
 impl<'a> std::fmt::Write for StackWriter<'a> {
     fn write_str(&mut self, s: &str) -> std::fmt::Result {
-        let bytes = s.as_bytes();
         let remaining = self.buf.len() - self.pos;
-        let to_copy = std::cmp::min(bytes.len(), remaining);
-        self.buf[self.pos..self.pos + to_copy].copy_from_slice(&bytes[..to_copy]);
+        let mut to_copy = std::cmp::min(s.len(), remaining);
+        // Truncate on a char boundary so as_str() stays valid
+        while !s.is_char_boundary(to_copy) {
+            to_copy -= 1;
+        }
+        self.buf[self.pos..self.pos + to_copy].copy_from_slice(&s.as_bytes()[..to_copy]);
         self.pos += to_copy;
         Ok(())
     }
 }
+
+/// Escapes `"`, `\` and control characters so a message can sit inside a
+/// quoted `msg="..."` log field and stay on one line.
+pub struct QuotedWriter<'a, W: std::fmt::Write>(pub &'a mut W);
+
+impl<W: std::fmt::Write> std::fmt::Write for QuotedWriter<'_, W> {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        let mut start = 0;
+        for (i, c) in s.char_indices() {
+            if c != '"' && c != '\\' && !c.is_control() {
+                continue;
+            }
+            self.0.write_str(&s[start..i])?;
+            match c {
+                '"' => self.0.write_str("\\\"")?,
+                '\\' => self.0.write_str("\\\\")?,
+                '\n' => self.0.write_str("\\n")?,
+                '\t' => self.0.write_str("\\t")?,
+                _ => write!(self.0, "\\x{:02x}", c as u32)?,
+            }
+            start = i + c.len_utf8();
+        }
+        self.0.write_str(&s[start..])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt::Write;
+
+    #[test]
+    fn test_stack_writer_truncates_on_char_boundary() {
+        let mut buf = [0u8; 8];
+        let mut w = StackWriter::new(&mut buf);
+        let _ = write!(w, "abcdefgé!");
+        assert_eq!(w.as_str(), "abcdefg");
+
+        let mut buf = [0u8; 8];
+        let mut w = StackWriter::new(&mut buf);
+        let _ = write!(w, "abcdeéé");
+        w.finish_with("\"\n");
+        assert_eq!(w.as_str(), "abcde\"\n");
+
+        let mut buf = [0u8; 8];
+        let mut w = StackWriter::new(&mut buf);
+        let _ = write!(w, "ab");
+        w.finish_with("\n");
+        assert_eq!(w.as_str(), "ab\n");
+    }
+
+    #[test]
+    fn test_quoted_writer_escapes() {
+        let mut out = String::new();
+        let _ = write!(QuotedWriter(&mut out), "say \"hi\"\\\n\tbell\x07 ok");
+        assert_eq!(out, "say \\\"hi\\\"\\\\\\n\\tbell\\x07 ok");
+    }
+}
@@ -0,0 +1,304 @@
+//! # Hot Packfile
+//!
+//! The inception layer's half of profile-guided packing (vriftd builds the
+//! packfiles):
+//!
+//! - With `VRIFT_PACK_TRACE` set, the content hash of every CAS blob this
+//!   process opens read-only is noted in first-access order and reported to
+//!   vriftd once, at exit (`PackTraceRecord`).
+//! - Read-only opens of blobs in the workspace's packfile
+//!   (`~/.vrift/packs/<project_id>.pack`) are served from the packfile's
+//!   mapping instead of the blob's own CAS file (Linux; the bytes are handed
+//!   out as a sealed memfd). `VRIFT_DISABLE_PACK=1` turns this off. vDird
+//!   records where the pack holds each entry's content in the VDir, so the
+//!   blob is found without searching the pack index; entries it hasn't
+//!   pointed at the pack (yet) fall back to the index.
+//!
+//! The packfile is mapped on the first read-only open and stays mapped for
+//! the life of the process; a pack rebuilt meanwhile is picked up by new
+//! processes.
+
+use crate::state::InceptionLayerState;
+use crate::sync::RecursiveMutex;
+use std::collections::BTreeSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+/// Blobs one process reports at most (32 bytes each on the wire)
+const TRACE_CAPACITY: usize = 16384;
+
+struct Trace {
+    order: Vec<[u8; 32]>,
+    seen: BTreeSet<[u8; 32]>,
+}
+
+static TRACE: RecursiveMutex<Trace> = RecursiveMutex::new(Trace {
+    order: Vec::new(),
+    seen: BTreeSet::new(),
+});
+static TRACE_ENABLED: OnceLock<bool> = OnceLock::new();
+static FLUSH_REGISTERED: AtomicBool = AtomicBool::new(false);
+
+fn env_flag(name: &std::ffi::CStr) -> bool {
+    unsafe {
+        let val = libc::getenv(name.as_ptr());
+        !val.is_null() && !matches!(std::ffi::CStr::from_ptr(val).to_bytes(), b"" | b"0")
+    }
+}
+
+/// Note a read-only open of the blob `hash` if this run is traced
+pub(crate) fn note_access(hash: &[u8; 32]) {
+    if !*TRACE_ENABLED.get_or_init(|| env_flag(c"VRIFT_PACK_TRACE")) {
+        return;
+    }
+    {
+        let mut trace = TRACE.lock();
+        if trace.order.len() >= TRACE_CAPACITY || !trace.seen.insert(*hash) {
+            return;
+        }
+        trace.order.push(*hash);
+    }
+    if !FLUSH_REGISTERED.swap(true, Ordering::SeqCst) {
+        unsafe { libc::atexit(flush_trace_atexit) };
+    }
+}
+
+extern "C" fn flush_trace_atexit() {
+    let hashes = std::mem::take(&mut TRACE.lock().order);
+    if hashes.is_empty() {
+        return;
+    }
+    let Some(state) = InceptionLayerState::get_no_spawn() else {
+        return;
+    };
+    let request = vrift_ipc::VeloRequest::PackTraceRecord { hashes };
+    if let Ok(payload) = rkyv::to_bytes::<rkyv::rancor::Error>(&request) {
+        // The worker thread may already be gone; send from here
+        unsafe { crate::ipc::send_fire_and_forget_sync(&state.socket_path, &payload) };
+    }
+}
+
+/// Open the blob `hash` from the workspace's packfile, if it holds it.
+/// `pack_ref` is the entry's VDir pack reference (see
+/// `InceptionLayerState::manifest_pack_ref`); it is used if it names the
+/// mapped pack.
+#[cfg(target_os = "linux")]
+pub(crate) unsafe fn open_packed(
+    state: &InceptionLayerState,
+    pack_ref: Option<(u32, u64, u32)>,
+    hash: &[u8; 32],
+    flags: libc::c_int,
+) -> Option<libc::c_int> {
+    let pack = linux::pack(state)?;
+    let bytes = match pack_ref.and_then(|pack_ref| pack.get_ref(pack_ref)) {
+        Some(bytes) => bytes,
+        None => pack.get(hash)?,
+    };
+    sealed_memfd(c"vrift-pack", bytes, flags & libc::O_CLOEXEC != 0)
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) use linux::sealed_memfd;
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) unsafe fn open_packed(
+    _state: &InceptionLayerState,
+    _pack_ref: Option<(u32, u64, u32)>,
+    _hash: &[u8; 32],
+    _flags: libc::c_int,
+) -> Option<libc::c_int> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::*;
+    use crate::syscalls::linux_raw::{
+        raw_close, raw_fstat, raw_lseek, raw_mmap, raw_openat, raw_write,
+    };
+
+    /// Mirror of velo-pack's packfile header; the archived layouts must match
+    #[derive(rkyv::Archive, rkyv::Deserialize)]
+    struct PackHeader {
+        magic: [u8; 8],
+        version: u32,
+        entry_count: u32,
+        index_offset: u64,
+        data_offset: u64,
+    }
+
+    /// Mirror of `vrift_pack::PackIndexEntry`
+    #[derive(rkyv::Archive, rkyv::Deserialize)]
+    struct PackIndexEntry {
+        hash: [u8; 32],
+        offset: u64,
+        length: u64,
+    }
+
+    const PACK_MAGIC: &[u8; 8] = b"VELOPACK";
+    const PACK_VERSION: u32 = 1;
+    const HEADER_LEN: usize = 32;
+
+    /// Mirror of `vrift_pack::pack_id`: FNV-1a of the index section, never 0
+    fn pack_id(index: &[u8]) -> u32 {
+        let mut hash: u32 = 0x811c_9dc5;
+        for b in index {
+            hash ^= u32::from(*b);
+            hash = hash.wrapping_mul(0x0100_0193);
+        }
+        hash.max(1)
+    }
+
+    pub(super) struct Pack {
+        /// `vrift_pack::pack_id` of this packfile
+        id: u32,
+        /// Data section of the mapping
+        data: &'static [u8],
+        /// (hash, offset, length), sorted by hash
+        index: Vec<([u8; 32], usize, usize)>,
+    }
+
+    impl Pack {
+        pub(super) fn get(&self, hash: &[u8; 32]) -> Option<&'static [u8]> {
+            let i = self.index.binary_search_by(|e| e.0.cmp(hash)).ok()?;
+            let (_, offset, length) = self.index[i];
+            self.data.get(offset..offset + length)
+        }
+
+        /// Blob at a VDir pack reference `(pack_id, offset, len)`, if it
+        /// names this pack
+        pub(super) fn get_ref(&self, (id, offset, len): (u32, u64, u32)) -> Option<&'static [u8]> {
+            if id != self.id {
+                return None;
+            }
+            let offset = usize::try_from(offset).ok()?;
+            self.data.get(offset..offset.checked_add(len as usize)?)
+        }
+    }
+
+    static PACK: OnceLock<Option<Pack>> = OnceLock::new();
+
+    pub(super) fn pack(state: &InceptionLayerState) -> Option<&'static Pack> {
+        PACK.get_or_init(|| unsafe { load(state) }).as_ref()
+    }
+
+    #[cold]
+    unsafe fn load(state: &InceptionLayerState) -> Option<Pack> {
+        if env_flag(c"VRIFT_DISABLE_PACK") || state.project_root.is_empty() {
+            return None;
+        }
+        let project_id = vrift_config::path::compute_project_id(&*state.project_root);
+        let path = vrift_config::path::get_pack_path(&project_id)?;
+        let cpath = std::ffi::CString::new(path.to_str()?).ok()?;
+
+        let fd = raw_openat(
+            libc::AT_FDCWD,
+            cpath.as_ptr(),
+            libc::O_RDONLY | libc::O_CLOEXEC,
+            0,
+        );
+        if fd < 0 {
+            return None;
+        }
+        let mut stat_buf: libc::stat = std::mem::zeroed();
+        let size = if raw_fstat(fd, &mut stat_buf) == 0 {
+            stat_buf.st_size as usize
+        } else {
+            0
+        };
+        let ptr = if size > HEADER_LEN {
+            raw_mmap(
+                std::ptr::null_mut(),
+                size,
+                libc::PROT_READ,
+                libc::MAP_PRIVATE,
+                fd,
+                0,
+            )
+        } else {
+            libc::MAP_FAILED
+        };
+        raw_close(fd);
+        if ptr == libc::MAP_FAILED {
+            return None;
+        }
+
+        let bytes: &'static [u8] = std::slice::from_raw_parts(ptr as *const u8, size);
+        let pack = parse(bytes);
+        match pack {
+            Some(ref pack) => {
+                // The hot set is read front to back; start reading it now
+                libc::madvise(ptr, size, libc::MADV_WILLNEED);
+                inception_log!("packfile '{}': {} blobs", path.display(), pack.index.len());
+            }
+            None => {
+                inception_warn!("ignoring invalid packfile '{}'", path.display());
+                libc::munmap(ptr, size);
+            }
+        }
+        pack
+    }
+
+    fn parse(bytes: &'static [u8]) -> Option<Pack> {
+        let header =
+            rkyv::from_bytes::<PackHeader, rkyv::rancor::Error>(bytes.get(..HEADER_LEN)?).ok()?;
+        if &header.magic != PACK_MAGIC || header.version != PACK_VERSION {
+            return None;
+        }
+        let index_bytes = bytes.get(header.index_offset as usize..header.data_offset as usize)?;
+        let entries =
+            rkyv::from_bytes::<Vec<PackIndexEntry>, rkyv::rancor::Error>(index_bytes).ok()?;
+        let data = bytes.get(header.data_offset as usize..)?;
+
+        let mut index = Vec::with_capacity(entries.len());
+        for e in entries {
+            let (offset, length) = (e.offset as usize, e.length as usize);
+            if offset.checked_add(length)? > data.len() {
+                return None;
+            }
+            index.push((e.hash, offset, length));
+        }
+        index.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        Some(Pack {
+            id: pack_id(index_bytes),
+            data,
+            index,
+        })
+    }
+
+    /// A read-only fd holding `bytes`: a memfd sealed against any change
+    pub(crate) unsafe fn sealed_memfd(
+        name: &std::ffi::CStr,
+        bytes: &[u8],
+        cloexec: bool,
+    ) -> Option<libc::c_int> {
+        let mut mfd_flags = libc::MFD_ALLOW_SEALING;
+        if cloexec {
+            mfd_flags |= libc::MFD_CLOEXEC;
+        }
+        let fd = libc::memfd_create(name.as_ptr(), mfd_flags);
+        if fd < 0 {
+            return None;
+        }
+        let mut written = 0;
+        while written < bytes.len() {
+            let n = raw_write(
+                fd,
+                bytes[written..].as_ptr() as *const libc::c_void,
+                bytes.len() - written,
+            );
+            if n <= 0 {
+                raw_close(fd);
+                return None;
+            }
+            written += n as usize;
+        }
+        let seals =
+            libc::F_SEAL_SEAL | libc::F_SEAL_SHRINK | libc::F_SEAL_GROW | libc::F_SEAL_WRITE;
+        if libc::fcntl(fd, libc::F_ADD_SEALS, seals) != 0 || raw_lseek(fd, 0, libc::SEEK_SET) != 0 {
+            raw_close(fd);
+            return None;
+        }
+        Some(fd)
+    }
+}
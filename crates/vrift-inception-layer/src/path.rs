@@ -1,5 +1,6 @@
 use libc::{c_char, c_int, AT_FDCWD};
-use std::ffi::CStr;
+use std::borrow::Cow;
+use std::ffi::{CStr, CString};
 use std::ptr;
 
 use crate::state::FixedString;
@@ -161,6 +162,11 @@ impl PathResolver {
             }
         };
 
+        // Same Unicode form as at ingest (allocates only for names not in it)
+        if let Cow::Owned(key) = vrift_ipc::path_key::normalize(key_fs.as_str()) {
+            key_fs.set(&key);
+        }
+
         let mut norm_fs = FixedString::<1024>::new();
         norm_fs.set(normalized);
 
@@ -256,6 +262,19 @@ pub(crate) unsafe fn raw_path_normalize(path: &str, out: &mut [u8]) -> Option<us
     Some(out_idx)
 }
 
+/// A C path as the shim handles it internally: the path itself if it is
+/// UTF-8, otherwise its manifest key form with the other bytes escaped (see
+/// `vrift_ipc::path_key`). Allocates only in the latter case.
+pub(crate) unsafe fn c_path_str<'a>(path: *const c_char) -> Cow<'a, str> {
+    vrift_ipc::path_key::from_bytes(CStr::from_ptr(path).to_bytes())
+}
+
+/// The C path for a path in its internal form (see [`c_path_str`]), for
+/// passing on to the real syscall
+pub(crate) fn path_cstring(path: &str) -> Option<CString> {
+    CString::new(vrift_ipc::path_key::to_bytes(path)).ok()
+}
+
 /// RFC-0049: Generate virtual inode from path
 /// Prevents st_ino collision when CAS dedup causes multiple logical files to share same blob
 /// Uses a simple hash to generate unique inode per logical path
@@ -270,19 +289,70 @@ pub(crate) fn path_to_virtual_ino(path: &str) -> libc::ino_t {
     hash as libc::ino_t
 }
 
+/// The path an `*at` call names, in the form `open`/`stat` take it: absolute
+/// paths and `AT_FDCWD`-relative ones as they are, others joined onto the
+/// path of the directory `dirfd` is open on (an empty path naming that
+/// directory itself). Written NUL-terminated into `out`; `None` if the
+/// directory is unknown or the result doesn't fit.
 pub(crate) unsafe fn resolve_path_at(
     dirfd: c_int,
     path: *const c_char,
-    out: &mut [u8],
-) -> Option<usize> {
-    let path_str = CStr::from_ptr(path).to_str().ok()?;
-    if path_str.starts_with('/') {
-        return raw_path_normalize(path_str, out);
+    out: &mut [u8; 1024],
+) -> Option<&CStr> {
+    if path.is_null() {
+        return None;
+    }
+    let rel = CStr::from_ptr(path).to_bytes();
+    let mut len = 0;
+    if rel.first() != Some(&b'/') && dirfd != AT_FDCWD {
+        len = dirfd_path(dirfd, out)?;
+        if !rel.is_empty() {
+            *out.get_mut(len)? = b'/';
+            len += 1;
+        }
     }
-    if dirfd == AT_FDCWD {
-        // Fallback to basic normalization if no complex resolver is available
-        return raw_path_normalize(path_str, out);
+    out.get_mut(len..len + rel.len())?.copy_from_slice(rel);
+    len += rel.len();
+    *out.get_mut(len)? = 0;
+    CStr::from_bytes_with_nul(&out[..=len]).ok()
+}
+
+/// The path of the directory `dirfd` is open on: from the FD table if it was
+/// opened in VFS territory, else from the kernel. Returns its length.
+unsafe fn dirfd_path(dirfd: c_int, out: &mut [u8; 1024]) -> Option<usize> {
+    if dirfd < 0 {
+        return None;
+    }
+    if let Some(state) = crate::state::InceptionLayerState::get() {
+        let entry = state.open_fds.get(dirfd as u32);
+        if !entry.is_null() && (*entry).is_dir {
+            let dir = vrift_ipc::path_key::to_bytes((*entry).vpath.as_str());
+            out.get_mut(..dir.len())?.copy_from_slice(&dir);
+            return Some(dir.len());
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        // F_GETPATH fills up to MAXPATHLEN (1024) bytes
+        if crate::syscalls::macos_raw::raw_fcntl(dirfd, libc::F_GETPATH, out.as_mut_ptr() as i64)
+            != 0
+        {
+            return None;
+        }
+        out.iter().position(|&b| b == 0)
+    }
+    #[cfg(target_os = "linux")]
+    {
+        use std::fmt::Write;
+        let mut link = [0u8; 32];
+        let mut w = crate::macros::StackWriter::new(&mut link);
+        let _ = write!(w, "/proc/self/fd/{}\0", dirfd);
+        let n = crate::syscalls::linux_raw::raw_readlink(
+            w.as_str().as_ptr() as *const c_char,
+            out.as_mut_ptr() as *mut c_char,
+            out.len(),
+        );
+        (n > 0 && (n as usize) < out.len()).then_some(n as usize)
     }
-    // Cannot resolve relative path to arbitrary dirfd easily without OS help.
-    None
 }
@@ -78,6 +78,7 @@ pub static REAL_TRUNCATE: RealSymbol = RealSymbol::new("truncate\0");
 pub static REAL_MMAP: RealSymbol = RealSymbol::new("mmap\0");
 pub static REAL_MUNMAP: RealSymbol = RealSymbol::new("munmap\0");
 pub static REAL_RENAMEAT: RealSymbol = RealSymbol::new("renameat\0");
+pub static REAL_DLOPEN: RealSymbol = RealSymbol::new("dlopen\0");
 pub static REAL_FCHMODAT: RealSymbol = RealSymbol::new("fchmodat\0");
 pub static REAL_CHFLAGS: RealSymbol = RealSymbol::new("chflags\0");
 pub static REAL_LINKAT: RealSymbol = RealSymbol::new("linkat\0");
@@ -97,3 +98,8 @@ pub static REAL_MKDIRAT: RealSymbol = RealSymbol::new("mkdirat\0");
 pub static REAL_SYMLINKAT: RealSymbol = RealSymbol::new("symlinkat\0");
 pub static REAL_FCHMOD: RealSymbol = RealSymbol::new("fchmod\0");
 pub static REAL_SETRLIMIT: RealSymbol = RealSymbol::new("setrlimit\0");
+pub static REAL_READDIR64: RealSymbol = RealSymbol::new("readdir64\0");
+pub static REAL_REWINDDIR: RealSymbol = RealSymbol::new("rewinddir\0");
+pub static REAL_FOPEN: RealSymbol = RealSymbol::new("fopen\0");
+pub static REAL_FCLOSE: RealSymbol = RealSymbol::new("fclose\0");
+pub static REAL_FREOPEN: RealSymbol = RealSymbol::new("freopen\0");
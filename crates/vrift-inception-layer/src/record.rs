@@ -0,0 +1,139 @@
+//! # Input Recording
+//!
+//! When `VRIFT_RECORD` names a file, every path this process successfully
+//! opens read-only is appended to it, one absolute path per line. `vrift record`
+//! sets this up for a whole process tree and turns the trace into the list of
+//! inputs the command actually consumed.
+//!
+//! Each process deduplicates its own reads; the same path read by several
+//! processes appears once per process and is collapsed by the consumer.
+
+use crate::state::InceptionLayerGuard;
+use libc::c_int;
+use std::ffi::CStr;
+use std::sync::atomic::{AtomicI32, AtomicU64, Ordering};
+use std::sync::OnceLock;
+
+#[cfg(target_os = "linux")]
+use crate::syscalls::linux_raw::{raw_close, raw_getcwd, raw_open, raw_write};
+#[cfg(target_os = "macos")]
+use crate::syscalls::macos_raw::{raw_close, raw_getcwd, raw_open, raw_write};
+
+/// Slots in the per-process dedup table; once full, paths may repeat in the trace
+const SEEN_SLOTS: usize = 4096;
+const MAX_PROBE: usize = 16;
+
+static TRACE_PATH: OnceLock<Option<std::ffi::CString>> = OnceLock::new();
+
+/// -1: not opened yet, -2: open failed (recording disabled for this process)
+static TRACE_FD: AtomicI32 = AtomicI32::new(-1);
+
+#[allow(clippy::declare_interior_mutable_const)]
+const EMPTY_SLOT: AtomicU64 = AtomicU64::new(0);
+static SEEN: [AtomicU64; SEEN_SLOTS] = [EMPTY_SLOT; SEEN_SLOTS];
+
+fn trace_path() -> Option<&'static std::ffi::CString> {
+    TRACE_PATH
+        .get_or_init(|| unsafe {
+            let ptr = libc::getenv(c"VRIFT_RECORD".as_ptr());
+            if ptr.is_null() || *ptr == 0 {
+                return None;
+            }
+            Some(CStr::from_ptr(ptr).to_owned())
+        })
+        .as_ref()
+}
+
+unsafe fn trace_fd(path: &CStr) -> Option<c_int> {
+    match TRACE_FD.load(Ordering::Acquire) {
+        -2 => return None,
+        -1 => {}
+        fd => return Some(fd),
+    }
+    let fd = raw_open(
+        path.as_ptr(),
+        libc::O_WRONLY | libc::O_CREAT | libc::O_APPEND | libc::O_CLOEXEC,
+        0o644,
+    );
+    let new = if fd < 0 { -2 } else { fd };
+    match TRACE_FD.compare_exchange(-1, new, Ordering::AcqRel, Ordering::Acquire) {
+        Ok(_) => (fd >= 0).then_some(fd),
+        Err(winner) => {
+            if fd >= 0 {
+                raw_close(fd);
+            }
+            (winner >= 0).then_some(winner)
+        }
+    }
+}
+
+/// Returns true the first time `hash` is seen (or when the table is saturated)
+fn first_sighting(hash: u64) -> bool {
+    let hash = hash.max(1);
+    let start = hash as usize % SEEN_SLOTS;
+    for i in 0..MAX_PROBE {
+        let slot = &SEEN[(start + i) % SEEN_SLOTS];
+        match slot.compare_exchange(0, hash, Ordering::AcqRel, Ordering::Acquire) {
+            Ok(_) => return true,
+            Err(existing) if existing == hash => return false,
+            Err(_) => continue,
+        }
+    }
+    true
+}
+
+/// Record a successful open of `path` if recording is enabled.
+///
+/// Only read-only, non-directory opens count as inputs. Relative paths are
+/// resolved against the current directory; callers with a directory fd other
+/// than `AT_FDCWD` should pass only absolute paths.
+pub(crate) unsafe fn note_open(path: &str, flags: c_int) {
+    let Some(trace) = trace_path() else {
+        return;
+    };
+    let writes = libc::O_WRONLY | libc::O_RDWR | libc::O_CREAT | libc::O_TRUNC | libc::O_APPEND;
+    if flags & (writes | libc::O_DIRECTORY) != 0 || path.is_empty() {
+        return;
+    }
+
+    // Opens issued by the inception layer itself (CAS blobs, IPC) are not inputs
+    let Some(_guard) = InceptionLayerGuard::enter() else {
+        return;
+    };
+
+    let mut joined = [0u8; 1024];
+    let input = if path.starts_with('/') {
+        path
+    } else {
+        if raw_getcwd(joined.as_mut_ptr() as *mut libc::c_char, joined.len()).is_null() {
+            return;
+        }
+        let cwd_len = joined.iter().position(|&b| b == 0).unwrap_or(joined.len());
+        let total = cwd_len + 1 + path.len();
+        if total > joined.len() {
+            return;
+        }
+        joined[cwd_len] = b'/';
+        joined[cwd_len + 1..total].copy_from_slice(path.as_bytes());
+        match std::str::from_utf8(&joined[..total]) {
+            Ok(s) => s,
+            Err(_) => return,
+        }
+    };
+
+    let mut norm_buf = [0u8; 1025];
+    let Some(len) = crate::path::raw_path_normalize(input, &mut norm_buf[..1024]) else {
+        return;
+    };
+    if !first_sighting(crate::state::fnv1a_hash(
+        std::str::from_utf8(&norm_buf[..len]).unwrap_or_default(),
+    )) {
+        return;
+    }
+    let Some(fd) = trace_fd(trace) else {
+        return;
+    };
+    // Single write per line so concurrent appenders don't interleave
+    norm_buf[len] = b'\n';
+    raw_write(fd, norm_buf.as_ptr() as *const libc::c_void, len + 1);
+}
@@ -0,0 +1,206 @@
+//! # Sandbox Mode
+//!
+//! Opt-in input verification: read-only opens of paths that are neither in the
+//! manifest nor on the allowlist are reported (`VRIFT_SANDBOX=log`) or refused
+//! with `EACCES` (`VRIFT_SANDBOX=deny`). A build that runs clean in deny mode
+//! has declared every input it reads.
+//!
+//! The allowlist is the built-in system prefixes, the CAS root, the project's
+//! `.vrift/` directory, and any extra prefixes in `VRIFT_SANDBOX_ALLOW`
+//! (colon-separated). Set `VRIFT_SANDBOX_REPORT` to a file path to append each
+//! violation to it, one path per line.
+
+use crate::state::{EventType, InceptionLayerGuard, InceptionLayerState};
+use libc::c_int;
+use std::ffi::CStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+
+#[cfg(target_os = "linux")]
+use crate::syscalls::linux_raw::{raw_access, raw_close, raw_open, raw_write};
+#[cfg(target_os = "macos")]
+use crate::syscalls::macos_raw::{raw_access, raw_close, raw_open, raw_write};
+
+/// Toolchain and OS locations every build may read
+const SYSTEM_ALLOW: &[&str] = &[
+    "/bin",
+    "/dev",
+    "/etc",
+    "/lib",
+    "/lib32",
+    "/lib64",
+    "/proc",
+    "/run",
+    "/sbin",
+    "/sys",
+    "/tmp",
+    "/usr",
+    "/var/tmp",
+    "/Library",
+    "/System",
+    "/private/etc",
+    "/private/tmp",
+    "/private/var",
+    "/var/folders",
+];
+
+/// Number of undeclared reads seen by this process
+pub static SANDBOX_VIOLATIONS: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SandboxMode {
+    Off,
+    Log,
+    Deny,
+}
+
+struct SandboxPolicy {
+    mode: SandboxMode,
+    allow: Vec<String>,
+    report: Option<std::ffi::CString>,
+}
+
+static POLICY: OnceLock<SandboxPolicy> = OnceLock::new();
+
+unsafe fn env_str(name: &CStr) -> Option<String> {
+    let ptr = libc::getenv(name.as_ptr());
+    if ptr.is_null() {
+        return None;
+    }
+    Some(CStr::from_ptr(ptr).to_string_lossy().into_owned())
+}
+
+fn policy(state: &InceptionLayerState) -> &'static SandboxPolicy {
+    POLICY.get_or_init(|| unsafe {
+        let mode = match env_str(c"VRIFT_SANDBOX").as_deref() {
+            Some("log") => SandboxMode::Log,
+            Some("deny") | Some("1") => SandboxMode::Deny,
+            _ => SandboxMode::Off,
+        };
+
+        let mut allow: Vec<String> = SYSTEM_ALLOW.iter().map(|s| s.to_string()).collect();
+        allow.push(state.cas_root.as_str().to_string());
+        if !state.project_root.is_empty() {
+            allow.push(format!("{}/.vrift", state.project_root.as_str()));
+        }
+        if let Some(extra) = env_str(c"VRIFT_SANDBOX_ALLOW") {
+            allow.extend(
+                extra
+                    .split(':')
+                    .map(|p| p.trim_end_matches('/'))
+                    .filter(|p| !p.is_empty())
+                    .map(str::to_string),
+            );
+        }
+
+        let report = env_str(c"VRIFT_SANDBOX_REPORT")
+            .filter(|p| !p.is_empty())
+            .and_then(|p| std::ffi::CString::new(p).ok());
+
+        SandboxPolicy {
+            mode,
+            allow,
+            report,
+        }
+    })
+}
+
+/// Prefix match on component boundaries (`/usr` allows `/usr/lib`, not `/usrx`)
+fn is_under(path: &str, prefix: &str) -> bool {
+    path.strip_prefix(prefix)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/') || prefix.ends_with('/'))
+}
+
+impl SandboxPolicy {
+    fn allows(&self, path: &str) -> bool {
+        self.allow.iter().any(|prefix| is_under(path, prefix))
+    }
+
+    unsafe fn append_report(&self, path: &str) {
+        let Some(ref report) = self.report else {
+            return;
+        };
+        let fd = raw_open(
+            report.as_ptr(),
+            libc::O_WRONLY | libc::O_CREAT | libc::O_APPEND | libc::O_CLOEXEC,
+            0o644,
+        );
+        if fd < 0 {
+            return;
+        }
+        // One write per line keeps concurrent appends from interleaving
+        let mut line = String::with_capacity(path.len() + 1);
+        line.push_str(path);
+        line.push('\n');
+        raw_write(fd, line.as_ptr() as *const libc::c_void, line.len());
+        raw_close(fd);
+    }
+}
+
+/// Check a read-only open against the sandbox policy.
+///
+/// Returns the errno to fail the open with, or `None` to let it proceed.
+/// Relative paths are only checked when they resolve into the VFS; with a
+/// directory fd other than `AT_FDCWD` the caller should pass only absolute paths.
+pub(crate) unsafe fn check_open(
+    state: &InceptionLayerState,
+    path: &str,
+    flags: c_int,
+) -> Option<c_int> {
+    let policy = policy(state);
+    if policy.mode == SandboxMode::Off {
+        return None;
+    }
+    let writes = libc::O_WRONLY | libc::O_RDWR | libc::O_CREAT | libc::O_TRUNC | libc::O_APPEND;
+    if flags & (writes | libc::O_DIRECTORY) != 0 {
+        return None;
+    }
+
+    let mut norm_buf = [0u8; 1024];
+    let violation = {
+        // Opens issued by the inception layer itself (CAS blobs, IPC) are exempt
+        let _guard = InceptionLayerGuard::enter()?;
+
+        let normalized = if path.starts_with('/') {
+            let len = crate::path::raw_path_normalize(path, &mut norm_buf)?;
+            std::str::from_utf8(&norm_buf[..len]).ok()?
+        } else {
+            let vpath = state.resolve_path(path)?;
+            let abs = vpath.absolute.as_str();
+            let len = abs.len().min(norm_buf.len());
+            norm_buf[..len].copy_from_slice(&abs.as_bytes()[..len]);
+            std::str::from_utf8(&norm_buf[..len]).ok()?
+        };
+
+        if policy.allows(normalized) {
+            return None;
+        }
+        if let Some(vpath) = state.resolve_path(normalized) {
+            if state.query_manifest(&vpath).is_some() {
+                return None;
+            }
+        }
+
+        // Probing for a file that doesn't exist is not an input; let the open
+        // fail with ENOENT as usual.
+        let cpath = std::ffi::CString::new(normalized).ok()?;
+        if raw_access(cpath.as_ptr(), libc::F_OK) != 0 {
+            return None;
+        }
+        normalized
+    };
+
+    SANDBOX_VIOLATIONS.fetch_add(1, Ordering::Relaxed);
+    inception_record!(
+        EventType::SandboxViolation,
+        crate::state::fnv1a_hash(violation),
+        0
+    );
+    inception_warn!("SANDBOX: undeclared input '{}'", violation);
+    policy.append_report(violation);
+
+    match policy.mode {
+        SandboxMode::Deny => Some(libc::EACCES),
+        _ => None,
+    }
+}
@@ -0,0 +1,202 @@
+// =============================================================================
+// state/crash.rs — Fatal-signal crash reports
+// =============================================================================
+//
+// On SIGSEGV/SIGBUS/SIGILL/SIGFPE/SIGABRT the handler writes
+// `<log dir>/vrift-shim-crash-<pid>.log` with the signal, faulting address
+// and the contents of the LOGGER ring buffer, then restores the previous
+// disposition and re-raises so the host process dies (or recovers) exactly
+// as it would have without us.
+//
+// Everything on the handler path is async-signal-safe: raw syscalls, a path
+// prefix prepared at install time, and core::fmt into stack buffers.
+// =============================================================================
+
+use std::cell::UnsafeCell;
+use std::ffi::CStr;
+use std::fmt::Write;
+use std::mem::MaybeUninit;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use libc::{c_int, c_void};
+
+use super::LOGGER;
+use crate::macros::StackWriter;
+
+const SIGNALS: [c_int; 5] = [
+    libc::SIGSEGV,
+    libc::SIGBUS,
+    libc::SIGILL,
+    libc::SIGFPE,
+    libc::SIGABRT,
+];
+
+/// Room for `<log dir>/vrift-shim-crash-` plus pid, suffix and NUL
+const PATH_CAP: usize = 512;
+const FILE_PREFIX: &[u8] = b"/vrift-shim-crash-";
+
+struct CrashState {
+    prev: UnsafeCell<[MaybeUninit<libc::sigaction>; SIGNALS.len()]>,
+    path_prefix: UnsafeCell<[u8; PATH_CAP]>,
+}
+
+// SAFETY: written once by install() before any handler is registered,
+// read-only afterwards.
+unsafe impl Sync for CrashState {}
+
+static STATE: CrashState = CrashState {
+    prev: UnsafeCell::new([const { MaybeUninit::uninit() }; SIGNALS.len()]),
+    path_prefix: UnsafeCell::new([0; PATH_CAP]),
+};
+static PREFIX_LEN: AtomicUsize = AtomicUsize::new(0);
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+static REPORTING: AtomicBool = AtomicBool::new(false);
+
+/// Register the crash handlers. Reports go to `VRIFT_LOG_DIR` (default
+/// `/tmp`, matching the daemon's `log_dir`).
+pub(crate) unsafe fn install() {
+    if INSTALLED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    let dir_ptr = libc::getenv(c"VRIFT_LOG_DIR".as_ptr());
+    let dir: &[u8] = if dir_ptr.is_null() {
+        b"/tmp"
+    } else {
+        CStr::from_ptr(dir_ptr).to_bytes()
+    };
+    let prefix = &mut *STATE.path_prefix.get();
+    // pid (<= 20 digits) + ".log" + NUL
+    if dir.len() + FILE_PREFIX.len() + 25 > PATH_CAP {
+        return;
+    }
+    prefix[..dir.len()].copy_from_slice(dir);
+    prefix[dir.len()..dir.len() + FILE_PREFIX.len()].copy_from_slice(FILE_PREFIX);
+    PREFIX_LEN.store(dir.len() + FILE_PREFIX.len(), Ordering::Release);
+
+    let prev = &mut *STATE.prev.get();
+    for (i, &sig) in SIGNALS.iter().enumerate() {
+        let mut action: libc::sigaction = std::mem::zeroed();
+        action.sa_sigaction = on_fatal_signal as usize;
+        action.sa_flags = libc::SA_SIGINFO | libc::SA_ONSTACK;
+        libc::sigemptyset(&mut action.sa_mask);
+        libc::sigaction(sig, &action, prev[i].as_mut_ptr());
+    }
+}
+
+extern "C" fn on_fatal_signal(sig: c_int, info: *mut libc::siginfo_t, _ctx: *mut c_void) {
+    unsafe {
+        // A second fault while reporting skips straight to the re-raise.
+        if !REPORTING.swap(true, Ordering::SeqCst) {
+            write_report(sig, info);
+        }
+
+        if let Some(i) = SIGNALS.iter().position(|&s| s == sig) {
+            let prev = &*STATE.prev.get();
+            libc::sigaction(sig, prev[i].as_ptr(), std::ptr::null_mut());
+        }
+        // Blocked until we return: then delivered to the restored handler.
+        libc::raise(sig);
+    }
+}
+
+unsafe fn write_report(sig: c_int, info: *mut libc::siginfo_t) {
+    let prefix_len = PREFIX_LEN.load(Ordering::Acquire);
+    if prefix_len == 0 {
+        return;
+    }
+    let pid = libc::getpid();
+
+    let mut path = [0u8; PATH_CAP];
+    let prefix = &*STATE.path_prefix.get();
+    path[..prefix_len].copy_from_slice(&prefix[..prefix_len]);
+    let mut tail = [0u8; 32];
+    let mut w = StackWriter::new(&mut tail);
+    let _ = write!(w, "{}.log", pid);
+    let tail = w.as_str().as_bytes();
+    path[prefix_len..prefix_len + tail.len()].copy_from_slice(tail);
+    // path[prefix_len + tail.len()] is already NUL
+
+    let fd = raw_open(
+        path.as_ptr() as *const libc::c_char,
+        libc::O_WRONLY | libc::O_CREAT | libc::O_TRUNC | libc::O_CLOEXEC,
+        0o600,
+    );
+    if fd < 0 {
+        return;
+    }
+
+    let fault_addr = if info.is_null() {
+        0
+    } else {
+        #[cfg(target_os = "linux")]
+        let addr = (*info).si_addr() as usize;
+        #[cfg(not(target_os = "linux"))]
+        let addr = (*info).si_addr as usize;
+        addr
+    };
+    let mut header = [0u8; 256];
+    let mut w = StackWriter::new(&mut header);
+    let _ = write!(
+        w,
+        "vrift inception layer crash\nsignal: {} ({})\npid: {}\nfault_addr: 0x{:x}\n\n--- log ring buffer ---\n",
+        sig,
+        signal_name(sig),
+        pid,
+        fault_addr
+    );
+    raw_write_all(fd, w.as_str().as_bytes());
+    LOGGER.flush_to_fd(fd);
+    raw_write_all(fd, b"\n--- end ---\n");
+    raw_close(fd);
+
+    let mut note = [0u8; PATH_CAP + 64];
+    let mut w = StackWriter::new(&mut note);
+    let _ = writeln!(
+        w,
+        "[vrift-shim] FATAL: {} - crash report: {}",
+        signal_name(sig),
+        std::str::from_utf8(&path[..prefix_len + tail.len()]).unwrap_or("?")
+    );
+    raw_write_all(2, w.as_str().as_bytes());
+}
+
+fn signal_name(sig: c_int) -> &'static str {
+    match sig {
+        libc::SIGSEGV => "SIGSEGV",
+        libc::SIGBUS => "SIGBUS",
+        libc::SIGILL => "SIGILL",
+        libc::SIGFPE => "SIGFPE",
+        libc::SIGABRT => "SIGABRT",
+        _ => "signal",
+    }
+}
+
+pub(super) unsafe fn raw_write_all(fd: c_int, mut bytes: &[u8]) {
+    while !bytes.is_empty() {
+        #[cfg(target_os = "macos")]
+        let n =
+            crate::syscalls::macos_raw::raw_write(fd, bytes.as_ptr() as *const c_void, bytes.len());
+        #[cfg(target_os = "linux")]
+        let n =
+            crate::syscalls::linux_raw::raw_write(fd, bytes.as_ptr() as *const c_void, bytes.len());
+        if n <= 0 {
+            return;
+        }
+        bytes = &bytes[n as usize..];
+    }
+}
+
+unsafe fn raw_open(path: *const libc::c_char, flags: c_int, mode: libc::mode_t) -> c_int {
+    #[cfg(target_os = "macos")]
+    return crate::syscalls::macos_raw::raw_open(path, flags, mode);
+    #[cfg(target_os = "linux")]
+    return crate::syscalls::linux_raw::raw_open(path, flags, mode);
+}
+
+unsafe fn raw_close(fd: c_int) {
+    #[cfg(target_os = "macos")]
+    crate::syscalls::macos_raw::raw_close(fd);
+    #[cfg(target_os = "linux")]
+    crate::syscalls::linux_raw::raw_close(fd);
+}
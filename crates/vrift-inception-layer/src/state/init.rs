@@ -8,6 +8,7 @@
 //   - init_logger()       — read env vars for log level and debug mode
 //   - boost_fd_limit()    — raise RLIMIT_NOFILE to 80% of hard cap
 //   - open_manifest_mmap()— mmap the manifest file for O(1) stat lookup
+//   - vdir_file_id()      — identity of that file, to notice it was replaced
 //   - init()              — primary initialization, allocates state via raw_mmap
 //   - audit_environment() — detect hazardous env vars
 //   - init_reactor()      — initialize the ring buffer reactor
@@ -23,9 +24,10 @@ use std::path::PathBuf;
 use std::ptr;
 use std::sync::atomic::Ordering;
 
+use super::vdir_map::{VdirMap, VdirMapping};
 use super::{
     FixedString, IdentityBuildHasher, InceptionLayerState, LogLevel, CIRCUIT_BREAKER_THRESHOLD,
-    DEBUG_ENABLED, FLIGHT_RECORDER, LOGGER, LOG_LEVEL,
+    DEBUG_ENABLED, FLIGHT_RECORDER, LOGGER, LOG_BURST, LOG_LEVEL, LOG_RATE, STDERR_LOG_LEVEL,
 };
 
 impl InceptionLayerState {
@@ -33,31 +35,44 @@ impl InceptionLayerState {
         let debug_ptr = libc::getenv(c"VRIFT_DEBUG".as_ptr());
         if !debug_ptr.is_null() {
             DEBUG_ENABLED.store(true, Ordering::Relaxed);
+            STDERR_LOG_LEVEL.store(LogLevel::Trace as u8, Ordering::Relaxed);
         }
 
-        // RFC-0050: Read log level
+        // RFC-0050: Read log level (zero-allocation parsing)
         let level_ptr = unsafe { libc::getenv(c"VRIFT_LOG_LEVEL".as_ptr()) };
         if !level_ptr.is_null() {
-            // Zero-allocation parsing
             let level_bytes = unsafe { CStr::from_ptr(level_ptr).to_bytes() };
-            let level = if level_bytes.eq_ignore_ascii_case(b"trace") {
-                LogLevel::Trace
-            } else if level_bytes.eq_ignore_ascii_case(b"debug") {
-                LogLevel::Debug
-            } else if level_bytes.eq_ignore_ascii_case(b"info") {
-                LogLevel::Info
-            } else if level_bytes.eq_ignore_ascii_case(b"warn") {
-                LogLevel::Warn
-            } else if level_bytes.eq_ignore_ascii_case(b"error") {
-                LogLevel::Error
-            } else if level_bytes.eq_ignore_ascii_case(b"off") {
-                LogLevel::Off
-            } else {
-                LogLevel::Info
-            };
+            let level = LogLevel::parse(level_bytes).unwrap_or(LogLevel::Info);
             LOG_LEVEL.store(level as u8, Ordering::Relaxed);
         }
 
+        // Level from which log lines are also copied to stderr
+        let stderr_ptr = unsafe { libc::getenv(c"VRIFT_LOG_STDERR".as_ptr()) };
+        if !stderr_ptr.is_null() {
+            let stderr_bytes = unsafe { CStr::from_ptr(stderr_ptr).to_bytes() };
+            if let Some(level) = LogLevel::parse(stderr_bytes) {
+                STDERR_LOG_LEVEL.store(level as u8, Ordering::Relaxed);
+            }
+        }
+
+        // Per-call-site rate limit: "<rate>" or "<rate>/<burst>"
+        let rate_ptr = unsafe { libc::getenv(c"VRIFT_LOG_RATE".as_ptr()) };
+        if !rate_ptr.is_null() {
+            let rate_bytes = unsafe { CStr::from_ptr(rate_ptr).to_bytes() };
+            if let Ok(s) = std::str::from_utf8(rate_bytes) {
+                let (rate, burst) = match s.split_once('/') {
+                    Some((rate, burst)) => (rate, Some(burst)),
+                    None => (s, None),
+                };
+                if let Ok(rate) = rate.trim().parse::<u32>() {
+                    LOG_RATE.store(rate, Ordering::Relaxed);
+                }
+                if let Some(Ok(burst)) = burst.map(|b| b.trim().parse::<u32>()) {
+                    LOG_BURST.store(burst.max(1), Ordering::Relaxed);
+                }
+            }
+        }
+
         // RFC-0050: Read circuit breaker threshold
         let threshold_ptr = unsafe { libc::getenv(c"VRIFT_CIRCUIT_BREAKER_THRESHOLD".as_ptr()) };
         if !threshold_ptr.is_null() {
@@ -202,6 +217,18 @@ impl InceptionLayerState {
             }
         }
 
+        // Keys are looked up in the Unicode form they were ingested in
+        let form_ptr = unsafe { libc::getenv(c"VRIFT_KEY_NORMALIZATION".as_ptr()) };
+        if !form_ptr.is_null() {
+            let form = unsafe { CStr::from_ptr(form_ptr) }
+                .to_str()
+                .ok()
+                .and_then(vrift_ipc::path_key::Normalization::parse);
+            if let Some(form) = form {
+                vrift_ipc::path_key::set_normalization(form);
+            }
+        }
+
         let mut socket_path = FixedString::<1024>::new();
         let socket_ptr = unsafe { libc::getenv(c"VRIFT_SOCKET_PATH".as_ptr()) };
         if socket_ptr.is_null() {
@@ -210,7 +237,7 @@ impl InceptionLayerState {
             socket_path.set(&unsafe { CStr::from_ptr(socket_ptr).to_string_lossy() });
         }
 
-        let (mmap_ptr, mmap_size) = open_manifest_mmap();
+        let vdir = VdirMap::new(open_manifest_mmap());
 
         let mut project_root_fs = FixedString::<1024>::new();
         let manifest_ptr = unsafe { libc::getenv(c"VRIFT_MANIFEST".as_ptr()) };
@@ -343,8 +370,7 @@ impl InceptionLayerState {
                     active_mmaps: RecursiveMutex::new(HashMap::with_hasher(IdentityBuildHasher)),
                     open_dirs: RecursiveMutex::new(HashMap::with_hasher(IdentityBuildHasher)),
                     bloom_ptr: ptr::null(),
-                    mmap_ptr,
-                    mmap_size,
+                    vdir,
                     project_root: project_root_fs,
                     path_resolver: PathResolver::new(vfs_prefix.as_str(), project_root_fs.as_str()),
                     cached_soft_limit: std::sync::atomic::AtomicUsize::new(soft_limit),
@@ -418,15 +444,12 @@ impl InceptionLayerState {
 // open_manifest_mmap: mmap-based O(1) stat lookup (BUG-007b: #[inline(never)])
 // =============================================================================
 
-/// Open mmap'd manifest file for O(1) stat lookup.
-/// Returns (ptr, size) or (null, 0) if unavailable.
-/// Uses raw libc to avoid recursion through inception layer.
+/// Path of the VDir file this process maps, NUL-terminated in `path_buf`.
+/// False if mmap is disabled or no path can be derived.
 /// BUG-007b: MUST NOT be inlined — allocates large stack buffers (PATH_MAX etc.)
-/// that would overflow the 512KB default pthread stack if merged into get().
 #[inline(never)]
 #[cold]
-#[allow(deprecated)]
-pub(crate) fn open_manifest_mmap() -> (*const u8, usize) {
+fn vdir_mmap_path(path_buf: &mut [u8; 1024]) -> bool {
     // Check if mmap is explicitly disabled
     unsafe {
         let env_key = c"VRIFT_DISABLE_MMAP";
@@ -434,7 +457,7 @@ pub(crate) fn open_manifest_mmap() -> (*const u8, usize) {
         if !env_val.is_null() {
             let val = CStr::from_ptr(env_val).to_str().unwrap_or("0");
             if val == "1" || val == "true" {
-                return (ptr::null(), 0);
+                return false;
             }
         }
     }
@@ -443,8 +466,7 @@ pub(crate) fn open_manifest_mmap() -> (*const u8, usize) {
     let vdir_mmap_ptr = unsafe { libc::getenv(c"VRIFT_VDIR_MMAP".as_ptr()) };
 
     // Construct path on stack
-    let mut path_buf = [0u8; 1024];
-    let mut writer = crate::macros::StackWriter::new(&mut path_buf);
+    let mut writer = crate::macros::StackWriter::new(path_buf);
     use std::fmt::Write;
 
     if !vdir_mmap_ptr.is_null() {
@@ -455,7 +477,7 @@ pub(crate) fn open_manifest_mmap() -> (*const u8, usize) {
         // Fallback: Derive from VRIFT_MANIFEST (legacy path)
         let manifest_ptr = unsafe { libc::getenv(c"VRIFT_MANIFEST".as_ptr()) };
         if manifest_ptr.is_null() {
-            return (ptr::null(), 0);
+            return false;
         }
 
         let root_bytes = unsafe { CStr::from_ptr(manifest_ptr).to_bytes() };
@@ -516,6 +538,41 @@ pub(crate) fn open_manifest_mmap() -> (*const u8, usize) {
         let _ = write!(writer, "{}\0", mmap_path.display());
     }
 
+    true
+}
+
+/// (st_dev, st_ino) of the VDir file, if it exists
+#[inline(never)]
+#[cold]
+#[allow(clippy::unnecessary_cast)] // dev_t is i32 on macOS, u64 on Linux
+pub(crate) fn vdir_file_id() -> Option<(u64, u64)> {
+    let mut path_buf = [0u8; 1024];
+    if !vdir_mmap_path(&mut path_buf) {
+        return None;
+    }
+    let path = path_buf.as_ptr() as *const libc::c_char;
+    let mut stat_buf: libc::stat = unsafe { std::mem::zeroed() };
+    #[cfg(target_os = "macos")]
+    let result = unsafe { crate::syscalls::macos_raw::raw_stat(path, &mut stat_buf) };
+    #[cfg(target_os = "linux")]
+    let result = unsafe { crate::syscalls::linux_raw::raw_stat(path, &mut stat_buf) };
+    (result == 0).then_some((stat_buf.st_dev as u64, stat_buf.st_ino as u64))
+}
+
+/// Open mmap'd manifest file for O(1) stat lookup.
+/// Returns [`VdirMapping::NONE`] if unavailable.
+/// Uses raw libc to avoid recursion through inception layer.
+/// BUG-007b: MUST NOT be inlined — allocates large stack buffers (PATH_MAX etc.)
+/// that would overflow the 512KB default pthread stack if merged into get().
+#[inline(never)]
+#[cold]
+#[allow(deprecated)]
+#[allow(clippy::unnecessary_cast)] // dev_t is i32 on macOS, u64 on Linux
+pub(crate) fn open_manifest_mmap() -> VdirMapping {
+    let mut path_buf = [0u8; 1024];
+    if !vdir_mmap_path(&mut path_buf) {
+        return VdirMapping::NONE;
+    }
     let mmap_path_ptr = path_buf.as_ptr() as *const libc::c_char;
 
     #[cfg(target_os = "macos")]
@@ -532,7 +589,7 @@ pub(crate) fn open_manifest_mmap() -> (*const u8, usize) {
         )
     };
     if fd < 0 {
-        return (ptr::null(), 0);
+        return VdirMapping::NONE;
     }
 
     // Get file size via fstat
@@ -550,7 +607,7 @@ pub(crate) fn open_manifest_mmap() -> (*const u8, usize) {
         unsafe {
             crate::syscalls::linux_raw::raw_close(fd)
         };
-        return (ptr::null(), 0);
+        return VdirMapping::NONE;
     }
     let size = stat_buf.st_size as usize;
 
@@ -587,29 +644,32 @@ pub(crate) fn open_manifest_mmap() -> (*const u8, usize) {
     };
 
     if ptr == libc::MAP_FAILED {
-        return (ptr::null(), 0);
+        return VdirMapping::NONE;
     }
 
-    // Phase 1.3: Validate VDirHeader magic instead of ManifestMmapHeader
-    use vrift_ipc::vdir_types::{VDIR_HEADER_SIZE, VDIR_MAGIC};
-    if size < VDIR_HEADER_SIZE {
-        unsafe { libc::munmap(ptr, size) };
-        return (ptr::null(), 0);
-    }
-    let magic = unsafe { *(ptr as *const u32) };
-    if magic != VDIR_MAGIC {
+    // Phase 1.3: Validate VDirHeader magic instead of ManifestMmapHeader.
+    // The version is checked per lookup, since vDird may renegotiate it.
+    if unsafe { vrift_ipc::vdir_types::vdir_header_version(ptr as *const u8, size) }.is_none() {
         // Fallback: Try legacy ManifestMmapHeader format
-        if size >= vrift_ipc::ManifestMmapHeader::SIZE {
-            let header = unsafe { &*(ptr as *const vrift_ipc::ManifestMmapHeader) };
-            if header.is_valid() {
-                return (ptr as *const u8, size);
-            }
+        let bytes = unsafe { std::slice::from_raw_parts(ptr as *const u8, size) };
+        if vrift_ipc::ManifestMmapHeader::read_le(bytes).is_some_and(|h| h.is_valid()) {
+            return VdirMapping {
+                ptr: ptr as *const u8,
+                size,
+                dev: stat_buf.st_dev as u64,
+                ino: stat_buf.st_ino as u64,
+            };
         }
         unsafe { libc::munmap(ptr, size) };
-        return (ptr::null(), 0);
+        return VdirMapping::NONE;
     }
 
-    (ptr as *const u8, size)
+    VdirMapping {
+        ptr: ptr as *const u8,
+        size,
+        dev: stat_buf.st_dev as u64,
+        ino: stat_buf.st_ino as u64,
+    }
 }
 
 // =============================================================================
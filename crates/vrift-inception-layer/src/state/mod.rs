@@ -12,8 +12,11 @@
 // Background worker code lives in state/worker.rs
 // =============================================================================
 
+mod crash;
 mod init;
+mod vdir_map;
 mod worker;
+pub(crate) use worker::queue_reingest;
 
 use crate::ipc::*;
 use crate::path::{PathResolver, VfsPath};
@@ -22,7 +25,9 @@ use libc::{c_int, c_void};
 use std::collections::HashMap;
 use std::ffi::CStr;
 use std::ptr;
-use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicU64, AtomicU8, AtomicUsize, Ordering};
+use std::sync::atomic::{
+    AtomicBool, AtomicPtr, AtomicU32, AtomicU64, AtomicU8, AtomicUsize, Ordering,
+};
 
 // ============================================================================
 // Global State & Recursion Guards
@@ -125,6 +130,11 @@ pub enum EventType {
     Close = 10,
     ReingestSuccess = 11,
     ReingestFail = 12,
+    SandboxViolation = 13,
+    MmapAdvise = 14,
+    IpcDegraded = 15,
+    IpcRecovered = 16,
+    IpcReconnect = 17,
 }
 
 #[repr(C)]
@@ -202,6 +212,11 @@ pub static EVENT_NAMES: &[&str] = &[
     "Close",
     "ReingestSuccess",
     "ReingestFail",
+    "SandboxViolation",
+    "MmapAdvise",
+    "IpcDegraded",
+    "IpcRecovered",
+    "IpcReconnect",
 ];
 
 // ============================================================================
@@ -393,9 +408,153 @@ impl LogLevel {
             _ => LogLevel::Off,
         }
     }
+
+    /// Case-insensitive level name, as used by `VRIFT_LOG_LEVEL`
+    pub fn parse(name: &[u8]) -> Option<Self> {
+        [
+            LogLevel::Trace,
+            LogLevel::Debug,
+            LogLevel::Info,
+            LogLevel::Warn,
+            LogLevel::Error,
+            LogLevel::Off,
+        ]
+        .into_iter()
+        .find(|l| name.eq_ignore_ascii_case(l.as_str().as_bytes()))
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LogLevel::Trace => "trace",
+            LogLevel::Debug => "debug",
+            LogLevel::Info => "info",
+            LogLevel::Warn => "warn",
+            LogLevel::Error => "error",
+            LogLevel::Off => "off",
+        }
+    }
 }
 
+/// Minimum level recorded in the log ring buffer (`VRIFT_LOG_LEVEL`)
 pub static LOG_LEVEL: AtomicU8 = AtomicU8::new(LogLevel::Info as u8);
+/// Minimum level also copied to stderr (`VRIFT_LOG_STDERR`; `VRIFT_DEBUG`
+/// copies everything that is recorded)
+pub static STDERR_LOG_LEVEL: AtomicU8 = AtomicU8::new(LogLevel::Off as u8);
+/// Sustained messages per second allowed from one log call site
+/// (`VRIFT_LOG_RATE=<rate>[/<burst>]`, 0 = unlimited)
+pub static LOG_RATE: AtomicU32 = AtomicU32::new(20);
+/// Messages one call site may emit back-to-back before the rate applies
+pub static LOG_BURST: AtomicU32 = AtomicU32::new(50);
+
+/// Token bucket for one log call site, kept in GCRA form so the whole state
+/// is a single atomic: `tat` is the time at which the bucket will be full
+/// again. Messages that find it emptier than `LOG_BURST` allows are dropped
+/// and counted; the next admitted message reports the count.
+pub struct RateLimit {
+    tat: AtomicU64,
+    suppressed: AtomicU32,
+}
+
+impl RateLimit {
+    pub const fn new() -> Self {
+        Self {
+            tat: AtomicU64::new(0),
+            suppressed: AtomicU32::new(0),
+        }
+    }
+
+    /// `Some(dropped since the last admitted message)`, or `None` to drop
+    pub fn admit(&self) -> Option<u32> {
+        self.admit_at(
+            clock_ns(libc::CLOCK_MONOTONIC),
+            LOG_RATE.load(Ordering::Relaxed),
+            LOG_BURST.load(Ordering::Relaxed),
+        )
+    }
+
+    /// [`admit`](Self::admit) at monotonic time `now` (ns) with the given
+    /// limits
+    fn admit_at(&self, now: u64, rate: u32, burst: u32) -> Option<u32> {
+        if rate == 0 {
+            return Some(self.suppressed.swap(0, Ordering::Relaxed));
+        }
+        let interval = 1_000_000_000 / rate as u64;
+        let tolerance = interval * (burst.max(1) as u64 - 1);
+
+        let mut tat = self.tat.load(Ordering::Relaxed);
+        loop {
+            let start = tat.max(now);
+            if start - now > tolerance {
+                self.suppressed.fetch_add(1, Ordering::Relaxed);
+                return None;
+            }
+            match self.tat.compare_exchange_weak(
+                tat,
+                start + interval,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return Some(self.suppressed.swap(0, Ordering::Relaxed)),
+                Err(current) => tat = current,
+            }
+        }
+    }
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub(crate) fn clock_ns(clock: libc::clockid_t) -> u64 {
+    let mut ts = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    unsafe { libc::clock_gettime(clock, &mut ts) };
+    ts.tv_sec as u64 * 1_000_000_000 + ts.tv_nsec as u64
+}
+
+/// Write one log line to the ring buffer (and stderr, per `STDERR_LOG_LEVEL`).
+///
+/// Lines are `key=value` pairs with the free-form message last and quoted:
+/// `[VR-INCEPTION] ts=1700000000.123456 pid=42 level=warn suppressed=3 msg="..."`
+/// (`suppressed` only when the call site dropped messages since its last line).
+/// Formatting happens on the stack; long messages are truncated.
+pub fn log_record(level: LogLevel, suppressed: u32, args: std::fmt::Arguments) {
+    use std::fmt::Write;
+
+    let mut buf = [0u8; 512];
+    let mut w = crate::macros::StackWriter::new(&mut buf);
+    let now = clock_ns(libc::CLOCK_REALTIME);
+    let pid = unsafe { libc::getpid() };
+    let _ = write!(
+        w,
+        "[VR-INCEPTION] ts={}.{:06} pid={} level={}",
+        now / 1_000_000_000,
+        now % 1_000_000_000 / 1000,
+        pid,
+        level.as_str()
+    );
+    if suppressed > 0 {
+        let _ = write!(w, " suppressed={}", suppressed);
+    }
+    let _ = w.write_str(" msg=\"");
+    let _ = std::fmt::write(&mut crate::macros::QuotedWriter(&mut w), args);
+    w.finish_with("\"\n");
+
+    let msg = w.as_str();
+    LOGGER.log(msg);
+    if STDERR_LOG_LEVEL.load(Ordering::Relaxed) <= level as u8 {
+        unsafe {
+            #[cfg(target_os = "macos")]
+            crate::syscalls::macos_raw::raw_write(2, msg.as_ptr() as *const c_void, msg.len());
+            #[cfg(target_os = "linux")]
+            libc::write(2, msg.as_ptr() as *const c_void, msg.len());
+        }
+    }
+}
 
 /// Circuit breaker state: trips after consecutive failures
 pub static CIRCUIT_BREAKER_FAILED_COUNT: AtomicUsize = AtomicUsize::new(0);
@@ -406,6 +565,14 @@ pub static CIRCUIT_TRIP_TIME: AtomicU64 = AtomicU64::new(0);
 /// Recovery delay in seconds (default 30s, configurable via VRIFT_CIRCUIT_RECOVERY_DELAY)
 pub static CIRCUIT_RECOVERY_DELAY: AtomicU64 = AtomicU64::new(30);
 
+/// Degraded mode: the daemon stayed unreachable through the connect retries,
+/// so lookups answer from the VDir mmap alone (see ipc.rs)
+pub static IPC_DEGRADED: AtomicBool = AtomicBool::new(false);
+/// Set by the first successful connect; only then are failed connects retried
+pub static IPC_EVER_CONNECTED: AtomicBool = AtomicBool::new(false);
+/// Earliest time (ms since the epoch) of the next reconnect probe while degraded
+pub static IPC_NEXT_PROBE_MS: AtomicU64 = AtomicU64::new(0);
+
 /// Activate VFS - called when daemon handshake succeeds
 #[inline]
 pub fn activate_vfs() {
@@ -560,6 +727,19 @@ impl Logger {
         }
     }
 
+    /// Write the buffered log, oldest first, to `fd`. Async-signal-safe.
+    pub(crate) unsafe fn flush_to_fd(&self, fd: libc::c_int) {
+        let head = self.head.load(Ordering::SeqCst);
+        let buf = &self.buffer[..];
+        if head > LOG_BUF_SIZE {
+            let start = head % LOG_BUF_SIZE;
+            crash::raw_write_all(fd, &buf[start..]);
+            crash::raw_write_all(fd, &buf[..start]);
+        } else {
+            crash::raw_write_all(fd, &buf[..head]);
+        }
+    }
+
     #[allow(dead_code)]
     pub(crate) fn dump(&self) {
         let head = self.head.load(Ordering::SeqCst);
@@ -572,29 +752,38 @@ impl Logger {
         }
     }
 
-    #[allow(dead_code)]
+    /// Append the buffered log to `<VRIFT_LOG_DIR>/vrift-shim-<pid>.log`,
+    /// rotating that file first once it has reached `VRIFT_LOG_MAX_SIZE`.
     pub(crate) fn dump_to_file(&self) {
-        let pid = unsafe { libc::getpid() };
-        let path = format!("/tmp/vrift-inception-layer-{}.log", pid);
-        if let Ok(mut f) = std::fs::File::create(&path) {
-            use std::io::Write;
-            let head = self.head.load(Ordering::SeqCst);
-            let size = if head > LOG_BUF_SIZE {
-                LOG_BUF_SIZE
-            } else {
-                head
-            };
-            let start = if head > LOG_BUF_SIZE {
-                head % LOG_BUF_SIZE
-            } else {
-                0
-            };
-            if head > LOG_BUF_SIZE {
-                let _ = f.write_all(&self.buffer[start..]);
-                let _ = f.write_all(&self.buffer[..start]);
-            } else {
-                let _ = f.write_all(&self.buffer[..size]);
-            }
+        use std::io::Write;
+        use vrift_config::log_files;
+
+        let head = self.head.load(Ordering::SeqCst);
+        if head == 0 {
+            return;
+        }
+        let dir = std::env::var_os("VRIFT_LOG_DIR").unwrap_or_else(|| "/tmp".into());
+        let max_bytes = std::env::var("VRIFT_LOG_MAX_SIZE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(log_files::DEFAULT_MAX_BYTES);
+        let pid = unsafe { libc::getpid() } as u32;
+        let path = log_files::shim_log_path(std::path::Path::new(&dir), pid);
+        let _ = log_files::rotate_if_needed(&path, max_bytes);
+
+        let Ok(mut f) = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+        else {
+            return;
+        };
+        if head > LOG_BUF_SIZE {
+            let start = head % LOG_BUF_SIZE;
+            let _ = f.write_all(&self.buffer[start..]);
+            let _ = f.write_all(&self.buffer[..start]);
+        } else {
+            let _ = f.write_all(&self.buffer[..head]);
         }
     }
 }
@@ -777,6 +966,10 @@ pub(crate) struct SyntheticDir {
     pub vpath: FixedString<1024>,
     pub entries: Vec<vrift_ipc::DirEntry>,
     pub position: usize,
+    /// What readdir last returned for this stream; boxed so that it stays
+    /// put while other streams come and go
+    #[cfg(target_os = "linux")]
+    pub dirent: Box<libc::dirent64>,
 }
 unsafe impl Send for SyntheticDir {} // Raw pointers in open_dirs HashMap
 unsafe impl Sync for SyntheticDir {}
@@ -791,118 +984,10 @@ pub(crate) static SYNTHETIC_DIR_COUNTER: std::sync::atomic::AtomicUsize =
 
 // ============================================================================
 // Phase 1.3: vdir_lookup — seqlock-protected O(1) stat from VDir mmap
+// (shared reader lives in vrift_ipc::vdir_types)
 // ============================================================================
 
-use vrift_ipc::vdir_types::{VDirEntry, VDIR_ENTRY_SIZE, VDIR_HEADER_SIZE, VDIR_MAGIC};
-
-/// Result from VDir lookup (VDirEntry fields needed for stat)
-#[derive(Debug, Clone, Copy)]
-pub(crate) struct VDirStatResult {
-    pub size: u64,
-    pub mtime_sec: i64,
-    pub mtime_nsec: u32,
-    pub mode: u32,
-    pub flags: u16,
-    pub cas_hash: [u8; 32],
-}
-
-/// Maximum seqlock spins before giving up and falling back to IPC.
-/// Prevents infinite hang if vDird crashes mid-write (odd generation stuck).
-const MAX_SEQLOCK_SPINS: u32 = 1000;
-
-/// O(1) seqlock-protected stat lookup from VDir MAP_SHARED mmap.
-/// ZERO ALLOCATIONS, ZERO LOCKS, ZERO SYSCALLS — safe for PSFS hot path.
-#[inline(always)]
-pub(crate) fn vdir_lookup(
-    mmap_ptr: *const u8,
-    mmap_size: usize,
-    path: &str,
-) -> Option<VDirStatResult> {
-    if mmap_ptr.is_null() || mmap_size < VDIR_HEADER_SIZE {
-        return None;
-    }
-
-    // Validate magic (first 4 bytes of header)
-    let magic = unsafe { *(mmap_ptr as *const u32) };
-    if magic != VDIR_MAGIC {
-        return None;
-    }
-
-    // Read header fields we need (offsets from VDirHeader layout)
-    // generation is at offset 8 (after magic:u32 + version:u32)
-    let gen_addr = mmap_ptr as usize + 8;
-    debug_assert!(
-        gen_addr.is_multiple_of(8),
-        "AtomicU64 (generation) not 8-byte aligned"
-    );
-    let gen_ptr = unsafe { &*(gen_addr as *const AtomicU64) };
-    // table_capacity at offset 20 (u32), table_offset at offset 24 (u32)
-    let table_capacity = unsafe { *((mmap_ptr as usize + 20) as *const u32) } as usize;
-    let table_offset = unsafe { *((mmap_ptr as usize + 24) as *const u32) } as usize;
-
-    if table_capacity == 0 {
-        return None;
-    }
-
-    let path_hash = vrift_ipc::fnv1a_hash(path);
-    let start_slot = (path_hash as usize) % table_capacity;
-
-    // Seqlock read loop with bounded spin
-    let mut spins: u32 = 0;
-    loop {
-        let g1 = gen_ptr.load(Ordering::Acquire);
-        if g1 & 1 != 0 {
-            // Writer active (odd generation) — spin with upper bound
-            spins += 1;
-            if spins > MAX_SEQLOCK_SPINS {
-                return None; // Fallback: vDird may have crashed mid-write
-            }
-            core::hint::spin_loop();
-            continue;
-        }
-
-        // O(1) hash table lookup with linear probing
-        let mut result: Option<VDirStatResult> = None;
-        for i in 0..table_capacity {
-            let slot = (start_slot + i) % table_capacity;
-            let entry_offset = table_offset + slot * VDIR_ENTRY_SIZE;
-            if entry_offset + VDIR_ENTRY_SIZE > mmap_size {
-                break;
-            }
-            let entry = unsafe { &*(mmap_ptr.add(entry_offset) as *const VDirEntry) };
-
-            if entry.path_hash == 0 {
-                break; // Empty slot = not found
-            }
-
-            if entry.path_hash == path_hash {
-                result = Some(VDirStatResult {
-                    size: entry.size,
-                    mtime_sec: entry.mtime_sec,
-                    mtime_nsec: entry.mtime_nsec,
-                    mode: entry.mode,
-                    flags: entry.flags,
-                    cas_hash: entry.cas_hash,
-                });
-                break;
-            }
-        }
-
-        // Re-read generation to check for concurrent write
-        let g2 = gen_ptr.load(Ordering::Acquire);
-        if g1 != g2 {
-            // Data changed during read — retry (also bounded by MAX_SEQLOCK_SPINS)
-            spins += 1;
-            if spins > MAX_SEQLOCK_SPINS {
-                return None;
-            }
-            core::hint::spin_loop();
-            continue;
-        }
-
-        return result;
-    }
-}
+pub(crate) use vrift_ipc::vdir_types::{vdir_generation, vdir_lookup};
 
 // mmap_dir_lookup removed — VDir entries store only path hashes (no filenames),
 // so readdir is served via IPC. Readdir is not on the PSFS hot path.
@@ -922,8 +1007,8 @@ pub(crate) struct InceptionLayerState {
     pub active_mmaps: RecursiveMutex<HashMap<usize, MmapInfo, IdentityBuildHasher>>,
     pub open_dirs: RecursiveMutex<HashMap<usize, SyntheticDir, IdentityBuildHasher>>,
     pub bloom_ptr: *const u8,
-    pub mmap_ptr: *const u8,
-    pub mmap_size: usize,
+    /// The VDir mmap for O(1) stat lookup
+    pub vdir: vdir_map::VdirMap,
     pub project_root: FixedString<1024>,
     pub path_resolver: PathResolver,
     pub cached_soft_limit: AtomicUsize,
@@ -966,8 +1051,9 @@ impl InceptionLayerState {
 
         let ptr = INCEPTION_LAYER_STATE.load(Ordering::Acquire);
         if !ptr.is_null() {
-            // Lazy spawn worker if not started
-            if !WORKER_STARTED.load(Ordering::Relaxed) {
+            // Lazy spawn worker if not started (the minimal build has no
+            // IPC or write-back for it to do)
+            if cfg!(not(feature = "minimal")) && !WORKER_STARTED.load(Ordering::Relaxed) {
                 Self::spawn_worker();
             }
             return unsafe { Some(&*ptr) };
@@ -1023,6 +1109,16 @@ impl InceptionLayerState {
             unsafe { libc::atexit(init::dump_logs_atexit) };
         }
 
+        // Crash reports on fatal signals. Opt-in on macOS for the same reason
+        // as above; VRIFT_CRASH_REPORTS=0 turns them off.
+        let crash_reports = unsafe {
+            let val = libc::getenv(c"VRIFT_CRASH_REPORTS".as_ptr());
+            val.is_null() || CStr::from_ptr(val).to_bytes() != b"0"
+        };
+        if crash_reports && (cfg!(target_os = "linux") || enable_handlers) {
+            unsafe { crash::install() };
+        }
+
         // Activate VFS - now it's safe to call into Rust from C wrappers.
         activate_vfs();
 
@@ -1031,26 +1127,73 @@ impl InceptionLayerState {
 
     pub(crate) fn query_manifest(&self, vpath: &VfsPath) -> Option<vrift_ipc::VnodeEntry> {
         // Seqlock-protected VDir lookup (zero alloc/lock/syscall)
-        if let Some(entry) = vdir_lookup(self.mmap_ptr, self.mmap_size, vpath.manifest_key.as_str())
+        let (mmap_ptr, mmap_size) = self.vdir.current();
+        // SAFETY: the VDir mappings live as long as the global state.
+        if let Some(entry) =
+            unsafe { vdir_lookup(mmap_ptr, mmap_size, vpath.manifest_key.as_str()) }
         {
+            // Copied up by vDird (the real file answers, not the manifest)
+            // or renamed away
+            if entry.is_passthrough() || entry.is_whiteout() {
+                return None;
+            }
             return Some(vrift_ipc::VnodeEntry {
                 content_hash: entry.cas_hash,
                 size: entry.size,
-                mtime: entry.mtime_sec as u64,
+                mtime: entry.mtime_ns(),
                 mode: entry.mode,
                 flags: entry.flags,
                 _pad: 0,
             });
         }
         // Fallback to IPC query (vDird → LMDB)
-        unsafe { sync_ipc_manifest_get(&self.vdird_socket_path, vpath.manifest_key.as_str()) }
+        #[cfg(not(feature = "minimal"))]
+        return unsafe {
+            sync_ipc_manifest_get_batched(&self.vdird_socket_path, vpath.manifest_key.as_str())
+        };
+        // Minimal build: the mmap is the whole manifest
+        #[cfg(feature = "minimal")]
+        None
+    }
+
+    /// Ingest time recorded in the VDir mmap for `vpath`, for stat synthesis
+    /// on paths that got the entry some other way (IPC, open-time cache)
+    pub(crate) fn manifest_ingest_ns(&self, vpath: &VfsPath) -> Option<i64> {
+        let (mmap_ptr, mmap_size) = self.vdir.current();
+        // SAFETY: the VDir mappings live as long as the global state.
+        unsafe { vdir_lookup(mmap_ptr, mmap_size, vpath.manifest_key.as_str()) }
+            .and_then(|entry| entry.ingest_ns())
+    }
+
+    /// Where the workspace's packfile holds the content of `vpath`, per its
+    /// VDir entry: `(pack_id, offset, len)`. Only if that entry's content is
+    /// `hash`, so an entry that changed since never names another blob.
+    pub(crate) fn manifest_pack_ref(
+        &self,
+        vpath: &VfsPath,
+        hash: &[u8; 32],
+    ) -> Option<(u32, u64, u32)> {
+        let (mmap_ptr, mmap_size) = self.vdir.current();
+        // SAFETY: the VDir mappings live as long as the global state.
+        unsafe { vdir_lookup(mmap_ptr, mmap_size, vpath.manifest_key.as_str()) }
+            .filter(|entry| entry.cas_hash == *hash)
+            .and_then(|entry| entry.pack_ref())
     }
 
     /// Query manifest directly via IPC (bypasses mmap cache)
     /// Required for open() which needs content_hash to locate CAS blob
     pub(crate) fn query_manifest_ipc(&self, vpath: &VfsPath) -> Option<vrift_ipc::VnodeEntry> {
+        if cfg!(feature = "minimal") {
+            return None;
+        }
         // Use the centrally resolved manifest key
-        unsafe { sync_ipc_manifest_get(&self.vdird_socket_path, &vpath.manifest_key) }
+        let entry = unsafe { sync_ipc_manifest_get(&self.vdird_socket_path, &vpath.manifest_key) };
+        if entry.is_none() && IPC_DEGRADED.load(Ordering::Relaxed) {
+            // No answer from vDird: the mmap may be stale, but a miss here
+            // would turn every managed file into ENOENT
+            return self.query_manifest(vpath);
+        }
+        entry
     }
 
     /// Resolve an incoming path into a VfsPath if it belongs to the VFS.
@@ -1085,14 +1228,10 @@ impl InceptionLayerState {
         }
     }
 
-    /// RFC-0047: Rename/move entry in manifest
-    /// Phase 3: Fire-and-forget — queued to worker thread
+    /// RFC-0047: Rename/move entry in manifest. Synchronous: once rename()
+    /// returns, no reader may still find the old name.
     pub(crate) fn manifest_rename(&self, old: &str, new: &str) -> Result<(), ()> {
-        let request = vrift_ipc::VeloRequest::ManifestRename {
-            old_path: old.to_string(),
-            new_path: new.to_string(),
-        };
-        if unsafe { fire_and_forget_ipc(&self.vdird_socket_path, &request) } {
+        if unsafe { sync_ipc_manifest_rename(&self.vdird_socket_path, old, new) } {
             Ok(())
         } else {
             Err(())
@@ -1103,16 +1242,12 @@ impl InceptionLayerState {
     /// Phase 3: Fire-and-forget — queued to worker thread
     #[allow(clippy::unnecessary_cast)] // mode_t is u16 on macOS, u32 on Linux
     pub(crate) fn manifest_mkdir(&self, path: &str, mode: libc::mode_t) -> Result<(), ()> {
-        use std::time::{SystemTime, UNIX_EPOCH};
         let request = vrift_ipc::VeloRequest::ManifestUpsert {
             path: path.to_string(),
             entry: vrift_ipc::VnodeEntry {
                 content_hash: [0u8; 32],
                 size: 0,
-                mtime: SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .map(|d| d.as_secs())
-                    .unwrap_or(0),
+                mtime: vrift_ipc::mtime::now(),
                 mode: mode as u32,
                 flags: 1, // is_dir flag
                 _pad: 0,
@@ -1128,16 +1263,12 @@ impl InceptionLayerState {
     /// RFC-0039: Create symlink entry in manifest for Live Ingest
     /// Phase 3: Fire-and-forget — queued to worker thread
     pub(crate) fn manifest_symlink(&self, path: &str, _target: &str) -> Result<(), ()> {
-        use std::time::{SystemTime, UNIX_EPOCH};
         let request = vrift_ipc::VeloRequest::ManifestUpsert {
             path: path.to_string(),
             entry: vrift_ipc::VnodeEntry {
                 content_hash: [0u8; 32],
                 size: 0,
-                mtime: SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .map(|d| d.as_secs())
-                    .unwrap_or(0),
+                mtime: vrift_ipc::mtime::now(),
                 mode: 0o777,
                 flags: 2, // is_symlink pseudo-flag
                 _pad: 0,
@@ -1153,8 +1284,17 @@ impl InceptionLayerState {
     /// Query daemon for directory listing (for opendir/readdir)
     #[allow(dead_code)]
     pub(crate) fn query_dir_listing(&self, path: &str) -> Option<Vec<vrift_ipc::DirEntry>> {
+        // Listings are rare enough to afford the stat that notices a VDir
+        // file created or replaced since it was mapped
+        self.vdir.revalidate();
         // Fall back to IPC (readdir is not on the PSFS hot path and VDir doesn't store filenames)
-        unsafe { sync_ipc_manifest_list_dir(&self.vdird_socket_path, path) }
+        let entries = unsafe { sync_ipc_manifest_list_dir(&self.vdird_socket_path, path) }?;
+        // Stats of the names listed are likely next: batch them
+        match self.resolve_path(path) {
+            Some(vpath) => note_dir_listing(vpath.manifest_key.as_str(), &entries),
+            None => note_dir_listing(path, &entries),
+        }
+        Some(entries)
     }
 
     fn try_connect(&self) -> i32 {
@@ -1172,51 +1312,20 @@ impl InceptionLayerState {
     }
 
     fn rpc(&self, request: &vrift_ipc::VeloRequest) -> Option<vrift_ipc::VeloResponse> {
-        use vrift_ipc::{next_seq_id, IpcHeader};
-
         unsafe {
             let fd = raw_unix_connect(&self.socket_path);
             if fd < 0 {
                 return None;
             }
 
-            // Serialize payload
-            let payload = rkyv::to_bytes::<rkyv::rancor::Error>(request).ok()?;
-            if payload.len() > vrift_ipc::IpcHeader::MAX_LENGTH {
-                libc::close(fd);
-                return None;
-            }
-
-            // Send request frame
-            let seq_id = next_seq_id();
-            let header = IpcHeader::new_request(payload.len() as u32, seq_id);
-            if !raw_write_all(fd, &header.to_bytes()) || !raw_write_all(fd, &payload) {
-                libc::close(fd);
-                return None;
-            }
-
-            // Read response header
-            let mut header_buf = [0u8; IpcHeader::SIZE];
-            if !raw_read_exact(fd, &mut header_buf) {
-                libc::close(fd);
-                return None;
-            }
-
-            let resp_header = IpcHeader::from_bytes(&header_buf);
-            if !resp_header.is_valid() {
-                libc::close(fd);
-                return None;
-            }
-
-            // Read response payload
-            let mut resp_buf = vec![0u8; resp_header.length as usize];
-            if !raw_read_exact(fd, &mut resp_buf) {
-                libc::close(fd);
-                return None;
-            }
+            let response = if crate::ipc::send_request_on_fd(fd, request) {
+                crate::ipc::recv_response_on_fd(fd)
+            } else {
+                None
+            };
 
             libc::close(fd);
-            rkyv::from_bytes::<vrift_ipc::VeloResponse, rkyv::rancor::Error>(&resp_buf).ok()
+            response
         }
     }
 
@@ -1336,6 +1445,63 @@ fn stack_remaining() -> usize {
 // ============================================================================
 // Tests for DirtyTracker
 // ============================================================================
+#[cfg(test)]
+mod rate_limit_tests {
+    use super::*;
+
+    const SEC: u64 = 1_000_000_000;
+
+    #[test]
+    fn test_burst_then_rate() {
+        let limit = RateLimit::new();
+        let t0 = 100 * SEC;
+        // 5 back-to-back messages pass, the 6th is dropped
+        for _ in 0..5 {
+            assert_eq!(limit.admit_at(t0, 10, 5), Some(0));
+        }
+        assert_eq!(limit.admit_at(t0, 10, 5), None);
+        assert_eq!(limit.admit_at(t0 + SEC / 20, 10, 5), None);
+
+        // One token back every 100ms; the admitted message reports the drops
+        assert_eq!(limit.admit_at(t0 + SEC / 10, 10, 5), Some(2));
+        assert_eq!(limit.admit_at(t0 + SEC / 10, 10, 5), None);
+        assert_eq!(limit.admit_at(t0 + SEC / 5, 10, 5), Some(1));
+    }
+
+    #[test]
+    fn test_refills_to_burst_only() {
+        let limit = RateLimit::new();
+        let t0 = 100 * SEC;
+        assert_eq!(limit.admit_at(t0, 10, 3), Some(0));
+        // A long quiet spell refills the bucket, but not past the burst
+        let later = t0 + 60 * SEC;
+        for _ in 0..3 {
+            assert_eq!(limit.admit_at(later, 10, 3), Some(0));
+        }
+        assert_eq!(limit.admit_at(later, 10, 3), None);
+    }
+
+    #[test]
+    fn test_burst_of_one_and_unlimited() {
+        let limit = RateLimit::new();
+        let t0 = 100 * SEC;
+        // A burst of 0 is treated as 1: strictly one message per interval
+        assert_eq!(limit.admit_at(t0, 2, 0), Some(0));
+        assert_eq!(limit.admit_at(t0 + SEC / 4, 2, 0), None);
+        assert_eq!(limit.admit_at(t0 + SEC / 2, 2, 0), Some(1));
+
+        // Rate 0 admits everything and still reports earlier drops
+        assert_eq!(limit.admit_at(t0 + SEC / 2, 2, 0), None);
+        for _ in 0..100 {
+            assert!(limit.admit_at(t0, 0, 0).is_some());
+        }
+        let limit = RateLimit::new();
+        assert_eq!(limit.admit_at(t0, 1, 1), Some(0));
+        assert_eq!(limit.admit_at(t0, 1, 1), None);
+        assert_eq!(limit.admit_at(t0, 0, 1), Some(1));
+    }
+}
+
 #[cfg(test)]
 mod dirty_tracker_tests {
     use super::*;
@@ -0,0 +1,135 @@
+// =============================================================================
+// state/vdir_map.rs — The VDir mapping, kept in step with vDird's file
+// =============================================================================
+//
+// A mapping keeps the size it was made with, but the VDir file changes under
+// long-running processes (dev servers, watchers, language servers):
+//
+//   - vDird grows the file in place when its table fills (VDir::resize);
+//     the header then describes a table past the end of the old mapping
+//   - the first shim of a workspace starts before vDird has created the
+//     file, so it maps nothing
+//   - the file is recreated, e.g. after /dev/shm was cleared
+//
+// Lookups would then miss, and every stat would go over IPC for the rest of
+// the process's life. current() compares the header with the mapping (no
+// syscall) on each lookup; revalidate(), called from opendir, also stats
+// the file. Either maps the file again when it changed.
+//
+// Replaced mappings are never unmapped: another thread may still be reading
+// one. Each remap follows a resize that doubles the table, so there are few.
+// A remap that does not help (the file can't be opened, or its header claims
+// more than the file holds) is not retried by lookups until the next
+// revalidate().
+// =============================================================================
+
+use std::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+
+/// One mapping of the VDir file
+#[derive(Clone, Copy)]
+pub(crate) struct VdirMapping {
+    pub ptr: *const u8,
+    pub size: usize,
+    /// st_dev/st_ino of the file mapped, to notice a replaced file
+    pub dev: u64,
+    pub ino: u64,
+}
+
+impl VdirMapping {
+    pub const NONE: Self = Self {
+        ptr: std::ptr::null(),
+        size: 0,
+        dev: 0,
+        ino: 0,
+    };
+
+    /// True if the table the header describes runs past the mapping
+    fn outgrown(&self) -> bool {
+        // SAFETY: ptr/size describe a live (never unmapped) mapping or are null/0
+        unsafe { vrift_ipc::vdir_types::vdir_table_end(self.ptr, self.size) }
+            .is_some_and(|end| end > self.size)
+    }
+}
+
+pub(crate) struct VdirMap {
+    /// The mapping made at init; `current` is null until the first remap
+    initial: VdirMapping,
+    current: AtomicPtr<VdirMapping>,
+    /// Held by the thread remapping, so a change is mapped once
+    remapping: AtomicBool,
+    /// The last remap failed; lookups leave it to revalidate()
+    stalled: AtomicBool,
+}
+
+impl VdirMap {
+    pub(crate) fn new(initial: VdirMapping) -> Self {
+        Self {
+            initial,
+            current: AtomicPtr::new(std::ptr::null_mut()),
+            remapping: AtomicBool::new(false),
+            stalled: AtomicBool::new(false),
+        }
+    }
+
+    fn load(&self) -> &VdirMapping {
+        let ptr = self.current.load(Ordering::Acquire);
+        if ptr.is_null() {
+            &self.initial
+        } else {
+            // SAFETY: set from Box::leak in remap(), never freed
+            unsafe { &*ptr }
+        }
+    }
+
+    /// The mapping as it is, for pollers that only read the header
+    #[inline]
+    pub(crate) fn get(&self) -> (*const u8, usize) {
+        let m = self.load();
+        (m.ptr, m.size)
+    }
+
+    /// The mapping, mapped again first if vDird grew the file past it
+    #[inline]
+    pub(crate) fn current(&self) -> (*const u8, usize) {
+        let m = self.load();
+        if m.outgrown() && !self.stalled.load(Ordering::Relaxed) {
+            self.remap();
+            return self.get();
+        }
+        (m.ptr, m.size)
+    }
+
+    /// Like [`VdirMap::current`], and also map the file again if it was
+    /// created or replaced since it was mapped. Costs a stat.
+    pub(crate) fn revalidate(&self) {
+        self.stalled.store(false, Ordering::Relaxed);
+        let m = self.load();
+        // A file that is gone keeps its old mapping: vDird may be restarting
+        let replaced =
+            super::init::vdir_file_id().is_some_and(|id| m.ptr.is_null() || id != (m.dev, m.ino));
+        if replaced || m.outgrown() {
+            self.remap();
+        }
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn remap(&self) {
+        if self.remapping.swap(true, Ordering::Acquire) {
+            return; // Another thread is at it; keep using the old mapping
+        }
+        let mapping = super::init::open_manifest_mmap();
+        if mapping.ptr.is_null() || mapping.outgrown() {
+            if !mapping.ptr.is_null() {
+                // SAFETY: just mapped, not published
+                unsafe { libc::munmap(mapping.ptr as *mut libc::c_void, mapping.size) };
+            }
+            self.stalled.store(true, Ordering::Relaxed);
+        } else {
+            inception_info!("VDir mapped again ({} bytes)", mapping.size);
+            let leaked: &'static mut VdirMapping = Box::leak(Box::new(mapping));
+            self.current.store(leaked, Ordering::Release);
+        }
+        self.remapping.store(false, Ordering::Release);
+    }
+}
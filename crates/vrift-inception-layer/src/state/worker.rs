@@ -7,12 +7,105 @@
 //   - spawn_worker()  — #[inline(never)] to isolate pthread_create side effects
 //   - worker_entry()  — adaptive backoff loop (spin → yield → sleep)
 //   - process_task()  — dispatch ring buffer tasks
+//   - queue_reingest() — CoW write-back, drained at process exit
+//   - worker_after_fork() — a forked child gets a worker of its own
 // =============================================================================
 
-use std::sync::atomic::Ordering;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
 use super::{InceptionLayerState, DIRTY_TRACKER, WORKER_STARTED};
 
+/// Reingests queued but not yet answered by vDird
+static PENDING_REINGESTS: AtomicUsize = AtomicUsize::new(0);
+static EXIT_DRAIN_REGISTERED: AtomicBool = AtomicBool::new(false);
+static ATFORK_REGISTERED: AtomicBool = AtomicBool::new(false);
+
+/// Longest a process exit waits for its queued reingests
+const EXIT_DRAIN_TIMEOUT_MS: u32 = 5000;
+
+/// Queue the write-back of a closed CoW copy.
+///
+/// Tools often write a file and exit right away. In phantom mode the staged
+/// copy is the only copy of the new content, so the first queued reingest
+/// registers an exit hook that waits for the worker to hand every pending
+/// one to vDird.
+///
+/// Without a worker to hand it to (reactor not up, ring buffer full) the
+/// write-back happens right here, on the closing thread, rather than not
+/// at all.
+pub(crate) fn queue_reingest(vpath: &str, temp_path: &str, base_hash: [u8; 32]) {
+    let Some(reactor) = crate::sync::get_reactor() else {
+        reingest_now(vpath, temp_path, &base_hash);
+        return;
+    };
+    if !EXIT_DRAIN_REGISTERED.swap(true, Ordering::SeqCst) {
+        unsafe { libc::atexit(drain_reingests_atexit) };
+    }
+    PENDING_REINGESTS.fetch_add(1, Ordering::SeqCst);
+    let task = crate::sync::Task::Reingest {
+        vpath: vpath.to_string(),
+        temp_path: temp_path.to_string(),
+        base_hash: Box::new(base_hash),
+    };
+    if reactor.ring_buffer.push(task).is_err() {
+        PENDING_REINGESTS.fetch_sub(1, Ordering::SeqCst);
+        reingest_now(vpath, temp_path, &base_hash);
+    }
+}
+
+/// Have vDird reingest the staged copy `temp_path` as `vpath` and wait for
+/// its answer. A refused or failed reingest leaves the path dirty, so stat
+/// keeps reporting the staged copy, and is logged with where the content is.
+fn reingest_now(vpath: &str, temp_path: &str, base_hash: &[u8; 32]) -> bool {
+    let Some(state) = InceptionLayerState::get_no_spawn() else {
+        return false;
+    };
+    let done = unsafe {
+        crate::ipc::sync_ipc_manifest_reingest(
+            &state.vdird_socket_path,
+            vpath,
+            temp_path,
+            base_hash,
+        )
+    };
+    if done {
+        // M4: Clear dirty status ONLY after the daemon confirms reingest.
+        DIRTY_TRACKER.clear_dirty(vpath);
+        unsafe { crate::ipc::report_session_writeback(&state.socket_path, vpath) };
+    } else {
+        inception_warn!(
+            "write-back of '{}' failed; its content is in '{}'",
+            vpath,
+            temp_path
+        );
+    }
+    done
+}
+
+/// fork copies the ring buffer and the worker's flags, not the worker. The
+/// child drops what the parent had queued (the parent's worker hands those
+/// on) and starts a worker of its own on its next call into the shim.
+extern "C" fn worker_after_fork() {
+    if let Some(reactor) = crate::sync::get_reactor() {
+        while let Some(task) = reactor.ring_buffer.pop() {
+            if let crate::sync::Task::ReclaimFd(..) = task {
+                InceptionLayerState::process_task(task);
+            }
+        }
+    }
+    PENDING_REINGESTS.store(0, Ordering::SeqCst);
+    WORKER_STARTED.store(false, Ordering::SeqCst);
+}
+
+extern "C" fn drain_reingests_atexit() {
+    for _ in 0..EXIT_DRAIN_TIMEOUT_MS {
+        if PENDING_REINGESTS.load(Ordering::SeqCst) == 0 {
+            return;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    }
+}
+
 impl InceptionLayerState {
     /// BUG-007b: Must not inline — pthread_create internally calls mmap (interposed).
     /// Keeps get()'s stack frame small and isolates pthread_create side effects.
@@ -24,6 +117,9 @@ impl InceptionLayerState {
         }
 
         unsafe {
+            if !ATFORK_REGISTERED.swap(true, Ordering::SeqCst) {
+                libc::pthread_atfork(None, None, Some(worker_after_fork));
+            }
             let mut thread: libc::pthread_t = std::mem::zeroed();
             libc::pthread_create(
                 &mut thread,
@@ -48,6 +144,18 @@ impl InceptionLayerState {
             None => return std::ptr::null_mut(),
         };
 
+        // Join the workspace session (parentage lets vriftd group the process tree)
+        if let Some(state) = InceptionLayerState::get_no_spawn() {
+            unsafe {
+                crate::ipc::report_session_process(
+                    &state.socket_path,
+                    libc::getpid() as u32,
+                    libc::getppid() as u32,
+                    "",
+                );
+            }
+        }
+
         // Worker thread loop with adaptive backoff for CPU efficiency
         let mut backoff_count = 0u32;
         loop {
@@ -83,19 +191,13 @@ impl InceptionLayerState {
                     }
                 }
             }
-            crate::sync::Task::Reingest { vpath, temp_path } => {
-                if let Some(state) = InceptionLayerState::get_no_spawn() {
-                    unsafe {
-                        if crate::ipc::sync_ipc_manifest_reingest(
-                            &state.socket_path,
-                            &vpath,
-                            &temp_path,
-                        ) {
-                            // M4: Clear dirty status ONLY after the daemon confirms reingest.
-                            DIRTY_TRACKER.clear_dirty(&vpath);
-                        }
-                    }
-                }
+            crate::sync::Task::Reingest {
+                vpath,
+                temp_path,
+                base_hash,
+            } => {
+                reingest_now(&vpath, &temp_path, &base_hash);
+                PENDING_REINGESTS.fetch_sub(1, Ordering::SeqCst);
             }
             crate::sync::Task::Log(msg) => {
                 unsafe { libc::write(2, msg.as_ptr() as *const _, msg.len()) };
@@ -13,6 +13,8 @@ pub enum Task {
     Reingest {
         vpath: String,
         temp_path: String,
+        /// Boxed to keep the ring buffer slots small
+        base_hash: Box<[u8; 32]>,
     },
     Log(String),
     /// Phase 3: Fire-and-forget IPC — pre-serialized request bytes pushed to worker.
@@ -1,12 +1,8 @@
 // Symbols imported from reals.rs via crate::reals
-#[cfg(target_os = "macos")]
 use crate::state::*;
-#[cfg(target_os = "linux")]
 use libc::c_int;
 #[cfg(target_os = "macos")]
-use libc::{c_int, c_void};
-#[cfg(target_os = "macos")]
-use std::ffi::CStr;
+use libc::c_void;
 
 #[no_mangle]
 #[cfg(target_os = "macos")]
@@ -20,15 +16,13 @@ pub unsafe extern "C" fn opendir_inception(path: *const libc::c_char) -> *mut c_
 
     // Early-boot passthrough
     passthrough_if_init!(real, path);
+    passthrough_if_disabled!(crate::intercept::DIR, real, path);
 
     if path.is_null() {
         return real(path);
     }
 
-    let path_str = match CStr::from_ptr(path).to_str() {
-        Ok(s) => s,
-        Err(_) => return real(path),
-    };
+    let path_str = crate::path::c_path_str(path);
 
     // Get inception layer state
     let state = match InceptionLayerState::get() {
@@ -37,15 +31,15 @@ pub unsafe extern "C" fn opendir_inception(path: *const libc::c_char) -> *mut c_
     };
 
     // Check if path is in VFS domain
-    if !state.inception_applicable(path_str) {
+    if !state.inception_applicable(&path_str) {
         return real(path);
     }
 
     // Query directory listing from daemon
-    if let Some(entries) = state.query_dir_listing(path_str) {
+    if let Some(entries) = state.query_dir_listing(&path_str) {
         // Create synthetic directory
         let mut fs_vpath = crate::state::FixedString::<1024>::new();
-        fs_vpath.set(path_str);
+        fs_vpath.set(&path_str);
         let syn_dir = Box::new(SyntheticDir {
             vpath: fs_vpath,
             entries,
@@ -122,15 +116,15 @@ pub unsafe extern "C" fn readdir_inception(dir: *mut c_void) -> *mut libc::diren
             } else {
                 libc::DT_REG
             };
-            DIRENT_BUF.d_namlen = entry.name.len() as u16;
 
-            // Copy name to buffer
-            let name_bytes = entry.name.as_bytes();
+            // Copy name to buffer, as the bytes the name stands for
+            let name_bytes = vrift_ipc::path_key::to_bytes(&entry.name);
             let copy_len = name_bytes.len().min(1023);
             let dirent_ptr = std::ptr::addr_of_mut!(DIRENT_BUF);
             let d_name_ptr = std::ptr::addr_of_mut!((*dirent_ptr).d_name);
             std::ptr::copy_nonoverlapping(name_bytes.as_ptr(), d_name_ptr as *mut u8, copy_len);
             (*dirent_ptr).d_name[copy_len] = 0;
+            (*dirent_ptr).d_namlen = copy_len as u16;
 
             return dirent_ptr;
         }
@@ -170,6 +164,150 @@ pub unsafe extern "C" fn closedir_inception(dir: *mut c_void) -> c_int {
 
     real(dir)
 }
+
+// Linux: the stream stays the kernel's, so dirfd(), telldir() and the fd
+// behind it keep working. Only what readdir returns changes: the manifest
+// listing first, then whatever exists on disk alone (`.`, `..` and files not
+// ingested yet).
+
+#[cfg(target_os = "linux")]
+type RealOpendir = unsafe extern "C" fn(*const libc::c_char) -> *mut libc::DIR;
+#[cfg(target_os = "linux")]
+type RealReaddir64 = unsafe extern "C" fn(*mut libc::DIR) -> *mut libc::dirent64;
+#[cfg(target_os = "linux")]
+type RealDirOp = unsafe extern "C" fn(*mut libc::DIR) -> c_int;
+#[cfg(target_os = "linux")]
+type RealRewinddir = unsafe extern "C" fn(*mut libc::DIR);
+
+#[no_mangle]
+#[cfg(target_os = "linux")]
+pub unsafe extern "C" fn opendir_inception(path: *const libc::c_char) -> *mut libc::DIR {
+    let real: RealOpendir = std::mem::transmute(crate::reals::REAL_OPENDIR.get());
+
+    passthrough_if_init!(real, path);
+    passthrough_if_disabled!(crate::intercept::DIR, real, path);
+
+    let dir = real(path);
+    if dir.is_null() {
+        return dir;
+    }
+    let Some(state) = InceptionLayerState::get() else {
+        return dir;
+    };
+    let Some(vpath) = state.resolve_path(&crate::path::c_path_str(path)) else {
+        return dir;
+    };
+
+    if let Some(mut entries) = state.query_dir_listing(vpath.manifest_key.as_str()) {
+        // Sorted, to skip the names already listed when the disk's turn comes
+        entries.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+        let mut key = crate::state::FixedString::<1024>::new();
+        key.set(vpath.manifest_key.as_str());
+        state.open_dirs.lock().insert(
+            dir as usize,
+            SyntheticDir {
+                vpath: key,
+                entries,
+                position: 0,
+                dirent: Box::new(std::mem::zeroed()),
+            },
+        );
+    }
+    dir
+}
+
+#[no_mangle]
+#[cfg(target_os = "linux")]
+pub unsafe extern "C" fn readdir64_inception(dir: *mut libc::DIR) -> *mut libc::dirent64 {
+    let real: RealReaddir64 = std::mem::transmute(crate::reals::REAL_READDIR64.get());
+
+    passthrough_if_init!(real, dir);
+
+    let Some(state) = InceptionLayerState::get() else {
+        return real(dir);
+    };
+    {
+        let mut dirs = state.open_dirs.lock();
+        let Some(sd) = dirs.get_mut(&(dir as usize)) else {
+            drop(dirs);
+            return real(dir);
+        };
+        if let Some(entry) = sd.entries.get(sd.position) {
+            sd.position += 1;
+            let child = format!("{}/{}", sd.vpath.as_str().trim_end_matches('/'), entry.name);
+            let dirent = &mut *sd.dirent;
+            // Same inode as stat gives the entry
+            dirent.d_ino = vrift_ipc::fnv1a_hash(&child);
+            dirent.d_off = sd.position as i64;
+            dirent.d_reclen = std::mem::size_of::<libc::dirent64>() as u16;
+            dirent.d_type = if entry.is_dir {
+                libc::DT_DIR
+            } else {
+                libc::DT_REG
+            };
+            let name = vrift_ipc::path_key::to_bytes(&entry.name);
+            let len = name.len().min(dirent.d_name.len() - 1);
+            std::ptr::copy_nonoverlapping(
+                name.as_ptr(),
+                dirent.d_name.as_mut_ptr() as *mut u8,
+                len,
+            );
+            dirent.d_name[len] = 0;
+            return dirent;
+        }
+    }
+
+    loop {
+        let ent = real(dir);
+        if ent.is_null() {
+            return ent;
+        }
+        // Listed names are keys, so a disk name is compared in key form
+        let raw_name = crate::path::c_path_str((*ent).d_name.as_ptr());
+        let name = vrift_ipc::path_key::normalize(&raw_name);
+        let listed = state
+            .open_dirs
+            .lock()
+            .get(&(dir as usize))
+            .is_some_and(|sd| {
+                sd.entries
+                    .binary_search_by(|e| e.name.as_str().cmp(&name))
+                    .is_ok()
+            });
+        if !listed {
+            return ent;
+        }
+    }
+}
+
+#[no_mangle]
+#[cfg(target_os = "linux")]
+pub unsafe extern "C" fn rewinddir_inception(dir: *mut libc::DIR) {
+    let real: RealRewinddir = std::mem::transmute(crate::reals::REAL_REWINDDIR.get());
+
+    passthrough_if_init!(real, dir);
+
+    if let Some(state) = InceptionLayerState::get() {
+        if let Some(sd) = state.open_dirs.lock().get_mut(&(dir as usize)) {
+            sd.position = 0;
+        }
+    }
+    real(dir)
+}
+
+#[no_mangle]
+#[cfg(target_os = "linux")]
+pub unsafe extern "C" fn closedir_inception(dir: *mut libc::DIR) -> c_int {
+    let real: RealDirOp = std::mem::transmute(crate::reals::REAL_CLOSEDIR.get());
+
+    passthrough_if_init!(real, dir);
+
+    if let Some(state) = InceptionLayerState::get() {
+        state.open_dirs.lock().remove(&(dir as usize));
+    }
+    real(dir)
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn getcwd_inception(
     buf: *mut libc::c_char,
@@ -183,6 +321,7 @@ pub unsafe extern "C" fn getcwd_inception(
 
         // Early-boot passthrough
         passthrough_if_init!(raw_getcwd, buf, size);
+        passthrough_if_disabled!(crate::intercept::DIR, raw_getcwd, buf, size);
 
         let res = raw_getcwd(buf, size);
         if res.is_null() {
@@ -191,10 +330,7 @@ pub unsafe extern "C" fn getcwd_inception(
 
         // Check if we need to reverse-map the path
         if let Some(state) = InceptionLayerState::get() {
-            let real_cwd = match CStr::from_ptr(buf).to_str() {
-                Ok(s) => s,
-                Err(_) => return res,
-            };
+            let real_cwd = crate::path::c_path_str(buf);
 
             let prefix = state.path_resolver.vfs_prefix.as_str();
             let project_root = state.path_resolver.project_root.as_str();
@@ -211,8 +347,8 @@ pub unsafe extern "C" fn getcwd_inception(
                 };
 
                 // Copy back to buffer if it fits
-                if virt_cwd.len() < size {
-                    let bytes = virt_cwd.as_bytes();
+                let bytes = vrift_ipc::path_key::to_bytes(&virt_cwd);
+                if bytes.len() < size {
                     std::ptr::copy_nonoverlapping(bytes.as_ptr(), buf as *mut u8, bytes.len());
                     *(buf.add(bytes.len())) = 0;
                     return buf;
@@ -235,21 +371,18 @@ pub unsafe extern "C" fn chdir_inception(path: *const libc::c_char) -> c_int {
     #[cfg(target_os = "macos")]
     {
         use crate::state::InceptionLayerState;
-        use std::ffi::CStr;
 
         let raw_chdir = crate::syscalls::macos_raw::raw_chdir;
 
         // Early-boot passthrough
         passthrough_if_init!(raw_chdir, path);
+        passthrough_if_disabled!(crate::intercept::DIR, raw_chdir, path);
 
         if path.is_null() {
             return raw_chdir(path);
         }
 
-        let path_str = match CStr::from_ptr(path).to_str() {
-            Ok(s) => s,
-            Err(_) => return raw_chdir(path),
-        };
+        let path_str = crate::path::c_path_str(path);
 
         // Get inception layer state
         let state = match InceptionLayerState::get() {
@@ -258,7 +391,7 @@ pub unsafe extern "C" fn chdir_inception(path: *const libc::c_char) -> c_int {
         };
 
         // Check if path is in VFS domain
-        if let Some(_vfs_path) = state.resolve_path(path_str) {
+        if let Some(_vfs_path) = state.resolve_path(&path_str) {
             // Map VFS path to real filesystem path:
             // VFS prefix      -> project_root
             // /vrift/subdir   -> /real/project/subdir
@@ -274,9 +407,9 @@ pub unsafe extern "C" fn chdir_inception(path: *const libc::c_char) -> c_int {
                 format!("{}/{}", project_root, relative)
             };
 
-            let c_real = match std::ffi::CString::new(real_path) {
-                Ok(c) => c,
-                Err(_) => return raw_chdir(path),
+            let c_real = match crate::path::path_cstring(&real_path) {
+                Some(c) => c,
+                None => return raw_chdir(path),
             };
             return raw_chdir(c_real.as_ptr());
         }
@@ -0,0 +1,483 @@
+//! inotify emulation for VFS paths (Linux)
+//!
+//! Writes committed through CoW reingest land in the CAS and the VDir, never
+//! on the real file the kernel is watching, so an `inotify_add_watch` on a VFS
+//! path silently never fires. When the VFS is active, `inotify_init1` hands
+//! the application one end of a socketpair instead of the kernel fd. A pump
+//! thread forwards kernel events unchanged and, while VFS watches exist, polls
+//! the vDird manifest change feed and writes synthesized `struct inotify_event`
+//! records into the same stream.
+//!
+//! If a file is also modified on disk, the kernel event and the synthesized
+//! one may both be delivered. Watchers (webpack, watchman, cargo-watch)
+//! debounce anyway, so duplicates are tolerated rather than filtered.
+//!
+//! Set `VRIFT_INOTIFY_EMULATION=0` to hand out plain kernel fds.
+
+use crate::state::{vdir_generation, InceptionLayerGuard, InceptionLayerState};
+use libc::{c_char, c_int, c_void};
+use std::ffi::CStr;
+use std::sync::{Mutex, MutexGuard};
+use vrift_ipc::{ManifestChange, ManifestChangeKind};
+
+/// Watch descriptors for VFS paths that do not exist on disk are allocated
+/// from here so they never collide with kernel-assigned ones.
+const SYNTHETIC_WD_BASE: c_int = 0x4000_0000;
+
+/// Pump poll interval; bounds the latency of synthesized events.
+const POLL_INTERVAL_MS: c_int = 100;
+
+/// Re-poll the change feed every N ticks even if the VDir generation did not
+/// move, so mutations that skip the VDir are still picked up.
+const FORCED_POLL_EVERY: u32 = 10;
+
+#[derive(Clone)]
+struct VfsWatch {
+    wd: c_int,
+    /// Manifest key of the watched path
+    key: String,
+    mask: u32,
+}
+
+struct EmulatedInotify {
+    /// fd returned to the application
+    app_fd: c_int,
+    /// Kernel inotify fd (non-blocking, owned by the pump)
+    real_fd: c_int,
+    /// Pump end of the socketpair
+    pump_fd: c_int,
+    watches: Vec<VfsWatch>,
+    next_synthetic_wd: c_int,
+    /// Events queued outside the pump (IN_IGNORED for synthetic watches)
+    pending: Vec<u8>,
+}
+
+static EMULATED: Mutex<Vec<EmulatedInotify>> = Mutex::new(Vec::new());
+
+fn table() -> MutexGuard<'static, Vec<EmulatedInotify>> {
+    match EMULATED.lock() {
+        Ok(g) => g,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+unsafe fn real_inotify_init1(flags: c_int) -> c_int {
+    libc::syscall(libc::SYS_inotify_init1, flags) as c_int
+}
+
+unsafe fn real_inotify_add_watch(fd: c_int, path: *const c_char, mask: u32) -> c_int {
+    libc::syscall(libc::SYS_inotify_add_watch, fd, path, mask) as c_int
+}
+
+unsafe fn real_inotify_rm_watch(fd: c_int, wd: c_int) -> c_int {
+    libc::syscall(libc::SYS_inotify_rm_watch, fd, wd) as c_int
+}
+
+unsafe fn emulation_enabled() -> bool {
+    if !crate::intercept::enabled(crate::intercept::WATCH) {
+        return false;
+    }
+    let val = libc::getenv(c"VRIFT_INOTIFY_EMULATION".as_ptr());
+    val.is_null() || CStr::from_ptr(val).to_bytes() != b"0"
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn inotify_init_inception() -> c_int {
+    inotify_init1_inception(0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn inotify_init1_inception(flags: c_int) -> c_int {
+    let _guard = match InceptionLayerGuard::enter() {
+        Some(g) => g,
+        None => return real_inotify_init1(flags),
+    };
+    if InceptionLayerState::get().is_none() || !emulation_enabled() {
+        return real_inotify_init1(flags);
+    }
+
+    let real_fd = real_inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC);
+    if real_fd < 0 {
+        return real_fd;
+    }
+
+    // IN_NONBLOCK/IN_CLOEXEC share values with SOCK_NONBLOCK/SOCK_CLOEXEC
+    let app_flags = flags & (libc::IN_NONBLOCK | libc::IN_CLOEXEC);
+    let mut pair = [-1 as c_int; 2];
+    if libc::socketpair(
+        libc::AF_UNIX,
+        libc::SOCK_STREAM | app_flags,
+        0,
+        pair.as_mut_ptr(),
+    ) != 0
+    {
+        // Fall back to the kernel fd: watches on VFS paths just won't fire
+        libc::close(real_fd);
+        return real_inotify_init1(flags);
+    }
+    let (app_fd, pump_fd) = (pair[0], pair[1]);
+
+    // The pump end must block on backpressure and never leak into children
+    let fl = libc::fcntl(pump_fd, libc::F_GETFL);
+    libc::fcntl(pump_fd, libc::F_SETFL, fl & !libc::O_NONBLOCK);
+    libc::fcntl(pump_fd, libc::F_SETFD, libc::FD_CLOEXEC);
+
+    table().push(EmulatedInotify {
+        app_fd,
+        real_fd,
+        pump_fd,
+        watches: Vec::new(),
+        next_synthetic_wd: SYNTHETIC_WD_BASE,
+        pending: Vec::new(),
+    });
+
+    let mut thread: libc::pthread_t = std::mem::zeroed();
+    if libc::pthread_create(
+        &mut thread,
+        std::ptr::null(),
+        pump_entry,
+        app_fd as usize as *mut c_void,
+    ) != 0
+    {
+        table().retain(|e| e.app_fd != app_fd);
+        libc::close(app_fd);
+        libc::close(pump_fd);
+        libc::close(real_fd);
+        return real_inotify_init1(flags);
+    }
+    libc::pthread_detach(thread);
+
+    inception_debug!("inotify emulation active on fd {}", app_fd);
+    app_fd
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn inotify_add_watch_inception(
+    fd: c_int,
+    path: *const c_char,
+    mask: u32,
+) -> c_int {
+    let _guard = match InceptionLayerGuard::enter() {
+        Some(g) => g,
+        None => return real_inotify_add_watch(fd, path, mask),
+    };
+
+    let mut emulated = table();
+    let Some(inst) = emulated.iter_mut().find(|e| e.app_fd == fd) else {
+        drop(emulated);
+        return real_inotify_add_watch(fd, path, mask);
+    };
+
+    let vpath = InceptionLayerState::get().and_then(|state| {
+        state
+            .resolve_path(&crate::path::c_path_str(path))
+            .map(|v| (state, v))
+    });
+
+    let wd = real_inotify_add_watch(inst.real_fd, path, mask);
+    let Some((state, vpath)) = vpath else {
+        return wd;
+    };
+
+    let wd = if wd >= 0 {
+        wd
+    } else if crate::get_errno() == libc::ENOENT && state.query_manifest(&vpath).is_some() {
+        // Phantom path: present in the manifest but not on disk
+        let wd = inst.next_synthetic_wd;
+        inst.next_synthetic_wd += 1;
+        wd
+    } else {
+        return wd;
+    };
+
+    let key = vpath
+        .manifest_key
+        .as_str()
+        .trim_end_matches('/')
+        .to_string();
+    record_watch(&mut inst.watches, wd, key, mask);
+    wd
+}
+
+/// Record a watch on `key` under `wd`. The kernel hands out the same wd when
+/// an inode is watched again, possibly through another path: the new path
+/// is the one later changes are matched against. IN_MASK_ADD merges the
+/// masks, otherwise the new one replaces the old.
+fn record_watch(watches: &mut Vec<VfsWatch>, wd: c_int, key: String, mask: u32) {
+    match watches.iter_mut().find(|w| w.wd == wd) {
+        Some(w) => {
+            w.key = key;
+            if mask & libc::IN_MASK_ADD != 0 {
+                w.mask |= mask;
+            } else {
+                w.mask = mask;
+            }
+        }
+        None => watches.push(VfsWatch { wd, key, mask }),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn inotify_rm_watch_inception(fd: c_int, wd: c_int) -> c_int {
+    let _guard = match InceptionLayerGuard::enter() {
+        Some(g) => g,
+        None => return real_inotify_rm_watch(fd, wd),
+    };
+
+    let mut emulated = table();
+    let Some(inst) = emulated.iter_mut().find(|e| e.app_fd == fd) else {
+        drop(emulated);
+        return real_inotify_rm_watch(fd, wd);
+    };
+
+    inst.watches.retain(|w| w.wd != wd);
+    if wd >= SYNTHETIC_WD_BASE {
+        // The kernel never saw this wd, so IN_IGNORED is ours to deliver
+        push_event(&mut inst.pending, wd, libc::IN_IGNORED, 0, "");
+        return 0;
+    }
+    real_inotify_rm_watch(inst.real_fd, wd)
+}
+
+/// Append one `struct inotify_event` (name NUL-padded to 4-byte alignment).
+fn push_event(out: &mut Vec<u8>, wd: c_int, mask: u32, cookie: u32, name: &str) {
+    let len = if name.is_empty() {
+        0
+    } else {
+        (name.len() + 1).next_multiple_of(4)
+    };
+    out.extend_from_slice(&wd.to_ne_bytes());
+    out.extend_from_slice(&mask.to_ne_bytes());
+    out.extend_from_slice(&cookie.to_ne_bytes());
+    out.extend_from_slice(&(len as u32).to_ne_bytes());
+    out.extend_from_slice(name.as_bytes());
+    out.resize(out.len() + len - name.len(), 0);
+}
+
+/// Translate one manifest change into events for a single watch.
+fn synthesize(out: &mut Vec<u8>, watch: &VfsWatch, change: &ManifestChange) {
+    let path = change.path.trim_end_matches('/');
+    let isdir = if change.is_dir { libc::IN_ISDIR } else { 0 };
+    let mut emit = |mask: u32, name: &str| {
+        if watch.mask & mask != 0 {
+            push_event(out, watch.wd, mask | isdir, change.cookie, name);
+        }
+    };
+
+    if path == watch.key {
+        // Event on the watched object itself
+        match change.kind {
+            ManifestChangeKind::Modified => {
+                emit(libc::IN_MODIFY, "");
+                emit(libc::IN_CLOSE_WRITE, "");
+            }
+            ManifestChangeKind::Removed => emit(libc::IN_DELETE_SELF, ""),
+            ManifestChangeKind::MovedFrom => emit(libc::IN_MOVE_SELF, ""),
+            ManifestChangeKind::Created | ManifestChangeKind::MovedTo => {}
+        }
+        return;
+    }
+
+    let Some((parent, name)) = path.rsplit_once('/') else {
+        return;
+    };
+    if parent != watch.key || name.is_empty() {
+        return;
+    }
+    match change.kind {
+        ManifestChangeKind::Created => emit(libc::IN_CREATE, name),
+        ManifestChangeKind::Modified => {
+            emit(libc::IN_MODIFY, name);
+            emit(libc::IN_CLOSE_WRITE, name);
+        }
+        ManifestChangeKind::Removed => emit(libc::IN_DELETE, name),
+        ManifestChangeKind::MovedFrom => emit(libc::IN_MOVED_FROM, name),
+        ManifestChangeKind::MovedTo => emit(libc::IN_MOVED_TO, name),
+    }
+}
+
+unsafe fn send_all(fd: c_int, mut buf: &[u8]) -> bool {
+    while !buf.is_empty() {
+        let n = libc::send(
+            fd,
+            buf.as_ptr() as *const c_void,
+            buf.len(),
+            libc::MSG_NOSIGNAL,
+        );
+        if n < 0 {
+            if crate::get_errno() == libc::EINTR {
+                continue;
+            }
+            return false;
+        }
+        buf = &buf[n as usize..];
+    }
+    true
+}
+
+extern "C" fn pump_entry(arg: *mut c_void) -> *mut c_void {
+    let app_fd = arg as usize as c_int;
+
+    // Signals belong to the application's threads
+    unsafe {
+        let mut mask: libc::sigset_t = std::mem::zeroed();
+        libc::sigfillset(&mut mask);
+        libc::pthread_sigmask(libc::SIG_BLOCK, &mask, std::ptr::null_mut());
+    }
+
+    let (real_fd, pump_fd) = match table().iter().find(|e| e.app_fd == app_fd) {
+        Some(e) => (e.real_fd, e.pump_fd),
+        None => return std::ptr::null_mut(),
+    };
+
+    let mut cursor = u64::MAX;
+    let mut last_generation = None;
+    let mut ticks = 0u32;
+    let mut kbuf = [0u8; 4096];
+
+    loop {
+        let mut fds = [
+            libc::pollfd {
+                fd: real_fd,
+                events: libc::POLLIN,
+                revents: 0,
+            },
+            libc::pollfd {
+                fd: pump_fd,
+                events: 0,
+                revents: 0,
+            },
+        ];
+        let n = unsafe { libc::poll(fds.as_mut_ptr(), 2, POLL_INTERVAL_MS) };
+        if n < 0 && unsafe { crate::get_errno() } != libc::EINTR {
+            break;
+        }
+
+        // Application closed its end
+        if fds[1].revents & (libc::POLLHUP | libc::POLLERR) != 0 {
+            break;
+        }
+
+        // Forward kernel events verbatim (reads return whole events)
+        if fds[0].revents & libc::POLLIN != 0 {
+            let r = unsafe { libc::read(real_fd, kbuf.as_mut_ptr() as *mut c_void, kbuf.len()) };
+            if r > 0 && !unsafe { send_all(pump_fd, &kbuf[..r as usize]) } {
+                break;
+            }
+        }
+
+        // Snapshot watches so IPC runs without holding the table lock
+        let (watches, pending) = {
+            let mut emulated = table();
+            match emulated.iter_mut().find(|e| e.app_fd == app_fd) {
+                Some(e) => (e.watches.clone(), std::mem::take(&mut e.pending)),
+                None => break,
+            }
+        };
+        if !pending.is_empty() && !unsafe { send_all(pump_fd, &pending) } {
+            break;
+        }
+        if watches.is_empty() {
+            continue;
+        }
+
+        let Some(state) = InceptionLayerState::get_no_spawn() else {
+            continue;
+        };
+
+        ticks = ticks.wrapping_add(1);
+        let (mmap_ptr, mmap_size) = state.vdir.get();
+        // SAFETY: the VDir mappings live as long as the global state.
+        let generation = unsafe { vdir_generation(mmap_ptr, mmap_size) };
+        if cursor != u64::MAX
+            && generation.is_some()
+            && generation == last_generation
+            && !ticks.is_multiple_of(FORCED_POLL_EVERY)
+        {
+            continue;
+        }
+        last_generation = generation;
+
+        let Some((next, changes, truncated)) = (unsafe {
+            crate::ipc::sync_ipc_manifest_changes_since(&state.vdird_socket_path, cursor)
+        }) else {
+            continue;
+        };
+        // First successful poll only establishes the starting point
+        let first = cursor == u64::MAX;
+        cursor = next;
+        if first {
+            continue;
+        }
+
+        let mut out = Vec::new();
+        if truncated {
+            push_event(&mut out, -1, libc::IN_Q_OVERFLOW, 0, "");
+        }
+        for change in &changes {
+            for watch in &watches {
+                synthesize(&mut out, watch, change);
+            }
+        }
+        if !out.is_empty() && !unsafe { send_all(pump_fd, &out) } {
+            break;
+        }
+    }
+
+    table().retain(|e| e.app_fd != app_fd);
+    unsafe {
+        libc::close(pump_fd);
+        libc::close(real_fd);
+    }
+    std::ptr::null_mut()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_watch_updates_key_and_mask() {
+        let mut watches = Vec::new();
+        record_watch(&mut watches, 1, "src/a.rs".to_string(), libc::IN_MODIFY);
+        record_watch(&mut watches, 2, "src".to_string(), libc::IN_CREATE);
+        assert_eq!(watches.len(), 2);
+
+        // Same inode through a new path (a rename or hard link)
+        record_watch(
+            &mut watches,
+            1,
+            "src/b.rs".to_string(),
+            libc::IN_MASK_ADD | libc::IN_DELETE_SELF,
+        );
+        assert_eq!(watches.len(), 2);
+        assert_eq!(watches[0].key, "src/b.rs");
+        assert_eq!(
+            watches[0].mask & !libc::IN_MASK_ADD,
+            libc::IN_MODIFY | libc::IN_DELETE_SELF
+        );
+
+        // Without IN_MASK_ADD the mask is replaced
+        record_watch(&mut watches, 1, "src/b.rs".to_string(), libc::IN_ATTRIB);
+        assert_eq!(watches[0].mask, libc::IN_ATTRIB);
+        assert_eq!(watches[1].key, "src");
+    }
+
+    #[test]
+    fn test_synthesize_uses_current_key() {
+        let mut watches = Vec::new();
+        record_watch(&mut watches, 7, "src/old.rs".to_string(), libc::IN_MODIFY);
+        record_watch(&mut watches, 7, "src/new.rs".to_string(), libc::IN_MODIFY);
+        let change = ManifestChange {
+            seq: 1,
+            kind: ManifestChangeKind::Modified,
+            path: "src/new.rs".to_string(),
+            is_dir: false,
+            cookie: 0,
+        };
+        let mut out = Vec::new();
+        synthesize(&mut out, &watches[0], &change);
+        assert_eq!(&out[..4], &7i32.to_ne_bytes());
+        assert_eq!(&out[4..8], &libc::IN_MODIFY.to_ne_bytes());
+    }
+}
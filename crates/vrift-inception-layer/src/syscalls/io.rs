@@ -19,7 +19,16 @@ pub struct FdEntry {
     pub manifest_key: crate::state::FixedString<1024>,
     pub manifest_key_hash: u64,
     pub temp_path: crate::state::FixedString<1024>,
+    /// Content hash the staged copy was taken from (zero: unknown, commit
+    /// without a conflict check)
+    pub base_hash: [u8; 32],
+    /// Process that staged `temp_path` and commits it. A forked child
+    /// inherits the fd and this entry, but the copy stays its parent's.
+    pub owner_pid: libc::pid_t,
     pub is_vfs: bool,
+    /// A directory in VFS territory; `vpath` is its absolute path, which
+    /// `*at` calls relative to this fd resolve against
+    pub is_dir: bool,
     pub cached_stat: Option<libc::stat>,
     pub mmap_count: usize,
     pub lock_fd: i32, // -1 if no lock FD held
@@ -44,16 +53,66 @@ pub fn track_fd(
     let mut vpath_fs = crate::state::FixedString::<1024>::new();
     vpath_fs.set(path);
 
-    let entry = Box::into_raw(Box::new(FdEntry {
-        vpath: vpath_fs,
-        manifest_key: vpath_fs, // For now assume path is the manifest key if not otherwise specified
-        manifest_key_hash,
-        temp_path: crate::state::FixedString::new(),
-        is_vfs,
-        cached_stat,
-        mmap_count: 0,
-        lock_fd: -1,
-    }));
+    insert_fd_entry(
+        fd,
+        FdEntry {
+            vpath: vpath_fs,
+            manifest_key: vpath_fs, // For now assume path is the manifest key if not otherwise specified
+            manifest_key_hash,
+            temp_path: crate::state::FixedString::new(),
+            base_hash: [0; 32],
+            owner_pid: 0,
+            is_vfs,
+            is_dir: false,
+            cached_stat,
+            mmap_count: 0,
+            lock_fd: -1,
+        },
+    );
+}
+
+/// Track a directory FD opened in VFS territory, so that paths relative to
+/// it resolve without asking the kernel
+pub(crate) fn track_dir_fd(fd: c_int, vpath: &crate::path::VfsPath) {
+    if fd < 0 {
+        return;
+    }
+    insert_fd_entry(
+        fd,
+        FdEntry {
+            vpath: vpath.absolute,
+            manifest_key: vpath.manifest_key,
+            manifest_key_hash: vpath.manifest_key_hash,
+            temp_path: crate::state::FixedString::new(),
+            base_hash: [0; 32],
+            owner_pid: 0,
+            is_vfs: false,
+            is_dir: true,
+            cached_stat: None,
+            mmap_count: 0,
+            lock_fd: -1,
+        },
+    );
+}
+
+/// Track `newfd` as a duplicate of the fd `entry` describes. A duplicate of
+/// a CoW fd keeps the staged copy: it is reingested when its last fd closes.
+fn track_dup(newfd: c_int, entry: &FdEntry) {
+    if newfd < 0 {
+        return;
+    }
+    insert_fd_entry(
+        newfd,
+        FdEntry {
+            mmap_count: 0,
+            lock_fd: -1,
+            ..entry.clone()
+        },
+    );
+}
+
+fn insert_fd_entry(fd: c_int, entry: FdEntry) {
+    let entry = Box::into_raw(Box::new(entry));
 
     if let Some(state) = crate::state::InceptionLayerState::get() {
         let old = state.open_fds.set(fd as u32, entry);
@@ -132,6 +191,7 @@ pub unsafe extern "C" fn dup_inception(oldfd: c_int) -> c_int {
         || crate::state::INCEPTION_LAYER_STATE
             .load(std::sync::atomic::Ordering::Acquire)
             .is_null()
+        || !crate::intercept::enabled(crate::intercept::IO)
     {
         #[cfg(target_os = "macos")]
         return crate::syscalls::macos_raw::raw_dup(oldfd);
@@ -157,13 +217,7 @@ pub unsafe extern "C" fn dup_inception(oldfd: c_int) -> c_int {
     if newfd >= 0 {
         // Copy tracking from oldfd to newfd
         if let Some(entry) = get_fd_entry(oldfd) {
-            track_fd(
-                newfd,
-                entry.vpath.as_str(),
-                entry.is_vfs,
-                entry.cached_stat,
-                entry.manifest_key_hash,
-            );
+            track_dup(newfd, &entry);
         }
     }
     newfd
@@ -178,6 +232,9 @@ pub unsafe extern "C" fn dup2_inception(oldfd: c_int, newfd: c_int) -> c_int {
         || crate::state::INCEPTION_LAYER_STATE
             .load(std::sync::atomic::Ordering::Acquire)
             .is_null()
+        || !crate::intercept::enabled(crate::intercept::IO)
+        // dup2(fd, fd) closes nothing: keep the tracking as it is
+        || oldfd == newfd
     {
         #[cfg(target_os = "macos")]
         return crate::syscalls::macos_raw::raw_dup2(oldfd, newfd);
@@ -196,6 +253,7 @@ pub unsafe extern "C" fn dup2_inception(oldfd: c_int, newfd: c_int) -> c_int {
     };
 
     // If newfd was tracked, untrack it (it's being replaced)
+    let replaced = get_fd_entry(newfd);
     untrack_fd(newfd);
 
     #[cfg(target_os = "macos")]
@@ -206,15 +264,13 @@ pub unsafe extern "C" fn dup2_inception(oldfd: c_int, newfd: c_int) -> c_int {
     if result >= 0 {
         // Copy tracking from oldfd to newfd
         if let Some(entry) = get_fd_entry(oldfd) {
-            track_fd(
-                result,
-                entry.vpath.as_str(),
-                entry.is_vfs,
-                entry.cached_stat,
-                entry.manifest_key_hash,
-            );
+            track_dup(result, &entry);
         }
     }
+    // dup2 closed the replaced fd
+    if let Some(replaced) = replaced {
+        release_staged_copy(&replaced);
+    }
     result
 }
 
@@ -261,8 +317,25 @@ pub unsafe extern "C" fn lseek_inception(fd: c_int, offset: off_t, whence: c_int
 // ftruncate inception layer - truncate VFS file's CoW copy
 // ============================================================================
 
+/// A writable VFS fd is already the staged CoW copy, reingested on close, so
+/// it is truncated in place. An fd served from the manifest without a staged
+/// copy (it carries the manifest's cached stat) reads the shared CAS blob and
+/// is refused the way a read-only fd is, whatever the blob's own permissions
+/// would allow. Files created in VFS territory are real files and pass.
 #[no_mangle]
 pub unsafe extern "C" fn ftruncate_inception(fd: c_int, length: off_t) -> c_int {
+    let init_state = crate::state::INITIALIZING.load(std::sync::atomic::Ordering::Relaxed);
+    if init_state == 0 && crate::intercept::enabled(crate::intercept::WRITE) {
+        if let Some(_guard) = InceptionLayerGuard::enter() {
+            if let Some(entry) = get_fd_entry(fd) {
+                if entry.is_vfs && entry.temp_path.is_empty() && entry.cached_stat.is_some() {
+                    crate::set_errno(libc::EINVAL);
+                    return -1;
+                }
+            }
+        }
+    }
+
     // Pattern 2930: Use raw syscall to avoid post-init dlsym hazard
     #[cfg(target_os = "macos")]
     return crate::syscalls::macos_raw::raw_ftruncate(fd, length);
@@ -350,31 +423,94 @@ pub unsafe extern "C" fn close_inception(fd: c_int) -> c_int {
     #[cfg(target_os = "linux")]
     let res = crate::syscalls::linux_raw::raw_close(fd);
 
-    // Offload IPC task to Worker (asynchronous)
     if let Some(info) = cow_info {
-        inception_log!(
-            "COW CLOSE: fd={} vpath='{}' temp='{}'",
-            fd,
-            info.vpath,
-            info.temp_path
-        );
+        release_staged_copy(&info);
+    }
+    res
+}
+
+/// fclose closes its fd inside glibc, out of reach of close_inception, so
+/// the FD table and a staged copy are seen to here (see [`closing_stream`]).
+#[cfg(target_os = "linux")]
+#[no_mangle]
+pub unsafe extern "C" fn fclose_inception(file: *mut libc::FILE) -> c_int {
+    type RealFclose = unsafe extern "C" fn(*mut libc::FILE) -> c_int;
+    let real: RealFclose = std::mem::transmute(crate::reals::REAL_FCLOSE.get());
+
+    passthrough_if_init!(real, file);
 
-        // Offload reingest to Worker (non-blocking)
-        if let Some(reactor) = crate::sync::get_reactor() {
-            let _ = reactor.ring_buffer.push(crate::sync::Task::Reingest {
-                vpath: info.vpath.to_string(),
-                temp_path: info.temp_path.to_string(),
-            });
+    if file.is_null() || crate::state::InceptionLayerState::get().is_none() {
+        return real(file);
+    }
+    closing_stream(file, || real(file))
+}
+
+/// Run `close`, a glibc call closing the fd of `file` internally (fclose,
+/// freopen). The fd's entry leaves the table before the fd is gone, as its
+/// number may be reused at once, and a staged copy is released after the
+/// stream has been flushed into it.
+#[cfg(target_os = "linux")]
+pub(crate) unsafe fn closing_stream<R>(file: *mut libc::FILE, close: impl FnOnce() -> R) -> R {
+    let entry_ptr = match crate::state::InceptionLayerState::get() {
+        Some(state) if !file.is_null() => {
+            let fd = libc::fileno(file);
+            if fd >= 0 {
+                state.open_fds.remove(fd as u32)
+            } else {
+                std::ptr::null_mut()
+            }
         }
+        _ => std::ptr::null_mut(),
+    };
+    let res = close();
+    if !entry_ptr.is_null() {
+        let _ = OPEN_FD_COUNT.fetch_update(
+            std::sync::atomic::Ordering::Relaxed,
+            std::sync::atomic::Ordering::Relaxed,
+            |val| Some(val.saturating_sub(1)),
+        );
+        release_staged_copy(&Box::from_raw(entry_ptr));
+    }
+    res
+}
 
-        res
-    } else {
-        // Not a COW file, but might be a VFS read-only file or non-VFS file
-        untrack_fd(fd);
-        #[cfg(target_os = "macos")]
-        return crate::syscalls::macos_raw::raw_close(fd);
-        #[cfg(target_os = "linux")]
-        return crate::syscalls::linux_raw::raw_close(fd);
+/// An fd of `info` was closed. Once no fd of this process holds its staged
+/// CoW copy any more, the copy is reingested by the worker (asynchronous),
+/// if this process staged it; other tracked fds just leave the table.
+fn release_staged_copy(info: &FdEntry) {
+    if info.temp_path.is_empty() {
+        return;
+    }
+    if info.owner_pid != unsafe { libc::getpid() } {
+        // Inherited across fork: the parent may still be writing the copy,
+        // and a commit moves it into the CAS
+        inception_log!("COW CLOSE: '{}' left to its owner", info.vpath);
+        return;
+    }
+    let Some(state) = crate::state::InceptionLayerState::get() else {
+        return;
+    };
+    let mut in_use = false;
+    state.open_fds.for_each(|entry| {
+        in_use |= entry.temp_path.as_str() == info.temp_path.as_str();
+    });
+    if in_use {
+        return;
+    }
+    inception_log!(
+        "COW CLOSE: vpath='{}' temp='{}'",
+        info.vpath,
+        info.temp_path
+    );
+
+    // With the `io` group off the entry is still dropped from the FD table,
+    // just not reingested.
+    if crate::intercept::enabled(crate::intercept::IO) {
+        crate::state::queue_reingest(
+            info.manifest_key.as_str(),
+            info.temp_path.as_str(),
+            info.base_hash,
+        );
     }
 }
 
@@ -0,0 +1,459 @@
+//! # Loader Closure Materialization
+//!
+//! `ld.so` and `dyld` open libraries with their own syscalls, which the
+//! shim never sees. A VFS library whose content only lives in the CAS
+//! (phantom mode) therefore cannot be loaded, and neither can the VFS
+//! libraries or interpreter it depends on.
+//!
+//! Before such an object reaches the loader, its dependency closure is
+//! walked with [`vrift_ipc::binfmt`]. For the interpreter and each needed
+//! library, the paths the loader would try are checked in order: one that
+//! exists on disk ends the search (the loader will find it itself), one
+//! that is a VFS file joins the closure and is walked in turn.
+//!
+//! The closure is copied to `.vrift/loader/<id>/<manifest key>`, where `<id>`
+//! hashes every member's key and content. Keeping the project layout means
+//! `$ORIGIN` / `@loader_path` lookups between members still resolve, and the
+//! copy is reused until any member changes. Libraries the members reach by
+//! absolute or search paths are loaded first by their host path, so the
+//! loader matches them by soname / install name instead of searching.
+
+use crate::path::VfsPath;
+use crate::state::*;
+use libc::{c_char, c_int, c_void};
+use std::ffi::CString;
+use std::fmt::Write;
+use vrift_ipc::binfmt::{self, BinFormat, LoadDeps};
+
+#[cfg(target_os = "linux")]
+use crate::syscalls::linux_raw::{
+    raw_access, raw_chmod, raw_mkdir, raw_rename, raw_rmdir, raw_unlink,
+};
+#[cfg(target_os = "macos")]
+use crate::syscalls::macos_raw::{
+    raw_access, raw_chmod, raw_mkdir, raw_rename, raw_rmdir, raw_unlink,
+};
+
+/// Deepest dependency chain followed
+const MAX_DEPTH: usize = 16;
+/// Most VFS objects copied for one load
+const MAX_MEMBERS: usize = 256;
+/// Marks a fully written closure directory
+const COMPLETE_MARKER: &str = "/.complete";
+
+struct Member {
+    vpath: VfsPath,
+    entry: vrift_ipc::VnodeEntry,
+}
+
+/// Host paths for a materialized load
+pub(crate) struct Closure {
+    /// `.vrift/loader/<id>` the members are copied to
+    pub dir: String,
+    /// The object that was asked for
+    pub root: String,
+    /// Everything to load before it, dependencies first: other closure
+    /// members and on-disk libraries they reach outside `$ORIGIN`
+    pub preload: Vec<String>,
+    /// `PT_INTERP` / `LC_LOAD_DYLINKER` of the root, when it is a VFS file
+    pub interpreter: Option<String>,
+    /// Whether the copies are on disk (false for a dry walk)
+    pub materialized: bool,
+}
+
+struct Walk<'a> {
+    state: &'a InceptionLayerState,
+    executable_dir: String,
+    seen: Vec<u64>,
+    /// Closure members, dependencies before dependents; the root is last
+    members: Vec<Member>,
+    /// (member index, host path) of on-disk libraries, in discovery order
+    native: Vec<(usize, String)>,
+    interpreter: Option<usize>,
+}
+
+pub(crate) fn dirname(path: &str) -> &str {
+    match path.rfind('/') {
+        Some(0) => "/",
+        Some(i) => &path[..i],
+        None => ".",
+    }
+}
+
+pub(crate) fn is_regular_file(entry: &vrift_ipc::VnodeEntry) -> bool {
+    // st_mode file type bits, identical on Linux and macOS
+    const S_IFMT: u32 = 0o170000;
+    const S_IFREG: u32 = 0o100000;
+    entry.mode & S_IFMT == S_IFREG
+}
+
+/// Parse the dependencies of `entry` straight from its CAS blob
+unsafe fn read_deps(
+    state: &InceptionLayerState,
+    entry: &vrift_ipc::VnodeEntry,
+) -> Option<LoadDeps> {
+    if let Some(content) = entry.inline_content() {
+        return binfmt::parse(content);
+    }
+    if !entry.is_blob_raw() || entry.size == 0 {
+        return None;
+    }
+    let blob = CString::new(crate::syscalls::open::cas_blob_path(state, entry)).ok()?;
+    let fd = libc::open(blob.as_ptr(), libc::O_RDONLY | libc::O_CLOEXEC);
+    if fd < 0 {
+        return None;
+    }
+    let len = usize::try_from(entry.size).ok()?;
+    let ptr = libc::mmap(
+        std::ptr::null_mut(),
+        len,
+        libc::PROT_READ,
+        libc::MAP_PRIVATE,
+        fd,
+        0,
+    );
+    libc::close(fd);
+    if ptr == libc::MAP_FAILED {
+        return None;
+    }
+    let deps = binfmt::parse(std::slice::from_raw_parts(ptr as *const u8, len));
+    libc::munmap(ptr, len);
+    deps
+}
+
+impl Walk<'_> {
+    /// Add the VFS file at `vpath` and, first, its VFS dependencies.
+    /// `rpaths` are the expanded Mach-O rpaths of the objects that led here.
+    unsafe fn visit(
+        &mut self,
+        vpath: VfsPath,
+        entry: vrift_ipc::VnodeEntry,
+        rpaths: &[String],
+        depth: usize,
+    ) -> Option<usize> {
+        if let Some(i) = self
+            .members
+            .iter()
+            .position(|m| m.vpath.manifest_key_hash == vpath.manifest_key_hash)
+        {
+            return Some(i);
+        }
+        if self.seen.contains(&vpath.manifest_key_hash)
+            || depth > MAX_DEPTH
+            || self.members.len() >= MAX_MEMBERS
+        {
+            return None;
+        }
+        self.seen.push(vpath.manifest_key_hash);
+
+        let mut interpreter = None;
+        if let Some(deps) = read_deps(self.state, &entry) {
+            let loader_dir = dirname(vpath.absolute.as_str()).to_string();
+            let mut chain = rpaths.to_vec();
+            if deps.format == BinFormat::MachO {
+                chain.extend(deps.expanded_search_paths(&loader_dir, &self.executable_dir));
+            }
+            if depth == 0 {
+                if let Some(ref interp) = deps.interpreter {
+                    interpreter = self.resolve(std::slice::from_ref(interp), &chain, depth + 1);
+                }
+            }
+            for name in &deps.needed {
+                let candidates = deps.candidates(name, &loader_dir, &self.executable_dir, rpaths);
+                self.resolve(&candidates, &chain, depth + 1);
+            }
+        }
+
+        self.members.push(Member { vpath, entry });
+        let index = self.members.len() - 1;
+        if depth == 0 {
+            self.interpreter = interpreter;
+        }
+        Some(index)
+    }
+
+    /// Follow the first candidate that exists, on disk or in the VFS
+    unsafe fn resolve(
+        &mut self,
+        candidates: &[String],
+        rpaths: &[String],
+        depth: usize,
+    ) -> Option<usize> {
+        for candidate in candidates {
+            let Some(c_candidate) = crate::path::path_cstring(candidate) else {
+                continue;
+            };
+            if raw_access(c_candidate.as_ptr(), libc::F_OK) == 0 {
+                if !self.native.iter().any(|(_, p)| p == candidate) {
+                    self.native.push((self.members.len(), candidate.clone()));
+                }
+                return None;
+            }
+            let Some(vpath) = self.state.resolve_path(candidate) else {
+                continue;
+            };
+            if let Some(entry) = self.state.query_manifest(&vpath) {
+                if is_regular_file(&entry) {
+                    return self.visit(vpath, entry, rpaths, depth);
+                }
+            }
+        }
+        None
+    }
+
+    /// Closure directory: `.vrift/loader/<hash of member keys and content>`
+    fn closure_dir(&self) -> String {
+        let mut id = String::new();
+        for member in &self.members {
+            let _ = write!(id, "{}\0", member.vpath.manifest_key.as_str());
+            for b in member.entry.content_hash {
+                let _ = write!(id, "{:02x}", b);
+            }
+            let _ = writeln!(id, "\0{}\0{:o}", member.entry.size, member.entry.mode);
+        }
+        format!(
+            "{}/.vrift/loader/{:016x}",
+            self.state.project_root.as_str(),
+            vrift_ipc::fnv1a_hash(&id)
+        )
+    }
+}
+
+/// `mkdir -p` below an existing `base`; newly created directories are
+/// appended to `created`
+unsafe fn make_dirs(base: &str, path: &str, created: &mut Vec<CString>) -> bool {
+    let Some(rest) = path.strip_prefix(base) else {
+        return false;
+    };
+    let mut current = base.to_string();
+    for component in rest.split('/').filter(|c| !c.is_empty()) {
+        current.push('/');
+        current.push_str(component);
+        let Some(c_dir) = crate::path::path_cstring(&current) else {
+            return false;
+        };
+        if raw_mkdir(c_dir.as_ptr(), 0o755) == 0 {
+            created.push(c_dir);
+        } else if crate::get_errno() != libc::EEXIST {
+            return false;
+        }
+    }
+    true
+}
+
+/// Copy every member below `dir`, via a temporary directory renamed into
+/// place so concurrent loaders never see a partial closure
+unsafe fn write_closure(state: &InceptionLayerState, walk: &Walk, dir: &str) -> bool {
+    let project_root = state.project_root.as_str();
+    let tmp = format!("{}.{}.tmp", dir, libc::getpid());
+    let mut dirs = Vec::new();
+    let mut files = Vec::new();
+
+    let mut ok = make_dirs(project_root, &tmp, &mut dirs);
+    for member in &walk.members {
+        if !ok {
+            break;
+        }
+        let dst = format!("{}{}", tmp, member.vpath.manifest_key.as_str());
+        ok = make_dirs(&tmp, dirname(&dst), &mut dirs);
+        let Some(c_dst) = crate::path::path_cstring(&dst) else {
+            ok = false;
+            break;
+        };
+        if ok {
+            ok = match member.entry.inline_content() {
+                Some(content) => crate::syscalls::open::write_file(&c_dst, content),
+                None if member.entry.is_external() => crate::syscalls::open::external_target(
+                    state,
+                    &member.entry.content_hash,
+                    member.entry.size,
+                )
+                .is_some_and(|target| crate::syscalls::open::copy_file(&target, &c_dst)),
+                None => {
+                    match CString::new(crate::syscalls::open::cas_blob_path(state, &member.entry)) {
+                        Ok(blob) => crate::syscalls::open::copy_file(&blob, &c_dst),
+                        Err(_) => false,
+                    }
+                }
+            };
+            files.push(c_dst);
+        }
+        if ok {
+            let c_dst = &files[files.len() - 1];
+            raw_chmod(c_dst.as_ptr(), (member.entry.mode & 0o777) as libc::mode_t);
+        }
+    }
+    if ok {
+        if let Some(marker) = crate::path::path_cstring(&format!("{}{}", tmp, COMPLETE_MARKER)) {
+            ok = crate::syscalls::open::write_file(&marker, b"");
+            files.push(marker);
+        }
+    }
+    let (Some(c_tmp), Some(c_dir)) = (
+        crate::path::path_cstring(&tmp),
+        crate::path::path_cstring(dir),
+    ) else {
+        return false;
+    };
+    if ok && raw_rename(c_tmp.as_ptr(), c_dir.as_ptr()) == 0 {
+        return true;
+    }
+
+    // Failed, or another process won the rename: drop our copy
+    for file in &files {
+        raw_unlink(file.as_ptr());
+    }
+    for d in dirs.iter().rev() {
+        raw_rmdir(d.as_ptr());
+    }
+    ok && raw_access(c_dir.as_ptr(), libc::F_OK) == 0
+}
+
+/// Walk the closure of the VFS object at `path` and, unless `dry_run`,
+/// make sure its copy is on disk. `None` when `path` is not a VFS file, or
+/// is one the loader can already open (Solid mode).
+pub(crate) unsafe fn materialize_closure(
+    state: &InceptionLayerState,
+    path: &str,
+    dry_run: bool,
+) -> Option<Closure> {
+    let vpath = state.resolve_path(path)?;
+    let entry = state.query_manifest(&vpath)?;
+    if !is_regular_file(&entry) {
+        return None;
+    }
+    let c_path = crate::path::path_cstring(&vpath.absolute)?;
+    if raw_access(c_path.as_ptr(), libc::F_OK) == 0 {
+        return None;
+    }
+
+    let executable_dir = std::env::current_exe()
+        .ok()
+        .and_then(|p| p.parent().map(|d| d.to_string_lossy().into_owned()))
+        .unwrap_or_default();
+    let mut walk = Walk {
+        state,
+        executable_dir,
+        seen: Vec::new(),
+        members: Vec::new(),
+        native: Vec::new(),
+        interpreter: None,
+    };
+    walk.visit(vpath, entry, &[], 0)?;
+
+    let dir = walk.closure_dir();
+    let c_marker = crate::path::path_cstring(&format!("{}{}", dir, COMPLETE_MARKER))?;
+    let materialized = raw_access(c_marker.as_ptr(), libc::F_OK) == 0
+        || (!dry_run && write_closure(state, &walk, &dir));
+    if !dry_run && !materialized {
+        inception_warn!(
+            "loader: cannot materialize closure of '{}' in '{}'",
+            path,
+            dir
+        );
+        return None;
+    }
+
+    let host = |m: &Member| format!("{}{}", dir, m.vpath.manifest_key.as_str());
+    let root_index = walk.members.len() - 1;
+    // Native libraries go right before the first member that needed them
+    let mut preload = Vec::new();
+    for (i, member) in walk.members.iter().enumerate().take(root_index + 1) {
+        preload.extend(
+            walk.native
+                .iter()
+                .filter(|(before, _)| *before == i)
+                .map(|(_, p)| p.clone()),
+        );
+        if i < root_index && Some(i) != walk.interpreter {
+            preload.push(host(member));
+        }
+    }
+    inception_log!(
+        "loader: '{}' -> '{}' ({} VFS objects, {} on disk)",
+        path,
+        dir,
+        walk.members.len(),
+        walk.native.len()
+    );
+    Some(Closure {
+        root: host(&walk.members[root_index]),
+        preload,
+        interpreter: walk.interpreter.map(|i| host(&walk.members[i])),
+        materialized,
+        dir,
+    })
+}
+
+unsafe fn real_dlopen(path: *const c_char, flags: c_int) -> *mut c_void {
+    #[cfg(target_os = "linux")]
+    {
+        let f = crate::reals::REAL_DLOPEN.get();
+        if f.is_null() {
+            return std::ptr::null_mut();
+        }
+        let real = std::mem::transmute::<
+            *mut c_void,
+            unsafe extern "C" fn(*const c_char, c_int) -> *mut c_void,
+        >(f);
+        real(path, flags)
+    }
+    // Calls from the interposing image itself are not interposed
+    #[cfg(target_os = "macos")]
+    {
+        libc::dlopen(path, flags)
+    }
+}
+
+/// `dlopen` of a VFS library: load its materialized copy
+pub(crate) unsafe fn dlopen_impl(path: *const c_char, flags: c_int) -> *mut c_void {
+    passthrough_if_init!(real_dlopen, path, flags);
+    if path.is_null() || !crate::intercept::enabled(crate::intercept::EXEC) {
+        return real_dlopen(path, flags);
+    }
+    let path_str = crate::path::c_path_str(path);
+    // Bare names go through the loader's own search
+    if !path_str.contains('/') {
+        return real_dlopen(path, flags);
+    }
+    let Some(state) = InceptionLayerState::get() else {
+        return real_dlopen(path, flags);
+    };
+
+    let closure = {
+        let Some(_guard) = InceptionLayerGuard::enter() else {
+            return real_dlopen(path, flags);
+        };
+        materialize_closure(state, &path_str, flags & libc::RTLD_NOLOAD != 0)
+    };
+    let Some(closure) = closure else {
+        return real_dlopen(path, flags);
+    };
+    let Some(root) = crate::path::path_cstring(&closure.root) else {
+        return real_dlopen(path, flags);
+    };
+    if flags & libc::RTLD_NOLOAD != 0 {
+        return real_dlopen(root.as_ptr(), flags);
+    }
+
+    // Constructors may open VFS files, so load outside the guard. The extra
+    // references are dropped once the root holds its own.
+    let dep_flags = (flags & (libc::RTLD_LAZY | libc::RTLD_NOW)) | libc::RTLD_LOCAL;
+    let handles: Vec<*mut c_void> = closure
+        .preload
+        .iter()
+        .filter_map(|p| crate::path::path_cstring(p))
+        .map(|p| real_dlopen(p.as_ptr(), dep_flags))
+        .collect();
+    let handle = real_dlopen(root.as_ptr(), flags);
+    for h in handles.into_iter().filter(|h| !h.is_null()) {
+        libc::dlclose(h);
+    }
+    handle
+}
+
+#[cfg(target_os = "macos")]
+#[no_mangle]
+pub unsafe extern "C" fn dlopen_inception(path: *const c_char, flags: c_int) -> *mut c_void {
+    dlopen_impl(path, flags)
+}
@@ -1,15 +1,17 @@
+use crate::intercept;
 use crate::state::*;
 #[cfg(target_os = "macos")]
 use libc::c_void;
 use libc::{c_char, c_int};
 use std::ffi::CStr;
-#[cfg(target_os = "macos")]
-use std::fmt::Write;
 use std::sync::atomic::Ordering;
 
 /// RFC-0047: Rename implementation with VFS boundary enforcement
 /// Returns EXDEV (18) for cross-domain renames
 unsafe fn rename_impl(old: *const c_char, new: *const c_char) -> Option<c_int> {
+    if !intercept::enabled(intercept::WRITE) {
+        return None;
+    }
     if old.is_null() || new.is_null() {
         return None;
     }
@@ -17,11 +19,11 @@ unsafe fn rename_impl(old: *const c_char, new: *const c_char) -> Option<c_int> {
     let _guard = InceptionLayerGuard::enter()?;
     let state = InceptionLayerState::get()?;
 
-    let old_str = CStr::from_ptr(old).to_str().ok()?;
-    let new_str = CStr::from_ptr(new).to_str().ok()?;
+    let old_str = crate::path::c_path_str(old);
+    let new_str = crate::path::c_path_str(new);
 
-    let old_in_vfs = state.inception_applicable(old_str);
-    let new_in_vfs = state.inception_applicable(new_str);
+    let old_in_vfs = state.inception_applicable(&old_str);
+    let new_in_vfs = state.inception_applicable(&new_str);
 
     // RFC-0047: Cross-boundary rename is forbidden
     if old_in_vfs != new_in_vfs {
@@ -31,26 +33,67 @@ unsafe fn rename_impl(old: *const c_char, new: *const c_char) -> Option<c_int> {
 
     // Both in VFS territory -> Virtual Rename via Daemon IPC
     if old_in_vfs && new_in_vfs {
-        if let (Some(v1), Some(v2)) = (state.resolve_path(old_str), state.resolve_path(new_str)) {
-            // RFC-0047: Only use Virtual Rename for managed files.
-            // For local files in VFS territory, let raw_rename handle it.
-            if state.query_manifest_ipc(&v1).is_some() {
-                if state
-                    .manifest_rename(&v1.manifest_key, &v2.manifest_key)
-                    .is_ok()
-                {
-                    return Some(0);
-                }
-                crate::set_errno(libc::EPERM);
-                return Some(-1);
-            }
-        }
-        return None; // Fallback to raw syscall for local files
+        return vfs_rename(state, &old_str, &new_str, || raw_rename(old, new));
     }
 
     None // Let real syscall handle non-VFS renames
 }
 
+/// Rename between two paths in VFS territory.
+///
+/// RFC-0047: a managed source is renamed in the manifest only. An untracked
+/// source renamed over a managed target (the write-temp-then-rename pattern)
+/// is renamed on disk with `raw` first; vDird then hashes the new target and
+/// swaps its entry, dropping any entry of the temp name, in one VDir write.
+/// Readers see the old target until they see the new one, never a missing
+/// target. `None` leaves both paths to the real syscall.
+unsafe fn vfs_rename(
+    state: &InceptionLayerState,
+    old_abs: &str,
+    new_abs: &str,
+    raw: impl FnOnce() -> c_int,
+) -> Option<c_int> {
+    let (v1, v2) = (state.resolve_path(old_abs)?, state.resolve_path(new_abs)?);
+    if state.query_manifest_ipc(&v1).is_some() {
+        if state
+            .manifest_rename(&v1.manifest_key, &v2.manifest_key)
+            .is_ok()
+        {
+            return Some(0);
+        }
+        crate::set_errno(libc::EPERM);
+        return Some(-1);
+    }
+    // Local files on both sides
+    state.query_manifest_ipc(&v2)?;
+
+    let ret = raw();
+    if ret == 0
+        && !crate::ipc::sync_ipc_manifest_rename_over(
+            &state.vdird_socket_path,
+            &v1.manifest_key,
+            &v2.manifest_key,
+        )
+    {
+        inception_warn!(
+            "rename over '{}': vDird did not take the new content",
+            v2.manifest_key
+        );
+    }
+    Some(ret)
+}
+
+unsafe fn raw_rename(old: *const c_char, new: *const c_char) -> c_int {
+    #[cfg(target_os = "macos")]
+    {
+        crate::syscalls::macos_raw::raw_rename(old, new)
+    }
+    #[cfg(target_os = "linux")]
+    {
+        crate::syscalls::linux_raw::raw_rename(old, new)
+    }
+}
+
 #[no_mangle]
 #[cfg(target_os = "macos")]
 pub unsafe extern "C" fn rename_inception(old: *const c_char, new: *const c_char) -> c_int {
@@ -66,14 +109,7 @@ pub unsafe extern "C" fn velo_rename_impl(old: *const c_char, new: *const c_char
     if let Some(res) = rename_impl(old, new) {
         return res;
     }
-    #[cfg(target_os = "macos")]
-    {
-        crate::syscalls::macos_raw::raw_rename(old, new)
-    }
-    #[cfg(target_os = "linux")]
-    {
-        crate::syscalls::linux_raw::raw_rename(old, new)
-    }
+    raw_rename(old, new)
 }
 
 /// Linux-specific rename inception call
@@ -94,7 +130,7 @@ pub unsafe extern "C" fn renameat_inception_linux(
     newfd: c_int,
     new: *const c_char,
 ) -> c_int {
-    if let Some(res) = renameat_impl(old, new) {
+    if let Some(res) = renameat_impl(oldfd, old, newfd, new) {
         return res;
     }
     crate::syscalls::linux_raw::raw_renameat(oldfd, old, newfd, new)
@@ -128,22 +164,23 @@ pub unsafe extern "C" fn velo_renameat_impl(
 ) -> c_int {
     // Resolve relative paths using getcwd for AT_FDCWD case
     if oldfd == libc::AT_FDCWD && newfd == libc::AT_FDCWD {
-        if let Some(result) = renameat_impl(old, new) {
+        if let Some(result) = renameat_impl(oldfd, old, newfd, new) {
             return result;
         }
     }
-    #[cfg(target_os = "macos")]
-    {
-        crate::syscalls::macos_raw::raw_renameat(oldfd, old, newfd, new)
-    }
-    #[cfg(target_os = "linux")]
-    {
-        crate::syscalls::linux_raw::raw_renameat(oldfd, old, newfd, new)
-    }
+    raw_renameat(oldfd, old, newfd, new)
 }
 
 /// renameat path resolution helper - resolves relative paths to absolute
-unsafe fn renameat_impl(old: *const c_char, new: *const c_char) -> Option<c_int> {
+unsafe fn renameat_impl(
+    oldfd: c_int,
+    old: *const c_char,
+    newfd: c_int,
+    new: *const c_char,
+) -> Option<c_int> {
+    if !intercept::enabled(intercept::WRITE) {
+        return None;
+    }
     if old.is_null() || new.is_null() {
         return None;
     }
@@ -151,8 +188,8 @@ unsafe fn renameat_impl(old: *const c_char, new: *const c_char) -> Option<c_int>
     let _guard = InceptionLayerGuard::enter()?;
     let state = InceptionLayerState::get()?;
 
-    let old_str = CStr::from_ptr(old).to_str().ok()?;
-    let new_str = CStr::from_ptr(new).to_str().ok()?;
+    let old_str = crate::path::c_path_str(old);
+    let new_str = crate::path::c_path_str(new);
 
     // Resolve relative paths via getcwd
     let resolve_path = |path: &str| -> Option<String> {
@@ -164,14 +201,14 @@ unsafe fn renameat_impl(old: *const c_char, new: *const c_char) -> Option<c_int>
             if cwd.is_null() {
                 None
             } else {
-                let cwd_str = CStr::from_ptr(cwd).to_str().ok()?;
+                let cwd_str = crate::path::c_path_str(cwd);
                 Some(format!("{}/{}", cwd_str, path))
             }
         }
     };
 
-    let old_abs = resolve_path(old_str)?;
-    let new_abs = resolve_path(new_str)?;
+    let old_abs = resolve_path(&old_str)?;
+    let new_abs = resolve_path(&new_str)?;
 
     let old_in_vfs = state.inception_applicable(&old_abs);
     let new_in_vfs = state.inception_applicable(&new_abs);
@@ -182,11 +219,39 @@ unsafe fn renameat_impl(old: *const c_char, new: *const c_char) -> Option<c_int>
         return Some(-1);
     }
 
+    // Relative paths were resolved against the cwd, which only holds for
+    // AT_FDCWD
+    let cwd_relative = |fd: c_int, path: &str| fd == libc::AT_FDCWD || path.starts_with('/');
+    if old_in_vfs && cwd_relative(oldfd, &old_str) && cwd_relative(newfd, &new_str) {
+        return vfs_rename(state, &old_abs, &new_abs, || {
+            raw_renameat(oldfd, old, newfd, new)
+        });
+    }
+
     None // Let real syscall handle
 }
 
+unsafe fn raw_renameat(
+    oldfd: c_int,
+    old: *const c_char,
+    newfd: c_int,
+    new: *const c_char,
+) -> c_int {
+    #[cfg(target_os = "macos")]
+    {
+        crate::syscalls::macos_raw::raw_renameat(oldfd, old, newfd, new)
+    }
+    #[cfg(target_os = "linux")]
+    {
+        crate::syscalls::linux_raw::raw_renameat(oldfd, old, newfd, new)
+    }
+}
+
 /// Helper to block mutation on VFS-managed files via FD
 pub(crate) unsafe fn quick_block_vfs_fd_mutation(fd: c_int) -> Option<c_int> {
+    if !intercept::enabled(intercept::WRITE) {
+        return None;
+    }
     let _guard = InceptionLayerGuard::enter()?;
     let state = InceptionLayerState::get()?;
 
@@ -195,12 +260,9 @@ pub(crate) unsafe fn quick_block_vfs_fd_mutation(fd: c_int) -> Option<c_int> {
     {
         let mut path_buf = [0i8; 1024];
         if libc::fcntl(fd, libc::F_GETPATH, path_buf.as_mut_ptr()) == 0 {
-            let path_cstr = CStr::from_ptr(path_buf.as_ptr());
-            if let Ok(path_str) = path_cstr.to_str() {
-                if state.inception_applicable(path_str) {
-                    crate::set_errno(libc::EPERM);
-                    return Some(-1);
-                }
+            if state.inception_applicable(&crate::path::c_path_str(path_buf.as_ptr())) {
+                crate::set_errno(libc::EPERM);
+                return Some(-1);
             }
         }
     }
@@ -339,6 +401,9 @@ pub unsafe extern "C" fn fchflags_inception(fd: c_int, flags: libc::c_uint) -> c
 /// RFC-0047: Link (hardlink) implementation with VFS boundary enforcement
 /// Hardlinks crossing VFS boundary or into CAS are forbidden (returns EXDEV)
 unsafe fn link_impl(old: *const c_char, new: *const c_char) -> Option<c_int> {
+    if !intercept::enabled(intercept::WRITE) {
+        return None;
+    }
     if old.is_null() || new.is_null() {
         return None;
     }
@@ -346,11 +411,8 @@ unsafe fn link_impl(old: *const c_char, new: *const c_char) -> Option<c_int> {
     let _guard = InceptionLayerGuard::enter()?;
     let state = InceptionLayerState::get()?;
 
-    let old_str = CStr::from_ptr(old).to_str().ok()?;
-    let new_str = CStr::from_ptr(new).to_str().ok()?;
-
-    let old_in_vfs = state.inception_applicable(old_str);
-    let new_in_vfs = state.inception_applicable(new_str);
+    let old_in_vfs = state.inception_applicable(&crate::path::c_path_str(old));
+    let new_in_vfs = state.inception_applicable(&crate::path::c_path_str(new));
 
     // RFC-0047: Cross-boundary hardlink is forbidden
     // Also block if source is in VFS (protects CAS blobs)
@@ -569,22 +631,30 @@ pub unsafe extern "C" fn mkdirat_inception(
             return crate::syscalls::macos_raw::raw_mkdirat(dirfd, path, mode);
         }
         // RFC-0039: Only block if path EXISTS in manifest, allow new dir creation
-        block_existing_vfs_entry_at(dirfd, path)
-            .unwrap_or_else(|| crate::syscalls::macos_raw::raw_mkdirat(dirfd, path, mode))
+        if let Some(err) = block_existing_vfs_entry_at(dirfd, path) {
+            return err;
+        }
+        let result = crate::syscalls::macos_raw::raw_mkdirat(dirfd, path, mode);
+        if result == 0 {
+            notify_created_at(dirfd, path, Created::Dir(mode));
+        }
+        result
     }
     #[cfg(target_os = "linux")]
     {
-        if INITIALIZING.load(Ordering::Relaxed) >= 2
-            || crate::state::INCEPTION_LAYER_STATE
-                .load(Ordering::Acquire)
-                .is_null()
-        {
+        if INITIALIZING.load(Ordering::Relaxed) >= 2 {
             // RFC-0039: During early init, allow mkdirat passthrough
             return crate::syscalls::linux_raw::raw_mkdirat(dirfd, path, mode);
         }
         // RFC-0039: Only block if path EXISTS in manifest, allow new dir creation
-        block_existing_vfs_entry(path)
-            .unwrap_or_else(|| crate::syscalls::linux_raw::raw_mkdirat(dirfd, path, mode))
+        if let Some(err) = block_existing_vfs_entry_at(dirfd, path) {
+            return err;
+        }
+        let result = crate::syscalls::linux_raw::raw_mkdirat(dirfd, path, mode);
+        if result == 0 {
+            notify_created_at(dirfd, path, Created::Dir(mode));
+        }
+        result
     }
 }
 
@@ -602,15 +672,15 @@ pub unsafe extern "C" fn symlinkat_inception(
                 .load(Ordering::Acquire)
                 .is_null()
         {
-            if let Some(err) = quick_block_vfs_mutation(p1).or_else(|| quick_block_vfs_mutation(p2))
-            {
+            if let Some(err) = quick_block_vfs_mutation(p2) {
                 return err;
             }
             return crate::syscalls::macos_raw::raw_symlinkat(p1, dirfd, p2);
         }
 
-        // Block mutation on existing VFS entries
-        if let Some(err) = block_vfs_mutation(p1).or_else(|| block_vfs_mutation(p2)) {
+        // RFC-0039: Only block if the link path EXISTS in manifest; the
+        // target is just a string and may name anything
+        if let Some(err) = block_existing_vfs_entry_at(dirfd, p2) {
             return err;
         }
 
@@ -619,33 +689,25 @@ pub unsafe extern "C" fn symlinkat_inception(
 
         // RFC-0039 Live Ingest: Notify daemon of successful symlink
         if result == 0 {
-            if let Some(state) = crate::state::InceptionLayerState::get() {
-                let target_str = CStr::from_ptr(p1).to_string_lossy();
-                let link_str = CStr::from_ptr(p2).to_string_lossy();
-                if let Some(vpath) = state.resolve_path(&link_str) {
-                    let _ = state.manifest_symlink(&vpath.manifest_key, &target_str);
-                }
-            }
+            notify_created_at(dirfd, p2, Created::Symlink(p1));
         }
 
         result
     }
     #[cfg(target_os = "linux")]
     {
-        if INITIALIZING.load(Ordering::Relaxed) >= 2
-            || crate::state::INCEPTION_LAYER_STATE
-                .load(Ordering::Acquire)
-                .is_null()
-        {
-            if let Some(err) = quick_block_vfs_mutation(p1).or_else(|| quick_block_vfs_mutation(p2))
-            {
+        // A fresh process (`ln -s`) has no state yet; the check below sets
+        // it up instead of refusing every link into the VFS
+        if INITIALIZING.load(Ordering::Relaxed) >= 2 {
+            if let Some(err) = quick_block_vfs_mutation(p2) {
                 return err;
             }
             return crate::syscalls::linux_raw::raw_symlinkat(p1, dirfd, p2);
         }
 
-        // Block mutation on existing VFS entries
-        if let Some(err) = block_vfs_mutation(p1).or_else(|| block_vfs_mutation(p2)) {
+        // RFC-0039: Only block if the link path EXISTS in manifest; the
+        // target is just a string and may name anything
+        if let Some(err) = block_existing_vfs_entry_at(dirfd, p2) {
             return err;
         }
 
@@ -654,13 +716,7 @@ pub unsafe extern "C" fn symlinkat_inception(
 
         // RFC-0039 Live Ingest: Notify daemon of successful symlink
         if result == 0 {
-            if let Some(state) = crate::state::InceptionLayerState::get() {
-                let target_str = CStr::from_ptr(p1).to_string_lossy();
-                let link_str = CStr::from_ptr(p2).to_string_lossy();
-                if let Some(vpath) = state.resolve_path(&link_str) {
-                    let _ = state.manifest_symlink(&vpath.manifest_key, &target_str);
-                }
-            }
+            notify_created_at(dirfd, p2, Created::Symlink(p1));
         }
 
         result
@@ -726,13 +782,7 @@ pub unsafe extern "C" fn mkdir_inception(path: *const c_char, mode: libc::mode_t
 
     // RFC-0039 Live Ingest: Notify daemon of successful mkdir
     if result == 0 {
-        if let Some(state) = crate::state::InceptionLayerState::get() {
-            let path_str = CStr::from_ptr(path).to_string_lossy();
-            if let Some(vpath) = state.resolve_path(&path_str) {
-                // Fire-and-forget IPC to register new dir in manifest
-                let _ = state.manifest_mkdir(&vpath.manifest_key, mode);
-            }
-        }
+        notify_created_at(libc::AT_FDCWD, path, Created::Dir(mode));
     }
 
     result
@@ -761,12 +811,7 @@ pub unsafe extern "C" fn mkdir_inception(path: *const c_char, mode: libc::mode_t
 
     // RFC-0039 Live Ingest: Notify daemon of successful mkdir
     if result == 0 {
-        if let Some(state) = crate::state::InceptionLayerState::get() {
-            let path_str = CStr::from_ptr(path).to_string_lossy();
-            if let Some(vpath) = state.resolve_path(&path_str) {
-                let _ = state.manifest_mkdir(&vpath.manifest_key, mode);
-            }
-        }
+        notify_created_at(libc::AT_FDCWD, path, Created::Dir(mode));
     }
 
     result
@@ -868,16 +913,19 @@ pub unsafe extern "C" fn setattrlist_inception(
 /// NOTE: This blocks ALL mutations in VFS territory (for destructive ops like unlink, chmod)
 /// For creation ops (mkdir, symlink), use block_existing_vfs_entry instead
 pub(crate) unsafe fn block_vfs_mutation(path: *const c_char) -> Option<c_int> {
+    if !intercept::enabled(intercept::WRITE) {
+        return None;
+    }
     if path.is_null() {
         return None;
     }
 
-    let path_str = CStr::from_ptr(path).to_str().ok()?;
+    let path_str = crate::path::c_path_str(path);
 
     // First try: Full inception layer state check (daemon connected)
     if let Some(_guard) = InceptionLayerGuard::enter() {
         if let Some(state) = InceptionLayerState::get() {
-            if let Some(vpath) = state.resolve_path(path_str) {
+            if let Some(vpath) = state.resolve_path(&path_str) {
                 // RFC-0047: Block all mutations in VFS territory to ensure integrity
                 inception_log!(
                     "blocking mutation on VFS territory path: '{}'",
@@ -907,47 +955,18 @@ pub(crate) unsafe fn block_existing_vfs_entry_at(
     dirfd: c_int,
     path: *const c_char,
 ) -> Option<c_int> {
+    if !intercept::enabled(intercept::WRITE) {
+        return None;
+    }
     if path.is_null() {
         return None;
     }
 
-    let path_str = CStr::from_ptr(path).to_str().ok()?;
-
     let _guard = InceptionLayerGuard::enter()?;
     let state = InceptionLayerState::get()?;
-    let mut resolved_vpath = None;
-
-    if path_str.starts_with('/') || dirfd == libc::AT_FDCWD {
-        resolved_vpath = state.resolve_path(path_str);
-    } else {
-        // Resolve dirfd
-        let mut _dir_path_buf = [0i8; 1024];
-        #[cfg(target_os = "macos")]
-        if crate::syscalls::macos_raw::raw_fcntl(
-            dirfd,
-            libc::F_GETPATH,
-            _dir_path_buf.as_mut_ptr() as i64,
-        ) == 0
-        {
-            let mut abs_buf = [0u8; 1024];
-            let mut aw = crate::macros::StackWriter::new(&mut abs_buf);
-            let dir_path_ptr = _dir_path_buf.as_ptr() as *const u8;
-            let dir_path_len = unsafe { libc::strlen(dir_path_ptr as *const i8) };
-
-            // Safety: libc::fcntl with F_GETPATH fills the buffer with a NUL-terminated C string.
-            // We use bytes directly to avoid to_string_lossy (which can allocate).
-            let dir_path_bytes = unsafe { std::slice::from_raw_parts(dir_path_ptr, dir_path_len) };
-            let _ = aw.write_str(std::str::from_utf8(dir_path_bytes).unwrap_or(""));
-            let _ = aw.write_char('/');
-            let _ = aw.write_str(path_str);
-
-            resolved_vpath = state.resolve_path(aw.as_str());
-        }
-        #[cfg(target_os = "linux")]
-        {
-            // TODO: linux dirfd resolution
-        }
-    }
+    let mut at_buf = [0u8; 1024];
+    let resolved_vpath = crate::path::resolve_path_at(dirfd, path, &mut at_buf)
+        .and_then(|at_path| state.resolve_path(&crate::path::c_path_str(at_path.as_ptr())));
 
     if let Some(vpath) = resolved_vpath {
         // Check if this path exists in manifest
@@ -968,47 +987,77 @@ pub(crate) unsafe fn block_existing_vfs_entry(path: *const c_char) -> Option<c_i
     block_existing_vfs_entry_at(libc::AT_FDCWD, path)
 }
 
+/// What a creation call made, for [`notify_created_at`]
+enum Created {
+    Dir(libc::mode_t),
+    /// A symlink, with its target
+    Symlink(*const c_char),
+}
+
+/// RFC-0039 Live Ingest: register an entry just created at `path` (relative
+/// to `dirfd`) in the manifest, if it is in VFS territory. Fire-and-forget,
+/// like the creation it follows.
+unsafe fn notify_created_at(dirfd: c_int, path: *const c_char, created: Created) {
+    if !intercept::enabled(intercept::WRITE) {
+        return;
+    }
+    let Some(state) = InceptionLayerState::get() else {
+        return;
+    };
+    let mut at_buf = [0u8; 1024];
+    let Some(vpath) = crate::path::resolve_path_at(dirfd, path, &mut at_buf)
+        .and_then(|at_path| state.resolve_path(&crate::path::c_path_str(at_path.as_ptr())))
+    else {
+        return;
+    };
+    match created {
+        Created::Dir(mode) => {
+            let _ = state.manifest_mkdir(&vpath.manifest_key, mode);
+        }
+        Created::Symlink(target) => {
+            let target_str = crate::path::c_path_str(target);
+            let _ = state.manifest_symlink(&vpath.manifest_key, &target_str);
+        }
+    }
+}
+
 #[inline]
 pub(crate) unsafe fn quick_is_in_vfs(path: *const c_char) -> bool {
-    if path.is_null() {
+    if path.is_null() || !intercept::enabled(intercept::WRITE) {
         return false;
     }
-    let path_str = if let Ok(s) = CStr::from_ptr(path).to_str() {
-        s
-    } else {
-        return false;
-    };
     let env_name = b"VRIFT_VFS_PREFIX\0";
     let vfs_prefix_ptr = libc::getenv(env_name.as_ptr() as *const c_char);
-    if !vfs_prefix_ptr.is_null() {
-        if let Ok(vfs_prefix) = CStr::from_ptr(vfs_prefix_ptr).to_str() {
-            return path_str.starts_with(vfs_prefix);
-        }
-    }
-    false
+    !vfs_prefix_ptr.is_null()
+        && CStr::from_ptr(path)
+            .to_bytes()
+            .starts_with(CStr::from_ptr(vfs_prefix_ptr).to_bytes())
 }
 
 /// Lightweight VFS check for raw syscall path - avoids TLS/InceptionLayerGuard
 /// Only checks VRIFT_VFS_PREFIX env var, safe to call during early init
 #[inline]
 pub(crate) unsafe fn quick_block_vfs_mutation(path: *const c_char) -> Option<c_int> {
+    if !intercept::enabled(intercept::WRITE) {
+        return None;
+    }
     if path.is_null() {
         return None;
     }
-    let path_str = CStr::from_ptr(path).to_str().ok()?;
+    let path_cstr = CStr::from_ptr(path);
     let env_name = b"VRIFT_VFS_PREFIX\0";
     let vfs_prefix_ptr = libc::getenv(env_name.as_ptr() as *const c_char);
     if !vfs_prefix_ptr.is_null() {
-        if let Ok(vfs_prefix) = CStr::from_ptr(vfs_prefix_ptr).to_str() {
-            let matches = path_str.starts_with(vfs_prefix);
-            if matches {
-                inception_log!(
-                    "blocking mutation (quick-block) on VFS path: '{}'",
-                    path_str
-                );
-                crate::set_errno(libc::EPERM);
-                return Some(-1);
-            }
+        let matches = path_cstr
+            .to_bytes()
+            .starts_with(CStr::from_ptr(vfs_prefix_ptr).to_bytes());
+        if matches {
+            inception_log!(
+                "blocking mutation (quick-block) on VFS path: '{}'",
+                path_cstr.to_string_lossy()
+            );
+            crate::set_errno(libc::EPERM);
+            return Some(-1);
         }
     }
     None
@@ -1084,6 +1133,7 @@ pub unsafe extern "C" fn fchmod_inception(fd: c_int, mode: libc::mode_t) -> c_in
             || crate::state::INCEPTION_LAYER_STATE
                 .load(Ordering::Acquire)
                 .is_null()
+            || !intercept::enabled(intercept::WRITE)
         {
             return crate::syscalls::macos_raw::raw_fchmod(fd, mode);
         }
@@ -1098,13 +1148,11 @@ pub unsafe extern "C" fn fchmod_inception(fd: c_int, mode: libc::mode_t) -> c_in
         // Strategy: Try to get path from FD (robust)
         let mut path_buf = [0; 1024];
         if unsafe { libc::fcntl(fd, libc::F_GETPATH, path_buf.as_mut_ptr()) } == 0 {
-            let path_cstr = unsafe { CStr::from_ptr(path_buf.as_ptr()) };
-            if let Ok(path_str) = path_cstr.to_str() {
-                if let Some(state) = InceptionLayerState::get() {
-                    if state.inception_applicable(path_str) {
-                        crate::set_errno(libc::EPERM);
-                        return -1;
-                    }
+            let path_str = unsafe { crate::path::c_path_str(path_buf.as_ptr()) };
+            if let Some(state) = InceptionLayerState::get() {
+                if state.inception_applicable(&path_str) {
+                    crate::set_errno(libc::EPERM);
+                    return -1;
                 }
             }
         }
@@ -1126,6 +1174,7 @@ pub unsafe extern "C" fn fchmod_inception(fd: c_int, mode: libc::mode_t) -> c_in
             || crate::state::INCEPTION_LAYER_STATE
                 .load(Ordering::Acquire)
                 .is_null()
+            || !intercept::enabled(intercept::WRITE)
         {
             return crate::syscalls::linux_raw::raw_fchmod(fd, mode);
         }
@@ -1186,6 +1235,7 @@ pub unsafe extern "C" fn fchown_inception(
             || crate::state::INCEPTION_LAYER_STATE
                 .load(Ordering::Acquire)
                 .is_null()
+            || !intercept::enabled(intercept::WRITE)
         {
             return crate::syscalls::macos_raw::raw_fchown(fd, owner, group);
         }
@@ -1200,13 +1250,11 @@ pub unsafe extern "C" fn fchown_inception(
         // Strategy: Try to get path from FD via F_GETPATH
         let mut path_buf = [0i8; 1024];
         if libc::fcntl(fd, libc::F_GETPATH, path_buf.as_mut_ptr()) == 0 {
-            let path_cstr = CStr::from_ptr(path_buf.as_ptr());
-            if let Ok(path_str) = path_cstr.to_str() {
-                if let Some(state) = InceptionLayerState::get() {
-                    if state.inception_applicable(path_str) {
-                        crate::set_errno(libc::EPERM);
-                        return -1;
-                    }
+            let path_str = crate::path::c_path_str(path_buf.as_ptr());
+            if let Some(state) = InceptionLayerState::get() {
+                if state.inception_applicable(&path_str) {
+                    crate::set_errno(libc::EPERM);
+                    return -1;
                 }
             }
         }
@@ -1228,6 +1276,7 @@ pub unsafe extern "C" fn fchown_inception(
             || crate::state::INCEPTION_LAYER_STATE
                 .load(Ordering::Acquire)
                 .is_null()
+            || !intercept::enabled(intercept::WRITE)
         {
             return crate::syscalls::linux_raw::raw_fchown(fd, owner, group);
         }
@@ -1405,13 +1454,10 @@ pub unsafe extern "C" fn readlinkat_inception(
         // Delegate to readlink inception logic which handles VFS paths
         if let Some(_guard) = InceptionLayerGuard::enter() {
             if let Some(state) = InceptionLayerState::get() {
-                let path_cstr = CStr::from_ptr(path);
-                if let Ok(path_str) = path_cstr.to_str() {
-                    if let Some(vpath) = state.resolve_path(path_str) {
-                        // VFS path: use raw readlinkat to read the underlying symlink
-                        // (the real symlink target is in the materialized workspace)
-                        inception_log!("readlinkat on VFS path: '{}'", vpath.absolute);
-                    }
+                if let Some(vpath) = state.resolve_path(&crate::path::c_path_str(path)) {
+                    // VFS path: use raw readlinkat to read the underlying symlink
+                    // (the real symlink target is in the materialized workspace)
+                    inception_log!("readlinkat on VFS path: '{}'", vpath.absolute);
                 }
             }
         }
@@ -1447,6 +1493,7 @@ pub unsafe extern "C" fn exchangedata_inception(
         || crate::state::INCEPTION_LAYER_STATE
             .load(Ordering::Acquire)
             .is_null()
+        || !intercept::enabled(intercept::WRITE)
     {
         return crate::syscalls::macos_raw::raw_exchangedata(path1, path2, options);
     }
@@ -1458,13 +1505,11 @@ pub unsafe extern "C" fn exchangedata_inception(
 
     // Full VFS check with state
     if let Some(state) = InceptionLayerState::get() {
-        let p1_str = CStr::from_ptr(path1).to_str().ok();
-        let p2_str = CStr::from_ptr(path2).to_str().ok();
-        if let (Some(p1), Some(p2)) = (p1_str, p2_str) {
-            if state.inception_applicable(p1) || state.inception_applicable(p2) {
-                crate::set_errno(libc::EXDEV);
-                return -1;
-            }
+        if state.inception_applicable(&crate::path::c_path_str(path1))
+            || state.inception_applicable(&crate::path::c_path_str(path2))
+        {
+            crate::set_errno(libc::EXDEV);
+            return -1;
         }
     }
 
@@ -1473,6 +1518,65 @@ pub unsafe extern "C" fn exchangedata_inception(
 
 // --- truncate ---
 
+/// Truncate a managed file by breaking CoW on it.
+///
+/// Phantom mode has no real file to truncate, so the file is opened for
+/// writing through `open_impl` (which stages a private copy), truncated
+/// there and closed, which reingests it. If this process already writes the
+/// file, its staged copy is truncated instead, so the open fd sees the new
+/// size and its close reingests both changes. Paths the manifest does not
+/// know are left to the real syscall.
+unsafe fn truncate_impl(path: *const c_char, length: libc::off_t) -> Option<c_int> {
+    if !intercept::enabled(intercept::WRITE) || path.is_null() {
+        return None;
+    }
+
+    let fd = {
+        let _guard = InceptionLayerGuard::enter()?;
+        let state = InceptionLayerState::get()?;
+        let vpath = state.resolve_path(&crate::path::c_path_str(path))?;
+
+        if let Some(temp_path) = crate::syscalls::stat::find_live_temp_path(&vpath.manifest_key) {
+            let temp_cpath = std::ffi::CString::new(temp_path.as_str()).ok()?;
+            #[cfg(target_os = "macos")]
+            return Some(crate::syscalls::macos_raw::raw_truncate(
+                temp_cpath.as_ptr(),
+                length,
+            ));
+            #[cfg(target_os = "linux")]
+            return Some(crate::syscalls::linux_raw::raw_truncate(
+                temp_cpath.as_ptr(),
+                length,
+            ));
+        }
+        state.query_manifest_ipc(&vpath)?;
+
+        // Nothing of the old content survives a truncate to zero
+        let flags = if length == 0 {
+            libc::O_WRONLY | libc::O_TRUNC
+        } else {
+            libc::O_WRONLY
+        };
+        match crate::syscalls::open::open_impl(path, flags, 0) {
+            Some(fd) if fd >= 0 => fd,
+            _ => {
+                crate::set_errno(libc::EIO);
+                return Some(-1);
+            }
+        }
+    };
+
+    #[cfg(target_os = "macos")]
+    let ret = crate::syscalls::macos_raw::raw_ftruncate(fd, length);
+    #[cfg(target_os = "linux")]
+    let ret = crate::syscalls::linux_raw::raw_ftruncate(fd, length);
+    let errno = crate::get_errno();
+    // Outside the guard, so the close reingests the staged copy
+    crate::syscalls::io::close_inception(fd);
+    crate::set_errno(errno);
+    Some(ret)
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn truncate_inception(path: *const c_char, length: libc::off_t) -> c_int {
     let init_state = INITIALIZING.load(Ordering::Relaxed);
@@ -1491,10 +1595,10 @@ pub unsafe extern "C" fn truncate_inception(path: *const c_char, length: libc::o
     }
     // Pattern 2930: Use raw syscall to avoid post-init dlsym hazard
     #[cfg(target_os = "macos")]
-    return block_vfs_mutation(path)
+    return truncate_impl(path, length)
         .unwrap_or_else(|| crate::syscalls::macos_raw::raw_truncate(path, length));
     #[cfg(target_os = "linux")]
-    return block_vfs_mutation(path)
+    return truncate_impl(path, length)
         .unwrap_or_else(|| crate::syscalls::linux_raw::raw_truncate(path, length));
 }
 
@@ -1602,6 +1706,12 @@ pub unsafe extern "C" fn setrlimit_inception(resource: c_int, rlp: *const libc::
 #[no_mangle]
 #[cfg(target_os = "macos")]
 pub unsafe extern "C" fn flock_inception(fd: c_int, op: c_int) -> c_int {
+    passthrough_if_disabled!(
+        crate::intercept::IO,
+        crate::syscalls::macos_raw::raw_flock,
+        fd,
+        op
+    );
     let _guard = match InceptionLayerGuard::enter() {
         Some(g) => g,
         None => {
@@ -1708,9 +1818,15 @@ pub unsafe extern "C" fn symlink_inception(p1: *const c_char, p2: *const c_char)
         return err;
     }
     #[cfg(target_os = "macos")]
-    return crate::syscalls::macos_raw::raw_symlinkat(p1, libc::AT_FDCWD, p2);
+    let result = crate::syscalls::macos_raw::raw_symlinkat(p1, libc::AT_FDCWD, p2);
     #[cfg(target_os = "linux")]
-    return crate::syscalls::linux_raw::raw_symlink(p1, p2);
+    let result = crate::syscalls::linux_raw::raw_symlink(p1, p2);
+
+    // RFC-0039 Live Ingest: Notify daemon of successful symlink
+    if result == 0 {
+        notify_created_at(libc::AT_FDCWD, p2, Created::Symlink(p1));
+    }
+    result
 }
 
 #[no_mangle]
@@ -1720,9 +1836,39 @@ pub unsafe extern "C" fn execve_inception(
     argv: *const *const c_char,
     envp: *const *const c_char,
 ) -> c_int {
+    #[cfg(not(feature = "minimal"))]
+    if INITIALIZING.load(Ordering::Relaxed) == 0 {
+        if let Some(r) = crate::syscalls::process::rewrite_exec(path, argv, envp, false) {
+            return libc::execve(r.path(path), r.argv(argv), r.envp(envp));
+        }
+    }
     libc::execve(path, argv, envp)
 }
 
+/// Report a freshly spawned child to the session tracker. Children whose
+/// shim never loads (SIP binaries, static executables) would otherwise start
+/// a new session for every grandchild.
+#[cfg(target_os = "macos")]
+unsafe fn report_spawned_child(pid: *const libc::pid_t, argv: *const *const c_char) {
+    if pid.is_null() || *pid <= 0 || !intercept::enabled(intercept::EXEC) {
+        return;
+    }
+    let Some(state) = InceptionLayerState::get_no_spawn() else {
+        return;
+    };
+    let command = if !argv.is_null() && !(*argv).is_null() {
+        CStr::from_ptr(*argv).to_string_lossy()
+    } else {
+        std::borrow::Cow::Borrowed("")
+    };
+    crate::ipc::report_session_process(
+        &state.socket_path,
+        *pid as u32,
+        libc::getpid() as u32,
+        &command,
+    );
+}
+
 #[no_mangle]
 #[cfg(target_os = "macos")]
 pub unsafe extern "C" fn posix_spawn_inception(
@@ -1733,14 +1879,29 @@ pub unsafe extern "C" fn posix_spawn_inception(
     argv: *const *const c_char,
     envp: *const *const c_char,
 ) -> c_int {
-    libc::posix_spawn(
+    #[cfg(not(feature = "minimal"))]
+    let rewrite = if INITIALIZING.load(Ordering::Relaxed) == 0 {
+        crate::syscalls::process::rewrite_exec(path, argv, envp, false)
+    } else {
+        None
+    };
+    #[cfg(not(feature = "minimal"))]
+    let (path, argv, envp) = match rewrite {
+        Some(ref r) => (r.path(path), r.argv(argv), r.envp(envp)),
+        None => (path, argv, envp),
+    };
+    let ret = libc::posix_spawn(
         pid,
         path,
         fa as *const libc::posix_spawn_file_actions_t,
         attr as *const libc::posix_spawnattr_t,
         argv as *const *mut c_char,
         envp as *const *mut c_char,
-    )
+    );
+    if ret == 0 {
+        report_spawned_child(pid, argv);
+    }
+    ret
 }
 
 #[no_mangle]
@@ -1753,25 +1914,70 @@ pub unsafe extern "C" fn posix_spawnp_inception(
     argv: *const *const c_char,
     envp: *const *const c_char,
 ) -> c_int {
-    libc::posix_spawnp(
+    #[cfg(not(feature = "minimal"))]
+    let rewrite = if INITIALIZING.load(Ordering::Relaxed) == 0 {
+        crate::syscalls::process::rewrite_exec(file, argv, envp, true)
+    } else {
+        None
+    };
+    #[cfg(not(feature = "minimal"))]
+    let (file, argv, envp) = match rewrite {
+        Some(ref r) => (r.path(file), r.argv(argv), r.envp(envp)),
+        None => (file, argv, envp),
+    };
+    let ret = libc::posix_spawnp(
         pid,
         file,
         fa as *const libc::posix_spawn_file_actions_t,
         attr as *const libc::posix_spawnattr_t,
         argv as *const *mut c_char,
         envp as *const *mut c_char,
-    )
+    );
+    if ret == 0 {
+        report_spawned_child(pid, argv);
+    }
+    ret
 }
 
+/// faccessat: a VFS path, once resolved against `dirfd`, is answered the
+/// way `access` answers it
 #[no_mangle]
-#[cfg(target_os = "macos")]
 pub unsafe extern "C" fn faccessat_inception(
     dirfd: c_int,
     path: *const c_char,
     mode: c_int,
     flags: c_int,
 ) -> c_int {
-    libc::faccessat(dirfd, path, mode, flags)
+    #[inline(always)]
+    unsafe fn raw_faccessat_internal(
+        dirfd: c_int,
+        path: *const c_char,
+        mode: c_int,
+        flags: c_int,
+    ) -> c_int {
+        #[cfg(target_os = "macos")]
+        return libc::faccessat(dirfd, path, mode, flags);
+        #[cfg(target_os = "linux")]
+        return crate::syscalls::linux_raw::raw_faccessat(dirfd, path, mode, flags);
+    }
+
+    if INITIALIZING.load(Ordering::Relaxed) != 0
+        || CIRCUIT_TRIPPED.load(Ordering::Relaxed)
+        || !intercept::enabled(intercept::STAT)
+    {
+        return raw_faccessat_internal(dirfd, path, mode, flags);
+    }
+    let Some(state) = InceptionLayerState::get() else {
+        return raw_faccessat_internal(dirfd, path, mode, flags);
+    };
+
+    let mut at_buf = [0u8; 1024];
+    match crate::path::resolve_path_at(dirfd, path, &mut at_buf) {
+        Some(at_path) if state.inception_applicable(&crate::path::c_path_str(at_path.as_ptr())) => {
+            crate::syscalls::stat::velo_access_impl(at_path.as_ptr(), mode)
+        }
+        _ => raw_faccessat_internal(dirfd, path, mode, flags),
+    }
 }
 
 /// fcntl implementation called from C bridge (variadic_inception.c)
@@ -1,8 +1,12 @@
 // Syscall implementations
 pub mod dir;
+#[cfg(target_os = "linux")]
+pub mod inotify;
 pub mod io;
 #[cfg(target_os = "linux")]
 pub mod linux_raw;
+#[cfg(not(feature = "minimal"))]
+pub mod loader;
 #[cfg(target_os = "macos")]
 pub mod macos_raw;
 pub mod mem;
@@ -11,6 +15,7 @@ pub mod mmap;
 pub mod open;
 pub mod path;
 pub mod path_ops;
+#[cfg(not(feature = "minimal"))]
 pub mod process;
 pub mod stat;
 pub mod vfs_ops;
@@ -1,13 +1,34 @@
+use crate::intercept;
 use crate::state::*;
 use libc::{c_char, c_int, c_void, mode_t};
 use std::ffi::CStr;
 use std::fmt::Write;
 use std::sync::atomic::Ordering;
+use std::sync::OnceLock;
+use vrift_config::serve::{ServeMode, ServePolicy};
 
 #[cfg(target_os = "linux")]
-use crate::syscalls::linux_raw::raw_open;
+use crate::syscalls::linux_raw::{raw_access, raw_open};
 #[cfg(target_os = "macos")]
-use crate::syscalls::macos_raw::raw_open;
+use crate::syscalls::macos_raw::{raw_access, raw_open};
+
+/// `VRIFT_SERVE_POLICY`, parsed on first use
+static SERVE_POLICY: OnceLock<ServePolicy> = OnceLock::new();
+
+fn serve_mode(manifest_key: &str) -> ServeMode {
+    let policy = SERVE_POLICY.get_or_init(|| unsafe {
+        let val = libc::getenv(c"VRIFT_SERVE_POLICY".as_ptr());
+        if val.is_null() {
+            return ServePolicy::default();
+        }
+        ServePolicy::from_env_value(&CStr::from_ptr(val).to_string_lossy())
+    });
+    if policy.is_empty() {
+        ServeMode::Cas
+    } else {
+        policy.mode_for(manifest_key)
+    }
+}
 
 /// Open implementation with VFS detection and CoW semantics.
 pub(crate) unsafe fn open_impl(path: *const c_char, flags: c_int, mode: mode_t) -> Option<c_int> {
@@ -15,15 +36,11 @@ pub(crate) unsafe fn open_impl(path: *const c_char, flags: c_int, mode: mode_t)
         return None;
     }
 
-    let path_cstr = CStr::from_ptr(path);
-    let path_str = match path_cstr.to_str() {
-        Ok(s) => s,
-        Err(_) => return None,
-    };
+    let path_str = crate::path::c_path_str(path);
 
     let state = InceptionLayerState::get()?;
 
-    let vpath = match state.resolve_path(path_str) {
+    let vpath = match state.resolve_path(&path_str) {
         Some(p) => {
             inception_log!(
                 "open path='{}' -> resolved='{}' (HIT)",
@@ -36,6 +53,11 @@ pub(crate) unsafe fn open_impl(path: *const c_char, flags: c_int, mode: mode_t)
         None => return None,
     };
 
+    if let Some(err) = crate::fault::injected_errno(crate::fault::Op::Open) {
+        crate::set_errno(err);
+        return Some(-1);
+    }
+
     let entry = match state.query_manifest_ipc(&vpath) {
         Some(e) => {
             inception_log!(
@@ -61,6 +83,10 @@ pub(crate) unsafe fn open_impl(path: *const c_char, flags: c_int, mode: mode_t)
             inception_record!(EventType::OpenMiss, vpath.manifest_key_hash, 0);
 
             let fd = unsafe { raw_open(path, flags, mode) };
+            if fd >= 0 && flags & libc::O_DIRECTORY != 0 {
+                crate::syscalls::io::track_dir_fd(fd, &vpath);
+                return Some(fd);
+            }
             if fd >= 0 {
                 // Track FD for Live Ingest on close() - especially important for writes
                 crate::syscalls::io::track_fd(
@@ -76,117 +102,108 @@ pub(crate) unsafe fn open_impl(path: *const c_char, flags: c_int, mode: mode_t)
         }
     };
 
-    let hash_hex = hex_encode(&entry.content_hash);
-    let blob_path = format!(
-        "{}/blake3/{}/{}/{}_{}.bin",
-        state.cas_root,
-        &hash_hex[0..2],
-        &hash_hex[2..4],
-        hash_hex,
-        entry.size
-    );
+    if entry.is_dir() {
+        // Directories are real; the fd is tracked for `*at` calls made
+        // relative to it
+        let fd = unsafe { raw_open(path, flags, mode) };
+        crate::syscalls::io::track_dir_fd(fd, &vpath);
+        return Some(fd);
+    }
 
-    inception_log!("redirection path: '{}'", blob_path);
+    if flags & (libc::O_CREAT | libc::O_EXCL) == libc::O_CREAT | libc::O_EXCL {
+        crate::set_errno(libc::EEXIST);
+        return Some(-1);
+    }
+
+    // The real file is the content: reads and writes go straight to it, with
+    // no copy-on-write and no reingest
+    if entry.is_external() {
+        let target = external_target(state, &entry.content_hash, entry.size)?;
+        inception_log!(
+            "open '{}': external -> '{}'",
+            vpath.manifest_key,
+            target.to_string_lossy()
+        );
+        return Some(raw_open(target.as_ptr(), flags, mode));
+    }
 
     let is_write = (flags & (libc::O_WRONLY | libc::O_RDWR | libc::O_APPEND | libc::O_TRUNC)) != 0;
+    // O_TRUNC discards the old content, so the blob is never read
+    let discards_content = is_write && (flags & libc::O_TRUNC) != 0;
+
+    // The shim cannot decode compressed or encrypted blobs; the real file
+    // (present in Solid mode) is the only faithful copy it can offer
+    let inline = entry.inline_content();
+    if !entry.is_blob_raw() && inline.is_none() && !discards_content {
+        inception_log!(
+            "open '{}': blob is not stored raw (flags=0x{:x}) -> passthrough",
+            vpath.manifest_key,
+            entry.flags
+        );
+        return None;
+    }
+
+    let blob_path = cas_blob_path(state, &entry);
+
+    inception_log!("redirection path: '{}'", blob_path);
 
     if is_write {
         inception_log!("open write request for '{}'", vpath.absolute);
 
+        // The base lets vDird spot a concurrent writer. A rewrite from
+        // scratch does not depend on it, and while a write of ours still
+        // awaits its reingest the manifest content is not what we wrote
+        let base_hash = if discards_content || DIRTY_TRACKER.is_dirty(&vpath.manifest_key) {
+            [0; 32]
+        } else {
+            entry.content_hash
+        };
+
         // M4: Mark path as dirty in DirtyTracker (enables stat redirect to staging)
         DIRTY_TRACKER.mark_dirty(&vpath.manifest_key);
 
-        let mut attempts = 0;
-        let mut fd = -1;
-        let mut temp_path_fs = FixedString::<1024>::new();
-        let pid = unsafe { libc::getpid() };
-        let tid_addr = &attempts as *const _ as usize;
-
-        while attempts < 100 {
-            let timestamp = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_nanos();
-
-            let mut buf = [0u8; 1024];
-            let mut writer = crate::macros::StackWriter::new(&mut buf);
-            let _ = write!(
-                writer,
-                "{}/.vrift/staging/vrift_cow_{}_{}_{}_{}.tmp",
-                state.project_root.as_str(),
-                pid,
-                timestamp,
-                tid_addr,
-                attempts
-            );
-            temp_path_fs.set(writer.as_str());
-
-            let c_temp = match std::ffi::CString::new(temp_path_fs.as_str()) {
-                Ok(c) => c,
-                Err(_) => break,
-            };
-            fd = unsafe {
-                libc::open(
-                    c_temp.as_ptr(),
-                    libc::O_RDWR | libc::O_CREAT | libc::O_EXCL | libc::O_CLOEXEC,
-                    0o600,
-                )
-            };
-            if fd >= 0 {
-                break;
-            }
-            if unsafe { crate::get_errno() } != libc::EEXIST {
-                break;
-            }
-            attempts += 1;
-        }
-
-        if fd < 0 {
-            return None;
-        }
-        let temp_fd = fd;
-        let temp_path = temp_path_fs;
-        unsafe { libc::close(temp_fd) };
-        let temp_cpath = std::ffi::CString::new(temp_path.as_str()).ok()?;
-
-        inception_log!("COW TRIGGERED: '{}' -> '{}'", vpath.absolute, temp_path);
-        inception_record!(EventType::CowTriggered, vpath.manifest_key_hash, 0);
-
-        let blob_cpath = std::ffi::CString::new(blob_path.as_str()).ok()?;
-        let src_fd = unsafe { libc::open(blob_cpath.as_ptr(), libc::O_RDONLY | libc::O_CLOEXEC) };
-        if src_fd >= 0 {
-            let dst_fd = unsafe {
-                libc::open(
-                    temp_cpath.as_ptr(),
-                    libc::O_WRONLY | libc::O_CREAT | libc::O_TRUNC | libc::O_CLOEXEC,
-                    0o644,
-                )
-            };
-            if dst_fd >= 0 {
-                let mut buf = [0u8; 8192];
-                loop {
-                    let n =
-                        unsafe { libc::read(src_fd, buf.as_mut_ptr() as *mut c_void, buf.len()) };
-                    if n <= 0 {
-                        break;
+        // Every writer of a path in this process shares one staged copy, as
+        // fds of one file would, so appends and positioned writes through
+        // different fds land in the same bytes
+        let (temp_path, base_hash) =
+            match crate::syscalls::stat::find_live_copy(&vpath.manifest_key) {
+                Some((temp_path, base_hash)) => {
+                    inception_log!("COW SHARED: '{}' -> '{}'", vpath.absolute, temp_path);
+                    (temp_path, base_hash)
+                }
+                None => {
+                    let temp_path = create_staging_file(state)?;
+                    let temp_cpath = std::ffi::CString::new(temp_path.as_str()).ok()?;
+                    inception_log!("COW TRIGGERED: '{}' -> '{}'", vpath.absolute, temp_path);
+                    inception_record!(EventType::CowTriggered, vpath.manifest_key_hash, 0);
+
+                    let blob_cpath = std::ffi::CString::new(blob_path.as_str()).ok()?;
+                    let filled = match inline {
+                        _ if discards_content => true,
+                        Some(content) => write_file(&temp_cpath, content),
+                        // Solid mode still has the real file if the blob is gone
+                        None => {
+                            copy_file(&blob_cpath, &temp_cpath)
+                                || copy_file(CStr::from_ptr(path), &temp_cpath)
+                                || fetch_blob(state, &entry)
+                                    .is_some_and(|fetched| copy_file(&fetched, &temp_cpath))
+                        }
+                    };
+                    if !filled {
+                        // Appends and positioned writes would land at the wrong
+                        // offsets in a partial copy
+                        inception_warn!(
+                            "open '{}': original content unavailable",
+                            vpath.manifest_key
+                        );
+                        libc::unlink(temp_cpath.as_ptr());
+                        crate::set_errno(libc::EIO);
+                        return Some(-1);
                     }
-                    unsafe { libc::write(dst_fd, buf.as_ptr() as *const c_void, n as usize) };
+                    (temp_path, base_hash)
                 }
-                unsafe { libc::close(dst_fd) };
-            }
-            unsafe { libc::close(src_fd) };
-        } else {
-            let dst_fd = unsafe {
-                libc::open(
-                    temp_cpath.as_ptr(),
-                    libc::O_WRONLY | libc::O_CREAT | libc::O_TRUNC | libc::O_CLOEXEC,
-                    0o644,
-                )
             };
-            if dst_fd >= 0 {
-                unsafe { libc::close(dst_fd) };
-            }
-        }
+        let temp_cpath = std::ffi::CString::new(temp_path.as_str()).ok()?;
 
         let fd = unsafe { libc::open(temp_cpath.as_ptr(), flags, mode as libc::c_uint) };
         if fd < 0 {
@@ -198,7 +215,10 @@ pub(crate) unsafe fn open_impl(path: *const c_char, flags: c_int, mode: mode_t)
                 manifest_key: vpath.manifest_key,
                 manifest_key_hash: vpath.manifest_key_hash,
                 temp_path,
+                base_hash,
+                owner_pid: libc::getpid(),
                 is_vfs: true,
+                is_dir: false,
                 cached_stat: None,
                 mmap_count: 0,
                 lock_fd: -1,
@@ -214,14 +234,61 @@ pub(crate) unsafe fn open_impl(path: *const c_char, flags: c_int, mode: mode_t)
             Some(fd)
         }
     } else {
+        let materialize = serve_mode(&vpath.manifest_key) == ServeMode::Materialize;
+        if materialize && unsafe { raw_access(path, libc::F_OK) } == 0 {
+            // Solid mode: the real file is already on disk at its own path
+            inception_log!(
+                "open '{}': serve policy materialize -> passthrough",
+                vpath.manifest_key
+            );
+            return None;
+        }
         let blob_cpath = std::ffi::CString::new(blob_path.as_str()).ok()?;
-        let fd = unsafe { libc::open(blob_cpath.as_ptr(), flags, mode as libc::c_uint) };
+        if inline.is_none() {
+            crate::pack::note_access(&entry.content_hash);
+        }
+        let fd = match inline {
+            // No blob to redirect to: the content lives in memory only
+            Some(content) => match open_inline(content, flags) {
+                Some(fd) => fd,
+                None => open_private_copy(state, flags, mode, |dst| unsafe {
+                    write_file(dst, content)
+                })?,
+            },
+            // Policy wants a file of its own rather than the shared blob
+            None if materialize => {
+                inception_log!(
+                    "open '{}': serve policy materialize -> private copy",
+                    vpath.manifest_key
+                );
+                open_private_copy(state, flags, mode, |dst| unsafe {
+                    copy_file(&blob_cpath, dst)
+                        || fetch_blob(state, &entry).is_some_and(|fetched| copy_file(&fetched, dst))
+                })?
+            }
+            None => match crate::pack::open_packed(
+                state,
+                state.manifest_pack_ref(&vpath, &entry.content_hash),
+                &entry.content_hash,
+                flags,
+            ) {
+                Some(fd) => {
+                    inception_log!("open '{}': served from packfile", vpath.manifest_key);
+                    fd
+                }
+                None => unsafe { open_blob(state, &entry, path, &blob_cpath, flags, mode) },
+            },
+        };
         if fd >= 0 {
             // 🔥 Build and cache stat for VFS file
             let mut cached_stat: libc::stat = unsafe { std::mem::zeroed() };
             cached_stat.st_size = entry.size as _;
             cached_stat.st_mode = entry.mode as _;
-            cached_stat.st_mtime = entry.mtime as _;
+            crate::syscalls::stat::set_stat_times(
+                &mut cached_stat,
+                entry.mtime,
+                state.manifest_ingest_ns(&vpath),
+            );
             cached_stat.st_dev = 0x52494654; // "RIFT"
             cached_stat.st_nlink = 1;
             cached_stat.st_ino = vpath.manifest_key_hash as _;
@@ -243,9 +310,244 @@ pub(crate) unsafe fn open_impl(path: *const c_char, flags: c_int, mode: mode_t)
 // Called by C bridge (c_open_bridge) after INITIALIZING check passes
 #[no_mangle]
 pub unsafe extern "C" fn velo_open_impl(path: *const c_char, flags: c_int, mode: mode_t) -> c_int {
+    passthrough_if_disabled!(intercept::OPEN, raw_open, path, flags, mode);
     open_impl(path, flags, mode).unwrap_or_else(|| raw_open(path, flags, mode))
 }
 
+/// Create an empty, uniquely named file in `.vrift/staging` and return its
+/// path. The `vrift_cow_<pid>_` prefix lets the daemon clean up after
+/// processes that die before removing it.
+unsafe fn create_staging_file(state: &InceptionLayerState) -> Option<FixedString<1024>> {
+    let mut attempts = 0;
+    let mut fd = -1;
+    let mut temp_path_fs = FixedString::<1024>::new();
+    let pid = unsafe { libc::getpid() };
+    let tid_addr = &attempts as *const _ as usize;
+
+    while attempts < 100 {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+
+        let mut buf = [0u8; 1024];
+        let mut writer = crate::macros::StackWriter::new(&mut buf);
+        let _ = write!(
+            writer,
+            "{}/.vrift/staging/vrift_cow_{}_{}_{}_{}.tmp",
+            state.project_root.as_str(),
+            pid,
+            timestamp,
+            tid_addr,
+            attempts
+        );
+        temp_path_fs.set(writer.as_str());
+
+        let c_temp = match std::ffi::CString::new(temp_path_fs.as_str()) {
+            Ok(c) => c,
+            Err(_) => break,
+        };
+        fd = unsafe {
+            libc::open(
+                c_temp.as_ptr(),
+                libc::O_RDWR | libc::O_CREAT | libc::O_EXCL | libc::O_CLOEXEC,
+                0o600,
+            )
+        };
+        if fd >= 0 {
+            break;
+        }
+        if unsafe { crate::get_errno() } != libc::EEXIST {
+            break;
+        }
+        attempts += 1;
+    }
+
+    if fd < 0 {
+        return None;
+    }
+    unsafe { libc::close(fd) };
+    Some(temp_path_fs)
+}
+
+/// Path of the CAS blob holding `entry`'s content.
+///
+/// Open the CAS blob of `entry` (the manifest file at `path`). A blob
+/// missing here is fetched from vriftd's upstream, unless the real file is
+/// on disk for the passthrough to serve.
+unsafe fn open_blob(
+    state: &InceptionLayerState,
+    entry: &vrift_ipc::VnodeEntry,
+    path: *const c_char,
+    blob_cpath: &CStr,
+    flags: c_int,
+    mode: mode_t,
+) -> c_int {
+    let flags = blob_read_flags(flags);
+    let fd = libc::open(blob_cpath.as_ptr(), flags, mode as libc::c_uint);
+    if fd >= 0 || crate::get_errno() != libc::ENOENT || raw_access(path, libc::F_OK) == 0 {
+        return fd;
+    }
+    match fetch_blob(state, entry) {
+        Some(fetched) => libc::open(fetched.as_ptr(), flags, mode as libc::c_uint),
+        None => {
+            crate::set_errno(libc::ENOENT);
+            fd
+        }
+    }
+}
+
+/// `flags` of a read-only open, trimmed to what may reach a shared CAS
+/// blob: reads share the blob itself, so nothing that creates or modifies
+fn blob_read_flags(flags: c_int) -> c_int {
+    libc::O_RDONLY | (flags & (libc::O_CLOEXEC | libc::O_NONBLOCK))
+}
+
+/// A read-only fd holding inline `content` without touching the disk
+/// (Linux: a sealed memfd); `None` where the caller must stage a copy
+#[cfg(target_os = "linux")]
+unsafe fn open_inline(content: &[u8], flags: c_int) -> Option<c_int> {
+    crate::pack::sealed_memfd(c"vrift-inline", content, flags & libc::O_CLOEXEC != 0)
+}
+
+#[cfg(not(target_os = "linux"))]
+unsafe fn open_inline(_content: &[u8], _flags: c_int) -> Option<c_int> {
+    None
+}
+
+/// Have vriftd fetch the missing blob of `entry` from its upstream CAS;
+/// the blob's path once it is here
+unsafe fn fetch_blob(
+    state: &InceptionLayerState,
+    entry: &vrift_ipc::VnodeEntry,
+) -> Option<std::ffi::CString> {
+    if !crate::ipc::sync_ipc_cas_fetch(&state.socket_path, &entry.content_hash, entry.size) {
+        return None;
+    }
+    // Fetched blobs are stored without the `.bin` of bulk ingest
+    let blob_path = cas_blob_path(state, entry);
+    inception_log!("fetched missing blob from upstream: '{}'", blob_path);
+    std::ffi::CString::new(blob_path).ok()
+}
+
+/// Bulk ingest names blobs `<hash>_<size>.bin`; the blobs vDird stores itself
+/// (CoW reingests, renames over a file) carry no extension.
+pub(crate) fn cas_blob_path(state: &InceptionLayerState, entry: &vrift_ipc::VnodeEntry) -> String {
+    blob_path(state, &entry.content_hash, entry.size)
+}
+
+/// [`cas_blob_path`] of the blob with `hash` holding `size` bytes
+fn blob_path(state: &InceptionLayerState, hash: &[u8; 32], size: u64) -> String {
+    let hash_hex = hex_encode(hash);
+    let bare = format!(
+        "{}/blake3/{}/{}/{}_{}",
+        state.cas_root,
+        &hash_hex[0..2],
+        &hash_hex[2..4],
+        hash_hex,
+        size
+    );
+    let bin = format!("{}.bin", bare);
+    let exists = |path: &str| {
+        std::ffi::CString::new(path)
+            .map(|c| unsafe { raw_access(c.as_ptr(), libc::F_OK) } == 0)
+            .unwrap_or(false)
+    };
+    if !exists(&bin) && exists(&bare) {
+        return bare;
+    }
+    bin
+}
+
+/// The real file an external entry serves: the absolute path held by its
+/// `len`-byte blob `hash`. `None` if the blob is unreadable.
+pub(crate) unsafe fn external_target(
+    state: &InceptionLayerState,
+    hash: &[u8; 32],
+    len: u64,
+) -> Option<std::ffi::CString> {
+    let len = usize::try_from(len)
+        .ok()
+        .filter(|&n| n < libc::PATH_MAX as usize)?;
+    let blob = std::ffi::CString::new(blob_path(state, hash, len as u64)).ok()?;
+    let fd = libc::open(blob.as_ptr(), libc::O_RDONLY | libc::O_CLOEXEC);
+    if fd < 0 {
+        return None;
+    }
+    let mut buf = vec![0u8; len];
+    let n = libc::read(fd, buf.as_mut_ptr() as *mut c_void, len);
+    libc::close(fd);
+    if n != len as isize {
+        return None;
+    }
+    std::ffi::CString::new(buf).ok()
+}
+
+/// Replace the contents of `path` with `content`
+pub(crate) unsafe fn write_file(path: &CStr, content: &[u8]) -> bool {
+    let fd = libc::open(
+        path.as_ptr(),
+        libc::O_WRONLY | libc::O_CREAT | libc::O_TRUNC | libc::O_CLOEXEC,
+        0o644,
+    );
+    if fd < 0 {
+        return false;
+    }
+    let written = libc::write(fd, content.as_ptr() as *const c_void, content.len());
+    libc::close(fd);
+    written == content.len() as isize
+}
+
+/// Copy `src` over `dst`; false if `src` cannot be read or `dst` written
+pub(crate) unsafe fn copy_file(src: &CStr, dst: &CStr) -> bool {
+    let src_fd = libc::open(src.as_ptr(), libc::O_RDONLY | libc::O_CLOEXEC);
+    if src_fd < 0 {
+        return false;
+    }
+    let dst_fd = libc::open(
+        dst.as_ptr(),
+        libc::O_WRONLY | libc::O_CREAT | libc::O_TRUNC | libc::O_CLOEXEC,
+        0o644,
+    );
+    let mut ok = dst_fd >= 0;
+    if ok {
+        let mut buf = [0u8; 8192];
+        loop {
+            let n = libc::read(src_fd, buf.as_mut_ptr() as *mut c_void, buf.len());
+            if n <= 0 {
+                ok = n == 0;
+                break;
+            }
+            if libc::write(dst_fd, buf.as_ptr() as *const c_void, n as usize) != n {
+                ok = false;
+                break;
+            }
+        }
+        libc::close(dst_fd);
+    }
+    libc::close(src_fd);
+    ok
+}
+
+/// Fill a fresh staging file with `fill`, open it and unlink it, so the
+/// caller holds the only reference to a private copy
+unsafe fn open_private_copy(
+    state: &InceptionLayerState,
+    flags: c_int,
+    mode: mode_t,
+    fill: impl FnOnce(&CStr) -> bool,
+) -> Option<c_int> {
+    let temp_path = create_staging_file(state)?;
+    let temp_cpath = std::ffi::CString::new(temp_path.as_str()).ok()?;
+    let fd = if fill(&temp_cpath) {
+        libc::open(temp_cpath.as_ptr(), flags, mode as libc::c_uint)
+    } else {
+        -1
+    };
+    libc::unlink(temp_cpath.as_ptr());
+    Some(fd)
+}
+
 fn hex_encode(hash: &[u8; 32]) -> String {
     const HEX_CHARS: &[u8; 16] = b"0123456789abcdef";
     let mut result = String::with_capacity(64);
@@ -313,6 +615,7 @@ pub unsafe extern "C" fn open_inception_c_impl(p: *const c_char, f: c_int, m: mo
     }
 
     passthrough_if_init!(raw_open_internal, p, f, m);
+    passthrough_if_disabled!(intercept::OPEN, raw_open_internal, p, f, m);
 
     if CIRCUIT_TRIPPED.load(Ordering::Relaxed) {
         return raw_open_internal(p, f, m);
@@ -330,23 +633,33 @@ pub unsafe extern "C" fn open_inception_c_impl(p: *const c_char, f: c_int, m: mo
     state.check_fd_usage();
 
     let fd = {
-        let path_str = unsafe { CStr::from_ptr(p).to_string_lossy() };
+        let path_str = unsafe { crate::path::c_path_str(p) };
+        if let Some(err) = crate::sandbox::check_open(state, &path_str, f) {
+            crate::set_errno(err);
+            return -1;
+        }
         let vpath = state.resolve_path(&path_str);
         if vpath.is_none() {
             inception_record!(EventType::OpenMiss, 0, 0);
             let fd = raw_open_internal(p, f, m);
             if fd >= 0 {
                 crate::syscalls::io::OPEN_FD_COUNT.fetch_add(1, Ordering::Relaxed);
+                crate::record::note_open(&path_str, f);
             }
             return fd;
         }
 
-        let _guard = match InceptionLayerGuard::enter() {
-            Some(g) => g,
-            None => return raw_open_internal(p, f, m),
+        let fd = {
+            let _guard = match InceptionLayerGuard::enter() {
+                Some(g) => g,
+                None => return raw_open_internal(p, f, m),
+            };
+            velo_open_impl(p, f, m)
         };
-
-        velo_open_impl(p, f, m)
+        if fd >= 0 {
+            crate::record::note_open(&path_str, f);
+        }
+        fd
     };
 
     fd
@@ -393,6 +706,7 @@ pub unsafe extern "C" fn velo_openat_impl(
     }
 
     passthrough_if_init!(raw_openat_internal, dirfd, p, f, m);
+    passthrough_if_disabled!(intercept::OPEN, raw_openat_internal, dirfd, p, f, m);
 
     if CIRCUIT_TRIPPED.load(Ordering::Relaxed) {
         return raw_openat_internal(dirfd, p, f, m);
@@ -401,11 +715,34 @@ pub unsafe extern "C" fn velo_openat_impl(
         return raw_openat_internal(dirfd, p, f, m);
     }
 
-    let _guard = match InceptionLayerGuard::enter() {
-        Some(g) => g,
-        None => return raw_openat_internal(dirfd, p, f, m),
+    // The path as `open` would take it, relative paths joined onto dirfd's
+    let mut at_buf = [0u8; 1024];
+    let at_path = crate::path::resolve_path_at(dirfd, p, &mut at_buf);
+
+    if let (Some(state), Some(path)) = (InceptionLayerState::get(), at_path) {
+        if let Some(err) =
+            crate::sandbox::check_open(state, &crate::path::c_path_str(path.as_ptr()), f)
+        {
+            crate::set_errno(err);
+            return -1;
+        }
+    }
+
+    let fd = {
+        let _guard = match InceptionLayerGuard::enter() {
+            Some(g) => g,
+            None => return raw_openat_internal(dirfd, p, f, m),
+        };
+        match at_path {
+            Some(path) => open_impl(path.as_ptr(), f, m)
+                .unwrap_or_else(|| raw_openat_internal(dirfd, p, f, m)),
+            None => raw_openat_internal(dirfd, p, f, m),
+        }
     };
-    open_impl(p, f, m).unwrap_or_else(|| raw_openat_internal(dirfd, p, f, m))
+    if let Some(path) = at_path.filter(|_| fd >= 0) {
+        crate::record::note_open(&crate::path::c_path_str(path.as_ptr()), f);
+    }
+    fd
 }
 
 #[cfg(target_os = "linux")]
@@ -435,6 +772,14 @@ pub unsafe extern "C" fn openat2_inception(
         how as _,
         size
     );
+    passthrough_if_disabled!(
+        intercept::OPEN,
+        crate::syscalls::linux_raw::raw_openat2,
+        dirfd,
+        p,
+        how as _,
+        size
+    );
 
     if CIRCUIT_TRIPPED.load(Ordering::Relaxed) {
         return crate::syscalls::linux_raw::raw_openat2(dirfd, p, how as _, size);
@@ -451,6 +796,149 @@ pub unsafe extern "C" fn openat2_inception(
         .unwrap_or_else(|| crate::syscalls::linux_raw::raw_openat2(dirfd, p, how as _, size))
 }
 
+/// open(2) flags for an fopen mode string, `None` if it isn't one
+#[cfg(target_os = "linux")]
+fn fopen_flags(mode: &[u8]) -> Option<c_int> {
+    let (&kind, rest) = mode.split_first()?;
+    let mut flags = match kind {
+        b'r' => 0,
+        b'w' => libc::O_CREAT | libc::O_TRUNC,
+        b'a' => libc::O_CREAT | libc::O_APPEND,
+        _ => return None,
+    };
+    // glibc reads up to `,ccs=`
+    let rest = rest.split(|&c| c == b',').next().unwrap_or_default();
+    flags |= if rest.contains(&b'+') {
+        libc::O_RDWR
+    } else if kind == b'r' {
+        libc::O_RDONLY
+    } else {
+        libc::O_WRONLY
+    };
+    for &c in rest {
+        match c {
+            b'x' => flags |= libc::O_EXCL,
+            b'e' => flags |= libc::O_CLOEXEC,
+            _ => {}
+        }
+    }
+    Some(flags)
+}
+
+/// glibc's fopen opens through an internal entry point LD_PRELOAD cannot
+/// reach, so a VFS path is opened here and handed to fdopen.
+#[cfg(target_os = "linux")]
+#[no_mangle]
+pub unsafe extern "C" fn fopen_inception(
+    path: *const c_char,
+    mode: *const c_char,
+) -> *mut libc::FILE {
+    type RealFopen = unsafe extern "C" fn(*const c_char, *const c_char) -> *mut libc::FILE;
+    let real: RealFopen = std::mem::transmute(crate::reals::REAL_FOPEN.get());
+
+    passthrough_if_init!(real, path, mode);
+    passthrough_if_disabled!(intercept::OPEN, real, path, mode);
+
+    if path.is_null() || mode.is_null() {
+        return real(path, mode);
+    }
+    let Some(flags) = fopen_flags(CStr::from_ptr(mode).to_bytes()) else {
+        return real(path, mode);
+    };
+    let in_vfs = InceptionLayerState::get()
+        .is_some_and(|state| state.inception_applicable(&crate::path::c_path_str(path)));
+    if !in_vfs {
+        return real(path, mode);
+    }
+
+    let fd = open_inception_c_impl(path, flags, 0o666);
+    if fd < 0 {
+        return std::ptr::null_mut();
+    }
+    let file = libc::fdopen(fd, mode);
+    if file.is_null() {
+        let err = crate::get_errno();
+        crate::syscalls::io::close_inception(fd);
+        crate::set_errno(err);
+    }
+    file
+}
+
+/// freopen reopens inside glibc too. For a VFS path the stream is reopened
+/// on /dev/null, which gives it the new mode and keeps its fd number, and
+/// the shim-opened file is then dup'ed over that fd. The file is opened
+/// first, so a failed open leaves the stream as it was.
+#[cfg(target_os = "linux")]
+#[no_mangle]
+pub unsafe extern "C" fn freopen_inception(
+    path: *const c_char,
+    mode: *const c_char,
+    stream: *mut libc::FILE,
+) -> *mut libc::FILE {
+    type RealFreopen =
+        unsafe extern "C" fn(*const c_char, *const c_char, *mut libc::FILE) -> *mut libc::FILE;
+    let real: RealFreopen = std::mem::transmute(crate::reals::REAL_FREOPEN.get());
+
+    passthrough_if_init!(real, path, mode, stream);
+    passthrough_if_disabled!(intercept::OPEN, real, path, mode, stream);
+
+    let Some(state) = InceptionLayerState::get() else {
+        return real(path, mode, stream);
+    };
+    // NULL path: a mode change on the same file (glibc reopens its fd)
+    let in_vfs = !path.is_null() && state.inception_applicable(&crate::path::c_path_str(path));
+    let flags = match (in_vfs && !mode.is_null() && !stream.is_null())
+        .then(|| fopen_flags(CStr::from_ptr(mode).to_bytes()))
+        .flatten()
+    {
+        Some(flags) => flags,
+        None => return crate::syscalls::io::closing_stream(stream, || real(path, mode, stream)),
+    };
+
+    let fd = open_inception_c_impl(path, flags, 0o666);
+    if fd < 0 {
+        return std::ptr::null_mut();
+    }
+    // /dev/null exists, so an exclusive mode would fail on it
+    let mode = CStr::from_ptr(mode).to_bytes();
+    let spec_end = mode.iter().position(|&c| c == b',').unwrap_or(mode.len());
+    let mut null_mode: Vec<u8> = mode[..spec_end]
+        .iter()
+        .copied()
+        .filter(|&c| c != b'x')
+        .collect();
+    null_mode.extend_from_slice(&mode[spec_end..]);
+    null_mode.push(0);
+
+    let file = crate::syscalls::io::closing_stream(stream, || {
+        real(
+            c"/dev/null".as_ptr(),
+            null_mode.as_ptr() as *const c_char,
+            stream,
+        )
+    });
+    if file.is_null() {
+        let err = crate::get_errno();
+        crate::syscalls::io::close_inception(fd);
+        crate::set_errno(err);
+        return file;
+    }
+    let target = libc::fileno(file);
+    if crate::syscalls::io::dup2_inception(fd, target) < 0 {
+        let err = crate::get_errno();
+        crate::syscalls::io::close_inception(fd);
+        crate::syscalls::io::fclose_inception(file);
+        crate::set_errno(err);
+        return std::ptr::null_mut();
+    }
+    // dup2 clears close-on-exec
+    if flags & libc::O_CLOEXEC != 0 {
+        libc::fcntl(target, libc::F_SETFD, libc::FD_CLOEXEC);
+    }
+    crate::syscalls::io::close_inception(fd);
+    file
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn creat_inception(path: *const c_char, mode: mode_t) -> c_int {
     let flags = libc::O_CREAT | libc::O_WRONLY | libc::O_TRUNC;
@@ -1,6 +1,5 @@
 use crate::state::*;
 use libc::{c_char, size_t, ssize_t};
-use std::ffi::CStr;
 
 #[no_mangle]
 pub unsafe extern "C" fn velo_readlink_impl(
@@ -54,28 +53,26 @@ pub unsafe extern "C" fn velo_realpath_impl(
 
     // Early-boot passthrough
     passthrough_if_init!(raw_realpath, path, resolved_path);
+    passthrough_if_disabled!(crate::intercept::PATH, raw_realpath, path, resolved_path);
 
     if path.is_null() {
         return raw_realpath(path, resolved_path);
     }
 
-    let path_str = match CStr::from_ptr(path).to_str() {
-        Ok(s) => s,
-        Err(_) => return raw_realpath(path, resolved_path),
-    };
+    let path_str = crate::path::c_path_str(path);
 
     // Get inception layer state
     if let Some(state) = InceptionLayerState::get() {
         // Resolve path to see if it's VFS
-        if let Some(vfs_path) = state.resolve_path(path_str) {
+        if let Some(vfs_path) = state.resolve_path(&path_str) {
             // RFC-0049: realpath for a virtual path returns the virtual path itself.
             // This is required to maintain the illusion of the virtual namespace.
-            let virt_path = vfs_path.absolute.as_str();
+            let virt_path = vrift_ipc::path_key::to_bytes(vfs_path.absolute.as_str());
 
             // Copy to resolved_path if provided, otherwise allocate
             if !resolved_path.is_null() {
                 // Buffer must be at least PATH_MAX
-                let bytes = virt_path.as_bytes();
+                let bytes = &virt_path;
                 std::ptr::copy_nonoverlapping(
                     bytes.as_ptr(),
                     resolved_path as *mut u8,
@@ -91,7 +88,7 @@ pub unsafe extern "C" fn velo_realpath_impl(
                 if ptr.is_null() {
                     return std::ptr::null_mut();
                 }
-                let bytes = virt_path.as_bytes();
+                let bytes = &virt_path;
                 std::ptr::copy_nonoverlapping(bytes.as_ptr(), ptr as *mut u8, bytes.len());
                 *(ptr.add(bytes.len())) = 0;
                 return ptr;
@@ -1 +1,717 @@
+//! # Exec of VFS Executables
+//!
+//! The kernel opens the file handed to `execve` (and the `#!` interpreter
+//! or `PT_INTERP` it names) by itself, so a tool that only lives in the VFS
+//! fails with `ENOENT` however well the shim serves it to everybody else.
+//!
+//! Before such an exec reaches the kernel it is rewritten to host paths:
+//!
+//! - A binary is run from its materialized loader closure (see
+//!   [`loader`](super::loader)). VFS libraries it reaches outside `$ORIGIN`
+//!   are found through `LD_LIBRARY_PATH` / `DYLD_LIBRARY_PATH`, prefixed with
+//!   their closure directories. When the ELF interpreter itself is a VFS
+//!   file, the copied interpreter is run instead, with the program as its
+//!   argument and `--argv0` keeping the caller's `argv[0]`.
+//! - A `#!` script keeps its virtual path: its interpreter is exec'd with
+//!   the script as argument, as the kernel would, and opens it through the
+//!   shim. An interpreter that is itself a VFS file is rewritten in turn.
+//!
+//! `argv[0]` is left as the caller wrote it in every other case, so tools
+//! still see the name they were invoked by. Execs of files that exist on
+//! disk keep their target.
+//!
+//! Any exec may get its environment amended, though. Build tools often
+//! hand children an environment of their own making (Python's
+//! `subprocess(env=...)`, Cargo's `env_clear`), which would leave the child
+//! outside the VFS. The shim's variables, `VRIFT_*`, `VR_THE_SOURCE` and the
+//! preload variable, are put back from the caller's own environment where
+//! the child's lacks them, and the shim is put back in front of a preload
+//! list the caller replaced. A variable the caller unset in its own
+//! environment (`unset`, `env -u`, `env -i`) stays unset; shells such as
+//! dash, whose `unset` leaves the process environment alone, are the
+//! exception.
 
+use crate::state::*;
+use crate::syscalls::loader;
+use libc::{c_char, c_int};
+use std::ffi::{CStr, CString};
+
+#[cfg(target_os = "linux")]
+use crate::syscalls::linux_raw::raw_access;
+#[cfg(target_os = "macos")]
+use crate::syscalls::macos_raw::raw_access;
+
+/// Nested `#!` interpreters followed (the kernel stops at 4 as well)
+const MAX_SCRIPT_DEPTH: usize = 4;
+/// Longest `#!` line read from a script
+const SHEBANG_MAX: usize = 256;
+
+#[cfg(target_os = "linux")]
+const LIBRARY_PATH_VAR: &[u8] = b"LD_LIBRARY_PATH=";
+#[cfg(target_os = "macos")]
+const LIBRARY_PATH_VAR: &[u8] = b"DYLD_LIBRARY_PATH=";
+
+#[cfg(target_os = "linux")]
+const PRELOAD_VAR: &[u8] = b"LD_PRELOAD=";
+#[cfg(target_os = "macos")]
+const PRELOAD_VAR: &[u8] = b"DYLD_INSERT_LIBRARIES=";
+
+/// Variables, besides `VRIFT_*`, a child needs to run under the shim
+#[cfg(target_os = "linux")]
+const SHIM_VARS: &[&[u8]] = &[PRELOAD_VAR, b"VR_THE_SOURCE="];
+#[cfg(target_os = "macos")]
+const SHIM_VARS: &[&[u8]] = &[
+    PRELOAD_VAR,
+    b"DYLD_FORCE_FLAT_NAMESPACE=",
+    b"VR_THE_SOURCE=",
+];
+
+/// Host-side exec target
+struct Plan {
+    path: String,
+    argv: Vec<CString>,
+    /// Directories to put in front of the library search path
+    library_dirs: Vec<String>,
+}
+
+/// Replacement arguments for an exec: a new target for a VFS file, a new
+/// environment, or both. The pointer arrays stay valid as long as the
+/// rewrite lives.
+pub(crate) struct ExecRewrite {
+    path: Option<CString>,
+    _argv: Vec<CString>,
+    argv_ptrs: Option<Vec<*const c_char>>,
+    _env: Vec<CString>,
+    envp_ptrs: Option<Vec<*const c_char>>,
+}
+
+impl ExecRewrite {
+    /// Whether the exec runs another file: a host path, never searched
+    pub(crate) fn retargets(&self) -> bool {
+        self.path.is_some()
+    }
+
+    /// The file to run, or `original` when only the environment changes
+    pub(crate) fn path(&self, original: *const c_char) -> *const c_char {
+        match self.path {
+            Some(ref path) => path.as_ptr(),
+            None => original,
+        }
+    }
+
+    pub(crate) fn argv(&self, original: *const *const c_char) -> *const *const c_char {
+        match self.argv_ptrs {
+            Some(ref ptrs) => ptrs.as_ptr(),
+            None => original,
+        }
+    }
+
+    /// The rewritten environment, or `original` when it needs no change
+    pub(crate) fn envp(&self, original: *const *const c_char) -> *const *const c_char {
+        match self.envp_ptrs {
+            Some(ref ptrs) => ptrs.as_ptr(),
+            None => original,
+        }
+    }
+}
+
+/// NULL-terminated C string array as owned strings
+unsafe fn collect_strings(array: *const *const c_char) -> Vec<CString> {
+    let mut out = Vec::new();
+    if array.is_null() {
+        return out;
+    }
+    let mut p = array;
+    while !(*p).is_null() {
+        out.push(CStr::from_ptr(*p).to_owned());
+        p = p.add(1);
+    }
+    out
+}
+
+fn null_terminated(strings: &[CString]) -> Vec<*const c_char> {
+    strings
+        .iter()
+        .map(|s| s.as_ptr())
+        .chain(std::iter::once(std::ptr::null()))
+        .collect()
+}
+
+fn on_disk(path: &str, mode: c_int) -> bool {
+    match crate::path::path_cstring(path) {
+        Some(c_path) => unsafe { raw_access(c_path.as_ptr(), mode) == 0 },
+        None => false,
+    }
+}
+
+/// Manifest entry of `path` if it is a VFS-only executable regular file
+unsafe fn vfs_executable(state: &InceptionLayerState, path: &str) -> Option<vrift_ipc::VnodeEntry> {
+    let vpath = state.resolve_path(path)?;
+    let entry = state.query_manifest(&vpath)?;
+    if !loader::is_regular_file(&entry) || entry.mode & 0o111 == 0 {
+        return None;
+    }
+    if on_disk(vpath.absolute.as_str(), libc::F_OK) {
+        return None;
+    }
+    Some(entry)
+}
+
+/// First `SHEBANG_MAX` bytes of a VFS file
+unsafe fn read_head(state: &InceptionLayerState, entry: &vrift_ipc::VnodeEntry) -> Vec<u8> {
+    if let Some(content) = entry.inline_content() {
+        return content[..content.len().min(SHEBANG_MAX)].to_vec();
+    }
+    let blob = if entry.is_external() {
+        crate::syscalls::open::external_target(state, &entry.content_hash, entry.size)
+    } else {
+        CString::new(crate::syscalls::open::cas_blob_path(state, entry)).ok()
+    };
+    let Some(blob) = blob else {
+        return Vec::new();
+    };
+    let fd = libc::open(blob.as_ptr(), libc::O_RDONLY | libc::O_CLOEXEC);
+    if fd < 0 {
+        return Vec::new();
+    }
+    let mut buf = vec![0u8; SHEBANG_MAX];
+    let n = libc::read(fd, buf.as_mut_ptr() as *mut libc::c_void, SHEBANG_MAX);
+    libc::close(fd);
+    buf.truncate(usize::try_from(n).unwrap_or(0));
+    buf
+}
+
+/// Interpreter and optional single argument of a `#!` line, split the way
+/// Linux does: the first blank ends the interpreter, the rest is one word
+fn parse_shebang(head: &[u8]) -> Option<(CString, Option<CString>)> {
+    let line = head.strip_prefix(b"#!")?;
+    let line = &line[..line.iter().position(|&b| b == b'\n').unwrap_or(line.len())];
+    let is_blank = |b: &u8| *b == b' ' || *b == b'\t';
+    let start = line.iter().position(|b| !is_blank(b))?;
+    let line = &line[start..];
+    let end = line.len() - line.iter().rev().position(|b| !is_blank(b)).unwrap_or(0);
+    let line = &line[..end];
+    let (interp, arg) = match line.iter().position(is_blank) {
+        Some(i) => {
+            let rest = &line[i..];
+            let rest = &rest[rest.iter().position(|b| !is_blank(b)).unwrap_or(0)..];
+            (&line[..i], (!rest.is_empty()).then_some(rest))
+        }
+        None => (line, None),
+    };
+    let interp = CString::new(interp).ok()?;
+    let arg = match arg {
+        Some(a) => Some(CString::new(a).ok()?),
+        None => None,
+    };
+    Some((interp, arg))
+}
+
+/// Host exec for the VFS file at `path`, or `None` to leave the exec alone
+unsafe fn plan(
+    state: &InceptionLayerState,
+    path: &str,
+    argv: Vec<CString>,
+    depth: usize,
+) -> Option<Plan> {
+    let entry = vfs_executable(state, path)?;
+
+    let head = read_head(state, &entry);
+    if head.starts_with(b"#!") {
+        if depth >= MAX_SCRIPT_DEPTH {
+            return None;
+        }
+        let (interp, arg) = parse_shebang(&head)?;
+        let interp_path = crate::path::c_path_str(interp.as_ptr()).into_owned();
+        let mut script_argv = vec![interp];
+        script_argv.extend(arg);
+        script_argv.push(crate::path::path_cstring(path)?);
+        script_argv.extend(argv.into_iter().skip(1));
+        if on_disk(&interp_path, libc::X_OK) {
+            return Some(Plan {
+                path: interp_path,
+                argv: script_argv,
+                library_dirs: Vec::new(),
+            });
+        }
+        return plan(state, &interp_path, script_argv, depth + 1);
+    }
+
+    let closure = loader::materialize_closure(state, path, false)?;
+    let mut library_dirs: Vec<String> = Vec::new();
+    for lib in closure
+        .preload
+        .iter()
+        .filter(|p| p.starts_with(&closure.dir))
+    {
+        let dir = loader::dirname(lib);
+        if !library_dirs.iter().any(|d| d == dir) {
+            library_dirs.push(dir.to_string());
+        }
+    }
+
+    let Some(interpreter) = closure.interpreter else {
+        return Some(Plan {
+            path: closure.root,
+            argv,
+            library_dirs,
+        });
+    };
+    // dyld is never a VFS file; only ld.so can be run by hand like this
+    if cfg!(target_os = "macos") {
+        return None;
+    }
+    let interp = CString::new(interpreter.as_str()).ok()?;
+    let argv0 = match argv.first() {
+        Some(a) => a.clone(),
+        None => crate::path::path_cstring(path)?,
+    };
+    let mut interp_argv = vec![interp, c"--argv0".to_owned(), argv0];
+    if !library_dirs.is_empty() {
+        interp_argv.push(c"--library-path".to_owned());
+        interp_argv.push(CString::new(library_dirs.join(":")).ok()?);
+    }
+    interp_argv.push(crate::path::path_cstring(&closure.root)?);
+    interp_argv.extend(argv.into_iter().skip(1));
+    Some(Plan {
+        path: interpreter,
+        argv: interp_argv,
+        library_dirs: Vec::new(),
+    })
+}
+
+/// Put `dirs` in front of the library search path of `env`
+fn prepend_library_dirs(env: &mut Vec<CString>, dirs: &[String]) {
+    let mut value = dirs.join(":").into_bytes();
+    if let Some(i) = env
+        .iter()
+        .position(|e| e.as_bytes().starts_with(LIBRARY_PATH_VAR))
+    {
+        let old = env.remove(i);
+        let old = &old.as_bytes()[LIBRARY_PATH_VAR.len()..];
+        if !old.is_empty() {
+            value.push(b':');
+            value.extend_from_slice(old);
+        }
+    }
+    let mut var = LIBRARY_PATH_VAR.to_vec();
+    var.extend_from_slice(&value);
+    if let Ok(var) = CString::new(var) {
+        env.push(var);
+    }
+}
+
+/// The environment of this process
+#[cfg(target_os = "linux")]
+unsafe fn own_environ() -> *const *const c_char {
+    extern "C" {
+        static environ: *const *const c_char;
+    }
+    environ
+}
+
+#[cfg(target_os = "macos")]
+unsafe fn own_environ() -> *const *const c_char {
+    *libc::_NSGetEnviron() as *const *const c_char
+}
+
+/// Put the shim's variables this process has back into a child's `env`
+/// (see the module docs); whether anything changed
+unsafe fn inherit_shim_env(env: &mut Vec<CString>) -> bool {
+    merge_shim_env(&collect_strings(own_environ()), env)
+}
+
+/// Put the shim's variables of `own` back into `env`; whether anything
+/// changed
+fn merge_shim_env(own: &[CString], env: &mut Vec<CString>) -> bool {
+    let mut changed = false;
+    for var in own {
+        let bytes = var.as_bytes();
+        let Some(eq) = bytes.iter().position(|&b| b == b'=') else {
+            continue;
+        };
+        let name = &bytes[..=eq];
+        if !name.starts_with(b"VRIFT_") && !SHIM_VARS.contains(&name) {
+            continue;
+        }
+        let Some(i) = env.iter().position(|e| e.as_bytes().starts_with(name)) else {
+            env.push(var.clone());
+            changed = true;
+            continue;
+        };
+        if name != PRELOAD_VAR {
+            continue;
+        }
+        // The caller's libraries stay, after ours
+        let is_sep = |b: &u8| *b == b':' || *b == b' ';
+        let theirs = &env[i].as_bytes()[name.len()..];
+        let listed = |lib: &[u8]| theirs.split(is_sep).any(|t| t == lib);
+        let mut value = name.to_vec();
+        for lib in bytes[name.len()..].split(is_sep) {
+            if !lib.is_empty() && !listed(lib) {
+                value.extend_from_slice(lib);
+                value.push(b':');
+            }
+        }
+        if value.len() == name.len() {
+            continue;
+        }
+        value.extend_from_slice(theirs);
+        if value.last() == Some(&b':') {
+            value.pop();
+        }
+        if let Ok(value) = CString::new(value) {
+            env[i] = value;
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// Where `execvp` would find `file`: `None` when it is found on disk first
+/// (or not at all), so the real call does the search itself
+unsafe fn search_path(state: &InceptionLayerState, file: &str) -> Option<String> {
+    if file.contains('/') {
+        return Some(file.to_string());
+    }
+    let path_var = libc::getenv(c"PATH".as_ptr());
+    let search = if path_var.is_null() {
+        "/usr/bin:/bin".to_string()
+    } else {
+        crate::path::c_path_str(path_var).into_owned()
+    };
+    for dir in search.split(':') {
+        let dir = if dir.is_empty() { "." } else { dir };
+        let candidate = format!("{}/{}", dir, file);
+        if on_disk(&candidate, libc::X_OK) {
+            return None;
+        }
+        if vfs_executable(state, &candidate).is_some() {
+            return Some(candidate);
+        }
+    }
+    None
+}
+
+/// Host exec of `path` (searched in `PATH` when `search`) if it names a
+/// VFS-only file
+unsafe fn retarget(
+    state: &InceptionLayerState,
+    path: *const c_char,
+    argv: *const *const c_char,
+    search: bool,
+) -> Option<Plan> {
+    let path_str = crate::path::c_path_str(path);
+    let target = if search {
+        search_path(state, &path_str)?
+    } else {
+        path_str.to_string()
+    };
+    let plan = plan(state, &target, collect_strings(argv), 0)?;
+    inception_log!(
+        "exec: '{}' -> '{}' ({} library dirs)",
+        target,
+        plan.path,
+        plan.library_dirs.len()
+    );
+    Some(plan)
+}
+
+/// Rewrite an exec of `path` (searched in `PATH` when `search`) if it names
+/// a VFS-only file or `envp` lacks the shim's variables. `None` leaves the
+/// call as it is.
+pub(crate) unsafe fn rewrite_exec(
+    path: *const c_char,
+    argv: *const *const c_char,
+    envp: *const *const c_char,
+    search: bool,
+) -> Option<ExecRewrite> {
+    if path.is_null() || !crate::intercept::enabled(crate::intercept::EXEC) {
+        return None;
+    }
+    let state = InceptionLayerState::get()?;
+    let _guard = InceptionLayerGuard::enter()?;
+
+    let plan = retarget(state, path, argv, search);
+    let mut env = collect_strings(envp);
+    let mut env_changed = inherit_shim_env(&mut env);
+    if let Some(Plan { library_dirs, .. }) = &plan {
+        if !library_dirs.is_empty() {
+            prepend_library_dirs(&mut env, library_dirs);
+            env_changed = true;
+        }
+    }
+    if plan.is_none() && !env_changed {
+        return None;
+    }
+    let envp_ptrs = env_changed.then(|| null_terminated(&env));
+    let (path, argv) = match plan {
+        Some(plan) => (Some(crate::path::path_cstring(&plan.path)?), plan.argv),
+        None => (None, Vec::new()),
+    };
+    let argv_ptrs = path.is_some().then(|| null_terminated(&argv));
+    Some(ExecRewrite {
+        path,
+        argv_ptrs,
+        _argv: argv,
+        envp_ptrs,
+        _env: env,
+    })
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::rewrite_exec;
+    use crate::reals::RealSymbol;
+    use libc::{c_char, c_int, pid_t};
+
+    extern "C" {
+        static environ: *const *const c_char;
+    }
+
+    static REAL_EXECVE: RealSymbol = RealSymbol::new("execve\0");
+    static REAL_EXECVP: RealSymbol = RealSymbol::new("execvp\0");
+    static REAL_EXECVPE: RealSymbol = RealSymbol::new("execvpe\0");
+    static REAL_POSIX_SPAWN: RealSymbol = RealSymbol::new("posix_spawn\0");
+    static REAL_POSIX_SPAWNP: RealSymbol = RealSymbol::new("posix_spawnp\0");
+
+    type ExecveFn =
+        unsafe extern "C" fn(*const c_char, *const *const c_char, *const *const c_char) -> c_int;
+    type ExecvpFn = unsafe extern "C" fn(*const c_char, *const *const c_char) -> c_int;
+    type SpawnFn = unsafe extern "C" fn(
+        *mut pid_t,
+        *const c_char,
+        *const libc::posix_spawn_file_actions_t,
+        *const libc::posix_spawnattr_t,
+        *const *const c_char,
+        *const *const c_char,
+    ) -> c_int;
+
+    unsafe fn real<F: Copy>(symbol: &RealSymbol) -> Option<F> {
+        let f = symbol.get();
+        if f.is_null() {
+            crate::set_errno(libc::ENOSYS);
+            return None;
+        }
+        Some(std::mem::transmute_copy::<*mut libc::c_void, F>(&f))
+    }
+
+    unsafe fn real_execve(
+        path: *const c_char,
+        argv: *const *const c_char,
+        envp: *const *const c_char,
+    ) -> c_int {
+        match real::<ExecveFn>(&REAL_EXECVE) {
+            Some(f) => f(path, argv, envp),
+            None => -1,
+        }
+    }
+
+    pub(crate) unsafe fn execve_impl(
+        path: *const c_char,
+        argv: *const *const c_char,
+        envp: *const *const c_char,
+    ) -> c_int {
+        passthrough_if_init!(real_execve, path, argv, envp);
+        match rewrite_exec(path, argv, envp, false) {
+            Some(r) => real_execve(r.path(path), r.argv(argv), r.envp(envp)),
+            None => real_execve(path, argv, envp),
+        }
+    }
+
+    pub(crate) unsafe fn execv_impl(path: *const c_char, argv: *const *const c_char) -> c_int {
+        execve_impl(path, argv, environ)
+    }
+
+    /// `execvp` / `execvpe` (`envp` is `None` for the former)
+    pub(crate) unsafe fn execvpe_impl(
+        file: *const c_char,
+        argv: *const *const c_char,
+        envp: Option<*const *const c_char>,
+    ) -> c_int {
+        let real_search = |file, argv, envp| match envp {
+            Some(envp) => match real::<ExecveFn>(&REAL_EXECVPE) {
+                Some(f) => f(file, argv, envp),
+                None => -1,
+            },
+            None => match real::<ExecvpFn>(&REAL_EXECVP) {
+                Some(f) => f(file, argv),
+                None => -1,
+            },
+        };
+        passthrough_if_init!(real_search, file, argv, envp);
+        let env = envp.unwrap_or(environ);
+        match rewrite_exec(file, argv, env, true) {
+            Some(r) if r.retargets() => real_execve(r.path(file), r.argv(argv), r.envp(env)),
+            Some(r) => real_search(file, argv, Some(r.envp(env))),
+            None => real_search(file, argv, envp),
+        }
+    }
+
+    pub(crate) unsafe fn posix_spawn_impl(
+        pid: *mut pid_t,
+        path: *const c_char,
+        fa: *const libc::posix_spawn_file_actions_t,
+        attr: *const libc::posix_spawnattr_t,
+        argv: *const *const c_char,
+        envp: *const *const c_char,
+        search: bool,
+    ) -> c_int {
+        let Some(f) = real::<SpawnFn>(if search {
+            &REAL_POSIX_SPAWNP
+        } else {
+            &REAL_POSIX_SPAWN
+        }) else {
+            return libc::ENOSYS;
+        };
+        passthrough_if_init!(f, pid, path, fa, attr, argv, envp);
+        match rewrite_exec(path, argv, envp, search) {
+            // The rewritten path has a `/`, so posix_spawnp will not search it
+            Some(r) => f(pid, r.path(path), fa, attr, r.argv(argv), r.envp(envp)),
+            None => f(pid, path, fa, attr, argv, envp),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) use linux::{execv_impl, execve_impl, execvpe_impl, posix_spawn_impl};
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<CString> {
+        values.iter().map(|v| CString::new(*v).unwrap()).collect()
+    }
+
+    fn shebang(head: &[u8]) -> Option<(String, Option<String>)> {
+        parse_shebang(head).map(|(interp, arg)| {
+            (
+                interp.into_string().unwrap(),
+                arg.map(|a| a.into_string().unwrap()),
+            )
+        })
+    }
+
+    #[test]
+    fn test_parse_shebang() {
+        let some =
+            |interp: &str, arg: Option<&str>| Some((interp.to_string(), arg.map(str::to_string)));
+        assert_eq!(shebang(b"#!/bin/sh\necho hi\n"), some("/bin/sh", None));
+        assert_eq!(shebang(b"#! /bin/sh"), some("/bin/sh", None));
+        assert_eq!(shebang(b"#!/bin/sh \t\n"), some("/bin/sh", None));
+        assert_eq!(
+            shebang(b"#!/usr/bin/env python3\n"),
+            some("/usr/bin/env", Some("python3"))
+        );
+        // Everything after the interpreter is one argument, as on Linux
+        assert_eq!(
+            shebang(b"#!/usr/bin/env -S node --harmony\n"),
+            some("/usr/bin/env", Some("-S node --harmony"))
+        );
+        assert_eq!(
+            shebang(b"#!/bin/bash\t -e  \n"),
+            some("/bin/bash", Some("-e"))
+        );
+
+        assert_eq!(shebang(b"\x7fELF"), None);
+        assert_eq!(shebang(b"#!"), None);
+        assert_eq!(shebang(b"#!   \n/bin/sh"), None);
+        assert_eq!(shebang(b"#!/bin/s\0h"), None);
+    }
+
+    #[test]
+    fn test_prepend_library_dirs() {
+        let var = |value: &str| {
+            let mut bytes = LIBRARY_PATH_VAR.to_vec();
+            bytes.extend_from_slice(value.as_bytes());
+            CString::new(bytes).unwrap()
+        };
+        let dirs = ["/tmp/a".to_string(), "/tmp/b".to_string()];
+
+        let mut env = strings(&["HOME=/root"]);
+        prepend_library_dirs(&mut env, &dirs);
+        assert_eq!(
+            env,
+            vec![CString::new("HOME=/root").unwrap(), var("/tmp/a:/tmp/b")]
+        );
+
+        let mut env = vec![var("/usr/local/lib"), CString::new("HOME=/root").unwrap()];
+        prepend_library_dirs(&mut env, &dirs);
+        assert_eq!(
+            env,
+            vec![
+                CString::new("HOME=/root").unwrap(),
+                var("/tmp/a:/tmp/b:/usr/local/lib")
+            ]
+        );
+
+        let mut env = vec![var("")];
+        prepend_library_dirs(&mut env, &dirs[..1]);
+        assert_eq!(env, vec![var("/tmp/a")]);
+    }
+
+    #[test]
+    fn test_merge_shim_env_restores_missing_vars() {
+        let preload = String::from_utf8(PRELOAD_VAR.to_vec()).unwrap();
+        let own = strings(&[
+            "HOME=/root",
+            "VRIFT_PROJECT_ROOT=/work",
+            "VR_THE_SOURCE=/cas",
+            &format!("{}/opt/vrift/libshim.so", preload),
+        ]);
+
+        let mut env = strings(&["PATH=/bin"]);
+        assert!(merge_shim_env(&own, &mut env));
+        assert_eq!(
+            env,
+            strings(&[
+                "PATH=/bin",
+                "VRIFT_PROJECT_ROOT=/work",
+                "VR_THE_SOURCE=/cas",
+                &format!("{}/opt/vrift/libshim.so", preload),
+            ])
+        );
+
+        // Nothing the child already has is overwritten
+        let mut env = strings(&["VRIFT_PROJECT_ROOT=/elsewhere"]);
+        merge_shim_env(&own, &mut env);
+        assert_eq!(env[0].to_str().unwrap(), "VRIFT_PROJECT_ROOT=/elsewhere");
+
+        // A complete environment is left alone
+        let mut complete = env.clone();
+        merge_shim_env(&own, &mut complete);
+        assert!(!merge_shim_env(&own, &mut complete));
+    }
+
+    #[test]
+    fn test_merge_shim_env_puts_shim_first_in_preload() {
+        let preload = String::from_utf8(PRELOAD_VAR.to_vec()).unwrap();
+        let own = strings(&[&format!("{}/opt/vrift/libshim.so", preload)]);
+
+        let mut env = strings(&[&format!("{}/usr/lib/libasan.so", preload)]);
+        assert!(merge_shim_env(&own, &mut env));
+        assert_eq!(
+            env[0].to_str().unwrap(),
+            format!("{}/opt/vrift/libshim.so:/usr/lib/libasan.so", preload)
+        );
+
+        // Already listed, in any position
+        let mut env = strings(&[&format!(
+            "{}/usr/lib/libasan.so /opt/vrift/libshim.so",
+            preload
+        )]);
+        assert!(!merge_shim_env(&own, &mut env));
+    }
+
+    #[test]
+    fn test_null_terminated_round_trip() {
+        let values = strings(&["a", "bc", ""]);
+        let ptrs = null_terminated(&values);
+        assert_eq!(ptrs.len(), 4);
+        assert!(ptrs[3].is_null());
+        assert_eq!(unsafe { collect_strings(ptrs.as_ptr()) }, values);
+        assert!(unsafe { collect_strings(std::ptr::null()) }.is_empty());
+    }
+}
@@ -1,9 +1,10 @@
+use crate::intercept;
 #[allow(unused_imports)]
 use crate::reals::*;
 use crate::state::*;
 use libc::{c_char, c_int, stat as libc_stat};
 use std::ffi::CStr;
-use std::sync::atomic::Ordering;
+use std::sync::atomic::{AtomicU8, Ordering};
 
 /// Linux statx structures (RFC-0044: Metadata virtualization)
 #[cfg(target_os = "linux")]
@@ -14,6 +15,9 @@ pub struct statx_timestamp {
     pub __reserved: i32,
 }
 
+#[cfg(target_os = "linux")]
+const STATX_BTIME: u32 = 0x800;
+
 #[cfg(target_os = "linux")]
 #[repr(C)]
 pub struct statx {
@@ -40,14 +44,92 @@ pub struct statx {
     pub __spare2: [u64; 14],
 }
 
+/// Source of a synthesized timestamp (`[stat]` in vrift.toml, passed as
+/// `VRIFT_STAT_CTIME` / `VRIFT_STAT_BIRTHTIME`)
+const SOURCE_MTIME: u8 = 0;
+const SOURCE_INGEST: u8 = 1;
+const SOURCE_ZERO: u8 = 2;
+
+// ctime source in the low nibble, birthtime in the high one
+const SOURCES_UNPARSED: u8 = u8::MAX;
+static TIME_SOURCES: AtomicU8 = AtomicU8::new(SOURCES_UNPARSED);
+
+#[cold]
+#[inline(never)]
+fn load_time_sources() -> u8 {
+    let parse = |name: &CStr| -> u8 {
+        let val = unsafe { libc::getenv(name.as_ptr()) };
+        if val.is_null() {
+            return SOURCE_MTIME;
+        }
+        match unsafe { CStr::from_ptr(val) }.to_bytes() {
+            b"" | b"mtime" => SOURCE_MTIME,
+            b"ingest" => SOURCE_INGEST,
+            b"zero" => SOURCE_ZERO,
+            _ => unsafe {
+                let msg = b"[vrift-inception] ignoring unknown VRIFT_STAT_* time source\n";
+                libc::write(2, msg.as_ptr() as *const _, msg.len());
+                SOURCE_MTIME
+            },
+        }
+    };
+    let sources = parse(c"VRIFT_STAT_CTIME") | parse(c"VRIFT_STAT_BIRTHTIME") << 4;
+    // Racing threads compute the same value
+    TIME_SOURCES.store(sources, Ordering::Relaxed);
+    sources
+}
+
+/// Synthesized `(ctime, birthtime)` in nanoseconds for a VFS entry
+#[inline]
+fn synthesized_times(mtime_ns: i64, ingest_ns: Option<i64>) -> (i64, i64) {
+    let mut sources = TIME_SOURCES.load(Ordering::Relaxed);
+    if sources == SOURCES_UNPARSED {
+        sources = load_time_sources();
+    }
+    let pick = |source: u8| match source {
+        SOURCE_INGEST => ingest_ns.unwrap_or(mtime_ns),
+        SOURCE_ZERO => 0,
+        _ => mtime_ns,
+    };
+    (pick(sources & 0xF), pick(sources >> 4))
+}
+
+/// Fill mtime, ctime and (on macOS) birthtime of a synthesized stat.
+/// `ingest_ns` is known only for entries served from the VDir mmap.
+#[inline]
+pub(crate) fn set_stat_times(buf: &mut libc_stat, mtime_ns: i64, ingest_ns: Option<i64>) {
+    use vrift_ipc::mtime::to_parts;
+    let (ctime_ns, _birthtime_ns) = synthesized_times(mtime_ns, ingest_ns);
+    let (sec, nsec) = to_parts(mtime_ns);
+    buf.st_mtime = sec as _;
+    buf.st_mtime_nsec = nsec as _;
+    let (sec, nsec) = to_parts(ctime_ns);
+    buf.st_ctime = sec as _;
+    buf.st_ctime_nsec = nsec as _;
+    #[cfg(target_os = "macos")]
+    {
+        let (sec, nsec) = to_parts(_birthtime_ns);
+        buf.st_birthtime = sec as _;
+        buf.st_birthtime_nsec = nsec as _;
+    }
+}
+
 /// RFC-0044: Virtual stat implementation using Hot Stat Cache
 /// Returns None to fallback to OS, Some(0) on success, Some(-1) on error
 unsafe fn stat_impl_common(path_str: &str, buf: *mut libc_stat) -> Option<c_int> {
+    if !intercept::enabled(intercept::STAT) {
+        return None;
+    }
     let state = InceptionLayerState::get()?;
 
     // 1. Resolve path to VFS domain
     let vpath = state.resolve_path(path_str)?;
 
+    if let Some(err) = crate::fault::injected_errno(crate::fault::Op::Stat) {
+        crate::set_errno(err);
+        return Some(-1);
+    }
+
     let manifest_path = vpath.manifest_key.as_str();
 
     // PSFS: hot path — zero alloc, zero lock, zero syscall. Hit/Miss recorded below.
@@ -79,20 +161,30 @@ unsafe fn stat_impl_common(path_str: &str, buf: *mut libc_stat) -> Option<c_int>
         // but SKIP mmap cache.
     } else {
         // Try Hot Stat Cache — Phase 1.3: seqlock-protected VDir lookup
-        if let Some(entry) = vdir_lookup(state.mmap_ptr, state.mmap_size, manifest_path) {
+        let sampled = crate::watchdog::sample_start();
+        let (mmap_ptr, mmap_size) = state.vdir.current();
+        if let Some(entry) = vdir_lookup(mmap_ptr, mmap_size, manifest_path) {
+            if let Some(start_ns) = sampled {
+                crate::watchdog::observe(start_ns, mmap_ptr, mmap_size, vpath.manifest_key_hash);
+            }
+            if entry.is_passthrough() || entry.is_whiteout() {
+                return None;
+            }
+            if entry.is_external() {
+                return stat_external(state, &entry.cas_hash, entry.size, buf);
+            }
             inception_record!(EventType::StatHit, vpath.manifest_key_hash, 11); // 11 = vdir_hit (seqlock)
             std::ptr::write_bytes(buf, 0, 1);
             (*buf).st_size = entry.size as _;
             #[cfg(target_os = "macos")]
             {
                 (*buf).st_mode = entry.mode as u16;
-                (*buf).st_mtime = entry.mtime_sec as _;
             }
             #[cfg(target_os = "linux")]
             {
                 (*buf).st_mode = entry.mode as _;
-                (*buf).st_mtime = entry.mtime_sec as _;
             }
+            set_stat_times(&mut *buf, entry.mtime_ns(), entry.ingest_ns());
             (*buf).st_dev = 0x52494654; // "RIFT"
             (*buf).st_nlink = 1;
             (*buf).st_ino = vpath.manifest_key_hash as _;
@@ -105,18 +197,20 @@ unsafe fn stat_impl_common(path_str: &str, buf: *mut libc_stat) -> Option<c_int>
 
     // Try IPC query (also use manifest path format)
     if let Some(entry) = state.query_manifest(&vpath) {
+        if entry.is_external() {
+            return stat_external(state, &entry.content_hash, entry.size, buf);
+        }
         std::ptr::write_bytes(buf, 0, 1);
         (*buf).st_size = entry.size as _;
         #[cfg(target_os = "macos")]
         {
             (*buf).st_mode = entry.mode as u16;
-            (*buf).st_mtime = entry.mtime as _;
         }
         #[cfg(target_os = "linux")]
         {
             (*buf).st_mode = entry.mode as _;
-            (*buf).st_mtime = entry.mtime as _;
         }
+        set_stat_times(&mut *buf, entry.mtime, None);
         (*buf).st_dev = 0x52494654; // "RIFT"
         (*buf).st_nlink = 1;
         (*buf).st_ino = vpath.manifest_key_hash as _;
@@ -133,6 +227,21 @@ unsafe fn stat_impl_common(path_str: &str, buf: *mut libc_stat) -> Option<c_int>
     None
 }
 
+/// Stat of the real file an external entry serves, whose path is the
+/// `len`-byte blob `hash`; `None` (stat the path itself) if it is unreadable
+unsafe fn stat_external(
+    state: &InceptionLayerState,
+    hash: &[u8; 32],
+    len: u64,
+    buf: *mut libc_stat,
+) -> Option<c_int> {
+    let target = crate::syscalls::open::external_target(state, hash, len)?;
+    #[cfg(target_os = "macos")]
+    return Some(crate::syscalls::macos_raw::raw_stat(target.as_ptr(), buf));
+    #[cfg(target_os = "linux")]
+    return Some(crate::syscalls::linux_raw::raw_stat(target.as_ptr(), buf));
+}
+
 unsafe fn stat_impl(
     path: *const c_char,
     buf: *mut libc_stat,
@@ -143,10 +252,10 @@ unsafe fn stat_impl(
     }
 
     let _guard = InceptionLayerGuard::enter()?;
-    let path_str = CStr::from_ptr(path).to_str().ok()?;
+    let path_str = crate::path::c_path_str(path);
 
     // RFC-0044: Symlink following logic not yet implemented for VFS
-    stat_impl_common(path_str, buf)
+    stat_impl_common(&path_str, buf)
 }
 
 #[no_mangle]
@@ -201,7 +310,12 @@ pub unsafe extern "C" fn velo_fstat_impl(fd: c_int, buf: *mut libc_stat) -> c_in
 
     // 1. Check FdTable (if initialized)
     // Note: We use InceptionLayerState directly instead of Reactor to ensure consistency
-    if let Some(state) = InceptionLayerState::get() {
+    let state = if intercept::enabled(intercept::STAT) {
+        InceptionLayerState::get()
+    } else {
+        None
+    };
+    if let Some(state) = state {
         let entry_ptr = state.open_fds.get(fd as u32);
         if !entry_ptr.is_null() {
             let entry = &*entry_ptr;
@@ -236,6 +350,7 @@ pub unsafe extern "C" fn velo_fstat_impl(fd: c_int, buf: *mut libc_stat) -> c_in
                 // BUG FIX: Use resolve_path to get a VfsPath for query_manifest
                 if let Some(vpath) = state.resolve_path(entry.vpath.as_str()) {
                     if let Some(vnode) = state.query_manifest(&vpath) {
+                        let ingest_ns = state.manifest_ingest_ns(&vpath);
                         std::ptr::write_bytes(buf, 0, 1);
                         (*buf).st_size = vnode.size as _;
                         #[cfg(target_os = "macos")]
@@ -246,7 +361,7 @@ pub unsafe extern "C" fn velo_fstat_impl(fd: c_int, buf: *mut libc_stat) -> c_in
                         {
                             (*buf).st_mode = vnode.mode as _;
                         }
-                        (*buf).st_mtime = vnode.mtime as _;
+                        set_stat_times(&mut *buf, vnode.mtime, ingest_ns);
                         (*buf).st_dev = 0x52494654;
                         (*buf).st_nlink = 1;
                         (*buf).st_ino = vpath.manifest_key_hash as _;
@@ -292,19 +407,12 @@ pub unsafe extern "C" fn velo_access_impl(path: *const c_char, mode: c_int) -> c
         }
     };
 
-    let path_str = match CStr::from_ptr(path).to_str() {
-        Ok(s) => s,
-        Err(_) => {
-            #[cfg(target_os = "macos")]
-            return crate::syscalls::macos_raw::raw_access(path, mode);
-            #[cfg(target_os = "linux")]
-            return crate::syscalls::linux_raw::raw_access(path, mode);
-        }
-    };
+    let path_str = crate::path::c_path_str(path);
 
-    if InceptionLayerState::get()
-        .map(|s| s.inception_applicable(path_str))
-        .unwrap_or(false)
+    if intercept::enabled(intercept::STAT)
+        && InceptionLayerState::get()
+            .map(|s| s.inception_applicable(&path_str))
+            .unwrap_or(false)
     {
         return 0;
     }
@@ -347,11 +455,10 @@ pub unsafe extern "C" fn velo_fstatat_impl(
         }
     };
 
-    if dirfd == libc::AT_FDCWD || (!path.is_null() && unsafe { *path == b'/' as libc::c_char }) {
-        if let Ok(path_str) = unsafe { CStr::from_ptr(path).to_str() } {
-            if let Some(res) = stat_impl_common(path_str, buf) {
-                return res;
-            }
+    let mut at_buf = [0u8; 1024];
+    if let Some(at_path) = crate::path::resolve_path_at(dirfd, path, &mut at_buf) {
+        if let Some(res) = stat_impl_common(&crate::path::c_path_str(at_path.as_ptr()), buf) {
+            return res;
         }
     }
 
@@ -397,16 +504,11 @@ pub unsafe fn fstatat_inception_linux(
         return -libc::EFAULT;
     }
 
-    let path_str = match CStr::from_ptr(path).to_str() {
-        Ok(s) => s,
-        Err(_) => return -libc::EINVAL,
-    };
-
-    if dirfd != libc::AT_FDCWD && !path_str.starts_with('/') {
+    let mut at_buf = [0u8; 1024];
+    let Some(at_path) = crate::path::resolve_path_at(dirfd, path, &mut at_buf) else {
         return -2;
-    }
-
-    stat_impl_common(path_str, buf).unwrap_or(-2)
+    };
+    stat_impl_common(&crate::path::c_path_str(at_path.as_ptr()), buf).unwrap_or(-2)
 }
 
 #[no_mangle]
@@ -428,12 +530,10 @@ pub unsafe extern "C" fn statx_inception(
         );
     }
 
+    // Not waiting for the state to exist: in a fresh process (coreutils
+    // `stat`) statx is often the first call to need it
     let init_state = INITIALIZING.load(Ordering::Relaxed);
-    if init_state != 0
-        || crate::state::INCEPTION_LAYER_STATE
-            .load(Ordering::Acquire)
-            .is_null()
-    {
+    if init_state != 0 || !intercept::enabled(intercept::STAT) {
         return crate::syscalls::linux_raw::raw_statx(
             dirfd,
             path,
@@ -443,9 +543,12 @@ pub unsafe extern "C" fn statx_inception(
         );
     }
 
-    let path_str = match CStr::from_ptr(path).to_str() {
-        Ok(s) => s,
-        Err(_) => {
+    let mut at_buf = [0u8; 1024];
+    let path_str = match crate::path::resolve_path_at(dirfd, path, &mut at_buf)
+        .map(|at_path| crate::path::c_path_str(at_path.as_ptr()))
+    {
+        Some(s) => s,
+        None => {
             return crate::syscalls::linux_raw::raw_statx(
                 dirfd,
                 path,
@@ -458,15 +561,38 @@ pub unsafe extern "C" fn statx_inception(
 
     // VFS lookup
     if let Some(state) = InceptionLayerState::get() {
-        if let Some(vpath) = state.resolve_path(path_str) {
+        if let Some(vpath) = state.resolve_path(&path_str) {
             if let Some(entry) = state.query_manifest(&vpath) {
+                if entry.is_external() {
+                    if let Some(target) = crate::syscalls::open::external_target(
+                        state,
+                        &entry.content_hash,
+                        entry.size,
+                    ) {
+                        return crate::syscalls::linux_raw::raw_statx(
+                            libc::AT_FDCWD,
+                            target.as_ptr(),
+                            flags,
+                            mask,
+                            buf as *mut libc::c_void,
+                        );
+                    }
+                }
+                let ingest_ns = state.manifest_ingest_ns(&vpath);
                 std::ptr::write_bytes(buf, 0, 1);
-                (*buf).stx_mask = 0x7FF; // basic stats
+                (*buf).stx_mask = 0x7FF | STATX_BTIME; // basic stats
                 (*buf).stx_size = entry.size as _;
                 (*buf).stx_mode = entry.mode as _;
-                (*buf).stx_ino = vrift_ipc::fnv1a_hash(path_str) as _;
+                (*buf).stx_ino = vrift_ipc::fnv1a_hash(&path_str) as _;
                 (*buf).stx_nlink = 1;
-                (*buf).stx_mtime.tv_sec = entry.mtime as _;
+                let (ctime_ns, btime_ns) = synthesized_times(entry.mtime, ingest_ns);
+                for (ts, ns) in [
+                    (&mut (*buf).stx_mtime, entry.mtime),
+                    (&mut (*buf).stx_ctime, ctime_ns),
+                    (&mut (*buf).stx_btime, btime_ns),
+                ] {
+                    (ts.tv_sec, ts.tv_nsec) = vrift_ipc::mtime::to_parts(ns);
+                }
                 (*buf).stx_blksize = 4096;
                 (*buf).stx_blocks = entry.size.div_ceil(512);
                 inception_record!(EventType::StatHit, (*buf).stx_ino, 0);
@@ -478,8 +604,30 @@ pub unsafe extern "C" fn statx_inception(
     crate::syscalls::linux_raw::raw_statx(dirfd, path, flags, mask, buf as *mut libc::c_void)
 }
 
+/// Helper: Find an open staged copy of a manifest path this process staged
+/// and the content hash it was taken from. A copy inherited across fork is
+/// not shared: its owner commits it whenever it is done with it.
+pub(crate) unsafe fn find_live_copy(
+    manifest_path: &str,
+) -> Option<(crate::state::FixedString<1024>, [u8; 32])> {
+    let state = InceptionLayerState::get()?;
+    let pid = libc::getpid();
+    let mut result = None;
+    state.open_fds.for_each(|entry| {
+        if entry.manifest_key.as_str() == manifest_path
+            && !entry.temp_path.is_empty()
+            && entry.owner_pid == pid
+        {
+            result = Some((entry.temp_path, entry.base_hash));
+        }
+    });
+    result
+}
+
 /// Helper: Find an open temp_path for a given manifest path.
-unsafe fn find_live_temp_path(manifest_path: &str) -> Option<crate::state::FixedString<1024>> {
+pub(crate) unsafe fn find_live_temp_path(
+    manifest_path: &str,
+) -> Option<crate::state::FixedString<1024>> {
     let state = InceptionLayerState::get()?;
     let mut result = None;
     state.open_fds.for_each(|entry| {
@@ -0,0 +1,109 @@
+//! # Stat Latency Watchdog
+//!
+//! A VDir hit normally costs well under a microsecond. When the machine is
+//! short on memory the kernel evicts the manifest mmap's pages and every hit
+//! turns into a major page fault, so stat latency becomes unpredictable just
+//! when builds are busiest.
+//!
+//! One in [`SAMPLE_EVERY`] mmap lookups is timed. If a sampled lookup takes
+//! longer than the threshold (`VRIFT_STAT_WATCHDOG_US`, microseconds, `0`
+//! turns the watchdog off), the whole mapping is re-advised with
+//! `MADV_WILLNEED` so the kernel reads it back in ahead of the next lookups,
+//! and a warning is logged. Advice is issued at most once per
+//! [`ADVISE_INTERVAL_NS`].
+
+use crate::state::{clock_ns, EventType};
+use std::ffi::CStr;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+
+/// Threshold when `VRIFT_STAT_WATCHDOG_US` is unset
+pub const DEFAULT_THRESHOLD_US: u64 = 200;
+
+/// Time one lookup in this many
+const SAMPLE_EVERY: u32 = 256;
+
+/// Minimum gap between two `madvise` calls
+const ADVISE_INTERVAL_NS: u64 = 1_000_000_000;
+
+const THRESHOLD_UNPARSED: u64 = u64::MAX;
+const THRESHOLD_OFF: u64 = 0;
+static THRESHOLD_NS: AtomicU64 = AtomicU64::new(THRESHOLD_UNPARSED);
+
+static LOOKUPS: AtomicU32 = AtomicU32::new(0);
+static LAST_ADVISE_NS: AtomicU64 = AtomicU64::new(0);
+
+#[cold]
+#[inline(never)]
+fn load_threshold() -> u64 {
+    let val = unsafe { libc::getenv(c"VRIFT_STAT_WATCHDOG_US".as_ptr()) };
+    let us = if val.is_null() {
+        DEFAULT_THRESHOLD_US
+    } else {
+        unsafe { CStr::from_ptr(val) }
+            .to_str()
+            .ok()
+            .and_then(|s| s.trim().parse::<u64>().ok())
+            .unwrap_or(DEFAULT_THRESHOLD_US)
+    };
+    let ns = us.saturating_mul(1000).min(THRESHOLD_UNPARSED - 1);
+    THRESHOLD_NS.store(ns, Ordering::Relaxed);
+    ns
+}
+
+#[inline]
+fn threshold_ns() -> u64 {
+    let ns = THRESHOLD_NS.load(Ordering::Relaxed);
+    if ns == THRESHOLD_UNPARSED {
+        load_threshold()
+    } else {
+        ns
+    }
+}
+
+/// Start time of this lookup if it is one of the sampled ones
+#[inline]
+pub(crate) fn sample_start() -> Option<u64> {
+    if !LOOKUPS
+        .fetch_add(1, Ordering::Relaxed)
+        .is_multiple_of(SAMPLE_EVERY)
+    {
+        return None;
+    }
+    if threshold_ns() == THRESHOLD_OFF {
+        return None;
+    }
+    Some(clock_ns(libc::CLOCK_MONOTONIC))
+}
+
+/// Finish a sampled lookup on the mapping at `ptr`/`size` that started at
+/// `start_ns`, re-advising the mapping if it was slow
+#[cold]
+pub(crate) unsafe fn observe(start_ns: u64, ptr: *const u8, size: usize, file_id: u64) {
+    let now = clock_ns(libc::CLOCK_MONOTONIC);
+    let elapsed = now.saturating_sub(start_ns);
+    if elapsed <= threshold_ns() || ptr.is_null() || size == 0 {
+        return;
+    }
+
+    let last = LAST_ADVISE_NS.load(Ordering::Relaxed);
+    if last != 0 && now.saturating_sub(last) < ADVISE_INTERVAL_NS {
+        return;
+    }
+    // One thread advises per interval
+    if LAST_ADVISE_NS
+        .compare_exchange(last, now, Ordering::Relaxed, Ordering::Relaxed)
+        .is_err()
+    {
+        return;
+    }
+
+    let rc = unsafe { libc::madvise(ptr as *mut libc::c_void, size, libc::MADV_WILLNEED) };
+    inception_record!(EventType::MmapAdvise, file_id, rc);
+    inception_warn!(
+        "slow mmap stat hit ({} us > {} us), advised WILLNEED on {} byte manifest mapping (rc={})",
+        elapsed / 1000,
+        threshold_ns() / 1000,
+        size,
+        rc
+    );
+}
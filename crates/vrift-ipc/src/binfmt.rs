@@ -0,0 +1,532 @@
+//! Just enough ELF and Mach-O parsing to find what the dynamic loader will
+//! open next: the program interpreter, the libraries an object depends on,
+//! and where it asks the loader to look for them.
+//!
+//! The shim uses this to copy a VFS executable or library together with the
+//! VFS files its loader will need onto disk before `dlopen` or `exec` hands
+//! them to a loader that cannot see the VFS.
+
+/// Object file format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinFormat {
+    Elf,
+    MachO,
+}
+
+/// Load-time dependencies of one object
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadDeps {
+    pub format: BinFormat,
+    /// `PT_INTERP` (ELF) or `LC_LOAD_DYLINKER` (Mach-O)
+    pub interpreter: Option<String>,
+    /// `DT_NEEDED` sonames, or `LC_LOAD_DYLIB` (and weak/re-export/lazy) install names
+    pub needed: Vec<String>,
+    /// `DT_RUNPATH` (else `DT_RPATH`) entries, or `LC_RPATH` paths, unexpanded
+    pub search_paths: Vec<String>,
+}
+
+const ELF_MAGIC: &[u8; 4] = b"\x7fELF";
+const PT_LOAD: u32 = 1;
+const PT_DYNAMIC: u32 = 2;
+const PT_INTERP: u32 = 3;
+const DT_NULL: u64 = 0;
+const DT_NEEDED: u64 = 1;
+const DT_STRTAB: u64 = 5;
+const DT_RPATH: u64 = 15;
+const DT_RUNPATH: u64 = 29;
+
+const MH_MAGIC: u32 = 0xfeed_face;
+const MH_MAGIC_64: u32 = 0xfeed_facf;
+const FAT_MAGIC: u32 = 0xcafe_babe;
+const FAT_MAGIC_64: u32 = 0xcafe_babf;
+const LC_LOAD_DYLIB: u32 = 0xc;
+const LC_LOAD_DYLINKER: u32 = 0xe;
+const LC_LOAD_WEAK_DYLIB: u32 = 0x8000_0018;
+const LC_RPATH: u32 = 0x8000_001c;
+const LC_REEXPORT_DYLIB: u32 = 0x8000_001f;
+const LC_LAZY_LOAD_DYLIB: u32 = 0x20;
+const LC_LOAD_UPWARD_DYLIB: u32 = 0x8000_0023;
+
+/// Mach-O CPU type of this build, to pick a slice out of universal binaries
+#[cfg(target_arch = "aarch64")]
+const HOST_CPU_TYPE: u32 = 0x0100_000c;
+#[cfg(target_arch = "x86_64")]
+const HOST_CPU_TYPE: u32 = 0x0100_0007;
+#[cfg(not(any(target_arch = "aarch64", target_arch = "x86_64")))]
+const HOST_CPU_TYPE: u32 = 0;
+
+/// Bounds-checked integer reads in one byte order
+#[derive(Clone, Copy)]
+struct Reader<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes<const N: usize>(&self, at: usize) -> Option<[u8; N]> {
+        self.data.get(at..at.checked_add(N)?)?.try_into().ok()
+    }
+
+    fn u16(&self, at: usize) -> Option<u16> {
+        let b = self.bytes::<2>(at)?;
+        Some(if self.big_endian {
+            u16::from_be_bytes(b)
+        } else {
+            u16::from_le_bytes(b)
+        })
+    }
+
+    fn u32(&self, at: usize) -> Option<u32> {
+        let b = self.bytes::<4>(at)?;
+        Some(if self.big_endian {
+            u32::from_be_bytes(b)
+        } else {
+            u32::from_le_bytes(b)
+        })
+    }
+
+    fn u64(&self, at: usize) -> Option<u64> {
+        let b = self.bytes::<8>(at)?;
+        Some(if self.big_endian {
+            u64::from_be_bytes(b)
+        } else {
+            u64::from_le_bytes(b)
+        })
+    }
+
+    /// Word-sized read: u64 for 64-bit objects, u32 otherwise
+    fn word(&self, at: usize, wide: bool) -> Option<u64> {
+        if wide {
+            self.u64(at)
+        } else {
+            self.u32(at).map(u64::from)
+        }
+    }
+
+    /// NUL-terminated string starting at `at`
+    fn c_str(&self, at: usize) -> Option<String> {
+        let rest = self.data.get(at..)?;
+        let len = rest.iter().position(|&b| b == 0)?;
+        std::str::from_utf8(&rest[..len]).ok().map(str::to_string)
+    }
+}
+
+/// Parse the load-time dependencies of an ELF or Mach-O object. Returns
+/// `None` for anything else (scripts, data) or a truncated header.
+pub fn parse(data: &[u8]) -> Option<LoadDeps> {
+    if data.starts_with(ELF_MAGIC) {
+        return parse_elf(data);
+    }
+    let magic = Reader {
+        data,
+        big_endian: true,
+    }
+    .u32(0)?;
+    match magic {
+        FAT_MAGIC | FAT_MAGIC_64 => parse_fat(data, magic == FAT_MAGIC_64),
+        _ => parse_macho(data),
+    }
+}
+
+fn parse_elf(data: &[u8]) -> Option<LoadDeps> {
+    let wide = match data.get(4)? {
+        1 => false,
+        2 => true,
+        _ => return None,
+    };
+    let r = Reader {
+        data,
+        big_endian: *data.get(5)? == 2,
+    };
+    let (phoff, phentsize, phnum) = if wide {
+        (r.u64(0x20)?, r.u16(0x36)?, r.u16(0x38)?)
+    } else {
+        (u64::from(r.u32(0x1c)?), r.u16(0x2a)?, r.u16(0x2c)?)
+    };
+
+    // (type, offset, vaddr, filesz)
+    let mut segments = Vec::with_capacity(phnum as usize);
+    for i in 0..phnum as usize {
+        let at = usize::try_from(phoff).ok()? + i * phentsize as usize;
+        let p_type = r.u32(at)?;
+        let segment = if wide {
+            (p_type, r.u64(at + 8)?, r.u64(at + 16)?, r.u64(at + 32)?)
+        } else {
+            (
+                p_type,
+                u64::from(r.u32(at + 4)?),
+                u64::from(r.u32(at + 8)?),
+                u64::from(r.u32(at + 16)?),
+            )
+        };
+        segments.push(segment);
+    }
+    // Dynamic entries point at virtual addresses; map them back to the file
+    let file_offset = |vaddr: u64| {
+        segments
+            .iter()
+            .find(|(t, _, start, size)| *t == PT_LOAD && vaddr >= *start && vaddr - start < *size)
+            .and_then(|(_, offset, start, _)| usize::try_from(vaddr - start + offset).ok())
+    };
+
+    let mut deps = LoadDeps {
+        format: BinFormat::Elf,
+        interpreter: None,
+        needed: Vec::new(),
+        search_paths: Vec::new(),
+    };
+    if let Some((_, offset, _, _)) = segments.iter().find(|s| s.0 == PT_INTERP) {
+        deps.interpreter = r.c_str(usize::try_from(*offset).ok()?);
+    }
+    let Some((_, dyn_offset, _, dyn_size)) = segments.iter().find(|s| s.0 == PT_DYNAMIC) else {
+        // Static executable
+        return Some(deps);
+    };
+
+    let entry_size = if wide { 16 } else { 8 };
+    let mut entries = Vec::new();
+    let mut strtab = None;
+    for i in 0..(*dyn_size as usize / entry_size) {
+        let at = usize::try_from(*dyn_offset).ok()? + i * entry_size;
+        let tag = r.word(at, wide)?;
+        let val = r.word(at + entry_size / 2, wide)?;
+        match tag {
+            DT_NULL => break,
+            DT_STRTAB => strtab = file_offset(val),
+            DT_NEEDED | DT_RPATH | DT_RUNPATH => entries.push((tag, val)),
+            _ => {}
+        }
+    }
+    let strtab = strtab?;
+    let string = |val: u64| r.c_str(strtab.checked_add(usize::try_from(val).ok()?)?);
+
+    let has_runpath = entries.iter().any(|(tag, _)| *tag == DT_RUNPATH);
+    for (tag, val) in entries {
+        match tag {
+            DT_NEEDED => deps.needed.extend(string(val)),
+            // DT_RPATH is ignored when DT_RUNPATH is present
+            DT_RPATH if has_runpath => {}
+            _ => {
+                if let Some(paths) = string(val) {
+                    deps.search_paths.extend(
+                        paths
+                            .split(':')
+                            .filter(|p| !p.is_empty())
+                            .map(str::to_string),
+                    );
+                }
+            }
+        }
+    }
+    Some(deps)
+}
+
+fn parse_fat(data: &[u8], wide: bool) -> Option<LoadDeps> {
+    let r = Reader {
+        data,
+        big_endian: true,
+    };
+    let count = r.u32(4)? as usize;
+    // Java class files share the magic; their "count" is a version number
+    if count == 0 || count > 32 {
+        return None;
+    }
+    let arch_size = if wide { 32 } else { 20 };
+    let slices: Vec<(u32, u64)> = (0..count)
+        .map(|i| {
+            let at = 8 + i * arch_size;
+            let offset = if wide {
+                r.u64(at + 8)?
+            } else {
+                u64::from(r.u32(at + 8)?)
+            };
+            Some((r.u32(at)?, offset))
+        })
+        .collect::<Option<_>>()?;
+    let (_, offset) = slices
+        .iter()
+        .find(|(cpu, _)| *cpu == HOST_CPU_TYPE)
+        .or(slices.first())?;
+    parse_macho(data.get(usize::try_from(*offset).ok()?..)?)
+}
+
+fn parse_macho(data: &[u8]) -> Option<LoadDeps> {
+    // Thin headers are in the target's byte order; all targets are little endian
+    let r = Reader {
+        data,
+        big_endian: false,
+    };
+    let header_size = match r.u32(0)? {
+        MH_MAGIC => 28,
+        MH_MAGIC_64 => 32,
+        _ => return None,
+    };
+    let ncmds = r.u32(16)?;
+
+    let mut deps = LoadDeps {
+        format: BinFormat::MachO,
+        interpreter: None,
+        needed: Vec::new(),
+        search_paths: Vec::new(),
+    };
+    let mut at = header_size;
+    for _ in 0..ncmds {
+        let cmd = r.u32(at)?;
+        let size = r.u32(at + 4)? as usize;
+        if size < 8 {
+            return None;
+        }
+        // Every command of interest stores a string offset right after cmd/cmdsize
+        let string = || {
+            let offset = r.u32(at + 8)? as usize;
+            if offset >= size {
+                return None;
+            }
+            let s = r.c_str(at + offset)?;
+            (offset + s.len() < size).then_some(s)
+        };
+        match cmd {
+            LC_LOAD_DYLIB | LC_LOAD_WEAK_DYLIB | LC_REEXPORT_DYLIB | LC_LAZY_LOAD_DYLIB
+            | LC_LOAD_UPWARD_DYLIB => deps.needed.extend(string()),
+            LC_RPATH => deps.search_paths.extend(string()),
+            LC_LOAD_DYLINKER => deps.interpreter = string(),
+            _ => {}
+        }
+        at = at.checked_add(size)?;
+    }
+    Some(deps)
+}
+
+impl LoadDeps {
+    /// Paths the loader would try, in order, for dependency `dep` of this
+    /// object. `loader_dir` is the directory the object is loaded from
+    /// (`$ORIGIN`, `@loader_path`), `executable_dir` that of the main
+    /// program (`@executable_path`), and `inherited_rpaths` the already
+    /// expanded `LC_RPATH`s of the objects that led here, which Mach-O
+    /// `@rpath` lookups also consult.
+    ///
+    /// Names without a `/` that no search path covers are left to the
+    /// system's default directories and produce no candidates.
+    pub fn candidates(
+        &self,
+        dep: &str,
+        loader_dir: &str,
+        executable_dir: &str,
+        inherited_rpaths: &[String],
+    ) -> Vec<String> {
+        match self.format {
+            BinFormat::Elf => {
+                if dep.contains('/') {
+                    return if dep.starts_with('/') {
+                        vec![dep.to_string()]
+                    } else {
+                        Vec::new()
+                    };
+                }
+                self.expanded_search_paths(loader_dir, executable_dir)
+                    .iter()
+                    .map(|dir| join(dir, dep))
+                    .collect()
+            }
+            BinFormat::MachO => {
+                if let Some(rest) = dep.strip_prefix("@rpath/") {
+                    self.expanded_search_paths(loader_dir, executable_dir)
+                        .iter()
+                        .chain(inherited_rpaths)
+                        .map(|dir| join(dir, rest))
+                        .collect()
+                } else if let Some(path) = expand(self.format, dep, loader_dir, executable_dir) {
+                    if path.starts_with('/') {
+                        vec![path]
+                    } else {
+                        Vec::new()
+                    }
+                } else {
+                    Vec::new()
+                }
+            }
+        }
+    }
+
+    /// Search paths with `$ORIGIN` / `@loader_path` / `@executable_path`
+    /// substituted; entries using other variables are dropped
+    pub fn expanded_search_paths(&self, loader_dir: &str, executable_dir: &str) -> Vec<String> {
+        self.search_paths
+            .iter()
+            .filter_map(|p| expand(self.format, p, loader_dir, executable_dir))
+            .filter(|p| p.starts_with('/'))
+            .collect()
+    }
+}
+
+fn expand(format: BinFormat, path: &str, loader_dir: &str, executable_dir: &str) -> Option<String> {
+    match format {
+        BinFormat::Elf => {
+            let expanded = path
+                .replace("${ORIGIN}", loader_dir)
+                .replace("$ORIGIN", loader_dir);
+            // $LIB, $PLATFORM and friends depend on the loader build
+            (!expanded.contains('$')).then_some(expanded)
+        }
+        BinFormat::MachO => {
+            if let Some(rest) = path.strip_prefix("@loader_path") {
+                Some(format!("{}{}", loader_dir, rest))
+            } else if let Some(rest) = path.strip_prefix("@executable_path") {
+                Some(format!("{}{}", executable_dir, rest))
+            } else if path.starts_with('@') {
+                None
+            } else {
+                Some(path.to_string())
+            }
+        }
+    }
+}
+
+fn join(dir: &str, name: &str) -> String {
+    format!("{}/{}", dir.trim_end_matches('/'), name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimal little-endian ELF64 with PT_INTERP, one PT_LOAD covering the
+    /// whole file and a PT_DYNAMIC listing two needed libraries and a runpath
+    fn elf64(runpath_tag: u64) -> Vec<u8> {
+        let mut f = vec![0u8; 0x300];
+        f[..4].copy_from_slice(ELF_MAGIC);
+        f[4] = 2; // ELFCLASS64
+        f[5] = 1; // little endian
+        f[0x20..0x28].copy_from_slice(&0x40u64.to_le_bytes()); // e_phoff
+        f[0x36..0x38].copy_from_slice(&56u16.to_le_bytes()); // e_phentsize
+        f[0x38..0x3a].copy_from_slice(&3u16.to_le_bytes()); // e_phnum
+
+        let vbase = 0x40_0000u64;
+        let mut phdr = |i: usize, p_type: u32, offset: u64, vaddr: u64, size: u64| {
+            let at = 0x40 + i * 56;
+            f[at..at + 4].copy_from_slice(&p_type.to_le_bytes());
+            f[at + 8..at + 16].copy_from_slice(&offset.to_le_bytes());
+            f[at + 16..at + 24].copy_from_slice(&vaddr.to_le_bytes());
+            f[at + 32..at + 40].copy_from_slice(&size.to_le_bytes());
+        };
+        phdr(0, PT_INTERP, 0x100, vbase + 0x100, 0x20);
+        phdr(1, PT_LOAD, 0, vbase, 0x300);
+        phdr(2, PT_DYNAMIC, 0x200, vbase + 0x200, 0x50);
+
+        let interp = b"/vfs/lib/ld-linux.so.2\0";
+        f[0x100..0x100 + interp.len()].copy_from_slice(interp);
+        // String table at 0x180
+        let strings = b"\0libfoo.so.1\0libbar.so\0$ORIGIN/../lib:/opt/x\0";
+        f[0x180..0x180 + strings.len()].copy_from_slice(strings);
+        let dynamic = [
+            (DT_NEEDED, 1u64),
+            (DT_NEEDED, 13),
+            (runpath_tag, 23),
+            (DT_STRTAB, vbase + 0x180),
+            (DT_NULL, 0),
+        ];
+        for (i, (tag, val)) in dynamic.iter().enumerate() {
+            let at = 0x200 + i * 16;
+            f[at..at + 8].copy_from_slice(&tag.to_le_bytes());
+            f[at + 8..at + 16].copy_from_slice(&val.to_le_bytes());
+        }
+        f
+    }
+
+    #[test]
+    fn test_parse_elf64() {
+        let deps = parse(&elf64(DT_RUNPATH)).unwrap();
+        assert_eq!(deps.format, BinFormat::Elf);
+        assert_eq!(deps.interpreter.as_deref(), Some("/vfs/lib/ld-linux.so.2"));
+        assert_eq!(deps.needed, vec!["libfoo.so.1", "libbar.so"]);
+        assert_eq!(deps.search_paths, vec!["$ORIGIN/../lib", "/opt/x"]);
+
+        assert_eq!(
+            deps.candidates("libfoo.so.1", "/vfs/bin", "/vfs/bin", &[]),
+            vec!["/vfs/bin/../lib/libfoo.so.1", "/opt/x/libfoo.so.1"]
+        );
+        assert_eq!(
+            deps.candidates("/abs/libz.so", "/vfs/bin", "/vfs/bin", &[]),
+            vec!["/abs/libz.so"]
+        );
+        // Old-style DT_RPATH is honoured when there is no DT_RUNPATH
+        assert_eq!(parse(&elf64(DT_RPATH)).unwrap().search_paths.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_truncated_or_foreign() {
+        let elf = elf64(DT_RUNPATH);
+        assert!(parse(&elf[..0x50]).is_none());
+        assert!(parse(b"#!/bin/sh\necho hi\n").is_none());
+        // Java class file: cafebabe followed by a large version
+        assert!(parse(&[0xca, 0xfe, 0xba, 0xbe, 0, 0, 0, 0x34]).is_none());
+    }
+
+    fn load_command(cmd: u32, s: &str) -> Vec<u8> {
+        let size = (12 + s.len() + 1).next_multiple_of(8);
+        let mut lc = vec![0u8; size];
+        lc[0..4].copy_from_slice(&cmd.to_le_bytes());
+        lc[4..8].copy_from_slice(&(size as u32).to_le_bytes());
+        lc[8..12].copy_from_slice(&12u32.to_le_bytes());
+        lc[12..12 + s.len()].copy_from_slice(s.as_bytes());
+        lc
+    }
+
+    fn macho64() -> Vec<u8> {
+        let commands = [
+            load_command(LC_LOAD_DYLINKER, "/usr/lib/dyld"),
+            load_command(LC_LOAD_DYLIB, "@rpath/libfoo.dylib"),
+            load_command(LC_LOAD_WEAK_DYLIB, "@loader_path/libbar.dylib"),
+            load_command(LC_RPATH, "@loader_path/../Frameworks"),
+        ];
+        let mut f = vec![0u8; 32];
+        f[0..4].copy_from_slice(&MH_MAGIC_64.to_le_bytes());
+        f[16..20].copy_from_slice(&(commands.len() as u32).to_le_bytes());
+        for lc in commands {
+            f.extend(lc);
+        }
+        f
+    }
+
+    #[test]
+    fn test_parse_macho() {
+        let deps = parse(&macho64()).unwrap();
+        assert_eq!(deps.format, BinFormat::MachO);
+        assert_eq!(deps.interpreter.as_deref(), Some("/usr/lib/dyld"));
+        assert_eq!(
+            deps.needed,
+            vec!["@rpath/libfoo.dylib", "@loader_path/libbar.dylib"]
+        );
+
+        let inherited = vec!["/vfs/app/lib".to_string()];
+        assert_eq!(
+            deps.candidates(
+                "@rpath/libfoo.dylib",
+                "/vfs/app/bin",
+                "/vfs/app/bin",
+                &inherited
+            ),
+            vec![
+                "/vfs/app/bin/../Frameworks/libfoo.dylib",
+                "/vfs/app/lib/libfoo.dylib"
+            ]
+        );
+        assert_eq!(
+            deps.candidates("@loader_path/libbar.dylib", "/vfs/x", "/vfs/y", &[]),
+            vec!["/vfs/x/libbar.dylib"]
+        );
+    }
+
+    #[test]
+    fn test_parse_fat_picks_a_slice() {
+        let thin = macho64();
+        let mut fat = vec![0u8; 4096];
+        fat[0..4].copy_from_slice(&FAT_MAGIC.to_be_bytes());
+        fat[4..8].copy_from_slice(&1u32.to_be_bytes());
+        fat[8..12].copy_from_slice(&HOST_CPU_TYPE.to_be_bytes());
+        fat[16..20].copy_from_slice(&4096u32.to_be_bytes());
+        fat[20..24].copy_from_slice(&(thin.len() as u32).to_be_bytes());
+        fat.extend(&thin);
+        assert_eq!(parse(&fat), parse(&thin));
+    }
+}
@@ -1,6 +1,11 @@
+pub mod binfmt;
+pub mod mtime;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 pub mod vdir_types;
 use rkyv::Archive;
 use serde::{Deserialize, Serialize};
+use vrift_error::{Classify, ErrorKind};
 
 /// IPC Protocol Version - bump when making breaking changes
 /// v1: Initial protocol with basic requests
@@ -29,6 +34,12 @@ pub enum FrameType {
     Response = 1,
     /// Heartbeat/keepalive
     Heartbeat = 2,
+    /// Next part of a request or response larger than
+    /// [`IpcHeader::MAX_LENGTH`], with the same seq ID
+    Continuation = 3,
+    /// Server-pushed [`VeloEvent`] on a subscribed connection, with the seq
+    /// ID of its `Subscribe` request
+    Event = 4,
 }
 
 impl TryFrom<u8> for FrameType {
@@ -39,6 +50,8 @@ impl TryFrom<u8> for FrameType {
             0 => Ok(FrameType::Request),
             1 => Ok(FrameType::Response),
             2 => Ok(FrameType::Heartbeat),
+            3 => Ok(FrameType::Continuation),
+            4 => Ok(FrameType::Event),
             _ => Err(()),
         }
     }
@@ -50,10 +63,14 @@ impl TryFrom<u8> for FrameType {
 /// ```text
 /// ┌──────────┬────────────┬─────────┬──────────┬──────────┐
 /// │Magic (2B)│Type+Ver(1B)│Flags(1B)│Length(4B)│ SeqID(4B)│
-/// │  "VR"    │ hi4=type   │reserved │ LE u32   │ LE u32   │
+/// │  "VR"    │ hi4=type   │bit0=more│ LE u32   │ LE u32   │
 /// │          │ lo4=version│         │ max 32MB │ 0-u32max │
 /// └──────────┴────────────┴─────────┴──────────┴──────────┘
 /// ```
+///
+/// A payload larger than [`IpcHeader::MAX_LENGTH`] is split: the first frame
+/// and every [`FrameType::Continuation`] but the last carry
+/// [`IpcHeader::FLAG_MORE`]. Smaller payloads keep the single-frame layout.
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
 pub struct IpcHeader {
@@ -61,7 +78,7 @@ pub struct IpcHeader {
     pub magic: [u8; 2],
     /// Type (high 4 bits) + Protocol Version (low 4 bits)
     pub type_ver: u8,
-    /// Flags (reserved for future use)
+    /// Flags ([`IpcHeader::FLAG_MORE`]; other bits reserved)
     pub flags: u8,
     /// Payload length in bytes (max u32::MAX)
     pub length: u32,
@@ -73,9 +90,15 @@ impl IpcHeader {
     /// Size of the header in bytes
     pub const SIZE: usize = 12;
 
-    /// Maximum payload length (32MB safety limit)
+    /// Maximum payload length of one frame (32MB safety limit)
     pub const MAX_LENGTH: usize = 32 * 1024 * 1024;
 
+    /// Maximum payload length of a message split across frames
+    pub const MAX_MESSAGE_LENGTH: usize = 512 * 1024 * 1024;
+
+    /// Flag bit: the payload continues in the next frame
+    pub const FLAG_MORE: u8 = 0x01;
+
     /// Create a new header with specified frame type
     pub fn new(frame_type: FrameType, length: u32, seq_id: u32) -> Self {
         Self {
@@ -102,6 +125,11 @@ impl IpcHeader {
         Self::new(FrameType::Heartbeat, 0, seq_id)
     }
 
+    /// Create an event header
+    pub fn new_event(length: u32, seq_id: u32) -> Self {
+        Self::new(FrameType::Event, length, seq_id)
+    }
+
     /// Validate the header magic, version, and frame type
     pub fn is_valid(&self) -> bool {
         self.magic == IPC_MAGIC
@@ -140,6 +168,41 @@ impl IpcHeader {
             seq_id: u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]),
         }
     }
+
+    /// Whether the payload continues in a [`FrameType::Continuation`] frame
+    pub fn has_more(&self) -> bool {
+        self.flags & Self::FLAG_MORE != 0
+    }
+
+    /// Check that `next` continues the message this header started, after
+    /// `received` payload bytes
+    pub fn check_continuation(&self, next: &IpcHeader, received: usize) -> std::io::Result<()> {
+        let invalid = |msg: String| std::io::Error::new(std::io::ErrorKind::InvalidData, msg);
+        if !next.is_valid() {
+            return Err(invalid("invalid continuation frame header".to_string()));
+        }
+        if next.frame_type() != Some(FrameType::Continuation) {
+            return Err(invalid(format!(
+                "expected Continuation frame, got {:?}",
+                next.frame_type()
+            )));
+        }
+        if next.seq_id != self.seq_id {
+            return Err(invalid(format!(
+                "continuation seq_id mismatch: expected {}, got {}",
+                self.seq_id, next.seq_id
+            )));
+        }
+        if next.length as usize > Self::MAX_LENGTH
+            || received + next.length as usize > Self::MAX_MESSAGE_LENGTH
+        {
+            return Err(invalid(format!(
+                "message too large: over {} bytes",
+                Self::MAX_MESSAGE_LENGTH
+            )));
+        }
+        Ok(())
+    }
 }
 
 // ============================================================================
@@ -156,6 +219,50 @@ pub fn next_seq_id() -> u32 {
     NEXT_SEQ_ID.fetch_add(1, Ordering::Relaxed)
 }
 
+/// Split `payload` into the frames of one message: the first of type
+/// `frame_type`, the rest continuations, each at most
+/// [`IpcHeader::MAX_LENGTH`] long
+pub fn message_frames(
+    frame_type: FrameType,
+    seq_id: u32,
+    payload: &[u8],
+) -> std::io::Result<impl Iterator<Item = (IpcHeader, &[u8])>> {
+    split_frames(frame_type, seq_id, payload, IpcHeader::MAX_LENGTH)
+}
+
+fn split_frames(
+    frame_type: FrameType,
+    seq_id: u32,
+    payload: &[u8],
+    chunk: usize,
+) -> std::io::Result<impl Iterator<Item = (IpcHeader, &[u8])>> {
+    if payload.len() > IpcHeader::MAX_MESSAGE_LENGTH {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!(
+                "payload too large: {} > {}",
+                payload.len(),
+                IpcHeader::MAX_MESSAGE_LENGTH
+            ),
+        ));
+    }
+    // An empty payload still takes one frame
+    let count = payload.len().div_ceil(chunk).max(1);
+    Ok((0..count).map(move |i| {
+        let part = &payload[i * chunk..payload.len().min((i + 1) * chunk)];
+        let kind = if i == 0 {
+            frame_type
+        } else {
+            FrameType::Continuation
+        };
+        let mut header = IpcHeader::new(kind, part.len() as u32, seq_id);
+        if i + 1 < count {
+            header.flags |= IpcHeader::FLAG_MORE;
+        }
+        (header, part)
+    }))
+}
+
 /// Synchronous frame IO (for vrift-shim and blocking contexts)
 pub mod frame_sync {
     use super::*;
@@ -166,23 +273,14 @@ pub mod frame_sync {
         let payload = rkyv::to_bytes::<rkyv::rancor::Error>(request)
             .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
 
-        if payload.len() > IpcHeader::MAX_LENGTH {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                format!(
-                    "payload too large: {} > {}",
-                    payload.len(),
-                    IpcHeader::MAX_LENGTH
-                ),
-            ));
-        }
-
         let seq_id = next_seq_id();
-        let header = IpcHeader::new_request(payload.len() as u32, seq_id);
-
-        writer.write_all(&header.to_bytes())?;
-        writer.write_all(&payload)?;
-        writer.flush()?;
+        write_message(
+            writer,
+            FrameType::Request,
+            seq_id,
+            &payload,
+            IpcHeader::MAX_LENGTH,
+        )?;
 
         Ok(seq_id)
     }
@@ -196,24 +294,45 @@ pub mod frame_sync {
         let payload = rkyv::to_bytes::<rkyv::rancor::Error>(response)
             .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
 
-        if payload.len() > IpcHeader::MAX_LENGTH {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                format!(
-                    "payload too large: {} > {}",
-                    payload.len(),
-                    IpcHeader::MAX_LENGTH
-                ),
-            ));
-        }
-
-        let header = IpcHeader::new_response(payload.len() as u32, seq_id);
-
-        writer.write_all(&header.to_bytes())?;
-        writer.write_all(&payload)?;
-        writer.flush()?;
+        write_message(
+            writer,
+            FrameType::Response,
+            seq_id,
+            &payload,
+            IpcHeader::MAX_LENGTH,
+        )
+    }
 
-        Ok(())
+    /// Write `payload` as one message, split into frames of at most `chunk`
+    /// bytes
+    fn write_message<W: Write>(
+        writer: &mut W,
+        frame_type: FrameType,
+        seq_id: u32,
+        payload: &[u8],
+        chunk: usize,
+    ) -> std::io::Result<()> {
+        for (header, part) in split_frames(frame_type, seq_id, payload, chunk)? {
+            writer.write_all(&header.to_bytes())?;
+            writer.write_all(part)?;
+        }
+        writer.flush()
+    }
+
+    /// Read the payload `header` starts, following continuation frames
+    fn read_payload<R: Read>(reader: &mut R, header: &IpcHeader) -> std::io::Result<Vec<u8>> {
+        let mut payload = vec![0u8; header.length as usize];
+        reader.read_exact(&mut payload)?;
+        let mut more = header.has_more();
+        while more {
+            let next = read_header(reader)?;
+            header.check_continuation(&next, payload.len())?;
+            let start = payload.len();
+            payload.resize(start + next.length as usize, 0);
+            reader.read_exact(&mut payload[start..])?;
+            more = next.has_more();
+        }
+        Ok(payload)
     }
 
     /// Read a frame header
@@ -265,8 +384,7 @@ pub mod frame_sync {
                 ));
             }
 
-            let mut payload = vec![0u8; header.length as usize];
-            reader.read_exact(&mut payload)?;
+            let payload = read_payload(reader, &header)?;
 
             let request: VeloRequest =
                 rkyv::from_bytes::<VeloRequest, rkyv::rancor::Error>(&payload).map_err(|e| {
@@ -294,8 +412,7 @@ pub mod frame_sync {
                 ));
             }
 
-            let mut payload = vec![0u8; header.length as usize];
-            reader.read_exact(&mut payload)?;
+            let payload = read_payload(reader, &header)?;
 
             let response: VeloResponse =
                 rkyv::from_bytes::<VeloResponse, rkyv::rancor::Error>(&payload).map_err(|e| {
@@ -306,6 +423,49 @@ pub mod frame_sync {
         }
     }
 
+    /// Send an event frame on the connection subscribed by request `seq_id`
+    pub fn send_event<W: Write>(
+        writer: &mut W,
+        event: &VeloEvent,
+        seq_id: u32,
+    ) -> std::io::Result<()> {
+        let payload = rkyv::to_bytes::<rkyv::rancor::Error>(event)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+
+        write_message(
+            writer,
+            FrameType::Event,
+            seq_id,
+            &payload,
+            IpcHeader::MAX_LENGTH,
+        )
+    }
+
+    /// Read frame payload and deserialize as event (skipping heartbeats)
+    pub fn read_event<R: Read>(reader: &mut R) -> std::io::Result<(IpcHeader, VeloEvent)> {
+        loop {
+            let header = read_header(reader)?;
+
+            if header.frame_type() == Some(FrameType::Heartbeat) {
+                continue;
+            }
+
+            if header.frame_type() != Some(FrameType::Event) {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("expected Event frame, got {:?}", header.frame_type()),
+                ));
+            }
+
+            let payload = read_payload(reader, &header)?;
+
+            let event: VeloEvent = rkyv::from_bytes::<VeloEvent, rkyv::rancor::Error>(&payload)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))?;
+
+            return Ok((header, event));
+        }
+    }
+
     /// Send a heartbeat frame (zero-length payload)
     pub fn send_heartbeat<W: Write>(writer: &mut W) -> std::io::Result<u32> {
         let seq_id = next_seq_id();
@@ -337,23 +497,15 @@ pub mod frame_async {
         let payload = rkyv::to_bytes::<rkyv::rancor::Error>(request)
             .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
 
-        if payload.len() > IpcHeader::MAX_LENGTH {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                format!(
-                    "payload too large: {} > {}",
-                    payload.len(),
-                    IpcHeader::MAX_LENGTH
-                ),
-            ));
-        }
-
         let seq_id = next_seq_id();
-        let header = IpcHeader::new_request(payload.len() as u32, seq_id);
-
-        writer.write_all(&header.to_bytes()).await?;
-        writer.write_all(&payload).await?;
-        writer.flush().await?;
+        write_message(
+            writer,
+            FrameType::Request,
+            seq_id,
+            &payload,
+            IpcHeader::MAX_LENGTH,
+        )
+        .await?;
 
         Ok(seq_id)
     }
@@ -367,24 +519,49 @@ pub mod frame_async {
         let payload = rkyv::to_bytes::<rkyv::rancor::Error>(response)
             .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
 
-        if payload.len() > IpcHeader::MAX_LENGTH {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                format!(
-                    "payload too large: {} > {}",
-                    payload.len(),
-                    IpcHeader::MAX_LENGTH
-                ),
-            ));
-        }
-
-        let header = IpcHeader::new_response(payload.len() as u32, seq_id);
+        write_message(
+            writer,
+            FrameType::Response,
+            seq_id,
+            &payload,
+            IpcHeader::MAX_LENGTH,
+        )
+        .await
+    }
 
-        writer.write_all(&header.to_bytes()).await?;
-        writer.write_all(&payload).await?;
-        writer.flush().await?;
+    /// Write `payload` as one message, split into frames of at most `chunk`
+    /// bytes
+    async fn write_message<W: AsyncWriteExt + Unpin>(
+        writer: &mut W,
+        frame_type: FrameType,
+        seq_id: u32,
+        payload: &[u8],
+        chunk: usize,
+    ) -> std::io::Result<()> {
+        for (header, part) in split_frames(frame_type, seq_id, payload, chunk)? {
+            writer.write_all(&header.to_bytes()).await?;
+            writer.write_all(part).await?;
+        }
+        writer.flush().await
+    }
 
-        Ok(())
+    /// Read the payload `header` starts, following continuation frames
+    async fn read_payload<R: AsyncReadExt + Unpin>(
+        reader: &mut R,
+        header: &IpcHeader,
+    ) -> std::io::Result<Vec<u8>> {
+        let mut payload = vec![0u8; header.length as usize];
+        reader.read_exact(&mut payload).await?;
+        let mut more = header.has_more();
+        while more {
+            let next = read_header(reader).await?;
+            header.check_continuation(&next, payload.len())?;
+            let start = payload.len();
+            payload.resize(start + next.length as usize, 0);
+            reader.read_exact(&mut payload[start..]).await?;
+            more = next.has_more();
+        }
+        Ok(payload)
     }
 
     /// Read a frame header
@@ -440,8 +617,7 @@ pub mod frame_async {
                 ));
             }
 
-            let mut payload = vec![0u8; header.length as usize];
-            reader.read_exact(&mut payload).await?;
+            let payload = read_payload(reader, &header).await?;
 
             let request: VeloRequest =
                 rkyv::from_bytes::<VeloRequest, rkyv::rancor::Error>(&payload).map_err(|e| {
@@ -471,8 +647,7 @@ pub mod frame_async {
                 ));
             }
 
-            let mut payload = vec![0u8; header.length as usize];
-            reader.read_exact(&mut payload).await?;
+            let payload = read_payload(reader, &header).await?;
 
             let response: VeloResponse =
                 rkyv::from_bytes::<VeloResponse, rkyv::rancor::Error>(&payload).map_err(|e| {
@@ -483,6 +658,52 @@ pub mod frame_async {
         }
     }
 
+    /// Send an event frame on the connection subscribed by request `seq_id`
+    pub async fn send_event<W: AsyncWriteExt + Unpin>(
+        writer: &mut W,
+        event: &VeloEvent,
+        seq_id: u32,
+    ) -> std::io::Result<()> {
+        let payload = rkyv::to_bytes::<rkyv::rancor::Error>(event)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+
+        write_message(
+            writer,
+            FrameType::Event,
+            seq_id,
+            &payload,
+            IpcHeader::MAX_LENGTH,
+        )
+        .await
+    }
+
+    /// Read frame payload and deserialize as event (skipping heartbeats)
+    pub async fn read_event<R: AsyncReadExt + Unpin>(
+        reader: &mut R,
+    ) -> std::io::Result<(IpcHeader, VeloEvent)> {
+        loop {
+            let header = read_header(reader).await?;
+
+            if header.frame_type() == Some(FrameType::Heartbeat) {
+                continue;
+            }
+
+            if header.frame_type() != Some(FrameType::Event) {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("expected Event frame, got {:?}", header.frame_type()),
+                ));
+            }
+
+            let payload = read_payload(reader, &header).await?;
+
+            let event: VeloEvent = rkyv::from_bytes::<VeloEvent, rkyv::rancor::Error>(&payload)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))?;
+
+            return Ok((header, event));
+        }
+    }
+
     // ========================================================================
     // Timeout Wrappers
     // ========================================================================
@@ -559,6 +780,8 @@ pub enum VeloRequest {
         protocol_version: u32,
     },
     Status,
+    /// Health probe: answered without locks or I/O, so it stays fast under load
+    Ping,
     Spawn {
         command: Vec<String>,
         env: Vec<(String, String)>,
@@ -596,7 +819,8 @@ pub enum VeloRequest {
     /// RFC-0047: Update manifest mtime (for utimes/touch)
     ManifestUpdateMtime {
         path: String,
-        mtime_ns: u64,
+        /// Signed nanoseconds since the epoch (see [`mtime`])
+        mtime_ns: i64,
     },
     /// RFC-0047: Reingest a modified temp file back to CAS and Manifest (for CoW close)
     ManifestReingest {
@@ -609,6 +833,11 @@ pub enum VeloRequest {
     ManifestListDir {
         path: String,
     },
+    /// Poll the manifest change feed (inotify emulation for VFS watchers).
+    /// Pass `cursor: u64::MAX` to learn the current head without receiving history.
+    ManifestChangesSince {
+        cursor: u64,
+    },
     /// RFC-0049: Acquire advisory lock on logical file
     FlockAcquire {
         path: String,
@@ -618,11 +847,44 @@ pub enum VeloRequest {
     FlockRelease {
         path: String,
     },
-    /// Trigger Garbage Collection using a Bloom Filter of active hashes
+    /// Queue a Garbage Collection sweep using a Bloom Filter of active
+    /// hashes. Answered immediately with the new job's `JobAck`.
     CasSweep {
         /// Bloom Filter of all active hashes in the manifest
         bloom_filter: Vec<u8>,
+        /// Answer with the sweep's `PlanAck` instead of queueing it
+        dry_run: bool,
+    },
+    /// Recent daemon jobs, newest first
+    JobList,
+    /// Poll a single job
+    JobStatus {
+        job_id: u64,
+    },
+    /// Ask a queued or running job to stop; answered with its current state
+    JobCancel {
+        job_id: u64,
+    },
+    /// Re-submit a failed or cancelled job with its original parameters
+    JobRetry {
+        job_id: u64,
     },
+    /// Report a process joining a workspace session. Sent by the inception
+    /// layer on load (pid = self) and after spawning a child (ppid = self).
+    SessionRegister {
+        pid: u32,
+        ppid: u32,
+        command: String,
+    },
+    /// Attribute a committed CoW write-back to the reporting process
+    SessionWriteBack {
+        pid: u32,
+        path: String,
+    },
+    /// List live workspace sessions (`vrift ps`)
+    SessionList,
+    /// Per-workspace load state (vDird readiness)
+    WorkspaceList,
     /// Register a workspace with the daemon
     RegisterWorkspace {
         /// The absolute path to the project root
@@ -649,7 +911,167 @@ pub enum VeloRequest {
         cas_root: Option<String>,
         /// Force full file read+hash, bypassing mtime+size cache skip (P0)
         force_hash: bool,
+        /// Answer with the ingest's `PlanAck` (files to hash, move or link)
+        /// without touching the source, the CAS or the manifest. Only
+        /// vriftd plans ingests.
+        dry_run: bool,
+        /// Ingest only part of `path`; `None` takes in all of it. Boxed so
+        /// the request keeps its archived size.
+        selection: Option<Box<IngestSelection>>,
+    },
+    /// Shim → vDird: newest VDir mmap version this reader understands.
+    /// vDird falls back to emitting that version if it currently writes a newer one.
+    VDirNegotiate {
+        max_version: u32,
+    },
+    /// CLI → vDird: reingest coalescing counters
+    ReingestStats,
+    /// Shim → vDird: an untracked file was renamed over `new_path` on disk.
+    /// vDird hashes the real file now at `new_path` and, in one VDir write,
+    /// points `new_path` at it and drops any entry left for `old_path`.
+    ManifestRenameOver {
+        old_path: String,
+        new_path: String,
+    },
+    /// Shim → vDird: like `ManifestReingest`, for a copy taken from content
+    /// `base_hash`. If the path no longer has that content, another writer
+    /// committed first and the reingest fails with `VeloErrorKind::Conflict`.
+    ManifestReingestChecked {
+        vpath: String,
+        temp_path: String,
+        base_hash: [u8; 32],
     },
+    /// Like `CasSweep`, deciding liveness from the reference counts under
+    /// the CAS root (`vrift_cas::RefCounts`) instead of a bloom filter. The
+    /// sender brings the counts up to date first.
+    CasSweepRefcounted {
+        /// As for `CasSweep`
+        dry_run: bool,
+    },
+    /// Shim → vriftd, at exit of a process traced with `VRIFT_PACK_TRACE`:
+    /// the blobs it opened, in first-access order. Appended to the access
+    /// profile of the registered workspace.
+    PackTraceRecord {
+        hashes: Vec<[u8; 32]>,
+    },
+    /// Queue a build of the registered workspace's hot packfile from the
+    /// access profile traced so far (or saved by an earlier build). Answered
+    /// immediately with the new job's `JobAck`.
+    PackBuild {
+        /// Answer with the build's `PlanAck`, leaving the trace running
+        dry_run: bool,
+    },
+    /// Shim → vriftd: the blob `hash` of a manifest entry (`size` bytes) is
+    /// missing from the local CAS; fetch it from the configured upstream.
+    /// Answered with `CasFound` once the blob is in place, else `CasNotFound`.
+    CasFetch {
+        hash: [u8; 32],
+        size: u64,
+    },
+    /// Look up several paths in one round trip; answered with
+    /// `ManifestManyAck`, one entry per path in request order
+    ManifestGetMany {
+        paths: Vec<String>,
+    },
+    /// Turn this connection into an event stream. Answered with
+    /// `SubscribeAck` naming the kinds this daemon pushes; if there are any,
+    /// only `Event` frames follow, and the daemon closes the connection
+    /// when the client sends anything else.
+    Subscribe {
+        events: Vec<EventKind>,
+    },
+    /// First frame on a TCP connection to a daemon with a `[tcp]
+    /// token_file`: every other request is refused until it is answered
+    /// with `AuthAck`. Unix socket connections need no token; the socket's
+    /// permissions guard them.
+    Authenticate {
+        token: String,
+    },
+    /// CLI → vDird: request and lookup counters since vDird started
+    Metrics,
+    /// Queue a re-hash of every blob in the CAS. Answered immediately with
+    /// the new job's `JobAck`; the job counts corrupt blobs as `affected`.
+    CasScrub {
+        /// Remove the corrupt blobs, so the next ingest or upstream fetch
+        /// stores them again
+        delete_corrupt: bool,
+    },
+    /// Queue a read of the registered workspace's profiled blobs (see
+    /// `PackBuild`) into the page cache. Answered immediately with the new
+    /// job's `JobAck`.
+    PackPrefetch,
+    /// vriftd → vDird: the project's packfile was rebuilt; point the VDir
+    /// entries at their blobs in it. Answered with `StatusAck`.
+    PackReload,
+}
+
+impl VeloRequest {
+    /// Short read-only query a client blocks on: stat and readdir
+    /// fallbacks, change polling, status probes. vDird also serves these on
+    /// its control socket (see [`control_socket_path`]), where they never
+    /// queue behind ingests or write-backs.
+    pub fn is_control(&self) -> bool {
+        matches!(
+            self,
+            VeloRequest::Handshake { .. }
+                | VeloRequest::Status
+                | VeloRequest::ManifestGet { .. }
+                | VeloRequest::ManifestGetMany { .. }
+                | VeloRequest::ManifestListDir { .. }
+                | VeloRequest::ManifestChangesSince { .. }
+                | VeloRequest::ReingestStats
+                | VeloRequest::Metrics
+        )
+    }
+
+    /// Variant name, for logs and for keying canned responses in tests
+    pub fn kind(&self) -> &'static str {
+        match self {
+            VeloRequest::Handshake { .. } => "Handshake",
+            VeloRequest::Status => "Status",
+            VeloRequest::Ping => "Ping",
+            VeloRequest::Spawn { .. } => "Spawn",
+            VeloRequest::CasInsert { .. } => "CasInsert",
+            VeloRequest::CasGet { .. } => "CasGet",
+            VeloRequest::Protect { .. } => "Protect",
+            VeloRequest::ManifestGet { .. } => "ManifestGet",
+            VeloRequest::ManifestUpsert { .. } => "ManifestUpsert",
+            VeloRequest::ManifestRemove { .. } => "ManifestRemove",
+            VeloRequest::ManifestRename { .. } => "ManifestRename",
+            VeloRequest::ManifestUpdateMtime { .. } => "ManifestUpdateMtime",
+            VeloRequest::ManifestReingest { .. } => "ManifestReingest",
+            VeloRequest::ManifestListDir { .. } => "ManifestListDir",
+            VeloRequest::ManifestChangesSince { .. } => "ManifestChangesSince",
+            VeloRequest::FlockAcquire { .. } => "FlockAcquire",
+            VeloRequest::FlockRelease { .. } => "FlockRelease",
+            VeloRequest::CasSweep { .. } => "CasSweep",
+            VeloRequest::JobList => "JobList",
+            VeloRequest::JobStatus { .. } => "JobStatus",
+            VeloRequest::JobCancel { .. } => "JobCancel",
+            VeloRequest::JobRetry { .. } => "JobRetry",
+            VeloRequest::SessionRegister { .. } => "SessionRegister",
+            VeloRequest::SessionWriteBack { .. } => "SessionWriteBack",
+            VeloRequest::SessionList => "SessionList",
+            VeloRequest::WorkspaceList => "WorkspaceList",
+            VeloRequest::RegisterWorkspace { .. } => "RegisterWorkspace",
+            VeloRequest::IngestFullScan { .. } => "IngestFullScan",
+            VeloRequest::VDirNegotiate { .. } => "VDirNegotiate",
+            VeloRequest::ReingestStats => "ReingestStats",
+            VeloRequest::ManifestRenameOver { .. } => "ManifestRenameOver",
+            VeloRequest::ManifestReingestChecked { .. } => "ManifestReingestChecked",
+            VeloRequest::CasSweepRefcounted { .. } => "CasSweepRefcounted",
+            VeloRequest::PackTraceRecord { .. } => "PackTraceRecord",
+            VeloRequest::PackBuild { .. } => "PackBuild",
+            VeloRequest::CasFetch { .. } => "CasFetch",
+            VeloRequest::ManifestGetMany { .. } => "ManifestGetMany",
+            VeloRequest::Subscribe { .. } => "Subscribe",
+            VeloRequest::Authenticate { .. } => "Authenticate",
+            VeloRequest::Metrics => "Metrics",
+            VeloRequest::CasScrub { .. } => "CasScrub",
+            VeloRequest::PackPrefetch => "PackPrefetch",
+            VeloRequest::PackReload => "PackReload",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Archive, rkyv::Serialize, rkyv::Deserialize)]
@@ -658,6 +1080,396 @@ pub struct DirEntry {
     pub is_dir: bool,
 }
 
+/// Part of a directory an `IngestFullScan` takes in. Manifest entries
+/// outside it are left as they are.
+#[derive(
+    Debug,
+    Clone,
+    Default,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
+)]
+pub struct IngestSelection {
+    /// Files and directories, relative to the ingested one, to walk instead
+    /// of all of it
+    pub paths: Vec<String>,
+    /// Globs a file must match one of, when any are given
+    pub include: Vec<String>,
+    /// Globs of files to leave out
+    pub exclude: Vec<String>,
+}
+
+impl IngestSelection {
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty() && self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// `Some` unless it selects everything, for `IngestFullScan::selection`
+    pub fn boxed(self) -> Option<Box<Self>> {
+        (!self.is_empty()).then(|| Box::new(self))
+    }
+}
+
+/// A blob an ingest added to the CAS
+#[derive(Debug, Clone, Serialize, Deserialize, Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct IngestBlob {
+    /// Relative to the ingested directory
+    pub path: String,
+    pub size: u64,
+}
+
+impl IngestBlob {
+    /// Blobs an [`VeloResponse::IngestAck`] lists
+    pub const MAX_LISTED: usize = 5;
+
+    /// `path` relative to the ingested directory `root`
+    pub fn new(root: &std::path::Path, path: &std::path::Path, size: u64) -> Self {
+        let canon_root = root.canonicalize().ok();
+        let relative = canon_root
+            .as_deref()
+            .and_then(|r| path.strip_prefix(r).ok())
+            .or_else(|| path.strip_prefix(root).ok())
+            .unwrap_or(path);
+        Self {
+            path: path_key::from_path(relative).into_owned(),
+            size,
+        }
+    }
+}
+
+/// Kind of manifest mutation recorded in the vDird change feed
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
+)]
+pub enum ManifestChangeKind {
+    /// Path did not exist before this mutation
+    Created,
+    /// Content or metadata of an existing path changed
+    Modified,
+    /// Path was removed
+    Removed,
+    /// Source side of a rename (paired with `MovedTo` via `cookie`)
+    MovedFrom,
+    /// Destination side of a rename (paired with `MovedFrom` via `cookie`)
+    MovedTo,
+}
+
+/// Single entry of the manifest change feed
+#[derive(Debug, Clone, Serialize, Deserialize, Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct ManifestChange {
+    /// Monotonic sequence number assigned by vDird
+    pub seq: u64,
+    pub kind: ManifestChangeKind,
+    /// Manifest key of the affected path
+    pub path: String,
+    pub is_dir: bool,
+    /// Non-zero for rename pairs, identical on both halves
+    pub cookie: u32,
+}
+
+/// Process tracked as part of a workspace session
+#[derive(Debug, Clone, Serialize, Deserialize, Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct SessionProcess {
+    pub pid: u32,
+    pub ppid: u32,
+    pub command: String,
+    /// Unix timestamp (seconds) when the process was first reported
+    pub started_at: u64,
+    /// CoW write-backs committed by this process
+    pub writebacks: u64,
+    /// Virtual flocks currently held by this process
+    pub locks_held: u32,
+}
+
+/// Workspace session: the process tree rooted at the first process that
+/// reported itself without a tracked parent
+#[derive(Debug, Clone, Serialize, Deserialize, Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct SessionInfo {
+    pub session_id: u64,
+    pub project_root: String,
+    pub root_pid: u32,
+    /// Unix timestamp (seconds) when the session started
+    pub started_at: u64,
+    /// Write-backs committed by the session, including exited processes
+    pub writebacks: u64,
+    /// Live processes only
+    pub processes: Vec<SessionProcess>,
+}
+
+/// Kind of long-running daemon job
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
+)]
+pub enum JobKind {
+    /// Full-scan ingest of a directory
+    Ingest,
+    /// CAS garbage collection sweep
+    Sweep,
+    /// CAS integrity check
+    Scrub,
+    /// Blob repacking
+    Repack,
+    /// Page-cache warm-up of a workspace's profiled blobs
+    Prefetch,
+    /// Tier re-evaluation of a workspace
+    Retier,
+}
+
+/// Lifecycle of a daemon job
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
+)]
+pub enum JobState {
+    /// Waiting for another store-wide job to finish
+    Queued,
+    Running,
+    Completed,
+    Cancelled,
+    Failed,
+}
+
+impl JobState {
+    /// True once the job can no longer change
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            JobState::Completed | JobState::Cancelled | JobState::Failed
+        )
+    }
+}
+
+/// Snapshot of a daemon job
+#[derive(Debug, Clone, Serialize, Deserialize, Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct JobInfo {
+    pub job_id: u64,
+    pub kind: JobKind,
+    pub state: JobState,
+    /// What the job works on, e.g. the ingested path
+    pub description: String,
+    /// Items examined so far (files ingested, blobs scanned)
+    pub processed: u64,
+    /// Approximate number of items to examine (0 if unknown)
+    pub total_estimate: u64,
+    /// Items acted upon (new blobs stored, orphans deleted)
+    pub affected: u64,
+    /// Bytes acted upon (new bytes stored, bytes reclaimed)
+    pub bytes: u64,
+    /// Unix timestamp (seconds) when the job was submitted
+    pub created_at: u64,
+    /// Run time so far, or total run time once finished
+    pub elapsed_ms: u64,
+    /// Job this one retries
+    pub retry_of: Option<u64>,
+    /// Failure reason for `Failed`
+    pub error: Option<String>,
+}
+
+/// What a step of a [`Plan`] does
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum PlanAction {
+    /// Hash a file into the CAS, leaving it in place
+    Hash,
+    /// Hash a file into the CAS and replace it with a link to its blob
+    Link,
+    /// Move a file into the CAS (phantom ingest)
+    Move,
+    /// Record an unreferenced blob, to be deleted once the grace period is over
+    Mark,
+    /// Delete a blob
+    Delete,
+    /// Copy a blob into the workspace's packfile
+    Pack,
+    /// Leave a file out of the CAS, by `[ingest.skip]`
+    Skip,
+}
+
+impl PlanAction {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PlanAction::Hash => "hash",
+            PlanAction::Link => "link",
+            PlanAction::Move => "move",
+            PlanAction::Mark => "mark",
+            PlanAction::Delete => "delete",
+            PlanAction::Pack => "pack",
+            PlanAction::Skip => "skip",
+        }
+    }
+}
+
+/// One step of a [`Plan`]
+#[derive(Debug, Clone, Serialize, Deserialize, Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct PlanStep {
+    pub action: PlanAction,
+    /// The file acted on, or the hex hash of the blob
+    pub target: String,
+    /// Size of the file or blob
+    pub bytes: u64,
+}
+
+/// Steps of one action in a [`Plan`], listed or not
+#[derive(Debug, Clone, Serialize, Deserialize, Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct PlanTotal {
+    pub action: PlanAction,
+    pub steps: u64,
+    pub bytes: u64,
+}
+
+/// Everything a mutating request would do, answered for its `dry_run`
+/// instead of doing it
+#[derive(
+    Debug, Clone, Default, Serialize, Deserialize, Archive, rkyv::Serialize, rkyv::Deserialize,
+)]
+pub struct Plan {
+    /// Up to [`Plan::MAX_STEPS`] of them
+    pub steps: Vec<PlanStep>,
+    /// Per action, over all steps
+    pub totals: Vec<PlanTotal>,
+    /// Files an ingest would leave alone, their mtime and size unchanged
+    /// since the last one
+    pub unchanged: u64,
+}
+
+impl Plan {
+    /// Steps listed in a plan; the ones beyond only count towards
+    /// [`totals`](Self::totals), which keeps the plan of a large store
+    /// within a frame
+    pub const MAX_STEPS: usize = 100_000;
+
+    pub fn push(&mut self, action: PlanAction, target: impl Into<String>, bytes: u64) {
+        match self.totals.iter_mut().find(|t| t.action == action) {
+            Some(total) => {
+                total.steps += 1;
+                total.bytes += bytes;
+            }
+            None => self.totals.push(PlanTotal {
+                action,
+                steps: 1,
+                bytes,
+            }),
+        }
+        if self.steps.len() < Self::MAX_STEPS {
+            self.steps.push(PlanStep {
+                action,
+                target: target.into(),
+                bytes,
+            });
+        }
+    }
+
+    /// Steps counted in the totals but not listed
+    pub fn omitted(&self) -> u64 {
+        self.totals.iter().map(|t| t.steps).sum::<u64>() - self.steps.len() as u64
+    }
+}
+
+/// Load state of a workspace's vDird
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
+)]
+pub enum WorkspaceState {
+    /// vDird spawned, manifest and VDir mmap still loading
+    Loading,
+    /// vDird is serving requests
+    Ready,
+    /// vDird failed to come up; the next registration retries
+    Failed,
+}
+
+/// Workspace known to the daemon, with its readiness
+#[derive(Debug, Clone, Serialize, Deserialize, Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct WorkspaceInfo {
+    pub project_root: String,
+    pub state: WorkspaceState,
+    /// Time spent loading so far (Loading) or total load time (Ready/Failed)
+    pub load_ms: u64,
+    /// vDird pid, 0 if none is running
+    pub vdird_pid: u32,
+    /// Failure reason for `Failed`
+    pub error: Option<String>,
+}
+
+/// Activity of a workspace's vDird since it started. Counters only grow;
+/// clients diff two snapshots for rates.
+#[derive(
+    Debug, Clone, Default, Serialize, Deserialize, Archive, rkyv::Serialize, rkyv::Deserialize,
+)]
+pub struct WorkspaceMetrics {
+    pub uptime_secs: u64,
+    /// Requests served, by [`VeloRequest::kind`]
+    pub requests: Vec<NamedCount>,
+    /// Manifest lookups answered from the VDir overlay
+    pub vdir_hits: u64,
+    /// Manifest lookups answered from LMDB
+    pub lmdb_hits: u64,
+    /// Manifest lookups of unknown paths
+    pub misses: u64,
+    /// Most looked-up paths, most first
+    pub hot_paths: Vec<NamedCount>,
+}
+
+/// A counter with its name (request kind, path)
+#[derive(
+    Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Archive, rkyv::Serialize, rkyv::Deserialize,
+)]
+pub struct NamedCount {
+    pub name: String,
+    pub count: u64,
+}
+
 #[cfg(feature = "manifest")]
 pub use vrift_manifest::VnodeEntry;
 
@@ -677,7 +1489,7 @@ pub use vrift_manifest::VnodeEntry;
 pub struct VnodeEntry {
     pub content_hash: [u8; 32],
     pub size: u64,
-    pub mtime: u64,
+    pub mtime: i64,
     pub mode: u32,
     pub flags: u16,
     #[serde(skip)]
@@ -685,11 +1497,46 @@ pub struct VnodeEntry {
     pub _pad: u16,
 }
 
+// Mirrors the vrift-manifest API the shim relies on
 #[cfg(not(feature = "manifest"))]
 impl VnodeEntry {
+    pub const FLAG_COMPRESSED: u16 = vdir_types::FLAG_COMPRESSED;
+    pub const FLAG_ENCRYPTED: u16 = vdir_types::FLAG_ENCRYPTED;
+    pub const FLAG_INLINE: u16 = vdir_types::FLAG_INLINE;
+    pub const FLAG_EXTERNAL: u16 = vdir_types::FLAG_EXTERNAL;
+    pub const STORAGE_MASK: u16 = vdir_types::FLAG_STORAGE_MASK;
+    pub const INLINE_MAX: usize = 32;
+
     pub fn is_dir(&self) -> bool {
         (self.flags & 1) != 0
     }
+
+    pub fn is_compressed(&self) -> bool {
+        self.flags & Self::FLAG_COMPRESSED != 0
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.flags & Self::FLAG_ENCRYPTED != 0
+    }
+
+    pub fn is_inline(&self) -> bool {
+        self.flags & Self::FLAG_INLINE != 0
+    }
+
+    pub fn is_external(&self) -> bool {
+        self.flags & Self::FLAG_EXTERNAL != 0
+    }
+
+    pub fn inline_content(&self) -> Option<&[u8]> {
+        if !self.is_inline() {
+            return None;
+        }
+        self.content_hash.get(..usize::try_from(self.size).ok()?)
+    }
+
+    pub fn is_blob_raw(&self) -> bool {
+        self.flags & Self::STORAGE_MASK == 0
+    }
 }
 
 // ============================================================================
@@ -726,6 +1573,8 @@ pub enum VeloErrorKind {
     LockFailed,
     /// Internal server error
     Internal,
+    /// The path changed since the client took its copy (concurrent write)
+    Conflict,
 }
 
 /// Structured error for IPC responses
@@ -806,21 +1655,50 @@ impl VeloError {
     /// - 22: Invalid argument (InvalidPath)
     /// - 77: Permission denied (PermissionDenied)
     /// - 78: Lock failure (LockFailed)
-    /// - 79: Ingest failure (IngestFailed)
+    /// - 79: Ingest failure (IngestFailed, Conflict)
     pub fn exit_code(&self) -> i32 {
-        match self.kind {
-            VeloErrorKind::NotFound => 2,
-            VeloErrorKind::WorkspaceNotRegistered => 2,
-            VeloErrorKind::InvalidPath => 22,
-            VeloErrorKind::PermissionDenied => 77,
-            VeloErrorKind::LockFailed => 78,
-            VeloErrorKind::IngestFailed => 79,
-            VeloErrorKind::IoError => 1,
-            VeloErrorKind::Internal => 1,
+        ErrorKind::from(self.kind).exit_code()
+    }
+}
+
+impl From<VeloErrorKind> for ErrorKind {
+    fn from(kind: VeloErrorKind) -> Self {
+        match kind {
+            VeloErrorKind::NotFound | VeloErrorKind::WorkspaceNotRegistered => ErrorKind::NotFound,
+            VeloErrorKind::PermissionDenied => ErrorKind::PermissionDenied,
+            VeloErrorKind::InvalidPath => ErrorKind::InvalidInput,
+            VeloErrorKind::IngestFailed | VeloErrorKind::Conflict => ErrorKind::IngestFailed,
+            VeloErrorKind::IoError => ErrorKind::Io,
+            VeloErrorKind::LockFailed => ErrorKind::LockFailed,
+            VeloErrorKind::Internal => ErrorKind::Internal,
         }
     }
 }
 
+/// Lossy: kinds without a wire equivalent travel as `Internal`, with the
+/// original cause still in the message.
+impl From<ErrorKind> for VeloErrorKind {
+    fn from(kind: ErrorKind) -> Self {
+        match kind {
+            ErrorKind::NotFound => VeloErrorKind::NotFound,
+            ErrorKind::PermissionDenied => VeloErrorKind::PermissionDenied,
+            ErrorKind::InvalidInput => VeloErrorKind::InvalidPath,
+            ErrorKind::IngestFailed => VeloErrorKind::IngestFailed,
+            ErrorKind::Io => VeloErrorKind::IoError,
+            ErrorKind::LockFailed => VeloErrorKind::LockFailed,
+            ErrorKind::Corrupted | ErrorKind::Unavailable | ErrorKind::Internal => {
+                VeloErrorKind::Internal
+            }
+        }
+    }
+}
+
+impl Classify for VeloError {
+    fn classify(&self) -> ErrorKind {
+        self.kind.into()
+    }
+}
+
 impl std::fmt::Display for VeloError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         if let Some(path) = &self.path {
@@ -845,6 +1723,13 @@ pub enum VeloResponse {
     StatusAck {
         status: String,
     },
+    /// Health probe reply
+    PingAck {
+        /// Daemon crate version
+        version: String,
+        uptime_secs: u64,
+        pid: u32,
+    },
     SpawnAck {
         pid: u32,
     },
@@ -860,14 +1745,33 @@ pub enum VeloResponse {
     ManifestListAck {
         entries: Vec<DirEntry>,
     },
+    /// Manifest change feed page
+    ManifestChanges {
+        /// Cursor to pass on the next poll
+        cursor: u64,
+        changes: Vec<ManifestChange>,
+        /// True if older changes were evicted before the client caught up
+        truncated: bool,
+    },
     ProtectAck,
-    /// Result of Garbage Collection sweep
-    CasSweepAck {
-        deleted_count: u32,
-        reclaimed_bytes: u64,
+    /// State of a single daemon job
+    JobAck {
+        job: JobInfo,
+    },
+    /// Recent daemon jobs, newest first
+    JobListAck {
+        jobs: Vec<JobInfo>,
     },
     /// RFC-0049: Acknowledgement for FlockAcquire/Release
     FlockAck,
+    /// Live workspace sessions
+    SessionListAck {
+        sessions: Vec<SessionInfo>,
+    },
+    /// Known workspaces and their readiness
+    WorkspaceListAck {
+        workspaces: Vec<WorkspaceInfo>,
+    },
     /// Acknowledge workspace registration
     RegisterAck {
         workspace_id: String,
@@ -876,23 +1780,142 @@ pub enum VeloResponse {
         /// VDir mmap file path for O(1) stat lookups
         vdir_mmap_path: String,
     },
-    /// Ingest completion acknowledgement
-    IngestAck {
-        /// Total files processed
-        files: u64,
-        /// Unique blobs stored
-        blobs: u64,
-        /// New bytes stored
-        new_bytes: u64,
-        /// Total bytes processed
-        total_bytes: u64,
-        /// Duration in milliseconds
-        duration_ms: u64,
-        /// Manifest path
-        manifest_path: String,
+    /// Ingest completion acknowledgement
+    IngestAck {
+        /// Total files processed
+        files: u64,
+        /// Unique blobs stored
+        blobs: u64,
+        /// New bytes stored
+        new_bytes: u64,
+        /// Total bytes processed
+        total_bytes: u64,
+        /// Duration in milliseconds
+        duration_ms: u64,
+        /// Manifest path
+        manifest_path: String,
+        /// Files this ingest wrote as Tier-1 (immutable)
+        tier1_files: u64,
+        /// Files this ingest wrote as Tier-2 (mutable)
+        tier2_files: u64,
+        /// Files left alone, their mtime and size unchanged since the last
+        /// ingest
+        unchanged_files: u64,
+        /// Files hashed whose content the CAS already had
+        deduped_files: u64,
+        /// Files the content scanner kept out of the manifest
+        quarantined_files: u64,
+        /// Largest blobs added, largest first
+        largest_new: Vec<IngestBlob>,
+        /// Files `[ingest.skip]` kept out of the CAS and the manifest, by
+        /// size or content
+        skipped_files: u64,
+    },
+    /// Structured error response (Phase 3: replaces Error(String))
+    Error(VeloError),
+    /// VDir mmap version in effect after negotiation
+    VDirNegotiateAck {
+        version: u32,
+    },
+    /// Reingests since vDird started
+    ReingestStatsAck {
+        /// Reingests hashed into the CAS
+        committed: u64,
+        /// Reingests dropped because a newer write of the same path arrived
+        /// within `ingest.dedup_window_ms`
+        coalesced: u64,
+        /// Paths waiting for their writes to quiesce
+        pending: u64,
+    },
+    /// What a dry run of a mutating request would do
+    PlanAck {
+        plan: Plan,
+    },
+    /// Entries for `ManifestGetMany`, in request order; `None` where the
+    /// path is unknown
+    ManifestManyAck {
+        entries: Vec<Option<VnodeEntry>>,
+    },
+    /// The requested kinds this daemon will push; empty if none, in which
+    /// case the connection stays a request connection
+    SubscribeAck {
+        events: Vec<EventKind>,
+    },
+    /// The connection may send requests now
+    AuthAck,
+    /// Counters of a vDird, for `vrift top`
+    MetricsAck {
+        metrics: WorkspaceMetrics,
+    },
+}
+
+/// What a [`VeloRequest::Subscribe`] can ask to be told about
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
+)]
+pub enum EventKind {
+    /// Manifest mutations of a workspace (vDird)
+    ManifestChange,
+    /// Ingest jobs starting, progressing and finishing (vriftd)
+    IngestProgress,
+    /// CAS sweep jobs starting, progressing and finishing (vriftd)
+    Gc,
+}
+
+/// Pushed in an [`FrameType::Event`] frame to a subscribed connection
+#[derive(Debug, Clone, Serialize, Deserialize, Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub enum VeloEvent {
+    ManifestChanged {
+        change: ManifestChange,
+    },
+    IngestProgress {
+        job: JobInfo,
     },
-    /// Structured error response (Phase 3: replaces Error(String))
-    Error(VeloError),
+    Gc {
+        job: JobInfo,
+    },
+    /// The subscriber fell behind and `missed` events were dropped; it
+    /// should rescan what it tracks
+    Lagged {
+        missed: u64,
+    },
+}
+
+impl VeloEvent {
+    /// Event reporting `job`, if its kind has one
+    pub fn for_job(job: JobInfo) -> Option<Self> {
+        match job.kind {
+            JobKind::Ingest => Some(VeloEvent::IngestProgress { job }),
+            JobKind::Sweep => Some(VeloEvent::Gc { job }),
+            _ => None,
+        }
+    }
+
+    /// Kind a subscriber asks for to receive this event; `None` for
+    /// `Lagged`, which every subscriber receives
+    pub fn kind(&self) -> Option<EventKind> {
+        match self {
+            VeloEvent::ManifestChanged { .. } => Some(EventKind::ManifestChange),
+            VeloEvent::IngestProgress { .. } => Some(EventKind::IngestProgress),
+            VeloEvent::Gc { .. } => Some(EventKind::Gc),
+            VeloEvent::Lagged { .. } => None,
+        }
+    }
+}
+
+/// vDird's control socket, next to its data socket `socket_path`. It serves
+/// only [`VeloRequest::is_control`] queries, over their own accept loop.
+pub fn control_socket_path(socket_path: &str) -> String {
+    format!("{}.ctl", socket_path)
 }
 
 /// Check if a protocol version is compatible with this build
@@ -910,14 +1933,64 @@ pub const DEFAULT_SOCKET_PATH: &str = "/tmp/vrift.sock";
 /// Default CAS root path
 pub const DEFAULT_CAS_ROOT: &str = "~/.vrift/the_source";
 
+/// Set to `1` in the environment of the daemons vrift spawns: the inception
+/// layer stays in passthrough there even if it was preloaded
+pub const NO_INCEPTION_ENV: &str = "VRIFT_NO_INCEPTION";
+
+/// Token [`client::DaemonClient::connect_to`] authenticates TCP
+/// connections with
+pub const DAEMON_TOKEN_ENV: &str = "VRIFT_DAEMON_TOKEN";
+
 /// Get default socket path
 fn default_socket_path() -> String {
     DEFAULT_SOCKET_PATH.to_string()
 }
 
+/// Where a daemon is reached: `unix:///run/vrift/daemon.sock` (or just the
+/// path) or `tcp://host:port`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DaemonAddr {
+    Unix(std::path::PathBuf),
+    /// `host:port`, connected to with the `tcp` feature
+    Tcp(String),
+}
+
+impl DaemonAddr {
+    pub fn parse(uri: &str) -> Result<Self, String> {
+        if let Some(path) = uri.strip_prefix("unix://") {
+            if path.is_empty() {
+                return Err(format!("No socket path in '{}'", uri));
+            }
+            return Ok(Self::Unix(path.into()));
+        }
+        if let Some(addr) = uri.strip_prefix("tcp://") {
+            let addr = addr.trim_end_matches('/');
+            match addr.rsplit_once(':') {
+                Some((host, port)) if !host.is_empty() && port.parse::<u16>().is_ok() => {
+                    return Ok(Self::Tcp(addr.to_string()))
+                }
+                _ => return Err(format!("Expected tcp://host:port, got '{}'", uri)),
+            }
+        }
+        match uri.split_once("://") {
+            Some((scheme, _)) => Err(format!("Unsupported daemon address scheme '{}'", scheme)),
+            None => Ok(Self::Unix(uri.into())),
+        }
+    }
+}
+
 #[cfg(feature = "cas")]
 pub use vrift_cas::{bloom_hashes, BloomFilter, BLOOM_SIZE};
 
+#[cfg(feature = "cas")]
+pub use vrift_cas::path_key;
+
+/// Both sides of a manifest key must escape non-UTF-8 bytes alike, so the
+/// shim (built without `cas`) compiles the same source in
+#[cfg(not(feature = "cas"))]
+#[path = "../../vrift-cas/src/path_key.rs"]
+pub mod path_key;
+
 #[cfg(not(feature = "cas"))]
 pub const BLOOM_SIZE: usize = 32 * 1024;
 
@@ -938,13 +2011,20 @@ pub fn bloom_hashes(s: &str) -> (usize, usize) {
 
 /// Magic number for manifest mmap file: "VMMP" (Vrift Manifest MmaP)
 pub const MMAP_MAGIC: u32 = 0x504D4D56;
-/// Current mmap format version
-pub const MMAP_VERSION: u32 = 1;
+/// Current mmap format version (v2: seqlock `generation` in the header)
+pub const MMAP_VERSION: u32 = 2;
+/// Oldest mmap format version still read
+pub const MMAP_MIN_VERSION: u32 = 1;
 /// Maximum entries in the hash table (power of 2 for fast modulo)
 pub const MMAP_MAX_ENTRIES: usize = 65536;
 
 /// Header for the mmap'd manifest file
-/// Layout: [Header][Bloom Filter][Hash Table]
+/// Layout: [Header][Bloom Filter][Hash Table][Dir Index][Children]
+///
+/// v1 headers end before `generation` (40 bytes). From v2 it is a seqlock:
+/// a writer makes it odd while it changes a slot and even again after, and
+/// readers retry a lookup that saw it odd or changing. Files are only
+/// rewritten whole now; in-place updates go to the VDir.
 #[deprecated(note = "Phase 2: Use VDirHeader from vrift-vdird instead (MAP_SHARED + seqlock)")]
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
@@ -959,11 +2039,20 @@ pub struct ManifestMmapHeader {
     pub dir_index_capacity: u32, // Capacity of directory index table
     pub children_offset: u32,    // Offset to children pool
     pub children_count: u32,     // Total children across all directories
+    pub generation: u64,         // Seqlock counter (v2+), must be AtomicU64-aligned
 }
 
+/// Offset of `ManifestMmapHeader::generation`
+pub const MMAP_HDR_GENERATION: usize = 40;
+
+#[allow(deprecated)]
+const _: () = assert!(std::mem::offset_of!(ManifestMmapHeader, generation) == MMAP_HDR_GENERATION);
+
 #[allow(deprecated)]
 impl ManifestMmapHeader {
     pub const SIZE: usize = std::mem::size_of::<Self>();
+    /// Size of a v1 header, which has no `generation`
+    pub const V1_SIZE: usize = MMAP_HDR_GENERATION;
 
     pub fn new(
         entry_count: u32,
@@ -988,14 +2077,85 @@ impl ManifestMmapHeader {
             dir_index_capacity,
             children_offset,
             children_count,
+            generation: 0,
         }
     }
 
+    /// Whether this is a manifest mmap in a version this build reads
     pub fn is_valid(&self) -> bool {
-        self.magic == MMAP_MAGIC && self.version == MMAP_VERSION
+        self.magic == MMAP_MAGIC && (MMAP_MIN_VERSION..=MMAP_VERSION).contains(&self.version)
+    }
+
+    /// Whether the header carries the seqlock `generation`
+    pub fn has_generation(&self) -> bool {
+        self.version >= 2
+    }
+
+    /// Length of a file with this header: the children pool comes last
+    pub fn file_size(&self) -> usize {
+        self.children_offset as usize + self.children_count as usize * MmapDirChild::SIZE
+    }
+
+    /// Decode from the start of `bytes` (little-endian, any alignment)
+    pub fn read_le(bytes: &[u8]) -> Option<Self> {
+        let b = bytes.get(..Self::V1_SIZE)?;
+        let mut header = Self {
+            magic: le_u32(b, 0),
+            version: le_u32(b, 4),
+            entry_count: le_u32(b, 8),
+            bloom_offset: le_u32(b, 12),
+            table_offset: le_u32(b, 16),
+            table_capacity: le_u32(b, 20),
+            dir_index_offset: le_u32(b, 24),
+            dir_index_capacity: le_u32(b, 28),
+            children_offset: le_u32(b, 32),
+            children_count: le_u32(b, 36),
+            generation: 0,
+        };
+        if header.has_generation() {
+            header.generation = le_u64(bytes.get(..Self::SIZE)?, MMAP_HDR_GENERATION);
+        }
+        Some(header)
+    }
+
+    /// On-disk (little-endian) image
+    pub fn to_le_bytes(&self) -> [u8; Self::SIZE] {
+        let mut out = [0u8; Self::SIZE];
+        for (i, v) in [
+            self.magic,
+            self.version,
+            self.entry_count,
+            self.bloom_offset,
+            self.table_offset,
+            self.table_capacity,
+            self.dir_index_offset,
+            self.dir_index_capacity,
+            self.children_offset,
+            self.children_count,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            out[i * 4..i * 4 + 4].copy_from_slice(&v.to_le_bytes());
+        }
+        out[MMAP_HDR_GENERATION..].copy_from_slice(&self.generation.to_le_bytes());
+        out
     }
 }
 
+// Field decoders for the legacy mmap structs. Callers bound-check the slice.
+#[inline(always)]
+fn le_u32(b: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([b[at], b[at + 1], b[at + 2], b[at + 3]])
+}
+
+#[inline(always)]
+fn le_u64(b: &[u8], at: usize) -> u64 {
+    let mut v = [0u8; 8];
+    v.copy_from_slice(&b[at..at + 8]);
+    u64::from_le_bytes(v)
+}
+
 /// Single stat entry in the hash table
 /// Uses open addressing with linear probing
 #[deprecated(note = "Phase 2: Use VDirEntry from vrift-vdird instead")]
@@ -1007,13 +2167,37 @@ pub struct MmapStatEntry {
     pub mtime: i64,
     pub mtime_nsec: i64,
     pub mode: u32,
-    pub flags: u32, // EntryFlags: is_dir, is_symlink, etc.
+    pub flags: u32, // EntryFlags: is_dir, is_symlink, etc., plus storage bits
 }
 
 #[allow(deprecated)]
 impl MmapStatEntry {
     pub const SIZE: usize = std::mem::size_of::<Self>();
 
+    /// Entry for `path`, arguments as for [`ManifestMmapBuilder::add_entry`]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        path: &str,
+        size: u64,
+        mtime_ns: i64,
+        mode: u32,
+        is_dir: bool,
+        is_symlink: bool,
+        storage: u16,
+    ) -> Self {
+        let (mtime, mtime_nsec) = mtime::to_parts(mtime_ns);
+        Self {
+            path_hash: fnv1a_hash(path),
+            size,
+            mtime,
+            mtime_nsec: i64::from(mtime_nsec),
+            mode,
+            flags: if is_dir { 0x01 } else { 0 }
+                | if is_symlink { 0x02 } else { 0 }
+                | u32::from(storage & vdir_types::FLAG_STORAGE_MASK),
+        }
+    }
+
     pub fn is_empty(&self) -> bool {
         self.path_hash == 0
     }
@@ -1025,6 +2209,37 @@ impl MmapStatEntry {
     pub fn is_symlink(&self) -> bool {
         (self.flags & 0x02) != 0
     }
+
+    /// `vdir_types::FLAG_COMPRESSED` / `FLAG_ENCRYPTED` / `FLAG_INLINE` /
+    /// `FLAG_EXTERNAL` bits
+    pub fn storage_flags(&self) -> u16 {
+        (self.flags & u32::from(vdir_types::FLAG_STORAGE_MASK)) as u16
+    }
+
+    /// Decode from the start of `bytes` (little-endian, any alignment)
+    pub fn read_le(bytes: &[u8]) -> Option<Self> {
+        let b = bytes.get(..Self::SIZE)?;
+        Some(Self {
+            path_hash: le_u64(b, 0),
+            size: le_u64(b, 8),
+            mtime: le_u64(b, 16) as i64,
+            mtime_nsec: le_u64(b, 24) as i64,
+            mode: le_u32(b, 32),
+            flags: le_u32(b, 36),
+        })
+    }
+
+    /// On-disk (little-endian) image
+    pub fn to_le_bytes(&self) -> [u8; Self::SIZE] {
+        let mut out = [0u8; Self::SIZE];
+        out[0..8].copy_from_slice(&self.path_hash.to_le_bytes());
+        out[8..16].copy_from_slice(&self.size.to_le_bytes());
+        out[16..24].copy_from_slice(&self.mtime.to_le_bytes());
+        out[24..32].copy_from_slice(&self.mtime_nsec.to_le_bytes());
+        out[32..36].copy_from_slice(&self.mode.to_le_bytes());
+        out[36..40].copy_from_slice(&self.flags.to_le_bytes());
+        out
+    }
 }
 
 /// Directory index entry (parent -> children)
@@ -1038,6 +2253,25 @@ pub struct MmapDirIndexEntry {
 
 impl MmapDirIndexEntry {
     pub const SIZE: usize = std::mem::size_of::<Self>();
+
+    /// Decode from the start of `bytes` (little-endian, any alignment)
+    pub fn read_le(bytes: &[u8]) -> Option<Self> {
+        let b = bytes.get(..Self::SIZE)?;
+        Some(Self {
+            parent_hash: le_u64(b, 0),
+            children_start: le_u32(b, 8),
+            children_count: le_u32(b, 12),
+        })
+    }
+
+    /// On-disk (little-endian) image
+    pub fn to_le_bytes(&self) -> [u8; Self::SIZE] {
+        let mut out = [0u8; Self::SIZE];
+        out[0..8].copy_from_slice(&self.parent_hash.to_le_bytes());
+        out[8..12].copy_from_slice(&self.children_start.to_le_bytes());
+        out[12..16].copy_from_slice(&self.children_count.to_le_bytes());
+        out
+    }
 }
 
 /// Child entry in the directory listing
@@ -1061,6 +2295,28 @@ impl MmapDirChild {
             .unwrap_or(self.name.len());
         std::str::from_utf8(&self.name[..len]).unwrap_or("")
     }
+
+    /// Decode from the start of `bytes` (little-endian, any alignment)
+    pub fn read_le(bytes: &[u8]) -> Option<Self> {
+        let b = bytes.get(..Self::SIZE)?;
+        let mut name = [0u8; 128];
+        name.copy_from_slice(&b[..128]);
+        Some(Self {
+            name,
+            stat_index: le_u32(b, 128),
+            is_dir: b[132],
+            _pad: [0; 3],
+        })
+    }
+
+    /// On-disk (little-endian) image
+    pub fn to_le_bytes(&self) -> [u8; Self::SIZE] {
+        let mut out = [0u8; Self::SIZE];
+        out[..128].copy_from_slice(&self.name);
+        out[128..132].copy_from_slice(&self.stat_index.to_le_bytes());
+        out[132] = self.is_dir;
+        out
+    }
 }
 
 /// Calculate FNV-1a hash for path strings (deterministic, no alloc)
@@ -1077,7 +2333,23 @@ pub fn fnv1a_hash(s: &str) -> u64 {
     hash
 }
 
-/// Calculate total mmap file size for given capacities
+/// Second 64-bit hash of a path for [`vdir_types::VDirEntry::path_check`].
+/// It mixes
+/// every byte through a multiply and xor-shift rather than FNV's xor and
+/// multiply, so paths that collide under [`fnv1a_hash`] still differ here.
+#[inline(always)]
+pub fn path_fingerprint(s: &str) -> u64 {
+    const K: u64 = 0x9E37_79B9_7F4A_7C15;
+    let mut hash = (s.len() as u64).wrapping_mul(K);
+    for byte in s.as_bytes() {
+        hash = (hash ^ u64::from(*byte)).wrapping_mul(K);
+        hash ^= hash >> 29;
+    }
+    hash ^= hash >> 32;
+    hash.wrapping_mul(K) ^ (hash >> 29)
+}
+
+/// Calculate total mmap file size for given capacities (current version)
 #[allow(deprecated)]
 pub fn mmap_file_size(
     table_capacity: usize,
@@ -1091,6 +2363,44 @@ pub fn mmap_file_size(
         + (children_count * MmapDirChild::SIZE)
 }
 
+/// Shape of a manifest mmap file, as [`ManifestMmapBuilder::write_to_file`]
+/// laid it out
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MmapBuildStats {
+    pub entries: usize,
+    /// Slots in the stat hash table
+    pub table_capacity: usize,
+    /// Longest probe sequence an entry needed to find its slot (1 = its home
+    /// slot)
+    pub max_probe: usize,
+    pub bloom_bits_set: usize,
+    pub bloom_bits: usize,
+    /// Directories with an index entry
+    pub dirs: usize,
+    /// Entries of the directory children pool
+    pub children: usize,
+    pub file_bytes: usize,
+}
+
+impl MmapBuildStats {
+    /// Share of stat table slots in use
+    pub fn load_factor(&self) -> f64 {
+        if self.table_capacity == 0 {
+            return 0.0;
+        }
+        self.entries as f64 / self.table_capacity as f64
+    }
+
+    /// Share of bloom filter bits set; the false positive rate of a miss
+    /// grows with its square
+    pub fn bloom_fill(&self) -> f64 {
+        if self.bloom_bits == 0 {
+            return 0.0;
+        }
+        self.bloom_bits_set as f64 / self.bloom_bits as f64
+    }
+}
+
 /// Builder for creating mmap manifest files (RFC-0044 Hot Stat Cache)
 /// Used by daemon to export manifest to shared memory for O(1) shim access
 #[deprecated(note = "Phase 2: VDir mmap is now managed by vDird directly")]
@@ -1117,19 +2427,20 @@ impl ManifestMmapBuilder {
         }
     }
 
-    /// Add a manifest entry to the builder
+    /// Add a manifest entry to the builder; `mtime_ns` is split into the
+    /// entry's seconds and nanoseconds fields, and `storage` takes the
+    /// storage bits of the entry's flags
+    #[allow(clippy::too_many_arguments)]
     pub fn add_entry(
         &mut self,
         path: &str,
         size: u64,
-        mtime: i64,
+        mtime_ns: i64,
         mode: u32,
         is_dir: bool,
         is_symlink: bool,
+        storage: u16,
     ) {
-        let path_hash = fnv1a_hash(path);
-        let flags = if is_dir { 0x01 } else { 0 } | if is_symlink { 0x02 } else { 0 };
-
         // Add to bloom filter
         let (h1, h2) = bloom_hashes(path);
         let b1 = h1 % (BLOOM_SIZE * 8);
@@ -1137,19 +2448,12 @@ impl ManifestMmapBuilder {
         self.bloom[b1 / 8] |= 1 << (b1 % 8);
         self.bloom[b2 / 8] |= 1 << (b2 % 8);
 
-        let entry = MmapStatEntry {
-            path_hash,
-            size,
-            mtime,
-            mtime_nsec: 0,
-            mode,
-            flags,
-        };
+        let entry = MmapStatEntry::new(path, size, mtime_ns, mode, is_dir, is_symlink, storage);
         self.entries.push((path.to_string(), entry));
     }
 
     /// Write mmap file to disk (now includes directory indexing)
-    pub fn write_to_file(&self, path: &str) -> std::io::Result<()> {
+    pub fn write_to_file(&self, path: &str) -> std::io::Result<MmapBuildStats> {
         use std::collections::HashMap;
         use std::io::Write;
 
@@ -1193,31 +2497,17 @@ impl ManifestMmapBuilder {
             dir_index_capacity as u32,
             children_count as u32,
         );
-        let header_bytes = unsafe {
-            std::slice::from_raw_parts(&header as *const _ as *const u8, ManifestMmapHeader::SIZE)
-        };
-        buffer[..ManifestMmapHeader::SIZE].copy_from_slice(header_bytes);
+        buffer[..ManifestMmapHeader::SIZE].copy_from_slice(&header.to_le_bytes());
 
         // 5. Write bloom filter
         let bloom_start = header.bloom_offset as usize;
         buffer[bloom_start..bloom_start + BLOOM_SIZE].copy_from_slice(&self.bloom);
 
-        // DEBUG: Check bloom filter content
-        let bloom_set_bits: usize = self.bloom.iter().map(|b| b.count_ones() as usize).sum();
-        println!(
-            "[DEBUG-BUILDER] Bloom filter has {} set bits out of {} total bits",
-            bloom_set_bits,
-            BLOOM_SIZE * 8
-        );
-        println!(
-            "[DEBUG-BUILDER] Bloom filter first 32 bytes: {:?}",
-            &self.bloom[..32]
-        );
-
         // 6. Write stat hash table with linear probing
         // We'll also need a way to map original index to actual slot for dir entries
         let table_start = header.table_offset as usize;
         let mut index_to_slot = vec![0u32; self.entries.len()];
+        let mut max_probe = 0;
 
         for (idx, (_path, entry)) in self.entries.iter().enumerate() {
             let start_slot = (entry.path_hash as usize) % table_capacity;
@@ -1228,20 +2518,17 @@ impl ManifestMmapBuilder {
                 let existing_hash =
                     u64::from_le_bytes(buffer[offset..offset + 8].try_into().unwrap());
                 if existing_hash == 0 {
-                    let entry_bytes = unsafe {
-                        std::slice::from_raw_parts(
-                            entry as *const _ as *const u8,
-                            MmapStatEntry::SIZE,
-                        )
-                    };
-                    buffer[offset..offset + MmapStatEntry::SIZE].copy_from_slice(entry_bytes);
+                    buffer[offset..offset + MmapStatEntry::SIZE]
+                        .copy_from_slice(&entry.to_le_bytes());
                     index_to_slot[idx] = slot as u32;
+                    max_probe = max_probe.max(i + 1);
                     break;
                 }
             }
         }
 
         // 7. Write children pool and directory index
+        let dirs = dir_map.len();
         let dir_index_start = header.dir_index_offset as usize;
         let children_start = header.children_offset as usize;
         let mut current_child_idx = 0;
@@ -1263,13 +2550,8 @@ impl ManifestMmapBuilder {
                 let existing_hash =
                     u64::from_le_bytes(buffer[offset..offset + 8].try_into().unwrap());
                 if existing_hash == 0 {
-                    let entry_bytes = unsafe {
-                        std::slice::from_raw_parts(
-                            &dir_entry as *const _ as *const u8,
-                            MmapDirIndexEntry::SIZE,
-                        )
-                    };
-                    buffer[offset..offset + MmapDirIndexEntry::SIZE].copy_from_slice(entry_bytes);
+                    buffer[offset..offset + MmapDirIndexEntry::SIZE]
+                        .copy_from_slice(&dir_entry.to_le_bytes());
                     break;
                 }
             }
@@ -1291,10 +2573,7 @@ impl ManifestMmapBuilder {
                 child.name[..copy_len].copy_from_slice(&name_bytes[..copy_len]);
 
                 let offset = children_start + current_child_idx * MmapDirChild::SIZE;
-                let entry_bytes = unsafe {
-                    std::slice::from_raw_parts(&child as *const _ as *const u8, MmapDirChild::SIZE)
-                };
-                buffer[offset..offset + MmapDirChild::SIZE].copy_from_slice(entry_bytes);
+                buffer[offset..offset + MmapDirChild::SIZE].copy_from_slice(&child.to_le_bytes());
                 current_child_idx += 1;
             }
         }
@@ -1306,7 +2585,29 @@ impl ManifestMmapBuilder {
         file.sync_all()?;
         std::fs::rename(&temp_path, path)?;
 
-        Ok(())
+        let stats = MmapBuildStats {
+            entries: self.entries.len(),
+            table_capacity,
+            max_probe,
+            bloom_bits_set: self.bloom.iter().map(|b| b.count_ones() as usize).sum(),
+            bloom_bits: BLOOM_SIZE * 8,
+            dirs,
+            children: children_count,
+            file_bytes: file_size,
+        };
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            target: "vrift::mmap",
+            path,
+            entries = stats.entries,
+            load_factor = stats.load_factor(),
+            max_probe = stats.max_probe,
+            bloom_fill = stats.bloom_fill(),
+            children = stats.children,
+            file_bytes = stats.file_bytes,
+            "Manifest mmap written"
+        );
+        Ok(stats)
     }
 
     /// Get entry count
@@ -1319,20 +2620,92 @@ impl ManifestMmapBuilder {
     }
 }
 
+/// Stat entry with `path_hash` in the stat table of `bytes`, if any
+#[allow(deprecated)]
+fn mmap_find_stat(
+    bytes: &[u8],
+    header: &ManifestMmapHeader,
+    path_hash: u64,
+) -> Option<MmapStatEntry> {
+    let capacity = header.table_capacity as usize;
+    if capacity == 0 {
+        return None;
+    }
+    let start = (path_hash as usize) % capacity;
+    for i in 0..capacity {
+        let slot = (start + i) % capacity;
+        let offset = header.table_offset as usize + slot * MmapStatEntry::SIZE;
+        let entry = MmapStatEntry::read_le(bytes.get(offset..)?)?;
+        if entry.is_empty() {
+            return None;
+        }
+        if entry.path_hash == path_hash {
+            return Some(entry);
+        }
+    }
+    None
+}
+
+/// Stat entry of `path` in a manifest mmap of any supported version. On v2+
+/// files the lookup is seqlock-protected; None if `path` is absent, or if a
+/// writer stays mid-update (it may have crashed), so the caller falls back
+/// to IPC.
+///
+/// # Safety
+///
+/// `mmap_ptr` must point to `mmap_size` readable bytes that stay mapped for
+/// the duration of the call.
+#[allow(deprecated)]
+pub unsafe fn manifest_mmap_lookup(
+    mmap_ptr: *const u8,
+    mmap_size: usize,
+    path: &str,
+) -> Option<MmapStatEntry> {
+    use std::sync::atomic::{fence, AtomicU64, Ordering};
+
+    let bytes = unsafe { std::slice::from_raw_parts(mmap_ptr, mmap_size) };
+    let header = ManifestMmapHeader::read_le(bytes).filter(|h| h.is_valid())?;
+    let path_hash = fnv1a_hash(path);
+    if !header.has_generation() {
+        return mmap_find_stat(bytes, &header, path_hash);
+    }
+
+    debug_assert!((mmap_ptr as usize + MMAP_HDR_GENERATION).is_multiple_of(8));
+    let gen = unsafe { &*(mmap_ptr.add(MMAP_HDR_GENERATION) as *const AtomicU64) };
+    for _ in 0..vdir_types::MAX_SEQLOCK_SPINS {
+        let g1 = u64::from_le(gen.load(Ordering::Acquire));
+        if g1 & 1 == 0 {
+            let found = mmap_find_stat(bytes, &header, path_hash);
+            fence(Ordering::Acquire);
+            if u64::from_le(gen.load(Ordering::Relaxed)) == g1 {
+                return found;
+            }
+        }
+        core::hint::spin_loop();
+    }
+    None
+}
+
 /// Check if daemon is running (socket exists and connectable)
 pub fn is_daemon_running() -> bool {
     std::path::Path::new(&default_socket_path()).exists()
 }
 
 /// IPC Client for communicating with vrift-daemon
+///
+/// Internal to the workspace; tools outside it should use the `vrift-client`
+/// crate, whose API does not change with the wire protocol.
 #[cfg(feature = "tokio")]
 pub mod client {
     use super::*;
-    use std::path::Path;
+    use tokio::io::{AsyncRead, AsyncWrite};
     use tokio::net::UnixStream;
 
+    trait Stream: AsyncRead + AsyncWrite + Unpin + Send {}
+    impl<T: AsyncRead + AsyncWrite + Unpin + Send> Stream for T {}
+
     pub struct DaemonClient {
-        stream: UnixStream,
+        stream: Box<dyn Stream>,
     }
 
     impl DaemonClient {
@@ -1341,10 +2714,46 @@ pub mod client {
             Self::connect_to(&default_socket_path()).await
         }
 
-        /// Connect to daemon at custom socket path
-        pub async fn connect_to(socket_path: &str) -> anyhow::Result<Self> {
-            let stream = UnixStream::connect(Path::new(socket_path)).await?;
-            Ok(Self { stream })
+        /// Connect to the daemon at `uri` (see [`DaemonAddr`]). TCP
+        /// connections authenticate with [`DAEMON_TOKEN_ENV`] if it is set.
+        pub async fn connect_to(uri: &str) -> anyhow::Result<Self> {
+            let token = std::env::var(DAEMON_TOKEN_ENV).ok();
+            Self::connect_with_token(uri, token.as_deref()).await
+        }
+
+        /// Connect to the daemon at `uri`, presenting `token` first if it
+        /// is a TCP address
+        pub async fn connect_with_token(uri: &str, token: Option<&str>) -> anyhow::Result<Self> {
+            let addr = DaemonAddr::parse(uri).map_err(|e| anyhow::anyhow!(e))?;
+            let stream: Box<dyn Stream> = match &addr {
+                DaemonAddr::Unix(path) => Box::new(UnixStream::connect(path).await?),
+                #[cfg(feature = "tcp")]
+                DaemonAddr::Tcp(host) => {
+                    let stream = tokio::net::TcpStream::connect(host.as_str()).await?;
+                    stream.set_nodelay(true)?;
+                    Box::new(stream)
+                }
+                #[cfg(not(feature = "tcp"))]
+                DaemonAddr::Tcp(_) => {
+                    anyhow::bail!("'{}': vrift-ipc was built without the tcp feature", uri)
+                }
+            };
+            let mut client = Self { stream };
+            if let (DaemonAddr::Tcp(_), Some(token)) = (&addr, token) {
+                client.authenticate(token).await?;
+            }
+            Ok(client)
+        }
+
+        async fn authenticate(&mut self, token: &str) -> anyhow::Result<()> {
+            let request = VeloRequest::Authenticate {
+                token: token.to_string(),
+            };
+            match self.send(request).await? {
+                VeloResponse::AuthAck => Ok(()),
+                VeloResponse::Error(e) => anyhow::bail!("Authentication failed: {}", e),
+                _ => anyhow::bail!("Unexpected response"),
+            }
         }
 
         /// Send a request and receive response using v3 frame protocol
@@ -1399,6 +2808,19 @@ pub mod client {
                 _ => anyhow::bail!("Unexpected response"),
             }
         }
+
+        /// Health probe; returns (version, uptime in seconds, pid)
+        pub async fn ping(&mut self) -> anyhow::Result<(String, u64, u32)> {
+            match self.send(VeloRequest::Ping).await? {
+                VeloResponse::PingAck {
+                    version,
+                    uptime_secs,
+                    pid,
+                } => Ok((version, uptime_secs, pid)),
+                VeloResponse::Error(e) => anyhow::bail!("Ping failed: {}", e),
+                _ => anyhow::bail!("Unexpected response"),
+            }
+        }
     }
 }
 
@@ -1426,6 +2848,52 @@ mod tests {
         assert!(matches!(decoded, VeloResponse::StatusAck { .. }));
     }
 
+    #[test]
+    fn test_daemon_addr_parse() {
+        let unix = DaemonAddr::Unix("/run/vrift/daemon.sock".into());
+        assert_eq!(
+            DaemonAddr::parse("/run/vrift/daemon.sock"),
+            Ok(unix.clone())
+        );
+        assert_eq!(DaemonAddr::parse("unix:///run/vrift/daemon.sock"), Ok(unix));
+        assert_eq!(
+            DaemonAddr::parse("tcp://build-7.internal:7422"),
+            Ok(DaemonAddr::Tcp("build-7.internal:7422".to_string()))
+        );
+        assert_eq!(
+            DaemonAddr::parse("tcp://[::1]:7422/"),
+            Ok(DaemonAddr::Tcp("[::1]:7422".to_string()))
+        );
+        assert!(DaemonAddr::parse("tcp://build-7.internal").is_err());
+        assert!(DaemonAddr::parse("unix://").is_err());
+        assert!(DaemonAddr::parse("http://localhost:7420").is_err());
+    }
+
+    #[test]
+    fn test_ping_roundtrip() {
+        let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&VeloRequest::Ping).unwrap();
+        let decoded: VeloRequest =
+            rkyv::from_bytes::<VeloRequest, rkyv::rancor::Error>(&bytes).unwrap();
+        assert!(matches!(decoded, VeloRequest::Ping));
+
+        let resp = VeloResponse::PingAck {
+            version: "0.1.0".to_string(),
+            uptime_secs: 42,
+            pid: 1234,
+        };
+        let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&resp).unwrap();
+        let decoded: VeloResponse =
+            rkyv::from_bytes::<VeloResponse, rkyv::rancor::Error>(&bytes).unwrap();
+        assert!(matches!(
+            decoded,
+            VeloResponse::PingAck {
+                uptime_secs: 42,
+                pid: 1234,
+                ..
+            }
+        ));
+    }
+
     #[test]
     fn test_default_socket_path() {
         // Verify default socket path is set
@@ -1540,6 +3008,58 @@ mod tests {
         assert!(matches!(decoded, VeloResponse::StatusAck { .. }));
     }
 
+    #[test]
+    fn test_frame_sync_event_roundtrip() {
+        use crate::frame_sync;
+        use std::io::Cursor;
+
+        let event = VeloEvent::ManifestChanged {
+            change: ManifestChange {
+                seq: 9,
+                kind: ManifestChangeKind::Created,
+                path: "/src/a.rs".to_string(),
+                is_dir: false,
+                cookie: 0,
+            },
+        };
+        let mut buf = Vec::new();
+        frame_sync::send_heartbeat(&mut buf).unwrap();
+        frame_sync::send_event(&mut buf, &event, 7).unwrap();
+
+        let mut cursor = Cursor::new(&buf);
+        let (header, decoded) = frame_sync::read_event(&mut cursor).unwrap();
+        assert_eq!(header.seq_id, 7);
+        assert_eq!(header.frame_type(), Some(FrameType::Event));
+        assert_eq!(decoded.kind(), Some(EventKind::ManifestChange));
+        assert!(matches!(decoded, VeloEvent::ManifestChanged { change } if change.seq == 9));
+
+        // An event is not a response
+        let mut cursor = Cursor::new(&buf);
+        assert!(frame_sync::read_response(&mut cursor).is_err());
+    }
+
+    #[test]
+    fn test_job_events_follow_job_kind() {
+        let job = |kind| JobInfo {
+            job_id: 1,
+            kind,
+            state: JobState::Running,
+            description: String::new(),
+            processed: 0,
+            total_estimate: 0,
+            affected: 0,
+            bytes: 0,
+            created_at: 0,
+            elapsed_ms: 0,
+            retry_of: None,
+            error: None,
+        };
+        let kind = |k| VeloEvent::for_job(job(k)).and_then(|e| e.kind());
+        assert_eq!(kind(JobKind::Ingest), Some(EventKind::IngestProgress));
+        assert_eq!(kind(JobKind::Sweep), Some(EventKind::Gc));
+        assert_eq!(kind(JobKind::Retier), None);
+    }
+
     #[test]
     fn test_ipc_hardening_validation() {
         use crate::frame_sync;
@@ -1592,6 +3112,132 @@ mod tests {
             .contains("expected Request frame"));
     }
 
+    fn big_listing(n: usize) -> VeloResponse {
+        VeloResponse::ManifestListAck {
+            entries: (0..n)
+                .map(|i| DirEntry {
+                    name: format!("file_{:05}.rs", i),
+                    is_dir: false,
+                })
+                .collect(),
+        }
+    }
+
+    fn chunked(frame_type: FrameType, seq_id: u32, payload: &[u8], chunk: usize) -> Vec<u8> {
+        let mut buf = Vec::new();
+        for (header, part) in split_frames(frame_type, seq_id, payload, chunk).unwrap() {
+            buf.extend_from_slice(&header.to_bytes());
+            buf.extend_from_slice(part);
+        }
+        buf
+    }
+
+    #[test]
+    fn test_small_payload_stays_one_frame() {
+        let frames: Vec<_> = message_frames(FrameType::Response, 7, &[1, 2, 3])
+            .unwrap()
+            .collect();
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].0.flags, 0);
+        assert_eq!(frames[0].0.length, 3);
+
+        let empty: Vec<_> = message_frames(FrameType::Request, 8, &[])
+            .unwrap()
+            .collect();
+        assert_eq!(empty.len(), 1);
+        assert!(!empty[0].0.has_more());
+    }
+
+    #[test]
+    fn test_frame_sync_reassembles_continuations() {
+        use crate::frame_sync;
+        use std::io::Cursor;
+
+        let payload = rkyv::to_bytes::<rkyv::rancor::Error>(&big_listing(2000)).unwrap();
+        let chunk = 4096;
+        let mut buf = chunked(FrameType::Response, 9, &payload, chunk);
+        // A heartbeat before the next message is still skipped
+        frame_sync::send_heartbeat(&mut buf).unwrap();
+        frame_sync::send_response(&mut buf, &VeloResponse::CasNotFound, 10).unwrap();
+
+        let frames = payload.len().div_ceil(chunk);
+        assert!(frames > 2);
+        let second = IpcHeader::from_bytes(
+            buf[IpcHeader::SIZE + chunk..][..IpcHeader::SIZE]
+                .try_into()
+                .unwrap(),
+        );
+        assert_eq!(second.frame_type(), Some(FrameType::Continuation));
+        assert!(second.has_more());
+
+        let mut cursor = Cursor::new(&buf);
+        let (header, resp) = frame_sync::read_response(&mut cursor).unwrap();
+        assert_eq!(header.seq_id, 9);
+        match resp {
+            VeloResponse::ManifestListAck { entries } => {
+                assert_eq!(entries.len(), 2000);
+                assert_eq!(entries[1999].name, "file_01999.rs");
+            }
+            other => panic!("unexpected response: {:?}", other),
+        }
+        let (header, resp) = frame_sync::read_response(&mut cursor).unwrap();
+        assert_eq!(header.seq_id, 10);
+        assert!(matches!(resp, VeloResponse::CasNotFound));
+    }
+
+    #[test]
+    fn test_frame_sync_rejects_broken_continuations() {
+        use crate::frame_sync;
+        use std::io::Cursor;
+
+        let payload = rkyv::to_bytes::<rkyv::rancor::Error>(&big_listing(200)).unwrap();
+
+        // Continuation from another message
+        let mut buf = chunked(FrameType::Response, 1, &payload, 1024);
+        let second = IpcHeader::SIZE + 1024;
+        buf[second + 8..second + 12].copy_from_slice(&2u32.to_le_bytes());
+        let err = frame_sync::read_response(&mut Cursor::new(&buf)).unwrap_err();
+        assert!(err.to_string().contains("seq_id mismatch"), "{}", err);
+
+        // A new message where the continuation should be
+        let mut buf = chunked(FrameType::Response, 1, &payload, 1024);
+        buf.truncate(second);
+        frame_sync::send_response(&mut buf, &VeloResponse::CasNotFound, 1).unwrap();
+        let err = frame_sync::read_response(&mut Cursor::new(&buf)).unwrap_err();
+        assert!(err.to_string().contains("expected Continuation"), "{}", err);
+
+        // A continuation with nothing before it
+        let buf = chunked(FrameType::Response, 1, &payload, 1024);
+        let err = frame_sync::read_response(&mut Cursor::new(&buf[second..])).unwrap_err();
+        assert!(
+            err.to_string().contains("expected Response frame"),
+            "{}",
+            err
+        );
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_frame_async_reassembles_continuations() {
+        use crate::frame_async;
+
+        let payload = rkyv::to_bytes::<rkyv::rancor::Error>(&big_listing(500)).unwrap();
+        let buf = chunked(FrameType::Response, 3, &payload, 1000);
+
+        let (header, resp) = frame_async::read_response(&mut &buf[..]).await.unwrap();
+        assert_eq!(header.seq_id, 3);
+        assert!(matches!(resp, VeloResponse::ManifestListAck { entries } if entries.len() == 500));
+
+        // Single-frame messages still round-trip through the async writer
+        let mut out = Vec::new();
+        frame_async::send_response(&mut out, &big_listing(3), 4)
+            .await
+            .unwrap();
+        let (header, _) = frame_async::read_response(&mut &out[..]).await.unwrap();
+        assert_eq!(header.seq_id, 4);
+        assert!(!header.has_more());
+    }
+
     // =========================================================================
     // VeloError Tests
     // =========================================================================
@@ -1660,10 +3306,36 @@ mod tests {
             VeloError::new(VeloErrorKind::IngestFailed, "").exit_code(),
             79
         );
+        assert_eq!(VeloError::new(VeloErrorKind::Conflict, "").exit_code(), 79);
         assert_eq!(VeloError::io_error("").exit_code(), 1);
         assert_eq!(VeloError::internal("").exit_code(), 1);
     }
 
+    #[test]
+    fn test_velo_error_kind_mapping() {
+        // Round-trips for every kind that has a wire equivalent
+        for kind in [
+            ErrorKind::NotFound,
+            ErrorKind::PermissionDenied,
+            ErrorKind::InvalidInput,
+            ErrorKind::IngestFailed,
+            ErrorKind::Io,
+            ErrorKind::LockFailed,
+            ErrorKind::Internal,
+        ] {
+            assert_eq!(ErrorKind::from(VeloErrorKind::from(kind)), kind);
+        }
+        assert_eq!(
+            VeloErrorKind::from(ErrorKind::Corrupted),
+            VeloErrorKind::Internal
+        );
+
+        let io = std::io::Error::from(std::io::ErrorKind::PermissionDenied);
+        let err = VeloError::new(io.classify().into(), io.to_string());
+        assert_eq!(err.kind, VeloErrorKind::PermissionDenied);
+        assert_eq!(err.classify(), ErrorKind::PermissionDenied);
+    }
+
     #[test]
     fn test_velo_error_response_serialization() {
         let response = VeloResponse::Error(VeloError::not_found("Not found"));
@@ -1676,4 +3348,156 @@ mod tests {
             panic!("Expected VeloResponse::Error");
         }
     }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_storage_flags_shared_across_formats() {
+        use vdir_types::{
+            FLAG_COMPRESSED, FLAG_ENCRYPTED, FLAG_EXTERNAL, FLAG_INLINE, FLAG_STORAGE_MASK,
+        };
+        assert_eq!(VnodeEntry::FLAG_COMPRESSED, FLAG_COMPRESSED);
+        assert_eq!(VnodeEntry::FLAG_ENCRYPTED, FLAG_ENCRYPTED);
+        assert_eq!(VnodeEntry::FLAG_INLINE, FLAG_INLINE);
+        assert_eq!(VnodeEntry::FLAG_EXTERNAL, FLAG_EXTERNAL);
+        assert_eq!(VnodeEntry::STORAGE_MASK, FLAG_STORAGE_MASK);
+        // Clear of both the VnodeFlags type values and the VDir state bits
+        let vdir_bits = vdir_types::FLAG_DIRTY
+            | vdir_types::FLAG_DELETED
+            | vdir_types::FLAG_SYMLINK
+            | vdir_types::FLAG_DIR
+            | vdir_types::FLAG_PASSTHROUGH
+            | vdir_types::FLAG_WHITEOUT;
+        assert_eq!(FLAG_STORAGE_MASK & (vdir_bits | 0x00FF), 0);
+        assert_eq!(vdir_types::FLAG_WHITEOUT & (FLAG_STORAGE_MASK | 0x00FF), 0);
+
+        let mut builder = ManifestMmapBuilder::new();
+        builder.add_entry("/c", 10, 0, 0o644, false, false, FLAG_COMPRESSED | 0x0001);
+        let entry = builder.entries[0].1;
+        assert_eq!(entry.storage_flags(), FLAG_COMPRESSED);
+        assert!(!entry.is_dir());
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_mmap_stat_entry_le_roundtrip_unaligned() {
+        let entry = MmapStatEntry {
+            path_hash: 0x0102_0304_0506_0708,
+            size: 4096,
+            mtime: -1,
+            mtime_nsec: 999,
+            mode: 0o100644,
+            flags: 0x02,
+        };
+        let bytes = entry.to_le_bytes();
+        assert_eq!(
+            &bytes[0..8],
+            &[0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01]
+        );
+
+        // Odd offset: decoding must not depend on alignment
+        let mut buf = [0u8; 1 + MmapStatEntry::SIZE];
+        buf[1..].copy_from_slice(&bytes);
+        let decoded = MmapStatEntry::read_le(&buf[1..]).unwrap();
+        assert_eq!(decoded.path_hash, entry.path_hash);
+        assert_eq!(decoded.mtime, -1);
+        assert!(decoded.is_symlink());
+        assert!(MmapStatEntry::read_le(&bytes[..10]).is_none());
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_mmap_lookup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("manifest.mmap");
+        let mut builder = ManifestMmapBuilder::new();
+        builder.add_entry("/a", 10, 0, 0o100644, false, false, 0);
+        let stats = builder.write_to_file(path.to_str().unwrap()).unwrap();
+        assert_eq!((stats.entries, stats.max_probe, stats.children), (1, 1, 1));
+        assert_eq!(stats.bloom_bits_set, 2);
+        assert_eq!(stats.table_capacity, 1024);
+        let mut bytes = std::fs::read(&path).unwrap();
+
+        // Readers need the generation 8-byte aligned, as in a mapping
+        let lookup = |bytes: &[u8], p: &str| {
+            let mut words = vec![0u64; bytes.len().div_ceil(8)];
+            unsafe {
+                std::ptr::copy_nonoverlapping(
+                    bytes.as_ptr(),
+                    words.as_mut_ptr() as *mut u8,
+                    bytes.len(),
+                );
+                manifest_mmap_lookup(words.as_ptr() as *const u8, bytes.len(), p)
+            }
+        };
+        assert_eq!(lookup(&bytes, "/a").unwrap().size, 10);
+        assert!(lookup(&bytes, "/b").is_none());
+        // A truncated mapping is rejected, not read past its end
+        assert!(lookup(&bytes[..8], "/a").is_none());
+
+        // A writer stuck mid-update: readers give up rather than spin
+        bytes[MMAP_HDR_GENERATION..MMAP_HDR_GENERATION + 8].copy_from_slice(&5u64.to_le_bytes());
+        assert!(lookup(&bytes, "/a").is_none());
+        bytes[MMAP_HDR_GENERATION..MMAP_HDR_GENERATION + 8].copy_from_slice(&6u64.to_le_bytes());
+        assert_eq!(lookup(&bytes, "/a").unwrap().size, 10);
+    }
+
+    #[test]
+    fn test_vdir_entry_read_le_unaligned() {
+        use vdir_types::{VDirEntry, VDIR_ENTRY_SIZE};
+
+        let entry = VDirEntry {
+            path_hash: fnv1a_hash("src/main.rs"),
+            cas_hash: [9; 32],
+            size: 1234,
+            mtime_sec: 1_700_000_000,
+            mtime_nsec: 5,
+            mode: 0o100755,
+            flags: vdir_types::FLAG_DIRTY,
+            _pad: 0,
+            ingest_sec: 1_700_000_100,
+            path_check: vdir_types::vdir_path_check("src/main.rs"),
+            pack_offset: 64,
+            pack_id: 3,
+            pack_len: 1234,
+        }
+        .to_le();
+        let raw = unsafe {
+            std::slice::from_raw_parts(&entry as *const VDirEntry as *const u8, VDIR_ENTRY_SIZE)
+        };
+        let mut buf = [0u8; 3 + VDIR_ENTRY_SIZE];
+        buf[3..].copy_from_slice(raw);
+
+        let decoded = unsafe { VDirEntry::read_le(buf.as_ptr().add(3)) };
+        assert_eq!(decoded.path_hash, fnv1a_hash("src/main.rs"));
+        assert_eq!(decoded.cas_hash, [9; 32]);
+        assert_eq!(decoded.size, 1234);
+        assert_eq!(decoded.mtime_nsec, 5);
+        assert_eq!(decoded.ingest_sec, 1_700_000_100);
+        // The path check and pack reference are kept outside the slot
+        assert_eq!(decoded.path_check, 0);
+        assert_eq!(decoded.pack_id, 0);
+        assert!(decoded.is_dirty());
+    }
+
+    #[test]
+    fn test_plan_totals_count_unlisted_steps() {
+        let mut plan = Plan::default();
+        for i in 0..Plan::MAX_STEPS + 2 {
+            plan.push(PlanAction::Delete, format!("{:064x}", i), 10);
+        }
+        plan.push(PlanAction::Mark, "ab", 3);
+
+        assert_eq!(plan.steps.len(), Plan::MAX_STEPS);
+        assert_eq!(plan.omitted(), 3);
+        let delete = &plan.totals[0];
+        assert_eq!(delete.action, PlanAction::Delete);
+        assert_eq!(
+            (delete.steps, delete.bytes),
+            (
+                Plan::MAX_STEPS as u64 + 2,
+                10 * (Plan::MAX_STEPS as u64 + 2)
+            )
+        );
+        assert_eq!((plan.totals[1].steps, plan.totals[1].bytes), (1, 3));
+    }
 }
@@ -0,0 +1,105 @@
+//! Modification times.
+//!
+//! Everywhere a time travels as one number — `VnodeEntry::mtime`, IPC
+//! requests, ingest cache hints — it is **signed nanoseconds since the Unix
+//! epoch** (`i64`, covering 1677..2262). Fixed-layout mmap entries store the
+//! same instant split as `(seconds, nanoseconds)` the way `struct timespec`
+//! does: `nsec` is always in `0..1_000_000_000`, so 0.5s before the epoch is
+//! `(-1, 500_000_000)`.
+//!
+//! Build tools compare mtimes for equality or ordering, so every conversion
+//! here is exact within that range and saturates outside it.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const NANOS_PER_SEC: i64 = 1_000_000_000;
+
+/// Join `timespec`-style parts (as returned by `MetadataExt::mtime` /
+/// `mtime_nsec`) into nanoseconds
+#[inline]
+pub fn from_parts(sec: i64, nsec: i64) -> i64 {
+    let ns = i128::from(sec) * i128::from(NANOS_PER_SEC) + i128::from(nsec);
+    ns.clamp(i128::from(i64::MIN), i128::from(i64::MAX)) as i64
+}
+
+/// Split nanoseconds into `(sec, nsec)` with `nsec` in `0..1_000_000_000`
+#[inline]
+pub fn to_parts(ns: i64) -> (i64, u32) {
+    (
+        ns.div_euclid(NANOS_PER_SEC),
+        ns.rem_euclid(NANOS_PER_SEC) as u32,
+    )
+}
+
+pub fn from_system_time(t: SystemTime) -> i64 {
+    match t.duration_since(UNIX_EPOCH) {
+        Ok(d) => i64::try_from(d.as_nanos()).unwrap_or(i64::MAX),
+        Err(e) => i64::try_from(e.duration().as_nanos())
+            .map(|ns| -ns)
+            .unwrap_or(i64::MIN),
+    }
+}
+
+pub fn to_system_time(ns: i64) -> SystemTime {
+    if ns >= 0 {
+        UNIX_EPOCH + Duration::from_nanos(ns as u64)
+    } else {
+        UNIX_EPOCH - Duration::from_nanos(ns.unsigned_abs())
+    }
+}
+
+pub fn now() -> i64 {
+    from_system_time(SystemTime::now())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLES: &[i64] = &[
+        0,
+        1,
+        999_999_999,
+        1_000_000_000,
+        1_700_000_000_123_456_789,
+        -1,
+        -500_000_000,
+        -1_000_000_001,
+        i64::MAX,
+        i64::MIN,
+    ];
+
+    #[test]
+    fn test_parts_round_trip() {
+        for &ns in SAMPLES {
+            let (sec, nsec) = to_parts(ns);
+            assert!(i64::from(nsec) < NANOS_PER_SEC, "{}", ns);
+            assert_eq!(from_parts(sec, i64::from(nsec)), ns);
+        }
+        assert_eq!(to_parts(-500_000_000), (-1, 500_000_000));
+        assert_eq!(to_parts(1_700_000_000_000_000_005), (1_700_000_000, 5));
+    }
+
+    #[test]
+    fn test_system_time_round_trip() {
+        for &ns in SAMPLES {
+            assert_eq!(from_system_time(to_system_time(ns)), ns);
+        }
+        let t = UNIX_EPOCH + Duration::new(1_700_000_000, 123_456_789);
+        assert_eq!(from_system_time(t), 1_700_000_000_123_456_789);
+    }
+
+    #[test]
+    fn test_ordering_preserved() {
+        let mut sorted = SAMPLES.to_vec();
+        sorted.sort();
+        let parts: Vec<_> = sorted.iter().map(|&ns| to_parts(ns)).collect();
+        assert!(parts.windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[test]
+    fn test_saturates() {
+        assert_eq!(from_parts(i64::MAX / 2, 0), i64::MAX);
+        assert_eq!(from_parts(i64::MIN / 2, 0), i64::MIN);
+    }
+}
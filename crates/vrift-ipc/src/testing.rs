@@ -0,0 +1,265 @@
+//! Test doubles for the daemon side of the protocol
+//!
+//! [`MockDaemon`] binds a Unix socket in a private temp directory and
+//! answers framed requests from a table of canned responses keyed by
+//! [`VeloRequest::kind`]. Every request it receives is logged, so tests can
+//! assert on what a client sent without a real vriftd, vDird or LMDB.
+//!
+//! The server runs on plain threads and speaks the `frame_sync` wire format,
+//! so it serves the blocking shim client and the tokio `DaemonClient` alike.
+//!
+//! ```no_run
+//! use vrift_ipc::testing::MockDaemon;
+//! use vrift_ipc::{VeloRequest, VeloResponse};
+//!
+//! let daemon = MockDaemon::start().unwrap();
+//! daemon.respond(
+//!     "Status",
+//!     &VeloResponse::StatusAck {
+//!         status: "OK".into(),
+//!     },
+//! );
+//! // ... point the client under test at daemon.socket_path() ...
+//! assert_eq!(daemon.request_kinds(), vec!["Status"]);
+//! ```
+
+use std::collections::HashMap;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+use crate::{frame_sync, VeloError, VeloRequest, VeloResponse, PROTOCOL_VERSION};
+
+type Responder = Box<dyn Fn(&VeloRequest) -> VeloResponse + Send>;
+
+#[derive(Default)]
+struct Shared {
+    responders: HashMap<&'static str, Responder>,
+    log: Vec<VeloRequest>,
+}
+
+/// In-process stand-in for vriftd
+pub struct MockDaemon {
+    socket_path: PathBuf,
+    shared: Arc<Mutex<Shared>>,
+    shutdown: Arc<AtomicBool>,
+    acceptor: Option<JoinHandle<()>>,
+    // Removed on drop, after the acceptor has exited
+    _dir: tempfile::TempDir,
+}
+
+impl MockDaemon {
+    /// Bind a fresh socket and start serving
+    ///
+    /// Handshakes are acknowledged as compatible unless overridden; any
+    /// other request without a canned response gets an `Internal` error
+    /// naming the request kind.
+    pub fn start() -> std::io::Result<Self> {
+        let dir = tempfile::Builder::new().prefix("vrift-mock-").tempdir()?;
+        let socket_path = dir.path().join("vriftd.sock");
+        let listener = UnixListener::bind(&socket_path)?;
+
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let acceptor = {
+            let shared = Arc::clone(&shared);
+            let shutdown = Arc::clone(&shutdown);
+            std::thread::Builder::new()
+                .name("mock-vriftd".into())
+                .spawn(move || accept_loop(listener, shared, shutdown))?
+        };
+
+        let daemon = Self {
+            socket_path,
+            shared,
+            shutdown,
+            acceptor: Some(acceptor),
+            _dir: dir,
+        };
+        daemon.respond_with("Handshake", |_| VeloResponse::HandshakeAck {
+            server_version: "mock".to_string(),
+            protocol_version: PROTOCOL_VERSION,
+            compatible: true,
+        });
+        Ok(daemon)
+    }
+
+    /// Path clients should connect to
+    pub fn socket_path(&self) -> &Path {
+        &self.socket_path
+    }
+
+    /// Answer every request of `kind` with a copy of `response`
+    pub fn respond(&self, kind: &'static str, response: &VeloResponse) {
+        // VeloResponse isn't Clone; keep the archived bytes and decode a
+        // fresh copy per request.
+        let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(response)
+            .expect("canned response must serialize")
+            .to_vec();
+        self.respond_with(kind, move |_| {
+            rkyv::from_bytes::<VeloResponse, rkyv::rancor::Error>(&bytes)
+                .expect("canned response must deserialize")
+        });
+    }
+
+    /// Compute the answer to requests of `kind` from the request itself
+    pub fn respond_with<F>(&self, kind: &'static str, responder: F)
+    where
+        F: Fn(&VeloRequest) -> VeloResponse + Send + 'static,
+    {
+        self.lock().responders.insert(kind, Box::new(responder));
+    }
+
+    /// Kinds of the requests received so far, in arrival order
+    pub fn request_kinds(&self) -> Vec<&'static str> {
+        self.lock().log.iter().map(VeloRequest::kind).collect()
+    }
+
+    /// Drain the request log
+    pub fn take_requests(&self) -> Vec<VeloRequest> {
+        std::mem::take(&mut self.lock().log)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Shared> {
+        // A panicking responder must not hide the log from the test
+        self.shared.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for MockDaemon {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        // Wake the blocking accept()
+        let _ = UnixStream::connect(&self.socket_path);
+        if let Some(acceptor) = self.acceptor.take() {
+            let _ = acceptor.join();
+        }
+    }
+}
+
+fn accept_loop(listener: UnixListener, shared: Arc<Mutex<Shared>>, shutdown: Arc<AtomicBool>) {
+    for stream in listener.incoming() {
+        if shutdown.load(Ordering::SeqCst) {
+            return;
+        }
+        let Ok(stream) = stream else { continue };
+        let shared = Arc::clone(&shared);
+        // Connections end when the client hangs up
+        let _ = std::thread::Builder::new()
+            .name("mock-vriftd-conn".into())
+            .spawn(move || serve_connection(stream, shared));
+    }
+}
+
+fn serve_connection(mut stream: UnixStream, shared: Arc<Mutex<Shared>>) {
+    while let Ok((header, request)) = frame_sync::read_request(&mut stream) {
+        let response = {
+            let mut shared = shared.lock().unwrap_or_else(|e| e.into_inner());
+            let response = match shared.responders.get(request.kind()) {
+                Some(responder) => responder(&request),
+                None => VeloResponse::Error(VeloError::internal(format!(
+                    "MockDaemon: no response configured for {}",
+                    request.kind()
+                ))),
+            };
+            shared.log.push(request);
+            response
+        };
+        if frame_sync::send_response(&mut stream, &response, header.seq_id).is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VeloErrorKind;
+
+    #[test]
+    fn test_canned_responses_and_request_log() {
+        let daemon = MockDaemon::start().unwrap();
+        daemon.respond(
+            "Status",
+            &VeloResponse::StatusAck {
+                status: "Operational".into(),
+            },
+        );
+        daemon.respond_with("ManifestGet", |req| match req {
+            VeloRequest::ManifestGet { path } if path == "/missing" => {
+                VeloResponse::ManifestAck { entry: None }
+            }
+            _ => VeloResponse::Error(VeloError::not_found("unexpected path")),
+        });
+
+        let mut stream = UnixStream::connect(daemon.socket_path()).unwrap();
+        for _ in 0..2 {
+            let seq = frame_sync::send_request(&mut stream, &VeloRequest::Status).unwrap();
+            let (header, resp) = frame_sync::read_response(&mut stream).unwrap();
+            assert_eq!(header.seq_id, seq);
+            assert!(matches!(resp, VeloResponse::StatusAck { status } if status == "Operational"));
+        }
+
+        frame_sync::send_request(
+            &mut stream,
+            &VeloRequest::ManifestGet {
+                path: "/missing".into(),
+            },
+        )
+        .unwrap();
+        let (_, resp) = frame_sync::read_response(&mut stream).unwrap();
+        assert!(matches!(resp, VeloResponse::ManifestAck { entry: None }));
+
+        // Unconfigured kinds fail loudly instead of hanging the client
+        frame_sync::send_request(&mut stream, &VeloRequest::JobList).unwrap();
+        let (_, resp) = frame_sync::read_response(&mut stream).unwrap();
+        match resp {
+            VeloResponse::Error(e) => {
+                assert_eq!(e.kind, VeloErrorKind::Internal);
+                assert!(e.message.contains("JobList"));
+            }
+            other => panic!("expected error, got {:?}", other),
+        }
+
+        assert_eq!(
+            daemon.request_kinds(),
+            vec!["Status", "Status", "ManifestGet", "JobList"]
+        );
+        let requests = daemon.take_requests();
+        assert!(matches!(&requests[2], VeloRequest::ManifestGet { path } if path == "/missing"));
+        assert!(daemon.request_kinds().is_empty());
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_serves_async_client() {
+        let daemon = MockDaemon::start().unwrap();
+        daemon.respond(
+            "Ping",
+            &VeloResponse::PingAck {
+                version: "9.9.9".into(),
+                uptime_secs: 7,
+                pid: 42,
+            },
+        );
+
+        let mut client =
+            crate::client::DaemonClient::connect_to(daemon.socket_path().to_str().unwrap())
+                .await
+                .unwrap();
+        assert_eq!(client.handshake().await.unwrap(), "mock");
+        assert_eq!(client.ping().await.unwrap(), ("9.9.9".to_string(), 7, 42));
+        assert_eq!(daemon.request_kinds(), vec!["Handshake", "Ping"]);
+    }
+
+    #[test]
+    fn test_drop_removes_socket() {
+        let daemon = MockDaemon::start().unwrap();
+        let path = daemon.socket_path().to_path_buf();
+        assert!(path.exists());
+        drop(daemon);
+        assert!(!path.exists());
+    }
+}
@@ -7,13 +7,32 @@
 pub const VDIR_MAGIC: u32 = 0x56524654;
 
 /// VDir format version. Bump on incompatible changes.
-pub const VDIR_VERSION: u32 = 2; // v2: Added CRC32 checksum
+pub const VDIR_VERSION: u32 = 4; // v4: Added pack reference table
+
+/// Oldest VDir version this build still reads, and that vDird can still emit
+/// for older shims. v3 is the v4 layout without the pack reference table, v2
+/// is v3 without the path check table, and v1 is v2 without the header CRC.
+pub const VDIR_MIN_VERSION: u32 = 1;
+
+/// Whether this build understands a VDir mmap of `version`
+#[inline(always)]
+pub fn vdir_version_supported(version: u32) -> bool {
+    (VDIR_MIN_VERSION..=VDIR_VERSION).contains(&version)
+}
 
 /// Default hash table capacity (slots)
 pub const VDIR_DEFAULT_CAPACITY: usize = 65536;
 
-/// Compile-time entry size (for offset calculations)
-pub const VDIR_ENTRY_SIZE: usize = std::mem::size_of::<VDirEntry>();
+/// Bytes per slot of the entry table: a [`VDirEntry`] up to `path_check`,
+/// which is kept in the check table
+pub const VDIR_ENTRY_SIZE: usize = 72;
+
+/// Bytes per slot of the check table (one little-endian `u64`)
+pub const VDIR_CHECK_SIZE: usize = 8;
+
+/// Bytes per slot of the pack reference table: `pack_offset` (`u64`),
+/// `pack_id` and `pack_len` (`u32` each), little-endian
+pub const VDIR_PACK_REF_SIZE: usize = 16;
 
 /// Compile-time header size
 pub const VDIR_HEADER_SIZE: usize = std::mem::size_of::<VDirHeader>();
@@ -30,6 +49,28 @@ pub const FLAG_DELETED: u16 = 0x0002;
 pub const FLAG_SYMLINK: u16 = 0x0004;
 /// Entry is a directory
 pub const FLAG_DIR: u16 = 0x0008;
+/// Entry was copied up to its real project path, which is authoritative from
+/// now on: the shim passes every call on it through to the kernel
+pub const FLAG_PASSTHROUGH: u16 = 0x0010;
+/// Path was renamed away: readers treat it as absent and do not fall back to
+/// the LMDB base. Outside both the low byte and the storage bits, so no flag
+/// copied verbatim from a manifest entry can set it.
+pub const FLAG_WHITEOUT: u16 = 0x0800;
+
+// Storage bits: how the content is kept, not what the entry is. Same values
+// as `VnodeEntry::FLAG_*`, so they survive the verbatim flag copy between
+// manifest entries and VDir entries.
+
+/// CAS blob is compressed; its bytes are not the file content
+pub const FLAG_COMPRESSED: u16 = 0x0100;
+/// CAS blob is encrypted; its bytes are not the file content
+pub const FLAG_ENCRYPTED: u16 = 0x0200;
+/// Content (at most 32 bytes) is stored in `cas_hash`; there is no blob
+pub const FLAG_INLINE: u16 = 0x0400;
+/// Content is a real file elsewhere; the CAS blob holds its absolute path
+pub const FLAG_EXTERNAL: u16 = 0x1000;
+/// All storage bits
+pub const FLAG_STORAGE_MASK: u16 = FLAG_COMPRESSED | FLAG_ENCRYPTED | FLAG_INLINE | FLAG_EXTERNAL;
 
 // ---------------------------------------------------------------------------
 // VDirHeader — occupies first 64 bytes of the mmap file
@@ -47,9 +88,18 @@ pub const FLAG_DIR: u16 = 0x0008;
 /// 16      entry_count       4
 /// 20      table_capacity    4
 /// 24      table_offset      4
-/// 28      crc32             4
-/// 32      _pad             32
+/// 28      crc32             4    (v2+, zero in v1)
+/// 32      check_offset      4    (v3+, 0 = no check table)
+/// 36      pack_ref_offset   4    (v4+, 0 = no pack reference table)
+/// 40      _pad             24
 /// ```
+///
+/// From v3 a check table of `table_capacity` little-endian `u64`s starts at
+/// `check_offset`: slot `i` holds the [`VDirEntry::path_check`] of entry
+/// slot `i`. It sits after the entry table, so shims that predate it read
+/// the entry table unchanged. From v4 a pack reference table of
+/// `table_capacity` slots of `VDIR_PACK_REF_SIZE` bytes follows it at
+/// `pack_ref_offset`, holding the pack fields of each entry.
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
 pub struct VDirHeader {
@@ -59,20 +109,41 @@ pub struct VDirHeader {
     pub entry_count: u32,
     pub table_capacity: u32,
     pub table_offset: u32,
-    pub crc32: u32,     // CRC32 checksum of header (fields before crc32)
-    pub _pad: [u8; 32], // Pad to 64 bytes
+    pub crc32: u32,           // CRC32 checksum of header (fields before crc32)
+    pub check_offset: u32,    // Offset to the path check table (v3+)
+    pub pack_ref_offset: u32, // Offset to the pack reference table (v4+)
+    pub _pad: [u8; 24],       // Pad to 64 bytes
 }
 
 // Compile-time assertion: VDirHeader must be exactly 64 bytes
 const _: () = assert!(std::mem::size_of::<VDirHeader>() == 64);
 
+impl VDirHeader {
+    /// Encode for storage in the mmap (identity on little-endian hosts).
+    /// Also decodes, since byte swapping is its own inverse.
+    pub fn to_le(self) -> Self {
+        Self {
+            magic: self.magic.to_le(),
+            version: self.version.to_le(),
+            generation: self.generation.to_le(),
+            entry_count: self.entry_count.to_le(),
+            table_capacity: self.table_capacity.to_le(),
+            table_offset: self.table_offset.to_le(),
+            crc32: self.crc32.to_le(),
+            check_offset: self.check_offset.to_le(),
+            pack_ref_offset: self.pack_ref_offset.to_le(),
+            _pad: self._pad,
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // VDirEntry — 72 bytes per slot in the hash table
 // ---------------------------------------------------------------------------
 
 /// Single VDir entry in the hash table (open addressing, linear probing).
 ///
-/// Layout (72 bytes total):
+/// Slot layout (`VDIR_ENTRY_SIZE`, 72 bytes):
 /// ```text
 /// offset  field         size
 /// ------  -----------   ----
@@ -83,8 +154,15 @@ const _: () = assert!(std::mem::size_of::<VDirHeader>() == 64);
 /// 56      mtime_nsec     4
 /// 60      mode           4
 /// 64      flags          2
-/// 66      _pad           6
+/// 66      _pad           2
+/// 68      ingest_sec     4
 /// ```
+///
+/// `ingest_sec` was padding before it was added; it reads as 0 ("unknown")
+/// in tables written by older vDird builds, so no version bump was needed.
+/// `path_check` and the pack fields follow the slot in memory but are stored
+/// in the header's check and pack reference tables; entries without them
+/// (v2/v3 tables) read them as 0.
 #[repr(C)]
 #[derive(Debug, Clone, Copy, Default)]
 pub struct VDirEntry {
@@ -94,12 +172,23 @@ pub struct VDirEntry {
     pub mtime_sec: i64,
     pub mtime_nsec: u32,
     pub mode: u32,
-    pub flags: u16, // FLAG_DIRTY | FLAG_DELETED | FLAG_SYMLINK | FLAG_DIR
-    pub _pad: [u16; 3],
+    pub flags: u16, // FLAG_DIRTY | FLAG_DELETED | FLAG_SYMLINK | FLAG_DIR | FLAG_PASSTHROUGH | storage bits
+    pub _pad: u16,
+    /// When this version was ingested, whole seconds since the epoch (0 = unknown)
+    pub ingest_sec: u32,
+    /// [`vdir_path_check`] of the path, so two paths with the same
+    /// `path_hash` don't share a slot (0 = not recorded)
+    pub path_check: u64,
+    /// Offset of the blob in the data section of pack `pack_id`
+    pub pack_offset: u64,
+    /// `vrift_pack::pack_id` of the packfile holding the content (0 = not packed)
+    pub pack_id: u32,
+    /// Length of the packed blob
+    pub pack_len: u32,
 }
 
-// Compile-time assertion: VDirEntry must be exactly 72 bytes
-const _: () = assert!(std::mem::size_of::<VDirEntry>() == 72);
+// Compile-time assertion: the slot is the entry up to `path_check`
+const _: () = assert!(std::mem::offset_of!(VDirEntry, path_check) == VDIR_ENTRY_SIZE);
 
 impl VDirEntry {
     /// True if slot is empty (never written)
@@ -131,4 +220,464 @@ impl VDirEntry {
     pub fn is_symlink(&self) -> bool {
         (self.flags & FLAG_SYMLINK) != 0
     }
+
+    /// True if the path was renamed away
+    #[inline]
+    pub fn is_whiteout(&self) -> bool {
+        (self.flags & FLAG_WHITEOUT) != 0
+    }
+
+    /// True if the real file at the entry's path is authoritative
+    #[inline]
+    pub fn is_passthrough(&self) -> bool {
+        (self.flags & FLAG_PASSTHROUGH) != 0
+    }
+
+    /// Content is served from the real file whose path the CAS blob holds
+    #[inline]
+    pub fn is_external(&self) -> bool {
+        (self.flags & FLAG_EXTERNAL) != 0
+    }
+
+    /// Whether this entry holds the path with `path_hash` and `path_check`
+    /// (a [`vdir_path_check`], never 0). An entry without a recorded check
+    /// is matched on `path_hash` alone.
+    #[inline]
+    pub fn is_for(&self, path_hash: u64, path_check: u64) -> bool {
+        self.path_hash == path_hash && (self.path_check == path_check || self.path_check == 0)
+    }
+
+    /// Set `path_hash`/`path_check` for `path`
+    #[inline]
+    pub fn set_path(&mut self, path: &str) {
+        self.path_hash = crate::fnv1a_hash(path);
+        self.path_check = vdir_path_check(path);
+    }
+
+    /// `mtime_sec`/`mtime_nsec` joined into signed nanoseconds
+    #[inline]
+    pub fn mtime_ns(&self) -> i64 {
+        crate::mtime::from_parts(self.mtime_sec, i64::from(self.mtime_nsec))
+    }
+
+    /// Set `mtime_sec`/`mtime_nsec` from signed nanoseconds
+    #[inline]
+    pub fn set_mtime_ns(&mut self, ns: i64) {
+        (self.mtime_sec, self.mtime_nsec) = crate::mtime::to_parts(ns);
+    }
+
+    /// Record an ingest time given in nanoseconds (truncated to seconds;
+    /// times outside 1970..2106 are stored as unknown)
+    #[inline]
+    pub fn set_ingest_ns(&mut self, ns: i64) {
+        self.ingest_sec = u32::try_from(crate::mtime::to_parts(ns).0).unwrap_or(0);
+    }
+
+    /// Encode for storage in the mmap (identity on little-endian hosts).
+    /// Also decodes, since byte swapping is its own inverse.
+    pub fn to_le(self) -> Self {
+        Self {
+            path_hash: self.path_hash.to_le(),
+            cas_hash: self.cas_hash,
+            size: self.size.to_le(),
+            mtime_sec: self.mtime_sec.to_le(),
+            mtime_nsec: self.mtime_nsec.to_le(),
+            mode: self.mode.to_le(),
+            flags: self.flags.to_le(),
+            _pad: self._pad,
+            ingest_sec: self.ingest_sec.to_le(),
+            path_check: self.path_check.to_le(),
+            pack_offset: self.pack_offset.to_le(),
+            pack_id: self.pack_id.to_le(),
+            pack_len: self.pack_len.to_le(),
+        }
+    }
+
+    /// Decode the slot at `ptr`, whatever its alignment. `path_check` and
+    /// the pack fields are not part of the slot and read as 0.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must point to `VDIR_ENTRY_SIZE` readable bytes.
+    #[inline(always)]
+    pub unsafe fn read_le(ptr: *const u8) -> Self {
+        unsafe {
+            let mut cas_hash = [0u8; 32];
+            std::ptr::copy_nonoverlapping(ptr.add(ENT_CAS_HASH), cas_hash.as_mut_ptr(), 32);
+            Self {
+                path_hash: read_le_u64(ptr, ENT_PATH_HASH),
+                cas_hash,
+                size: read_le_u64(ptr, ENT_SIZE),
+                mtime_sec: read_le_u64(ptr, ENT_MTIME_SEC) as i64,
+                mtime_nsec: read_le_u32(ptr, ENT_MTIME_NSEC),
+                mode: read_le_u32(ptr, ENT_MODE),
+                flags: read_le_u16(ptr, ENT_FLAGS),
+                _pad: 0,
+                ingest_sec: read_le_u32(ptr, ENT_INGEST_SEC),
+                path_check: 0,
+                pack_offset: 0,
+                pack_id: 0,
+                pack_len: 0,
+            }
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Byte order — the mapping is little-endian on every host
+// ---------------------------------------------------------------------------
+//
+// Readers never cast the mapping to `&VDirHeader` / `&VDirEntry`. Fields are
+// decoded with unaligned little-endian loads at fixed offsets, so big-endian
+// hosts and mappings that aren't 8-byte aligned see what vDird wrote. The one
+// exception is `generation`, which has to be an aligned atomic; its value is
+// stored little-endian as well.
+
+// Field offsets in `VDirHeader`
+pub const HDR_MAGIC: usize = 0;
+pub const HDR_VERSION: usize = 4;
+pub const HDR_GENERATION: usize = 8;
+pub const HDR_ENTRY_COUNT: usize = 16;
+pub const HDR_TABLE_CAPACITY: usize = 20;
+pub const HDR_TABLE_OFFSET: usize = 24;
+pub const HDR_CHECK_OFFSET: usize = 32;
+pub const HDR_PACK_REF_OFFSET: usize = 36;
+
+// Field offsets in `VDirEntry`
+pub const ENT_PATH_HASH: usize = 0;
+pub const ENT_CAS_HASH: usize = 8;
+pub const ENT_SIZE: usize = 40;
+pub const ENT_MTIME_SEC: usize = 48;
+pub const ENT_MTIME_NSEC: usize = 56;
+pub const ENT_MODE: usize = 60;
+pub const ENT_FLAGS: usize = 64;
+pub const ENT_INGEST_SEC: usize = 68;
+
+// Field offsets in a pack reference table slot
+pub const PREF_OFFSET: usize = 0;
+pub const PREF_ID: usize = 8;
+pub const PREF_LEN: usize = 12;
+
+const _: () = {
+    assert!(std::mem::offset_of!(VDirHeader, generation) == HDR_GENERATION);
+    assert!(std::mem::offset_of!(VDirHeader, table_offset) == HDR_TABLE_OFFSET);
+    assert!(std::mem::offset_of!(VDirHeader, check_offset) == HDR_CHECK_OFFSET);
+    assert!(std::mem::offset_of!(VDirHeader, pack_ref_offset) == HDR_PACK_REF_OFFSET);
+    assert!(std::mem::offset_of!(VDirEntry, size) == ENT_SIZE);
+    assert!(std::mem::offset_of!(VDirEntry, flags) == ENT_FLAGS);
+    assert!(std::mem::offset_of!(VDirEntry, ingest_sec) == ENT_INGEST_SEC);
+};
+
+/// [`VDirEntry::path_check`] of `path`: its [`crate::path_fingerprint`],
+/// never 0
+#[inline(always)]
+pub fn vdir_path_check(path: &str) -> u64 {
+    crate::path_fingerprint(path).max(1)
+}
+
+/// Little-endian `u16` at `base + offset`
+///
+/// # Safety
+///
+/// The two bytes must be readable.
+#[inline(always)]
+pub unsafe fn read_le_u16(base: *const u8, offset: usize) -> u16 {
+    u16::from_le_bytes(unsafe { std::ptr::read_unaligned(base.add(offset) as *const [u8; 2]) })
+}
+
+/// Little-endian `u32` at `base + offset`
+///
+/// # Safety
+///
+/// The four bytes must be readable.
+#[inline(always)]
+pub unsafe fn read_le_u32(base: *const u8, offset: usize) -> u32 {
+    u32::from_le_bytes(unsafe { std::ptr::read_unaligned(base.add(offset) as *const [u8; 4]) })
+}
+
+/// Little-endian `u64` at `base + offset`
+///
+/// # Safety
+///
+/// The eight bytes must be readable.
+#[inline(always)]
+pub unsafe fn read_le_u64(base: *const u8, offset: usize) -> u64 {
+    u64::from_le_bytes(unsafe { std::ptr::read_unaligned(base.add(offset) as *const [u8; 8]) })
+}
+
+/// Format version of a VDir mapping, or None if it is too short or the
+/// magic doesn't match
+///
+/// # Safety
+///
+/// `mmap_ptr` must be null or point to `mmap_size` readable bytes.
+#[inline(always)]
+pub unsafe fn vdir_header_version(mmap_ptr: *const u8, mmap_size: usize) -> Option<u32> {
+    if mmap_ptr.is_null() || mmap_size < VDIR_HEADER_SIZE {
+        return None;
+    }
+    if unsafe { read_le_u32(mmap_ptr, HDR_MAGIC) } != VDIR_MAGIC {
+        return None;
+    }
+    Some(unsafe { read_le_u32(mmap_ptr, HDR_VERSION) })
+}
+
+/// Offset of the path check table of a mapping whose header declares
+/// `version`, or 0 if it has none
+///
+/// # Safety
+///
+/// `mmap_ptr` must point to `VDIR_HEADER_SIZE` readable bytes.
+#[inline(always)]
+pub unsafe fn vdir_check_offset(mmap_ptr: *const u8, version: u32) -> usize {
+    if version < 3 {
+        return 0;
+    }
+    unsafe { read_le_u32(mmap_ptr, HDR_CHECK_OFFSET) as usize }
+}
+
+/// Offset of the pack reference table of a mapping whose header declares
+/// `version`, or 0 if it has none
+///
+/// # Safety
+///
+/// `mmap_ptr` must point to `VDIR_HEADER_SIZE` readable bytes.
+#[inline(always)]
+pub unsafe fn vdir_pack_ref_offset(mmap_ptr: *const u8, version: u32) -> usize {
+    if version < 4 {
+        return 0;
+    }
+    unsafe { read_le_u32(mmap_ptr, HDR_PACK_REF_OFFSET) as usize }
+}
+
+// ---------------------------------------------------------------------------
+// Reader — seqlock-protected lookups shared by every VDir consumer
+// ---------------------------------------------------------------------------
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Result from VDir lookup (VDirEntry fields needed for stat)
+#[derive(Debug, Clone, Copy)]
+pub struct VDirStatResult {
+    pub size: u64,
+    pub mtime_sec: i64,
+    pub mtime_nsec: u32,
+    pub mode: u32,
+    pub flags: u16,
+    pub cas_hash: [u8; 32],
+    pub ingest_sec: u32,
+    pub pack_offset: u64,
+    pub pack_id: u32,
+    pub pack_len: u32,
+}
+
+impl VDirStatResult {
+    /// True if the path was renamed away
+    #[inline]
+    pub fn is_whiteout(&self) -> bool {
+        (self.flags & FLAG_WHITEOUT) != 0
+    }
+
+    /// True if the real file at the entry's path is authoritative
+    #[inline]
+    pub fn is_passthrough(&self) -> bool {
+        (self.flags & FLAG_PASSTHROUGH) != 0
+    }
+
+    /// Content is served from the real file whose path the CAS blob holds
+    #[inline]
+    pub fn is_external(&self) -> bool {
+        (self.flags & FLAG_EXTERNAL) != 0
+    }
+
+    /// `mtime_sec`/`mtime_nsec` joined into signed nanoseconds
+    #[inline]
+    pub fn mtime_ns(&self) -> i64 {
+        crate::mtime::from_parts(self.mtime_sec, i64::from(self.mtime_nsec))
+    }
+
+    /// Ingest time in nanoseconds, if the entry recorded one
+    #[inline]
+    pub fn ingest_ns(&self) -> Option<i64> {
+        (self.ingest_sec != 0).then(|| i64::from(self.ingest_sec) * crate::mtime::NANOS_PER_SEC)
+    }
+
+    /// Where pack `pack_id` holds the content: `(pack_id, offset, len)` in
+    /// its data section, if the content is packed
+    #[inline]
+    pub fn pack_ref(&self) -> Option<(u32, u64, u32)> {
+        (self.pack_id != 0).then_some((self.pack_id, self.pack_offset, self.pack_len))
+    }
+}
+
+/// Maximum seqlock spins before giving up and falling back to IPC.
+/// Prevents infinite hang if vDird crashes mid-write (odd generation stuck).
+pub(crate) const MAX_SEQLOCK_SPINS: u32 = 1000;
+
+/// O(1) seqlock-protected stat lookup from VDir MAP_SHARED mmap.
+/// ZERO ALLOCATIONS, ZERO LOCKS, ZERO SYSCALLS — safe for PSFS hot path.
+///
+/// # Safety
+///
+/// `mmap_ptr` must be null or point to `mmap_size` readable bytes of a VDir
+/// mapping that stays mapped for the duration of the call.
+#[inline(always)]
+pub unsafe fn vdir_lookup(
+    mmap_ptr: *const u8,
+    mmap_size: usize,
+    path: &str,
+) -> Option<VDirStatResult> {
+    // Validate magic; an unknown (newer) layout lets the caller fall back to IPC
+    let version = match unsafe { vdir_header_version(mmap_ptr, mmap_size) } {
+        Some(version) if vdir_version_supported(version) => version,
+        _ => return None,
+    };
+
+    let gen_addr = mmap_ptr as usize + HDR_GENERATION;
+    debug_assert!(
+        gen_addr.is_multiple_of(8),
+        "AtomicU64 (generation) not 8-byte aligned"
+    );
+    let gen_ptr = unsafe { &*(gen_addr as *const AtomicU64) };
+    let table_capacity = unsafe { read_le_u32(mmap_ptr, HDR_TABLE_CAPACITY) } as usize;
+    let table_offset = unsafe { read_le_u32(mmap_ptr, HDR_TABLE_OFFSET) } as usize;
+
+    let check_offset = unsafe { vdir_check_offset(mmap_ptr, version) };
+    let pack_ref_offset = unsafe { vdir_pack_ref_offset(mmap_ptr, version) };
+
+    if table_capacity == 0 {
+        return None;
+    }
+    if check_offset != 0 && check_offset + table_capacity * VDIR_CHECK_SIZE > mmap_size {
+        return None; // Mapping predates a resize
+    }
+    if pack_ref_offset != 0 && pack_ref_offset + table_capacity * VDIR_PACK_REF_SIZE > mmap_size {
+        return None;
+    }
+
+    let path_hash = crate::fnv1a_hash(path);
+    let path_check = vdir_path_check(path);
+    let start_slot = (path_hash as usize) % table_capacity;
+
+    // Seqlock read loop with bounded spin
+    let mut spins: u32 = 0;
+    loop {
+        let g1 = u64::from_le(gen_ptr.load(Ordering::Acquire));
+        if g1 & 1 != 0 {
+            // Writer active (odd generation) — spin with upper bound
+            spins += 1;
+            if spins > MAX_SEQLOCK_SPINS {
+                return None; // Fallback: vDird may have crashed mid-write
+            }
+            core::hint::spin_loop();
+            continue;
+        }
+
+        // O(1) hash table lookup with linear probing
+        let mut result: Option<VDirStatResult> = None;
+        for i in 0..table_capacity {
+            let slot = (start_slot + i) % table_capacity;
+            let entry_offset = table_offset + slot * VDIR_ENTRY_SIZE;
+            if entry_offset + VDIR_ENTRY_SIZE > mmap_size {
+                break;
+            }
+            let entry_ptr = unsafe { mmap_ptr.add(entry_offset) };
+            let slot_hash = unsafe { read_le_u64(entry_ptr, ENT_PATH_HASH) };
+
+            if slot_hash == 0 {
+                break; // Empty slot = not found
+            }
+
+            if slot_hash == path_hash {
+                // Another path with the same hash: keep probing
+                if check_offset != 0 {
+                    let slot_check =
+                        unsafe { read_le_u64(mmap_ptr, check_offset + slot * VDIR_CHECK_SIZE) };
+                    if slot_check != 0 && slot_check != path_check {
+                        continue;
+                    }
+                }
+                let entry = unsafe { VDirEntry::read_le(entry_ptr) };
+                let (pack_offset, pack_id, pack_len) = if pack_ref_offset != 0 {
+                    let ref_ptr =
+                        unsafe { mmap_ptr.add(pack_ref_offset + slot * VDIR_PACK_REF_SIZE) };
+                    unsafe {
+                        (
+                            read_le_u64(ref_ptr, PREF_OFFSET),
+                            read_le_u32(ref_ptr, PREF_ID),
+                            read_le_u32(ref_ptr, PREF_LEN),
+                        )
+                    }
+                } else {
+                    (0, 0, 0)
+                };
+                result = Some(VDirStatResult {
+                    size: entry.size,
+                    mtime_sec: entry.mtime_sec,
+                    mtime_nsec: entry.mtime_nsec,
+                    mode: entry.mode,
+                    flags: entry.flags,
+                    cas_hash: entry.cas_hash,
+                    ingest_sec: entry.ingest_sec,
+                    pack_offset,
+                    pack_id,
+                    pack_len,
+                });
+                break;
+            }
+        }
+
+        // Re-read generation to check for concurrent write
+        let g2 = u64::from_le(gen_ptr.load(Ordering::Acquire));
+        if g1 != g2 {
+            // Data changed during read — retry (also bounded by MAX_SEQLOCK_SPINS)
+            spins += 1;
+            if spins > MAX_SEQLOCK_SPINS {
+                return None;
+            }
+            core::hint::spin_loop();
+            continue;
+        }
+
+        return result;
+    }
+}
+
+/// Current VDir seqlock generation, or None if the mapping is absent/invalid.
+/// Cheap change detector for pollers (inotify emulation) — no IPC needed.
+///
+/// # Safety
+///
+/// Same contract as [`vdir_lookup`].
+pub unsafe fn vdir_generation(mmap_ptr: *const u8, mmap_size: usize) -> Option<u64> {
+    match unsafe { vdir_header_version(mmap_ptr, mmap_size) } {
+        Some(version) if vdir_version_supported(version) => {}
+        _ => return None,
+    }
+    let gen_ptr = unsafe { &*((mmap_ptr as usize + HDR_GENERATION) as *const AtomicU64) };
+    Some(u64::from_le(gen_ptr.load(Ordering::Acquire)))
+}
+
+/// Bytes from the start of the file to the end of the tables its header
+/// describes, or None if the mapping is absent/invalid. vDird grows the
+/// file in place when the table fills, so a value past `mmap_size` means the
+/// mapping is shorter than the file and should be mapped again.
+///
+/// # Safety
+///
+/// Same contract as [`vdir_lookup`].
+pub unsafe fn vdir_table_end(mmap_ptr: *const u8, mmap_size: usize) -> Option<usize> {
+    let version = unsafe { vdir_header_version(mmap_ptr, mmap_size) }?;
+    let table_capacity = unsafe { read_le_u32(mmap_ptr, HDR_TABLE_CAPACITY) } as usize;
+    let table_offset = unsafe { read_le_u32(mmap_ptr, HDR_TABLE_OFFSET) } as usize;
+    let check_offset = unsafe { vdir_check_offset(mmap_ptr, version) };
+    let pack_ref_offset = unsafe { vdir_pack_ref_offset(mmap_ptr, version) };
+    let mut end = table_offset + table_capacity * VDIR_ENTRY_SIZE;
+    if check_offset != 0 {
+        end = end.max(check_offset + table_capacity * VDIR_CHECK_SIZE);
+    }
+    if pack_ref_offset != 0 {
+        end = end.max(pack_ref_offset + table_capacity * VDIR_PACK_REF_SIZE);
+    }
+    Some(end)
 }
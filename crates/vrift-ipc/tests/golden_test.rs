@@ -0,0 +1,308 @@
+//! Golden-file compatibility tests for IPC frames and the legacy
+//! ManifestMmap (RFC-0044) layout.
+//!
+//! Fixtures under `tests/fixtures/` were produced by the release that shipped
+//! the version in their file name and must never be regenerated: a running
+//! daemon talks to shims built from older releases, and mmap files outlive
+//! the process that wrote them. If a change breaks one of these tests, bump
+//! `PROTOCOL_VERSION` / `MMAP_VERSION` and add a new fixture.
+//!
+//! To add fixtures for new versions:
+//! `cargo test -p vrift-ipc --test golden_test -- --ignored write_current_fixtures`
+
+#![allow(deprecated)]
+
+use std::io::Cursor;
+use std::path::PathBuf;
+
+use vrift_ipc::{
+    fnv1a_hash, frame_sync, FrameType, IpcHeader, ManifestMmapBuilder, ManifestMmapHeader,
+    MmapDirChild, MmapDirIndexEntry, MmapStatEntry, VeloError, VeloErrorKind, VeloRequest,
+    VeloResponse, VnodeEntry, MMAP_VERSION, PROTOCOL_VERSION,
+};
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+}
+
+fn frames_fixture() -> String {
+    format!("frames-v{}.bin", PROTOCOL_VERSION)
+}
+
+fn mmap_fixture() -> String {
+    format!("manifest-mmap-v{}.bin", MMAP_VERSION)
+}
+
+// ============================================================================
+// IPC frames
+// ============================================================================
+
+fn sample_entry() -> VnodeEntry {
+    VnodeEntry {
+        content_hash: [0xab; 32],
+        size: 4096,
+        mtime: 1_700_000_000_123_456_789,
+        mode: 0o100644,
+        flags: 0,
+        _pad: 0,
+    }
+}
+
+fn sample_requests() -> Vec<VeloRequest> {
+    vec![
+        VeloRequest::Handshake {
+            client_version: "0.1.0".to_string(),
+            protocol_version: PROTOCOL_VERSION,
+        },
+        VeloRequest::Status,
+        VeloRequest::ManifestGet {
+            path: "src/main.rs".to_string(),
+        },
+        VeloRequest::ManifestUpsert {
+            path: "target/debug/app".to_string(),
+            entry: sample_entry(),
+        },
+        VeloRequest::CasGet { hash: [0x5a; 32] },
+    ]
+}
+
+fn sample_responses() -> Vec<VeloResponse> {
+    vec![
+        VeloResponse::HandshakeAck {
+            server_version: "0.1.0".to_string(),
+            protocol_version: PROTOCOL_VERSION,
+            compatible: true,
+        },
+        VeloResponse::StatusAck {
+            status: "ok".to_string(),
+        },
+        VeloResponse::ManifestAck {
+            entry: Some(sample_entry()),
+        },
+        VeloResponse::CasNotFound,
+        VeloResponse::Error(VeloError::with_path(
+            VeloErrorKind::NotFound,
+            "no such entry",
+            "src/missing.rs",
+        )),
+    ]
+}
+
+/// Request frames (seq 1..), a heartbeat, then response frames echoing the
+/// request sequence ids. Seq ids are fixed so the bytes are reproducible.
+fn encode_frames() -> Vec<u8> {
+    let mut out = Vec::new();
+    for (i, req) in sample_requests().iter().enumerate() {
+        let payload = rkyv::to_bytes::<rkyv::rancor::Error>(req).unwrap();
+        out.extend_from_slice(
+            &IpcHeader::new_request(payload.len() as u32, i as u32 + 1).to_bytes(),
+        );
+        out.extend_from_slice(&payload);
+    }
+    out.extend_from_slice(&IpcHeader::new_heartbeat(100).to_bytes());
+    for (i, resp) in sample_responses().iter().enumerate() {
+        let payload = rkyv::to_bytes::<rkyv::rancor::Error>(resp).unwrap();
+        out.extend_from_slice(
+            &IpcHeader::new_response(payload.len() as u32, i as u32 + 1).to_bytes(),
+        );
+        out.extend_from_slice(&payload);
+    }
+    out
+}
+
+#[test]
+fn reads_v4_frames() {
+    let bytes = std::fs::read(fixture("frames-v4.bin")).unwrap();
+    let mut cursor = Cursor::new(&bytes[..]);
+
+    let expected: Vec<String> = sample_requests()
+        .iter()
+        .map(|r| format!("{:?}", r))
+        .collect();
+    for (i, want) in expected.iter().enumerate() {
+        let (header, req) = frame_sync::read_request(&mut cursor).unwrap();
+        assert_eq!(header.frame_type(), Some(FrameType::Request));
+        assert_eq!(header.seq_id, i as u32 + 1);
+        assert_eq!(&format!("{:?}", req), want);
+    }
+
+    // The heartbeat between requests and responses is skipped transparently
+    let expected: Vec<String> = sample_responses()
+        .iter()
+        .map(|r| format!("{:?}", r))
+        .collect();
+    for (i, want) in expected.iter().enumerate() {
+        let (header, resp) = frame_sync::read_response(&mut cursor).unwrap();
+        assert_eq!(header.frame_type(), Some(FrameType::Response));
+        assert_eq!(header.seq_id, i as u32 + 1);
+        assert_eq!(&format!("{:?}", resp), want);
+    }
+
+    assert_eq!(cursor.position() as usize, bytes.len());
+}
+
+#[test]
+fn v4_frame_header_layout() {
+    let bytes = std::fs::read(fixture("frames-v4.bin")).unwrap();
+
+    // "VR", type 0 (request) in the high nibble, version 4 in the low nibble
+    assert_eq!(&bytes[0..2], b"VR");
+    assert_eq!(bytes[2], 0x04);
+    assert_eq!(bytes[3], 0);
+    let length = u32::from_le_bytes(bytes[4..8].try_into().unwrap()) as usize;
+    assert_eq!(u32::from_le_bytes(bytes[8..12].try_into().unwrap()), 1);
+    assert!(IpcHeader::SIZE + length < bytes.len());
+}
+
+#[test]
+fn encoder_matches_current_frames_fixture() {
+    let path = fixture(&frames_fixture());
+    assert!(
+        path.exists(),
+        "missing {}: run write_current_fixtures after bumping PROTOCOL_VERSION",
+        path.display()
+    );
+    assert_eq!(
+        encode_frames(),
+        std::fs::read(path).unwrap(),
+        "IPC wire encoding changed: bump PROTOCOL_VERSION and add a new fixture"
+    );
+}
+
+// ============================================================================
+// ManifestMmap (legacy hot stat cache)
+// ============================================================================
+
+/// (path, size, mtime, mode, is_dir, is_symlink)
+const MMAP_ENTRIES: &[(&str, u64, i64, u32, bool, bool)] = &[
+    ("/src", 0, 1_700_000_000, 0o040755, true, false),
+    ("/src/main.rs", 120, 1_700_000_001, 0o100644, false, false),
+    ("/src/lib.rs", 2048, 1_700_000_002, 0o100644, false, false),
+    ("/README.md", 77, 1_700_000_003, 0o100644, false, false),
+    ("/link", 8, 1_700_000_004, 0o120777, false, true),
+];
+
+fn lookup_stat(bytes: &[u8], header: &ManifestMmapHeader, path: &str) -> Option<MmapStatEntry> {
+    let hash = fnv1a_hash(path);
+    let capacity = header.table_capacity as usize;
+    let start = hash as usize % capacity;
+    for i in 0..capacity {
+        let slot = (start + i) % capacity;
+        let entry = MmapStatEntry::read_le(
+            &bytes[header.table_offset as usize + slot * MmapStatEntry::SIZE..],
+        )
+        .unwrap();
+        if entry.is_empty() {
+            return None;
+        }
+        if entry.path_hash == hash {
+            return Some(entry);
+        }
+    }
+    None
+}
+
+fn list_dir(bytes: &[u8], header: &ManifestMmapHeader, dir: &str) -> Vec<String> {
+    let hash = fnv1a_hash(dir);
+    let capacity = header.dir_index_capacity as usize;
+    let start = hash as usize % capacity;
+    for i in 0..capacity {
+        let slot = (start + i) % capacity;
+        let entry = MmapDirIndexEntry::read_le(
+            &bytes[header.dir_index_offset as usize + slot * MmapDirIndexEntry::SIZE..],
+        )
+        .unwrap();
+        if entry.parent_hash == 0 {
+            break;
+        }
+        if entry.parent_hash == hash {
+            let mut names: Vec<String> = (0..entry.children_count as usize)
+                .map(|c| {
+                    let child = MmapDirChild::read_le(
+                        &bytes[header.children_offset as usize
+                            + (entry.children_start as usize + c) * MmapDirChild::SIZE..],
+                    )
+                    .unwrap();
+                    child.name_as_str().to_string()
+                })
+                .collect();
+            names.sort();
+            return names;
+        }
+    }
+    Vec::new()
+}
+
+fn check_manifest_mmap(name: &str, version: u32) {
+    let bytes = std::fs::read(fixture(name)).unwrap();
+    let header = ManifestMmapHeader::read_le(&bytes).unwrap();
+
+    assert!(header.is_valid());
+    assert_eq!(header.version, version);
+    assert_eq!(header.entry_count as usize, MMAP_ENTRIES.len());
+    assert_eq!(bytes.len(), header.file_size());
+
+    for &(path, size, mtime, mode, is_dir, is_symlink) in MMAP_ENTRIES {
+        let entry = lookup_stat(&bytes, &header, path).unwrap();
+        assert_eq!(entry.size, size, "{}", path);
+        assert_eq!(entry.mtime, mtime, "{}", path);
+        assert_eq!(entry.mode, mode, "{}", path);
+        assert_eq!(entry.is_dir(), is_dir, "{}", path);
+        assert_eq!(entry.is_symlink(), is_symlink, "{}", path);
+    }
+    assert!(lookup_stat(&bytes, &header, "/src/missing.rs").is_none());
+
+    assert_eq!(list_dir(&bytes, &header, "/src"), ["lib.rs", "main.rs"]);
+    assert_eq!(list_dir(&bytes, &header, "/"), ["README.md", "link", "src"]);
+
+    let bloom = &bytes[header.bloom_offset as usize..][..vrift_ipc::BLOOM_SIZE];
+    for &(path, ..) in MMAP_ENTRIES {
+        let (h1, h2) = vrift_ipc::bloom_hashes(path);
+        for bit in [h1, h2] {
+            let bit = bit % (vrift_ipc::BLOOM_SIZE * 8);
+            assert_ne!(bloom[bit / 8] & (1 << (bit % 8)), 0, "{}", path);
+        }
+    }
+}
+
+#[test]
+fn reads_v1_manifest_mmap() {
+    check_manifest_mmap("manifest-mmap-v1.bin", 1);
+    let bytes = std::fs::read(fixture("manifest-mmap-v1.bin")).unwrap();
+    assert!(!ManifestMmapHeader::read_le(&bytes)
+        .unwrap()
+        .has_generation());
+}
+
+#[test]
+fn reads_v2_manifest_mmap() {
+    check_manifest_mmap("manifest-mmap-v2.bin", 2);
+    let bytes = std::fs::read(fixture("manifest-mmap-v2.bin")).unwrap();
+    assert_eq!(ManifestMmapHeader::read_le(&bytes).unwrap().generation, 0);
+}
+
+/// Writes fixtures for the current versions if they are missing. Existing
+/// fixtures belong to past releases and are left untouched.
+#[test]
+#[ignore]
+fn write_current_fixtures() {
+    let dir = fixture("");
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let frames = dir.join(frames_fixture());
+    if !frames.exists() {
+        std::fs::write(&frames, encode_frames()).unwrap();
+    }
+
+    let mmap = dir.join(mmap_fixture());
+    if !mmap.exists() {
+        let mut builder = ManifestMmapBuilder::new();
+        for &(path, size, mtime, mode, is_dir, is_symlink) in MMAP_ENTRIES {
+            let mtime_ns = mtime * vrift_ipc::mtime::NANOS_PER_SEC;
+            builder.add_entry(path, size, mtime_ns, mode, is_dir, is_symlink, 0);
+        }
+        builder.write_to_file(mmap.to_str().unwrap()).unwrap();
+    }
+}
@@ -12,6 +12,7 @@ use std::path::Path;
 
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use vrift_error::{Classify, ErrorKind};
 
 #[derive(Error, Debug)]
 pub enum LockError {
@@ -22,6 +23,15 @@ pub enum LockError {
     Json(#[from] serde_json::Error),
 }
 
+impl Classify for LockError {
+    fn classify(&self) -> ErrorKind {
+        match self {
+            LockError::Io(e) => e.classify(),
+            LockError::Json(_) => ErrorKind::InvalidInput,
+        }
+    }
+}
+
 pub type Result<T> = std::result::Result<T, LockError>;
 
 /// Top-level vrift.lock structure
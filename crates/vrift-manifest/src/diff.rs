@@ -0,0 +1,146 @@
+//! # Manifest diffs
+//!
+//! What changed between two manifests, path by path. An entry counts as
+//! modified when its type, permission bits or recorded content hash differ;
+//! as with [digests](crate::digest), a new modification time alone is not a
+//! change. Only recorded hashes are compared, no file content is read.
+
+use std::collections::BTreeMap;
+
+use vrift_cas::Blake3Hash;
+
+use crate::{normalize_vfs_path, VnodeEntry};
+
+/// How a path changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+impl DiffKind {
+    /// Lowercase name, as in `vrift diff --json`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DiffKind::Added => "added",
+            DiffKind::Removed => "removed",
+            DiffKind::Modified => "modified",
+        }
+    }
+}
+
+/// One changed path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffEntry {
+    pub path: String,
+    pub kind: DiffKind,
+    /// Content hash before; `None` if added
+    pub old_hash: Option<Blake3Hash>,
+    /// Content hash after; `None` if removed
+    pub new_hash: Option<Blake3Hash>,
+}
+
+/// Changes from the entries `old` to the entries `new`, sorted by path
+pub fn diff_entries<'a, I, J>(old: I, new: J) -> Vec<DiffEntry>
+where
+    I: IntoIterator<Item = (&'a str, &'a VnodeEntry)>,
+    J: IntoIterator<Item = (&'a str, &'a VnodeEntry)>,
+{
+    let mut old: BTreeMap<String, &VnodeEntry> = old
+        .into_iter()
+        .map(|(path, entry)| (normalize_vfs_path(path), entry))
+        .collect();
+    let new: BTreeMap<String, &VnodeEntry> = new
+        .into_iter()
+        .map(|(path, entry)| (normalize_vfs_path(path), entry))
+        .collect();
+
+    let mut changes = Vec::new();
+    for (path, after) in new {
+        match old.remove(&path) {
+            None => changes.push(DiffEntry {
+                path,
+                kind: DiffKind::Added,
+                old_hash: None,
+                new_hash: Some(after.content_hash),
+            }),
+            Some(before) if changed(before, after) => changes.push(DiffEntry {
+                path,
+                kind: DiffKind::Modified,
+                old_hash: Some(before.content_hash),
+                new_hash: Some(after.content_hash),
+            }),
+            Some(_) => {}
+        }
+    }
+    changes.extend(old.into_iter().map(|(path, before)| DiffEntry {
+        path,
+        kind: DiffKind::Removed,
+        old_hash: Some(before.content_hash),
+        new_hash: None,
+    }));
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    changes
+}
+
+fn changed(before: &VnodeEntry, after: &VnodeEntry) -> bool {
+    before.flags != after.flags
+        || before.mode != after.mode
+        || before.content_hash != after.content_hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(byte: u8) -> VnodeEntry {
+        VnodeEntry::new_file([byte; 32], 10, 1, 0o644)
+    }
+
+    #[test]
+    fn test_diff_added_removed_modified() {
+        let (a, b, c) = (file(1), file(2), file(3));
+        let old = [("/keep", &a), ("/edit", &a), ("/gone", &b)];
+        let new = [("/new", &c), ("/edit", &b), ("/keep", &a)];
+
+        let changes = diff_entries(old, new);
+        let summary: Vec<(&str, DiffKind)> =
+            changes.iter().map(|d| (d.path.as_str(), d.kind)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("/edit", DiffKind::Modified),
+                ("/gone", DiffKind::Removed),
+                ("/new", DiffKind::Added),
+            ]
+        );
+        assert_eq!(
+            (changes[0].old_hash, changes[0].new_hash),
+            (Some([1; 32]), Some([2; 32]))
+        );
+        assert_eq!(
+            (changes[1].old_hash, changes[1].new_hash),
+            (Some([2; 32]), None)
+        );
+        assert_eq!(
+            (changes[2].old_hash, changes[2].new_hash),
+            (None, Some([3; 32]))
+        );
+    }
+
+    #[test]
+    fn test_diff_ignores_mtime_but_not_mode() {
+        let a = file(1);
+        let mut touched = a.clone();
+        touched.mtime = 999;
+        let mut chmodded = a.clone();
+        chmodded.mode = 0o755;
+
+        assert!(diff_entries([("/a", &a)], [("a", &touched)]).is_empty());
+        let changes = diff_entries([("/a", &a)], [("/a", &chmodded)]);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].kind, DiffKind::Modified);
+        assert_eq!(changes[0].old_hash, changes[0].new_hash);
+    }
+}
@@ -0,0 +1,142 @@
+//! # Path-set digests
+//!
+//! Stable cache keys computed from manifest entries alone. A digest covers a
+//! set of roots (files or directory prefixes) and folds in, for every entry
+//! underneath them in path order: the path, entry type, permission bits and
+//! recorded content hash. Modification times are deliberately left out so
+//! that touching a file without changing it keeps the key stable.
+//!
+//! No file content is read; staleness of the recorded hashes is the caller's
+//! concern (see [`ManifestEntry::stale`](crate::ManifestEntry)).
+
+use vrift_cas::Blake3Hash;
+
+use crate::{normalize_vfs_path, VnodeEntry};
+
+/// Domain separator, bumped if the encoding below ever changes
+const DIGEST_DOMAIN: &[u8] = b"vrift-path-digest-v1\0";
+
+/// Result of digesting a set of roots
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathDigest {
+    /// Combined BLAKE3 digest
+    pub digest: Blake3Hash,
+    /// Number of manifest entries folded into the digest
+    pub entries: usize,
+    /// Roots that matched no entry at all
+    pub missing: Vec<String>,
+}
+
+impl PathDigest {
+    /// Lowercase hex form of the digest
+    pub fn to_hex(&self) -> String {
+        self.digest.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
+
+/// True if manifest key `path` is `root` itself or lies underneath it
+pub fn covers(root: &str, path: &str) -> bool {
+    root == "/"
+        || path
+            .strip_prefix(root)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// Digest every entry covered by `roots`.
+///
+/// Roots are manifest keys (`/src`, `/Cargo.toml`); `/` covers everything.
+/// The result depends only on the covered entries, not on the order of
+/// `entries` or `roots`, nor on duplicates among the roots.
+pub fn digest_paths<'a, I>(entries: I, roots: &[&str]) -> PathDigest
+where
+    I: IntoIterator<Item = (&'a str, &'a VnodeEntry)>,
+{
+    let roots: Vec<String> = roots.iter().map(|r| normalize_vfs_path(r)).collect();
+    let mut matched = vec![false; roots.len()];
+
+    let mut covered: Vec<(String, &VnodeEntry)> = Vec::new();
+    for (path, entry) in entries {
+        let path = normalize_vfs_path(path);
+        let mut hit = false;
+        for (i, root) in roots.iter().enumerate() {
+            if covers(root, &path) {
+                matched[i] = true;
+                hit = true;
+            }
+        }
+        if hit {
+            covered.push((path, entry));
+        }
+    }
+    covered.sort_by(|a, b| a.0.cmp(&b.0));
+    covered.dedup_by(|a, b| a.0 == b.0);
+
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(DIGEST_DOMAIN);
+    for (path, entry) in &covered {
+        hasher.update(&(path.len() as u64).to_le_bytes());
+        hasher.update(path.as_bytes());
+        hasher.update(&entry.flags.to_le_bytes());
+        hasher.update(&entry.mode.to_le_bytes());
+        hasher.update(&entry.content_hash);
+    }
+
+    let mut missing: Vec<String> = roots
+        .into_iter()
+        .zip(matched)
+        .filter(|(_, hit)| !hit)
+        .map(|(root, _)| root)
+        .collect();
+    missing.sort();
+    missing.dedup();
+
+    PathDigest {
+        digest: *hasher.finalize().as_bytes(),
+        entries: covered.len(),
+        missing,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(byte: u8) -> VnodeEntry {
+        VnodeEntry::new_file([byte; 32], 10, 1, 0o644)
+    }
+
+    #[test]
+    fn test_digest_is_order_independent() {
+        let a = file(1);
+        let b = file(2);
+        let forward = digest_paths([("/src/a.rs", &a), ("/src/b.rs", &b)], &["/src"]);
+        let reverse = digest_paths([("/src/b.rs", &b), ("/src/a.rs", &a)], &["/src/", "/src"]);
+        assert_eq!(forward.digest, reverse.digest);
+        assert_eq!(forward.entries, 2);
+        assert!(forward.missing.is_empty());
+    }
+
+    #[test]
+    fn test_digest_ignores_mtime_but_not_content() {
+        let a = file(1);
+        let mut touched = a.clone();
+        touched.mtime = 999;
+        let mut edited = a.clone();
+        edited.content_hash = [9; 32];
+
+        let base = digest_paths([("/a", &a)], &["/a"]);
+        assert_eq!(base, digest_paths([("/a", &touched)], &["/a"]));
+        assert_ne!(base.digest, digest_paths([("/a", &edited)], &["/a"]).digest);
+    }
+
+    #[test]
+    fn test_digest_scoping_and_missing() {
+        let a = file(1);
+        let b = file(2);
+        let entries = [("/src/a.rs", &a), ("/srcx/b.rs", &b)];
+        let result = digest_paths(entries, &["/src", "/docs"]);
+        assert_eq!(result.entries, 1);
+        assert_eq!(result.missing, vec!["/docs".to_string()]);
+        assert_eq!(digest_paths(entries, &["/"]).entries, 2);
+    }
+}
@@ -0,0 +1,219 @@
+//! Write-ahead journal of the delta layer of [`LmdbManifest`](crate::LmdbManifest).
+//!
+//! vDird commits its delta every 30 seconds; a daemon killed in between
+//! used to lose every change since the last commit, leaving the manifest
+//! behind the CAS and the files on disk. Each change of the delta is now
+//! appended to `delta.journal` in the manifest directory before it is
+//! applied, the journal is emptied once a commit lands, and the next open
+//! replays whatever it still holds.
+//!
+//! Records are framed as `len: u32 LE | check: [u8; 4] | bincode payload`,
+//! `check` being the first bytes of the payload's BLAKE3 hash. A process
+//! killed mid-append leaves a torn last record; replay stops there.
+//! Records are not fsynced: they survive the process, not the machine.
+//!
+//! One process owns the journal at a time, holding an exclusive lock on the
+//! file. A manifest opened while another process owns it commits without
+//! journaling, as every manifest did before.
+
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::lmdb::{LmdbError, LmdbResult, ManifestEntry};
+use crate::PathHash;
+
+/// Journal file inside the manifest directory
+pub const JOURNAL_FILE: &str = "delta.journal";
+
+/// Bytes before each payload: length and check
+const FRAME_HEADER: usize = 8;
+
+/// One change of the delta layer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum JournalRecord {
+    /// An entry was inserted or replaced; `path` is kept when `None`
+    Put {
+        hash: PathHash,
+        path: Option<String>,
+        entry: ManifestEntry,
+        /// Not serialized with the entry itself
+        ingested_at: i64,
+    },
+    /// An entry was removed
+    Delete { hash: PathHash },
+}
+
+/// The journal of a manifest directory, open for appending
+#[derive(Debug)]
+pub struct Journal {
+    file: File,
+}
+
+impl Journal {
+    /// Take the journal of the manifest directory `dir`, creating it if
+    /// needed. `Ok(None)` if another process owns it.
+    pub fn open(dir: &Path) -> LmdbResult<Option<Self>> {
+        let file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(dir.join(JOURNAL_FILE))?;
+        match file.try_lock() {
+            Ok(()) => Ok(Some(Self { file })),
+            Err(TryLockError::WouldBlock) => Ok(None),
+            Err(TryLockError::Error(e)) => Err(e.into()),
+        }
+    }
+
+    /// The records in the journal, oldest first, up to a torn or corrupt one
+    pub fn records(&mut self) -> LmdbResult<Vec<JournalRecord>> {
+        let mut bytes = Vec::new();
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.read_to_end(&mut bytes)?;
+        let (records, used) = decode(&bytes);
+        if used < bytes.len() {
+            warn!(
+                dropped = bytes.len() - used,
+                "Manifest journal ends in a torn record, dropping it"
+            );
+        }
+        Ok(records)
+    }
+
+    /// Append `record`
+    pub fn append(&mut self, record: &JournalRecord) -> LmdbResult<()> {
+        let payload = bincode::serialize(record)
+            .map_err(|e| LmdbError::Corrupted(format!("journal record: {}", e)))?;
+        let len = u32::try_from(payload.len())
+            .map_err(|_| LmdbError::Corrupted("journal record too large".to_string()))?;
+        let mut frame = Vec::with_capacity(FRAME_HEADER + payload.len());
+        frame.extend_from_slice(&len.to_le_bytes());
+        frame.extend_from_slice(&check(&payload));
+        frame.extend_from_slice(&payload);
+        // One write, so a kill leaves at most the last record torn
+        self.file.write_all(&frame)?;
+        Ok(())
+    }
+
+    /// Drop every record: they are all committed
+    pub fn clear(&mut self) -> LmdbResult<()> {
+        self.file.set_len(0)?;
+        Ok(())
+    }
+}
+
+/// Whether the manifest directory `dir` has journaled changes that no
+/// commit has applied yet
+pub fn has_pending(dir: &Path) -> bool {
+    std::fs::metadata(dir.join(JOURNAL_FILE)).is_ok_and(|m| m.len() > 0)
+}
+
+fn check(payload: &[u8]) -> [u8; 4] {
+    let hash = blake3::hash(payload);
+    let mut check = [0u8; 4];
+    check.copy_from_slice(&hash.as_bytes()[..4]);
+    check
+}
+
+/// Records framed in `bytes`, and how many bytes they take
+fn decode(bytes: &[u8]) -> (Vec<JournalRecord>, usize) {
+    let mut records = Vec::new();
+    let mut used = 0;
+    while let Some(header) = bytes.get(used..used + FRAME_HEADER) {
+        let len = u32::from_le_bytes(header[..4].try_into().unwrap()) as usize;
+        let Some(payload) = bytes.get(used + FRAME_HEADER..used + FRAME_HEADER + len) else {
+            break;
+        };
+        if header[4..] != check(payload) {
+            break;
+        }
+        let Ok(record) = bincode::deserialize(payload) else {
+            break;
+        };
+        records.push(record);
+        used += FRAME_HEADER + len;
+    }
+    (records, used)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lmdb::AssetTier;
+    use crate::VnodeEntry;
+
+    fn put(i: u8) -> JournalRecord {
+        JournalRecord::Put {
+            hash: [i; 32],
+            path: Some(format!("/file_{}", i)),
+            entry: ManifestEntry {
+                vnode: VnodeEntry::new_file([i; 32], i as u64, 0, 0o644),
+                tier: AssetTier::Tier2Mutable,
+                stale: false,
+                ingested_at: 0,
+            },
+            ingested_at: i as i64,
+        }
+    }
+
+    #[test]
+    fn test_journal_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut journal = Journal::open(dir.path()).unwrap().unwrap();
+        journal.append(&put(1)).unwrap();
+        journal
+            .append(&JournalRecord::Delete { hash: [2; 32] })
+            .unwrap();
+        assert!(has_pending(dir.path()));
+
+        let records = journal.records().unwrap();
+        assert_eq!(records.len(), 2);
+        assert!(matches!(
+            &records[0],
+            JournalRecord::Put { ingested_at: 1, .. }
+        ));
+        assert!(matches!(&records[1], JournalRecord::Delete { hash } if *hash == [2; 32]));
+
+        journal.clear().unwrap();
+        assert!(!has_pending(dir.path()));
+        journal.append(&put(3)).unwrap();
+        assert_eq!(journal.records().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_journal_stops_at_torn_record() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let mut journal = Journal::open(dir.path()).unwrap().unwrap();
+            journal.append(&put(1)).unwrap();
+            journal.append(&put(2)).unwrap();
+        }
+        let path = dir.path().join(JOURNAL_FILE);
+        let len = std::fs::metadata(&path).unwrap().len();
+        let file = OpenOptions::new().write(true).open(&path).unwrap();
+        file.set_len(len - 3).unwrap();
+
+        let mut journal = Journal::open(dir.path()).unwrap().unwrap();
+        assert_eq!(journal.records().unwrap().len(), 1);
+
+        // A flipped payload byte fails the check
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[FRAME_HEADER + 2] ^= 0xff;
+        std::fs::write(&path, &bytes).unwrap();
+        assert!(journal.records().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_journal_has_one_owner() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = Journal::open(dir.path()).unwrap();
+        assert!(journal.is_some());
+        assert!(Journal::open(dir.path()).unwrap().is_none());
+        drop(journal);
+        assert!(Journal::open(dir.path()).unwrap().is_some());
+    }
+}
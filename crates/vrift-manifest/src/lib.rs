@@ -8,13 +8,33 @@
 //! ## Storage Backends
 //!
 //! - `Manifest`: In-memory HashMap with rkyv file persistence
-//! - `LmdbManifest`: LMDB-backed with ACID transactions (RFC-0039)
+//! - `LmdbManifest`: ACID transactions over a [`store::ManifestStore`],
+//!   LMDB (RFC-0039) or redb, chosen by `storage.manifest_backend`
+//!
+//! Both backends can produce a [`PathDigest`] over a set of paths for use as a
+//! build cache key. [`Manifest::diff`] and [`snapshot::diff`] list what
+//! changed between two manifests.
 
+pub mod diff;
+pub mod digest;
+pub mod journal;
 pub mod lmdb;
+pub mod redb_store;
+pub mod snapshot;
+pub mod store;
 pub mod tier;
 
-pub use lmdb::{AssetTier, LmdbError, LmdbManifest, LmdbResult, ManifestEntry};
-pub use tier::{classify_tier, TierClassifier, DEFAULT_TIER1_PATTERNS, DEFAULT_TIER2_PATTERNS};
+pub use diff::{diff_entries, DiffEntry, DiffKind};
+pub use digest::{digest_paths, PathDigest};
+pub use lmdb::{
+    AssetTier, LmdbError, LmdbManifest, LmdbResult, ManifestEntry, RepairReport, RestoreReport,
+};
+pub use snapshot::Snapshot;
+pub use store::{ManifestBackend, ManifestStore};
+pub use tier::{
+    classify_tier, TierChange, TierClassifier, TierLedger, DEFAULT_TIER1_PATTERNS,
+    DEFAULT_TIER2_PATTERNS,
+};
 
 use std::collections::HashMap;
 use std::fs::File;
@@ -26,6 +46,7 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use vrift_cas::Blake3Hash;
+use vrift_error::{Classify, ErrorKind};
 
 /// Errors that can occur during manifest operations
 #[derive(Error, Debug)]
@@ -40,6 +61,16 @@ pub enum ManifestError {
     PathNotFound(String),
 }
 
+impl Classify for ManifestError {
+    fn classify(&self) -> ErrorKind {
+        match self {
+            ManifestError::Io(e) => e.classify(),
+            ManifestError::Rkyv(_) => ErrorKind::Corrupted,
+            ManifestError::PathNotFound(_) => ErrorKind::NotFound,
+        }
+    }
+}
+
 pub type Result<T> = std::result::Result<T, ManifestError>;
 
 /// Flags for VnodeEnt#[repr(u8)]
@@ -90,11 +121,13 @@ pub struct VnodeEntry {
     pub content_hash: Blake3Hash,
     /// File size in bytes
     pub size: u64,
-    /// Modification time (nanoseconds since Unix epoch)
-    pub mtime: u64,
+    /// Modification time in signed nanoseconds since the Unix epoch
+    /// (see `vrift_ipc::mtime` for conversions)
+    pub mtime: i64,
     /// Permission mode bits (e.g., 0o644)
     pub mode: u32,
-    /// Entry type flags
+    /// Entry type ([`VnodeFlags`]) in the low byte; storage bits
+    /// (`FLAG_COMPRESSED`, ...) above it
     pub flags: u16,
     /// Padding for alignment
     #[serde(skip)]
@@ -103,8 +136,26 @@ pub struct VnodeEntry {
 }
 
 impl VnodeEntry {
+    /// Bits of `flags` holding the [`VnodeFlags`] entry type
+    pub const TYPE_MASK: u16 = 0x00FF;
+    /// The CAS blob is compressed: its bytes are not the file content
+    pub const FLAG_COMPRESSED: u16 = 0x0100;
+    /// The CAS blob is encrypted: its bytes are not the file content
+    pub const FLAG_ENCRYPTED: u16 = 0x0200;
+    /// The content is stored in `content_hash` itself and there is no blob
+    pub const FLAG_INLINE: u16 = 0x0400;
+    /// The content is a real file elsewhere; the CAS blob holds its absolute
+    /// path (see [`Self::new_external`])
+    pub const FLAG_EXTERNAL: u16 = 0x1000;
+    /// Bits describing how the content is stored rather than what the entry is.
+    /// vDird's VDir entries use the same bits, so they survive projection.
+    pub const STORAGE_MASK: u16 =
+        Self::FLAG_COMPRESSED | Self::FLAG_ENCRYPTED | Self::FLAG_INLINE | Self::FLAG_EXTERNAL;
+    /// Largest file that can be stored inline
+    pub const INLINE_MAX: usize = 32;
+
     /// Create a new VnodeEntry for a regular file
-    pub fn new_file(content_hash: Blake3Hash, size: u64, mtime: u64, mode: u32) -> Self {
+    pub fn new_file(content_hash: Blake3Hash, size: u64, mtime: i64, mode: u32) -> Self {
         Self {
             content_hash,
             size,
@@ -116,7 +167,7 @@ impl VnodeEntry {
     }
 
     /// Create a new VnodeEntry for a directory
-    pub fn new_directory(mtime: u64, mode: u32) -> Self {
+    pub fn new_directory(mtime: i64, mode: u32) -> Self {
         Self {
             content_hash: [0u8; 32],
             size: 0,
@@ -131,7 +182,7 @@ impl VnodeEntry {
     ///
     /// `target_hash` is the hash of the target path string.
     /// `target_len` is the length of the target path string.
-    pub fn new_symlink(target_hash: Blake3Hash, target_len: u64, mtime: u64) -> Self {
+    pub fn new_symlink(target_hash: Blake3Hash, target_len: u64, mtime: i64) -> Self {
         Self {
             content_hash: target_hash,
             size: target_len,
@@ -142,6 +193,39 @@ impl VnodeEntry {
         }
     }
 
+    /// Create a regular file whose content lives in the entry itself.
+    /// Returns `None` if `content` is longer than [`Self::INLINE_MAX`].
+    pub fn new_inline(content: &[u8], mtime: i64, mode: u32) -> Option<Self> {
+        let mut content_hash = [0u8; 32];
+        content_hash
+            .get_mut(..content.len())?
+            .copy_from_slice(content);
+        Some(Self {
+            content_hash,
+            size: content.len() as u64,
+            mtime,
+            mode,
+            flags: VnodeFlags::File as u16 | Self::FLAG_INLINE,
+            _pad: 0,
+        })
+    }
+
+    /// Create a regular file served from a real file instead of the CAS.
+    ///
+    /// As for a symlink, `path_hash` is the hash of the absolute path string,
+    /// stored as a CAS blob, and `path_len` its length; readers stat the real
+    /// file for its size. `mtime` and `mode` are the real file's at ingest.
+    pub fn new_external(path_hash: Blake3Hash, path_len: u64, mtime: i64, mode: u32) -> Self {
+        Self {
+            content_hash: path_hash,
+            size: path_len,
+            mtime,
+            mode,
+            flags: VnodeFlags::File as u16 | Self::FLAG_EXTERNAL,
+            _pad: 0,
+        }
+    }
+
     /// Check if this entry is a directory
     pub fn is_dir(&self) -> bool {
         self.flags & (VnodeFlags::Directory as u16) != 0
@@ -149,7 +233,7 @@ impl VnodeEntry {
 
     /// Check if this entry is a regular file
     pub fn is_file(&self) -> bool {
-        self.flags == VnodeFlags::File as u16
+        self.flags & Self::TYPE_MASK == VnodeFlags::File as u16
     }
 
     /// Check if this entry is a symbolic link
@@ -161,6 +245,41 @@ impl VnodeEntry {
     pub fn is_executable(&self) -> bool {
         self.flags & (VnodeFlags::Executable as u16) != 0
     }
+
+    /// Whether the CAS blob is stored compressed
+    pub fn is_compressed(&self) -> bool {
+        self.flags & Self::FLAG_COMPRESSED != 0
+    }
+
+    /// Whether the CAS blob is stored encrypted
+    pub fn is_encrypted(&self) -> bool {
+        self.flags & Self::FLAG_ENCRYPTED != 0
+    }
+
+    /// Whether the content is stored inline (see [`Self::inline_content`])
+    pub fn is_inline(&self) -> bool {
+        self.flags & Self::FLAG_INLINE != 0
+    }
+
+    /// Whether the entry references a real file (see [`Self::new_external`])
+    pub fn is_external(&self) -> bool {
+        self.flags & Self::FLAG_EXTERNAL != 0
+    }
+
+    /// The file content of an inline entry; `None` for other entries and for
+    /// inline entries whose size does not fit
+    pub fn inline_content(&self) -> Option<&[u8]> {
+        if !self.is_inline() {
+            return None;
+        }
+        self.content_hash.get(..usize::try_from(self.size).ok()?)
+    }
+
+    /// Whether the CAS blob named by `content_hash` holds the file content
+    /// byte for byte, so it can be handed to readers directly
+    pub fn is_blob_raw(&self) -> bool {
+        self.flags & Self::STORAGE_MASK == 0
+    }
 }
 
 /// Path hash type - hash of the normalized path string
@@ -264,6 +383,16 @@ impl Manifest {
         self.paths.values().map(|s| s.as_str())
     }
 
+    /// Combined digest of the entries under `roots` (see [`digest_paths`])
+    pub fn digest(&self, roots: &[&str]) -> PathDigest {
+        digest_paths(self.iter(), roots)
+    }
+
+    /// Changes from this manifest to `other` (see [`diff_entries`])
+    pub fn diff(&self, other: &Manifest) -> Vec<DiffEntry> {
+        diff_entries(self.iter(), other.iter())
+    }
+
     /// Save the manifest to a file using rkyv
     pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
         let data = rkyv::to_bytes::<rkyv::rancor::Error>(self)
@@ -340,6 +469,41 @@ mod tests {
         assert!(!entry.is_dir());
     }
 
+    #[test]
+    fn test_storage_flags() {
+        let file = VnodeEntry::new_file([0u8; 32], 10, 0, 0o644);
+        assert!(file.is_blob_raw());
+        assert_eq!(file.inline_content(), None);
+
+        let inline = VnodeEntry::new_inline(b"hello\n", 0, 0o644).unwrap();
+        assert!(inline.is_file());
+        assert!(inline.is_inline());
+        assert!(!inline.is_blob_raw());
+        assert_eq!(inline.size, 6);
+        assert_eq!(inline.inline_content(), Some(&b"hello\n"[..]));
+        assert!(VnodeEntry::new_inline(&[0u8; VnodeEntry::INLINE_MAX], 0, 0o644).is_some());
+        assert!(VnodeEntry::new_inline(&[0u8; VnodeEntry::INLINE_MAX + 1], 0, 0o644).is_none());
+
+        let mut compressed = file.clone();
+        compressed.flags |= VnodeEntry::FLAG_COMPRESSED;
+        assert!(compressed.is_file());
+        assert!(compressed.is_compressed());
+        assert!(!compressed.is_encrypted());
+        assert!(!compressed.is_blob_raw());
+
+        let external = VnodeEntry::new_external([2u8; 32], 17, 0, 0o100644);
+        assert!(external.is_file());
+        assert!(external.is_external());
+        assert!(!external.is_blob_raw());
+        assert_eq!(external.inline_content(), None);
+        assert!(!file.is_external());
+
+        // Oversized inline entries are rejected rather than read past the hash
+        let mut corrupt = inline;
+        corrupt.size = 33;
+        assert_eq!(corrupt.inline_content(), None);
+    }
+
     #[test]
     fn test_manifest_insert_get() {
         let mut manifest = Manifest::new();
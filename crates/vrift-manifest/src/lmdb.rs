@@ -1,19 +1,26 @@
 //! LMDB-backed Manifest for persistent, crash-safe path→hash mapping.
 //!
 //! Implements dual-layer architecture:
-//! - Base Layer: Immutable entries (LMDB)
+//! - Base Layer: Immutable entries ([`ManifestStore`]: LMDB, or redb)
 //! - Delta Layer: Mutable modifications (DashMap)
 
-use std::path::Path;
-use std::sync::Arc;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
 
 use dashmap::DashMap;
-use heed::types::{Bytes, SerdeBincode, Str};
-use heed::{Database, Env, EnvOpenOptions};
+use heed::byteorder::LE;
+use heed::types::{Bytes, SerdeBincode, Str, I64};
+use heed::{Database, Env, EnvFlags, EnvOpenOptions, MdbError, RoTxn};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
-use tracing::debug;
+use tracing::{debug, warn};
+use vrift_cas::CasStore;
+use vrift_error::{Classify, ErrorKind};
 
+use crate::journal::{Journal, JournalRecord};
+use crate::snapshot::{self, Snapshot};
+use crate::store::{ManifestBackend, ManifestStore, StoreOp};
 use crate::{compute_path_hash, PathHash, VnodeEntry};
 
 /// LMDB Manifest errors
@@ -30,6 +37,39 @@ pub enum LmdbError {
 
     #[error("Manifest corrupted: {0}")]
     Corrupted(String),
+
+    #[error("redb error: {0}")]
+    Redb(Box<redb::Error>),
+
+    #[error("Manifest is open read-only")]
+    ReadOnly,
+
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    #[error("CAS error: {0}")]
+    Cas(#[from] vrift_cas::CasError),
+}
+
+impl Classify for LmdbError {
+    fn classify(&self) -> ErrorKind {
+        match self {
+            LmdbError::Heed(heed::Error::Io(e)) | LmdbError::Io(e) => e.classify(),
+            LmdbError::Heed(heed::Error::Mdb(heed::MdbError::Corrupted))
+            | LmdbError::Heed(heed::Error::Decoding(_))
+            | LmdbError::Corrupted(_) => ErrorKind::Corrupted,
+            LmdbError::Heed(_) => ErrorKind::Internal,
+            LmdbError::Redb(e) => match e.as_ref() {
+                redb::Error::Io(e) => e.classify(),
+                redb::Error::Corrupted(_) => ErrorKind::Corrupted,
+                _ => ErrorKind::Internal,
+            },
+            LmdbError::NotFound(_) => ErrorKind::NotFound,
+            LmdbError::ReadOnly => ErrorKind::PermissionDenied,
+            LmdbError::InvalidInput(_) => ErrorKind::InvalidInput,
+            LmdbError::Cas(e) => e.classify(),
+        }
+    }
 }
 
 pub type LmdbResult<T> = std::result::Result<T, LmdbError>;
@@ -60,6 +100,12 @@ pub struct ManifestEntry {
     /// Whether this entry is "stale" (pending re-ingest)
     #[serde(default)]
     pub stale: bool,
+
+    /// When this version of the entry was ingested, in signed nanoseconds
+    /// since the epoch; 0 if unknown (recorded before ingest times were
+    /// kept). Lives in its own table so existing entries still decode.
+    #[serde(skip)]
+    pub ingested_at: i64,
 }
 
 /// Delta entry for in-memory modifications
@@ -71,42 +117,54 @@ pub enum DeltaEntry {
     Deleted,
 }
 
-/// LMDB-backed manifest with dual-layer architecture
+/// Manifest with dual-layer architecture
 ///
-/// Base Layer (LMDB): Immutable, O(1) mmap reads, ACID transactions
-/// Delta Layer (DashMap): Mutable, per-session modifications
+/// Base Layer ([`ManifestStore`]): committed entries, ACID transactions;
+/// LMDB by default, see [`crate::store`] for the others
+/// Delta Layer (DashMap): Mutable, per-session modifications, journaled
+/// (see [`crate::journal`]) until they are committed
 pub struct LmdbManifest {
-    /// LMDB environment
-    env: Env,
-
-    /// Path hash → ManifestEntry database
-    entries_db: Database<Bytes, SerdeBincode<ManifestEntry>>,
-
-    /// Path hash → original path string database
-    paths_db: Database<Bytes, Str>,
+    /// Committed entries
+    store: Box<dyn ManifestStore>,
 
     /// Delta layer for uncommitted modifications
     delta: Arc<DashMap<PathHash, DeltaEntry>>,
 
     /// Path hash → path string for delta entries
     delta_paths: Arc<DashMap<PathHash, String>>,
-}
 
-impl LmdbManifest {
-    /// Default LMDB map size: 1GB (expandable)
-    const DEFAULT_MAP_SIZE: usize = 1024 * 1024 * 1024;
+    /// Opened with [`LmdbManifest::open_readonly`]: commits fail
+    read_only: bool,
 
-    /// Maximum readers
-    const MAX_READERS: u32 = 128;
+    /// Write-ahead journal of the delta layer; None when read-only or
+    /// owned by another process. Held across a commit.
+    journal: Option<Mutex<Journal>>,
 
-    /// Open or create an LMDB manifest at the given path
+    /// Journaled changes replayed at open
+    recovered: usize,
+
+    /// Manifest directory, holding the journal and the snapshot names
+    dir: PathBuf,
+}
+
+impl LmdbManifest {
+    /// Open or create a manifest at the given path
     ///
-    /// Path should point to a directory that will contain the LMDB files.
+    /// Path should point to a directory that will contain the store files.
+    /// An existing manifest is opened with the backend it was created with,
+    /// a new one with [`crate::store::default_backend`].
     pub fn open<P: AsRef<Path>>(path: P) -> LmdbResult<Self> {
         let path = path.as_ref();
+        let backend = ManifestBackend::detect(path).unwrap_or_else(crate::store::default_backend);
+        Self::open_with(path, backend)
+    }
+
+    /// Open or create a manifest at the given path with `backend`
+    pub fn open_with<P: AsRef<Path>>(path: P, backend: ManifestBackend) -> LmdbResult<Self> {
+        let path = path.as_ref();
 
         // If path exists as a regular file (e.g., legacy flat manifest),
-        // remove it first — LMDB needs a directory.
+        // remove it first — the stores need a directory.
         if path.is_file() {
             std::fs::remove_file(path)?;
         }
@@ -114,29 +172,69 @@ impl LmdbManifest {
         // Create directory if needed
         std::fs::create_dir_all(path)?;
 
-        // Open LMDB environment
-        let env = unsafe {
-            EnvOpenOptions::new()
-                .map_size(Self::DEFAULT_MAP_SIZE)
-                .max_readers(Self::MAX_READERS)
-                .max_dbs(2)
-                .open(path)?
+        let store = crate::store::open_store(path, backend)?;
+        debug!("Opened {} manifest at {:?}", backend.as_str(), path);
+
+        let mut journal = Journal::open(path)?;
+        if journal.is_none() {
+            debug!("Manifest journal at {:?} is owned by another process", path);
+        }
+        let records = match journal.as_mut() {
+            Some(journal) => journal.records()?,
+            None => Vec::new(),
         };
 
-        // Open databases
-        let mut wtxn = env.write_txn()?;
-        let entries_db = env.create_database(&mut wtxn, Some("entries"))?;
-        let paths_db = env.create_database(&mut wtxn, Some("paths"))?;
-        wtxn.commit()?;
+        let manifest = Self {
+            store,
+            delta: Arc::new(DashMap::new()),
+            delta_paths: Arc::new(DashMap::new()),
+            read_only: false,
+            journal: journal.map(Mutex::new),
+            recovered: records.len(),
+            dir: path.to_path_buf(),
+        };
 
-        debug!("Opened LMDB manifest at {:?}", path);
+        // A previous owner stopped before committing these
+        if !records.is_empty() {
+            for record in records {
+                manifest.apply_to_delta(record);
+            }
+            manifest.commit()?;
+            warn!(
+                count = manifest.recovered,
+                "Recovered uncommitted manifest changes from journal"
+            );
+        }
+        Ok(manifest)
+    }
+
+    /// Open an existing manifest for inspection only
+    ///
+    /// Nothing is created or removed, and no write transaction is begun, so
+    /// CLI tools can read while vDird commits: an LMDB manifest opens with
+    /// `MDB_RDONLY` and waits on no writer. A redb manifest still takes its
+    /// file lock. The delta layer works as usual, but [`LmdbManifest::commit`]
+    /// fails with [`LmdbError::ReadOnly`].
+    pub fn open_readonly<P: AsRef<Path>>(path: P) -> LmdbResult<Self> {
+        let path = path.as_ref();
+        let Some(backend) = ManifestBackend::detect(path) else {
+            return Err(LmdbError::NotFound(path.display().to_string()));
+        };
+        let store = crate::store::open_store_readonly(path, backend)?;
+        debug!(
+            "Opened {} manifest at {:?} read-only",
+            backend.as_str(),
+            path
+        );
 
         Ok(Self {
-            env,
-            entries_db,
-            paths_db,
+            store,
             delta: Arc::new(DashMap::new()),
             delta_paths: Arc::new(DashMap::new()),
+            read_only: true,
+            journal: None,
+            recovered: 0,
+            dir: path.to_path_buf(),
         })
     }
 
@@ -145,16 +243,74 @@ impl LmdbManifest {
         Self::open(".vrift/manifest.lmdb")
     }
 
-    /// Insert an entry into the delta layer (uncommitted)
+    /// The backend holding the committed entries
+    pub fn backend(&self) -> ManifestBackend {
+        self.store.backend()
+    }
+
+    /// Whether the manifest was opened with [`LmdbManifest::open_readonly`]
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Journaled changes replayed (and committed) when the manifest was
+    /// opened: the previous owner stopped before committing them
+    pub fn recovered(&self) -> usize {
+        self.recovered
+    }
+
+    /// Insert an entry into the delta layer (uncommitted), stamped with the
+    /// current time as its ingest time
     pub fn insert(&self, path: &str, vnode: VnodeEntry, tier: AssetTier) {
-        let hash = compute_path_hash(path);
         let entry = ManifestEntry {
             vnode,
             tier,
             stale: false,
+            ingested_at: now_ns(),
         };
-        self.delta.insert(hash, DeltaEntry::Modified(entry));
-        self.delta_paths.insert(hash, path.to_string());
+        self.stage_put(compute_path_hash(path), Some(path.to_string()), entry);
+    }
+
+    /// Journal `record`, then apply it to the delta layer. A change that
+    /// cannot be journaled is still applied: only its crash safety is lost.
+    fn stage(&self, record: JournalRecord) {
+        let mut journal = self.journal.as_ref().map(|j| j.lock().unwrap());
+        if let Some(journal) = journal.as_mut() {
+            if let Err(e) = journal.append(&record) {
+                warn!(error = %e, "Failed to journal manifest change");
+            }
+        }
+        self.apply_to_delta(record);
+    }
+
+    fn stage_put(&self, hash: PathHash, path: Option<String>, entry: ManifestEntry) {
+        self.stage(JournalRecord::Put {
+            hash,
+            path,
+            ingested_at: entry.ingested_at,
+            entry,
+        });
+    }
+
+    fn apply_to_delta(&self, record: JournalRecord) {
+        match record {
+            JournalRecord::Put {
+                hash,
+                path,
+                mut entry,
+                ingested_at,
+            } => {
+                entry.ingested_at = ingested_at;
+                self.delta.insert(hash, DeltaEntry::Modified(entry));
+                if let Some(path) = path {
+                    self.delta_paths.insert(hash, path);
+                }
+            }
+            JournalRecord::Delete { hash } => {
+                self.delta.insert(hash, DeltaEntry::Deleted);
+                self.delta_paths.remove(&hash);
+            }
+        }
     }
 
     /// Get an entry by path (checks delta first, then base)
@@ -174,40 +330,55 @@ impl LmdbManifest {
         }
 
         // Check base layer
-        let rtxn = self.env.read_txn()?;
-        if let Some(entry) = self.entries_db.get(&rtxn, hash)? {
-            return Ok(Some(entry));
-        }
-
-        Ok(None)
+        self.store.get(hash)
     }
 
     /// Mark an entry as stale (pending re-ingest after write)
     pub fn mark_stale(&self, path: &str) {
         let hash = compute_path_hash(path);
 
-        if let Some(mut delta_ref) = self.delta.get_mut(&hash) {
-            if let DeltaEntry::Modified(entry) = delta_ref.value_mut() {
+        let in_delta = self
+            .delta
+            .get(&hash)
+            .map(|delta_ref| match delta_ref.value() {
+                DeltaEntry::Modified(entry) => Some(entry.clone()),
+                DeltaEntry::Deleted => None,
+            });
+        match in_delta {
+            Some(Some(mut entry)) => {
                 entry.stale = true;
+                self.stage_put(hash, None, entry);
             }
-        } else {
-            // Copy from base to delta and mark stale
-            if let Ok(Some(mut entry)) = self.get_by_hash(&hash) {
-                entry.stale = true;
-                self.delta.insert(hash, DeltaEntry::Modified(entry));
-                // Path should already exist, but ensure it's in delta_paths
-                if let Ok(Some(p)) = self.get_path_by_hash(&hash) {
-                    self.delta_paths.insert(hash, p);
+            Some(None) => {}
+            None => {
+                // Copy from base to delta and mark stale
+                if let Ok(Some(mut entry)) = self.get_by_hash(&hash) {
+                    entry.stale = true;
+                    // Path should already exist, but ensure it's in delta_paths
+                    let path = self.get_path_by_hash(&hash).ok().flatten();
+                    self.stage_put(hash, path, entry);
                 }
             }
         }
     }
 
+    /// Change the tier of an existing entry, keeping its ingest time so the
+    /// change does not look like a write. Returns false if `path` is absent.
+    pub fn set_tier(&self, path: &str, tier: AssetTier) -> LmdbResult<bool> {
+        let hash = compute_path_hash(path);
+        let Some(mut entry) = self.get_by_hash(&hash)? else {
+            return Ok(false);
+        };
+        entry.tier = tier;
+        self.stage_put(hash, Some(path.to_string()), entry);
+        Ok(true)
+    }
+
     /// Remove an entry (creates whiteout in delta)
     pub fn remove(&self, path: &str) {
-        let hash = compute_path_hash(path);
-        self.delta.insert(hash, DeltaEntry::Deleted);
-        self.delta_paths.remove(&hash);
+        self.stage(JournalRecord::Delete {
+            hash: compute_path_hash(path),
+        });
     }
 
     /// Get the original path string for a hash
@@ -218,12 +389,7 @@ impl LmdbManifest {
         }
 
         // Check base
-        let rtxn = self.env.read_txn()?;
-        if let Some(path) = self.paths_db.get(&rtxn, hash)? {
-            return Ok(Some(path.to_string()));
-        }
-
-        Ok(None)
+        self.store.get_path(hash)
     }
 
     /// Commit delta layer to base layer (ACID transaction)
@@ -231,40 +397,193 @@ impl LmdbManifest {
         if self.delta.is_empty() {
             return Ok(());
         }
-
-        let mut wtxn = self.env.write_txn()?;
+        if self.read_only {
+            return Err(LmdbError::ReadOnly);
+        }
+        // Changes staged meanwhile wait, so the journal is only emptied of
+        // what this commit applies
+        let mut journal = self.journal.as_ref().map(|j| j.lock().unwrap());
 
         // Apply delta to base
-        for entry in self.delta.iter() {
-            let hash = entry.key();
-            match entry.value() {
-                DeltaEntry::Modified(manifest_entry) => {
-                    self.entries_db.put(&mut wtxn, hash, manifest_entry)?;
-                    if let Some(path_ref) = self.delta_paths.get(hash) {
-                        self.paths_db.put(&mut wtxn, hash, path_ref.value())?;
-                    }
-                }
-                DeltaEntry::Deleted => {
-                    self.entries_db.delete(&mut wtxn, hash)?;
-                    self.paths_db.delete(&mut wtxn, hash)?;
-                }
+        let delta: Vec<(PathHash, DeltaEntry)> = self
+            .delta
+            .iter()
+            .map(|entry| (*entry.key(), entry.value().clone()))
+            .collect();
+        let paths: Vec<Option<String>> = delta
+            .iter()
+            .map(|(hash, _)| self.delta_paths.get(hash).map(|p| p.value().clone()))
+            .collect();
+        let ops: Vec<StoreOp<'_>> = delta
+            .iter()
+            .zip(&paths)
+            .map(|((hash, entry), path)| match entry {
+                DeltaEntry::Modified(entry) => StoreOp::Put {
+                    hash: *hash,
+                    path: path.as_deref(),
+                    entry,
+                },
+                DeltaEntry::Deleted => StoreOp::Delete(*hash),
+            })
+            .collect();
+        self.store.apply(&ops)?;
+
+        // Clear delta
+        self.delta.clear();
+        self.delta_paths.clear();
+        if let Some(journal) = journal.as_mut() {
+            journal.clear()?;
+        }
+
+        debug!("Committed delta to {}", self.store.backend().as_str());
+        Ok(())
+    }
+
+    /// Bring the manifest back in line with the CAS after a crash
+    ///
+    /// Commits what is still in the delta layer, including changes replayed
+    /// from the journal at open, then checks that every entry backed by a
+    /// blob (all but directories and inline files) finds it in `cas`.
+    /// Entries whose blob is missing are marked stale, pending re-ingest,
+    /// rather than left to serve content that is gone.
+    pub fn repair(&self, cas: &CasStore) -> LmdbResult<RepairReport> {
+        if self.read_only {
+            return Err(LmdbError::ReadOnly);
+        }
+        self.commit()?;
+
+        let mut report = RepairReport {
+            replayed: self.recovered,
+            ..Default::default()
+        };
+        for (path, entry) in self.iter()? {
+            let vnode = &entry.vnode;
+            if vnode.is_dir() || vnode.is_inline() {
+                continue;
+            }
+            report.checked += 1;
+            if !entry.stale && !cas.exists(&vnode.content_hash) {
+                self.mark_stale(&path);
+                report.missing_blobs.push(path);
             }
         }
+        report.missing_blobs.sort();
+        self.commit()?;
+        Ok(report)
+    }
 
-        wtxn.commit()?;
+    /// Capture the manifest as snapshot `name`, for
+    /// [`LmdbManifest::snapshot_restore`]
+    ///
+    /// The entries, the delta layer included, are stored in `cas` as one
+    /// tree object. Works on a read-only manifest too, which sees only what
+    /// its writer has committed.
+    pub fn snapshot_create(&self, name: &str, cas: &CasStore) -> LmdbResult<Snapshot> {
+        snapshot::validate_name(name)?;
+        if snapshot::list(&self.dir)?.iter().any(|s| s.name == name) {
+            return Err(LmdbError::InvalidInput(format!(
+                "snapshot {} already exists",
+                name
+            )));
+        }
+        let entries = self.iter()?;
+        let snapshot = Snapshot {
+            name: name.to_string(),
+            entries: entries.len(),
+            tree: snapshot::write_tree(cas, entries)?,
+            created: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+        };
+        snapshot::add(&self.dir, &snapshot)?;
+        debug!(
+            name,
+            entries = snapshot.entries,
+            "Created manifest snapshot"
+        );
+        Ok(snapshot)
+    }
+
+    /// Replace the manifest with snapshot `name`
+    ///
+    /// The committed entries are swapped for the snapshot's in one store
+    /// transaction, and uncommitted changes are dropped. Entries whose blob
+    /// has since left `cas` are restored stale, pending re-ingest.
+    pub fn snapshot_restore(&self, name: &str, cas: &CasStore) -> LmdbResult<RestoreReport> {
+        if self.read_only {
+            return Err(LmdbError::ReadOnly);
+        }
+        let snapshot = snapshot::find(&self.dir, name)?;
+        let mut entries = snapshot::read_tree(cas, &snapshot.tree)?;
+        // Nothing may be staged until the delta is dropped with the journal
+        let mut journal = self.journal.as_ref().map(|j| j.lock().unwrap());
+
+        let mut missing_blobs = Vec::new();
+        for (path, entry) in &mut entries {
+            let vnode = &entry.vnode;
+            if !vnode.is_dir()
+                && !vnode.is_inline()
+                && !entry.stale
+                && !cas.exists(&vnode.content_hash)
+            {
+                entry.stale = true;
+                missing_blobs.push(path.clone());
+            }
+        }
+        missing_blobs.sort();
+
+        let hashes: Vec<PathHash> = entries.iter().map(|(p, _)| compute_path_hash(p)).collect();
+        let kept: HashSet<&PathHash> = hashes.iter().collect();
+        let dropped: Vec<PathHash> = self
+            .store
+            .entries()?
+            .into_iter()
+            .map(|(hash, _, _)| hash)
+            .filter(|hash| !kept.contains(hash))
+            .collect();
+        let ops: Vec<StoreOp<'_>> = dropped
+            .iter()
+            .map(|hash| StoreOp::Delete(*hash))
+            .chain(
+                hashes
+                    .iter()
+                    .zip(&entries)
+                    .map(|(hash, (path, entry))| StoreOp::Put {
+                        hash: *hash,
+                        path: Some(path),
+                        entry,
+                    }),
+            )
+            .collect();
+        self.store.apply(&ops)?;
 
-        // Clear delta
         self.delta.clear();
         self.delta_paths.clear();
+        if let Some(journal) = journal.as_mut() {
+            journal.clear()?;
+        }
+        if !missing_blobs.is_empty() {
+            warn!(
+                count = missing_blobs.len(),
+                "Restored entries whose blob is gone from the CAS as stale"
+            );
+        }
+        debug!(name, entries = entries.len(), "Restored manifest snapshot");
+        Ok(RestoreReport {
+            entries: entries.len(),
+            removed: dropped.len(),
+            missing_blobs,
+        })
+    }
 
-        debug!("Committed delta to LMDB");
-        Ok(())
+    /// Snapshots of this manifest, oldest first
+    pub fn snapshots(&self) -> LmdbResult<Vec<Snapshot>> {
+        snapshot::list(&self.dir)
     }
 
     /// Get the number of entries (base + delta)
     pub fn len(&self) -> LmdbResult<usize> {
-        let rtxn = self.env.read_txn()?;
-        let base_len = self.entries_db.len(&rtxn)?;
+        let base_len = self.store.len()?;
 
         // Adjust for delta
         let mut delta_added = 0usize;
@@ -274,19 +593,19 @@ impl LmdbManifest {
             match entry.value() {
                 DeltaEntry::Modified(_) => {
                     // Check if it's a new entry or modification
-                    if self.entries_db.get(&rtxn, entry.key())?.is_none() {
+                    if !self.store.contains(entry.key())? {
                         delta_added += 1;
                     }
                 }
                 DeltaEntry::Deleted => {
-                    if self.entries_db.get(&rtxn, entry.key())?.is_some() {
+                    if self.store.contains(entry.key())? {
                         delta_removed += 1;
                     }
                 }
             }
         }
 
-        Ok(base_len as usize + delta_added - delta_removed)
+        Ok(base_len + delta_added - delta_removed)
     }
 
     /// Check if manifest is empty
@@ -298,16 +617,7 @@ impl LmdbManifest {
     ///
     /// Note: This is an expensive operation for large manifests
     pub fn iter(&self) -> LmdbResult<Vec<(String, ManifestEntry)>> {
-        let rtxn = self.env.read_txn()?;
         let mut result = Vec::new();
-        let mut deleted_hashes = std::collections::HashSet::new();
-
-        // Collect delta deletions
-        for entry in self.delta.iter() {
-            if matches!(entry.value(), DeltaEntry::Deleted) {
-                deleted_hashes.insert(*entry.key());
-            }
-        }
 
         // Add delta modifications first
         for entry in self.delta.iter() {
@@ -318,27 +628,34 @@ impl LmdbManifest {
             }
         }
 
-        // Add base entries not in delta
-        let mut iter = self.entries_db.iter(&rtxn)?;
-        while let Some(Ok((hash_bytes, entry))) = iter.next() {
-            let hash: PathHash = hash_bytes.try_into().unwrap_or([0u8; 32]);
-            if !self.delta.contains_key(&hash) && !deleted_hashes.contains(&hash) {
-                if let Some(path) = self.paths_db.get(&rtxn, &hash)? {
-                    result.push((path.to_string(), entry));
-                }
+        // Add base entries not in delta (modified or deleted)
+        for (hash, path, entry) in self.store.entries()? {
+            if !self.delta.contains_key(&hash) {
+                result.push((path, entry));
             }
         }
 
         Ok(result)
     }
 
-    /// Sync/flush LMDB to disk
+    /// Combined digest of the entries under `roots`, from recorded hashes only
+    pub fn digest(&self, roots: &[&str]) -> LmdbResult<crate::PathDigest> {
+        let entries = self.iter()?;
+        Ok(crate::digest_paths(
+            entries.iter().map(|(path, e)| (path.as_str(), &e.vnode)),
+            roots,
+        ))
+    }
+
+    /// Sync/flush the base layer to disk
     pub fn sync(&self) -> LmdbResult<()> {
-        self.env.force_sync()?;
-        Ok(())
+        if self.read_only {
+            return Ok(());
+        }
+        self.store.sync()
     }
 
-    /// Get environment statistics
+    /// Get manifest statistics
     pub fn stats(&self) -> LmdbResult<ManifestStats> {
         let entries = self.iter()?;
 
@@ -372,7 +689,308 @@ impl LmdbManifest {
     }
 }
 
-/// Statistics about the LMDB manifest
+/// What [`LmdbManifest::repair`] found and fixed
+#[derive(Debug, Clone, Default)]
+pub struct RepairReport {
+    /// Journaled changes replayed when the manifest was opened
+    pub replayed: usize,
+    /// Entries checked for their blob
+    pub checked: usize,
+    /// Paths whose blob is missing from the CAS, now marked stale
+    pub missing_blobs: Vec<String>,
+}
+
+/// What [`LmdbManifest::snapshot_restore`] did
+#[derive(Debug, Clone, Default)]
+pub struct RestoreReport {
+    /// Entries of the snapshot, now the whole manifest
+    pub entries: usize,
+    /// Committed entries the snapshot did not have, now removed
+    pub removed: usize,
+    /// Paths whose blob is missing from the CAS, restored stale
+    pub missing_blobs: Vec<String>,
+}
+
+fn now_ns() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| i64::try_from(d.as_nanos()).unwrap_or(i64::MAX))
+        .unwrap_or(0)
+}
+
+/// LMDB store: one environment with three databases keyed by path hash
+///
+/// The map grows on demand: a commit that hits `MDB_MAP_FULL` doubles the
+/// map and runs again, and a transaction that finds the map grown by another
+/// process (`MDB_MAP_RESIZED`) adopts the new size. LMDB may only resize
+/// with no transaction open in the process, so transactions hold `gate`
+/// shared and resizing holds it exclusively.
+pub struct LmdbStore {
+    /// LMDB environment
+    env: Env,
+
+    /// Path hash → ManifestEntry database
+    entries_db: Database<Bytes, SerdeBincode<ManifestEntry>>,
+
+    /// Path hash → original path string database
+    paths_db: Database<Bytes, Str>,
+
+    /// Path hash → ingest time (ns since epoch)
+    ingested_db: Database<Bytes, I64<LE>>,
+
+    /// Held shared by transactions, exclusively by resizes
+    gate: RwLock<()>,
+
+    /// Opened `MDB_RDONLY` by this store: closed on drop, so the process
+    /// can open the path read-write afterwards
+    close_on_drop: bool,
+}
+
+impl LmdbStore {
+    /// Default LMDB map size: 1GB (grows on demand)
+    const DEFAULT_MAP_SIZE: usize = 1024 * 1024 * 1024;
+
+    /// The map stops growing here; commits past it fail with `MapFull`
+    const MAX_MAP_SIZE: u64 = 1 << 40;
+
+    /// Maximum readers
+    const MAX_READERS: u32 = 128;
+
+    /// Open or create the LMDB environment in the directory `path`
+    pub fn open(path: &Path) -> LmdbResult<Self> {
+        Self::open_sized(path, Self::DEFAULT_MAP_SIZE)
+    }
+
+    /// Like [`LmdbStore::open`] with a map of `map_size` bytes to start
+    /// from (LMDB uses the file size instead if that is larger)
+    pub fn open_sized(path: &Path, map_size: usize) -> LmdbResult<Self> {
+        let env = unsafe {
+            EnvOpenOptions::new()
+                .map_size(map_size)
+                .max_readers(Self::MAX_READERS)
+                .max_dbs(3)
+                .open(path)?
+        };
+
+        // Open databases
+        let mut wtxn = env.write_txn()?;
+        let entries_db = env.create_database(&mut wtxn, Some("entries"))?;
+        let paths_db = env.create_database(&mut wtxn, Some("paths"))?;
+        let ingested_db = env.create_database(&mut wtxn, Some("ingested"))?;
+        wtxn.commit()?;
+
+        Ok(Self {
+            env,
+            entries_db,
+            paths_db,
+            ingested_db,
+            gate: RwLock::new(()),
+            close_on_drop: false,
+        })
+    }
+
+    /// Open the existing environment in `path` read-only (`MDB_RDONLY`).
+    /// The databases are opened in a read transaction, so this does not
+    /// wait for a writer in another process.
+    pub fn open_readonly(path: &Path) -> LmdbResult<Self> {
+        let opened = unsafe {
+            EnvOpenOptions::new()
+                .map_size(Self::DEFAULT_MAP_SIZE)
+                .max_readers(Self::MAX_READERS)
+                .max_dbs(3)
+                .flags(EnvFlags::READ_ONLY)
+                .open(path)
+        };
+        let (env, close_on_drop) = match opened {
+            // heed keeps one environment per path and process: read through
+            // the one this process already opened read-write
+            Err(heed::Error::BadOpenOptions { env, .. }) => (env, false),
+            opened => (opened?, true),
+        };
+
+        let rtxn = env.read_txn()?;
+        let missing =
+            |name: &str| LmdbError::Corrupted(format!("{}: no {} database", path.display(), name));
+        let entries_db = env
+            .open_database(&rtxn, Some("entries"))?
+            .ok_or_else(|| missing("entries"))?;
+        let paths_db = env
+            .open_database(&rtxn, Some("paths"))?
+            .ok_or_else(|| missing("paths"))?;
+        let ingested_db = env
+            .open_database(&rtxn, Some("ingested"))?
+            .ok_or_else(|| missing("ingested"))?;
+        // Committing keeps the database handles open for later transactions
+        rtxn.commit()?;
+
+        Ok(Self {
+            env,
+            entries_db,
+            paths_db,
+            ingested_db,
+            gate: RwLock::new(()),
+            close_on_drop,
+        })
+    }
+
+    /// Current map size in bytes
+    pub fn map_size(&self) -> usize {
+        self.env.info().map_size
+    }
+
+    /// Run `f` in a read transaction, adopting a map another process grew
+    fn read<T>(&self, f: impl Fn(&RoTxn) -> heed::Result<T>) -> LmdbResult<T> {
+        loop {
+            let result = {
+                let _gate = self.gate.read().unwrap();
+                self.env.read_txn().and_then(|rtxn| f(&rtxn))
+            };
+            match result {
+                Err(heed::Error::Mdb(MdbError::MapResized)) => self.resize(0)?,
+                result => return Ok(result?),
+            }
+        }
+    }
+
+    /// Set the map size; 0 adopts the size of the data file
+    fn resize(&self, size: usize) -> LmdbResult<()> {
+        let _gate = self.gate.write().unwrap();
+        // SAFETY: the exclusive gate means no transaction of this process is open
+        unsafe { self.env.resize(size)? };
+        Ok(())
+    }
+
+    /// Double the map, unless the data file already grew past it (another
+    /// process resized) or it would pass `MAX_MAP_SIZE`
+    fn grow(&self, full_at: usize) -> LmdbResult<()> {
+        let current = self.map_size();
+        if current > full_at {
+            return Ok(());
+        }
+        let next = current.saturating_mul(2);
+        if next as u64 > Self::MAX_MAP_SIZE {
+            return Err(heed::Error::Mdb(MdbError::MapFull).into());
+        }
+        debug!(from = current, to = next, "Growing LMDB manifest map");
+        self.resize(next)
+    }
+
+    fn write(&self, ops: &[StoreOp<'_>]) -> heed::Result<()> {
+        let _gate = self.gate.read().unwrap();
+        let mut wtxn = self.env.write_txn()?;
+        for op in ops {
+            match op {
+                StoreOp::Put { hash, path, entry } => {
+                    self.entries_db.put(&mut wtxn, hash, entry)?;
+                    if entry.ingested_at != 0 {
+                        self.ingested_db.put(&mut wtxn, hash, &entry.ingested_at)?;
+                    }
+                    if let Some(path) = path {
+                        self.paths_db.put(&mut wtxn, hash, path)?;
+                    }
+                }
+                StoreOp::Delete(hash) => {
+                    self.entries_db.delete(&mut wtxn, hash)?;
+                    self.paths_db.delete(&mut wtxn, hash)?;
+                    self.ingested_db.delete(&mut wtxn, hash)?;
+                }
+            }
+        }
+        wtxn.commit()
+    }
+
+    /// Rewrite the environment in `dir` without its free pages. The copy is
+    /// written next to `data.mdb` and renamed over it, so a crash leaves
+    /// one or the other. No process may have the manifest open.
+    pub fn compact(dir: &Path) -> LmdbResult<()> {
+        let data = dir.join("data.mdb");
+        let tmp = dir.join("data.mdb.compact");
+        if tmp.exists() {
+            std::fs::remove_file(&tmp)?;
+        }
+        {
+            let store = Self::open(dir)?;
+            let _gate = store.gate.write().unwrap();
+            let file = store
+                .env
+                .copy_to_file(&tmp, heed::CompactionOption::Enabled)?;
+            file.sync_all()?;
+        }
+        std::fs::rename(&tmp, &data)?;
+        std::fs::File::open(dir)?.sync_all()?;
+        Ok(())
+    }
+}
+
+impl Drop for LmdbStore {
+    fn drop(&mut self) {
+        if self.close_on_drop {
+            // The environment closes when its last handle (ours) goes
+            let _ = self.env.clone().prepare_for_closing();
+        }
+    }
+}
+
+impl ManifestStore for LmdbStore {
+    fn backend(&self) -> ManifestBackend {
+        ManifestBackend::Lmdb
+    }
+
+    fn get(&self, hash: &PathHash) -> LmdbResult<Option<ManifestEntry>> {
+        self.read(|rtxn| {
+            let Some(mut entry) = self.entries_db.get(rtxn, hash)? else {
+                return Ok(None);
+            };
+            entry.ingested_at = self.ingested_db.get(rtxn, hash)?.unwrap_or(0);
+            Ok(Some(entry))
+        })
+    }
+
+    fn get_path(&self, hash: &PathHash) -> LmdbResult<Option<String>> {
+        self.read(|rtxn| Ok(self.paths_db.get(rtxn, hash)?.map(str::to_string)))
+    }
+
+    fn contains(&self, hash: &PathHash) -> LmdbResult<bool> {
+        self.read(|rtxn| Ok(self.entries_db.get(rtxn, hash)?.is_some()))
+    }
+
+    fn len(&self) -> LmdbResult<usize> {
+        self.read(|rtxn| Ok(self.entries_db.len(rtxn)? as usize))
+    }
+
+    fn entries(&self) -> LmdbResult<Vec<(PathHash, String, ManifestEntry)>> {
+        self.read(|rtxn| {
+            let mut result = Vec::new();
+            let mut iter = self.entries_db.iter(rtxn)?;
+            while let Some(Ok((hash_bytes, mut entry))) = iter.next() {
+                let hash: PathHash = hash_bytes.try_into().unwrap_or([0u8; 32]);
+                if let Some(path) = self.paths_db.get(rtxn, &hash)? {
+                    entry.ingested_at = self.ingested_db.get(rtxn, &hash)?.unwrap_or(0);
+                    result.push((hash, path.to_string(), entry));
+                }
+            }
+            Ok(result)
+        })
+    }
+
+    fn apply(&self, ops: &[StoreOp<'_>]) -> LmdbResult<()> {
+        loop {
+            let map_size = self.map_size();
+            match self.write(ops) {
+                Err(heed::Error::Mdb(MdbError::MapFull)) => self.grow(map_size)?,
+                Err(heed::Error::Mdb(MdbError::MapResized)) => self.resize(0)?,
+                result => return Ok(result?),
+            }
+        }
+    }
+
+    fn sync(&self) -> LmdbResult<()> {
+        self.env.force_sync()?;
+        Ok(())
+    }
+}
+
+/// Statistics about the manifest
 #[derive(Debug, Clone, Default)]
 pub struct ManifestStats {
     pub file_count: u64,
@@ -425,6 +1043,61 @@ mod tests {
         assert_eq!(retrieved.tier, AssetTier::Tier1Immutable);
     }
 
+    #[test]
+    fn test_lmdb_manifest_ingest_time_persists() {
+        let temp = TempDir::new().unwrap();
+        let manifest = LmdbManifest::open(temp.path().join("manifest")).unwrap();
+
+        manifest.insert(
+            "/a.txt",
+            VnodeEntry::new_file([1u8; 32], 1, 0, 0o644),
+            AssetTier::Tier2Mutable,
+        );
+        let ingested_at = manifest.get("/a.txt").unwrap().unwrap().ingested_at;
+        assert!(ingested_at > 0);
+        manifest.commit().unwrap();
+
+        drop(manifest);
+        let manifest = LmdbManifest::open(temp.path().join("manifest")).unwrap();
+        assert_eq!(
+            manifest.get("/a.txt").unwrap().unwrap().ingested_at,
+            ingested_at
+        );
+        assert_eq!(manifest.iter().unwrap()[0].1.ingested_at, ingested_at);
+
+        manifest.remove("/a.txt");
+        manifest.commit().unwrap();
+        drop(manifest);
+        let store = LmdbStore::open(&temp.path().join("manifest")).unwrap();
+        let rtxn = store.env.read_txn().unwrap();
+        assert!(store.ingested_db.is_empty(&rtxn).unwrap());
+    }
+
+    #[test]
+    fn test_lmdb_manifest_set_tier_keeps_ingest_time() {
+        let temp = TempDir::new().unwrap();
+        let manifest = LmdbManifest::open(temp.path().join("manifest")).unwrap();
+        manifest.insert(
+            "/a.txt",
+            VnodeEntry::new_file([1u8; 32], 1, 0, 0o644),
+            AssetTier::Tier2Mutable,
+        );
+        manifest.commit().unwrap();
+        let ingested_at = manifest.get("/a.txt").unwrap().unwrap().ingested_at;
+
+        assert!(manifest
+            .set_tier("/a.txt", AssetTier::Tier1Immutable)
+            .unwrap());
+        assert!(!manifest
+            .set_tier("/missing", AssetTier::Tier1Immutable)
+            .unwrap());
+        manifest.commit().unwrap();
+
+        let entry = manifest.get("/a.txt").unwrap().unwrap();
+        assert_eq!(entry.tier, AssetTier::Tier1Immutable);
+        assert_eq!(entry.ingested_at, ingested_at);
+    }
+
     #[test]
     fn test_lmdb_manifest_delta_override() {
         let temp = TempDir::new().unwrap();
@@ -473,6 +1146,273 @@ mod tests {
         assert!(manifest.get("/to_delete.txt").unwrap().is_none());
     }
 
+    fn put_files(manifest: &LmdbManifest, count: usize) {
+        for i in 0..count {
+            manifest.insert(
+                &format!("/src/module_{}/file_{}.rs", i / 100, i),
+                VnodeEntry::new_file([i as u8; 32], i as u64, 0, 0o644),
+                AssetTier::Tier2Mutable,
+            );
+        }
+        manifest.commit().unwrap();
+    }
+
+    #[test]
+    fn test_lmdb_map_grows_when_full() {
+        let temp = TempDir::new().unwrap();
+        let store = LmdbStore::open_sized(temp.path(), 64 * 1024).unwrap();
+        let initial = store.map_size();
+        let manifest = LmdbManifest {
+            store: Box::new(store),
+            delta: Arc::new(DashMap::new()),
+            delta_paths: Arc::new(DashMap::new()),
+            read_only: false,
+            journal: None,
+            recovered: 0,
+            dir: temp.path().to_path_buf(),
+        };
+
+        put_files(&manifest, 5000);
+        assert_eq!(manifest.len().unwrap(), 5000);
+        assert!(manifest
+            .get("/src/module_49/file_4999.rs")
+            .unwrap()
+            .is_some());
+        drop(manifest);
+
+        // Reopening keeps the grown map
+        let store = LmdbStore::open_sized(temp.path(), 64 * 1024).unwrap();
+        assert!(store.map_size() > initial);
+        assert_eq!(store.len().unwrap(), 5000);
+    }
+
+    #[test]
+    fn test_lmdb_compact_keeps_entries() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path().join("manifest");
+        let manifest = LmdbManifest::open_with(&dir, ManifestBackend::Lmdb).unwrap();
+        put_files(&manifest, 5000);
+        for i in 100..5000 {
+            manifest.remove(&format!("/src/module_{}/file_{}.rs", i / 100, i));
+        }
+        manifest.commit().unwrap();
+        drop(manifest);
+
+        let stats = crate::store::compact(&dir).unwrap();
+        assert_eq!(stats.backend, ManifestBackend::Lmdb);
+        assert!(stats.bytes_after < stats.bytes_before);
+        assert!(!dir.join("data.mdb.compact").exists());
+
+        let manifest = LmdbManifest::open(&dir).unwrap();
+        assert_eq!(manifest.len().unwrap(), 100);
+        let entry = manifest.get("/src/module_0/file_7.rs").unwrap().unwrap();
+        assert_eq!(entry.vnode.size, 7);
+        assert!(entry.ingested_at > 0);
+    }
+
+    #[test]
+    fn test_open_readonly() {
+        let temp = TempDir::new().unwrap();
+        let missing = temp.path().join("missing");
+        assert!(matches!(
+            LmdbManifest::open_readonly(&missing),
+            Err(LmdbError::NotFound(_))
+        ));
+        assert!(!missing.exists());
+
+        for backend in [ManifestBackend::Lmdb, ManifestBackend::Redb] {
+            let written = temp.path().join(format!("{}-written", backend.as_str()));
+            let manifest = LmdbManifest::open_with(&written, backend).unwrap();
+            put_files(&manifest, 10);
+            drop(manifest);
+            // This process keeps the LMDB environment it wrote with open, and
+            // a read-only open of that path reads through it
+            assert_eq!(
+                LmdbManifest::open_readonly(&written)
+                    .unwrap()
+                    .len()
+                    .unwrap(),
+                10
+            );
+
+            // A copy has no environment open yet: opened MDB_RDONLY
+            let dir = temp.path().join(backend.as_str());
+            std::fs::create_dir(&dir).unwrap();
+            let file = match backend {
+                ManifestBackend::Lmdb => "data.mdb",
+                ManifestBackend::Redb => crate::redb_store::REDB_FILE,
+            };
+            std::fs::copy(written.join(file), dir.join(file)).unwrap();
+
+            let manifest = LmdbManifest::open_readonly(&dir).unwrap();
+            assert!(manifest.is_read_only());
+            assert_eq!(manifest.backend(), backend);
+            assert_eq!(manifest.len().unwrap(), 10);
+            assert_eq!(manifest.iter().unwrap().len(), 10);
+            let entry = manifest.get("/src/module_0/file_3.rs").unwrap().unwrap();
+            assert_eq!(entry.vnode.size, 3);
+
+            // The delta layer still works; committing it does not
+            manifest.remove("/src/module_0/file_3.rs");
+            assert!(manifest.get("/src/module_0/file_3.rs").unwrap().is_none());
+            assert!(matches!(manifest.commit(), Err(LmdbError::ReadOnly)));
+            manifest.sync().unwrap();
+            drop(manifest);
+
+            let manifest = LmdbManifest::open(&dir).unwrap();
+            assert_eq!(manifest.len().unwrap(), 10);
+        }
+    }
+
+    #[test]
+    fn test_lmdb_manifest_replays_journal() {
+        let temp = TempDir::new().unwrap();
+        for backend in [ManifestBackend::Lmdb, ManifestBackend::Redb] {
+            let dir = temp.path().join(backend.as_str());
+            let manifest = LmdbManifest::open_with(&dir, backend).unwrap();
+            put_files(&manifest, 10);
+            assert!(!crate::journal::has_pending(&dir));
+
+            // Killed before the next commit
+            manifest.insert(
+                "/new.txt",
+                VnodeEntry::new_file([0xAB; 32], 3, 0, 0o644),
+                AssetTier::Tier2Mutable,
+            );
+            manifest.remove("/src/module_0/file_3.rs");
+            manifest.mark_stale("/src/module_0/file_4.rs");
+            let ingested_at = manifest.get("/new.txt").unwrap().unwrap().ingested_at;
+            assert!(crate::journal::has_pending(&dir));
+            drop(manifest);
+
+            // Nothing is replayed while another handle owns the journal
+            // (redb allows only one handle at all)
+            let manifest = LmdbManifest::open(&dir).unwrap();
+            if backend == ManifestBackend::Lmdb {
+                let second = LmdbManifest::open(&dir).unwrap();
+                assert_eq!(second.recovered(), 0);
+            }
+            assert_eq!(manifest.recovered(), 3);
+            assert!(!crate::journal::has_pending(&dir));
+            drop(manifest);
+
+            let manifest = LmdbManifest::open_readonly(&dir).unwrap();
+            let entry = manifest.get("/new.txt").unwrap().unwrap();
+            assert_eq!(entry.vnode.size, 3);
+            assert_eq!(entry.ingested_at, ingested_at);
+            assert!(manifest.get("/src/module_0/file_3.rs").unwrap().is_none());
+            assert!(
+                manifest
+                    .get("/src/module_0/file_4.rs")
+                    .unwrap()
+                    .unwrap()
+                    .stale
+            );
+            assert_eq!(manifest.len().unwrap(), 10);
+        }
+    }
+
+    #[test]
+    fn test_lmdb_manifest_repair() {
+        let temp = TempDir::new().unwrap();
+        let cas = CasStore::new(temp.path().join("cas")).unwrap();
+        let manifest = LmdbManifest::open(temp.path().join("manifest")).unwrap();
+
+        let stored = cas.store(b"stored").unwrap();
+        let file = |hash| VnodeEntry::new_file(hash, 6, 0, 0o644);
+        manifest.insert("/stored.txt", file(stored), AssetTier::Tier2Mutable);
+        manifest.insert("/lost.txt", file([0x42; 32]), AssetTier::Tier2Mutable);
+        manifest.insert(
+            "/dir",
+            VnodeEntry::new_directory(0, 0o755),
+            AssetTier::Tier2Mutable,
+        );
+        manifest.insert(
+            "/tiny.txt",
+            VnodeEntry::new_inline(b"hi", 0, 0o644).unwrap(),
+            AssetTier::Tier2Mutable,
+        );
+
+        let report = manifest.repair(&cas).unwrap();
+        assert_eq!(report.replayed, 0);
+        assert_eq!(report.checked, 2);
+        assert_eq!(report.missing_blobs, vec!["/lost.txt".to_string()]);
+        assert!(!crate::journal::has_pending(&temp.path().join("manifest")));
+        assert!(manifest.get("/lost.txt").unwrap().unwrap().stale);
+        assert!(!manifest.get("/stored.txt").unwrap().unwrap().stale);
+
+        // Entries already pending re-ingest are left alone
+        let report = manifest.repair(&cas).unwrap();
+        assert!(report.missing_blobs.is_empty());
+
+        let readonly = LmdbManifest::open_readonly(temp.path().join("manifest")).unwrap();
+        assert!(matches!(readonly.repair(&cas), Err(LmdbError::ReadOnly)));
+    }
+
+    #[test]
+    fn test_lmdb_manifest_snapshot_restore() {
+        let temp = TempDir::new().unwrap();
+        let cas = CasStore::new(temp.path().join("cas")).unwrap();
+        for backend in [ManifestBackend::Lmdb, ManifestBackend::Redb] {
+            let dir = temp.path().join(backend.as_str());
+            let manifest = LmdbManifest::open_with(&dir, backend).unwrap();
+            let good = cas.store(b"good").unwrap();
+            let file = |hash| VnodeEntry::new_file(hash, 4, 0, 0o644);
+            manifest.insert("/keep.txt", file(good), AssetTier::Tier2Mutable);
+            manifest.insert("/gone.txt", file([0x42; 32]), AssetTier::Tier2Mutable);
+            manifest.commit().unwrap();
+            let ingested_at = manifest.get("/keep.txt").unwrap().unwrap().ingested_at;
+
+            let snapshot = manifest.snapshot_create("good-build", &cas).unwrap();
+            assert_eq!(snapshot.entries, 2);
+            assert!(cas.exists(&snapshot.tree));
+            assert!(matches!(
+                manifest.snapshot_create("good-build", &cas),
+                Err(LmdbError::InvalidInput(_))
+            ));
+
+            // A bad build, partly committed
+            let bad = cas.store(b"bad").unwrap();
+            manifest.insert("/keep.txt", file(bad), AssetTier::Tier2Mutable);
+            manifest.insert("/junk.o", file(bad), AssetTier::Tier2Mutable);
+            manifest.commit().unwrap();
+            manifest.insert("/more-junk.o", file(bad), AssetTier::Tier2Mutable);
+
+            let report = manifest.snapshot_restore("good-build", &cas).unwrap();
+            assert_eq!(report.entries, 2);
+            assert_eq!(report.removed, 1);
+            assert_eq!(report.missing_blobs, vec!["/gone.txt".to_string()]);
+            assert!(!crate::journal::has_pending(&dir));
+
+            let keep = manifest.get("/keep.txt").unwrap().unwrap();
+            assert_eq!(keep.vnode.content_hash, good);
+            assert_eq!(keep.ingested_at, ingested_at);
+            assert!(manifest.get("/gone.txt").unwrap().unwrap().stale);
+            assert!(manifest.get("/junk.o").unwrap().is_none());
+            assert!(manifest.get("/more-junk.o").unwrap().is_none());
+            assert_eq!(manifest.len().unwrap(), 2);
+            assert_eq!(
+                manifest
+                    .get_path_by_hash(&compute_path_hash("/keep.txt"))
+                    .unwrap(),
+                Some("/keep.txt".to_string())
+            );
+            drop(manifest);
+
+            // Snapshots outlive the handle; restoring needs a writable one
+            let readonly = LmdbManifest::open_readonly(&dir).unwrap();
+            assert_eq!(readonly.snapshots().unwrap(), vec![snapshot]);
+            assert!(matches!(
+                readonly.snapshot_restore("good-build", &cas),
+                Err(LmdbError::ReadOnly)
+            ));
+            assert!(matches!(
+                readonly.snapshot_create("../escape", &cas),
+                Err(LmdbError::InvalidInput(_))
+            ));
+        }
+    }
+
     #[test]
     fn test_tier_classification() {
         assert_eq!(AssetTier::default(), AssetTier::Tier2Mutable);
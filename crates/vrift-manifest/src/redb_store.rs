@@ -0,0 +1,227 @@
+//! redb store: the manifest's committed entries in one pure-Rust file.
+//!
+//! Same tables and encoding as [`LmdbStore`](crate::lmdb::LmdbStore):
+//! entries are bincode, paths UTF-8, ingest times i64. redb grows its file
+//! as needed and takes a file lock instead of an LMDB lock file, so it works
+//! where LMDB's map size or shared-memory locks don't (small `/dev/shm`,
+//! some container overlays). The lock is exclusive: while one process has
+//! the manifest open, others fail to open it.
+
+use std::path::Path;
+
+use redb::{Database, ReadableTable, ReadableTableMetadata, TableDefinition};
+
+use crate::lmdb::{LmdbError, LmdbResult, ManifestEntry};
+use crate::store::{ManifestBackend, ManifestStore, StoreOp};
+use crate::PathHash;
+
+/// File the store lives in, inside the manifest directory
+pub const REDB_FILE: &str = "manifest.redb";
+
+const ENTRIES: TableDefinition<&[u8], &[u8]> = TableDefinition::new("entries");
+const PATHS: TableDefinition<&[u8], &str> = TableDefinition::new("paths");
+const INGESTED: TableDefinition<&[u8], i64> = TableDefinition::new("ingested");
+
+/// redb's errors each convert into [`redb::Error`], boxed: it is large
+macro_rules! from_redb {
+    ($($err:ty),*) => {
+        $(impl From<$err> for LmdbError {
+            fn from(e: $err) -> Self {
+                LmdbError::Redb(Box::new(e.into()))
+            }
+        })*
+    };
+}
+
+from_redb!(
+    redb::Error,
+    redb::DatabaseError,
+    redb::TransactionError,
+    redb::TableError,
+    redb::StorageError,
+    redb::CommitError,
+    redb::CompactionError
+);
+
+pub struct RedbStore {
+    db: Database,
+}
+
+impl RedbStore {
+    /// Open or create `manifest.redb` in the directory `dir`
+    pub fn open(dir: &Path) -> LmdbResult<Self> {
+        let db = Database::create(dir.join(REDB_FILE))?;
+
+        // Create the tables, so readers can open them
+        let wtxn = db.begin_write()?;
+        wtxn.open_table(ENTRIES)?;
+        wtxn.open_table(PATHS)?;
+        wtxn.open_table(INGESTED)?;
+        wtxn.commit()?;
+
+        Ok(Self { db })
+    }
+
+    /// Open the existing `manifest.redb` in `dir` without creating tables.
+    /// redb has no shared lock, so this still excludes other processes.
+    pub fn open_readonly(dir: &Path) -> LmdbResult<Self> {
+        Ok(Self {
+            db: Database::open(dir.join(REDB_FILE))?,
+        })
+    }
+
+    /// Compact `manifest.redb` in `dir` in place. redb moves pages through
+    /// its own transactions, so a crash leaves a consistent file. No process
+    /// may have the manifest open.
+    pub fn compact(dir: &Path) -> LmdbResult<()> {
+        let mut db = Database::create(dir.join(REDB_FILE))?;
+        db.compact()?;
+        Ok(())
+    }
+}
+
+fn decode(hash: &[u8], bytes: &[u8]) -> LmdbResult<ManifestEntry> {
+    bincode::deserialize(bytes)
+        .map_err(|e| LmdbError::Corrupted(format!("entry {}: {}", hex_prefix(hash), e)))
+}
+
+fn hex_prefix(hash: &[u8]) -> String {
+    hash.iter().take(8).map(|b| format!("{:02x}", b)).collect()
+}
+
+impl ManifestStore for RedbStore {
+    fn backend(&self) -> ManifestBackend {
+        ManifestBackend::Redb
+    }
+
+    fn get(&self, hash: &PathHash) -> LmdbResult<Option<ManifestEntry>> {
+        let rtxn = self.db.begin_read()?;
+        let Some(bytes) = rtxn.open_table(ENTRIES)?.get(hash.as_slice())? else {
+            return Ok(None);
+        };
+        let mut entry = decode(hash, bytes.value())?;
+        entry.ingested_at = rtxn
+            .open_table(INGESTED)?
+            .get(hash.as_slice())?
+            .map_or(0, |t| t.value());
+        Ok(Some(entry))
+    }
+
+    fn get_path(&self, hash: &PathHash) -> LmdbResult<Option<String>> {
+        let rtxn = self.db.begin_read()?;
+        let path = rtxn.open_table(PATHS)?.get(hash.as_slice())?;
+        Ok(path.map(|p| p.value().to_string()))
+    }
+
+    fn contains(&self, hash: &PathHash) -> LmdbResult<bool> {
+        let rtxn = self.db.begin_read()?;
+        Ok(rtxn.open_table(ENTRIES)?.get(hash.as_slice())?.is_some())
+    }
+
+    fn len(&self) -> LmdbResult<usize> {
+        let rtxn = self.db.begin_read()?;
+        Ok(rtxn.open_table(ENTRIES)?.len()? as usize)
+    }
+
+    fn entries(&self) -> LmdbResult<Vec<(PathHash, String, ManifestEntry)>> {
+        let rtxn = self.db.begin_read()?;
+        let entries = rtxn.open_table(ENTRIES)?;
+        let paths = rtxn.open_table(PATHS)?;
+        let ingested = rtxn.open_table(INGESTED)?;
+        let mut result = Vec::new();
+        for row in entries.iter()? {
+            let (key, bytes) = row?;
+            let Ok(hash) = PathHash::try_from(key.value()) else {
+                continue;
+            };
+            if let Some(path) = paths.get(key.value())? {
+                let mut entry = decode(&hash, bytes.value())?;
+                entry.ingested_at = ingested.get(key.value())?.map_or(0, |t| t.value());
+                result.push((hash, path.value().to_string(), entry));
+            }
+        }
+        Ok(result)
+    }
+
+    fn apply(&self, ops: &[StoreOp<'_>]) -> LmdbResult<()> {
+        let wtxn = self.db.begin_write()?;
+        {
+            let mut entries = wtxn.open_table(ENTRIES)?;
+            let mut paths = wtxn.open_table(PATHS)?;
+            let mut ingested = wtxn.open_table(INGESTED)?;
+            for op in ops {
+                match op {
+                    StoreOp::Put { hash, path, entry } => {
+                        let bytes = bincode::serialize(entry)
+                            .map_err(|e| LmdbError::Io(std::io::Error::other(e)))?;
+                        entries.insert(hash.as_slice(), bytes.as_slice())?;
+                        if entry.ingested_at != 0 {
+                            ingested.insert(hash.as_slice(), entry.ingested_at)?;
+                        }
+                        if let Some(path) = path {
+                            paths.insert(hash.as_slice(), *path)?;
+                        }
+                    }
+                    StoreOp::Delete(hash) => {
+                        entries.remove(hash.as_slice())?;
+                        paths.remove(hash.as_slice())?;
+                        ingested.remove(hash.as_slice())?;
+                    }
+                }
+            }
+        }
+        wtxn.commit()?;
+        Ok(())
+    }
+
+    fn sync(&self) -> LmdbResult<()> {
+        // Commits are durable when they return
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AssetTier, LmdbManifest, VnodeEntry};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_redb_manifest_roundtrip() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path().join("manifest");
+        let manifest = LmdbManifest::open_with(&dir, ManifestBackend::Redb).unwrap();
+        manifest.insert(
+            "/a.txt",
+            VnodeEntry::new_file([1u8; 32], 10, 0, 0o644),
+            AssetTier::Tier1Immutable,
+        );
+        manifest.insert(
+            "/b.txt",
+            VnodeEntry::new_file([2u8; 32], 20, 0, 0o644),
+            AssetTier::Tier2Mutable,
+        );
+        manifest.commit().unwrap();
+        let ingested_at = manifest.get("/a.txt").unwrap().unwrap().ingested_at;
+        assert!(ingested_at > 0);
+        manifest.remove("/b.txt");
+        assert_eq!(manifest.len().unwrap(), 1);
+        manifest.commit().unwrap();
+        drop(manifest);
+
+        // Reopened with the LMDB default, the directory still says redb
+        assert_eq!(ManifestBackend::detect(&dir), Some(ManifestBackend::Redb));
+        let manifest = LmdbManifest::open(&dir).unwrap();
+        assert_eq!(manifest.backend(), ManifestBackend::Redb);
+        assert!(!dir.join("data.mdb").exists());
+
+        let entry = manifest.get("/a.txt").unwrap().unwrap();
+        assert_eq!(entry.vnode.content_hash, [1u8; 32]);
+        assert_eq!(entry.tier, AssetTier::Tier1Immutable);
+        assert_eq!(entry.ingested_at, ingested_at);
+        assert!(manifest.get("/b.txt").unwrap().is_none());
+        let all = manifest.iter().unwrap();
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].0, "/a.txt");
+    }
+}
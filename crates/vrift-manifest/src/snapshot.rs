@@ -0,0 +1,303 @@
+//! Named snapshots of a manifest, for rolling a workspace back.
+//!
+//! [`LmdbManifest::snapshot_create`](crate::LmdbManifest::snapshot_create)
+//! writes every entry, sorted by path, into a tree object stored in the CAS,
+//! so equal manifests share one object. The `snapshots` file in the manifest
+//! directory names the trees, one line per snapshot:
+//!
+//! ```text
+//! <name> <tree hash, hex> <created, seconds since the epoch> <entries>
+//! ```
+//!
+//! [`LmdbManifest::snapshot_restore`](crate::LmdbManifest::snapshot_restore)
+//! replaces the committed entries with a tree's in one store transaction:
+//! readers see the old manifest or the restored one, never a mix. GC keeps
+//! the trees and the blobs they reference alive ([`referenced_blobs`]).
+
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use vrift_cas::{Blake3Hash, CasStore};
+
+use crate::lmdb::{LmdbError, LmdbResult, ManifestEntry};
+use crate::DiffEntry;
+
+/// Snapshot names file inside the manifest directory
+pub const SNAPSHOTS_FILE: &str = "snapshots";
+
+/// First bytes of a tree object
+const TREE_MAGIC: &[u8; 8] = b"VRTREE01";
+
+/// Longest snapshot name
+const NAME_MAX: usize = 64;
+
+/// A named tree of manifest entries
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    pub name: String,
+    /// CAS hash of the tree object
+    pub tree: Blake3Hash,
+    /// Creation time, seconds since the epoch
+    pub created: u64,
+    /// Number of entries in the tree
+    pub entries: usize,
+}
+
+/// One entry of a tree object; `ingested_at` is not serialized with the
+/// entry itself
+#[derive(Serialize, Deserialize)]
+struct TreeEntry {
+    path: String,
+    entry: ManifestEntry,
+    ingested_at: i64,
+}
+
+/// Check that `name` can name a snapshot: 1 to 64 ASCII letters, digits,
+/// `.`, `_` or `-`, not starting with `.` or `-`
+pub fn validate_name(name: &str) -> LmdbResult<()> {
+    let valid = !name.is_empty()
+        && name.len() <= NAME_MAX
+        && !name.starts_with(['.', '-'])
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"._-".contains(&b));
+    if valid {
+        Ok(())
+    } else {
+        Err(LmdbError::InvalidInput(format!(
+            "invalid snapshot name {:?}: use up to {} letters, digits, '.', '_' or '-'",
+            name, NAME_MAX
+        )))
+    }
+}
+
+/// Snapshots of the manifest directory `dir`, oldest first
+pub fn list(dir: &Path) -> LmdbResult<Vec<Snapshot>> {
+    let text = match std::fs::read_to_string(dir.join(SNAPSHOTS_FILE)) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            parse_line(line).ok_or_else(|| {
+                LmdbError::Corrupted(format!("{}: bad line {:?}", SNAPSHOTS_FILE, line))
+            })
+        })
+        .collect()
+}
+
+/// Snapshot `name` of the manifest directory `dir`
+pub fn find(dir: &Path, name: &str) -> LmdbResult<Snapshot> {
+    list(dir)?
+        .into_iter()
+        .find(|s| s.name == name)
+        .ok_or_else(|| LmdbError::NotFound(format!("snapshot {}", name)))
+}
+
+fn parse_line(line: &str) -> Option<Snapshot> {
+    let mut fields = line.split_whitespace();
+    let name = fields.next()?.to_string();
+    let tree = CasStore::hex_to_hash(fields.next()?)?;
+    let created = fields.next()?.parse().ok()?;
+    let entries = fields.next()?.parse().ok()?;
+    Some(Snapshot {
+        name,
+        tree,
+        created,
+        entries,
+    })
+}
+
+/// Add `snapshot` to the names of `dir`, replacing the file in one rename
+pub(crate) fn add(dir: &Path, snapshot: &Snapshot) -> LmdbResult<()> {
+    let mut snapshots = list(dir)?;
+    if snapshots.iter().any(|s| s.name == snapshot.name) {
+        return Err(LmdbError::InvalidInput(format!(
+            "snapshot {} already exists",
+            snapshot.name
+        )));
+    }
+    snapshots.push(snapshot.clone());
+
+    let tmp = dir.join(format!("{}.tmp", SNAPSHOTS_FILE));
+    let mut file = std::fs::File::create(&tmp)?;
+    for s in &snapshots {
+        writeln!(
+            file,
+            "{} {} {} {}",
+            s.name,
+            CasStore::hash_to_hex(&s.tree),
+            s.created,
+            s.entries
+        )?;
+    }
+    file.sync_all()?;
+    std::fs::rename(&tmp, dir.join(SNAPSHOTS_FILE))?;
+    Ok(())
+}
+
+/// Store `entries` in `cas` as a tree object; its hash
+pub(crate) fn write_tree(
+    cas: &CasStore,
+    mut entries: Vec<(String, ManifestEntry)>,
+) -> LmdbResult<Blake3Hash> {
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    let tree: Vec<TreeEntry> = entries
+        .into_iter()
+        .map(|(path, entry)| TreeEntry {
+            path,
+            ingested_at: entry.ingested_at,
+            entry,
+        })
+        .collect();
+    let mut bytes = TREE_MAGIC.to_vec();
+    bincode::serialize_into(&mut bytes, &tree)
+        .map_err(|e| LmdbError::Corrupted(format!("snapshot tree: {}", e)))?;
+    Ok(cas.store(&bytes)?)
+}
+
+/// Entries of the tree object `tree` in `cas`
+pub fn read_tree(cas: &CasStore, tree: &Blake3Hash) -> LmdbResult<Vec<(String, ManifestEntry)>> {
+    let hex = CasStore::hash_to_hex(tree);
+    let bytes = cas.get(tree)?;
+    if cas.hash(&bytes) != *tree {
+        return Err(LmdbError::Corrupted(format!(
+            "snapshot tree {}: hash mismatch",
+            hex
+        )));
+    }
+    let body = bytes
+        .strip_prefix(TREE_MAGIC.as_slice())
+        .ok_or_else(|| LmdbError::Corrupted(format!("{} is not a snapshot tree", hex)))?;
+    let tree: Vec<TreeEntry> = bincode::deserialize(body)
+        .map_err(|e| LmdbError::Corrupted(format!("snapshot tree {}: {}", hex, e)))?;
+    Ok(tree
+        .into_iter()
+        .map(|t| {
+            let mut entry = t.entry;
+            entry.ingested_at = t.ingested_at;
+            (t.path, entry)
+        })
+        .collect())
+}
+
+/// Changes from snapshot `from` to snapshot `to` (see
+/// [`diff_entries`](crate::diff_entries))
+pub fn diff(cas: &CasStore, from: &Snapshot, to: &Snapshot) -> LmdbResult<Vec<DiffEntry>> {
+    let old = read_tree(cas, &from.tree)?;
+    let new = read_tree(cas, &to.tree)?;
+    Ok(crate::diff_entries(
+        old.iter().map(|(path, e)| (path.as_str(), &e.vnode)),
+        new.iter().map(|(path, e)| (path.as_str(), &e.vnode)),
+    ))
+}
+
+/// Blobs the snapshots of the manifest directory `dir` keep alive: their
+/// tree objects and every blob the trees reference. A tree missing from
+/// `cas` is skipped.
+pub fn referenced_blobs(dir: &Path, cas: &CasStore) -> LmdbResult<HashSet<Blake3Hash>> {
+    let mut blobs = HashSet::new();
+    for snapshot in list(dir)? {
+        blobs.insert(snapshot.tree);
+        match read_tree(cas, &snapshot.tree) {
+            Ok(entries) => blobs.extend(entries.iter().map(|(_, e)| e.vnode.content_hash)),
+            Err(e) => warn!(
+                snapshot = %snapshot.name,
+                error = %e,
+                "Skipping unreadable snapshot tree"
+            ),
+        }
+    }
+    Ok(blobs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_names() {
+        for name in ["nightly", "v1.2.0", "before_upgrade", "a-b", "0"] {
+            validate_name(name).unwrap();
+        }
+        for name in ["", ".hidden", "-flag", "has space", "a/b", "ünicode"] {
+            assert!(validate_name(name).is_err(), "{:?}", name);
+        }
+        assert!(validate_name(&"x".repeat(NAME_MAX + 1)).is_err());
+    }
+
+    #[test]
+    fn test_snapshot_list_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(list(dir.path()).unwrap().is_empty());
+
+        let first = Snapshot {
+            name: "first".to_string(),
+            tree: [0xAB; 32],
+            created: 1_700_000_000,
+            entries: 3,
+        };
+        add(dir.path(), &first).unwrap();
+        let second = Snapshot {
+            name: "second".to_string(),
+            ..first.clone()
+        };
+        add(dir.path(), &second).unwrap();
+        assert!(matches!(
+            add(dir.path(), &first),
+            Err(LmdbError::InvalidInput(_))
+        ));
+
+        assert_eq!(list(dir.path()).unwrap(), vec![first.clone(), second]);
+        assert_eq!(find(dir.path(), "first").unwrap(), first);
+        assert!(matches!(
+            find(dir.path(), "third"),
+            Err(LmdbError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_snapshot_diff() {
+        let dir = tempfile::tempdir().unwrap();
+        let cas = CasStore::new(dir.path()).unwrap();
+        let entry = |byte| ManifestEntry {
+            vnode: crate::VnodeEntry::new_file([byte; 32], 1, 0, 0o644),
+            tier: crate::AssetTier::Tier2Mutable,
+            stale: false,
+            ingested_at: 0,
+        };
+        let snapshot = |name: &str, entries: Vec<(&str, ManifestEntry)>| Snapshot {
+            name: name.to_string(),
+            entries: entries.len(),
+            tree: write_tree(
+                &cas,
+                entries
+                    .into_iter()
+                    .map(|(path, e)| (path.to_string(), e))
+                    .collect(),
+            )
+            .unwrap(),
+            created: 0,
+        };
+        let before = snapshot("before", vec![("/a", entry(1)), ("/b", entry(2))]);
+        let after = snapshot("after", vec![("/b", entry(3)), ("/c", entry(4))]);
+
+        let changes = diff(&cas, &before, &after).unwrap();
+        let kinds: Vec<(&str, crate::DiffKind)> =
+            changes.iter().map(|d| (d.path.as_str(), d.kind)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("/a", crate::DiffKind::Removed),
+                ("/b", crate::DiffKind::Modified),
+                ("/c", crate::DiffKind::Added),
+            ]
+        );
+        assert!(diff(&cas, &after, &after).unwrap().is_empty());
+    }
+}
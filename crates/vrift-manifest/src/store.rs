@@ -0,0 +1,165 @@
+//! Storage backends for the base layer of [`LmdbManifest`](crate::LmdbManifest).
+//!
+//! The manifest keeps uncommitted changes in its delta layer and hands them
+//! to a [`ManifestStore`] on commit. Two stores exist:
+//!
+//! - [`ManifestBackend::Lmdb`]: heed/LMDB, the default. Readers in other
+//!   processes see commits at once, but the map size is fixed at open and the
+//!   lock file needs a writable, mmap-able directory.
+//! - [`ManifestBackend::Redb`]: redb, pure Rust, one file that grows as
+//!   needed. Only one process can have it open at a time.
+//!
+//! A manifest directory keeps the backend it was created with:
+//! [`ManifestBackend::detect`] looks at the files inside, and only new
+//! manifests take [`default_backend`] (`storage.manifest_backend`).
+
+use std::path::Path;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use crate::lmdb::{LmdbError, LmdbResult, ManifestEntry};
+use crate::PathHash;
+
+/// Which store holds a manifest's committed entries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(u8)]
+pub enum ManifestBackend {
+    #[default]
+    Lmdb = 0,
+    Redb = 1,
+}
+
+impl ManifestBackend {
+    /// Parse a config value: `lmdb` or `redb`
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "lmdb" => Some(Self::Lmdb),
+            "redb" => Some(Self::Redb),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Lmdb => "lmdb",
+            Self::Redb => "redb",
+        }
+    }
+
+    /// The backend of the manifest directory `dir`, if it holds one
+    pub fn detect(dir: &Path) -> Option<Self> {
+        if dir.join("data.mdb").is_file() {
+            Some(Self::Lmdb)
+        } else if dir.join(crate::redb_store::REDB_FILE).is_file() {
+            Some(Self::Redb)
+        } else {
+            None
+        }
+    }
+}
+
+static DEFAULT_BACKEND: AtomicU8 = AtomicU8::new(ManifestBackend::Lmdb as u8);
+
+/// Backend of the manifests this process creates. vrift-config sets it
+/// from `storage.manifest_backend`.
+pub fn set_default_backend(backend: ManifestBackend) {
+    DEFAULT_BACKEND.store(backend as u8, Ordering::Relaxed);
+}
+
+pub fn default_backend() -> ManifestBackend {
+    match DEFAULT_BACKEND.load(Ordering::Relaxed) {
+        1 => ManifestBackend::Redb,
+        _ => ManifestBackend::Lmdb,
+    }
+}
+
+/// One change of a commit
+#[derive(Debug)]
+pub enum StoreOp<'a> {
+    /// Insert or replace an entry; `path` is kept when `None`
+    Put {
+        hash: PathHash,
+        path: Option<&'a str>,
+        entry: &'a ManifestEntry,
+    },
+    /// Remove an entry and its path
+    Delete(PathHash),
+}
+
+/// Committed manifest entries, keyed by path hash.
+///
+/// An entry's `ingested_at` is stored beside it (0 is not stored), so
+/// [`ManifestStore::get`] and [`ManifestStore::entries`] fill it in.
+pub trait ManifestStore: Send + Sync {
+    fn backend(&self) -> ManifestBackend;
+
+    fn get(&self, hash: &PathHash) -> LmdbResult<Option<ManifestEntry>>;
+
+    fn get_path(&self, hash: &PathHash) -> LmdbResult<Option<String>>;
+
+    fn contains(&self, hash: &PathHash) -> LmdbResult<bool>;
+
+    fn len(&self) -> LmdbResult<usize>;
+
+    fn is_empty(&self) -> LmdbResult<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Every entry that has a path
+    fn entries(&self) -> LmdbResult<Vec<(PathHash, String, ManifestEntry)>>;
+
+    /// Apply `ops` in one transaction: all of them or none
+    fn apply(&self, ops: &[StoreOp<'_>]) -> LmdbResult<()>;
+
+    /// Flush committed data to disk
+    fn sync(&self) -> LmdbResult<()>;
+}
+
+/// Open the store of `backend` in the directory `dir`
+pub fn open_store(dir: &Path, backend: ManifestBackend) -> LmdbResult<Box<dyn ManifestStore>> {
+    Ok(match backend {
+        ManifestBackend::Lmdb => Box::new(crate::lmdb::LmdbStore::open(dir)?),
+        ManifestBackend::Redb => Box::new(crate::redb_store::RedbStore::open(dir)?),
+    })
+}
+
+/// Open the existing store of `backend` in the directory `dir` for reading
+/// only; see [`LmdbManifest::open_readonly`](crate::LmdbManifest::open_readonly)
+pub fn open_store_readonly(
+    dir: &Path,
+    backend: ManifestBackend,
+) -> LmdbResult<Box<dyn ManifestStore>> {
+    Ok(match backend {
+        ManifestBackend::Lmdb => Box::new(crate::lmdb::LmdbStore::open_readonly(dir)?),
+        ManifestBackend::Redb => Box::new(crate::redb_store::RedbStore::open_readonly(dir)?),
+    })
+}
+
+/// Sizes of a manifest's store before and after [`compact`]
+#[derive(Debug, Clone, Copy)]
+pub struct CompactStats {
+    pub backend: ManifestBackend,
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+}
+
+/// Rewrite the manifest in `dir` without the space freed by removed and
+/// replaced entries. No process may have the manifest open.
+pub fn compact(dir: &Path) -> LmdbResult<CompactStats> {
+    let Some(backend) = ManifestBackend::detect(dir) else {
+        return Err(LmdbError::NotFound(dir.display().to_string()));
+    };
+    let file = dir.join(match backend {
+        ManifestBackend::Lmdb => "data.mdb",
+        ManifestBackend::Redb => crate::redb_store::REDB_FILE,
+    });
+    let bytes_before = std::fs::metadata(&file)?.len();
+    match backend {
+        ManifestBackend::Lmdb => crate::lmdb::LmdbStore::compact(dir)?,
+        ManifestBackend::Redb => crate::redb_store::RedbStore::compact(dir)?,
+    }
+    Ok(CompactStats {
+        backend,
+        bytes_before,
+        bytes_after: std::fs::metadata(&file)?.len(),
+    })
+}
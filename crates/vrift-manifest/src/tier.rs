@@ -3,8 +3,16 @@
 //! Assets are classified by write frequency for optimized protection strategies:
 //! - Tier-1 (Immutable): Never written, maximum protection via symlink + chattr
 //! - Tier-2 (Mutable): Rarely written, protected via hardlink + Break-Before-Write
+//!
+//! Patterns only give the initial tier. [`TierLedger`] tracks which entries
+//! are actually written across sessions so the daemon can later promote
+//! entries that are never written and demote Tier-1 entries that are.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
 
-use crate::AssetTier;
+use crate::{AssetTier, ManifestEntry};
 
 /// Default path patterns for Tier-1 (Immutable) classification
 pub const DEFAULT_TIER1_PATTERNS: &[&str] = &[
@@ -133,6 +141,87 @@ pub fn is_immutable_candidate(path: &str) -> bool {
     TierClassifier::default().is_immutable_candidate(path)
 }
 
+/// When an entry was last seen written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PathUsage {
+    /// `ManifestEntry::ingested_at` when the ledger last looked
+    pub ingested_at: i64,
+    /// `TierLedger::sessions` when a new `ingested_at` was first seen
+    pub written_at_session: u64,
+}
+
+/// A tier change decided by [`TierLedger::reevaluate`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TierChange {
+    pub path: String,
+    pub to: AssetTier,
+}
+
+/// Write history of a workspace's manifest entries, counted in sessions
+/// (process trees that ran against the workspace and exited).
+///
+/// A write shows up as a new `ingested_at` on the entry. The ledger compares
+/// each entry against what it saw on the previous evaluation, so it only
+/// notices writes at evaluation granularity: an entry written in several
+/// sessions between two evaluations counts as written once, at the later one.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TierLedger {
+    /// Sessions that ended in the workspace
+    pub sessions: u64,
+    pub paths: HashMap<String, PathUsage>,
+}
+
+impl TierLedger {
+    /// Record the writes visible in `entries` and return the entries whose
+    /// tier should change:
+    /// - a Tier-1 entry that was written is demoted to Tier-2
+    /// - a Tier-2 entry not written during the last `promote_after` sessions
+    ///   is promoted to Tier-1 (never, if `promote_after` is 0)
+    ///
+    /// Entries seen for the first time count as written now. Only files
+    /// backed by a CAS blob are tracked: directories, symlinks, inline and
+    /// stale entries are ignored, and paths no longer in `entries` are
+    /// forgotten.
+    pub fn reevaluate<'a, I>(&mut self, entries: I, promote_after: u64) -> Vec<TierChange>
+    where
+        I: IntoIterator<Item = (&'a str, &'a ManifestEntry)>,
+    {
+        let mut changes = Vec::new();
+        let mut seen = HashMap::with_capacity(self.paths.len());
+        for (path, entry) in entries {
+            let vnode = &entry.vnode;
+            if vnode.is_dir() || vnode.is_symlink() || vnode.is_inline() || entry.stale {
+                continue;
+            }
+            let usage = match self.paths.remove(path) {
+                Some(usage) if usage.ingested_at == entry.ingested_at => usage,
+                previous => {
+                    if previous.is_some() && entry.tier == AssetTier::Tier1Immutable {
+                        changes.push(TierChange {
+                            path: path.to_string(),
+                            to: AssetTier::Tier2Mutable,
+                        });
+                    }
+                    PathUsage {
+                        ingested_at: entry.ingested_at,
+                        written_at_session: self.sessions,
+                    }
+                }
+            };
+            let idle = self.sessions.saturating_sub(usage.written_at_session);
+            if entry.tier == AssetTier::Tier2Mutable && promote_after > 0 && idle >= promote_after {
+                changes.push(TierChange {
+                    path: path.to_string(),
+                    to: AssetTier::Tier1Immutable,
+                });
+            }
+            seen.insert(path.to_string(), usage);
+        }
+        self.paths = seen;
+        changes
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -333,4 +422,84 @@ mod tests {
         assert!(classifier.is_immutable_candidate("immutable/file.txt"));
         assert!(!classifier.is_immutable_candidate("mutable/file.txt"));
     }
+
+    // ========== TierLedger Tests ==========
+
+    fn entry(tier: AssetTier, ingested_at: i64) -> ManifestEntry {
+        ManifestEntry {
+            vnode: crate::VnodeEntry::new_file([1; 32], 1, 0, 0o644),
+            tier,
+            stale: false,
+            ingested_at,
+        }
+    }
+
+    fn reevaluate(ledger: &mut TierLedger, entries: &[(&str, ManifestEntry)]) -> Vec<TierChange> {
+        ledger.reevaluate(entries.iter().map(|(p, e)| (*p, e)), 3)
+    }
+
+    #[test]
+    fn test_ledger_promotes_unwritten_tier2() {
+        let mut ledger = TierLedger::default();
+        let entries = [("/lib.rs", entry(AssetTier::Tier2Mutable, 10))];
+        assert!(reevaluate(&mut ledger, &entries).is_empty());
+
+        ledger.sessions += 2;
+        assert!(reevaluate(&mut ledger, &entries).is_empty());
+        ledger.sessions += 1;
+        assert_eq!(
+            reevaluate(&mut ledger, &entries),
+            vec![TierChange {
+                path: "/lib.rs".to_string(),
+                to: AssetTier::Tier1Immutable,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_ledger_write_restarts_the_count() {
+        let mut ledger = TierLedger::default();
+        reevaluate(
+            &mut ledger,
+            &[("/out.o", entry(AssetTier::Tier2Mutable, 10))],
+        );
+        ledger.sessions += 5;
+        let written = [("/out.o", entry(AssetTier::Tier2Mutable, 20))];
+        assert!(reevaluate(&mut ledger, &written).is_empty());
+        assert_eq!(ledger.paths["/out.o"].written_at_session, 5);
+    }
+
+    #[test]
+    fn test_ledger_demotes_written_tier1() {
+        let mut ledger = TierLedger::default();
+        // First sighting is not a write of a Tier-1 entry
+        assert!(reevaluate(
+            &mut ledger,
+            &[("/dep.js", entry(AssetTier::Tier1Immutable, 10))]
+        )
+        .is_empty());
+        assert_eq!(
+            reevaluate(
+                &mut ledger,
+                &[("/dep.js", entry(AssetTier::Tier1Immutable, 11))]
+            ),
+            vec![TierChange {
+                path: "/dep.js".to_string(),
+                to: AssetTier::Tier2Mutable,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_ledger_forgets_removed_paths_and_zero_disables_promotion() {
+        let mut ledger = TierLedger::default();
+        let entries = [("/a", entry(AssetTier::Tier2Mutable, 1))];
+        ledger.reevaluate(entries.iter().map(|(p, e)| (*p, e)), 0);
+        ledger.sessions += 100;
+        assert!(ledger
+            .reevaluate(entries.iter().map(|(p, e)| (*p, e)), 0)
+            .is_empty());
+        reevaluate(&mut ledger, &[]);
+        assert!(ledger.paths.is_empty());
+    }
 }
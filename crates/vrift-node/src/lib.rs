@@ -0,0 +1,207 @@
+//! # @velo-rift/vrift
+//!
+//! N-API bindings so JS tooling (webpack plugins, jest resolvers) can resolve
+//! virtual paths and read their contents straight from the daemon:
+//!
+//! ```js
+//! const { openWorkspace } = require('@velo-rift/vrift');
+//!
+//! const ws = await openWorkspace(process.cwd());
+//! const entry = await ws.stat('src/index.ts');
+//! const source = await ws.readFile('src/index.ts');
+//! ```
+//!
+//! Metadata comes from the workspace's vDird via `vrift-client`; contents
+//! are read from the local CAS by content hash, so the daemon never copies
+//! file data over its socket. Paths may be absolute (inside the project) or
+//! relative to the project root.
+
+use std::path::{Path, PathBuf};
+
+use napi::bindgen_prelude::Buffer;
+use napi_derive::napi;
+use tokio::sync::Mutex;
+
+/// A path as the VFS sees it
+#[napi(object)]
+pub struct Entry {
+    /// Manifest key (project-relative, leading `/`)
+    pub path: String,
+    /// "file", "dir" or "symlink"
+    pub kind: String,
+    /// BLAKE3 content hash (hex)
+    pub hash: String,
+    pub size: i64,
+    pub mtime: i64,
+    pub mode: u32,
+}
+
+/// One child of a listed directory
+#[napi(object)]
+pub struct DirEntry {
+    pub name: String,
+    pub is_dir: bool,
+}
+
+/// Connection to one project's virtual tree
+#[napi]
+pub struct Workspace {
+    inner: Mutex<vrift_client::Workspace>,
+    cas: vrift_cas::CasStore,
+    id: String,
+    root: PathBuf,
+}
+
+/// Register `projectRoot` with the daemon (starting its vDird if needed) and
+/// connect to its workspace
+#[napi]
+pub async fn open_workspace(project_root: String) -> napi::Result<Workspace> {
+    let mut client = vrift_client::Client::connect().await.map_err(to_napi)?;
+    let workspace = client
+        .open_workspace(&project_root)
+        .await
+        .map_err(to_napi)?;
+
+    let cas_root = vrift_config::config().cas_root().display().to_string();
+    let cas =
+        vrift_cas::CasStore::new(vrift_manifest::normalize_path(&cas_root)).map_err(to_napi)?;
+
+    Ok(Workspace {
+        id: workspace.id().to_string(),
+        root: workspace.project_root().to_path_buf(),
+        inner: Mutex::new(workspace),
+        cas,
+    })
+}
+
+#[napi]
+impl Workspace {
+    #[napi(getter)]
+    pub fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    #[napi(getter)]
+    pub fn project_root(&self) -> String {
+        self.root.display().to_string()
+    }
+
+    /// Metadata for `path`, or `null` if the VFS does not know it
+    #[napi]
+    pub async fn stat(&self, path: String) -> napi::Result<Option<Entry>> {
+        let key = self.manifest_key(&path);
+        let entry = self
+            .inner
+            .lock()
+            .await
+            .get_entry(&key)
+            .await
+            .map_err(to_napi)?;
+        Ok(entry.map(|e| to_entry(key, e)))
+    }
+
+    /// Whether the VFS knows `path`
+    #[napi]
+    pub async fn exists(&self, path: String) -> napi::Result<bool> {
+        Ok(self.stat(path).await?.is_some())
+    }
+
+    /// Children of the directory `path`
+    #[napi]
+    pub async fn readdir(&self, path: String) -> napi::Result<Vec<DirEntry>> {
+        let key = self.manifest_key(&path);
+        let entries = self
+            .inner
+            .lock()
+            .await
+            .list_dir(&key)
+            .await
+            .map_err(to_napi)?;
+        Ok(entries
+            .into_iter()
+            .map(|e| DirEntry {
+                name: e.name,
+                is_dir: e.is_dir,
+            })
+            .collect())
+    }
+
+    /// Contents of the file at `path`, or `null` if the VFS does not know it
+    #[napi]
+    pub async fn read_file(&self, path: String) -> napi::Result<Option<Buffer>> {
+        let Some(hash) = self.file_hash(&path).await? else {
+            return Ok(None);
+        };
+        let cas = self.cas.clone();
+        let data = tokio::task::spawn_blocking(move || cas.get(&hash))
+            .await
+            .map_err(to_napi)?
+            .map_err(to_napi)?;
+        Ok(Some(data.into()))
+    }
+
+    /// On-disk CAS location of the file at `path`, for tools that read or
+    /// mmap files themselves; `null` if unknown or not yet in the CAS
+    #[napi]
+    pub async fn blob_path(&self, path: String) -> napi::Result<Option<String>> {
+        let Some(hash) = self.file_hash(&path).await? else {
+            return Ok(None);
+        };
+        Ok(self
+            .cas
+            .blob_path_for_hash(&hash)
+            .map(|p| p.display().to_string()))
+    }
+}
+
+impl Workspace {
+    /// Map an absolute path inside the project, or a project-relative one,
+    /// to its manifest key
+    fn manifest_key(&self, path: &str) -> String {
+        let relative = Path::new(path)
+            .strip_prefix(&self.root)
+            .map(|p| p.to_string_lossy().into_owned())
+            .unwrap_or_else(|_| path.to_string());
+        let trimmed = relative.trim_start_matches("./").trim_matches('/');
+        format!("/{}", trimmed)
+    }
+
+    async fn file_hash(&self, path: &str) -> napi::Result<Option<vrift_cas::Blake3Hash>> {
+        let key = self.manifest_key(path);
+        let entry = self
+            .inner
+            .lock()
+            .await
+            .get_entry(&key)
+            .await
+            .map_err(to_napi)?;
+        match entry {
+            Some(e) if e.kind == vrift_client::EntryKind::File => Ok(Some(e.content_hash)),
+            Some(_) => Err(napi::Error::from_reason(format!(
+                "EISDIR: not a regular file: {}",
+                key
+            ))),
+            None => Ok(None),
+        }
+    }
+}
+
+fn to_entry(path: String, entry: vrift_client::Entry) -> Entry {
+    let kind = match entry.kind {
+        vrift_client::EntryKind::Directory => "dir",
+        vrift_client::EntryKind::Symlink => "symlink",
+        _ => "file",
+    };
+    Entry {
+        path,
+        kind: kind.to_string(),
+        hash: vrift_cas::CasStore::hash_to_hex(&entry.content_hash),
+        size: entry.size as i64,
+        mtime: entry.mtime,
+        mode: entry.mode,
+    }
+}
+
+fn to_napi(e: impl std::fmt::Display) -> napi::Error {
+    napi::Error::from_reason(e.to_string())
+}
@@ -8,7 +8,8 @@
 //! ## Design
 //!
 //! Based on profile-guided packing: files accessed together during startup
-//! are packed contiguously.
+//! are packed contiguously. Profiles from many machines can be merged into a
+//! fleet-wide order with [`ProfileAggregate`].
 //!
 //! ## Packfile Format
 //!
@@ -22,6 +23,10 @@
 //! +----------------+
 //! ```
 
+pub mod profile;
+
+pub use profile::ProfileAggregate;
+
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::{self, BufReader, BufWriter, Seek, SeekFrom, Write};
@@ -33,6 +38,7 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use vrift_cas::Blake3Hash;
+use vrift_error::{Classify, ErrorKind};
 
 /// Magic bytes for packfile identification
 const PACK_MAGIC: &[u8; 8] = b"VELOPACK";
@@ -55,6 +61,16 @@ pub enum PackError {
     NotFound { hash: String },
 }
 
+impl Classify for PackError {
+    fn classify(&self) -> ErrorKind {
+        match self {
+            PackError::Io(e) => e.classify(),
+            PackError::Rkyv(_) | PackError::Invalid(_) => ErrorKind::Corrupted,
+            PackError::NotFound { .. } => ErrorKind::NotFound,
+        }
+    }
+}
+
 pub type Result<T> = std::result::Result<T, PackError>;
 
 /// Packfile header (fixed 32 bytes)
@@ -105,12 +121,26 @@ pub struct PackIndexEntry {
     pub length: u64,
 }
 
+/// Identifier of a packfile, from the bytes of its index section (FNV-1a,
+/// never 0). VDir entries name the pack their offset points into
+/// by it, so a reader holding a rebuilt pack ignores offsets into the old
+/// one.
+pub fn pack_id(index: &[u8]) -> u32 {
+    let mut hash: u32 = 0x811c_9dc5;
+    for b in index {
+        hash ^= u32::from(*b);
+        hash = hash.wrapping_mul(0x0100_0193);
+    }
+    hash.max(1)
+}
+
 /// Reader for packfiles
 pub struct PackReader {
     path: PathBuf,
     mmap: Mmap,
     index: HashMap<Blake3Hash, PackIndexEntry>,
     data_offset: u64,
+    id: u32,
 }
 
 impl PackReader {
@@ -139,15 +169,27 @@ impl PackReader {
 
         let index: HashMap<Blake3Hash, PackIndexEntry> =
             entries.into_iter().map(|e| (e.hash, e)).collect();
+        let id = pack_id(index_bytes);
 
         Ok(Self {
             path,
             mmap,
             index,
             data_offset: header.data_offset,
+            id,
         })
     }
 
+    /// [`pack_id`] of this packfile
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Index entry of a blob: its offset in the data section and length
+    pub fn entry(&self, hash: &Blake3Hash) -> Option<&PackIndexEntry> {
+        self.index.get(hash)
+    }
+
     /// Get a blob by hash (zero-copy via mmap slice)
     pub fn get(&self, hash: &Blake3Hash) -> Result<&[u8]> {
         let entry = self.index.get(hash).ok_or_else(|| PackError::NotFound {
@@ -325,6 +367,16 @@ mod tests {
         let retrieved2 = reader.get(&hash2).unwrap();
         assert_eq!(retrieved1, data1);
         assert_eq!(retrieved2, data2);
+        assert_eq!(reader.entry(&hash2).unwrap().offset, data1.len() as u64);
+
+        // A pack with other contents gets another id
+        let other_path = temp.path().join("other.pack");
+        let mut writer = PackWriter::new(&other_path);
+        writer.add(hash2, data2);
+        writer.finish().unwrap();
+        let other = PackReader::open(&other_path).unwrap();
+        assert_ne!(reader.id(), 0);
+        assert_ne!(reader.id(), other.id());
     }
 
     #[test]
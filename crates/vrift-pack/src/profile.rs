@@ -0,0 +1,317 @@
+//! Access profiles aggregated across machines
+//!
+//! A single [`AccessProfile`] captures one person's workload. To pack for a
+//! whole team, profiles from many developers and CI runs are exported to a
+//! small text format, merged into a [`ProfileAggregate`], and turned back
+//! into one packing order.
+//!
+//! ## Exchange format
+//!
+//! ```text
+//! vrift-access-profile 1
+//! weight 12
+//! <blake3 hex> <seen> <position sum>
+//! ...
+//! ```
+//!
+//! `weight` is the number of runs the file stands for. Per blob, `seen` is the
+//! weight of the runs that read it and `position sum` adds up its first-access
+//! position within each run (0 = first, towards 1 = last), scaled by the run's
+//! weight. Everything is a plain sum, so exports can be merged in any order or
+//! grouping with the same result.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use vrift_cas::{Blake3Hash, CasStore};
+
+use crate::{AccessProfile, PackError, Result};
+
+const EXPORT_MAGIC: &str = "vrift-access-profile";
+const EXPORT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct BlobStats {
+    seen: u64,
+    position_sum: f64,
+}
+
+/// Access statistics merged from many runs
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProfileAggregate {
+    weight: u64,
+    blobs: HashMap<Blake3Hash, BlobStats>,
+}
+
+impl ProfileAggregate {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add one run's profile, counted `weight` times
+    pub fn add_run(&mut self, profile: &AccessProfile, weight: u64) {
+        if weight == 0 {
+            return;
+        }
+        self.weight += weight;
+        let len = profile.access_order.len() as f64;
+        for (i, hash) in profile.access_order.iter().enumerate() {
+            let stats = self.blobs.entry(*hash).or_default();
+            stats.seen += weight;
+            stats.position_sum += i as f64 / len * weight as f64;
+        }
+    }
+
+    /// Fold another aggregate into this one
+    pub fn merge(&mut self, other: &ProfileAggregate) {
+        self.weight += other.weight;
+        for (hash, theirs) in &other.blobs {
+            let stats = self.blobs.entry(*hash).or_default();
+            stats.seen += theirs.seen;
+            stats.position_sum += theirs.position_sum;
+        }
+    }
+
+    /// Total weight of the runs merged so far
+    pub fn weight(&self) -> u64 {
+        self.weight
+    }
+
+    /// Number of distinct blobs seen
+    pub fn len(&self) -> usize {
+        self.blobs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blobs.is_empty()
+    }
+
+    /// Share of runs (by weight) that read `hash`
+    pub fn frequency(&self, hash: &Blake3Hash) -> f64 {
+        match self.blobs.get(hash) {
+            Some(stats) if self.weight > 0 => stats.seen as f64 / self.weight as f64,
+            _ => 0.0,
+        }
+    }
+
+    /// Expected first-access position of `hash` over all runs, counting runs
+    /// that never read it as reading it last (1.0)
+    fn expected_position(&self, stats: &BlobStats) -> f64 {
+        let missed = self.weight.saturating_sub(stats.seen) as f64;
+        (stats.position_sum + missed) / self.weight.max(1) as f64
+    }
+
+    /// Blobs in fleet-wide packing order: files most runs read early come
+    /// first, files few runs read drift towards the end
+    pub fn packing_order(&self) -> Vec<Blake3Hash> {
+        let mut ranked: Vec<(f64, &Blake3Hash)> = self
+            .blobs
+            .iter()
+            .map(|(hash, stats)| (self.expected_position(stats), hash))
+            .collect();
+        ranked.sort_by(|a, b| a.0.total_cmp(&b.0).then_with(|| a.1.cmp(b.1)));
+        ranked.into_iter().map(|(_, hash)| *hash).collect()
+    }
+
+    /// A profile in [`Self::packing_order`], for the pack planner
+    pub fn to_profile(&self) -> AccessProfile {
+        AccessProfile {
+            access_order: self.packing_order(),
+        }
+    }
+
+    /// Write the exchange format (blobs in packing order, so exports diff
+    /// sensibly)
+    pub fn export<W: Write>(&self, mut out: W) -> Result<()> {
+        writeln!(out, "{} {}", EXPORT_MAGIC, EXPORT_VERSION)?;
+        writeln!(out, "weight {}", self.weight)?;
+        for hash in self.packing_order() {
+            let stats = &self.blobs[&hash];
+            writeln!(
+                out,
+                "{} {} {}",
+                CasStore::hash_to_hex(&hash),
+                stats.seen,
+                stats.position_sum
+            )?;
+        }
+        out.flush()?;
+        Ok(())
+    }
+
+    /// Read the exchange format
+    pub fn import<R: BufRead>(input: R) -> Result<Self> {
+        let invalid = |line: usize, msg: &str| {
+            PackError::Invalid(format!("access profile line {}: {}", line, msg))
+        };
+        let mut lines = input.lines().enumerate().map(|(i, l)| (i + 1, l));
+
+        match lines.next() {
+            Some((_, line)) => {
+                let line = line?;
+                let version = line
+                    .strip_prefix(EXPORT_MAGIC)
+                    .and_then(|v| v.trim().parse::<u32>().ok())
+                    .ok_or_else(|| invalid(1, "not an exported access profile"))?;
+                if version != EXPORT_VERSION {
+                    return Err(invalid(1, &format!("unsupported version {}", version)));
+                }
+            }
+            None => return Err(invalid(1, "empty file")),
+        }
+        let weight = match lines.next() {
+            Some((n, line)) => line?
+                .strip_prefix("weight ")
+                .and_then(|w| w.trim().parse::<u64>().ok())
+                .ok_or_else(|| invalid(n, "expected 'weight <runs>'"))?,
+            None => return Err(invalid(2, "missing weight")),
+        };
+
+        let mut aggregate = Self {
+            weight,
+            blobs: HashMap::new(),
+        };
+        for (n, line) in lines {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let mut fields = line.split_ascii_whitespace();
+            let (Some(hex), Some(seen), Some(position_sum), None) =
+                (fields.next(), fields.next(), fields.next(), fields.next())
+            else {
+                return Err(invalid(n, "expected '<hash> <seen> <position sum>'"));
+            };
+            let hash = CasStore::hex_to_hash(hex).ok_or_else(|| invalid(n, "bad hash"))?;
+            let seen: u64 = seen.parse().map_err(|_| invalid(n, "bad seen count"))?;
+            let position_sum: f64 = position_sum
+                .parse()
+                .ok()
+                .filter(|p: &f64| p.is_finite() && *p >= 0.0)
+                .ok_or_else(|| invalid(n, "bad position sum"))?;
+            if seen > weight {
+                return Err(invalid(n, "seen exceeds weight"));
+            }
+            let stats = aggregate.blobs.entry(hash).or_default();
+            stats.seen += seen;
+            stats.position_sum += position_sum;
+        }
+        Ok(aggregate)
+    }
+
+    /// Write the exchange format to `path`
+    pub fn export_to<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.export(BufWriter::new(File::create(path)?))
+    }
+
+    /// Load either an exported aggregate or a binary [`AccessProfile`]
+    /// (counted as one run)
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let mut reader = BufReader::new(File::open(path.as_ref())?);
+        let mut head = [0u8; EXPORT_MAGIC.len()];
+        let is_export = match reader.read_exact(&mut head) {
+            Ok(()) => head == *EXPORT_MAGIC.as_bytes(),
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => false,
+            Err(e) => return Err(e.into()),
+        };
+        if is_export {
+            let rest = BufReader::new(File::open(path.as_ref())?);
+            return Self::import(rest);
+        }
+        let mut aggregate = Self::new();
+        aggregate.add_run(&AccessProfile::load(path)?, 1);
+        Ok(aggregate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn run(hashes: &[u8]) -> AccessProfile {
+        let mut profile = AccessProfile::default();
+        for &h in hashes {
+            profile.record([h; 32]);
+        }
+        profile
+    }
+
+    #[test]
+    fn test_frequency_beats_one_early_access() {
+        let mut fleet = ProfileAggregate::new();
+        // Nine developers read 1 then 2; one CI job starts with 9
+        fleet.add_run(&run(&[1, 2]), 9);
+        fleet.add_run(&run(&[9, 1, 2]), 1);
+
+        assert_eq!(fleet.weight(), 10);
+        assert_eq!(fleet.frequency(&[1; 32]), 1.0);
+        assert_eq!(fleet.frequency(&[9; 32]), 0.1);
+        assert_eq!(fleet.packing_order(), vec![[1; 32], [2; 32], [9; 32]]);
+    }
+
+    #[test]
+    fn test_merge_is_associative() {
+        let (a, b, c) = (run(&[1, 2, 3]), run(&[3, 1]), run(&[4]));
+
+        let mut all = ProfileAggregate::new();
+        all.add_run(&a, 1);
+        all.add_run(&b, 2);
+        all.add_run(&c, 1);
+
+        let mut left = ProfileAggregate::new();
+        left.add_run(&a, 1);
+        let mut right = ProfileAggregate::new();
+        right.add_run(&b, 2);
+        right.add_run(&c, 1);
+        left.merge(&right);
+
+        assert_eq!(left.weight(), all.weight());
+        assert_eq!(left.packing_order(), all.packing_order());
+    }
+
+    #[test]
+    fn test_export_import_roundtrip() {
+        let mut fleet = ProfileAggregate::new();
+        fleet.add_run(&run(&[1, 2, 3]), 3);
+        fleet.add_run(&run(&[2, 5]), 1);
+
+        let mut text = Vec::new();
+        fleet.export(&mut text).unwrap();
+        assert!(text.starts_with(b"vrift-access-profile 1\nweight 4\n"));
+        let imported = ProfileAggregate::import(&text[..]).unwrap();
+        assert_eq!(imported, fleet);
+    }
+
+    #[test]
+    fn test_import_rejects_malformed() {
+        for bad in [
+            "",
+            "something else\n",
+            "vrift-access-profile 2\nweight 1\n",
+            "vrift-access-profile 1\n",
+            "vrift-access-profile 1\nweight 1\nzz 1 0\n",
+            "vrift-access-profile 1\nweight 1\n0101010101010101010101010101010101010101010101010101010101010101 2 0\n",
+        ] {
+            let err = ProfileAggregate::import(bad.as_bytes()).unwrap_err();
+            assert!(matches!(err, PackError::Invalid(_)), "{:?}: {}", bad, err);
+        }
+    }
+
+    #[test]
+    fn test_load_accepts_binary_and_export() {
+        let temp = TempDir::new().unwrap();
+        let binary = temp.path().join("profile.bin");
+        let export = temp.path().join("fleet.txt");
+
+        run(&[7, 8]).save(&binary).unwrap();
+        let one = ProfileAggregate::load(&binary).unwrap();
+        assert_eq!(one.weight(), 1);
+        assert_eq!(one.packing_order(), vec![[7; 32], [8; 32]]);
+
+        one.export_to(&export).unwrap();
+        assert_eq!(ProfileAggregate::load(&export).unwrap(), one);
+    }
+}
@@ -0,0 +1,266 @@
+//! # vrift (Python)
+//!
+//! PyO3 bindings for reading manifests and the CAS directly, so Python build
+//! tooling can query the virtual tree and fetch blobs without shelling out to
+//! the CLI:
+//!
+//! ```python
+//! import vrift
+//!
+//! manifest = vrift.Manifest.open(".vrift/manifest.lmdb")
+//! entry = manifest.get("/src/main.rs")
+//! data = vrift.CasStore().get(entry.hash)
+//! ```
+//!
+//! Paths are manifest keys (project-relative with a leading `/`); hashes are
+//! BLAKE3 hex strings. Build with `maturin develop` from this directory.
+
+use std::path::PathBuf;
+
+use pyo3::exceptions::{PyIOError, PyKeyError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use vrift_manifest::lmdb::{AssetTier, LmdbManifest};
+use vrift_manifest::VnodeEntry;
+
+/// One manifest entry
+#[pyclass(frozen, module = "vrift")]
+#[derive(Clone)]
+struct Entry {
+    #[pyo3(get)]
+    path: String,
+    /// BLAKE3 content hash (hex)
+    #[pyo3(get)]
+    hash: String,
+    #[pyo3(get)]
+    size: u64,
+    /// Nanoseconds since the Unix epoch (negative before 1970)
+    #[pyo3(get)]
+    mtime: i64,
+    #[pyo3(get)]
+    mode: u32,
+    /// "file", "dir" or "symlink"
+    #[pyo3(get)]
+    kind: &'static str,
+    /// 1 (immutable) or 2 (mutable); None for binary manifests
+    #[pyo3(get)]
+    tier: Option<u8>,
+    /// Pending re-ingest (LMDB manifests only)
+    #[pyo3(get)]
+    stale: bool,
+}
+
+impl Entry {
+    fn new(path: &str, vnode: &VnodeEntry, tier: Option<AssetTier>, stale: bool) -> Self {
+        let kind = if vnode.is_dir() {
+            "dir"
+        } else if vnode.is_symlink() {
+            "symlink"
+        } else {
+            "file"
+        };
+        Self {
+            path: path.to_string(),
+            hash: vrift_cas::CasStore::hash_to_hex(&vnode.content_hash),
+            size: vnode.size,
+            mtime: vnode.mtime,
+            mode: vnode.mode,
+            kind,
+            tier: tier.map(|t| t as u8),
+            stale,
+        }
+    }
+}
+
+#[pymethods]
+impl Entry {
+    fn is_dir(&self) -> bool {
+        self.kind == "dir"
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Entry(path={:?}, kind={:?}, size={}, hash={:?})",
+            self.path, self.kind, self.size, self.hash
+        )
+    }
+}
+
+enum Backend {
+    Lmdb(LmdbManifest),
+    Binary(vrift_manifest::Manifest),
+}
+
+/// Read access to a manifest: an LMDB manifest directory or a binary
+/// `.manifest` file
+#[pyclass(module = "vrift")]
+struct Manifest {
+    backend: Backend,
+}
+
+#[pymethods]
+impl Manifest {
+    /// Open the manifest at `path`
+    #[staticmethod]
+    fn open(path: PathBuf) -> PyResult<Self> {
+        let backend = if path.is_dir() {
+            Backend::Lmdb(LmdbManifest::open(&path).map_err(io_err)?)
+        } else if path.is_file() {
+            Backend::Binary(vrift_manifest::Manifest::load(&path).map_err(io_err)?)
+        } else {
+            return Err(PyIOError::new_err(format!(
+                "No manifest at {}",
+                path.display()
+            )));
+        };
+        Ok(Self { backend })
+    }
+
+    /// Entry for `path`, or None
+    fn get(&self, path: &str) -> PyResult<Option<Entry>> {
+        Ok(match &self.backend {
+            Backend::Lmdb(m) => m
+                .get(path)
+                .map_err(io_err)?
+                .map(|e| Entry::new(path, &e.vnode, Some(e.tier), e.stale)),
+            Backend::Binary(m) => m.get(path).map(|v| Entry::new(path, v, None, false)),
+        })
+    }
+
+    fn __getitem__(&self, path: &str) -> PyResult<Entry> {
+        self.get(path)?
+            .ok_or_else(|| PyKeyError::new_err(path.to_string()))
+    }
+
+    fn __contains__(&self, path: &str) -> PyResult<bool> {
+        Ok(self.get(path)?.is_some())
+    }
+
+    fn __len__(&self) -> PyResult<usize> {
+        match &self.backend {
+            Backend::Lmdb(m) => m.len().map_err(io_err),
+            Backend::Binary(m) => Ok(m.len()),
+        }
+    }
+
+    /// All manifest keys, sorted
+    fn paths(&self) -> PyResult<Vec<String>> {
+        let mut paths: Vec<String> = self.entries()?.into_iter().map(|e| e.path).collect();
+        paths.sort();
+        Ok(paths)
+    }
+
+    /// Entries directly inside directory `path` (default: the root), sorted
+    /// by path
+    #[pyo3(signature = (path = "/"))]
+    fn list(&self, path: &str) -> PyResult<Vec<Entry>> {
+        let dir = path.trim_end_matches('/');
+        let mut children: Vec<Entry> = self
+            .entries()?
+            .into_iter()
+            .filter(|e| {
+                e.path
+                    .strip_prefix(dir)
+                    .and_then(|rest| rest.strip_prefix('/'))
+                    .is_some_and(|name| !name.is_empty() && !name.contains('/'))
+            })
+            .collect();
+        children.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(children)
+    }
+}
+
+impl Manifest {
+    fn entries(&self) -> PyResult<Vec<Entry>> {
+        Ok(match &self.backend {
+            Backend::Lmdb(m) => m
+                .iter()
+                .map_err(io_err)?
+                .into_iter()
+                .map(|(path, e)| Entry::new(&path, &e.vnode, Some(e.tier), e.stale))
+                .collect(),
+            Backend::Binary(m) => m
+                .iter()
+                .map(|(path, v)| Entry::new(path, v, None, false))
+                .collect(),
+        })
+    }
+}
+
+/// Content-addressable blob store
+#[pyclass(module = "vrift")]
+struct CasStore {
+    inner: vrift_cas::CasStore,
+}
+
+#[pymethods]
+impl CasStore {
+    /// Open the store at `root`, or at `~/.vrift/the_source` when omitted
+    #[new]
+    #[pyo3(signature = (root = None))]
+    fn new(root: Option<PathBuf>) -> PyResult<Self> {
+        let inner = match root {
+            Some(root) => vrift_cas::CasStore::new(root),
+            None => vrift_cas::CasStore::default_location(),
+        }
+        .map_err(io_err)?;
+        Ok(Self { inner })
+    }
+
+    #[getter]
+    fn root(&self) -> PathBuf {
+        self.inner.root().to_path_buf()
+    }
+
+    /// Blob content for `hash`; raises KeyError if absent
+    fn get<'py>(&self, py: Python<'py>, hash: &str) -> PyResult<Bound<'py, PyBytes>> {
+        let hash = parse_hash(hash)?;
+        match self.inner.get(&hash) {
+            Ok(data) => Ok(PyBytes::new_bound(py, &data)),
+            Err(vrift_cas::CasError::NotFound { hash }) => Err(PyKeyError::new_err(hash)),
+            Err(e) => Err(io_err(e)),
+        }
+    }
+
+    /// Store `data` and return its hash
+    fn store(&self, data: &[u8]) -> PyResult<String> {
+        let hash = self.inner.store(data).map_err(io_err)?;
+        Ok(vrift_cas::CasStore::hash_to_hex(&hash))
+    }
+
+    /// Store the file at `path` and return its hash
+    fn store_file(&self, path: PathBuf) -> PyResult<String> {
+        let hash = self.inner.store_file(path).map_err(io_err)?;
+        Ok(vrift_cas::CasStore::hash_to_hex(&hash))
+    }
+
+    fn exists(&self, hash: &str) -> PyResult<bool> {
+        Ok(self.inner.exists(&parse_hash(hash)?))
+    }
+
+    /// On-disk location of a blob, or None
+    fn path(&self, hash: &str) -> PyResult<Option<PathBuf>> {
+        Ok(self.inner.blob_path_for_hash(&parse_hash(hash)?))
+    }
+
+    fn __contains__(&self, hash: &str) -> PyResult<bool> {
+        self.exists(hash)
+    }
+}
+
+fn parse_hash(hex: &str) -> PyResult<vrift_cas::Blake3Hash> {
+    vrift_cas::CasStore::hex_to_hash(hex)
+        .ok_or_else(|| PyValueError::new_err(format!("Invalid BLAKE3 hex hash: {:?}", hex)))
+}
+
+fn io_err(e: impl std::fmt::Display) -> PyErr {
+    PyIOError::new_err(e.to_string())
+}
+
+#[pymodule]
+fn vrift(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Entry>()?;
+    m.add_class::<Manifest>()?;
+    m.add_class::<CasStore>()?;
+    Ok(())
+}
@@ -0,0 +1,29 @@
+"""Tests for the vrift Python bindings. Run after `maturin develop`."""
+
+import pytest
+
+vrift = pytest.importorskip("vrift")
+
+
+def test_cas_roundtrip(tmp_path):
+    cas = vrift.CasStore(tmp_path / "cas")
+    digest = cas.store(b"hello vrift")
+
+    assert len(digest) == 64
+    assert digest in cas
+    assert cas.get(digest) == b"hello vrift"
+    assert cas.path(digest) is not None
+
+
+def test_cas_missing_and_invalid(tmp_path):
+    cas = vrift.CasStore(tmp_path / "cas")
+
+    with pytest.raises(KeyError):
+        cas.get("00" * 32)
+    with pytest.raises(ValueError):
+        cas.get("not-a-hash")
+
+
+def test_manifest_open_missing(tmp_path):
+    with pytest.raises(OSError):
+        vrift.Manifest.open(tmp_path / "missing.manifest")
@@ -0,0 +1,44 @@
+from os import PathLike
+from typing import Literal, Optional, Union
+
+_Path = Union[str, PathLike[str]]
+
+class Entry:
+    @property
+    def path(self) -> str: ...
+    @property
+    def hash(self) -> str: ...
+    @property
+    def size(self) -> int: ...
+    @property
+    def mtime(self) -> int: ...
+    @property
+    def mode(self) -> int: ...
+    @property
+    def kind(self) -> Literal["file", "dir", "symlink"]: ...
+    @property
+    def tier(self) -> Optional[int]: ...
+    @property
+    def stale(self) -> bool: ...
+    def is_dir(self) -> bool: ...
+
+class Manifest:
+    @staticmethod
+    def open(path: _Path) -> Manifest: ...
+    def get(self, path: str) -> Optional[Entry]: ...
+    def paths(self) -> list[str]: ...
+    def list(self, path: str = "/") -> list[Entry]: ...
+    def __getitem__(self, path: str) -> Entry: ...
+    def __contains__(self, path: str) -> bool: ...
+    def __len__(self) -> int: ...
+
+class CasStore:
+    def __init__(self, root: Optional[_Path] = None) -> None: ...
+    @property
+    def root(self) -> str: ...
+    def get(self, hash: str) -> bytes: ...
+    def store(self, data: bytes) -> str: ...
+    def store_file(self, path: _Path) -> str: ...
+    def exists(self, hash: str) -> bool: ...
+    def path(self, hash: str) -> Optional[str]: ...
+    def __contains__(self, hash: str) -> bool: ...
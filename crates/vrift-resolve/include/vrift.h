@@ -0,0 +1,58 @@
+/*
+ * vrift.h - resolve Velo Rift VFS paths from C/C++
+ *
+ * Link against libvrift_resolve.a (cargo build -p vrift-resolve --release).
+ * Lookups read the workspace's VDir mapping maintained by vDird, and file
+ * contents come straight from the CAS, so no LD_PRELOAD/DYLD_INSERT_LIBRARIES
+ * is involved. The workspace must have been registered with the daemon
+ * (e.g. `vrift run` or `vrift ingest`) so the VDir exists.
+ *
+ * All functions return 0 (or a file descriptor) on success and a negated
+ * errno value on failure. They are thread-safe.
+ */
+#ifndef VRIFT_H
+#define VRIFT_H
+
+#include <sys/stat.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/*
+ * Attach to the workspace rooted at project_root. Must be called before any
+ * other function; calling it again switches workspaces.
+ *
+ * -ENOENT: the project has no VDir (workspace not registered)
+ * -EINVAL: project_root is NULL or not valid UTF-8
+ */
+int vrift_init(const char *project_root);
+
+/*
+ * stat() a path through the VFS. path is absolute, or relative to the
+ * project root.
+ *
+ * -ENOENT:   the VFS does not know path
+ * -EXDEV:    path is outside the project; use the real filesystem
+ * -ENOTCONN: vrift_init() has not been called
+ */
+int vrift_lookup(const char *path, struct stat *stat_out);
+
+/*
+ * Open a file read-only through the VFS. Returns a file descriptor on the
+ * CAS blob; the caller owns it and must close() it.
+ *
+ * -EISDIR: path is a directory
+ * -EAGAIN: path has uncommitted writes; read it from the project tree
+ * Other errors as for vrift_lookup().
+ */
+int vrift_open(const char *path);
+
+/* Detach from the workspace and release the mapping. */
+void vrift_shutdown(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* VRIFT_H */
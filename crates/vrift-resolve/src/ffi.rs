@@ -0,0 +1,97 @@
+//! C ABI (see `include/vrift.h`)
+//!
+//! One process-wide [`Resolver`] set by `vrift_init`. Every function reports
+//! failure as a negated errno so callers can forward it unchanged.
+
+use std::ffi::CStr;
+use std::io;
+use std::os::fd::IntoRawFd;
+use std::sync::RwLock;
+
+use libc::{c_char, c_int};
+
+use crate::Resolver;
+
+static RESOLVER: RwLock<Option<Resolver>> = RwLock::new(None);
+
+fn neg_errno(e: io::Error) -> c_int {
+    -e.raw_os_error().unwrap_or(libc::EIO)
+}
+
+unsafe fn path_arg<'a>(path: *const c_char) -> Result<&'a str, c_int> {
+    if path.is_null() {
+        return Err(-libc::EINVAL);
+    }
+    CStr::from_ptr(path).to_str().map_err(|_| -libc::EINVAL)
+}
+
+fn with_resolver(f: impl FnOnce(&Resolver) -> Result<c_int, c_int>) -> c_int {
+    let Ok(guard) = RESOLVER.read() else {
+        return -libc::EIO;
+    };
+    match guard.as_ref() {
+        Some(resolver) => f(resolver).unwrap_or_else(|e| e),
+        None => -libc::ENOTCONN,
+    }
+}
+
+/// Attach to the workspace rooted at `project_root`
+#[no_mangle]
+pub unsafe extern "C" fn vrift_init(project_root: *const c_char) -> c_int {
+    let root = match path_arg(project_root) {
+        Ok(root) => root,
+        Err(e) => return e,
+    };
+    let resolver = match Resolver::for_project(root) {
+        Ok(resolver) => resolver,
+        Err(e) => return neg_errno(e),
+    };
+    match RESOLVER.write() {
+        Ok(mut guard) => {
+            *guard = Some(resolver);
+            0
+        }
+        Err(_) => -libc::EIO,
+    }
+}
+
+/// `stat()` a path through the VFS
+#[no_mangle]
+pub unsafe extern "C" fn vrift_lookup(path: *const c_char, stat_out: *mut libc::stat) -> c_int {
+    if stat_out.is_null() {
+        return -libc::EINVAL;
+    }
+    with_resolver(|resolver| {
+        let key = resolver.manifest_key(path_arg(path)?).map_err(neg_errno)?;
+        let entry = resolver.lookup_key(&key).map_err(neg_errno)?;
+
+        std::ptr::write_bytes(stat_out, 0, 1);
+        let st = &mut *stat_out;
+        st.st_size = entry.size as _;
+        st.st_mode = entry.mode as _;
+        st.st_mtime = entry.mtime_sec as _;
+        st.st_mtime_nsec = entry.mtime_nsec as _;
+        st.st_nlink = 1;
+        // Same virtual identity the inception layer reports for VFS paths
+        st.st_dev = 0x52494654 as _; // "RIFT"
+        st.st_ino = vrift_ipc::fnv1a_hash(&key) as _;
+        Ok(0)
+    })
+}
+
+/// Open a VFS file read-only; returns a descriptor the caller must close
+#[no_mangle]
+pub unsafe extern "C" fn vrift_open(path: *const c_char) -> c_int {
+    with_resolver(|resolver| {
+        let fd = resolver.open(path_arg(path)?).map_err(neg_errno)?;
+        Ok(fd.into_raw_fd())
+    })
+}
+
+/// Detach from the workspace
+#[no_mangle]
+pub extern "C" fn vrift_shutdown() {
+    if let Ok(mut guard) = RESOLVER.write() {
+        *guard = None;
+    }
+}
@@ -0,0 +1,300 @@
+//! # vrift-resolve
+//!
+//! Path resolution for tools that can link a library (a custom FUSE layer,
+//! Buck2 integration) instead of relying on the inception layer's
+//! `LD_PRELOAD` / `DYLD_INSERT_LIBRARIES` interposition.
+//!
+//! It uses the same resolution core as the inception layer: paths are
+//! normalized into manifest keys and looked up in the workspace's VDir
+//! mapping (seqlock-protected, maintained by vDird), and file contents are
+//! served from the CAS blob named by the entry's hash.
+//!
+//! Rust callers use [`Resolver`]; C callers use the functions declared in
+//! `include/vrift.h` and link `libvrift_resolve.a`.
+
+mod ffi;
+
+use std::fs::File;
+use std::io;
+use std::os::fd::{AsRawFd, OwnedFd};
+use std::path::{Component, Path, PathBuf};
+use std::sync::RwLock;
+
+use vrift_ipc::vdir_types::{vdir_header_version, vdir_lookup, vdir_table_end, VDIR_HEADER_SIZE};
+
+pub use vrift_ipc::vdir_types::VDirStatResult as Entry;
+
+/// Read-only view of one workspace's virtual tree
+pub struct Resolver {
+    project_root: PathBuf,
+    cas_root: PathBuf,
+    vdir_path: PathBuf,
+    map: RwLock<VDirMap>,
+}
+
+impl Resolver {
+    /// Attach to the workspace rooted at `project_root`, using the VDir
+    /// vDird maintains for it and the configured CAS root
+    pub fn for_project(project_root: impl AsRef<Path>) -> io::Result<Self> {
+        let project_root = project_root.as_ref().canonicalize()?;
+        let project_id = vrift_config::path::compute_project_id(&project_root);
+        let vdir_path = vrift_config::path::get_vdir_mmap_path(&project_id)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no home directory"))?;
+        let cas_root = expand_home(vrift_config::config().cas_root());
+        Self::new(project_root, vdir_path, cas_root)
+    }
+
+    /// Attach to an explicit VDir file and CAS root
+    pub fn new(
+        project_root: impl Into<PathBuf>,
+        vdir_path: impl Into<PathBuf>,
+        cas_root: impl Into<PathBuf>,
+    ) -> io::Result<Self> {
+        let vdir_path = vdir_path.into();
+        Ok(Self {
+            map: RwLock::new(VDirMap::open(&vdir_path)?),
+            project_root: project_root.into(),
+            cas_root: cas_root.into(),
+            vdir_path,
+        })
+    }
+
+    pub fn project_root(&self) -> &Path {
+        &self.project_root
+    }
+
+    /// Manifest key for `path` (absolute, or relative to the project root).
+    /// Fails with `EXDEV` if the path lies outside the project.
+    pub fn manifest_key(&self, path: impl AsRef<Path>) -> io::Result<String> {
+        let path = path.as_ref();
+        let absolute = if path.is_absolute() {
+            normalize(path)
+        } else {
+            normalize(&self.project_root.join(path))
+        };
+        let relative = absolute
+            .strip_prefix(&self.project_root)
+            .map_err(|_| io::Error::from_raw_os_error(libc::EXDEV))?;
+        let relative = relative
+            .to_str()
+            .ok_or_else(|| io::Error::from_raw_os_error(libc::EINVAL))?;
+        Ok(format!("/{}", relative))
+    }
+
+    /// VDir entry for `path`; `ENOENT` if the VFS does not know it
+    pub fn lookup(&self, path: impl AsRef<Path>) -> io::Result<Entry> {
+        let key = self.manifest_key(path)?;
+        self.lookup_key(&key)
+    }
+
+    /// VDir entry for an already-resolved manifest key
+    pub fn lookup_key(&self, key: &str) -> io::Result<Entry> {
+        let found = {
+            let map = self.map.read().map_err(|_| lock_poisoned())?;
+            // SAFETY: the mapping stays alive while the read guard is held.
+            match unsafe { vdir_lookup(map.ptr, map.len, key) } {
+                Some(entry) => Some(entry),
+                None if map.is_outgrown() => None,
+                None => return Err(io::Error::from_raw_os_error(libc::ENOENT)),
+            }
+        };
+        let entry = match found {
+            Some(entry) => entry,
+            None => {
+                // vDird resized the table since we mapped it; remap and retry.
+                let mut map = self.map.write().map_err(|_| lock_poisoned())?;
+                if map.is_outgrown() {
+                    *map = VDirMap::open(&self.vdir_path)?;
+                }
+                // SAFETY: as above, under the write guard.
+                unsafe { vdir_lookup(map.ptr, map.len, key) }
+                    .ok_or_else(|| io::Error::from_raw_os_error(libc::ENOENT))?
+            }
+        };
+        if entry.flags & vrift_ipc::vdir_types::FLAG_DELETED != 0 || entry.is_whiteout() {
+            return Err(io::Error::from_raw_os_error(libc::ENOENT));
+        }
+        Ok(entry)
+    }
+
+    /// Open the file at `path` read-only from the CAS
+    pub fn open(&self, path: impl AsRef<Path>) -> io::Result<OwnedFd> {
+        let entry = self.lookup(path)?;
+        if entry.flags & vrift_ipc::vdir_types::FLAG_DIR != 0 {
+            return Err(io::Error::from_raw_os_error(libc::EISDIR));
+        }
+        if entry.flags & vrift_ipc::vdir_types::FLAG_DIRTY != 0 {
+            // The CAS still holds the last committed content.
+            return Err(io::Error::from_raw_os_error(libc::EAGAIN));
+        }
+        Ok(File::open(self.blob_path(&entry))?.into())
+    }
+
+    /// CAS location of an entry's content
+    pub fn blob_path(&self, entry: &Entry) -> PathBuf {
+        let hex: String = entry
+            .cas_hash
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        self.cas_root
+            .join("blake3")
+            .join(&hex[0..2])
+            .join(&hex[2..4])
+            .join(format!("{}_{}.bin", hex, entry.size))
+    }
+}
+
+/// Shared read-only mapping of a VDir file
+struct VDirMap {
+    ptr: *const u8,
+    len: usize,
+}
+
+// SAFETY: the mapping is read-only and only accessed through the seqlock
+// reader, which tolerates concurrent writers in other processes.
+unsafe impl Send for VDirMap {}
+unsafe impl Sync for VDirMap {}
+
+impl VDirMap {
+    fn open(path: &Path) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let len = file.metadata()?.len() as usize;
+        if len < VDIR_HEADER_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} is not a VDir", path.display()),
+            ));
+        }
+        // MAP_SHARED so updates from vDird are visible without remapping.
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ,
+                libc::MAP_SHARED,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        let map = Self {
+            ptr: ptr as *const u8,
+            len,
+        };
+        if unsafe { vdir_header_version(map.ptr, map.len) }.is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} has no VDir header", path.display()),
+            ));
+        }
+        Ok(map)
+    }
+
+    /// Whether vDird has grown the table past the end of this mapping
+    fn is_outgrown(&self) -> bool {
+        unsafe { vdir_table_end(self.ptr, self.len) }.is_some_and(|end| end > self.len)
+    }
+}
+
+impl Drop for VDirMap {
+    fn drop(&mut self) {
+        unsafe { libc::munmap(self.ptr as *mut libc::c_void, self.len) };
+    }
+}
+
+/// Lexical normalization (`.`, `..`, repeated separators); symlinks in the
+/// project tree are not followed, matching the inception layer.
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                out.pop();
+            }
+            Component::CurDir => {}
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), std::env::var_os("HOME")) {
+        (Ok(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+fn lock_poisoned() -> io::Error {
+    io::Error::other("resolver lock poisoned")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use vrift_ipc::vdir_types::{VDirEntry, FLAG_DIR};
+
+    fn entry(key: &str, content: &[u8], flags: u16) -> VDirEntry {
+        VDirEntry {
+            path_hash: vrift_ipc::fnv1a_hash(key),
+            // Any stable name will do: the resolver only builds a path from it.
+            cas_hash: [content.len() as u8; 32],
+            size: content.len() as u64,
+            mtime_sec: 1_700_000_000,
+            mode: if flags & FLAG_DIR != 0 {
+                0o040755
+            } else {
+                0o100644
+            },
+            flags,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_lookup_and_open() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().join("project");
+        let cas_root = tmp.path().join("cas");
+        let vdir_path = tmp.path().join("vdir.mmap");
+        std::fs::create_dir_all(&root).unwrap();
+
+        let file = entry("/src/main.rs", b"fn main() {}", 0);
+        let dir = entry("/src", b"", FLAG_DIR);
+        let mut vdir = vrift_vdird::vdir::VDir::create_or_open(&vdir_path).unwrap();
+        vdir.upsert(file).unwrap();
+        vdir.upsert(dir).unwrap();
+
+        let resolver = Resolver::new(&root, &vdir_path, &cas_root).unwrap();
+        let found = resolver.lookup(root.join("src/./main.rs")).unwrap();
+        assert_eq!(found.size, 12);
+
+        let blob = resolver.blob_path(&found);
+        std::fs::create_dir_all(blob.parent().unwrap()).unwrap();
+        std::fs::write(&blob, b"fn main() {}").unwrap();
+
+        let mut content = String::new();
+        File::from(resolver.open("src/main.rs").unwrap())
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "fn main() {}");
+
+        let errno = |e: io::Error| e.raw_os_error();
+        assert_eq!(
+            resolver.open("src").map(drop).map_err(errno),
+            Err(Some(libc::EISDIR))
+        );
+        assert_eq!(
+            resolver.lookup("src/missing.rs").map(drop).map_err(errno),
+            Err(Some(libc::ENOENT))
+        );
+        assert_eq!(
+            resolver.lookup("/etc/passwd").map(drop).map_err(errno),
+            Err(Some(libc::EXDEV))
+        );
+    }
+}
@@ -12,6 +12,7 @@ use std::path::{Path, PathBuf};
 
 use thiserror::Error;
 use vrift_cas::CasStore;
+use vrift_error::{Classify, ErrorKind};
 use vrift_manifest::Manifest;
 
 #[derive(Error, Debug)]
@@ -31,6 +32,23 @@ pub enum RuntimeError {
 
     #[error("OverlayFS error: {0}")]
     Overlay(String),
+
+    #[error("Blob for {0} is not stored raw (compressed or encrypted)")]
+    BlobNotRaw(String),
+}
+
+impl Classify for RuntimeError {
+    fn classify(&self) -> ErrorKind {
+        match self {
+            RuntimeError::Io(e) => e.classify(),
+            RuntimeError::Cas(e) => e.classify(),
+            RuntimeError::BlobNotFound(_) => ErrorKind::NotFound,
+            #[cfg(target_os = "linux")]
+            RuntimeError::Nix(errno) => std::io::Error::from(*errno).classify(),
+            RuntimeError::Overlay(_) => ErrorKind::Internal,
+            RuntimeError::BlobNotRaw(_) => ErrorKind::Unavailable,
+        }
+    }
 }
 
 pub type Result<T> = std::result::Result<T, RuntimeError>;
@@ -70,7 +88,7 @@ impl LinkFarm {
 
                 // Construct full destination path
                 let relative_path = path_str.trim_start_matches('/');
-                let dest_path = target.join(relative_path);
+                let dest_path = target.join(vrift_cas::path_key::to_path(relative_path));
 
                 if entry.is_dir() {
                     fs::create_dir_all(&dest_path)?;
@@ -82,7 +100,20 @@ impl LinkFarm {
                     fs::create_dir_all(parent)?;
                 }
 
-                if entry.is_file() {
+                if let Some(content) = entry.inline_content() {
+                    // No blob to link: the entry carries the bytes
+                    if dest_path.exists() {
+                        fs::remove_file(&dest_path)?;
+                    }
+                    fs::write(&dest_path, content)?;
+                    use std::os::unix::fs::PermissionsExt;
+                    fs::set_permissions(&dest_path, fs::Permissions::from_mode(entry.mode))?;
+                } else if entry.is_file() {
+                    // A hard link would expose the encoded bytes
+                    if !entry.is_blob_raw() {
+                        return Err(RuntimeError::BlobNotRaw(path_str.to_string()));
+                    }
+
                     // Find source blob in CAS
                     let src_path = self
                         .cas
@@ -394,6 +425,30 @@ mod tests {
         assert!(log_path.is_dir());
     }
 
+    #[test]
+    fn test_link_farm_storage_flags() {
+        let temp = TempDir::new().unwrap();
+        let cas = CasStore::new(temp.path().join("cas")).unwrap();
+        let farm = LinkFarm::new(cas);
+
+        let mut manifest = Manifest::new();
+        manifest.insert(
+            "/small.txt",
+            VnodeEntry::new_inline(b"tiny", 0, 0o600).unwrap(),
+        );
+        farm.populate(&[manifest], &temp.path().join("a")).unwrap();
+        assert_eq!(fs::read(temp.path().join("a/small.txt")).unwrap(), b"tiny");
+
+        let mut compressed = VnodeEntry::new_file([7u8; 32], 100, 0, 0o644);
+        compressed.flags |= VnodeEntry::FLAG_COMPRESSED;
+        let mut manifest = Manifest::new();
+        manifest.insert("/big.bin", compressed);
+        let err = farm
+            .populate(&[manifest], &temp.path().join("b"))
+            .unwrap_err();
+        assert!(matches!(err, RuntimeError::BlobNotRaw(_)), "{}", err);
+    }
+
     #[test]
     fn test_link_farm_merge() {
         let temp = TempDir::new().unwrap();
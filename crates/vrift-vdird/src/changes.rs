@@ -0,0 +1,189 @@
+//! Manifest change feed
+//!
+//! Bounded in-memory log of manifest mutations. Clients (the inception layer's
+//! inotify emulation) poll it with `ManifestChangesSince` to learn which VFS
+//! paths changed, since writes committed through CoW reingest never touch the
+//! real file the kernel is watching. Connections subscribed to
+//! `EventKind::ManifestChange` get each change pushed as it is recorded.
+
+use std::collections::VecDeque;
+use tokio::sync::broadcast;
+use vrift_ipc::{ManifestChange, ManifestChangeKind};
+
+/// Default number of changes retained before the oldest are evicted
+pub const DEFAULT_CHANGE_LOG_CAPACITY: usize = 4096;
+
+/// Maximum number of changes returned by a single poll
+const MAX_CHANGES_PER_POLL: usize = 512;
+
+/// Changes a subscriber may fall behind by before it is told it lagged
+const SUBSCRIBER_BACKLOG: usize = 1024;
+
+/// Ring buffer of recent manifest changes with monotonic sequence numbers
+pub struct ChangeLog {
+    entries: VecDeque<ManifestChange>,
+    capacity: usize,
+    /// Sequence number assigned to the next recorded change (starts at 1)
+    next_seq: u64,
+    subscribers: broadcast::Sender<ManifestChange>,
+}
+
+impl Default for ChangeLog {
+    fn default() -> Self {
+        Self::new(DEFAULT_CHANGE_LOG_CAPACITY)
+    }
+}
+
+impl ChangeLog {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity.min(DEFAULT_CHANGE_LOG_CAPACITY)),
+            capacity: capacity.max(1),
+            next_seq: 1,
+            subscribers: broadcast::channel(SUBSCRIBER_BACKLOG).0,
+        }
+    }
+
+    /// Receive every change recorded from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<ManifestChange> {
+        self.subscribers.subscribe()
+    }
+
+    /// Record a single change and return its sequence number
+    pub fn record(&mut self, kind: ManifestChangeKind, path: &str, is_dir: bool) -> u64 {
+        self.push(kind, path, is_dir, 0)
+    }
+
+    /// Record both halves of a rename, sharing a cookie
+    pub fn record_rename(&mut self, old_path: &str, new_path: &str, is_dir: bool) {
+        // Cookie only needs to be unique among in-flight renames; the low
+        // bits of the sequence number are good enough (and never zero).
+        let cookie = (self.next_seq as u32).max(1);
+        self.push(ManifestChangeKind::MovedFrom, old_path, is_dir, cookie);
+        self.push(ManifestChangeKind::MovedTo, new_path, is_dir, cookie);
+    }
+
+    fn push(&mut self, kind: ManifestChangeKind, path: &str, is_dir: bool, cookie: u32) -> u64 {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        let change = ManifestChange {
+            seq,
+            kind,
+            path: path.to_string(),
+            is_dir,
+            cookie,
+        };
+        if self.subscribers.receiver_count() > 0 {
+            let _ = self.subscribers.send(change.clone());
+        }
+        self.entries.push_back(change);
+        seq
+    }
+
+    /// Cursor pointing just past the most recent change
+    pub fn head(&self) -> u64 {
+        self.next_seq
+    }
+
+    /// Return changes with `seq >= cursor`, the cursor for the next poll, and
+    /// whether changes between `cursor` and the oldest retained entry were lost.
+    ///
+    /// `u64::MAX` is treated as "start from now": no changes, current head.
+    pub fn since(&self, cursor: u64) -> (Vec<ManifestChange>, u64, bool) {
+        if cursor == u64::MAX || cursor >= self.next_seq {
+            return (Vec::new(), self.next_seq, false);
+        }
+
+        let oldest = self.entries.front().map(|c| c.seq).unwrap_or(self.next_seq);
+        let truncated = cursor < oldest;
+
+        let changes: Vec<ManifestChange> = self
+            .entries
+            .iter()
+            .filter(|c| c.seq >= cursor)
+            .take(MAX_CHANGES_PER_POLL)
+            .cloned()
+            .collect();
+        let next = changes.last().map(|c| c.seq + 1).unwrap_or(self.next_seq);
+
+        (changes, next, truncated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_since_returns_changes_in_order() {
+        let mut log = ChangeLog::default();
+        let start = log.head();
+        log.record(ManifestChangeKind::Created, "/src/a.rs", false);
+        log.record(ManifestChangeKind::Modified, "/src/a.rs", false);
+
+        let (changes, next, truncated) = log.since(start);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].kind, ManifestChangeKind::Created);
+        assert_eq!(changes[1].kind, ManifestChangeKind::Modified);
+        assert_eq!(next, log.head());
+        assert!(!truncated);
+
+        let (changes, _, _) = log.since(next);
+        assert!(changes.is_empty());
+    }
+
+    #[test]
+    fn test_max_cursor_returns_head_only() {
+        let mut log = ChangeLog::default();
+        log.record(ManifestChangeKind::Created, "/a", false);
+        let (changes, next, truncated) = log.since(u64::MAX);
+        assert!(changes.is_empty());
+        assert_eq!(next, log.head());
+        assert!(!truncated);
+    }
+
+    #[test]
+    fn test_rename_pairs_share_cookie() {
+        let mut log = ChangeLog::default();
+        log.record_rename("/old", "/new", false);
+        let (changes, _, _) = log.since(1);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].kind, ManifestChangeKind::MovedFrom);
+        assert_eq!(changes[1].kind, ManifestChangeKind::MovedTo);
+        assert_ne!(changes[0].cookie, 0);
+        assert_eq!(changes[0].cookie, changes[1].cookie);
+    }
+
+    #[test]
+    fn test_subscribers_receive_new_changes() {
+        let mut log = ChangeLog::default();
+        log.record(ManifestChangeKind::Created, "/before", false);
+        let mut rx = log.subscribe();
+        log.record_rename("/old", "/new", false);
+
+        let from = rx.try_recv().unwrap();
+        let to = rx.try_recv().unwrap();
+        assert_eq!(
+            (from.kind, to.kind),
+            (ManifestChangeKind::MovedFrom, ManifestChangeKind::MovedTo)
+        );
+        assert_eq!(to.path, "/new");
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_eviction_reports_truncation() {
+        let mut log = ChangeLog::new(2);
+        log.record(ManifestChangeKind::Created, "/a", false);
+        log.record(ManifestChangeKind::Created, "/b", false);
+        log.record(ManifestChangeKind::Created, "/c", false);
+
+        let (changes, _, truncated) = log.since(1);
+        assert!(truncated);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].path, "/b");
+    }
+}
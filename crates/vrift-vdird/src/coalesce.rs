@@ -0,0 +1,323 @@
+//! Coalescing of reingests
+//!
+//! Ninja and cargo rewrite some files several times within one build, and
+//! every close of a CoW copy sends a `ManifestReingest`. Hashing each of them
+//! only to replace the blob a few milliseconds later wastes CAS work.
+//!
+//! A reingest therefore waits `ingest.dedup_window_ms` before it is hashed.
+//! If another reingest of the same path arrives meanwhile, the older staged
+//! file is deleted and the wait starts over for the newer one; every request
+//! of the burst is acked once the last write has been committed. The wait
+//! happens outside the handler lock, so other requests keep being served.
+//!
+//! Within one connection requests still run in order, so coalescing only
+//! merges writes arriving over different connections (the shims of different
+//! processes).
+//!
+//! A checked reingest (one whose content depends on the content it was
+//! copied from) never replaces a pending write: it was copied before that
+//! write was committed, so the pending write is committed right away and the
+//! newer one is checked against it as a possible conflict.
+
+use crate::commands::CommandHandler;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::{oneshot, RwLock};
+use tracing::debug;
+use vrift_ipc::{VeloError, VeloRequest, VeloResponse, VnodeEntry};
+
+/// Reingest counters since vDird started
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CoalesceStats {
+    /// Reingests hashed into the CAS
+    pub committed: u64,
+    /// Reingests superseded by a newer write of the same path
+    pub coalesced: u64,
+    /// Paths waiting for their writes to quiesce
+    pub pending: u64,
+}
+
+/// Newest staged write of a path and everyone waiting for it
+struct Pending {
+    temp_path: String,
+    /// Content the newest write was copied from, if the shim sent it
+    base_hash: Option<[u8; 32]>,
+    generation: u64,
+    waiters: Vec<oneshot::Sender<VeloResponse>>,
+}
+
+/// Delays reingests until writes of a path quiesce
+pub struct Coalescer {
+    window: Duration,
+    pending: Mutex<HashMap<String, Pending>>,
+    next_generation: AtomicU64,
+    committed: AtomicU64,
+    coalesced: AtomicU64,
+}
+
+impl Coalescer {
+    /// A zero `window` hashes every reingest right away
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            pending: Mutex::new(HashMap::new()),
+            next_generation: AtomicU64::new(0),
+            committed: AtomicU64::new(0),
+            coalesced: AtomicU64::new(0),
+        }
+    }
+
+    pub fn stats(&self) -> CoalesceStats {
+        CoalesceStats {
+            committed: self.committed.load(Ordering::Relaxed),
+            coalesced: self.coalesced.load(Ordering::Relaxed),
+            pending: self.lock_pending().len() as u64,
+        }
+    }
+
+    /// Reingest `temp_path` as `vpath` once no newer write of `vpath` has
+    /// arrived for a full window
+    pub async fn reingest(
+        &self,
+        handler: &RwLock<CommandHandler>,
+        vpath: String,
+        temp_path: String,
+        base_hash: Option<[u8; 32]>,
+    ) -> VeloResponse {
+        if self.window.is_zero() {
+            return self.commit(handler, vpath, temp_path, base_hash).await;
+        }
+
+        let (tx, rx) = oneshot::channel();
+        let generation = self.next_generation.fetch_add(1, Ordering::Relaxed);
+        let overtaken = {
+            let mut pending = self.lock_pending();
+            match pending.get_mut(&vpath) {
+                Some(job) if base_hash.is_none() => {
+                    let superseded = std::mem::replace(&mut job.temp_path, temp_path);
+                    job.base_hash = None;
+                    job.generation = generation;
+                    job.waiters.push(tx);
+                    let _ = std::fs::remove_file(&superseded);
+                    self.coalesced.fetch_add(1, Ordering::Relaxed);
+                    debug!(vpath = %vpath, superseded = %superseded, "Reingest coalesced");
+                    None
+                }
+                // A checked write never replaces a pending one
+                _ => pending.insert(
+                    vpath.clone(),
+                    Pending {
+                        temp_path,
+                        base_hash,
+                        generation,
+                        waiters: vec![tx],
+                    },
+                ),
+            }
+        };
+        if let Some(job) = overtaken {
+            debug!(vpath = %vpath, "Pending reingest committed ahead of a checked one");
+            self.finish(handler, vpath.clone(), job).await;
+        }
+
+        tokio::time::sleep(self.window).await;
+
+        // Only the newest write of the burst commits; the others wait for it
+        let job = {
+            let mut pending = self.lock_pending();
+            match pending.get(&vpath) {
+                Some(job) if job.generation == generation => pending.remove(&vpath),
+                _ => None,
+            }
+        };
+        if let Some(job) = job {
+            self.finish(handler, vpath, job).await;
+        }
+
+        rx.await.unwrap_or_else(|_| {
+            VeloResponse::Error(VeloError::internal("Coalesced reingest was dropped"))
+        })
+    }
+
+    /// `vpath` was replaced by other means (a rename over it): drop its
+    /// pending write and answer its waiters with `entry`
+    pub fn supersede(&self, vpath: &str, entry: &VnodeEntry) {
+        let Some(job) = self.lock_pending().remove(vpath) else {
+            return;
+        };
+        let _ = std::fs::remove_file(&job.temp_path);
+        self.coalesced.fetch_add(1, Ordering::Relaxed);
+        debug!(vpath = %vpath, superseded = %job.temp_path, "Pending reingest superseded");
+        for waiter in job.waiters {
+            let _ = waiter.send(VeloResponse::ManifestAck {
+                entry: Some(entry.clone()),
+            });
+        }
+    }
+
+    /// Commit `job` and answer everyone waiting for it
+    async fn finish(&self, handler: &RwLock<CommandHandler>, vpath: String, job: Pending) {
+        let response = self
+            .commit(handler, vpath, job.temp_path, job.base_hash)
+            .await;
+        for waiter in job.waiters {
+            let _ = waiter.send(duplicate(&response));
+        }
+    }
+
+    async fn commit(
+        &self,
+        handler: &RwLock<CommandHandler>,
+        vpath: String,
+        temp_path: String,
+        base_hash: Option<[u8; 32]>,
+    ) -> VeloResponse {
+        let request = match base_hash {
+            Some(base_hash) => VeloRequest::ManifestReingestChecked {
+                vpath,
+                temp_path,
+                base_hash,
+            },
+            None => VeloRequest::ManifestReingest { vpath, temp_path },
+        };
+        let response = handler.write().await.handle_request(request).await;
+        self.committed.fetch_add(1, Ordering::Relaxed);
+        response
+    }
+
+    fn lock_pending(&self) -> std::sync::MutexGuard<'_, HashMap<String, Pending>> {
+        self.pending.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Copy of a reingest response for each request of a burst
+fn duplicate(response: &VeloResponse) -> VeloResponse {
+    match response {
+        VeloResponse::ManifestAck { entry } => VeloResponse::ManifestAck {
+            entry: entry.clone(),
+        },
+        VeloResponse::Error(e) => VeloResponse::Error(e.clone()),
+        other => VeloResponse::Error(VeloError::internal(format!(
+            "Unexpected reingest response: {:?}",
+            other
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vdir::VDir;
+    use crate::ProjectConfig;
+    use std::sync::Arc;
+    use tempfile::tempdir;
+
+    fn handler(root: &std::path::Path) -> RwLock<CommandHandler> {
+        let mut config = ProjectConfig::from_project_root(root.to_path_buf());
+        config.cas_path = root.join("cas");
+        let vdir = VDir::create_or_open(&root.join("test.vdir")).unwrap();
+        let manifest =
+            Arc::new(vrift_manifest::lmdb::LmdbManifest::open(root.join("manifest.lmdb")).unwrap());
+        RwLock::new(CommandHandler::new(config, vdir, manifest))
+    }
+
+    fn stage(root: &std::path::Path, name: &str, content: &[u8]) -> String {
+        let path = root.join("staging").join(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, content).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_burst_commits_only_last_write() {
+        let temp = tempdir().unwrap();
+        let handler = handler(temp.path());
+        let coalescer = Coalescer::new(Duration::from_millis(100));
+
+        let first = stage(temp.path(), "1.tmp", b"first");
+        let second = stage(temp.path(), "2.tmp", b"second!");
+        let (a, b) = tokio::join!(
+            coalescer.reingest(&handler, "/out.o".to_string(), first.clone(), None),
+            async {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                coalescer
+                    .reingest(&handler, "/out.o".to_string(), second, None)
+                    .await
+            }
+        );
+
+        for response in [a, b] {
+            match response {
+                VeloResponse::ManifestAck { entry: Some(e) } => {
+                    assert_eq!(e.content_hash, *blake3::hash(b"second!").as_bytes());
+                }
+                other => panic!("Expected ManifestAck, got {:?}", other),
+            }
+        }
+        assert!(!std::path::Path::new(&first).exists());
+        assert_eq!(
+            coalescer.stats(),
+            CoalesceStats {
+                committed: 1,
+                coalesced: 1,
+                pending: 0,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_checked_write_does_not_supersede_pending_one() {
+        let temp = tempdir().unwrap();
+        let handler = handler(temp.path());
+        let coalescer = Coalescer::new(Duration::from_millis(100));
+        let base = stage(temp.path(), "0.tmp", b"base");
+        coalescer
+            .reingest(&handler, "/lib.rs".to_string(), base, None)
+            .await;
+
+        // Both copied `base`; the second arrives while the first is pending
+        let base_hash = Some(*blake3::hash(b"base").as_bytes());
+        let first = stage(temp.path(), "1.tmp", b"first");
+        let second = stage(temp.path(), "2.tmp", b"second");
+        let (a, b) = tokio::join!(
+            coalescer.reingest(&handler, "/lib.rs".to_string(), first, base_hash),
+            async {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                coalescer
+                    .reingest(&handler, "/lib.rs".to_string(), second, base_hash)
+                    .await
+            }
+        );
+
+        match a {
+            VeloResponse::ManifestAck { entry: Some(e) } => {
+                assert_eq!(e.content_hash, *blake3::hash(b"first").as_bytes());
+            }
+            other => panic!("Expected ManifestAck, got {:?}", other),
+        }
+        match b {
+            VeloResponse::Error(e) => assert_eq!(e.kind, vrift_ipc::VeloErrorKind::Conflict),
+            other => panic!("Expected a conflict, got {:?}", other),
+        }
+        assert_eq!(coalescer.stats().coalesced, 0);
+    }
+
+    #[tokio::test]
+    async fn test_zero_window_commits_every_write() {
+        let temp = tempdir().unwrap();
+        let handler = handler(temp.path());
+        let coalescer = Coalescer::new(Duration::ZERO);
+
+        for (i, content) in [b"a", b"b"].iter().enumerate() {
+            let staged = stage(temp.path(), &format!("{}.tmp", i), *content);
+            let response = coalescer
+                .reingest(&handler, "/x".to_string(), staged, None)
+                .await;
+            assert!(matches!(response, VeloResponse::ManifestAck { .. }));
+        }
+        assert_eq!(coalescer.stats().committed, 2);
+        assert_eq!(coalescer.stats().coalesced, 0);
+    }
+}
@@ -1,14 +1,22 @@
 //! Command handlers for vdir_d
 
-use crate::vdir::{fnv1a_hash, VDir, VDirEntry, FLAG_DIR};
+use crate::changes::ChangeLog;
+use crate::coalesce::Coalescer;
+use crate::hot_writes::{self, HotWrites};
+use crate::metrics::{Lookup, Metrics};
+use crate::vdir::{
+    fnv1a_hash, vdir_path_check, VDir, VDirEntry, FLAG_DIR, FLAG_PASSTHROUGH, FLAG_WHITEOUT,
+};
 use crate::ProjectConfig;
 use anyhow::Result;
 use std::fs;
 use std::os::unix::fs::MetadataExt;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 use tracing::{debug, error, info, warn};
+use vrift_error::Classify;
 use vrift_ipc::{
-    VeloError, VeloErrorKind, VeloRequest, VeloResponse, VnodeEntry, PROTOCOL_VERSION,
+    IngestBlob, ManifestChangeKind, VeloError, VeloErrorKind, VeloRequest, VeloResponse,
+    VnodeEntry, PROTOCOL_VERSION,
 };
 
 /// Command handler for vdir_d
@@ -16,6 +24,124 @@ pub struct CommandHandler {
     config: ProjectConfig,
     vdir: VDir,
     manifest: std::sync::Arc<vrift_manifest::lmdb::LmdbManifest>,
+    /// Recent manifest mutations, polled by VFS watchers
+    changes: ChangeLog,
+    /// CoW break counts and copied-up paths
+    hot_writes: HotWrites,
+    /// Reingests waiting for their writes to quiesce
+    coalescer: std::sync::Arc<Coalescer>,
+    /// Commit the losing write of a conflict next to its path
+    keep_conflicts: bool,
+    /// Request and lookup counters for `vrift top`
+    metrics: std::sync::Arc<Metrics>,
+}
+
+/// VDir slot for the manifest entry of `path`, keeping the full-precision
+/// mtime
+fn vdir_entry_from_vnode(path: &str, vnode: &VnodeEntry, ingested_at: i64) -> VDirEntry {
+    let mut entry = VDirEntry {
+        cas_hash: vnode.content_hash,
+        size: vnode.size,
+        mode: vnode.mode,
+        flags: vnode.flags,
+        ..Default::default()
+    };
+    entry.set_path(path);
+    entry.set_mtime_ns(vnode.mtime);
+    entry.set_ingest_ns(ingested_at);
+    entry
+}
+
+/// Run an `IngestFullScan`. It needs no handler state beyond the default
+/// CAS root, and a scan can take minutes, so socket lanes run it on a
+/// blocking thread without holding the handler lock.
+pub fn ingest_full_scan(default_cas_path: &Path, request: VeloRequest) -> VeloResponse {
+    let VeloRequest::IngestFullScan {
+        path,
+        manifest_path,
+        threads,
+        phantom,
+        tier1,
+        prefix,
+        cas_root,
+        force_hash: _,
+        dry_run,
+        selection,
+    } = request
+    else {
+        return VeloResponse::Error(VeloError::internal(format!(
+            "{} is not a full scan",
+            request.kind()
+        )));
+    };
+    if dry_run {
+        return VeloResponse::Error(VeloError::internal("Ingest dry runs are planned by vriftd"));
+    }
+    let selection = selection.map(|s| *s).unwrap_or_default();
+    let mut filter = match vrift_cas::IngestFilter::new(
+        Path::new(&path),
+        &selection.paths,
+        selection.include,
+        selection.exclude,
+    ) {
+        Ok(filter) => filter,
+        Err(outside) => {
+            return VeloResponse::Error(VeloError::with_path(
+                VeloErrorKind::InvalidPath,
+                "Path is outside the ingested directory",
+                outside,
+            ))
+        }
+    };
+    let skip = &vrift_config::config().ingest.skip;
+    filter.skip = vrift_cas::SkipPolicy {
+        max_size: (skip.max_file_bytes > 0).then_some(skip.max_file_bytes),
+        binary: skip.binary,
+        ..Default::default()
+    };
+    CommandHandler::handle_ingest_full_scan(
+        default_cas_path,
+        &path,
+        &manifest_path,
+        threads,
+        phantom,
+        tier1,
+        prefix.as_deref(),
+        cas_root.as_deref(),
+        &filter,
+    )
+}
+
+/// Where the losing write of a conflict on `vpath` is kept
+fn conflict_path(vpath: &str, hash: &[u8; 32]) -> String {
+    format!("{}.conflict-{}", vpath, &hex::encode(hash)[..8])
+}
+
+/// Entry that hides `entry`'s path from readers while keeping its slot
+fn whiteout(entry: VDirEntry) -> VDirEntry {
+    VDirEntry {
+        flags: entry.flags | FLAG_WHITEOUT,
+        ..entry
+    }
+}
+
+/// Replace `real` with a copy of `blob`, via a temporary sibling
+fn write_real_file(blob: &Path, real: &Path, mode: u32) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    if let Some(parent) = real.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut name = real.file_name().unwrap_or_default().to_os_string();
+    name.push(".vrift-copyup");
+    let temp = real.with_file_name(name);
+    let result = fs::copy(blob, &temp)
+        .and_then(|_| fs::set_permissions(&temp, fs::Permissions::from_mode(mode & 0o7777)))
+        .and_then(|_| fs::rename(&temp, real));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
 }
 
 impl CommandHandler {
@@ -24,31 +150,51 @@ impl CommandHandler {
         vdir: VDir,
         manifest: std::sync::Arc<vrift_manifest::lmdb::LmdbManifest>,
     ) -> Self {
-        Self {
+        let (breaks, window_secs, dedup_window_ms, keep_conflicts) = {
+            let cfg = vrift_config::config();
+            (
+                cfg.ingest.hot_write_breaks,
+                cfg.ingest.hot_write_window_secs,
+                cfg.ingest.dedup_window_ms,
+                cfg.ingest.keep_conflicts,
+            )
+        };
+        let hot_writes = HotWrites::load(
+            hot_writes::report_path(&config.project_root),
+            breaks,
+            window_secs,
+        );
+        let mut handler = Self {
             config,
             vdir,
             manifest,
-        }
+            changes: ChangeLog::default(),
+            hot_writes,
+            coalescer: std::sync::Arc::new(Coalescer::new(std::time::Duration::from_millis(
+                dedup_window_ms,
+            ))),
+            keep_conflicts,
+            metrics: std::sync::Arc::default(),
+        };
+        handler.reapply_promotions();
+        handler.reload_pack();
+        handler
+    }
+
+    /// Coalescer the socket lanes route `ManifestReingest` through
+    pub fn coalescer(&self) -> std::sync::Arc<Coalescer> {
+        std::sync::Arc::clone(&self.coalescer)
+    }
+
+    /// Counters the socket lanes count requests in
+    pub fn metrics(&self) -> std::sync::Arc<Metrics> {
+        std::sync::Arc::clone(&self.metrics)
     }
 
     /// Handle incoming request
     pub async fn handle_request(&mut self, request: VeloRequest) -> VeloResponse {
         match request {
-            VeloRequest::Handshake {
-                client_version,
-                protocol_version,
-            } => {
-                info!(client_version = %client_version, protocol_version, "Handshake");
-                VeloResponse::HandshakeAck {
-                    server_version: env!("CARGO_PKG_VERSION").to_string(),
-                    protocol_version: PROTOCOL_VERSION,
-                    compatible: vrift_ipc::is_version_compatible(protocol_version),
-                }
-            }
-
-            VeloRequest::Status => VeloResponse::StatusAck {
-                status: "ready".to_string(),
-            },
+            request if request.is_control() => self.handle_query(request),
 
             VeloRequest::RegisterWorkspace { project_root } => {
                 info!(project_root = %project_root, "Workspace registered");
@@ -59,8 +205,6 @@ impl CommandHandler {
                 }
             }
 
-            VeloRequest::ManifestGet { path } => self.handle_manifest_get(&path),
-
             VeloRequest::ManifestUpsert { path, entry } => {
                 self.handle_manifest_upsert(&path, entry)
             }
@@ -75,32 +219,34 @@ impl CommandHandler {
                 self.handle_manifest_update_mtime(&path, mtime_ns)
             }
 
-            VeloRequest::ManifestListDir { path } => self.handle_manifest_list_dir(&path),
-
             VeloRequest::ManifestReingest { vpath, temp_path } => {
-                self.handle_reingest(&vpath, &temp_path).await
+                self.handle_reingest(&vpath, &temp_path, None).await
             }
 
-            VeloRequest::IngestFullScan {
-                path,
-                manifest_path,
-                threads,
-                phantom,
-                tier1,
-                prefix,
-                cas_root,
-                force_hash: _,
+            VeloRequest::ManifestReingestChecked {
+                vpath,
+                temp_path,
+                base_hash,
             } => {
-                self.handle_ingest_full_scan(
-                    &path,
-                    &manifest_path,
-                    threads,
-                    phantom,
-                    tier1,
-                    prefix.as_deref(),
-                    cas_root.as_deref(),
-                )
-                .await
+                self.handle_reingest(&vpath, &temp_path, Some(base_hash))
+                    .await
+            }
+
+            request @ VeloRequest::IngestFullScan { .. } => {
+                ingest_full_scan(&self.config.cas_path, request)
+            }
+
+            VeloRequest::VDirNegotiate { max_version } => self.handle_vdir_negotiate(max_version),
+
+            VeloRequest::ManifestRenameOver { old_path, new_path } => {
+                self.handle_manifest_rename_over(&old_path, &new_path)
+            }
+
+            VeloRequest::PackReload => {
+                self.reload_pack();
+                VeloResponse::StatusAck {
+                    status: "ok".to_string(),
+                }
             }
 
             // Not yet implemented - forward to future handlers
@@ -111,59 +257,175 @@ impl CommandHandler {
         }
     }
 
+    /// Handle a read-only query (see [`VeloRequest::is_control`]). Needs only
+    /// a shared borrow, so queries run side by side under the handler's read
+    /// lock.
+    pub fn handle_query(&self, request: VeloRequest) -> VeloResponse {
+        match request {
+            VeloRequest::Handshake {
+                client_version,
+                protocol_version,
+            } => {
+                info!(client_version = %client_version, protocol_version, "Handshake");
+                VeloResponse::HandshakeAck {
+                    server_version: env!("CARGO_PKG_VERSION").to_string(),
+                    protocol_version: PROTOCOL_VERSION,
+                    compatible: vrift_ipc::is_version_compatible(protocol_version),
+                }
+            }
+
+            VeloRequest::Status => VeloResponse::StatusAck {
+                status: "ready".to_string(),
+            },
+
+            VeloRequest::ManifestGet { path } => self.handle_manifest_get(&path),
+
+            VeloRequest::ManifestGetMany { paths } => VeloResponse::ManifestManyAck {
+                entries: paths.iter().map(|path| self.manifest_get(path)).collect(),
+            },
+
+            VeloRequest::ManifestListDir { path } => self.handle_manifest_list_dir(&path),
+
+            VeloRequest::ManifestChangesSince { cursor } => {
+                let (changes, cursor, truncated) = self.changes.since(cursor);
+                VeloResponse::ManifestChanges {
+                    cursor,
+                    changes,
+                    truncated,
+                }
+            }
+
+            VeloRequest::ReingestStats => {
+                let stats = self.coalescer.stats();
+                VeloResponse::ReingestStatsAck {
+                    committed: stats.committed,
+                    coalesced: stats.coalesced,
+                    pending: stats.pending,
+                }
+            }
+
+            VeloRequest::Metrics => VeloResponse::MetricsAck {
+                metrics: self.metrics.snapshot(),
+            },
+
+            request => VeloResponse::Error(VeloError::internal(format!(
+                "{} is not a query",
+                request.kind()
+            ))),
+        }
+    }
+
+    /// Push feed of manifest changes recorded from now on
+    pub fn subscribe_changes(&self) -> tokio::sync::broadcast::Receiver<vrift_ipc::ManifestChange> {
+        self.changes.subscribe()
+    }
+
+    /// Default CAS root of this workspace
+    pub fn cas_path(&self) -> &Path {
+        &self.config.cas_path
+    }
+
+    /// Downgrade the VDir to the newest version the shim can read. Never
+    /// upgrades: another, older shim may already have negotiated us down.
+    fn handle_vdir_negotiate(&mut self, max_version: u32) -> VeloResponse {
+        let current = self.vdir.version();
+        let target = max_version.min(current);
+        if target < current {
+            if let Err(e) = self.vdir.set_version(target) {
+                warn!(max_version, current, error = %e, "VDir negotiation failed");
+                return VeloResponse::Error(VeloError::internal(format!(
+                    "cannot emit VDir version {}: {}",
+                    max_version, e
+                )));
+            }
+        }
+        debug!(max_version, version = target, "VDir version negotiated");
+        VeloResponse::VDirNegotiateAck { version: target }
+    }
+
+    /// Whether a path is currently known (VDir overlay or LMDB base)
+    fn path_exists(&self, path: &str) -> bool {
+        self.lookup_entry(path).is_some()
+    }
+
+    /// Current entry of a path: the VDir overlay, then the LMDB base. A VDir
+    /// whiteout hides the LMDB entry.
+    fn lookup_entry(&self, path: &str) -> Option<VDirEntry> {
+        if let Some(entry) = self.vdir.lookup_path(path) {
+            return (!entry.is_whiteout()).then_some(entry);
+        }
+        match self.manifest.get(path) {
+            Ok(Some(lmdb_entry)) => Some(vdir_entry_from_vnode(
+                path,
+                &lmdb_entry.vnode,
+                lmdb_entry.ingested_at,
+            )),
+            _ => None,
+        }
+    }
+
     /// Handle ManifestGet
-    /// First checks VDir (runtime overlay for COW), then falls back to LMDB (persistent storage)
     fn handle_manifest_get(&self, path: &str) -> VeloResponse {
-        let path_hash = fnv1a_hash(path);
+        VeloResponse::ManifestAck {
+            entry: self.manifest_get(path),
+        }
+    }
 
+    /// Entry of a path as ManifestGet reports it
+    /// First checks VDir (runtime overlay for COW), then falls back to LMDB (persistent storage)
+    fn manifest_get(&self, path: &str) -> Option<VnodeEntry> {
         // 1. First check VDir (runtime overlay for COW mutations)
-        if let Some(entry) = self.vdir.lookup(path_hash) {
-            let vnode = VnodeEntry {
+        if let Some(entry) = self.vdir.lookup_path(path) {
+            if entry.is_passthrough() || entry.is_whiteout() {
+                // Copied up (the real file is authoritative) or renamed away
+                self.metrics.record_lookup(path, Lookup::Miss);
+                return None;
+            }
+            self.metrics.record_lookup(path, Lookup::Vdir);
+            return Some(VnodeEntry {
                 content_hash: entry.cas_hash,
                 size: entry.size,
-                mtime: entry.mtime_sec as u64,
+                mtime: entry.mtime_ns(),
                 mode: entry.mode,
                 flags: entry.flags,
                 _pad: 0,
-            };
-            return VeloResponse::ManifestAck { entry: Some(vnode) };
+            });
         }
 
         // 2. Fallback to LMDB (persistent storage)
         match self.manifest.get(path) {
             Ok(Some(entry)) => {
                 debug!(path = %path, "ManifestGet: found in LMDB");
-                VeloResponse::ManifestAck {
-                    entry: Some(entry.vnode),
-                }
+                self.metrics.record_lookup(path, Lookup::Lmdb);
+                Some(entry.vnode)
             }
             Ok(None) => {
                 debug!(path = %path, "ManifestGet: not found in VDir or LMDB");
-                VeloResponse::ManifestAck { entry: None }
+                self.metrics.record_lookup(path, Lookup::Miss);
+                None
             }
             Err(e) => {
                 warn!(path = %path, error = %e, "ManifestGet: LMDB lookup failed");
-                VeloResponse::ManifestAck { entry: None }
+                self.metrics.record_lookup(path, Lookup::Miss);
+                None
             }
         }
     }
 
     /// Handle ManifestUpsert
     fn handle_manifest_upsert(&mut self, path: &str, entry: VnodeEntry) -> VeloResponse {
-        let vdir_entry = VDirEntry {
-            path_hash: fnv1a_hash(path),
-            cas_hash: entry.content_hash,
-            size: entry.size,
-            mtime_sec: entry.mtime as i64,
-            mtime_nsec: 0,
-            mode: entry.mode,
-            flags: entry.flags,
-            _pad: [0; 3],
-        };
+        let vdir_entry = vdir_entry_from_vnode(path, &entry, vrift_ipc::mtime::now());
 
+        let existed = self.path_exists(path);
         match self.vdir.upsert(vdir_entry) {
             Ok(_) => {
                 debug!(path = %path, "Upserted entry");
+                let kind = if existed {
+                    ManifestChangeKind::Modified
+                } else {
+                    ManifestChangeKind::Created
+                };
+                self.changes.record(kind, path, entry.flags & FLAG_DIR != 0);
                 VeloResponse::ManifestAck { entry: Some(entry) }
             }
             Err(e) => {
@@ -175,8 +437,16 @@ impl CommandHandler {
 
     /// Handle ManifestRemove
     fn handle_manifest_remove(&mut self, path: &str) -> VeloResponse {
-        let path_hash = fnv1a_hash(path);
-        if self.vdir.mark_dirty(path_hash, false) {
+        let is_dir = self
+            .vdir
+            .lookup_path(path)
+            .is_some_and(|e| e.flags & FLAG_DIR != 0);
+        self.changes
+            .record(ManifestChangeKind::Removed, path, is_dir);
+        if self
+            .vdir
+            .mark_dirty(fnv1a_hash(path), vdir_path_check(path), false)
+        {
             // For now, just clear dirty bit. Full deletion would require tombstone.
             debug!(path = %path, "Marked for removal");
             VeloResponse::ManifestAck { entry: None }
@@ -185,91 +455,137 @@ impl CommandHandler {
         }
     }
 
-    /// Handle ManifestRename: remove old path, upsert under new path
+    /// Handle ManifestRename: move the entry to the new path and leave a
+    /// whiteout at the old one, in one VDir write
     fn handle_manifest_rename(&mut self, old_path: &str, new_path: &str) -> VeloResponse {
-        let old_hash = fnv1a_hash(old_path);
-        let new_hash = fnv1a_hash(new_path);
-
-        // Lookup old entry (VDir first, then LMDB)
-        let old_entry = if let Some(entry) = self.vdir.lookup(old_hash) {
-            Some(*entry)
-        } else if let Ok(Some(lmdb_entry)) = self.manifest.get(old_path) {
-            Some(VDirEntry {
-                path_hash: old_hash,
-                cas_hash: lmdb_entry.vnode.content_hash,
-                size: lmdb_entry.vnode.size,
-                mtime_sec: lmdb_entry.vnode.mtime as i64,
-                mtime_nsec: 0,
-                mode: lmdb_entry.vnode.mode,
-                flags: lmdb_entry.vnode.flags,
-                _pad: [0; 3],
-            })
-        } else {
-            None
+        let Some(entry) = self.lookup_entry(old_path) else {
+            debug!(path = %old_path, "Rename: source not found, treating as no-op");
+            return VeloResponse::ManifestAck { entry: None };
         };
 
-        match old_entry {
-            Some(entry) => {
-                // Mark old path as removed
-                self.vdir.mark_dirty(old_hash, false);
-
-                // Insert under new path hash
-                let new_entry = VDirEntry {
-                    path_hash: new_hash,
-                    ..entry
-                };
-                match self.vdir.upsert(new_entry) {
-                    Ok(_) => {
-                        debug!(old = %old_path, new = %new_path, "Manifest rename");
-                        VeloResponse::ManifestAck { entry: None }
-                    }
-                    Err(e) => {
-                        error!(error = %e, "Rename upsert failed");
-                        VeloResponse::Error(VeloError::internal(format!("{}", e)))
-                    }
-                }
-            }
-            None => {
-                debug!(path = %old_path, "Rename: source not found, treating as no-op");
+        let mut new_entry = entry;
+        new_entry.set_path(new_path);
+        match self.vdir.upsert_many(&[new_entry, whiteout(entry)]) {
+            Ok(_) => {
+                debug!(old = %old_path, new = %new_path, "Manifest rename");
+                self.changes
+                    .record_rename(old_path, new_path, entry.flags & FLAG_DIR != 0);
                 VeloResponse::ManifestAck { entry: None }
             }
+            Err(e) => {
+                error!(error = %e, "Rename upsert failed");
+                VeloResponse::Error(VeloError::internal(format!("{}", e)))
+            }
         }
     }
 
-    /// Handle ManifestUpdateMtime: update mtime on existing entry
-    fn handle_manifest_update_mtime(&mut self, path: &str, mtime_ns: u64) -> VeloResponse {
-        let path_hash = fnv1a_hash(path);
-        let mtime_sec = (mtime_ns / 1_000_000_000) as i64;
-        let mtime_nsec = (mtime_ns % 1_000_000_000) as u32;
-
-        // Look up existing entry (VDir first, then LMDB)
-        let existing = if let Some(entry) = self.vdir.lookup(path_hash) {
-            Some(*entry)
-        } else if let Ok(Some(lmdb_entry)) = self.manifest.get(path) {
-            Some(VDirEntry {
-                path_hash,
-                cas_hash: lmdb_entry.vnode.content_hash,
-                size: lmdb_entry.vnode.size,
-                mtime_sec: lmdb_entry.vnode.mtime as i64,
-                mtime_nsec: 0,
-                mode: lmdb_entry.vnode.mode,
-                flags: lmdb_entry.vnode.flags,
-                _pad: [0; 3],
-            })
+    /// Handle ManifestRenameOver: the shim renamed an untracked file over
+    /// `new_path` on disk. Hash the real file and swap `new_path` to it while
+    /// dropping `old_path`, in one VDir write, so readers go straight from
+    /// the old target to the new one.
+    fn handle_manifest_rename_over(&mut self, old_path: &str, new_path: &str) -> VeloResponse {
+        let Some(real) = self.real_path(new_path) else {
+            return VeloResponse::Error(VeloError::invalid_path(format!(
+                "Not a project path: {}",
+                new_path
+            )));
+        };
+        let store = match vrift_cas::CasStore::new(&self.config.cas_path) {
+            Ok(s) => s,
+            Err(e) => {
+                error!(error = %e, "Failed to initialize CAS store");
+                return VeloResponse::Error(VeloError::new(
+                    e.classify().into(),
+                    format!("CAS init error: {}", e),
+                ));
+            }
+        };
+        let (hash_bytes, meta) =
+            match store
+                .store_file(&real)
+                .map_err(|e| e.to_string())
+                .and_then(|h| {
+                    fs::metadata(&real)
+                        .map(|m| (h, m))
+                        .map_err(|e| e.to_string())
+                }) {
+                Ok(v) => v,
+                Err(e) => {
+                    error!(real = %real.display(), error = %e, "Rename-over ingest failed");
+                    return VeloResponse::Error(VeloError::new(
+                        VeloErrorKind::IngestFailed,
+                        format!("Ingest error: {}", e),
+                    ));
+                }
+            };
+
+        let mut entry = VDirEntry {
+            cas_hash: hash_bytes,
+            size: meta.len(),
+            mtime_sec: meta.mtime(),
+            mtime_nsec: meta.mtime_nsec() as u32,
+            mode: meta.mode(),
+            ..Default::default()
+        };
+        entry.set_path(new_path);
+        entry.set_ingest_ns(vrift_ipc::mtime::now());
+
+        let mut batch = vec![entry];
+        if let Some(old) = self.lookup_entry(old_path) {
+            batch.push(whiteout(old));
+        }
+        let existed = self.path_exists(new_path);
+        if let Err(e) = self.vdir.upsert_many(&batch) {
+            return VeloResponse::Error(VeloError::io_error(format!("VDir update error: {}", e)));
+        }
+        if batch.len() > 1 {
+            self.changes.record_rename(old_path, new_path, false);
         } else {
-            None
+            let kind = if existed {
+                ManifestChangeKind::Modified
+            } else {
+                ManifestChangeKind::Created
+            };
+            self.changes.record(kind, new_path, false);
+        }
+
+        let vnode = VnodeEntry {
+            content_hash: hash_bytes,
+            size: meta.len(),
+            mtime: vrift_cas::mtime_nsec_from_metadata(&meta),
+            mode: meta.mode(),
+            flags: 0,
+            _pad: 0,
         };
+        // A CoW write of the target still waiting to be hashed is older than
+        // the file that replaced it
+        self.coalescer.supersede(new_path, &vnode);
+        info!(old = %old_path, new = %new_path, hash = %hex::encode(hash_bytes), "Rename-over committed");
+
+        VeloResponse::ManifestAck { entry: Some(vnode) }
+    }
+
+    /// Handle ManifestUpdateMtime: update mtime on existing entry
+    fn handle_manifest_update_mtime(&mut self, path: &str, mtime_ns: i64) -> VeloResponse {
+        let existing = self.lookup_entry(path);
 
         match existing {
-            Some(entry) => {
-                let updated = VDirEntry {
-                    mtime_sec,
-                    mtime_nsec,
-                    ..entry
+            Some(mut updated) => {
+                updated.set_mtime_ns(mtime_ns);
+                // VDir entries are patched in place; LMDB ones join the overlay
+                let result = if self.vdir.update_entry(path, |e| e.set_mtime_ns(mtime_ns)) {
+                    Ok(())
+                } else {
+                    self.vdir.upsert(updated)
                 };
-                match self.vdir.upsert(updated) {
+                match result {
                     Ok(_) => {
-                        debug!(path = %path, mtime_sec, "Updated mtime");
+                        debug!(path = %path, mtime_ns, "Updated mtime");
+                        self.changes.record(
+                            ManifestChangeKind::Modified,
+                            path,
+                            updated.flags & FLAG_DIR != 0,
+                        );
                         VeloResponse::ManifestAck { entry: None }
                     }
                     Err(e) => {
@@ -325,14 +641,22 @@ impl CommandHandler {
                 if child_name.is_empty() {
                     continue;
                 }
+                // Renamed away or removed since the LMDB snapshot
+                if self
+                    .vdir
+                    .lookup_path(entry_path)
+                    .is_some_and(|e| e.is_whiteout())
+                {
+                    continue;
+                }
                 if !seen.insert(child_name.to_string()) {
                     continue;
                 }
 
-                let is_dir = manifest_entry.vnode.flags & FLAG_DIR != 0;
+                // Manifest entries carry a `VnodeFlags` type, not VDir flags
                 entries.push(vrift_ipc::DirEntry {
                     name: child_name.to_string(),
-                    is_dir,
+                    is_dir: manifest_entry.vnode.is_dir(),
                 });
             }
         }
@@ -341,8 +665,15 @@ impl CommandHandler {
         VeloResponse::ManifestListAck { entries }
     }
 
-    /// Handle ManifestReingest (CoW commit)
-    async fn handle_reingest(&mut self, vpath: &str, temp_path: &str) -> VeloResponse {
+    /// Handle ManifestReingest (CoW commit). With a `base_hash`, the commit
+    /// is refused if `vpath` no longer has the content the copy was taken
+    /// from: another writer got there first.
+    async fn handle_reingest(
+        &mut self,
+        vpath: &str,
+        temp_path: &str,
+        base_hash: Option<[u8; 32]>,
+    ) -> VeloResponse {
         let temp = PathBuf::from(temp_path);
 
         // 1. Initialize CAS store
@@ -351,12 +682,15 @@ impl CommandHandler {
             Err(e) => {
                 error!(error = %e, "Failed to initialize CAS store");
                 return VeloResponse::Error(VeloError::new(
-                    VeloErrorKind::Internal,
+                    e.classify().into(),
                     format!("CAS init error: {}", e),
                 ));
             }
         };
 
+        // Permissions of the written file, for a copy-up (the blob's are not)
+        let temp_mode = fs::metadata(&temp).map(|m| m.mode()).ok();
+
         // 2. Ingest to CAS via move (atomic & deduplicated)
         let hash_bytes = match store.store_by_move(&temp) {
             Ok(h) => h,
@@ -374,33 +708,88 @@ impl CommandHandler {
         let meta = match fs::metadata(&cas_path) {
             Ok(m) => m,
             Err(e) => {
-                return VeloResponse::Error(VeloError::io_error(format!("Metadata error: {}", e)));
+                return VeloResponse::Error(VeloError::new(
+                    e.classify().into(),
+                    format!("Metadata error: {}", e),
+                ));
             }
         };
 
-        // 4. Update VDir
-        let entry = VDirEntry {
-            path_hash: fnv1a_hash(vpath),
+        // 4. Both writers started from the same content: keep the version
+        // committed first at the path and this one next to it. Identical
+        // results are not a conflict.
+        let conflict = base_hash.and_then(|base| {
+            self.lookup_entry(vpath)
+                .map(|current| current.cas_hash)
+                .filter(|current| *current != base && *current != hash_bytes)
+        });
+        let target = match conflict {
+            Some(_) => conflict_path(vpath, &hash_bytes),
+            None => vpath.to_string(),
+        };
+        if let Some(current) = conflict {
+            warn!(
+                vpath = %vpath,
+                current = %hex::encode(current),
+                ours = %hex::encode(hash_bytes),
+                "Write conflict"
+            );
+            if !self.keep_conflicts {
+                return VeloResponse::Error(VeloError::with_path(
+                    VeloErrorKind::Conflict,
+                    "Path changed since the copy was taken; write discarded",
+                    vpath,
+                ));
+            }
+        }
+
+        // 5. Update VDir
+        let mut entry = VDirEntry {
             cas_hash: hash_bytes,
             size: meta.len(),
             mtime_sec: meta.mtime(),
             mtime_nsec: meta.mtime_nsec() as u32,
             mode: meta.mode(),
             flags: if meta.is_dir() { FLAG_DIR } else { 0 },
-            _pad: [0; 3],
+            ..Default::default()
         };
+        entry.set_path(&target);
+        entry.set_ingest_ns(vrift_ipc::mtime::now());
 
+        let existed = self.path_exists(&target);
         if let Err(e) = self.vdir.upsert(entry) {
             return VeloResponse::Error(VeloError::io_error(format!("VDir update error: {}", e)));
         }
+        let kind = if existed {
+            ManifestChangeKind::Modified
+        } else {
+            ManifestChangeKind::Created
+        };
+        self.changes.record(kind, &target, meta.is_dir());
+
+        if conflict.is_some() {
+            return VeloResponse::Error(VeloError::with_path(
+                VeloErrorKind::Conflict,
+                format!(
+                    "Path changed since the copy was taken; write kept as {}",
+                    target
+                ),
+                vpath,
+            ));
+        }
 
         info!(vpath = %vpath, hash = %hex::encode(hash_bytes), "Reingest complete");
 
+        let now_secs = u64::from(entry.ingest_sec);
+        if self.hot_writes.is_promoted(vpath) || self.hot_writes.record_break(vpath, now_secs) {
+            self.copy_up(vpath, &cas_path, temp_mode.unwrap_or(meta.mode()), entry);
+        }
+
         VeloResponse::ManifestAck {
             entry: Some(VnodeEntry {
                 content_hash: hash_bytes,
                 size: meta.len(),
-                mtime: meta.mtime() as u64,
+                mtime: vrift_cas::mtime_nsec_from_metadata(&meta),
                 mode: meta.mode(),
                 flags: 0,
                 _pad: 0,
@@ -408,11 +797,111 @@ impl CommandHandler {
         }
     }
 
+    /// Real file behind a manifest key, if the key stays inside the project
+    fn real_path(&self, vpath: &str) -> Option<PathBuf> {
+        let path = vrift_ipc::path_key::to_path(vpath);
+        let rel = path.strip_prefix("/").unwrap_or(&path);
+        if rel.as_os_str().is_empty()
+            || !rel.components().all(|c| matches!(c, Component::Normal(_)))
+        {
+            return None;
+        }
+        Some(self.config.project_root.join(rel))
+    }
+
+    /// Write `blob` to the real path of `vpath` and flag its VDir entry
+    /// passthrough, recording the promotion on first copy-up
+    fn copy_up(&mut self, vpath: &str, blob: &Path, mode: u32, mut entry: VDirEntry) {
+        let Some(real) = self.real_path(vpath) else {
+            return;
+        };
+        if let Err(e) = write_real_file(blob, &real, mode) {
+            warn!(vpath = %vpath, real = %real.display(), error = %e, "Copy-up failed");
+            return;
+        }
+        entry.flags |= FLAG_PASSTHROUGH;
+        if let Err(e) = self.vdir.upsert(entry) {
+            warn!(vpath = %vpath, error = %e, "Copy-up: VDir update failed");
+            return;
+        }
+        if self.hot_writes.is_promoted(vpath) {
+            debug!(vpath = %vpath, "Copy-up refreshed");
+            return;
+        }
+        let promoted_secs = u64::from(entry.ingest_sec);
+        match self.hot_writes.promote(vpath, promoted_secs) {
+            Ok(()) => info!(
+                vpath = %vpath,
+                real = %real.display(),
+                "Hot-write path copied up, now passthrough"
+            ),
+            Err(e) => warn!(vpath = %vpath, error = %e, "Failed to write hot-write report"),
+        }
+    }
+
+    /// Flag the paths promoted before a restart again (the VDir may be new),
+    /// dropping those whose real file has gone
+    fn reapply_promotions(&mut self) {
+        let paths: Vec<String> = self.hot_writes.promoted().keys().cloned().collect();
+        for vpath in paths {
+            let meta = self
+                .real_path(&vpath)
+                .and_then(|real| fs::symlink_metadata(real).ok())
+                .filter(|m| m.is_file());
+            let Some(meta) = meta else {
+                if let Err(e) = self.hot_writes.demote(&vpath) {
+                    warn!(vpath = %vpath, error = %e, "Failed to write hot-write report");
+                }
+                continue;
+            };
+            let mut entry = VDirEntry {
+                size: meta.len(),
+                mode: meta.mode(),
+                flags: FLAG_PASSTHROUGH,
+                ..Default::default()
+            };
+            entry.set_path(&vpath);
+            entry.set_mtime_ns(vrift_cas::mtime_nsec_from_metadata(&meta));
+            if let Err(e) = self.vdir.upsert(entry) {
+                warn!(vpath = %vpath, error = %e, "Failed to re-flag hot-write path");
+            }
+        }
+        if !self.hot_writes.promoted().is_empty() {
+            info!(
+                count = self.hot_writes.promoted().len(),
+                "Hot-write paths served from real files"
+            );
+        }
+    }
+
+    /// Point the VDir entries at their blobs in the project's packfile, so
+    /// the shim serves packed content without searching the pack index.
+    /// Without a (readable) packfile every pack reference is dropped.
+    fn reload_pack(&mut self) {
+        let Some(path) = self.config.pack_path.as_ref().filter(|p| p.exists()) else {
+            self.vdir.apply_pack(0, |_| None);
+            return;
+        };
+        match vrift_pack::PackReader::open(path) {
+            Ok(pack) => {
+                let packed = self.vdir.apply_pack(pack.id(), |hash| {
+                    let entry = pack.entry(hash)?;
+                    Some((entry.offset, u32::try_from(entry.length).ok()?))
+                });
+                info!(path = %path.display(), packed, "VDir entries point into packfile");
+            }
+            Err(e) => {
+                warn!(path = %path.display(), error = %e, "Failed to read packfile");
+                self.vdir.apply_pack(0, |_| None);
+            }
+        }
+    }
+
     /// Handle IngestFullScan - unified ingest through daemon
     /// CLI sends this request instead of doing ingest itself
     #[allow(clippy::too_many_arguments)]
-    async fn handle_ingest_full_scan(
-        &self,
+    fn handle_ingest_full_scan(
+        default_cas_path: &Path,
         path: &str,
         manifest_path: &str,
         threads: Option<usize>,
@@ -420,10 +909,10 @@ impl CommandHandler {
         tier1: bool,
         prefix: Option<&str>,
         cas_root_override: Option<&str>,
+        filter: &vrift_cas::IngestFilter,
     ) -> VeloResponse {
         use std::time::Instant;
         use vrift_cas::{parallel_ingest_with_progress, IngestMode};
-        use walkdir::WalkDir;
 
         let source_path = PathBuf::from(path);
         let manifest_out = PathBuf::from(manifest_path);
@@ -439,12 +928,19 @@ impl CommandHandler {
 
         let start = Instant::now();
 
-        // 1. Collect files
-        let file_paths: Vec<PathBuf> = WalkDir::new(&source_path)
-            .into_iter()
-            .filter_map(|e| e.ok())
-            .filter(|e| e.file_type().is_file())
-            .map(|e| e.path().to_path_buf())
+        // 1. Collect files; those the skip policy keeps out are only reported
+        let mut skipped = Vec::new();
+        let file_paths: Vec<PathBuf> = vrift_cas::walk_files(&source_path, filter)
+            .filter(|path| {
+                let size = fs::metadata(path).map_or(0, |m| m.len());
+                match filter.skip.check(path, size) {
+                    Ok(()) => true,
+                    Err(e) => {
+                        skipped.push(Err(e));
+                        false
+                    }
+                }
+            })
             .collect();
 
         let total_files = file_paths.len() as u64;
@@ -456,6 +952,13 @@ impl CommandHandler {
                 total_bytes: 0,
                 duration_ms: 0,
                 manifest_path: manifest_path.to_string(),
+                tier1_files: 0,
+                tier2_files: 0,
+                unchanged_files: 0,
+                deduped_files: 0,
+                quarantined_files: 0,
+                largest_new: Vec::new(),
+                skipped_files: skipped.len() as u64,
             };
         }
 
@@ -475,9 +978,9 @@ impl CommandHandler {
                 info!(cas_root = %p.display(), "Using CLI-provided CAS root");
                 p
             }
-            None => self.config.cas_path.clone(),
+            None => default_cas_path.to_path_buf(),
         };
-        let results = parallel_ingest_with_progress(
+        let mut results = parallel_ingest_with_progress(
             &file_paths,
             &effective_cas_path,
             mode,
@@ -487,24 +990,16 @@ impl CommandHandler {
             },
         );
 
+        results.append(&mut skipped);
+
         // 4. Collect stats
-        let mut total_bytes = 0u64;
-        let mut new_bytes = 0u64;
-        let mut unique_blobs = 0u64;
-
-        for r in results.iter().flatten() {
-            total_bytes += r.size;
-            if r.was_new {
-                unique_blobs += 1;
-                new_bytes += r.size;
-            }
-        }
+        let totals = vrift_cas::IngestTotals::of(&results, IngestBlob::MAX_LISTED);
 
         let duration = start.elapsed();
 
         // 5. Build and write manifest (using vrift_manifest if available)
         // For now, just write a simple binary manifest
-        if let Err(e) = self.write_manifest(&manifest_out, &source_path, &results, prefix) {
+        if let Err(e) = Self::write_manifest(&manifest_out, &source_path, &results, prefix) {
             return VeloResponse::Error(VeloError::io_error(format!(
                 "Failed to write manifest: {}",
                 e
@@ -512,26 +1007,38 @@ impl CommandHandler {
         }
 
         info!(
-            files = total_files,
-            blobs = unique_blobs,
-            new_bytes = new_bytes,
+            files = totals.files,
+            blobs = totals.blobs,
+            new_bytes = totals.new_bytes,
             duration_ms = duration.as_millis() as u64,
             "Full scan ingest complete"
         );
 
+        // Everything hashed is written at the one tier the request asked for
+        let written = totals.blobs + totals.deduped;
         VeloResponse::IngestAck {
-            files: total_files,
-            blobs: unique_blobs,
-            new_bytes,
-            total_bytes,
+            files: totals.files,
+            blobs: totals.blobs,
+            new_bytes: totals.new_bytes,
+            total_bytes: totals.total_bytes,
             duration_ms: duration.as_millis() as u64,
             manifest_path: manifest_path.to_string(),
+            tier1_files: if tier1 { written } else { 0 },
+            tier2_files: if tier1 { 0 } else { written },
+            unchanged_files: totals.unchanged,
+            deduped_files: totals.deduped,
+            quarantined_files: totals.quarantined,
+            skipped_files: totals.skipped,
+            largest_new: totals
+                .largest_new
+                .iter()
+                .map(|(path, size)| IngestBlob::new(&source_path, path, *size))
+                .collect(),
         }
     }
 
     /// Write manifest file from ingest results
     fn write_manifest(
-        &self,
         manifest_path: &Path,
         source_root: &Path,
         results: &[Result<vrift_cas::IngestResult, vrift_cas::CasError>],
@@ -542,7 +1049,7 @@ impl CommandHandler {
         for result in results.iter().flatten() {
             // Try to get metadata for mtime/mode
             let (mtime, mode) = match fs::metadata(&result.source_path) {
-                Ok(meta) => (meta.mtime() as u64, meta.mode()),
+                Ok(meta) => (vrift_cas::mtime_nsec_from_metadata(&meta), meta.mode()),
                 Err(_) => (0, 0o644), // Fallback
             };
 
@@ -567,11 +1074,12 @@ impl CommandHandler {
                 .strip_prefix(&canon_root)
                 .unwrap_or(&canon_source);
 
+            let rel = vrift_ipc::path_key::from_path(rel);
             let prefix_str = prefix.unwrap_or("");
             let key = if prefix_str == "/" || prefix_str.is_empty() {
-                format!("/{}", rel.display())
+                format!("/{}", rel)
             } else {
-                format!("{}/{}", prefix_str.trim_end_matches('/'), rel.display())
+                format!("{}/{}", prefix_str.trim_end_matches('/'), rel)
             };
 
             manifest.insert(&key, entry);
@@ -669,6 +1177,44 @@ mod tests {
         }
     }
 
+    // ==================== VDirNegotiate Tests ====================
+
+    #[tokio::test]
+    async fn test_vdir_negotiate_downgrades_for_older_shim() {
+        let (mut handler, _temp) = create_test_handler();
+
+        let response = handler
+            .handle_request(VeloRequest::VDirNegotiate { max_version: 1 })
+            .await;
+        match response {
+            VeloResponse::VDirNegotiateAck { version } => assert_eq!(version, 1),
+            _ => panic!("Expected VDirNegotiateAck"),
+        }
+        assert_eq!(handler.vdir.version(), 1);
+
+        // A newer shim arriving later does not undo the downgrade
+        let response = handler
+            .handle_request(VeloRequest::VDirNegotiate {
+                max_version: crate::vdir::VDIR_VERSION + 1,
+            })
+            .await;
+        match response {
+            VeloResponse::VDirNegotiateAck { version } => assert_eq!(version, 1),
+            _ => panic!("Expected VDirNegotiateAck"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_vdir_negotiate_rejects_unsupported_version() {
+        let (mut handler, _temp) = create_test_handler();
+
+        let response = handler
+            .handle_request(VeloRequest::VDirNegotiate { max_version: 0 })
+            .await;
+        assert!(matches!(response, VeloResponse::Error(_)));
+        assert_eq!(handler.vdir.version(), crate::vdir::VDIR_VERSION);
+    }
+
     // ==================== ManifestUpsert Tests ====================
 
     #[tokio::test]
@@ -711,6 +1257,46 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_pack_reload_points_entries_into_pack() {
+        let (mut handler, temp) = create_test_handler();
+        let entry = VnodeEntry {
+            content_hash: [7; 32],
+            size: 6,
+            mtime: 1234567890,
+            mode: 0o644,
+            flags: 0,
+            _pad: 0,
+        };
+        handler
+            .handle_request(VeloRequest::ManifestUpsert {
+                path: "src/main.rs".to_string(),
+                entry,
+            })
+            .await;
+
+        let pack_path = temp.path().join("test.pack");
+        let mut writer = vrift_pack::PackWriter::new(&pack_path);
+        writer.add([1; 32], b"other");
+        writer.add([7; 32], b"packed");
+        writer.finish().unwrap();
+        handler.config.pack_path = Some(pack_path.clone());
+
+        let response = handler.handle_request(VeloRequest::PackReload).await;
+        assert!(matches!(response, VeloResponse::StatusAck { .. }));
+        let pack = vrift_pack::PackReader::open(&pack_path).unwrap();
+        let found = handler.vdir.lookup_path("src/main.rs").unwrap();
+        assert_eq!(
+            (found.pack_id, found.pack_offset, found.pack_len),
+            (pack.id(), 5, 6)
+        );
+
+        // The pack is gone: so are the references into it
+        fs::remove_file(&pack_path).unwrap();
+        handler.handle_request(VeloRequest::PackReload).await;
+        assert_eq!(handler.vdir.lookup_path("src/main.rs").unwrap().pack_id, 0);
+    }
+
     #[tokio::test]
     async fn test_manifest_upsert_overwrites_existing() {
         let (mut handler, _temp) = create_test_handler();
@@ -899,6 +1485,109 @@ mod tests {
         }
     }
 
+    /// Two writers copied `base`; the first commits `ours`, the second
+    /// `theirs`. Returns the second writer's response.
+    async fn race_writes(
+        handler: &mut CommandHandler,
+        temp: &std::path::Path,
+        base: &[u8],
+        ours: &[u8],
+        theirs: &[u8],
+    ) -> VeloResponse {
+        let staging = temp.join("staging");
+        std::fs::create_dir_all(&staging).unwrap();
+        let mut response = VeloResponse::ManifestAck { entry: None };
+        for (i, content) in [base, ours, theirs].iter().enumerate() {
+            let temp_file = staging.join(format!("w{}.tmp", i));
+            std::fs::write(&temp_file, content).unwrap();
+            let request = match i {
+                0 => VeloRequest::ManifestReingest {
+                    vpath: "/src/lib.rs".to_string(),
+                    temp_path: temp_file.to_str().unwrap().to_string(),
+                },
+                _ => VeloRequest::ManifestReingestChecked {
+                    vpath: "/src/lib.rs".to_string(),
+                    temp_path: temp_file.to_str().unwrap().to_string(),
+                    base_hash: *blake3::hash(base).as_bytes(),
+                },
+            };
+            response = handler.handle_request(request).await;
+        }
+        response
+    }
+
+    async fn content_hash(handler: &mut CommandHandler, path: &str) -> Option<[u8; 32]> {
+        match handler
+            .handle_request(VeloRequest::ManifestGet {
+                path: path.to_string(),
+            })
+            .await
+        {
+            VeloResponse::ManifestAck { entry } => entry.map(|e| e.content_hash),
+            other => panic!("Expected ManifestAck, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_write_conflict_keeps_both_versions() {
+        let (mut handler, temp) = create_test_handler();
+
+        let response = race_writes(&mut handler, temp.path(), b"base", b"first", b"second").await;
+
+        let kept = conflict_path("/src/lib.rs", blake3::hash(b"second").as_bytes());
+        match response {
+            VeloResponse::Error(e) => {
+                assert_eq!(e.kind, VeloErrorKind::Conflict);
+                assert_eq!(e.path.as_deref(), Some("/src/lib.rs"));
+                assert!(e.message.contains(&kept), "{}", e.message);
+            }
+            other => panic!("Expected a conflict, got {:?}", other),
+        }
+        // The first committed write stays; the second is kept next to it
+        assert_eq!(
+            content_hash(&mut handler, "/src/lib.rs").await,
+            Some(*blake3::hash(b"first").as_bytes())
+        );
+        assert_eq!(
+            content_hash(&mut handler, &kept).await,
+            Some(*blake3::hash(b"second").as_bytes())
+        );
+        let (changes, _, _) = handler.changes.since(0);
+        let last = changes.last().unwrap();
+        assert_eq!(last.kind, ManifestChangeKind::Created);
+        assert_eq!(last.path, kept);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_write_conflict_discarded() {
+        let (mut handler, temp) = create_test_handler();
+        handler.keep_conflicts = false;
+
+        let response = race_writes(&mut handler, temp.path(), b"base", b"first", b"second").await;
+
+        assert!(matches!(
+            response,
+            VeloResponse::Error(VeloError {
+                kind: VeloErrorKind::Conflict,
+                ..
+            })
+        ));
+        let kept = conflict_path("/src/lib.rs", blake3::hash(b"second").as_bytes());
+        assert_eq!(content_hash(&mut handler, &kept).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_identical_concurrent_writes_do_not_conflict() {
+        let (mut handler, temp) = create_test_handler();
+
+        let response = race_writes(&mut handler, temp.path(), b"base", b"same", b"same").await;
+
+        assert!(matches!(
+            response,
+            VeloResponse::ManifestAck { entry: Some(_) }
+        ));
+    }
+
     #[tokio::test]
     async fn test_reingest_nonexistent_file_returns_error() {
         let (mut handler, _temp) = create_test_handler();
@@ -918,6 +1607,61 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_hot_write_path_is_copied_up() {
+        let (mut handler, temp) = create_test_handler();
+        let report = hot_writes::report_path(temp.path());
+        handler.hot_writes = HotWrites::load(report.clone(), 2, 3600);
+        let staging = temp.path().join("staging");
+        std::fs::create_dir_all(&staging).unwrap();
+
+        for (i, content) in [&b"first build"[..], b"second build"].iter().enumerate() {
+            let temp_file = staging.join(format!("hot{}.tmp", i));
+            std::fs::write(&temp_file, content).unwrap();
+            let response = handler
+                .handle_request(VeloRequest::ManifestReingest {
+                    vpath: "/out/hot.bin".to_string(),
+                    temp_path: temp_file.to_str().unwrap().to_string(),
+                })
+                .await;
+            assert!(matches!(
+                response,
+                VeloResponse::ManifestAck { entry: Some(_) }
+            ));
+        }
+
+        // Second break promoted it: real file written, manifest hands it off
+        let real = temp.path().join("out/hot.bin");
+        assert_eq!(std::fs::read(&real).unwrap(), b"second build");
+        assert!(handler
+            .vdir
+            .lookup_path("/out/hot.bin")
+            .unwrap()
+            .is_passthrough());
+        let response = handler
+            .handle_request(VeloRequest::ManifestGet {
+                path: "/out/hot.bin".to_string(),
+            })
+            .await;
+        assert!(matches!(
+            response,
+            VeloResponse::ManifestAck { entry: None }
+        ));
+        assert!(std::fs::read_to_string(&report)
+            .unwrap()
+            .contains("\t/out/hot.bin\n"));
+
+        // A restarted handler re-flags it from the report
+        let vdir = VDir::create_or_open(&temp.path().join("restart.vdir")).unwrap();
+        handler.vdir = vdir;
+        handler.reapply_promotions();
+        assert!(handler
+            .vdir
+            .lookup_path("/out/hot.bin")
+            .unwrap()
+            .is_passthrough());
+    }
+
     // ==================== ManifestRename Tests ====================
 
     #[tokio::test]
@@ -980,6 +1724,141 @@ mod tests {
         ));
     }
 
+    async fn get(handler: &mut CommandHandler, path: &str) -> Option<VnodeEntry> {
+        match handler
+            .handle_request(VeloRequest::ManifestGet {
+                path: path.to_string(),
+            })
+            .await
+        {
+            VeloResponse::ManifestAck { entry } => entry,
+            other => panic!("Expected ManifestAck, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_manifest_rename_hides_old_path() {
+        let (mut handler, _temp) = create_test_handler();
+        let entry = VnodeEntry {
+            content_hash: [9; 32],
+            size: 5,
+            mtime: 1,
+            mode: 0o644,
+            flags: 0,
+            _pad: 0,
+        };
+        handler
+            .handle_request(VeloRequest::ManifestUpsert {
+                path: "/out/.tmp123".to_string(),
+                entry,
+            })
+            .await;
+
+        handler
+            .handle_request(VeloRequest::ManifestRename {
+                old_path: "/out/.tmp123".to_string(),
+                new_path: "/out/app".to_string(),
+            })
+            .await;
+
+        assert!(get(&mut handler, "/out/.tmp123").await.is_none());
+        assert_eq!(get(&mut handler, "/out/app").await.unwrap().size, 5);
+        let (changes, _, _) = handler.changes.since(0);
+        assert!(changes
+            .iter()
+            .any(|c| c.kind == ManifestChangeKind::MovedTo && c.path == "/out/app"));
+
+        // A rename of the whited-out name finds nothing to move
+        handler
+            .handle_request(VeloRequest::ManifestRename {
+                old_path: "/out/.tmp123".to_string(),
+                new_path: "/out/other".to_string(),
+            })
+            .await;
+        assert!(get(&mut handler, "/out/other").await.is_none());
+    }
+
+    /// Cargo writes an artifact to a fresh temp name in the output directory
+    /// and renames it over the previous build's artifact
+    #[tokio::test]
+    async fn test_rename_over_swaps_artifact() {
+        let (mut handler, temp) = create_test_handler();
+        let deps = temp.path().join("target/debug/deps");
+        fs::create_dir_all(&deps).unwrap();
+
+        // Previous build's artifact, tracked by the manifest
+        let old_content = b"rlib v1";
+        fs::write(deps.join("libfoo.rlib"), old_content).unwrap();
+        handler
+            .handle_request(VeloRequest::ManifestUpsert {
+                path: "/target/debug/deps/libfoo.rlib".to_string(),
+                entry: VnodeEntry {
+                    content_hash: *blake3::hash(old_content).as_bytes(),
+                    size: old_content.len() as u64,
+                    mtime: 1,
+                    mode: 0o644,
+                    flags: 0,
+                    _pad: 0,
+                },
+            })
+            .await;
+
+        // The watcher already picked up the temp file before the rename
+        let new_content = b"rlib v2, longer";
+        fs::write(deps.join(".tmpXk3f"), new_content).unwrap();
+        handler
+            .handle_request(VeloRequest::ManifestUpsert {
+                path: "/target/debug/deps/.tmpXk3f".to_string(),
+                entry: VnodeEntry {
+                    content_hash: *blake3::hash(new_content).as_bytes(),
+                    size: new_content.len() as u64,
+                    mtime: 2,
+                    mode: 0o644,
+                    flags: 0,
+                    _pad: 0,
+                },
+            })
+            .await;
+        let generation = handler.vdir.get_stats().generation;
+
+        fs::rename(deps.join(".tmpXk3f"), deps.join("libfoo.rlib")).unwrap();
+        let response = handler
+            .handle_request(VeloRequest::ManifestRenameOver {
+                old_path: "/target/debug/deps/.tmpXk3f".to_string(),
+                new_path: "/target/debug/deps/libfoo.rlib".to_string(),
+            })
+            .await;
+        match response {
+            VeloResponse::ManifestAck { entry: Some(e) } => {
+                assert_eq!(e.content_hash, *blake3::hash(new_content).as_bytes());
+            }
+            other => panic!("Expected ManifestAck, got {:?}", other),
+        }
+
+        // Both names changed in a single seqlock write
+        assert_eq!(handler.vdir.get_stats().generation, generation + 2);
+        let target = get(&mut handler, "/target/debug/deps/libfoo.rlib")
+            .await
+            .unwrap();
+        assert_eq!(target.content_hash, *blake3::hash(new_content).as_bytes());
+        assert_eq!(target.size, new_content.len() as u64);
+        assert!(get(&mut handler, "/target/debug/deps/.tmpXk3f")
+            .await
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_rename_over_rejects_paths_outside_project() {
+        let (mut handler, _temp) = create_test_handler();
+        let response = handler
+            .handle_request(VeloRequest::ManifestRenameOver {
+                old_path: "/a".to_string(),
+                new_path: "/../escape".to_string(),
+            })
+            .await;
+        assert!(matches!(response, VeloResponse::Error(_)));
+    }
+
     // ==================== ManifestUpdateMtime Tests ====================
 
     #[tokio::test]
@@ -1002,7 +1881,7 @@ mod tests {
             .await;
 
         // Update mtime (nanoseconds)
-        let new_mtime_ns: u64 = 5_000_000_000 + 500_000_000; // 5.5 seconds
+        let new_mtime_ns: i64 = 5_000_000_000 + 500_000_000; // 5.5 seconds
         let response = handler
             .handle_request(VeloRequest::ManifestUpdateMtime {
                 path: "test.txt".to_string(),
@@ -1019,13 +1898,136 @@ mod tests {
             .await;
         match response {
             VeloResponse::ManifestAck { entry: Some(e) } => {
-                assert_eq!(e.mtime, 5); // 5 seconds
+                assert_eq!(e.mtime, new_mtime_ns); // sub-second part kept
                 assert_eq!(e.size, 100); // size preserved
             }
             _ => panic!("Expected entry"),
         }
     }
 
+    #[tokio::test]
+    async fn test_manifest_mtime_ns_round_trip() {
+        let (mut handler, _temp) = create_test_handler();
+
+        // Two writes within the same second, and one before the epoch
+        for (path, mtime) in [
+            ("a.o", 1_700_000_000_000_000_001),
+            ("b.o", 1_700_000_000_999_999_999),
+            ("old.c", -1_500_000_000),
+        ] {
+            handler
+                .handle_request(VeloRequest::ManifestUpsert {
+                    path: path.to_string(),
+                    entry: VnodeEntry {
+                        content_hash: [1; 32],
+                        size: 1,
+                        mtime,
+                        mode: 0o644,
+                        flags: 0,
+                        _pad: 0,
+                    },
+                })
+                .await;
+            handler
+                .handle_request(VeloRequest::ManifestRename {
+                    old_path: path.to_string(),
+                    new_path: format!("moved/{}", path),
+                })
+                .await;
+            match handler
+                .handle_request(VeloRequest::ManifestGet {
+                    path: format!("moved/{}", path),
+                })
+                .await
+            {
+                VeloResponse::ManifestAck { entry: Some(e) } => assert_eq!(e.mtime, mtime),
+                other => panic!("Expected entry for {}, got {:?}", path, other),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_manifest_storage_flags_survive_vdir() {
+        let (mut handler, _temp) = create_test_handler();
+
+        let inline = VnodeEntry::new_inline(b"v1\n", 1, 0o644).unwrap();
+        let mut compressed = VnodeEntry::new_file([3; 32], 4096, 1, 0o644);
+        compressed.flags |= VnodeEntry::FLAG_COMPRESSED;
+
+        for (path, entry) in [("VERSION", &inline), ("big.bin", &compressed)] {
+            handler
+                .handle_request(VeloRequest::ManifestUpsert {
+                    path: path.to_string(),
+                    entry: entry.clone(),
+                })
+                .await;
+            handler
+                .handle_request(VeloRequest::ManifestRename {
+                    old_path: path.to_string(),
+                    new_path: format!("moved/{}", path),
+                })
+                .await;
+            let served = match handler
+                .handle_request(VeloRequest::ManifestGet {
+                    path: format!("moved/{}", path),
+                })
+                .await
+            {
+                VeloResponse::ManifestAck { entry: Some(e) } => e,
+                other => panic!("Expected entry for {}, got {:?}", path, other),
+            };
+            assert_eq!(served.flags, entry.flags, "{}", path);
+            assert_eq!(served.inline_content(), entry.inline_content());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_manifest_get_many_answers_in_request_order() {
+        let (mut handler, _temp) = create_test_handler();
+        let tier = vrift_manifest::lmdb::AssetTier::Tier2Mutable;
+        handler.manifest.insert(
+            "/src/base.rs",
+            VnodeEntry::new_file([1; 32], 10, 0, 0o644),
+            tier,
+        );
+        handler.manifest.insert(
+            "/src/gone.rs",
+            VnodeEntry::new_file([2; 32], 20, 0, 0o644),
+            tier,
+        );
+        handler
+            .handle_request(VeloRequest::ManifestUpsert {
+                path: "/src/new.rs".to_string(),
+                entry: VnodeEntry::new_file([3; 32], 30, 0, 0o644),
+            })
+            .await;
+        handler
+            .handle_request(VeloRequest::ManifestRename {
+                old_path: "/src/gone.rs".to_string(),
+                new_path: "/src/moved.rs".to_string(),
+            })
+            .await;
+
+        let paths = [
+            "/src/new.rs",
+            "/src/missing.rs",
+            "/src/gone.rs",
+            "/src/base.rs",
+            "/src/moved.rs",
+        ];
+        let response = handler
+            .handle_request(VeloRequest::ManifestGetMany {
+                paths: paths.iter().map(|p| p.to_string()).collect(),
+            })
+            .await;
+
+        let VeloResponse::ManifestManyAck { entries } = response else {
+            panic!("Expected ManifestManyAck, got {:?}", response);
+        };
+        let sizes: Vec<_> = entries.iter().map(|e| e.as_ref().map(|e| e.size)).collect();
+        assert_eq!(sizes, vec![Some(30), None, None, Some(10), Some(20)]);
+    }
+
     // ==================== ManifestListDir Tests ====================
 
     #[tokio::test]
@@ -1045,6 +2047,40 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_manifest_list_dir_marks_directories() {
+        let (mut handler, _temp) = create_test_handler();
+        let tier = vrift_manifest::lmdb::AssetTier::Tier2Mutable;
+        handler
+            .manifest
+            .insert("/src/sub", VnodeEntry::new_directory(0, 0o755), tier);
+        handler.manifest.insert(
+            "/src/sub/b.txt",
+            VnodeEntry::new_file([1; 32], 5, 0, 0o644),
+            tier,
+        );
+        handler.manifest.insert(
+            "/src/a.txt",
+            VnodeEntry::new_file([2; 32], 6, 0, 0o644),
+            tier,
+        );
+
+        let response = handler
+            .handle_request(VeloRequest::ManifestListDir {
+                path: "/src".to_string(),
+            })
+            .await;
+        let VeloResponse::ManifestListAck { mut entries } = response else {
+            panic!("Expected ManifestListAck");
+        };
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        let listed: Vec<(&str, bool)> = entries
+            .iter()
+            .map(|e| (e.name.as_str(), e.is_dir))
+            .collect();
+        assert_eq!(listed, [("a.txt", false), ("sub", true)]);
+    }
+
     // ==================== Unhandled Request Tests ====================
 
     #[tokio::test]
@@ -1103,4 +2139,70 @@ mod tests {
             }
         }
     }
+
+    // ==================== Change Feed Tests ====================
+
+    #[tokio::test]
+    async fn test_changes_since_reports_mutations() {
+        let (mut handler, _temp) = create_test_handler();
+
+        let cursor = match handler
+            .handle_request(VeloRequest::ManifestChangesSince { cursor: u64::MAX })
+            .await
+        {
+            VeloResponse::ManifestChanges {
+                cursor, changes, ..
+            } => {
+                assert!(changes.is_empty());
+                cursor
+            }
+            other => panic!("Unexpected response: {:?}", other),
+        };
+
+        let entry = VnodeEntry {
+            content_hash: [0; 32],
+            size: 1,
+            mtime: 0,
+            mode: 0o644,
+            flags: 0,
+            _pad: 0,
+        };
+        for _ in 0..2 {
+            handler
+                .handle_request(VeloRequest::ManifestUpsert {
+                    path: "/src/a.rs".to_string(),
+                    entry: entry.clone(),
+                })
+                .await;
+        }
+        handler
+            .handle_request(VeloRequest::ManifestRename {
+                old_path: "/src/a.rs".to_string(),
+                new_path: "/src/b.rs".to_string(),
+            })
+            .await;
+
+        match handler
+            .handle_request(VeloRequest::ManifestChangesSince { cursor })
+            .await
+        {
+            VeloResponse::ManifestChanges {
+                changes, truncated, ..
+            } => {
+                assert!(!truncated);
+                let kinds: Vec<_> = changes.iter().map(|c| c.kind).collect();
+                assert_eq!(
+                    kinds,
+                    vec![
+                        ManifestChangeKind::Created,
+                        ManifestChangeKind::Modified,
+                        ManifestChangeKind::MovedFrom,
+                        ManifestChangeKind::MovedTo,
+                    ]
+                );
+                assert_eq!(changes[3].path, "/src/b.rs");
+            }
+            other => panic!("Unexpected response: {:?}", other),
+        }
+    }
 }
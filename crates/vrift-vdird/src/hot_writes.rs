@@ -0,0 +1,216 @@
+//! Copy-up of hot-write paths
+//!
+//! Some outputs (`target/debug/incremental/*`, `.o` files) are rewritten on
+//! every build. Each rewrite breaks CoW, stages a private copy and reingests
+//! it on close, which only churns the CAS: the next build replaces the blob
+//! again.
+//!
+//! vDird counts reingests per path. Once a path has been broken
+//! `ingest.hot_write_breaks` times within `ingest.hot_write_window_secs`, its
+//! content is copied up to the real project path and its VDir entry gets
+//! `FLAG_PASSTHROUGH`, so the shim leaves every later call on it to the
+//! kernel and the FS watcher ingests it like any other native file.
+//!
+//! Promoted paths are listed in `.vrift/hot_writes`, one
+//! `<breaks>\t<promoted at>\t<path>` line each. The file is the report users
+//! read and what re-applies the flag when vDird restarts.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Most paths whose breaks are counted at once
+const MAX_TRACKED: usize = 65536;
+
+const REPORT_HEADER: &str =
+    "# Paths copied up after repeated CoW breaks: breaks, promoted at (unix seconds), path\n";
+
+/// A path served from its real file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Promoted {
+    /// Breaks counted when it was promoted
+    pub breaks: u32,
+    /// Unix seconds
+    pub promoted_secs: u64,
+}
+
+/// Breaks counted for a path in the current window
+#[derive(Debug, Clone, Copy)]
+struct Breaks {
+    count: u32,
+    window_start_secs: u64,
+}
+
+/// Per-path CoW break counter and the set of promoted paths
+pub struct HotWrites {
+    report_path: PathBuf,
+    threshold: u32,
+    window_secs: u64,
+    breaks: HashMap<String, Breaks>,
+    promoted: BTreeMap<String, Promoted>,
+}
+
+impl HotWrites {
+    /// Load the promoted paths from `report_path` (missing file = none).
+    /// A `threshold` of 0 never promotes.
+    pub fn load(report_path: PathBuf, threshold: u32, window_secs: u64) -> Self {
+        let promoted = match fs::read_to_string(&report_path) {
+            Ok(text) => parse_report(&text),
+            Err(_) => BTreeMap::new(),
+        };
+        Self {
+            report_path,
+            threshold,
+            window_secs,
+            breaks: HashMap::new(),
+            promoted,
+        }
+    }
+
+    /// Count a CoW break of `path` at `now_secs`; true once the path should
+    /// be promoted
+    pub fn record_break(&mut self, path: &str, now_secs: u64) -> bool {
+        if self.threshold == 0 {
+            return false;
+        }
+        if self.breaks.len() >= MAX_TRACKED && !self.breaks.contains_key(path) {
+            let window = self.window_secs;
+            self.breaks
+                .retain(|_, b| now_secs.saturating_sub(b.window_start_secs) < window);
+            if self.breaks.len() >= MAX_TRACKED {
+                return false;
+            }
+        }
+
+        let window = self.window_secs;
+        let breaks = self.breaks.entry(path.to_string()).or_insert(Breaks {
+            count: 0,
+            window_start_secs: now_secs,
+        });
+        if now_secs.saturating_sub(breaks.window_start_secs) >= window {
+            *breaks = Breaks {
+                count: 0,
+                window_start_secs: now_secs,
+            };
+        }
+        breaks.count += 1;
+        breaks.count >= self.threshold
+    }
+
+    /// Mark `path` promoted and rewrite the report
+    pub fn promote(&mut self, path: &str, now_secs: u64) -> io::Result<()> {
+        let breaks = self.breaks.remove(path).map_or(0, |b| b.count);
+        self.promoted.insert(
+            path.to_string(),
+            Promoted {
+                breaks,
+                promoted_secs: now_secs,
+            },
+        );
+        self.save()
+    }
+
+    /// Forget a promotion (its real file is gone) and rewrite the report
+    pub fn demote(&mut self, path: &str) -> io::Result<()> {
+        if self.promoted.remove(path).is_some() {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    pub fn is_promoted(&self, path: &str) -> bool {
+        self.promoted.contains_key(path)
+    }
+
+    pub fn promoted(&self) -> &BTreeMap<String, Promoted> {
+        &self.promoted
+    }
+
+    fn save(&self) -> io::Result<()> {
+        if let Some(parent) = self.report_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut text = String::from(REPORT_HEADER);
+        for (path, p) in &self.promoted {
+            text.push_str(&format!("{}\t{}\t{}\n", p.breaks, p.promoted_secs, path));
+        }
+        let temp = self.report_path.with_extension("tmp");
+        fs::write(&temp, text)?;
+        fs::rename(&temp, &self.report_path)
+    }
+}
+
+fn parse_report(text: &str) -> BTreeMap<String, Promoted> {
+    text.lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            let breaks = fields.next()?.parse().ok()?;
+            let promoted_secs = fields.next()?.parse().ok()?;
+            let path = fields.next()?;
+            Some((
+                path.to_string(),
+                Promoted {
+                    breaks,
+                    promoted_secs,
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Report location for a project
+pub fn report_path(project_root: &Path) -> PathBuf {
+    project_root.join(".vrift").join("hot_writes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_promotes_after_threshold_within_window() {
+        let temp = tempdir().unwrap();
+        let mut hot = HotWrites::load(report_path(temp.path()), 3, 100);
+
+        assert!(!hot.record_break("/a", 0));
+        assert!(!hot.record_break("/a", 10));
+        // Window expired: counting starts over
+        assert!(!hot.record_break("/a", 200));
+        assert!(!hot.record_break("/a", 210));
+        assert!(hot.record_break("/a", 220));
+        assert!(!hot.record_break("/b", 220));
+
+        let mut off = HotWrites::load(report_path(temp.path()), 0, 100);
+        assert!(!off.record_break("/a", 0));
+    }
+
+    #[test]
+    fn test_report_roundtrip() {
+        let temp = tempdir().unwrap();
+        let path = report_path(temp.path());
+        let mut hot = HotWrites::load(path.clone(), 2, 100);
+        hot.record_break("/target/x.o", 5);
+        hot.record_break("/target/x.o", 6);
+        hot.promote("/target/x.o", 7).unwrap();
+        hot.promote("/target/y.o", 8).unwrap();
+        hot.demote("/target/y.o").unwrap();
+
+        let text = fs::read_to_string(&path).unwrap();
+        assert!(text.starts_with('#'));
+        assert!(text.contains("2\t7\t/target/x.o\n"));
+
+        let reloaded = HotWrites::load(path, 2, 100);
+        assert!(reloaded.is_promoted("/target/x.o"));
+        assert!(!reloaded.is_promoted("/target/y.o"));
+        assert_eq!(
+            reloaded.promoted()["/target/x.o"],
+            Promoted {
+                breaks: 2,
+                promoted_secs: 7
+            }
+        );
+    }
+}
@@ -192,6 +192,14 @@ impl IngestHandler {
 
     fn handle_file_changed(&self, path: &std::path::Path) {
         let rel_path = self.to_manifest_key(path);
+        // An external entry is served from the real file itself, so a change
+        // to that file leaves the entry as it is
+        if let Ok(Some(existing)) = self.manifest.get(&rel_path) {
+            if existing.vnode.is_external() {
+                debug!(path = %rel_path, "Ingest: external entry, nothing to store");
+                return;
+            }
+        }
         let tier = self.classify_tier(&rel_path);
 
         // Zero-copy ingest: reflink → hardlink → copy (RFC-0040)
@@ -207,7 +215,7 @@ impl IngestHandler {
                         let vnode = vrift_ipc::VnodeEntry {
                             content_hash: result.hash,
                             size: result.size,
-                            mtime: meta.mtime() as u64,
+                            mtime: vrift_cas::mtime_nsec_from_metadata(&meta),
                             mode: meta.mode(),
                             flags: 0,
                             _pad: 0,
@@ -268,7 +276,7 @@ impl IngestHandler {
                 let vnode = vrift_ipc::VnodeEntry {
                     content_hash: [0u8; 32], // Directories have empty hash
                     size: 0,
-                    mtime: meta.mtime() as u64,
+                    mtime: vrift_cas::mtime_nsec_from_metadata(&meta),
                     mode: meta.mode(),
                     flags: 1, // Directory flag
                     _pad: 0,
@@ -300,8 +308,6 @@ impl IngestHandler {
         // Use symlink metadata (lstat)
         match std::fs::symlink_metadata(path) {
             Ok(meta) => {
-                use std::os::unix::fs::MetadataExt;
-
                 // Store target path as blob in CAS (for symlink reconstruction)
                 let target_bytes = target.as_os_str().as_encoded_bytes();
                 let content_hash = match self.cas.store(target_bytes) {
@@ -315,7 +321,7 @@ impl IngestHandler {
                 let vnode = vrift_ipc::VnodeEntry {
                     content_hash,
                     size: target_bytes.len() as u64,
-                    mtime: meta.mtime() as u64,
+                    mtime: vrift_cas::mtime_nsec_from_metadata(&meta),
                     mode: 0o777,
                     flags: 2, // Symlink flag
                     _pad: 0,
@@ -343,8 +349,8 @@ impl IngestHandler {
     /// Convert absolute path to manifest key (relative path)
     fn to_manifest_key(&self, path: &std::path::Path) -> String {
         path.strip_prefix(&self.project_root)
-            .map(|p| format!("/{}", p.display()))
-            .unwrap_or_else(|_| path.display().to_string())
+            .map(|p| format!("/{}", vrift_ipc::path_key::from_path(p)))
+            .unwrap_or_else(|_| vrift_ipc::path_key::from_path(path).into_owned())
     }
 }
 
@@ -12,13 +12,20 @@
 //! ## Communication
 //!
 //! Clients (InceptionLayer) communicate via Unix Domain Socket:
-//! - Socket path: `~/.vrift/sockets/<project_id>.sock`
+//! - Socket path: `<runtime dir>/vdird.sock`, or
+//!   `~/.vrift/sockets/<project_id>.sock` without a runtime base
+//!   (see `vrift_config::path::get_runtime_dir`)
+//! - Control socket: `<socket path>.ctl`, serving only queries
 //! - Protocol: rkyv-serialized VeloRequest/VeloResponse
 
+pub mod changes;
+pub mod coalesce;
 pub mod commands;
+pub mod hot_writes;
 pub mod ignore;
 pub mod ingest;
 pub mod journal;
+pub mod metrics;
 pub mod scan;
 pub mod socket;
 pub mod state;
@@ -40,12 +47,16 @@ pub struct ProjectConfig {
     pub vdir_path: PathBuf,
     /// Path to UDS socket
     pub socket_path: PathBuf,
+    /// Path to the UDS socket that serves only queries
+    pub control_socket_path: PathBuf,
     /// Path to staging directory
     pub staging_base: PathBuf,
     /// Path to CAS storage
     pub cas_path: PathBuf,
     /// Path to LMDB manifest
     pub manifest_path: PathBuf,
+    /// Path to the project's packfile, built by vriftd (may not exist)
+    pub pack_path: Option<PathBuf>,
 }
 
 impl ProjectConfig {
@@ -56,28 +67,28 @@ impl ProjectConfig {
             .unwrap_or_else(|| PathBuf::from("/tmp"))
             .join(".vrift");
 
-        // VDir path: use /dev/shm on Linux (fast), tmpdir on macOS
-        #[cfg(target_os = "linux")]
-        let vdir_path = PathBuf::from(format!("/dev/shm/vrift_vdir_{}", &project_id[..16]));
-
-        #[cfg(not(target_os = "linux"))]
-        let vdir_path = {
-            let vdir_dir = vrift_home.join("vdir");
-            std::fs::create_dir_all(&vdir_dir).ok();
-            vdir_dir.join(format!("{}.vdir", &project_id[..16]))
-        };
+        let vdir_path = vrift_config::path::get_vdir_mmap_path(&project_id)
+            .unwrap_or_else(|| project_root.join(".vrift").join("vdir.mmap"));
+
+        let socket_path = std::env::var("VRIFT_SOCKET_PATH")
+            .map(PathBuf::from)
+            .ok()
+            .or_else(|| vrift_config::path::get_vdird_socket_path(&project_id))
+            .unwrap_or_else(|| {
+                vrift_home
+                    .join("sockets")
+                    .join(format!("{}.sock", &project_id[..16]))
+            });
+        let control_socket_path = PathBuf::from(vrift_ipc::control_socket_path(
+            &socket_path.to_string_lossy(),
+        ));
 
         Self {
             project_root: project_root.clone(),
             project_id: project_id.clone(),
             vdir_path,
-            socket_path: std::env::var("VRIFT_SOCKET_PATH")
-                .map(PathBuf::from)
-                .unwrap_or_else(|_| {
-                    vrift_home
-                        .join("sockets")
-                        .join(format!("{}.sock", &project_id[..16]))
-                }),
+            socket_path,
+            control_socket_path,
             staging_base: project_root.join(".vrift").join("staging"),
             cas_path: std::env::var("VR_THE_SOURCE")
                 .map(PathBuf::from)
@@ -89,6 +100,7 @@ impl ProjectConfig {
                     vrift_config::path::get_manifest_db_path(&project_id)
                         .unwrap_or_else(|| project_root.join(".vrift").join("manifest.lmdb"))
                 }),
+            pack_path: vrift_config::path::get_pack_path(&project_id),
         }
     }
 
@@ -108,8 +120,13 @@ pub async fn run_daemon(config: ProjectConfig) -> Result<()> {
         "Starting vdir_d"
     );
 
-    // Ensure directories exist
+    // Ensure directories exist (vriftd creates the runtime directory first,
+    // but vdir_d may also be started on its own)
+    if let Some(dir) = vrift_config::path::get_runtime_dir(&config.project_id) {
+        vrift_config::path::create_runtime_dir(&dir)?;
+    }
     std::fs::create_dir_all(config.socket_path.parent().unwrap())?;
+    std::fs::create_dir_all(config.vdir_path.parent().unwrap())?;
     std::fs::create_dir_all(&config.staging_base)?;
     std::fs::create_dir_all(&config.cas_path)?;
 
@@ -244,6 +261,7 @@ pub async fn run_daemon(config: ProjectConfig) -> Result<()> {
     info!("Periodic commit task started (30s interval)");
 
     let socket_handle = socket::run_listener(config, vdir, manifest.clone());
+    let mut sigterm = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?;
 
     // Wait for any task to complete, or signal for graceful shutdown
     tokio::select! {
@@ -260,7 +278,10 @@ pub async fn run_daemon(config: ProjectConfig) -> Result<()> {
             result?;
         }
         _ = tokio::signal::ctrl_c() => {
-            info!("Received SIGINT/SIGTERM, initiating graceful shutdown...");
+            info!("Received SIGINT, initiating graceful shutdown...");
+        }
+        _ = sigterm.recv() => {
+            info!("Received SIGTERM, initiating graceful shutdown...");
         }
     }
 
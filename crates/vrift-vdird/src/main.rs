@@ -44,6 +44,9 @@ async fn main() -> Result<()> {
 
     info!(path = %project_root.display(), "Starting vdir_d for project");
 
+    // Loading the config sets the Unicode form of the manifest keys we build
+    drop(vrift_config::config());
+
     // Create config and run
     let config = ProjectConfig::from_project_root(project_root);
     run_daemon(config).await
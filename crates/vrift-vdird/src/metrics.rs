@@ -0,0 +1,152 @@
+//! Request and lookup counters for `vrift top`
+//!
+//! Every request a lane executes is counted by kind, and every manifest
+//! lookup by where it was answered: the VDir overlay, LMDB, or neither.
+//! Lookups are also counted per path, so `vrift top` can show which paths
+//! miss the shim's mmap most. The per-path map is bounded: once full, all
+//! counts are halved and paths that drop to zero are forgotten, so a burst of
+//! one-off paths cannot evict the hot ones.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Instant;
+use vrift_ipc::{NamedCount, WorkspaceMetrics};
+
+/// Most paths whose lookups are counted at once
+const MAX_TRACKED: usize = 4096;
+
+/// Hot paths reported in a snapshot
+const HOT_PATHS: usize = 20;
+
+/// Where a manifest lookup was answered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lookup {
+    Vdir,
+    Lmdb,
+    Miss,
+}
+
+#[derive(Default)]
+struct Counters {
+    requests: HashMap<&'static str, u64>,
+    vdir_hits: u64,
+    lmdb_hits: u64,
+    misses: u64,
+    paths: HashMap<String, u64>,
+}
+
+/// Counters shared by the socket lanes and the command handler
+pub struct Metrics {
+    started: Instant,
+    counters: Mutex<Counters>,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            counters: Mutex::default(),
+        }
+    }
+}
+
+impl Metrics {
+    /// Count a request of `kind` (see [`vrift_ipc::VeloRequest::kind`])
+    pub fn record_request(&self, kind: &'static str) {
+        let mut counters = self.counters.lock().unwrap();
+        *counters.requests.entry(kind).or_default() += 1;
+    }
+
+    /// Count a manifest lookup of `path`
+    pub fn record_lookup(&self, path: &str, lookup: Lookup) {
+        let mut counters = self.counters.lock().unwrap();
+        match lookup {
+            Lookup::Vdir => counters.vdir_hits += 1,
+            Lookup::Lmdb => counters.lmdb_hits += 1,
+            Lookup::Miss => counters.misses += 1,
+        }
+        if let Some(count) = counters.paths.get_mut(path) {
+            *count += 1;
+            return;
+        }
+        if counters.paths.len() >= MAX_TRACKED {
+            counters.paths.retain(|_, count| {
+                *count /= 2;
+                *count > 0
+            });
+        }
+        counters.paths.insert(path.to_string(), 1);
+    }
+
+    pub fn snapshot(&self) -> WorkspaceMetrics {
+        let counters = self.counters.lock().unwrap();
+        let mut requests: Vec<NamedCount> = counters
+            .requests
+            .iter()
+            .map(|(kind, count)| NamedCount {
+                name: kind.to_string(),
+                count: *count,
+            })
+            .collect();
+        requests.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut hot: Vec<(&String, &u64)> = counters.paths.iter().collect();
+        hot.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+        let hot_paths = hot
+            .into_iter()
+            .take(HOT_PATHS)
+            .map(|(path, count)| NamedCount {
+                name: path.clone(),
+                count: *count,
+            })
+            .collect();
+
+        WorkspaceMetrics {
+            uptime_secs: self.started.elapsed().as_secs(),
+            requests,
+            vdir_hits: counters.vdir_hits,
+            lmdb_hits: counters.lmdb_hits,
+            misses: counters.misses,
+            hot_paths,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hot_paths_ranked() {
+        let metrics = Metrics::default();
+        for _ in 0..3 {
+            metrics.record_lookup("/a", Lookup::Vdir);
+        }
+        metrics.record_lookup("/b", Lookup::Lmdb);
+        metrics.record_lookup("/c", Lookup::Miss);
+        metrics.record_request("ManifestGet");
+        metrics.record_request("ManifestGet");
+
+        let snap = metrics.snapshot();
+        assert_eq!((snap.vdir_hits, snap.lmdb_hits, snap.misses), (3, 1, 1));
+        assert_eq!(snap.hot_paths[0].name, "/a");
+        assert_eq!(snap.hot_paths[0].count, 3);
+        assert_eq!(snap.requests[0].count, 2);
+    }
+
+    #[test]
+    fn test_full_map_decays() {
+        let metrics = Metrics::default();
+        for _ in 0..4 {
+            metrics.record_lookup("/hot", Lookup::Vdir);
+        }
+        for i in 0..MAX_TRACKED {
+            metrics.record_lookup(&format!("/cold{}", i), Lookup::Miss);
+        }
+        let snap = metrics.snapshot();
+        assert_eq!(snap.hot_paths[0].name, "/hot");
+        assert_eq!(snap.hot_paths[0].count, 2);
+        let tracked = metrics.counters.lock().unwrap().paths.len();
+        assert!(tracked < MAX_TRACKED);
+    }
+}
@@ -1,17 +1,27 @@
 //! Unix Domain Socket listener for vdir_d
 //!
 //! Uses IpcHeader frame protocol for all IPC communication.
+//!
+//! vdir_d listens on two sockets, each with its own accept loop. The data
+//! socket serves every request. The control socket serves only short
+//! read-only queries ([`VeloRequest::is_control`]), so a stat fallback never
+//! waits for a connection slot, or a lane, behind a bulk ingest or a burst of
+//! write-backs. Queries on either socket run under the handler's read lock,
+//! and full-scan ingests run without the lock.
 
-use crate::commands::CommandHandler;
+use crate::coalesce::Coalescer;
+use crate::commands::{self, CommandHandler};
+use crate::metrics::Metrics;
 use crate::vdir::VDir;
 use crate::ProjectConfig;
 use anyhow::Result;
+use std::path::Path;
 use std::sync::Arc;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{UnixListener, UnixStream};
 use tokio::sync::RwLock;
 use tracing::{debug, error, info, warn};
-use vrift_ipc::{IpcHeader, VeloError, VeloRequest, VeloResponse};
+use vrift_ipc::{EventKind, IpcHeader, VeloError, VeloEvent, VeloRequest, VeloResponse};
 
 /// Run the UDS listener loop
 pub async fn run_listener(
@@ -19,45 +29,230 @@ pub async fn run_listener(
     vdir: VDir,
     manifest: std::sync::Arc<vrift_manifest::lmdb::LmdbManifest>,
 ) -> Result<()> {
-    // Remove existing socket if present
-    if config.socket_path.exists() {
-        std::fs::remove_file(&config.socket_path)?;
-    }
-
-    let listener = UnixListener::bind(&config.socket_path)?;
+    let listener = bind(&config.socket_path)?;
     info!(socket = %config.socket_path.display(), "Listening for connections");
+    let control = bind(&config.control_socket_path)?;
+    info!(socket = %config.control_socket_path.display(), "Listening for queries");
 
-    let handler = Arc::new(RwLock::new(CommandHandler::new(
-        config.clone(),
-        vdir,
-        manifest,
-    )));
+    let handler = CommandHandler::new(config.clone(), vdir, manifest);
+    let coalescer = handler.coalescer();
+    let metrics = handler.metrics();
+    let handler = Arc::new(RwLock::new(handler));
 
+    tokio::spawn(accept_loop(
+        control,
+        Socket::Control,
+        Arc::clone(&handler),
+        Arc::clone(&coalescer),
+        Arc::clone(&metrics),
+    ));
+    accept_loop(listener, Socket::Data, handler, coalescer, metrics).await;
+    Ok(())
+}
+
+/// Bind `path`, replacing a socket left over from an earlier run
+fn bind(path: &Path) -> Result<UnixListener> {
+    if path.exists() {
+        std::fs::remove_file(path)?;
+    }
+    Ok(UnixListener::bind(path)?)
+}
+
+async fn accept_loop(
+    listener: UnixListener,
+    socket: Socket,
+    handler: Arc<RwLock<CommandHandler>>,
+    coalescer: Arc<Coalescer>,
+    metrics: Arc<Metrics>,
+) {
     loop {
         match listener.accept().await {
             Ok((stream, _addr)) => {
                 let handler = Arc::clone(&handler);
+                let coalescer = Arc::clone(&coalescer);
+                let metrics = Arc::clone(&metrics);
                 tokio::spawn(async move {
-                    if let Err(e) = handle_client(stream, handler).await {
+                    if let Err(e) = handle_client(stream, socket, handler, coalescer, metrics).await
+                    {
                         warn!(error = %e, "Client handler error");
                     }
                 });
             }
             Err(e) => {
-                error!(error = %e, "Accept failed");
+                error!(error = %e, ?socket, "Accept failed");
             }
         }
     }
 }
 
-/// Handle a single client connection using IpcHeader frame protocol
-async fn handle_client(mut stream: UnixStream, handler: Arc<RwLock<CommandHandler>>) -> Result<()> {
+/// Which of vdir_d's sockets a connection came in on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Socket {
+    /// Serves every request
+    Data,
+    /// Serves only queries
+    Control,
+}
+
+/// Requests a connection may have in flight before its reader stops pulling
+/// frames off the socket
+const LANE_DEPTH: usize = 64;
+
+/// Work item on a connection's ordered lane
+enum LaneItem {
+    /// Decoded request to execute
+    Request { seq_id: u32, request: VeloRequest },
+    /// Frame that failed to decode; answered in order with this error
+    Reject { seq_id: u32, response: VeloResponse },
+}
+
+/// Handle a single client connection using IpcHeader frame protocol.
+///
+/// The connection is split into a reader and an ordered execution lane. The
+/// reader decodes frames as they arrive, so clients may pipeline requests;
+/// the lane executes them strictly one at a time in arrival order and writes
+/// each response tagged with its request's `seq_id`.
+///
+/// Consistency model:
+/// - Within a connection, requests take effect in the order they were sent
+///   and every request observes the effects of all earlier ones (an upsert
+///   followed by a get of the same path returns the upserted entry, even if
+///   the get was sent before the upsert's response arrived).
+/// - Responses are returned in request order; `seq_id` lets clients verify
+///   the pairing.
+/// - Across connections there is no ordering beyond what clients establish
+///   themselves: a mutation is visible to other connections once its
+///   response has been sent.
+/// - A `ManifestReingest` (checked or not) is answered once its path has
+///   seen no newer write for `ingest.dedup_window_ms` (see
+///   [`crate::coalesce`]); superseded writes get the response of the write
+///   that replaced them.
+async fn handle_client(
+    stream: UnixStream,
+    socket: Socket,
+    handler: Arc<RwLock<CommandHandler>>,
+    coalescer: Arc<Coalescer>,
+    metrics: Arc<Metrics>,
+) -> Result<()> {
     debug!("New client connected");
 
+    let (mut reader, mut writer) = stream.into_split();
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<LaneItem>(LANE_DEPTH);
+
+    let lane = tokio::spawn(async move {
+        while let Some(item) = rx.recv().await {
+            if let LaneItem::Request { request, .. } = &item {
+                metrics.record_request(request.kind());
+            }
+            let (seq_id, response) = match item {
+                LaneItem::Request {
+                    seq_id,
+                    request: VeloRequest::Subscribe { events },
+                } => {
+                    // Allowed on either socket: events only read
+                    let events: Vec<EventKind> = events
+                        .into_iter()
+                        .filter(|kind| *kind == EventKind::ManifestChange)
+                        .collect();
+                    if events.is_empty() {
+                        (seq_id, VeloResponse::SubscribeAck { events })
+                    } else {
+                        debug!("Client subscribed to manifest changes");
+                        let changes = handler.read().await.subscribe_changes();
+                        send_response(&mut writer, &VeloResponse::SubscribeAck { events }, seq_id)
+                            .await?;
+                        return push_changes(&mut writer, &mut rx, changes, seq_id).await;
+                    }
+                }
+                LaneItem::Request { seq_id, request } if request.is_control() => {
+                    debug!(?request, "Received query");
+                    (seq_id, handler.read().await.handle_query(request))
+                }
+                LaneItem::Request { seq_id, request } if socket == Socket::Control => {
+                    warn!(
+                        kind = request.kind(),
+                        "Non-query request on the control socket"
+                    );
+                    let response = VeloResponse::Error(VeloError::internal(format!(
+                        "{} must be sent to the data socket",
+                        request.kind()
+                    )));
+                    (seq_id, response)
+                }
+                LaneItem::Request {
+                    seq_id,
+                    request: VeloRequest::ManifestReingest { vpath, temp_path },
+                } => {
+                    debug!(vpath = %vpath, "Received reingest");
+                    // Waits for the path's writes to quiesce without holding
+                    // the handler lock
+                    let response = coalescer.reingest(&handler, vpath, temp_path, None).await;
+                    (seq_id, response)
+                }
+                LaneItem::Request {
+                    seq_id,
+                    request:
+                        VeloRequest::ManifestReingestChecked {
+                            vpath,
+                            temp_path,
+                            base_hash,
+                        },
+                } => {
+                    debug!(vpath = %vpath, "Received checked reingest");
+                    let response = coalescer
+                        .reingest(&handler, vpath, temp_path, Some(base_hash))
+                        .await;
+                    (seq_id, response)
+                }
+                LaneItem::Request {
+                    seq_id,
+                    request: request @ VeloRequest::IngestFullScan { .. },
+                } => {
+                    debug!(?request, "Received full scan");
+                    let cas_path = handler.read().await.cas_path().to_path_buf();
+                    let response = tokio::task::spawn_blocking(move || {
+                        commands::ingest_full_scan(&cas_path, request)
+                    })
+                    .await
+                    .unwrap_or_else(|e| {
+                        VeloResponse::Error(VeloError::internal(format!(
+                            "Full scan task failed: {}",
+                            e
+                        )))
+                    });
+                    (seq_id, response)
+                }
+                LaneItem::Request { seq_id, request } => {
+                    debug!(?request, "Received request");
+                    let mut h = handler.write().await;
+                    (seq_id, h.handle_request(request).await)
+                }
+                LaneItem::Reject { seq_id, response } => (seq_id, response),
+            };
+            // Send response with matching seq_id
+            send_response(&mut writer, &response, seq_id).await?;
+        }
+        Ok::<(), anyhow::Error>(())
+    });
+
+    let read_result = read_requests(&mut reader, &tx).await;
+    // Let the lane drain whatever was already accepted, then finish
+    drop(tx);
+    let lane_result = lane
+        .await
+        .map_err(|e| anyhow::anyhow!("Lane task failed: {}", e))?;
+    read_result.and(lane_result)
+}
+
+/// Decode frames from `reader` onto the lane until EOF or a protocol error
+async fn read_requests<R: AsyncReadExt + Unpin>(
+    reader: &mut R,
+    lane: &tokio::sync::mpsc::Sender<LaneItem>,
+) -> Result<()> {
     loop {
         // Read IpcHeader (12 bytes)
         let mut header_buf = [0u8; IpcHeader::SIZE];
-        match stream.read_exact(&mut header_buf).await {
+        match reader.read_exact(&mut header_buf).await {
             Ok(_) => {}
             Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
                 debug!("Client disconnected");
@@ -106,60 +301,92 @@ async fn handle_client(mut stream: UnixStream, handler: Arc<RwLock<CommandHandle
             return Ok(());
         }
 
-        // Read payload
+        // Read payload, then the continuation frames of a large request
         let mut payload = vec![0u8; header.length as usize];
         if !payload.is_empty() {
-            stream.read_exact(&mut payload).await?;
+            reader.read_exact(&mut payload).await?;
+        }
+        let mut more = header.has_more();
+        while more {
+            let mut next_buf = [0u8; IpcHeader::SIZE];
+            reader.read_exact(&mut next_buf).await?;
+            let next = IpcHeader::from_bytes(&next_buf);
+            if let Err(e) = header.check_continuation(&next, payload.len()) {
+                warn!(error = %e, "Bad continuation frame, dropping client");
+                return Ok(());
+            }
+            let start = payload.len();
+            payload.resize(start + next.length as usize, 0);
+            reader.read_exact(&mut payload[start..]).await?;
+            more = next.has_more();
         }
 
         // Deserialize request
-        let request: VeloRequest =
-            match rkyv::from_bytes::<VeloRequest, rkyv::rancor::Error>(&payload) {
-                Ok(req) => req,
-                Err(e) => {
-                    warn!(error = %e, "Failed to deserialize request");
-                    let response = VeloResponse::Error(VeloError::internal(format!(
+        let seq_id = header.seq_id;
+        let item = match rkyv::from_bytes::<VeloRequest, rkyv::rancor::Error>(&payload) {
+            Ok(request) => LaneItem::Request { seq_id, request },
+            Err(e) => {
+                warn!(error = %e, "Failed to deserialize request");
+                LaneItem::Reject {
+                    seq_id,
+                    response: VeloResponse::Error(VeloError::internal(format!(
                         "Deserialize error: {}",
                         e
-                    )));
-                    send_response(&mut stream, &response, header.seq_id).await?;
-                    continue;
+                    ))),
                 }
-            };
+            }
+        };
 
-        debug!(?request, "Received request");
+        // Backpressure: waits while the lane is full; fails once the lane has
+        // stopped (e.g. the client went away mid-write)
+        if lane.send(item).await.is_err() {
+            return Ok(());
+        }
+    }
+}
 
-        // Handle request
-        let response = {
-            let mut h = handler.write().await;
-            h.handle_request(request).await
+/// Push manifest changes to a subscribed connection until the client goes
+/// away or sends another request
+async fn push_changes<W: AsyncWriteExt + Unpin>(
+    writer: &mut W,
+    lane: &mut tokio::sync::mpsc::Receiver<LaneItem>,
+    mut changes: tokio::sync::broadcast::Receiver<vrift_ipc::ManifestChange>,
+    seq_id: u32,
+) -> Result<()> {
+    use tokio::sync::broadcast::error::RecvError;
+    loop {
+        let event = tokio::select! {
+            change = changes.recv() => match change {
+                Ok(change) => VeloEvent::ManifestChanged { change },
+                Err(RecvError::Lagged(missed)) => VeloEvent::Lagged { missed },
+                Err(RecvError::Closed) => return Ok(()),
+            },
+            item = lane.recv() => {
+                if item.is_some() {
+                    debug!("Request on a subscribed connection, closing it");
+                }
+                return Ok(());
+            }
         };
-
-        // Send response with matching seq_id
-        send_response(&mut stream, &response, header.seq_id).await?;
+        vrift_ipc::frame_async::send_event(writer, &event, seq_id).await?;
     }
 }
 
 /// Send response using IpcHeader frame protocol
-async fn send_response(
-    stream: &mut UnixStream,
+async fn send_response<W: AsyncWriteExt + Unpin>(
+    stream: &mut W,
     response: &VeloResponse,
     seq_id: u32,
 ) -> Result<()> {
     let payload = rkyv::to_bytes::<rkyv::rancor::Error>(response)
         .map_err(|e| anyhow::anyhow!("Serialize error: {}", e))?;
 
-    if payload.len() > IpcHeader::MAX_LENGTH {
-        return Err(anyhow::anyhow!(
-            "Response too large: {} bytes",
-            payload.len()
-        ));
+    let frames = vrift_ipc::message_frames(vrift_ipc::FrameType::Response, seq_id, &payload)
+        .map_err(|e| anyhow::anyhow!("Response too large: {}", e))?;
+    for (header, part) in frames {
+        stream.write_all(&header.to_bytes()).await?;
+        stream.write_all(part).await?;
     }
-
-    let header = IpcHeader::new_response(payload.len() as u32, seq_id);
-
-    stream.write_all(&header.to_bytes()).await?;
-    stream.write_all(&payload).await?;
     Ok(())
 }
 
@@ -187,4 +414,222 @@ mod tests {
         assert!(result.is_ok());
         handle.await.unwrap().unwrap();
     }
+
+    #[tokio::test]
+    async fn test_pipelined_requests_execute_in_order() {
+        use vrift_ipc::frame_async::{read_response, send_request};
+
+        let temp = tempdir().unwrap();
+        let config = ProjectConfig::from_project_root(temp.path().to_path_buf());
+        let vdir = VDir::create_or_open(&temp.path().join("test.vdir")).unwrap();
+        let manifest = Arc::new(
+            vrift_manifest::lmdb::LmdbManifest::open(temp.path().join("manifest.lmdb")).unwrap(),
+        );
+        let handler = CommandHandler::new(config, vdir, manifest);
+        let coalescer = handler.coalescer();
+        let metrics = handler.metrics();
+        let handler = Arc::new(RwLock::new(handler));
+
+        let (mut client, server) = UnixStream::pair().unwrap();
+        let server_task = tokio::spawn(handle_client(
+            server,
+            Socket::Data,
+            handler,
+            coalescer,
+            metrics,
+        ));
+
+        // Send the get before the upsert's response has been read
+        let entry = vrift_ipc::VnodeEntry {
+            content_hash: [7; 32],
+            size: 42,
+            mtime: 1,
+            mode: 0o644,
+            flags: 0,
+            _pad: 0,
+        };
+        let upsert_seq = send_request(
+            &mut client,
+            &VeloRequest::ManifestUpsert {
+                path: "src/lib.rs".to_string(),
+                entry,
+            },
+        )
+        .await
+        .unwrap();
+        let get_seq = send_request(
+            &mut client,
+            &VeloRequest::ManifestGet {
+                path: "src/lib.rs".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+
+        let (header, _) = read_response(&mut client).await.unwrap();
+        assert_eq!(header.seq_id, upsert_seq);
+        let (header, response) = read_response(&mut client).await.unwrap();
+        assert_eq!(header.seq_id, get_seq);
+        match response {
+            VeloResponse::ManifestAck { entry: Some(e) } => assert_eq!(e.size, 42),
+            other => panic!("Expected upserted entry, got {:?}", other),
+        }
+
+        send_request(&mut client, &VeloRequest::Metrics)
+            .await
+            .unwrap();
+        match read_response(&mut client).await.unwrap().1 {
+            VeloResponse::MetricsAck { metrics } => {
+                assert_eq!(metrics.vdir_hits, 1);
+                assert_eq!(metrics.hot_paths[0].name, "src/lib.rs");
+                let kinds: Vec<&str> = metrics.requests.iter().map(|r| r.name.as_str()).collect();
+                assert_eq!(kinds, ["ManifestGet", "ManifestUpsert", "Metrics"]);
+            }
+            other => panic!("Expected metrics, got {:?}", other),
+        }
+
+        drop(client);
+        server_task.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_control_socket_serves_only_queries() {
+        use vrift_ipc::frame_async::{read_response, send_request};
+
+        let temp = tempdir().unwrap();
+        let config = ProjectConfig::from_project_root(temp.path().to_path_buf());
+        let vdir = VDir::create_or_open(&temp.path().join("test.vdir")).unwrap();
+        let manifest = Arc::new(
+            vrift_manifest::lmdb::LmdbManifest::open(temp.path().join("manifest.lmdb")).unwrap(),
+        );
+        let handler = CommandHandler::new(config, vdir, manifest);
+        let coalescer = handler.coalescer();
+        let metrics = handler.metrics();
+        let handler = Arc::new(RwLock::new(handler));
+
+        let (mut client, server) = UnixStream::pair().unwrap();
+        let server_task = tokio::spawn(handle_client(
+            server,
+            Socket::Control,
+            Arc::clone(&handler),
+            coalescer,
+            metrics,
+        ));
+
+        // Queries only share the handler lock
+        let reader = handler.read().await;
+        let get = VeloRequest::ManifestGet {
+            path: "src/lib.rs".to_string(),
+        };
+        send_request(&mut client, &get).await.unwrap();
+        let (_, response) = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            read_response(&mut client),
+        )
+        .await
+        .expect("query blocked behind the handler lock")
+        .unwrap();
+        assert!(matches!(
+            response,
+            VeloResponse::ManifestAck { entry: None }
+        ));
+        drop(reader);
+
+        let upsert = VeloRequest::ManifestUpsert {
+            path: "src/lib.rs".to_string(),
+            entry: vrift_ipc::VnodeEntry {
+                content_hash: [7; 32],
+                size: 42,
+                mtime: 1,
+                mode: 0o644,
+                flags: 0,
+                _pad: 0,
+            },
+        };
+        send_request(&mut client, &upsert).await.unwrap();
+        let (_, response) = read_response(&mut client).await.unwrap();
+        assert!(matches!(response, VeloResponse::Error(_)));
+
+        send_request(&mut client, &get).await.unwrap();
+        let (_, response) = read_response(&mut client).await.unwrap();
+        assert!(matches!(
+            response,
+            VeloResponse::ManifestAck { entry: None }
+        ));
+
+        drop(client);
+        server_task.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_subscribed_connection_receives_changes() {
+        use vrift_ipc::frame_async::{read_event, read_response, send_request};
+
+        let temp = tempdir().unwrap();
+        let config = ProjectConfig::from_project_root(temp.path().to_path_buf());
+        let vdir = VDir::create_or_open(&temp.path().join("test.vdir")).unwrap();
+        let manifest = Arc::new(
+            vrift_manifest::lmdb::LmdbManifest::open(temp.path().join("manifest.lmdb")).unwrap(),
+        );
+        let handler = CommandHandler::new(config, vdir, manifest);
+        let coalescer = handler.coalescer();
+        let metrics = handler.metrics();
+        let handler = Arc::new(RwLock::new(handler));
+
+        let (mut client, server) = UnixStream::pair().unwrap();
+        let server_task = tokio::spawn(handle_client(
+            server,
+            Socket::Control,
+            Arc::clone(&handler),
+            coalescer,
+            metrics,
+        ));
+
+        let subscribe = VeloRequest::Subscribe {
+            events: vec![EventKind::Gc, EventKind::ManifestChange],
+        };
+        let seq = send_request(&mut client, &subscribe).await.unwrap();
+        let (_, response) = read_response(&mut client).await.unwrap();
+        match response {
+            VeloResponse::SubscribeAck { events } => {
+                assert_eq!(events, vec![EventKind::ManifestChange])
+            }
+            other => panic!("Expected SubscribeAck, got {:?}", other),
+        }
+
+        let upsert = VeloRequest::ManifestUpsert {
+            path: "src/lib.rs".to_string(),
+            entry: vrift_ipc::VnodeEntry {
+                content_hash: [7; 32],
+                size: 42,
+                mtime: 1,
+                mode: 0o644,
+                flags: 0,
+                _pad: 0,
+            },
+        };
+        handler.write().await.handle_request(upsert).await;
+
+        let (header, event) =
+            tokio::time::timeout(std::time::Duration::from_secs(5), read_event(&mut client))
+                .await
+                .expect("change was not pushed")
+                .unwrap();
+        assert_eq!(header.seq_id, seq);
+        match event {
+            VeloEvent::ManifestChanged { change } => assert!(change.path.ends_with("src/lib.rs")),
+            other => panic!("Expected ManifestChanged, got {:?}", other),
+        }
+
+        // Any further request ends the subscription
+        send_request(&mut client, &VeloRequest::Status)
+            .await
+            .unwrap();
+        let mut rest = Vec::new();
+        client.read_to_end(&mut rest).await.unwrap();
+        assert!(rest.is_empty());
+
+        drop(client);
+        server_task.await.unwrap().unwrap();
+    }
 }
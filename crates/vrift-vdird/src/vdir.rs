@@ -14,6 +14,11 @@ pub use vrift_ipc::vdir_types::*;
 pub struct VDir {
     mmap: MmapMut,
     capacity: usize,
+    /// Offset of the path check table (0 = none), fixed like `capacity`
+    /// until this VDir resizes the table itself
+    check_offset: usize,
+    /// Offset of the pack reference table (0 = none), like `check_offset`
+    pack_ref_offset: usize,
     path: std::path::PathBuf,
 }
 
@@ -21,7 +26,7 @@ impl VDir {
     /// Create or open existing VDir mmap file
     pub fn create_or_open(path: &Path) -> Result<Self> {
         let capacity = VDIR_DEFAULT_CAPACITY;
-        let file_size = VDIR_HEADER_SIZE + (capacity * VDIR_ENTRY_SIZE);
+        let file_size = Self::file_size(capacity);
 
         let file = OpenOptions::new()
             .read(true)
@@ -39,37 +44,88 @@ impl VDir {
         }
 
         // mmap the file
-        let mut mmap = unsafe { MmapMut::map_mut(&file)? };
+        let mmap = unsafe { MmapMut::map_mut(&file)? };
+        let mut vdir = Self {
+            mmap,
+            capacity,
+            check_offset: 0,
+            pack_ref_offset: 0,
+            path: path.to_path_buf(),
+        };
 
         // Initialize or validate header
-        let header = unsafe { &mut *(mmap.as_mut_ptr() as *mut VDirHeader) };
-        let needs_init = header.magic != VDIR_MAGIC || header.version != VDIR_VERSION;
+        let header = vdir.header();
+        let needs_init = header.magic != VDIR_MAGIC || !vdir_version_supported(header.version);
 
         if needs_init {
-            if header.magic == VDIR_MAGIC && header.version < VDIR_VERSION {
+            if header.magic == VDIR_MAGIC {
+                warn!(
+                    version = header.version,
+                    "Unsupported VDir version, rebuilding"
+                );
+            }
+            vdir.grow_to(file_size)?;
+            vdir.generation().store(0, Ordering::Release);
+            vdir.update_header(|h| {
+                *h = VDirHeader {
+                    magic: VDIR_MAGIC,
+                    version: VDIR_VERSION,
+                    generation: 0,
+                    entry_count: 0,
+                    table_capacity: capacity as u32,
+                    table_offset: VDIR_HEADER_SIZE as u32,
+                    crc32: 0,
+                    check_offset: Self::check_offset_for(capacity) as u32,
+                    pack_ref_offset: Self::pack_ref_offset_for(capacity) as u32,
+                    _pad: [0; 24],
+                }
+            });
+            vdir.seal_header();
+            vdir.mmap.flush()?;
+            debug!("Initialized VDir header");
+        } else {
+            vdir.capacity = header.table_capacity as usize;
+
+            // Older layouts we still read are migrated in place: v1 → v2
+            // only adds the header CRC, v2 → v3 the check table and v3 → v4
+            // the pack reference table, the entry table is reused as is. A
+            // v2 file may have been written by an older vDird since this one
+            // last kept its check table, so the table starts out empty:
+            // existing entries are matched on `path_hash` alone until they
+            // are next written. Pack references are likewise dropped, as the
+            // entries they belonged to may have changed.
+            if header.version < VDIR_VERSION {
                 info!(
                     old_version = header.version,
                     new_version = VDIR_VERSION,
-                    "Upgrading VDir version"
+                    "Upgrading VDir version in place"
                 );
+                let capacity = vdir.capacity;
+                let check_offset = if header.version < 3 {
+                    let offset = header.table_offset as usize + capacity * VDIR_ENTRY_SIZE;
+                    vdir.grow_to(offset + capacity * VDIR_CHECK_SIZE)?;
+                    vdir.mmap[offset..offset + capacity * VDIR_CHECK_SIZE].fill(0);
+                    offset
+                } else {
+                    header.check_offset as usize
+                };
+                let pack_ref_offset = check_offset + capacity * VDIR_CHECK_SIZE;
+                let pack_ref_end = pack_ref_offset + capacity * VDIR_PACK_REF_SIZE;
+                vdir.grow_to(pack_ref_end)?;
+                vdir.mmap[pack_ref_offset..pack_ref_end].fill(0);
+                vdir.update_header(|h| {
+                    h.version = VDIR_VERSION;
+                    h.check_offset = check_offset as u32;
+                    h.pack_ref_offset = pack_ref_offset as u32;
+                });
+                vdir.seal_header();
+                vdir.mmap.flush()?;
             }
-            *header = VDirHeader {
-                magic: VDIR_MAGIC,
-                version: VDIR_VERSION,
-                generation: 0,
-                entry_count: 0,
-                table_capacity: capacity as u32,
-                table_offset: VDIR_HEADER_SIZE as u32,
-                crc32: 0,
-                _pad: [0; 32],
-            };
-            header.crc32 = Self::compute_header_crc(header);
-            mmap.flush()?;
-            debug!("Initialized VDir header");
-        } else {
+
             // Validate CRC
+            let header = vdir.header();
             let stored_crc = header.crc32;
-            let computed_crc = Self::compute_header_crc(header);
+            let computed_crc = Self::compute_header_crc(&header);
             if stored_crc != computed_crc {
                 warn!(
                     stored = stored_crc,
@@ -89,66 +145,190 @@ impl VDir {
                     recovered_gen = recovered_gen,
                     "VDir generation stuck at odd value (previous writer crashed). Recovering."
                 );
-                let gen_ptr = &header.generation as *const u64;
-                let atomic = unsafe { &*(gen_ptr as *const AtomicU64) };
-                atomic.store(recovered_gen, Ordering::Release);
+                vdir.generation()
+                    .store(recovered_gen.to_le(), Ordering::Release);
                 // Recompute CRC with updated generation
-                header.crc32 = Self::compute_header_crc(header);
-                mmap.flush()?;
+                vdir.seal_header();
+                vdir.mmap.flush()?;
             }
         }
 
-        Ok(Self {
-            mmap,
-            capacity,
-            path: path.to_path_buf(),
-        })
+        vdir.check_offset = vdir.header().check_offset as usize;
+        vdir.pack_ref_offset = vdir.header().pack_ref_offset as usize;
+        Ok(vdir)
+    }
+
+    /// File length for a table of `capacity` slots
+    fn file_size(capacity: usize) -> usize {
+        VDIR_HEADER_SIZE + capacity * (VDIR_ENTRY_SIZE + VDIR_CHECK_SIZE + VDIR_PACK_REF_SIZE)
+    }
+
+    /// Check table offset for a table of `capacity` slots
+    fn check_offset_for(capacity: usize) -> usize {
+        VDIR_HEADER_SIZE + capacity * VDIR_ENTRY_SIZE
+    }
+
+    /// Pack reference table offset for a table of `capacity` slots
+    fn pack_ref_offset_for(capacity: usize) -> usize {
+        Self::check_offset_for(capacity) + capacity * VDIR_CHECK_SIZE
     }
 
-    /// Compute CRC32 of header fields (excluding crc32 field itself)
+    /// Extend the file to at least `len` bytes and map it again
+    fn grow_to(&mut self, len: usize) -> Result<()> {
+        if self.mmap.len() >= len {
+            return Ok(());
+        }
+        let file = OpenOptions::new().read(true).write(true).open(&self.path)?;
+        file.set_len(len as u64)?;
+        self.mmap = unsafe { MmapMut::map_mut(&file)? };
+        Ok(())
+    }
+
+    /// Compute CRC32 of header fields (excluding crc32 field itself).
+    /// v1 headers carry no CRC, so the field stays zero for them.
     fn compute_header_crc(header: &VDirHeader) -> u32 {
-        // CRC32 of first 28 bytes (magic + version + generation + entry_count + table_capacity + table_offset)
+        if header.version < 2 {
+            return 0;
+        }
+        // CRC32 of the first 28 bytes as stored (magic + version + generation
+        // + entry_count + table_capacity + table_offset, little-endian)
+        let stored = header.to_le();
         let bytes = unsafe {
             std::slice::from_raw_parts(
-                header as *const VDirHeader as *const u8,
+                &stored as *const VDirHeader as *const u8,
                 28, // Bytes before crc32 field
             )
         };
         crc32fast::hash(bytes)
     }
 
-    /// Get header reference
-    fn header(&self) -> &VDirHeader {
-        unsafe { &*(self.mmap.as_ptr() as *const VDirHeader) }
+    /// Decoded copy of the header. The mmap holds it little-endian.
+    fn header(&self) -> VDirHeader {
+        unsafe { std::ptr::read(self.mmap.as_ptr() as *const VDirHeader) }.to_le()
     }
 
-    /// Get mutable header reference
-    fn header_mut(&mut self) -> &mut VDirHeader {
-        unsafe { &mut *(self.mmap.as_mut_ptr() as *mut VDirHeader) }
+    /// Read-modify-write every header field except `generation`, which only
+    /// changes through the seqlock atomic.
+    fn update_header(&mut self, f: impl FnOnce(&mut VDirHeader)) {
+        let mut header = self.header();
+        f(&mut header);
+        let stored = header.to_le();
+        let raw = unsafe { &mut *(self.mmap.as_mut_ptr() as *mut VDirHeader) };
+        raw.magic = stored.magic;
+        raw.version = stored.version;
+        raw.entry_count = stored.entry_count;
+        raw.table_capacity = stored.table_capacity;
+        raw.table_offset = stored.table_offset;
+        raw.crc32 = stored.crc32;
+        raw.check_offset = stored.check_offset;
+        raw.pack_ref_offset = stored.pack_ref_offset;
     }
 
-    /// Get entry table slice
-    fn entries(&self) -> &[VDirEntry] {
-        let offset = self.header().table_offset as usize;
-        unsafe {
-            std::slice::from_raw_parts(
-                self.mmap.as_ptr().add(offset) as *const VDirEntry,
-                self.capacity,
-            )
+    /// Recompute the header CRC for the current field values
+    fn seal_header(&mut self) {
+        let crc = Self::compute_header_crc(&self.header());
+        self.update_header(|h| h.crc32 = crc);
+    }
+
+    /// Seqlock counter (little-endian value)
+    fn generation(&self) -> &AtomicU64 {
+        unsafe { &*(self.mmap.as_ptr().add(HDR_GENERATION) as *const AtomicU64) }
+    }
+
+    /// Decoded entry in `slot`, with `path_check` and the pack fields from
+    /// their tables (0 if the file has none)
+    fn read_entry(&self, slot: usize) -> VDirEntry {
+        let header = self.header();
+        let base = self.mmap.as_ptr();
+        let mut entry = unsafe {
+            VDirEntry::read_le(base.add(header.table_offset as usize + slot * VDIR_ENTRY_SIZE))
+        };
+        if self.check_offset != 0 {
+            let at = self.check_offset + slot * VDIR_CHECK_SIZE;
+            entry.path_check = unsafe { read_le_u64(base, at) };
+        }
+        if self.pack_ref_offset != 0 {
+            let at = self.pack_ref_offset + slot * VDIR_PACK_REF_SIZE;
+            unsafe {
+                entry.pack_offset = read_le_u64(base, at + PREF_OFFSET);
+                entry.pack_id = read_le_u32(base, at + PREF_ID);
+                entry.pack_len = read_le_u32(base, at + PREF_LEN);
+            }
         }
+        entry
     }
 
-    /// Get mutable entry table slice
-    fn entries_mut(&mut self) -> &mut [VDirEntry] {
-        let offset = self.header().table_offset as usize;
-        unsafe {
-            std::slice::from_raw_parts_mut(
-                self.mmap.as_mut_ptr().add(offset) as *mut VDirEntry,
-                self.capacity,
-            )
+    /// Store `entry` in `slot`, its `path_check` in the check table and its
+    /// pack fields in the pack reference table
+    fn write_entry(&mut self, slot: usize, entry: &VDirEntry) {
+        let header = self.header();
+        let stored = entry.to_le();
+        let at = header.table_offset as usize + slot * VDIR_ENTRY_SIZE;
+        let bytes = unsafe {
+            std::slice::from_raw_parts(&stored as *const VDirEntry as *const u8, VDIR_ENTRY_SIZE)
+        };
+        self.mmap[at..at + VDIR_ENTRY_SIZE].copy_from_slice(bytes);
+        if self.check_offset != 0 {
+            let at = self.check_offset + slot * VDIR_CHECK_SIZE;
+            self.mmap[at..at + VDIR_CHECK_SIZE].copy_from_slice(&entry.path_check.to_le_bytes());
+        }
+        if self.pack_ref_offset != 0 {
+            let at = self.pack_ref_offset + slot * VDIR_PACK_REF_SIZE;
+            let slot_ref = &mut self.mmap[at..at + VDIR_PACK_REF_SIZE];
+            slot_ref[PREF_OFFSET..PREF_ID].copy_from_slice(&entry.pack_offset.to_le_bytes());
+            slot_ref[PREF_ID..PREF_LEN].copy_from_slice(&entry.pack_id.to_le_bytes());
+            slot_ref[PREF_LEN..].copy_from_slice(&entry.pack_len.to_le_bytes());
         }
     }
 
+    /// Carry the pack reference of the entry in `slot` over to `entry` if it
+    /// names none and the content is unchanged, so rewriting an entry (a
+    /// re-ingest, a flag change) doesn't drop it
+    fn keep_pack_ref(&self, slot: usize, entry: &mut VDirEntry) {
+        if entry.pack_id != 0 {
+            return;
+        }
+        let old = self.read_entry(slot);
+        if !old.is_empty() && old.cas_hash == entry.cas_hash && old.size == entry.size {
+            entry.pack_offset = old.pack_offset;
+            entry.pack_id = old.pack_id;
+            entry.pack_len = old.pack_len;
+        }
+    }
+
+    /// Format version currently written to the mmap
+    pub fn version(&self) -> u32 {
+        self.header().version
+    }
+
+    /// Emit an older (or the current) format version so shims from previous
+    /// releases keep their zero-IPC stat path. Layouts from
+    /// `VDIR_MIN_VERSION` up share the same entry table, so only the header
+    /// is rewritten; the check and pack reference tables stay in place and
+    /// are ignored by shims that predate them.
+    pub fn set_version(&mut self, version: u32) -> Result<()> {
+        if !vdir_version_supported(version) {
+            anyhow::bail!(
+                "VDir version {} not supported (supported: {}..={})",
+                version,
+                VDIR_MIN_VERSION,
+                VDIR_VERSION
+            );
+        }
+        if self.header().version != version {
+            info!(
+                old_version = self.header().version,
+                new_version = version,
+                "Switching VDir format version"
+            );
+            self.begin_write();
+            self.update_header(|h| h.version = version);
+            self.end_write();
+            self.flush()?;
+        }
+        Ok(())
+    }
+
     /// Open an existing VDir in read-only mode (for observability)
     pub fn open_readonly(path: &Path) -> Result<Self> {
         let file = OpenOptions::new()
@@ -157,7 +337,10 @@ impl VDir {
             .context("Failed to open VDir file in read-only mode")?;
 
         let mmap_ro = unsafe { memmap2::Mmap::map(&file)? };
-        let header = unsafe { &*(mmap_ro.as_ptr() as *const VDirHeader) };
+        if mmap_ro.len() < VDIR_HEADER_SIZE {
+            anyhow::bail!("VDir file too small: {} bytes", mmap_ro.len());
+        }
+        let header = unsafe { std::ptr::read(mmap_ro.as_ptr() as *const VDirHeader) }.to_le();
 
         if header.magic != VDIR_MAGIC {
             anyhow::bail!("Invalid VDir magic: {:x}", header.magic);
@@ -172,6 +355,8 @@ impl VDir {
         Ok(Self {
             mmap,
             capacity,
+            check_offset: header.check_offset as usize,
+            pack_ref_offset: header.pack_ref_offset as usize,
             path: path.to_path_buf(),
         })
     }
@@ -180,15 +365,14 @@ impl VDir {
     /// Stores current_gen + 1 (odd) with Release ordering to signal "write in progress".
     /// Readers seeing an odd generation will spin-wait.
     pub fn begin_write(&mut self) {
-        let gen_ptr = &self.header().generation as *const u64;
-        let atomic = unsafe { &*(gen_ptr as *const AtomicU64) };
-        let current = atomic.load(Ordering::Relaxed);
+        let atomic = self.generation();
+        let current = u64::from_le(atomic.load(Ordering::Relaxed));
         debug_assert!(
             current & 1 == 0,
             "begin_write called while already writing (gen={})",
             current
         );
-        atomic.store(current + 1, Ordering::Release);
+        atomic.store((current + 1).to_le(), Ordering::Release);
     }
 
     /// Seqlock writer: end a write transaction.
@@ -196,89 +380,158 @@ impl VDir {
     /// Also recomputes header CRC.
     pub fn end_write(&mut self) {
         // Recompute CRC before bumping to even (readers validate CRC after gen check)
-        self.header_mut().crc32 = Self::compute_header_crc(self.header());
-        let gen_ptr = &self.header().generation as *const u64;
-        let atomic = unsafe { &*(gen_ptr as *const AtomicU64) };
-        let current = atomic.load(Ordering::Relaxed);
+        self.seal_header();
+        let atomic = self.generation();
+        let current = u64::from_le(atomic.load(Ordering::Relaxed));
         debug_assert!(
             current & 1 == 1,
             "end_write called without begin_write (gen={})",
             current
         );
-        atomic.store(current + 1, Ordering::Release);
+        atomic.store((current + 1).to_le(), Ordering::Release);
     }
 
-    /// Find slot for path hash (linear probing)
-    fn find_slot(&self, path_hash: u64) -> Option<usize> {
+    /// Find slot for a path (linear probing): its entry, or the empty slot
+    /// that would take it
+    fn find_slot(&self, path_hash: u64, path_check: u64) -> Option<usize> {
         let start = (path_hash as usize) % self.capacity;
         for i in 0..self.capacity {
             let slot = (start + i) % self.capacity;
-            let entry = &self.entries()[slot];
-            if entry.is_empty() || entry.path_hash == path_hash {
+            let entry = self.read_entry(slot);
+            if entry.is_empty() || entry.is_for(path_hash, path_check) {
                 return Some(slot);
             }
         }
         None
     }
 
-    /// Lookup entry by path hash
-    pub fn lookup(&self, path_hash: u64) -> Option<&VDirEntry> {
+    /// Lookup entry by path hash and check (see [`VDirEntry::is_for`])
+    pub fn lookup(&self, path_hash: u64, path_check: u64) -> Option<VDirEntry> {
         let start = (path_hash as usize) % self.capacity;
         for i in 0..self.capacity {
             let slot = (start + i) % self.capacity;
-            let entry = &self.entries()[slot];
+            let entry = self.read_entry(slot);
             if entry.is_empty() {
                 return None;
             }
-            if entry.path_hash == path_hash {
+            if entry.is_for(path_hash, path_check) {
                 return Some(entry);
             }
         }
         None
     }
 
-    /// Insert or update entry
-    pub fn upsert(&mut self, entry: VDirEntry) -> Result<()> {
+    /// Lookup entry of `path`
+    pub fn lookup_path(&self, path: &str) -> Option<VDirEntry> {
+        self.lookup(fnv1a_hash(path), vdir_path_check(path))
+    }
+
+    /// Insert or update entry. An entry naming no pack keeps the pack
+    /// reference of the one it replaces while the content is the same.
+    pub fn upsert(&mut self, mut entry: VDirEntry) -> Result<()> {
         // Dynamic Resize: Check if resulting load factor would exceed 75%
         let current_count = self.header().entry_count as usize;
-        let existing_entry = self.lookup(entry.path_hash);
+        let existing_entry = self.lookup(entry.path_hash, entry.path_check);
         let is_new = existing_entry.is_none();
 
         if is_new && (current_count + 1) as f64 / self.capacity as f64 > 0.75 {
             self.resize(self.capacity * 2)?;
         }
 
-        let slot = self.find_slot(entry.path_hash).context("VDir full")?;
+        let slot = self
+            .find_slot(entry.path_hash, entry.path_check)
+            .context("VDir full")?;
 
-        let existing = &self.entries()[slot];
-        let is_new = existing.is_empty();
+        let is_new = self.read_entry(slot).is_empty();
+        self.keep_pack_ref(slot, &mut entry);
 
         self.begin_write();
-        self.entries_mut()[slot] = entry;
+        self.write_entry(slot, &entry);
 
         if is_new {
-            self.header_mut().entry_count += 1;
+            self.update_header(|h| h.entry_count += 1);
         }
 
         self.end_write();
         Ok(())
     }
 
+    /// Insert or update several entries inside one seqlock write, so a
+    /// reader sees either none or all of them changed
+    pub fn upsert_many(&mut self, entries: &[VDirEntry]) -> Result<()> {
+        let added = entries
+            .iter()
+            .filter(|e| self.lookup(e.path_hash, e.path_check).is_none())
+            .count();
+        let mut capacity = self.capacity;
+        while (self.header().entry_count as usize + added) as f64 / capacity as f64 > 0.75 {
+            capacity *= 2;
+        }
+        if capacity != self.capacity {
+            self.resize(capacity)?;
+        }
+
+        let mut slots = Vec::with_capacity(entries.len());
+        for entry in entries {
+            slots.push(
+                self.find_slot(entry.path_hash, entry.path_check)
+                    .context("VDir full")?,
+            );
+        }
+
+        self.begin_write();
+        for (entry, slot) in entries.iter().zip(slots) {
+            let mut entry = *entry;
+            if self.read_entry(slot).is_empty() {
+                self.update_header(|h| h.entry_count += 1);
+            }
+            self.keep_pack_ref(slot, &mut entry);
+            self.write_entry(slot, &entry);
+        }
+        self.end_write();
+        Ok(())
+    }
+
+    /// Change the entry of `path` in place, inside one seqlock write. Unlike
+    /// [`Self::upsert`] it never inserts or resizes, so readers keep their
+    /// mapping. Returns false, changing nothing, if `path` has no entry.
+    /// `update` cannot move the entry: its path fields are reset to `path`,
+    /// and its pack reference is dropped if the content changes.
+    pub fn update_entry(&mut self, path: &str, update: impl FnOnce(&mut VDirEntry)) -> bool {
+        let Some(slot) = self.find_slot(fnv1a_hash(path), vdir_path_check(path)) else {
+            return false;
+        };
+        let mut entry = self.read_entry(slot);
+        if entry.is_empty() {
+            return false;
+        }
+        let (cas_hash, size) = (entry.cas_hash, entry.size);
+        update(&mut entry);
+        entry.set_path(path);
+        if entry.cas_hash != cas_hash || entry.size != size {
+            entry.pack_offset = 0;
+            entry.pack_id = 0;
+            entry.pack_len = 0;
+        }
+
+        self.begin_write();
+        self.write_entry(slot, &entry);
+        self.end_write();
+        true
+    }
+
     /// Mark entry as dirty
-    pub fn mark_dirty(&mut self, path_hash: u64, dirty: bool) -> bool {
-        if let Some(slot) = self.find_slot(path_hash) {
-            let entries = self.entries_mut();
-            let entry = &mut entries[slot];
-            if !entry.is_empty() && entry.path_hash == path_hash {
-                self.begin_write();
-                // Re-acquire mutable entry after begin_write borrows self
-                let entries = self.entries_mut();
-                let entry = &mut entries[slot];
+    pub fn mark_dirty(&mut self, path_hash: u64, path_check: u64, dirty: bool) -> bool {
+        if let Some(slot) = self.find_slot(path_hash, path_check) {
+            let mut entry = self.read_entry(slot);
+            if !entry.is_empty() {
                 if dirty {
                     entry.flags |= FLAG_DIRTY;
                 } else {
                     entry.flags &= !FLAG_DIRTY;
                 }
+                self.begin_write();
+                self.write_entry(slot, &entry);
                 self.end_write();
                 return true;
             }
@@ -286,6 +539,58 @@ impl VDir {
         false
     }
 
+    /// Point every entry whose content pack `pack_id` holds at its blob, and
+    /// drop the pack references of all others, inside one seqlock write.
+    /// `locate` gives the offset and length of a blob in the pack's data
+    /// section. Only entries whose CAS blob is the file content itself can
+    /// be served from a pack. Pass 0 and a `locate` that finds nothing when
+    /// the project has no pack. Returns the number of packed entries.
+    pub fn apply_pack(
+        &mut self,
+        pack_id: u32,
+        locate: impl Fn(&[u8; 32]) -> Option<(u64, u32)>,
+    ) -> usize {
+        let mut packed = 0;
+        let mut changed = Vec::new();
+        for slot in 0..self.capacity {
+            let mut entry = self.read_entry(slot);
+            if entry.is_empty() {
+                continue;
+            }
+            let pack_ref = if pack_id == 0
+                || entry.flags & (FLAG_STORAGE_MASK | FLAG_DIR | FLAG_SYMLINK | FLAG_WHITEOUT) != 0
+            {
+                None
+            } else {
+                locate(&entry.cas_hash).filter(|&(_, len)| u64::from(len) == entry.size)
+            };
+            let (pack_offset, entry_pack_id, pack_len) = match pack_ref {
+                Some((offset, len)) => {
+                    packed += 1;
+                    (offset, pack_id, len)
+                }
+                None => (0, 0, 0),
+            };
+            if (entry.pack_offset, entry.pack_id, entry.pack_len)
+                != (pack_offset, entry_pack_id, pack_len)
+            {
+                entry.pack_offset = pack_offset;
+                entry.pack_id = entry_pack_id;
+                entry.pack_len = pack_len;
+                changed.push((slot, entry));
+            }
+        }
+
+        if !changed.is_empty() {
+            self.begin_write();
+            for (slot, entry) in &changed {
+                self.write_entry(*slot, entry);
+            }
+            self.end_write();
+        }
+        packed
+    }
+
     /// Flush mmap to disk
     pub fn flush(&self) -> Result<()> {
         self.mmap.flush()?;
@@ -294,13 +599,13 @@ impl VDir {
 
     /// Calculate VDir statistics for observability
     pub fn get_stats(&self) -> VDirStats {
-        let entries = self.entries();
         let capacity = self.capacity;
         let mut occupied = 0;
         let mut max_chain = 0;
         let mut total_chain = 0;
 
-        for (i, entry) in entries.iter().enumerate().take(capacity) {
+        for i in 0..capacity {
+            let entry = self.read_entry(i);
             if !entry.is_empty() {
                 occupied += 1;
 
@@ -350,42 +655,40 @@ impl VDir {
 
         // 1. Snapshot existing entries
         // We use a Vec because we're about to unmap/remap.
-        let entries_snapshot: Vec<VDirEntry> = self
-            .entries()
-            .iter()
+        let entries_snapshot: Vec<VDirEntry> = (0..self.capacity)
+            .map(|slot| self.read_entry(slot))
             .filter(|e| !e.is_empty())
-            .cloned()
             .collect();
 
         // 2. Resize file and remap
-        let file = OpenOptions::new().read(true).write(true).open(&self.path)?;
-        let new_size = VDIR_HEADER_SIZE + (new_capacity * VDIR_ENTRY_SIZE);
-        file.set_len(new_size as u64)?;
-
-        // Re-map MmapMut
-        self.mmap = unsafe { MmapMut::map_mut(&file)? };
+        self.grow_to(Self::file_size(new_capacity))?;
         self.capacity = new_capacity;
+        self.check_offset = Self::check_offset_for(new_capacity);
+        self.pack_ref_offset = Self::pack_ref_offset_for(new_capacity);
 
-        // 3. Update header
+        // 3. Update header; the check and pack reference tables move past
+        // the larger entry table
+        let check_offset = self.check_offset as u32;
+        let pack_ref_offset = self.pack_ref_offset as u32;
         self.begin_write();
-        let header = self.header_mut();
-        header.table_capacity = new_capacity as u32;
-        header.entry_count = 0; // Reset count, re-increment during insertion
-
-        // 4. Clear table (zero out)
-        let entries_ptr = unsafe { self.mmap.as_mut_ptr().add(VDIR_HEADER_SIZE) };
-        unsafe {
-            std::ptr::write_bytes(entries_ptr, 0, new_capacity * VDIR_ENTRY_SIZE);
-        }
+        self.update_header(|h| {
+            h.table_capacity = new_capacity as u32;
+            h.check_offset = check_offset;
+            h.pack_ref_offset = pack_ref_offset;
+            h.entry_count = 0; // Reset count, re-increment during insertion
+        });
+
+        // 4. Clear all tables (zero out)
+        self.mmap[VDIR_HEADER_SIZE..Self::file_size(new_capacity)].fill(0);
 
         // 5. Re-insert (rehash)
         for entry in entries_snapshot {
             // Internal upsert-like logic without seqlock wrapping (already in seqlock)
             let slot = self
-                .find_slot(entry.path_hash)
+                .find_slot(entry.path_hash, entry.path_check)
                 .context("VDir full after resize")?;
-            self.entries_mut()[slot] = entry;
-            self.header_mut().entry_count += 1;
+            self.write_entry(slot, &entry);
+            self.update_header(|h| h.entry_count += 1);
         }
 
         self.end_write();
@@ -446,12 +749,12 @@ mod tests {
             mtime_nsec: 0,
             mode: 0o644,
             flags: 0,
-            _pad: [0; 3],
+            ..Default::default()
         };
         vdir.upsert(entry).unwrap();
 
         // Lookup
-        let found = vdir.lookup(fnv1a_hash("src/main.rs"));
+        let found = vdir.lookup_path("src/main.rs");
         assert!(found.is_some());
         assert_eq!(found.unwrap().size, 1024);
     }
@@ -470,12 +773,64 @@ mod tests {
         vdir.upsert(entry).unwrap();
 
         // Mark dirty
-        assert!(vdir.mark_dirty(fnv1a_hash("main.o"), true));
-        assert!(vdir.lookup(fnv1a_hash("main.o")).unwrap().is_dirty());
+        assert!(vdir.mark_dirty(fnv1a_hash("main.o"), 0, true));
+        assert!(vdir.lookup_path("main.o").unwrap().is_dirty());
 
         // Clear dirty
-        assert!(vdir.mark_dirty(fnv1a_hash("main.o"), false));
-        assert!(!vdir.lookup(fnv1a_hash("main.o")).unwrap().is_dirty());
+        assert!(vdir.mark_dirty(fnv1a_hash("main.o"), 0, false));
+        assert!(!vdir.lookup_path("main.o").unwrap().is_dirty());
+    }
+
+    #[test]
+    fn test_update_entry_in_place() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("test.vdir");
+        let mut vdir = VDir::create_or_open(&path).unwrap();
+
+        let mut entry = VDirEntry {
+            size: 10,
+            ..Default::default()
+        };
+        entry.set_path("src/main.rs");
+        vdir.upsert(entry).unwrap();
+        let generation = vdir.header().generation;
+
+        assert!(vdir.update_entry("src/main.rs", |e| {
+            e.size = 99;
+            e.set_mtime_ns(2_000_000_000);
+            // Not honoured: the entry stays at its path
+            e.set_path("src/other.rs");
+        }));
+        assert_eq!(vdir.header().generation, generation + 2);
+        assert_eq!(vdir.header().entry_count, 1);
+        let updated = vdir.lookup_path("src/main.rs").unwrap();
+        assert_eq!((updated.size, updated.mtime_sec), (99, 2));
+        assert!(vdir.lookup_path("src/other.rs").is_none());
+        let found = unsafe { vdir_lookup(vdir.mmap.as_ptr(), vdir.mmap.len(), "src/main.rs") };
+        assert_eq!(found.unwrap().size, 99);
+
+        // Unknown paths are left to upsert
+        assert!(!vdir.update_entry("src/new.rs", |e| e.size = 1));
+        assert_eq!(vdir.header().generation, generation + 2);
+        assert!(vdir.lookup_path("src/new.rs").is_none());
+    }
+
+    #[test]
+    fn test_update_entry_records_missing_check() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("test.vdir");
+        let mut vdir = VDir::create_or_open(&path).unwrap();
+
+        // As migrated from a v2 table: no check recorded
+        vdir.upsert(VDirEntry {
+            path_hash: fnv1a_hash("legacy.rs"),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(vdir.update_entry("legacy.rs", |e| e.flags |= FLAG_DIRTY));
+        let entry = vdir.lookup_path("legacy.rs").unwrap();
+        assert!(entry.is_dirty());
+        assert_eq!(entry.path_check, vdir_path_check("legacy.rs"));
     }
 
     // ==================== Edge Cases ====================
@@ -486,7 +841,7 @@ mod tests {
         let path = temp.path().join("test.vdir");
 
         let vdir = VDir::create_or_open(&path).unwrap();
-        assert!(vdir.lookup(fnv1a_hash("nonexistent.txt")).is_none());
+        assert!(vdir.lookup_path("nonexistent.txt").is_none());
     }
 
     #[test]
@@ -495,7 +850,7 @@ mod tests {
         let path = temp.path().join("test.vdir");
 
         let mut vdir = VDir::create_or_open(&path).unwrap();
-        assert!(!vdir.mark_dirty(fnv1a_hash("nonexistent.txt"), true));
+        assert!(!vdir.mark_dirty(fnv1a_hash("nonexistent.txt"), 0, true));
     }
 
     #[test]
@@ -512,7 +867,7 @@ mod tests {
             ..Default::default()
         };
         vdir.upsert(entry1).unwrap();
-        assert_eq!(vdir.lookup(fnv1a_hash("file.txt")).unwrap().size, 100);
+        assert_eq!(vdir.lookup_path("file.txt").unwrap().size, 100);
 
         // Update with new size
         let entry2 = VDirEntry {
@@ -521,7 +876,7 @@ mod tests {
             ..Default::default()
         };
         vdir.upsert(entry2).unwrap();
-        assert_eq!(vdir.lookup(fnv1a_hash("file.txt")).unwrap().size, 200);
+        assert_eq!(vdir.lookup_path("file.txt").unwrap().size, 200);
     }
 
     #[test]
@@ -556,6 +911,40 @@ mod tests {
         assert_eq!(vdir.header().entry_count, 2);
     }
 
+    #[test]
+    fn test_upsert_many_is_one_write() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("test.vdir");
+
+        let mut vdir = VDir::create_or_open(&path).unwrap();
+        vdir.upsert(VDirEntry {
+            path_hash: fnv1a_hash("a.txt"),
+            size: 1,
+            ..Default::default()
+        })
+        .unwrap();
+        let gen_before = vdir.header().generation;
+
+        vdir.upsert_many(&[
+            VDirEntry {
+                path_hash: fnv1a_hash("a.txt"),
+                size: 2,
+                ..Default::default()
+            },
+            VDirEntry {
+                path_hash: fnv1a_hash("b.txt"),
+                size: 3,
+                ..Default::default()
+            },
+        ])
+        .unwrap();
+
+        assert_eq!(vdir.header().generation, gen_before + 2);
+        assert_eq!(vdir.header().entry_count, 2);
+        assert_eq!(vdir.lookup_path("a.txt").unwrap().size, 2);
+        assert_eq!(vdir.lookup_path("b.txt").unwrap().size, 3);
+    }
+
     // ==================== Generation Counter ====================
 
     #[test]
@@ -589,7 +978,7 @@ mod tests {
         .unwrap();
 
         let gen_before = vdir.header().generation;
-        vdir.mark_dirty(fnv1a_hash("file.txt"), true);
+        vdir.mark_dirty(fnv1a_hash("file.txt"), 0, true);
         assert_eq!(vdir.header().generation, gen_before + 2);
     }
 
@@ -616,7 +1005,7 @@ mod tests {
         // Reopen and verify
         {
             let vdir = VDir::create_or_open(&path).unwrap();
-            let entry = vdir.lookup(fnv1a_hash("persistent.txt"));
+            let entry = vdir.lookup_path("persistent.txt");
             assert!(entry.is_some());
             assert_eq!(entry.unwrap().size, 42);
             assert_eq!(entry.unwrap().cas_hash, [7; 32]);
@@ -668,7 +1057,7 @@ mod tests {
             ..Default::default()
         };
         vdir.upsert(dir_entry).unwrap();
-        assert!(vdir.lookup(fnv1a_hash("mydir/")).unwrap().is_dir());
+        assert!(vdir.lookup_path("mydir/").unwrap().is_dir());
 
         // Test SYMLINK flag
         let symlink_entry = VDirEntry {
@@ -677,7 +1066,7 @@ mod tests {
             ..Default::default()
         };
         vdir.upsert(symlink_entry).unwrap();
-        let e = vdir.lookup(fnv1a_hash("link")).unwrap();
+        let e = vdir.lookup_path("link").unwrap();
         assert_eq!(e.flags & FLAG_SYMLINK, FLAG_SYMLINK);
     }
 
@@ -703,18 +1092,9 @@ mod tests {
         assert_eq!(vdir.header().entry_count, 1000);
 
         // Verify random lookups
-        assert_eq!(
-            vdir.lookup(fnv1a_hash("path/to/file_0.rs")).unwrap().size,
-            0
-        );
-        assert_eq!(
-            vdir.lookup(fnv1a_hash("path/to/file_500.rs")).unwrap().size,
-            500
-        );
-        assert_eq!(
-            vdir.lookup(fnv1a_hash("path/to/file_999.rs")).unwrap().size,
-            999
-        );
+        assert_eq!(vdir.lookup_path("path/to/file_0.rs").unwrap().size, 0);
+        assert_eq!(vdir.lookup_path("path/to/file_500.rs").unwrap().size, 500);
+        assert_eq!(vdir.lookup_path("path/to/file_999.rs").unwrap().size, 999);
     }
 
     #[test]
@@ -744,7 +1124,7 @@ mod tests {
                 thread::spawn(move || {
                     let vdir = VDir::open_readonly(&p).unwrap();
                     for i in 0..100 {
-                        let entry = vdir.lookup(fnv1a_hash(&format!("file_{}", i)));
+                        let entry = vdir.lookup_path(&format!("file_{}", i));
                         assert!(entry.is_some());
                         assert_eq!(entry.unwrap().size, i as u64);
                     }
@@ -830,7 +1210,7 @@ mod tests {
 
         // mark_dirty also leaves generation even
         for i in 0..10 {
-            vdir.mark_dirty(fnv1a_hash(&format!("file_{}.rs", i)), true);
+            vdir.mark_dirty(fnv1a_hash(&format!("file_{}.rs", i)), 0, true);
             assert_eq!(
                 vdir.header().generation & 1,
                 0,
@@ -901,7 +1281,7 @@ mod tests {
                             continue;
                         }
                         // Read a sample entry while gen is even
-                        let _entry = vdir.lookup(fnv1a_hash("file_0"));
+                        let _entry = vdir.lookup_path("file_0");
                         reads += 1;
                     }
                     (reads, retries)
@@ -971,8 +1351,9 @@ mod tests {
                         let mmap_ptr = mmap_addr as *const u8;
 
                         // Seqlock protocol: read gen, read data, read gen again
-                        let gen_ptr = unsafe { &*((mmap_ptr as usize + 8) as *const AtomicU64) };
-                        let g1 = gen_ptr.load(Ordering::Acquire);
+                        let gen_ptr =
+                            unsafe { &*((mmap_ptr as usize + HDR_GENERATION) as *const AtomicU64) };
+                        let g1 = u64::from_le(gen_ptr.load(Ordering::Acquire));
                         if g1 & 1 != 0 {
                             retries += 1;
                             core::hint::spin_loop();
@@ -980,9 +1361,9 @@ mod tests {
                         }
 
                         let table_capacity =
-                            unsafe { *((mmap_ptr as usize + 20) as *const u32) } as usize;
+                            unsafe { read_le_u32(mmap_ptr, HDR_TABLE_CAPACITY) } as usize;
                         let table_offset =
-                            unsafe { *((mmap_ptr as usize + 24) as *const u32) } as usize;
+                            unsafe { read_le_u32(mmap_ptr, HDR_TABLE_OFFSET) } as usize;
 
                         if table_capacity == 0 {
                             continue;
@@ -999,7 +1380,7 @@ mod tests {
                             if off + VDIR_ENTRY_SIZE > mmap_len {
                                 break;
                             }
-                            let e = unsafe { &*(mmap_ptr.add(off) as *const VDirEntry) };
+                            let e = unsafe { VDirEntry::read_le(mmap_ptr.add(off)) };
                             if e.path_hash == 0 {
                                 break;
                             }
@@ -1009,7 +1390,7 @@ mod tests {
                             }
                         }
 
-                        let g2 = gen_ptr.load(Ordering::Acquire);
+                        let g2 = u64::from_le(gen_ptr.load(Ordering::Acquire));
                         if g1 != g2 {
                             retries += 1;
                             continue;
@@ -1086,11 +1467,10 @@ mod tests {
             vdir.flush().unwrap();
 
             // Simulate crash: force odd generation via direct atomic write
-            let gen_ptr = &vdir.header().generation as *const u64;
-            let atomic = unsafe { &*(gen_ptr as *const AtomicU64) };
-            let current = atomic.load(Ordering::Relaxed);
+            let atomic = vdir.generation();
+            let current = u64::from_le(atomic.load(Ordering::Relaxed));
             assert!(current & 1 == 0, "Should be even before crash sim");
-            atomic.store(current + 1, Ordering::Release);
+            atomic.store((current + 1).to_le(), Ordering::Release);
             vdir.flush().unwrap();
         }
 
@@ -1103,7 +1483,7 @@ mod tests {
                 "Generation should be even after recovery, got {}",
                 gen
             );
-            let entry = vdir.lookup(fnv1a_hash("orphan_file"));
+            let entry = vdir.lookup_path("orphan_file");
             assert!(entry.is_some(), "Entry should survive crash recovery");
             assert_eq!(entry.unwrap().size, 42);
         }
@@ -1118,6 +1498,12 @@ mod tests {
         // Use real VDir (starts at VDIR_DEFAULT_CAPACITY = 65536)
         let mut vdir = VDir::create_or_open(&path).unwrap();
         let initial_capacity = vdir.capacity;
+        // A shim's mapping, taken before the resize
+        let shim_map = unsafe { memmap2::Mmap::map(&std::fs::File::open(&path).unwrap()) }.unwrap();
+        assert_eq!(
+            unsafe { vdir_table_end(shim_map.as_ptr(), shim_map.len()) },
+            Some(shim_map.len())
+        );
 
         // Insert until it exceeds 75%
         // Using a loop to insert many entries
@@ -1137,15 +1523,23 @@ mod tests {
         assert_eq!(vdir.capacity, initial_capacity * 2);
         assert_eq!(vdir.header().table_capacity as usize, initial_capacity * 2);
 
+        // The old mapping sees the new header and knows it is too short
+        let file_len = std::fs::metadata(&path).unwrap().len() as usize;
+        assert!(file_len > shim_map.len());
+        assert_eq!(
+            unsafe { vdir_table_end(shim_map.as_ptr(), shim_map.len()) },
+            Some(file_len)
+        );
+
         // Verify we can still find the first entry
         let entry = vdir
-            .lookup(1001)
+            .lookup(1001, 0)
             .expect("Entry 1001 not found after resize");
         assert_eq!(entry.size, 0);
 
         // Verify we can find the last entry
         let entry = vdir
-            .lookup(target as u64 + 1001 - 1)
+            .lookup(target as u64 + 1001 - 1, 0)
             .expect("Last entry not found after resize");
         assert_eq!(entry.size, target as u64 - 1);
 
@@ -1225,13 +1619,275 @@ mod tests {
         assert_eq!(vdir.capacity, initial_capacity * 4);
 
         // Verify lookups across the range
-        assert!(vdir.lookup(1).is_some());
-        assert!(vdir.lookup(target1 as u64).is_some());
-        assert!(vdir.lookup(target2 as u64).is_some());
+        assert!(vdir.lookup(1, 0).is_some());
+        assert!(vdir.lookup(target1 as u64, 0).is_some());
+        assert!(vdir.lookup(target2 as u64, 0).is_some());
 
         // Verify statistics
         let stats = vdir.get_stats();
         assert_eq!(stats.capacity, initial_capacity * 4);
         assert_eq!(stats.entry_count, target2);
     }
+
+    // ==================== Format Versions ====================
+
+    #[test]
+    fn test_v1_vdir_upgraded_in_place() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("test.vdir");
+
+        {
+            let mut vdir = VDir::create_or_open(&path).unwrap();
+            vdir.upsert(VDirEntry {
+                path_hash: fnv1a_hash("kept.txt"),
+                size: 7,
+                ..Default::default()
+            })
+            .unwrap();
+            // Rewrite the header as a v1 writer would have left it
+            vdir.update_header(|h| {
+                h.version = 1;
+                h.crc32 = 0;
+            });
+            vdir.flush().unwrap();
+        }
+
+        let vdir = VDir::create_or_open(&path).unwrap();
+        assert_eq!(vdir.version(), VDIR_VERSION);
+        assert_ne!(vdir.header().crc32, 0);
+        assert_eq!(vdir.lookup_path("kept.txt").unwrap().size, 7);
+    }
+
+    #[test]
+    fn test_v2_vdir_gets_check_table() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("test.vdir");
+
+        {
+            let mut vdir = VDir::create_or_open(&path).unwrap();
+            let mut entry = VDirEntry {
+                size: 7,
+                ..Default::default()
+            };
+            entry.set_path("kept.txt");
+            vdir.upsert(entry).unwrap();
+            // Rewrite the header as a v2 writer would have left it
+            vdir.update_header(|h| {
+                h.version = 2;
+                h.check_offset = 0;
+            });
+            vdir.seal_header();
+            vdir.flush().unwrap();
+        }
+
+        let mut vdir = VDir::create_or_open(&path).unwrap();
+        assert_eq!(vdir.version(), VDIR_VERSION);
+        assert_eq!(
+            vdir.header().check_offset as usize,
+            VDIR_HEADER_SIZE + vdir.capacity * VDIR_ENTRY_SIZE
+        );
+        // The v2 entry has no check yet and is found by its hash
+        assert_eq!(vdir.lookup_path("kept.txt").unwrap().path_check, 0);
+        let found = unsafe { vdir_lookup(vdir.mmap.as_ptr(), vdir.mmap.len(), "kept.txt") };
+        assert_eq!(found.unwrap().size, 7);
+
+        let mut entry = vdir.lookup_path("kept.txt").unwrap();
+        entry.set_path("kept.txt");
+        vdir.upsert(entry).unwrap();
+        assert_eq!(
+            vdir.lookup_path("kept.txt").unwrap().path_check,
+            vdir_path_check("kept.txt")
+        );
+        assert_eq!(vdir.header().entry_count, 1);
+    }
+
+    #[test]
+    fn test_v3_vdir_gets_pack_table() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("test.vdir");
+
+        {
+            let mut vdir = VDir::create_or_open(&path).unwrap();
+            let mut entry = VDirEntry {
+                size: 7,
+                ..Default::default()
+            };
+            entry.set_path("kept.txt");
+            vdir.upsert(entry).unwrap();
+            vdir.apply_pack(9, |_| Some((0, 7)));
+            // Rewrite the header as a v3 writer would have left it
+            vdir.update_header(|h| {
+                h.version = 3;
+                h.pack_ref_offset = 0;
+            });
+            vdir.seal_header();
+            vdir.flush().unwrap();
+        }
+
+        let vdir = VDir::create_or_open(&path).unwrap();
+        assert_eq!(vdir.version(), VDIR_VERSION);
+        assert_eq!(
+            vdir.header().pack_ref_offset as usize,
+            VDir::pack_ref_offset_for(vdir.capacity)
+        );
+        // The check survives, the pack reference does not
+        let kept = vdir.lookup_path("kept.txt").unwrap();
+        assert_eq!(kept.path_check, vdir_path_check("kept.txt"));
+        assert_eq!(kept.pack_id, 0);
+        let found = unsafe { vdir_lookup(vdir.mmap.as_ptr(), vdir.mmap.len(), "kept.txt") };
+        assert_eq!(found.unwrap().pack_ref(), None);
+    }
+
+    #[test]
+    fn test_apply_pack() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("test.vdir");
+        let mut vdir = VDir::create_or_open(&path).unwrap();
+
+        let entry = |path: &str, content: u8, size: u64, flags: u16| {
+            let mut entry = VDirEntry {
+                cas_hash: [content; 32],
+                size,
+                flags,
+                ..Default::default()
+            };
+            entry.set_path(path);
+            entry
+        };
+        vdir.upsert(entry("a.txt", 1, 10, 0)).unwrap();
+        vdir.upsert(entry("b.txt", 2, 20, 0)).unwrap();
+        vdir.upsert(entry("c.txt", 1, 10, FLAG_COMPRESSED)).unwrap();
+        vdir.upsert(entry("d.txt", 3, 30, 0)).unwrap();
+
+        // Blobs 1 and 2 are packed; blob 2's length doesn't match its entry
+        let locate = |hash: &[u8; 32]| match hash[0] {
+            1 => Some((100, 10)),
+            2 => Some((200, 21)),
+            _ => None,
+        };
+        assert_eq!(vdir.apply_pack(7, locate), 1);
+        let found = unsafe { vdir_lookup(vdir.mmap.as_ptr(), vdir.mmap.len(), "a.txt") };
+        assert_eq!(found.unwrap().pack_ref(), Some((7, 100, 10)));
+        for other in ["b.txt", "c.txt", "d.txt"] {
+            assert_eq!(vdir.lookup_path(other).unwrap().pack_id, 0, "{other}");
+        }
+
+        // Same content keeps the reference; new content drops it
+        let generation = vdir.header().generation;
+        assert_eq!(vdir.apply_pack(7, locate), 1);
+        assert_eq!(vdir.header().generation, generation);
+        vdir.upsert(entry("a.txt", 1, 10, FLAG_DIRTY)).unwrap();
+        assert_eq!(vdir.lookup_path("a.txt").unwrap().pack_id, 7);
+        vdir.update_entry("a.txt", |e| e.set_mtime_ns(5));
+        assert_eq!(vdir.lookup_path("a.txt").unwrap().pack_id, 7);
+        vdir.update_entry("a.txt", |e| e.cas_hash = [4; 32]);
+        assert_eq!(vdir.lookup_path("a.txt").unwrap().pack_id, 0);
+
+        // References move with the entries on resize
+        vdir.upsert(entry("a.txt", 1, 10, 0)).unwrap();
+        vdir.apply_pack(7, locate);
+        vdir.resize(vdir.capacity * 2).unwrap();
+        let found = unsafe { vdir_lookup(vdir.mmap.as_ptr(), vdir.mmap.len(), "a.txt") };
+        assert_eq!(found.unwrap().pack_ref(), Some((7, 100, 10)));
+
+        // No pack: every reference goes
+        assert_eq!(vdir.apply_pack(0, |_| None), 0);
+        assert_eq!(vdir.lookup_path("a.txt").unwrap().pack_id, 0);
+    }
+
+    #[test]
+    fn test_set_version_emits_v1_readable_by_shim() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("test.vdir");
+
+        let mut vdir = VDir::create_or_open(&path).unwrap();
+        vdir.upsert(VDirEntry {
+            path_hash: fnv1a_hash("src/lib.rs"),
+            size: 11,
+            ..Default::default()
+        })
+        .unwrap();
+
+        vdir.set_version(1).unwrap();
+        assert_eq!(vdir.version(), 1);
+        assert_eq!(vdir.header().crc32, 0);
+
+        // Writes keep the v1 header shape
+        vdir.upsert(VDirEntry {
+            path_hash: fnv1a_hash("src/main.rs"),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(vdir.header().crc32, 0);
+
+        let found = unsafe { vdir_lookup(vdir.mmap.as_ptr(), vdir.mmap.len(), "src/lib.rs") };
+        assert_eq!(found.unwrap().size, 11);
+    }
+
+    #[test]
+    fn test_colliding_paths_keep_own_slots() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("test.vdir");
+        let mut vdir = VDir::create_or_open(&path).unwrap();
+        let shim_lookup = |vdir: &VDir, path: &str| unsafe {
+            vdir_lookup(vdir.mmap.as_ptr(), vdir.mmap.len(), path)
+        };
+
+        // Some other path whose FNV-1a hash is that of src/b.rs
+        let mut other = VDirEntry {
+            size: 1,
+            ..Default::default()
+        };
+        other.set_path("src/a.rs");
+        other.path_hash = fnv1a_hash("src/b.rs");
+        vdir.upsert(other).unwrap();
+        assert!(vdir.lookup_path("src/b.rs").is_none());
+        assert!(shim_lookup(&vdir, "src/b.rs").is_none());
+
+        let mut b = VDirEntry {
+            size: 2,
+            ..Default::default()
+        };
+        b.set_path("src/b.rs");
+        vdir.upsert(b).unwrap();
+        assert_eq!(vdir.header().entry_count, 2);
+
+        vdir.resize(vdir.capacity * 2).unwrap();
+        assert_eq!(vdir.lookup_path("src/b.rs").unwrap().size, 2);
+        assert_eq!(shim_lookup(&vdir, "src/b.rs").unwrap().size, 2);
+        let kept = vdir.lookup(other.path_hash, other.path_check).unwrap();
+        assert_eq!(kept.size, 1);
+        // A check of 0 on the lookup side matches neither
+        assert!(vdir.lookup(other.path_hash, 0).is_none());
+
+        // Entries of older builds carry no check and match on the hash
+        vdir.upsert(VDirEntry {
+            path_hash: fnv1a_hash("legacy.rs"),
+            size: 3,
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(shim_lookup(&vdir, "legacy.rs").unwrap().size, 3);
+        assert_eq!(vdir.lookup_path("legacy.rs").unwrap().size, 3);
+    }
+
+    #[test]
+    fn test_unknown_version_rejected() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("test.vdir");
+
+        let mut vdir = VDir::create_or_open(&path).unwrap();
+        vdir.upsert(VDirEntry {
+            path_hash: fnv1a_hash("a.txt"),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(vdir.set_version(VDIR_VERSION + 1).is_err());
+        assert!(vdir.set_version(0).is_err());
+
+        // Readers refuse layouts they don't know rather than misparse them
+        vdir.update_header(|h| h.version = VDIR_VERSION + 1);
+        let found = unsafe { vdir_lookup(vdir.mmap.as_ptr(), vdir.mmap.len(), "a.txt") };
+        assert!(found.is_none());
+    }
 }
@@ -0,0 +1,431 @@
+//! One writer, many readers: the consistency model of the manifest and its
+//! mmap caches under load.
+//!
+//! The writer plays vDird. Every change of a path is committed to the LMDB
+//! manifest, then patched into the VDir and written to the (legacy) manifest
+//! mmap, which is rebuilt and renamed into place; the VDir is grown now and
+//! then. Reader threads look
+//! paths up through all three the whole time and check that
+//!
+//! - an entry is never torn: each of its fields decodes to the same version
+//! - an entry is never older than the last change of its path that had
+//!   finished before the lookup, nor newer than the last one begun after it
+//! - LMDB only reports a path absent if it was removed in that window
+//!
+//! A `None` from a mmap lookup is not checked: it sends the shim to IPC.
+//! A manifest mmap reader is only held to the lower bound while its mapping
+//! is the current file; after a rebuild it is stale until it maps again.
+
+#![allow(deprecated)]
+
+use std::fs::File;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Barrier};
+use std::thread;
+use std::time::Instant;
+
+use memmap2::Mmap;
+use vrift_ipc::vdir_types::{vdir_lookup, vdir_table_end, VDirStatResult};
+use vrift_ipc::{manifest_mmap_lookup, ManifestMmapBuilder, MmapStatEntry};
+use vrift_manifest::lmdb::{AssetTier, LmdbManifest};
+use vrift_vdird::vdir::{fnv1a_hash, VDir, VDirEntry, FLAG_WHITEOUT};
+
+const PATHS: usize = 256;
+const READERS: usize = 4;
+const WRITES: usize = 600;
+/// The VDir is doubled (and rehashed) this many times during the run
+const VDIR_GROWTHS: usize = 2;
+
+fn path_of(index: usize) -> String {
+    format!("src/module_{}/file_{}.rs", index / 16, index)
+}
+
+// Every field of an entry is derived from its version, so an entry mixing
+// two writes is caught. Odd versions are removals.
+
+fn mtime_of(version: u64) -> i64 {
+    version as i64 * 1_000_003
+}
+
+fn mode_of(version: u64) -> u32 {
+    0o100000 | (version as u32 % 0o1000)
+}
+
+fn hash_of(version: u64) -> [u8; 32] {
+    let mut hash = [0u8; 32];
+    for chunk in hash.chunks_mut(8) {
+        chunk.copy_from_slice(&version.to_le_bytes());
+    }
+    hash
+}
+
+/// Version of an entry; `hash` is None for stores that keep no content hash
+fn decode(
+    store: &str,
+    path: &str,
+    size: u64,
+    mtime: i64,
+    mode: u32,
+    hash: Option<[u8; 32]>,
+) -> u64 {
+    let version = size;
+    assert!(
+        mtime == mtime_of(version)
+            && mode == mode_of(version)
+            && hash.is_none_or(|hash| hash == hash_of(version)),
+        "{}: torn entry for {}: size {} mtime {} mode {:o}",
+        store,
+        path,
+        size,
+        mtime,
+        mode
+    );
+    version
+}
+
+/// Per-path versions published by the writer
+struct Versions {
+    /// Last change begun: the newest a reader may see
+    begun: Vec<AtomicU64>,
+    /// Last removal begun
+    removed: Vec<AtomicU64>,
+    /// Last change written to every store: the oldest a reader may see
+    finished: Vec<AtomicU64>,
+}
+
+/// Versions a lookup of one path may legally return
+struct Window {
+    oldest: u64,
+    newest: u64,
+    last_removal: u64,
+}
+
+impl Versions {
+    fn new() -> Self {
+        let zeros = || (0..PATHS).map(|_| AtomicU64::new(0)).collect();
+        Self {
+            begun: zeros(),
+            removed: zeros(),
+            finished: zeros(),
+        }
+    }
+
+    fn begin(&self, index: usize, version: u64) {
+        if version & 1 != 0 {
+            self.removed[index].store(version, Ordering::Release);
+        }
+        self.begun[index].store(version, Ordering::Release);
+    }
+
+    fn finish(&self, index: usize, version: u64) {
+        self.finished[index].store(version, Ordering::Release);
+    }
+
+    /// Load before the lookup
+    fn oldest(&self, index: usize) -> u64 {
+        self.finished[index].load(Ordering::Acquire)
+    }
+
+    /// Close the window after the lookup
+    fn window(&self, index: usize, oldest: u64) -> Window {
+        Window {
+            oldest,
+            newest: self.begun[index].load(Ordering::Acquire),
+            last_removal: self.removed[index].load(Ordering::Acquire),
+        }
+    }
+}
+
+impl Window {
+    fn check(&self, store: &str, path: &str, seen: Option<u64>) {
+        match seen {
+            Some(version) => assert!(
+                (self.oldest..=self.newest).contains(&version),
+                "{}: {} at version {}, expected {}..={}",
+                store,
+                path,
+                version,
+                self.oldest,
+                self.newest
+            ),
+            None => assert!(
+                self.last_removal >= self.oldest,
+                "{}: {} absent, but not removed since version {}",
+                store,
+                path,
+                self.oldest
+            ),
+        }
+    }
+}
+
+fn map(path: &Path) -> Mmap {
+    unsafe { Mmap::map(&File::open(path).unwrap()).unwrap() }
+}
+
+fn vnode(version: u64) -> vrift_ipc::VnodeEntry {
+    vrift_ipc::VnodeEntry {
+        content_hash: hash_of(version),
+        size: version,
+        mtime: mtime_of(version),
+        mode: mode_of(version),
+        flags: 0,
+        _pad: 0,
+    }
+}
+
+fn vdir_entry(path: &str, version: u64) -> VDirEntry {
+    let mut entry = VDirEntry {
+        path_hash: fnv1a_hash(path),
+        cas_hash: hash_of(version),
+        size: version,
+        mode: mode_of(version),
+        flags: if version & 1 != 0 { FLAG_WHITEOUT } else { 0 },
+        ..Default::default()
+    };
+    entry.set_mtime_ns(mtime_of(version));
+    entry
+}
+
+fn decode_vdir(path: &str, found: &VDirStatResult) -> u64 {
+    let version = decode(
+        "vdir",
+        path,
+        found.size,
+        found.mtime_ns(),
+        found.mode,
+        Some(found.cas_hash),
+    );
+    assert_eq!(
+        found.is_whiteout(),
+        version & 1 != 0,
+        "vdir: whiteout flag of {} does not match version {}",
+        path,
+        version
+    );
+    version
+}
+
+fn decode_mmap(path: &str, found: &MmapStatEntry) -> u64 {
+    let mtime = vrift_ipc::mtime::from_parts(found.mtime, found.mtime_nsec);
+    let version = decode("mmap", path, found.size, mtime, found.mode, None);
+    assert_eq!(version & 1, 0, "mmap: removed {} still listed", path);
+    version
+}
+
+/// Write the manifest mmap from scratch with the paths currently present
+fn rebuild_mmap(path: &Path, current: &[u64]) {
+    let mut builder = ManifestMmapBuilder::new();
+    for (index, &version) in current.iter().enumerate() {
+        if version & 1 == 0 {
+            builder.add_entry(
+                &path_of(index),
+                version,
+                mtime_of(version),
+                mode_of(version),
+                false,
+                false,
+                0,
+            );
+        }
+    }
+    builder.write_to_file(path.to_str().unwrap()).unwrap();
+}
+
+#[derive(Default)]
+struct ReaderStats {
+    lookups: usize,
+    fallbacks: usize,
+    remaps: usize,
+}
+
+#[test]
+fn stress_readers_see_no_torn_or_stale_entries() {
+    let temp = tempfile::tempdir().unwrap();
+    let manifest_dir = temp.path().join("manifest.lmdb");
+    let vdir_path = temp.path().join("vdir.mmap");
+    let mmap_path = temp.path().join("manifest.mmap");
+
+    let manifest = Arc::new(LmdbManifest::open(&manifest_dir).unwrap());
+    let mut vdir = VDir::create_or_open(&vdir_path).unwrap();
+    let versions = Arc::new(Versions::new());
+
+    // 1. Every path starts out present at version 2
+    let mut current = vec![2u64; PATHS];
+    for index in 0..PATHS {
+        let path = path_of(index);
+        manifest.insert(&path, vnode(2), AssetTier::Tier2Mutable);
+        vdir.upsert(vdir_entry(&path, 2)).unwrap();
+        versions.begin(index, 2);
+        versions.finish(index, 2);
+    }
+    manifest.commit().unwrap();
+    rebuild_mmap(&mmap_path, &current);
+    let mmap_epoch = Arc::new(AtomicU64::new(0));
+
+    let done = Arc::new(AtomicBool::new(false));
+    let barrier = Arc::new(Barrier::new(READERS + 1));
+
+    // 2. Readers: look up random paths through all three stores
+    let mut readers = Vec::new();
+    for seed in 0..READERS {
+        let manifest = manifest.clone();
+        let versions = versions.clone();
+        let mmap_epoch = mmap_epoch.clone();
+        let done = done.clone();
+        let barrier = barrier.clone();
+        let vdir_path = vdir_path.clone();
+        let mmap_path = mmap_path.clone();
+
+        readers.push(thread::spawn(move || {
+            let mut stats = ReaderStats::default();
+            let mut vdir_map = map(&vdir_path);
+            let mut epoch = mmap_epoch.load(Ordering::Acquire);
+            let mut mmap = map(&mmap_path);
+            let mut rng = 0x9e37_79b9_7f4a_7c15u64 ^ seed as u64;
+
+            barrier.wait();
+            while !done.load(Ordering::Relaxed) {
+                rng ^= rng << 13;
+                rng ^= rng >> 7;
+                rng ^= rng << 17;
+                let index = (rng % PATHS as u64) as usize;
+                let path = path_of(index);
+                stats.lookups += 1;
+
+                // LMDB
+                let oldest = versions.oldest(index);
+                let seen = manifest.get(&path).unwrap().map(|e| {
+                    decode(
+                        "lmdb",
+                        &path,
+                        e.vnode.size,
+                        e.vnode.mtime,
+                        e.vnode.mode,
+                        Some(e.vnode.content_hash),
+                    )
+                });
+                versions.window(index, oldest).check("lmdb", &path, seen);
+
+                // VDir: map again once it has grown past the mapping
+                let end = unsafe { vdir_table_end(vdir_map.as_ptr(), vdir_map.len()) };
+                if end.is_some_and(|end| end > vdir_map.len()) {
+                    vdir_map = map(&vdir_path);
+                    stats.remaps += 1;
+                }
+                let oldest = versions.oldest(index);
+                match unsafe { vdir_lookup(vdir_map.as_ptr(), vdir_map.len(), &path) } {
+                    Some(found) => {
+                        let seen = decode_vdir(&path, &found);
+                        versions
+                            .window(index, oldest)
+                            .check("vdir", &path, Some(seen));
+                    }
+                    None => stats.fallbacks += 1,
+                }
+
+                // Manifest mmap: map again after a rebuild
+                let latest = mmap_epoch.load(Ordering::Acquire);
+                if latest != epoch {
+                    epoch = latest;
+                    mmap = map(&mmap_path);
+                    stats.remaps += 1;
+                }
+                let mut oldest = versions.oldest(index);
+                if mmap_epoch.load(Ordering::Acquire) != epoch {
+                    // Rebuilt since: the change may only be in the new file
+                    oldest = 0;
+                }
+                match unsafe { manifest_mmap_lookup(mmap.as_ptr(), mmap.len(), &path) } {
+                    Some(found) => {
+                        let seen = decode_mmap(&path, &found);
+                        versions
+                            .window(index, oldest)
+                            .check("mmap", &path, Some(seen));
+                    }
+                    None => stats.fallbacks += 1,
+                }
+            }
+            stats
+        }));
+    }
+
+    // 3. Writer: change random paths, one commit per change
+    barrier.wait();
+    let start = Instant::now();
+    let mut next = 2u64;
+    let mut rng = 0x2545_f491_4f6c_dd1du64;
+    for write in 0..WRITES {
+        rng ^= rng << 13;
+        rng ^= rng >> 7;
+        rng ^= rng << 17;
+        let index = (rng % PATHS as u64) as usize;
+        let path = path_of(index);
+        let remove = current[index] & 1 == 0 && rng.is_multiple_of(4);
+        next += 1;
+        if (next & 1 != 0) != remove {
+            next += 1;
+        }
+        let version = next;
+
+        versions.begin(index, version);
+        if remove {
+            manifest.remove(&path);
+        } else {
+            manifest.insert(&path, vnode(version), AssetTier::Tier2Mutable);
+        }
+        manifest.commit().unwrap();
+
+        vdir.upsert(vdir_entry(&path, version)).unwrap();
+
+        current[index] = version;
+        rebuild_mmap(&mmap_path, &current);
+        mmap_epoch.fetch_add(1, Ordering::Release);
+        versions.finish(index, version);
+
+        if (write + 1).is_multiple_of(WRITES / (VDIR_GROWTHS + 1)) && write + 1 < WRITES {
+            let capacity = vdir.get_stats().capacity;
+            vdir.resize(capacity * 2).unwrap();
+        }
+    }
+    let elapsed = start.elapsed();
+
+    done.store(true, Ordering::Relaxed);
+    let mut total = ReaderStats::default();
+    for reader in readers {
+        let stats = reader.join().expect("reader panicked");
+        total.lookups += stats.lookups;
+        total.fallbacks += stats.fallbacks;
+        total.remaps += stats.remaps;
+    }
+
+    println!(
+        "{} writes in {:?}; {} readers: {} lookups, {} fallbacks, {} remaps",
+        WRITES, elapsed, READERS, total.lookups, total.fallbacks, total.remaps
+    );
+    assert!(total.lookups >= READERS);
+
+    // 4. Once quiet, every store agrees with the writer
+    let vdir_map = map(&vdir_path);
+    let mmap = map(&mmap_path);
+    for (index, &version) in current.iter().enumerate() {
+        let path = path_of(index);
+        let lmdb = manifest.get(&path).unwrap().map(|e| e.vnode.size);
+        assert_eq!(
+            lmdb,
+            (version & 1 == 0).then_some(version),
+            "lmdb: {}",
+            path
+        );
+        let found = unsafe { vdir_lookup(vdir_map.as_ptr(), vdir_map.len(), &path) }.unwrap();
+        assert_eq!(decode_vdir(&path, &found), version, "vdir: {}", path);
+        let found = unsafe { manifest_mmap_lookup(mmap.as_ptr(), mmap.len(), &path) };
+        assert_eq!(
+            found.map(|e| decode_mmap(&path, &e)),
+            (version & 1 == 0).then_some(version),
+            "mmap: {}",
+            path
+        );
+    }
+}
@@ -51,9 +51,11 @@ async fn test_daemon_lifecycle_handshake() {
         project_id: "test_project".to_string(),
         vdir_path: temp.path().join("test.vdir"),
         socket_path: socket_path.clone(),
+        control_socket_path: temp.path().join("test.sock.ctl"),
         staging_base: temp.path().join("staging"),
         cas_path: temp.path().join("the_source"),
         manifest_path: temp.path().join("test.lmdb"),
+        pack_path: None,
     };
 
     // Create required directories
@@ -104,9 +106,11 @@ async fn test_daemon_manifest_operations_over_socket() {
         project_id: "test_project".to_string(),
         vdir_path: temp.path().join("test.vdir"),
         socket_path: socket_path.clone(),
+        control_socket_path: temp.path().join("test.sock.ctl"),
         staging_base: temp.path().join("staging"),
         cas_path: temp.path().join("the_source"),
         manifest_path: temp.path().join("test.lmdb"),
+        pack_path: None,
     };
 
     std::fs::create_dir_all(&config.staging_base).unwrap();
@@ -177,9 +181,11 @@ async fn test_daemon_multiple_clients() {
         project_id: "test_project".to_string(),
         vdir_path: temp.path().join("test.vdir"),
         socket_path: socket_path.clone(),
+        control_socket_path: temp.path().join("test.sock.ctl"),
         staging_base: temp.path().join("staging"),
         cas_path: temp.path().join("the_source"),
         manifest_path: temp.path().join("test.lmdb"),
+        pack_path: None,
     };
 
     std::fs::create_dir_all(&config.staging_base).unwrap();
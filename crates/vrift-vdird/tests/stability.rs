@@ -32,7 +32,7 @@ fn test_vdir_resize_reader_stability() {
         let mut fallback = 0;
         while !done_r.load(Ordering::Relaxed) {
             // Lookup existing entry
-            if vdir.lookup(1000).is_some() {
+            if vdir.lookup(1000, 0).is_some() {
                 success += 1;
             } else {
                 fallback += 1;
@@ -1,29 +1,85 @@
 #!/usr/bin/env python3
+"""Velo Rift shim log consumer.
+
+Shim log lines are key=value pairs with the quoted message last:
+    [VR-INCEPTION] ts=1700000000.123456 pid=42 level=warn suppressed=3 msg="..."
+
+Usage: vlog.py [--once] [--level LEVEL]
+"""
+
 import glob
+import os
+import re
 import sys
 import time
 
+LEVELS = ["trace", "debug", "info", "warn", "error"]
+FIELD_RE = re.compile(r'(\w+)=("(?:[^"\\]|\\.)*"|\S+)')
+ESCAPE_RE = re.compile(r'\\(x[0-9a-f]{2}|.)')
+ESCAPES = {"n": "\n", "t": "\t"}
+
+
+def unescape(value: str) -> str:
+    def replace(m: re.Match) -> str:
+        esc = m[1]
+        if len(esc) == 3:
+            return chr(int(esc[1:], 16))
+        return ESCAPES.get(esc, esc)
+
+    return ESCAPE_RE.sub(replace, value)
+
+
+def parse_line(line: str) -> dict | None:
+    """Parse one shim log line into a dict, or None if it isn't one."""
+    if not line.startswith("[VR-INCEPTION] "):
+        return None
+    fields = {}
+    for key, value in FIELD_RE.findall(line):
+        if value.startswith('"'):
+            value = unescape(value[1:-1])
+        fields[key] = value
+    return fields
+
 
 def main() -> None:
+    once = "--once" in sys.argv
+    min_level = 0
+    if "--level" in sys.argv:
+        min_level = LEVELS.index(sys.argv[sys.argv.index("--level") + 1])
+
     print("\033[1;34m[VLog] Velo Rift Log Consumer\033[0m")
-    log_pattern = "/tmp/vrift-shim-*.log"
+    log_dir = os.environ.get("VRIFT_LOG_DIR", "/tmp")
+    # Rotated generations (.log.1 ..) sort after the live file; read oldest first
+    log_patterns = [os.path.join(log_dir, "vrift-shim-*.log*")]
 
     seen_files = set()
 
     while True:
-        files = glob.glob(log_pattern)
+        files = [f for pattern in log_patterns for f in glob.glob(pattern)]
+        files.sort(key=os.path.getmtime)
         for f in files:
             if f not in seen_files:
-                pid = f.split("-")[-1].split(".")[0]
+                pid = os.path.basename(f).split("-")[-1].split(".")[0]
                 print(f"\n\033[1;32m--- Log for PID {pid} ({f}) ---\033[0m")
                 try:
                     with open(f, errors="replace") as fd:
-                        print(fd.read())
+                        for line in fd:
+                            record = parse_line(line)
+                            if record is None:
+                                print(line, end="")
+                                continue
+                            level = record.get("level", "info")
+                            if level in LEVELS and LEVELS.index(level) < min_level:
+                                continue
+                            dropped = record.get("suppressed")
+                            suffix = f" (+{dropped} suppressed)" if dropped else ""
+                            ts, msg = record.get("ts", ""), record.get("msg", "")
+                            print(f"{ts} {level:>5} {msg}{suffix}")
                 except Exception as e:
                     print(f"Error reading {f}: {e}")
                 seen_files.add(f)
 
-        if len(sys.argv) > 1 and sys.argv[1] == "--once":
+        if once:
             break
 
         time.sleep(1)
@@ -0,0 +1,149 @@
+#!/bin/bash
+# ============================================================================
+# Test: *at Family Resolution Relative to Directory FDs
+# ============================================================================
+# openat/faccessat with a path relative to a directory fd must reach the VFS
+# the way open/access do. In a phantom-mode project the files only exist in
+# the CAS, so a dirfd-relative open that bypassed the VFS would fail with
+# ENOENT. Covers a dirfd opened with O_DIRECTORY (tracked by the shim), one
+# nested below it, and one the shim never saw open (resolved by the kernel).
+
+set -e
+PROJECT_ROOT="$(cd "$(dirname "${BASH_SOURCE[0]}")/../.." && pwd)"
+BUILD="${VRIFT_BUILD:-release}"
+VRIFT_BIN="$PROJECT_ROOT/target/$BUILD/vrift"
+VRIFTD_BIN="$PROJECT_ROOT/target/$BUILD/vriftd"
+SHIM_LIB="$PROJECT_ROOT/target/$BUILD/libvrift_inception_layer.dylib"
+[ ! -f "$SHIM_LIB" ] && SHIM_LIB="$PROJECT_ROOT/target/$BUILD/libvrift_inception_layer.so"
+
+WORK_DIR="/tmp/vrift_at_family_$$"
+PROJECT="$WORK_DIR/project"
+export VR_THE_SOURCE="$WORK_DIR/cas"
+export VRIFT_SOCKET_PATH="$WORK_DIR/vrift.sock"
+export VRIFT_MANIFEST="$PROJECT/.vrift/manifest.lmdb"
+DAEMON_PID=""
+
+cleanup() {
+    unset LD_PRELOAD DYLD_INSERT_LIBRARIES
+    if [ -n "$DAEMON_PID" ]; then
+        kill -9 "$DAEMON_PID" 2>/dev/null
+        wait "$DAEMON_PID" 2>/dev/null || true
+    fi
+    pkill -f "vdir_d.*$PROJECT" 2>/dev/null || true
+    rm -f "$VRIFT_SOCKET_PATH"
+    [ "$(uname -s)" = "Darwin" ] && chflags -R nouchg "$WORK_DIR" 2>/dev/null
+    chmod -R u+w "$WORK_DIR" 2>/dev/null
+    rm -rf "$WORK_DIR" 2>/dev/null || true
+}
+trap cleanup EXIT
+
+rm -rf "$WORK_DIR"
+mkdir -p "$PROJECT/src/nested" "$VR_THE_SOURCE"
+printf 'top level\n' > "$PROJECT/src/top.txt"
+printf 'one level down\n' > "$PROJECT/src/nested/deep.txt"
+
+echo "----------------------------------------------------------------"
+echo "🧪 *at Family Resolution Relative to Directory FDs"
+echo "----------------------------------------------------------------"
+
+cd "$PROJECT"
+"$VRIFT_BIN" init . >/dev/null 2>&1
+"$VRIFT_BIN" ingest . --mode phantom --output .vrift/manifest.lmdb >/dev/null 2>&1
+if [ -e "$PROJECT/src/top.txt" ]; then
+    echo "❌ FAIL: phantom ingest left src/top.txt on disk"
+    exit 1
+fi
+
+"$VRIFTD_BIN" start >"$WORK_DIR/vriftd.log" 2>&1 &
+DAEMON_PID=$!
+for _ in $(seq 1 20); do
+    [ -S "$VRIFT_SOCKET_PATH" ] && break
+    sleep 0.5
+done
+if [ ! -S "$VRIFT_SOCKET_PATH" ]; then
+    echo "❌ FAIL: vriftd did not start"
+    tail -10 "$WORK_DIR/vriftd.log"
+    exit 1
+fi
+
+export VRIFT_PROJECT_ROOT="$PROJECT"
+export VRIFT_VFS_PREFIX="$PROJECT"
+export VRIFT_INCEPTION=1
+if [ "$(uname -s)" = "Darwin" ]; then
+    export DYLD_INSERT_LIBRARIES="$SHIM_LIB"
+    export DYLD_FORCE_FLAT_NAMESPACE=1
+else
+    export LD_PRELOAD="$SHIM_LIB"
+fi
+
+# vriftd spawns vDird on the first shim connection
+for _ in $(seq 1 20); do
+    cat "$PROJECT/src/top.txt" >/dev/null 2>&1 && break
+    sleep 0.5
+done
+
+FAILED=0
+
+PRELUDE='
+import errno, os, sys
+root = sys.argv[1]
+def read_at(dirfd, name):
+    fd = os.open(name, os.O_RDONLY, dir_fd=dirfd)
+    try:
+        return os.read(fd, 4096).decode().strip()
+    finally:
+        os.close(fd)
+'
+
+# run_case <name> <expected> <python snippet>
+run_case() {
+    local name="$1" expected="$2" body="$3"
+    echo -n "  $name ... "
+    local out
+    out=$(python3 -c "$PRELUDE$body" "$PROJECT" 2>&1 || true)
+    if [ "$out" = "$expected" ]; then
+        echo "✅ PASS"
+    else
+        echo "❌ FAIL (got: '$out')"
+        FAILED=$((FAILED + 1))
+    fi
+}
+
+run_case "openat relative to an O_DIRECTORY fd" "top level" '
+src = os.open(os.path.join(root, "src"), os.O_RDONLY | os.O_DIRECTORY)
+print(read_at(src, "top.txt"))'
+
+run_case "openat relative to a nested dirfd" "one level down" '
+src = os.open(os.path.join(root, "src"), os.O_RDONLY | os.O_DIRECTORY)
+nested = os.open("nested", os.O_RDONLY | os.O_DIRECTORY, dir_fd=src)
+print(read_at(nested, "deep.txt"))'
+
+run_case "openat with .. through a dirfd" "top level" '
+nested = os.open(os.path.join(root, "src/nested"), os.O_RDONLY | os.O_DIRECTORY)
+print(read_at(nested, "../top.txt"))'
+
+# An O_PATH fd is opened without O_DIRECTORY, so the shim asks the kernel
+if [ "$(uname -s)" = "Linux" ]; then
+    run_case "openat relative to an untracked dirfd" "top level" '
+src = os.open(os.path.join(root, "src"), os.O_PATH)
+print(read_at(src, "top.txt"))'
+fi
+
+run_case "faccessat relative to a dirfd" "True" '
+src = os.open(os.path.join(root, "src"), os.O_RDONLY | os.O_DIRECTORY)
+print(os.access("top.txt", os.R_OK, dir_fd=src))'
+
+run_case "openat outside the VFS is untouched" "ENOENT" "
+tmp = os.open('/tmp', os.O_RDONLY | os.O_DIRECTORY)
+try:
+    os.open('vrift-no-such-file-$$', os.O_RDONLY, dir_fd=tmp)
+    print('opened')
+except OSError as e:
+    print('ENOENT' if e.errno == errno.ENOENT else e)"
+
+echo "----------------------------------------------------------------"
+if [ "$FAILED" -ne 0 ]; then
+    echo "❌ $FAILED *at case(s) failed"
+    exit 1
+fi
+echo "✅ *at calls resolve relative to directory FDs"
@@ -0,0 +1,220 @@
+#!/bin/bash
+# ============================================================================
+# Test: CoW Offsets for O_APPEND and pwrite
+# ============================================================================
+# A write open of an ingested file works on a staged copy of the blob, so
+# every offset the program sees must match the original content exactly:
+#
+#   O_APPEND after interleaved reads | pwrite inside and past EOF
+#   two fds appending to the same file | dup'd fd closed before the original
+#   O_CREAT|O_EXCL on a managed file | fd inherited by a forked child
+#
+# After each case the file is read back through the shim and its sha256
+# compared with the expected bytes.
+
+set -e
+PROJECT_ROOT="$(cd "$(dirname "${BASH_SOURCE[0]}")/../.." && pwd)"
+BUILD="${VRIFT_BUILD:-release}"
+VRIFT_BIN="$PROJECT_ROOT/target/$BUILD/vrift"
+VRIFTD_BIN="$PROJECT_ROOT/target/$BUILD/vriftd"
+SHIM_LIB="$PROJECT_ROOT/target/$BUILD/libvrift_inception_layer.dylib"
+[ ! -f "$SHIM_LIB" ] && SHIM_LIB="$PROJECT_ROOT/target/$BUILD/libvrift_inception_layer.so"
+
+WORK_DIR="/tmp/vrift_cow_offsets_$$"
+PROJECT="$WORK_DIR/project"
+export VR_THE_SOURCE="$WORK_DIR/cas"
+export VRIFT_SOCKET_PATH="$WORK_DIR/vrift.sock"
+# vDird inherits this from vriftd and serves the ingested manifest
+export VRIFT_MANIFEST="$PROJECT/.vrift/manifest.lmdb"
+DAEMON_PID=""
+
+cleanup() {
+    if [ -n "$DAEMON_PID" ]; then
+        kill -9 "$DAEMON_PID" 2>/dev/null
+        wait "$DAEMON_PID" 2>/dev/null || true
+    fi
+    pkill -f "vdir_d.*$PROJECT" 2>/dev/null || true
+    rm -f "$VRIFT_SOCKET_PATH"
+    [ "$(uname -s)" = "Darwin" ] && chflags -R nouchg "$WORK_DIR" 2>/dev/null
+    rm -rf "$WORK_DIR" 2>/dev/null || true
+}
+trap cleanup EXIT
+
+rm -rf "$WORK_DIR"
+mkdir -p "$PROJECT/src" "$VR_THE_SOURCE"
+
+# Sizes around a page boundary so offsets past the first block are covered
+CASES="append_interleaved pwrite_inside pwrite_past_eof append_two_fds dup_close_first fork_child_closes fork_child_writes excl_create"
+for name in $CASES; do
+    python3 -c 'import sys; open(sys.argv[1], "wb").write(bytes(i % 251 for i in range(4100)))' \
+        "$PROJECT/src/$name.bin"
+done
+
+echo "----------------------------------------------------------------"
+echo "🧪 CoW Offsets: O_APPEND and pwrite"
+echo "----------------------------------------------------------------"
+
+cd "$PROJECT"
+"$VRIFT_BIN" init . >/dev/null 2>&1
+"$VRIFT_BIN" ingest . --mode phantom --output .vrift/manifest.lmdb >/dev/null 2>&1
+
+"$VRIFTD_BIN" start >"$WORK_DIR/vriftd.log" 2>&1 &
+DAEMON_PID=$!
+for _ in $(seq 1 20); do
+    [ -S "$VRIFT_SOCKET_PATH" ] && break
+    sleep 0.5
+done
+if [ ! -S "$VRIFT_SOCKET_PATH" ]; then
+    echo "❌ FAIL: vriftd did not start"
+    tail -10 "$WORK_DIR/vriftd.log"
+    exit 1
+fi
+
+export VRIFT_PROJECT_ROOT="$PROJECT"
+export VRIFT_VFS_PREFIX="$PROJECT"
+export VRIFT_INCEPTION=1
+if [ "$(uname -s)" = "Darwin" ]; then
+    export DYLD_INSERT_LIBRARIES="$SHIM_LIB"
+    export DYLD_FORCE_FLAT_NAMESPACE=1
+else
+    export LD_PRELOAD="$SHIM_LIB"
+fi
+
+# vriftd spawns vDird on the first shim connection
+for _ in $(seq 1 20); do
+    cat "$PROJECT/src/excl_create.bin" >/dev/null 2>&1 && break
+    sleep 0.5
+done
+
+# Each case: <name> <python mutation>. The mutation starts from `orig` (the
+# ingested bytes), applies the same edits to `want` and asserts every offset
+# and read it observes on the way; the file is then read back and compared
+# against `want`.
+run_case() {
+    local name="$1" mutation="$2"
+    local path="$PROJECT/src/$name.bin"
+    echo -n "  $name ... "
+
+    local out
+    if ! out=$(python3 - "$path" 2>&1 <<EOF
+import errno, hashlib, os, sys
+path = sys.argv[1]
+orig = bytes(i % 251 for i in range(4100))
+want = bytearray(orig)
+$mutation
+sys.stdout.write(hashlib.sha256(want).hexdigest())
+EOF
+    ); then
+        echo "❌ FAIL (mutation: $out)"
+        FAILED=$((FAILED + 1))
+        return
+    fi
+
+    local actual
+    actual=$(python3 -c 'import hashlib, sys; sys.stdout.write(hashlib.sha256(open(sys.argv[1], "rb").read()).hexdigest())' "$path" 2>&1) || true
+    if [ "$actual" == "$out" ]; then
+        echo "✅ PASS"
+    else
+        echo "❌ FAIL (read back sha256 $actual, want $out)"
+        FAILED=$((FAILED + 1))
+    fi
+}
+
+FAILED=0
+
+run_case append_interleaved '
+fd = os.open(path, os.O_RDWR | os.O_APPEND)
+assert os.read(fd, 10) == orig[:10]
+os.write(fd, b"tail-1")
+want += b"tail-1"
+# O_APPEND leaves the offset at the new end of file
+assert os.lseek(fd, 0, os.SEEK_CUR) == len(want), os.lseek(fd, 0, os.SEEK_CUR)
+os.lseek(fd, 100, os.SEEK_SET)
+assert os.read(fd, 5) == bytes(want[100:105])
+os.write(fd, b"tail-2")
+want += b"tail-2"
+assert os.pread(fd, 12, len(orig)) == b"tail-1tail-2"
+assert os.fstat(fd).st_size == len(want)
+os.close(fd)'
+
+run_case pwrite_inside '
+fd = os.open(path, os.O_RDWR)
+assert os.read(fd, 4) == orig[:4]
+os.pwrite(fd, b"PAGE", 4094)
+want[4094:4098] = b"PAGE"
+# pwrite must not move the file offset
+assert os.lseek(fd, 0, os.SEEK_CUR) == 4
+assert os.read(fd, 4) == orig[4:8]
+assert os.pread(fd, 6, 4093) == bytes(want[4093:4099])
+os.close(fd)'
+
+run_case pwrite_past_eof '
+fd = os.open(path, os.O_WRONLY)
+os.pwrite(fd, b"END", len(orig) + 10)
+want += b"\0" * 10 + b"END"
+assert os.fstat(fd).st_size == len(want)
+os.close(fd)'
+
+run_case append_two_fds '
+a = os.open(path, os.O_WRONLY | os.O_APPEND)
+b = os.open(path, os.O_RDWR | os.O_APPEND)
+os.write(a, b"A1")
+os.write(b, b"B1")
+os.write(a, b"A2")
+want += b"A1B1A2"
+assert os.pread(b, 6, len(orig)) == b"A1B1A2"
+os.close(a)
+os.write(b, b"B2")
+want += b"B2"
+os.close(b)'
+
+run_case dup_close_first '
+fd = os.open(path, os.O_RDWR)
+dup = os.dup(fd)
+os.pwrite(fd, b"xy", 0)
+os.close(fd)
+# the dup still writes to the same staged copy
+os.pwrite(dup, b"z", 2)
+want[0:3] = b"xyz"
+os.close(dup)'
+
+run_case fork_child_closes '
+fd = os.open(path, os.O_RDWR)
+os.write(fd, b"P1")
+pid = os.fork()
+if pid == 0:
+    # the child inherits the fd, not the staged copy: its close and exit
+    # must leave the copy to the parent
+    os.write(fd, b"C")
+    os.close(fd)
+    sys.exit(0)
+assert os.waitpid(pid, 0)[1] == 0
+os.pwrite(fd, b"P2", 10)
+want[0:3] = b"P1C"
+want[10:12] = b"P2"
+os.close(fd)'
+
+run_case fork_child_writes '
+pid = os.fork()
+if pid == 0:
+    # a forked child writes back its own copies, without the parent
+    fd = os.open(path, os.O_WRONLY | os.O_APPEND)
+    os.write(fd, b"child")
+    os.close(fd)
+    sys.exit(0)
+assert os.waitpid(pid, 0)[1] == 0
+want += b"child"'
+
+run_case excl_create '
+try:
+    os.close(os.open(path, os.O_WRONLY | os.O_CREAT | os.O_EXCL, 0o644))
+    sys.exit("O_EXCL create of a managed file succeeded")
+except OSError as e:
+    assert e.errno == errno.EEXIST, e'
+
+echo "----------------------------------------------------------------"
+if [ "$FAILED" -ne 0 ]; then
+    echo "❌ $FAILED CoW offset case(s) failed"
+    exit 1
+fi
+echo "✅ All CoW offset cases passed"
@@ -0,0 +1,138 @@
+#!/bin/bash
+# ============================================================================
+# Test: Dry Runs (--dry-run on ingest, gc and pack build)
+# ============================================================================
+# A dry run asks vriftd for the plan of a mutating command: the files an
+# ingest would hash or move, the blobs a sweep would delete, the blobs a
+# pack build would copy. The plan has to name exactly those, and nothing
+# may change: no manifest, no CAS blob, no GC journal, no packfile.
+
+set -e
+PROJECT_ROOT="$(cd "$(dirname "${BASH_SOURCE[0]}")/../.." && pwd)"
+BUILD="${VRIFT_BUILD:-release}"
+VRIFT_BIN="$PROJECT_ROOT/target/$BUILD/vrift"
+VRIFTD_BIN="$PROJECT_ROOT/target/$BUILD/vriftd"
+
+WORK_DIR="/tmp/vrift_dry_run_$$"
+PROJECT="$WORK_DIR/project"
+export HOME="$WORK_DIR/home"
+export VR_THE_SOURCE="$WORK_DIR/cas"
+export VRIFT_SOCKET_PATH="$WORK_DIR/vrift.sock"
+DAEMON_PID=""
+
+cleanup() {
+    if [ -n "$DAEMON_PID" ]; then
+        kill -9 "$DAEMON_PID" 2>/dev/null
+        wait "$DAEMON_PID" 2>/dev/null || true
+    fi
+    pkill -f "vdir_d.*$PROJECT" 2>/dev/null || true
+    rm -f "$VRIFT_SOCKET_PATH"
+    [ "$(uname -s)" = "Darwin" ] && chflags -R nouchg "$WORK_DIR" 2>/dev/null
+    chmod -R u+w "$WORK_DIR" 2>/dev/null || true
+    rm -rf "$WORK_DIR" 2>/dev/null || true
+}
+trap cleanup EXIT
+
+rm -rf "$WORK_DIR"
+mkdir -p "$PROJECT/src" "$VR_THE_SOURCE" "$HOME/.vrift"
+printf 'alpha %s\n' "$$" > "$PROJECT/src/a.txt"
+printf 'beta %s\n' "$$" > "$PROJECT/src/b.txt"
+# Orphans are due for deletion at once, so the sweep plan shows deletes
+printf '[gc]\ngrace_secs = 0\n' > "$HOME/.vrift/config.toml"
+
+echo "----------------------------------------------------------------"
+echo "🧪 Dry Runs"
+echo "----------------------------------------------------------------"
+
+(cd "$PROJECT" && "$VRIFT_BIN" init . >/dev/null 2>&1)
+
+"$VRIFTD_BIN" start >"$WORK_DIR/vriftd.log" 2>&1 &
+DAEMON_PID=$!
+for _ in $(seq 1 20); do
+    [ -S "$VRIFT_SOCKET_PATH" ] && break
+    sleep 0.5
+done
+if [ ! -S "$VRIFT_SOCKET_PATH" ]; then
+    echo "❌ FAIL: vriftd did not start"
+    tail -10 "$WORK_DIR/vriftd.log"
+    exit 1
+fi
+
+FAILED=0
+check() {
+    echo -n "  $1 ... "
+    if [ "$2" = "$3" ]; then
+        echo "✅ PASS"
+    else
+        echo "❌ FAIL (expected '$2', got '$3')"
+        FAILED=$((FAILED + 1))
+    fi
+}
+
+# <action> <target suffix> pairs of a JSON plan on stdin, sorted
+plan_steps() {
+    python3 -c '
+import json, os, sys
+plan = json.load(sys.stdin)
+print(" ".join(sorted(s["action"] + ":" + os.path.basename(s["target"]) for s in plan["steps"])))'
+}
+
+blob_count() {
+    find "$VR_THE_SOURCE" -type f -path '*blake3*' 2>/dev/null | wc -l | tr -d ' '
+}
+
+vrift() {
+    (cd "$PROJECT" && "$VRIFT_BIN" "$@")
+}
+
+# --- ingest ------------------------------------------------------------------
+rm -rf "$PROJECT/.vrift/manifest.lmdb"
+plan=$(vrift ingest . --output .vrift/manifest.lmdb --dry-run --json 2>/dev/null)
+check "ingest plan hashes every file" "hash:a.txt hash:b.txt" "$(echo "$plan" | plan_steps)"
+check "ingest dry run writes no manifest" "no" \
+    "$([ -e "$PROJECT/.vrift/manifest.lmdb" ] && echo yes || echo no)"
+check "ingest dry run stores no blob" "0" "$(blob_count)"
+
+plan=$(vrift ingest . --output .vrift/manifest.lmdb --mode phantom --dry-run --json 2>/dev/null)
+check "phantom plan moves every file" "move:a.txt move:b.txt" "$(echo "$plan" | plan_steps)"
+check "phantom dry run leaves the files" "yes" \
+    "$([ -f "$PROJECT/src/a.txt" ] && [ -f "$PROJECT/src/b.txt" ] && echo yes || echo no)"
+
+vrift ingest . --output .vrift/manifest.lmdb >/dev/null 2>&1
+# Ingested files are immutable now, so the change is a new file
+printf 'gamma %s\n' "$$" > "$PROJECT/src/c.txt"
+out=$(vrift ingest . --output .vrift/manifest.lmdb --dry-run 2>/dev/null)
+check "re-ingest plan hashes only the new file" "1 0" \
+    "$(echo "$out" | grep -c '^  hash .*src/c.txt$') $(echo "$out" | grep -c 'src/[ab].txt')"
+check "re-ingest plan counts the unchanged ones" "yes" \
+    "$(echo "$out" | grep -q '2 files unchanged' && echo yes || echo no)"
+
+# --- gc ----------------------------------------------------------------------
+# A blob only a discarded manifest referenced is an orphan
+mkdir -p "$WORK_DIR/scratch"
+printf 'orphan %s\n' "$$" > "$WORK_DIR/scratch/orphan.txt"
+(cd "$WORK_DIR" && "$VRIFT_BIN" ingest scratch --output "$WORK_DIR/scratch.lmdb" >/dev/null 2>&1)
+rm -rf "$WORK_DIR/scratch.lmdb"
+orphan=$(find "$VR_THE_SOURCE" -type f -path '*blake3*' -exec grep -lF "orphan $$" {} + | head -1)
+vrift ingest . --output .vrift/manifest.lmdb >/dev/null 2>&1
+plan=$(vrift gc --dry-run --json 2>/dev/null)
+# Blobs are stored as <hash>_<size>.bin, plans name them by hash
+check "sweep plan deletes the orphan" "delete:$(basename "$orphan" | cut -d_ -f1)" \
+    "$(echo "$plan" | plan_steps)"
+check "gc dry run deletes nothing" "yes" "$([ -f "$orphan" ] && echo yes || echo no)"
+check "gc dry run writes no GC journal" "0" \
+    "$(find "$VR_THE_SOURCE" -maxdepth 1 -name 'gc-journal.*' | wc -l | tr -d ' ')"
+
+# --- pack build --------------------------------------------------------------
+check "pack plan needs a profile" "yes" \
+    "$(vrift pack build --dry-run 2>&1 | grep -q 'No access profile' && echo yes || echo no)"
+check "pack dry run saves no profile" "0" \
+    "$(find "$HOME/.vrift" -name '*.profile' 2>/dev/null | wc -l | tr -d ' ')"
+
+echo "----------------------------------------------------------------"
+if [ "$FAILED" -ne 0 ]; then
+    echo "❌ $FAILED dry-run case(s) failed"
+    tail -20 "$WORK_DIR/vriftd.log"
+    exit 1
+fi
+echo "✅ Dry runs report their plan and change nothing"
@@ -0,0 +1,178 @@
+#!/bin/bash
+# ============================================================================
+# Test: Shim Environment Survives Curated Child Environments
+# ============================================================================
+# A program under the shim that starts children with an environment of its
+# own making (subprocess env=, posix_spawn with envp) must still get them
+# into the VFS: the shim's variables come back from its own environment.
+# A child whose parent deliberately unset them (env -u) stays outside.
+#
+# The project is ingested in phantom mode, so its files are only readable
+# through the shim.
+
+set -e
+PROJECT_ROOT="$(cd "$(dirname "${BASH_SOURCE[0]}")/../.." && pwd)"
+BUILD="${VRIFT_BUILD:-release}"
+VRIFT_BIN="$PROJECT_ROOT/target/$BUILD/vrift"
+VRIFTD_BIN="$PROJECT_ROOT/target/$BUILD/vriftd"
+SHIM_LIB="$PROJECT_ROOT/target/$BUILD/libvrift_inception_layer.dylib"
+[ ! -f "$SHIM_LIB" ] && SHIM_LIB="$PROJECT_ROOT/target/$BUILD/libvrift_inception_layer.so"
+
+WORK_DIR="/tmp/vrift_exec_env_$$"
+PROJECT="$WORK_DIR/project"
+export VR_THE_SOURCE="$WORK_DIR/cas"
+export VRIFT_SOCKET_PATH="$WORK_DIR/vrift.sock"
+export VRIFT_MANIFEST="$PROJECT/.vrift/manifest.lmdb"
+DAEMON_PID=""
+
+cleanup() {
+    if [ -n "$DAEMON_PID" ]; then
+        kill -9 "$DAEMON_PID" 2>/dev/null
+        wait "$DAEMON_PID" 2>/dev/null || true
+    fi
+    pkill -f "vdir_d.*$PROJECT" 2>/dev/null || true
+    rm -f "$VRIFT_SOCKET_PATH"
+    [ "$(uname -s)" = "Darwin" ] && chflags -R nouchg "$WORK_DIR" 2>/dev/null
+    chmod -R u+w "$WORK_DIR" 2>/dev/null || true
+    rm -rf "$WORK_DIR" 2>/dev/null || true
+}
+trap cleanup EXIT
+
+rm -rf "$WORK_DIR"
+mkdir -p "$PROJECT/src" "$VR_THE_SOURCE"
+echo "phantom content" >"$PROJECT/src/data.txt"
+
+echo "----------------------------------------------------------------"
+echo "🧪 Exec: Shim Environment in Curated Child Environments"
+echo "----------------------------------------------------------------"
+
+cd "$PROJECT"
+"$VRIFT_BIN" init . >/dev/null 2>&1
+"$VRIFT_BIN" ingest . --mode phantom --output .vrift/manifest.lmdb >/dev/null 2>&1
+if [ -e "$PROJECT/src/data.txt" ]; then
+    echo "❌ FAIL: phantom ingest left src/data.txt on disk"
+    exit 1
+fi
+
+"$VRIFTD_BIN" start >"$WORK_DIR/vriftd.log" 2>&1 &
+DAEMON_PID=$!
+for _ in $(seq 1 20); do
+    [ -S "$VRIFT_SOCKET_PATH" ] && break
+    sleep 0.5
+done
+if [ ! -S "$VRIFT_SOCKET_PATH" ]; then
+    echo "❌ FAIL: vriftd did not start"
+    tail -10 "$WORK_DIR/vriftd.log"
+    exit 1
+fi
+
+export VRIFT_PROJECT_ROOT="$PROJECT"
+export VRIFT_VFS_PREFIX="$PROJECT"
+export VRIFT_INCEPTION=1
+if [ "$(uname -s)" = "Darwin" ]; then
+    PRELOAD_VAR=DYLD_INSERT_LIBRARIES
+    export DYLD_INSERT_LIBRARIES="$SHIM_LIB"
+    export DYLD_FORCE_FLAT_NAMESPACE=1
+else
+    PRELOAD_VAR=LD_PRELOAD
+    export LD_PRELOAD="$SHIM_LIB"
+fi
+
+# vriftd spawns vDird on the first shim connection
+for _ in $(seq 1 20); do
+    cat "$PROJECT/src/data.txt" >/dev/null 2>&1 && break
+    sleep 0.5
+done
+
+FAILED=0
+# <name> <python body>: the body runs under the shim with `path`, `bare`
+# (an environment with only PATH) and `preload` set, and must print "ok"
+run_case() {
+    local name="$1" body="$2"
+    echo -n "  $name ... "
+    local out
+    out=$(python3 - "$PROJECT/src/data.txt" "$PRELOAD_VAR" 2>&1 <<EOF
+import os, subprocess, sys
+path, preload = sys.argv[1], sys.argv[2]
+bare = {"PATH": os.environ["PATH"]}
+$body
+EOF
+    ) || true
+    if [ "$out" == "ok" ]; then
+        echo "✅ PASS"
+    else
+        echo "❌ FAIL ($out)"
+        FAILED=$((FAILED + 1))
+    fi
+}
+
+run_case subprocess_bare_env '
+out = subprocess.run(["cat", path], env=bare, capture_output=True)
+assert out.stdout == b"phantom content\n", out
+print("ok")'
+
+run_case child_sees_shim_vars '
+out = subprocess.run(["env"], env=bare, capture_output=True, text=True).stdout
+lines = out.splitlines()
+assert any(l.startswith(preload + "=") for l in lines), out
+assert "VRIFT_VFS_PREFIX=" + os.environ["VRIFT_VFS_PREFIX"] in lines, out
+print("ok")'
+
+run_case explicit_value_wins '
+os.environ["VRIFT_PROFILE"] = "parent"
+env = dict(bare, VRIFT_PROFILE="mine")
+out = subprocess.run(["env"], env=env, capture_output=True, text=True).stdout
+assert "VRIFT_PROFILE=mine" in out.splitlines(), out
+print("ok")'
+
+run_case preload_replaced '
+env = dict(bare, **{preload: ""})
+out = subprocess.run(["cat", path], env=env, capture_output=True)
+assert out.stdout == b"phantom content\n", out
+print("ok")'
+
+run_case posix_spawn_envp '
+import shutil
+r, w = os.pipe()
+pid = os.posix_spawn(shutil.which("cat"), ["cat", path], bare,
+                     file_actions=[(os.POSIX_SPAWN_DUP2, w, 1)])
+os.close(w)
+data = os.read(r, 100)
+assert os.waitpid(pid, 0)[1] == 0
+assert data == b"phantom content\n", data
+print("ok")'
+
+run_case posix_spawnp_envp '
+r, w = os.pipe()
+pid = os.posix_spawnp("cat", ["cat", path], bare,
+                      file_actions=[(os.POSIX_SPAWN_DUP2, w, 1)])
+os.close(w)
+data = os.read(r, 100)
+assert os.waitpid(pid, 0)[1] == 0
+assert data == b"phantom content\n", data
+print("ok")'
+
+run_case execvpe_bare_env '
+r, w = os.pipe()
+pid = os.fork()
+if pid == 0:
+    os.dup2(w, 1)
+    os.execvpe("cat", ["cat", path], bare)
+os.close(w)
+data = os.read(r, 100)
+assert os.waitpid(pid, 0)[1] == 0
+assert data == b"phantom content\n", data
+print("ok")'
+
+# Unset on purpose, in the environment of the process that execs
+run_case env_unset_opts_out '
+out = subprocess.run(["env", "-u", preload, "cat", path], capture_output=True)
+assert out.returncode != 0, out
+print("ok")'
+
+echo "----------------------------------------------------------------"
+if [ "$FAILED" -ne 0 ]; then
+    echo "❌ $FAILED exec environment case(s) failed"
+    exit 1
+fi
+echo "✅ All exec environment cases passed"
@@ -0,0 +1,143 @@
+#!/bin/bash
+# ============================================================================
+# Test: Ingest Scanner Quarantine ([ingest.scanner])
+# ============================================================================
+# vriftd runs with a scanner script that flags files containing a test
+# signature. Ingesting a project must keep the flagged file out of the CAS
+# and the manifest and log it, while clean files go in as usual. A file an
+# earlier, unscanned ingest let in must leave the manifest on the next one,
+# and a scanner that fails must quarantine its whole batch.
+
+set -e
+PROJECT_ROOT="$(cd "$(dirname "${BASH_SOURCE[0]}")/../.." && pwd)"
+BUILD="${VRIFT_BUILD:-release}"
+VRIFT_BIN="$PROJECT_ROOT/target/$BUILD/vrift"
+VRIFTD_BIN="$PROJECT_ROOT/target/$BUILD/vriftd"
+
+WORK_DIR="/tmp/vrift_ingest_scanner_$$"
+PROJECT="$WORK_DIR/project"
+export VR_THE_SOURCE="$WORK_DIR/cas"
+export VRIFT_SOCKET_PATH="$WORK_DIR/vrift.sock"
+SIGNATURE="VRIFT-TEST-SIGNATURE-$$"
+DAEMON_PID=""
+
+stop_daemon() {
+    if [ -n "$DAEMON_PID" ]; then
+        kill -9 "$DAEMON_PID" 2>/dev/null
+        wait "$DAEMON_PID" 2>/dev/null || true
+        DAEMON_PID=""
+    fi
+    rm -f "$VRIFT_SOCKET_PATH"
+}
+
+cleanup() {
+    stop_daemon
+    [ "$(uname -s)" = "Darwin" ] && chflags -R nouchg "$WORK_DIR" 2>/dev/null
+    chmod -R u+w "$WORK_DIR" 2>/dev/null || true
+    rm -rf "$WORK_DIR" 2>/dev/null || true
+}
+trap cleanup EXIT
+
+# start_daemon <log> [scanner command]
+start_daemon() {
+    stop_daemon
+    VRIFT_INGEST_SCANNER="$2" "$VRIFTD_BIN" start >"$1" 2>&1 &
+    DAEMON_PID=$!
+    for _ in $(seq 1 20); do
+        [ -S "$VRIFT_SOCKET_PATH" ] && return 0
+        sleep 0.5
+    done
+    echo "❌ FAIL: vriftd did not start"
+    tail -10 "$1"
+    exit 1
+}
+
+ingest() {
+    (cd "$PROJECT" && "$VRIFT_BIN" ingest . --output .vrift/manifest.lmdb >/dev/null 2>&1)
+}
+
+# Paths of the manifest, one per line
+manifest_paths() {
+    (cd "$PROJECT" && "$VRIFT_BIN" lock -m .vrift/manifest.lmdb -o "$WORK_DIR/manifest.sum" \
+        >/dev/null 2>&1)
+    grep -v '^#' "$WORK_DIR/manifest.sum" | awk '{print $NF}'
+}
+
+cas_has() {
+    grep -rlF "$1" "$VR_THE_SOURCE/blake3" >/dev/null 2>&1
+}
+
+rm -rf "$WORK_DIR"
+mkdir -p "$PROJECT/src" "$VR_THE_SOURCE"
+printf 'clean content %s\n' "$$" > "$PROJECT/src/clean.txt"
+printf 'payload %s\n' "$SIGNATURE" > "$PROJECT/src/infected.bin"
+
+cat > "$WORK_DIR/scanner.sh" <<EOF
+#!/bin/sh
+while IFS= read -r path; do
+    if grep -qF "$SIGNATURE" "\$path"; then
+        printf '%s\tTest.Signature\n' "\$path"
+    fi
+done
+EOF
+printf '#!/bin/sh\ncat >/dev/null\nexit 2\n' > "$WORK_DIR/broken_scanner.sh"
+chmod +x "$WORK_DIR/scanner.sh" "$WORK_DIR/broken_scanner.sh"
+
+echo "----------------------------------------------------------------"
+echo "🧪 Ingest Scanner Quarantine"
+echo "----------------------------------------------------------------"
+
+(cd "$PROJECT" && "$VRIFT_BIN" init . >/dev/null 2>&1)
+
+FAILED=0
+check() {
+    echo -n "  $1 ... "
+    if [ "$2" = "$3" ]; then
+        echo "✅ PASS"
+    else
+        echo "❌ FAIL (expected '$2', got '$3')"
+        FAILED=$((FAILED + 1))
+    fi
+}
+
+start_daemon "$WORK_DIR/vriftd.log" "$WORK_DIR/scanner.sh"
+ingest
+
+check "flagged file kept out of the CAS" "no" "$(cas_has "$SIGNATURE" && echo yes || echo no)"
+check "clean file ingested into the CAS" "yes" "$(cas_has "clean content $$" && echo yes || echo no)"
+paths=$(manifest_paths)
+check "flagged file kept out of the manifest" "no" \
+    "$(echo "$paths" | grep -qx '/src/infected.bin' && echo yes || echo no)"
+check "clean file in the manifest" "yes" \
+    "$(echo "$paths" | grep -qx '/src/clean.txt' && echo yes || echo no)"
+check "quarantine logged with the reason" "yes" \
+    "$(grep 'Quarantined at ingest' "$WORK_DIR/vriftd.log" | grep 'infected.bin' \
+        | grep -q 'Test.Signature' && echo yes || echo no)"
+
+# A file let in while no scanner ran leaves the manifest once one flags it
+printf 'late payload %s\n' "$SIGNATURE" > "$PROJECT/src/late.bin"
+start_daemon "$WORK_DIR/vriftd_unscanned.log" ""
+ingest
+check "unscanned ingest lets the file in" "yes" \
+    "$(manifest_paths | grep -qx '/src/late.bin' && echo yes || echo no)"
+start_daemon "$WORK_DIR/vriftd_rescan.log" "$WORK_DIR/scanner.sh"
+ingest
+check "rescan drops it from the manifest" "no" \
+    "$(manifest_paths | grep -qx '/src/late.bin' && echo yes || echo no)"
+
+# A scanner that cannot vouch for a batch keeps all of it out
+printf 'unvetted %s\n' "$$" > "$PROJECT/src/unvetted.txt"
+start_daemon "$WORK_DIR/vriftd_broken.log" "$WORK_DIR/broken_scanner.sh"
+ingest
+check "failing scanner quarantines its batch" "no" \
+    "$(cas_has "unvetted $$" && echo yes || echo no)"
+check "failure is logged" "yes" \
+    "$(grep -q 'scanner failed' "$WORK_DIR/vriftd_broken.log" && echo yes || echo no)"
+
+echo "----------------------------------------------------------------"
+if [ "$FAILED" -ne 0 ]; then
+    echo "❌ $FAILED ingest scanner case(s) failed"
+    tail -20 "$WORK_DIR/vriftd.log"
+    exit 1
+fi
+echo "✅ Files flagged by the ingest scanner are quarantined"
@@ -0,0 +1,131 @@
+#!/bin/bash
+# ============================================================================
+# Test: Skipped Files Kept As External Entries ([ingest.skip] reference)
+# ============================================================================
+# With `reference = true` a file the skip policy keeps out of the CAS stays
+# in the manifest as an external entry: the CAS holds only its path and the
+# shim serves the real file in its place.
+#
+#   skipped file in the manifest        | listed, content not in the CAS
+#   read through the shim               | the real content
+#   real file grows, stat through shim  | the new size, not the ingested one
+
+set -e
+PROJECT_ROOT="$(cd "$(dirname "${BASH_SOURCE[0]}")/../.." && pwd)"
+BUILD="${VRIFT_BUILD:-release}"
+VRIFT_BIN="$PROJECT_ROOT/target/$BUILD/vrift"
+VRIFTD_BIN="$PROJECT_ROOT/target/$BUILD/vriftd"
+if [ "$(uname -s)" = "Darwin" ]; then
+    SHIM_LIB="$PROJECT_ROOT/target/$BUILD/libvrift_inception_layer.dylib"
+    PRELOAD_VAR="DYLD_INSERT_LIBRARIES"
+else
+    SHIM_LIB="$PROJECT_ROOT/target/$BUILD/libvrift_inception_layer.so"
+    PRELOAD_VAR="LD_PRELOAD"
+fi
+
+WORK_DIR="/tmp/vrift_skip_reference_$$"
+PROJECT="$WORK_DIR/project"
+export HOME="$WORK_DIR/home"
+export VR_THE_SOURCE="$WORK_DIR/cas"
+export VRIFT_SOCKET_PATH="$WORK_DIR/vrift.sock"
+export VRIFT_MANIFEST="$PROJECT/.vrift/manifest.lmdb"
+MARKER="large-asset-$$"
+DAEMON_PID=""
+
+cleanup() {
+    if [ -n "$DAEMON_PID" ]; then
+        kill -9 "$DAEMON_PID" 2>/dev/null
+        wait "$DAEMON_PID" 2>/dev/null || true
+    fi
+    pkill -9 -f "vdir_d.*$PROJECT" 2>/dev/null || true
+    rm -f "$VRIFT_SOCKET_PATH"
+    [ "$(uname -s)" = "Darwin" ] && chflags -R nouchg "$WORK_DIR" 2>/dev/null
+    chmod -R u+w "$WORK_DIR" 2>/dev/null || true
+    rm -rf "$WORK_DIR" 2>/dev/null || true
+}
+trap cleanup EXIT
+
+rm -rf "$WORK_DIR"
+mkdir -p "$PROJECT/src" "$PROJECT/assets" "$VR_THE_SOURCE" "$HOME/.vrift"
+printf 'small %s\n' "$$" >"$PROJECT/src/small.txt"
+{
+    printf '%s\n' "$MARKER"
+    head -c 200 /dev/zero | tr '\0' 'x'
+} >"$PROJECT/assets/big.dat"
+BIG_SIZE=$(wc -c <"$PROJECT/assets/big.dat" | tr -d ' ')
+
+cat >"$HOME/.vrift/config.toml" <<EOF
+[ingest.skip]
+max_file_bytes = 64
+reference = true
+EOF
+
+echo "----------------------------------------------------------------"
+echo "🧪 Ingest: Skipped Files As External Entries"
+echo "----------------------------------------------------------------"
+
+"$VRIFTD_BIN" start >"$WORK_DIR/vriftd.log" 2>&1 &
+DAEMON_PID=$!
+for _ in $(seq 1 20); do
+    [ -S "$VRIFT_SOCKET_PATH" ] && break
+    sleep 0.5
+done
+if [ ! -S "$VRIFT_SOCKET_PATH" ]; then
+    echo "❌ FAIL: vriftd did not start"
+    tail -10 "$WORK_DIR/vriftd.log"
+    exit 1
+fi
+
+cd "$PROJECT"
+"$VRIFT_BIN" init . >/dev/null 2>&1
+"$VRIFT_BIN" ingest . --output .vrift/manifest.lmdb >/dev/null 2>&1
+
+FAILED=0
+check() {
+    if [ "$1" = "$2" ]; then
+        echo "✅ PASS"
+    else
+        echo "❌ FAIL (got '$1', want '$2')"
+        FAILED=$((FAILED + 1))
+    fi
+}
+
+echo -n "  skipped file is in the manifest ... "
+"$VRIFT_BIN" lock -m .vrift/manifest.lmdb -o "$WORK_DIR/manifest.sum" >/dev/null 2>&1
+listed=$(grep -c '/assets/big.dat$' "$WORK_DIR/manifest.sum" || true)
+check "$listed" "1"
+
+echo -n "  its content stays out of the CAS ... "
+stored=$(grep -rlF "$MARKER" "$VR_THE_SOURCE" "$HOME/.vrift/the_source" 2>/dev/null | wc -l | tr -d ' ')
+check "$stored" "0"
+
+SHIM_ENV=(
+    "$PRELOAD_VAR=$SHIM_LIB"
+    VRIFT_PROJECT_ROOT="$PROJECT"
+    VRIFT_VFS_PREFIX="$PROJECT"
+    VRIFT_INCEPTION=1
+    VRIFT_LOG_STDERR=warn
+)
+
+# vriftd spawns vDird when a shim registers the workspace
+for _ in $(seq 1 40); do
+    env "${SHIM_ENV[@]}" cat "$PROJECT/src/small.txt" >/dev/null 2>&1 || true
+    grep -q "vDird ready" "$WORK_DIR/vriftd.log" && break
+    sleep 0.25
+done
+
+echo -n "  read through the shim serves the real file ... "
+check "$(env "${SHIM_ENV[@]}" head -1 "$PROJECT/assets/big.dat" 2>/dev/null)" "$MARKER"
+
+echo -n "  stat through the shim follows the real file ... "
+printf 'more\n' >>"$PROJECT/assets/big.dat"
+size=$(env "${SHIM_ENV[@]}" python3 -c \
+    'import os, sys; print(os.stat(sys.argv[1]).st_size)' "$PROJECT/assets/big.dat" 2>/dev/null)
+check "$size" "$((BIG_SIZE + 5))"
+
+echo "----------------------------------------------------------------"
+if [ "$FAILED" -ne 0 ]; then
+    echo "❌ $FAILED external entry case(s) failed"
+    exit 1
+fi
+echo "✅ Skipped files were served from their real path"
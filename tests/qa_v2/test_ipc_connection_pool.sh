@@ -0,0 +1,192 @@
+#!/bin/bash
+# ============================================================================
+# Test: Shim Reuses Its Daemon Connections
+# ============================================================================
+# Every open of a managed file asks vDird for the manifest entry. The shim
+# keeps the connection for the next lookup instead of connecting again:
+#
+#   200 opens                 | no socket opened after the first few
+#   daemons restarted mid-run | the next open reconnects, no error, no
+#                             | degraded warning
+#   fork                      | the child drops the parent's sockets and
+#                             | opens its own
+#
+# Sockets are observed from inside the reader through /proc/self/fd, so the
+# test runs on Linux only.
+
+set -e
+PROJECT_ROOT="$(cd "$(dirname "${BASH_SOURCE[0]}")/../.." && pwd)"
+BUILD="${VRIFT_BUILD:-release}"
+VRIFT_BIN="$PROJECT_ROOT/target/$BUILD/vrift"
+VRIFTD_BIN="$PROJECT_ROOT/target/$BUILD/vriftd"
+SHIM_LIB="$PROJECT_ROOT/target/$BUILD/libvrift_inception_layer.so"
+
+if [ "$(uname -s)" != "Linux" ]; then
+    echo "⏭️  SKIP: needs /proc to see the shim's sockets"
+    exit 0
+fi
+
+WORK_DIR="/tmp/vrift_ipc_pool_$$"
+PROJECT="$WORK_DIR/project"
+export VR_THE_SOURCE="$WORK_DIR/cas"
+export VRIFT_SOCKET_PATH="$WORK_DIR/vrift.sock"
+export VRIFT_MANIFEST="$PROJECT/.vrift/manifest.lmdb"
+DAEMON_PID=""
+
+cleanup() {
+    if [ -n "$DAEMON_PID" ]; then
+        kill -9 "$DAEMON_PID" 2>/dev/null
+        wait "$DAEMON_PID" 2>/dev/null || true
+    fi
+    pkill -9 -f "vdir_d.*$PROJECT" 2>/dev/null || true
+    rm -f "$VRIFT_SOCKET_PATH"
+    chmod -R u+w "$WORK_DIR" 2>/dev/null || true
+    rm -rf "$WORK_DIR" 2>/dev/null || true
+}
+trap cleanup EXIT
+
+rm -rf "$WORK_DIR"
+mkdir -p "$PROJECT/src" "$VR_THE_SOURCE"
+for i in 1 2 3; do
+    echo "content $i" >"$PROJECT/src/f$i.txt"
+done
+
+echo "----------------------------------------------------------------"
+echo "🧪 IPC: Connection Reuse"
+echo "----------------------------------------------------------------"
+
+cd "$PROJECT"
+"$VRIFT_BIN" init . >/dev/null 2>&1
+"$VRIFT_BIN" ingest . --mode solid --output .vrift/manifest.lmdb >/dev/null 2>&1
+
+SHIM_ENV=(
+    "LD_PRELOAD=$SHIM_LIB"
+    VRIFT_PROJECT_ROOT="$PROJECT"
+    VRIFT_VFS_PREFIX="$PROJECT"
+    VRIFT_INCEPTION=1
+    VRIFT_LOG_STDERR=warn
+)
+
+# vriftd spawns vDird when a shim registers the workspace
+"$VRIFTD_BIN" start >"$WORK_DIR/vriftd.log" 2>&1 &
+DAEMON_PID=$!
+for _ in $(seq 1 40); do
+    if [ -S "$VRIFT_SOCKET_PATH" ]; then
+        env "${SHIM_ENV[@]}" cat "$PROJECT/src/f1.txt" >/dev/null 2>&1 || true
+        grep -q "vDird ready" "$WORK_DIR/vriftd.log" && break
+    fi
+    sleep 0.25
+done
+if ! grep -q "vDird ready" "$WORK_DIR/vriftd.log"; then
+    echo "❌ FAIL: vriftd did not start"
+    tail -10 "$WORK_DIR/vriftd.log"
+    exit 1
+fi
+
+# read_all opens every file <rounds> times and returns the sockets the
+# process held after each round
+cat >"$WORK_DIR/reader.py" <<'EOF'
+import os, sys
+
+def sockets():
+    found = set()
+    for fd in os.listdir("/proc/self/fd"):
+        # stdio may be a socket of whoever started the test
+        if int(fd) < 3:
+            continue
+        try:
+            target = os.readlink("/proc/self/fd/" + fd)
+        except OSError:
+            continue
+        if target.startswith("socket:"):
+            found.add(target)
+    return found
+
+def read_all(src, rounds):
+    held = []
+    for _ in range(rounds):
+        for i in (1, 2, 3):
+            path = os.path.join(src, "f%d.txt" % i)
+            with open(path) as f:
+                assert f.read() == "content %d\n" % i, path
+        held.append(sockets())
+    return held
+
+src, mode = sys.argv[1], sys.argv[2]
+if mode == "plain":
+    # <sockets after round 1> <sockets opened in rounds 2..67>
+    held = read_all(src, 67)
+    print(len(held[0]), len(set().union(*held) - held[0]))
+elif mode == "restart":
+    # <sockets before> <sockets after, none of them from before>
+    before = read_all(src, 10)[-1]
+    open(sys.argv[3], "w").close()
+    while os.path.exists(sys.argv[3]):
+        pass
+    after = read_all(src, 10)[-1]
+    print(len(before) > 0, len(after) > 0 and not after & before)
+elif mode == "fork":
+    # <parent's sockets the child holds> <child has sockets of its own>
+    parent = read_all(src, 5)[-1]
+    r, w = os.pipe()
+    pid = os.fork()
+    if pid == 0:
+        inherited = len(sockets() & parent)
+        own = read_all(src, 5)[-1] - parent
+        os.write(w, ("%d %s" % (inherited, len(own) > 0)).encode())
+        os._exit(0)
+    os.waitpid(pid, 0)
+    print(os.read(r, 64).decode())
+EOF
+
+reader() {
+    env "${SHIM_ENV[@]}" python3 "$WORK_DIR/reader.py" "$PROJECT/src" "$@"
+}
+
+FAILED=0
+check() {
+    if [ "$1" = 0 ]; then
+        echo "✅ PASS"
+    else
+        echo "❌ FAIL ($2)"
+        FAILED=$((FAILED + 1))
+    fi
+}
+
+echo -n "  200 opens reuse their connections ... "
+out=$(reader plain 2>"$WORK_DIR/plain.err" || true)
+read -r held opened <<<"$out"
+[ "${held:-0}" -ge 1 ] && [ "$opened" = 0 ] && ok=0 || ok=1
+check $ok "held/opened later: '$out'; $(tail -3 "$WORK_DIR/plain.err")"
+
+echo -n "  daemons restarted between opens ... "
+flag="$WORK_DIR/restart.flag"
+reader restart "$flag" >"$WORK_DIR/restart.out" 2>"$WORK_DIR/restart.err" &
+READER=$!
+for _ in $(seq 1 100); do
+    [ -e "$flag" ] && break
+    sleep 0.05
+done
+kill -9 "$DAEMON_PID" 2>/dev/null || true
+wait "$DAEMON_PID" 2>/dev/null || true
+pkill -9 -f "vdir_d.*$PROJECT" 2>/dev/null || true
+rm -f "$VRIFT_SOCKET_PATH"
+"$VRIFTD_BIN" start >>"$WORK_DIR/vriftd.log" 2>&1 &
+DAEMON_PID=$!
+rm -f "$flag"
+wait "$READER" && ok=0 || ok=1
+out=$(cat "$WORK_DIR/restart.out")
+[ "$ok" = 0 ] && [ "$out" = "True True" ] && ! grep -q "degraded mode" "$WORK_DIR/restart.err" || ok=1
+check $ok "before/new sockets: '$out'; $(tail -3 "$WORK_DIR/restart.err")"
+
+echo -n "  forked child connects on its own ... "
+out=$(reader fork 2>"$WORK_DIR/fork.err" || true)
+[ "$out" = "0 True" ] && ok=0 || ok=1
+check $ok "inherited/own: '$out'; $(tail -3 "$WORK_DIR/fork.err")"
+
+echo "----------------------------------------------------------------"
+if [ "$FAILED" -ne 0 ]; then
+    echo "❌ $FAILED connection reuse case(s) failed"
+    exit 1
+fi
+echo "✅ Shim reused its daemon connections"
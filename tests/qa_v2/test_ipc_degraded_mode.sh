@@ -0,0 +1,202 @@
+#!/bin/bash
+# ============================================================================
+# Test: Shim Survives a Daemon Restart Mid-Build
+# ============================================================================
+# A process under the shim keeps reading managed files while vriftd and
+# vDird are killed and started again:
+#
+#   daemons restarted within the retry window | no degraded warning
+#   daemons gone | reads keep working, one warning logged, no per-call
+#                  connect retries
+#   daemons back | the shim reconnects on its next probe
+
+set -e
+PROJECT_ROOT="$(cd "$(dirname "${BASH_SOURCE[0]}")/../.." && pwd)"
+BUILD="${VRIFT_BUILD:-release}"
+VRIFT_BIN="$PROJECT_ROOT/target/$BUILD/vrift"
+VRIFTD_BIN="$PROJECT_ROOT/target/$BUILD/vriftd"
+SHIM_LIB="$PROJECT_ROOT/target/$BUILD/libvrift_inception_layer.dylib"
+[ ! -f "$SHIM_LIB" ] && SHIM_LIB="$PROJECT_ROOT/target/$BUILD/libvrift_inception_layer.so"
+
+WORK_DIR="/tmp/vrift_ipc_degraded_$$"
+PROJECT="$WORK_DIR/project"
+export VR_THE_SOURCE="$WORK_DIR/cas"
+export VRIFT_SOCKET_PATH="$WORK_DIR/vrift.sock"
+export VRIFT_MANIFEST="$PROJECT/.vrift/manifest.lmdb"
+DAEMON_PID=""
+
+cleanup() {
+    if [ -n "$DAEMON_PID" ]; then
+        kill -9 "$DAEMON_PID" 2>/dev/null
+        wait "$DAEMON_PID" 2>/dev/null || true
+    fi
+    pkill -9 -f "vdir_d.*$PROJECT" 2>/dev/null || true
+    rm -f "$VRIFT_SOCKET_PATH"
+    [ "$(uname -s)" = "Darwin" ] && chflags -R nouchg "$WORK_DIR" 2>/dev/null
+    chmod -R u+w "$WORK_DIR" 2>/dev/null || true
+    rm -rf "$WORK_DIR" 2>/dev/null || true
+}
+trap cleanup EXIT
+
+rm -rf "$WORK_DIR"
+mkdir -p "$PROJECT/src" "$VR_THE_SOURCE"
+for i in 1 2 3; do
+    echo "content $i" >"$PROJECT/src/f$i.txt"
+done
+
+echo "----------------------------------------------------------------"
+echo "🧪 IPC: Daemon Restart Mid-Build"
+echo "----------------------------------------------------------------"
+
+cd "$PROJECT"
+"$VRIFT_BIN" init . >/dev/null 2>&1
+"$VRIFT_BIN" ingest . --mode solid --output .vrift/manifest.lmdb >/dev/null 2>&1
+
+# Both daemons die, as when the machine's service manager restarts them
+kill_daemons() {
+    kill -9 "$DAEMON_PID" 2>/dev/null || true
+    wait "$DAEMON_PID" 2>/dev/null || true
+    pkill -9 -f "vdir_d.*$PROJECT" 2>/dev/null || true
+    rm -f "$VRIFT_SOCKET_PATH"
+}
+
+if [ "$(uname -s)" = "Darwin" ]; then
+    PRELOAD_VAR=DYLD_INSERT_LIBRARIES
+    SHIM_ENV=(DYLD_FORCE_FLAT_NAMESPACE=1)
+else
+    PRELOAD_VAR=LD_PRELOAD
+    SHIM_ENV=()
+fi
+SHIM_ENV+=(
+    "$PRELOAD_VAR=$SHIM_LIB"
+    VRIFT_PROJECT_ROOT="$PROJECT"
+    VRIFT_VFS_PREFIX="$PROJECT"
+    VRIFT_INCEPTION=1
+    VRIFT_LOG_STDERR=warn
+)
+
+# vriftd spawns vDird when a shim registers the workspace, once its CAS
+# warm-up is done
+start_daemon() {
+    local spawned
+    spawned=$(grep -c "vDird ready" "$WORK_DIR/vriftd.log" 2>/dev/null || true)
+    "$VRIFTD_BIN" start >>"$WORK_DIR/vriftd.log" 2>&1 &
+    DAEMON_PID=$!
+    for _ in $(seq 1 40); do
+        if [ -S "$VRIFT_SOCKET_PATH" ]; then
+            env "${SHIM_ENV[@]}" cat "$PROJECT/src/f1.txt" >/dev/null 2>&1 || true
+            [ "$(grep -c "vDird ready" "$WORK_DIR/vriftd.log")" -gt "${spawned:-0}" ] && return 0
+        fi
+        sleep 0.25
+    done
+    echo "❌ FAIL: vriftd did not start"
+    tail -10 "$WORK_DIR/vriftd.log"
+    exit 1
+}
+
+start_daemon
+
+# The reader reads every file 20 times, then again after each checkpoint
+# <name>:<secs>. There it touches <name>.reached and waits for <name>.go,
+# keeps reading for <secs> seconds, and prints how long 20 more reads take.
+cat >"$WORK_DIR/reader.py" <<'EOF'
+import os, sys, time
+src, sync = sys.argv[1], sys.argv[2]
+
+def read_all(rounds=20, secs=0):
+    start = time.time()
+    while rounds > 0 or time.time() < start + secs:
+        rounds -= 1
+        for i in (1, 2, 3):
+            path = os.path.join(src, "f%d.txt" % i)
+            with open(path) as f:
+                assert f.read() == "content %d\n" % i, path
+            assert os.stat(path).st_size == len("content %d\n" % i), path
+    return time.time() - start
+
+def checkpoint(name):
+    open(os.path.join(sync, name + ".reached"), "w").close()
+    while not os.path.exists(os.path.join(sync, name + ".go")):
+        time.sleep(0.05)
+
+read_all()
+for arg in sys.argv[3:]:
+    name, secs = arg.split(":")
+    checkpoint(name)
+    read_all(0, float(secs))
+    print("%s %.2f" % (name, read_all()))
+print("ok")
+EOF
+
+run_reader() {
+    local name="$1"
+    shift
+    env "${SHIM_ENV[@]}" python3 "$WORK_DIR/reader.py" "$PROJECT/src" "$WORK_DIR" "$@" \
+        >"$WORK_DIR/$name.out" 2>"$WORK_DIR/$name.err" &
+    READER=$!
+}
+
+await_checkpoint() {
+    for _ in $(seq 1 100); do
+        [ -e "$WORK_DIR/$1.reached" ] && return 0
+        kill -0 "$READER" 2>/dev/null || return 1
+        sleep 0.1
+    done
+    return 1
+}
+
+FAILED=0
+check() {
+    if [ "$1" = 0 ]; then
+        echo "✅ PASS"
+    else
+        echo "❌ FAIL ($2)"
+        FAILED=$((FAILED + 1))
+    fi
+}
+
+# Case 1: the daemons come back while the reader would still be retrying
+echo -n "  restart within retry window ... "
+run_reader quick quick:0
+await_checkpoint quick || true
+kill_daemons
+touch "$WORK_DIR/quick.go"
+sleep 0.1
+"$VRIFTD_BIN" start >>"$WORK_DIR/vriftd.log" 2>&1 &
+DAEMON_PID=$!
+wait "$READER" || true
+ok=1
+[ "$(tail -1 "$WORK_DIR/quick.out")" = "ok" ] && ! grep -q "degraded mode" "$WORK_DIR/quick.err" && ok=0
+check $ok "$(cat "$WORK_DIR/quick.out" "$WORK_DIR/quick.err" | tail -5)"
+
+# Let case 2 start from a registered workspace
+kill_daemons
+start_daemon
+
+# Case 2: the daemons stay away for a while, then come back
+echo -n "  daemons down, then back ... "
+# Reading on for a few probe intervals after `up` gives the shim its chance
+# to reconnect
+run_reader outage down:0 up:3
+await_checkpoint down || true
+kill_daemons
+touch "$WORK_DIR/down.go"
+await_checkpoint up || true
+start_daemon
+touch "$WORK_DIR/up.go"
+wait "$READER" || true
+ok=1
+warnings=$(grep -c "unreachable, degraded mode" "$WORK_DIR/outage.err" || true)
+# 60 reads; paying the connect retries on each would take half a minute
+down_secs=$(awk '$1 == "down" { print int($2) }' "$WORK_DIR/outage.out")
+[ "$(tail -1 "$WORK_DIR/outage.out")" = "ok" ] && [ "$warnings" = 1 ] \
+    && [ "${down_secs:-99}" -lt 10 ] \
+    && grep -q "reachable again" "$WORK_DIR/outage.err" && ok=0
+check $ok "$warnings degraded warnings; $(cat "$WORK_DIR/outage.out" "$WORK_DIR/outage.err" | tail -5)"
+
+echo "----------------------------------------------------------------"
+if [ "$FAILED" -ne 0 ]; then
+    echo "❌ $FAILED daemon restart case(s) failed"
+    exit 1
+fi
+echo "✅ Shim rode out the daemon restarts"
@@ -0,0 +1,147 @@
+#!/bin/bash
+# ============================================================================
+# Test: Shim Batches Lookups In A Listed Directory
+# ============================================================================
+# Without the VDir mmap every stat of a managed file asks vDird for its
+# manifest entry. After a directory is listed, the shim fetches the entries
+# of its names with one ManifestGetMany instead:
+#
+#   list, then stat 64 files   | right sizes, a handful of requests
+#   stat without a listing     | still answered, one request each
+#
+# Requests are counted as the read syscalls the reader spends on replies
+# (/proc/self/io), so the test runs on Linux only.
+
+set -e
+PROJECT_ROOT="$(cd "$(dirname "${BASH_SOURCE[0]}")/../.." && pwd)"
+BUILD="${VRIFT_BUILD:-release}"
+VRIFT_BIN="$PROJECT_ROOT/target/$BUILD/vrift"
+VRIFTD_BIN="$PROJECT_ROOT/target/$BUILD/vriftd"
+SHIM_LIB="$PROJECT_ROOT/target/$BUILD/libvrift_inception_layer.so"
+
+if [ "$(uname -s)" != "Linux" ]; then
+    echo "⏭️  SKIP: needs /proc to count the shim's requests"
+    exit 0
+fi
+
+WORK_DIR="/tmp/vrift_ipc_batch_$$"
+PROJECT="$WORK_DIR/project"
+export VR_THE_SOURCE="$WORK_DIR/cas"
+export VRIFT_SOCKET_PATH="$WORK_DIR/vrift.sock"
+export VRIFT_MANIFEST="$PROJECT/.vrift/manifest.lmdb"
+DAEMON_PID=""
+
+cleanup() {
+    if [ -n "$DAEMON_PID" ]; then
+        kill -9 "$DAEMON_PID" 2>/dev/null
+        wait "$DAEMON_PID" 2>/dev/null || true
+    fi
+    pkill -9 -f "vdir_d.*$PROJECT" 2>/dev/null || true
+    rm -f "$VRIFT_SOCKET_PATH"
+    chmod -R u+w "$WORK_DIR" 2>/dev/null || true
+    rm -rf "$WORK_DIR" 2>/dev/null || true
+}
+trap cleanup EXIT
+
+rm -rf "$WORK_DIR"
+mkdir -p "$PROJECT/src" "$PROJECT/lib" "$VR_THE_SOURCE"
+for i in $(seq 1 64); do
+    head -c "$i" /dev/zero >"$PROJECT/src/f$i.txt"
+done
+for i in 1 2 3; do
+    head -c "$i" /dev/zero >"$PROJECT/lib/g$i.txt"
+done
+
+echo "----------------------------------------------------------------"
+echo "🧪 IPC: Batched Manifest Lookups"
+echo "----------------------------------------------------------------"
+
+cd "$PROJECT"
+"$VRIFT_BIN" init . >/dev/null 2>&1
+"$VRIFT_BIN" ingest . --mode solid --output .vrift/manifest.lmdb >/dev/null 2>&1
+
+SHIM_ENV=(
+    "LD_PRELOAD=$SHIM_LIB"
+    VRIFT_PROJECT_ROOT="$PROJECT"
+    VRIFT_VFS_PREFIX="$PROJECT"
+    VRIFT_INCEPTION=1
+    VRIFT_LOG_STDERR=warn
+    # Every lookup goes to vDird
+    VRIFT_DISABLE_MMAP=1
+)
+
+# vriftd spawns vDird when a shim registers the workspace
+"$VRIFTD_BIN" start >"$WORK_DIR/vriftd.log" 2>&1 &
+DAEMON_PID=$!
+for _ in $(seq 1 40); do
+    if [ -S "$VRIFT_SOCKET_PATH" ]; then
+        env "${SHIM_ENV[@]}" cat "$PROJECT/src/f1.txt" >/dev/null 2>&1 || true
+        grep -q "vDird ready" "$WORK_DIR/vriftd.log" && break
+    fi
+    sleep 0.25
+done
+if ! grep -q "vDird ready" "$WORK_DIR/vriftd.log"; then
+    echo "❌ FAIL: vriftd did not start"
+    tail -10 "$WORK_DIR/vriftd.log"
+    exit 1
+fi
+
+cat >"$WORK_DIR/scan.py" <<'EOF'
+import os, sys
+
+def reads():
+    with open("/proc/self/io") as f:
+        for line in f:
+            if line.startswith("syscr:"):
+                return int(line.split()[1])
+
+def sizes(d, names):
+    out = []
+    for name in names:
+        try:
+            out.append(os.stat(os.path.join(d, name)).st_size)
+        except FileNotFoundError:
+            out.append(None)
+    return out
+
+d, mode = sys.argv[1], sys.argv[2]
+if mode == "listed":
+    # <sizes right> <reads for the stats>
+    names = os.listdir(d)
+    before = reads()
+    got = sizes(d, names)
+    spent = reads() - before
+    print(got == [int(n[1:-4]) for n in names], spent)
+elif mode == "unlisted":
+    # <sizes>
+    print(*sizes(d, ["g1.txt", "g2.txt", "g3.txt"]))
+EOF
+
+scan() {
+    env "${SHIM_ENV[@]}" python3 "$WORK_DIR/scan.py" "$@" 2>>"$WORK_DIR/scan.err"
+}
+
+FAILED=0
+check() {
+    if [ "$1" = "$2" ]; then
+        echo "✅ PASS"
+    else
+        echo "❌ FAIL (got '$1', want '$2'; $(tail -3 "$WORK_DIR/scan.err"))"
+        FAILED=$((FAILED + 1))
+    fi
+}
+
+echo -n "  64 stats after a listing take a few requests ... "
+read -r right spent <<<"$(scan "$PROJECT/src" listed)"
+few=$([ "${spent:-999}" -le 8 ] && echo few || echo "$spent reads")
+check "$right $few" "True few"
+
+echo -n "  stats without a listing ... "
+check "$(scan "$PROJECT/lib" unlisted)" "1 2 3"
+
+echo "----------------------------------------------------------------"
+if [ "$FAILED" -ne 0 ]; then
+    echo "❌ $FAILED batched lookup case(s) failed"
+    exit 1
+fi
+echo "✅ Shim batched the lookups of a listed directory"
@@ -0,0 +1,185 @@
+#!/bin/bash
+# ============================================================================
+# Test: Linux Symbol Parity
+# ============================================================================
+# glibc-based tools reach the file system through more than open/stat: stat64
+# and statx, the readdir family, the stdio opens (fopen, freopen), and the versioned and fortified
+# variants. In a phantom-mode project the files only exist in the CAS, so any
+# of these that bypassed the VFS would see an empty directory or ENOENT.
+
+set -e
+if [ "$(uname -s)" != "Linux" ]; then
+    echo "⏭️  SKIP: Linux-only symbols"
+    exit 0
+fi
+PROJECT_ROOT="$(cd "$(dirname "${BASH_SOURCE[0]}")/../.." && pwd)"
+BUILD="${VRIFT_BUILD:-release}"
+VRIFT_BIN="$PROJECT_ROOT/target/$BUILD/vrift"
+VRIFTD_BIN="$PROJECT_ROOT/target/$BUILD/vriftd"
+SHIM_LIB="$PROJECT_ROOT/target/$BUILD/libvrift_inception_layer.dylib"
+[ ! -f "$SHIM_LIB" ] && SHIM_LIB="$PROJECT_ROOT/target/$BUILD/libvrift_inception_layer.so"
+
+WORK_DIR="/tmp/vrift_linux_symbols_$$"
+PROJECT="$WORK_DIR/project"
+export VR_THE_SOURCE="$WORK_DIR/cas"
+export VRIFT_SOCKET_PATH="$WORK_DIR/vrift.sock"
+export VRIFT_MANIFEST="$PROJECT/.vrift/manifest.lmdb"
+DAEMON_PID=""
+
+cleanup() {
+    unset LD_PRELOAD DYLD_INSERT_LIBRARIES
+    if [ -n "$DAEMON_PID" ]; then
+        kill -9 "$DAEMON_PID" 2>/dev/null
+        wait "$DAEMON_PID" 2>/dev/null || true
+    fi
+    pkill -f "vdir_d.*$PROJECT" 2>/dev/null || true
+    rm -f "$VRIFT_SOCKET_PATH"
+    [ "$(uname -s)" = "Darwin" ] && chflags -R nouchg "$WORK_DIR" 2>/dev/null
+    chmod -R u+w "$WORK_DIR" 2>/dev/null
+    rm -rf "$WORK_DIR" 2>/dev/null || true
+}
+trap cleanup EXIT
+
+rm -rf "$WORK_DIR"
+mkdir -p "$PROJECT/src/nested" "$VR_THE_SOURCE"
+printf 'top level\n' > "$PROJECT/src/top.txt"
+printf 'one level down\n' > "$PROJECT/src/nested/deep.txt"
+
+echo "----------------------------------------------------------------"
+echo "🧪 Linux Symbol Parity"
+echo "----------------------------------------------------------------"
+
+cd "$PROJECT"
+"$VRIFT_BIN" init . >/dev/null 2>&1
+"$VRIFT_BIN" ingest . --mode phantom --output .vrift/manifest.lmdb >/dev/null 2>&1
+if [ -e "$PROJECT/src/top.txt" ]; then
+    echo "❌ FAIL: phantom ingest left src/top.txt on disk"
+    exit 1
+fi
+
+"$VRIFTD_BIN" start >"$WORK_DIR/vriftd.log" 2>&1 &
+DAEMON_PID=$!
+for _ in $(seq 1 20); do
+    [ -S "$VRIFT_SOCKET_PATH" ] && break
+    sleep 0.5
+done
+if [ ! -S "$VRIFT_SOCKET_PATH" ]; then
+    echo "❌ FAIL: vriftd did not start"
+    tail -10 "$WORK_DIR/vriftd.log"
+    exit 1
+fi
+
+export VRIFT_PROJECT_ROOT="$PROJECT"
+export VRIFT_VFS_PREFIX="$PROJECT"
+export VRIFT_INCEPTION=1
+if [ "$(uname -s)" = "Darwin" ]; then
+    export DYLD_INSERT_LIBRARIES="$SHIM_LIB"
+    export DYLD_FORCE_FLAT_NAMESPACE=1
+else
+    export LD_PRELOAD="$SHIM_LIB"
+fi
+
+# vriftd spawns vDird on the first shim connection
+for _ in $(seq 1 20); do
+    cat "$PROJECT/src/top.txt" >/dev/null 2>&1 && break
+    sleep 0.5
+done
+
+FAILED=0
+
+# check <name> <expected> <actual>
+check() {
+    local name="$1" expected="$2" actual="$3"
+    echo -n "  $name ... "
+    if [ "$actual" = "$expected" ]; then
+        echo "✅ PASS"
+    else
+        echo "❌ FAIL (got: '$actual')"
+        FAILED=$((FAILED + 1))
+    fi
+}
+
+check "readdir lists ingested files" "nested top.txt" \
+    "$(ls "$PROJECT/src" 2>&1 | tr '\n' ' ' | sed 's/ $//')"
+
+check "readdir takes . and .. from disk" ". .. nested top.txt" \
+    "$(ls -a "$PROJECT/src" 2>&1 | tr '\n' ' ' | sed 's/ $//')"
+
+check "statx of an ingested file" "10 regular file" \
+    "$(stat -c '%s %F' "$PROJECT/src/top.txt" 2>&1)"
+
+check "stat/lstat of an ingested file" "10 10" \
+    "$(python3 -c 'import os, sys; p = sys.argv[1]; print(os.stat(p).st_size, os.lstat(p).st_size)' \
+        "$PROJECT/src/top.txt" 2>&1)"
+
+check "scandir types entries" "nested:dir top.txt:file" \
+    "$(python3 -c '
+import os, sys
+print(" ".join(sorted(e.name + (":dir" if e.is_dir() else ":file") for e in os.scandir(sys.argv[1]))))' \
+        "$PROJECT/src" 2>&1)"
+
+check "fopen reads an ingested file" "one level down" \
+    "$(python3 -c '
+import ctypes, sys
+libc = ctypes.CDLL(None)
+libc.fopen.restype = ctypes.c_void_p
+libc.fgets.argtypes = [ctypes.c_char_p, ctypes.c_int, ctypes.c_void_p]
+libc.fclose.argtypes = [ctypes.c_void_p]
+f = libc.fopen(sys.argv[1].encode(), b"r")
+if not f:
+    print("fopen failed")
+    sys.exit()
+buf = ctypes.create_string_buffer(64)
+libc.fgets(buf, 64, f)
+libc.fclose(f)
+print(buf.value.decode().strip())' "$PROJECT/src/nested/deep.txt" 2>&1)"
+
+check "python open() reads an ingested file" "one level down" \
+    "$(python3 -c 'import sys; print(open(sys.argv[1]).read().strip())' \
+        "$PROJECT/src/nested/deep.txt" 2>&1)"
+
+check "freopen points a stream at an ingested file" "one level down" \
+    "$(python3 -c '
+import ctypes, sys
+libc = ctypes.CDLL(None)
+libc.fopen.restype = ctypes.c_void_p
+libc.freopen.restype = ctypes.c_void_p
+libc.freopen.argtypes = [ctypes.c_char_p, ctypes.c_char_p, ctypes.c_void_p]
+libc.fgets.argtypes = [ctypes.c_char_p, ctypes.c_int, ctypes.c_void_p]
+libc.fclose.argtypes = [ctypes.c_void_p]
+f = libc.fopen(b"/dev/null", b"w")
+f = libc.freopen(sys.argv[1].encode(), b"r", f)
+if not f:
+    print("freopen failed")
+    sys.exit()
+buf = ctypes.create_string_buffer(64)
+libc.fgets(buf, 64, f)
+libc.fclose(f)
+print(buf.value.decode().strip())' "$PROJECT/src/nested/deep.txt" 2>&1)"
+
+check "fdopen wraps an fd of an ingested file" "one level down" \
+    "$(python3 -c '
+import ctypes, os, sys
+libc = ctypes.CDLL(None)
+libc.fdopen.restype = ctypes.c_void_p
+libc.fgets.argtypes = [ctypes.c_char_p, ctypes.c_int, ctypes.c_void_p]
+libc.fclose.argtypes = [ctypes.c_void_p]
+f = libc.fdopen(os.open(sys.argv[1], os.O_RDONLY), b"r")
+buf = ctypes.create_string_buffer(64)
+libc.fgets(buf, 64, f)
+libc.fclose(f)
+print(buf.value.decode().strip())' "$PROJECT/src/nested/deep.txt" 2>&1)"
+
+check "sed -i rewrites an ingested file" "rewritten" \
+    "$(sed -i 's/top level/rewritten/' "$PROJECT/src/top.txt" 2>&1; cat "$PROJECT/src/top.txt" 2>&1)"
+
+check "faccessat of an ingested file" "True" \
+    "$(python3 -c 'import os, sys; print(os.access(sys.argv[1], os.R_OK, effective_ids=True))' \
+        "$PROJECT/src/nested/deep.txt" 2>&1)"
+
+echo "----------------------------------------------------------------"
+if [ "$FAILED" -ne 0 ]; then
+    echo "❌ $FAILED symbol case(s) failed"
+    exit 1
+fi
+echo "✅ glibc entry points see the VFS"
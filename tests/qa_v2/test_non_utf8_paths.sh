@@ -0,0 +1,141 @@
+#!/bin/bash
+# ============================================================================
+# Test: File Names That Are Not Valid UTF-8
+# ============================================================================
+# POSIX names are bytes. A Latin-1 name ("caf\xe9.txt") inside a phantom
+# project must be ingested, opened, stat'ed and listed under the shim with
+# its exact bytes, and stay apart from the UTF-8 spelling of the same word
+# ("café.txt"). Before, the shim passed such paths through to the disk,
+# where a phantom file does not exist.
+
+set -e
+PROJECT_ROOT="$(cd "$(dirname "${BASH_SOURCE[0]}")/../.." && pwd)"
+BUILD="${VRIFT_BUILD:-release}"
+VRIFT_BIN="$PROJECT_ROOT/target/$BUILD/vrift"
+VRIFTD_BIN="$PROJECT_ROOT/target/$BUILD/vriftd"
+SHIM_LIB="$PROJECT_ROOT/target/$BUILD/libvrift_inception_layer.dylib"
+[ ! -f "$SHIM_LIB" ] && SHIM_LIB="$PROJECT_ROOT/target/$BUILD/libvrift_inception_layer.so"
+
+WORK_DIR="/tmp/vrift_non_utf8_$$"
+PROJECT="$WORK_DIR/project"
+export VR_THE_SOURCE="$WORK_DIR/cas"
+export VRIFT_SOCKET_PATH="$WORK_DIR/vrift.sock"
+export VRIFT_MANIFEST="$PROJECT/.vrift/manifest.lmdb"
+DAEMON_PID=""
+
+cleanup() {
+    unset LD_PRELOAD DYLD_INSERT_LIBRARIES
+    if [ -n "$DAEMON_PID" ]; then
+        kill -9 "$DAEMON_PID" 2>/dev/null
+        wait "$DAEMON_PID" 2>/dev/null || true
+    fi
+    pkill -f "vdir_d.*$PROJECT" 2>/dev/null || true
+    rm -f "$VRIFT_SOCKET_PATH"
+    [ "$(uname -s)" = "Darwin" ] && chflags -R nouchg "$WORK_DIR" 2>/dev/null
+    chmod -R u+w "$WORK_DIR" 2>/dev/null
+    rm -rf "$WORK_DIR" 2>/dev/null || true
+}
+trap cleanup EXIT
+
+rm -rf "$WORK_DIR"
+mkdir -p "$PROJECT/src" "$VR_THE_SOURCE"
+# APFS rejects names that are not UTF-8
+if ! printf 'latin-1\n' >"$PROJECT/src/$(printf 'caf\xe9').txt" 2>/dev/null; then
+    echo "⏭️  SKIP: filesystem does not accept non-UTF-8 names"
+    exit 0
+fi
+printf 'utf-8\n' >"$PROJECT/src/café.txt"
+
+echo "----------------------------------------------------------------"
+echo "🧪 File Names That Are Not Valid UTF-8"
+echo "----------------------------------------------------------------"
+
+cd "$PROJECT"
+"$VRIFT_BIN" init . >/dev/null 2>&1
+"$VRIFT_BIN" ingest . --mode phantom --output .vrift/manifest.lmdb >/dev/null 2>&1
+if [ -e "$PROJECT/src/$(printf 'caf\xe9').txt" ]; then
+    echo "❌ FAIL: phantom ingest left the Latin-1 file on disk"
+    exit 1
+fi
+
+"$VRIFTD_BIN" start >"$WORK_DIR/vriftd.log" 2>&1 &
+DAEMON_PID=$!
+for _ in $(seq 1 20); do
+    [ -S "$VRIFT_SOCKET_PATH" ] && break
+    sleep 0.5
+done
+if [ ! -S "$VRIFT_SOCKET_PATH" ]; then
+    echo "❌ FAIL: vriftd did not start"
+    tail -10 "$WORK_DIR/vriftd.log"
+    exit 1
+fi
+
+export VRIFT_PROJECT_ROOT="$PROJECT"
+export VRIFT_VFS_PREFIX="$PROJECT"
+export VRIFT_INCEPTION=1
+if [ "$(uname -s)" = "Darwin" ]; then
+    export DYLD_INSERT_LIBRARIES="$SHIM_LIB"
+    export DYLD_FORCE_FLAT_NAMESPACE=1
+else
+    export LD_PRELOAD="$SHIM_LIB"
+fi
+
+# vriftd spawns vDird on the first shim connection
+for _ in $(seq 1 20); do
+    cat "$PROJECT/src/café.txt" >/dev/null 2>&1 && break
+    sleep 0.5
+done
+
+FAILED=0
+
+# Paths are handed to the syscalls as bytes, never as str
+PRELUDE='
+import os, sys
+src = os.path.join(os.fsencode(sys.argv[1]), b"src")
+latin1 = os.path.join(src, b"caf\xe9.txt")
+utf8 = os.path.join(src, "café.txt".encode())
+def read(path):
+    with open(path, "rb") as f:
+        return f.read().decode().strip()
+'
+
+# run_case <name> <expected> <python snippet>
+run_case() {
+    local name="$1" expected="$2" body="$3"
+    echo -n "  $name ... "
+    local out
+    out=$(python3 -c "$PRELUDE$body" "$PROJECT" 2>&1 || true)
+    if [ "$out" = "$expected" ]; then
+        echo "✅ PASS"
+    else
+        echo "❌ FAIL (got: '$out')"
+        FAILED=$((FAILED + 1))
+    fi
+}
+
+run_case "open a Latin-1 name" "latin-1" '
+print(read(latin1))'
+
+run_case "UTF-8 spelling is a different file" "utf-8" '
+print(read(utf8))'
+
+run_case "stat a Latin-1 name" "8" '
+print(os.stat(latin1).st_size)'
+
+run_case "access a Latin-1 name" "True" '
+print(os.access(latin1, os.R_OK))'
+
+run_case "openat a Latin-1 name through a dirfd" "latin-1" '
+d = os.open(src, os.O_RDONLY | os.O_DIRECTORY)
+fd = os.open(b"caf\xe9.txt", os.O_RDONLY, dir_fd=d)
+print(os.read(fd, 64).decode().strip())'
+
+run_case "readdir returns the raw bytes" "[b'caf\\xc3\\xa9.txt', b'caf\\xe9.txt']" '
+print(sorted(os.listdir(src)))'
+
+echo "----------------------------------------------------------------"
+if [ "$FAILED" -ne 0 ]; then
+    echo "❌ $FAILED non-UTF-8 case(s) failed"
+    exit 1
+fi
+echo "✅ Non-UTF-8 names are served from the VFS"
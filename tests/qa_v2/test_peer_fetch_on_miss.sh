@@ -0,0 +1,135 @@
+#!/bin/bash
+# ============================================================================
+# Test: Fetch-on-Miss from a LAN Peer
+# ============================================================================
+# Two daemons with [peers] enabled find each other over mDNS. A phantom-mode
+# project is ingested into the CAS of the first; the second starts with an
+# empty CAS and no upstream. Reads through the shim, served by the second
+# daemon, must still see the original content, fetched from the first.
+#
+# Needs vriftd built with `--features peers` and multicast between the two
+# daemons; skipped otherwise.
+
+set -e
+PROJECT_ROOT="$(cd "$(dirname "${BASH_SOURCE[0]}")/../.." && pwd)"
+BUILD="${VRIFT_BUILD:-release}"
+VRIFT_BIN="$PROJECT_ROOT/target/$BUILD/vrift"
+VRIFTD_BIN="$PROJECT_ROOT/target/$BUILD/vriftd"
+SHIM_LIB="$PROJECT_ROOT/target/$BUILD/libvrift_inception_layer.dylib"
+[ ! -f "$SHIM_LIB" ] && SHIM_LIB="$PROJECT_ROOT/target/$BUILD/libvrift_inception_layer.so"
+
+WORK_DIR="/tmp/vrift_peer_fetch_$$"
+PROJECT="$WORK_DIR/project"
+PORT_A=$((20000 + $$ % 20000))
+PORT_B=$((PORT_A + 1))
+export VRIFT_MANIFEST="$PROJECT/.vrift/manifest.lmdb"
+export VRIFT_PEERS=1
+export VRIFT_HTTP_TOKEN_FILE="$WORK_DIR/token"
+unset VRIFT_UPSTREAM_URL
+PEER_PID=""
+DAEMON_PID=""
+
+cleanup() {
+    unset LD_PRELOAD DYLD_INSERT_LIBRARIES
+    for pid in $DAEMON_PID $PEER_PID; do
+        kill -9 "$pid" 2>/dev/null
+        wait "$pid" 2>/dev/null || true
+    done
+    pkill -f "vdir_d.*$PROJECT" 2>/dev/null || true
+    [ "$(uname -s)" = "Darwin" ] && chflags -R nouchg "$WORK_DIR" 2>/dev/null
+    chmod -R u+w "$WORK_DIR" 2>/dev/null
+    rm -rf "$WORK_DIR" 2>/dev/null || true
+}
+trap cleanup EXIT
+
+rm -rf "$WORK_DIR"
+mkdir -p "$PROJECT/src" "$WORK_DIR/peer_cas" "$WORK_DIR/cas"
+printf 'fetched from a peer\n' > "$PROJECT/src/shared.txt"
+printf 'peer-token-%s\n' "$$" > "$VRIFT_HTTP_TOKEN_FILE"
+
+echo "----------------------------------------------------------------"
+echo "🧪 Fetch-on-Miss from a LAN Peer"
+echo "----------------------------------------------------------------"
+
+# start_daemon <cas> <socket> <port> <log>; sets STARTED_PID
+start_daemon() {
+    VR_THE_SOURCE="$1" VRIFT_SOCKET_PATH="$2" VRIFT_HTTP_LISTEN="0.0.0.0:$3" \
+        "$VRIFTD_BIN" start >"$4" 2>&1 &
+    STARTED_PID=$!
+    for _ in $(seq 1 20); do
+        [ -S "$2" ] && return 0
+        sleep 0.5
+    done
+    echo "❌ FAIL: vriftd did not start"
+    tail -10 "$4"
+    exit 1
+}
+
+cd "$PROJECT"
+"$VRIFT_BIN" init . >/dev/null 2>&1
+VR_THE_SOURCE="$WORK_DIR/peer_cas" \
+    "$VRIFT_BIN" ingest . --mode phantom --output .vrift/manifest.lmdb >/dev/null 2>&1
+
+start_daemon "$WORK_DIR/peer_cas" "$WORK_DIR/peer.sock" "$PORT_A" "$WORK_DIR/peer.log"
+PEER_PID=$STARTED_PID
+export VR_THE_SOURCE="$WORK_DIR/cas"
+export VRIFT_SOCKET_PATH="$WORK_DIR/vrift.sock"
+start_daemon "$VR_THE_SOURCE" "$VRIFT_SOCKET_PATH" "$PORT_B" "$WORK_DIR/vriftd.log"
+DAEMON_PID=$STARTED_PID
+
+for _ in $(seq 1 20); do
+    grep -q "Found peer\|without the peers feature" "$WORK_DIR/vriftd.log" && break
+    sleep 0.5
+done
+if grep -q "without the peers feature" "$WORK_DIR/vriftd.log"; then
+    echo "⏭️  SKIP: vriftd built without --features peers"
+    exit 0
+fi
+if ! grep -q "Found peer" "$WORK_DIR/vriftd.log"; then
+    echo "⏭️  SKIP: the daemons did not find each other (no multicast here?)"
+    exit 0
+fi
+
+export VRIFT_PROJECT_ROOT="$PROJECT"
+export VRIFT_VFS_PREFIX="$PROJECT"
+export VRIFT_INCEPTION=1
+if [ "$(uname -s)" = "Darwin" ]; then
+    export DYLD_INSERT_LIBRARIES="$SHIM_LIB"
+    export DYLD_FORCE_FLAT_NAMESPACE=1
+else
+    export LD_PRELOAD="$SHIM_LIB"
+fi
+
+FAILED=0
+
+echo -n "  missing blob is fetched from the peer ... "
+actual=""
+# vriftd spawns vDird on the first shim connection; the peer's bloom filter
+# arrives shortly after it is found
+for _ in $(seq 1 20); do
+    actual=$(cat "$PROJECT/src/shared.txt" 2>/dev/null) && break
+    sleep 0.5
+done
+if [ "$actual" = "fetched from a peer" ]; then
+    echo "✅ PASS"
+else
+    echo "❌ FAIL (read: '$actual')"
+    FAILED=$((FAILED + 1))
+fi
+
+echo -n "  fetched blob is kept in the local CAS ... "
+if env -u LD_PRELOAD -u DYLD_INSERT_LIBRARIES find "$VR_THE_SOURCE/blake3" -type f 2>/dev/null \
+    | xargs -r env -u LD_PRELOAD -u DYLD_INSERT_LIBRARIES grep -l "fetched from a peer" >/dev/null 2>&1; then
+    echo "✅ PASS"
+else
+    echo "❌ FAIL"
+    FAILED=$((FAILED + 1))
+fi
+
+echo "----------------------------------------------------------------"
+if [ "$FAILED" -ne 0 ]; then
+    echo "❌ $FAILED peer fetch case(s) failed"
+    tail -20 "$WORK_DIR/vriftd.log"
+    exit 1
+fi
+echo "✅ Missing blobs are fetched from LAN peers"
@@ -0,0 +1,171 @@
+#!/bin/bash
+# ============================================================================
+# Test: Phantom Mode Write Matrix
+# ============================================================================
+# In phantom mode the originals are moved into the CAS, so every write path
+# has to break CoW through the shim: there is no real file to fall back to.
+# Each case mutates an ingested file and reads it back through the shim.
+#
+#   O_TRUNC rewrite | O_APPEND | O_RDWR in place | truncate | ftruncate
+#   truncate to 0   | truncate while open for write
+#   write-temp-then-rename over a managed file
+#
+# None of them may fail with EROFS, EPERM or ENOENT. ftruncate on a
+# read-only fd must fail with EINVAL and leave the shared blob alone.
+
+set -e
+PROJECT_ROOT="$(cd "$(dirname "${BASH_SOURCE[0]}")/../.." && pwd)"
+BUILD="${VRIFT_BUILD:-release}"
+VRIFT_BIN="$PROJECT_ROOT/target/$BUILD/vrift"
+VRIFTD_BIN="$PROJECT_ROOT/target/$BUILD/vriftd"
+SHIM_LIB="$PROJECT_ROOT/target/$BUILD/libvrift_inception_layer.dylib"
+[ ! -f "$SHIM_LIB" ] && SHIM_LIB="$PROJECT_ROOT/target/$BUILD/libvrift_inception_layer.so"
+
+WORK_DIR="/tmp/vrift_phantom_matrix_$$"
+PROJECT="$WORK_DIR/project"
+export VR_THE_SOURCE="$WORK_DIR/cas"
+export VRIFT_SOCKET_PATH="$WORK_DIR/vrift.sock"
+# vDird inherits this from vriftd and serves the ingested manifest
+export VRIFT_MANIFEST="$PROJECT/.vrift/manifest.lmdb"
+DAEMON_PID=""
+
+cleanup() {
+    if [ -n "$DAEMON_PID" ]; then
+        kill -9 "$DAEMON_PID" 2>/dev/null
+        wait "$DAEMON_PID" 2>/dev/null || true
+    fi
+    pkill -f "vdir_d.*$PROJECT" 2>/dev/null || true
+    rm -f "$VRIFT_SOCKET_PATH"
+    [ "$(uname -s)" = "Darwin" ] && chflags -R nouchg "$WORK_DIR" 2>/dev/null
+    rm -rf "$WORK_DIR" 2>/dev/null || true
+}
+trap cleanup EXIT
+
+rm -rf "$WORK_DIR"
+mkdir -p "$PROJECT/src" "$VR_THE_SOURCE"
+
+CASES="trunc_rewrite append rdwr_inplace truncate_shrink truncate_zero truncate_open ftruncate ftruncate_readonly rename_over"
+for name in $CASES; do
+    printf 'original-%s\n' "$name" > "$PROJECT/src/$name.txt"
+done
+
+echo "----------------------------------------------------------------"
+echo "🧪 Phantom Mode Write Matrix"
+echo "----------------------------------------------------------------"
+
+cd "$PROJECT"
+"$VRIFT_BIN" init . >/dev/null 2>&1
+"$VRIFT_BIN" ingest . --mode phantom --output .vrift/manifest.lmdb >/dev/null 2>&1
+
+if [ -e "$PROJECT/src/append.txt" ]; then
+    echo "❌ FAIL: phantom ingest left the original in place"
+    exit 1
+fi
+
+"$VRIFTD_BIN" start >"$WORK_DIR/vriftd.log" 2>&1 &
+DAEMON_PID=$!
+for _ in $(seq 1 20); do
+    [ -S "$VRIFT_SOCKET_PATH" ] && break
+    sleep 0.5
+done
+if [ ! -S "$VRIFT_SOCKET_PATH" ]; then
+    echo "❌ FAIL: vriftd did not start"
+    tail -10 "$WORK_DIR/vriftd.log"
+    exit 1
+fi
+
+export VRIFT_PROJECT_ROOT="$PROJECT"
+export VRIFT_VFS_PREFIX="$PROJECT"
+export VRIFT_INCEPTION=1
+if [ "$(uname -s)" = "Darwin" ]; then
+    export DYLD_INSERT_LIBRARIES="$SHIM_LIB"
+    export DYLD_FORCE_FLAT_NAMESPACE=1
+else
+    export LD_PRELOAD="$SHIM_LIB"
+fi
+
+# vriftd spawns vDird on the first shim connection
+for _ in $(seq 1 20); do
+    cat "$PROJECT/src/rename_over.txt" >/dev/null 2>&1 && break
+    sleep 0.5
+done
+
+# Each case: <name> <python mutation> <expected content after>
+run_case() {
+    local name="$1" mutation="$2" expected="$3"
+    local path="$PROJECT/src/$name.txt"
+    echo -n "  $name ... "
+
+    local out
+    if ! out=$(python3 - "$path" 2>&1 <<EOF
+import os, sys
+path = sys.argv[1]
+$mutation
+EOF
+    ); then
+        echo "❌ FAIL (mutation: $out)"
+        FAILED=$((FAILED + 1))
+        return
+    fi
+
+    local actual
+    actual=$(python3 -c 'import sys; sys.stdout.write(open(sys.argv[1]).read())' "$path" 2>&1) || true
+    if [ "$actual" == "$(printf "$expected")" ]; then
+        echo "✅ PASS"
+    else
+        echo "❌ FAIL (read back: '$actual')"
+        FAILED=$((FAILED + 1))
+    fi
+}
+
+FAILED=0
+
+run_case trunc_rewrite \
+    'fd = os.open(path, os.O_WRONLY | os.O_TRUNC); os.write(fd, b"rewritten\n"); os.close(fd)' \
+    'rewritten'
+
+run_case append \
+    'fd = os.open(path, os.O_WRONLY | os.O_APPEND); os.write(fd, b"appended\n"); os.close(fd)' \
+    'original-append\nappended'
+
+run_case rdwr_inplace \
+    'fd = os.open(path, os.O_RDWR); os.pwrite(fd, b"ORIG", 0); os.close(fd)' \
+    'ORIGinal-rdwr_inplace'
+
+run_case truncate_shrink \
+    'os.truncate(path, 8)' \
+    'original'
+
+run_case truncate_zero \
+    'os.truncate(path, 0)' \
+    ''
+
+run_case truncate_open \
+    'fd = os.open(path, os.O_WRONLY); os.truncate(path, 4); os.lseek(fd, 0, os.SEEK_END); os.write(fd, b"X"); os.close(fd)' \
+    'origX'
+
+run_case ftruncate \
+    'fd = os.open(path, os.O_RDWR); os.ftruncate(fd, 4); os.close(fd)' \
+    'orig'
+
+run_case ftruncate_readonly \
+    'import errno
+fd = os.open(path, os.O_RDONLY)
+try:
+    os.ftruncate(fd, 0)
+    sys.exit("ftruncate on a read-only fd succeeded")
+except OSError as e:
+    assert e.errno == errno.EINVAL, e
+os.close(fd)' \
+    'original-ftruncate_readonly'
+
+run_case rename_over \
+    'tmp = path + ".tmp"; open(tmp, "w").write("renamed\n"); os.rename(tmp, path)' \
+    'renamed'
+
+echo "----------------------------------------------------------------"
+if [ "$FAILED" -ne 0 ]; then
+    echo "❌ $FAILED phantom write case(s) failed"
+    exit 1
+fi
+echo "✅ All phantom write cases passed"
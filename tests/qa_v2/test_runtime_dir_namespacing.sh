@@ -0,0 +1,146 @@
+#!/bin/bash
+# ============================================================================
+# Test: Per-Workspace Runtime Directories
+# ============================================================================
+# Two projects served by one vriftd must not share a vDird socket or a VDir.
+# Each gets <runtime base>/<project-id>/ with its own vdird.sock and
+# vdir.mmap, and a shim in a project maps that project's VDir.
+#
+#   two projects                  | two runtime directories
+#   directory mode                | 700
+#   legacy ~/.vrift/sockets       | unused
+#   shim in project A             | maps A's vdir.mmap, stat still right
+# ============================================================================
+
+set -e
+PROJECT_ROOT="$(cd "$(dirname "${BASH_SOURCE[0]}")/../.." && pwd)"
+BUILD="${VRIFT_BUILD:-release}"
+VRIFT_BIN="$PROJECT_ROOT/target/$BUILD/vrift"
+VRIFTD_BIN="$PROJECT_ROOT/target/$BUILD/vriftd"
+if [ "$(uname -s)" = "Darwin" ]; then
+    SHIM_LIB="$PROJECT_ROOT/target/$BUILD/libvrift_inception_layer.dylib"
+    PRELOAD_VAR="DYLD_INSERT_LIBRARIES"
+else
+    SHIM_LIB="$PROJECT_ROOT/target/$BUILD/libvrift_inception_layer.so"
+    PRELOAD_VAR="LD_PRELOAD"
+fi
+
+WORK_DIR="/tmp/vrift_runtime_dir_$$"
+export HOME="$WORK_DIR/home"
+export VR_THE_SOURCE="$WORK_DIR/cas"
+export VRIFT_SOCKET_PATH="$WORK_DIR/vrift.sock"
+export VRIFT_RUNTIME_DIR="$WORK_DIR/run"
+DAEMON_PID=""
+
+cleanup() {
+    if [ -n "$DAEMON_PID" ]; then
+        kill -9 "$DAEMON_PID" 2>/dev/null
+        wait "$DAEMON_PID" 2>/dev/null || true
+    fi
+    pkill -9 -f "vdir_d.*$WORK_DIR" 2>/dev/null || true
+    rm -f "$VRIFT_SOCKET_PATH"
+    [ "$(uname -s)" = "Darwin" ] && chflags -R nouchg "$WORK_DIR" 2>/dev/null
+    chmod -R u+w "$WORK_DIR" 2>/dev/null || true
+    rm -rf "$WORK_DIR" 2>/dev/null || true
+}
+trap cleanup EXIT
+
+rm -rf "$WORK_DIR"
+mkdir -p "$VR_THE_SOURCE" "$HOME/.vrift"
+for name in alpha beta; do
+    mkdir -p "$WORK_DIR/$name/src"
+    printf '%s %s\n' "$name" "$$" >"$WORK_DIR/$name/src/hello.txt"
+done
+
+echo "----------------------------------------------------------------"
+echo "🧪 Runtime Directories: One Per Workspace"
+echo "----------------------------------------------------------------"
+
+"$VRIFTD_BIN" start >"$WORK_DIR/vriftd.log" 2>&1 &
+DAEMON_PID=$!
+for _ in $(seq 1 20); do
+    [ -S "$VRIFT_SOCKET_PATH" ] && break
+    sleep 0.5
+done
+if [ ! -S "$VRIFT_SOCKET_PATH" ]; then
+    echo "❌ FAIL: vriftd did not start"
+    tail -10 "$WORK_DIR/vriftd.log"
+    exit 1
+fi
+
+# Runs python under the shim in project $1
+shim_python() {
+    local project="$WORK_DIR/$1"
+    shift
+    (cd "$project" && env "$PRELOAD_VAR=$SHIM_LIB" \
+        VRIFT_PROJECT_ROOT="$project" \
+        VRIFT_VFS_PREFIX="$project" \
+        VRIFT_MANIFEST="$project/.vrift/manifest.lmdb" \
+        VRIFT_INCEPTION=1 \
+        python3 -c "$@" 2>>"$WORK_DIR/shim.err")
+}
+
+READY=0
+for name in alpha beta; do
+    (cd "$WORK_DIR/$name" && "$VRIFT_BIN" init . >/dev/null 2>&1 &&
+        VRIFT_MANIFEST="$WORK_DIR/$name/.vrift/manifest.lmdb" \
+            "$VRIFT_BIN" ingest . --output .vrift/manifest.lmdb >/dev/null 2>&1)
+    # The first shim of the project registers it, which spawns its vDird
+    shim_python "$name" 'import os; os.stat("src/hello.txt")' >/dev/null
+    READY=$((READY + 1))
+    for _ in $(seq 1 40); do
+        [ "$(grep -c 'vDird ready' "$WORK_DIR/vriftd.log")" -ge "$READY" ] && break
+        sleep 0.25
+    done
+done
+
+FAILED=0
+check() {
+    if [ "$1" = "$2" ]; then
+        echo "✅ PASS"
+    else
+        echo "❌ FAIL (got '$1', want '$2')"
+        FAILED=$((FAILED + 1))
+    fi
+}
+
+echo -n "  two projects, two runtime directories ... "
+check "$(ls -d "$VRIFT_RUNTIME_DIR"/*/ 2>/dev/null | wc -l | tr -d ' ')" "2"
+
+echo -n "  each with its socket and VDir ... "
+check "$(ls "$VRIFT_RUNTIME_DIR"/*/vdird.sock "$VRIFT_RUNTIME_DIR"/*/vdir.mmap 2>/dev/null | wc -l | tr -d ' ')" "4"
+
+echo -n "  owner-only ... "
+DIR_MODES=$(for dir in "$VRIFT_RUNTIME_DIR"/*/; do
+    if [ "$(uname -s)" = "Darwin" ]; then stat -f '%Lp' "$dir"; else stat -c '%a' "$dir"; fi
+done | sort -u)
+check "$DIR_MODES" "700"
+
+echo -n "  legacy socket directory unused ... "
+check "$(ls "$HOME/.vrift/sockets" 2>/dev/null | wc -l | tr -d ' ')" "0"
+
+ALPHA_SIZE=$(wc -c <"$WORK_DIR/alpha/src/hello.txt" | tr -d ' ')
+echo -n "  stat in alpha ... "
+check "$(shim_python alpha 'import os; print(os.stat("src/hello.txt").st_size)')" "$ALPHA_SIZE"
+
+if [ "$(uname -s)" = "Linux" ]; then
+    # The project's VDir is the one mapped (no other project's)
+    echo -n "  alpha maps its own VDir ... "
+    MAPPED=$(shim_python alpha '
+import os
+os.stat("src/hello.txt")
+maps = open("/proc/self/maps").read()
+print(" ".join(sorted({l.split()[-1] for l in maps.splitlines() if l.endswith("/vdir.mmap")})))
+')
+    ALPHA_ID=$(grep "Workspace registered" "$WORK_DIR/vriftd.log" |
+        grep "root=\"$WORK_DIR/alpha\"" | head -1 | sed 's/.*id=\([0-9a-f]\{16\}\).*/\1/')
+    check "$MAPPED" "$VRIFT_RUNTIME_DIR/$ALPHA_ID/vdir.mmap"
+fi
+
+echo "----------------------------------------------------------------"
+if [ "$FAILED" -ne 0 ]; then
+    echo "❌ $FAILED runtime directory case(s) failed"
+    tail -20 "$WORK_DIR/vriftd.log"
+    exit 1
+fi
+echo "✅ Each workspace has its own runtime directory"
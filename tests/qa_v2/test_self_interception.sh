@@ -0,0 +1,62 @@
+#!/bin/bash
+# ============================================================================
+# Test: Self-Interception Interlock
+# ============================================================================
+# vrift, vriftd and vdir_d never virtualize their own I/O: started with the
+# inception layer preloaded, the shim stays in passthrough and says so once.
+# Any other process with VRIFT_NO_INCEPTION set is exempt silently, while an
+# ordinary process is still intercepted.
+
+set -e
+PROJECT_ROOT="$(cd "$(dirname "${BASH_SOURCE[0]}")/../.." && pwd)"
+BUILD="${VRIFT_BUILD:-release}"
+VRIFT_BIN="$PROJECT_ROOT/target/$BUILD/vrift"
+SHIM_LIB="$PROJECT_ROOT/target/$BUILD/libvrift_inception_layer.dylib"
+[ ! -f "$SHIM_LIB" ] && SHIM_LIB="$PROJECT_ROOT/target/$BUILD/libvrift_inception_layer.so"
+
+WORK_DIR="/tmp/vrift_self_interception_$$"
+trap 'rm -rf "$WORK_DIR"' EXIT
+mkdir -p "$WORK_DIR"
+
+preload() {
+    if [ "$(uname -s)" = "Darwin" ]; then
+        DYLD_INSERT_LIBRARIES="$SHIM_LIB" DYLD_FORCE_FLAT_NAMESPACE=1 "$@"
+    else
+        LD_PRELOAD="$SHIM_LIB" "$@"
+    fi
+}
+
+echo "----------------------------------------------------------------"
+echo "🧪 Self-Interception Interlock"
+echo "----------------------------------------------------------------"
+
+FAILED=0
+check() {
+    local what="$1" ok="$2"
+    echo -n "  $what ... "
+    if [ "$ok" = "yes" ]; then
+        echo "✅ PASS"
+    else
+        echo "❌ FAIL"
+        FAILED=$((FAILED + 1))
+    fi
+}
+
+preload "$VRIFT_BIN" --version >"$WORK_DIR/out" 2>"$WORK_DIR/err"
+check "vrift still runs" "$(grep -q vrift "$WORK_DIR/out" && echo yes)"
+check "vrift warns it is exempt" "$(grep -q "inception layer disabled" "$WORK_DIR/err" && echo yes)"
+
+# A renamed copy is only recognized through the marker
+cp "$VRIFT_BIN" "$WORK_DIR/renamed"
+VRIFT_NO_INCEPTION=1 preload "$WORK_DIR/renamed" --version >/dev/null 2>"$WORK_DIR/err"
+check "marker exempts silently" "$([ ! -s "$WORK_DIR/err" ] && echo yes)"
+
+preload /bin/echo hi >/dev/null 2>"$WORK_DIR/err"
+check "other processes are not exempt" "$(grep -q "inception layer disabled" "$WORK_DIR/err" || echo yes)"
+
+echo "----------------------------------------------------------------"
+if [ "$FAILED" -ne 0 ]; then
+    echo "❌ $FAILED self-interception check(s) failed"
+    exit 1
+fi
+echo "✅ All self-interception checks passed"
@@ -0,0 +1,175 @@
+#!/bin/bash
+# ============================================================================
+# Test: Shim Fault Injection
+# ============================================================================
+# A shim built with `--features fault-injection` misbehaves as VRIFT_FAULT
+# asks:
+#
+#   eio=open          | managed opens fail with EIO, others still work
+#   eio=stat          | managed stats fail with EIO
+#   VRIFT_FAULT_TRIGGER | faults apply only while the trigger file exists
+#   daemon-down       | reads are served in degraded mode, with a warning
+#   delay=<ms>        | each daemon request takes at least <ms>
+#
+# The fault build goes to its own target dir so it never replaces the
+# regular shim. Set VRIFT_FAULT_SHIM to use a prebuilt one.
+
+set -e
+PROJECT_ROOT="$(cd "$(dirname "${BASH_SOURCE[0]}")/../.." && pwd)"
+BUILD="${VRIFT_BUILD:-release}"
+VRIFT_BIN="$PROJECT_ROOT/target/$BUILD/vrift"
+VRIFTD_BIN="$PROJECT_ROOT/target/$BUILD/vriftd"
+
+if [ "$(uname -s)" = "Darwin" ]; then
+    LIB_NAME=libvrift_inception_layer.dylib
+    PRELOAD_VAR=DYLD_INSERT_LIBRARIES
+    SHIM_ENV=(DYLD_FORCE_FLAT_NAMESPACE=1)
+else
+    LIB_NAME=libvrift_inception_layer.so
+    PRELOAD_VAR=LD_PRELOAD
+    SHIM_ENV=()
+fi
+
+SHIM_LIB="${VRIFT_FAULT_SHIM:-}"
+if [ -z "$SHIM_LIB" ]; then
+    FAULT_TARGET="$PROJECT_ROOT/target/fault-injection"
+    echo "   Building the fault-injection shim..."
+    (cd "$PROJECT_ROOT" && cargo build --release --quiet -p vrift-inception-layer \
+        --features fault-injection --target-dir "$FAULT_TARGET")
+    SHIM_LIB="$FAULT_TARGET/release/$LIB_NAME"
+fi
+
+WORK_DIR="/tmp/vrift_fault_$$"
+PROJECT="$WORK_DIR/project"
+export VR_THE_SOURCE="$WORK_DIR/cas"
+export VRIFT_SOCKET_PATH="$WORK_DIR/vrift.sock"
+export VRIFT_MANIFEST="$PROJECT/.vrift/manifest.lmdb"
+DAEMON_PID=""
+
+cleanup() {
+    if [ -n "$DAEMON_PID" ]; then
+        kill -9 "$DAEMON_PID" 2>/dev/null
+        wait "$DAEMON_PID" 2>/dev/null || true
+    fi
+    pkill -9 -f "vdir_d.*$PROJECT" 2>/dev/null || true
+    rm -f "$VRIFT_SOCKET_PATH"
+    [ "$(uname -s)" = "Darwin" ] && chflags -R nouchg "$WORK_DIR" 2>/dev/null
+    chmod -R u+w "$WORK_DIR" 2>/dev/null || true
+    rm -rf "$WORK_DIR" 2>/dev/null || true
+}
+trap cleanup EXIT
+
+rm -rf "$WORK_DIR"
+mkdir -p "$PROJECT/src" "$VR_THE_SOURCE"
+for i in 1 2 3; do
+    echo "content $i" >"$PROJECT/src/f$i.txt"
+done
+echo "outside" >"$WORK_DIR/outside.txt"
+
+echo "----------------------------------------------------------------"
+echo "🧪 Shim: Fault Injection"
+echo "----------------------------------------------------------------"
+
+cd "$PROJECT"
+"$VRIFT_BIN" init . >/dev/null 2>&1
+"$VRIFT_BIN" ingest . --mode solid --output .vrift/manifest.lmdb >/dev/null 2>&1
+
+SHIM_ENV+=(
+    "$PRELOAD_VAR=$SHIM_LIB"
+    VRIFT_PROJECT_ROOT="$PROJECT"
+    VRIFT_VFS_PREFIX="$PROJECT"
+    VRIFT_INCEPTION=1
+    VRIFT_LOG_STDERR=warn
+)
+
+# vriftd spawns vDird when a shim registers the workspace
+"$VRIFTD_BIN" start >"$WORK_DIR/vriftd.log" 2>&1 &
+DAEMON_PID=$!
+for _ in $(seq 1 40); do
+    if [ -S "$VRIFT_SOCKET_PATH" ]; then
+        env "${SHIM_ENV[@]}" cat "$PROJECT/src/f1.txt" >/dev/null 2>&1 || true
+        grep -q "vDird ready" "$WORK_DIR/vriftd.log" && break
+    fi
+    sleep 0.25
+done
+if ! grep -q "vDird ready" "$WORK_DIR/vriftd.log"; then
+    echo "❌ FAIL: vriftd did not start"
+    tail -10 "$WORK_DIR/vriftd.log"
+    exit 1
+fi
+
+# probe <op> <path>... prints, per path, "ok" or the errno name
+cat >"$WORK_DIR/probe.py" <<'EOF'
+import errno, os, sys, time
+op, paths = sys.argv[1], sys.argv[2:]
+start = time.time()
+out = []
+for path in paths:
+    try:
+        if op in ("open", "timed"):
+            with open(path) as f:
+                f.read()
+        else:
+            os.stat(path)
+        out.append("ok")
+    except OSError as e:
+        out.append(errno.errorcode.get(e.errno, str(e.errno)))
+if op == "timed":
+    out.append("%.2f" % (time.time() - start))
+print(" ".join(out))
+EOF
+
+# The probes run outside the project: with eio=stat even the interpreter's
+# look at its working directory would fail
+cd "$WORK_DIR"
+
+# faulty <VRIFT_FAULT> <probe args...>
+faulty() {
+    local fault="$1"
+    shift
+    env "${SHIM_ENV[@]}" VRIFT_FAULT="$fault" python3 "$WORK_DIR/probe.py" "$@" \
+        2>>"$WORK_DIR/probe.err"
+}
+
+FAILED=0
+check() {
+    if [ "$1" = "$2" ]; then
+        echo "✅ PASS"
+    else
+        echo "❌ FAIL (got '$1', want '$2'; $(tail -3 "$WORK_DIR/probe.err"))"
+        FAILED=$((FAILED + 1))
+    fi
+}
+
+echo -n "  eio=open fails managed opens only ... "
+check "$(faulty eio=open open "$PROJECT/src/f1.txt" "$WORK_DIR/outside.txt")" "EIO ok"
+
+echo -n "  eio=stat fails managed stats ... "
+check "$(faulty eio=stat stat "$PROJECT/src/f2.txt" "$PROJECT/src/f3.txt")" "EIO EIO"
+
+echo -n "  faults wait for the trigger file ... "
+trigger="$WORK_DIR/fault.on"
+before=$(VRIFT_FAULT_TRIGGER="$trigger" faulty eio open "$PROJECT/src/f1.txt")
+touch "$trigger"
+after=$(VRIFT_FAULT_TRIGGER="$trigger" faulty eio open "$PROJECT/src/f1.txt")
+rm -f "$trigger"
+check "$before $after" "ok EIO"
+
+echo -n "  daemon-down degrades, reads still work ... "
+: >"$WORK_DIR/probe.err"
+out=$(faulty daemon-down open "$PROJECT/src/f1.txt" "$PROJECT/src/f2.txt")
+grep -q "degraded mode" "$WORK_DIR/probe.err" && warned=warned || warned=silent
+check "$out $warned" "ok ok warned"
+
+echo -n "  delay=300 slows every daemon request ... "
+out=$(faulty delay=300 timed "$PROJECT/src/f1.txt" "$PROJECT/src/f2.txt")
+read -r a b secs <<<"$out"
+slow=$(awk -v s="${secs:-0}" 'BEGIN { print (s >= 0.6) ? "slow" : "fast: " s "s" }')
+check "$a $b $slow" "ok ok slow"
+
+echo "----------------------------------------------------------------"
+if [ "$FAILED" -ne 0 ]; then
+    echo "❌ $FAILED fault injection case(s) failed"
+    exit 1
+fi
+echo "✅ Shim injected the requested faults"
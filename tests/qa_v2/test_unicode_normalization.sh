@@ -0,0 +1,143 @@
+#!/bin/bash
+# ============================================================================
+# Test: Composed and Decomposed Spellings of a Name
+# ============================================================================
+# "café" is "caf\u00e9" composed (NFC) and "cafe\u0301" decomposed (NFD).
+# HFS+ lists the decomposed form, while most tools type the composed one.
+# With VRIFT_KEY_NORMALIZATION=nfc, ingest stores one key for both, and
+# the shim finds a phantom file under either spelling. Without it (the Linux
+# default) the forms stay two different names.
+
+set -e
+PROJECT_ROOT="$(cd "$(dirname "${BASH_SOURCE[0]}")/../.." && pwd)"
+BUILD="${VRIFT_BUILD:-release}"
+VRIFT_BIN="$PROJECT_ROOT/target/$BUILD/vrift"
+VRIFTD_BIN="$PROJECT_ROOT/target/$BUILD/vriftd"
+SHIM_LIB="$PROJECT_ROOT/target/$BUILD/libvrift_inception_layer.dylib"
+[ ! -f "$SHIM_LIB" ] && SHIM_LIB="$PROJECT_ROOT/target/$BUILD/libvrift_inception_layer.so"
+
+WORK_DIR="/tmp/vrift_unicode_nf_$$"
+PROJECT="$WORK_DIR/project"
+export VR_THE_SOURCE="$WORK_DIR/cas"
+export VRIFT_SOCKET_PATH="$WORK_DIR/vrift.sock"
+export VRIFT_MANIFEST="$PROJECT/.vrift/manifest.lmdb"
+DAEMON_PID=""
+
+cleanup() {
+    unset LD_PRELOAD DYLD_INSERT_LIBRARIES
+    if [ -n "$DAEMON_PID" ]; then
+        kill -9 "$DAEMON_PID" 2>/dev/null
+        wait "$DAEMON_PID" 2>/dev/null || true
+    fi
+    pkill -f "vdir_d.*$PROJECT" 2>/dev/null || true
+    rm -f "$VRIFT_SOCKET_PATH"
+    [ "$(uname -s)" = "Darwin" ] && chflags -R nouchg "$WORK_DIR" 2>/dev/null
+    chmod -R u+w "$WORK_DIR" 2>/dev/null
+    rm -rf "$WORK_DIR" 2>/dev/null || true
+}
+trap cleanup EXIT
+
+rm -rf "$WORK_DIR"
+mkdir -p "$PROJECT/src" "$VR_THE_SOURCE"
+# The ingest, vriftd and the shim below all build keys in this form
+export VRIFT_KEY_NORMALIZATION=nfc
+printf 'accent\n' >"$PROJECT/src/$(printf 'cafe\xcc\x81').txt"
+
+echo "----------------------------------------------------------------"
+echo "🧪 Unicode Normalization of Manifest Keys"
+echo "----------------------------------------------------------------"
+
+cd "$PROJECT"
+"$VRIFT_BIN" init . >/dev/null 2>&1
+"$VRIFT_BIN" ingest . --mode phantom --output .vrift/manifest.lmdb >/dev/null 2>&1
+if [ -n "$(ls "$PROJECT/src")" ]; then
+    echo "❌ FAIL: phantom ingest left the file on disk"
+    exit 1
+fi
+
+"$VRIFTD_BIN" start >"$WORK_DIR/vriftd.log" 2>&1 &
+DAEMON_PID=$!
+for _ in $(seq 1 20); do
+    [ -S "$VRIFT_SOCKET_PATH" ] && break
+    sleep 0.5
+done
+if [ ! -S "$VRIFT_SOCKET_PATH" ]; then
+    echo "❌ FAIL: vriftd did not start"
+    tail -10 "$WORK_DIR/vriftd.log"
+    exit 1
+fi
+
+export VRIFT_PROJECT_ROOT="$PROJECT"
+export VRIFT_VFS_PREFIX="$PROJECT"
+export VRIFT_INCEPTION=1
+if [ "$(uname -s)" = "Darwin" ]; then
+    export DYLD_INSERT_LIBRARIES="$SHIM_LIB"
+    export DYLD_FORCE_FLAT_NAMESPACE=1
+else
+    export LD_PRELOAD="$SHIM_LIB"
+fi
+
+# vriftd spawns vDird on the first shim connection
+for _ in $(seq 1 20); do
+    cat "$PROJECT/src/$(printf 'caf\xc3\xa9').txt" >/dev/null 2>&1 && break
+    sleep 0.5
+done
+
+FAILED=0
+
+# Paths are handed to the syscalls as bytes, so Python normalizes nothing
+PRELUDE='
+import os, sys
+src = os.path.join(os.fsencode(sys.argv[1]), b"src")
+nfc = os.path.join(src, b"caf\xc3\xa9.txt")
+nfd = os.path.join(src, b"cafe\xcc\x81.txt")
+def read(path):
+    with open(path, "rb") as f:
+        return f.read().decode().strip()
+'
+
+# run_case <name> <expected> <python snippet>
+run_case() {
+    local name="$1" expected="$2" body="$3"
+    echo -n "  $name ... "
+    local out
+    out=$(python3 -c "$PRELUDE$body" "$PROJECT" 2>&1 || true)
+    if [ "$out" = "$expected" ]; then
+        echo "✅ PASS"
+    else
+        echo "❌ FAIL (got: '$out')"
+        FAILED=$((FAILED + 1))
+    fi
+}
+
+run_case "open the composed spelling" "accent" '
+print(read(nfc))'
+
+run_case "open the decomposed spelling" "accent" '
+print(read(nfd))'
+
+run_case "stat both spellings" "7 7" '
+print(os.stat(nfc).st_size, os.stat(nfd).st_size)'
+
+run_case "openat the composed spelling through a dirfd" "accent" '
+d = os.open(src, os.O_RDONLY | os.O_DIRECTORY)
+fd = os.open(b"caf\xc3\xa9.txt", os.O_RDONLY, dir_fd=d)
+print(os.read(fd, 64).decode().strip())'
+
+run_case "readdir lists one entry, composed" "[b'caf\\xc3\\xa9.txt']" '
+print(sorted(os.listdir(src)))'
+
+# A shim told to keep names as they are no longer matches the ingest
+run_case "without normalization the spellings differ" "False True" '
+import subprocess
+env = dict(os.environ, VRIFT_KEY_NORMALIZATION="none")
+probe = "import os, sys; print(os.path.exists(sys.argv[1]))"
+out = [subprocess.run([sys.executable, "-c", probe, p], env=env, capture_output=True, text=True).stdout.strip() for p in (nfd, nfc)]
+print(*out)'
+
+echo "----------------------------------------------------------------"
+if [ "$FAILED" -ne 0 ]; then
+    echo "❌ $FAILED normalization case(s) failed"
+    exit 1
+fi
+echo "✅ Both spellings of a name find its manifest entry"
@@ -0,0 +1,168 @@
+#!/bin/bash
+# ============================================================================
+# Test: Fetch-on-Miss from an Upstream CAS
+# ============================================================================
+# A phantom-mode project whose blobs are then moved out of the local CAS into
+# a plain HTTP server (one file per hex hash). With [upstream] url pointing
+# there, reads through the shim must still see the original content, and the
+# fetched blobs must land in the local CAS. A blob the upstream lacks must
+# fail with ENOENT, not hang.
+#
+# Needs vriftd built with `--features upstream`; skipped otherwise.
+
+set -e
+PROJECT_ROOT="$(cd "$(dirname "${BASH_SOURCE[0]}")/../.." && pwd)"
+BUILD="${VRIFT_BUILD:-release}"
+VRIFT_BIN="$PROJECT_ROOT/target/$BUILD/vrift"
+VRIFTD_BIN="$PROJECT_ROOT/target/$BUILD/vriftd"
+SHIM_LIB="$PROJECT_ROOT/target/$BUILD/libvrift_inception_layer.dylib"
+[ ! -f "$SHIM_LIB" ] && SHIM_LIB="$PROJECT_ROOT/target/$BUILD/libvrift_inception_layer.so"
+
+WORK_DIR="/tmp/vrift_upstream_fetch_$$"
+PROJECT="$WORK_DIR/project"
+UPSTREAM="$WORK_DIR/upstream"
+PORT=$((20000 + $$ % 20000))
+export VR_THE_SOURCE="$WORK_DIR/cas"
+export VRIFT_SOCKET_PATH="$WORK_DIR/vrift.sock"
+export VRIFT_MANIFEST="$PROJECT/.vrift/manifest.lmdb"
+export VRIFT_UPSTREAM_URL="http://127.0.0.1:$PORT/{hash}"
+DAEMON_PID=""
+HTTP_PID=""
+
+cleanup() {
+    unset LD_PRELOAD DYLD_INSERT_LIBRARIES
+    for pid in $DAEMON_PID $HTTP_PID; do
+        kill -9 "$pid" 2>/dev/null
+        wait "$pid" 2>/dev/null || true
+    done
+    pkill -f "vdir_d.*$PROJECT" 2>/dev/null || true
+    rm -f "$VRIFT_SOCKET_PATH"
+    [ "$(uname -s)" = "Darwin" ] && chflags -R nouchg "$WORK_DIR" 2>/dev/null
+    chmod -R u+w "$WORK_DIR" 2>/dev/null
+    rm -rf "$WORK_DIR" 2>/dev/null || true
+}
+trap cleanup EXIT
+
+rm -rf "$WORK_DIR"
+mkdir -p "$PROJECT/src" "$UPSTREAM" "$VR_THE_SOURCE"
+printf 'fetched from upstream\n' > "$PROJECT/src/remote.txt"
+printf 'never uploaded\n' > "$PROJECT/src/lost.txt"
+
+echo "----------------------------------------------------------------"
+echo "🧪 Fetch-on-Miss from an Upstream CAS"
+echo "----------------------------------------------------------------"
+
+cd "$PROJECT"
+"$VRIFT_BIN" init . >/dev/null 2>&1
+"$VRIFT_BIN" ingest . --mode phantom --output .vrift/manifest.lmdb >/dev/null 2>&1
+
+# Publish every blob but the one of lost.txt, then empty the local CAS
+LOST_HASH=$(printf 'never uploaded\n' | python3 -c '
+import sys
+try:
+    import blake3
+except ImportError:
+    sys.exit(0)
+print(blake3.blake3(sys.stdin.buffer.read()).hexdigest())')
+find "$VR_THE_SOURCE/blake3" -type f | while read -r blob; do
+    name=$(basename "$blob")
+    hash="${name%%_*}"
+    size=$(wc -c < "$blob" | tr -d ' ')
+    # Without python's blake3, tell lost.txt's blob apart by its size
+    if [ "$hash" = "$LOST_HASH" ] || { [ -z "$LOST_HASH" ] && [ "$size" = 15 ]; }; then
+        continue
+    fi
+    cp "$blob" "$UPSTREAM/$hash"
+done
+chmod -R u+w "$VR_THE_SOURCE"
+rm -rf "$VR_THE_SOURCE/blake3"
+
+python3 -m http.server "$PORT" --bind 127.0.0.1 --directory "$UPSTREAM" \
+    >"$WORK_DIR/http.log" 2>&1 &
+HTTP_PID=$!
+for _ in $(seq 1 20); do
+    python3 -c 'import socket, sys; socket.create_connection(("127.0.0.1", int(sys.argv[1])))' \
+        "$PORT" 2>/dev/null && break
+    sleep 0.5
+done
+
+"$VRIFTD_BIN" start >"$WORK_DIR/vriftd.log" 2>&1 &
+DAEMON_PID=$!
+for _ in $(seq 1 20); do
+    [ -S "$VRIFT_SOCKET_PATH" ] && break
+    sleep 0.5
+done
+if [ ! -S "$VRIFT_SOCKET_PATH" ]; then
+    echo "❌ FAIL: vriftd did not start"
+    tail -10 "$WORK_DIR/vriftd.log"
+    exit 1
+fi
+# The upstream is set up (or refused) just after the socket is bound
+for _ in $(seq 1 20); do
+    grep -q "Fetching missing blobs from\|without the upstream feature" "$WORK_DIR/vriftd.log" && break
+    sleep 0.5
+done
+if grep -q "without the upstream feature" "$WORK_DIR/vriftd.log"; then
+    echo "⏭️  SKIP: vriftd built without --features upstream"
+    exit 0
+fi
+
+export VRIFT_PROJECT_ROOT="$PROJECT"
+export VRIFT_VFS_PREFIX="$PROJECT"
+export VRIFT_INCEPTION=1
+if [ "$(uname -s)" = "Darwin" ]; then
+    export DYLD_INSERT_LIBRARIES="$SHIM_LIB"
+    export DYLD_FORCE_FLAT_NAMESPACE=1
+else
+    export LD_PRELOAD="$SHIM_LIB"
+fi
+
+FAILED=0
+
+echo -n "  missing blob is fetched on open ... "
+actual=""
+# vriftd spawns vDird on the first shim connection
+for _ in $(seq 1 20); do
+    actual=$(cat "$PROJECT/src/remote.txt" 2>/dev/null) && break
+    sleep 0.5
+done
+if [ "$actual" = "fetched from upstream" ]; then
+    echo "✅ PASS"
+else
+    echo "❌ FAIL (read: '$actual')"
+    FAILED=$((FAILED + 1))
+fi
+
+echo -n "  fetched blob is kept in the local CAS ... "
+if env -u LD_PRELOAD -u DYLD_INSERT_LIBRARIES find "$VR_THE_SOURCE/blake3" -type f 2>/dev/null \
+    | xargs -r env -u LD_PRELOAD -u DYLD_INSERT_LIBRARIES grep -l "fetched from upstream" >/dev/null 2>&1; then
+    echo "✅ PASS"
+else
+    echo "❌ FAIL"
+    FAILED=$((FAILED + 1))
+fi
+
+echo -n "  blob the upstream lacks fails with ENOENT ... "
+out=$(python3 - "$PROJECT/src/lost.txt" 2>&1 <<'EOF' || true
+import errno, sys
+try:
+    open(sys.argv[1]).read()
+    print("read succeeded")
+except OSError as e:
+    print("ENOENT" if e.errno == errno.ENOENT else e)
+EOF
+)
+if [ "$out" = "ENOENT" ]; then
+    echo "✅ PASS"
+else
+    echo "❌ FAIL ($out)"
+    FAILED=$((FAILED + 1))
+fi
+
+echo "----------------------------------------------------------------"
+if [ "$FAILED" -ne 0 ]; then
+    echo "❌ $FAILED upstream fetch case(s) failed"
+    tail -20 "$WORK_DIR/vriftd.log"
+    exit 1
+fi
+echo "✅ Missing blobs are fetched from the upstream"
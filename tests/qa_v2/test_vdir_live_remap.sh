@@ -0,0 +1,146 @@
+#!/bin/bash
+# ============================================================================
+# Test: Long-Running Processes Pick Up a VDir Created After They Started
+# ============================================================================
+# A shim that starts before vDird has created the VDir file maps nothing.
+# Its next directory listing must notice the file and map it, instead of
+# sending every stat over IPC for the rest of its life.
+#
+# VRIFT_VDIR_MMAP points at a path that only appears (as a link to vDird's
+# file) once the shimmed process is running, so the order is deterministic.
+#
+#   shim starts, VDir path missing    | nothing mapped
+#   listing after the path appears    | VDir mapped, stat still right
+# ============================================================================
+
+set -e
+PROJECT_ROOT="$(cd "$(dirname "${BASH_SOURCE[0]}")/../.." && pwd)"
+BUILD="${VRIFT_BUILD:-release}"
+VRIFT_BIN="$PROJECT_ROOT/target/$BUILD/vrift"
+VRIFTD_BIN="$PROJECT_ROOT/target/$BUILD/vriftd"
+if [ "$(uname -s)" = "Darwin" ]; then
+    SHIM_LIB="$PROJECT_ROOT/target/$BUILD/libvrift_inception_layer.dylib"
+    PRELOAD_VAR="DYLD_INSERT_LIBRARIES"
+else
+    SHIM_LIB="$PROJECT_ROOT/target/$BUILD/libvrift_inception_layer.so"
+    PRELOAD_VAR="LD_PRELOAD"
+fi
+
+WORK_DIR="/tmp/vrift_vdir_remap_$$"
+PROJECT="$WORK_DIR/project"
+export HOME="$WORK_DIR/home"
+export VR_THE_SOURCE="$WORK_DIR/cas"
+export VRIFT_SOCKET_PATH="$WORK_DIR/vrift.sock"
+export VRIFT_MANIFEST="$PROJECT/.vrift/manifest.lmdb"
+export VRIFT_RUNTIME_DIR="$WORK_DIR/run"
+DAEMON_PID=""
+SHIM_PID=""
+
+cleanup() {
+    [ -n "$SHIM_PID" ] && kill -9 "$SHIM_PID" 2>/dev/null
+    if [ -n "$DAEMON_PID" ]; then
+        kill -9 "$DAEMON_PID" 2>/dev/null
+        wait "$DAEMON_PID" 2>/dev/null || true
+    fi
+    pkill -9 -f "vdir_d.*$PROJECT" 2>/dev/null || true
+    rm -f "$VRIFT_SOCKET_PATH"
+    [ "$(uname -s)" = "Darwin" ] && chflags -R nouchg "$WORK_DIR" 2>/dev/null
+    chmod -R u+w "$WORK_DIR" 2>/dev/null || true
+    rm -rf "$WORK_DIR" 2>/dev/null || true
+}
+trap cleanup EXIT
+
+rm -rf "$WORK_DIR"
+mkdir -p "$PROJECT/src" "$VR_THE_SOURCE" "$HOME/.vrift"
+printf 'hello %s\n' "$$" >"$PROJECT/src/hello.txt"
+HELLO_SIZE=$(wc -c <"$PROJECT/src/hello.txt" | tr -d ' ')
+
+echo "----------------------------------------------------------------"
+echo "🧪 VDir: Picked Up By Running Processes"
+echo "----------------------------------------------------------------"
+
+"$VRIFTD_BIN" start >"$WORK_DIR/vriftd.log" 2>&1 &
+DAEMON_PID=$!
+for _ in $(seq 1 20); do
+    [ -S "$VRIFT_SOCKET_PATH" ] && break
+    sleep 0.5
+done
+if [ ! -S "$VRIFT_SOCKET_PATH" ]; then
+    echo "❌ FAIL: vriftd did not start"
+    tail -10 "$WORK_DIR/vriftd.log"
+    exit 1
+fi
+
+cd "$PROJECT"
+"$VRIFT_BIN" init . >/dev/null 2>&1
+"$VRIFT_BIN" ingest . --output .vrift/manifest.lmdb >/dev/null 2>&1
+
+# Touches the project (the shim sets up on first use), says so, waits for
+# the go file, then lists src/ and stats a file in it
+VDIR_LINK="$WORK_DIR/vdir.mmap"
+env "$PRELOAD_VAR=$SHIM_LIB" \
+    VRIFT_PROJECT_ROOT="$PROJECT" \
+    VRIFT_VDIR_MMAP="$VDIR_LINK" \
+    VRIFT_VFS_PREFIX="$PROJECT" \
+    VRIFT_INCEPTION=1 \
+    VRIFT_LOG_STDERR=info \
+    python3 -c '
+import os, sys, time
+os.stat(sys.argv[2] + "/src/hello.txt")
+open(sys.argv[1] + "/started", "w").close()
+go = sys.argv[1] + "/go"
+while not os.path.exists(go):
+    time.sleep(0.1)
+os.listdir(sys.argv[2] + "/src")
+print(os.stat(sys.argv[2] + "/src/hello.txt").st_size, flush=True)
+' "$WORK_DIR" "$PROJECT" >"$WORK_DIR/shim.out" 2>"$WORK_DIR/shim.err" &
+SHIM_PID=$!
+
+for _ in $(seq 1 40); do
+    [ -f "$WORK_DIR/started" ] && grep -q "vDird ready" "$WORK_DIR/vriftd.log" && break
+    sleep 0.25
+done
+
+FAILED=0
+check() {
+    if [ "$1" = "$2" ]; then
+        echo "✅ PASS"
+    else
+        echo "❌ FAIL (got '$1', want '$2')"
+        FAILED=$((FAILED + 1))
+    fi
+}
+
+# The shim's registration spawned vDird in the workspace's runtime directory
+VDIR_FILE=$(ls "$VRIFT_RUNTIME_DIR"/*/vdir.mmap 2>/dev/null | head -1)
+if [ ! -f "$VDIR_FILE" ]; then
+    echo "❌ FAIL: vDird did not create the VDir"
+    tail -10 "$WORK_DIR/vriftd.log"
+    exit 1
+fi
+
+echo -n "  nothing mapped before the VDir appears ... "
+check "$(grep -c 'VDir mapped again' "$WORK_DIR/shim.err")" "0"
+
+ln -s "$VDIR_FILE" "$VDIR_LINK"
+touch "$WORK_DIR/go"
+for _ in $(seq 1 40); do
+    kill -0 "$SHIM_PID" 2>/dev/null || break
+    sleep 0.25
+done
+wait "$SHIM_PID" 2>/dev/null || true
+SHIM_PID=""
+
+echo -n "  the listing mapped the VDir ... "
+check "$(grep -c 'VDir mapped again' "$WORK_DIR/shim.err")" "1"
+
+echo -n "  stat after the remap ... "
+check "$(cat "$WORK_DIR/shim.out")" "$HELLO_SIZE"
+
+echo "----------------------------------------------------------------"
+if [ "$FAILED" -ne 0 ]; then
+    echo "❌ $FAILED remap case(s) failed"
+    tail -20 "$WORK_DIR/shim.err"
+    exit 1
+fi
+echo "✅ Running processes picked up the VDir"
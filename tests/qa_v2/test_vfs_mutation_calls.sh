@@ -0,0 +1,173 @@
+#!/bin/bash
+# ============================================================================
+# Test: Namespace and Metadata Mutations in the VFS
+# ============================================================================
+# In a phantom-mode project, new directories and symlinks may be created next
+# to ingested files, by path or relative to a directory fd, but never over an
+# ingested entry (EEXIST, even though the file only exists in the CAS). Hard
+# links into the CAS are refused with EXDEV; chmod/chown of VFS files with
+# EPERM. A fresh process (coreutils `ln`) must behave the same way.
+
+set -e
+PROJECT_ROOT="$(cd "$(dirname "${BASH_SOURCE[0]}")/../.." && pwd)"
+BUILD="${VRIFT_BUILD:-release}"
+VRIFT_BIN="$PROJECT_ROOT/target/$BUILD/vrift"
+VRIFTD_BIN="$PROJECT_ROOT/target/$BUILD/vriftd"
+SHIM_LIB="$PROJECT_ROOT/target/$BUILD/libvrift_inception_layer.dylib"
+[ ! -f "$SHIM_LIB" ] && SHIM_LIB="$PROJECT_ROOT/target/$BUILD/libvrift_inception_layer.so"
+
+WORK_DIR="/tmp/vrift_mutation_calls_$$"
+PROJECT="$WORK_DIR/project"
+export VR_THE_SOURCE="$WORK_DIR/cas"
+export VRIFT_SOCKET_PATH="$WORK_DIR/vrift.sock"
+export VRIFT_MANIFEST="$PROJECT/.vrift/manifest.lmdb"
+DAEMON_PID=""
+
+cleanup() {
+    unset LD_PRELOAD DYLD_INSERT_LIBRARIES
+    if [ -n "$DAEMON_PID" ]; then
+        kill -9 "$DAEMON_PID" 2>/dev/null
+        wait "$DAEMON_PID" 2>/dev/null || true
+    fi
+    pkill -f "vdir_d.*$PROJECT" 2>/dev/null || true
+    rm -f "$VRIFT_SOCKET_PATH"
+    [ "$(uname -s)" = "Darwin" ] && chflags -R nouchg "$WORK_DIR" 2>/dev/null
+    chmod -R u+w "$WORK_DIR" 2>/dev/null
+    rm -rf "$WORK_DIR" 2>/dev/null || true
+}
+trap cleanup EXIT
+
+rm -rf "$WORK_DIR"
+mkdir -p "$PROJECT/src/nested" "$VR_THE_SOURCE"
+printf 'top level\n' > "$PROJECT/src/top.txt"
+printf 'one level down\n' > "$PROJECT/src/nested/deep.txt"
+
+echo "----------------------------------------------------------------"
+echo "🧪 Namespace and Metadata Mutations in the VFS"
+echo "----------------------------------------------------------------"
+
+cd "$PROJECT"
+"$VRIFT_BIN" init . >/dev/null 2>&1
+"$VRIFT_BIN" ingest . --mode phantom --output .vrift/manifest.lmdb >/dev/null 2>&1
+if [ -e "$PROJECT/src/top.txt" ]; then
+    echo "❌ FAIL: phantom ingest left src/top.txt on disk"
+    exit 1
+fi
+
+"$VRIFTD_BIN" start >"$WORK_DIR/vriftd.log" 2>&1 &
+DAEMON_PID=$!
+for _ in $(seq 1 20); do
+    [ -S "$VRIFT_SOCKET_PATH" ] && break
+    sleep 0.5
+done
+if [ ! -S "$VRIFT_SOCKET_PATH" ]; then
+    echo "❌ FAIL: vriftd did not start"
+    tail -10 "$WORK_DIR/vriftd.log"
+    exit 1
+fi
+
+export VRIFT_PROJECT_ROOT="$PROJECT"
+export VRIFT_VFS_PREFIX="$PROJECT"
+export VRIFT_INCEPTION=1
+if [ "$(uname -s)" = "Darwin" ]; then
+    export DYLD_INSERT_LIBRARIES="$SHIM_LIB"
+    export DYLD_FORCE_FLAT_NAMESPACE=1
+else
+    export LD_PRELOAD="$SHIM_LIB"
+fi
+
+# vriftd spawns vDird on the first shim connection
+for _ in $(seq 1 20); do
+    cat "$PROJECT/src/top.txt" >/dev/null 2>&1 && break
+    sleep 0.5
+done
+
+FAILED=0
+
+PRELUDE='
+import errno, os, sys
+root = sys.argv[1]
+src = os.open(os.path.join(root, "src"), os.O_RDONLY | os.O_DIRECTORY)
+def attempt(f):
+    try:
+        f()
+        print("ok")
+    except OSError as e:
+        print(errno.errorcode.get(e.errno, e.errno))
+'
+
+# run_case <name> <expected> <python snippet>
+run_case() {
+    local name="$1" expected="$2" body="$3"
+    echo -n "  $name ... "
+    local out
+    out=$(python3 -c "$PRELUDE$body" "$PROJECT" 2>&1 || true)
+    if [ "$out" = "$expected" ]; then
+        echo "✅ PASS"
+    else
+        echo "❌ FAIL (got: '$out')"
+        FAILED=$((FAILED + 1))
+    fi
+}
+
+run_case "mkdir of a new directory" "ok" '
+attempt(lambda: os.mkdir(os.path.join(root, "src/made")))'
+
+run_case "mkdirat of a new directory" "ok" '
+attempt(lambda: os.mkdir("made_at", dir_fd=src))'
+
+run_case "mkdir over an ingested file" "EEXIST" '
+attempt(lambda: os.mkdir(os.path.join(root, "src/top.txt")))'
+
+run_case "mkdirat over an ingested directory" "EEXIST" '
+attempt(lambda: os.mkdir("nested", dir_fd=src))'
+
+run_case "symlink of a new link" "top.txt" '
+os.symlink("top.txt", os.path.join(root, "src/link"))
+print(os.readlink(os.path.join(root, "src/link")))'
+
+run_case "symlinkat of a new link" "top.txt" '
+os.symlink("top.txt", "link_at", dir_fd=src)
+print(os.readlink("link_at", dir_fd=src))'
+
+run_case "symlinkat over an ingested file" "EEXIST" '
+attempt(lambda: os.symlink("elsewhere", "top.txt", dir_fd=src))'
+
+run_case "link of an ingested file" "EXDEV" '
+attempt(lambda: os.link(os.path.join(root, "src/top.txt"), os.path.join(root, "src/hard")))'
+
+run_case "chmod of an ingested file" "EPERM" '
+attempt(lambda: os.chmod(os.path.join(root, "src/top.txt"), 0o755))'
+
+run_case "chown of an ingested file" "EPERM" '
+attempt(lambda: os.chown(os.path.join(root, "src/top.txt"), os.getuid(), os.getgid()))'
+
+run_case "ingested file is untouched" "top level" '
+print(open(os.path.join(root, "src/top.txt")).read().strip())'
+
+echo -n "  ln -s from a fresh process ... "
+if ln -s top.txt "$PROJECT/src/ln_link" 2>/dev/null \
+    && [ "$(readlink "$PROJECT/src/ln_link")" = "top.txt" ]; then
+    echo "✅ PASS"
+else
+    echo "❌ FAIL"
+    FAILED=$((FAILED + 1))
+fi
+
+# -T (GNU) keeps ln from treating the destination as a directory
+if [ "$(uname -s)" = "Linux" ]; then
+    echo -n "  ln -sT over an ingested file ... "
+    if ln -sT elsewhere "$PROJECT/src/top.txt" 2>/dev/null; then
+        echo "❌ FAIL (link created)"
+        FAILED=$((FAILED + 1))
+    else
+        echo "✅ PASS"
+    fi
+fi
+
+echo "----------------------------------------------------------------"
+if [ "$FAILED" -ne 0 ]; then
+    echo "❌ $FAILED mutation case(s) failed"
+    exit 1
+fi
+echo "✅ Mutations of VFS paths are created or refused consistently"
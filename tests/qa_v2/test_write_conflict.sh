@@ -0,0 +1,138 @@
+#!/bin/bash
+# ============================================================================
+# Test: Concurrent Write Conflicts
+# ============================================================================
+# Two processes copy the same ingested file and both modify it. The write
+# committed second was copied from content that no longer exists, so vDird
+# does not let it overwrite the first: it fails with a conflict and the
+# losing version is kept as <path>.conflict-<hash>.
+#
+# A rewrite from scratch (O_TRUNC) does not depend on the old content and
+# still wins silently.
+
+set -e
+PROJECT_ROOT="$(cd "$(dirname "${BASH_SOURCE[0]}")/../.." && pwd)"
+BUILD="${VRIFT_BUILD:-release}"
+VRIFT_BIN="$PROJECT_ROOT/target/$BUILD/vrift"
+VRIFTD_BIN="$PROJECT_ROOT/target/$BUILD/vriftd"
+SHIM_LIB="$PROJECT_ROOT/target/$BUILD/libvrift_inception_layer.dylib"
+[ ! -f "$SHIM_LIB" ] && SHIM_LIB="$PROJECT_ROOT/target/$BUILD/libvrift_inception_layer.so"
+
+WORK_DIR="/tmp/vrift_write_conflict_$$"
+PROJECT="$WORK_DIR/project"
+export VR_THE_SOURCE="$WORK_DIR/cas"
+export VRIFT_SOCKET_PATH="$WORK_DIR/vrift.sock"
+# vDird inherits this from vriftd and serves the ingested manifest
+export VRIFT_MANIFEST="$PROJECT/.vrift/manifest.lmdb"
+DAEMON_PID=""
+
+cleanup() {
+    if [ -n "$DAEMON_PID" ]; then
+        kill -9 "$DAEMON_PID" 2>/dev/null
+        wait "$DAEMON_PID" 2>/dev/null || true
+    fi
+    pkill -f "vdir_d.*$PROJECT" 2>/dev/null || true
+    rm -f "$VRIFT_SOCKET_PATH"
+    [ "$(uname -s)" = "Darwin" ] && chflags -R nouchg "$WORK_DIR" 2>/dev/null
+    rm -rf "$WORK_DIR" 2>/dev/null || true
+}
+trap cleanup EXIT
+
+rm -rf "$WORK_DIR"
+mkdir -p "$PROJECT/src" "$VR_THE_SOURCE"
+printf 'base\n' > "$PROJECT/src/edited.txt"
+printf 'base\n' > "$PROJECT/src/rewritten.txt"
+
+echo "----------------------------------------------------------------"
+echo "🧪 Concurrent Write Conflicts"
+echo "----------------------------------------------------------------"
+
+cd "$PROJECT"
+"$VRIFT_BIN" init . >/dev/null 2>&1
+"$VRIFT_BIN" ingest . --mode phantom --output .vrift/manifest.lmdb >/dev/null 2>&1
+
+"$VRIFTD_BIN" start >"$WORK_DIR/vriftd.log" 2>&1 &
+DAEMON_PID=$!
+for _ in $(seq 1 20); do
+    [ -S "$VRIFT_SOCKET_PATH" ] && break
+    sleep 0.5
+done
+if [ ! -S "$VRIFT_SOCKET_PATH" ]; then
+    echo "❌ FAIL: vriftd did not start"
+    tail -10 "$WORK_DIR/vriftd.log"
+    exit 1
+fi
+
+export VRIFT_PROJECT_ROOT="$PROJECT"
+export VRIFT_VFS_PREFIX="$PROJECT"
+export VRIFT_INCEPTION=1
+export VRIFT_LOG_STDERR=warn
+if [ "$(uname -s)" = "Darwin" ]; then
+    export DYLD_INSERT_LIBRARIES="$SHIM_LIB"
+    export DYLD_FORCE_FLAT_NAMESPACE=1
+else
+    export LD_PRELOAD="$SHIM_LIB"
+fi
+
+# vriftd spawns vDird on the first shim connection
+for _ in $(seq 1 20); do
+    cat "$PROJECT/src/edited.txt" >/dev/null 2>&1 && break
+    sleep 0.5
+done
+
+# The parent copies the file (appending, or truncating with `trunc`), a child
+# appends and commits, then the parent commits its own write
+race() {
+    python3 - "$1" "$2" <<'PY'
+import os, subprocess, sys
+path, mode = sys.argv[1], sys.argv[2]
+extra = os.O_TRUNC if mode == "trunc" else os.O_APPEND
+fd = os.open(path, os.O_WRONLY | extra)
+child = "import os, sys; fd = os.open(sys.argv[1], os.O_WRONLY | os.O_APPEND); os.write(fd, b'child\\n'); os.close(fd)"
+subprocess.run([sys.executable, "-c", child, path], check=True)
+os.write(fd, b"parent\n")
+os.close(fd)
+PY
+}
+
+FAILED=0
+check() {
+    local what="$1" actual="$2" expected="$3"
+    echo -n "  $what ... "
+    if [ "$actual" == "$(printf "$expected")" ]; then
+        echo "✅ PASS"
+    else
+        echo "❌ FAIL (got '$actual')"
+        FAILED=$((FAILED + 1))
+    fi
+}
+
+race "$PROJECT/src/edited.txt" append 2>"$WORK_DIR/edited.log" || true
+check "first commit kept" "$(cat "$PROJECT/src/edited.txt")" 'base\nchild'
+
+KEPT=$(grep -o 'kept as /src/edited.txt.conflict-[0-9a-f]*' "$WORK_DIR/edited.log" | head -1 | cut -d' ' -f3)
+echo -n "  conflict reported ... "
+if [ -n "$KEPT" ]; then
+    echo "✅ PASS"
+    check "losing write kept" "$(cat "$PROJECT$KEPT")" 'base\nparent'
+else
+    echo "❌ FAIL (shim log: $(cat "$WORK_DIR/edited.log"))"
+    FAILED=$((FAILED + 1))
+fi
+
+race "$PROJECT/src/rewritten.txt" trunc 2>"$WORK_DIR/rewritten.log" || true
+check "rewrite from scratch wins" "$(cat "$PROJECT/src/rewritten.txt")" 'parent'
+echo -n "  rewrite not reported as conflict ... "
+if grep -q "write conflict" "$WORK_DIR/rewritten.log"; then
+    echo "❌ FAIL"
+    FAILED=$((FAILED + 1))
+else
+    echo "✅ PASS"
+fi
+
+echo "----------------------------------------------------------------"
+if [ "$FAILED" -ne 0 ]; then
+    echo "❌ $FAILED write conflict check(s) failed"
+    exit 1
+fi
+echo "✅ All write conflict checks passed"
@@ -0,0 +1,133 @@
+#!/bin/bash
+# ============================================================================
+# Test: Zero-Copy Reads of VFS Files
+# ============================================================================
+# A read-only open of a VFS file must be served by the CAS blob itself,
+# never by a copy written to disk. The fd must not allow writes that would reach the shared
+# blob; a write open still gets a private CoW copy.
+#
+# Linux only: the fd's target is read from /proc/self/fd.
+
+set -e
+PROJECT_ROOT="$(cd "$(dirname "${BASH_SOURCE[0]}")/../.." && pwd)"
+BUILD="${VRIFT_BUILD:-release}"
+VRIFT_BIN="$PROJECT_ROOT/target/$BUILD/vrift"
+VRIFTD_BIN="$PROJECT_ROOT/target/$BUILD/vriftd"
+SHIM_LIB="$PROJECT_ROOT/target/$BUILD/libvrift_inception_layer.so"
+
+if [ "$(uname -s)" != "Linux" ]; then
+    echo "⏭️  SKIP: needs /proc/self/fd"
+    exit 0
+fi
+
+WORK_DIR="/tmp/vrift_zero_copy_$$"
+PROJECT="$WORK_DIR/project"
+export VR_THE_SOURCE="$WORK_DIR/cas"
+export VRIFT_SOCKET_PATH="$WORK_DIR/vrift.sock"
+export VRIFT_MANIFEST="$PROJECT/.vrift/manifest.lmdb"
+export VRIFT_DISABLE_PACK=1
+DAEMON_PID=""
+
+cleanup() {
+    unset LD_PRELOAD
+    if [ -n "$DAEMON_PID" ]; then
+        kill -9 "$DAEMON_PID" 2>/dev/null
+        wait "$DAEMON_PID" 2>/dev/null || true
+    fi
+    pkill -f "vdir_d.*$PROJECT" 2>/dev/null || true
+    rm -f "$VRIFT_SOCKET_PATH"
+    chmod -R u+w "$WORK_DIR" 2>/dev/null
+    rm -rf "$WORK_DIR" 2>/dev/null || true
+}
+trap cleanup EXIT
+
+rm -rf "$WORK_DIR"
+mkdir -p "$PROJECT/src" "$VR_THE_SOURCE"
+python3 -c 'print("blob content " * 100)' > "$PROJECT/src/blob.txt"
+
+echo "----------------------------------------------------------------"
+echo "🧪 Zero-Copy Reads of VFS Files"
+echo "----------------------------------------------------------------"
+
+cd "$PROJECT"
+"$VRIFT_BIN" init . >/dev/null 2>&1
+"$VRIFT_BIN" ingest . --mode phantom --output .vrift/manifest.lmdb >/dev/null 2>&1
+
+"$VRIFTD_BIN" start >"$WORK_DIR/vriftd.log" 2>&1 &
+DAEMON_PID=$!
+for _ in $(seq 1 20); do
+    [ -S "$VRIFT_SOCKET_PATH" ] && break
+    sleep 0.5
+done
+if [ ! -S "$VRIFT_SOCKET_PATH" ]; then
+    echo "❌ FAIL: vriftd did not start"
+    tail -10 "$WORK_DIR/vriftd.log"
+    exit 1
+fi
+
+export VRIFT_PROJECT_ROOT="$PROJECT"
+export VRIFT_VFS_PREFIX="$PROJECT"
+export VRIFT_INCEPTION=1
+export LD_PRELOAD="$SHIM_LIB"
+
+# vriftd spawns vDird on the first shim connection
+for _ in $(seq 1 20); do
+    cat "$PROJECT/src/blob.txt" >/dev/null 2>&1 && break
+    sleep 0.5
+done
+
+FAILED=0
+
+PRELUDE='
+import errno, os, sys
+root, cas = sys.argv[1], sys.argv[2]
+def target(path, flags=os.O_RDONLY):
+    fd = os.open(os.path.join(root, path), flags)
+    return fd, os.readlink("/proc/self/fd/%d" % fd)
+'
+
+# run_case <name> <expected> <python snippet>
+run_case() {
+    local name="$1" expected="$2" body="$3"
+    echo -n "  $name ... "
+    local out
+    out=$(python3 -c "$PRELUDE$body" "$PROJECT" "$VR_THE_SOURCE" 2>&1 || true)
+    if [ "$out" = "$expected" ]; then
+        echo "✅ PASS"
+    else
+        echo "❌ FAIL (got: '$out')"
+        FAILED=$((FAILED + 1))
+    fi
+}
+
+run_case "read is served by the CAS blob" "cas" '
+fd, link = target("src/blob.txt")
+print("cas" if link.startswith(cas + "/") else link)'
+
+run_case "a read-only fd cannot write the blob" "EBADF" '
+fd, _ = target("src/blob.txt")
+try:
+    os.write(fd, b"x")
+    print("written")
+except OSError as e:
+    print("EBADF" if e.errno == errno.EBADF else e)'
+
+echo -n "  reads leave no staging copies behind ... "
+leftover=$(env -u LD_PRELOAD find "$PROJECT/.vrift/staging" -type f 2>/dev/null | wc -l | tr -d ' ')
+if [ "$leftover" = "0" ]; then
+    echo "✅ PASS"
+else
+    echo "❌ FAIL ($leftover file(s))"
+    FAILED=$((FAILED + 1))
+fi
+
+run_case "a write open gets a private copy" "staging" '
+fd, link = target("src/blob.txt", os.O_RDWR)
+print("staging" if "/.vrift/staging/" in link else link)'
+
+echo "----------------------------------------------------------------"
+if [ "$FAILED" -ne 0 ]; then
+    echo "❌ $FAILED zero-copy case(s) failed"
+    exit 1
+fi
+echo "✅ VFS reads are served without copies"
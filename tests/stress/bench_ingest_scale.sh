@@ -0,0 +1,59 @@
+#!/bin/bash
+# bench_ingest_scale.sh - Benchmark ingest of a large synthetic tree
+# Priority: P2 (Performance)
+#
+# The tree comes from vrift-genfs, so runs on different machines ingest the
+# same corpus; quote its id with the numbers. Tunables (defaults in braces):
+#   GENFS_FILES {100000}  GENFS_SEED {0}  GENFS_SIZE {log:512..64k}
+#   GENFS_DUP_RATIO {0.2}
+set -e
+
+echo "=== Test: Ingest Scale Benchmark ==="
+
+PROJECT_ROOT="$(cd "$(dirname "$0")/../.." && pwd)"
+BUILD="${VRIFT_BUILD:-release}"
+VRIFT_CLI="${PROJECT_ROOT}/target/${BUILD}/vrift"
+GENFS_BIN="${PROJECT_ROOT}/target/${BUILD}/vrift-genfs"
+
+TEST_DIR="/tmp/ingest_scale_$$"
+export VR_THE_SOURCE="$TEST_DIR/.cas"
+
+cleanup() {
+    [ "$(uname -s)" = "Darwin" ] && chflags -R nouchg "$TEST_DIR" 2>/dev/null
+    chmod -R u+w "$TEST_DIR" 2>/dev/null || true
+    rm -rf "$TEST_DIR" 2>/dev/null || true
+}
+trap cleanup EXIT
+
+mkdir -p "$VR_THE_SOURCE"
+
+now_ms() {
+    perl -MTime::HiRes=time -e 'printf "%.0f\n", time * 1000'
+}
+
+echo "[1] Generating corpus..."
+"$GENFS_BIN" "$TEST_DIR/project" \
+    --files "${GENFS_FILES:-100000}" \
+    --seed "${GENFS_SEED:-0}" \
+    --size "${GENFS_SIZE:-log:512..64k}" \
+    --dup-ratio "${GENFS_DUP_RATIO:-0.2}" | sed 's/^/    /'
+
+cd "$TEST_DIR/project"
+"$VRIFT_CLI" init . >/dev/null 2>&1
+
+echo "[2] Cold ingest..."
+START=$(now_ms)
+"$VRIFT_CLI" ingest . --mode solid --output .vrift/manifest.lmdb >/dev/null 2>&1
+COLD=$(($(now_ms) - START))
+
+echo "[3] Warm re-ingest (mtime+size cache)..."
+START=$(now_ms)
+"$VRIFT_CLI" ingest . --mode solid --output .vrift/manifest.lmdb >/dev/null 2>&1
+WARM=$(($(now_ms) - START))
+
+FILES="${GENFS_FILES:-100000}"
+echo ""
+echo "    Cold: ${COLD}ms ($((FILES * 1000 / (COLD > 0 ? COLD : 1))) files/sec)"
+echo "    Warm: ${WARM}ms ($((FILES * 1000 / (WARM > 0 ? WARM : 1))) files/sec)"
+echo ""
+echo "✅ PASS: Ingest scale benchmark complete"